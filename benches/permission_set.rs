@@ -0,0 +1,124 @@
+//! Times the permission checks of a token claiming 500 groups, of which
+//! only one is named by the policies, against checking every group.
+//!
+//! Run with `cargo bench --bench permission_set --features ssr`.
+
+use std::{
+    hint::black_box,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use casbin::{CoreApi, Enforcer};
+use treasury::{
+    authentication::authenticated_token::{AuthenticatedToken, Claims},
+    authorization::{
+        PermissionConfig, PermissionSet,
+        actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+    },
+    config::GroupFilterConfig,
+};
+
+const GROUPS: usize = 500;
+const ITERATIONS: u32 = 200;
+
+const CONFIG: PermissionConfig = PermissionConfig {
+    min_read_level: ReadLevel::ReadAll,
+    min_create_level: CreateLevel::CreateAll,
+    min_update_level: UpdateLevel::UpdateAll,
+    min_delete_level: DeleteLevel::DeleteAll,
+};
+
+fn token(groups: &[String]) -> AuthenticatedToken {
+    let claims = serde_json::from_value::<Claims>(serde_json::json!({
+        "groups": groups,
+        "email": "user@example.com",
+        "email_verified": true,
+        "sub": "sub",
+        "iss": "iss",
+        "iat": 0,
+        "exp": 0,
+    }))
+    .unwrap();
+    let mut token = AuthenticatedToken::new(claims);
+    token.normalize_groups();
+    token
+}
+
+/// Checks every level of every group, as the permission set did before
+/// it skipped the groups the policies don't name.
+fn check_every_group(enforcer: &Enforcer, token: &AuthenticatedToken) -> usize {
+    let levels: [&str; 8] = [
+        ReadLevel::ReadAll.into(),
+        ReadLevel::Read.into(),
+        CreateLevel::CreateAll.into(),
+        CreateLevel::Create.into(),
+        UpdateLevel::UpdateAll.into(),
+        UpdateLevel::Update.into(),
+        DeleteLevel::DeleteAll.into(),
+        DeleteLevel::Delete.into(),
+    ];
+    let mut granted = 0;
+    for level in levels {
+        for group in token.groups() {
+            if enforcer
+                .enforce((group.as_policy_subject(), "accounts", level))
+                .unwrap()
+            {
+                granted += 1;
+                break;
+            }
+        }
+    }
+    granted
+}
+
+fn time(name: &str, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    println!("{name:<40} {elapsed:>12?} per call");
+    elapsed
+}
+
+fn main() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let enforcer = Arc::new(
+        runtime
+            .block_on(Enforcer::new("model.conf", "policies.csv"))
+            .expect("Failed to load authorization policy"),
+    );
+
+    // The one group the policies name comes last, so every other one is
+    // checked before it.
+    let mut groups = (0..GROUPS)
+        .map(|i| format!("org:team-{i}"))
+        .collect::<Vec<_>>();
+    groups.push("org:user".to_owned());
+    let large = token(&groups);
+    let mut filtered = large.clone();
+    filtered.filter_groups(&GroupFilterConfig {
+        allowed: vec!["user".to_owned()],
+        prefixes: vec![],
+    });
+    let small = token(&["user".to_owned()]);
+
+    let every_group = time("every group checked", || {
+        black_box(check_every_group(&enforcer, &large));
+    });
+    let policy_groups = time("policy groups checked", || {
+        black_box(PermissionSet::new("accounts", &enforcer, &large, CONFIG).unwrap());
+    });
+    time("groups filtered by the token", || {
+        black_box(PermissionSet::new("accounts", &enforcer, &filtered, CONFIG).unwrap());
+    });
+    time("a single group", || {
+        black_box(PermissionSet::new("accounts", &enforcer, &small, CONFIG).unwrap());
+    });
+    println!(
+        "Checking only the policy groups is {:.1}x faster with {GROUPS} groups.",
+        every_group.as_secs_f64() / policy_groups.as_secs_f64()
+    );
+}
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use treasury::{
+    model::cursor_key::{CursorKey, CursorKeyId},
+    schema::{Cursor, Pagination},
+};
+
+fuzz_target!(|data: &[u8]| {
+    let cursor_key = CursorKey::new(CursorKeyId(1), vec![7; 32]);
+    let _ = cursor_key.decrypt::<Cursor>(data);
+    if let Ok(cursor) = std::str::from_utf8(data)
+        && let Ok((_, cursor_bytes)) = Pagination::decode_cursor(cursor)
+    {
+        let _ = Pagination::open_cursor(&cursor_key, &cursor_bytes);
+    }
+});
@@ -0,0 +1,4 @@
+DROP TABLE step_up_grant;
+DROP TABLE webauthn_challenge;
+DROP TRIGGER update_passkey_updated_at ON passkey;
+DROP TABLE passkey;
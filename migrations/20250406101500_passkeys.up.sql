@@ -0,0 +1,35 @@
+CREATE TABLE passkey (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        name VARCHAR(254) NOT NULL,
+        credential_id BYTEA NOT NULL UNIQUE,
+        credential JSONB NOT NULL,
+        last_used_at TIMESTAMPTZ,
+        CONSTRAINT fk_passkey_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_passkey_user_id ON passkey (user_id);
+
+CREATE TRIGGER update_passkey_updated_at
+        BEFORE UPDATE ON passkey
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE webauthn_challenge (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        expires_at TIMESTAMPTZ NOT NULL,
+        user_id UUID NOT NULL,
+        state JSONB NOT NULL,
+        CONSTRAINT fk_webauthn_challenge_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE TABLE step_up_grant (
+        session TEXT NOT NULL PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        expires_at TIMESTAMPTZ NOT NULL,
+        user_id UUID NOT NULL,
+        CONSTRAINT fk_step_up_grant_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
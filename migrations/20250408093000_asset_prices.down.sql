@@ -0,0 +1,2 @@
+DROP TRIGGER update_asset_price_updated_at ON asset_price;
+DROP TABLE asset_price;
@@ -0,0 +1,19 @@
+CREATE TABLE asset_price (
+        id BIGSERIAL PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        asset_id UUID NOT NULL,
+        quote_asset_id UUID NOT NULL,
+        priced_at TIMESTAMPTZ NOT NULL,
+        rate NUMERIC NOT NULL CHECK (rate > 0),
+        CONSTRAINT fk_asset_price_asset_id_asset FOREIGN KEY (asset_id) REFERENCES asset (id) ON DELETE CASCADE,
+        CONSTRAINT fk_asset_price_quote_asset_id_asset FOREIGN KEY (quote_asset_id) REFERENCES asset (id) ON DELETE CASCADE,
+        CONSTRAINT uq_asset_price_asset_quote_priced_at UNIQUE (asset_id, quote_asset_id, priced_at)
+);
+
+CREATE INDEX idx_asset_price_lookup ON asset_price (asset_id, quote_asset_id, priced_at DESC);
+
+CREATE TRIGGER update_asset_price_updated_at
+        BEFORE UPDATE ON asset_price
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
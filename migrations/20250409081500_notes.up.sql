@@ -0,0 +1,7 @@
+ALTER TABLE account
+        ADD COLUMN notes TEXT,
+        ADD CONSTRAINT ck_account_notes_length CHECK (octet_length(notes) <= 10240);
+
+ALTER TABLE "transaction"
+        ADD COLUMN notes TEXT,
+        ADD CONSTRAINT ck_transaction_notes_length CHECK (octet_length(notes) <= 10240);
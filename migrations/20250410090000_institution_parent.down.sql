@@ -0,0 +1,2 @@
+DROP INDEX idx_institution_parent_id;
+ALTER TABLE institution DROP COLUMN parent_id;
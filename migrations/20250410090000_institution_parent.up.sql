@@ -0,0 +1,6 @@
+ALTER TABLE institution
+        ADD COLUMN parent_id UUID,
+        ADD CONSTRAINT fk_institution_parent_id_institution FOREIGN KEY (parent_id) REFERENCES institution (id),
+        ADD CONSTRAINT ck_institution_parent_id_not_self CHECK (parent_id <> id);
+
+CREATE INDEX idx_institution_parent_id ON institution (parent_id);
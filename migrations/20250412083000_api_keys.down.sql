@@ -0,0 +1,2 @@
+DROP TRIGGER update_api_key_updated_at ON api_key;
+DROP TABLE api_key;
@@ -0,0 +1,18 @@
+CREATE TABLE api_key (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        name VARCHAR(254) NOT NULL,
+        key_hash BYTEA NOT NULL UNIQUE,
+        read_only BOOLEAN NOT NULL DEFAULT TRUE,
+        account_ids UUID[],
+        CONSTRAINT fk_api_key_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_api_key_user_id ON api_key (user_id);
+
+CREATE TRIGGER update_api_key_updated_at
+        BEFORE UPDATE ON api_key
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,3 @@
+DROP TRIGGER update_import_profile_updated_at ON import_profile;
+DROP TABLE import_profile;
+DROP TYPE import_sign_convention;
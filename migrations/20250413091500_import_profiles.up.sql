@@ -0,0 +1,25 @@
+CREATE TYPE import_sign_convention AS ENUM ('as_is', 'inverted');
+
+CREATE TABLE import_profile (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        name VARCHAR(254) NOT NULL,
+        column_mapping JSONB NOT NULL,
+        date_format VARCHAR(64) NOT NULL,
+        decimal_separator VARCHAR(1) NOT NULL DEFAULT '.',
+        decimal_places SMALLINT NOT NULL DEFAULT 0,
+        sign_convention import_sign_convention NOT NULL DEFAULT 'as_is',
+        default_account_id UUID,
+        CONSTRAINT fk_import_profile_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_import_profile_default_account_id_account FOREIGN KEY (default_account_id) REFERENCES account (id) ON DELETE SET NULL,
+        CONSTRAINT uq_import_profile_user_id_name UNIQUE (user_id, name),
+        CONSTRAINT ck_import_profile_decimal_separator CHECK (decimal_separator IN ('.', ',')),
+        CONSTRAINT ck_import_profile_decimal_places CHECK (decimal_places BETWEEN 0 AND 18)
+);
+
+CREATE TRIGGER update_import_profile_updated_at
+        BEFORE UPDATE ON import_profile
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,4 @@
+DROP TRIGGER update_provider_connection_updated_at ON provider_connection;
+DROP TABLE provider_connection;
+DROP INDEX uq_transaction_account_id_external_id;
+ALTER TABLE "transaction" DROP COLUMN external_id;
@@ -0,0 +1,23 @@
+ALTER TABLE "transaction" ADD COLUMN external_id VARCHAR(254);
+
+CREATE UNIQUE INDEX uq_transaction_account_id_external_id ON "transaction" (account_id, external_id);
+
+CREATE TABLE provider_connection (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        institution_id UUID NOT NULL,
+        connector VARCHAR(64) NOT NULL,
+        config JSONB NOT NULL DEFAULT '{}',
+        cursors JSONB NOT NULL DEFAULT '{}',
+        last_synced_at TIMESTAMPTZ,
+        CONSTRAINT fk_provider_connection_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_provider_connection_institution_id_institution FOREIGN KEY (institution_id) REFERENCES institution (id) ON DELETE CASCADE,
+        CONSTRAINT uq_provider_connection_user_id_institution_id UNIQUE (user_id, institution_id)
+);
+
+CREATE TRIGGER update_provider_connection_updated_at
+        BEFORE UPDATE ON provider_connection
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
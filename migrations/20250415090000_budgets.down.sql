@@ -0,0 +1,3 @@
+DROP TRIGGER update_budget_updated_at ON budget;
+DROP TABLE budget;
+DROP TYPE budget_kind;
@@ -0,0 +1,27 @@
+CREATE TYPE budget_kind AS ENUM ('fixed', 'percent_of_income');
+
+CREATE TABLE budget (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        name VARCHAR(254) NOT NULL,
+        asset_id UUID NOT NULL,
+        kind budget_kind NOT NULL DEFAULT 'fixed',
+        amount BIGINT,
+        percent SMALLINT,
+        account_ids UUID[],
+        CONSTRAINT fk_budget_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_budget_asset_id_asset FOREIGN KEY (asset_id) REFERENCES asset (id) ON DELETE CASCADE,
+        CONSTRAINT ck_budget_limit CHECK (
+                (kind = 'fixed' AND amount IS NOT NULL AND amount >= 0 AND percent IS NULL)
+                OR (kind = 'percent_of_income' AND percent IS NOT NULL AND percent BETWEEN 0 AND 100 AND amount IS NULL)
+        )
+);
+
+CREATE INDEX idx_budget_user_id ON budget (user_id);
+
+CREATE TRIGGER update_budget_updated_at
+        BEFORE UPDATE ON budget
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,5 @@
+DROP TRIGGER update_attachment_extraction_updated_at ON attachment_extraction;
+DROP TABLE attachment_extraction;
+DROP TYPE extraction_status;
+DROP TRIGGER update_attachment_updated_at ON attachment;
+DROP TABLE attachment;
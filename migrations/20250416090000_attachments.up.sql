@@ -0,0 +1,40 @@
+CREATE TABLE attachment (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        transaction_id BIGINT NOT NULL,
+        filename VARCHAR(254) NOT NULL,
+        content_type VARCHAR(127) NOT NULL,
+        size BIGINT NOT NULL,
+        content BYTEA NOT NULL,
+        CONSTRAINT fk_attachment_transaction_id_transaction FOREIGN KEY (transaction_id) REFERENCES transaction (id) ON DELETE CASCADE
+);
+
+CREATE INDEX ix_attachment_transaction_id ON attachment (transaction_id);
+
+CREATE TRIGGER update_attachment_updated_at
+        BEFORE UPDATE ON attachment
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TYPE extraction_status AS ENUM ('pending', 'complete', 'failed');
+
+CREATE TABLE attachment_extraction (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        attachment_id UUID NOT NULL,
+        extractor VARCHAR(64) NOT NULL,
+        status extraction_status NOT NULL DEFAULT 'pending',
+        quantity BIGINT,
+        posted_at TIMESTAMPTZ,
+        merchant VARCHAR(254),
+        error TEXT,
+        CONSTRAINT fk_attachment_extraction_attachment_id_attachment FOREIGN KEY (attachment_id) REFERENCES attachment (id) ON DELETE CASCADE,
+        CONSTRAINT uq_attachment_extraction_attachment_id UNIQUE (attachment_id)
+);
+
+CREATE TRIGGER update_attachment_extraction_updated_at
+        BEFORE UPDATE ON attachment_extraction
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,3 @@
+DROP TRIGGER update_announcement_updated_at ON announcement;
+DROP TABLE announcement;
+DROP TYPE announcement_severity;
@@ -0,0 +1,21 @@
+CREATE TYPE announcement_severity AS ENUM ('info', 'warning', 'critical');
+
+CREATE TABLE announcement (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        message VARCHAR(1000) NOT NULL,
+        severity announcement_severity NOT NULL DEFAULT 'info',
+        starts_at TIMESTAMPTZ NOT NULL,
+        ends_at TIMESTAMPTZ NOT NULL,
+        created_by UUID,
+        CONSTRAINT fk_announcement_created_by_user FOREIGN KEY (created_by) REFERENCES "user" (id) ON DELETE SET NULL,
+        CONSTRAINT ck_announcement_window CHECK (starts_at < ends_at)
+);
+
+CREATE INDEX idx_announcement_window ON announcement (starts_at, ends_at);
+
+CREATE TRIGGER update_announcement_updated_at
+        BEFORE UPDATE ON announcement
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
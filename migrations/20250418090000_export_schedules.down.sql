@@ -0,0 +1,5 @@
+DROP TRIGGER update_export_schedule_updated_at ON export_schedule;
+DROP TABLE export_schedule;
+DROP TYPE export_run_status;
+DROP TYPE export_destination_kind;
+DROP TYPE export_format;
@@ -0,0 +1,26 @@
+CREATE TYPE export_format AS ENUM ('json', 'csv');
+CREATE TYPE export_destination_kind AS ENUM ('s3', 'webdav', 'none');
+CREATE TYPE export_run_status AS ENUM ('succeeded', 'failed');
+
+CREATE TABLE export_schedule (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        format export_format NOT NULL DEFAULT 'json',
+        destination_kind export_destination_kind NOT NULL DEFAULT 'none',
+        -- The destination configuration, sealed with the data key
+        destination_config BYTEA NOT NULL,
+        cadence VARCHAR(100) NOT NULL,
+        last_run_at TIMESTAMPTZ,
+        last_status export_run_status,
+        last_error TEXT,
+        CONSTRAINT fk_export_schedule_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_export_schedule_user_id ON export_schedule (user_id);
+
+CREATE TRIGGER update_export_schedule_updated_at
+        BEFORE UPDATE ON export_schedule
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
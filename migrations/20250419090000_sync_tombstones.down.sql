@@ -0,0 +1,8 @@
+DROP TRIGGER record_transaction_tombstone ON "transaction";
+DROP TRIGGER record_account_tombstone ON account;
+DROP FUNCTION record_transaction_tombstone();
+DROP FUNCTION record_account_tombstone();
+DROP INDEX idx_transaction_account_id_updated_at_id;
+DROP INDEX idx_account_user_id_updated_at_id;
+DROP TABLE sync_tombstone;
+DROP TYPE sync_resource;
@@ -0,0 +1,46 @@
+CREATE TYPE sync_resource AS ENUM ('account', 'transaction');
+
+-- What was deleted for each user, so that delta syncs can report it.
+CREATE TABLE sync_tombstone (
+        id BIGSERIAL PRIMARY KEY,
+        deleted_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        account_id UUID NOT NULL,
+        resource sync_resource NOT NULL,
+        resource_id VARCHAR(36) NOT NULL,
+        CONSTRAINT fk_sync_tombstone_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_sync_tombstone_user_id_id ON sync_tombstone (user_id, id);
+CREATE INDEX idx_account_user_id_updated_at_id ON account (user_id, updated_at, id);
+CREATE INDEX idx_transaction_account_id_updated_at_id ON "transaction" (account_id, updated_at, id);
+
+CREATE OR REPLACE FUNCTION record_account_tombstone()
+RETURNS TRIGGER AS $$
+BEGIN
+        INSERT INTO sync_tombstone (user_id, account_id, resource, resource_id)
+        VALUES (OLD.user_id, OLD.id, 'account', OLD.id::text);
+        RETURN OLD;
+END;
+$$ language 'plpgsql';
+
+CREATE OR REPLACE FUNCTION record_transaction_tombstone()
+RETURNS TRIGGER AS $$
+BEGIN
+        INSERT INTO sync_tombstone (user_id, account_id, resource, resource_id)
+        SELECT account.user_id, OLD.account_id, 'transaction', OLD.id::text
+        FROM account
+        WHERE account.id = OLD.account_id;
+        RETURN OLD;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER record_account_tombstone
+        AFTER DELETE ON account
+        FOR EACH ROW
+        EXECUTE FUNCTION record_account_tombstone();
+
+CREATE TRIGGER record_transaction_tombstone
+        AFTER DELETE ON "transaction"
+        FOR EACH ROW
+        EXECUTE FUNCTION record_transaction_tombstone();
@@ -0,0 +1,3 @@
+DROP TRIGGER record_transaction_history ON "transaction";
+DROP FUNCTION record_transaction_history();
+DROP TABLE transaction_history;
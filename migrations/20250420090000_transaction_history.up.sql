@@ -0,0 +1,42 @@
+-- The state of a transaction before and after each edit, so that an edit
+-- can be reverted.
+CREATE TABLE transaction_history (
+        id BIGSERIAL PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        transaction_id BIGINT NOT NULL,
+        before_posted_at TIMESTAMPTZ NOT NULL,
+        before_asset_id UUID NOT NULL,
+        before_description TEXT,
+        before_quantity BIGINT NOT NULL,
+        after_posted_at TIMESTAMPTZ NOT NULL,
+        after_asset_id UUID NOT NULL,
+        after_description TEXT,
+        after_quantity BIGINT NOT NULL,
+        CONSTRAINT fk_transaction_history_transaction_id_transaction FOREIGN KEY (transaction_id) REFERENCES "transaction" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_transaction_history_transaction_id_id ON transaction_history (transaction_id, id);
+
+CREATE OR REPLACE FUNCTION record_transaction_history()
+RETURNS TRIGGER AS $$
+BEGIN
+        INSERT INTO transaction_history (
+                transaction_id,
+                before_posted_at, before_asset_id, before_description, before_quantity,
+                after_posted_at, after_asset_id, after_description, after_quantity
+        )
+        VALUES (
+                NEW.id,
+                OLD.posted_at, OLD.asset_id, OLD.description, OLD.quantity,
+                NEW.posted_at, NEW.asset_id, NEW.description, NEW.quantity
+        );
+        RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER record_transaction_history
+        AFTER UPDATE ON "transaction"
+        FOR EACH ROW
+        WHEN ((OLD.posted_at, OLD.asset_id, OLD.description, OLD.quantity)
+                IS DISTINCT FROM (NEW.posted_at, NEW.asset_id, NEW.description, NEW.quantity))
+        EXECUTE FUNCTION record_transaction_history();
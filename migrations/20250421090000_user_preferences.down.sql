@@ -0,0 +1,2 @@
+DROP TRIGGER update_user_preference_updated_at ON user_preference;
+DROP TABLE user_preference;
@@ -0,0 +1,14 @@
+-- The settings a user chooses for themselves, one row per user.
+CREATE TABLE user_preference (
+        user_id UUID PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        default_asset_id UUID,
+        CONSTRAINT fk_user_preference_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_user_preference_default_asset_id_asset FOREIGN KEY (default_asset_id) REFERENCES asset (id) ON DELETE SET NULL
+);
+
+CREATE TRIGGER update_user_preference_updated_at
+        BEFORE UPDATE ON user_preference
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
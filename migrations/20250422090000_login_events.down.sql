@@ -0,0 +1,2 @@
+DROP TABLE login_event;
+DROP TYPE login_event_type;
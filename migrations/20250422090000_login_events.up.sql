@@ -0,0 +1,17 @@
+CREATE TYPE login_event_type AS ENUM ('login', 'refresh', 'logout');
+
+-- The sign-ins of each user, so they can review where their account was
+-- used. The address is stored only in the form `LOGIN_EVENT_IP_MODE` asks
+-- for.
+CREATE TABLE login_event (
+        id BIGSERIAL PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        event_type login_event_type NOT NULL,
+        issuer VARCHAR(255) NOT NULL,
+        ip_address VARCHAR(64),
+        user_agent VARCHAR(512),
+        CONSTRAINT fk_login_event_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_login_event_user_id_id ON login_event (user_id, id);
@@ -0,0 +1,21 @@
+-- Fractional quantities are rounded to the nearest integer.
+ALTER TABLE budget
+        ALTER COLUMN amount TYPE BIGINT USING ROUND(amount)::BIGINT;
+
+DROP TRIGGER record_transaction_history ON "transaction";
+
+ALTER TABLE transaction_history
+        ALTER COLUMN before_quantity TYPE BIGINT USING ROUND(before_quantity)::BIGINT,
+        ALTER COLUMN after_quantity TYPE BIGINT USING ROUND(after_quantity)::BIGINT;
+
+ALTER TABLE "transaction"
+        ALTER COLUMN quantity TYPE BIGINT USING ROUND(quantity)::BIGINT;
+
+CREATE TRIGGER record_transaction_history
+        AFTER UPDATE ON "transaction"
+        FOR EACH ROW
+        WHEN ((OLD.posted_at, OLD.asset_id, OLD.description, OLD.quantity)
+                IS DISTINCT FROM (NEW.posted_at, NEW.asset_id, NEW.description, NEW.quantity))
+        EXECUTE FUNCTION record_transaction_history();
+
+ALTER TABLE asset DROP COLUMN decimals;
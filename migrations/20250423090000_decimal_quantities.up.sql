@@ -0,0 +1,27 @@
+-- Quantities become decimals in whole units of their asset, so assets with
+-- fractional units keep their precision. An asset records how many
+-- decimals its quantities have; existing assets get none, which keeps the
+-- integers already stored the same quantities.
+ALTER TABLE asset
+        ADD COLUMN decimals SMALLINT NOT NULL DEFAULT 0 CHECK (decimals BETWEEN 0 AND 18);
+
+-- The history trigger names the quantity, so it has to be dropped while
+-- the column changes type.
+DROP TRIGGER record_transaction_history ON "transaction";
+
+ALTER TABLE "transaction"
+        ALTER COLUMN quantity TYPE NUMERIC USING quantity::NUMERIC;
+
+ALTER TABLE transaction_history
+        ALTER COLUMN before_quantity TYPE NUMERIC USING before_quantity::NUMERIC,
+        ALTER COLUMN after_quantity TYPE NUMERIC USING after_quantity::NUMERIC;
+
+CREATE TRIGGER record_transaction_history
+        AFTER UPDATE ON "transaction"
+        FOR EACH ROW
+        WHEN ((OLD.posted_at, OLD.asset_id, OLD.description, OLD.quantity)
+                IS DISTINCT FROM (NEW.posted_at, NEW.asset_id, NEW.description, NEW.quantity))
+        EXECUTE FUNCTION record_transaction_history();
+
+ALTER TABLE budget
+        ALTER COLUMN amount TYPE NUMERIC USING amount::NUMERIC;
@@ -0,0 +1,7 @@
+ALTER TABLE "transaction"
+        DROP COLUMN applied_rule_id,
+        DROP COLUMN category;
+
+DROP TRIGGER update_categorization_rule_updated_at ON categorization_rule;
+DROP TABLE categorization_rule;
+DROP TYPE categorization_rule_field;
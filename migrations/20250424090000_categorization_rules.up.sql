@@ -0,0 +1,38 @@
+CREATE TYPE categorization_rule_field AS ENUM ('description', 'quantity_range', 'account');
+
+-- Rules a user sets to categorize their transactions when no category is
+-- given. The columns a rule matches on depend on its field.
+CREATE TABLE categorization_rule (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        field categorization_rule_field NOT NULL,
+        pattern TEXT,
+        min_quantity NUMERIC,
+        max_quantity NUMERIC,
+        account_id UUID,
+        category VARCHAR(254) NOT NULL,
+        priority INTEGER NOT NULL DEFAULT 0,
+        CONSTRAINT fk_categorization_rule_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_categorization_rule_account_id_account FOREIGN KEY (account_id) REFERENCES account (id) ON DELETE CASCADE,
+        CONSTRAINT ck_categorization_rule_match CHECK (
+                (field = 'description' AND pattern IS NOT NULL AND min_quantity IS NULL AND max_quantity IS NULL AND account_id IS NULL)
+                OR (field = 'quantity_range' AND (min_quantity IS NOT NULL OR max_quantity IS NOT NULL) AND pattern IS NULL AND account_id IS NULL)
+                OR (field = 'account' AND account_id IS NOT NULL AND pattern IS NULL AND min_quantity IS NULL AND max_quantity IS NULL)
+        )
+);
+
+CREATE INDEX idx_categorization_rule_user_id ON categorization_rule (user_id);
+
+CREATE TRIGGER update_categorization_rule_updated_at
+        BEFORE UPDATE ON categorization_rule
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
+
+-- The rule that set the category, which is cleared once the category is
+-- set by hand.
+ALTER TABLE "transaction"
+        ADD COLUMN category VARCHAR(254),
+        ADD COLUMN applied_rule_id UUID,
+        ADD CONSTRAINT fk_transaction_applied_rule_id_categorization_rule FOREIGN KEY (applied_rule_id) REFERENCES categorization_rule (id) ON DELETE SET NULL;
@@ -0,0 +1,16 @@
+-- The refresh token chains of each user, one per device they signed in on.
+-- Only a digest of the latest refresh token of a chain is kept. A revoked
+-- chain stays behind so the next refresh from its device is refused.
+CREATE TABLE user_session (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        last_refreshed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        token_hash BYTEA NOT NULL UNIQUE,
+        ip_address VARCHAR(64),
+        user_agent VARCHAR(512),
+        revoked_at TIMESTAMPTZ,
+        CONSTRAINT fk_user_session_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_user_session_user_id ON user_session (user_id);
@@ -0,0 +1,17 @@
+-- The balance of each account in each asset, kept up to date as its
+-- transactions are created, updated and deleted so reading it doesn't sum
+-- every transaction. `as_of_transaction_id` is the last transaction applied.
+CREATE TABLE account_balance (
+        account_id UUID NOT NULL,
+        asset_id UUID NOT NULL,
+        balance NUMERIC NOT NULL DEFAULT 0,
+        as_of_transaction_id BIGINT,
+        PRIMARY KEY (account_id, asset_id),
+        CONSTRAINT fk_account_balance_account_id_account FOREIGN KEY (account_id) REFERENCES account (id) ON DELETE CASCADE,
+        CONSTRAINT fk_account_balance_asset_id_asset FOREIGN KEY (asset_id) REFERENCES asset (id) ON DELETE CASCADE
+);
+
+INSERT INTO account_balance (account_id, asset_id, balance, as_of_transaction_id)
+SELECT account_id, asset_id, SUM(quantity), MAX(id)
+FROM "transaction"
+GROUP BY account_id, asset_id;
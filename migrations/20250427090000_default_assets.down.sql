@@ -0,0 +1,2 @@
+ALTER TABLE account DROP COLUMN default_asset_id;
+ALTER TABLE institution DROP COLUMN default_asset_id;
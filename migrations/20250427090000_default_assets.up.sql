@@ -0,0 +1,9 @@
+-- The asset new accounts at an institution show their values in, and the
+-- one an account shows its values in. Deleting the asset clears them.
+ALTER TABLE institution
+        ADD COLUMN default_asset_id UUID,
+        ADD CONSTRAINT fk_institution_default_asset_id_asset FOREIGN KEY (default_asset_id) REFERENCES asset (id) ON DELETE SET NULL;
+
+ALTER TABLE account
+        ADD COLUMN default_asset_id UUID,
+        ADD CONSTRAINT fk_account_default_asset_id_asset FOREIGN KEY (default_asset_id) REFERENCES asset (id) ON DELETE SET NULL;
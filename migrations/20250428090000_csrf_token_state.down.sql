@@ -0,0 +1,11 @@
+DROP INDEX uq_csrf_token_state;
+
+ALTER TABLE csrf_token
+        DROP COLUMN consumed_at,
+        DROP COLUMN created_at,
+        DROP COLUMN id;
+
+ALTER TABLE csrf_token RENAME COLUMN state TO token;
+
+ALTER TABLE csrf_token
+        ADD CONSTRAINT csrf_token_pkey PRIMARY KEY (token);
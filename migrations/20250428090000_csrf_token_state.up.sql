@@ -0,0 +1,14 @@
+-- Consumed tokens are kept so a state used twice can be told apart from one
+-- that was never issued.
+ALTER TABLE csrf_token
+        DROP CONSTRAINT csrf_token_pkey,
+        DROP CONSTRAINT IF EXISTS csrf_token_token_key;
+
+ALTER TABLE csrf_token RENAME COLUMN token TO state;
+
+ALTER TABLE csrf_token
+        ADD COLUMN id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+        ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        ADD COLUMN consumed_at TIMESTAMPTZ;
+
+CREATE UNIQUE INDEX uq_csrf_token_state ON csrf_token (state);
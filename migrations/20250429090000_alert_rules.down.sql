@@ -0,0 +1,5 @@
+DROP TABLE alert_event;
+DROP TRIGGER update_alert_rule_updated_at ON alert_rule;
+DROP TABLE alert_rule;
+DROP TYPE alert_channel;
+DROP TYPE alert_comparator;
@@ -0,0 +1,42 @@
+CREATE TYPE alert_comparator AS ENUM ('below', 'above');
+CREATE TYPE alert_channel AS ENUM ('email', 'webhook', 'sse');
+
+-- Rules a user sets to be told when the balance of an account in an asset
+-- crosses a threshold. `last_fired_at` holds the rule back until its
+-- cooldown has passed.
+CREATE TABLE alert_rule (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        account_id UUID NOT NULL,
+        asset_id UUID NOT NULL,
+        comparator alert_comparator NOT NULL,
+        threshold NUMERIC NOT NULL,
+        channel alert_channel NOT NULL,
+        cooldown_seconds BIGINT NOT NULL DEFAULT 0 CHECK (cooldown_seconds >= 0),
+        last_fired_at TIMESTAMPTZ,
+        CONSTRAINT fk_alert_rule_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_alert_rule_account_id_account FOREIGN KEY (account_id) REFERENCES account (id) ON DELETE CASCADE,
+        CONSTRAINT fk_alert_rule_asset_id_asset FOREIGN KEY (asset_id) REFERENCES asset (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_alert_rule_user_id ON alert_rule (user_id);
+CREATE INDEX idx_alert_rule_account_id_asset_id ON alert_rule (account_id, asset_id);
+
+CREATE TRIGGER update_alert_rule_updated_at
+        BEFORE UPDATE ON alert_rule
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
+
+-- Each time a rule fired, with the balance that set it off.
+CREATE TABLE alert_event (
+        id BIGSERIAL PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        alert_rule_id UUID NOT NULL,
+        balance NUMERIC NOT NULL,
+        channel alert_channel NOT NULL,
+        CONSTRAINT fk_alert_event_alert_rule_id_alert_rule FOREIGN KEY (alert_rule_id) REFERENCES alert_rule (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_alert_event_alert_rule_id_id ON alert_event (alert_rule_id, id);
@@ -0,0 +1 @@
+ALTER TABLE user_preference DROP COLUMN locale;
@@ -0,0 +1,2 @@
+-- The locale the exports of the user are formatted for.
+ALTER TABLE user_preference ADD COLUMN locale TEXT;
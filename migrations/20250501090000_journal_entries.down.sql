@@ -0,0 +1,5 @@
+ALTER TABLE user_preference DROP COLUMN double_entry;
+DROP INDEX idx_transaction_journal_entry_id;
+ALTER TABLE "transaction" DROP COLUMN journal_entry_id;
+DROP TRIGGER update_journal_entry_updated_at ON journal_entry;
+DROP TABLE journal_entry;
@@ -0,0 +1,24 @@
+-- Entries of double-entry bookkeeping, whose legs are transactions that sum
+-- to zero in each asset. Who an entry belongs to follows from the accounts
+-- of its legs, as it does for transactions.
+CREATE TABLE journal_entry (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        posted_at TIMESTAMPTZ NOT NULL,
+        description TEXT
+);
+
+CREATE TRIGGER update_journal_entry_updated_at
+        BEFORE UPDATE ON journal_entry
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE "transaction"
+        ADD COLUMN journal_entry_id UUID,
+        ADD CONSTRAINT fk_transaction_journal_entry_id_journal_entry FOREIGN KEY (journal_entry_id) REFERENCES journal_entry (id) ON DELETE CASCADE;
+
+CREATE INDEX idx_transaction_journal_entry_id ON "transaction" (journal_entry_id);
+
+-- Users in double-entry mode only create transactions as journal entries.
+ALTER TABLE user_preference ADD COLUMN double_entry BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+DROP INDEX idx_account_merge_target_id;
+DROP TABLE account_merge;
@@ -0,0 +1,25 @@
+-- The merges of duplicate accounts. The source account is deleted by the
+-- merge, so what it was is kept here along with how much was moved off it.
+CREATE TABLE account_merge (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        merged_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        target_id UUID NOT NULL,
+        source_id UUID NOT NULL,
+        source_name VARCHAR(254) NOT NULL,
+        source_institution_id UUID NOT NULL,
+        source_notes TEXT,
+        source_created_at TIMESTAMPTZ NOT NULL,
+        forced BOOLEAN NOT NULL,
+        transactions BIGINT NOT NULL,
+        attachments BIGINT NOT NULL,
+        categorization_rules BIGINT NOT NULL,
+        alert_rules BIGINT NOT NULL,
+        import_profiles BIGINT NOT NULL,
+        budgets BIGINT NOT NULL,
+        api_keys BIGINT NOT NULL,
+        CONSTRAINT fk_account_merge_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_account_merge_target_id_account FOREIGN KEY (target_id) REFERENCES account (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_account_merge_target_id ON account_merge (target_id);
@@ -0,0 +1,2 @@
+DROP INDEX idx_transaction_account_id_created_at_id;
+DROP INDEX idx_account_user_id_created_at_id;
@@ -0,0 +1,4 @@
+-- Lists filtered on when rows were created. Those filtered on when they
+-- were last changed use the indexes delta syncs already do.
+CREATE INDEX idx_account_user_id_created_at_id ON account (user_id, created_at, id);
+CREATE INDEX idx_transaction_account_id_created_at_id ON "transaction" (account_id, created_at, id);
@@ -0,0 +1,2 @@
+DROP TRIGGER update_quick_entry_updated_at ON quick_entry;
+DROP TABLE quick_entry;
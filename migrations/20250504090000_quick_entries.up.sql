@@ -0,0 +1,25 @@
+-- Transactions a user enters often, kept to be created again in one step.
+-- An entry outlives its account, losing it until it is pointed at another.
+CREATE TABLE quick_entry (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        label TEXT NOT NULL,
+        account_id UUID,
+        asset_id UUID NOT NULL,
+        quantity NUMERIC NOT NULL,
+        description TEXT,
+        category TEXT,
+        CONSTRAINT fk_quick_entry_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_quick_entry_account_id_account FOREIGN KEY (account_id) REFERENCES account (id) ON DELETE SET NULL,
+        CONSTRAINT fk_quick_entry_asset_id_asset FOREIGN KEY (asset_id) REFERENCES asset (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_quick_entry_user_id ON quick_entry (user_id);
+CREATE INDEX idx_quick_entry_account_id ON quick_entry (account_id);
+
+CREATE TRIGGER update_quick_entry_updated_at
+        BEFORE UPDATE ON quick_entry
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,7 @@
+-- The features an admin turned on or off, which take precedence over the
+-- environment across restarts.
+CREATE TABLE feature_flag (
+        name TEXT PRIMARY KEY,
+        enabled BOOLEAN NOT NULL,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1 @@
+DROP INDEX idx_transaction_account_id_posted_at_uncategorized;
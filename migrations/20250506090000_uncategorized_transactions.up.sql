@@ -0,0 +1,3 @@
+-- The latest transactions of a user still without a category, which the
+-- dashboard offers to categorize.
+CREATE INDEX idx_transaction_account_id_posted_at_uncategorized ON "transaction" (account_id, posted_at) WHERE category IS NULL;
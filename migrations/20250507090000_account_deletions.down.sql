@@ -0,0 +1,4 @@
+DROP INDEX idx_webhook_event_user_id_id;
+DROP TABLE webhook_event;
+DROP INDEX idx_account_deletion_user_id;
+DROP TABLE account_deletion;
@@ -0,0 +1,34 @@
+-- The deletions of accounts. The account is deleted along with its
+-- transactions, their attachments and its rules, so what it was is kept
+-- here along with how many of its rows went with it.
+CREATE TABLE account_deletion (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        deleted_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        account_id UUID NOT NULL,
+        account_name VARCHAR(254) NOT NULL,
+        institution_id UUID NOT NULL,
+        notes TEXT,
+        account_created_at TIMESTAMPTZ NOT NULL,
+        transactions BIGINT NOT NULL,
+        attachments BIGINT NOT NULL,
+        categorization_rules BIGINT NOT NULL,
+        alert_rules BIGINT NOT NULL,
+        CONSTRAINT fk_account_deletion_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_account_deletion_user_id ON account_deletion (user_id);
+
+-- The events to deliver to the webhooks of users, in the order they
+-- happened.
+CREATE TABLE webhook_event (
+        id BIGSERIAL PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        event VARCHAR(64) NOT NULL,
+        payload JSONB NOT NULL,
+        delivered_at TIMESTAMPTZ,
+        CONSTRAINT fk_webhook_event_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_webhook_event_user_id_id ON webhook_event (user_id, id);
@@ -0,0 +1,5 @@
+DROP TRIGGER update_account_statement_updated_at ON account_statement;
+DROP INDEX idx_account_statement_account_id;
+DROP TABLE account_statement;
+DROP TYPE statement_status;
+DROP TYPE statement_format;
@@ -0,0 +1,27 @@
+CREATE TYPE statement_format AS ENUM ('html', 'pdf');
+
+CREATE TYPE statement_status AS ENUM ('pending', 'complete', 'failed');
+
+-- The statements of months too large to render while responding. The
+-- document is stored once rendered, for the client to download.
+CREATE TABLE account_statement (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        account_id UUID NOT NULL,
+        year INTEGER NOT NULL,
+        month INTEGER NOT NULL,
+        format statement_format NOT NULL,
+        status statement_status NOT NULL DEFAULT 'pending',
+        content BYTEA,
+        error TEXT,
+        CONSTRAINT fk_account_statement_account_id_account FOREIGN KEY (account_id) REFERENCES account (id) ON DELETE CASCADE,
+        CONSTRAINT ck_account_statement_month CHECK (month BETWEEN 1 AND 12)
+);
+
+CREATE INDEX idx_account_statement_account_id ON account_statement (account_id);
+
+CREATE TRIGGER update_account_statement_updated_at
+        BEFORE UPDATE ON account_statement
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,6 @@
+DROP TRIGGER update_change_proposal_updated_at ON change_proposal;
+DROP INDEX idx_change_proposal_proposer_id;
+DROP INDEX idx_change_proposal_status_created_at;
+DROP TABLE change_proposal;
+DROP TYPE change_proposal_status;
+DROP TYPE change_proposal_kind;
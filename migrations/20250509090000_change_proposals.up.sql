@@ -0,0 +1,35 @@
+CREATE TYPE change_proposal_kind AS ENUM ('create_institution', 'edit_institution', 'create_asset');
+
+CREATE TYPE change_proposal_status AS ENUM ('pending', 'approved', 'rejected');
+
+-- The changes to the shared institutions and assets users propose for an
+-- admin to review. An approved proposal links the resource it was applied
+-- to.
+CREATE TABLE change_proposal (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        kind change_proposal_kind NOT NULL,
+        payload JSONB NOT NULL,
+        proposer_id UUID NOT NULL,
+        status change_proposal_status NOT NULL DEFAULT 'pending',
+        reviewer_id UUID,
+        review_note TEXT,
+        reviewed_at TIMESTAMPTZ,
+        institution_id UUID,
+        asset_id UUID,
+        CONSTRAINT fk_change_proposal_proposer_id_user FOREIGN KEY (proposer_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_change_proposal_reviewer_id_user FOREIGN KEY (reviewer_id) REFERENCES "user" (id) ON DELETE SET NULL,
+        CONSTRAINT fk_change_proposal_institution_id_institution FOREIGN KEY (institution_id) REFERENCES institution (id) ON DELETE SET NULL,
+        CONSTRAINT fk_change_proposal_asset_id_asset FOREIGN KEY (asset_id) REFERENCES asset (id) ON DELETE SET NULL,
+        CONSTRAINT ck_change_proposal_reviewed CHECK ((status = 'pending') = (reviewed_at IS NULL))
+);
+
+CREATE INDEX idx_change_proposal_status_created_at ON change_proposal (status, created_at);
+
+CREATE INDEX idx_change_proposal_proposer_id ON change_proposal (proposer_id);
+
+CREATE TRIGGER update_change_proposal_updated_at
+        BEFORE UPDATE ON change_proposal
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
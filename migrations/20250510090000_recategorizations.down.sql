@@ -0,0 +1,4 @@
+DROP TRIGGER update_recategorization_updated_at ON recategorization;
+DROP INDEX idx_recategorization_user_id;
+DROP TABLE recategorization;
+DROP TYPE recategorization_status;
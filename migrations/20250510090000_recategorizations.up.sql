@@ -0,0 +1,25 @@
+CREATE TYPE recategorization_status AS ENUM ('pending', 'complete', 'failed');
+
+-- The bulk recategorizations of transactions by a filter, one row for each
+-- operation. It records the filter and the category it was made with, and
+-- how many transactions it changed so far while it runs.
+CREATE TABLE recategorization (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        filter JSONB NOT NULL,
+        category VARCHAR(254) NOT NULL,
+        matched BIGINT NOT NULL,
+        changed BIGINT NOT NULL DEFAULT 0,
+        status recategorization_status NOT NULL DEFAULT 'pending',
+        error TEXT,
+        CONSTRAINT fk_recategorization_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_recategorization_user_id ON recategorization (user_id);
+
+CREATE TRIGGER update_recategorization_updated_at
+        BEFORE UPDATE ON recategorization
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,4 @@
+-- Whether the email of the token a user registered with was verified. Users
+-- registered before it was recorded have none, as do users created without
+-- a token, like seeded ones.
+ALTER TABLE "user" ADD COLUMN email_verified_at_registration BOOLEAN;
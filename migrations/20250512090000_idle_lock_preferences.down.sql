@@ -0,0 +1,3 @@
+ALTER TABLE user_preference
+    DROP COLUMN idle_lock_while_hidden,
+    DROP COLUMN idle_lock_minutes;
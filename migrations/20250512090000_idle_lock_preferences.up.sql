@@ -0,0 +1,6 @@
+-- How long the app of a user may sit idle before it locks, in minutes, the
+-- default if NULL and never if 0, and whether time spent in a hidden tab
+-- counts towards it.
+ALTER TABLE user_preference
+    ADD COLUMN idle_lock_minutes INTEGER,
+    ADD COLUMN idle_lock_while_hidden BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,3 @@
+DROP TRIGGER update_watchlist_entry_updated_at ON watchlist_entry;
+DROP TABLE watchlist_entry;
+DROP TYPE watch_direction;
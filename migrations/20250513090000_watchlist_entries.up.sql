@@ -0,0 +1,30 @@
+CREATE TYPE watch_direction AS ENUM ('above', 'below');
+
+-- Assets a user keeps an eye on, priced in a quote asset. An entry with a
+-- target price fires once the latest price reaches it in its direction,
+-- then stays quiet until its target changes, unless it is `repeating`.
+CREATE TABLE watchlist_entry (
+        id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id UUID NOT NULL,
+        asset_id UUID NOT NULL,
+        quote_asset_id UUID NOT NULL,
+        target_price NUMERIC CHECK (target_price > 0),
+        direction watch_direction NOT NULL DEFAULT 'above',
+        channel alert_channel NOT NULL DEFAULT 'email',
+        repeating BOOLEAN NOT NULL DEFAULT FALSE,
+        triggered_at TIMESTAMPTZ,
+        triggered_price NUMERIC,
+        CONSTRAINT fk_watchlist_entry_user_id_user FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+        CONSTRAINT fk_watchlist_entry_asset_id_asset FOREIGN KEY (asset_id) REFERENCES asset (id) ON DELETE CASCADE,
+        CONSTRAINT fk_watchlist_entry_quote_asset_id_asset FOREIGN KEY (quote_asset_id) REFERENCES asset (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_watchlist_entry_user_id ON watchlist_entry (user_id);
+CREATE INDEX idx_watchlist_entry_asset_id_quote_asset_id ON watchlist_entry (asset_id, quote_asset_id);
+
+CREATE TRIGGER update_watchlist_entry_updated_at
+        BEFORE UPDATE ON watchlist_entry
+        FOR EACH ROW
+        EXECUTE FUNCTION update_updated_at_column();
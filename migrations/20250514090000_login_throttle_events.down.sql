@@ -0,0 +1,2 @@
+DROP TABLE login_throttle_event;
+DROP TYPE login_throttle_key;
@@ -0,0 +1,17 @@
+CREATE TYPE login_throttle_key AS ENUM ('ip', 'refresh_token');
+
+-- The addresses and refresh tokens the sign in and refresh endpoints made
+-- wait, one row each time one starts to, for reviewing abuse. Addresses
+-- are stored only in the form `LOGIN_EVENT_IP_MODE` asks for and refresh
+-- tokens as a fingerprint of their hash.
+CREATE TABLE login_throttle_event (
+        id BIGSERIAL PRIMARY KEY,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        endpoint VARCHAR(64) NOT NULL,
+        key_kind login_throttle_key NOT NULL,
+        key VARCHAR(64),
+        failures INTEGER NOT NULL,
+        locked_until TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_login_throttle_event_created_at ON login_throttle_event (created_at);
@@ -0,0 +1,3 @@
+-- Lets the transactions of an account be filtered by the size of their
+-- quantity regardless of its sign.
+CREATE INDEX idx_transaction_account_id_abs_quantity ON "transaction" (account_id, ABS(quantity));
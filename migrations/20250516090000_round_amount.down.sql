@@ -0,0 +1 @@
+DROP FUNCTION round_amount(NUMERIC, INT, TEXT);
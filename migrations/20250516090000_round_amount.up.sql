@@ -0,0 +1,13 @@
+-- Rounds like `RoundingPolicy` in the server, so the amounts queries round
+-- agree with the ones it does. `ROUND` already rounds halves away from
+-- zero, banker's rounding takes those to the even neighbour instead.
+CREATE FUNCTION round_amount(value NUMERIC, decimals INT, policy TEXT) RETURNS NUMERIC
+LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT CASE
+        WHEN policy = 'bankers' AND ABS(shifted - TRUNC(shifted)) = 0.5
+            THEN ROUND((TRUNC(shifted) + MOD(TRUNC(shifted), 2)) / (10::NUMERIC ^ decimals), decimals)
+        ELSE ROUND(value, decimals)
+    END
+    FROM (SELECT value * (10::NUMERIC ^ decimals) AS shifted) AS s
+$$;
@@ -0,0 +1 @@
+DROP INDEX idx_csrf_token_created_at;
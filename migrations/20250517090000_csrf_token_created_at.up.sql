@@ -0,0 +1,2 @@
+-- Stale tokens are deleted by when they were issued.
+CREATE INDEX idx_csrf_token_created_at ON csrf_token (created_at);
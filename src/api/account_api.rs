@@ -1,48 +1,81 @@
 use crate::{
     api::{ApiError, client::ApiClient},
-    model::account::AccountId,
+    model::{account::AccountId, statement::StatementId},
     schema::{
         Pagination,
         account::{
-            AccountCreateResponse, AccountGetResponse, AccountUpdateResponse, CreateRequest,
-            DeleteResponse, GetListRequest, GetListResponse, UpdateRequest,
+            AccountCreateResponse, AccountGetResponse, AccountUpdateResponse, BalancesResponse,
+            CreateRequest, DeleteResponse, DeletionPreviewResponse, FromTemplateRequest,
+            FromTemplateResponse, GetListRequest, GetListResponse, MergeRequest, MergeResponse,
+            SyncResponse, UpdateRequest,
         },
     },
 };
 use leptos::{
     server,
-    server_fn::codec::{DeleteUrl, GetUrl, Json, PatchJson},
+    server_fn::codec::{ByteStream, DeleteUrl, GetUrl, Json, PatchJson, Streaming},
 };
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
-        api::{Api, ApiErrorResponse, AppState, extract_with_state, set_user_groups},
+        api::transaction_api::TransactionApiState,
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
         authentication::{
-            authenticated_token::AuthenticatedToken, authenticator::Authenticator,
-            registered_user::RegisteredUser,
+            api_key::authenticate_api_key, authenticator::Authenticator,
+            registered_user::RegisteredUser, step_up::Elevation,
         },
         authorization::{
             PermissionConfig, PermissionSet,
             actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
         },
-        model::{account::AccountCreate, cursor_key::CursorKey},
+        config::PagedResource,
+        integration::SyncJob,
+        model::{
+            account::AccountCreate,
+            account_template::AccountTemplate,
+            cursor_key::CursorKey,
+            provider_connection::ProviderConnectionFilter,
+            statement::{AccountStatement, StatementFormat},
+        },
+        resource::{
+            GetListRepository, GetRepository, account_balance_repository::AccountBalanceRepository,
+            deadline, institution_repository::InstitutionRepository,
+            provider_connection_repository::ProviderConnectionRepository,
+        },
+        schema::{
+            DateRange,
+            account::{DeleteRequest, StatementRequest, StatementResponse},
+            notes::validate_notes,
+            text::ACCOUNT_NAME,
+        },
         service::{
-            account_service::AccountServiceMethods, account_service_factory::AccountServiceFactory,
+            ServiceError, account_service::AccountServiceMethods,
+            account_service_factory::AccountServiceFactory,
         },
+        statement::{Rendering, StatementError, StatementMonth, render_or_queue},
     };
     pub use axum::{
-        RequestPartsExt, Router,
+        Router,
         body::Body,
-        extract::{FromRequestParts, Path, Request, State},
+        extract::{Query, Request, State},
         middleware::from_fn_with_state,
         response::IntoResponse,
     };
-    pub use http::request::Parts;
+    pub use chrono::Utc;
+    pub use futures::stream;
+    pub use http::{
+        HeaderValue, Method,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    };
     pub use leptos::prelude::*;
     pub use leptos_axum::{
-        ResponseOptions, extract, generate_request_and_parts, handle_server_fns_with_context,
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
     };
     pub use std::sync::Arc;
     pub use tower::ServiceBuilder;
@@ -58,56 +91,43 @@ pub struct PathAccountId {
     id: AccountId,
 }
 
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathStatementId {
+    id: AccountId,
+    statement_id: StatementId,
+}
+
 #[cfg(feature = "ssr")]
 mod ssr {
     use super::*;
-    pub struct AccountApiState {
-        pub authenticated_token: AuthenticatedToken,
-        pub account_service: Box<dyn AccountServiceMethods + Send>,
-    }
 
-    impl FromRequestParts<AppState> for AccountApiState {
-        type Rejection = ApiError;
+    /// The levels of `accounts` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct AccountApiResource;
+
+    impl ApiResource for AccountApiResource {
+        const NAME: &'static str = "accounts";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn AccountServiceMethods + Send>;
 
-        async fn from_request_parts(
-            parts: &mut Parts,
+        fn service(
             state: &AppState,
-        ) -> Result<Self, Self::Rejection> {
-            let authenticated_token = parts
-                .extract_with_state::<AuthenticatedToken, _>(state)
-                .await?;
-
-            let registered_user = parts.extract_with_state::<RegisteredUser, _>(state).await?;
-
-            let permission_set = PermissionSet::new(
-                "accounts",
-                &state.enforcer,
-                &authenticated_token,
-                PermissionConfig {
-                    min_read_level: ReadLevel::Read,
-                    min_create_level: CreateLevel::Create,
-                    min_update_level: UpdateLevel::Update,
-                    min_delete_level: DeleteLevel::Delete,
-                },
-            )
-            .map_err(|e| {
-                error!("{e}");
-                ApiError::ServerError
-            })?;
-
-            let account_service = AccountServiceFactory::build(
-                registered_user,
-                Arc::clone(&state.connection_pool),
-                permission_set,
-            );
-
-            Ok(Self {
-                authenticated_token,
-                account_service,
-            })
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            AccountServiceFactory::build(owner, Arc::clone(&state.connection_pool), permission_set)
         }
     }
 
+    pub type AccountApiState = ResourceContext<AccountApiResource>;
+
     async fn server_fn_handler(
         State(state): State<AppState>,
         req: Request<Body>,
@@ -115,10 +135,22 @@ mod ssr {
         let path = match req.uri().to_string() {
             val if val == "/" => "".to_string(),
             val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            val if val == "/from-template" => "/from-template".to_string(),
+            val if val.ends_with("/sync") => "/sync".to_string(),
+            val if val.ends_with("/balances") => "/balances".to_string(),
+            val if val.ends_with("/merge") => "/merge".to_string(),
+            val if val.ends_with("/deletion-preview") => "/deletion-preview".to_string(),
+            val if val.ends_with("/statement") || val.contains("/statement?") => {
+                "/statement".to_string()
+            }
+            val if val.contains("/statements/") => "/statements/".to_string(),
             _ => "/".to_string(),
         };
         let (mut req, parts) = generate_request_and_parts(req);
-        *req.uri_mut() = format!("/api/accounts{path}").parse().unwrap();
+        *req.uri_mut() = match server_fn_uri(format!("/api/accounts{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
         handle_server_fns_with_context(
             {
                 let app_state = state.clone();
@@ -130,31 +162,106 @@ mod ssr {
             req,
         )
         .await
+        .into_response()
     }
 
     pub struct AccountApi;
 
     impl Api for AccountApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::POST, "/from-template"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
+                (Method::POST, "/{id}/sync"),
+                (Method::GET, "/{id}/balances"),
+                (Method::POST, "/{id}/merge"),
+                (Method::GET, "/{id}/deletion-preview"),
+                (Method::GET, "/{id}/statement"),
+                (Method::GET, "/{id}/statements/{statement_id}"),
+            ]
+        }
+
         fn router(state: AppState) -> Router<AppState> {
             Router::new()
                 .route(
                     "/",
                     axum::routing::get(server_fn_handler).post(server_fn_handler),
                 )
+                .route("/from-template", axum::routing::post(server_fn_handler))
                 .route(
                     "/{id}",
                     axum::routing::get(server_fn_handler)
                         .patch(server_fn_handler)
                         .delete(server_fn_handler),
                 )
+                .route("/{id}/sync", axum::routing::post(server_fn_handler))
+                .route("/{id}/balances", axum::routing::get(server_fn_handler))
+                .route("/{id}/merge", axum::routing::post(server_fn_handler))
+                .route(
+                    "/{id}/deletion-preview",
+                    axum::routing::get(server_fn_handler),
+                )
+                .route("/{id}/statement", axum::routing::get(server_fn_handler))
+                .route(
+                    "/{id}/statements/{statement_id}",
+                    axum::routing::get(server_fn_handler),
+                )
                 .layer(
                     ServiceBuilder::new()
+                        .layer(from_fn_with_state(state.clone(), authenticate_api_key))
                         .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
                         .layer(from_fn_with_state(state.clone(), set_user_groups)),
                 )
                 .with_state(state)
         }
     }
+
+    /// Responds with a rendered statement, named after its account and
+    /// month for when it is saved.
+    pub fn statement_document(
+        account_id: AccountId,
+        month: StatementMonth,
+        format: StatementFormat,
+        content: Vec<u8>,
+    ) -> Result<ByteStream<ApiError>, ApiError> {
+        let disposition = format!(
+            "inline; filename=\"statement-{account_id}-{}.{}\"",
+            month.label(),
+            format.extension()
+        );
+        let response_opts = expect_context::<ResponseOptions>();
+        response_opts.insert_header(
+            CONTENT_TYPE,
+            HeaderValue::from_static(format.content_type()),
+        );
+        response_opts.insert_header(
+            CONTENT_DISPOSITION,
+            HeaderValue::try_from(disposition).map_err(|e| {
+                error!("{e}");
+                ApiError::ServerError
+            })?,
+        );
+        provide_context(response_opts);
+        Ok(ByteStream::new(stream::iter([Ok::<_, ApiError>(content)])))
+    }
+
+    /// Responds with where a statement rendered after responding stands.
+    pub fn statement_status(statement: AccountStatement) -> Result<ByteStream<ApiError>, ApiError> {
+        let response = StatementResponse::from(statement);
+        let body = serde_json::to_vec(&response).map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?;
+        let response_opts = expect_context::<ResponseOptions>();
+        response_opts.set_status(response.status_code());
+        response_opts.insert_header(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        provide_context(response_opts);
+        Ok(ByteStream::new(stream::iter([Ok::<_, ApiError>(body)])))
+    }
 }
 
 #[cfg(feature = "ssr")]
@@ -170,7 +277,7 @@ pub use ssr::*;
         ("OpenIDConnect" = ["groups", "email"])
     ),
     responses(
-        (status = 200, description = "The list of accounts.", body = GetListResponse)
+        (status = 200, description = "The list of accounts. The `created_*` and `updated_*` filters are on when an account was recorded and last changed, and exclude the time itself.", body = GetListResponse)
     ),
 ))]
 #[server(
@@ -191,13 +298,15 @@ pub async fn get_list(
 ) -> Result<GetListResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
-    let pagination = extract_with_state::<Pagination, _>(&state).await?;
+    let pagination = extract_with_state::<Pagination, _>(&state)
+        .await?
+        .for_resource(PagedResource::Accounts)?;
     let cursor_key = extract_with_state::<CursorKey, _>(&state).await?;
 
     let offset = pagination.offset();
     let accounts = api_state
-        .account_service
-        .get_list(offset, pagination.max_items, filter.into())
+        .service
+        .get_list(offset, pagination.limit().into(), filter.into())
         .await?;
     let response = GetListResponse::new(accounts, &pagination, &cursor_key)?;
     Ok(response)
@@ -227,10 +336,25 @@ pub async fn get_list(
 pub async fn get() -> Result<AccountGetResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
-    let Path(PathAccountId { id }) = extract().await?;
+    let PathAccountId { id } = extract_path().await?;
 
-    let account = api_state.account_service.get(id).await?;
-    Ok(account.into())
+    let account = api_state.service.get(id).await?;
+    let institution_default = match account.default_asset_id {
+        Some(_) => None,
+        None => {
+            InstitutionRepository
+                .get(
+                    deadline::begin(&state.connection_pool)
+                        .await
+                        .map_err(ServiceError::from)?,
+                    account.institution_id,
+                )
+                .await
+                .map_err(ServiceError::from)?
+                .default_asset_id
+        }
+    };
+    Ok(AccountGetResponse::from(account).with_institution_default(institution_default))
 }
 
 #[cfg_attr(feature = "ssr", utoipa::path(
@@ -256,15 +380,18 @@ pub async fn get() -> Result<AccountGetResponse, ApiError> {
 pub async fn create(
     #[server(flatten)] create_request: CreateRequest,
 ) -> Result<AccountCreateResponse, ApiError> {
+    validate_notes(create_request.notes.as_deref())?;
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
     let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
     let account_create = AccountCreate {
-        name: create_request.name,
+        name: ACCOUNT_NAME.sanitize(&create_request.name)?,
         institution_id: create_request.institution_id,
         user_id: registered_user.id(),
+        notes: create_request.notes,
+        default_asset_id: create_request.default_asset_id,
     };
-    let account = api_state.account_service.create(account_create).await?;
+    let account = api_state.service.create(account_create).await?;
 
     let response_opts = expect_context::<ResponseOptions>();
     response_opts.set_status(AccountCreateResponse::status());
@@ -272,6 +399,58 @@ pub async fn create(
     Ok(account.into())
 }
 
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/accounts/from-template",
+    tag = "Accounts",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = FromTemplateRequest,
+    responses(
+        (status = 201, description = "The accounts of the template, all created at once.", body = FromTemplateResponse),
+        (status = 400, description = "There is no template of the name."),
+        (status = 409, description = "None of the accounts were created, some of them are already at the institution. `errors` names each of them."),
+    ),
+))]
+#[server(
+    name = AccountApiFromTemplate,
+    prefix = "/api",
+    endpoint = "accounts/from-template",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn from_template(
+    #[server(flatten)] from_template_request: FromTemplateRequest,
+) -> Result<FromTemplateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+    let template = AccountTemplate::find(&from_template_request.template).ok_or_else(|| {
+        ApiError::client(ClientErrorCode::InvalidRequest, "Unknown account template.")
+    })?;
+    let account_creates = template
+        .accounts
+        .iter()
+        .map(|account| AccountCreate {
+            name: account.name.to_owned(),
+            institution_id: from_template_request.institution_id,
+            user_id: registered_user.id(),
+            notes: None,
+            default_asset_id: None,
+        })
+        .collect();
+    let accounts = api_state.service.create_many(account_creates).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(FromTemplateResponse::status());
+    provide_context(response_opts);
+    Ok(FromTemplateResponse {
+        accounts: accounts.into_iter().map(|x| x.into()).collect(),
+    })
+}
+
 #[cfg_attr(feature = "ssr", utoipa::path(
     patch,
     path = "/api/accounts/{id}",
@@ -298,13 +477,15 @@ pub async fn create(
 pub async fn update(
     #[server(flatten)] update_request: UpdateRequest,
 ) -> Result<AccountUpdateResponse, ApiError> {
+    validate_notes(update_request.notes.as_deref())?;
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
-    let Path(PathAccountId { id }) = extract().await?;
-    let account = api_state
-        .account_service
-        .update(id, update_request.into())
-        .await?;
+    let PathAccountId { id } = extract_path().await?;
+    let update_request = UpdateRequest {
+        name: ACCOUNT_NAME.sanitize(&update_request.name)?,
+        ..update_request
+    };
+    let account = api_state.service.update(id, update_request.into()).await?;
 
     Ok(account.into())
 }
@@ -312,17 +493,29 @@ pub async fn update(
 #[cfg_attr(feature = "ssr", utoipa::path(
     delete,
     path = "/api/accounts/{id}",
-    params(AccountId),
+    params(AccountId, DeleteRequest),
     tag = "Accounts",
     security(
         ("OpenIDConnect" = ["groups", "email"])
     ),
     responses(
-        (status = 204, description = "The account was successfully deleted."),
+        (status = 204, description = "The account was deleted along with its transactions, their attachments and its rules, all at once. The deletion is recorded and sent to the webhooks of the user as an `account.deleted` event."),
+        (status = 401, description = "A step-up is required to delete the account.", body = ApiErrorResponse, content_type="application/json", example = json!(ApiErrorResponse {
+            code: 4011,
+            message: "step_up_required".to_string()
+        })),
         (status = 404, description = "The account was not found.", body = ApiErrorResponse, content_type="application/json", example = json!(ApiErrorResponse {
             code: 4040,
             message: "Not found.".to_string()
         })),
+        (status = 409, description = "The account has legs of journal entries, which must be deleted first.", body = ApiErrorResponse, content_type="application/json", example = json!(ApiErrorResponse {
+            code: 4093,
+            message: "The account has legs of journal entries, which would no longer balance. Delete those entries with DELETE /api/journal-entries/{id} first.".to_string()
+        })),
+        (status = 428, description = "`confirm` wasn't set.", body = ApiErrorResponse, content_type="application/json", example = json!(ApiErrorResponse {
+            code: 4280,
+            message: "Deleting the account deletes its transactions, their attachments and its rules. Count them with GET /api/accounts/{id}/deletion-preview, then pass confirm=true to delete it.".to_string()
+        })),
     ),
 ))]
 #[server(
@@ -335,11 +528,286 @@ pub async fn update(
 pub async fn delete() -> Result<DeleteResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
-    let Path(PathAccountId { id }) = extract().await?;
-    api_state.account_service.delete(id).await?;
+    let PathAccountId { id } = extract_path().await?;
+    let Query(delete_request) = extract_with_state::<Query<DeleteRequest>, _>(&()).await?;
+    if !delete_request.confirm {
+        return Err(ApiError::DeletionUnconfirmed);
+    }
+    extract_with_state::<Elevation, _>(&state)
+        .await?
+        .require()?;
+    api_state.service.delete(id).await?;
 
     let response_opts = expect_context::<ResponseOptions>();
     response_opts.set_status(DeleteResponse::status());
     provide_context(response_opts);
     Ok(DeleteResponse {})
 }
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/accounts/{id}/sync",
+    params(AccountId),
+    tag = "Accounts",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The transactions synced from the provider of the account.", body = SyncResponse),
+        (status = 400, description = "The provider connection is misconfigured or lacks the account."),
+        (status = 404, description = "The account or its provider connection was not found."),
+    ),
+))]
+#[server(
+    name = AccountApiSync,
+    prefix = "/api",
+    endpoint = "accounts/sync",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn sync() -> Result<SyncResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
+    let transaction_api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+    let PathAccountId { id } = extract_path().await?;
+
+    let account = api_state.service.get(id).await?;
+    let connection = ProviderConnectionRepository
+        .get_list(
+            deadline::begin(&state.connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            0,
+            Some(1),
+            ProviderConnectionFilter {
+                user_id: registered_user.id().into(),
+                institution_id: account.institution_id.into(),
+            },
+        )
+        .await
+        .map_err(ServiceError::from)?
+        .pop()
+        .ok_or(ApiError::NotFound)?;
+
+    let report = SyncJob {
+        connection,
+        account,
+    }
+    .run(&state.connection_pool, transaction_api_state.service)
+    .await?;
+    Ok(report.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/accounts/{id}/balances",
+    params(AccountId),
+    tag = "Accounts",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The balance of the account in each asset.", body = BalancesResponse),
+        (status = 404, description = "The account was not found."),
+    ),
+))]
+#[server(
+    name = AccountApiBalances,
+    prefix = "/api",
+    endpoint = "accounts/balances",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn balances() -> Result<BalancesResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
+    let PathAccountId { id } = extract_path().await?;
+
+    // Only the balances of accounts the caller can read.
+    let account = api_state.service.get(id).await?;
+    let balances = AccountBalanceRepository
+        .get_list_for_account(
+            deadline::begin(&state.connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            account.id,
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    Ok(BalancesResponse {
+        balances: balances.into_iter().map(|x| x.into()).collect(),
+    })
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/accounts/{id}/merge",
+    params(AccountId),
+    tag = "Accounts",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = MergeRequest,
+    responses(
+        (status = 200, description = "The source account was merged into the account and deleted. `moved` counts the rows moved off it.", body = MergeResponse),
+        (status = 400, description = "The source is the account itself."),
+        (status = 401, description = "A step-up is required to merge the accounts, as the source is deleted.", body = ApiErrorResponse, content_type="application/json", example = json!(ApiErrorResponse {
+            code: 4011,
+            message: "step_up_required".to_string()
+        })),
+        (status = 404, description = "Either account was not found among the accounts of the caller."),
+        (status = 409, description = "The accounts are at different institutions and `force` wasn't set.", body = ApiErrorResponse, content_type="application/json", example = json!(ApiErrorResponse {
+            code: 4094,
+            message: "The accounts are at different institutions, pass force=true to merge them anyway.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = AccountApiMerge,
+    prefix = "/api",
+    endpoint = "accounts/merge",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn merge(
+    #[server(flatten)] merge_request: MergeRequest,
+) -> Result<MergeResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
+    let PathAccountId { id } = extract_path().await?;
+    if merge_request.source_id == id {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            "An account cannot be merged into itself.",
+        ));
+    }
+    extract_with_state::<Elevation, _>(&state)
+        .await?
+        .require()?;
+
+    let account_merge = api_state
+        .service
+        .merge(id, merge_request.source_id, merge_request.force)
+        .await?;
+    Ok(account_merge.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/accounts/{id}/deletion-preview",
+    params(AccountId),
+    tag = "Accounts",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "How many rows of each kind deleting the account would delete with it. Rows added before the deletion are deleted too.", body = DeletionPreviewResponse),
+        (status = 404, description = "The account was not found."),
+    ),
+))]
+#[server(
+    name = AccountApiDeletionPreview,
+    prefix = "/api",
+    endpoint = "accounts/deletion-preview",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn deletion_preview() -> Result<DeletionPreviewResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
+    let PathAccountId { id } = extract_path().await?;
+
+    let counts = api_state.service.deletion_preview(id).await?;
+    Ok(counts.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/accounts/{id}/statement",
+    params(AccountId, StatementRequest, DateRange),
+    tag = "Accounts",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The statement of the month: the opening and closing balance of the account in each asset, the transactions posted in the month and their subtotals by category. Months are in UTC, so a date range such as `preset=last_month` must be one without a `utc_offset`.", content(
+            (String = "text/html"),
+            (String = "application/pdf"),
+        )),
+        (status = 202, description = "The month has too many transactions to render while responding. The statement is rendered after, poll `url` for it.", body = StatementResponse),
+        (status = 400, description = "The month is invalid or hasn't ended yet, the date range is not a month, or the server doesn't render PDF statements."),
+        (status = 404, description = "The account was not found."),
+    ),
+))]
+#[server(
+    name = AccountApiStatement,
+    prefix = "/api",
+    endpoint = "accounts/statement",
+    input = GetUrl,
+    output = Streaming,
+    client = ApiClient,
+)]
+pub async fn statement() -> Result<ByteStream<ApiError>, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
+    let PathAccountId { id } = extract_path().await?;
+    let Query(statement_request) = extract_with_state::<Query<StatementRequest>, _>(&()).await?;
+    let Query(range) = extract_with_state::<Query<DateRange>, _>(&()).await?;
+    let month = match (statement_request.year, statement_request.month) {
+        (Some(year), Some(month)) if range.is_empty() => StatementMonth::new(year, month)?,
+        (None, None) if !range.is_empty() => StatementMonth::of_range(range.resolve(Utc::now())?)?,
+        _ => return Err(StatementError::NoMonth.into()),
+    };
+    let format = statement_request.format;
+
+    // Only the statements of accounts the caller can read.
+    let account = api_state.service.get(id).await?;
+    match render_or_queue(&state.connection_pool, account, month, format).await? {
+        Rendering::Inline(content) => statement_document(id, month, format, content),
+        Rendering::Queued(statement) => statement_status(statement),
+    }
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/accounts/{id}/statements/{statement_id}",
+    params(AccountId, StatementId),
+    tag = "Accounts",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The statement once it is rendered, in the format it was asked for. A statement that could not be rendered is answered with its `error` instead.", content(
+            (String = "text/html"),
+            (String = "application/pdf"),
+            (StatementResponse = "application/json"),
+        )),
+        (status = 202, description = "The statement is still being rendered.", body = StatementResponse),
+        (status = 404, description = "The account or the statement was not found."),
+    ),
+))]
+#[server(
+    name = AccountApiGetStatement,
+    prefix = "/api",
+    endpoint = "accounts/statements/",
+    input = GetUrl,
+    output = Streaming,
+    client = ApiClient,
+)]
+pub async fn get_statement() -> Result<ByteStream<ApiError>, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AccountApiState, _>(&state).await?;
+    let PathStatementId { id, statement_id } = extract_path().await?;
+
+    let (statement, content) = api_state.service.get_statement(id, statement_id).await?;
+    let Some(content) = content else {
+        return statement_status(statement);
+    };
+    let month = StatementMonth::of(&statement)?;
+    statement_document(id, month, statement.format, content)
+}
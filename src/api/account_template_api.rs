@@ -0,0 +1,109 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::account_template::AccountTemplateGetListResponse,
+};
+use leptos::{
+    server,
+    server_fn::codec::{GetUrl, Json},
+};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{Api, AppState, server_fn_uri, set_user_groups},
+        authentication::authenticator::Authenticator,
+        model::account_template::ACCOUNT_TEMPLATES,
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = req.uri().to_string();
+        let path = path.trim_start_matches('/');
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/account-templates{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct AccountTemplateApi;
+
+    impl Api for AccountTemplateApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::GET, "/")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/account-templates",
+    tag = "Accounts",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The templates accounts can be created from.", body = AccountTemplateGetListResponse),
+    ),
+))]
+#[server(
+    name = AccountTemplateApiGetList,
+    prefix = "/api",
+    endpoint = "account-templates",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<AccountTemplateGetListResponse, ApiError> {
+    Ok(AccountTemplateGetListResponse {
+        templates: ACCOUNT_TEMPLATES.iter().map(|x| x.into()).collect(),
+    })
+}
@@ -0,0 +1,526 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::admin::{
+        AdminBulkUpsertInstitutionsResponse, AdminFeaturesResponse, AdminPolicySummaryResponse,
+        AdminSimulatePoliciesResponse, AdminStatsResponse, AdminUpdateFeaturesResponse,
+        SimulatePoliciesRequest, StatsRequest, UpdateFeaturesRequest,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{ByteStream, GetUrl, Json, PatchJson, Streaming},
+};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        AUTH_MODEL_PATH,
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, extract_with_state, login_throttle,
+            panic,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{authenticated_token::AuthenticatedToken, authenticator::Authenticator},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+            group::Group,
+            simulation::{parse_policies, scratch_enforcer},
+            summary::PolicySummary,
+        },
+        config::{Feature, FeatureFlags},
+        import::institutions::DirectoryUpsert,
+        resource::{
+            deadline,
+            feature_flag_repository::FeatureFlagRepository,
+            stats_repository::{Count, StatsRepository},
+        },
+        schema::admin::{
+            BulkUpsertRequest, CacheStatsResponse, EventStatsResponse, MAX_SIMULATION_CHECKS,
+            PolicyCheckRequest, PolicyComparisonResponse, PoolStatsResponse,
+        },
+        service::ServiceError,
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Query, Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use casbin::Enforcer;
+    pub use futures::StreamExt;
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+    pub use sqlx::PgPool;
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+    pub use tracing::{error, warn};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `admin` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::ReadAll,
+        min_create_level: CreateLevel::CreateAll,
+        min_update_level: UpdateLevel::UpdateAll,
+        min_delete_level: DeleteLevel::DeleteAll,
+    };
+
+    pub struct AdminApiResource;
+
+    impl ApiResource for AdminApiResource {
+        const NAME: &'static str = "admin";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = ();
+        type Service = ();
+
+        fn service(_: &AppState, _: (), _: PermissionSet) {}
+    }
+
+    /// The permissions of the caller on the `admin` resource. The stats
+    /// span every user, so reading them takes `read_all`.
+    pub type AdminApiState = ResourceContext<AdminApiResource>;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = req.uri().to_string();
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/admin{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    /// Counts `count` in a session of its own, so the counts run in
+    /// parallel.
+    pub async fn count(state: &AppState, count: Count) -> Result<i64, ApiError> {
+        let session = deadline::begin(&state.connection_pool)
+            .await
+            .map_err(ServiceError::from)?;
+        Ok(StatsRepository
+            .count(session, count)
+            .await
+            .map_err(ServiceError::from)?)
+    }
+
+    /// The number of transactions and whether it is exact. The estimate is
+    /// used unless `exact` is asked for or there is none yet.
+    pub async fn count_transactions(
+        state: &AppState,
+        exact: bool,
+    ) -> Result<(i64, bool), ApiError> {
+        if !exact {
+            let session = deadline::begin(&state.connection_pool)
+                .await
+                .map_err(ServiceError::from)?;
+            let estimate = StatsRepository
+                .estimate_transactions(session)
+                .await
+                .map_err(ServiceError::from)?;
+            if let Some(estimate) = estimate {
+                return Ok((estimate, false));
+            }
+        }
+        Ok((count(state, Count::Transactions).await?, true))
+    }
+
+    /// Applies the overrides admins saved to `features`, before any routes
+    /// are mounted from them. The overrides of features that no longer
+    /// exist are ignored.
+    pub async fn load_feature_flags(
+        connection_pool: &PgPool,
+        features: FeatureFlags,
+    ) -> Result<FeatureFlags, ServiceError> {
+        let session = deadline::begin(&connection_pool).await?;
+        let overrides = FeatureFlagRepository.get_all(session).await?;
+        Ok(
+            features.with_overrides(overrides.into_iter().filter_map(|x| {
+                match x.name.parse::<Feature>() {
+                    Ok(feature) => Some((feature, x.enabled)),
+                    Err(e) => {
+                        warn!("Ignoring a feature flag override: {e}");
+                        None
+                    }
+                }
+            })),
+        )
+    }
+
+    /// What `enforcer` grants the group of `check` on its resource, at
+    /// every level.
+    pub fn simulate(
+        enforcer: &Arc<Enforcer>,
+        check: &PolicyCheckRequest,
+    ) -> Result<PermissionSet, ApiError> {
+        let token = AuthenticatedToken::for_group(Group::from(check.group.as_str()));
+        PermissionSet::new(&check.resource, enforcer, &token, PERMISSION_CONFIG).map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })
+    }
+
+    pub struct AdminApi;
+
+    impl Api for AdminApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/stats"),
+                (Method::GET, "/features"),
+                (Method::PATCH, "/features"),
+                (Method::POST, "/policies/simulate"),
+                (Method::GET, "/policies/summary"),
+                (Method::POST, "/institutions/bulk-upsert"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/stats", axum::routing::get(server_fn_handler))
+                .route(
+                    "/features",
+                    axum::routing::get(server_fn_handler).patch(server_fn_handler),
+                )
+                .route("/policies/simulate", axum::routing::post(server_fn_handler))
+                .route("/policies/summary", axum::routing::get(server_fn_handler))
+                .route(
+                    "/institutions/bulk-upsert",
+                    axum::routing::post(server_fn_handler),
+                )
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/admin/stats",
+    tag = "Admin",
+    params(StatsRequest),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "System wide counts and the gauges of this server.", body = AdminStatsResponse),
+        (status = 403, description = "The caller is not an admin."),
+    ),
+))]
+#[server(
+    name = AdminApiStats,
+    prefix = "/api",
+    endpoint = "admin/stats",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn stats(
+    #[server(flatten)]
+    #[server(default)]
+    stats_request: StatsRequest,
+) -> Result<AdminStatsResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AdminApiState, _>(&state).await?;
+    if api_state.permission_set.read_level != ReadLevel::ReadAll {
+        return Err(ApiError::Forbidden);
+    }
+
+    let exact = stats_request.exact.unwrap_or_default();
+    let (users, accounts, (transactions, transactions_exact), active_sessions, pending_extractions) =
+        tokio::try_join!(
+            count(&state, Count::Users),
+            count(&state, Count::Accounts),
+            count_transactions(&state, exact),
+            count(&state, Count::ActiveSessions),
+            count(&state, Count::PendingExtractions),
+        )?;
+
+    Ok(AdminStatsResponse {
+        users,
+        accounts,
+        transactions,
+        transactions_exact,
+        active_sessions,
+        pending_extractions,
+        query_timeouts: deadline::query_timeouts(),
+        panics: panic::panics(),
+        login_throttled: login_throttle::throttled(),
+        pool: PoolStatsResponse {
+            size: state.connection_pool.size(),
+            idle: state.connection_pool.num_idle(),
+        },
+        caches: CacheStatsResponse {
+            assets: state.service_caches.assets.len(),
+            institutions: state.service_caches.institutions.len(),
+        },
+        events: EventStatsResponse {
+            active_streams: state.events.active_streams(),
+            dropped_events: state.events.dropped_events(),
+        },
+    })
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/admin/features",
+    tag = "Admin",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "Whether each optional subsystem is enabled, and whether its routes are served.", body = AdminFeaturesResponse),
+        (status = 403, description = "The caller is not an admin."),
+    ),
+))]
+#[server(
+    name = AdminApiFeatures,
+    prefix = "/api",
+    endpoint = "admin/features",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn features() -> Result<AdminFeaturesResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AdminApiState, _>(&state).await?;
+    if api_state.permission_set.read_level != ReadLevel::ReadAll {
+        return Err(ApiError::Forbidden);
+    }
+
+    Ok((&state.features).into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/admin/features",
+    tag = "Admin",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = UpdateFeaturesRequest,
+    responses(
+        (status = 200, description = "The features after the update. A feature with routes of its own only adds or removes them on the next restart, which `restart_required` tells.", body = AdminUpdateFeaturesResponse),
+        (status = 400, description = "There is no feature with one of the names."),
+        (status = 403, description = "The caller is not an admin."),
+    ),
+))]
+#[server(
+    name = AdminApiUpdateFeatures,
+    prefix = "/api",
+    endpoint = "admin/features",
+    input = PatchJson,
+    output = PatchJson,
+    client = ApiClient,
+)]
+pub async fn update_features(
+    #[server(flatten)] update_request: UpdateFeaturesRequest,
+) -> Result<AdminUpdateFeaturesResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AdminApiState, _>(&state).await?;
+    if api_state.permission_set.update_level != UpdateLevel::UpdateAll {
+        return Err(ApiError::Forbidden);
+    }
+
+    let updates = update_request
+        .features
+        .into_iter()
+        .map(|(name, enabled)| Ok((name.parse::<Feature>()?, enabled)))
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|e| ApiError::client(ClientErrorCode::InvalidRequest, e))?;
+    // Each override is saved before it is applied, so a toggle the server
+    // runs with survives a restart.
+    for (feature, enabled) in updates {
+        let session = deadline::begin(&state.connection_pool)
+            .await
+            .map_err(ServiceError::from)?;
+        FeatureFlagRepository
+            .set(session, feature.name(), enabled)
+            .await
+            .map_err(ServiceError::from)?;
+        state.features.set(feature, enabled);
+    }
+
+    Ok((&state.features).into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/admin/policies/simulate",
+    tag = "Admin",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = SimulatePoliciesRequest,
+    responses(
+        (status = 200, description = "The permissions of each group on each resource under the policies enforced now and under the proposed ones, with the actions that differ.", body = AdminSimulatePoliciesResponse),
+        (status = 400, description = "The proposed policies could not be read, or there are too many of them or of the checks.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4001,
+            message: "Line 2 of the policies is neither `p, subject, object, action` nor `g, member, role`.".to_string()
+        })),
+        (status = 403, description = "The caller is not an admin."),
+    ),
+))]
+#[server(
+    name = AdminApiSimulatePolicies,
+    prefix = "/api",
+    endpoint = "admin/policies/simulate",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn simulate_policies(
+    #[server(flatten)] simulate_request: SimulatePoliciesRequest,
+) -> Result<AdminSimulatePoliciesResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AdminApiState, _>(&state).await?;
+    if api_state.permission_set.read_level != ReadLevel::ReadAll {
+        return Err(ApiError::Forbidden);
+    }
+    if simulate_request.checks.len() > MAX_SIMULATION_CHECKS {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            format!("A simulation can have at most {MAX_SIMULATION_CHECKS} checks."),
+        ));
+    }
+
+    let rules = parse_policies(&simulate_request.policies)
+        .map_err(|e| ApiError::client(ClientErrorCode::InvalidRequest, e))?;
+    let model_path = AUTH_MODEL_PATH.get().ok_or(ApiError::ServerError)?;
+    // The proposal is loaded into an enforcer of its own, so the live one
+    // is never touched.
+    let proposed = Arc::new(scratch_enforcer(model_path, rules).await.map_err(|e| {
+        error!("{e}");
+        ApiError::ServerError
+    })?);
+
+    let mut comparisons = Vec::with_capacity(simulate_request.checks.len());
+    for check in simulate_request.checks {
+        let current_permissions = simulate(&state.enforcer, &check)?;
+        let proposed_permissions = simulate(&proposed, &check)?;
+        comparisons.push(PolicyComparisonResponse::new(
+            check,
+            current_permissions,
+            proposed_permissions,
+        ));
+    }
+    Ok(AdminSimulatePoliciesResponse {
+        changed: comparisons
+            .iter()
+            .filter(|x| !x.differences.is_empty())
+            .count(),
+        comparisons,
+    })
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/admin/policies/summary",
+    tag = "Admin",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "What the policies enforced now grant each subject on each resource, the members of each role, and the policies whose action is unknown and so grant nothing.", body = AdminPolicySummaryResponse),
+        (status = 403, description = "The caller is not an admin."),
+    ),
+))]
+#[server(
+    name = AdminApiPolicySummary,
+    prefix = "/api",
+    endpoint = "admin/policies/summary",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn policy_summary() -> Result<AdminPolicySummaryResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AdminApiState, _>(&state).await?;
+    if api_state.permission_set.read_level != ReadLevel::ReadAll {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(PolicySummary::of(&state.enforcer).into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/admin/institutions/bulk-upsert",
+    tag = "Admin",
+    params(BulkUpsertRequest),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body(content = String, content_type = "application/x-ndjson", description = "An institution directory in the `format` asked for. Institutions are matched on their name, and a parent must come before or alongside its children."),
+    responses(
+        (status = 200, description = "How many institutions were created, updated or left unchanged, and why rows were not written. Sending the directory again only writes what is still missing. With `dry_run`, what would have been written, of which nothing was kept.", body = AdminBulkUpsertInstitutionsResponse),
+        (status = 400, description = "The CSV has no header row, or no `name` column.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4001,
+            message: "Column `name` is not in the CSV header.".to_string()
+        })),
+        (status = 403, description = "The caller is not an admin."),
+    ),
+))]
+#[server(
+    name = AdminApiBulkUpsertInstitutions,
+    prefix = "/api",
+    endpoint = "admin/institutions/bulk-upsert",
+    input = Streaming,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn bulk_upsert_institutions(
+    directory: ByteStream<ApiError>,
+) -> Result<AdminBulkUpsertInstitutionsResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AdminApiState, _>(&state).await?;
+    if api_state.permission_set.update_level != UpdateLevel::UpdateAll {
+        return Err(ApiError::Forbidden);
+    }
+    let Query(bulk_upsert_request) = extract_with_state::<Query<BulkUpsertRequest>, _>(&()).await?;
+
+    // The directory is written as it arrives, so it is never held whole.
+    let mut upsert = DirectoryUpsert::new(
+        &state.connection_pool,
+        bulk_upsert_request.format,
+        bulk_upsert_request.dry_run,
+    )
+    .await?;
+    let mut chunks = directory.into_inner();
+    while let Some(chunk) = chunks.next().await {
+        upsert.push(&chunk?).await?;
+    }
+    let report = upsert.finish().await?;
+    if !report.dry_run {
+        state.service_caches.institutions.invalidate();
+    }
+    Ok(report)
+}
@@ -0,0 +1,403 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::alert_rule::AlertRuleId,
+    schema::alert_rule::{
+        AlertRuleCreateResponse, AlertRuleGetEventsResponse, AlertRuleGetListResponse,
+        AlertRuleGetResponse, AlertRuleUpdateResponse, CreateRequest, DeleteResponse,
+        UpdateRequest,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json, PatchJson},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode,
+            asset_api::asset_scale,
+            extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        model::alert_rule::{AlertRuleCreate, AlertRuleFilter},
+        service::{
+            alert_rule_service::AlertRuleServiceMethods,
+            alert_rule_service_factory::AlertRuleServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathAlertRuleId {
+    id: AlertRuleId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `alert_rules` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct AlertRuleApiResource;
+
+    impl ApiResource for AlertRuleApiResource {
+        const NAME: &'static str = "alert_rules";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn AlertRuleServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            AlertRuleServiceFactory::build(
+                owner,
+                Arc::clone(&state.connection_pool),
+                permission_set,
+            )
+        }
+    }
+
+    pub type AlertRuleApiState = ResourceContext<AlertRuleApiResource>;
+
+    /// Checks a cooldown isn't negative.
+    pub fn validate_cooldown(cooldown_seconds: i64) -> Result<(), ApiError> {
+        if cooldown_seconds < 0 {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "The `cooldown_seconds` must not be negative.",
+            ));
+        }
+        Ok(())
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            val if val.ends_with("/events") => "/events".to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/alert-rules{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct AlertRuleApi;
+
+    impl Api for AlertRuleApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
+                (Method::GET, "/{id}/events"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler)
+                        .patch(server_fn_handler)
+                        .delete(server_fn_handler),
+                )
+                .route("/{id}/events", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/alert-rules",
+    tag = "Alert Rules",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The alert rules of the user.", body = AlertRuleGetListResponse)
+    ),
+))]
+#[server(
+    name = AlertRuleApiGetList,
+    prefix = "/api",
+    endpoint = "/alert-rules",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<AlertRuleGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AlertRuleApiState, _>(&state).await?;
+
+    let alert_rules = api_state
+        .service
+        .get_list(0, None, AlertRuleFilter::default())
+        .await?;
+    Ok(alert_rules.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/alert-rules/{id}",
+    tag = "Alert Rules",
+    params(AlertRuleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The alert rule.", body = AlertRuleGetResponse),
+        (status = 404, description = "The alert rule was not found."),
+    ),
+))]
+#[server(
+    name = AlertRuleApiGet,
+    prefix = "/api",
+    endpoint = "alert-rules/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<AlertRuleGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AlertRuleApiState, _>(&state).await?;
+    let PathAlertRuleId { id } = extract_path().await?;
+
+    let alert_rule = api_state.service.get(id).await?;
+    Ok(alert_rule.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/alert-rules",
+    tag = "Alert Rules",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The newly created alert rule.", body = AlertRuleCreateResponse),
+        (status = 400, description = "The threshold or the cooldown is invalid."),
+        (status = 404, description = "The account or the asset was not found."),
+    ),
+))]
+#[server(
+    name = AlertRuleApiCreate,
+    prefix = "/api",
+    endpoint = "alert-rules",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<AlertRuleCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AlertRuleApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+
+    validate_cooldown(create_request.cooldown_seconds)?;
+    let scale = asset_scale(&state, create_request.asset_id).await?;
+    let threshold = create_request.threshold.in_asset(scale)?;
+
+    let alert_rule = api_state
+        .service
+        .create(AlertRuleCreate {
+            user_id: registered_user.id(),
+            account_id: create_request.account_id,
+            asset_id: create_request.asset_id,
+            comparator: create_request.comparator,
+            threshold,
+            channel: create_request.channel,
+            cooldown_seconds: create_request.cooldown_seconds,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(AlertRuleCreateResponse::status());
+    provide_context(response_opts);
+    Ok(alert_rule.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/alert-rules/{id}",
+    tag = "Alert Rules",
+    params(AlertRuleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = UpdateRequest,
+    responses(
+        (status = 200, description = "The updated alert rule.", body = AlertRuleUpdateResponse),
+        (status = 400, description = "The threshold or the cooldown is invalid."),
+        (status = 404, description = "The alert rule was not found."),
+    ),
+))]
+#[server(
+    name = AlertRuleApiUpdate,
+    prefix = "/api",
+    endpoint = "alert-rules/",
+    input = PatchJson,
+    output = PatchJson,
+    client = ApiClient,
+)]
+pub async fn update(
+    #[server(flatten)] update_request: UpdateRequest,
+) -> Result<AlertRuleUpdateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AlertRuleApiState, _>(&state).await?;
+    let PathAlertRuleId { id } = extract_path().await?;
+
+    let mut alert_rule = api_state.service.get(id).await?;
+    if let Some(comparator) = update_request.comparator {
+        alert_rule.comparator = comparator;
+    }
+    if let Some(threshold) = update_request.threshold {
+        let scale = asset_scale(&state, alert_rule.asset_id).await?;
+        alert_rule.threshold = threshold.in_asset(scale)?;
+    }
+    if let Some(channel) = update_request.channel {
+        alert_rule.channel = channel;
+    }
+    if let Some(cooldown_seconds) = update_request.cooldown_seconds {
+        validate_cooldown(cooldown_seconds)?;
+        alert_rule.cooldown_seconds = cooldown_seconds;
+    }
+
+    let alert_rule = api_state.service.update(id, alert_rule).await?;
+    Ok(alert_rule.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/alert-rules/{id}",
+    tag = "Alert Rules",
+    params(AlertRuleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The alert rule and its events were successfully deleted."),
+        (status = 404, description = "The alert rule was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = AlertRuleApiDelete,
+    prefix = "/api",
+    endpoint = "alert-rules/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AlertRuleApiState, _>(&state).await?;
+    let PathAlertRuleId { id } = extract_path().await?;
+
+    api_state.service.delete(id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/alert-rules/{id}/events",
+    tag = "Alert Rules",
+    params(AlertRuleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The latest firings of the alert rule, newest first.", body = AlertRuleGetEventsResponse),
+        (status = 404, description = "The alert rule was not found."),
+    ),
+))]
+#[server(
+    name = AlertRuleApiGetEvents,
+    prefix = "/api",
+    endpoint = "alert-rules/events",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_events() -> Result<AlertRuleGetEventsResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AlertRuleApiState, _>(&state).await?;
+    let PathAlertRuleId { id } = extract_path().await?;
+
+    let alert_events = api_state.service.events(id).await?;
+    Ok(alert_events.into())
+}
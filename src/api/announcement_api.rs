@@ -0,0 +1,442 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::announcement::AnnouncementId,
+    schema::{
+        Pagination,
+        announcement::{
+            ActiveResponse, AnnouncementCreateResponse, AnnouncementGetListResponse,
+            AnnouncementGetResponse, AnnouncementUpdateResponse, CreateRequest, DeleteResponse,
+            UpdateRequest,
+        },
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json, PatchJson},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        config::PagedResource,
+        model::{
+            announcement::{AnnouncementCreate, AnnouncementFilter},
+            cursor_key::CursorKey,
+        },
+        service::{
+            announcement_service::AnnouncementServiceMethods,
+            announcement_service_factory::AnnouncementServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use chrono::{DateTime, Utc};
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// The longest announcement message, in characters.
+pub const MAX_MESSAGE_LENGTH: usize = 1000;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathAnnouncementId {
+    id: AnnouncementId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `announcements` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::ReadAll,
+        min_create_level: CreateLevel::CreateAll,
+        min_update_level: UpdateLevel::UpdateAll,
+        min_delete_level: DeleteLevel::DeleteAll,
+    };
+
+    pub struct AnnouncementApiResource;
+
+    impl ApiResource for AnnouncementApiResource {
+        const NAME: &'static str = "announcements";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = Option<RegisteredUser>;
+        type Service = Box<dyn AnnouncementServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: Option<RegisteredUser>,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            AnnouncementServiceFactory::build(
+                owner,
+                Arc::clone(&state.connection_pool),
+                permission_set,
+            )
+        }
+    }
+
+    /// The caller of a request on announcements, which are shared by every
+    /// user, so managing them takes the `_all` levels.
+    pub type AnnouncementApiState = ResourceContext<AnnouncementApiResource>;
+
+    /// Checks the message fits and the announcement ends after it starts.
+    pub fn validate_announcement(
+        message: &str,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        let length = message.trim().chars().count();
+        if length == 0 || length > MAX_MESSAGE_LENGTH {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                format!("The message must be from 1 to {MAX_MESSAGE_LENGTH} characters."),
+            ));
+        }
+        if ends_at <= starts_at {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "An announcement must end after it starts.",
+            ));
+        }
+        Ok(())
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            val if val == "/active" => "/active".to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/announcements{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct AnnouncementApi;
+
+    impl Api for AnnouncementApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/active"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route("/active", axum::routing::get(server_fn_handler))
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler)
+                        .patch(server_fn_handler)
+                        .delete(server_fn_handler),
+                )
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/announcements/active",
+    tag = "Announcements",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The announcements shown right now.", body = ActiveResponse)
+    ),
+))]
+#[server(
+    name = AnnouncementApiGetActive,
+    prefix = "/api",
+    endpoint = "announcements/active",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_active() -> Result<ActiveResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AnnouncementApiState, _>(&state).await?;
+
+    let announcements = api_state.service.active(Utc::now()).await?;
+    Ok(announcements.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/announcements",
+    tag = "Announcements",
+    params(Pagination),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "Every announcement, newest first.", body = AnnouncementGetListResponse),
+        (status = 403, description = "Only admins can list announcements."),
+    ),
+))]
+#[server(
+    name = AnnouncementApiGetList,
+    prefix = "/api",
+    endpoint = "/announcements",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list(
+    #[server(flatten)]
+    #[server(default)]
+    pagination: Pagination,
+) -> Result<AnnouncementGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AnnouncementApiState, _>(&state).await?;
+
+    let pagination = extract_with_state::<Pagination, _>(&state)
+        .await?
+        .for_resource(PagedResource::Announcements)?;
+    let cursor_key = extract_with_state::<CursorKey, _>(&state).await?;
+
+    let announcements = api_state
+        .service
+        .get_list(
+            pagination.offset(),
+            pagination.limit().into(),
+            AnnouncementFilter::default(),
+        )
+        .await?;
+    let response = AnnouncementGetListResponse::new(announcements, &pagination, &cursor_key)?;
+    Ok(response)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/announcements/{id}",
+    tag = "Announcements",
+    params(AnnouncementId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The announcement.", body = AnnouncementGetResponse),
+        (status = 403, description = "Only admins can read announcements by id."),
+        (status = 404, description = "The announcement was not found."),
+    ),
+))]
+#[server(
+    name = AnnouncementApiGet,
+    prefix = "/api",
+    endpoint = "announcements/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<AnnouncementGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AnnouncementApiState, _>(&state).await?;
+    let PathAnnouncementId { id } = extract_path().await?;
+
+    let announcement = api_state.service.get(id).await?;
+    Ok(announcement.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/announcements",
+    tag = "Announcements",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The newly created announcement.", body = AnnouncementCreateResponse),
+        (status = 400, description = "The message is empty or too long, or the window is empty."),
+        (status = 403, description = "Only admins can create announcements."),
+    ),
+))]
+#[server(
+    name = AnnouncementApiCreate,
+    prefix = "/api",
+    endpoint = "announcements",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<AnnouncementCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AnnouncementApiState, _>(&state).await?;
+    validate_announcement(
+        &create_request.message,
+        create_request.starts_at,
+        create_request.ends_at,
+    )?;
+
+    let announcement = api_state
+        .service
+        .create(AnnouncementCreate {
+            message: create_request.message.trim().to_owned(),
+            severity: create_request.severity,
+            starts_at: create_request.starts_at,
+            ends_at: create_request.ends_at,
+            created_by: None,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(AnnouncementCreateResponse::status());
+    provide_context(response_opts);
+    Ok(announcement.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/announcements/{id}",
+    tag = "Announcements",
+    params(AnnouncementId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = UpdateRequest,
+    responses(
+        (status = 200, description = "The updated announcement.", body = AnnouncementUpdateResponse),
+        (status = 400, description = "The message is empty or too long, or the window is empty."),
+        (status = 403, description = "Only admins can update announcements."),
+        (status = 404, description = "The announcement was not found."),
+    ),
+))]
+#[server(
+    name = AnnouncementApiUpdate,
+    prefix = "/api",
+    endpoint = "announcements/",
+    input = PatchJson,
+    output = PatchJson,
+    client = ApiClient,
+)]
+pub async fn update(
+    #[server(flatten)] update_request: UpdateRequest,
+) -> Result<AnnouncementUpdateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AnnouncementApiState, _>(&state).await?;
+    let PathAnnouncementId { id } = extract_path().await?;
+
+    let mut announcement = api_state.service.get(id).await?;
+    if let Some(message) = update_request.message {
+        announcement.message = message.trim().to_owned();
+    }
+    if let Some(severity) = update_request.severity {
+        announcement.severity = severity;
+    }
+    if let Some(starts_at) = update_request.starts_at {
+        announcement.starts_at = starts_at;
+    }
+    if let Some(ends_at) = update_request.ends_at {
+        announcement.ends_at = ends_at;
+    }
+    validate_announcement(
+        &announcement.message,
+        announcement.starts_at,
+        announcement.ends_at,
+    )?;
+
+    let announcement = api_state.service.update(id, announcement).await?;
+    Ok(announcement.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/announcements/{id}",
+    tag = "Announcements",
+    params(AnnouncementId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The announcement was successfully deleted."),
+        (status = 403, description = "Only admins can delete announcements."),
+        (status = 404, description = "The announcement was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = AnnouncementApiDelete,
+    prefix = "/api",
+    endpoint = "announcements/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<AnnouncementApiState, _>(&state).await?;
+    let PathAnnouncementId { id } = extract_path().await?;
+
+    api_state.service.delete(id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
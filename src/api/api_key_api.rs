@@ -0,0 +1,287 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::{api_key::ApiKeyId, user::UserId},
+    schema::api_key::{
+        ApiKeyCreateRequest, ApiKeyCreateResponse, ApiKeyDeleteResponse, ApiKeyGetListResponse,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, extract_path, extract_with_state,
+            passkey_api::path_user,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{
+            api_key::generate_secret, authenticator::Authenticator, registered_user::RegisteredUser,
+        },
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        model::api_key::{ApiKeyCreate, ApiKeyFilter},
+        service::{
+            api_key_service::ApiKeyServiceMethods, api_key_service_factory::ApiKeyServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathApiKeyId {
+    api_key_id: ApiKeyId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `api_keys` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct ApiKeyApiResource;
+
+    impl ApiResource for ApiKeyApiResource {
+        const NAME: &'static str = "api_keys";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn ApiKeyServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            ApiKeyServiceFactory::build(owner, Arc::clone(&state.connection_pool), permission_set)
+        }
+    }
+
+    pub type ApiKeyApiState = ResourceContext<ApiKeyApiResource>;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        // The user and key ids are recovered with `extract` inside each
+        // server fn.
+        let path = match req.uri().path() {
+            "/" => "",
+            _ => "/",
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/users/api-keys{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct ApiKeyApi;
+
+    impl Api for ApiKeyApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::DELETE, "/{api_key_id}"),
+            ]
+        }
+
+        // API keys can't manage API keys, so only OIDC tokens are accepted.
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route("/{api_key_id}", axum::routing::delete(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/users/{id}/api-keys",
+    tag = "API Keys",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The API keys issued by the user.", body = ApiKeyGetListResponse),
+        (status = 404, description = "The user was not found."),
+    ),
+))]
+#[server(
+    name = ApiKeyApiGetList,
+    prefix = "/api",
+    endpoint = "users/api-keys",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<ApiKeyGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    path_user(&state).await?;
+    let api_state = extract_with_state::<ApiKeyApiState, _>(&state).await?;
+
+    let api_keys = api_state
+        .service
+        .get_list(0, None, ApiKeyFilter::default())
+        .await?;
+    Ok(api_keys.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/users/{id}/api-keys",
+    tag = "API Keys",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = ApiKeyCreateRequest,
+    responses(
+        (status = 201, description = "The newly issued API key and its secret.", body = ApiKeyCreateResponse),
+        (status = 400, description = "The key was scoped to no accounts."),
+        (status = 404, description = "The user or one of the accounts was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = ApiKeyApiCreate,
+    prefix = "/api",
+    endpoint = "users/api-keys",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: ApiKeyCreateRequest,
+) -> Result<ApiKeyCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let registered_user = path_user(&state).await?;
+    let api_state = extract_with_state::<ApiKeyApiState, _>(&state).await?;
+
+    let account_ids = match create_request.account_ids {
+        Some(mut account_ids) => {
+            account_ids.sort_by_key(|id| id.0);
+            account_ids.dedup();
+            if account_ids.is_empty() {
+                return Err(ApiError::client(
+                    ClientErrorCode::InvalidRequest,
+                    "An API key must be scoped to at least one account.",
+                ));
+            }
+            Some(account_ids)
+        }
+        None => None,
+    };
+
+    let (secret, key_hash) = generate_secret();
+    // The accounts of other users are as good as missing.
+    let api_key = api_state
+        .service
+        .create(ApiKeyCreate {
+            user_id: registered_user.id(),
+            name: create_request.name,
+            key_hash,
+            read_only: create_request.read_only,
+            account_ids,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(ApiKeyCreateResponse::status());
+    provide_context(response_opts);
+    Ok(ApiKeyCreateResponse::new(api_key, secret))
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/users/{id}/api-keys/{api_key_id}",
+    tag = "API Keys",
+    params(UserId, ApiKeyId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The API key was successfully revoked."),
+        (status = 404, description = "The API key was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = ApiKeyApiDelete,
+    prefix = "/api",
+    endpoint = "users/api-keys/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<ApiKeyDeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    path_user(&state).await?;
+    let PathApiKeyId { api_key_id } = extract_path().await?;
+    let api_state = extract_with_state::<ApiKeyApiState, _>(&state).await?;
+
+    api_state.service.delete(api_key_id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(ApiKeyDeleteResponse::status());
+    provide_context(response_opts);
+    Ok(ApiKeyDeleteResponse {})
+}
@@ -1,6 +1,6 @@
 pub use crate::{
     api::{ApiError, client::ApiClient},
-    model::asset::AssetId,
+    model::asset::{AssetId, MAX_DECIMALS},
     schema::{
         Pagination,
         asset::{
@@ -18,31 +18,40 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
-        api::{Api, ApiErrorResponse, AppState, extract_with_state, set_user_groups},
-        authentication::{authenticated_token::AuthenticatedToken, authenticator::Authenticator},
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{api_key::authenticate_api_key, authenticator::Authenticator},
         authorization::{
             PermissionConfig, PermissionSet,
             actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
         },
+        config::PagedResource,
         model::cursor_key::CursorKey,
-        service::{asset_service::AssetServiceMethods, asset_service_factory::AssetServiceFactory},
+        resource::{GetRepository, asset_repository::AssetRepository, deadline},
+        schema::text::{ASSET_NAME, ASSET_SYMBOL},
+        service::{
+            ServiceError, asset_service::AssetServiceMethods,
+            asset_service_factory::AssetServiceFactory,
+        },
     };
     pub use axum::{
-        RequestPartsExt, Router,
+        Router,
         body::Body,
-        extract::{FromRequestParts, Path, Request, State},
+        extract::{Request, State},
         middleware::from_fn_with_state,
         response::IntoResponse,
     };
     pub use http::request::Parts;
     pub use leptos::prelude::*;
     pub use leptos_axum::{
-        ResponseOptions, extract, generate_request_and_parts, handle_server_fns_with_context,
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
     };
     pub use std::sync::Arc;
     pub use tower::ServiceBuilder;
     pub use tower_http::auth::AsyncRequireAuthorizationLayer;
-    pub use tracing::error;
 }
 
 #[cfg(feature = "ssr")]
@@ -57,46 +66,46 @@ pub struct PathAssetId {
 mod ssr {
     use super::*;
 
-    pub struct AssetApiState {
-        pub authenticated_token: AuthenticatedToken,
-        pub asset_service: Box<dyn AssetServiceMethods + Send>,
-    }
+    /// The levels of `assets` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
 
-    impl FromRequestParts<AppState> for AssetApiState {
-        type Rejection = ApiError;
+    pub struct AssetApiResource;
 
-        async fn from_request_parts(
-            parts: &mut Parts,
-            state: &AppState,
-        ) -> Result<Self, Self::Rejection> {
-            let authenticated_token = parts
-                .extract_with_state::<AuthenticatedToken, _>(state)
-                .await?;
+    impl ApiResource for AssetApiResource {
+        const NAME: &'static str = "assets";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = ();
+        type Service = Box<dyn AssetServiceMethods + Send>;
 
-            let permission_set = PermissionSet::new(
-                "assets",
-                &state.enforcer,
-                &authenticated_token,
-                PermissionConfig {
-                    min_read_level: ReadLevel::Read,
-                    min_create_level: CreateLevel::Create,
-                    min_update_level: UpdateLevel::Update,
-                    min_delete_level: DeleteLevel::Delete,
-                },
+        fn service(state: &AppState, _: (), permission_set: PermissionSet) -> Self::Service {
+            AssetServiceFactory::build(
+                Arc::clone(&state.connection_pool),
+                permission_set,
+                state.service_caches.assets.clone(),
             )
-            .map_err(|e| {
-                error!("{e}");
-                ApiError::ServerError
-            })?;
+        }
+    }
 
-            let asset_service =
-                AssetServiceFactory::build(Arc::clone(&state.connection_pool), permission_set);
+    pub type AssetApiState = ResourceContext<AssetApiResource>;
 
-            Ok(Self {
-                authenticated_token,
-                asset_service,
-            })
-        }
+    /// The scale of the quantities of an asset, to read the quantities
+    /// clients send in it with.
+    pub async fn asset_scale(state: &AppState, asset_id: AssetId) -> Result<u32, ApiError> {
+        let asset = AssetRepository
+            .get(
+                deadline::begin(&state.connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                asset_id,
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        Ok(asset.scale())
     }
 
     async fn server_fn_handler(
@@ -109,7 +118,10 @@ mod ssr {
             _ => "/".to_string(),
         };
         let (mut req, parts) = generate_request_and_parts(req);
-        *req.uri_mut() = format!("/api/assets{path}").parse().unwrap();
+        *req.uri_mut() = match server_fn_uri(format!("/api/assets{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
         handle_server_fns_with_context(
             {
                 let app_state = state.clone();
@@ -121,6 +133,7 @@ mod ssr {
             req,
         )
         .await
+        .into_response()
     }
 
     pub struct AssetApi;
@@ -140,6 +153,7 @@ mod ssr {
                 )
                 .layer(
                     ServiceBuilder::new()
+                        .layer(from_fn_with_state(state.clone(), authenticate_api_key))
                         .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
                         .layer(from_fn_with_state(state.clone(), set_user_groups)),
                 )
@@ -183,13 +197,15 @@ pub async fn get_list(
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AssetApiState, _>(&state).await?;
 
-    let pagination = extract_with_state::<Pagination, _>(&state).await?;
+    let pagination = extract_with_state::<Pagination, _>(&state)
+        .await?
+        .for_resource(PagedResource::Assets)?;
     let cursor_key = extract_with_state::<CursorKey, _>(&state).await?;
 
     let offset = pagination.offset();
     let assets = api_state
-        .asset_service
-        .get_list(offset, pagination.max_items, filter.into())
+        .service
+        .get_list(offset, pagination.limit().into(), filter.into())
         .await?;
     let response = AssetGetListResponse::new(assets, &pagination, &cursor_key)?;
     Ok(response)
@@ -220,8 +236,8 @@ pub async fn get() -> Result<AssetGetResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AssetApiState, _>(&state).await?;
 
-    let Path(PathAssetId { id }) = extract().await?;
-    let asset = api_state.asset_service.get(id).await?;
+    let PathAssetId { id } = extract_path().await?;
+    let asset = api_state.service.get(id).await?;
     Ok(asset.into())
 }
 
@@ -234,7 +250,8 @@ pub async fn get() -> Result<AssetGetResponse, ApiError> {
     ),
     request_body = CreateRequest,
     responses(
-        (status = 201, description = "The newly created asset.", body = AssetCreateResponse)
+        (status = 201, description = "The newly created asset.", body = AssetCreateResponse),
+        (status = 400, description = "The name, symbol or decimals are invalid."),
     ),
 ))]
 #[server(
@@ -251,10 +268,18 @@ pub async fn create(
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AssetApiState, _>(&state).await?;
 
-    let asset = api_state
-        .asset_service
-        .create(create_request.into())
-        .await?;
+    if !(0..=MAX_DECIMALS).contains(&create_request.decimals) {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            format!("Assets have between 0 and {MAX_DECIMALS} decimals."),
+        ));
+    }
+    let create_request = CreateRequest {
+        name: ASSET_NAME.sanitize(&create_request.name)?,
+        symbol: ASSET_SYMBOL.sanitize(&create_request.symbol)?,
+        ..create_request
+    };
+    let asset = api_state.service.create(create_request.into()).await?;
     let response_opts = expect_context::<ResponseOptions>();
     response_opts.set_status(AssetCreateResponse::status());
     provide_context(response_opts);
@@ -289,11 +314,12 @@ pub async fn update(
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AssetApiState, _>(&state).await?;
 
-    let Path(PathAssetId { id }) = extract().await?;
-    let asset = api_state
-        .asset_service
-        .update(id, update_request.into())
-        .await?;
+    let PathAssetId { id } = extract_path().await?;
+    let update_request = UpdateRequest {
+        name: ASSET_NAME.sanitize_option(update_request.name)?,
+        symbol: ASSET_SYMBOL.sanitize_option(update_request.symbol)?,
+    };
+    let asset = api_state.service.update(id, update_request.into()).await?;
     Ok(asset.into())
 }
 
@@ -324,7 +350,12 @@ pub async fn delete() -> Result<DeleteResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<AssetApiState, _>(&state).await?;
 
-    let Path(PathAssetId { id }) = extract().await?;
-    api_state.asset_service.delete(id).await?;
+    let PathAssetId { id } = extract_path().await?;
+    api_state.service.delete(id).await?;
+    // Deleting the asset cleared it as the default of any institution.
+    state.service_caches.institutions.invalidate();
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
     Ok(DeleteResponse {})
 }
@@ -0,0 +1,458 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::attachment::AttachmentId,
+    schema::attachment::{
+        AttachmentCreateResponse, AttachmentGetListResponse, AttachmentGetResponse, CreateRequest,
+        DeleteResponse, ExtractionResponse, GetListRequest,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{ByteStream, DeleteUrl, GetUrl, Json, Streaming},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, byte_range::respond_with_range,
+            extract_path, extract_with_state, server_fn_uri, set_user_groups,
+            transaction_api::TransactionApiState,
+        },
+        authentication::{api_key::authenticate_api_key, authenticator::Authenticator},
+        config::Feature,
+        extraction::{ExtractionJob, extractor},
+        model::attachment::{Attachment, AttachmentCreate, AttachmentFilter},
+        resource::{
+            GetListRepository, GetRepository, attachment_repository::AttachmentRepository, deadline,
+        },
+        service::{ServiceError, transaction_service::TransactionServiceAttachment},
+        upload::{content_disposition, inspect},
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use base64::{Engine, prelude::BASE64_STANDARD};
+    pub use http::{
+        HeaderValue, Method,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+    };
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+    pub use tracing::error;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// The largest attachment accepted, in bytes.
+pub const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathAttachmentId {
+    id: AttachmentId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// Loads an attachment of one of the transactions the caller can read.
+    /// Attachments of other transactions are indistinguishable from missing
+    /// ones.
+    pub async fn readable_attachment(
+        state: &AppState,
+        api_state: &TransactionApiState,
+        id: AttachmentId,
+    ) -> Result<Attachment, ApiError> {
+        let attachment = AttachmentRepository
+            .get(
+                deadline::begin(&state.connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                id,
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        api_state.service.get(attachment.transaction_id).await?;
+        Ok(attachment)
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            val if val.ends_with("/extraction") => "/extraction".to_string(),
+            val if val.ends_with("/content") => "/content".to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/attachments{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct AttachmentApi;
+
+    impl Api for AttachmentApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::DELETE, "/{id}"),
+                (Method::GET, "/{id}/extraction"),
+                (Method::GET, "/{id}/content"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler).delete(server_fn_handler),
+                )
+                .route("/{id}/extraction", axum::routing::get(server_fn_handler))
+                .route("/{id}/content", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(from_fn_with_state(state.clone(), authenticate_api_key))
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/attachments",
+    tag = "Attachments",
+    params(GetListRequest),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The attachments of the transaction.", body = AttachmentGetListResponse),
+        (status = 404, description = "The transaction was not found."),
+    ),
+))]
+#[server(
+    name = AttachmentApiGetList,
+    prefix = "/api",
+    endpoint = "/attachments",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list(
+    #[server(flatten)] get_list_request: GetListRequest,
+) -> Result<AttachmentGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+
+    api_state
+        .service
+        .get(get_list_request.transaction_id)
+        .await?;
+    let attachments = AttachmentRepository
+        .get_list(
+            deadline::begin(&state.connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            0,
+            None,
+            AttachmentFilter {
+                transaction_id: get_list_request.transaction_id.into(),
+            },
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    Ok(attachments.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/attachments/{id}",
+    tag = "Attachments",
+    params(AttachmentId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The attachment.", body = AttachmentGetResponse),
+        (status = 404, description = "The attachment was not found."),
+    ),
+))]
+#[server(
+    name = AttachmentApiGet,
+    prefix = "/api",
+    endpoint = "attachments/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<AttachmentGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathAttachmentId { id } = extract_path().await?;
+
+    let attachment = readable_attachment(&state, &api_state, id).await?;
+    Ok(attachment.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/attachments",
+    tag = "Attachments",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The uploaded attachment. Its receipt details are extracted before responding. The filename is kept sanitized, and the content type as sniffed from the content.", body = AttachmentCreateResponse),
+        (status = 400, description = "The file is empty, too large or not base64 encoded, isn't a PDF, PNG, JPEG or HEIC file, isn't the content type it is declared as, or has too many pixels or pages."),
+        (status = 403, description = "The caller may not update transactions."),
+        (status = 404, description = "The transaction was not found, or the caller can only read it."),
+    ),
+))]
+#[server(
+    name = AttachmentApiCreate,
+    prefix = "/api",
+    endpoint = "attachments",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<AttachmentCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+
+    if create_request.content_type.is_empty() || create_request.content_type.len() > 127 {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidFile,
+            "The content type must be from 1 to 127 bytes.",
+        ));
+    }
+    let content = BASE64_STANDARD
+        .decode(&create_request.content)
+        .map_err(|_| {
+            ApiError::client(
+                ClientErrorCode::InvalidFile,
+                "The content is not valid base64.",
+            )
+        })?;
+    if content.is_empty() || content.len() > MAX_ATTACHMENT_BYTES {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidFile,
+            format!("Attachments must be from 1 to {MAX_ATTACHMENT_BYTES} bytes."),
+        ));
+    }
+    let upload = inspect(
+        &create_request.filename,
+        &create_request.content_type,
+        &content,
+    )?;
+    let attachment = api_state
+        .service
+        .create_attachment(AttachmentCreate {
+            transaction_id: create_request.transaction_id,
+            filename: upload.filename,
+            content_type: upload.content_type.to_owned(),
+            content: content.clone(),
+        })
+        .await?;
+
+    // There is no job queue yet, so the extraction runs before responding.
+    if state.features.is_enabled(Feature::ReceiptExtraction) {
+        ExtractionJob {
+            attachment: attachment.clone(),
+            content,
+        }
+        .run(&state.connection_pool, extractor().as_ref())
+        .await?;
+    }
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(AttachmentCreateResponse::status());
+    provide_context(response_opts);
+    Ok(attachment.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/attachments/{id}",
+    tag = "Attachments",
+    params(AttachmentId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The attachment was successfully deleted."),
+        (status = 403, description = "The caller may not update transactions."),
+        (status = 404, description = "The attachment was not found, or the caller can only read its transaction.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = AttachmentApiDelete,
+    prefix = "/api",
+    endpoint = "attachments/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathAttachmentId { id } = extract_path().await?;
+
+    api_state.service.delete_attachment(id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/attachments/{id}/extraction",
+    tag = "Attachments",
+    params(AttachmentId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The receipt details read off the attachment.", body = ExtractionResponse),
+        (status = 404, description = "The attachment was not found or has not been extracted."),
+    ),
+))]
+#[server(
+    name = AttachmentApiGetExtraction,
+    prefix = "/api",
+    endpoint = "attachments/extraction",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_extraction() -> Result<ExtractionResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathAttachmentId { id } = extract_path().await?;
+
+    readable_attachment(&state, &api_state, id).await?;
+    let extraction = AttachmentRepository
+        .get_extraction(
+            deadline::begin(&state.connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            id,
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    Ok(extraction.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/attachments/{id}/content",
+    tag = "Attachments",
+    params(AttachmentId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The uploaded file, as its sniffed content type. It is always downloaded rather than shown, under its sanitized filename.", content(
+            (String = "application/pdf"),
+            (String = "image/png"),
+            (String = "image/jpeg"),
+            (String = "image/heic"),
+        )),
+        (status = 206, description = "The part of the file the `Range` header asks for, unless an `If-Range` no longer matches the `ETag` of the file."),
+        (status = 404, description = "The attachment was not found."),
+        (status = 416, description = "The range starts past the end of the file."),
+    ),
+))]
+#[server(
+    name = AttachmentApiGetContent,
+    prefix = "/api",
+    endpoint = "attachments/content",
+    input = GetUrl,
+    output = Streaming,
+    client = ApiClient,
+)]
+pub async fn get_content() -> Result<ByteStream<ApiError>, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathAttachmentId { id } = extract_path().await?;
+
+    let attachment = readable_attachment(&state, &api_state, id).await?;
+    let header = |value: String| {
+        HeaderValue::try_from(value).map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })
+    };
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.insert_header(CONTENT_TYPE, header(attachment.content_type)?);
+    response_opts.insert_header(
+        CONTENT_DISPOSITION,
+        header(content_disposition(&attachment.filename))?,
+    );
+    response_opts.insert_header(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    provide_context(response_opts);
+
+    // The content of an attachment never changes under its id.
+    let etag = format!("\"{}\"", attachment.id);
+    respond_with_range(&etag, attachment.size as u64, |range| async move {
+        let content = AttachmentRepository
+            .get_content_range(
+                deadline::begin(&state.connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                id,
+                range,
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        Ok(content)
+    })
+    .await
+}
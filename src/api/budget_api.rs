@@ -0,0 +1,404 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::budget::BudgetId,
+    schema::budget::{
+        BudgetCreateResponse, BudgetGetListResponse, BudgetGetResponse, BudgetStatusResponse,
+        CreateRequest, DeleteResponse, StatusRequest,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode,
+            asset_api::asset_scale,
+            extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        model::budget::{BudgetCreate, BudgetFilter, BudgetKind},
+        rounding::RoundingPolicy,
+        service::{
+            budget_service::BudgetServiceMethods, budget_service_factory::BudgetServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use chrono::{DateTime, Datelike, Months, NaiveTime, Utc};
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use rust_decimal::Decimal;
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathBudgetId {
+    id: BudgetId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `budgets` the API resolves for a caller. Budgets are
+    /// never updated.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::NoPermission,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct BudgetApiResource;
+
+    impl ApiResource for BudgetApiResource {
+        const NAME: &'static str = "budgets";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn BudgetServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            BudgetServiceFactory::build(owner, Arc::clone(&state.connection_pool), permission_set)
+        }
+    }
+
+    pub type BudgetApiState = ResourceContext<BudgetApiResource>;
+
+    /// The start of the current month, which budget periods default to.
+    pub fn start_of_month() -> DateTime<Utc> {
+        let today = Utc::now().date_naive();
+        today
+            .with_day(1)
+            .unwrap_or(today)
+            .and_time(NaiveTime::MIN)
+            .and_utc()
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            val if req.uri().path().ends_with("/status") => match val.split_once('?') {
+                Some((_, query)) => format!("/status?{query}"),
+                None => "/status".to_string(),
+            },
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/budgets{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct BudgetApi;
+
+    impl Api for BudgetApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::DELETE, "/{id}"),
+                (Method::GET, "/{id}/status"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler).delete(server_fn_handler),
+                )
+                .route("/{id}/status", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/budgets",
+    tag = "Budgets",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The budgets of the user.", body = BudgetGetListResponse)
+    ),
+))]
+#[server(
+    name = BudgetApiGetList,
+    prefix = "/api",
+    endpoint = "/budgets",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<BudgetGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<BudgetApiState, _>(&state).await?;
+
+    let budgets = api_state
+        .service
+        .get_list(0, None, BudgetFilter::default())
+        .await?;
+    Ok(budgets.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/budgets/{id}",
+    tag = "Budgets",
+    params(BudgetId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The budget.", body = BudgetGetResponse),
+        (status = 404, description = "The budget was not found."),
+    ),
+))]
+#[server(
+    name = BudgetApiGet,
+    prefix = "/api",
+    endpoint = "budgets/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<BudgetGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<BudgetApiState, _>(&state).await?;
+    let PathBudgetId { id } = extract_path().await?;
+
+    let budget = api_state.service.get(id).await?;
+    Ok(budget.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/budgets",
+    tag = "Budgets",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The newly created budget.", body = BudgetCreateResponse),
+        (status = 400, description = "The limit does not suit the kind of budget."),
+        (status = 404, description = "The asset or one of the accounts was not found."),
+    ),
+))]
+#[server(
+    name = BudgetApiCreate,
+    prefix = "/api",
+    endpoint = "budgets",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<BudgetCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<BudgetApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+
+    let scale = asset_scale(&state, create_request.asset_id).await?;
+    let amount = create_request
+        .amount
+        .map(|amount| amount.in_asset(scale))
+        .transpose()?;
+
+    match (create_request.kind, amount, create_request.percent) {
+        (BudgetKind::Fixed, Some(amount), None) if amount >= Decimal::ZERO => {}
+        (BudgetKind::PercentOfIncome, None, Some(percent)) if (0..=100).contains(&percent) => {}
+        (BudgetKind::Fixed, _, _) => {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "A fixed budget needs a non-negative `amount` and no `percent`.",
+            ));
+        }
+        (BudgetKind::PercentOfIncome, _, _) => {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "A percent of income budget needs a `percent` from 0 to 100 and no `amount`.",
+            ));
+        }
+    }
+
+    let account_ids = match create_request.account_ids {
+        Some(mut account_ids) => {
+            account_ids.sort_by_key(|id| id.0);
+            account_ids.dedup();
+            if account_ids.is_empty() {
+                return Err(ApiError::client(
+                    ClientErrorCode::InvalidRequest,
+                    "A budget must track at least one account.",
+                ));
+            }
+            Some(account_ids)
+        }
+        None => None,
+    };
+
+    let budget = api_state
+        .service
+        .create(BudgetCreate {
+            user_id: registered_user.id(),
+            name: create_request.name,
+            asset_id: create_request.asset_id,
+            kind: create_request.kind,
+            amount,
+            percent: create_request.percent,
+            account_ids,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(BudgetCreateResponse::status());
+    provide_context(response_opts);
+    Ok(budget.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/budgets/{id}",
+    tag = "Budgets",
+    params(BudgetId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The budget was successfully deleted."),
+        (status = 404, description = "The budget was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = BudgetApiDelete,
+    prefix = "/api",
+    endpoint = "budgets/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<BudgetApiState, _>(&state).await?;
+    let PathBudgetId { id } = extract_path().await?;
+
+    api_state.service.delete(id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/budgets/{id}/status",
+    tag = "Budgets",
+    params(BudgetId, StatusRequest),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The spending against the budget over the period.", body = BudgetStatusResponse),
+        (status = 400, description = "The period is empty."),
+        (status = 404, description = "The budget was not found."),
+    ),
+))]
+#[server(
+    name = BudgetApiGetStatus,
+    prefix = "/api",
+    endpoint = "budgets/status",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_status(
+    #[server(flatten)]
+    #[server(default)]
+    status_request: StatusRequest,
+) -> Result<BudgetStatusResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<BudgetApiState, _>(&state).await?;
+    let PathBudgetId { id } = extract_path().await?;
+
+    let start = status_request.start.unwrap_or_else(start_of_month);
+    let end = status_request
+        .end
+        .or_else(|| start.checked_add_months(Months::new(1)))
+        .ok_or(ApiError::ServerError)?;
+    if end <= start {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            "The period must end after it starts.",
+        ));
+    }
+
+    let rounding = RoundingPolicy::from_env();
+    let (budget, totals) = api_state.service.status(id, start, end, rounding).await?;
+    Ok(BudgetStatusResponse::new(
+        &budget, start, end, totals, rounding,
+    ))
+}
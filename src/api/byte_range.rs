@@ -0,0 +1,232 @@
+//! Lets clients download large files in parts and resume them.
+//!
+//! [`respond_with_range`] answers a download with only the part a `Range`
+//! header asks for, as `206 Partial Content`, or with all of it, marked
+//! with `Accept-Ranges: bytes`. Each download has an `ETag`, so that a
+//! client resuming with `If-Range` gets the rest of the file it started
+//! on, or all of it again if the file changed since. Only a single range
+//! of bytes is served, a request for several is answered in full.
+
+use std::ops::Range;
+
+use futures::stream;
+use http::{
+    HeaderMap, HeaderValue, StatusCode,
+    header::{ACCEPT_RANGES, CONTENT_RANGE, ETAG, IF_RANGE, RANGE},
+    request::Parts,
+};
+use leptos::{prelude::*, server_fn::codec::ByteStream};
+use leptos_axum::ResponseOptions;
+use tracing::error;
+
+use crate::api::ApiError;
+
+/// An inclusive range of the bytes of a file, within its length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub first: u64,
+    pub last: u64,
+}
+
+impl From<ByteRange> for Range<u64> {
+    fn from(value: ByteRange) -> Self {
+        value.first..value.last + 1
+    }
+}
+
+/// What a request asks of a file of some length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// All of the file, because no range was asked for, the range could
+    /// not be read, or the file changed since the client's `If-Range`
+    Full,
+    Partial(ByteRange),
+    /// A range that starts past the end of the file
+    Unsatisfiable,
+}
+
+impl RangeRequest {
+    /// Reads the `Range` and `If-Range` headers of a request for a file of
+    /// `len` bytes whose entity tag is `etag`.
+    pub fn of(headers: &HeaderMap, etag: &str, len: u64) -> Self {
+        let Some(range) = headers.get(RANGE).and_then(|x| x.to_str().ok()) else {
+            return Self::Full;
+        };
+        // Weak tags never match, and neither do dates, which would need
+        // the file's modification time to compare.
+        let unchanged = headers
+            .get(IF_RANGE)
+            .is_none_or(|x| x.as_bytes() == etag.as_bytes() && !etag.starts_with("W/"));
+        if !unchanged {
+            return Self::Full;
+        }
+        Self::parse(range, len)
+    }
+
+    /// Parses a `Range` header for a file of `len` bytes. Headers that
+    /// aren't a single range of bytes are ignored, as RFC 9110 allows.
+    pub fn parse(range: &str, len: u64) -> Self {
+        let Some(range) = range.trim().strip_prefix("bytes=") else {
+            return Self::Full;
+        };
+        let Some((first, last)) = range.trim().split_once('-') else {
+            return Self::Full;
+        };
+        if range.contains(',') {
+            return Self::Full;
+        }
+        let parse = |x: &str| {
+            (!x.is_empty() && x.bytes().all(|x| x.is_ascii_digit()))
+                .then(|| x.parse::<u64>().ok())
+                .flatten()
+        };
+        match (first.trim(), last.trim()) {
+            ("", suffix) => match parse(suffix) {
+                Some(0) => Self::Unsatisfiable,
+                Some(_) if len == 0 => Self::Unsatisfiable,
+                Some(suffix) => Self::Partial(ByteRange {
+                    first: len.saturating_sub(suffix),
+                    last: len - 1,
+                }),
+                None => Self::Full,
+            },
+            (first, "") => match parse(first) {
+                Some(first) if first >= len => Self::Unsatisfiable,
+                Some(first) => Self::Partial(ByteRange {
+                    first,
+                    last: len - 1,
+                }),
+                None => Self::Full,
+            },
+            (first, last) => match (parse(first), parse(last)) {
+                (Some(first), Some(last)) if first > last => Self::Full,
+                (Some(first), Some(_)) if first >= len => Self::Unsatisfiable,
+                (Some(first), Some(last)) => Self::Partial(ByteRange {
+                    first,
+                    last: last.min(len - 1),
+                }),
+                _ => Self::Full,
+            },
+        }
+    }
+}
+
+/// Responds with the part of a file of `len` bytes the request asks for,
+/// reading it with `read`. `etag` is the strong entity tag of the file,
+/// quoted, which must change whenever its content does.
+pub async fn respond_with_range<F, Fut>(
+    etag: &str,
+    len: u64,
+    read: F,
+) -> Result<ByteStream<ApiError>, ApiError>
+where
+    F: FnOnce(Range<u64>) -> Fut,
+    Fut: Future<Output = Result<Vec<u8>, ApiError>>,
+{
+    let header = |value: String| {
+        HeaderValue::try_from(value).map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })
+    };
+    let parts = expect_context::<Parts>();
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.insert_header(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    response_opts.insert_header(ETAG, header(etag.to_owned())?);
+    let content = match RangeRequest::of(&parts.headers, etag, len) {
+        RangeRequest::Full => read(0..len).await?,
+        RangeRequest::Partial(range) => {
+            response_opts.set_status(StatusCode::PARTIAL_CONTENT);
+            response_opts.insert_header(
+                CONTENT_RANGE,
+                header(format!("bytes {}-{}/{len}", range.first, range.last))?,
+            );
+            read(range.into()).await?
+        }
+        RangeRequest::Unsatisfiable => {
+            response_opts.set_status(StatusCode::RANGE_NOT_SATISFIABLE);
+            response_opts.insert_header(CONTENT_RANGE, header(format!("bytes */{len}"))?);
+            vec![]
+        }
+    };
+    provide_context(response_opts);
+    Ok(ByteStream::new(stream::iter([Ok::<_, ApiError>(content)])))
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("bytes=0-99", RangeRequest::Partial(ByteRange { first: 0, last: 99 }))]
+    #[case("bytes=100-", RangeRequest::Partial(ByteRange { first: 100, last: 999 }))]
+    #[case("bytes=-100", RangeRequest::Partial(ByteRange { first: 900, last: 999 }))]
+    #[case("bytes=-5000", RangeRequest::Partial(ByteRange { first: 0, last: 999 }))]
+    #[case("bytes=900-5000", RangeRequest::Partial(ByteRange { first: 900, last: 999 }))]
+    #[case("bytes=999-999", RangeRequest::Partial(ByteRange { first: 999, last: 999 }))]
+    #[case("bytes=1000-", RangeRequest::Unsatisfiable)]
+    #[case("bytes=1000-1999", RangeRequest::Unsatisfiable)]
+    #[case("bytes=-0", RangeRequest::Unsatisfiable)]
+    #[case("bytes=100-0", RangeRequest::Full)]
+    #[case("bytes=0-9,20-29", RangeRequest::Full)]
+    #[case("bytes=a-b", RangeRequest::Full)]
+    #[case("bytes=+1-2", RangeRequest::Full)]
+    #[case("bytes=-", RangeRequest::Full)]
+    #[case("items=0-9", RangeRequest::Full)]
+    fn it_parses_single_byte_ranges(#[case] range: &str, #[case] expected: RangeRequest) {
+        assert_eq!(RangeRequest::parse(range, 1000), expected);
+    }
+
+    #[test]
+    fn it_can_not_satisfy_any_range_of_an_empty_file() {
+        for range in ["bytes=0-", "bytes=0-0", "bytes=-1"] {
+            assert_eq!(RangeRequest::parse(range, 0), RangeRequest::Unsatisfiable);
+        }
+    }
+
+    #[test]
+    fn it_serves_everything_when_the_file_changed() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RANGE, HeaderValue::from_static("bytes=10-"));
+        let partial = RangeRequest::Partial(ByteRange {
+            first: 10,
+            last: 99,
+        });
+        assert_eq!(RangeRequest::of(&headers, "\"v1\"", 100), partial);
+        headers.insert(IF_RANGE, HeaderValue::from_static("\"v1\""));
+        assert_eq!(RangeRequest::of(&headers, "\"v1\"", 100), partial);
+        assert_eq!(
+            RangeRequest::of(&headers, "\"v2\"", 100),
+            RangeRequest::Full
+        );
+        headers.insert(
+            IF_RANGE,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(
+            RangeRequest::of(&headers, "\"v1\"", 100),
+            RangeRequest::Full
+        );
+        headers.insert(IF_RANGE, HeaderValue::from_static("W/\"v1\""));
+        assert_eq!(
+            RangeRequest::of(&headers, "W/\"v1\"", 100),
+            RangeRequest::Full
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn it_only_serves_ranges_within_the_file(first: u64, last: u64, len in 0..u64::MAX) {
+            if let RangeRequest::Partial(range) =
+                RangeRequest::parse(&format!("bytes={first}-{last}"), len)
+            {
+                prop_assert!(range.first <= range.last);
+                prop_assert!(range.last < len);
+                prop_assert_eq!(Range::from(range).end - range.first, range.last - range.first + 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Lets browsers keep the responses that rarely or never change.
+//!
+//! The [`set_cache_control`] middleware sorts each request into a
+//! [`CacheClass`] by its route and marks successful responses of the class
+//! cacheable. Errors are never marked, and lose any `Cache-Control` a
+//! handler gave them, so a failure is not replayed from a cache.
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use http::{
+    HeaderValue, Method,
+    header::{CACHE_CONTROL, VARY},
+};
+
+use crate::config::CacheConfig;
+
+/// The lists whose responses may be kept for
+/// [`CacheConfig::list_max_age`].
+pub const CACHED_LISTS: [&str; 2] = ["/api/assets", "/api/institutions"];
+
+/// How long the responses of a route may be kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheClass {
+    /// The files of the site under `/pkg`, which never change under a name
+    /// once their hash is in it
+    Immutable,
+    /// Lists that change rarely but differ between callers
+    PrivateList,
+}
+
+impl CacheClass {
+    pub fn of(method: &Method, path: &str) -> Option<Self> {
+        if method != Method::GET {
+            return None;
+        }
+        if path.starts_with("/pkg/") {
+            Some(Self::Immutable)
+        } else if CACHED_LISTS.contains(&path) {
+            Some(Self::PrivateList)
+        } else {
+            None
+        }
+    }
+}
+
+/// What the middleware marks cacheable.
+#[derive(Debug, Clone, Copy)]
+pub struct CachePolicy {
+    /// Whether the files of the site have their hash in their name. Files
+    /// that keep their name across builds are left to revalidate.
+    pub hashed_files: bool,
+    pub config: CacheConfig,
+}
+
+impl CachePolicy {
+    /// The `Cache-Control` of the successful responses of `class`, if they
+    /// may be cached at all.
+    pub fn header(&self, class: CacheClass) -> Option<HeaderValue> {
+        match class {
+            CacheClass::Immutable if self.hashed_files => Some(HeaderValue::from_static(
+                "public, max-age=31536000, immutable",
+            )),
+            CacheClass::Immutable => None,
+            CacheClass::PrivateList => {
+                let max_age = self.config.list_max_age.as_secs();
+                (max_age > 0).then(|| {
+                    HeaderValue::from_str(&format!("private, max-age={max_age}"))
+                        .expect("Invalid Cache-Control header.")
+                })
+            }
+        }
+    }
+}
+
+/// Marks the successful responses of the routes of a [`CacheClass`]
+/// cacheable, and keeps any error response from being cached.
+pub async fn set_cache_control(
+    State(policy): State<CachePolicy>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let class = CacheClass::of(request.method(), request.uri().path());
+    let mut response = next.run(request).await;
+    if !response.status().is_success() {
+        response.headers_mut().remove(CACHE_CONTROL);
+        return response;
+    }
+    let Some(class) = class else {
+        return response;
+    };
+    if let Some(cache_control) = policy.header(class) {
+        let headers = response.headers_mut();
+        headers.insert(CACHE_CONTROL, cache_control);
+        if class == CacheClass::PrivateList {
+            headers.append(VARY, HeaderValue::from_static("Authorization"));
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use axum::{Router, body::Body, middleware::from_fn_with_state, routing::get};
+    use http::StatusCode;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn policy(hashed_files: bool, list_max_age: u64) -> CachePolicy {
+        CachePolicy {
+            hashed_files,
+            config: CacheConfig {
+                list_max_age: Duration::from_secs(list_max_age),
+            },
+        }
+    }
+
+    async fn respond(policy: CachePolicy, method: Method, uri: &str) -> Response {
+        let router = Router::new()
+            .route("/pkg/{*file}", get(|| async { "file" }))
+            .route(
+                "/api/assets",
+                get(|| async { "assets" }).post(|| async { "created" }),
+            )
+            .route(
+                "/api/institutions",
+                get(|| async { StatusCode::UNAUTHORIZED }),
+            )
+            .route(
+                "/api/transactions",
+                get(|| async { ([(CACHE_CONTROL, "max-age=10")], "transactions") }),
+            )
+            .route(
+                "/api/accounts",
+                get(|| async { (StatusCode::BAD_REQUEST, [(CACHE_CONTROL, "max-age=10")]) }),
+            )
+            .layer(from_fn_with_state(policy, set_cache_control));
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        router.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_caches_hashed_files_for_good() {
+        let response = respond(policy(true, 60), Method::GET, "/pkg/treasury.wasm").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+        assert!(response.headers().get(VARY).is_none());
+
+        let response = respond(policy(false, 60), Method::GET, "/pkg/treasury.wasm").await;
+        assert!(response.headers().get(CACHE_CONTROL).is_none());
+    }
+
+    #[tokio::test]
+    async fn it_caches_lists_privately_per_caller() {
+        let response = respond(policy(true, 60), Method::GET, "/api/assets?max_items=5").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], "private, max-age=60");
+        assert_eq!(response.headers()[VARY], "Authorization");
+
+        let response = respond(policy(true, 0), Method::GET, "/api/assets").await;
+        assert!(response.headers().get(CACHE_CONTROL).is_none());
+        assert!(response.headers().get(VARY).is_none());
+    }
+
+    #[tokio::test]
+    async fn it_leaves_other_routes_alone() {
+        let response = respond(policy(true, 60), Method::POST, "/api/assets").await;
+        assert!(response.headers().get(CACHE_CONTROL).is_none());
+
+        let response = respond(policy(true, 60), Method::GET, "/api/transactions").await;
+        assert_eq!(response.headers()[CACHE_CONTROL], "max-age=10");
+    }
+
+    #[tokio::test]
+    async fn it_never_caches_errors() {
+        let response = respond(policy(true, 60), Method::GET, "/api/institutions").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().get(CACHE_CONTROL).is_none());
+        assert!(response.headers().get(VARY).is_none());
+
+        let response = respond(policy(true, 60), Method::GET, "/api/accounts").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.headers().get(CACHE_CONTROL).is_none());
+    }
+}
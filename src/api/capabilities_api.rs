@@ -0,0 +1,380 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::capabilities::CapabilitiesResponse,
+};
+use leptos::{
+    server,
+    server_fn::codec::{GetUrl, Json},
+};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, AppState,
+            account_api::{self, AccountApi},
+            admin_api::{self, AdminApi},
+            alert_rule_api::{self, AlertRuleApi},
+            announcement_api::{self, AnnouncementApi},
+            asset_api::{self, AssetApi},
+            attachment_api::MAX_ATTACHMENT_BYTES,
+            budget_api::{self, BudgetApi},
+            categorization_rule_api::{self, CategorizationRuleApi},
+            event_api::{self, EventApi},
+            exchange_rate_api::{self, ExchangeRateApi},
+            export_schedule_api::{self, ExportScheduleApi},
+            extract_with_state,
+            import_profile_api::{self, ImportProfileApi},
+            institution_api::{self, InstitutionApi},
+            proposal_api::{self, ProposalApi},
+            quick_entry_api::{self, QuickEntryApi},
+            resource_context::ApiResource,
+            seed_api::{self, SeedApi},
+            server_fn_uri, set_user_groups,
+            sync_api::MAX_SYNC_ITEMS,
+            transaction_api::{self, TransactionApi},
+            user_api::{self, UserApi},
+            watchlist_api::{self, WatchlistApi},
+        },
+        authentication::{authenticated_token::AuthenticatedToken, authenticator::Authenticator},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        categorization::MAX_RULES,
+        config::PagedResource,
+        schema::{
+            capabilities::{EndpointResponse, LimitsResponse, ResourceCapabilitiesResponse},
+            notes::MAX_NOTES_BYTES,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+    pub use tracing::error;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// A resource the authorization policy grants levels of, along with the
+    /// API serving it.
+    pub struct RegisteredResource {
+        /// The name of the resource in the policy
+        pub name: &'static str,
+        /// Where the API of the resource is mounted
+        pub prefix: &'static str,
+        /// The levels the API resolves for a caller
+        pub permission_config: PermissionConfig,
+        pub endpoints: fn() -> Vec<(Method, &'static str)>,
+        /// The endpoints a newer version of the API supersedes, which are
+        /// answered with `Deprecation` and `Sunset` headers
+        pub deprecated: fn() -> Vec<(Method, &'static str)>,
+        /// How the list of the resource is paged, if it is
+        pub paged: Option<PagedResource>,
+    }
+
+    impl RegisteredResource {
+        /// The endpoints `permission_set` allows calling, with the prefix
+        /// applied. Reads are `GET`s, creates `POST`s, updates `PATCH`es and
+        /// deletes `DELETE`s.
+        pub fn allowed_endpoints(&self, permission_set: &PermissionSet) -> Vec<EndpointResponse> {
+            (self.endpoints)()
+                .into_iter()
+                .filter(|(method, _)| match *method {
+                    Method::GET => permission_set.read_level != ReadLevel::NoPermission,
+                    Method::POST => permission_set.create_level != CreateLevel::NoPermission,
+                    Method::PATCH | Method::PUT => {
+                        permission_set.update_level != UpdateLevel::NoPermission
+                    }
+                    Method::DELETE => permission_set.delete_level != DeleteLevel::NoPermission,
+                    _ => false,
+                })
+                .map(|(method, path)| EndpointResponse {
+                    method: method.to_string(),
+                    path: self.path(path),
+                })
+                .collect()
+        }
+
+        /// The deprecated endpoints, with the prefix applied.
+        pub fn deprecated_endpoints(&self) -> Vec<(Method, String)> {
+            (self.deprecated)()
+                .into_iter()
+                .map(|(method, path)| (method, self.path(path)))
+                .collect()
+        }
+
+        fn path(&self, path: &str) -> String {
+            if path == "/" {
+                self.prefix.to_owned()
+            } else {
+                format!("{}{path}", self.prefix)
+            }
+        }
+    }
+
+    /// Every resource whose API resolves a `PermissionSet`.
+    pub const RESOURCES: &[RegisteredResource] = &[
+        RegisteredResource {
+            name: account_api::AccountApiResource::NAME,
+            prefix: "/api/accounts",
+            permission_config: account_api::AccountApiResource::PERMISSION_CONFIG,
+            endpoints: AccountApi::endpoints,
+            deprecated: Vec::new,
+            paged: Some(PagedResource::Accounts),
+        },
+        RegisteredResource {
+            name: admin_api::AdminApiResource::NAME,
+            prefix: "/api/admin",
+            permission_config: admin_api::AdminApiResource::PERMISSION_CONFIG,
+            endpoints: AdminApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: alert_rule_api::AlertRuleApiResource::NAME,
+            prefix: "/api/alert-rules",
+            permission_config: alert_rule_api::AlertRuleApiResource::PERMISSION_CONFIG,
+            endpoints: AlertRuleApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: announcement_api::AnnouncementApiResource::NAME,
+            prefix: "/api/announcements",
+            permission_config: announcement_api::AnnouncementApiResource::PERMISSION_CONFIG,
+            endpoints: AnnouncementApi::endpoints,
+            deprecated: Vec::new,
+            paged: Some(PagedResource::Announcements),
+        },
+        RegisteredResource {
+            name: asset_api::AssetApiResource::NAME,
+            prefix: "/api/assets",
+            permission_config: asset_api::AssetApiResource::PERMISSION_CONFIG,
+            endpoints: AssetApi::endpoints,
+            deprecated: Vec::new,
+            paged: Some(PagedResource::Assets),
+        },
+        RegisteredResource {
+            name: budget_api::BudgetApiResource::NAME,
+            prefix: "/api/budgets",
+            permission_config: budget_api::BudgetApiResource::PERMISSION_CONFIG,
+            endpoints: BudgetApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: categorization_rule_api::CategorizationRuleApiResource::NAME,
+            prefix: "/api/categorization-rules",
+            permission_config:
+                categorization_rule_api::CategorizationRuleApiResource::PERMISSION_CONFIG,
+            endpoints: CategorizationRuleApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: event_api::EventApiResource::NAME,
+            prefix: "/api/events",
+            permission_config: event_api::EventApiResource::PERMISSION_CONFIG,
+            endpoints: EventApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: exchange_rate_api::ExchangeRateApiResource::NAME,
+            prefix: "/api/exchange-rates",
+            permission_config: exchange_rate_api::ExchangeRateApiResource::PERMISSION_CONFIG,
+            endpoints: ExchangeRateApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: export_schedule_api::ExportScheduleApiResource::NAME,
+            prefix: "/api/export-schedules",
+            permission_config: export_schedule_api::ExportScheduleApiResource::PERMISSION_CONFIG,
+            endpoints: ExportScheduleApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: import_profile_api::ImportProfileApiResource::NAME,
+            prefix: "/api/import-profiles",
+            permission_config: import_profile_api::ImportProfileApiResource::PERMISSION_CONFIG,
+            endpoints: ImportProfileApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: institution_api::InstitutionApiResource::NAME,
+            prefix: "/api/institutions",
+            permission_config: institution_api::InstitutionApiResource::PERMISSION_CONFIG,
+            endpoints: InstitutionApi::endpoints,
+            deprecated: Vec::new,
+            paged: Some(PagedResource::Institutions),
+        },
+        RegisteredResource {
+            name: proposal_api::ProposalApiResource::NAME,
+            prefix: "/api/proposals",
+            permission_config: proposal_api::ProposalApiResource::PERMISSION_CONFIG,
+            endpoints: ProposalApi::endpoints,
+            deprecated: Vec::new,
+            paged: Some(PagedResource::Proposals),
+        },
+        RegisteredResource {
+            name: quick_entry_api::QuickEntryApiResource::NAME,
+            prefix: "/api/quick-entries",
+            permission_config: quick_entry_api::QuickEntryApiResource::PERMISSION_CONFIG,
+            endpoints: QuickEntryApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: seed_api::SeedApiResource::NAME,
+            prefix: "/api/seed",
+            permission_config: seed_api::SeedApiResource::PERMISSION_CONFIG,
+            endpoints: SeedApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+        RegisteredResource {
+            name: transaction_api::TransactionApiResource::NAME,
+            prefix: "/api/transactions",
+            permission_config: transaction_api::TransactionApiResource::PERMISSION_CONFIG,
+            endpoints: TransactionApi::endpoints,
+            deprecated: TransactionApi::deprecated,
+            paged: Some(PagedResource::Transactions),
+        },
+        RegisteredResource {
+            name: user_api::UserApiResource::NAME,
+            prefix: "/api/users",
+            permission_config: user_api::UserApiResource::PERMISSION_CONFIG,
+            endpoints: UserApi::endpoints,
+            deprecated: Vec::new,
+            paged: Some(PagedResource::Users),
+        },
+        RegisteredResource {
+            name: watchlist_api::WatchlistApiResource::NAME,
+            prefix: "/api/watchlist",
+            permission_config: watchlist_api::WatchlistApiResource::PERMISSION_CONFIG,
+            endpoints: WatchlistApi::endpoints,
+            deprecated: Vec::new,
+            paged: None,
+        },
+    ];
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = req.uri().to_string();
+        let path = path.trim_start_matches('/');
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/capabilities{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct CapabilitiesApi;
+
+    impl Api for CapabilitiesApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::GET, "/")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/capabilities",
+    tag = "Capabilities",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "What the calling token can do and where, and the limits it is held to.", body = CapabilitiesResponse),
+    ),
+))]
+#[server(
+    name = CapabilitiesApiGet,
+    prefix = "/api",
+    endpoint = "capabilities",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<CapabilitiesResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let authenticated_token = extract_with_state::<AuthenticatedToken, _>(&state).await?;
+
+    let mut resources = vec![];
+    for resource in RESOURCES {
+        let permission_set = PermissionSet::new(
+            resource.name,
+            &state.enforcer,
+            &authenticated_token,
+            resource.permission_config,
+        )
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?;
+        resources.push(ResourceCapabilitiesResponse::new(
+            resource.name,
+            permission_set,
+            resource.allowed_endpoints(&permission_set),
+            resource.paged.map(|x| x.page_size()),
+        ));
+    }
+
+    Ok(CapabilitiesResponse {
+        resources,
+        rate_limit: state.rate_limiter.config().into(),
+        limits: LimitsResponse {
+            max_attachment_bytes: MAX_ATTACHMENT_BYTES,
+            max_notes_bytes: MAX_NOTES_BYTES,
+            max_categorization_rules: MAX_RULES,
+            max_sync_items: MAX_SYNC_ITEMS,
+        },
+    })
+}
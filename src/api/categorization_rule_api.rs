@@ -0,0 +1,472 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::categorization_rule::CategorizationRuleId,
+    schema::categorization_rule::{
+        ApplyResponse, CategorizationRuleCreateResponse, CategorizationRuleGetListResponse,
+        CategorizationRuleGetResponse, CategorizationRuleUpdateResponse, CreateRequest,
+        DeleteResponse, UpdateRequest,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json, PatchJson},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        categorization::{CategorizationJob, compile_pattern, sanitize_category},
+        model::{
+            account::AccountId,
+            categorization_rule::{
+                CategorizationRuleCreate, CategorizationRuleField, CategorizationRuleFilter,
+            },
+        },
+        service::{
+            categorization_rule_service::CategorizationRuleServiceMethods,
+            categorization_rule_service_factory::CategorizationRuleServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use rust_decimal::Decimal;
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathCategorizationRuleId {
+    id: CategorizationRuleId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `categorization_rules` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct CategorizationRuleApiResource;
+
+    impl ApiResource for CategorizationRuleApiResource {
+        const NAME: &'static str = "categorization_rules";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn CategorizationRuleServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            CategorizationRuleServiceFactory::build(
+                owner,
+                Arc::clone(&state.connection_pool),
+                permission_set,
+            )
+        }
+    }
+
+    pub type CategorizationRuleApiState = ResourceContext<CategorizationRuleApiResource>;
+
+    /// Checks a rule matches on what its field does and nothing else, and
+    /// that its pattern compiles.
+    pub fn validate_categorization_rule(
+        field: CategorizationRuleField,
+        pattern: Option<&str>,
+        min_quantity: Option<Decimal>,
+        max_quantity: Option<Decimal>,
+        account_id: Option<AccountId>,
+    ) -> Result<(), ApiError> {
+        let quantity_range = min_quantity.is_some() || max_quantity.is_some();
+        match (field, pattern, quantity_range, account_id) {
+            (CategorizationRuleField::Description, Some(pattern), false, None) => {
+                compile_pattern(pattern)?;
+            }
+            (CategorizationRuleField::QuantityRange, None, true, None) => {
+                if let (Some(min), Some(max)) = (min_quantity, max_quantity)
+                    && min > max
+                {
+                    return Err(ApiError::client(
+                        ClientErrorCode::InvalidRequest,
+                        "The `min_quantity` must not be above the `max_quantity`.",
+                    ));
+                }
+            }
+            (CategorizationRuleField::Account, None, false, Some(_)) => {}
+            (CategorizationRuleField::Description, _, _, _) => {
+                return Err(ApiError::client(
+                    ClientErrorCode::InvalidRequest,
+                    "A description rule needs a `pattern` and nothing else to match on.",
+                ));
+            }
+            (CategorizationRuleField::QuantityRange, _, _, _) => {
+                return Err(ApiError::client(
+                    ClientErrorCode::InvalidRequest,
+                    "A quantity range rule needs a `min_quantity` or a `max_quantity` and nothing else to match on.",
+                ));
+            }
+            (CategorizationRuleField::Account, _, _, _) => {
+                return Err(ApiError::client(
+                    ClientErrorCode::InvalidRequest,
+                    "An account rule needs an `account_id` and nothing else to match on.",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            val if val.ends_with("/apply") => "/apply".to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/categorization-rules{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct CategorizationRuleApi;
+
+    impl Api for CategorizationRuleApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::POST, "/apply"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route("/apply", axum::routing::post(server_fn_handler))
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler)
+                        .patch(server_fn_handler)
+                        .delete(server_fn_handler),
+                )
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/categorization-rules",
+    tag = "Categorization Rules",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The categorization rules of the user, in the order they are tried.", body = CategorizationRuleGetListResponse)
+    ),
+))]
+#[server(
+    name = CategorizationRuleApiGetList,
+    prefix = "/api",
+    endpoint = "/categorization-rules",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<CategorizationRuleGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<CategorizationRuleApiState, _>(&state).await?;
+
+    let categorization_rules = api_state
+        .service
+        .get_list(0, None, CategorizationRuleFilter::default())
+        .await?;
+    Ok(categorization_rules.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/categorization-rules/{id}",
+    tag = "Categorization Rules",
+    params(CategorizationRuleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The categorization rule.", body = CategorizationRuleGetResponse),
+        (status = 404, description = "The categorization rule was not found."),
+    ),
+))]
+#[server(
+    name = CategorizationRuleApiGet,
+    prefix = "/api",
+    endpoint = "categorization-rules/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<CategorizationRuleGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<CategorizationRuleApiState, _>(&state).await?;
+    let PathCategorizationRuleId { id } = extract_path().await?;
+
+    let categorization_rule = api_state.service.get(id).await?;
+    Ok(categorization_rule.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/categorization-rules",
+    tag = "Categorization Rules",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The newly created categorization rule.", body = CategorizationRuleCreateResponse),
+        (status = 400, description = "The rule does not suit its field, or the pattern is invalid."),
+        (status = 404, description = "The account was not found."),
+        (status = 422, description = "The user has as many categorization rules as they may."),
+    ),
+))]
+#[server(
+    name = CategorizationRuleApiCreate,
+    prefix = "/api",
+    endpoint = "categorization-rules",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<CategorizationRuleCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<CategorizationRuleApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+
+    let category = sanitize_category(&create_request.category)?;
+    validate_categorization_rule(
+        create_request.field,
+        create_request.pattern.as_deref(),
+        create_request.min_quantity,
+        create_request.max_quantity,
+        create_request.account_id,
+    )?;
+
+    let categorization_rule = api_state
+        .service
+        .create(CategorizationRuleCreate {
+            user_id: registered_user.id(),
+            field: create_request.field,
+            pattern: create_request.pattern,
+            min_quantity: create_request.min_quantity,
+            max_quantity: create_request.max_quantity,
+            account_id: create_request.account_id,
+            category,
+            priority: create_request.priority,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(CategorizationRuleCreateResponse::status());
+    provide_context(response_opts);
+    Ok(categorization_rule.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/categorization-rules/{id}",
+    tag = "Categorization Rules",
+    params(CategorizationRuleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = UpdateRequest,
+    responses(
+        (status = 200, description = "The updated categorization rule.", body = CategorizationRuleUpdateResponse),
+        (status = 400, description = "The rule does not suit its field, or the pattern is invalid."),
+        (status = 404, description = "The categorization rule or the account was not found."),
+    ),
+))]
+#[server(
+    name = CategorizationRuleApiUpdate,
+    prefix = "/api",
+    endpoint = "categorization-rules/",
+    input = PatchJson,
+    output = PatchJson,
+    client = ApiClient,
+)]
+pub async fn update(
+    #[server(flatten)] update_request: UpdateRequest,
+) -> Result<CategorizationRuleUpdateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<CategorizationRuleApiState, _>(&state).await?;
+    let PathCategorizationRuleId { id } = extract_path().await?;
+
+    let mut categorization_rule = api_state.service.get(id).await?;
+    if let Some(field) = update_request.field
+        && field != categorization_rule.field
+    {
+        categorization_rule.field = field;
+        categorization_rule.pattern = None;
+        categorization_rule.min_quantity = None;
+        categorization_rule.max_quantity = None;
+        categorization_rule.account_id = None;
+    }
+    if let Some(pattern) = update_request.pattern {
+        categorization_rule.pattern = Some(pattern);
+    }
+    if let Some(min_quantity) = update_request.min_quantity {
+        categorization_rule.min_quantity = Some(min_quantity);
+    }
+    if let Some(max_quantity) = update_request.max_quantity {
+        categorization_rule.max_quantity = Some(max_quantity);
+    }
+    if let Some(account_id) = update_request.account_id {
+        categorization_rule.account_id = Some(account_id);
+    }
+    if let Some(category) = update_request.category {
+        categorization_rule.category = sanitize_category(&category)?;
+    }
+    if let Some(priority) = update_request.priority {
+        categorization_rule.priority = priority;
+    }
+    validate_categorization_rule(
+        categorization_rule.field,
+        categorization_rule.pattern.as_deref(),
+        categorization_rule.min_quantity,
+        categorization_rule.max_quantity,
+        categorization_rule.account_id,
+    )?;
+
+    let categorization_rule = api_state.service.update(id, categorization_rule).await?;
+    Ok(categorization_rule.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/categorization-rules/{id}",
+    tag = "Categorization Rules",
+    params(CategorizationRuleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The categorization rule was successfully deleted. The transactions it categorized keep their category."),
+        (status = 404, description = "The categorization rule was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = CategorizationRuleApiDelete,
+    prefix = "/api",
+    endpoint = "categorization-rules/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<CategorizationRuleApiState, _>(&state).await?;
+    let PathCategorizationRuleId { id } = extract_path().await?;
+
+    api_state.service.delete(id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/categorization-rules/apply",
+    tag = "Categorization Rules",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "How many uncategorized transactions the rules categorized.", body = ApplyResponse),
+    ),
+))]
+#[server(
+    name = CategorizationRuleApiApply,
+    prefix = "/api",
+    endpoint = "categorization-rules/apply",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn apply() -> Result<ApplyResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+
+    let changed = CategorizationJob {
+        user_id: registered_user.id(),
+    }
+    .run(&state.connection_pool)
+    .await?;
+    Ok(ApplyResponse { changed })
+}
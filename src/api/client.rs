@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use futures::{Sink, Stream};
 use leptos::{
     prelude::*,
@@ -9,8 +11,182 @@ use leptos::{
     },
 };
 
-use crate::app::AuthToken;
+use crate::{api::messages::Language, app::AuthToken, schema::NumberFormat};
+
+/// The header a mutation carries to make it safe to send again.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// How long an attempt waits for a response before it gives up.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// What went wrong with a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiFailure {
+    /// No response came back, like when the network is down
+    Network,
+    /// No response came back within [`REQUEST_TIMEOUT`]
+    Timeout,
+    /// The server failed with a 5xx status
+    Server { status: u16 },
+    /// The client is over its rate limit, and may try again after
+    /// `retry_after` if the server said when
+    RateLimited { retry_after: Option<Duration> },
+    /// The request was refused with a 4xx status
+    Client { status: u16 },
+}
+
+impl ApiFailure {
+    /// The failure a response status stands for, if any. `retry_after` is
+    /// the `Retry-After` header of the response.
+    pub fn from_status(status: u16, retry_after: Option<&str>) -> Option<Self> {
+        match status {
+            429 => Some(Self::RateLimited {
+                retry_after: retry_after.and_then(parse_retry_after),
+            }),
+            400..=499 => Some(Self::Client { status }),
+            500..=599 => Some(Self::Server { status }),
+            _ => None,
+        }
+    }
+
+    /// Whether the same request may succeed if sent again.
+    pub fn is_transient(&self) -> bool {
+        !matches!(self, Self::Client { .. })
+    }
+}
+
+/// Reads a `Retry-After` given in seconds. Dates are not supported.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Whether a request may be sent again after a failure. Reads are, while
+/// mutations only are with an idempotency key, so they aren't applied twice.
+pub fn may_retry(method: &str, idempotency_key: bool) -> bool {
+    matches!(method, "GET" | "HEAD") || idempotency_key
+}
+
+/// How a failed request is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How many times a request is sent again after its first attempt
+    pub max_retries: u32,
+    /// The backoff before the first retry, doubling with each one after
+    pub base_delay: Duration,
+    /// The longest a request waits to be retried
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            base_delay: Duration::from_millis(300),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait before sending a request again after `retries`
+    /// retries, or `None` if it should not be. `jitter` is from 0 to 1 and
+    /// spreads the backoff over its upper half, so clients that failed
+    /// together don't retry together.
+    ///
+    /// A `Retry-After` is waited out as given, unless it is longer than the
+    /// longest wait, in which case the failure is final.
+    pub fn delay(&self, retries: u32, failure: &ApiFailure, jitter: f64) -> Option<Duration> {
+        if retries >= self.max_retries || !failure.is_transient() {
+            return None;
+        }
+        if let ApiFailure::RateLimited {
+            retry_after: Some(retry_after),
+        } = failure
+        {
+            return (*retry_after <= self.max_delay).then_some(*retry_after);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2_u32.saturating_pow(retries))
+            .min(self.max_delay);
+        Some(backoff.mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0))
+    }
+}
+
+/// Where the latest request stands, for components to tell a request that
+/// is being retried apart from one that failed for good.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RequestState {
+    #[default]
+    Idle,
+    /// The request failed with `failure` and is being sent again
+    Retrying { attempt: u32, failure: ApiFailure },
+    /// The request failed with `failure` and won't be retried
+    Failed(ApiFailure),
+}
+
+/// The [`RequestState`] of the requests of the [`ApiClient`], provided by
+/// [`App`](crate::app::App).
+#[derive(Debug, Clone, Copy)]
+pub struct ApiStatus(pub RwSignal<RequestState>);
+
+/// Sends one request, once per attempt.
+pub trait Transport {
+    type Response;
+    type Error;
+
+    fn send(&mut self) -> impl Future<Output = Result<Self::Response, Self::Error>>;
+
+    /// What went wrong with an attempt, `None` if it succeeded.
+    fn classify(&self, outcome: &Result<Self::Response, Self::Error>) -> Option<ApiFailure>;
+
+    fn sleep(&mut self, duration: Duration) -> impl Future<Output = ()>;
+}
+
+/// Sends a request until it succeeds or fails in a way `policy` doesn't
+/// retry, reporting each failure. A request that isn't `retryable` is sent
+/// once. The last outcome is returned as it came, so the caller reads the
+/// error the server gave.
+pub async fn send_with_retries<T: Transport>(
+    transport: &mut T,
+    retryable: bool,
+    policy: RetryPolicy,
+    mut jitter: impl FnMut() -> f64,
+    mut report: impl FnMut(RequestState),
+) -> Result<T::Response, T::Error> {
+    let mut retries = 0;
+    loop {
+        let outcome = transport.send().await;
+        let Some(failure) = transport.classify(&outcome) else {
+            report(RequestState::Idle);
+            return outcome;
+        };
+        let delay = retryable
+            .then(|| policy.delay(retries, &failure, jitter()))
+            .flatten();
+        let Some(delay) = delay else {
+            report(RequestState::Failed(failure));
+            return outcome;
+        };
+        retries += 1;
+        report(RequestState::Retrying {
+            attempt: retries,
+            failure,
+        });
+        transport.sleep(delay).await;
+    }
+}
 
+/// Attaches the session's access token to server fn requests.
+///
+/// Browsers refresh through the `refresh_token` cookie, so the
+/// `Refresh-Token` header flow for non-browser clients is never used here.
+///
+/// Quantities are asked for as strings, since the page cannot trust a JSON
+/// number past 2^53, and errors in the [`Language`] of the app.
+///
+/// Reads, and mutations with an [`IDEMPOTENCY_KEY_HEADER`], are retried
+/// on transient failures by the [`RetryPolicy`], with the progress in the
+/// [`ApiStatus`].
 pub struct ApiClient;
 
 impl<E> Client<E> for ApiClient
@@ -27,7 +203,18 @@ where
             .get_untracked()
             .expect("Missing auth token");
         headers.append("Authorization", &format!("Bearer {auth_token}"));
-        BrowserClient::send(req)
+        headers.append(
+            "Accept",
+            &format!("application/json; {}", NumberFormat::STRING_PARAMETER),
+        );
+        if let Some(language) = use_context::<Language>() {
+            headers.append("Accept-Language", language.tag());
+        }
+        #[cfg(feature = "hydrate")]
+        let response = send_wrapper::SendWrapper::new(browser::send(req, use_context()));
+        #[cfg(not(feature = "hydrate"))]
+        let response = BrowserClient::send(req);
+        response
     }
 
     fn open_websocket(
@@ -48,3 +235,378 @@ where
         <BrowserClient as Client<E>>::spawn(future)
     }
 }
+
+#[cfg(feature = "hydrate")]
+mod browser {
+    use futures::{
+        FutureExt,
+        channel::oneshot,
+        future::{Either, select},
+    };
+    use leptos::server_fn::{error::ServerFnErrorErr, request::ClientReq, response::ClientRes};
+    use wasm_bindgen_futures::JsFuture;
+
+    use super::*;
+
+    /// Sent by the rate limiter of the server, which has no `Retry-After`.
+    const RATE_LIMIT_RESET_HEADER: &str = "X-RateLimit-Reset";
+
+    /// Resolves once `duration` has passed.
+    async fn sleep(duration: Duration) {
+        let (sender, receiver) = oneshot::channel();
+        set_timeout(
+            move || {
+                let _ = sender.send(());
+            },
+            duration,
+        );
+        let _ = receiver.await;
+    }
+
+    /// A server fn request, rebuilt from its parts for each attempt since a
+    /// browser request can only be sent once.
+    struct BrowserTransport<E> {
+        method: String,
+        path: String,
+        query: String,
+        content_type: String,
+        accepts: String,
+        body: String,
+        headers: Vec<(String, String)>,
+        timed_out: bool,
+        _error: std::marker::PhantomData<E>,
+    }
+
+    impl<E: FromServerFnError> BrowserTransport<E> {
+        fn request(&self) -> Result<BrowserRequest, E> {
+            let (path, content_type, accepts) = (&self.path, &self.content_type, &self.accepts);
+            let request: Result<BrowserRequest, E> = match self.method.as_str() {
+                "GET" => ClientReq::try_new_get(path, content_type, accepts, &self.query),
+                "DELETE" => ClientReq::try_new_delete(path, content_type, accepts, &self.query),
+                "PATCH" => ClientReq::try_new_patch(path, content_type, accepts, self.body.clone()),
+                "PUT" => ClientReq::try_new_put(path, content_type, accepts, self.body.clone()),
+                _ => ClientReq::try_new_post(path, content_type, accepts, self.body.clone()),
+            };
+            let request = request?;
+            let headers = request.headers();
+            for (name, value) in &self.headers {
+                if !name.eq_ignore_ascii_case("content-type")
+                    && !name.eq_ignore_ascii_case("accept")
+                {
+                    headers.append(name, value);
+                }
+            }
+            Ok(request)
+        }
+    }
+
+    impl<E: FromServerFnError> Transport for BrowserTransport<E> {
+        type Response = BrowserResponse;
+        type Error = E;
+
+        async fn send(&mut self) -> Result<BrowserResponse, E> {
+            let request = self.request()?;
+            let response = BrowserClient::send(request).boxed_local();
+            match select(response, sleep(REQUEST_TIMEOUT).boxed_local()).await {
+                Either::Left((response, _)) => {
+                    self.timed_out = false;
+                    response
+                }
+                Either::Right(_) => {
+                    self.timed_out = true;
+                    Err(E::from_server_fn_error(ServerFnErrorErr::Request(
+                        "The request timed out.".into(),
+                    )))
+                }
+            }
+        }
+
+        fn classify(&self, outcome: &Result<BrowserResponse, E>) -> Option<ApiFailure> {
+            match outcome {
+                Ok(response) => {
+                    let status = <BrowserResponse as ClientRes<E>>::status(response);
+                    let headers = response.generate_headers();
+                    let retry_after = headers
+                        .get("Retry-After")
+                        .or_else(|| headers.get(RATE_LIMIT_RESET_HEADER));
+                    ApiFailure::from_status(status, retry_after.as_deref())
+                }
+                Err(_) if self.timed_out => Some(ApiFailure::Timeout),
+                Err(_) => Some(ApiFailure::Network),
+            }
+        }
+
+        async fn sleep(&mut self, duration: Duration) {
+            sleep(duration).await
+        }
+    }
+
+    /// Sends a server fn request, retrying it if [`may_retry`] allows.
+    pub async fn send<E: FromServerFnError>(
+        req: BrowserRequest,
+        status: Option<ApiStatus>,
+    ) -> Result<BrowserResponse, E> {
+        let headers = req.headers().entries().collect::<Vec<_>>();
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+        let idempotency_key = header(IDEMPOTENCY_KEY_HEADER).is_some();
+        let content_type = header("Content-Type").unwrap_or_default();
+        let accepts = header("Accept").unwrap_or_default();
+
+        let request = web_sys::Request::from(req);
+        let method = request.method();
+        let url = web_sys::Url::new(&request.url()).map_err(|_| {
+            E::from_server_fn_error(ServerFnErrorErr::Request("Invalid request URL.".into()))
+        })?;
+        let body = match method.as_str() {
+            "GET" | "HEAD" | "DELETE" => String::new(),
+            _ => {
+                let text = request.text().map_err(|_| {
+                    E::from_server_fn_error(ServerFnErrorErr::Request(
+                        "Unreadable request body.".into(),
+                    ))
+                })?;
+                JsFuture::from(text)
+                    .await
+                    .ok()
+                    .and_then(|text| text.as_string())
+                    .unwrap_or_default()
+            }
+        };
+
+        let mut transport = BrowserTransport::<E> {
+            method,
+            path: url.pathname(),
+            query: url.search().trim_start_matches('?').to_owned(),
+            content_type,
+            accepts,
+            body,
+            headers,
+            timed_out: false,
+            _error: std::marker::PhantomData,
+        };
+        let retryable = may_retry(&transport.method, idempotency_key);
+        send_with_retries(
+            &mut transport,
+            retryable,
+            RetryPolicy::default(),
+            js_sys::Math::random,
+            |state| {
+                if let Some(status) = status {
+                    status.0.try_set(state);
+                }
+            },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    /// Plays back canned outcomes, recording how long it was made to wait.
+    struct MockTransport {
+        outcomes: VecDeque<Result<(u16, Option<&'static str>), ApiFailure>>,
+        sent: usize,
+        slept: Vec<Duration>,
+    }
+
+    impl MockTransport {
+        fn new(outcomes: Vec<Result<(u16, Option<&'static str>), ApiFailure>>) -> Self {
+            Self {
+                outcomes: outcomes.into(),
+                sent: 0,
+                slept: vec![],
+            }
+        }
+    }
+
+    impl Transport for MockTransport {
+        type Response = (u16, Option<&'static str>);
+        type Error = ApiFailure;
+
+        async fn send(&mut self) -> Result<Self::Response, Self::Error> {
+            self.sent += 1;
+            self.outcomes.pop_front().expect("Sent too many times.")
+        }
+
+        fn classify(&self, outcome: &Result<Self::Response, Self::Error>) -> Option<ApiFailure> {
+            match outcome {
+                Ok((status, retry_after)) => ApiFailure::from_status(*status, *retry_after),
+                Err(failure) => Some(failure.clone()),
+            }
+        }
+
+        async fn sleep(&mut self, duration: Duration) {
+            self.slept.push(duration);
+        }
+    }
+
+    async fn run(
+        transport: &mut MockTransport,
+        retryable: bool,
+    ) -> (
+        Result<(u16, Option<&'static str>), ApiFailure>,
+        Vec<RequestState>,
+    ) {
+        let mut states = vec![];
+        let outcome = send_with_retries(
+            transport,
+            retryable,
+            RetryPolicy::default(),
+            || 1.0,
+            |state| states.push(state),
+        )
+        .await;
+        (outcome, states)
+    }
+
+    #[test]
+    fn it_classifies_statuses() {
+        assert_eq!(ApiFailure::from_status(200, None), None);
+        assert_eq!(ApiFailure::from_status(304, None), None);
+        assert_eq!(
+            ApiFailure::from_status(404, None),
+            Some(ApiFailure::Client { status: 404 })
+        );
+        assert_eq!(
+            ApiFailure::from_status(503, None),
+            Some(ApiFailure::Server { status: 503 })
+        );
+        assert_eq!(
+            ApiFailure::from_status(429, Some(" 3 ")),
+            Some(ApiFailure::RateLimited {
+                retry_after: Some(Duration::from_secs(3))
+            })
+        );
+        assert_eq!(
+            ApiFailure::from_status(429, Some("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(ApiFailure::RateLimited { retry_after: None })
+        );
+    }
+
+    #[test]
+    fn it_only_retries_reads_and_idempotent_mutations() {
+        assert!(may_retry("GET", false));
+        assert!(!may_retry("POST", false));
+        assert!(!may_retry("DELETE", false));
+        assert!(may_retry("POST", true));
+    }
+
+    #[test]
+    fn it_backs_off_with_jitter() {
+        let policy = RetryPolicy::default();
+        let failure = ApiFailure::Network;
+        assert_eq!(
+            policy.delay(0, &failure, 0.0),
+            Some(Duration::from_millis(150))
+        );
+        assert_eq!(
+            policy.delay(0, &failure, 1.0),
+            Some(Duration::from_millis(300))
+        );
+        assert_eq!(
+            policy.delay(1, &failure, 1.0),
+            Some(Duration::from_millis(600))
+        );
+        assert_eq!(policy.delay(2, &failure, 1.0), None);
+    }
+
+    #[tokio::test]
+    async fn it_retries_network_errors() {
+        let mut transport = MockTransport::new(vec![Err(ApiFailure::Network), Ok((200, None))]);
+        let (outcome, states) = run(&mut transport, true).await;
+        assert_eq!(outcome, Ok((200, None)));
+        assert_eq!(transport.sent, 2);
+        assert_eq!(
+            states,
+            vec![
+                RequestState::Retrying {
+                    attempt: 1,
+                    failure: ApiFailure::Network
+                },
+                RequestState::Idle
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_on_timeouts_after_two_retries() {
+        let mut transport = MockTransport::new(vec![
+            Err(ApiFailure::Timeout),
+            Err(ApiFailure::Timeout),
+            Err(ApiFailure::Timeout),
+        ]);
+        let (outcome, states) = run(&mut transport, true).await;
+        assert_eq!(outcome, Err(ApiFailure::Timeout));
+        assert_eq!(transport.sent, 3);
+        assert_eq!(
+            transport.slept,
+            vec![Duration::from_millis(300), Duration::from_millis(600)]
+        );
+        assert_eq!(
+            states.last(),
+            Some(&RequestState::Failed(ApiFailure::Timeout))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_retries_server_errors_and_returns_the_last_response() {
+        let mut transport =
+            MockTransport::new(vec![Ok((502, None)), Ok((500, None)), Ok((503, None))]);
+        let (outcome, states) = run(&mut transport, true).await;
+        assert_eq!(outcome, Ok((503, None)));
+        assert_eq!(transport.sent, 3);
+        assert_eq!(
+            states.last(),
+            Some(&RequestState::Failed(ApiFailure::Server { status: 503 }))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_waits_out_retry_after_when_rate_limited() {
+        let mut transport = MockTransport::new(vec![Ok((429, Some("2"))), Ok((200, None))]);
+        let (outcome, _) = run(&mut transport, true).await;
+        assert_eq!(outcome, Ok((200, None)));
+        assert_eq!(transport.slept, vec![Duration::from_secs(2)]);
+
+        let mut transport = MockTransport::new(vec![Ok((429, Some("3600")))]);
+        let (outcome, states) = run(&mut transport, true).await;
+        assert_eq!(outcome, Ok((429, Some("3600"))));
+        assert!(transport.slept.is_empty());
+        assert_eq!(
+            states,
+            vec![RequestState::Failed(ApiFailure::RateLimited {
+                retry_after: Some(Duration::from_secs(3600))
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_never_retries_client_errors() {
+        let mut transport = MockTransport::new(vec![Ok((404, None))]);
+        let (outcome, states) = run(&mut transport, true).await;
+        assert_eq!(outcome, Ok((404, None)));
+        assert_eq!(transport.sent, 1);
+        assert_eq!(
+            states,
+            vec![RequestState::Failed(ApiFailure::Client { status: 404 })]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_sends_mutations_without_a_key_once() {
+        let mut transport = MockTransport::new(vec![Err(ApiFailure::Network)]);
+        let (outcome, states) = run(&mut transport, may_retry("POST", false)).await;
+        assert_eq!(outcome, Err(ApiFailure::Network));
+        assert_eq!(transport.sent, 1);
+        assert_eq!(states, vec![RequestState::Failed(ApiFailure::Network)]);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::dashboard::DashboardGetResponse,
+};
+use leptos::{
+    server,
+    server_fn::codec::{GetUrl, Json},
+};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, AppState,
+            account_api::AccountApiState,
+            budget_api::{BudgetApiState, start_of_month},
+            extract_with_state, server_fn_uri, set_user_groups,
+            transaction_api::TransactionApiState,
+        },
+        authentication::authenticator::Authenticator,
+        model::budget::BudgetFilter,
+        rounding::RoundingPolicy,
+        schema::{
+            budget::BudgetStatusResponse,
+            dashboard::{BudgetSummaryResponse, DashboardResponse},
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use chrono::{Months, Utc};
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// How many of the latest transactions the dashboard shows.
+    pub const RECENT_TRANSACTIONS: i64 = 5;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = req.uri().to_string();
+        let path = path.trim_start_matches('/');
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/dashboard{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct DashboardApi;
+
+    impl Api for DashboardApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::GET, "/")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/dashboard",
+    tag = "Dashboard",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The balances, latest transactions and budgets of the user.", body = DashboardGetResponse),
+    ),
+))]
+#[server(
+    name = DashboardApiGet,
+    prefix = "/api",
+    endpoint = "dashboard",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<DashboardGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let account_state = extract_with_state::<AccountApiState, _>(&state).await?;
+    let transaction_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let budget_state = extract_with_state::<BudgetApiState, _>(&state).await?;
+
+    let now = Utc::now();
+    let last_month = now
+        .checked_sub_months(Months::new(1))
+        .ok_or(ApiError::ServerError)?;
+    let balances = account_state.service.net_balances(last_month).await?;
+
+    let recent_transactions = transaction_state
+        .service
+        .get_recent(RECENT_TRANSACTIONS)
+        .await?;
+
+    let start = start_of_month();
+    let end = start
+        .checked_add_months(Months::new(1))
+        .ok_or(ApiError::ServerError)?;
+    let rounding = RoundingPolicy::from_env();
+    let user_budgets = budget_state
+        .service
+        .get_list(0, None, BudgetFilter::default())
+        .await?;
+    let mut budgets = vec![];
+    for budget in user_budgets {
+        let (budget, totals) = budget_state
+            .service
+            .status(budget.id, start, end, rounding)
+            .await?;
+        budgets.push(BudgetSummaryResponse {
+            status: BudgetStatusResponse::new(&budget, start, end, totals, rounding),
+            name: budget.name,
+        });
+    }
+
+    Ok(DashboardResponse {
+        balances: balances.into_iter().map(|x| x.into()).collect(),
+        recent_transactions: recent_transactions.into_iter().map(|x| x.into()).collect(),
+        budgets,
+    })
+}
@@ -1,15 +1,38 @@
 use std::env::var;
 
-use axum::{Router, response::Html, routing::get};
+use axum::{
+    Router,
+    extract::{Request, State},
+    middleware::{Next, from_fn_with_state},
+    response::{Html, IntoResponse, Response},
+    routing::{any, get},
+};
+use http::{Method, StatusCode};
+use tower::ServiceBuilder;
+use tower_http::auth::AsyncRequireAuthorizationLayer;
+use tracing::error;
 use utoipa::{
     Modify, OpenApi,
-    openapi::security::{OpenIdConnect, SecurityScheme},
+    openapi::{
+        Deprecated,
+        security::{OpenIdConnect, SecurityScheme},
+    },
 };
+use utoipa_swagger_ui::SwaggerUi;
 use utoipauto::utoipauto;
 
 use crate::{
-    api::{Api, AppState},
-    authentication::authenticator::AUTH_WELL_KNOWN_URI,
+    api::{Api, ApiError, AppState, capabilities_api::RESOURCES, set_user_groups},
+    authentication::{
+        authenticated_token::AuthenticatedToken,
+        authenticator::{AUTH_WELL_KNOWN_URI, Authenticator},
+    },
+    authorization::{
+        PermissionConfig, PermissionSet,
+        actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+    },
+    config::{DocsMode, PagedResource},
+    schema::version::ApiVersion,
 };
 
 #[utoipauto]
@@ -17,44 +40,199 @@ use crate::{
 #[openapi(
     tags(
         (name = "Accounts", description = "Account endpoints"),
+        (name = "Admin", description = "System administration endpoints"),
+        (name = "Alert Rules", description = "Account balance alert endpoints"),
+        (name = "Announcements", description = "Announcement endpoints"),
+        (name = "API Keys", description = "API key endpoints"),
         (name = "Assets", description = "Asset endpoints"),
+        (name = "Attachments", description = "Transaction attachment endpoints"),
+        (name = "Budgets", description = "Budget endpoints"),
+        (name = "Capabilities", description = "What the caller can do and where"),
+        (name = "Categorization Rules", description = "Transaction categorization rule endpoints"),
+        (name = "Dashboard", description = "The overview of the caller's finances"),
+        (name = "Events", description = "The stream of the caller's events"),
+        (name = "Exchange Rates", description = "Exchange rate ingestion endpoints"),
+        (name = "Export Schedules", description = "Scheduled export endpoints"),
+        (name = "Import Profiles", description = "CSV import profile endpoints"),
         (name = "Institutions", description = "Institution endpoints"),
+        (name = "Journal Entries", description = "Double-entry journal endpoints"),
+        (name = "Me", description = "Endpoints about the caller"),
+        (name = "Passkeys", description = "Passkey and step-up endpoints"),
+        (name = "Proposals", description = "Institution and asset change proposal endpoints"),
+        (name = "Quick Entries", description = "Saved transaction endpoints"),
+        (name = "Reports", description = "Accounting report endpoints"),
+        (name = "Seed", description = "Development seeding endpoints"),
+        (name = "Sessions", description = "Signed in session endpoints"),
+        (name = "Sync", description = "Delta sync endpoints"),
         (name = "Transactions", description = "Transaction endpoints"),
-        (name = "Users", description = "User endpoints")
+        (name = "Transfers", description = "Endpoints moving assets between accounts"),
+        (name = "Users", description = "User endpoints"),
+        (name = "Version", description = "The versions of the API"),
+        (name = "Watchlist", description = "Watched asset price endpoints")
     ),
     paths(
         crate::api::account_api::get_list,
         crate::api::account_api::get,
         crate::api::account_api::create,
+        crate::api::account_api::from_template,
         crate::api::account_api::update,
         crate::api::account_api::delete,
+        crate::api::account_api::deletion_preview,
+        crate::api::account_api::sync,
+        crate::api::account_api::balances,
+        crate::api::account_api::merge,
+        crate::api::account_api::statement,
+        crate::api::account_api::get_statement,
+        crate::api::account_template_api::get_list,
+        crate::api::admin_api::stats,
+        crate::api::admin_api::features,
+        crate::api::admin_api::update_features,
+        crate::api::admin_api::simulate_policies,
+        crate::api::admin_api::policy_summary,
+        crate::api::admin_api::bulk_upsert_institutions,
+        crate::api::alert_rule_api::get_list,
+        crate::api::alert_rule_api::get,
+        crate::api::alert_rule_api::create,
+        crate::api::alert_rule_api::update,
+        crate::api::alert_rule_api::delete,
+        crate::api::alert_rule_api::get_events,
+        crate::api::announcement_api::get_active,
+        crate::api::announcement_api::get_list,
+        crate::api::announcement_api::get,
+        crate::api::announcement_api::create,
+        crate::api::announcement_api::update,
+        crate::api::announcement_api::delete,
+        crate::api::api_key_api::get_list,
+        crate::api::api_key_api::create,
+        crate::api::api_key_api::delete,
         crate::api::asset_api::get_list,
         crate::api::asset_api::get,
         crate::api::asset_api::create,
         crate::api::asset_api::update,
         crate::api::asset_api::delete,
+        crate::api::attachment_api::get_list,
+        crate::api::attachment_api::get,
+        crate::api::attachment_api::create,
+        crate::api::attachment_api::delete,
+        crate::api::attachment_api::get_extraction,
+        crate::api::attachment_api::get_content,
+        crate::api::budget_api::get_list,
+        crate::api::budget_api::get,
+        crate::api::budget_api::create,
+        crate::api::budget_api::delete,
+        crate::api::budget_api::get_status,
+        crate::api::capabilities_api::get,
+        crate::api::categorization_rule_api::get_list,
+        crate::api::categorization_rule_api::get,
+        crate::api::categorization_rule_api::create,
+        crate::api::categorization_rule_api::update,
+        crate::api::categorization_rule_api::delete,
+        crate::api::categorization_rule_api::apply,
+        crate::api::dashboard_api::get,
+        crate::api::event_api::stream,
+        crate::api::exchange_rate_api::backfill,
+        crate::api::export_schedule_api::get_list,
+        crate::api::export_schedule_api::get,
+        crate::api::export_schedule_api::create,
+        crate::api::export_schedule_api::update,
+        crate::api::export_schedule_api::delete,
+        crate::api::export_schedule_api::run,
+        crate::api::export_schedule_api::download,
+        crate::api::import_profile_api::get_list,
+        crate::api::import_profile_api::get,
+        crate::api::import_profile_api::create,
+        crate::api::import_profile_api::update,
+        crate::api::import_profile_api::delete,
         crate::api::institution_api::get_list,
+        crate::api::institution_api::get_permissions,
         crate::api::institution_api::get,
         crate::api::institution_api::create,
         crate::api::institution_api::update,
         crate::api::institution_api::delete,
+        crate::api::institution_api::get_rollup,
+        crate::api::journal_entry_api::get,
+        crate::api::journal_entry_api::create,
+        crate::api::journal_entry_api::delete,
+        crate::api::me_api::onboarding_state,
+        crate::api::me_api::get_preferences,
+        crate::api::me_api::update_preferences,
+        crate::api::passkey_api::get_list,
+        crate::api::passkey_api::register_start,
+        crate::api::passkey_api::register_finish,
+        crate::api::passkey_api::delete,
+        crate::api::passkey_api::step_up_start,
+        crate::api::passkey_api::step_up_finish,
+        crate::api::proposal_api::get_list,
+        crate::api::proposal_api::get,
+        crate::api::proposal_api::create,
+        crate::api::proposal_api::review,
+        crate::api::quick_entry_api::get_list,
+        crate::api::quick_entry_api::get,
+        crate::api::quick_entry_api::create,
+        crate::api::quick_entry_api::update,
+        crate::api::quick_entry_api::delete,
+        crate::api::quick_entry_api::execute,
+        crate::api::report_api::trial_balance,
+        crate::api::seed_api::create,
+        crate::api::session_api::get_list,
+        crate::api::session_api::delete,
+        crate::api::session_api::revoke_others,
+        crate::api::sync_api::get,
+        crate::api::transaction_api::get_list,
+        crate::api::transaction_api::get,
+        crate::api::transaction_api::create,
+        crate::api::transaction_api::update,
+        crate::api::transaction_api::delete,
+        crate::api::transaction_api::get_notes_html,
+        crate::api::transaction_api::get_history,
+        crate::api::transaction_api::revert,
+        crate::api::transaction_api::import,
+        crate::api::transaction_api::import_preview,
+        crate::api::transaction_api::get_uncategorized,
+        crate::api::transaction_api::categorize,
+        crate::api::transaction_api::recategorize,
+        crate::api::transaction_api::get_recategorization,
+        crate::api::transfer_api::create,
+        crate::api::user_api::get_list,
+        crate::api::user_api::get,
+        crate::api::user_api::create,
+        crate::api::user_api::update,
+        crate::api::user_api::delete,
+        crate::api::user_api::integrity,
+        crate::api::user_api::activity,
+        crate::api::version_api::get,
+        crate::api::watchlist_api::get_list,
+        crate::api::watchlist_api::get,
+        crate::api::watchlist_api::create,
+        crate::api::watchlist_api::update,
+        crate::api::watchlist_api::delete,
     ),
-    modifiers(&SecurityAddon)
+    modifiers(&SecurityAddon, &PageSizeAddon)
 )]
 pub struct DocsApi;
 
+/// The discovery document the spec points to when `AUTH_WELL_KNOWN_URI`
+/// isn't set, as when it is dumped for generating clients.
+pub const UNCONFIGURED_WELL_KNOWN_URI: &str =
+    "https://dex.invalid/.well-known/openid-configuration";
+
 pub struct SecurityAddon;
 
 impl Modify for SecurityAddon {
     fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // The spec is built without the rest of the server too, so a
+        // missing discovery document isn't the error it is to the
+        // authenticator, which is left to read it itself.
+        let well_known_uri = AUTH_WELL_KNOWN_URI
+            .get()
+            .cloned()
+            .or_else(|| var("AUTH_WELL_KNOWN_URI").ok())
+            .unwrap_or_else(|| UNCONFIGURED_WELL_KNOWN_URI.to_owned());
         if let Some(schema) = openapi.components.as_mut() {
             schema.add_security_scheme(
                 "OpenIDConnect",
                 SecurityScheme::OpenIdConnect(OpenIdConnect::with_description(
-                    AUTH_WELL_KNOWN_URI.get_or_init(|| {
-                        var("AUTH_WELL_KNOWN_URI")
-                            .expect("Failed to read `AUTH_WELL_KNOWN_URI` environment variable")
-                    }),
+                    &well_known_uri,
                     &"Authenticate with Dex".to_owned(),
                 )),
             );
@@ -62,13 +240,170 @@ impl Modify for SecurityAddon {
     }
 }
 
+/// Documents the configured page size of each resource on its list endpoint.
+pub struct PageSizeAddon;
+
+impl Modify for PageSizeAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for resource in PagedResource::ALL {
+            let page_size = resource.page_size();
+            let parameters = openapi
+                .paths
+                .paths
+                .get_mut(resource.path())
+                .and_then(|path| path.get.as_mut())
+                .and_then(|operation| operation.parameters.as_mut());
+            for parameter in parameters.into_iter().flatten() {
+                if parameter.name == "max_items" {
+                    parameter.description = Some(format!(
+                        "The maximum items to return, {} by default and at most {}",
+                        page_size.default, page_size.max
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Marks the operations the resource registry flags as deprecated.
+pub struct DeprecatedAddon;
+
+impl Modify for DeprecatedAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for (method, path) in RESOURCES
+            .iter()
+            .flat_map(|resource| resource.deprecated_endpoints())
+        {
+            let Some(item) = openapi.paths.paths.get_mut(&path) else {
+                continue;
+            };
+            let operation = match method {
+                Method::GET => item.get.as_mut(),
+                Method::POST => item.post.as_mut(),
+                Method::PUT => item.put.as_mut(),
+                Method::PATCH => item.patch.as_mut(),
+                Method::DELETE => item.delete.as_mut(),
+                _ => None,
+            };
+            if let Some(operation) = operation {
+                operation.deprecated = Some(Deprecated::True);
+            }
+        }
+    }
+}
+
+/// Moves the paths of the spec under the prefix of version 2, and
+/// documents how it differs from version 1.
+pub struct V2Addon;
+
+impl Modify for V2Addon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let v1 = ApiVersion::V1.prefix();
+        let paths = std::mem::take(&mut openapi.paths.paths);
+        openapi.paths.paths = paths
+            .into_iter()
+            .map(|(path, item)| {
+                let path = match path.strip_prefix(v1) {
+                    Some(rest) => format!("{}{rest}", ApiVersion::V2.prefix()),
+                    None => path,
+                };
+                (path, item)
+            })
+            .collect();
+        openapi.info.description = Some(
+            "Version 2 of the API. Quantities are always written as decimal strings of whole units of their asset, `numbers=string` is implied.".to_owned(),
+        );
+    }
+}
+
 impl DocsApi {
+    /// The spec of `version`, with its paths under the prefix of the
+    /// version.
+    pub fn openapi_for(version: ApiVersion) -> utoipa::openapi::OpenApi {
+        let mut openapi = Self::openapi();
+        match version {
+            ApiVersion::V1 => DeprecatedAddon.modify(&mut openapi),
+            ApiVersion::V2 => V2Addon.modify(&mut openapi),
+        }
+        openapi
+    }
+
     pub async fn oauth2_redirect() -> Html<&'static str> {
         Html(include_str!("../../static/oauth2-redirect.html"))
     }
+
+    async fn not_found() -> StatusCode {
+        StatusCode::NOT_FOUND
+    }
+
+    /// The Swagger UI at `/docs` and the specs of version 1 at
+    /// `/private/api.json` and version 2 at `/private/v2/api.json`, served
+    /// to whoever `mode` allows.
+    pub fn mount(mode: DocsMode, state: AppState) -> Router<AppState> {
+        let docs = Router::new()
+            .merge(
+                SwaggerUi::new("/docs")
+                    .url("/private/api.json", Self::openapi_for(ApiVersion::V1))
+                    .url("/private/v2/api.json", Self::openapi_for(ApiVersion::V2)),
+            )
+            .nest("/docs", Self::router(state.clone()));
+        match mode {
+            DocsMode::Open => docs,
+            DocsMode::Admin => docs.layer(
+                ServiceBuilder::new()
+                    .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                    .layer(from_fn_with_state(state.clone(), set_user_groups))
+                    .layer(from_fn_with_state(state, require_docs_reader)),
+            ),
+            // A 403 would tell callers the docs are there.
+            DocsMode::Disabled => Router::new()
+                .route("/docs", any(Self::not_found))
+                .route("/docs/{*path}", any(Self::not_found))
+                .route("/private/api.json", any(Self::not_found))
+                .route("/private/v2/api.json", any(Self::not_found)),
+        }
+    }
+}
+
+/// Lets through callers who may read every resource, which the full API
+/// surface is part of.
+async fn require_docs_reader(
+    State(state): State<AppState>,
+    token: AuthenticatedToken,
+    request: Request,
+    next: Next,
+) -> Response {
+    let permission_set = match PermissionSet::new(
+        "docs",
+        &state.enforcer,
+        &token,
+        PermissionConfig {
+            min_read_level: ReadLevel::ReadAll,
+            min_create_level: CreateLevel::CreateAll,
+            min_update_level: UpdateLevel::UpdateAll,
+            min_delete_level: DeleteLevel::DeleteAll,
+        },
+    ) {
+        Ok(permission_set) => permission_set,
+        Err(e) => {
+            error!("{e}");
+            return ApiError::ServerError.into_response();
+        }
+    };
+    if permission_set.read_level != ReadLevel::ReadAll {
+        return ApiError::Forbidden.into_response();
+    }
+    next.run(request).await
 }
 
 impl Api for DocsApi {
+    fn endpoints() -> Vec<(Method, &'static str)> {
+        vec![
+            (Method::GET, "/oauth2-redirect"),
+            (Method::GET, "/oauth2-redirect.html"),
+        ]
+    }
+
     fn router(_state: AppState) -> Router<AppState> {
         Router::new()
             .route("/oauth2-redirect", get(Self::oauth2_redirect))
@@ -1,18 +1,32 @@
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
 use leptos::server_fn::error::{FromServerFnError, ServerFnErrorErr};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
+use crate::api::messages::{Language, field_name};
+
 #[cfg(feature = "ssr")]
 mod ssr_imports {
-    pub use crate::{api::ApiJson, model::cursor_key::EncryptionError, service::ServiceError};
-    pub use axum::response::{IntoResponse, Response};
-    pub use http::{HeaderValue, StatusCode, header::CONTENT_TYPE};
+    pub use crate::{
+        api::{ApiJson, messages::translate, rate_limit::RateLimit, request_id::RequestId},
+        categorization::MAX_RULES,
+        model::cursor_key::EncryptionError,
+        service::ServiceError,
+    };
+    pub use axum::{
+        extract::rejection::{PathRejection, QueryRejection},
+        response::{IntoResponse, Response},
+    };
+    pub use http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{ACCEPT, CONTENT_TYPE},
+    };
     pub use leptos::{
-        prelude::{expect_context, provide_context},
+        prelude::{provide_context, use_context},
         server_fn::{codec::IntoRes, error::ServerFnError},
     };
     pub use leptos_axum::ResponseOptions;
-    pub use tracing::error;
+    pub use tracing::{error, warn};
     pub use utoipa::ToSchema;
 }
 
@@ -28,17 +42,184 @@ pub enum ApiError {
     #[error("Not found.")]
     NotFound,
     #[cfg(feature = "ssr")]
+    #[error("Method not allowed.")]
+    MethodNotAllowed,
+    #[cfg(feature = "ssr")]
     #[error("Error in service.")]
     Service(#[from] ServiceError),
+    /// The deletion of an account wasn't confirmed after previewing it.
+    #[cfg(feature = "ssr")]
+    #[error("The deletion was not confirmed.")]
+    DeletionUnconfirmed,
     #[cfg(feature = "ssr")]
     #[error("{0}")]
     Encryption(#[from] EncryptionError),
     #[error("Internal server error.")]
     ServerError,
+    /// A mistake of the client, made with [`ApiError::client`].
+    #[error("{1}")]
+    ClientError(ClientErrorCode, String),
+    /// A field of the request is not valid.
     #[error("{0}")]
-    ClientError(String),
+    InvalidField(FieldError),
     #[error("Forbidden")]
     Forbidden,
+    #[error("Step-up authentication required.")]
+    StepUpRequired,
+    #[error("Too many requests.")]
+    TooManyRequests,
+    /// The client is over its rate limit.
+    #[cfg(feature = "ssr")]
+    #[error("Too many requests.")]
+    RateLimited(RateLimit),
+    /// The request ran past its deadline.
+    #[cfg(feature = "ssr")]
+    #[error("The request timed out.")]
+    Timeout,
+}
+
+/// The longest message of an [`ApiError::ClientError`] in characters, past
+/// which it is cut short.
+pub const MAX_CLIENT_MESSAGE_CHARS: usize = 300;
+
+impl ApiError {
+    /// A mistake of the client, which it is told with `safe_message`.
+    ///
+    /// The message is one written for the client, never the text of an
+    /// internal error, which is logged instead. It may still quote what the
+    /// client sent, so it is cut short past [`MAX_CLIENT_MESSAGE_CHARS`]
+    /// and has its control characters replaced.
+    pub fn client(code: ClientErrorCode, safe_message: impl Into<String>) -> Self {
+        Self::ClientError(code, bounded_message(&safe_message.into()))
+    }
+}
+
+fn bounded_message(message: &str) -> String {
+    let mut chars = message
+        .chars()
+        .map(|x| if x.is_control() { ' ' } else { x })
+        .take(MAX_CLIENT_MESSAGE_CHARS + 1)
+        .collect::<Vec<_>>();
+    if chars.len() > MAX_CLIENT_MESSAGE_CHARS {
+        chars.truncate(MAX_CLIENT_MESSAGE_CHARS - 1);
+        chars.push('…');
+    }
+    chars.into_iter().collect()
+}
+
+/// What kind of mistake an [`ApiError::ClientError`] is, which is the
+/// [`code`](ApiErrorResponse::code) it is answered with. The codes are
+/// stable, so clients can tell the mistakes apart without reading the
+/// message:
+///
+/// | Code | Variant                                         |
+/// |------|-------------------------------------------------|
+/// | 4001 | [`InvalidRequest`](Self::InvalidRequest)        |
+/// | 4002 | [`InvalidId`](Self::InvalidId)                  |
+/// | 4003 | [`InvalidQuery`](Self::InvalidQuery)            |
+/// | 4004 | [`InvalidBody`](Self::InvalidBody)              |
+/// | 4005 | [`InvalidCursor`](Self::InvalidCursor)          |
+/// | 4006 | [`InvalidQuantity`](Self::InvalidQuantity)      |
+/// | 4007 | [`InvalidFile`](Self::InvalidFile)              |
+/// | 4008 | [`InvalidPasskey`](Self::InvalidPasskey)        |
+/// | 4009 | [`InvalidSignIn`](Self::InvalidSignIn)          |
+/// | 4010 | [`NotEnabled`](Self::NotEnabled)                |
+///
+/// An invalid field is also 4001, the code of client errors before they
+/// had kinds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ClientErrorCode {
+    /// The request can be read but asks for something that can't be done,
+    /// like a period that ends before it starts
+    #[default]
+    InvalidRequest,
+    /// A parameter of the path, like an id, doesn't parse
+    InvalidId,
+    /// The query string or one of its parameters can't be read
+    InvalidQuery,
+    /// The body can't be read as the request of the endpoint
+    InvalidBody,
+    /// The cursor of a page is malformed or of another list
+    InvalidCursor,
+    /// A quantity doesn't fit the decimals of its asset
+    InvalidQuantity,
+    /// An uploaded file or an imported document can't be used
+    InvalidFile,
+    /// A passkey challenge or credential doesn't check out
+    InvalidPasskey,
+    /// A sign in or the session presented doesn't check out
+    InvalidSignIn,
+    /// The request needs a feature the server isn't set up for
+    NotEnabled,
+}
+
+impl ClientErrorCode {
+    pub const ALL: [Self; 10] = [
+        Self::InvalidRequest,
+        Self::InvalidId,
+        Self::InvalidQuery,
+        Self::InvalidBody,
+        Self::InvalidCursor,
+        Self::InvalidQuantity,
+        Self::InvalidFile,
+        Self::InvalidPasskey,
+        Self::InvalidSignIn,
+        Self::NotEnabled,
+    ];
+
+    pub fn code(self) -> usize {
+        match self {
+            Self::InvalidRequest => 4001,
+            Self::InvalidId => 4002,
+            Self::InvalidQuery => 4003,
+            Self::InvalidBody => 4004,
+            Self::InvalidCursor => 4005,
+            Self::InvalidQuantity => 4006,
+            Self::InvalidFile => 4007,
+            Self::InvalidPasskey => 4008,
+            Self::InvalidSignIn => 4009,
+            Self::NotEnabled => 4010,
+        }
+    }
+
+    /// The kind of client error with `code`, if it is one.
+    pub fn from_code(code: usize) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.code() == code)
+    }
+}
+
+/// Why a field of a request is not valid, which is written in the
+/// language of the request.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FieldError {
+    #[error("The {0} must not contain control characters.")]
+    ControlCharacters(&'static str),
+    #[error("The {0} must be at most {1} characters.")]
+    TooLong(&'static str, usize),
+    #[error("The {0} must be at most {1} bytes.")]
+    TooLarge(&'static str, usize),
+}
+
+impl FieldError {
+    pub fn message(&self, language: Language) -> String {
+        match language {
+            Language::En => self.to_string(),
+            Language::Ko => match *self {
+                Self::ControlCharacters(field) => format!(
+                    "{}에는 제어 문자를 쓸 수 없습니다.",
+                    field_name(field, language)
+                ),
+                Self::TooLong(field, max) => format!(
+                    "{}은(는) 최대 {max}자까지 쓸 수 있습니다.",
+                    field_name(field, language)
+                ),
+                Self::TooLarge(field, max) => format!(
+                    "{}은(는) 최대 {max}바이트까지 쓸 수 있습니다.",
+                    field_name(field, language)
+                ),
+            },
+        }
+    }
 }
 
 #[cfg(not(feature = "ssr"))]
@@ -49,14 +230,26 @@ impl From<&ApiError> for ApiErrorResponse {
                 code: INTERNAL_SERVER_ERROR,
                 message: "Internal server error.".into(),
             },
-            ApiError::ClientError(message) => Self {
-                code: 4000,
+            ApiError::ClientError(code, message) => Self {
+                code: code.code(),
                 message: message.clone(),
             },
+            ApiError::InvalidField(field_error) => Self {
+                code: 4001,
+                message: field_error.to_string(),
+            },
             ApiError::Forbidden => Self {
                 code: 4030,
                 message: "Forbbiden.".into(),
             },
+            ApiError::StepUpRequired => Self {
+                code: STEP_UP_REQUIRED,
+                message: "step_up_required".into(),
+            },
+            ApiError::TooManyRequests => Self {
+                code: TOO_MANY_REQUESTS,
+                message: "Too many requests.".into(),
+            },
         }
     }
 }
@@ -70,13 +263,23 @@ impl Serialize for ApiError {
     }
 }
 
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
 impl FromServerFnError for ApiError {
     #[allow(unused_variables)]
     fn from_server_fn_error(value: ServerFnErrorErr) -> Self {
         match value {
-            ServerFnErrorErr::Request(e) => Self::ClientError(e),
-            ServerFnErrorErr::Deserialization(e) => Self::ClientError(e),
-            ServerFnErrorErr::Serialization(e) => Self::ClientError(e),
+            // The messages of the deserializer quote what was sent, so the
+            // client only gets the gist.
+            ServerFnErrorErr::Request(e)
+            | ServerFnErrorErr::Deserialization(e)
+            | ServerFnErrorErr::Serialization(e) => {
+                #[cfg(feature = "ssr")]
+                ssr::log_rejection(&e);
+                Self::client(
+                    ClientErrorCode::InvalidBody,
+                    "The request could not be read.",
+                )
+            }
             e => {
                 #[cfg(feature = "ssr")]
                 error!("{e}");
@@ -86,8 +289,85 @@ impl FromServerFnError for ApiError {
     }
 }
 
+/// The header holding the [`code`](ApiErrorResponse::code) of an error,
+/// which errors written as plain text carry nowhere else.
+pub const ERROR_CODE_HEADER: &str = "Error-Code";
+
+const STEP_UP_REQUIRED: usize = 4011;
+const TOO_MANY_REQUESTS: usize = 4290;
 const INTERNAL_SERVER_ERROR: usize = 5000;
 
+/// How errors are written, negotiated from the `Accept` header.
+///
+/// Errors are JSON [`ApiErrorResponse`]s unless the client asks for
+/// something JSON can't satisfy, in which case they are the bare message
+/// as plain text.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    #[default]
+    Json,
+    Text,
+}
+
+#[cfg(feature = "ssr")]
+tokio::task_local! {
+    /// The error format of the request being handled.
+    pub static ERROR_FORMAT: ErrorFormat;
+}
+
+#[cfg(feature = "ssr")]
+impl ErrorFormat {
+    /// The format of the request being handled, or JSON outside of one.
+    pub fn current() -> Self {
+        ERROR_FORMAT.try_with(|format| *format).unwrap_or_default()
+    }
+
+    /// Reads the format accepted by the `Accept` header. A request without
+    /// one accepts anything, so gets JSON.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut media_ranges = headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter(|media_range| {
+                // A zero quality explicitly refuses the media range.
+                !media_range
+                    .split(';')
+                    .skip(1)
+                    .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+                    .any(|quality| quality.parse::<f32>().is_ok_and(|q| q == 0.0))
+            })
+            .map(|media_range| {
+                media_range
+                    .split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_ascii_lowercase()
+            })
+            .peekable();
+        if media_ranges.peek().is_none() {
+            return Self::Json;
+        }
+        let accepts_json = media_ranges.any(|media_range| {
+            matches!(
+                media_range.as_str(),
+                "*/*" | "application/*" | "application/json"
+            ) || media_range.ends_with("+json")
+        });
+        if accepts_json { Self::Json } else { Self::Text }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
 #[cfg(feature = "ssr")]
 mod ssr {
     use super::*;
@@ -97,16 +377,35 @@ mod ssr {
             match self {
                 Self::JsonRejection => StatusCode::BAD_REQUEST,
                 Self::NotFound => StatusCode::NOT_FOUND,
+                Self::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+                Self::DeletionUnconfirmed => StatusCode::PRECONDITION_REQUIRED,
                 Self::Service(service_error) => match service_error {
-                    ServiceError::AlreadyRegistered => StatusCode::CONFLICT,
+                    ServiceError::AccountInJournalEntries
+                    | ServiceError::AccountsExist(_)
+                    | ServiceError::AlreadyRegistered
+                    | ServiceError::DifferentInstitutions
+                    | ServiceError::DoubleEntry
+                    | ServiceError::InstitutionInUse
+                    | ServiceError::JournalEntryLeg
+                    | ServiceError::ProposalReviewed => StatusCode::CONFLICT,
+                    ServiceError::CategorizationRuleLimit
+                    | ServiceError::InstitutionCycle
+                    | ServiceError::InstitutionTooDeep => StatusCode::UNPROCESSABLE_ENTITY,
+                    ServiceError::NoCurrentSession => StatusCode::BAD_REQUEST,
                     ServiceError::NotFound => StatusCode::NOT_FOUND,
-                    ServiceError::Unauthorized => StatusCode::FORBIDDEN,
+                    ServiceError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+                    ServiceError::EmailNotVerified | ServiceError::Unauthorized => {
+                        StatusCode::FORBIDDEN
+                    }
                     _ => StatusCode::INTERNAL_SERVER_ERROR,
                 },
                 Self::Encryption(_) => StatusCode::INTERNAL_SERVER_ERROR,
                 Self::ServerError => StatusCode::INTERNAL_SERVER_ERROR,
-                Self::ClientError(_) => StatusCode::BAD_REQUEST,
+                Self::ClientError(..) | Self::InvalidField(_) => StatusCode::BAD_REQUEST,
                 Self::Forbidden => StatusCode::FORBIDDEN,
+                Self::StepUpRequired => StatusCode::UNAUTHORIZED,
+                Self::TooManyRequests | Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+                Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
             }
         }
     }
@@ -114,9 +413,15 @@ mod ssr {
     impl From<ServerFnError> for ApiError {
         fn from(value: ServerFnError) -> Self {
             match value {
-                ServerFnError::Request(e) => Self::ClientError(e),
-                ServerFnError::Deserialization(e) => Self::ClientError(e),
-                ServerFnError::Serialization(e) => Self::ClientError(e),
+                ServerFnError::Request(e)
+                | ServerFnError::Deserialization(e)
+                | ServerFnError::Serialization(e) => {
+                    log_rejection(&e);
+                    Self::client(
+                        ClientErrorCode::InvalidBody,
+                        "The request could not be read.",
+                    )
+                }
                 e => {
                     error!("{e}");
                     Self::ServerError
@@ -131,17 +436,133 @@ mod ssr {
         }
     }
 
+    /// A path parameter that doesn't parse, like a malformed id, is a
+    /// mistake of the client.
+    impl From<PathRejection> for ApiError {
+        fn from(value: PathRejection) -> Self {
+            match value {
+                PathRejection::FailedToDeserializePathParams(_) => {
+                    Self::client(ClientErrorCode::InvalidId, "Invalid id format.")
+                }
+                e => {
+                    error!("{e}");
+                    Self::ServerError
+                }
+            }
+        }
+    }
+
+    /// A query string that doesn't deserialize. The message of the
+    /// rejection quotes the query and the internals it failed in, so the
+    /// client is only told which part of the request is wrong.
+    impl From<QueryRejection> for ApiError {
+        fn from(value: QueryRejection) -> Self {
+            log_rejection(&value.body_text());
+            Self::client(
+                ClientErrorCode::InvalidQuery,
+                "The query string is not valid.",
+            )
+        }
+    }
+
+    /// Logs why a request was refused, with the id the client can report
+    /// it by, which is all the client is told of it.
+    pub(super) fn log_rejection(detail: &str) {
+        let request_id = RequestId::current();
+        warn!(
+            request_id = request_id.as_ref().map_or("", RequestId::as_str),
+            "Refused the request: {detail}"
+        );
+    }
+
     const JSON_REJECTION: usize = 4000;
     const BAD_REQUEST: usize = 4001;
     const FORBIDDEN: usize = 4030;
+    const EMAIL_NOT_VERIFIED: usize = 4031;
     const NOT_FOUND: usize = 4040;
+    const METHOD_NOT_ALLOWED: usize = 4050;
     const ALREADY_REGISTERED: usize = 4090;
+    const IN_USE: usize = 4091;
+    const ALREADY_EXISTS: usize = 4092;
+    const DOUBLE_ENTRY: usize = 4093;
+    const DIFFERENT_INSTITUTIONS: usize = 4094;
+    const ALREADY_REVIEWED: usize = 4095;
+    const UNPROCESSABLE: usize = 4220;
+    const PRECONDITION_REQUIRED: usize = 4280;
+    const GATEWAY_TIMEOUT: usize = 5040;
+
+    /// The body of an [`ApiError::RateLimited`], which also tells the client
+    /// where it stands against its limit.
+    #[derive(Debug, Serialize)]
+    struct RateLimitedResponse {
+        #[serde(flatten)]
+        error: ApiErrorResponse,
+        #[serde(flatten)]
+        rate_limit: RateLimit,
+    }
+
+    /// Why one item of a batch failed.
+    #[derive(Debug, Serialize)]
+    struct ItemErrorResponse {
+        /// The name of the item
+        name: String,
+        message: String,
+    }
+
+    /// The body of an error failing a whole batch, with the items that
+    /// failed it.
+    #[derive(Debug, Serialize)]
+    struct BatchErrorResponse {
+        #[serde(flatten)]
+        error: ApiErrorResponse,
+        errors: Vec<ItemErrorResponse>,
+    }
 
     impl IntoResponse for ApiError {
         fn into_response(self) -> Response {
             let status = self.status();
             let message = ApiErrorResponse::from(&self);
-            (status, ApiJson(message)).into_response()
+            let code = HeaderValue::from(message.code);
+            let mut response = match (ErrorFormat::current(), &self) {
+                (ErrorFormat::Json, Self::Service(ServiceError::AccountsExist(names))) => (
+                    status,
+                    ApiJson(BatchErrorResponse {
+                        error: message,
+                        errors: names
+                            .iter()
+                            .map(|name| ItemErrorResponse {
+                                name: name.clone(),
+                                message: translate(
+                                    ALREADY_EXISTS,
+                                    "An account of this name is already at the institution.",
+                                    Language::current(),
+                                ),
+                            })
+                            .collect(),
+                    }),
+                )
+                    .into_response(),
+                (ErrorFormat::Json, Self::RateLimited(rate_limit)) => (
+                    status,
+                    ApiJson(RateLimitedResponse {
+                        error: message,
+                        rate_limit: *rate_limit,
+                    }),
+                )
+                    .into_response(),
+                (ErrorFormat::Json, _) => (status, ApiJson(message)).into_response(),
+                (ErrorFormat::Text, _) => (
+                    status,
+                    [(CONTENT_TYPE, ErrorFormat::Text.content_type())],
+                    message.message,
+                )
+                    .into_response(),
+            };
+            response.headers_mut().insert(ERROR_CODE_HEADER, code);
+            if let Self::RateLimited(rate_limit) = self {
+                response.extensions_mut().insert(rate_limit);
+            }
+            response
         }
     }
 
@@ -152,7 +573,8 @@ mod ssr {
     }
     impl From<&ApiError> for ApiErrorResponse {
         fn from(value: &ApiError) -> Self {
-            let response = match value {
+            let language = Language::current();
+            let mut response = match value {
                 ApiError::JsonRejection => Self {
                     code: JSON_REJECTION,
                     message: "Invalid JSON in request.".into(),
@@ -161,15 +583,82 @@ mod ssr {
                     code: NOT_FOUND,
                     message: "Not found.".into(),
                 },
+                ApiError::MethodNotAllowed => Self {
+                    code: METHOD_NOT_ALLOWED,
+                    message: "Method not allowed.".into(),
+                },
+                ApiError::DeletionUnconfirmed => Self {
+                    code: PRECONDITION_REQUIRED,
+                    message: "Deleting the account deletes its transactions, their attachments and its rules. Count them with GET /api/accounts/{id}/deletion-preview, then pass confirm=true to delete it.".into(),
+                },
                 ApiError::Service(service_error) => match service_error {
+                    ServiceError::AccountInJournalEntries => Self {
+                        code: DOUBLE_ENTRY,
+                        message: "The account has legs of journal entries, which would no longer balance. Delete those entries with DELETE /api/journal-entries/{id} first.".into(),
+                    },
+                    ServiceError::AccountsExist(_) => Self {
+                        code: ALREADY_EXISTS,
+                        message: "No accounts were created, some already exist.".into(),
+                    },
                     ServiceError::AlreadyRegistered => Self {
                         code: ALREADY_REGISTERED,
                         message: "User is already registered.".into(),
                     },
+                    ServiceError::CategorizationRuleLimit => Self {
+                        code: UNPROCESSABLE,
+                        message: match language {
+                            Language::En => format!(
+                                "A user may have at most {MAX_RULES} categorization rules."
+                            ),
+                            Language::Ko => format!(
+                                "분류 규칙은 사용자마다 최대 {MAX_RULES}개까지 만들 수 있습니다."
+                            ),
+                        },
+                    },
+                    ServiceError::DifferentInstitutions => Self {
+                        code: DIFFERENT_INSTITUTIONS,
+                        message: "The accounts are at different institutions, pass force=true to merge them anyway.".into(),
+                    },
+                    ServiceError::DoubleEntry => Self {
+                        code: DOUBLE_ENTRY,
+                        message: "Double-entry mode is on, create transactions as balanced journal entries with POST /api/journal-entries.".into(),
+                    },
+                    ServiceError::JournalEntryLeg => Self {
+                        code: DOUBLE_ENTRY,
+                        message: "The transaction is a leg of a journal entry, which would no longer balance. Delete the whole entry with DELETE /api/journal-entries/{id} instead.".into(),
+                    },
+                    ServiceError::EmailNotVerified => Self {
+                        code: EMAIL_NOT_VERIFIED,
+                        message: "The email address is not verified.".into(),
+                    },
+                    ServiceError::InstitutionCycle => Self {
+                        code: UNPROCESSABLE,
+                        message: "An institution cannot be its own ancestor.".into(),
+                    },
+                    ServiceError::InstitutionTooDeep => Self {
+                        code: UNPROCESSABLE,
+                        message: "The institution hierarchy is too deep.".into(),
+                    },
+                    ServiceError::InstitutionInUse => Self {
+                        code: IN_USE,
+                        message: "The institution still has accounts.".into(),
+                    },
+                    ServiceError::NoCurrentSession => Self {
+                        code: ClientErrorCode::InvalidSignIn.code(),
+                        message: "The request must present the refresh token of the current session.".into(),
+                    },
                     ServiceError::NotFound => Self {
                         code: NOT_FOUND,
                         message: "Not found.".into(),
                     },
+                    ServiceError::ProposalReviewed => Self {
+                        code: ALREADY_REVIEWED,
+                        message: "The proposal was already reviewed.".into(),
+                    },
+                    ServiceError::Timeout => Self {
+                        code: GATEWAY_TIMEOUT,
+                        message: "The request took too long and was cancelled.".into(),
+                    },
                     ServiceError::Unauthorized => Self {
                         code: FORBIDDEN,
                         message: "Forbidden.".into(),
@@ -186,14 +675,30 @@ mod ssr {
                     code: INTERNAL_SERVER_ERROR,
                     message: "Internal server error.".into(),
                 },
-                ApiError::ClientError(message) => Self {
-                    code: BAD_REQUEST,
+                ApiError::ClientError(code, message) => Self {
+                    code: code.code(),
                     message: message.clone(),
                 },
+                ApiError::InvalidField(field_error) => Self {
+                    code: BAD_REQUEST,
+                    message: field_error.message(language),
+                },
                 ApiError::Forbidden => Self {
                     code: FORBIDDEN,
                     message: "Forbidden".into(),
                 },
+                ApiError::StepUpRequired => Self {
+                    code: STEP_UP_REQUIRED,
+                    message: "step_up_required".into(),
+                },
+                ApiError::TooManyRequests | ApiError::RateLimited(_) => Self {
+                    code: TOO_MANY_REQUESTS,
+                    message: "Too many requests.".into(),
+                },
+                ApiError::Timeout => Self {
+                    code: GATEWAY_TIMEOUT,
+                    message: "The request took too long and was cancelled.".into(),
+                },
                 e => {
                     error!("{e}");
                     Self {
@@ -202,13 +707,17 @@ mod ssr {
                     }
                 }
             };
-            let response_opts = expect_context::<ResponseOptions>();
-            response_opts.set_status(value.status());
-            response_opts.insert_header(
-                CONTENT_TYPE,
-                HeaderValue::from_str("application/json").unwrap(),
-            );
-            provide_context(response_opts);
+            response.message = translate(response.code, &response.message, language);
+            // Errors raised outside of a server fn, like those of the
+            // fallbacks, have no response options to set.
+            if let Some(response_opts) = use_context::<ResponseOptions>() {
+                response_opts.set_status(value.status());
+                response_opts.insert_header(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static(ErrorFormat::current().content_type()),
+                );
+                provide_context(response_opts);
+            }
             response
         }
     }
@@ -219,10 +728,20 @@ impl<'de> Deserialize<'de> for ApiError {
     where
         D: Deserializer<'de>,
     {
-        let error_response = ApiErrorResponse::deserialize(deserializer)?;
-        match error_response.code {
-            INTERNAL_SERVER_ERROR => Ok(Self::ServerError),
-            _ => Ok(Self::ClientError(error_response.message)),
+        Ok(ApiErrorResponse::deserialize(deserializer)?.into())
+    }
+}
+
+impl From<ApiErrorResponse> for ApiError {
+    fn from(value: ApiErrorResponse) -> Self {
+        match value.code {
+            INTERNAL_SERVER_ERROR => Self::ServerError,
+            STEP_UP_REQUIRED => Self::StepUpRequired,
+            TOO_MANY_REQUESTS => Self::TooManyRequests,
+            code => Self::client(
+                ClientErrorCode::from_code(code).unwrap_or_default(),
+                value.message,
+            ),
         }
     }
 }
@@ -230,6 +749,66 @@ impl<'de> Deserialize<'de> for ApiError {
 #[derive(Debug, Deserialize, Serialize)]
 #[cfg_attr(feature = "ssr", derive(ToSchema))]
 pub struct ApiErrorResponse {
+    /// What went wrong, which stays the same in every language
     pub code: usize,
+    /// What went wrong, in the language of the request
     pub message: String,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_cuts_client_messages_short_at_the_bound() {
+        let message = "a".repeat(MAX_CLIENT_MESSAGE_CHARS);
+        let ApiError::ClientError(_, kept) =
+            ApiError::client(ClientErrorCode::InvalidRequest, message.clone())
+        else {
+            panic!("not a client error");
+        };
+        assert_eq!(kept, message);
+
+        // Characters rather than bytes are counted, so a cut never splits
+        // one.
+        let ApiError::ClientError(_, cut) = ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            "가".repeat(MAX_CLIENT_MESSAGE_CHARS + 1),
+        ) else {
+            panic!("not a client error");
+        };
+        assert_eq!(cut.chars().count(), MAX_CLIENT_MESSAGE_CHARS);
+        assert!(cut.ends_with("가…"));
+    }
+
+    #[test]
+    fn it_replaces_control_characters_in_client_messages() {
+        let error = ApiError::client(
+            ClientErrorCode::InvalidFile,
+            "No column `a\r\nSet-Cookie: x`.",
+        );
+        assert_eq!(error.to_string(), "No column `a  Set-Cookie: x`.");
+    }
+
+    #[test]
+    fn it_keeps_the_code_of_client_errors_through_the_client() {
+        for code in ClientErrorCode::ALL {
+            assert_eq!(ClientErrorCode::from_code(code.code()), Some(code));
+            let error = ApiError::from(ApiErrorResponse {
+                code: code.code(),
+                message: "Wrong.".to_owned(),
+            });
+            assert!(matches!(error, ApiError::ClientError(x, _) if x == code));
+        }
+        // Other codes of client errors, like conflicts, are told as they
+        // are, without a kind of their own.
+        let error = ApiError::from(ApiErrorResponse {
+            code: 4091,
+            message: "The institution still has accounts.".to_owned(),
+        });
+        assert!(matches!(
+            error,
+            ApiError::ClientError(ClientErrorCode::InvalidRequest, _)
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::api::{ApiError, client::ApiClient};
+use leptos::{
+    server,
+    server_fn::codec::{ByteStream, GetUrl, Streaming},
+};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, AppState, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            set_user_groups,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::{
+        HeaderValue, Method, Uri,
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+    };
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `events` the API resolves for a caller. Users only
+    /// stream their own events.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct EventApiResource;
+
+    impl ApiResource for EventApiResource {
+        const NAME: &'static str = "events";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = RegisteredUser;
+
+        fn service(
+            _: &AppState,
+            registered_user: RegisteredUser,
+            _: PermissionSet,
+        ) -> Self::Service {
+            registered_user
+        }
+    }
+
+    pub type EventApiState = ResourceContext<EventApiResource>;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = Uri::from_static("/api/events");
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+    }
+
+    pub struct EventApi;
+
+    impl Api for EventApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::GET, "/")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/events",
+    tag = "Events",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The events of the caller as they happen, such as `alert.fired` when an alert rule on the `sse` channel fires. A client that falls too far behind is sent `resync_required` and the stream ends, after which it should refetch what it shows before streaming again.", content(
+            (String = "text/event-stream"),
+        )),
+        (status = 429, description = "The caller already has as many streams open as they may."),
+    ),
+))]
+#[server(
+    name = EventApiStream,
+    prefix = "/api",
+    endpoint = "events",
+    input = GetUrl,
+    output = Streaming,
+    client = ApiClient,
+)]
+pub async fn stream() -> Result<ByteStream<ApiError>, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<EventApiState, _>(&state).await?;
+    if api_state.permission_set.read_level == ReadLevel::NoPermission {
+        return Err(ApiError::Forbidden);
+    }
+
+    let subscription = state.events.subscribe(api_state.service.id())?;
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.insert_header(CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+    response_opts.insert_header(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    provide_context(response_opts);
+    Ok(ByteStream::new(subscription.into_stream()))
+}
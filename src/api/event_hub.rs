@@ -0,0 +1,322 @@
+//! Fans the events of each user out to the streams they have open.
+//!
+//! Every stream reads from a queue of its own, holding at most
+//! [`QUEUE_CAPACITY`] events, so a client that reads slower than its events
+//! arrive can't make the server buffer without bound. A queue that overflows
+//! drops its oldest event for a [`RESYNC_REQUIRED`] event and is closed: the
+//! client reads what is left, then the stream ends and the client refetches
+//! everything rather than carry on having missed events.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use futures::{Stream, stream};
+use serde::Serialize;
+use tokio::{sync::Notify, time::timeout};
+
+use crate::{api::ApiError, model::user::UserId};
+
+/// How many events a stream holds for its client before it overflows.
+pub const QUEUE_CAPACITY: usize = 64;
+
+/// How many streams a user may have open at once.
+pub const MAX_STREAMS_PER_USER: usize = 5;
+
+/// The event a stream ends with when its client fell too far behind.
+pub const RESYNC_REQUIRED: &str = "resync_required";
+
+/// The event of an alert rule on the event stream channel firing.
+pub const ALERT_FIRED: &str = "alert.fired";
+
+/// The event of a watchlist entry on the event stream channel firing.
+pub const WATCHLIST_FIRED: &str = "watchlist.fired";
+
+/// How long a stream goes quiet before a comment is written to it, which is
+/// how a client that went away is noticed.
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// An event for the streams of a user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub name: String,
+    /// The JSON of the event
+    pub data: String,
+}
+
+impl Event {
+    pub fn new(name: &str, data: &impl Serialize) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            name: name.to_owned(),
+            data: serde_json::to_string(data)?,
+        })
+    }
+
+    pub fn resync_required() -> Self {
+        Self {
+            name: RESYNC_REQUIRED.to_owned(),
+            data: "{}".to_owned(),
+        }
+    }
+
+    /// The event as written to a `text/event-stream`.
+    pub fn to_sse(&self) -> String {
+        format!("event: {}\ndata: {}\n\n", self.name, self.data)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    events: VecDeque<Event>,
+    /// Whether the queue overflowed, and takes no more events
+    closed: bool,
+}
+
+#[derive(Debug, Default)]
+struct Subscriber {
+    queue: Mutex<Queue>,
+    notify: Notify,
+}
+
+impl Subscriber {
+    /// Queues `event`, returning whether it fit. A full queue drops its
+    /// oldest event to end with [`RESYNC_REQUIRED`] instead, and is closed.
+    fn push(&self, event: Event) -> bool {
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        if queue.closed {
+            return false;
+        }
+        let fits = queue.events.len() < QUEUE_CAPACITY;
+        if fits {
+            queue.events.push_back(event);
+        } else {
+            queue.events.pop_front();
+            queue.events.push_back(Event::resync_required());
+            queue.closed = true;
+        }
+        drop(queue);
+        self.notify.notify_one();
+        fits
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    subscribers: Mutex<HashMap<UserId, Vec<(u64, Arc<Subscriber>)>>>,
+    next_id: AtomicU64,
+    dropped_events: AtomicU64,
+}
+
+/// The streams open on this server, by user.
+#[derive(Debug, Clone, Default)]
+pub struct EventHub {
+    inner: Arc<Inner>,
+}
+
+impl EventHub {
+    /// Opens a stream of the events of `user_id`, unless they already have
+    /// [`MAX_STREAMS_PER_USER`] open.
+    pub fn subscribe(&self, user_id: UserId) -> Result<Subscription, ApiError> {
+        let mut subscribers = self
+            .inner
+            .subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let streams = subscribers.entry(user_id).or_default();
+        if streams.len() >= MAX_STREAMS_PER_USER {
+            return Err(ApiError::TooManyRequests);
+        }
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let subscriber = Arc::new(Subscriber::default());
+        streams.push((id, Arc::clone(&subscriber)));
+        Ok(Subscription {
+            hub: self.clone(),
+            user_id,
+            id,
+            subscriber,
+        })
+    }
+
+    /// Queues `event` on every stream of `user_id`. The streams it
+    /// overflows are let go of right away, their clients only have what is
+    /// left in them to read.
+    pub fn publish(&self, user_id: UserId, event: Event) {
+        let mut subscribers = self
+            .inner
+            .subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let Some(streams) = subscribers.get_mut(&user_id) else {
+            return;
+        };
+        streams.retain(|(_, subscriber)| {
+            let fits = subscriber.push(event.clone());
+            if !fits {
+                // The oldest event of the queue and the one that didn't fit.
+                self.inner.dropped_events.fetch_add(2, Ordering::Relaxed);
+            }
+            fits
+        });
+        if streams.is_empty() {
+            subscribers.remove(&user_id);
+        }
+    }
+
+    /// The streams open on this server.
+    pub fn active_streams(&self) -> usize {
+        self.inner
+            .subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// The events dropped from the queues of slow clients since the server
+    /// started.
+    pub fn dropped_events(&self) -> u64 {
+        self.inner.dropped_events.load(Ordering::Relaxed)
+    }
+
+    fn unsubscribe(&self, user_id: UserId, id: u64) {
+        let mut subscribers = self
+            .inner
+            .subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(streams) = subscribers.get_mut(&user_id) {
+            streams.retain(|(x, _)| *x != id);
+            if streams.is_empty() {
+                subscribers.remove(&user_id);
+            }
+        }
+    }
+}
+
+/// A stream of the events of a user, closed when dropped.
+#[derive(Debug)]
+pub struct Subscription {
+    hub: EventHub,
+    user_id: UserId,
+    id: u64,
+    subscriber: Arc<Subscriber>,
+}
+
+impl Subscription {
+    /// The next event, waiting for one if none is queued. `None` once the
+    /// queue overflowed and every event left in it was read.
+    pub async fn next(&mut self) -> Option<Event> {
+        loop {
+            {
+                let mut queue = self
+                    .subscriber
+                    .queue
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                if let Some(event) = queue.events.pop_front() {
+                    return Some(event);
+                }
+                if queue.closed {
+                    return None;
+                }
+            }
+            self.subscriber.notify.notified().await;
+        }
+    }
+
+    /// How many events are waiting to be read.
+    pub fn queued(&self) -> usize {
+        self.subscriber
+            .queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .events
+            .len()
+    }
+
+    /// The events as written to a `text/event-stream`, with a comment in
+    /// between those more than [`KEEP_ALIVE_INTERVAL`] apart.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Vec<u8>, ApiError>> + Send + 'static {
+        stream::unfold(self, |mut subscription| async move {
+            let chunk = match timeout(KEEP_ALIVE_INTERVAL, subscription.next()).await {
+                Ok(Some(event)) => event.to_sse(),
+                Ok(None) => return None,
+                Err(_) => ": keep-alive\n\n".to_owned(),
+            };
+            Some((Ok(chunk.into_bytes()), subscription))
+        })
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.hub.unsubscribe(self.user_id, self.id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use uuid::Uuid;
+
+    fn event(n: usize) -> Event {
+        Event::new("transaction.created", &n).unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_closes_the_stream_of_a_client_that_does_not_read() {
+        let hub = EventHub::default();
+        let user_id = UserId(Uuid::new_v4());
+        let mut slow = hub.subscribe(user_id).unwrap();
+        let mut reading = hub.subscribe(user_id).unwrap();
+
+        for n in 0..QUEUE_CAPACITY * 10 {
+            hub.publish(user_id, event(n));
+            assert_eq!(reading.next().await, Some(event(n)));
+            assert!(slow.queued() <= QUEUE_CAPACITY);
+        }
+        assert_eq!(slow.queued(), QUEUE_CAPACITY);
+        assert_eq!(hub.active_streams(), 1);
+        assert_eq!(hub.dropped_events(), 2);
+
+        // The client reads what it has queued, less the oldest, then the
+        // resync and the end of the stream.
+        for n in 1..QUEUE_CAPACITY {
+            assert_eq!(slow.next().await, Some(event(n)));
+        }
+        assert_eq!(slow.next().await, Some(Event::resync_required()));
+        assert_eq!(slow.next().await, None);
+
+        let chunk = Event::resync_required().to_sse();
+        assert_eq!(chunk, "event: resync_required\ndata: {}\n\n");
+    }
+
+    #[tokio::test]
+    async fn it_limits_the_streams_of_a_user() {
+        let hub = EventHub::default();
+        let user_id = UserId(Uuid::new_v4());
+        let mut streams = (0..MAX_STREAMS_PER_USER)
+            .map(|_| hub.subscribe(user_id).unwrap())
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            hub.subscribe(user_id),
+            Err(ApiError::TooManyRequests)
+        ));
+        assert!(hub.subscribe(UserId(Uuid::new_v4())).is_ok());
+
+        // Closing a stream makes room for another right away.
+        streams.pop();
+        assert_eq!(hub.active_streams(), MAX_STREAMS_PER_USER - 1);
+        assert!(hub.subscribe(user_id).is_ok());
+
+        drop(streams);
+        assert_eq!(hub.active_streams(), 0);
+        assert!(hub.inner.subscribers.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::exchange_rate::{BackfillRequest, BackfillResponse},
+};
+use leptos::{server, server_fn::codec::Json};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, AppState, ClientErrorCode, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::authenticator::Authenticator,
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "fx")]
+use crate::fx::FxSource;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `exchange_rates` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::ReadAll,
+        min_create_level: CreateLevel::CreateAll,
+        min_update_level: UpdateLevel::UpdateAll,
+        min_delete_level: DeleteLevel::DeleteAll,
+    };
+
+    pub struct ExchangeRateApiResource;
+
+    impl ApiResource for ExchangeRateApiResource {
+        const NAME: &'static str = "exchange_rates";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = ();
+        type Service = ();
+
+        fn service(_: &AppState, _: (), _: PermissionSet) {}
+    }
+
+    /// The permissions of the caller on exchange rates. Rates are shared by
+    /// every user, so ingesting them takes the `_all` levels.
+    pub type ExchangeRateApiState = ResourceContext<ExchangeRateApiResource>;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = req.uri().to_string();
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/exchange-rates{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct ExchangeRateApi;
+
+    impl Api for ExchangeRateApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::POST, "/backfill")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/backfill", axum::routing::post(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/exchange-rates/backfill",
+    tag = "Exchange Rates",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = BackfillRequest,
+    responses(
+        (status = 200, description = "What ingesting each date of the range did.", body = BackfillResponse),
+        (status = 400, description = "The range is invalid or ingestion is not configured."),
+        (status = 403, description = "The caller may not ingest rates."),
+    ),
+))]
+#[server(
+    name = ExchangeRateApiBackfill,
+    prefix = "/api",
+    endpoint = "exchange-rates/backfill",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn backfill(
+    #[server(flatten)] backfill_request: BackfillRequest,
+) -> Result<BackfillResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ExchangeRateApiState, _>(&state).await?;
+    if api_state.permission_set.create_level != CreateLevel::CreateAll {
+        return Err(ApiError::Forbidden);
+    }
+
+    #[cfg(feature = "fx")]
+    {
+        let source = FxSource::from_env()?;
+        let days = crate::fx::backfill(
+            &state.connection_pool,
+            &state.events,
+            &source,
+            backfill_request.from,
+            backfill_request.to,
+        )
+        .await?;
+        Ok(days.into())
+    }
+    #[cfg(not(feature = "fx"))]
+    {
+        let _ = backfill_request;
+        Err(ApiError::client(
+            ClientErrorCode::NotEnabled,
+            "Exchange rate ingestion is not enabled.",
+        ))
+    }
+}
@@ -0,0 +1,529 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::export_schedule::ExportScheduleId,
+    schema::export_schedule::{
+        CreateRequest, DeleteResponse, ExportScheduleCreateResponse, ExportScheduleGetListResponse,
+        ExportScheduleGetResponse, ExportScheduleRunResponse, ExportScheduleUpdateResponse,
+        UpdateRequest,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{ByteStream, DeleteUrl, GetUrl, Json, PatchJson, Streaming},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState,
+            byte_range::respond_with_range,
+            extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        export::{
+            self, Cadence, CsvOptions, ExportError, ExportJob, ObjectStoreDestination,
+            StorageDestination,
+        },
+        model::export_schedule::{
+            DestinationConfig, ExportRunStatus, ExportSchedule, ExportScheduleCreate,
+            ExportScheduleFilter,
+        },
+        resource::{deadline, user_preference_repository::UserPreferenceRepository},
+        schema::{
+            DateRange, ResolvedDateRange,
+            export_schedule::{ExportOptionsRequest, ExportScheduleResponse, GetListResponse},
+        },
+        service::{
+            ServiceError, export_schedule_service::ExportScheduleServiceMethods,
+            export_schedule_service_factory::ExportScheduleServiceFactory,
+        },
+        upload::content_disposition,
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Query, Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use chrono::Utc;
+    pub use http::{
+        HeaderValue, Method,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    };
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use std::{str::FromStr, sync::Arc};
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+    pub use tracing::error;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathExportScheduleId {
+    id: ExportScheduleId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `export_schedules` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct ExportScheduleApiResource;
+
+    impl ApiResource for ExportScheduleApiResource {
+        const NAME: &'static str = "export_schedules";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn ExportScheduleServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            ExportScheduleServiceFactory::build(
+                owner,
+                Arc::clone(&state.connection_pool),
+                permission_set,
+            )
+        }
+    }
+
+    pub type ExportScheduleApiState = ResourceContext<ExportScheduleApiResource>;
+
+    /// Checks the cadence parses and the destination can be built.
+    pub fn validate_export_schedule(
+        cadence: &str,
+        destination: &DestinationConfig,
+    ) -> Result<(), ApiError> {
+        Cadence::from_str(cadence)?;
+        ObjectStoreDestination::from_config(destination)?;
+        Ok(())
+    }
+
+    /// The response for a schedule, with its destination opened and
+    /// redacted.
+    pub fn export_schedule_response<T>(
+        export_schedule: ExportSchedule,
+    ) -> Result<ExportScheduleResponse<T>, ApiError> {
+        let destination = export::open(&export_schedule.destination_config)?;
+        Ok(ExportScheduleResponse::new(export_schedule, destination))
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            val if val.split('?').next().is_some_and(|x| x.ends_with("/run")) => "/run".to_string(),
+            val if val.ends_with("/download") => "/download".to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/export-schedules{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct ExportScheduleApi;
+
+    impl Api for ExportScheduleApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
+                (Method::POST, "/{id}/run"),
+                (Method::GET, "/{id}/download"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler)
+                        .patch(server_fn_handler)
+                        .delete(server_fn_handler),
+                )
+                .route("/{id}/run", axum::routing::post(server_fn_handler))
+                .route("/{id}/download", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/export-schedules",
+    tag = "Export Schedules",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The export schedules of the user.", body = ExportScheduleGetListResponse)
+    ),
+))]
+#[server(
+    name = ExportScheduleApiGetList,
+    prefix = "/api",
+    endpoint = "/export-schedules",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<ExportScheduleGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ExportScheduleApiState, _>(&state).await?;
+
+    let export_schedules = api_state
+        .service
+        .get_list(0, None, ExportScheduleFilter::default())
+        .await?;
+    Ok(GetListResponse {
+        export_schedules: export_schedules
+            .into_iter()
+            .map(export_schedule_response)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/export-schedules/{id}",
+    tag = "Export Schedules",
+    params(ExportScheduleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The export schedule.", body = ExportScheduleGetResponse),
+        (status = 404, description = "The export schedule was not found."),
+    ),
+))]
+#[server(
+    name = ExportScheduleApiGet,
+    prefix = "/api",
+    endpoint = "export-schedules/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<ExportScheduleGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ExportScheduleApiState, _>(&state).await?;
+    let PathExportScheduleId { id } = extract_path().await?;
+
+    let export_schedule = api_state.service.get(id).await?;
+    export_schedule_response(export_schedule)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/export-schedules",
+    tag = "Export Schedules",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The newly created export schedule.", body = ExportScheduleCreateResponse),
+        (status = 400, description = "The cadence or the destination is invalid."),
+    ),
+))]
+#[server(
+    name = ExportScheduleApiCreate,
+    prefix = "/api",
+    endpoint = "export-schedules",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<ExportScheduleCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ExportScheduleApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+
+    validate_export_schedule(&create_request.cadence, &create_request.destination)?;
+    let export_schedule = api_state
+        .service
+        .create(ExportScheduleCreate {
+            user_id: registered_user.id(),
+            format: create_request.format,
+            destination_kind: create_request.destination.kind(),
+            destination_config: export::seal(&create_request.destination)?,
+            cadence: create_request.cadence.trim().to_owned(),
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(ExportScheduleCreateResponse::status());
+    provide_context(response_opts);
+    export_schedule_response(export_schedule)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/export-schedules/{id}",
+    tag = "Export Schedules",
+    params(ExportScheduleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = UpdateRequest,
+    responses(
+        (status = 200, description = "The updated export schedule.", body = ExportScheduleUpdateResponse),
+        (status = 400, description = "The cadence or the destination is invalid."),
+        (status = 404, description = "The export schedule was not found."),
+    ),
+))]
+#[server(
+    name = ExportScheduleApiUpdate,
+    prefix = "/api",
+    endpoint = "export-schedules/",
+    input = PatchJson,
+    output = PatchJson,
+    client = ApiClient,
+)]
+pub async fn update(
+    #[server(flatten)] update_request: UpdateRequest,
+) -> Result<ExportScheduleUpdateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ExportScheduleApiState, _>(&state).await?;
+    let PathExportScheduleId { id } = extract_path().await?;
+
+    let mut export_schedule = api_state.service.get(id).await?;
+    let stored = export::open(&export_schedule.destination_config)?;
+    let destination = match update_request.destination {
+        Some(destination) => destination.keep_credentials_of(&stored),
+        None => stored,
+    };
+    if let Some(format) = update_request.format {
+        export_schedule.format = format;
+    }
+    if let Some(cadence) = update_request.cadence {
+        export_schedule.cadence = cadence.trim().to_owned();
+    }
+    validate_export_schedule(&export_schedule.cadence, &destination)?;
+    export_schedule.destination_kind = destination.kind();
+    export_schedule.destination_config = export::seal(&destination)?;
+
+    let export_schedule = api_state.service.update(id, export_schedule).await?;
+    export_schedule_response(export_schedule)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/export-schedules/{id}",
+    tag = "Export Schedules",
+    params(ExportScheduleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The export schedule was successfully deleted."),
+        (status = 404, description = "The export schedule was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = ExportScheduleApiDelete,
+    prefix = "/api",
+    endpoint = "export-schedules/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ExportScheduleApiState, _>(&state).await?;
+    let PathExportScheduleId { id } = extract_path().await?;
+
+    api_state.service.delete(id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/export-schedules/{id}/run",
+    tag = "Export Schedules",
+    params(ExportScheduleId, ExportOptionsRequest, DateRange),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The export schedule with the outcome of the run, which exports the transactions posted in the date range, or all of them.", body = ExportScheduleRunResponse),
+        (status = 400, description = "The schedule has no destination, an option is not supported, or the date range is invalid."),
+        (status = 404, description = "The export schedule was not found."),
+    ),
+))]
+#[server(
+    name = ExportScheduleApiRun,
+    prefix = "/api",
+    endpoint = "export-schedules/run",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn run() -> Result<ExportScheduleRunResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ExportScheduleApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+    let PathExportScheduleId { id } = extract_path().await?;
+    let Query(export_options) = extract_with_state::<Query<ExportOptionsRequest>, _>(&()).await?;
+    let Query(range) = extract_with_state::<Query<DateRange>, _>(&()).await?;
+    let posted = range.resolve(Utc::now())?;
+
+    let export_schedule = api_state.service.get(id).await?;
+    let destination =
+        ObjectStoreDestination::from_config(&export::open(&export_schedule.destination_config)?)?
+            .ok_or(ExportError::NoDestination)?;
+    let user_preference = UserPreferenceRepository
+        .get_by_user_id(
+            deadline::begin(&state.connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            registered_user.id(),
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    let csv_options = CsvOptions::resolve(
+        &export_options,
+        user_preference.and_then(|x| x.locale).as_deref(),
+    )?;
+    let export_schedule = ExportJob {
+        export_schedule,
+        csv_options: Some(csv_options),
+        posted,
+    }
+    .run(&state.connection_pool, &destination, Utc::now())
+    .await?;
+    export_schedule_response(export_schedule)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/export-schedules/{id}/download",
+    tag = "Export Schedules",
+    params(ExportScheduleId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The export of the last run of the schedule, read back from its destination.", content(
+            (String = "application/json"),
+            (String = "text/csv"),
+        )),
+        (status = 206, description = "The part of the export the `Range` header asks for, unless an `If-Range` no longer matches the `ETag` of the export."),
+        (status = 400, description = "The schedule has no destination."),
+        (status = 404, description = "The export schedule was not found, its last run failed, or the export is no longer at the destination."),
+        (status = 416, description = "The range starts past the end of the export."),
+    ),
+))]
+#[server(
+    name = ExportScheduleApiDownload,
+    prefix = "/api",
+    endpoint = "export-schedules/download",
+    input = GetUrl,
+    output = Streaming,
+    client = ApiClient,
+)]
+pub async fn download() -> Result<ByteStream<ApiError>, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ExportScheduleApiState, _>(&state).await?;
+    let PathExportScheduleId { id } = extract_path().await?;
+
+    let export_schedule = api_state.service.get(id).await?;
+    let (Some(ran_at), Some(ExportRunStatus::Succeeded)) =
+        (export_schedule.last_run_at, export_schedule.last_status)
+    else {
+        return Err(ApiError::NotFound);
+    };
+    let destination =
+        ObjectStoreDestination::from_config(&export::open(&export_schedule.destination_config)?)?
+            .ok_or(ExportError::NoDestination)?;
+    let content_type = export_schedule.format.content_type();
+    let name = ExportJob {
+        export_schedule,
+        csv_options: None,
+        posted: ResolvedDateRange::default(),
+    }
+    .file_name(ran_at);
+    let size = destination.size(&name).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.insert_header(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response_opts.insert_header(
+        CONTENT_DISPOSITION,
+        HeaderValue::try_from(content_disposition(&name)).map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?,
+    );
+    provide_context(response_opts);
+
+    // Every run uploads under a name of its own, which is never written
+    // again.
+    let etag = format!("\"{name}\"");
+    respond_with_range(&etag, size, |range| async move {
+        Ok(destination.read_range(&name, range).await?)
+    })
+    .await
+}
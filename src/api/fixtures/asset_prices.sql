@@ -0,0 +1,8 @@
+INSERT INTO asset_price (asset_id, quote_asset_id, priced_at, rate)
+SELECT base.id, quote.id, p.priced_at, p.rate
+FROM (
+        VALUES ('USD', 'KRW', TIMESTAMPTZ '2025-01-01 00:00:00+00', 1400.5),
+                ('USD', 'KRW', TIMESTAMPTZ '2025-01-10 00:00:00+00', 1450)
+) AS p (base_symbol, quote_symbol, priced_at, rate)
+JOIN asset base ON base.symbol = p.base_symbol
+JOIN asset quote ON quote.symbol = p.quote_symbol;
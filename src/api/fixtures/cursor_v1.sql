@@ -0,0 +1,4 @@
+-- The key the version 1 cursor of the cursor compatibility test was
+-- encrypted with, when cursors held only their offset.
+INSERT INTO cursor_key (id, key_data)
+VALUES (1000001, '\x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f'::BYTEA);
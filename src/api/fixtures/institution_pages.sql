@@ -0,0 +1,3 @@
+INSERT INTO institution (name)
+SELECT 'Institution ' || n
+FROM generate_series(1, 250) AS n;
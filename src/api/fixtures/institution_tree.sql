@@ -0,0 +1,5 @@
+INSERT INTO institution (id, name, parent_id)
+VALUES ('00000000-0000-0000-0000-0000000000a1', 'Shinhan Bank', NULL),
+        ('00000000-0000-0000-0000-0000000000b1', 'Shinhan Bank Seoul', '00000000-0000-0000-0000-0000000000a1'),
+        ('00000000-0000-0000-0000-0000000000b2', 'Shinhan Bank Busan', '00000000-0000-0000-0000-0000000000a1'),
+        ('00000000-0000-0000-0000-0000000000c1', 'Shinhan Bank Gangnam', '00000000-0000-0000-0000-0000000000b1');
@@ -0,0 +1,25 @@
+INSERT INTO "user" (id, name, email, iss, sub)
+VALUES ('00000000-0000-0000-0000-00000000000a', 'Alice', 'alice@example.com', 'fixture', 'alice'),
+        ('00000000-0000-0000-0000-00000000000b', 'Bob', 'bob@example.com', 'fixture', 'bob');
+
+INSERT INTO account (id, user_id, institution_id, name)
+SELECT a.id, a.user_id, i.id, a.name
+FROM (
+        VALUES ('00000000-0000-0000-0000-0000000000a1'::UUID, '00000000-0000-0000-0000-00000000000a'::UUID, 'Checking'),
+                ('00000000-0000-0000-0000-0000000000a2'::UUID, '00000000-0000-0000-0000-00000000000a'::UUID, 'Savings'),
+                ('00000000-0000-0000-0000-0000000000b1'::UUID, '00000000-0000-0000-0000-00000000000b'::UUID, 'Checking')
+) AS a (id, user_id, name)
+CROSS JOIN (SELECT id FROM institution WHERE name = 'Toss Bank') AS i;
+
+INSERT INTO "transaction" (account_id, asset_id, description, posted_at, quantity)
+SELECT t.account_id, asset.id, t.description, t.posted_at, t.quantity
+FROM (
+        VALUES ('00000000-0000-0000-0000-0000000000a1'::UUID, 'USD', 'Coffee', TIMESTAMPTZ '2025-01-01 08:00:00+00', -5),
+                ('00000000-0000-0000-0000-0000000000a1'::UUID, 'USD', 'Salary', TIMESTAMPTZ '2025-01-02 09:00:00+00', 3000),
+                ('00000000-0000-0000-0000-0000000000a1'::UUID, 'KRW', 'Iced coffee', TIMESTAMPTZ '2025-01-03 10:00:00+00', -4500),
+                ('00000000-0000-0000-0000-0000000000a2'::UUID, 'USD', 'Interest', TIMESTAMPTZ '2025-01-04 00:00:00+00', 12),
+                ('00000000-0000-0000-0000-0000000000a2'::UUID, 'USD', NULL, TIMESTAMPTZ '2025-01-05 00:00:00+00', 100),
+                ('00000000-0000-0000-0000-0000000000b1'::UUID, 'USD', 'Coffee', TIMESTAMPTZ '2025-01-01 08:30:00+00', -6),
+                ('00000000-0000-0000-0000-0000000000b1'::UUID, 'USD', 'Rent', TIMESTAMPTZ '2025-01-06 00:00:00+00', -1200)
+) AS t (account_id, symbol, description, posted_at, quantity)
+JOIN asset ON asset.symbol = t.symbol;
@@ -0,0 +1,363 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::import_profile::ImportProfileId,
+    schema::import_profile::{
+        CreateRequest, DeleteResponse, ImportProfileCreateResponse, ImportProfileGetListResponse,
+        ImportProfileGetResponse, ImportProfileUpdateResponse, UpdateRequest,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json, PatchJson},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        model::import_profile::{ImportProfileCreate, ImportProfileFilter},
+        service::{
+            import_profile_service::ImportProfileServiceMethods,
+            import_profile_service_factory::ImportProfileServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathImportProfileId {
+    id: ImportProfileId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `import_profiles` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct ImportProfileApiResource;
+
+    impl ApiResource for ImportProfileApiResource {
+        const NAME: &'static str = "import_profiles";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn ImportProfileServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            ImportProfileServiceFactory::build(
+                owner,
+                Arc::clone(&state.connection_pool),
+                permission_set,
+            )
+        }
+    }
+
+    pub type ImportProfileApiState = ResourceContext<ImportProfileApiResource>;
+
+    /// Checks the name is free among the profiles of the user, but for the
+    /// profile of `id`.
+    pub async fn validate_name(
+        api_state: &ImportProfileApiState,
+        id: Option<ImportProfileId>,
+        name: &str,
+    ) -> Result<(), ApiError> {
+        let existing = api_state
+            .service
+            .get_list(
+                0,
+                Some(1),
+                ImportProfileFilter {
+                    name: name.to_owned().into(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        if existing.iter().any(|x| Some(x.id) != id) {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                format!("An import profile named `{name}` already exists."),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/import-profiles{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct ImportProfileApi;
+
+    impl Api for ImportProfileApi {
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler)
+                        .patch(server_fn_handler)
+                        .delete(server_fn_handler),
+                )
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/import-profiles",
+    tag = "Import Profiles",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The import profiles of the user.", body = ImportProfileGetListResponse)
+    ),
+))]
+#[server(
+    name = ImportProfileApiGetList,
+    prefix = "/api",
+    endpoint = "/import-profiles",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<ImportProfileGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ImportProfileApiState, _>(&state).await?;
+
+    let import_profiles = api_state
+        .service
+        .get_list(0, None, ImportProfileFilter::default())
+        .await?;
+    Ok(import_profiles.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/import-profiles/{id}",
+    tag = "Import Profiles",
+    params(ImportProfileId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The import profile.", body = ImportProfileGetResponse),
+        (status = 404, description = "The import profile was not found."),
+    ),
+))]
+#[server(
+    name = ImportProfileApiGet,
+    prefix = "/api",
+    endpoint = "import-profiles/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<ImportProfileGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ImportProfileApiState, _>(&state).await?;
+    let PathImportProfileId { id } = extract_path().await?;
+
+    let import_profile = api_state.service.get(id).await?;
+    Ok(import_profile.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/import-profiles",
+    tag = "Import Profiles",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The newly created import profile.", body = ImportProfileCreateResponse),
+        (status = 400, description = "The mapping is invalid or the name is taken."),
+        (status = 404, description = "The default account was not found."),
+    ),
+))]
+#[server(
+    name = ImportProfileApiCreate,
+    prefix = "/api",
+    endpoint = "import-profiles",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<ImportProfileCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ImportProfileApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+    create_request.mapping.validate()?;
+    validate_name(&api_state, None, &create_request.name).await?;
+
+    let import_profile = api_state
+        .service
+        .create(ImportProfileCreate {
+            user_id: registered_user.id(),
+            name: create_request.name,
+            mapping: create_request.mapping,
+            default_account_id: create_request.default_account_id,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(ImportProfileCreateResponse::status());
+    provide_context(response_opts);
+    Ok(import_profile.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/import-profiles/{id}",
+    tag = "Import Profiles",
+    params(ImportProfileId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = UpdateRequest,
+    responses(
+        (status = 200, description = "The updated import profile.", body = ImportProfileUpdateResponse),
+        (status = 400, description = "The mapping is invalid or the name is taken."),
+        (status = 404, description = "The import profile or default account was not found."),
+    ),
+))]
+#[server(
+    name = ImportProfileApiUpdate,
+    prefix = "/api",
+    endpoint = "import-profiles/",
+    input = PatchJson,
+    output = PatchJson,
+    client = ApiClient,
+)]
+pub async fn update(
+    #[server(flatten)] update_request: UpdateRequest,
+) -> Result<ImportProfileUpdateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ImportProfileApiState, _>(&state).await?;
+    let PathImportProfileId { id } = extract_path().await?;
+
+    let mut import_profile = api_state.service.get(id).await?;
+    let mapping = update_request.apply(import_profile.mapping());
+    mapping.validate()?;
+    if let Some(name) = update_request.name {
+        import_profile.name = name;
+    }
+    if let Some(default_account_id) = update_request.default_account_id {
+        import_profile.default_account_id = Some(default_account_id);
+    }
+    validate_name(&api_state, Some(id), &import_profile.name).await?;
+    import_profile.set_mapping(mapping);
+
+    let import_profile = api_state.service.update(id, import_profile).await?;
+    Ok(import_profile.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/import-profiles/{id}",
+    tag = "Import Profiles",
+    params(ImportProfileId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The import profile was successfully deleted."),
+        (status = 404, description = "The import profile was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = ImportProfileApiDelete,
+    prefix = "/api",
+    endpoint = "import-profiles/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ImportProfileApiState, _>(&state).await?;
+    let PathImportProfileId { id } = extract_path().await?;
+
+    api_state.service.delete(id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
@@ -4,9 +4,9 @@ use crate::{
     schema::{
         Pagination,
         institution::{
-            CreateRequest, DeleteResponse, GetListRequest, InstitutionCreateResponse,
+            CreateRequest, DeleteResponse, GetListRequest, GetRequest, InstitutionCreateResponse,
             InstitutionGetListResponse, InstitutionGetResponse, InstitutionUpdateResponse,
-            UpdateRequest,
+            PermissionsResponse, RollupResponse, UpdateRequest,
         },
     },
 };
@@ -19,28 +19,41 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
-        api::{Api, ApiErrorResponse, AppState, extract_with_state, set_user_groups},
-        authentication::{authenticated_token::AuthenticatedToken, authenticator::Authenticator},
+        api::{
+            Api, ApiErrorResponse, AppState, extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{
+            api_key::authenticate_api_key, authenticated_token::AuthenticatedToken,
+            authenticator::Authenticator, registered_user::RegisteredUser,
+        },
         authorization::{
             PermissionConfig, PermissionSet,
             actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
         },
-        model::cursor_key::CursorKey,
+        config::PagedResource,
+        model::{cursor_key::CursorKey, institution::InstitutionFilter},
+        schema::text::INSTITUTION_NAME,
         service::{
+            account_service::{AccountServiceMethods, AccountServiceRollup},
+            account_service_factory::AccountServiceFactory,
             institution_service::InstitutionServiceMethods,
             institution_service_factory::InstitutionServiceFactory,
         },
     };
     pub use axum::{
-        RequestPartsExt, Router,
+        Router,
         body::Body,
-        extract::{FromRequestParts, Path, Request, State},
+        extract::{Request, State},
         middleware::from_fn_with_state,
         response::IntoResponse,
     };
-    pub use http::request::Parts;
+    pub use http::Method;
     pub use leptos::prelude::*;
-    pub use leptos_axum::{extract, generate_request_and_parts, handle_server_fns_with_context};
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
     pub use std::sync::Arc;
     pub use tower::ServiceBuilder;
     pub use tower_http::auth::AsyncRequireAuthorizationLayer;
@@ -58,50 +71,63 @@ pub struct PathInstitutionId {
 #[cfg(feature = "ssr")]
 mod ssr {
     use super::*;
-    pub struct InstitutionApiState {
-        pub authenticated_token: AuthenticatedToken,
-        pub institution_service: Box<dyn InstitutionServiceMethods + Send>,
-    }
 
-    impl FromRequestParts<AppState> for InstitutionApiState {
-        type Rejection = ApiError;
+    /// The levels of `institutions` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
 
-        async fn from_request_parts(
-            parts: &mut Parts,
-            state: &AppState,
-        ) -> Result<Self, Self::Rejection> {
-            let authenticated_token = parts
-                .extract_with_state::<AuthenticatedToken, _>(state)
-                .await?;
+    pub struct InstitutionApiResource;
 
-            let permission_set = PermissionSet::new(
-                "institutions",
-                &state.enforcer,
-                &authenticated_token,
-                PermissionConfig {
-                    min_read_level: ReadLevel::Read,
-                    min_create_level: CreateLevel::Create,
-                    min_update_level: UpdateLevel::Update,
-                    min_delete_level: DeleteLevel::Delete,
-                },
-            )
-            .map_err(|e| {
-                error!("{e}");
-                ApiError::ServerError
-            })?;
+    impl ApiResource for InstitutionApiResource {
+        const NAME: &'static str = "institutions";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = ();
+        type Service = Box<dyn InstitutionServiceMethods + Send>;
 
-            let institution_service = InstitutionServiceFactory::build(
+        fn service(state: &AppState, _: (), permission_set: PermissionSet) -> Self::Service {
+            InstitutionServiceFactory::build(
                 Arc::clone(&state.connection_pool),
                 permission_set,
-            );
-
-            Ok(Self {
-                authenticated_token,
-                institution_service,
-            })
+                state.service_caches.institutions.clone(),
+            )
         }
     }
 
+    pub type InstitutionApiState = ResourceContext<InstitutionApiResource>;
+
+    /// The account service of the caller, for the totals of their accounts
+    /// at an institution.
+    pub fn account_service(
+        state: &AppState,
+        authenticated_token: &AuthenticatedToken,
+        registered_user: RegisteredUser,
+    ) -> Result<Box<dyn AccountServiceMethods + Send>, ApiError> {
+        let account_permission_set = PermissionSet::new(
+            "accounts",
+            &state.enforcer,
+            authenticated_token,
+            PermissionConfig {
+                min_read_level: ReadLevel::Read,
+                min_create_level: CreateLevel::Create,
+                min_update_level: UpdateLevel::Update,
+                min_delete_level: DeleteLevel::Delete,
+            },
+        )
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?;
+        Ok(AccountServiceFactory::build(
+            registered_user,
+            Arc::clone(&state.connection_pool),
+            account_permission_set,
+        ))
+    }
+
     async fn server_fn_handler(
         State(state): State<AppState>,
         req: Request<Body>,
@@ -109,10 +135,18 @@ mod ssr {
         let path = match req.uri().to_string() {
             val if val == "/" => "".to_string(),
             val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
-            _ => "/".to_string(),
+            val if val.ends_with("/rollup") => "/rollup".to_string(),
+            val if val == "/permissions" => "/permissions".to_string(),
+            val => match val.split_once('?') {
+                Some((_, query)) => format!("/?{query}"),
+                None => "/".to_string(),
+            },
         };
         let (mut req, parts) = generate_request_and_parts(req);
-        *req.uri_mut() = format!("/api/institutions{path}").parse().unwrap();
+        *req.uri_mut() = match server_fn_uri(format!("/api/institutions{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
         handle_server_fns_with_context(
             {
                 let app_state = state.clone();
@@ -124,25 +158,41 @@ mod ssr {
             req,
         )
         .await
+        .into_response()
     }
 
     pub struct InstitutionApi;
 
     impl Api for InstitutionApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/permissions"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
+                (Method::GET, "/{id}/rollup"),
+            ]
+        }
+
         fn router(state: AppState) -> Router<AppState> {
             Router::new()
                 .route(
                     "/",
                     axum::routing::get(server_fn_handler).post(server_fn_handler),
                 )
+                .route("/permissions", axum::routing::get(server_fn_handler))
                 .route(
                     "/{id}",
                     axum::routing::get(server_fn_handler)
                         .patch(server_fn_handler)
                         .delete(server_fn_handler),
                 )
+                .route("/{id}/rollup", axum::routing::get(server_fn_handler))
                 .layer(
                     ServiceBuilder::new()
+                        .layer(from_fn_with_state(state.clone(), authenticate_api_key))
                         .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
                         .layer(from_fn_with_state(state.clone(), set_user_groups)),
                 )
@@ -185,23 +235,59 @@ pub async fn get_list(
 ) -> Result<InstitutionGetListResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<InstitutionApiState, _>(&state).await?;
-    let pagination = extract_with_state::<Pagination, _>(&state).await?;
+    let pagination = extract_with_state::<Pagination, _>(&state)
+        .await?
+        .for_resource(PagedResource::Institutions)?;
     let cursor_key = extract_with_state::<CursorKey, _>(&state).await?;
 
+    let include_counts = filter.include_counts.unwrap_or_default();
     let offset = pagination.offset();
     let institutions = api_state
-        .institution_service
-        .get_list(offset, pagination.max_items, filter.into())
+        .service
+        .get_list(offset, pagination.limit().into(), filter.into())
         .await?;
+    let institution_ids = institutions.iter().map(|x| x.id).collect::<Vec<_>>();
     let response = InstitutionGetListResponse::new(institutions, &pagination, &cursor_key)?;
-    Ok(response)
+    if !include_counts {
+        return Ok(response);
+    }
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+    let account_counts = account_service(&state, &api_state.authenticated_token, registered_user)?
+        .account_counts(institution_ids)
+        .await?;
+    Ok(response.with_account_counts(account_counts))
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/institutions/permissions",
+    tag = "Institutions",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "What the caller may do with institutions.", body = PermissionsResponse)
+    ),
+))]
+#[server(
+    name = InstitutionApiGetPermissions,
+    prefix = "/api",
+    endpoint = "institutions/permissions",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_permissions() -> Result<PermissionsResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<InstitutionApiState, _>(&state).await?;
+    Ok(api_state.permission_set.into())
 }
 
 #[cfg_attr(feature = "ssr", utoipa::path(
     get,
     path = "/api/institutions/{id}",
     tag = "Institutions",
-    params(InstitutionId),
+    params(InstitutionId, GetRequest),
     security(
         ("OpenIDConnect" = ["groups", "email"])
     ),
@@ -218,14 +304,32 @@ pub async fn get_list(
     output = Json,
     client = ApiClient,
 )]
-pub async fn get() -> Result<InstitutionGetResponse, ApiError> {
+pub async fn get(
+    #[server(flatten)]
+    #[server(default)]
+    get_request: GetRequest,
+) -> Result<InstitutionGetResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<InstitutionApiState, _>(&state).await?;
-    let Path(PathInstitutionId { id }) = extract().await?;
+    let PathInstitutionId { id } = extract_path().await?;
 
-    let institution = api_state.institution_service.get(id).await?;
-    let response = institution.into();
-    Ok(response)
+    let institution = api_state.service.get(id).await?;
+    let response = InstitutionGetResponse::from(institution);
+    if !get_request.expand_children() {
+        return Ok(response);
+    }
+    let children = api_state
+        .service
+        .get_list(
+            0,
+            None,
+            InstitutionFilter {
+                parent_id: id.into(),
+                ..Default::default()
+            },
+        )
+        .await?;
+    Ok(response.with_children(children))
 }
 
 #[cfg_attr(feature = "ssr", utoipa::path(
@@ -254,10 +358,14 @@ pub async fn create(
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<InstitutionApiState, _>(&state).await?;
 
-    let institution = api_state
-        .institution_service
-        .create(create_request.into())
-        .await?;
+    let create_request = CreateRequest {
+        name: INSTITUTION_NAME.sanitize(&create_request.name)?,
+        ..create_request
+    };
+    let institution = api_state.service.create(create_request.into()).await?;
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(InstitutionCreateResponse::status());
+    provide_context(response_opts);
     Ok(institution.into())
 }
 
@@ -288,12 +396,13 @@ pub async fn update(
 ) -> Result<InstitutionUpdateResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<InstitutionApiState, _>(&state).await?;
-    let Path(PathInstitutionId { id }) = extract().await?;
+    let PathInstitutionId { id } = extract_path().await?;
 
-    let institution = api_state
-        .institution_service
-        .update(id, update_request.into())
-        .await?;
+    let update_request = UpdateRequest {
+        name: INSTITUTION_NAME.sanitize_option(update_request.name)?,
+        ..update_request
+    };
+    let institution = api_state.service.update(id, update_request.into()).await?;
     Ok(institution.into())
 }
 
@@ -311,6 +420,10 @@ pub async fn update(
             code: 4040,
             message: "Not found.".to_string()
         })),
+        (status = 409, description = "The institution still has accounts.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4091,
+            message: "The institution still has accounts.".to_string()
+        })),
     ),
 ))]
 #[server(
@@ -324,7 +437,43 @@ pub async fn delete() -> Result<DeleteResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<InstitutionApiState, _>(&state).await?;
 
-    let Path(PathInstitutionId { id }) = extract().await?;
-    api_state.institution_service.delete(id).await?;
+    let PathInstitutionId { id } = extract_path().await?;
+    api_state.service.delete(id).await?;
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
     Ok(DeleteResponse {})
 }
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/institutions/{id}/rollup",
+    tag = "Institutions",
+    params(InstitutionId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The totals of the caller's accounts across the institution and its descendants.", body = RollupResponse),
+        (status = 404, description = "The institution was not found."),
+    )
+))]
+#[server(
+    name = InstitutionApiGetRollup,
+    prefix = "/api",
+    endpoint = "institutions/rollup",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_rollup() -> Result<RollupResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<InstitutionApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+    let PathInstitutionId { id } = extract_path().await?;
+    api_state.service.get(id).await?;
+
+    let account_service = account_service(&state, &api_state.authenticated_token, registered_user)?;
+    let rollup = account_service.rollup(id).await?;
+    Ok(RollupResponse::new(id, rollup))
+}
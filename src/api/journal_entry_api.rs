@@ -0,0 +1,270 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::journal_entry::JournalEntryId,
+    schema::journal_entry::{
+        CreateRequest, DeleteResponse, JournalEntryCreateResponse, JournalEntryGetResponse,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, asset_api::asset_scale, extract_path,
+            extract_with_state, server_fn_uri, set_user_groups,
+            transaction_api::TransactionApiState,
+        },
+        authentication::{api_key::authenticate_api_key, authenticator::Authenticator},
+        model::journal_entry::{JournalEntryCreate, JournalLeg},
+        schema::{journal_entry::MAX_LEGS, notes::validate_notes, text::TRANSACTION_DESCRIPTION},
+        service::transaction_service::{
+            TransactionServiceJournal, TransactionServiceJournalCreate,
+            TransactionServiceJournalDelete,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathJournalEntryId {
+    id: JournalEntryId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// Reads the legs of an entry in the scale of their assets, checking
+    /// there are neither too few nor too many of them.
+    pub async fn journal_legs(
+        state: &AppState,
+        create_request: &CreateRequest,
+    ) -> Result<Vec<JournalLeg>, ApiError> {
+        if create_request.legs.len() < 2 {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "A journal entry needs at least two legs.",
+            ));
+        }
+        if create_request.legs.len() > MAX_LEGS {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                format!("A journal entry can have at most {MAX_LEGS} legs."),
+            ));
+        }
+        let mut legs = Vec::with_capacity(create_request.legs.len());
+        for leg in &create_request.legs {
+            validate_notes(leg.notes.as_deref())?;
+            let scale = asset_scale(state, leg.asset_id).await?;
+            legs.push(JournalLeg {
+                account_id: leg.account_id,
+                asset_id: leg.asset_id,
+                quantity: leg.quantity.in_asset(scale)?,
+                notes: leg.notes.clone(),
+            });
+        }
+        Ok(legs)
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/journal-entries{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct JournalEntryApi;
+
+    impl Api for JournalEntryApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::DELETE, "/{id}"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::post(server_fn_handler))
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler).delete(server_fn_handler),
+                )
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(from_fn_with_state(state.clone(), authenticate_api_key))
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/journal-entries/{id}",
+    tag = "Journal Entries",
+    params(JournalEntryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The journal entry with its legs.", body = JournalEntryGetResponse),
+        (status = 404, description = "The journal entry was not found."),
+    )
+))]
+#[server(
+    name = JournalEntryApiGet,
+    prefix = "/api",
+    endpoint = "journal-entries/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<JournalEntryGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathJournalEntryId { id } = extract_path().await?;
+
+    let journal_entry = api_state.service.get_journal_entry(id).await?;
+    Ok(journal_entry.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/journal-entries",
+    tag = "Journal Entries",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The newly created journal entry with its legs.", body = JournalEntryCreateResponse),
+        (status = 400, description = "The entry has fewer than two legs, or they don't sum to zero in each asset.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4001,
+            message: "The legs of the entry don't balance, in asset 0196a1b2-7c3d-7e4f-8a9b-0c1d2e3f4a5b they sum to 5.".to_string()
+        })),
+        (status = 404, description = "An account or asset of a leg was not found."),
+    ),
+))]
+#[server(
+    name = JournalEntryApiCreate,
+    prefix = "/api",
+    endpoint = "journal-entries",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<JournalEntryCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+
+    let create_model = JournalEntryCreate {
+        posted_at: create_request.posted_at,
+        description: TRANSACTION_DESCRIPTION.sanitize_option(create_request.description.clone())?,
+        legs: journal_legs(&state, &create_request).await?,
+    };
+    let imbalances = create_model.imbalances();
+    if !imbalances.is_empty() {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            format!(
+                "The legs of the entry don't balance, {}.",
+                imbalances
+                    .iter()
+                    .map(|(asset_id, sum)| format!("in asset {} they sum to {sum}", asset_id.0))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ));
+    }
+
+    let journal_entry = api_state.service.create_journal_entry(create_model).await?;
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(JournalEntryCreateResponse::status());
+    provide_context(response_opts);
+    Ok(journal_entry.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/journal-entries/{id}",
+    tag = "Journal Entries",
+    params(JournalEntryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The journal entry and all of its legs were successfully deleted."),
+        (status = 404, description = "The journal entry was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = JournalEntryApiDelete,
+    prefix = "/api",
+    endpoint = "journal-entries/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathJournalEntryId { id } = extract_path().await?;
+
+    api_state.service.delete_journal_entry(id).await?;
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
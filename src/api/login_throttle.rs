@@ -0,0 +1,460 @@
+//! Throttles the sign in and refresh endpoints harder than the rest.
+//!
+//! Each attempt at `/login/sso` writes a CSRF token and each one at
+//! `/login/oauth2-redirect` or `/login/refresh` asks the identity provider
+//! for tokens, so guessing at them is costly even when every guess fails.
+//! [`throttle_login`] counts their requests against the address of the
+//! client, which is that of the peer unless a trusted proxy forwards it,
+//! and, for refreshes, against a fingerprint of the refresh token,
+//! each in windows of their own. Refusals in a row make a key wait, twice
+//! as long after every further one, and the start of every wait is
+//! recorded as a [`LoginThrottleEvent`](crate::model::login_throttle_event)
+//! for review. [`throttled`] counts the requests turned away.
+//!
+//! The thresholds are those of [`LoginThrottleConfig`], which explains
+//! why a browser doesn't reach them.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::cookie::CookieJar;
+use chrono::Utc;
+use http::{Extensions, HeaderMap, StatusCode};
+use sha2::{Digest, Sha256};
+use tracing::{error, warn};
+
+use crate::{
+    api::{ApiError, AppState, rate_limit::RateLimit},
+    authentication::{client_address::ClientAddress, header_refresh::REFRESH_TOKEN_HEADER},
+    config::LoginThrottleConfig,
+    model::login_throttle_event::{LoginThrottleEventCreate, ThrottleKey},
+    resource::{
+        CreateRepository, deadline, login_throttle_event_repository::LoginThrottleEventRepository,
+    },
+};
+
+/// The endpoints that are throttled.
+pub const THROTTLED_PATHS: [&str; 3] = ["/login/sso", "/login/oauth2-redirect", "/login/refresh"];
+const REFRESH_PATH: &str = "/login/refresh";
+
+/// How many keys are kept before those that are neither waiting nor in a
+/// window are dropped.
+const MAX_ENTRIES: usize = 10_000;
+
+static THROTTLED: AtomicU64 = AtomicU64::new(0);
+
+/// The requests to the sign in and refresh endpoints turned away since the
+/// server started.
+pub fn throttled() -> u64 {
+    THROTTLED.load(Ordering::Relaxed)
+}
+
+/// A key that has to wait.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lockout {
+    /// How long is left to wait
+    pub wait: Duration,
+    /// The refusals in a row that made it wait, zero if it went over the
+    /// limit of its window
+    pub failures: u32,
+    /// Whether the wait started with this request
+    pub started: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    window_started_at: Instant,
+    count: u32,
+    /// Whether a request of the window was turned away
+    over_limit: bool,
+    failures: u32,
+    locked_until: Option<Instant>,
+}
+
+impl Entry {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_started_at: now,
+            count: 0,
+            over_limit: false,
+            failures: 0,
+            locked_until: None,
+        }
+    }
+}
+
+/// Counts the requests and refusals of each key.
+#[derive(Debug, Clone)]
+pub struct LoginThrottle {
+    config: LoginThrottleConfig,
+    entries: Arc<Mutex<HashMap<(ThrottleKey, String), Entry>>>,
+}
+
+impl LoginThrottle {
+    pub fn new(config: LoginThrottleConfig) -> Self {
+        Self {
+            config,
+            entries: Arc::default(),
+        }
+    }
+
+    pub fn config(&self) -> LoginThrottleConfig {
+        self.config
+    }
+
+    fn limit(&self, kind: ThrottleKey) -> u32 {
+        match kind {
+            ThrottleKey::Ip => self.config.per_ip,
+            ThrottleKey::RefreshToken => self.config.per_refresh_token,
+        }
+    }
+
+    /// What a request to `path` counts against: the address of the client,
+    /// or of the peer without a trusted proxy, and the fingerprint of the
+    /// refresh token of a refresh.
+    pub fn keys(
+        &self,
+        path: &str,
+        headers: &HeaderMap,
+        extensions: &Extensions,
+    ) -> Vec<(ThrottleKey, String)> {
+        let mut keys = vec![];
+        let client_address = ClientAddress {
+            trust_forwarded_for: self.config.trust_forwarded_for,
+            ..Default::default()
+        };
+        if let Some(ip) = client_address.client_or_peer_ip(headers, extensions) {
+            keys.push((ThrottleKey::Ip, ip.to_string()));
+        }
+        if path == REFRESH_PATH {
+            let refresh_token = headers
+                .get(REFRESH_TOKEN_HEADER)
+                .and_then(|x| x.to_str().ok())
+                .map(str::to_owned)
+                .or_else(|| {
+                    CookieJar::from_headers(headers)
+                        .get("refresh_token")
+                        .map(|x| x.value().to_owned())
+                });
+            if let Some(refresh_token) = refresh_token {
+                keys.push((ThrottleKey::RefreshToken, fingerprint(&refresh_token)));
+            }
+        }
+        keys
+    }
+
+    /// Counts a request of `key` made at `now`. Fails with how long it has
+    /// to wait while it is waiting, or when it has no requests left in the
+    /// window.
+    pub fn check(&self, kind: ThrottleKey, key: &str, now: Instant) -> Result<(), Lockout> {
+        let window = self.config.window;
+        let limit = self.limit(kind);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| {
+                entry.locked_until.is_some_and(|x| x > now)
+                    || now.duration_since(entry.window_started_at) < window
+            });
+        }
+        let entry = entries
+            .entry((kind, key.to_owned()))
+            .or_insert_with(|| Entry::new(now));
+        if let Some(locked_until) = entry.locked_until
+            && locked_until > now
+        {
+            return Err(Lockout {
+                wait: locked_until - now,
+                failures: entry.failures,
+                started: false,
+            });
+        }
+        if now.duration_since(entry.window_started_at) >= window {
+            entry.window_started_at = now;
+            entry.count = 0;
+            entry.over_limit = false;
+        }
+        if entry.count >= limit {
+            let started = !entry.over_limit;
+            entry.over_limit = true;
+            return Err(Lockout {
+                wait: window.saturating_sub(now.duration_since(entry.window_started_at)),
+                failures: 0,
+                started,
+            });
+        }
+        entry.count += 1;
+        Ok(())
+    }
+
+    /// Records whether the request of `key` was refused, returning the
+    /// wait it starts once there were too many refusals in a row. Anything
+    /// else ends the run.
+    pub fn record(
+        &self,
+        kind: ThrottleKey,
+        key: &str,
+        refused: bool,
+        now: Instant,
+    ) -> Option<Lockout> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries
+            .entry((kind, key.to_owned()))
+            .or_insert_with(|| Entry::new(now));
+        if !refused {
+            entry.failures = 0;
+            return None;
+        }
+        entry.failures += 1;
+        let past = entry.failures.checked_sub(self.config.failures)?;
+        let wait = self
+            .config
+            .backoff
+            .saturating_mul(2_u32.saturating_pow(past))
+            .min(self.config.max_backoff);
+        entry.locked_until = Some(now + wait);
+        Some(Lockout {
+            wait,
+            failures: entry.failures,
+            started: true,
+        })
+    }
+}
+
+/// A fingerprint of a refresh token, which doesn't reveal it.
+fn fingerprint(refresh_token: &str) -> String {
+    Sha256::digest(refresh_token.as_bytes())[..16]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Whether a response counts as a refusal of its request. Errors of the
+/// server, and the 429s of the throttle itself, are not the client's doing.
+fn is_refusal(status: StatusCode) -> bool {
+    status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Logs the start of a wait and records it for review.
+async fn record_lockout(
+    state: &AppState,
+    path: &str,
+    kind: ThrottleKey,
+    key: &str,
+    lockout: Lockout,
+) {
+    warn!(
+        endpoint = path,
+        key_kind = ?kind,
+        failures = lockout.failures,
+        "Throttled sign ins for {}s",
+        lockout.wait.as_secs()
+    );
+    let key = match kind {
+        ThrottleKey::Ip => key
+            .parse::<IpAddr>()
+            .ok()
+            .and_then(|ip| ClientAddress::from_env().anonymize(ip)),
+        ThrottleKey::RefreshToken => Some(key.to_owned()),
+    };
+    let create_model = LoginThrottleEventCreate {
+        endpoint: path.to_owned(),
+        key_kind: kind,
+        key,
+        failures: lockout.failures as i32,
+        locked_until: Utc::now() + lockout.wait,
+    };
+    let result = match deadline::begin(&state.connection_pool).await {
+        Ok(session) => LoginThrottleEventRepository
+            .create(session, create_model)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        error!("Failed to record a login throttle event: {e}");
+    }
+}
+
+/// Counts requests to the sign in and refresh endpoints against their
+/// keys, turning away those of keys that have to wait.
+pub async fn throttle_login(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_owned();
+    if !THROTTLED_PATHS.contains(&path.as_str()) {
+        return next.run(request).await;
+    }
+    let throttle = &state.login_throttle;
+    let keys = throttle.keys(&path, request.headers(), request.extensions());
+    for (kind, key) in &keys {
+        if let Err(lockout) = throttle.check(*kind, key, Instant::now()) {
+            THROTTLED.fetch_add(1, Ordering::Relaxed);
+            if lockout.started {
+                record_lockout(&state, &path, *kind, key, lockout).await;
+            }
+            return ApiError::RateLimited(RateLimit {
+                limit: throttle.limit(*kind),
+                remaining: 0,
+                reset: lockout.wait.as_secs_f64().ceil() as u64,
+            })
+            .into_response();
+        }
+    }
+
+    let response = next.run(request).await;
+    let refused = is_refusal(response.status());
+    for (kind, key) in &keys {
+        if let Some(lockout) = throttle.record(*kind, key, refused, Instant::now()) {
+            record_lockout(&state, &path, *kind, key, lockout).await;
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use axum::extract::ConnectInfo;
+    use http::HeaderValue;
+
+    use super::*;
+    use crate::authentication::client_address::FORWARDED_FOR_HEADER;
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle::new(LoginThrottleConfig {
+            per_ip: 3,
+            per_refresh_token: 2,
+            window: Duration::from_secs(60),
+            failures: 2,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(4),
+            trust_forwarded_for: true,
+        })
+    }
+
+    #[test]
+    fn it_limits_each_key_in_its_window() {
+        let throttle = throttle();
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(throttle.check(ThrottleKey::Ip, "a", now), Ok(()));
+        }
+        assert_eq!(
+            throttle.check(ThrottleKey::Ip, "a", now + Duration::from_secs(20)),
+            Err(Lockout {
+                wait: Duration::from_secs(40),
+                failures: 0,
+                started: true,
+            })
+        );
+        // The wait only starts once a window.
+        assert!(matches!(
+            throttle.check(ThrottleKey::Ip, "a", now + Duration::from_secs(30)),
+            Err(Lockout { started: false, .. })
+        ));
+        assert_eq!(throttle.check(ThrottleKey::Ip, "b", now), Ok(()));
+        assert_eq!(throttle.check(ThrottleKey::RefreshToken, "a", now), Ok(()));
+        assert_eq!(
+            throttle.check(ThrottleKey::Ip, "a", now + Duration::from_secs(60)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn it_backs_off_after_refusals_in_a_row() {
+        let throttle = throttle();
+        let now = Instant::now();
+        let key = ThrottleKey::RefreshToken;
+        assert_eq!(throttle.record(key, "a", true, now), None);
+        let lockout = throttle.record(key, "a", true, now);
+        assert_eq!(lockout.map(|x| x.wait), Some(Duration::from_secs(1)));
+        assert!(throttle.check(key, "a", now).is_err());
+
+        let later = now + Duration::from_secs(1);
+        assert_eq!(throttle.check(key, "a", later), Ok(()));
+        let waits = (0..3)
+            .map(|_| throttle.record(key, "a", true, later).map(|x| x.wait))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            waits,
+            [2, 4, 4].map(|x| Some(Duration::from_secs(x))).to_vec()
+        );
+
+        // Anything but a refusal ends the run.
+        assert_eq!(throttle.record(key, "a", false, later), None);
+        assert_eq!(throttle.record(key, "a", true, later), None);
+    }
+
+    #[test]
+    fn it_keys_refreshes_by_address_and_refresh_token() {
+        let throttle = throttle();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            FORWARDED_FOR_HEADER,
+            HeaderValue::from_static("203.0.113.7"),
+        );
+        headers.insert("Cookie", HeaderValue::from_static("refresh_token=secret"));
+        let extensions = Extensions::new();
+
+        let keys = throttle.keys("/login/refresh", &headers, &extensions);
+        assert_eq!(keys[0], (ThrottleKey::Ip, "203.0.113.7".to_owned()));
+        assert_eq!(keys[1].0, ThrottleKey::RefreshToken);
+        assert_eq!(keys[1].1.len(), 32);
+        assert!(!keys[1].1.contains("secret"));
+
+        headers.insert(REFRESH_TOKEN_HEADER, HeaderValue::from_static("other"));
+        assert_ne!(
+            throttle.keys("/login/refresh", &headers, &extensions)[1],
+            keys[1]
+        );
+        assert_eq!(
+            throttle.keys("/login/sso", &headers, &extensions),
+            keys[..1]
+        );
+
+        let throttle = LoginThrottle::new(LoginThrottleConfig::default());
+        assert_eq!(throttle.keys("/login/sso", &headers, &extensions), vec![]);
+    }
+
+    #[test]
+    fn it_keys_by_the_peer_without_a_trusted_proxy() {
+        let throttle = LoginThrottle::new(LoginThrottleConfig::default());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            FORWARDED_FOR_HEADER,
+            HeaderValue::from_static("203.0.113.7"),
+        );
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([198, 51, 100, 1], 443))));
+
+        assert_eq!(
+            throttle.keys("/login/sso", &headers, &extensions),
+            vec![(ThrottleKey::Ip, "198.51.100.1".to_owned())]
+        );
+    }
+
+    #[test]
+    fn it_only_counts_refusals_of_the_client() {
+        assert!(is_refusal(StatusCode::FORBIDDEN));
+        assert!(is_refusal(StatusCode::BAD_REQUEST));
+        assert!(!is_refusal(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_refusal(StatusCode::BAD_GATEWAY));
+        assert!(!is_refusal(StatusCode::OK));
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::me::{OnboardingStateResponse, PreferenceResponse, PreferenceUpdateRequest},
+};
+use leptos::{
+    server,
+    server_fn::codec::{GetUrl, Json, PatchJson},
+};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, AppState, ClientErrorCode, account_api::AccountApiState, extract_with_state,
+            server_fn_uri, set_user_groups, user_api::UserApiState,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        export::format::Locale,
+        model::account::AccountFilter,
+        schema::me::{MAX_IDLE_LOCK_MINUTES, OnboardingStep},
+        service::ServiceError,
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+    pub use std::str::FromStr;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    // The endpoints are addressed by the caller rather than by id, so the
+    // path needs no rewriting.
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = req.uri().to_string();
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/me{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct MeApi;
+
+    impl Api for MeApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/onboarding-state"),
+                (Method::GET, "/preferences"),
+                (Method::PATCH, "/preferences"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/onboarding-state", axum::routing::get(server_fn_handler))
+                .route(
+                    "/preferences",
+                    axum::routing::get(server_fn_handler).patch(server_fn_handler),
+                )
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/me/onboarding-state",
+    tag = "Me",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "How far the caller is through onboarding.", body = OnboardingStateResponse),
+    ),
+))]
+#[server(
+    name = MeApiOnboardingState,
+    prefix = "/api",
+    endpoint = "me/onboarding-state",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn onboarding_state() -> Result<OnboardingStateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let registered_user = extract_with_state::<Option<RegisteredUser>, _>(&state).await?;
+
+    let Some(registered_user) = registered_user else {
+        return Ok(OnboardingStateResponse {
+            step: OnboardingStep::Register,
+            complete: false,
+            user_id: None,
+            name: None,
+            default_asset_id: None,
+            has_account: false,
+        });
+    };
+    // Only registered callers have an owner for the account service.
+    let user_state = extract_with_state::<UserApiState, _>(&state).await?;
+    let account_state = extract_with_state::<AccountApiState, _>(&state).await?;
+    let user_id = registered_user.id();
+    let default_asset_id = user_state
+        .service
+        .preferences()
+        .await?
+        .and_then(|x| x.default_asset_id);
+    let has_account = !account_state
+        .service
+        .get_list(0, Some(1), AccountFilter::default())
+        .await?
+        .is_empty();
+
+    let name = registered_user.user.name;
+    let step = OnboardingStep::next(true, Some(&name), default_asset_id, has_account);
+    Ok(OnboardingStateResponse {
+        step,
+        complete: step == OnboardingStep::Done,
+        user_id: Some(user_id),
+        name: Some(name),
+        default_asset_id,
+        has_account,
+    })
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/me/preferences",
+    tag = "Me",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The preferences of the caller.", body = PreferenceResponse),
+    ),
+))]
+#[server(
+    name = MeApiGetPreferences,
+    prefix = "/api",
+    endpoint = "me/preferences",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_preferences() -> Result<PreferenceResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<UserApiState, _>(&state).await?;
+
+    let user_preference = api_state.service.preferences().await?;
+    Ok(user_preference.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/me/preferences",
+    tag = "Me",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = PreferenceUpdateRequest,
+    responses(
+        (status = 200, description = "The updated preferences of the caller.", body = PreferenceResponse),
+        (status = 400, description = "The default asset does not exist, the locale is not supported, or the idle lock is out of range."),
+    ),
+))]
+#[server(
+    name = MeApiUpdatePreferences,
+    prefix = "/api",
+    endpoint = "me/preferences",
+    input = PatchJson,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn update_preferences(
+    #[server(flatten)] update_request: PreferenceUpdateRequest,
+) -> Result<PreferenceResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<UserApiState, _>(&state).await?;
+
+    if let Some(locale) = &update_request.locale {
+        Locale::from_str(locale)?;
+    }
+    if update_request
+        .idle_lock_minutes
+        .is_some_and(|x| !(0..=MAX_IDLE_LOCK_MINUTES).contains(&x))
+    {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            format!("The idle lock must be between 0 and {MAX_IDLE_LOCK_MINUTES} minutes."),
+        ));
+    }
+    let user_preference = api_state
+        .service
+        .update_preferences(update_request.into())
+        .await
+        .map_err(|e| match e {
+            ServiceError::NotFound => ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "The default asset does not exist.",
+            ),
+            e => e.into(),
+        })?;
+    Ok(Some(user_preference).into())
+}
@@ -0,0 +1,251 @@
+//! The messages of errors in the languages the API answers in.
+//!
+//! Messages are looked up by the [`code`](super::ApiErrorResponse::code)
+//! of their error, which is what clients should tell errors apart by. A
+//! code covering several errors has an entry for each, told apart by its
+//! English message. Messages without an entry, like the details of a
+//! malformed request, are left in English.
+
+/// A language errors are written in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    En,
+    Ko,
+}
+
+#[cfg(feature = "ssr")]
+tokio::task_local! {
+    /// The language of the request being handled.
+    pub static LANGUAGE: Language;
+}
+
+impl Language {
+    pub const ALL: [Self; 2] = [Self::En, Self::Ko];
+
+    /// The tag of the language in `Accept-Language`.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ko => "ko",
+        }
+    }
+
+    /// Reads a language tag, like `ko` or `ko-KR`, by its primary subtag.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next().unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .find(|language| primary.eq_ignore_ascii_case(language.tag()))
+    }
+
+    /// The language of highest quality in an `Accept-Language` header that
+    /// errors can be written in, or English if there is none. Of languages
+    /// of the same quality, the first is taken.
+    pub fn from_accept_language(value: &str) -> Self {
+        let mut preferred = None::<(f32, Self)>;
+        for language_range in value.split(',') {
+            let mut parts = language_range.split(';');
+            let Some(language) = Self::from_tag(parts.next().unwrap_or_default()) else {
+                continue;
+            };
+            let quality = parts
+                .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+                .find_map(|quality| quality.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            // A zero quality explicitly refuses the language.
+            if quality > 0.0 && preferred.is_none_or(|(best, _)| quality > best) {
+                preferred = Some((quality, language));
+            }
+        }
+        preferred.map(|(_, language)| language).unwrap_or_default()
+    }
+
+    /// The language of the request being handled, or English outside of
+    /// one.
+    #[cfg(feature = "ssr")]
+    pub fn current() -> Self {
+        LANGUAGE.try_with(|language| *language).unwrap_or_default()
+    }
+
+    /// The language the browser is set to, or English while rendering on
+    /// the server.
+    pub fn of_browser() -> Self {
+        #[cfg(feature = "hydrate")]
+        if let Some(tag) = web_sys::window().and_then(|window| window.navigator().language()) {
+            return Self::from_tag(&tag).unwrap_or_default();
+        }
+        Self::default()
+    }
+}
+
+/// The translations of the messages, by code and English message.
+const CATALOG: &[(usize, &str, &str)] = &[
+    (
+        4000,
+        "Invalid JSON in request.",
+        "요청의 JSON이 올바르지 않습니다.",
+    ),
+    (4002, "Invalid id format.", "ID 형식이 올바르지 않습니다."),
+    (
+        4003,
+        "The query string is not valid.",
+        "쿼리 문자열이 올바르지 않습니다.",
+    ),
+    (
+        4004,
+        "The request could not be read.",
+        "요청을 읽을 수 없습니다.",
+    ),
+    (4030, "Forbidden.", "권한이 없습니다."),
+    (4030, "Forbidden", "권한이 없습니다."),
+    (
+        4031,
+        "The email address is not verified.",
+        "이메일 주소가 인증되지 않았습니다.",
+    ),
+    (4040, "Not found.", "찾을 수 없습니다."),
+    (4050, "Method not allowed.", "허용되지 않는 메서드입니다."),
+    (
+        4090,
+        "User is already registered.",
+        "이미 등록된 사용자입니다.",
+    ),
+    (
+        4091,
+        "The institution still has accounts.",
+        "기관에 아직 계좌가 있습니다.",
+    ),
+    (
+        4092,
+        "No accounts were created, some already exist.",
+        "이미 있는 계좌가 있어 계좌를 하나도 만들지 않았습니다.",
+    ),
+    (
+        4092,
+        "An account of this name is already at the institution.",
+        "이 기관에 같은 이름의 계좌가 이미 있습니다.",
+    ),
+    (
+        4093,
+        "Double-entry mode is on, create transactions as balanced journal entries with POST /api/journal-entries.",
+        "복식부기 모드가 켜져 있습니다. POST /api/journal-entries로 균형이 맞는 분개를 만들어 거래를 기록하세요.",
+    ),
+    (
+        4093,
+        "The transaction is a leg of a journal entry, which would no longer balance. Delete the whole entry with DELETE /api/journal-entries/{id} instead.",
+        "이 거래는 분개의 일부이므로 삭제하면 분개의 균형이 맞지 않습니다. 대신 DELETE /api/journal-entries/{id}로 분개 전체를 삭제하세요.",
+    ),
+    (
+        4093,
+        "The account has legs of journal entries, which would no longer balance. Delete those entries with DELETE /api/journal-entries/{id} first.",
+        "이 계좌에는 분개의 일부인 거래가 있어 삭제하면 분개의 균형이 맞지 않습니다. 먼저 DELETE /api/journal-entries/{id}로 해당 분개를 삭제하세요.",
+    ),
+    (
+        4094,
+        "The accounts are at different institutions, pass force=true to merge them anyway.",
+        "계좌들이 서로 다른 기관에 있습니다. 그래도 합치려면 force=true를 전달하세요.",
+    ),
+    (
+        4095,
+        "The proposal was already reviewed.",
+        "이미 검토된 제안입니다.",
+    ),
+    (
+        4220,
+        "An institution cannot be its own ancestor.",
+        "기관은 자기 자신의 상위 기관이 될 수 없습니다.",
+    ),
+    (
+        4220,
+        "The institution hierarchy is too deep.",
+        "기관 계층이 너무 깊습니다.",
+    ),
+    (
+        4280,
+        "Deleting the account deletes its transactions, their attachments and its rules. Count them with GET /api/accounts/{id}/deletion-preview, then pass confirm=true to delete it.",
+        "계좌를 삭제하면 거래와 그 첨부 파일, 계좌의 규칙도 함께 삭제됩니다. GET /api/accounts/{id}/deletion-preview로 개수를 확인한 뒤 confirm=true를 전달해 삭제하세요.",
+    ),
+    (4290, "Too many requests.", "요청이 너무 많습니다."),
+    (5000, "Internal server error.", "내부 서버 오류입니다."),
+    (
+        5040,
+        "The request took too long and was cancelled.",
+        "요청이 너무 오래 걸려 취소되었습니다.",
+    ),
+];
+
+/// The names of the fields of [`FieldError`](super::error::FieldError)s.
+const FIELD_NAMES: &[(&str, &str)] = &[
+    ("user name", "사용자 이름"),
+    ("account name", "계좌 이름"),
+    ("institution name", "기관 이름"),
+    ("asset name", "자산 이름"),
+    ("asset symbol", "자산 기호"),
+    ("transaction description", "거래 설명"),
+    ("category", "카테고리"),
+    ("quick entry label", "빠른 입력 이름"),
+    ("notes", "메모"),
+];
+
+/// The message of the error with `code` and the English `message` in
+/// `language`, or the English message if it has no translation.
+pub fn translate(code: usize, message: &str, language: Language) -> String {
+    let translation = match language {
+        Language::En => None,
+        Language::Ko => CATALOG
+            .iter()
+            .find(|(x, en, _)| *x == code && *en == message)
+            .map(|(_, _, ko)| *ko),
+    };
+    translation.unwrap_or(message).to_owned()
+}
+
+/// The name of a field in `language`.
+pub fn field_name(name: &'static str, language: Language) -> &'static str {
+    match language {
+        Language::En => name,
+        Language::Ko => FIELD_NAMES
+            .iter()
+            .find(|(en, _)| *en == name)
+            .map_or(name, |(_, ko)| *ko),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("ko", Language::Ko)]
+    #[case("ko-KR,ko;q=0.9,en-US;q=0.8", Language::Ko)]
+    #[case("en-US,en;q=0.9,ko;q=0.8", Language::En)]
+    #[case("fr-FR, ko;q=0.5", Language::Ko)]
+    #[case("fr-FR", Language::En)]
+    #[case("ko;q=0", Language::En)]
+    #[case("*", Language::En)]
+    #[case("", Language::En)]
+    fn it_negotiates_the_language(#[case] accept_language: &str, #[case] expected: Language) {
+        assert_eq!(Language::from_accept_language(accept_language), expected);
+    }
+
+    #[test]
+    fn it_translates_each_code_and_message_once() {
+        for (i, (code, en, _)) in CATALOG.iter().enumerate() {
+            assert!(
+                !CATALOG[..i].iter().any(|(x, y, _)| x == code && y == en),
+                "{code} {en}"
+            );
+        }
+        assert_eq!(
+            translate(4040, "Not found.", Language::Ko),
+            "찾을 수 없습니다."
+        );
+        assert_eq!(translate(4040, "Not found.", Language::En), "Not found.");
+        assert_eq!(
+            translate(4001, "The period must end after it starts.", Language::Ko),
+            "The period must end after it starts."
+        );
+    }
+}
@@ -1,25 +1,92 @@
-pub use error::{ApiError, ApiErrorResponse};
+pub use error::{ApiError, ApiErrorResponse, ClientErrorCode};
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
         api::{
-            account_api::AccountApi, asset_api::AssetApi, docs_api::DocsApi,
-            institution_api::InstitutionApi, transaction_api::TransactionApi, user_api::UserApi,
+            account_api::AccountApi,
+            account_template_api::AccountTemplateApi,
+            admin_api::AdminApi,
+            alert_rule_api::AlertRuleApi,
+            announcement_api::AnnouncementApi,
+            api_key_api::ApiKeyApi,
+            asset_api::AssetApi,
+            attachment_api::AttachmentApi,
+            budget_api::BudgetApi,
+            cache_control::{CachePolicy, set_cache_control},
+            capabilities_api::{CapabilitiesApi, RESOURCES},
+            categorization_rule_api::CategorizationRuleApi,
+            dashboard_api::DashboardApi,
+            docs_api::DocsApi,
+            error::{ERROR_CODE_HEADER, ERROR_FORMAT, ErrorFormat},
+            event_api::EventApi,
+            event_hub::EventHub,
+            exchange_rate_api::ExchangeRateApi,
+            export_schedule_api::ExportScheduleApi,
+            import_profile_api::ImportProfileApi,
+            institution_api::InstitutionApi,
+            journal_entry_api::JournalEntryApi,
+            login_throttle::{LoginThrottle, throttle_login},
+            me_api::MeApi,
+            messages::{LANGUAGE, Language},
+            panic::handle_panic,
+            passkey_api::PasskeyApi,
+            proposal_api::ProposalApi,
+            quick_entry_api::QuickEntryApi,
+            rate_limit::{
+                RATE_LIMIT_LIMIT_HEADER, RATE_LIMIT_REMAINING_HEADER, RATE_LIMIT_RESET_HEADER,
+                RateLimiter, rate_limit, set_rate_limit_headers,
+            },
+            report_api::ReportApi,
+            request_id::{REQUEST_ID_HEADER, set_request_id},
+            seed_api::SeedApi,
+            server_time::{SERVER_TIME_HEADER, set_server_time},
+            session_api::SessionApi,
+            sync_api::SyncApi,
+            transaction_api::TransactionApi,
+            transfer_api::TransferApi,
+            user_api::UserApi,
+            version_api::VersionApi,
+            versioning::{
+                DEPRECATION_HEADER, DeprecationPolicy, SUNSET_HEADER, fall_through,
+                set_deprecation_headers,
+            },
+            watchlist_api::WatchlistApi,
         },
         app::App,
         authentication::{
-            authenticated_token::AuthenticatedToken, registered_user::RegisteredUser,
+            authenticated_token::AuthenticatedToken,
+            header_refresh::HeaderRefresh,
+            registered_user::{CachedUser, RegisteredUser},
+        },
+        authorization::group::Group,
+        config::{
+            CacheConfig, DemoConfig, DeprecationConfig, DocsMode, Feature, FeatureFlags,
+            GroupFilterConfig, LoginThrottleConfig, RateLimitConfig,
+        },
+        demo,
+        model::user::UserId,
+        resource::{
+            deadline::{self, DEADLINE, record_timeout},
+            user_preference_repository::UserPreferenceRepository,
         },
+        schema::{NUMBER_FORMAT, NumberFormat, version::ApiVersion},
+        service::cache::ServiceCaches,
+        telemetry::make_request_span,
     };
     pub use axum::{
         Json, Router,
-        extract::{FromRef, FromRequest, FromRequestParts, Request},
-        middleware::Next,
+        extract::{FromRef, FromRequest, FromRequestParts, Path, Request, State},
+        middleware::{Next, from_fn, from_fn_with_state, map_response},
         response::{IntoResponse, Response},
+        routing::any,
     };
     pub use casbin::Enforcer;
-    pub use http::{Method, request::Parts};
+    pub use http::{
+        HeaderName, HeaderValue, Method, Uri,
+        header::{ACCEPT_LANGUAGE, LINK},
+        request::Parts,
+    };
     pub use leptos::{prelude::*, server_fn::axum::server_fn_paths};
     pub use leptos_axum::{AxumRouteListing, LeptosRoutes, generate_route_list_with_exclusions};
     pub use leptos_router::{Method as LeptosMethod, SsrMode};
@@ -31,31 +98,112 @@ mod ssr_imports {
             BasicTokenType,
         },
     };
-    pub use serde::{Deserialize, Serialize};
+    pub use serde::{Deserialize, Serialize, de::DeserializeOwned};
     pub use sqlx::PgPool;
     pub use std::{
         env::var,
+        str::FromStr,
         sync::{Arc, OnceLock},
-        time::Duration,
+        time::{Duration, Instant},
     };
     pub use tower::ServiceBuilder;
     pub use tower_http::{
-        compression::CompressionLayer, cors::CorsLayer, timeout::TimeoutLayer, trace::TraceLayer,
+        catch_panic::CatchPanicLayer, compression::CompressionLayer, cors::CorsLayer,
+        timeout::TimeoutLayer, trace::TraceLayer,
     };
     pub use utoipa::OpenApi;
-    pub use utoipa_swagger_ui::SwaggerUi;
 }
 
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
 pub mod account_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod account_template_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod admin_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod alert_rule_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod announcement_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod api_key_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
 pub mod asset_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod attachment_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod budget_api;
+#[cfg(feature = "ssr")]
+pub mod byte_range;
+#[cfg(feature = "ssr")]
+pub mod cache_control;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod capabilities_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod categorization_rule_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
 pub mod client;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod dashboard_api;
 #[cfg(feature = "ssr")]
 pub mod docs_api;
 pub mod error;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod event_api;
+#[cfg(feature = "ssr")]
+pub mod event_hub;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod exchange_rate_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod export_schedule_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod import_profile_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
 pub mod institution_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod journal_entry_api;
+#[cfg(feature = "ssr")]
+pub mod login_throttle;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod me_api;
+pub mod messages;
+#[cfg(feature = "ssr")]
+pub mod openapi_diff;
+#[cfg(feature = "ssr")]
+pub mod panic;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod passkey_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod proposal_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod quick_entry_api;
+#[cfg(feature = "ssr")]
+pub mod rate_limit;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod report_api;
+#[cfg(feature = "ssr")]
+pub mod request_id;
+#[cfg(feature = "ssr")]
+pub mod resource_context;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod seed_api;
+#[cfg(feature = "ssr")]
+pub mod server_time;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod session_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod sync_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
 pub mod transaction_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod transfer_api;
 #[cfg(feature = "ssr")]
 pub mod user_api;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod version_api;
+#[cfg(feature = "ssr")]
+pub mod versioning;
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
+pub mod watchlist_api;
 
 #[cfg(feature = "ssr")]
 mod ssr {
@@ -72,6 +220,28 @@ mod ssr {
     static DEX_TOKEN_URL: OnceLock<String> = OnceLock::new();
     static DEX_REDIRECT_URL: OnceLock<String> = OnceLock::new();
 
+    /// How long a request may take before the connection gives up on it.
+    pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// How long the handlers of a request may take, short of
+    /// [`REQUEST_TIMEOUT`] to leave time to answer that they ran out of it.
+    pub const REQUEST_BUDGET: Duration = Duration::from_secs(29);
+
+    /// What the identity provider settings a demo goes without are set to.
+    /// Signing in with them fails, a demo signs in through `/demo/login`
+    /// instead.
+    const DEMO_OIDC_PLACEHOLDER: &str = "http://localhost/demo";
+
+    /// Reads an identity provider setting, which only a demo may leave
+    /// unset.
+    fn oidc_setting(name: &str, demo_config: DemoConfig) -> String {
+        match var(name) {
+            Ok(value) => value,
+            Err(_) if demo_config.enabled => DEMO_OIDC_PLACEHOLDER.to_owned(),
+            Err(_) => panic!("Failed to read `{name}` environment variable."),
+        }
+    }
+
     #[derive(Debug, Clone, Deserialize, Serialize)]
     pub struct IDToken {
         pub id_token: String,
@@ -79,7 +249,23 @@ mod ssr {
 
     impl ExtraTokenFields for IDToken {}
 
+    /// The language of the locale a user prefers, if they chose one.
+    async fn preferred_language(state: &AppState, user_id: UserId) -> Option<Language> {
+        let session = deadline::begin(&state.connection_pool).await.ok()?;
+        UserPreferenceRepository
+            .get_by_user_id(session, user_id)
+            .await
+            .ok()
+            .flatten()?
+            .locale
+            .as_deref()
+            .and_then(Language::from_tag)
+    }
+
+    /// Groups the token of a registered user, and writes their errors in
+    /// the language of their locale when the request doesn't ask for one.
     pub async fn set_user_groups(
+        State(state): State<AppState>,
         mut token: AuthenticatedToken,
         user: Option<RegisteredUser>,
         mut request: Request,
@@ -87,87 +273,321 @@ mod ssr {
     ) -> Response {
         if token.groups().is_empty() && token.email_verified() {
             if user.is_some() {
-                token.add_group("user".into());
+                token.add_group(Group::User);
             } else {
-                token.add_group("unregistered_user".into());
+                token.add_group(Group::UnregisteredUser);
             }
         }
+        token.filter_groups(GroupFilterConfig::from_env());
         token.normalize_groups();
         request.extensions_mut().insert(token);
-        next.run(request).await
+        let language = match &user {
+            Some(user) if !request.headers().contains_key(ACCEPT_LANGUAGE) => {
+                preferred_language(&state, user.id()).await
+            }
+            _ => None,
+        };
+        request.extensions_mut().insert(CachedUser(user));
+        match language {
+            Some(language) => LANGUAGE.scope(language, next.run(request)).await,
+            None => next.run(request).await,
+        }
+    }
+
+    /// Writes the quantities of the response in the [`NumberFormat`] asked
+    /// for by the request. Version 2 of the API always writes strings.
+    pub async fn set_number_format(request: Request, next: Next) -> Response {
+        let number_format = match request.extensions().get::<ApiVersion>() {
+            Some(ApiVersion::V2) => NumberFormat::String,
+            _ => NumberFormat::from_request(request.headers(), request.uri().query()),
+        };
+        NUMBER_FORMAT.scope(number_format, next.run(request)).await
+    }
+
+    /// Writes the errors of the response in the [`ErrorFormat`] the request
+    /// accepts.
+    pub async fn set_error_format(request: Request, next: Next) -> Response {
+        let error_format = ErrorFormat::from_headers(request.headers());
+        ERROR_FORMAT.scope(error_format, next.run(request)).await
+    }
+
+    /// Writes the errors of the response in the [`Language`] the request
+    /// accepts.
+    pub async fn set_language(request: Request, next: Next) -> Response {
+        let language = request
+            .headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map(Language::from_accept_language)
+            .unwrap_or_default();
+        LANGUAGE.scope(language, next.run(request)).await
+    }
+
+    /// Gives the request `budget` to be answered in, which the queries it
+    /// makes are held to, answering with a 504 once it runs out.
+    pub async fn set_request_deadline(
+        State(budget): State<Duration>,
+        request: Request,
+        next: Next,
+    ) -> Response {
+        let deadline = Instant::now() + budget;
+        DEADLINE
+            .scope(deadline, async move {
+                match tokio::time::timeout_at(deadline.into(), next.run(request)).await {
+                    Ok(response) => response,
+                    Err(_) => {
+                        record_timeout();
+                        ApiError::Timeout.into_response()
+                    }
+                }
+            })
+            .await
+    }
+
+    /// Answers API paths no route matched, which would otherwise get the
+    /// not found page of the app.
+    async fn api_not_found() -> ApiError {
+        ApiError::NotFound
+    }
+
+    /// Panics, for the tests of [`handle_panic`].
+    #[cfg(test)]
+    async fn panic_for_test() -> ApiError {
+        panic!("Deliberate panic for the tests.")
+    }
+
+    /// Answers a known path called with a method it doesn't serve. Only API
+    /// paths get an error body, pages keep the bare status.
+    async fn method_not_allowed(request: Request) -> Response {
+        if request.uri().path().starts_with("/api/") {
+            ApiError::MethodNotAllowed.into_response()
+        } else {
+            http::StatusCode::METHOD_NOT_ALLOWED.into_response()
+        }
     }
 
     pub trait Api {
-        fn routes(mode: SsrMode) -> Vec<AxumRouteListing> {
+        /// The SSR mode of a route. Collections stream their rows in out of
+        /// order, while a single item is small enough to block on.
+        fn ssr_mode(path: &str) -> SsrMode {
+            if path == "/" {
+                SsrMode::OutOfOrder
+            } else {
+                SsrMode::PartiallyBlocked
+            }
+        }
+        /// The route listings for `endpoints`, each with its own SSR mode.
+        fn routes() -> Vec<AxumRouteListing> {
+            let mut routes = Vec::<(&'static str, Vec<LeptosMethod>)>::new();
+            for (method, path) in Self::endpoints() {
+                let method = match method {
+                    Method::POST => LeptosMethod::Post,
+                    Method::PUT => LeptosMethod::Put,
+                    Method::PATCH => LeptosMethod::Patch,
+                    Method::DELETE => LeptosMethod::Delete,
+                    _ => LeptosMethod::Get,
+                };
+                match routes.iter_mut().find(|(p, _)| *p == path) {
+                    Some((_, methods)) => methods.push(method),
+                    None => routes.push((path, vec![method])),
+                }
+            }
+            routes
+                .into_iter()
+                .map(|(path, methods)| {
+                    AxumRouteListing::new(path.to_owned(), Self::ssr_mode(path), methods, vec![])
+                })
+                .collect()
+        }
+        /// The `(method, path)` pairs served by `router`, relative to where
+        /// it is nested. Every one of them must be documented in `DocsApi`.
+        fn endpoints() -> Vec<(Method, &'static str)> {
             vec![
-                AxumRouteListing::new(
-                    "/".to_owned(),
-                    mode.clone(),
-                    vec![LeptosMethod::Get, LeptosMethod::Post],
-                    vec![],
-                ),
-                AxumRouteListing::new(
-                    "/{id}".to_owned(),
-                    mode.clone(),
-                    vec![LeptosMethod::Get, LeptosMethod::Patch, LeptosMethod::Delete],
-                    vec![],
-                ),
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
             ]
         }
+        /// The endpoints of `endpoints` a newer version of the API
+        /// supersedes. Flag them in the resource registry to have their
+        /// responses announce it.
+        fn deprecated() -> Vec<(Method, &'static str)> {
+            vec![]
+        }
         fn router(state: AppState) -> Router<AppState>;
     }
 
+    /// How [`ApiV1::router_with_config`] sets the router up. A setting of a
+    /// new feature is a new field, which the tests that don't care about it
+    /// leave to its default.
+    #[derive(Debug, Clone, Default)]
+    pub struct RouterConfig {
+        /// Who the API docs are served to
+        pub docs_mode: DocsMode,
+        /// How many requests each client may make
+        pub rate_limit_config: RateLimitConfig,
+        /// How many attempts to sign in each client may make
+        pub login_throttle_config: LoginThrottleConfig,
+        /// The clients that may send their refresh token in a header
+        pub header_refresh: HeaderRefresh,
+        /// Whether demo sessions may be started, and how many
+        pub demo_config: DemoConfig,
+        /// When the deprecated endpoints are announced to go away
+        pub deprecation_config: DeprecationConfig,
+        /// The optional features whose routes are mounted
+        pub features: FeatureFlags,
+        /// Where the events of the services are published
+        pub events: EventHub,
+    }
+
+    impl RouterConfig {
+        /// The configuration of the environment, publishing events to a hub
+        /// of its own.
+        pub fn from_env() -> Self {
+            Self {
+                docs_mode: DocsMode::from_env(),
+                rate_limit_config: RateLimitConfig::from_env(),
+                login_throttle_config: LoginThrottleConfig::from_env(),
+                header_refresh: HeaderRefresh::from_env().clone(),
+                demo_config: DemoConfig::from_env(),
+                deprecation_config: DeprecationConfig::from_env(),
+                features: FeatureFlags::from_env(),
+                events: EventHub::default(),
+            }
+        }
+    }
+
     pub struct ApiV1;
 
     impl ApiV1 {
         pub fn router(connection_pool: Arc<PgPool>, enforcer: Arc<Enforcer>) -> Router {
+            Self::router_with_features(
+                connection_pool,
+                enforcer,
+                FeatureFlags::from_env(),
+                EventHub::default(),
+            )
+        }
+
+        /// The router, mounting the routes of the features `features`
+        /// enables and sharing their flags with the services, which publish
+        /// their events to the streams of `events`.
+        pub fn router_with_features(
+            connection_pool: Arc<PgPool>,
+            enforcer: Arc<Enforcer>,
+            features: FeatureFlags,
+            events: EventHub,
+        ) -> Router {
+            Self::router_with_config(
+                connection_pool,
+                enforcer,
+                RouterConfig {
+                    features,
+                    events,
+                    ..RouterConfig::from_env()
+                },
+            )
+        }
+
+        /// The router, serving the API docs to whoever `docs_mode` allows.
+        pub fn router_with_docs(
+            connection_pool: Arc<PgPool>,
+            enforcer: Arc<Enforcer>,
+            docs_mode: DocsMode,
+        ) -> Router {
+            Self::router_with_config(
+                connection_pool,
+                enforcer,
+                RouterConfig {
+                    docs_mode,
+                    ..RouterConfig::from_env()
+                },
+            )
+        }
+
+        /// The routes of the optional features that are enabled.
+        fn feature_routes(state: &AppState) -> Router<AppState> {
+            let mut router = Router::new();
+            if state.features.is_enabled(Feature::Alerts) {
+                router = router.nest("/api/alert-rules", AlertRuleApi::router(state.clone()));
+            }
+            if state.features.is_enabled(Feature::ExchangeRates) {
+                router = router.nest(
+                    "/api/exchange-rates",
+                    ExchangeRateApi::router(state.clone()),
+                );
+            }
+            if state.features.is_enabled(Feature::ExportSchedules) {
+                router = router.nest(
+                    "/api/export-schedules",
+                    ExportScheduleApi::router(state.clone()),
+                );
+            }
+            router
+        }
+
+        /// The router, configured by `config`.
+        pub fn router_with_config(
+            connection_pool: Arc<PgPool>,
+            enforcer: Arc<Enforcer>,
+            config: RouterConfig,
+        ) -> Router {
+            let RouterConfig {
+                docs_mode,
+                rate_limit_config,
+                login_throttle_config,
+                header_refresh,
+                demo_config,
+                deprecation_config,
+                features,
+                events,
+            } = config;
             let allow_origin = CORS_ALLOWED_ORIGIN.get_or_init(|| {
                 var("CORS_ALLOWED_ORIGIN")
                     .expect("Failed to read `CORS_ALLOWED_ORIGIN` environment variable.")
             });
             let conf = get_configuration(Some("Cargo.toml")).unwrap();
             let leptos_options = conf.leptos_options;
+            let cache_policy = CachePolicy {
+                hashed_files: leptos_options.hash_files,
+                config: CacheConfig::from_env(),
+            };
+            let deprecation_policy = DeprecationPolicy {
+                endpoints: Arc::new(
+                    RESOURCES
+                        .iter()
+                        .flat_map(|resource| resource.deprecated_endpoints())
+                        .collect(),
+                ),
+                config: deprecation_config,
+            };
             let client_id = ClientId::new(
                 DEX_STATIC_CLIENT_ID
-                    .get_or_init(|| {
-                        var("DEX_STATIC_CLIENT_ID")
-                            .expect("Failed to read `DEX_STATIC_CLIENT_ID` environment variable.")
-                    })
+                    .get_or_init(|| oidc_setting("DEX_STATIC_CLIENT_ID", demo_config))
                     .clone(),
             );
             let client_secret = ClientSecret::new(
                 DEX_STATIC_CLIENT_SECRET
-                    .get_or_init(|| {
-                        var("DEX_STATIC_CLIENT_SECRET").expect(
-                            "Failed to read `DEX_STATIC_CLIENT_SECRET` environment variable.",
-                        )
-                    })
+                    .get_or_init(|| oidc_setting("DEX_STATIC_CLIENT_SECRET", demo_config))
                     .clone(),
             );
             let auth_url = AuthUrl::new(
                 DEX_AUTH_URL
-                    .get_or_init(|| {
-                        var("DEX_AUTH_URL")
-                            .expect("Failed to read `DEX_AUTH_URL` environment variable.")
-                    })
+                    .get_or_init(|| oidc_setting("DEX_AUTH_URL", demo_config))
                     .clone(),
             )
             .expect("Invalid auth url.");
             let token_url = TokenUrl::new(
                 DEX_TOKEN_URL
-                    .get_or_init(|| {
-                        var("DEX_TOKEN_URL")
-                            .expect("Failed to read `DEX_TOKEN_URL` environment variable.")
-                    })
+                    .get_or_init(|| oidc_setting("DEX_TOKEN_URL", demo_config))
                     .clone(),
             )
             .expect("Invalid token url.");
             let redirect_url = RedirectUrl::new(
                 DEX_REDIRECT_URL
-                    .get_or_init(|| {
-                        var("DEX_REDIRECT_URL")
-                            .expect("Failed to read `DEX_REDIRECT_URL` environment variable.")
-                    })
+                    .get_or_init(|| oidc_setting("DEX_REDIRECT_URL", demo_config))
                     .clone(),
             )
             .expect("Invalid redirect url.");
@@ -180,7 +600,18 @@ mod ssr {
                 connection_pool,
                 enforcer,
                 leptos_options: leptos_options.clone(),
+                service_caches: ServiceCaches::default(),
                 oauth_client,
+                rate_limiter: RateLimiter::new(rate_limit_config),
+                login_throttle: LoginThrottle::new(login_throttle_config),
+                header_refresh: Arc::new(header_refresh),
+                events,
+                demo_config,
+                demo_starts: RateLimiter::new(RateLimitConfig {
+                    limit: demo_config.starts_per_ip,
+                    window: demo::START_WINDOW,
+                }),
+                features,
             };
 
             let api_paths = server_fn_paths()
@@ -190,46 +621,154 @@ mod ssr {
 
             let routes = generate_route_list_with_exclusions(App, Some(api_paths));
 
-            let swagger = SwaggerUi::new("/docs").url("/private/api.json", DocsApi::openapi());
-            Router::new()
-                .merge(swagger)
+            let router = Router::new()
+                .merge(DocsApi::mount(docs_mode, state.clone()))
                 .leptos_routes(&state, routes, move || {
                     let leptos_options = leptos_options.clone();
                     shell(leptos_options.clone())
                 })
                 .fallback(file_and_error_handler::<AppState, _>(shell))
                 .nest("/api/accounts", AccountApi::router(state.clone()))
+                .nest(
+                    "/api/account-templates",
+                    AccountTemplateApi::router(state.clone()),
+                )
+                .nest("/api/admin", AdminApi::router(state.clone()))
+                .merge(Self::feature_routes(&state))
+                .nest("/api/announcements", AnnouncementApi::router(state.clone()))
                 .nest("/api/assets", AssetApi::router(state.clone()))
+                .nest("/api/attachments", AttachmentApi::router(state.clone()))
+                .nest("/api/budgets", BudgetApi::router(state.clone()))
+                .nest("/api/capabilities", CapabilitiesApi::router(state.clone()))
+                .nest(
+                    "/api/categorization-rules",
+                    CategorizationRuleApi::router(state.clone()),
+                )
+                .nest("/api/dashboard", DashboardApi::router(state.clone()))
+                .nest("/api/events", EventApi::router(state.clone()))
+                .nest(
+                    "/api/journal-entries",
+                    JournalEntryApi::router(state.clone()),
+                )
+                .nest("/api/me", MeApi::router(state.clone()))
+                .nest("/api/proposals", ProposalApi::router(state.clone()))
+                .nest("/api/quick-entries", QuickEntryApi::router(state.clone()))
+                .nest("/api/reports", ReportApi::router(state.clone()))
+                .nest("/api/seed", SeedApi::router(state.clone()))
+                .nest("/api/sync", SyncApi::router(state.clone()))
                 .nest("/api/transactions", TransactionApi::router(state.clone()))
+                .nest("/api/transfers", TransferApi::router(state.clone()))
                 .nest("/api/users", UserApi::router(state.clone()))
+                .nest("/api/users/{id}", PasskeyApi::router(state.clone()))
+                .nest("/api/users/{id}/api-keys", ApiKeyApi::router(state.clone()))
+                .nest(
+                    "/api/users/{id}/sessions",
+                    SessionApi::router(state.clone()),
+                )
+                .nest(
+                    "/api/import-profiles",
+                    ImportProfileApi::router(state.clone()),
+                )
                 .nest("/api/institutions", InstitutionApi::router(state.clone()))
-                .nest("/docs", DocsApi::router(state.clone()))
+                .nest("/api/version", VersionApi::router(state.clone()))
+                .nest("/api/watchlist", WatchlistApi::router(state.clone()))
+                .route("/api", any(api_not_found))
+                .route("/api/{*path}", any(api_not_found))
+                .method_not_allowed_fallback(method_not_allowed);
+            #[cfg(test)]
+            let router = router.route("/api/test/panic", any(panic_for_test));
+            router
                 .layer(
                     ServiceBuilder::new()
-                        .layer(TraceLayer::new_for_http())
+                        .layer(TraceLayer::new_for_http().make_span_with(make_request_span))
+                        .layer(from_fn(set_request_id))
                         .layer(CompressionLayer::new().gzip(true))
-                        .layer(TimeoutLayer::new(Duration::from_secs(30)))
+                        .layer(TimeoutLayer::new(REQUEST_TIMEOUT))
+                        .layer(from_fn(set_number_format))
+                        .layer(from_fn(set_error_format))
+                        .layer(from_fn(set_language))
+                        .layer(CatchPanicLayer::custom(handle_panic))
+                        .layer(from_fn_with_state(REQUEST_BUDGET, set_request_deadline))
+                        .layer(from_fn_with_state(cache_policy, set_cache_control))
+                        .layer(from_fn_with_state(
+                            deprecation_policy,
+                            set_deprecation_headers,
+                        ))
+                        .layer(map_response(set_rate_limit_headers))
+                        .layer(map_response(set_server_time))
+                        .layer(from_fn_with_state(state.clone(), rate_limit))
+                        .layer(from_fn_with_state(state.clone(), throttle_login))
                         .layer(
                             CorsLayer::new()
-                                .allow_origin([allow_origin.parse().unwrap()])
+                                .allow_origin([allow_origin
+                                    .parse::<HeaderValue>()
+                                    .expect("Invalid CORS allowed origin.")])
                                 .allow_methods([
                                     Method::GET,
                                     Method::PUT,
                                     Method::POST,
                                     Method::PATCH,
                                     Method::DELETE,
-                                ]),
+                                ])
+                                .expose_headers(
+                                    [
+                                        RATE_LIMIT_LIMIT_HEADER,
+                                        RATE_LIMIT_REMAINING_HEADER,
+                                        RATE_LIMIT_RESET_HEADER,
+                                        DEPRECATION_HEADER,
+                                        SUNSET_HEADER,
+                                        SERVER_TIME_HEADER,
+                                        ERROR_CODE_HEADER,
+                                        REQUEST_ID_HEADER,
+                                        LINK.as_str(),
+                                    ]
+                                    .map(|header| HeaderName::from_str(header).unwrap()),
+                                ),
                         ),
                 )
                 .with_state(state)
         }
     }
 
+    /// The second version of the API, mounted at `/api/v2`. It serves the
+    /// endpoints it changes itself and hands the rest to [`ApiV1`], which
+    /// writes their quantities as decimal strings for it.
+    pub struct ApiV2;
+
+    impl ApiV2 {
+        pub fn router(connection_pool: Arc<PgPool>, enforcer: Arc<Enforcer>) -> Router {
+            Self::mount(ApiV1::router(connection_pool, enforcer))
+        }
+
+        /// Mounts version 2 in front of `v1`, which keeps serving everything
+        /// outside of `/api/v2`.
+        pub fn mount(v1: Router) -> Router {
+            let previous = v1.clone();
+            Router::new()
+                .nest_service(
+                    ApiVersion::V2.prefix(),
+                    Self::overrides().fallback(move |request: Request| {
+                        fall_through(previous.clone(), ApiVersion::V2, request)
+                    }),
+                )
+                .fallback_service(v1)
+        }
+
+        /// The endpoints version 2 serves itself, relative to its prefix.
+        /// They take precedence over those of version 1, and are served
+        /// without its middleware.
+        fn overrides() -> Router {
+            Router::new()
+        }
+    }
+
     #[derive(Clone, FromRef)]
     pub struct AppState {
         pub connection_pool: Arc<PgPool>,
         pub enforcer: Arc<Enforcer>,
         pub leptos_options: LeptosOptions,
+        /// Read-through caches of the rarely changing institutions and assets
+        pub service_caches: ServiceCaches,
         pub oauth_client: Client<
             BasicErrorResponse,
             StandardTokenResponse<IDToken, BasicTokenType>,
@@ -242,6 +781,20 @@ mod ssr {
             EndpointNotSet,
             EndpointSet,
         >,
+        /// Counts the API requests of each client
+        pub rate_limiter: RateLimiter,
+        /// Counts the requests to sign in of each address and refresh token
+        pub login_throttle: LoginThrottle,
+        /// Which clients may present their refresh token in a header
+        pub header_refresh: Arc<HeaderRefresh>,
+        /// The event streams open on this server
+        pub events: EventHub,
+        /// Whether `/demo/login` starts demo sessions
+        pub demo_config: DemoConfig,
+        /// Counts the demo sessions each address starts
+        pub demo_starts: RateLimiter,
+        /// Which optional subsystems are enabled
+        pub features: FeatureFlags,
     }
 
     #[derive(FromRequest, Serialize)]
@@ -266,6 +819,28 @@ mod ssr {
         let mut parts = expect_context::<Parts>();
         T::from_request_parts(&mut parts, state).await
     }
+
+    /// Parses the URI a server fn handler points its request at, which is
+    /// made of the URI of the request and may not be a valid one.
+    pub fn server_fn_uri(uri: String) -> Result<Uri, ApiError> {
+        uri.parse().map_err(|_| {
+            ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "The URI of the request is not valid.",
+            )
+        })
+    }
+
+    /// Extracts the parameters of the path of the request. Unlike
+    /// `leptos_axum::extract`, parameters that don't parse are a client
+    /// error instead of a server one.
+    pub async fn extract_path<T>() -> Result<T, ApiError>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let Path(params) = extract_with_state::<Path<T>, _>(&()).await?;
+        Ok(params)
+    }
 }
 
 #[cfg(feature = "ssr")]
@@ -274,38 +849,120 @@ pub use ssr::*;
 #[cfg(test)]
 mod test {
     use ssr_imports::*;
-    use std::env::var;
+    use std::{env::var, net::SocketAddr};
 
-    use axum::{body::Body, routing::RouterIntoService};
-    use casbin::{CoreApi, Enforcer};
-    use chrono::Utc;
-    use http::{StatusCode, Uri};
+    use axum::{body::Body, extract::ConnectInfo, routing::RouterIntoService};
+    use base64::{Engine, prelude::BASE64_STANDARD};
+    use casbin::{CoreApi, Enforcer, MgmtApi};
+    use chrono::{DateTime, Datelike, SubsecRound, TimeDelta, Utc};
+    use http::{
+        HeaderMap, StatusCode, Uri,
+        header::{CACHE_CONTROL, LOCATION, VARY},
+    };
     use http_body_util::BodyExt;
+    use object_store::{ObjectStore, memory::InMemory, path::Path as ObjectPath};
     use reqwest::Client;
     use rstest::{fixture, rstest};
+    use rust_decimal::Decimal;
     use serde_json::Value;
     use sqlx::{Pool, Postgres};
     use tower::{Service, ServiceExt};
-    use tracing::subscriber::DefaultGuard;
-    use tracing_subscriber::{EnvFilter, FmtSubscriber};
+    use tracing::{
+        Subscriber,
+        span::{Attributes, Id},
+        subscriber::DefaultGuard,
+    };
+    use tracing_subscriber::{
+        EnvFilter, FmtSubscriber, Layer,
+        layer::{Context, SubscriberExt},
+        registry::{LookupSpan, Registry},
+    };
+    use webauthn_authenticator_rs::{WebauthnAuthenticator, softpasskey::SoftPasskey};
+    use webauthn_rs::prelude::{CreationChallengeResponse, RequestChallengeResponse, Url};
 
     use crate::{
         AUTH_MODEL_PATH, AUTH_POLICY_PATH,
-        model::user::UserId,
+        api::event_hub::MAX_STREAMS_PER_USER,
+        app::auth::RefreshResponse,
+        authentication::{
+            api_key::hash_secret,
+            authenticated_token::Claims,
+            csrf,
+            header_refresh::{CLIENT_ID_HEADER, HeaderRefresh, REFRESH_TOKEN_HEADER},
+            mock_issuer::{MockClaims, MockIssuer},
+        },
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+            simulation::MAX_PROPOSAL_BYTES,
+            summary::validate_policies,
+        },
+        categorization::{MAX_RULES, RECATEGORIZE_BATCH_SIZE},
+        client::{ClientError, Page, TreasuryClient},
+        config::{DatabaseConfig, PagedResource},
+        demo::{self, DEMO_ISSUER, DEMO_SEED_SUB, DEMO_TOKEN_PREFIX},
+        export::{
+            self, CsvOptions, ExportError, ExportJob, ObjectStoreDestination, StorageDestination,
+        },
+        extraction::{ExtractionJob, fake::FakeExtractor},
+        import::{PREVIEW_ROWS, institutions::MAX_ROW_BYTES},
+        integration::fake,
+        integrity,
+        model::{
+            account::AccountId,
+            announcement::{AnnouncementCreate, AnnouncementFilter, AnnouncementSeverity},
+            asset::AssetId,
+            attachment::AttachmentId,
+            csrf_token::{CsrfState, CsrfTokenCreate},
+            export_schedule::{DestinationConfig, ExportRunStatus, ExportScheduleId},
+            institution::InstitutionId,
+            provider_connection::ProviderConnectionCreate,
+            transaction::{TransactionDirection, TransactionFilter, TransactionId},
+            user::{UserCreate, UserId},
+            user_session::UserSessionCreate,
+        },
+        resource::{
+            CreateRepository, DeleteRepository, GetListRepository, GetRepository, RepositoryError,
+            announcement_repository::AnnouncementRepository, asset_repository::AssetRepository,
+            attachment_repository::AttachmentRepository,
+            csrf_token_repository::CsrfTokenRepository, deadline,
+            export_schedule_repository::ExportScheduleRepository,
+            provider_connection_repository::ProviderConnectionRepository,
+            stats_repository::StatsRepository, transaction_repository::TransactionRepository,
+            user_repository::UserRepository, user_session_repository::UserSessionRepository,
+        },
+        rounding::RoundingPolicy,
         schema::{
-            GetList,
+            GetList, ResolvedDateRange,
             account::{
-                AccountCreateResponse, CreateRequest as AccountCreateRequest,
-                GetListResponse as AccountGetListResponse,
+                AccountCreateResponse, CreateRequest as AccountCreateRequest, FromTemplateResponse,
+                GetListResponse as AccountGetListResponse, SyncResponse,
+            },
+            account_template::AccountTemplateGetListResponse,
+            api_key::{ApiKeyCreateResponse, ApiKeyGetListResponse},
+            asset::{AssetGetListResponse, AssetResponse, GetListRequest as AssetGetListRequest},
+            capabilities::{CapabilitiesResponse, PageSizeResponse, RateLimitResponse},
+            export_schedule::ExportOptionsRequest,
+            import_profile::ImportProfileCreateResponse,
+            institution::{
+                GetListRequest as InstitutionGetListRequest, InstitutionGetListResponse,
+                InstitutionGetResponse, InstitutionResponse, RollupResponse,
+            },
+            notes::{MAX_NOTES_BYTES, NotesHtmlResponse},
+            passkey::ChallengeResponse,
+            transaction::{
+                CreateRequest as TransactionCreateRequest,
+                GetListRequest as TransactionGetListRequest, ImportPreviewResponse, ImportResponse,
+                TransactionCreateResponse, TransactionGetListResponse,
             },
-            asset::{AssetGetListResponse, AssetResponse},
-            institution::{InstitutionGetListResponse, InstitutionResponse},
-            transaction::{CreateRequest as TransactionCreateRequest, TransactionCreateResponse},
             user::{
                 CreateRequest as UserCreateRequest, UpdateRequest as UserUpdateRequest,
                 UserCreateResponse, UserDeleteResponse, UserGetResponse, UserUpdateResponse,
             },
+            user_session::UserSessionGetListResponse,
         },
+        service::{ServiceCreate, ServiceError, user_service_factory::UserServiceFactory},
+        statement::{INLINE_TRANSACTIONS, format_quantity},
     };
 
     use super::*;
@@ -534,10 +1191,202 @@ mod test {
         serde_json::from_slice(&body).unwrap()
     }
 
+    async fn post_json(
+        uri: &str,
+        body: Value,
+        auth_token: &str,
+        api: &mut RouterIntoService<Body>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method("POST")
+            .header("Authorization", auth_token)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .uri(uri)
+            .body(Body::from(serde_json::to_vec(&body).unwrap()))
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    async fn send_json(
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+        auth_token: &str,
+        api: &mut RouterIntoService<Body>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .header("Authorization", auth_token)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .uri(uri)
+            .body(body.map_or_else(Body::empty, |body| {
+                Body::from(serde_json::to_vec(&body).unwrap())
+            }))
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    /// Sends `body` as is, for the endpoints that don't take JSON.
+    async fn send_bytes(
+        uri: &str,
+        content_type: &str,
+        body: Vec<u8>,
+        auth_token: &str,
+        api: &mut RouterIntoService<Body>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method("POST")
+            .header("Authorization", auth_token)
+            .header("Content-Type", content_type)
+            .header("Accept", "application/json")
+            .uri(uri)
+            .body(Body::from(body))
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    /// Gets a document that isn't JSON, like a statement, with its headers.
+    async fn get_document(
+        uri: &str,
+        auth_token: &str,
+        api: &mut RouterIntoService<Body>,
+    ) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let request = Request::builder()
+            .method("GET")
+            .header("Authorization", auth_token)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, headers, body.to_vec())
+    }
+
+    /// Gets part of a download with a `Range` header, and an `If-Range`
+    /// if given.
+    async fn get_range(
+        uri: &str,
+        range: &str,
+        if_range: Option<&str>,
+        auth_token: &str,
+        api: &mut RouterIntoService<Body>,
+    ) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let mut request = Request::builder()
+            .method("GET")
+            .header("Authorization", auth_token)
+            .header("Range", range)
+            .uri(uri);
+        if let Some(if_range) = if_range {
+            request = request.header("If-Range", if_range);
+        }
+        let response = ServiceExt::<Request<Body>>::ready(api)
+            .await
+            .unwrap()
+            .call(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, headers, body.to_vec())
+    }
+
+    /// Renders a page of the app, with a refresh token cookie if given.
+    async fn get_page(
+        uri: &str,
+        refresh_token: Option<&str>,
+        api: &mut RouterIntoService<Body>,
+    ) -> (StatusCode, HeaderMap, String) {
+        let mut request = Request::builder()
+            .method("GET")
+            .header("Accept", "text/html")
+            .uri(uri);
+        if let Some(refresh_token) = refresh_token {
+            request = request.header("Cookie", format!("refresh_token={refresh_token}"));
+        }
+        let response = ServiceExt::<Request<Body>>::ready(api)
+            .await
+            .unwrap()
+            .call(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, headers, String::from_utf8_lossy(&body).into_owned())
+    }
+
     fn create_api(pool: PgPool, enforcer: Arc<Enforcer>) -> RouterIntoService<Body> {
         ApiV1::router(Arc::new(pool), enforcer).into_service()
     }
 
+    /// Serves the router on a free local port for clients that need a real
+    /// connection, returning its base url.
+    async fn serve_api(pool: PgPool, enforcer: Arc<Enforcer>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(
+            axum::serve(
+                listener,
+                ApiV1::router(Arc::new(pool), enforcer)
+                    .into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .into_future(),
+        );
+        format!("http://{address}")
+    }
+
+    /// Collects the name of every span created, with the name of its parent.
+    #[derive(Debug, Clone, Default)]
+    struct SpanRecorder(Arc<std::sync::Mutex<Vec<(String, Option<String>)>>>);
+
+    impl<S> Layer<S> for SpanRecorder
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let span = ctx.span(id).unwrap();
+            let parent = span.parent().map(|parent| parent.name().to_owned());
+            self.0
+                .lock()
+                .unwrap()
+                .push((span.name().to_owned(), parent));
+        }
+    }
+
     #[fixture]
     fn tracer() -> DefaultGuard {
         let subscriber = FmtSubscriber::builder()
@@ -563,8 +1412,14 @@ mod test {
         )
     }
 
-    #[fixture]
-    async fn user_auth_token() -> String {
+    /// An id token for a user of the Dex the tests run against, or one
+    /// minted for them by the mock issuer when `TEST_OIDC=mock`.
+    async fn auth_token(username: &str, user_id: &str) -> String {
+        if MockIssuer::enabled() {
+            let mut claims = MockClaims::new(user_id, &format!("{username}@example.com"));
+            claims.name = Some(username.to_owned());
+            return MockIssuer::shared().await.token(&claims);
+        }
         let client = Client::new();
         let client_id = var("DEX_STATIC_CLIENT_ID").expect("Failed to read `DEX_STATIC_CLIENT_ID`");
         let client_secret =
@@ -575,7 +1430,7 @@ mod test {
                 ("grant_type", "password"),
                 ("client_id", &client_id),
                 ("client_secret", &client_secret),
-                ("username", "user@example.com"),
+                ("username", &format!("{username}@example.com")),
                 ("password", "password"),
                 ("scope", "openid profile email groups"),
             ])
@@ -592,34 +1447,15 @@ mod test {
         format!("Bearer {bearer}")
     }
 
+    #[fixture]
+    async fn user_auth_token() -> String {
+        auth_token("user", "08a8684b-db88-4b73-90a9-3cd1661f5466").await
+    }
+
     #[fixture]
     async fn user_two_auth_token() -> String {
-        let client = Client::new();
-        let client_id = var("DEX_STATIC_CLIENT_ID").expect("Failed to read `DEX_STATIC_CLIENT_ID`");
-        let client_secret =
-            var("DEX_STATIC_CLIENT_SECRET").expect("Failed to read `DEX_STATIC_CLIENT_SECRET`");
-        let response = client
-            .post("http://127.0.0.1:5556/dex/token")
-            .form(&[
-                ("grant_type", "password"),
-                ("client_id", &client_id),
-                ("client_secret", &client_secret),
-                ("username", "user2@example.com"),
-                ("password", "password"),
-                ("scope", "openid profile email groups"),
-            ])
-            .send()
-            .await
-            .expect("Failed to get auth token");
-
-        let response_json = response.json::<Value>().await.unwrap();
-        let bearer = response_json["id_token"]
-            .to_string()
-            .trim_start_matches("\"")
-            .trim_end_matches("\"")
-            .to_string();
-        format!("Bearer {bearer}")
-    }
+        auth_token("user2", "96e88e65-c095-4047-9c19-c21caf253c1e").await
+    }
 
     #[rstest]
     #[case("/api/users")]
@@ -676,214 +1512,11638 @@ mod test {
         assert_eq!(status, StatusCode::FORBIDDEN);
     }
 
-    #[rstest]
-    #[sqlx::test]
-    #[awt]
-    async fn it_creates_a_user(
-        #[future] enforcer: Arc<Enforcer>,
-        #[future] user_auth_token: String,
-        #[ignore] pool: Pool<Postgres>,
-    ) {
-        let mut api = create_api(pool, enforcer);
-        let create_request = UserCreateRequest {
-            name: "Test User".into(),
-        };
-
-        let create_response = create_user(&create_request, &user_auth_token, &mut api).await;
-        assert_eq!(create_response.name, create_request.name);
-
-        let get_response = get_user(create_response.id, &user_auth_token, &mut api).await;
-        assert_eq!(get_response.name, create_request.name);
-        assert_eq!(&get_response.email, "user@example.com");
+    /// Maps a documented path to the server fn its handler dispatches to.
+    /// Path parameters are dropped, keeping the trailing slash for ids.
+    fn server_fn_path(path: &str) -> String {
+        let mut server_fn_path = path
+            .split('/')
+            .filter(|segment| !segment.starts_with('{'))
+            .collect::<Vec<_>>()
+            .join("/");
+        if path.ends_with('}') {
+            server_fn_path.push('/');
+        }
+        server_fn_path
     }
 
     #[rstest]
     #[awt]
     #[sqlx::test]
-    async fn it_allows_user_to_update_their_name(
+    async fn it_documents_every_mounted_endpoint(
         #[future] enforcer: Arc<Enforcer>,
         #[future] user_auth_token: String,
         #[ignore] pool: Pool<Postgres>,
     ) {
+        let mut documented = DocsApi::openapi()
+            .paths
+            .paths
+            .into_iter()
+            .flat_map(|(path, item)| {
+                [
+                    (Method::GET, item.get.is_some()),
+                    (Method::POST, item.post.is_some()),
+                    (Method::PUT, item.put.is_some()),
+                    (Method::PATCH, item.patch.is_some()),
+                    (Method::DELETE, item.delete.is_some()),
+                ]
+                .into_iter()
+                .filter(|(_, documented)| *documented)
+                .map(move |(method, _)| (method.to_string(), path.clone()))
+            })
+            .collect::<Vec<_>>();
+        documented.sort();
+
+        // What the router serves is found by asking it for every method of
+        // each documented path. A path it doesn't route is not found
+        // whatever the method, while one it does refuses the methods it
+        // doesn't serve.
         let mut api = create_api(pool, enforcer);
-        let create_request = UserCreateRequest {
-            name: "Test User".into(),
-        };
-        let create_response = create_user(&create_request, &user_auth_token, &mut api).await;
-        let update_request = UserUpdateRequest {
-            name: "Test Updated User Name".to_owned().into(),
-        };
-        let update_response = update_user(
-            create_response.id,
-            &update_request,
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
             &user_auth_token,
             &mut api,
         )
         .await;
-        assert_eq!(update_response.name, update_request.name.clone().unwrap());
-        assert_eq!(update_response.id, create_response.id);
+        let mut paths = documented
+            .iter()
+            .map(|(_, path)| path.clone())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        let mut mounted = vec![];
+        for path in paths {
+            let uri = path
+                .split('/')
+                .map(|segment| {
+                    if segment.starts_with('{') {
+                        uuid::Uuid::nil().to_string()
+                    } else {
+                        segment.to_owned()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            let mut served = vec![];
+            for method in [
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ] {
+                let request = Request::builder()
+                    .method(method.clone())
+                    .header("Authorization", &user_auth_token)
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .unwrap();
+                let response = ServiceExt::<Request<Body>>::ready(&mut api)
+                    .await
+                    .unwrap()
+                    .call(request)
+                    .await
+                    .unwrap();
+                served.push((method, response.status()));
+            }
+            if served
+                .iter()
+                .all(|(_, status)| *status == StatusCode::NOT_FOUND)
+            {
+                continue;
+            }
+            mounted.extend(
+                served
+                    .into_iter()
+                    .filter(|(_, status)| *status != StatusCode::METHOD_NOT_ALLOWED)
+                    .map(|(method, _)| (method.to_string(), path.clone())),
+            );
+        }
+        mounted.sort();
+        assert_eq!(documented, mounted);
 
-        let get_response = get_user(create_response.id, &user_auth_token, &mut api).await;
-        assert_eq!(get_response.name, update_request.name.unwrap());
-        assert_eq!(get_response.id, create_response.id);
+        // Every endpoint is a server fn, so one that isn't documented at
+        // all still shows up as a server fn that no documented path has.
+        let server_fns = server_fn_paths()
+            .map(|(path, method)| (method.to_string(), path.to_owned()))
+            .collect::<Vec<_>>();
+        for (method, path) in &mounted {
+            let server_fn = (method.clone(), server_fn_path(path));
+            assert!(
+                server_fns.contains(&server_fn),
+                "{method} {path} has no server fn at {}",
+                server_fn.1
+            );
+        }
+        for (method, path) in server_fns
+            .iter()
+            .filter(|(_, path)| path.starts_with("/api/"))
+        {
+            assert!(
+                mounted
+                    .iter()
+                    .any(|x| x.0 == *method && server_fn_path(&x.1) == *path),
+                "The server fn {method} {path} is not documented"
+            );
+        }
     }
 
     #[rstest]
     #[awt]
     #[sqlx::test]
-    async fn it_allows_user_to_be_deleted(
+    async fn it_serves_the_docs_to_whoever_the_mode_allows(
         #[future] enforcer: Arc<Enforcer>,
         #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
         #[ignore] pool: Pool<Postgres>,
     ) {
-        let mut api = create_api(pool, enforcer);
-        let create_request = UserCreateRequest {
-            name: "Test User".into(),
+        let mut enforcer = Arc::into_inner(enforcer).unwrap();
+        enforcer.enable_auto_save(false);
+        enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "docs".to_owned(),
+                "read_all".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let enforcer = Arc::new(enforcer);
+        let docs_api = |docs_mode| {
+            ApiV1::router_with_docs(Arc::new(pool.clone()), enforcer.clone(), docs_mode)
+                .into_service()
         };
-        let create_response = create_user(&create_request, &user_auth_token, &mut api).await;
+        let docs_paths = [
+            "/docs/",
+            "/docs/oauth2-redirect",
+            "/private/api.json",
+            "/private/v2/api.json",
+        ];
 
-        let _ = delete_user(create_response.id, &user_auth_token, &mut api).await;
+        let mut api = docs_api(DocsMode::Open);
+        for path in docs_paths {
+            let (status, _) = send_json("GET", path, None, "", &mut api).await;
+            assert_eq!(status, StatusCode::OK, "{path}");
+        }
+
+        let mut api = docs_api(DocsMode::Disabled);
+        for path in docs_paths {
+            let (status, _) = send_json("GET", path, None, &user_auth_token, &mut api).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{path}");
+        }
+
+        // Only the registered user is in a group that may read every
+        // resource.
+        let mut api = docs_api(DocsMode::Admin);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        for path in docs_paths {
+            let (status, _) = send_json("GET", path, None, "", &mut api).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{path}");
+            let (status, _) = send_json("GET", path, None, &user_two_auth_token, &mut api).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{path}");
+            let (status, _) = send_json("GET", path, None, &user_auth_token, &mut api).await;
+            assert_eq!(status, StatusCode::OK, "{path}");
+        }
     }
 
     #[rstest]
     #[awt]
-    #[sqlx::test(fixtures("institutions"))]
-    async fn it_allows_user_to_create_an_account(
+    #[sqlx::test]
+    async fn it_reports_the_rate_limit_on_every_api_response(
         #[future] enforcer: Arc<Enforcer>,
         #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
         #[ignore] pool: Pool<Postgres>,
     ) {
-        let mut api = create_api(pool, enforcer);
-        let create_user_request = UserCreateRequest {
-            name: "Test User".into(),
+        let mut api = ApiV1::router_with_config(
+            Arc::new(pool),
+            enforcer,
+            RouterConfig {
+                rate_limit_config: RateLimitConfig {
+                    limit: 3,
+                    window: Duration::from_secs(1),
+                },
+                ..Default::default()
+            },
+        )
+        .into_service();
+        let rate_limit = |headers: &HeaderMap| {
+            [
+                RATE_LIMIT_LIMIT_HEADER,
+                RATE_LIMIT_REMAINING_HEADER,
+                RATE_LIMIT_RESET_HEADER,
+            ]
+            .map(|header| headers[header].to_str().unwrap().parse::<u64>().unwrap())
         };
-        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
-        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
-        let create_account_request = AccountCreateRequest {
-            name: "Test Account".into(),
-            institution_id: institution.id,
+        let get = async |api: &mut RouterIntoService<Body>, auth_token: &str| {
+            let request = Request::builder()
+                .method("GET")
+                .header("Authorization", auth_token)
+                .uri("/api/users")
+                .body(Body::empty())
+                .unwrap();
+            let response = ServiceExt::<Request<Body>>::ready(api)
+                .await
+                .unwrap()
+                .call(request)
+                .await
+                .unwrap();
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, headers, body)
         };
-        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+
+        for remaining in [2, 1, 0] {
+            let (status, headers, _) = get(&mut api, &user_auth_token).await;
+            assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(rate_limit(&headers), [3, remaining, 1]);
+        }
+        let (status, headers, body) = get(&mut api, &user_auth_token).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rate_limit(&headers), [3, 0, 1]);
         assert_eq!(
-            account.institution_id,
-            create_account_request.institution_id
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            serde_json::json!({
+                "code": 4290,
+                "message": "Too many requests.",
+                "limit": 3,
+                "remaining": 0,
+                "reset": 1,
+            })
         );
-        assert_eq!(account.name, create_account_request.name);
-        assert_eq!(account.user_id, user.id);
+
+        // Clients are limited apart from each other.
+        let (status, headers, _) = get(&mut api, &user_two_auth_token).await;
+        assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rate_limit(&headers), [3, 2, 1]);
+
+        tokio::time::sleep(Duration::from_millis(1_100)).await;
+        let (status, headers, _) = get(&mut api, &user_auth_token).await;
+        assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rate_limit(&headers), [3, 2, 1]);
     }
 
     #[rstest]
     #[awt]
-    #[sqlx::test(fixtures("institutions"))]
-    async fn it_allows_user_to_see_only_their_accounts(
+    #[sqlx::test]
+    async fn it_limits_by_user_or_by_address_whatever_the_credentials(
         #[future] enforcer: Arc<Enforcer>,
         #[future] user_auth_token: String,
-        #[future] user_two_auth_token: String,
         #[ignore] pool: Pool<Postgres>,
     ) {
-        let mut api = create_api(pool, enforcer);
-        let create_user_one_request = UserCreateRequest {
-            name: "Test User 1".into(),
-        };
-        let user = create_user(&create_user_one_request, &user_auth_token, &mut api).await;
-        let create_user_two_request = UserCreateRequest {
-            name: "Test User 2".into(),
+        let mut api = ApiV1::router_with_config(
+            Arc::new(pool),
+            enforcer,
+            RouterConfig {
+                rate_limit_config: RateLimitConfig {
+                    limit: 2,
+                    window: Duration::from_secs(60),
+                },
+                ..Default::default()
+            },
+        )
+        .into_service();
+        let get = async |api: &mut RouterIntoService<Body>, peer: [u8; 4], auth_token: &str| {
+            let request = Request::builder()
+                .method("GET")
+                .header("Authorization", auth_token)
+                .uri("/api/users")
+                .extension(ConnectInfo(SocketAddr::from((peer, 443))))
+                .body(Body::empty())
+                .unwrap();
+            ServiceExt::<Request<Body>>::ready(api)
+                .await
+                .unwrap()
+                .call(request)
+                .await
+                .unwrap()
+                .status()
         };
-        let user_2 = create_user(&create_user_two_request, &user_two_auth_token, &mut api).await;
 
-        let institution_one =
-            get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
-        let user_one_account_one_create_request = AccountCreateRequest {
-            name: "User 1 Test Account 1".into(),
-            institution_id: institution_one.id,
-        };
-        let user_one_account_one = create_account(
-            &user_one_account_one_create_request,
-            &user_auth_token,
-            &mut api,
-        )
-        .await;
-        assert_eq!(user_one_account_one.user_id, user.id);
+        // Made up credentials count against the address they come from.
+        for junk in ["Bearer junk-1", "Bearer trk_junk-2"] {
+            let status = get(&mut api, [198, 51, 100, 1], junk).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+        let status = get(&mut api, [198, 51, 100, 1], "Bearer junk-3").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        let status = get(&mut api, [198, 51, 100, 2], "Bearer junk-4").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
 
-        let user_one_account_two_create_request = AccountCreateRequest {
-            name: "User 1 Test Account 2".into(),
-            institution_id: institution_one.id,
-        };
-        let user_one_account_two = create_account(
-            &user_one_account_two_create_request,
-            &user_auth_token,
-            &mut api,
-        )
-        .await;
-        assert_eq!(user_one_account_two.user_id, user.id);
+        // A user counts against their own limit from wherever they are.
+        let status = get(&mut api, [198, 51, 100, 1], &user_auth_token).await;
+        assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+        let status = get(&mut api, [198, 51, 100, 3], &user_auth_token).await;
+        assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+        let status = get(&mut api, [198, 51, 100, 4], &user_auth_token).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
 
-        let institution_two =
-            get_institution_by_name("Hana Bank", &user_two_auth_token, &mut api).await;
+    async fn get_refresh_token(username: &str) -> String {
+        let client = Client::new();
+        let client_id = var("DEX_STATIC_CLIENT_ID").expect("Failed to read `DEX_STATIC_CLIENT_ID`");
+        let client_secret =
+            var("DEX_STATIC_CLIENT_SECRET").expect("Failed to read `DEX_STATIC_CLIENT_SECRET`");
+        let response = client
+            .post("http://127.0.0.1:5556/dex/token")
+            .form(&[
+                ("grant_type", "password"),
+                ("client_id", &client_id),
+                ("client_secret", &client_secret),
+                ("username", username),
+                ("password", "password"),
+                ("scope", "openid profile email groups offline_access"),
+            ])
+            .send()
+            .await
+            .expect("Failed to get refresh token");
 
-        let user_two_account_one_create_request = AccountCreateRequest {
-            name: "User 2 Test Account 1".into(),
-            institution_id: institution_two.id,
+        let response_json = response.json::<Value>().await.unwrap();
+        response_json["refresh_token"].as_str().unwrap().to_owned()
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_refreshes_with_the_refresh_token_cookie(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let refresh_token = get_refresh_token("user@example.com").await;
+        let request = Request::builder()
+            .method("POST")
+            .header("Cookie", format!("refresh_token={refresh_token}"))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Accept", "application/json")
+            .uri("/login/refresh")
+            .body(Body::empty())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(&mut api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookie = response
+            .headers()
+            .get("Set-Cookie")
+            .expect("Missing rotated refresh token cookie")
+            .to_str()
+            .unwrap();
+        assert!(cookie.starts_with("refresh_token="));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let refresh_response = serde_json::from_slice::<RefreshResponse>(&body).unwrap();
+        assert!(refresh_response.refresh_token.is_none());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_refreshes_with_the_refresh_token_header(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let pool = Arc::new(pool);
+        let router = |header_refresh| {
+            ApiV1::router_with_config(
+                pool.clone(),
+                enforcer.clone(),
+                RouterConfig {
+                    header_refresh,
+                    ..Default::default()
+                },
+            )
+            .into_service()
         };
-        let user_two_account_one = create_account(
-            &user_two_account_one_create_request,
-            &user_two_auth_token,
-            &mut api,
-        )
-        .await;
-        assert_eq!(user_two_account_one.user_id, user_2.id);
-        let user_two_account_two_create_request = AccountCreateRequest {
-            name: "User 2 Test Account 2".into(),
-            institution_id: institution_two.id,
+        let refresh = async |api: &mut RouterIntoService<Body>, refresh_token: &str| {
+            let request = Request::builder()
+                .method("POST")
+                .header(REFRESH_TOKEN_HEADER, refresh_token)
+                .header(CLIENT_ID_HEADER, "cli")
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .header("Accept", "application/json")
+                .uri("/login/refresh")
+                .body(Body::empty())
+                .unwrap();
+            ServiceExt::<Request<Body>>::ready(api)
+                .await
+                .unwrap()
+                .call(request)
+                .await
+                .unwrap()
         };
-        let user_two_account_two = create_account(
-            &user_two_account_two_create_request,
-            &user_two_auth_token,
-            &mut api,
-        )
-        .await;
-        assert_eq!(user_two_account_two.user_id, user_2.id);
+        let refresh_token = get_refresh_token("user@example.com").await;
 
-        let user_one_accounts = get_accounts(&user_auth_token, &mut api).await;
-        let user_two_accounts = get_accounts(&user_two_auth_token, &mut api).await;
+        // The header is refused unless the flow is enabled.
+        let mut api = router(HeaderRefresh::default());
+        let response = refresh(&mut api, &refresh_token).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
-        assert_ne!(user_one_accounts.accounts, user_two_accounts.accounts);
-        assert_eq!(user_one_accounts.accounts.len(), 2);
-        assert_eq!(user_two_accounts.accounts.len(), 2);
-        assert_eq!(user_one_accounts.accounts[0], user_one_account_one);
-        assert_eq!(user_one_accounts.accounts[1], user_one_account_two);
-        assert_eq!(user_two_accounts.accounts[0], user_two_account_one);
-        assert_eq!(user_two_accounts.accounts[1], user_two_account_two);
+        // Without a cookie, the rotated token comes back in the body.
+        let mut api = router(HeaderRefresh {
+            enabled: true,
+            clients: vec!["cli".to_owned()],
+        });
+        let response = refresh(&mut api, &refresh_token).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("Set-Cookie").is_none());
+        assert_eq!(response.headers()["Cache-Control"], "no-store");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let refresh_response = serde_json::from_slice::<RefreshResponse>(&body).unwrap();
+        let rotated = refresh_response
+            .refresh_token
+            .expect("Missing rotated refresh token");
+
+        // The rotated token refreshes in turn.
+        let response = refresh(&mut api, &rotated).await;
+        assert_eq!(response.status(), StatusCode::OK);
     }
 
     #[rstest]
     #[awt]
-    #[sqlx::test(fixtures("institutions", "assets"))]
-    async fn it_allows_a_user_to_create_a_transaction(
+    #[sqlx::test]
+    async fn it_rejects_a_refresh_without_a_refresh_token(
         #[future] enforcer: Arc<Enforcer>,
-        #[future] user_auth_token: String,
         #[ignore] pool: Pool<Postgres>,
     ) {
         let mut api = create_api(pool, enforcer);
-        let create_user_request = UserCreateRequest {
-            name: "Test User".into(),
+        let request = Request::builder()
+            .method("POST")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Accept", "application/json")
+            .uri("/login/refresh")
+            .body(Body::empty())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(&mut api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_throttles_refreshes_with_an_invalid_refresh_token(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        use crate::{api::login_throttle, model::login_throttle_event::ThrottleKey};
+
+        let pool = Arc::new(pool);
+        // Without a trusted proxy, addresses are those of the peers.
+        let config = LoginThrottleConfig::default();
+        let mut api = ApiV1::router_with_config(
+            pool.clone(),
+            enforcer,
+            RouterConfig {
+                login_throttle_config: config,
+                ..Default::default()
+            },
+        )
+        .into_service();
+        let refresh = async |api: &mut RouterIntoService<Body>, ip: &str, refresh_token: &str| {
+            let peer = SocketAddr::new(ip.parse().unwrap(), 443);
+            let request = Request::builder()
+                .method("POST")
+                .header("Cookie", format!("refresh_token={refresh_token}"))
+                .extension(ConnectInfo(peer))
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .header("Accept", "application/json")
+                .uri("/login/refresh")
+                .body(Body::empty())
+                .unwrap();
+            ServiceExt::<Request<Body>>::ready(api)
+                .await
+                .unwrap()
+                .call(request)
+                .await
+                .unwrap()
+                .status()
         };
-        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
-        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+
+        let throttled = login_throttle::throttled();
+        for _ in 0..config.failures {
+            assert_eq!(
+                refresh(&mut api, "203.0.113.7", "invalid").await,
+                StatusCode::FORBIDDEN
+            );
+        }
+        assert_eq!(
+            refresh(&mut api, "203.0.113.7", "invalid").await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        // The address failed as often, so it waits with any token.
+        assert_eq!(
+            refresh(&mut api, "203.0.113.7", "another").await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert!(login_throttle::throttled() >= throttled + 2);
+
+        // A user elsewhere refreshes as usual.
+        let refresh_token = get_refresh_token("user@example.com").await;
+        assert_eq!(
+            refresh(&mut api, "198.51.100.20", &refresh_token).await,
+            StatusCode::OK
+        );
+
+        let events = sqlx::query_as::<_, (String, ThrottleKey, i32)>(
+            "SELECT endpoint, key_kind, failures FROM login_throttle_event ORDER BY id",
+        )
+        .fetch_all(&*pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                ("/login/refresh".to_owned(), ThrottleKey::Ip, 5),
+                ("/login/refresh".to_owned(), ThrottleKey::RefreshToken, 5),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn it_only_accepts_a_header_refresh_when_enabled_for_the_client() {
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            HeaderRefresh::default().refresh_token(&headers),
+            Ok(None)
+        ));
+
+        headers.insert(REFRESH_TOKEN_HEADER, "token".parse().unwrap());
+        headers.insert(CLIENT_ID_HEADER, "cli".parse().unwrap());
+        assert!(matches!(
+            HeaderRefresh::default().refresh_token(&headers),
+            Err(ApiError::Forbidden)
+        ));
+
+        let header_refresh = HeaderRefresh {
+            enabled: true,
+            clients: vec!["mobile".to_owned()],
+        };
+        assert!(matches!(
+            header_refresh.refresh_token(&headers),
+            Err(ApiError::Forbidden)
+        ));
+
+        let header_refresh = HeaderRefresh {
+            enabled: true,
+            clients: vec!["cli".to_owned()],
+        };
+        assert_eq!(
+            header_refresh.refresh_token(&headers).unwrap().as_deref(),
+            Some("token")
+        );
+    }
+
+    #[rstest]
+    #[sqlx::test]
+    #[awt]
+    async fn it_creates_a_user(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+
+        let create_response = create_user(&create_request, &user_auth_token, &mut api).await;
+        assert_eq!(create_response.name, create_request.name);
+
+        let get_response = get_user(create_response.id, &user_auth_token, &mut api).await;
+        assert_eq!(get_response.name, create_request.name);
+        assert_eq!(&get_response.email, "user@example.com");
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_records_whether_registering_emails_were_verified(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), admin_enforcer().await);
+        let create_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let created = create_user(&create_request, &user_auth_token, &mut api).await;
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/users/{}", created.id),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["email_verified_at_registration"], true);
+
+        // Only admins see it.
+        let mut api = create_api(pool, enforcer);
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/users/{}", created.id),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.get("email_verified_at_registration").is_none());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_does_not_register_unverified_emails(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        // The service refuses them whatever the policies grant.
+        let permission_set = PermissionSet {
+            read_level: ReadLevel::NoPermission,
+            create_level: CreateLevel::Create,
+            update_level: UpdateLevel::NoPermission,
+            delete_level: DeleteLevel::NoPermission,
+        };
+        let service = UserServiceFactory::build(None, Arc::new(pool.clone()), permission_set);
+        for email_verified in [Some(false), None] {
+            let result = service
+                .create(UserCreate {
+                    name: "Test User".into(),
+                    email: "unverified@example.com".into(),
+                    sub: "unverified".into(),
+                    iss: "iss".into(),
+                    email_verified,
+                })
+                .await;
+            assert!(
+                matches!(result, Err(ServiceError::EmailNotVerified)),
+                "{result:?}"
+            );
+        }
+        let error = ApiErrorResponse::from(&ApiError::from(ServiceError::EmailNotVerified));
+        assert_eq!(error.code, 4031);
+        assert_eq!(
+            ApiError::from(ServiceError::EmailNotVerified).status(),
+            StatusCode::FORBIDDEN
+        );
+
+        // Dex only issues tokens with verified emails.
+        if !MockIssuer::enabled() {
+            return;
+        }
+        let unverified_token = async |groups: &[&str]| {
+            let mut claims = MockClaims::new("unverified", "unverified@example.com");
+            claims.groups = groups.iter().map(|x| x.to_string()).collect();
+            claims.extra.insert("email_verified".into(), false.into());
+            MockIssuer::shared().await.token(&claims)
+        };
+        let mut api = create_api(pool.clone(), enforcer);
+        let body = serde_json::json!({ "name": "Test User" });
+
+        // The middleware grants a token without groups nothing to register
+        // with.
+        let (status, body_one) = send_json(
+            "POST",
+            "/api/users",
+            Some(body.clone()),
+            &unverified_token(&[]).await,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body_one["code"], 4030);
+
+        // A token claiming the group itself gets past the policies, and is
+        // refused by the service.
+        let (status, body_two) = send_json(
+            "POST",
+            "/api/users",
+            Some(body),
+            &unverified_token(&["unregistered_user"]).await,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body_two["code"], 4031);
+
+        let users =
+            sqlx::query_scalar::<_, i64>(r#"SELECT COUNT(*) FROM "user" WHERE sub = 'unverified'"#)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(users, 0);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_allows_user_to_update_their_name(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let create_response = create_user(&create_request, &user_auth_token, &mut api).await;
+        let update_request = UserUpdateRequest {
+            name: "Test Updated User Name".to_owned().into(),
+            email: None,
+        };
+        let update_response = update_user(
+            create_response.id,
+            &update_request,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(update_response.name, update_request.name.clone().unwrap());
+        assert_eq!(update_response.id, create_response.id);
+
+        let get_response = get_user(create_response.id, &user_auth_token, &mut api).await;
+        assert_eq!(get_response.name, update_request.name.unwrap());
+        assert_eq!(get_response.id, create_response.id);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_allows_user_to_be_deleted(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let create_response = create_user(&create_request, &user_auth_token, &mut api).await;
+
+        let _ = delete_user(create_response.id, &user_auth_token, &mut api).await;
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions"))]
+    async fn it_allows_user_to_create_an_account(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
         let create_account_request = AccountCreateRequest {
             name: "Test Account".into(),
             institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
         };
         let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
-        let asset = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
-        let create_request = TransactionCreateRequest {
-            posted_at: Utc::now(),
-            description: "A test transaction".to_owned().into(),
-            account_id: account.id,
-            asset_id: asset.id,
-            quantity: 1_000_000,
+        assert_eq!(
+            account.institution_id,
+            create_account_request.institution_id
+        );
+        assert_eq!(account.name, create_account_request.name);
+        assert_eq!(account.user_id, user.id);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions"))]
+    async fn it_allows_user_to_see_only_their_accounts(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_one_request = UserCreateRequest {
+            name: "Test User 1".into(),
         };
-        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        let user = create_user(&create_user_one_request, &user_auth_token, &mut api).await;
+        let create_user_two_request = UserCreateRequest {
+            name: "Test User 2".into(),
+        };
+        let user_2 = create_user(&create_user_two_request, &user_two_auth_token, &mut api).await;
 
-        assert_eq!(create_request, transaction);
+        let institution_one =
+            get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let user_one_account_one_create_request = AccountCreateRequest {
+            name: "User 1 Test Account 1".into(),
+            institution_id: institution_one.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let user_one_account_one = create_account(
+            &user_one_account_one_create_request,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(user_one_account_one.user_id, user.id);
+
+        let user_one_account_two_create_request = AccountCreateRequest {
+            name: "User 1 Test Account 2".into(),
+            institution_id: institution_one.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let user_one_account_two = create_account(
+            &user_one_account_two_create_request,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(user_one_account_two.user_id, user.id);
+
+        let institution_two =
+            get_institution_by_name("Hana Bank", &user_two_auth_token, &mut api).await;
+
+        let user_two_account_one_create_request = AccountCreateRequest {
+            name: "User 2 Test Account 1".into(),
+            institution_id: institution_two.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let user_two_account_one = create_account(
+            &user_two_account_one_create_request,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(user_two_account_one.user_id, user_2.id);
+        let user_two_account_two_create_request = AccountCreateRequest {
+            name: "User 2 Test Account 2".into(),
+            institution_id: institution_two.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let user_two_account_two = create_account(
+            &user_two_account_two_create_request,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(user_two_account_two.user_id, user_2.id);
+
+        let user_one_accounts = get_accounts(&user_auth_token, &mut api).await;
+        let user_two_accounts = get_accounts(&user_two_auth_token, &mut api).await;
+
+        assert_ne!(user_one_accounts.accounts, user_two_accounts.accounts);
+        assert_eq!(user_one_accounts.accounts.len(), 2);
+        assert_eq!(user_two_accounts.accounts.len(), 2);
+        assert_eq!(user_one_accounts.accounts[0], user_one_account_one);
+        assert_eq!(user_one_accounts.accounts[1], user_one_account_two);
+        assert_eq!(user_two_accounts.accounts[0], user_two_account_one);
+        assert_eq!(user_two_accounts.accounts[1], user_two_account_two);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_allows_a_user_to_create_a_transaction(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Test Account".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let asset = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: "A test transaction".to_owned().into(),
+            account_id: account.id,
+            asset_id: asset.id,
+            quantity: 1_000_000.into(),
+            notes: None,
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+
+        assert_eq!(create_request, transaction);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets", "asset_prices"))]
+    async fn it_converts_transactions_for_display(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Test Account".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let usd = get_asset_by_symbol(&user_auth_token, &mut api, "USD").await;
+        let jpy = get_asset_by_symbol(&user_auth_token, &mut api, "JPY").await;
+
+        let mut create = async |posted_at: &str, asset_id, quantity| {
+            let create_request = TransactionCreateRequest {
+                posted_at: posted_at.parse().unwrap(),
+                description: None,
+                account_id: account.id,
+                asset_id,
+                quantity,
+                notes: None,
+                category: None,
+            };
+            create_transaction(&create_request, &user_auth_token, &mut api)
+                .await
+                .id
+        };
+        let exact = create("2025-01-10T00:00:00Z", usd.id, 100.into()).await;
+        let stale = create("2025-01-05T00:00:00Z", usd.id, 100.into()).await;
+        let before_first_rate = create("2024-12-31T00:00:00Z", usd.id, 100.into()).await;
+        let unpriced = create("2025-01-10T00:00:00Z", jpy.id, 100.into()).await;
+        // 1,400.5 KRW, which rounds to the even won.
+        let half = create("2025-01-05T00:00:00Z", usd.id, 1.into()).await;
+
+        let request = Request::builder()
+            .method("GET")
+            .header("Authorization", &user_auth_token)
+            .header("Accept", "application/json")
+            .uri("/api/transactions?convert_to=KRW")
+            .body(Body::empty())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(&mut api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let response = serde_json::from_slice::<TransactionGetListResponse>(&body).unwrap();
+        let row = |id| {
+            response
+                .transactions
+                .iter()
+                .find(|x| x.id == id)
+                .map(|x| (x.converted_quantity, x.rate_used.clone()))
+                .unwrap()
+        };
+
+        assert_eq!(
+            row(exact),
+            (Some(Decimal::from(145_000)), Some("1450".to_owned()))
+        );
+        assert_eq!(
+            row(stale),
+            (Some(Decimal::from(140_050)), Some("1400.5".to_owned()))
+        );
+        assert_eq!(row(before_first_rate), (None, None));
+        assert_eq!(row(unpriced), (None, None));
+        assert_eq!(
+            row(half),
+            (Some(Decimal::from(1_400)), Some("1400.5".to_owned()))
+        );
+        assert_eq!(response.missing_rates, Some(2));
+        assert_eq!(response.rounding, Some(RoundingPolicy::Bankers));
+    }
+
+    #[sqlx::test]
+    async fn it_rounds_in_queries_as_the_server_does(pool: Pool<Postgres>) {
+        let mut values = [
+            "2.5",
+            "-2.5",
+            "3.5",
+            "-3.5",
+            "0.5",
+            "-0.5",
+            "1.005",
+            "-1.005",
+            "1.015",
+            "2.4999999999",
+            "2.5000000001",
+            "-2.5000000001",
+            "7",
+        ]
+        .map(|x| x.parse::<Decimal>().unwrap())
+        .to_vec();
+        // Steps of 0.035, which land on halves at every precision.
+        values.extend(
+            (-2_000_i64..2_000)
+                .step_by(7)
+                .map(|x| Decimal::new(x * 5, 3)),
+        );
+
+        for policy in RoundingPolicy::ALL {
+            for decimals in 0..3_u32 {
+                let rounded = sqlx::query_scalar::<_, Decimal>(
+                    r#"
+                    SELECT round_amount(v.value, $2, $3)
+                    FROM UNNEST($1::NUMERIC[]) WITH ORDINALITY AS v (value, i)
+                    ORDER BY v.i
+                    "#,
+                )
+                .bind(&values)
+                .bind(decimals as i32)
+                .bind(policy.name())
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+                let expected = values
+                    .iter()
+                    .map(|x| policy.round(*x, decimals))
+                    .collect::<Vec<_>>();
+                assert_eq!(rounded, expected, "{policy:?} to {decimals} places");
+                assert!(rounded.iter().all(|x| x.scale() == decimals));
+            }
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_renders_transaction_notes_as_sanitized_html(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Test Account".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let asset = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let notes = "# Receipt\n\n<script>alert(1)</script>\n\n\
+            [click](javascript:alert(1)) <img src=x onerror=alert(1)>";
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: "Groceries".to_owned().into(),
+            account_id: account.id,
+            asset_id: asset.id,
+            quantity: 1_000.into(),
+            notes: notes.to_owned().into(),
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        assert_eq!(create_request, transaction);
+
+        let request = Request::builder()
+            .method("GET")
+            .header("Authorization", &user_auth_token)
+            .header("Accept", "application/json")
+            .uri(format!("/api/transactions/{}/notes/html", transaction.id.0))
+            .body(Body::empty())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(&mut api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = serde_json::from_slice::<NotesHtmlResponse>(&body)
+            .unwrap()
+            .html;
+        assert!(html.contains("<h1>Receipt</h1>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("onerror"));
+
+        let (status, _) = post_json(
+            "/api/transactions",
+            serde_json::to_value(TransactionCreateRequest {
+                notes: "a".repeat(MAX_NOTES_BYTES + 1).into(),
+                category: None,
+                ..create_request
+            })
+            .unwrap(),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_clears_notes_updated_to_an_empty_string(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Test Account".into(),
+            institution_id: institution.id,
+            notes: "Joint account".to_owned().into(),
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let asset = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: "Groceries".to_owned().into(),
+            account_id: account.id,
+            asset_id: asset.id,
+            quantity: 1_000.into(),
+            notes: "Split with a friend".to_owned().into(),
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+
+        // Leaving notes out keeps them.
+        let (status, body) = send_json(
+            "PATCH",
+            &format!("/api/transactions/{}", transaction.id.0),
+            Some(serde_json::json!({ "description": "Market" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["notes"], "Split with a friend");
+
+        let (status, body) = send_json(
+            "PATCH",
+            &format!("/api/transactions/{}", transaction.id.0),
+            Some(serde_json::json!({ "notes": "" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["notes"].is_null());
+
+        let (status, body) = send_json(
+            "PATCH",
+            &format!("/api/accounts/{}", account.id.0),
+            Some(serde_json::json!({ "name": "Test Account", "notes": "" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["notes"].is_null());
+    }
+
+    #[rstest]
+    #[case(100)]
+    #[case(30)]
+    #[case(7)]
+    #[awt]
+    #[sqlx::test(fixtures("institution_pages"))]
+    async fn it_pages_forward_then_backward_with_the_cursor_page_size(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[case] page_size: i64,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+
+        let mut page = async |query: String| {
+            let (status, body) = send_json(
+                "GET",
+                &format!("/api/institutions?{query}"),
+                None,
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            let ids = body["institutions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["id"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>();
+            let cursor = |key: &str| body[key].as_str().map(str::to_owned);
+            (ids, cursor("next_cursor"), cursor("prev_cursor"))
+        };
+
+        let mut forward = vec![];
+        let (mut ids, mut next_cursor, mut prev_cursor) =
+            page(format!("max_items={page_size}")).await;
+        assert!(prev_cursor.is_none());
+        while !ids.is_empty() {
+            forward.push((ids, prev_cursor));
+            let cursor = next_cursor.take().unwrap();
+            (ids, next_cursor, prev_cursor) = page(format!("cursor={cursor}")).await;
+        }
+        assert!(next_cursor.is_none());
+        assert_eq!(forward.len() as i64, (250 + page_size - 1) / page_size);
+        let mut seen = forward
+            .iter()
+            .flat_map(|(ids, _)| ids.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(seen.len(), 250);
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 250);
+
+        let (_, mut prev_cursor) = forward.last().cloned().unwrap();
+        for (expected, _) in forward.iter().rev().skip(1) {
+            let cursor = prev_cursor.take().unwrap();
+            let (ids, _, cursor) = page(format!("cursor={cursor}")).await;
+            assert_eq!(&ids, expected);
+            prev_cursor = cursor;
+        }
+        assert!(prev_cursor.is_none());
+
+        let (_, next_cursor, _) = page(format!("max_items={page_size}")).await;
+        let next_cursor = next_cursor.unwrap();
+        let (status, _) = send_json(
+            "GET",
+            &format!(
+                "/api/institutions?cursor={next_cursor}&max_items={}",
+                page_size / 2
+            ),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    /// A cursor at offset 100, minted as the offset alone with the key of
+    /// `cursor_v1.sql` before cursors had a version.
+    const V1_CURSOR: &str = "QUIPAAABAgMEBQYHCAkKCwTg7DV-O4W2IrVa_xD9he-SfNVt11G_aA";
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institution_pages", "cursor_v1"))]
+    async fn it_pages_on_from_cursors_minted_before_cursors_had_a_version(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+
+        let mut page = async |query: String| {
+            let (status, body) = send_json(
+                "GET",
+                &format!("/api/institutions?{query}"),
+                None,
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            let ids = body["institutions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["id"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>();
+            let cursor = |key: &str| body[key].as_str().map(str::to_owned);
+            (ids, cursor("next_cursor"), cursor("prev_cursor"))
+        };
+
+        let mut pages = vec![];
+        let (mut ids, mut next_cursor, _) = page(String::new()).await;
+        while !ids.is_empty() {
+            pages.push(ids);
+            let cursor = next_cursor.take().unwrap();
+            (ids, next_cursor, _) = page(format!("cursor={cursor}")).await;
+        }
+        assert_eq!(pages.len(), 3);
+
+        // The old cursor carries no page size, so it lands where a walk of
+        // the default page size does, and the cursors issued along with it
+        // page on from there.
+        let (ids, next_cursor, prev_cursor) = page(format!("cursor={V1_CURSOR}")).await;
+        assert_eq!(ids, pages[1]);
+        let (ids, _, _) = page(format!("cursor={}", next_cursor.unwrap())).await;
+        assert_eq!(ids, pages[2]);
+        let (ids, _, _) = page(format!("cursor={}", prev_cursor.unwrap())).await;
+        assert_eq!(ids, pages[0]);
+        let (ids, _, _) = page(format!("cursor={V1_CURSOR}&max_items=100")).await;
+        assert_eq!(ids, pages[1]);
+    }
+
+    const SHINHAN: &str = "00000000-0000-0000-0000-0000000000a1";
+    const SHINHAN_SEOUL: &str = "00000000-0000-0000-0000-0000000000b1";
+    const SHINHAN_BUSAN: &str = "00000000-0000-0000-0000-0000000000b2";
+    const SHINHAN_GANGNAM: &str = "00000000-0000-0000-0000-0000000000c1";
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets", "institution_tree"))]
+    async fn it_rolls_up_accounts_across_an_institution_tree(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let usd = get_asset_by_symbol(&user_auth_token, &mut api, "USD").await;
+
+        let mut open = async |institution: &str, auth_token: &str, quantities: &[i64]| {
+            let create_account_request = AccountCreateRequest {
+                name: "Test Account".into(),
+                institution_id: InstitutionId(institution.parse().unwrap()),
+                notes: None,
+                default_asset_id: None,
+            };
+            let account = create_account(&create_account_request, auth_token, &mut api).await;
+            for quantity in quantities {
+                let create_request = TransactionCreateRequest {
+                    posted_at: Utc::now(),
+                    description: None,
+                    account_id: account.id,
+                    asset_id: krw.id,
+                    quantity: (*quantity).into(),
+                    notes: None,
+                    category: None,
+                };
+                let _ = create_transaction(&create_request, auth_token, &mut api).await;
+            }
+            account.id
+        };
+        let root_account = open(SHINHAN, &user_auth_token, &[1_000]).await;
+        open(SHINHAN_SEOUL, &user_auth_token, &[200, -50]).await;
+        open(SHINHAN_GANGNAM, &user_auth_token, &[30]).await;
+        open(SHINHAN_GANGNAM, &user_two_auth_token, &[9_999]).await;
+
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: None,
+            account_id: root_account,
+            asset_id: usd.id,
+            quantity: 7.into(),
+            notes: None,
+            category: None,
+        };
+        let _ = create_transaction(&create_request, &user_auth_token, &mut api).await;
+
+        let mut rollup = async |institution: &str| {
+            let (status, body) = send_json(
+                "GET",
+                &format!("/api/institutions/{institution}/rollup"),
+                None,
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            serde_json::from_value::<RollupResponse>(body).unwrap()
+        };
+
+        let root = rollup(SHINHAN).await;
+        assert_eq!(root.institution_count, 4);
+        assert_eq!(root.account_count, 3);
+        let krw_balance = root.balances.iter().find(|x| x.asset_id == krw.id).unwrap();
+        assert_eq!(
+            (krw_balance.quantity, krw_balance.transaction_count),
+            (Decimal::from(1_180), 4)
+        );
+        let usd_balance = root.balances.iter().find(|x| x.asset_id == usd.id).unwrap();
+        assert_eq!(
+            (usd_balance.quantity, usd_balance.transaction_count),
+            (Decimal::from(7), 1)
+        );
+
+        let seoul = rollup(SHINHAN_SEOUL).await;
+        assert_eq!((seoul.institution_count, seoul.account_count), (2, 2));
+        let krw_balance = seoul
+            .balances
+            .iter()
+            .find(|x| x.asset_id == krw.id)
+            .unwrap();
+        assert_eq!(krw_balance.quantity, Decimal::from(180));
+
+        let gangnam = rollup(SHINHAN_GANGNAM).await;
+        assert_eq!((gangnam.institution_count, gangnam.account_count), (1, 1));
+        assert_eq!(gangnam.balances.len(), 1);
+        assert_eq!(gangnam.balances[0].quantity, Decimal::from(30));
+
+        let busan = rollup(SHINHAN_BUSAN).await;
+        assert_eq!((busan.institution_count, busan.account_count), (1, 0));
+        assert!(busan.balances.is_empty());
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/institutions/{SHINHAN}?expand=children"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let institution = serde_json::from_value::<InstitutionGetResponse>(body).unwrap();
+        let mut children = institution
+            .children
+            .unwrap()
+            .into_iter()
+            .map(|x| x.name)
+            .collect::<Vec<_>>();
+        children.sort();
+        assert_eq!(children, ["Shinhan Bank Busan", "Shinhan Bank Seoul"]);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/institutions/{SHINHAN}"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.get("children").is_none());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "institution_tree"))]
+    async fn it_rejects_an_institution_cycle(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut enforcer = Arc::into_inner(enforcer).unwrap();
+        enforcer.enable_auto_save(false);
+        enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "institutions".to_owned(),
+                "update".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let mut api = create_api(pool, Arc::new(enforcer));
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+
+        let mut reparent = async |institution: &str, parent: &str| {
+            send_json(
+                "PATCH",
+                &format!("/api/institutions/{institution}"),
+                Some(serde_json::json!({ "parent_id": parent })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await
+        };
+
+        for (institution, parent) in [
+            (SHINHAN, SHINHAN_GANGNAM),
+            (SHINHAN_SEOUL, SHINHAN_GANGNAM),
+            (SHINHAN_SEOUL, SHINHAN_SEOUL),
+        ] {
+            let (status, body) = reparent(institution, parent).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(body["code"], 4220);
+        }
+
+        let (status, body) = reparent(SHINHAN_BUSAN, SHINHAN_GANGNAM).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["parent_id"], SHINHAN_GANGNAM);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "institution_tree"))]
+    async fn it_counts_the_institutions_below_a_moved_one_towards_the_depth(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        // A hierarchy of three levels of its own.
+        sqlx::query(
+            r#"
+            INSERT INTO institution (id, name, parent_id)
+            VALUES ('00000000-0000-0000-0000-0000000000d1', 'Toss Bank Seoul', NULL),
+                ('00000000-0000-0000-0000-0000000000d2', 'Toss Bank Jongno', '00000000-0000-0000-0000-0000000000d1'),
+                ('00000000-0000-0000-0000-0000000000d3', 'Toss Bank Insadong', '00000000-0000-0000-0000-0000000000d2')
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let mut enforcer = Arc::into_inner(enforcer).unwrap();
+        enforcer.enable_auto_save(false);
+        enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "institutions".to_owned(),
+                "update".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let mut api = create_api(pool, Arc::new(enforcer));
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+
+        let mut reparent = async |parent: &str| {
+            send_json(
+                "PATCH",
+                "/api/institutions/00000000-0000-0000-0000-0000000000d1",
+                Some(serde_json::json!({ "parent_id": parent })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await
+        };
+
+        // Under the third level its last one would be the sixth.
+        let (status, body) = reparent(SHINHAN_GANGNAM).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["message"], "The institution hierarchy is too deep.");
+
+        let (status, body) = reparent(SHINHAN_BUSAN).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["parent_id"], SHINHAN_BUSAN);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_falls_back_to_the_default_asset_of_the_institution(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut enforcer = Arc::into_inner(enforcer).unwrap();
+        enforcer.enable_auto_save(false);
+        for (resource, action) in [("institutions", "update"), ("assets", "delete")] {
+            enforcer
+                .add_policy(vec![
+                    Group::User.as_policy_subject().to_owned(),
+                    resource.to_owned(),
+                    action.to_owned(),
+                ])
+                .await
+                .unwrap();
+        }
+        let mut api = create_api(pool, Arc::new(enforcer));
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let usd = get_asset_by_symbol(&user_auth_token, &mut api, "USD").await;
+        let create = async |name: &str,
+                            default_asset_id: Option<AssetId>,
+                            api: &mut RouterIntoService<Body>| {
+            create_account(
+                &AccountCreateRequest {
+                    name: name.into(),
+                    institution_id: institution.id,
+                    notes: None,
+                    default_asset_id,
+                },
+                &user_auth_token,
+                api,
+            )
+            .await
+        };
+
+        // Neither the account nor the institution has a default.
+        let no_default = create("No Default", None, &mut api).await;
+        assert_eq!(no_default.default_asset_id, None);
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/accounts/{}", no_default.id),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.get("effective_default_asset_id").is_none());
+
+        let (status, body) = send_json(
+            "PATCH",
+            &format!("/api/institutions/{}", institution.id.0),
+            Some(serde_json::json!({ "default_asset_id": krw.id })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["default_asset_id"], krw.id.0.to_string());
+
+        // An account without a default shows the one of its institution.
+        let (_, body) = send_json(
+            "GET",
+            &format!("/api/accounts/{}", no_default.id),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert!(body.get("default_asset_id").is_none());
+        assert_eq!(body["effective_default_asset_id"], krw.id.0.to_string());
+
+        // A new account takes the default of its institution.
+        let inherited = create("Inherited", None, &mut api).await;
+        assert_eq!(inherited.default_asset_id, Some(krw.id));
+
+        // The default of the account wins over the one of its institution.
+        let own_default = create("Own Default", Some(usd.id), &mut api).await;
+        assert_eq!(own_default.default_asset_id, Some(usd.id));
+        let (_, body) = send_json(
+            "GET",
+            &format!("/api/accounts/{}", own_default.id),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(body["effective_default_asset_id"], usd.id.0.to_string());
+
+        // Deleting the asset clears it as a default everywhere.
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/assets/{}", krw.id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, body) = send_json(
+            "GET",
+            &format!("/api/institutions/{}", institution.id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert!(body.get("default_asset_id").is_none());
+        let (_, body) = send_json(
+            "GET",
+            &format!("/api/accounts/{}", inherited.id),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert!(body.get("default_asset_id").is_none());
+        assert!(body.get("effective_default_asset_id").is_none());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions"))]
+    async fn it_creates_the_accounts_of_a_template_at_once(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let toss = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/account-templates",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let templates = serde_json::from_value::<AccountTemplateGetListResponse>(body).unwrap();
+        let everyday = templates
+            .templates
+            .iter()
+            .find(|x| x.name == "everyday")
+            .unwrap();
+        let names = everyday
+            .accounts
+            .iter()
+            .map(|x| x.name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Checking", "Savings", "Credit Card"]);
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/accounts/from-template",
+            Some(serde_json::json!({ "institution_id": toss.id, "template": "everyday" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let created = serde_json::from_value::<FromTemplateResponse>(body).unwrap();
+        let created_names = created
+            .accounts
+            .iter()
+            .map(|x| x.name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(created_names, names);
+        assert!(created.accounts.iter().all(|x| x.institution_id == toss.id));
+        assert_eq!(
+            get_accounts(&user_auth_token, &mut api)
+                .await
+                .accounts
+                .len(),
+            3
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions"))]
+    async fn it_creates_none_of_a_template_when_one_account_exists(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let toss = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let _ = create_account(
+            &AccountCreateRequest {
+                name: "Savings".into(),
+                institution_id: toss.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/accounts/from-template",
+            Some(serde_json::json!({ "institution_id": toss.id, "template": "everyday" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], 4092);
+        assert_eq!(body["errors"].as_array().unwrap().len(), 1);
+        assert_eq!(body["errors"][0]["name"], "Savings");
+        // The accounts of the template that didn't exist weren't created.
+        let accounts = get_accounts(&user_auth_token, &mut api).await.accounts;
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].name, "Savings");
+
+        // The same names at another institution don't conflict.
+        let other = get_institution_by_name("Hana Bank", &user_auth_token, &mut api).await;
+        let (status, _) = send_json(
+            "POST",
+            "/api/accounts/from-template",
+            Some(serde_json::json!({ "institution_id": other.id, "template": "everyday" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions"))]
+    async fn it_rejects_an_unknown_account_template(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let toss = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/accounts/from-template",
+            Some(serde_json::json!({ "institution_id": toss.id, "template": "pirate" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Unknown account template.");
+        assert!(
+            get_accounts(&user_auth_token, &mut api)
+                .await
+                .accounts
+                .is_empty()
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_streams_the_shell_before_lists_resolve(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        // Without a session the page redirects before it streams.
+        let request = Request::builder()
+            .method("GET")
+            .header("Accept", "text/html")
+            .header("Cookie", "refresh_token=token")
+            .uri("/accounts")
+            .body(Body::empty())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(&mut api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body();
+        let first_chunk = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        let first_chunk = String::from_utf8_lossy(&first_chunk);
+        assert!(first_chunk.starts_with("<!DOCTYPE html>"));
+        assert!(first_chunk.contains("/pkg/treasury.css"));
+
+        let listings = AccountApi::routes();
+        let modes = listings
+            .iter()
+            .map(|x| (x.path(), matches!(x.mode(), SsrMode::OutOfOrder)))
+            .collect::<Vec<_>>();
+        assert_eq!(modes, [("/", true), ("/{id}", false)]);
+
+        let listings = generate_route_list_with_exclusions(App, None);
+        let streamed = listings
+            .iter()
+            .filter(|x| {
+                ["/home", "/accounts", "/institutions", "/transactions"]
+                    .iter()
+                    .any(|page| x.path().starts_with(page))
+            })
+            .map(|x| (x.path(), matches!(x.mode(), SsrMode::OutOfOrder)))
+            .collect::<Vec<_>>();
+        assert!(streamed.len() >= 4, "{streamed:?}");
+        assert!(
+            streamed.iter().all(|(_, out_of_order)| *out_of_order),
+            "{streamed:?}"
+        );
+    }
+
+    /// A list that resolves after the shell is sent, loaded into `Suspense`
+    /// the way the data heavy pages load theirs.
+    #[component]
+    fn StreamedRows() -> impl IntoView {
+        let rows = Resource::new(
+            || (),
+            |_| async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                vec!["First row".to_owned(), "Second row".to_owned()]
+            },
+        );
+        view! {
+            <Suspense fallback=|| view! { <p data-fallback>"Loading..."</p> }>
+                <ul>
+                    {move || rows.get().map(|rows| {
+                        rows.into_iter().map(|row| view! { <li data-row>{row}</li> }).collect_view()
+                    })}
+                </ul>
+            </Suspense>
+        }
+    }
+
+    #[tokio::test]
+    async fn it_streams_list_rows_after_the_suspense_fallback() {
+        use leptos_router::{
+            components::{Route, Router, Routes},
+            path,
+        };
+
+        let app = || {
+            view! {
+                <Router>
+                    <Routes fallback=|| ()>
+                        <Route path=path!("/rows") view=StreamedRows ssr=SsrMode::OutOfOrder/>
+                    </Routes>
+                </Router>
+            }
+        };
+        let leptos_options = LeptosOptions::builder().output_name("treasury").build();
+        let routes = generate_route_list_with_exclusions(app, None);
+        let mut router = axum::Router::new()
+            .leptos_routes(&leptos_options, routes, move || {
+                view! { <!DOCTYPE html><html><body>{app()}</body></html> }
+            })
+            .with_state(leptos_options)
+            .into_service::<Body>();
+        let request = Request::builder()
+            .method("GET")
+            .header("Accept", "text/html")
+            .uri("/rows")
+            .body(Body::empty())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(&mut router)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut body = response.into_body();
+        let first_frame = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        let first_frame = String::from_utf8_lossy(&first_frame);
+        assert!(first_frame.starts_with("<!DOCTYPE html>"));
+        assert!(first_frame.contains("data-fallback"), "{first_frame}");
+        assert!(!first_frame.contains("data-row"), "{first_frame}");
+
+        let mut later_frames = String::new();
+        while let Some(frame) = body.frame().await {
+            if let Ok(data) = frame.unwrap().into_data() {
+                later_frames.push_str(&String::from_utf8_lossy(&data));
+            }
+        }
+        assert!(later_frames.contains("First row"), "{later_frames}");
+        assert!(later_frames.contains("Second row"), "{later_frames}");
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_sends_visitors_without_a_session_to_log_in(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        for (uri, next) in [
+            ("/accounts", "%2Faccounts"),
+            (
+                "/transactions/1?tab=splits",
+                "%2Ftransactions%2F1%3Ftab%3Dsplits",
+            ),
+            ("/welcome", "%2Fwelcome"),
+        ] {
+            let (status, headers, _) = get_page(uri, None, &mut api).await;
+            assert_eq!(status, StatusCode::FOUND, "{uri}");
+            assert_eq!(headers[LOCATION], format!("/home?next={next}"));
+        }
+
+        // With a session the page waits for the browser to refresh it,
+        // rendering neither the page nor a redirect.
+        let (status, headers, body) = get_page("/accounts", Some("token"), &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(headers.get(LOCATION).is_none());
+        assert!(body.contains("data-session=\"pending\""));
+
+        // The login entry itself is not guarded.
+        let (status, _, _) = get_page("/home?next=%2Faccounts", None, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_sends_visitors_with_a_session_home_from_the_oauth_redirect(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let (status, headers, _) = get_page("/oauth2-redirect", Some("token"), &mut api).await;
+        assert_eq!(status, StatusCode::FOUND);
+        assert_eq!(headers[LOCATION], "/home");
+
+        let (status, headers, body) = get_page("/oauth2-redirect", None, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(headers.get(LOCATION).is_none());
+        assert!(!body.contains("data-session=\"pending\""));
+
+        // A sign in under way is finished, even over an old session.
+        let (status, headers, _) = get_page(
+            "/oauth2-redirect?code=code&state=state",
+            Some("token"),
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(headers.get(LOCATION).is_none());
+    }
+
+    #[rstest]
+    fn it_parses_and_displays_groups() {
+        for (name, group) in [
+            ("user", Group::User),
+            ("unregistered_user", Group::UnregisteredUser),
+            ("admin", Group::Admin),
+            ("auditors", Group::Custom("auditors".into())),
+        ] {
+            assert_eq!(Group::from(name), group);
+            assert_eq!(group.to_string(), name);
+            assert_eq!(serde_json::to_value(&group).unwrap(), name);
+            assert_eq!(serde_json::from_value::<Group>(name.into()).unwrap(), group);
+        }
+        assert_eq!(Group::from("treasury:admin").normalize(), Group::Admin);
+        assert_eq!(
+            Group::from("org:team:auditors").normalize(),
+            Group::Custom("auditors".into())
+        );
+    }
+
+    #[rstest]
+    #[case(&["user"], "accounts", (ReadLevel::Read, CreateLevel::Create, UpdateLevel::Update, DeleteLevel::Delete))]
+    #[case(&["user"], "institutions", (ReadLevel::Read, CreateLevel::NoPermission, UpdateLevel::NoPermission, DeleteLevel::NoPermission))]
+    #[case(&["unregistered_user"], "users", (ReadLevel::NoPermission, CreateLevel::Create, UpdateLevel::NoPermission, DeleteLevel::NoPermission))]
+    #[case(&["unregistered_user"], "accounts", (ReadLevel::NoPermission, CreateLevel::NoPermission, UpdateLevel::NoPermission, DeleteLevel::NoPermission))]
+    #[case(&["admin"], "institutions", (ReadLevel::ReadAll, CreateLevel::CreateAll, UpdateLevel::UpdateAll, DeleteLevel::DeleteAll))]
+    #[case(&["treasury:admin"], "accounts", (ReadLevel::ReadAll, CreateLevel::CreateAll, UpdateLevel::UpdateAll, DeleteLevel::DeleteAll))]
+    #[case(&["auditors"], "accounts", (ReadLevel::NoPermission, CreateLevel::NoPermission, UpdateLevel::NoPermission, DeleteLevel::NoPermission))]
+    #[case(&["auditors", "user"], "transactions", (ReadLevel::Read, CreateLevel::Create, UpdateLevel::Update, DeleteLevel::Delete))]
+    #[awt]
+    #[tokio::test]
+    async fn it_evaluates_policies_for_groups(
+        #[future] enforcer: Arc<Enforcer>,
+        #[case] groups: &[&str],
+        #[case] resource: &str,
+        #[case] expected: (ReadLevel, CreateLevel, UpdateLevel, DeleteLevel),
+    ) {
+        let claims = serde_json::from_value::<Claims>(serde_json::json!({
+            "groups": groups,
+            "email": "user@example.com",
+            "email_verified": true,
+            "sub": "sub",
+            "iss": "iss",
+            "iat": 0,
+            "exp": 0,
+        }))
+        .unwrap();
+        let mut token = AuthenticatedToken::new(claims);
+        token.normalize_groups();
+
+        let permission_set = PermissionSet::new(
+            resource,
+            &enforcer,
+            &token,
+            PermissionConfig {
+                min_read_level: ReadLevel::ReadAll,
+                min_create_level: CreateLevel::CreateAll,
+                min_update_level: UpdateLevel::UpdateAll,
+                min_delete_level: DeleteLevel::DeleteAll,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            (
+                permission_set.read_level,
+                permission_set.create_level,
+                permission_set.update_level,
+                permission_set.delete_level,
+            ),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(&["user"], "accounts")]
+    #[case(&["unregistered_user"], "users")]
+    #[case(&["treasury:admin"], "institutions")]
+    #[case(&["auditors", "user"], "transactions")]
+    #[awt]
+    #[tokio::test]
+    async fn it_evaluates_policies_for_many_groups(
+        #[future] enforcer: Arc<Enforcer>,
+        #[case] groups: &[&str],
+        #[case] resource: &str,
+    ) {
+        let token = |groups: Vec<String>| {
+            let claims = serde_json::from_value::<Claims>(serde_json::json!({
+                "groups": groups,
+                "email": "user@example.com",
+                "email_verified": true,
+                "sub": "sub",
+                "iss": "iss",
+                "iat": 0,
+                "exp": 0,
+            }))
+            .unwrap();
+            AuthenticatedToken::new(claims)
+        };
+        let permissions = |token: &AuthenticatedToken| {
+            let permission_set = PermissionSet::new(
+                resource,
+                &enforcer,
+                token,
+                PermissionConfig {
+                    min_read_level: ReadLevel::ReadAll,
+                    min_create_level: CreateLevel::CreateAll,
+                    min_update_level: UpdateLevel::UpdateAll,
+                    min_delete_level: DeleteLevel::DeleteAll,
+                },
+            )
+            .unwrap();
+            (
+                permission_set.read_level,
+                permission_set.create_level,
+                permission_set.update_level,
+                permission_set.delete_level,
+            )
+        };
+        let mut small = token(groups.iter().map(|x| x.to_string()).collect());
+        small.normalize_groups();
+
+        let mut many = (0..250)
+            .map(|i| format!("org:team-{i}"))
+            .chain(groups.iter().map(|x| x.to_string()))
+            .chain((250..500).map(|i| format!("team-{i}")))
+            .chain(groups.iter().map(|x| x.to_string()))
+            .collect::<Vec<_>>();
+        many.push("org:auditors".into());
+        let mut large = token(many);
+        let mut filtered = large.clone();
+        large.normalize_groups();
+        filtered.filter_groups(&GroupFilterConfig {
+            allowed: vec!["auditors".into()],
+            prefixes: vec!["treasury:".into()],
+        });
+        filtered.normalize_groups();
+        assert!(filtered.groups().len() < 10);
+
+        assert_eq!(permissions(&large), permissions(&small));
+        assert_eq!(permissions(&filtered), permissions(&small));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_requires_a_step_up_to_delete_a_user_with_a_passkey(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_request, &user_auth_token, &mut api).await;
+        let origin =
+            Url::parse(&var("WEBAUTHN_RP_ORIGIN").expect("Failed to read `WEBAUTHN_RP_ORIGIN`"))
+                .unwrap();
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+        let (status, body) = post_json(
+            &format!("/api/users/{}/passkeys/register/start", user.id),
+            Value::Object(Default::default()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let challenge = serde_json::from_value::<ChallengeResponse>(body).unwrap();
+        let options =
+            serde_json::from_value::<CreationChallengeResponse>(challenge.options).unwrap();
+        let credential = authenticator
+            .do_registration(origin.clone(), options)
+            .unwrap();
+        let (status, _) = post_json(
+            &format!("/api/users/{}/passkeys/register/finish", user.id),
+            serde_json::json!({
+                "challenge_id": challenge.challenge_id,
+                "name": "Test Passkey",
+                "credential": credential,
+            }),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let request = Request::builder()
+            .method("DELETE")
+            .header("Authorization", &user_auth_token)
+            .header("Accept", "application/json")
+            .uri(format!("/api/users/{}", user.id))
+            .body(Body::default())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(&mut api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let error = serde_json::from_slice::<ApiErrorResponse>(&body).unwrap();
+        assert_eq!(error.code, 4011);
+
+        let (status, body) = post_json(
+            &format!("/api/users/{}/step-up/start", user.id),
+            Value::Object(Default::default()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let challenge = serde_json::from_value::<ChallengeResponse>(body).unwrap();
+        let options =
+            serde_json::from_value::<RequestChallengeResponse>(challenge.options).unwrap();
+        let credential = authenticator.do_authentication(origin, options).unwrap();
+        let (status, _) = post_json(
+            &format!("/api/users/{}/step-up/finish", user.id),
+            serde_json::json!({
+                "challenge_id": challenge.challenge_id,
+                "credential": credential,
+            }),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let _ = delete_user(user.id, &user_auth_token, &mut api).await;
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_sweeps_expired_webauthn_challenges_as_one_is_taken(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_request, &user_auth_token, &mut api).await;
+        let mut register_start = async || {
+            let (status, body) = post_json(
+                &format!("/api/users/{}/passkeys/register/start", user.id),
+                Value::Object(Default::default()),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            serde_json::from_value::<ChallengeResponse>(body).unwrap()
+        };
+        let expired = register_start().await;
+        sqlx::query("UPDATE webauthn_challenge SET expires_at = now() - interval '1 minute'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let challenge = register_start().await;
+
+        let mut register_finish = async |challenge_id| {
+            post_json(
+                &format!("/api/users/{}/passkeys/register/finish", user.id),
+                serde_json::json!({
+                    "challenge_id": challenge_id,
+                    "name": "Test Passkey",
+                    "credential": {},
+                }),
+                &user_auth_token,
+                &mut api,
+            )
+            .await
+        };
+        let (status, body) = register_finish(challenge.challenge_id).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let error = serde_json::from_value::<ApiErrorResponse>(body).unwrap();
+        assert_eq!(error.message, "Invalid credential.");
+        let challenges = sqlx::query_scalar::<_, i64>("SELECT count(*) FROM webauthn_challenge")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(challenges, 0);
+
+        let (status, body) = register_finish(expired.challenge_id).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let error = serde_json::from_value::<ApiErrorResponse>(body).unwrap();
+        assert_eq!(error.message, "Invalid challenge.");
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_scopes_api_keys_to_their_accounts(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        let mut open = async |name: &str, auth_token: &str| {
+            let create_account_request = AccountCreateRequest {
+                name: name.into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            };
+            let account = create_account(&create_account_request, auth_token, &mut api).await;
+            let create_request = TransactionCreateRequest {
+                posted_at: Utc::now(),
+                description: None,
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: 1_000.into(),
+                notes: None,
+                category: None,
+            };
+            let transaction = create_transaction(&create_request, auth_token, &mut api).await;
+            (account.id, transaction.id)
+        };
+        let (scoped_account, scoped_transaction) = open("Budget", &user_auth_token).await;
+        let (other_account, other_transaction) = open("Savings", &user_auth_token).await;
+        let (foreign_account, _) = open("Other", &user_two_auth_token).await;
+
+        let api_keys_uri = format!("/api/users/{}/api-keys", user.id);
+        let (status, _) = send_json(
+            "POST",
+            &api_keys_uri,
+            Some(serde_json::json!({
+                "name": "Budget script",
+                "account_ids": [scoped_account, foreign_account],
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json(
+            "POST",
+            &api_keys_uri,
+            Some(serde_json::json!({ "name": "Budget script", "account_ids": [] })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let mut issue = async |read_only: bool| {
+            let (status, body) = send_json(
+                "POST",
+                &api_keys_uri,
+                Some(serde_json::json!({
+                    "name": "Budget script",
+                    "read_only": read_only,
+                    "account_ids": [scoped_account],
+                })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED);
+            let api_key = serde_json::from_value::<ApiKeyCreateResponse>(body).unwrap();
+            assert_eq!(api_key.api_key.account_ids, Some(vec![scoped_account]));
+            (api_key.api_key.id, format!("Bearer {}", api_key.secret))
+        };
+        let (read_only_key_id, read_only_key) = issue(true).await;
+        let (_, read_write_key) = issue(false).await;
+
+        let accounts = get_accounts(&read_only_key, &mut api).await;
+        assert_eq!(
+            accounts.accounts.iter().map(|x| x.id).collect::<Vec<_>>(),
+            [scoped_account]
+        );
+        let (status, _) = send_json(
+            "GET",
+            &format!("/api/accounts/{scoped_account}"),
+            None,
+            &read_only_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send_json(
+            "GET",
+            &format!("/api/accounts/{other_account}"),
+            None,
+            &read_only_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) =
+            send_json("GET", "/api/transactions", None, &read_only_key, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        let transactions = serde_json::from_value::<TransactionGetListResponse>(body).unwrap();
+        assert_eq!(
+            transactions
+                .transactions
+                .iter()
+                .map(|x| x.id)
+                .collect::<Vec<_>>(),
+            [scoped_transaction]
+        );
+        let (status, _) = send_json(
+            "GET",
+            &format!("/api/transactions/{other_transaction}"),
+            None,
+            &read_only_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let transaction_request = |account_id| {
+            serde_json::to_value(TransactionCreateRequest {
+                posted_at: Utc::now(),
+                description: None,
+                account_id,
+                asset_id: krw.id,
+                quantity: 10.into(),
+                notes: None,
+                category: None,
+            })
+            .unwrap()
+        };
+        let (status, _) = send_json(
+            "POST",
+            "/api/transactions",
+            Some(transaction_request(scoped_account)),
+            &read_only_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send_json(
+            "POST",
+            "/api/transactions",
+            Some(transaction_request(other_account)),
+            &read_write_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json(
+            "POST",
+            "/api/transactions",
+            Some(transaction_request(scoped_account)),
+            &read_write_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/transactions/{other_transaction}"),
+            None,
+            &read_write_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/accounts/{other_account}?confirm=true"),
+            None,
+            &read_write_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json(
+            "POST",
+            "/api/accounts",
+            Some(
+                serde_json::to_value(AccountCreateRequest {
+                    name: "Unscoped".into(),
+                    institution_id: institution.id,
+                    notes: None,
+                    default_asset_id: None,
+                })
+                .unwrap(),
+            ),
+            &read_write_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = send_json("GET", &api_keys_uri, None, &read_write_key, &mut api).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) =
+            send_json("GET", &api_keys_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        let api_keys = serde_json::from_value::<ApiKeyGetListResponse>(body).unwrap();
+        assert_eq!(api_keys.api_keys.len(), 2);
+
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("{api_keys_uri}/{read_only_key_id}"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json("GET", "/api/accounts", None, &read_only_key, &mut api).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_requires_update_permission_for_attachments(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut enforcer = Arc::into_inner(enforcer).unwrap();
+        enforcer.enable_auto_save(false);
+        enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "transactions".to_owned(),
+                "read_all".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let mut api = create_api(pool, Arc::new(enforcer));
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        let mut open = async |auth_token: &str| {
+            let create_account_request = AccountCreateRequest {
+                name: "Checking".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            };
+            let account = create_account(&create_account_request, auth_token, &mut api).await;
+            let create_request = TransactionCreateRequest {
+                posted_at: Utc::now(),
+                description: None,
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: -3_000.into(),
+                notes: None,
+                category: None,
+            };
+            let transaction = create_transaction(&create_request, auth_token, &mut api).await;
+            (account.id, transaction.id)
+        };
+        let (account, transaction) = open(&user_auth_token).await;
+        let (_, foreign_transaction) = open(&user_two_auth_token).await;
+        let upload = |transaction_id: TransactionId| {
+            serde_json::json!({
+                "transaction_id": transaction_id,
+                "filename": "receipt.png",
+                "content_type": "image/png",
+                "content": BASE64_STANDARD.encode(b"\x89PNG\r\n\x1a\n"),
+            })
+        };
+        let (status, body) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload(transaction)),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = serde_json::from_value::<AttachmentId>(body["id"].clone()).unwrap();
+        let (status, body) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload(foreign_transaction)),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let foreign_id = serde_json::from_value::<AttachmentId>(body["id"].clone()).unwrap();
+
+        // Reading every transaction doesn't allow changing their attachments.
+        let (status, _) = send_json(
+            "GET",
+            &format!("/api/attachments/{foreign_id}"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload(foreign_transaction)),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/attachments/{foreign_id}"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json(
+            "POST",
+            &format!("/api/users/{}/api-keys", user.id),
+            Some(serde_json::json!({
+                "name": "Receipt reader",
+                "read_only": true,
+                "account_ids": [account],
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let api_key = serde_json::from_value::<ApiKeyCreateResponse>(body).unwrap();
+        let read_only_key = format!("Bearer {}", api_key.secret);
+        let (status, _) = send_json(
+            "GET",
+            &format!("/api/attachments/{id}"),
+            None,
+            &read_only_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload(transaction)),
+            &read_only_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/attachments/{id}"),
+            None,
+            &read_only_key,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/attachments/{id}"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_revokes_sessions_selectively(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let user_two = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+
+        // Each login starts a refresh token chain of its own.
+        let start = async |user_id: UserId, refresh_token: &str| {
+            UserSessionRepository
+                .create(
+                    pool.begin().await.unwrap(),
+                    UserSessionCreate {
+                        user_id,
+                        token_hash: hash_secret(refresh_token),
+                        ip_address: Some("203.0.113.0/24".into()),
+                        user_agent: Some(format!("{refresh_token} browser")),
+                    },
+                )
+                .await
+                .unwrap()
+        };
+        let laptop = start(user.id, "laptop-refresh-token").await;
+        let phone = start(user.id, "phone-refresh-token").await;
+        let _ = start(user_two.id, "other-refresh-token").await;
+
+        let send = async |method: &str,
+                          uri: &str,
+                          refresh_token: Option<&str>,
+                          api: &mut RouterIntoService<Body>| {
+            let mut request = Request::builder()
+                .method(method)
+                .header("Authorization", &user_auth_token)
+                .header("Content-Type", "application/json")
+                .header("Accept", "application/json")
+                .uri(uri);
+            if let Some(refresh_token) = refresh_token {
+                request = request.header("Cookie", format!("refresh_token={refresh_token}"));
+            }
+            let body = match method {
+                "POST" => Body::from("{}"),
+                _ => Body::empty(),
+            };
+            let response = ServiceExt::<Request<Body>>::ready(api)
+                .await
+                .unwrap()
+                .call(request.body(body).unwrap())
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (
+                status,
+                serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+            )
+        };
+        let sessions_uri = format!("/api/users/{}/sessions", user.id);
+        let session_ids = |body: &Value| {
+            serde_json::from_value::<UserSessionGetListResponse>(body.clone())
+                .unwrap()
+                .sessions
+                .into_iter()
+                .map(|x| (x.id, x.current))
+                .collect::<Vec<_>>()
+        };
+
+        let (status, body) =
+            send("GET", &sessions_uri, Some("laptop-refresh-token"), &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        let mut sessions = session_ids(&body);
+        sessions.sort_by_key(|(id, _)| *id != laptop.id);
+        assert_eq!(sessions, [(laptop.id, true), (phone.id, false)]);
+
+        // Sessions of other users are not found.
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/users/{}/sessions/{}", user_two.id, phone.id),
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) =
+            send_json("GET", &sessions_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = send(
+            "DELETE",
+            &format!("{sessions_uri}/{}", phone.id),
+            None,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(
+            "DELETE",
+            &format!("{sessions_uri}/{}", phone.id),
+            None,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, body) = send("GET", &sessions_uri, Some("laptop-refresh-token"), &mut api).await;
+        assert_eq!(session_ids(&body), [(laptop.id, true)]);
+
+        // The next refresh from the phone finds its chain revoked.
+        let revoked = UserSessionRepository
+            .get_by_token_hash(
+                pool.begin().await.unwrap(),
+                &hash_secret("phone-refresh-token"),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(revoked.revoked_at.is_some());
+
+        let tablet = start(user.id, "tablet-refresh-token").await;
+        let (status, _) = send(
+            "POST",
+            &format!("{sessions_uri}/revoke-others"),
+            None,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send(
+            "POST",
+            &format!("{sessions_uri}/revoke-others"),
+            Some("phone-refresh-token"),
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, body) = send(
+            "POST",
+            &format!("{sessions_uri}/revoke-others"),
+            Some("tablet-refresh-token"),
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "revoked": 1 }));
+        let (_, body) = send("GET", &sessions_uri, Some("tablet-refresh-token"), &mut api).await;
+        assert_eq!(session_ids(&body), [(tablet.id, true)]);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/users/{}/sessions", user_two.id),
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(session_ids(&body).len(), 1);
+    }
+
+    const IMPORT_CSV: &str = concat!(
+        "Datum,Beschreibung,Betrag\n",
+        "\"03.01.2025\",\"Coffee, large\",\"4,50\"\n",
+        "04.01.2025,Salary,\"-2.500,00\"\n",
+        "05.01.2025,,\"1.234,5\"\n",
+    );
+
+    fn import_mapping(amount_column: &str) -> Value {
+        serde_json::json!({
+            "column_mapping": {
+                "posted_at": "Datum",
+                "amount": amount_column,
+                "description": "Beschreibung",
+            },
+            "date_format": "%d.%m.%Y",
+            "decimal_separator": ",",
+            "decimal_places": 2,
+            "sign_convention": "inverted",
+        })
+    }
+
+    async fn create_import_account(
+        auth_token: &str,
+        api: &mut RouterIntoService<Body>,
+    ) -> AccountCreateResponse {
+        let institution = get_institution_by_name("Toss Bank", auth_token, api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        create_account(&create_account_request, auth_token, api).await
+    }
+
+    async fn create_import_profile(
+        mapping: Value,
+        default_account_id: Option<AccountId>,
+        auth_token: &str,
+        api: &mut RouterIntoService<Body>,
+    ) -> ImportProfileCreateResponse {
+        let mut body = mapping;
+        body["name"] = "Sparkasse".into();
+        body["default_account_id"] = serde_json::to_value(default_account_id).unwrap();
+        let (status, body) =
+            send_json("POST", "/api/import-profiles", Some(body), auth_token, api).await;
+        assert_eq!(status, StatusCode::CREATED);
+        serde_json::from_value::<ImportProfileCreateResponse>(body).unwrap()
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_imports_transactions_with_a_profile(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let account = create_import_account(&user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let profile = create_import_profile(
+            import_mapping("Betrag"),
+            Some(account.id),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(profile.default_account_id, Some(account.id));
+
+        let mut duplicate_request = import_mapping("Betrag");
+        duplicate_request["name"] = "Sparkasse".into();
+        let (status, _) = send_json(
+            "POST",
+            "/api/import-profiles",
+            Some(duplicate_request),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let import_request = serde_json::json!({
+            "profile_id": profile.id,
+            "asset_id": krw.id,
+            "csv": IMPORT_CSV,
+        });
+        let (status, _) = send_json(
+            "POST",
+            "/api/transactions/import",
+            Some(import_request.clone()),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let mut ambiguous_request = import_request.clone();
+        ambiguous_request["mapping"] = import_mapping("Betrag");
+        let (status, _) = send_json(
+            "POST",
+            "/api/transactions/import",
+            Some(ambiguous_request),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions/import",
+            Some(import_request),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let imported = serde_json::from_value::<ImportResponse>(body).unwrap();
+        let imported = imported
+            .transactions
+            .iter()
+            .map(|x| {
+                (
+                    x.account_id,
+                    x.posted_at.format("%Y-%m-%d").to_string(),
+                    x.description.clone(),
+                    x.quantity,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            imported,
+            vec![
+                (
+                    account.id,
+                    "2025-01-03".to_string(),
+                    Some("Coffee, large".to_string()),
+                    Decimal::from(-450)
+                ),
+                (
+                    account.id,
+                    "2025-01-04".to_string(),
+                    Some("Salary".to_string()),
+                    Decimal::from(250_000)
+                ),
+                (
+                    account.id,
+                    "2025-01-05".to_string(),
+                    None,
+                    Decimal::from(-123_450)
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_dry_runs_imports_without_keeping_anything(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let account = create_import_account(&user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let csv = (1..=50).fold("Datum,Beschreibung,Betrag\n".to_string(), |csv, day| {
+            csv + &format!("{:02}.01.2025,Row {day},\"-{day},00\"\n", day % 28 + 1)
+        });
+        let import_request = serde_json::json!({
+            "mapping": import_mapping("Betrag"),
+            "account_id": account.id,
+            "asset_id": krw.id,
+            "csv": csv,
+        });
+        let counts = async || {
+            let transactions =
+                sqlx::query_scalar::<_, i64>(r#"SELECT COUNT(*) FROM "transaction""#)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            let balances = sqlx::query_scalar::<_, i64>(r#"SELECT COUNT(*) FROM account_balance"#)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            (transactions, balances)
+        };
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions/import?dry_run=true",
+            Some(import_request.clone()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["dry_run"], true);
+        let dry_run = serde_json::from_value::<ImportResponse>(body).unwrap();
+        assert_eq!(dry_run.transactions.len(), 50);
+        assert_eq!(counts().await, (0, 0));
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions/import",
+            Some(import_request),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(body.get("dry_run").is_none());
+        let imported = serde_json::from_value::<ImportResponse>(body).unwrap();
+        let summarize = |response: &ImportResponse| {
+            response
+                .transactions
+                .iter()
+                .map(|x| (x.posted_at, x.description.clone(), x.quantity))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(summarize(&imported), summarize(&dry_run));
+        assert_eq!(counts().await, (50, 1));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_previews_the_first_rows_of_an_import(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let account = create_import_account(&user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        let csv = format!(
+            "{IMPORT_CSV}31.02.2025,Typo,\"1,00\"\n06.01.2025,Rent,\"700,00\"\n07.01.2025,Late,\"1,00\"\n"
+        );
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions/import/preview",
+            Some(serde_json::json!({
+                "mapping": import_mapping("Betrag"),
+                "account_id": account.id,
+                "asset_id": krw.id,
+                "csv": csv,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let preview = serde_json::from_value::<ImportPreviewResponse>(body).unwrap();
+        assert_eq!(preview.account_id, account.id);
+        assert_eq!(preview.rows.len(), PREVIEW_ROWS);
+        assert_eq!(
+            preview.rows.iter().map(|x| x.line).collect::<Vec<_>>(),
+            vec![2, 3, 4, 5, 6]
+        );
+        assert_eq!(
+            preview.rows.iter().map(|x| x.quantity).collect::<Vec<_>>(),
+            vec![
+                Some(Decimal::from(-450)),
+                Some(Decimal::from(250_000)),
+                Some(Decimal::from(-123_450)),
+                None,
+                Some(Decimal::from(-70_000))
+            ]
+        );
+        assert_eq!(
+            preview.rows[3].error.as_deref(),
+            Some("Line 5: `31.02.2025` does not match the date format.")
+        );
+        assert!(
+            preview
+                .rows
+                .iter()
+                .filter(|x| x.line != 5)
+                .all(|x| x.error.is_none())
+        );
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/transactions?account_id={}", account.id),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let transactions = serde_json::from_value::<TransactionGetListResponse>(body).unwrap();
+        assert!(transactions.transactions.is_empty());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_rejects_a_mapping_with_a_missing_column(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let account = create_import_account(&user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let profile = create_import_profile(
+            import_mapping("Amount"),
+            Some(account.id),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        for uri in [
+            "/api/transactions/import/preview",
+            "/api/transactions/import",
+        ] {
+            let (status, body) = send_json(
+                "POST",
+                uri,
+                Some(serde_json::json!({
+                    "profile_id": profile.id,
+                    "asset_id": krw.id,
+                    "csv": IMPORT_CSV,
+                })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["message"], "Column `Amount` is not in the CSV header.");
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_syncs_accounts_through_their_provider_connection(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let mut open = async |name: &str| {
+            let create_account_request = AccountCreateRequest {
+                name: name.into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            };
+            create_account(&create_account_request, &user_auth_token, &mut api).await
+        };
+        let account = open("Checking").await;
+        let unlinked_account = open("Savings").await;
+
+        let remote_transaction = |external_id: &str, quantity: i64, asset_symbol: &str| {
+            serde_json::json!({
+                "external_id": external_id,
+                "posted_at": "2025-04-01T09:00:00Z",
+                "description": format!("Remote {external_id}"),
+                "quantity": quantity,
+                "asset_symbol": asset_symbol,
+            })
+        };
+        ProviderConnectionRepository
+            .create(
+                pool.begin().await.unwrap(),
+                ProviderConnectionCreate {
+                    user_id: user.id,
+                    institution_id: institution.id,
+                    connector: fake::NAME.into(),
+                    config: serde_json::json!({
+                        "accounts": [{ "id": "remote-1", "name": "Checking" }],
+                        "transactions": {
+                            "remote-1": [
+                                remote_transaction("t1", 1_000, "KRW"),
+                                remote_transaction("t2", -500, "KRW"),
+                                remote_transaction("t1", 1_000, "KRW"),
+                                remote_transaction("t3", 1, "BTC"),
+                            ],
+                        },
+                    }),
+                },
+            )
+            .await
+            .unwrap();
+
+        let sync_uri = format!("/api/accounts/{}/sync", account.id);
+        let (status, _) = send_json(
+            "POST",
+            &sync_uri,
+            Some(serde_json::json!({})),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json(
+            "POST",
+            &format!("/api/accounts/{}/sync", unlinked_account.id),
+            Some(serde_json::json!({})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "No remote account is named `Savings`.");
+
+        let (status, body) = send_json(
+            "POST",
+            &sync_uri,
+            Some(serde_json::json!({})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let sync = serde_json::from_value::<SyncResponse>(body).unwrap();
+        assert_eq!(
+            sync.created
+                .iter()
+                .map(|x| (x.external_id.as_deref(), x.quantity, x.account_id))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("t1"), Decimal::from(1_000), account.id),
+                (Some("t2"), Decimal::from(-500), account.id),
+            ]
+        );
+        assert_eq!(sync.duplicates, 1);
+        assert_eq!(sync.unknown_assets, 1);
+
+        let (status, body) = send_json(
+            "POST",
+            &sync_uri,
+            Some(serde_json::json!({})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let sync = serde_json::from_value::<SyncResponse>(body).unwrap();
+        assert!(sync.created.is_empty());
+        assert_eq!(sync.duplicates, 3);
+        assert_eq!(sync.unknown_assets, 1);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/transactions?account_id={}", account.id),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let transactions = serde_json::from_value::<TransactionGetListResponse>(body).unwrap();
+        assert_eq!(transactions.transactions.len(), 2);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets", "asset_prices"))]
+    async fn it_reports_budget_status_in_the_budget_asset(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let usd = get_asset_by_symbol(&user_auth_token, &mut api, "USD").await;
+        let jpy = get_asset_by_symbol(&user_auth_token, &mut api, "JPY").await;
+        for (posted_at, asset_id, quantity) in [
+            ("2025-01-02T00:00:00Z", krw.id, 1_000_000_i64),
+            ("2025-01-05T00:00:00Z", krw.id, -50_000),
+            ("2025-01-10T00:00:00Z", usd.id, -100),
+            ("2025-01-12T00:00:00Z", jpy.id, -1_000),
+            ("2025-02-03T00:00:00Z", usd.id, -100),
+        ] {
+            let create_request = TransactionCreateRequest {
+                posted_at: posted_at.parse().unwrap(),
+                description: None,
+                account_id: account.id,
+                asset_id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            let _ = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        }
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/budgets",
+            Some(serde_json::json!({
+                "name": "Groceries",
+                "asset_id": krw.id,
+                "kind": "percent_of_income",
+                "amount": 200_000,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["message"],
+            "A percent of income budget needs a `percent` from 0 to 100 and no `amount`."
+        );
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/budgets",
+            Some(serde_json::json!({
+                "name": "Groceries",
+                "asset_id": krw.id,
+                "amount": 200_000,
+                "account_ids": [account.id],
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let status_uri = format!(
+            "/api/budgets/{}/status?start=2025-01-01T00:00:00Z&end=2025-02-01T00:00:00Z",
+            body["id"].as_str().unwrap()
+        );
+
+        let (status, _) = send_json("GET", &status_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json("GET", &status_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["asset_id"], serde_json::json!(krw.id));
+        assert_eq!(body["spent"], 195_000);
+        assert_eq!(body["income"], 1_000_000);
+        assert_eq!(body["limit"], 200_000);
+        assert_eq!(body["remaining"], 5_000);
+        assert_eq!(body["met"], true);
+        assert_eq!(
+            body["unconverted"],
+            serde_json::json!([{ "asset_id": jpy.id, "spent": 1_000, "income": 0 }])
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_leaves_percent_of_income_budgets_unmet_without_income(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let record = async |api: &mut RouterIntoService<Body>, posted_at: &str, quantity: i64| {
+            let create_request = TransactionCreateRequest {
+                posted_at: posted_at.parse().unwrap(),
+                description: None,
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            let _ = create_transaction(&create_request, &user_auth_token, api).await;
+        };
+        record(&mut api, "2025-03-04T00:00:00Z", -30_000).await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/budgets",
+            Some(serde_json::json!({
+                "name": "Dining",
+                "asset_id": krw.id,
+                "kind": "percent_of_income",
+                "percent": 10,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let status_uri = format!(
+            "/api/budgets/{}/status?start=2025-03-01T00:00:00Z&end=2025-04-01T00:00:00Z",
+            body["id"].as_str().unwrap()
+        );
+
+        let (status, body) = send_json("GET", &status_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["spent"], 30_000);
+        assert_eq!(body["limit"], Value::Null);
+        assert_eq!(body["met"], false);
+        assert_eq!(body["no_income"], true);
+
+        record(&mut api, "2025-03-10T00:00:00Z", 500_000).await;
+        let (status, body) = send_json("GET", &status_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["income"], 500_000);
+        assert_eq!(body["limit"], 50_000);
+        assert_eq!(body["met"], true);
+        assert_eq!(body["no_income"], false);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_categorizes_new_transactions_by_rule_priority(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        let mut rule_ids = vec![];
+        for rule in [
+            serde_json::json!({
+                "field": "quantity_range",
+                "max_quantity": 0,
+                "category": "Spending",
+            }),
+            serde_json::json!({
+                "field": "description",
+                "pattern": "coffee|cafe",
+                "category": "Eating out",
+                "priority": 10,
+            }),
+            serde_json::json!({
+                "field": "account",
+                "account_id": account.id,
+                "category": "Checking",
+                "priority": -10,
+            }),
+        ] {
+            let (status, body) = send_json(
+                "POST",
+                "/api/categorization-rules",
+                Some(rule),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED);
+            rule_ids.push(body["id"].clone());
+        }
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/categorization-rules",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let categories = body["categorization_rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["category"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(categories, ["Eating out", "Spending", "Checking"]);
+
+        for (description, quantity, category, rule_id) in [
+            ("Blue Bottle Coffee", -5_000, "Eating out", &rule_ids[1]),
+            ("Rent", -500_000, "Spending", &rule_ids[0]),
+            ("Salary", 3_000_000, "Checking", &rule_ids[2]),
+        ] {
+            let create_request = TransactionCreateRequest {
+                posted_at: Utc::now(),
+                description: Some(description.into()),
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+            assert_eq!(transaction.category.as_deref(), Some(category));
+            assert_eq!(
+                serde_json::to_value(transaction.applied_rule_id).unwrap(),
+                *rule_id
+            );
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_keeps_explicit_categories_over_rules(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let (status, _) = send_json(
+            "POST",
+            "/api/categorization-rules",
+            Some(serde_json::json!({
+                "pattern": "coffee",
+                "category": "Eating out",
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: Some("Coffee beans".into()),
+            account_id: account.id,
+            asset_id: krw.id,
+            quantity: (-20_000).into(),
+            notes: None,
+            category: Some("  Groceries ".into()),
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        assert_eq!(transaction.category.as_deref(), Some("Groceries"));
+        assert_eq!(transaction.applied_rule_id, None);
+
+        let create_request = TransactionCreateRequest {
+            category: None,
+            ..create_request
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        assert_eq!(transaction.category.as_deref(), Some("Eating out"));
+        assert!(transaction.applied_rule_id.is_some());
+
+        let (status, body) = send_json(
+            "PATCH",
+            &format!("/api/transactions/{}", transaction.id.0),
+            Some(serde_json::json!({ "category": "Groceries" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["category"], "Groceries");
+        assert!(body.get("applied_rule_id").is_none());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_applies_rules_to_uncategorized_transactions(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let other_account =
+            create_account(&create_account_request, &user_two_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let mut transaction_ids = vec![];
+        for (description, category) in [
+            ("Coffee", None),
+            ("Cafe latte", None),
+            ("Coffee grinder", Some("Appliances")),
+            ("Rent", None),
+        ] {
+            let create_request = TransactionCreateRequest {
+                posted_at: Utc::now(),
+                description: Some(description.into()),
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: (-5_000).into(),
+                notes: None,
+                category: category.map(Into::into),
+            };
+            let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+            transaction_ids.push(transaction.id.0);
+        }
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: Some("Coffee".into()),
+            account_id: other_account.id,
+            asset_id: krw.id,
+            quantity: (-5_000).into(),
+            notes: None,
+            category: None,
+        };
+        let other_transaction =
+            create_transaction(&create_request, &user_two_auth_token, &mut api).await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/categorization-rules",
+            Some(serde_json::json!({
+                "pattern": "coffee|cafe",
+                "category": "Eating out",
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let rule_id = body["id"].clone();
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/categorization-rules/apply",
+            Some(serde_json::json!({})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["changed"], 2);
+
+        for (id, category) in transaction_ids.iter().zip([
+            Some("Eating out"),
+            Some("Eating out"),
+            Some("Appliances"),
+            None,
+        ]) {
+            let (status, body) = send_json(
+                "GET",
+                &format!("/api/transactions/{id}"),
+                None,
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["category"].as_str(), category);
+            if category == Some("Eating out") {
+                assert_eq!(body["applied_rule_id"], rule_id);
+            } else {
+                assert!(body.get("applied_rule_id").is_none());
+            }
+        }
+        let (_, body) = send_json(
+            "GET",
+            &format!("/api/transactions/{}", other_transaction.id.0),
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert!(body.get("category").is_none());
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/categorization-rules/apply",
+            Some(serde_json::json!({})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["changed"], 0);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_lists_the_latest_uncategorized_transactions(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let other_account =
+            create_account(&create_account_request, &user_two_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        for (days_ago, description, category, account_id, auth_token) in [
+            (3, "Oldest", None, account.id, &user_auth_token),
+            (1, "Newest", None, account.id, &user_auth_token),
+            (2, "Middle", None, account.id, &user_auth_token),
+            (0, "Rent", Some("Housing"), account.id, &user_auth_token),
+            (
+                0,
+                "Someone else's",
+                None,
+                other_account.id,
+                &user_two_auth_token,
+            ),
+        ] {
+            let create_request = TransactionCreateRequest {
+                posted_at: Utc::now() - TimeDelta::days(days_ago),
+                description: Some(description.into()),
+                account_id,
+                asset_id: krw.id,
+                quantity: (-5_000).into(),
+                notes: None,
+                category: category.map(Into::into),
+            };
+            create_transaction(&create_request, auth_token, &mut api).await;
+        }
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/transactions/uncategorized?limit=2",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 3);
+        let descriptions = body["transactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["description"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(descriptions, ["Newest", "Middle"]);
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/transactions/uncategorized",
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 1);
+
+        // The queue of an account is read from the partial index rather than
+        // every transaction of the account.
+        let mut trans = pool.begin().await.unwrap();
+        sqlx::query("SET LOCAL enable_seqscan = off")
+            .execute(&mut *trans)
+            .await
+            .unwrap();
+        let plan = sqlx::query_scalar::<_, String>(
+            r#"
+            EXPLAIN SELECT * FROM "transaction"
+            WHERE account_id = $1
+            AND category IS NULL
+            ORDER BY posted_at DESC
+            LIMIT 20
+            "#,
+        )
+        .bind(account.id.0)
+        .fetch_all(&mut *trans)
+        .await
+        .unwrap()
+        .join("\n");
+        assert!(
+            plan.contains("idx_transaction_account_id_posted_at_uncategorized"),
+            "{plan}"
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_categorizes_a_transaction_and_creates_its_rule_together(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: Some("Blue Bottle (Seoul)".into()),
+            account_id: account.id,
+            asset_id: krw.id,
+            quantity: (-5_000).into(),
+            notes: None,
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        let categorize_uri = format!("/api/transactions/{}/categorize", transaction.id.0);
+
+        let (status, _) = send_json(
+            "POST",
+            &categorize_uri,
+            Some(serde_json::json!({ "category": "Eating out", "create_rule": true })),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json(
+            "POST",
+            &categorize_uri,
+            Some(serde_json::json!({ "category": "Eating out", "pattern": "bottle" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send_json(
+            "POST",
+            &categorize_uri,
+            Some(serde_json::json!({ "category": " Eating out ", "create_rule": true })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["transaction"]["category"], "Eating out");
+        assert!(body["transaction"].get("applied_rule_id").is_none());
+        assert_eq!(body["rule"]["field"], "description");
+        assert_eq!(body["rule"]["pattern"], r"Blue Bottle \(Seoul\)");
+        assert_eq!(body["rule"]["category"], "Eating out");
+        let rule_id = body["rule"]["id"].clone();
+
+        // The rule takes the description as it is, so it categorizes the
+        // next one like it.
+        let next = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        assert_eq!(next.category.as_deref(), Some("Eating out"));
+        assert_eq!(serde_json::to_value(next.applied_rule_id).unwrap(), rule_id);
+
+        let (status, body) = send_json(
+            "POST",
+            &format!("/api/transactions/{}/categorize", next.id.0),
+            Some(serde_json::json!({ "category": "Treats" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["transaction"]["category"], "Treats");
+        assert!(body.get("rule").is_none());
+
+        // With no room for the rule, the category isn't set either.
+        sqlx::query(
+            r#"
+            INSERT INTO categorization_rule (user_id, field, pattern, category)
+            SELECT account.user_id, 'description', 'filler ' || n, 'Filler'
+            FROM account, generate_series(2, $2) AS n
+            WHERE account.id = $1
+            "#,
+        )
+        .bind(account.id.0)
+        .bind(MAX_RULES as i32)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let create_request = TransactionCreateRequest {
+            description: Some("Rent".into()),
+            ..create_request
+        };
+        let rent = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        let (status, _) = send_json(
+            "POST",
+            &format!("/api/transactions/{}/categorize", rent.id.0),
+            Some(serde_json::json!({ "category": "Housing", "create_rule": true })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (_, body) = send_json(
+            "GET",
+            &format!("/api/transactions/{}", rent.id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert!(body.get("category").is_none());
+        let (_, body) = send_json(
+            "GET",
+            "/api/categorization-rules",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(
+            body["categorization_rules"].as_array().unwrap().len(),
+            MAX_RULES
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions"))]
+    async fn it_validates_categorization_rules(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution =
+            get_institution_by_name("Toss Bank", &user_two_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let other_account =
+            create_account(&create_account_request, &user_two_auth_token, &mut api).await;
+
+        for (rule, expected) in [
+            (
+                serde_json::json!({ "pattern": "(unclosed", "category": "Broken" }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({ "pattern": "a".repeat(257), "category": "Long" }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({ "pattern": "coffee", "category": "  " }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({
+                    "pattern": "coffee",
+                    "max_quantity": 0,
+                    "category": "Mixed",
+                }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({
+                    "field": "quantity_range",
+                    "min_quantity": 10,
+                    "max_quantity": 0,
+                    "category": "Backwards",
+                }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({
+                    "field": "account",
+                    "account_id": other_account.id,
+                    "category": "Theirs",
+                }),
+                StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let (status, _) = send_json(
+                "POST",
+                "/api/categorization-rules",
+                Some(rule),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, expected);
+        }
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/categorization-rules",
+            Some(serde_json::json!({ "pattern": "coffee", "category": "Eating out" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let uri = format!("/api/categorization-rules/{}", body["id"].as_str().unwrap());
+
+        let (status, _) = send_json("GET", &uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json("DELETE", &uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = send_json(
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({ "field": "quantity_range" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, body) = send_json(
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({ "field": "quantity_range", "min_quantity": "1.5" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["min_quantity"], "1.5");
+        assert!(body.get("pattern").is_none());
+
+        let (status, _) = send_json("DELETE", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_calls_the_api_through_the_typed_client(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let base_url = serve_api(pool, enforcer).await;
+        let client = TreasuryClient::new(base_url, user_auth_token.trim_start_matches("Bearer "));
+
+        let user = client
+            .create_user(&UserCreateRequest {
+                name: "Test User".into(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(user.name, "Test User");
+
+        let institutions = client
+            .list_institutions(
+                &InstitutionGetListRequest {
+                    name: Some("Toss Bank".into()),
+                    ..Default::default()
+                },
+                &Page::default(),
+            )
+            .await
+            .unwrap();
+        let institution = &institutions.institutions[0];
+        let account = client
+            .create_account(&AccountCreateRequest {
+                name: "Checking".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            })
+            .await
+            .unwrap();
+        let assets = client
+            .list_all_assets(&AssetGetListRequest::default(), Some(3))
+            .await
+            .unwrap();
+        assert_eq!(assets.len(), 8);
+
+        for quantity in 1..=5_i64 {
+            client
+                .create_transaction(&TransactionCreateRequest {
+                    posted_at: Utc::now(),
+                    description: None,
+                    account_id: account.id,
+                    asset_id: assets[0].id,
+                    quantity: quantity.into(),
+                    notes: None,
+                    category: None,
+                })
+                .await
+                .unwrap();
+        }
+        let transactions = client
+            .list_all_transactions(
+                &TransactionGetListRequest {
+                    account_id: Some(account.id),
+                    ..Default::default()
+                },
+                Some(2),
+            )
+            .await
+            .unwrap();
+        let mut quantities = transactions.iter().map(|x| x.quantity).collect::<Vec<_>>();
+        quantities.sort();
+        assert_eq!(quantities, (1..=5).map(Decimal::from).collect::<Vec<_>>());
+
+        client.delete_account(account.id).await.unwrap();
+        let Err(ClientError::Api { status, response }) = client.get_account(account.id).await
+        else {
+            panic!("Expected the account to be gone");
+        };
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(response.code, 4040);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_extracts_receipt_details_from_attachments(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: None,
+            account_id: account.id,
+            asset_id: krw.id,
+            quantity: -12_500.into(),
+            notes: None,
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        let upload = |filename: &str, content_type: &str, content: &[u8]| {
+            serde_json::json!({
+                "transaction_id": transaction.id,
+                "filename": filename,
+                "content_type": content_type,
+                "content": BASE64_STANDARD.encode(content),
+            })
+        };
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(serde_json::json!({
+                "transaction_id": transaction.id,
+                "filename": "receipt.txt",
+                "content_type": "text/plain",
+                "content": "not base64!",
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "The content is not valid base64.");
+
+        let (status, _) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload("receipt.pdf", "application/pdf", b"%PDF-1.7\n")),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let receipt = "%PDF-1.7\nGS25 Gangnam\n2025-03-04 12:00\nCard 1234\nTOTAL 12,500\n";
+        let (status, body) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload("receipt.pdf", "application/pdf", receipt.as_bytes())),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["size"], receipt.len());
+        let id = serde_json::from_value::<AttachmentId>(body["id"].clone()).unwrap();
+        let extraction_uri = format!("/api/attachments/{id}/extraction");
+
+        let (status, body) =
+            send_json("GET", &extraction_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["extractor"], "noop");
+        assert_eq!(body["status"], "complete");
+        assert_eq!(body["quantity"], Value::Null);
+
+        let (status, _) =
+            send_json("GET", &extraction_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let attachment = AttachmentRepository
+            .get(pool.begin().await.unwrap(), id)
+            .await
+            .unwrap();
+        let content = AttachmentRepository
+            .get_content(pool.begin().await.unwrap(), id)
+            .await
+            .unwrap();
+        ExtractionJob {
+            attachment,
+            content,
+        }
+        .run(&pool, &FakeExtractor)
+        .await
+        .unwrap();
+        let (status, body) =
+            send_json("GET", &extraction_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["extractor"], "fake");
+        assert_eq!(body["status"], "complete");
+        assert_eq!(body["quantity"], 12_500);
+        assert_eq!(body["merchant"], "GS25 Gangnam");
+        assert_eq!(body["posted_at"], "2025-03-04T00:00:00Z");
+        assert_eq!(body["error"], Value::Null);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/attachments?transaction_id={}", transaction.id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["attachments"].as_array().unwrap().len(), 1);
+
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/attachments/{id}"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json("GET", &extraction_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_records_failed_extractions_and_keeps_the_attachment(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: None,
+            account_id: account.id,
+            asset_id: krw.id,
+            quantity: -3_000.into(),
+            notes: None,
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(serde_json::json!({
+                "transaction_id": transaction.id,
+                "filename": "receipt.png",
+                "content_type": "image/png",
+                "content": BASE64_STANDARD.encode(b"\x89PNG\r\n\x1a\n"),
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = serde_json::from_value::<AttachmentId>(body["id"].clone()).unwrap();
+
+        let attachment = AttachmentRepository
+            .get(pool.begin().await.unwrap(), id)
+            .await
+            .unwrap();
+        let content = AttachmentRepository
+            .get_content(pool.begin().await.unwrap(), id)
+            .await
+            .unwrap();
+        let extraction = ExtractionJob {
+            attachment,
+            content,
+        }
+        .run(&pool, &FakeExtractor)
+        .await
+        .unwrap();
+        assert!(extraction.error.is_some());
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/attachments/{id}/extraction"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "failed");
+        assert_eq!(body["merchant"], Value::Null);
+        assert!(body["error"].as_str().unwrap().contains("image/png"));
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/attachments/{id}"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["filename"], "receipt.png");
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_does_not_trust_the_names_and_types_of_uploads(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: None,
+            account_id: account.id,
+            asset_id: krw.id,
+            quantity: (-9_000).into(),
+            notes: None,
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        let upload = |filename: &str, content_type: &str, content: &[u8]| {
+            serde_json::json!({
+                "transaction_id": transaction.id,
+                "filename": filename,
+                "content_type": content_type,
+                "content": BASE64_STANDARD.encode(content),
+            })
+        };
+
+        // A GIF with a script after it is neither a PNG nor allowed as HTML.
+        let polyglot = b"GIF89a/*<html><script>alert(document.cookie)</script>*/";
+        let (status, body) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload("receipt.png", "image/png", polyglot)),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["message"],
+            "The content is `image/gif`, not the declared `image/png`."
+        );
+        let (status, _) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload("receipt.html", "text/html", polyglot)),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // A PDF that is HTML as well is kept, but only ever served as a
+        // PDF to download.
+        let polyglot = b"%PDF-1.7\n<html><script>alert(document.cookie)</script></html>";
+        let (status, body) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload(
+                "../../../etc/passwd.pdf",
+                "Application/PDF; charset=binary",
+                polyglot,
+            )),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["filename"], "passwd.pdf");
+        assert_eq!(body["content_type"], "application/pdf");
+        let content_uri = format!("/api/attachments/{}/content", body["id"].as_str().unwrap());
+        let (status, headers, content) =
+            get_document(&content_uri, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content, polyglot);
+        assert_eq!(headers["content-type"], "application/pdf");
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(
+            headers["content-disposition"],
+            "attachment; filename=\"passwd.pdf\"; filename*=UTF-8''passwd.pdf"
+        );
+        let (status, _, _) = get_document(&content_uri, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(upload("영수증 3월.pdf", "application/pdf", b"%PDF-1.7\n")),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["filename"], "영수증 3월.pdf");
+        let (status, headers, _) = get_document(
+            &format!("/api/attachments/{}/content", body["id"].as_str().unwrap()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            headers["content-disposition"],
+            "attachment; filename=\"___ 3_.pdf\"; filename*=UTF-8''%EC%98%81%EC%88%98%EC%A6%9D%203%EC%9B%94.pdf"
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_resumes_downloads_of_attachments_in_ranges(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: None,
+            account_id: account.id,
+            asset_id: krw.id,
+            quantity: (-9_000).into(),
+            notes: None,
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        let blob = [
+            b"%PDF-1.7\n".as_slice(),
+            &(0..1_000).map(|x| (x % 251) as u8).collect::<Vec<_>>(),
+        ]
+        .concat();
+        let (status, body) = send_json(
+            "POST",
+            "/api/attachments",
+            Some(serde_json::json!({
+                "transaction_id": transaction.id,
+                "filename": "statement.pdf",
+                "content_type": "application/pdf",
+                "content": BASE64_STANDARD.encode(&blob),
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let content_uri = format!("/api/attachments/{}/content", body["id"].as_str().unwrap());
+
+        let (status, headers, content) =
+            get_document(&content_uri, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content, blob);
+        assert_eq!(headers["accept-ranges"], "bytes");
+        let etag = headers["etag"].to_str().unwrap().to_owned();
+
+        let len = blob.len();
+        let mut reassembled = vec![];
+        for (range, content_range) in [
+            ("bytes=0-399".to_owned(), format!("bytes 0-399/{len}")),
+            ("bytes=400-799".to_owned(), format!("bytes 400-799/{len}")),
+            (
+                "bytes=800-".to_owned(),
+                format!("bytes 800-{}/{len}", len - 1),
+            ),
+        ] {
+            let (status, headers, content) = get_range(
+                &content_uri,
+                &range,
+                Some(&etag),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+            assert_eq!(headers["content-range"], content_range.as_str());
+            assert_eq!(headers["etag"], etag.as_str());
+            assert_eq!(headers["content-type"], "application/pdf");
+            reassembled.extend(content);
+        }
+        assert_eq!(reassembled, blob);
+
+        let (status, _, content) =
+            get_range(&content_uri, "bytes=-8", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content, blob[len - 8..]);
+
+        // A file that changed since the client started is sent in full.
+        let (status, headers, content) = get_range(
+            &content_uri,
+            "bytes=400-",
+            Some("\"another\""),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!headers.contains_key("content-range"));
+        assert_eq!(content, blob);
+
+        let (status, headers, content) = get_range(
+            &content_uri,
+            &format!("bytes={len}-"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(headers["content-range"], format!("bytes */{len}").as_str());
+        assert!(content.is_empty());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_manages_announcements_as_an_admin(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_enforcer = Enforcer::new(
+            AUTH_MODEL_PATH.get().unwrap().as_str(),
+            AUTH_POLICY_PATH.get().unwrap().as_str(),
+        )
+        .await
+        .unwrap();
+        admin_enforcer.enable_auto_save(false);
+        admin_enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "announcements".to_owned(),
+                "*".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let mut admin_api = create_api(pool, Arc::new(admin_enforcer));
+        let user = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        let now = Utc::now().trunc_subsecs(0);
+        let announcement = |message: &str, starts_at: TimeDelta, ends_at: TimeDelta| {
+            serde_json::json!({
+                "message": message,
+                "severity": "warning",
+                "starts_at": (now + starts_at).to_rfc3339(),
+                "ends_at": (now + ends_at).to_rfc3339(),
+            })
+        };
+        let maintenance = announcement(
+            "Maintenance Sunday 02:00 UTC",
+            TimeDelta::hours(-1),
+            TimeDelta::hours(1),
+        );
+
+        let (status, _) = send_json(
+            "POST",
+            "/api/announcements",
+            Some(maintenance.clone()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send_json(
+            "GET",
+            "/api/announcements",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/announcements",
+            Some(announcement(
+                "Backwards",
+                TimeDelta::hours(1),
+                TimeDelta::hours(-1),
+            )),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "An announcement must end after it starts.");
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/announcements",
+            Some(maintenance),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["created_by"], serde_json::json!(user.id));
+        let id = body["id"].as_str().unwrap().to_owned();
+        for (message, starts_at, ends_at) in [
+            ("Expired", TimeDelta::hours(-2), TimeDelta::minutes(-1)),
+            ("Upcoming", TimeDelta::hours(1), TimeDelta::hours(2)),
+        ] {
+            let (status, _) = send_json(
+                "POST",
+                "/api/announcements",
+                Some(announcement(message, starts_at, ends_at)),
+                &user_auth_token,
+                &mut admin_api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/announcements/active",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["announcements"],
+            serde_json::json!([{
+                "id": id,
+                "message": "Maintenance Sunday 02:00 UTC",
+                "severity": "warning",
+                "ends_at": (now + TimeDelta::hours(1)).to_rfc3339(),
+            }])
+        );
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/announcements?max_items=2",
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["announcements"].as_array().unwrap().len(), 2);
+        assert_eq!(body["announcements"][0]["message"], "Upcoming");
+        let (status, body) = send_json(
+            "GET",
+            &format!(
+                "/api/announcements?cursor={}",
+                body["next_cursor"].as_str().unwrap()
+            ),
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["announcements"].as_array().unwrap().len(), 1);
+        assert_eq!(body["announcements"][0]["message"], "Expired");
+
+        let (status, body) = send_json(
+            "PATCH",
+            &format!("/api/announcements/{id}"),
+            Some(serde_json::json!({ "message": "Maintenance moved to Monday" })),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "Maintenance moved to Monday");
+        assert_eq!(body["severity"], "warning");
+
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/announcements/{id}"),
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json(
+            "GET",
+            &format!("/api/announcements/{id}"),
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, body) = send_json(
+            "GET",
+            "/api/announcements/active",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["announcements"], serde_json::json!([]));
+    }
+
+    #[sqlx::test]
+    async fn it_activates_announcements_from_start_until_end(pool: Pool<Postgres>) {
+        let starts_at = "2025-06-01T02:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let ends_at = starts_at + TimeDelta::hours(2);
+        AnnouncementRepository
+            .create(
+                pool.begin().await.unwrap(),
+                AnnouncementCreate {
+                    message: "Maintenance".into(),
+                    severity: AnnouncementSeverity::Info,
+                    starts_at,
+                    ends_at,
+                    created_by: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        for (active_at, active) in [
+            (starts_at - TimeDelta::microseconds(1), false),
+            (starts_at, true),
+            (ends_at - TimeDelta::microseconds(1), true),
+            (ends_at, false),
+        ] {
+            let announcements = AnnouncementRepository
+                .get_list(
+                    pool.begin().await.unwrap(),
+                    0,
+                    None,
+                    AnnouncementFilter {
+                        active_at: Some(active_at),
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(announcements.len(), usize::from(active), "at {active_at}");
+        }
+    }
+
+    fn transaction_filter() -> TransactionFilter {
+        TransactionFilter {
+            account_id: None,
+            asset_id: None,
+            description: None,
+            quantity: None,
+            max_quantity: None,
+            min_quantity: None,
+            direction: None,
+            max_abs_quantity: None,
+            min_abs_quantity: None,
+            posted_at: None,
+            posted_before: None,
+            posted_after: None,
+            posted_from: None,
+            created_after: None,
+            created_before: None,
+            updated_after: None,
+            updated_before: None,
+        }
+    }
+
+    #[sqlx::test(fixtures("institutions", "assets", "transactions"))]
+    async fn it_lists_the_same_transactions_as_the_hand_written_query(pool: Pool<Postgres>) {
+        let alice = UserId(uuid::Uuid::from_u128(0xa));
+        let savings = AccountId(uuid::Uuid::from_u128(0xa2));
+        let usd = sqlx::query_scalar::<_, AssetId>(r#"SELECT id FROM asset WHERE symbol = 'USD'"#)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let posted_after = "2025-01-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let posted_before = "2025-01-05T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let cases = [
+            (None, transaction_filter()),
+            (
+                None,
+                TransactionFilter {
+                    description: Some("COFFEE".into()),
+                    ..transaction_filter()
+                },
+            ),
+            (
+                None,
+                TransactionFilter {
+                    asset_id: Some(usd),
+                    min_quantity: Some(Decimal::from(-5)),
+                    max_quantity: Some(Decimal::from(100)),
+                    ..transaction_filter()
+                },
+            ),
+            (
+                Some(vec![savings]),
+                TransactionFilter {
+                    posted_after: Some(posted_after),
+                    posted_before: Some(posted_before),
+                    ..transaction_filter()
+                },
+            ),
+        ];
+
+        for (account_ids, filter) in cases {
+            // The query `get_list_with_user_id` made before its predicates
+            // were shared with `get_list`.
+            let mut expected = sqlx::query_scalar::<_, i64>(
+                r#"
+                SELECT t.id
+                FROM "transaction" t
+                WHERE t.account_id IN (SELECT id FROM account WHERE user_id = $1)
+                AND ($2::UUID[] IS NULL OR t.account_id = ANY($2))
+                AND ($3::TEXT IS NULL OR t.description ILIKE '%' || $3 || '%')
+                AND ($4::UUID IS NULL OR t.asset_id = $4)
+                AND ($5::NUMERIC IS NULL OR t.quantity <= $5)
+                AND ($6::NUMERIC IS NULL OR t.quantity >= $6)
+                AND ($7::TIMESTAMPTZ IS NULL OR t.posted_at < $7)
+                AND ($8::TIMESTAMPTZ IS NULL OR t.posted_at > $8)
+                "#,
+            )
+            .bind(alice)
+            .bind(account_ids.clone())
+            .bind(filter.description.clone())
+            .bind(filter.asset_id)
+            .bind(filter.max_quantity)
+            .bind(filter.min_quantity)
+            .bind(filter.posted_before)
+            .bind(filter.posted_after)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            expected.sort();
+            assert!(!expected.is_empty());
+
+            let mut listed = TransactionRepository
+                .get_list_with_user_id(
+                    pool.begin().await.unwrap(),
+                    0,
+                    None,
+                    alice,
+                    account_ids,
+                    filter,
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|transaction| transaction.id.0)
+                .collect::<Vec<_>>();
+            listed.sort();
+            assert_eq!(listed, expected);
+        }
+
+        let all = TransactionRepository
+            .get_list(
+                pool.begin().await.unwrap(),
+                0,
+                None,
+                TransactionFilter {
+                    description: Some("coffee".into()),
+                    ..transaction_filter()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 3);
+    }
+
+    #[sqlx::test(fixtures("institutions", "assets", "transactions"))]
+    async fn it_filters_transactions_by_direction_and_absolute_quantity(pool: Pool<Postgres>) {
+        let alice = UserId(uuid::Uuid::from_u128(0xa));
+        let quantities = |quantities: &[i64]| {
+            let mut quantities = quantities
+                .iter()
+                .copied()
+                .map(Decimal::from)
+                .collect::<Vec<_>>();
+            quantities.sort();
+            quantities
+        };
+
+        // Alice has -5, 3000, -4500, 12 and 100, Bob -6 and -1200.
+        let cases = [
+            (
+                TransactionFilter {
+                    direction: Some(TransactionDirection::Debit),
+                    ..transaction_filter()
+                },
+                quantities(&[-5, -4500]),
+            ),
+            (
+                TransactionFilter {
+                    direction: Some(TransactionDirection::Credit),
+                    ..transaction_filter()
+                },
+                quantities(&[3000, 12, 100]),
+            ),
+            (
+                TransactionFilter {
+                    min_abs_quantity: Some(Decimal::from(100)),
+                    ..transaction_filter()
+                },
+                quantities(&[3000, -4500, 100]),
+            ),
+            (
+                TransactionFilter {
+                    max_abs_quantity: Some(Decimal::from(12)),
+                    ..transaction_filter()
+                },
+                quantities(&[-5, 12]),
+            ),
+            (
+                TransactionFilter {
+                    direction: Some(TransactionDirection::Debit),
+                    min_abs_quantity: Some(Decimal::from(10)),
+                    ..transaction_filter()
+                },
+                quantities(&[-4500]),
+            ),
+            // The signed and absolute bounds all apply.
+            (
+                TransactionFilter {
+                    min_quantity: Some(Decimal::from(-10)),
+                    min_abs_quantity: Some(Decimal::from(10)),
+                    ..transaction_filter()
+                },
+                quantities(&[3000, 12, 100]),
+            ),
+            (
+                TransactionFilter {
+                    direction: Some(TransactionDirection::Credit),
+                    max_quantity: Some(Decimal::from(100)),
+                    min_abs_quantity: Some(Decimal::from(50)),
+                    ..transaction_filter()
+                },
+                quantities(&[100]),
+            ),
+            (
+                TransactionFilter {
+                    direction: Some(TransactionDirection::Debit),
+                    min_quantity: Some(Decimal::ZERO),
+                    ..transaction_filter()
+                },
+                quantities(&[]),
+            ),
+        ];
+
+        for (filter, expected) in cases {
+            let mut listed = TransactionRepository
+                .get_list_with_user_id(pool.begin().await.unwrap(), 0, None, alice, None, filter)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|transaction| transaction.quantity)
+                .collect::<Vec<_>>();
+            listed.sort();
+            assert_eq!(listed, expected);
+        }
+
+        let mut listed = TransactionRepository
+            .get_list(
+                pool.begin().await.unwrap(),
+                0,
+                None,
+                TransactionFilter {
+                    direction: Some(TransactionDirection::Debit),
+                    max_abs_quantity: Some(Decimal::from(1200)),
+                    ..transaction_filter()
+                },
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|transaction| transaction.quantity)
+            .collect::<Vec<_>>();
+        listed.sort();
+        assert_eq!(listed, quantities(&[-5, -6, -1200]));
+    }
+
+    #[sqlx::test(fixtures("institutions", "assets", "transactions"))]
+    async fn it_binds_descriptions_instead_of_injecting_them(pool: Pool<Postgres>) {
+        let alice = UserId(uuid::Uuid::from_u128(0xa));
+        for description in [
+            "' OR 1=1 --",
+            "%' OR '1'='1",
+            r#"'; DROP TABLE "transaction"; --"#,
+            r#"'); DELETE FROM "transaction" WHERE ('1'='1"#,
+        ] {
+            let filter = TransactionFilter {
+                description: Some(description.into()),
+                ..transaction_filter()
+            };
+            let listed = TransactionRepository
+                .get_list_with_user_id(pool.begin().await.unwrap(), 0, None, alice, None, filter)
+                .await
+                .unwrap();
+            assert!(listed.is_empty(), "{description} matched {listed:?}");
+
+            let filter = TransactionFilter {
+                description: Some(description.into()),
+                ..transaction_filter()
+            };
+            let listed = TransactionRepository
+                .get_list(pool.begin().await.unwrap(), 0, None, filter)
+                .await
+                .unwrap();
+            assert!(listed.is_empty(), "{description} matched {listed:?}");
+        }
+
+        let count = sqlx::query_scalar::<_, i64>(r#"SELECT COUNT(*) FROM "transaction""#)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 7);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("assets"))]
+    async fn it_serves_assets_from_the_cache_until_a_write(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut enforcer = Arc::into_inner(enforcer).unwrap();
+        enforcer.enable_auto_save(false);
+        enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "assets".to_owned(),
+                "create".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let mut api = create_api(pool.clone(), Arc::new(enforcer));
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let sgd = get_asset_by_symbol(&user_auth_token, &mut api, "SGD").await;
+        let sgd_uri = format!("/api/assets/{}", sgd.id);
+
+        let (status, body) =
+            send_json("GET", "/api/assets", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["assets"].as_array().unwrap().len(), 8);
+        let (status, _) = send_json("GET", &sgd_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Deleting behind the back of the service shows the reads no longer
+        // reach the repository.
+        AssetRepository
+            .delete(pool.begin().await.unwrap(), sgd.id)
+            .await
+            .unwrap();
+        let (status, body) =
+            send_json("GET", "/api/assets", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["assets"].as_array().unwrap().len(), 8);
+        let (status, _) = send_json("GET", &sgd_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = send_json(
+            "POST",
+            "/api/assets",
+            Some(serde_json::json!({ "name": "Swiss Franc", "symbol": "CHF" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, body) =
+            send_json("GET", "/api/assets", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        let symbols = body["assets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["symbol"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(symbols.len(), 8);
+        assert!(symbols.contains(&"CHF"));
+        assert!(!symbols.contains(&"SGD"));
+        let (status, _) = send_json("GET", &sgd_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_round_trips_quantities_above_two_to_the_fifty_three(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Test Account".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let asset = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        // 2^53 + 1, the first integer a JavaScript number cannot hold.
+        let quantity = 9_007_199_254_740_993_i64;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions?numbers=string",
+            Some(serde_json::json!({
+                "posted_at": Utc::now().to_rfc3339(),
+                "account_id": account.id,
+                "asset_id": asset.id,
+                "quantity": quantity.to_string(),
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["quantity"], serde_json::json!(quantity.to_string()));
+        let uri = format!("/api/transactions/{}", body["id"].as_str().unwrap());
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("{uri}?numbers=string"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quantity"], serde_json::json!(quantity.to_string()));
+
+        let request = Request::builder()
+            .method("GET")
+            .header("Authorization", &user_auth_token)
+            .header("Accept", "application/json; numbers=string")
+            .uri(&uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(&mut api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = serde_json::from_slice::<Value>(&body).unwrap();
+        assert_eq!(body["quantity"], serde_json::json!(quantity.to_string()));
+
+        // Numbers stay the default, and are still accepted on input.
+        let (status, body) = send_json(
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({ "quantity": quantity - 1 })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quantity"], serde_json::json!(quantity - 1));
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quantity"].as_i64(), Some(quantity - 1));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_keeps_quantities_at_the_precision_of_their_asset(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut enforcer = Arc::into_inner(enforcer).unwrap();
+        enforcer.enable_auto_save(false);
+        enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "assets".to_owned(),
+                "create".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let mut api = create_api(pool, Arc::new(enforcer));
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Test Account".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+
+        let (status, _) = send_json(
+            "POST",
+            "/api/assets",
+            Some(serde_json::json!({ "name": "Too Fine", "symbol": "TFN", "decimals": 19 })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, body) = send_json(
+            "POST",
+            "/api/assets",
+            Some(serde_json::json!({ "name": "Bitcoin", "symbol": "BTC", "decimals": 8 })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["decimals"], 8);
+        let btc = body["id"].clone();
+
+        let mut create = async |quantity: Value| {
+            send_json(
+                "POST",
+                "/api/transactions",
+                Some(serde_json::json!({
+                    "posted_at": Utc::now().to_rfc3339(),
+                    "account_id": account.id,
+                    "asset_id": btc,
+                    "quantity": quantity,
+                })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await
+        };
+        // Clients from before decimal quantities send and read integers of
+        // the smallest unit.
+        let (status, body) = create(serde_json::json!(150_000_000)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["quantity"], 150_000_000);
+        let uri = format!("/api/transactions/{}", body["id"].as_str().unwrap());
+        let (status, smallest) = create(serde_json::json!("0.00000001")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(smallest["quantity"], 1);
+        let (status, _) = create(serde_json::json!("0.000000001")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("{uri}?numbers=string"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quantity"], "1.50000000");
+
+        let (status, body) = send_json(
+            "PATCH",
+            &format!("{uri}?numbers=string"),
+            Some(serde_json::json!({ "quantity": "-0.25" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quantity"], "-0.25000000");
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("assets"))]
+    async fn it_traces_requests_through_the_service_and_repository(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let recorder = SpanRecorder::default();
+        let _guard = tracing::subscriber::set_default(Registry::default().with(recorder.clone()));
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+
+        let (status, _) = send_json("GET", "/api/assets", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let spans = recorder.0.lock().unwrap().clone();
+        let parent_of = |name: &str| {
+            spans
+                .iter()
+                .find(|(span, _)| span == name)
+                .map(|(_, parent)| parent.clone())
+                .unwrap_or_else(|| panic!("No `{name}` span in {spans:?}"))
+        };
+        assert_eq!(parent_of("request"), None);
+        parent_of("AssetService::get_list");
+        assert_eq!(
+            parent_of("AssetRepository::get_list").as_deref(),
+            Some("AssetService::get_list")
+        );
+        assert!(spans.iter().any(|(span, parent)| span == "db.query"
+            && parent.as_deref() == Some("AssetRepository::get_list")));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_manages_export_schedules_without_returning_secrets(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/export-schedules",
+            Some(serde_json::json!({ "cadence": "every day" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "`every day` is not a valid cadence.");
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/export-schedules",
+            Some(serde_json::json!({
+                "format": "csv",
+                "destination": {
+                    "kind": "s3",
+                    "bucket": "backups",
+                    "region": "us-east-1",
+                    "access_key_id": "AKIAEXAMPLE",
+                    "secret_access_key": "secret",
+                },
+                "cadence": "30 2 * * 1",
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            body["destination"],
+            serde_json::json!({ "kind": "s3", "bucket": "backups", "region": "us-east-1" })
+        );
+        let uri = format!("/api/export-schedules/{}", body["id"].as_str().unwrap());
+
+        let (status, _) = send_json("GET", &uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/export-schedules",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["export_schedules"].as_array().unwrap().len(), 1);
+        assert!(!body.to_string().contains("secret"));
+
+        let (status, body) = send_json(
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({
+                "destination": {
+                    "kind": "s3",
+                    "bucket": "archive",
+                    "region": "us-east-1",
+                },
+                "cadence": "@weekly",
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["cadence"], "@weekly");
+        assert_eq!(body["destination"]["bucket"], "archive");
+        assert!(body["destination"].get("secret_access_key").is_none());
+        let id = serde_json::from_value::<ExportScheduleId>(body["id"].clone()).unwrap();
+        let stored = ExportScheduleRepository
+            .get(pool.begin().await.unwrap(), id)
+            .await
+            .unwrap();
+        assert_eq!(
+            export::open(&stored.destination_config).unwrap(),
+            DestinationConfig::S3 {
+                bucket: "archive".into(),
+                region: "us-east-1".into(),
+                endpoint: None,
+                prefix: None,
+                access_key_id: Some("AKIAEXAMPLE".into()),
+                secret_access_key: Some("secret".into()),
+            }
+        );
+
+        let (status, _) = send_json(
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({ "destination": { "kind": "none" } })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = send_json(
+            "POST",
+            &format!("{uri}/run"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "The schedule has no destination.");
+
+        let (status, _) = send_json("DELETE", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    struct FailingDestination;
+
+    #[async_trait::async_trait]
+    impl StorageDestination for FailingDestination {
+        async fn upload(&self, _name: &str, _content: Vec<u8>) -> Result<(), ExportError> {
+            Err(ExportError::Upload("The bucket does not exist.".into()))
+        }
+
+        async fn size(&self, _name: &str) -> Result<u64, ExportError> {
+            Err(ExportError::NotFound)
+        }
+
+        async fn read_range(
+            &self,
+            _name: &str,
+            _range: std::ops::Range<u64>,
+        ) -> Result<Vec<u8>, ExportError> {
+            Err(ExportError::NotFound)
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_uploads_scheduled_exports_and_records_the_outcome(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now().trunc_subsecs(0),
+            description: Some("Rent, March".into()),
+            account_id: account.id,
+            asset_id: krw.id,
+            quantity: -800_000.into(),
+            notes: None,
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/export-schedules",
+            Some(serde_json::json!({
+                "destination": {
+                    "kind": "webdav",
+                    "url": "https://dav.example.com/backups",
+                    "username": "user",
+                    "password": "hunter2",
+                },
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["cadence"], "@daily");
+        let id = serde_json::from_value::<ExportScheduleId>(body["id"].clone()).unwrap();
+        let export_schedule = ExportScheduleRepository
+            .get(pool.begin().await.unwrap(), id)
+            .await
+            .unwrap();
+
+        let store = Arc::new(InMemory::new());
+        let destination = ObjectStoreDestination::new(store.clone(), Some("treasury".into()));
+        let ran_at = Utc::now().trunc_subsecs(0);
+        let job = ExportJob {
+            export_schedule: export_schedule.clone(),
+            csv_options: None,
+            posted: ResolvedDateRange::default(),
+        };
+        let name = job.file_name(ran_at);
+        let export_schedule = job.run(&pool, &destination, ran_at).await.unwrap();
+        assert_eq!(export_schedule.last_run_at, Some(ran_at));
+        assert_eq!(
+            export_schedule.last_status,
+            Some(ExportRunStatus::Succeeded)
+        );
+        assert_eq!(export_schedule.last_error, None);
+
+        let content = store
+            .get(&ObjectPath::from(format!("treasury/{name}")))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let content = serde_json::from_slice::<Value>(&content).unwrap();
+        assert_eq!(content["accounts"][0]["id"], serde_json::json!(account.id));
+        assert_eq!(
+            content["transactions"][0]["id"],
+            serde_json::json!(transaction.id)
+        );
+        assert_eq!(content["transactions"][0]["description"], "Rent, March");
+
+        let export_schedule = ExportJob {
+            export_schedule,
+            csv_options: None,
+            posted: ResolvedDateRange::default(),
+        }
+        .run(&pool, &FailingDestination, ran_at)
+        .await
+        .unwrap();
+        assert_eq!(export_schedule.last_status, Some(ExportRunStatus::Failed));
+        assert_eq!(
+            export_schedule.last_error.as_deref(),
+            Some("The upload failed: The bucket does not exist.")
+        );
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/export-schedules/{}", id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["last_status"], "failed");
+        assert!(body["destination"].get("password").is_none());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_formats_csv_exports_for_the_locale(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: "2025-04-29T09:30:00Z".parse::<DateTime<Utc>>().unwrap(),
+            description: Some("Rent, March".into()),
+            account_id: account.id,
+            asset_id: krw.id,
+            quantity: (-800_000).into(),
+            notes: None,
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/export-schedules",
+            Some(serde_json::json!({
+                "format": "csv",
+                "destination": {
+                    "kind": "webdav",
+                    "url": "https://dav.example.com/backups",
+                    "username": "user",
+                    "password": "hunter2",
+                },
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = serde_json::from_value::<ExportScheduleId>(body["id"].clone()).unwrap();
+
+        let (status, body) = send_json(
+            "POST",
+            &format!("/api/export-schedules/{}/run?locale=fr-FR", id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["message"],
+            "`fr-FR` is not a supported locale, use one of en-US, ko-KR, de-DE."
+        );
+        let (status, _) = send_json(
+            "PATCH",
+            "/api/me/preferences",
+            Some(serde_json::json!({ "locale": "de" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, body) = send_json(
+            "PATCH",
+            "/api/me/preferences",
+            Some(serde_json::json!({ "locale": "de-DE" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["locale"], "de-DE");
+
+        let export_schedule = ExportScheduleRepository
+            .get(pool.begin().await.unwrap(), id)
+            .await
+            .unwrap();
+        let store = Arc::new(InMemory::new());
+        let destination = ObjectStoreDestination::new(store.clone(), None);
+        let ran_at = Utc::now().trunc_subsecs(0);
+        for (csv_options, expected) in [
+            // Without options, the preferred locale of the user.
+            (
+                None,
+                format!(
+                    "id;posted_at;account_id;asset_id;quantity;description;notes\n\
+                     {};29.04.2025;{};{};-800000;Rent, March;\n",
+                    transaction.id.0, account.id.0, krw.id.0,
+                ),
+            ),
+            (
+                Some(
+                    CsvOptions::resolve(
+                        &ExportOptionsRequest {
+                            locale: Some("ko-KR".into()),
+                            bom: Some(true),
+                            ..Default::default()
+                        },
+                        Some("de-DE"),
+                    )
+                    .unwrap(),
+                ),
+                format!(
+                    "\u{feff}id,posted_at,account_id,asset_id,quantity,description,notes\n\
+                     {},2025-04-29,{},{},-800000,\"Rent, March\",\n",
+                    transaction.id.0, account.id.0, krw.id.0,
+                ),
+            ),
+        ] {
+            let job = ExportJob {
+                export_schedule: export_schedule.clone(),
+                csv_options,
+                posted: ResolvedDateRange::default(),
+            };
+            let name = job.file_name(ran_at);
+            let export_schedule = job.run(&pool, &destination, ran_at).await.unwrap();
+            assert_eq!(
+                export_schedule.last_status,
+                Some(ExportRunStatus::Succeeded)
+            );
+            let content = store
+                .get(&ObjectPath::from(name))
+                .await
+                .unwrap()
+                .bytes()
+                .await
+                .unwrap();
+            assert_eq!(content.as_ref(), expected.as_bytes());
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_syncs_changes_since_the_last_sync(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let checking = create_account(
+            &AccountCreateRequest {
+                name: "Checking".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let savings = create_account(
+            &AccountCreateRequest {
+                name: "Savings".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let mut transactions = vec![];
+        for quantity in [-1_000_i64, -2_000] {
+            let create_request = TransactionCreateRequest {
+                posted_at: Utc::now().trunc_subsecs(0),
+                description: None,
+                account_id: checking.id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            transactions
+                .push(create_transaction(&create_request, &user_auth_token, &mut api).await);
+        }
+        let ids = |body: &Value, key: &str| {
+            body[key]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["id"].to_string().trim_matches('"').to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let (status, body) = send_json("GET", "/api/sync", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            ids(&body, "accounts"),
+            vec![checking.id.0.to_string(), savings.id.0.to_string()]
+        );
+        assert_eq!(
+            ids(&body, "transactions"),
+            vec![
+                transactions[0].id.0.to_string(),
+                transactions[1].id.0.to_string()
+            ]
+        );
+        assert_eq!(body["deleted"], serde_json::json!([]));
+        assert_eq!(body["has_more"], false);
+        let since = body["next_since"].as_str().unwrap().to_owned();
+
+        let uri = format!("/api/sync?since={since}");
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["accounts"], serde_json::json!([]));
+        assert_eq!(body["transactions"], serde_json::json!([]));
+        assert_eq!(body["deleted"], serde_json::json!([]));
+
+        // Changes interleaved across both kinds of resource.
+        let (status, _) = send_json(
+            "PATCH",
+            &format!("/api/transactions/{}", transactions[0].id.0),
+            Some(serde_json::json!({ "description": "Coffee" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/transactions/{}", transactions[1].id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/accounts/{}?confirm=true", savings.id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json(
+            "PATCH",
+            &format!("/api/accounts/{}", checking.id.0),
+            Some(serde_json::json!({ "name": "Everyday" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now().trunc_subsecs(0),
+            description: None,
+            account_id: checking.id,
+            asset_id: krw.id,
+            quantity: -3_000.into(),
+            notes: None,
+            category: None,
+        };
+        let created = create_transaction(&create_request, &user_auth_token, &mut api).await;
+
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&body, "accounts"), vec![checking.id.0.to_string()]);
+        assert_eq!(body["accounts"][0]["name"], "Everyday");
+        assert_eq!(
+            ids(&body, "transactions"),
+            vec![transactions[0].id.0.to_string(), created.id.0.to_string()]
+        );
+        assert_eq!(body["transactions"][0]["description"], "Coffee");
+        let deleted = body["deleted"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| (x["resource"].as_str().unwrap(), x["id"].as_str().unwrap()))
+            .collect::<Vec<_>>();
+        let transaction_id = transactions[1].id.0.to_string();
+        let account_id = savings.id.0.to_string();
+        assert_eq!(
+            deleted,
+            vec![
+                ("transaction", transaction_id.as_str()),
+                ("account", account_id.as_str()),
+            ]
+        );
+
+        // Paging one change at a time walks the same changes.
+        let mut since = None::<String>;
+        let mut changes = 0;
+        loop {
+            let uri = match &since {
+                Some(since) => format!("/api/sync?max_items=1&since={since}"),
+                None => "/api/sync?max_items=1".to_owned(),
+            };
+            let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+            assert_eq!(status, StatusCode::OK);
+            let page = ids(&body, "accounts").len()
+                + ids(&body, "transactions").len()
+                + body["deleted"].as_array().unwrap().len();
+            assert!(page <= 1);
+            changes += page;
+            since = body["next_since"].as_str().map(str::to_owned);
+            if body["has_more"] == false {
+                break;
+            }
+        }
+        // One account, two transactions and the two deletions.
+        assert_eq!(changes, 5);
+
+        let (status, body) =
+            send_json("GET", "/api/sync", None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["accounts"], serde_json::json!([]));
+        assert_eq!(body["transactions"], serde_json::json!([]));
+        assert_eq!(body["deleted"], serde_json::json!([]));
+
+        let (status, _) = send_json(
+            "GET",
+            "/api/sync?since=not-a-token",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_clamps_transaction_pages_to_the_configured_max(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let account = create_account(
+            &AccountCreateRequest {
+                name: "Checking".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let page_size = PagedResource::Transactions.page_size();
+        sqlx::query(
+            r#"
+            INSERT INTO "transaction" (posted_at, account_id, asset_id, quantity)
+            SELECT CURRENT_TIMESTAMP, $1, $2, g FROM generate_series(1, $3) g
+            "#,
+        )
+        .bind(account.id)
+        .bind(krw.id)
+        .bind(page_size.max + 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let (status, body) =
+            send_json("GET", "/api/transactions", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["transactions"].as_array().unwrap().len() as i64,
+            page_size.default
+        );
+
+        for max_items in [page_size.max, page_size.max + 1] {
+            let (status, body) = send_json(
+                "GET",
+                &format!("/api/transactions?max_items={max_items}"),
+                None,
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(
+                body["transactions"].as_array().unwrap().len() as i64,
+                page_size.max
+            );
+        }
+
+        let openapi = DocsApi::openapi();
+        let max_items = openapi.paths.paths["/api/transactions"]
+            .get
+            .as_ref()
+            .and_then(|operation| operation.parameters.as_ref())
+            .and_then(|parameters| parameters.iter().find(|x| x.name == "max_items"))
+            .and_then(|parameter| parameter.description.clone())
+            .unwrap();
+        assert!(max_items.ends_with(&format!("at most {}", page_size.max)));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_clamps_account_pages_to_the_configured_max(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let page_size = PagedResource::Accounts.page_size();
+        sqlx::query(
+            r#"
+            INSERT INTO account (user_id, institution_id, name)
+            SELECT $1, $2, 'Account ' || g FROM generate_series(1, $3) g
+            "#,
+        )
+        .bind(user.id)
+        .bind(institution.id)
+        .bind(page_size.max + 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/accounts?max_items={}", page_size.max + 1),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["accounts"].as_array().unwrap().len() as i64,
+            page_size.max
+        );
+
+        // The cursor keeps the clamped size, so asking for more again is
+        // not a mismatch.
+        let cursor = body["next_cursor"].as_str().unwrap().to_owned();
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/accounts?max_items={}&cursor={cursor}", page_size.max),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["accounts"].as_array().unwrap().len(), 1);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_pages_each_row_once_when_rows_tie(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        // Rows inserted by one statement share their `created_at`, and
+        // every fifth shares its name.
+        sqlx::query(
+            r#"
+            INSERT INTO account (user_id, institution_id, name)
+            SELECT $1, $2, 'Account ' || (g % 5) FROM generate_series(1, 50) g
+            "#,
+        )
+        .bind(user.id)
+        .bind(institution.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let created_ats = sqlx::query_scalar::<_, i64>(
+            r#"SELECT COUNT(DISTINCT created_at) FROM account WHERE user_id = $1"#,
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(created_ats, 1);
+
+        for sort in ["", "&sort=name"] {
+            let mut query = format!("max_items=7{sort}");
+            let mut accounts = vec![];
+            loop {
+                let (status, body) = send_json(
+                    "GET",
+                    &format!("/api/accounts?{query}"),
+                    None,
+                    &user_auth_token,
+                    &mut api,
+                )
+                .await;
+                assert_eq!(status, StatusCode::OK);
+                let page = body["accounts"].as_array().unwrap();
+                if page.is_empty() {
+                    break;
+                }
+                accounts.extend(page.iter().map(|x| {
+                    (
+                        x["name"].as_str().unwrap().to_owned(),
+                        x["id"].as_str().unwrap().to_owned(),
+                    )
+                }));
+                let Some(cursor) = body["next_cursor"].as_str() else {
+                    break;
+                };
+                query = format!("cursor={cursor}{sort}");
+            }
+
+            assert_eq!(accounts.len(), 50);
+            if sort.is_empty() {
+                assert!(accounts.windows(2).all(|x| x[0].1 < x[1].1));
+            } else {
+                assert!(accounts.windows(2).all(|x| x[0] < x[1]));
+            }
+            let mut ids = accounts.into_iter().map(|(_, id)| id).collect::<Vec<_>>();
+            ids.sort();
+            ids.dedup();
+            assert_eq!(ids.len(), 50);
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_reverts_transactions_to_before_an_edit(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let mut transactions = vec![];
+        for auth_token in [&user_auth_token, &user_two_auth_token] {
+            let account = create_account(
+                &AccountCreateRequest {
+                    name: "Checking".into(),
+                    institution_id: institution.id,
+                    notes: None,
+                    default_asset_id: None,
+                },
+                auth_token,
+                &mut api,
+            )
+            .await;
+            let create_request = TransactionCreateRequest {
+                posted_at: Utc::now().trunc_subsecs(0),
+                description: Some("Coffee".into()),
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: -1_000.into(),
+                notes: None,
+                category: None,
+            };
+            transactions.push(create_transaction(&create_request, auth_token, &mut api).await);
+        }
+        let uri = format!("/api/transactions/{}", transactions[0].id.0);
+        let other_uri = format!("/api/transactions/{}", transactions[1].id.0);
+
+        for edit in [
+            serde_json::json!({"description": "Tea", "quantity": -2_000}),
+            serde_json::json!({"quantity": -3_000}),
+        ] {
+            let (status, _) =
+                send_json("PATCH", &uri, Some(edit), &user_auth_token, &mut api).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let (status, _) = send_json(
+            "PATCH",
+            &other_uri,
+            Some(serde_json::json!({"quantity": -5_000})),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("{uri}/history"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let history = body["history"].as_array().unwrap().clone();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0]["before"]["quantity"], -2_000);
+        assert_eq!(history[0]["after"]["quantity"], -3_000);
+        assert_eq!(history[1]["before"]["description"], "Coffee");
+        assert_eq!(history[1]["before"]["quantity"], -1_000);
+
+        // Reverting the first edit undoes both.
+        let (status, body) = send_json(
+            "POST",
+            &format!("{uri}/revert/{}", history[1]["id"]),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["description"], "Coffee");
+        assert_eq!(body["quantity"], -1_000);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("{uri}/history"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let history = body["history"].as_array().unwrap().clone();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0]["before"]["quantity"], -3_000);
+        assert_eq!(history[0]["after"]["quantity"], -1_000);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("{other_uri}/history"),
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let other_history_id = body["history"][0]["id"].clone();
+
+        // The history of someone else's transaction, through either one.
+        for (uri, history_id, auth_token) in [
+            (&uri, &other_history_id, &user_auth_token),
+            (&other_uri, &history[0]["id"], &user_auth_token),
+            (&uri, &history[0]["id"], &user_two_auth_token),
+        ] {
+            let (status, _) = send_json(
+                "POST",
+                &format!("{uri}/revert/{history_id}"),
+                None,
+                auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_reports_inconsistent_data(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let user_two = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let mut accounts = vec![];
+        let mut transactions = vec![];
+        for (name, count) in [("Closed", 1), ("Checking", 2)] {
+            let account = create_account(
+                &AccountCreateRequest {
+                    name: name.into(),
+                    institution_id: institution.id,
+                    notes: None,
+                    default_asset_id: None,
+                },
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            for _ in 0..count {
+                let create_request = TransactionCreateRequest {
+                    posted_at: Utc::now().trunc_subsecs(0),
+                    description: Some("Coffee".into()),
+                    account_id: account.id,
+                    asset_id: krw.id,
+                    quantity: -1_000.into(),
+                    notes: None,
+                    category: None,
+                };
+                transactions
+                    .push(create_transaction(&create_request, &user_auth_token, &mut api).await);
+            }
+            accounts.push(account);
+        }
+        let (status, _) = send_json(
+            "PATCH",
+            &format!("/api/transactions/{}", transactions[1].id.0),
+            Some(serde_json::json!({"description": "Tea"})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Balances that overflow, quantities finer than their asset, edits
+        // that bypassed the history, a tombstone of a transaction that
+        // exists and the transactions of a deleted account.
+        sqlx::query(r#"ALTER TABLE "transaction" DISABLE TRIGGER record_transaction_history"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"UPDATE "transaction" SET quantity = 79228162514264337593543950335 WHERE account_id = $1"#,
+        )
+        .bind(accounts[1].id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(r#"UPDATE "transaction" SET quantity = quantity + 0.5 WHERE id = $1"#)
+            .bind(transactions[2].id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO sync_tombstone (user_id, account_id, resource, resource_id)
+            VALUES ($1, $2, 'transaction', $3)
+            "#,
+        )
+        .bind(user.id)
+        .bind(accounts[1].id)
+        .bind(transactions[2].id.0.to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"ALTER TABLE "transaction" DROP CONSTRAINT fk_transaction_account_id_account"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(r#"DELETE FROM account WHERE id = $1"#)
+            .bind(accounts[0].id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let uri = format!("/api/users/{}/integrity", user.id.0);
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        let found = |name: &str| {
+            body["checks"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|check| check["name"] == name)
+                .map(|check| (check["count"].clone(), check["sample_ids"].clone()))
+                .unwrap()
+        };
+        for (name, id) in [
+            (
+                "transactions_of_deleted_accounts",
+                transactions[0].id.0.to_string(),
+            ),
+            (
+                "tombstones_of_existing_rows",
+                transactions[2].id.0.to_string(),
+            ),
+            ("history_out_of_sync", transactions[1].id.0.to_string()),
+            ("quantity_precision", transactions[2].id.0.to_string()),
+            ("balance_overflow", accounts[1].id.0.to_string()),
+        ] {
+            assert_eq!(
+                found(name),
+                (serde_json::json!(1), serde_json::json!([id])),
+                "{name}"
+            );
+        }
+
+        // The checks only run once a minute.
+        let (status, _) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+        let (status, _) = send_json("GET", &uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/users/{}/integrity", user_two.id.0),
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(
+            body["checks"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .all(|check| check["count"] == 0)
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_reports_onboarding_progress(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let uri = "/api/me/onboarding-state";
+        let step = async |api: &mut RouterIntoService<Body>| {
+            let (status, body) = send_json("GET", uri, None, &user_auth_token, api).await;
+            assert_eq!(status, StatusCode::OK);
+            (body["step"].clone(), body["complete"].clone())
+        };
+        assert_eq!(
+            step(&mut api).await,
+            (serde_json::json!("register"), serde_json::json!(false))
+        );
+
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(
+            step(&mut api).await,
+            (serde_json::json!("currency"), serde_json::json!(false))
+        );
+
+        let (status, _) = send_json(
+            "PATCH",
+            "/api/me/preferences",
+            Some(serde_json::json!({"default_asset_id": uuid::Uuid::nil()})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let (status, body) = send_json(
+            "PATCH",
+            "/api/me/preferences",
+            Some(serde_json::json!({"default_asset_id": krw.id})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["default_asset_id"], krw.id.0.to_string());
+        assert_eq!(
+            step(&mut api).await,
+            (serde_json::json!("account"), serde_json::json!(false))
+        );
+
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let _ = create_account(
+            &AccountCreateRequest {
+                name: "Checking".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(
+            step(&mut api).await,
+            (serde_json::json!("done"), serde_json::json!(true))
+        );
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/me/preferences",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["default_asset_id"], krw.id.0.to_string());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_keeps_how_long_the_app_may_sit_idle(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let (status, body) = send_json(
+            "GET",
+            "/api/me/preferences",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["idle_lock_minutes"], 10);
+        assert_eq!(body["idle_lock_while_hidden"], true);
+
+        for idle_lock_minutes in [-1, 24 * 60 + 1] {
+            let (status, _) = send_json(
+                "PATCH",
+                "/api/me/preferences",
+                Some(serde_json::json!({ "idle_lock_minutes": idle_lock_minutes })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        let (status, body) = send_json(
+            "PATCH",
+            "/api/me/preferences",
+            Some(serde_json::json!({ "idle_lock_minutes": 3, "idle_lock_while_hidden": false })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["idle_lock_minutes"], 3);
+        assert_eq!(body["idle_lock_while_hidden"], false);
+
+        // Other preferences leave the idle lock as it was.
+        let (status, body) = send_json(
+            "PATCH",
+            "/api/me/preferences",
+            Some(serde_json::json!({ "double_entry": true })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["idle_lock_minutes"], 3);
+        assert_eq!(body["idle_lock_while_hidden"], false);
+        let (status, body) = send_json(
+            "PATCH",
+            "/api/me/preferences",
+            Some(serde_json::json!({ "idle_lock_minutes": 0 })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["idle_lock_minutes"], 0);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_sanitizes_names_and_descriptions(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let (status, _) = send_json(
+            "POST",
+            "/api/users",
+            Some(serde_json::json!({"name": "Test\u{0}User"})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        for name in ["Checking\u{7}", &"a".repeat(101)] {
+            let (status, _) = send_json(
+                "POST",
+                "/api/accounts",
+                Some(serde_json::json!({"name": name, "institution_id": institution.id})),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        let (status, body) = send_json(
+            "POST",
+            "/api/accounts",
+            Some(serde_json::json!({
+                "name": "Cafe\u{301}\u{202E}gnikcehC",
+                "institution_id": institution.id,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["name"], "Caf\u{e9}gnikcehC");
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions",
+            Some(serde_json::json!({
+                "posted_at": Utc::now().trunc_subsecs(0),
+                "description": "Coffee\u{2066}",
+                "account_id": body["id"],
+                "asset_id": krw.id,
+                "quantity": -1_000,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["description"], "Coffee");
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_lists_the_login_history_of_the_user(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        use crate::{
+            model::login_event::{LoginEventCreate, LoginEventType},
+            resource::{CreateRepository, login_event_repository::LoginEventRepository},
+        };
+
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let user = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        for event_type in [LoginEventType::Login, LoginEventType::Refresh] {
+            LoginEventRepository
+                .create(
+                    pool.begin().await.unwrap(),
+                    LoginEventCreate {
+                        user_id: user.id,
+                        event_type,
+                        issuer: "http://localhost:5556/dex".into(),
+                        ip_address: Some("203.0.113.0".into()),
+                        user_agent: Some("curl/8.0".into()),
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        let uri = format!("/api/users/{}/activity?max_items=1", user.id.0);
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["events"].as_array().unwrap().len(), 1);
+        assert_eq!(body["events"][0]["event_type"], "refresh");
+        assert_eq!(body["events"][0]["ip_address"], "203.0.113.0");
+
+        let uri = format!(
+            "/api/users/{}/activity?cursor={}",
+            user.id.0,
+            body["next_cursor"].as_str().unwrap()
+        );
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["events"][0]["event_type"], "login");
+
+        // Another user cannot see the history.
+        let uri = format!("/api/users/{}/activity", user.id.0);
+        let (status, _) = send_json("GET", &uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_only_lets_admins_backfill_exchange_rates(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_enforcer = Enforcer::new(
+            AUTH_MODEL_PATH.get().unwrap().as_str(),
+            AUTH_POLICY_PATH.get().unwrap().as_str(),
+        )
+        .await
+        .unwrap();
+        admin_enforcer.enable_auto_save(false);
+        admin_enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "exchange_rates".to_owned(),
+                "*".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let mut admin_api = create_api(pool, Arc::new(admin_enforcer));
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        let range = serde_json::json!({ "from": "2025-01-01", "to": "2025-01-03" });
+        let (status, _) = send_json(
+            "POST",
+            "/api/exchange-rates/backfill",
+            Some(range.clone()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // Without a source to ingest from the admin is told so.
+        let (status, _) = send_json(
+            "POST",
+            "/api/exchange-rates/backfill",
+            Some(range),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    /// An enforcer under which every user is an admin.
+    async fn admin_enforcer() -> Arc<Enforcer> {
+        let mut admin_enforcer = Enforcer::new(
+            AUTH_MODEL_PATH.get().unwrap().as_str(),
+            AUTH_POLICY_PATH.get().unwrap().as_str(),
+        )
+        .await
+        .unwrap();
+        admin_enforcer.enable_auto_save(false);
+        admin_enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "admin".to_owned(),
+                "*".to_owned(),
+            ])
+            .await
+            .unwrap();
+        Arc::new(admin_enforcer)
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets", "transactions"))]
+    async fn it_only_lets_admins_read_the_stats(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_api = create_api(pool, admin_enforcer().await);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        let (status, _) =
+            send_json("GET", "/api/admin/stats", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, stats) = send_json(
+            "GET",
+            "/api/admin/stats?exact=true",
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["users"], 3);
+        assert_eq!(stats["accounts"], 3);
+        assert_eq!(stats["transactions"], 7);
+        assert_eq!(stats["transactions_exact"], true);
+        assert_eq!(stats["pending_extractions"], 0);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets", "transactions"))]
+    async fn it_estimates_transactions_unless_asked_to_count_exactly(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_api = create_api(pool.clone(), admin_enforcer().await);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        // The estimate is what the table held when it was last analyzed.
+        sqlx::query(r#"ANALYZE "transaction""#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"INSERT INTO "transaction" (account_id, asset_id, description, posted_at, quantity)
+            SELECT account_id, asset_id, description, posted_at, quantity FROM "transaction""#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let (status, stats) = send_json(
+            "GET",
+            "/api/admin/stats",
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["transactions"], 7);
+        assert_eq!(stats["transactions_exact"], false);
+
+        let (status, stats) = send_json(
+            "GET",
+            "/api/admin/stats?exact=true",
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["transactions"], 14);
+        assert_eq!(stats["transactions_exact"], true);
+    }
+
+    /// The cached balance of an account in an asset and the sum of its
+    /// transactions.
+    async fn cached_and_live_balance(
+        pool: &PgPool,
+        account_id: AccountId,
+        asset_id: AssetId,
+    ) -> (Option<Decimal>, Option<Decimal>) {
+        sqlx::query_as::<_, (Option<Decimal>, Option<Decimal>)>(
+            r#"
+            SELECT
+                (SELECT balance FROM account_balance WHERE account_id = $1 AND asset_id = $2),
+                (SELECT SUM(quantity) FROM "transaction" WHERE account_id = $1 AND asset_id = $2)
+            "#,
+        )
+        .bind(account_id)
+        .bind(asset_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_caches_balances_through_concurrent_transactions(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let account = create_account(
+            &AccountCreateRequest {
+                name: "Test Account".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        let created = futures::future::join_all((1..=20).map(|i| {
+            let mut api = api.clone();
+            let user_auth_token = user_auth_token.clone();
+            async move {
+                let create_request = TransactionCreateRequest {
+                    posted_at: Utc::now(),
+                    description: None,
+                    account_id: account.id,
+                    asset_id: krw.id,
+                    quantity: (i * 100).into(),
+                    notes: None,
+                    category: None,
+                };
+                create_transaction(&create_request, &user_auth_token, &mut api).await
+            }
+        }))
+        .await;
+        let (cached, live) = cached_and_live_balance(&pool, account.id, krw.id).await;
+        assert_eq!(live, Some(Decimal::from(21_000)));
+        assert_eq!(cached, live);
+
+        // Updates and deletions adjust the balance as well.
+        let (status, _) = send_json(
+            "PATCH",
+            &format!("/api/transactions/{}", created[0].id.0),
+            Some(serde_json::json!({"quantity": -50})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/transactions/{}", created[1].id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (cached, live) = cached_and_live_balance(&pool, account.id, krw.id).await;
+        assert_eq!(live, Some(Decimal::from(20_650)));
+        assert_eq!(cached, live);
+
+        let uri = format!("/api/accounts/{}/balances", account.id.0);
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["balances"].as_array().unwrap().len(), 1);
+        assert_eq!(body["balances"][0]["asset_id"], krw.id.0.to_string());
+        assert_eq!(body["balances"][0]["balance"], 20_650);
+
+        // Without a cached balance it is summed from the transactions.
+        sqlx::query("DELETE FROM account_balance")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["balances"][0]["balance"], 20_650);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_finds_and_repairs_balances_that_drifted(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let user = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let account = create_account(
+            &AccountCreateRequest {
+                name: "Test Account".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let create_request = TransactionCreateRequest {
+            posted_at: Utc::now(),
+            description: None,
+            account_id: account.id,
+            asset_id: krw.id,
+            quantity: 1_000.into(),
+            notes: None,
+            category: None,
+        };
+        let transaction = create_transaction(&create_request, &user_auth_token, &mut api).await;
+
+        // A change that bypasses the service leaves the cache behind.
+        sqlx::query(r#"UPDATE "transaction" SET quantity = 3000 WHERE id = $1"#)
+            .bind(transaction.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let uri = format!("/api/users/{}/integrity", user.id.0);
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        let drift = body["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|check| check["name"] == "cached_balance_drift")
+            .unwrap();
+        assert_eq!(drift["count"], 1);
+        assert_eq!(drift["sample_ids"], serde_json::json!([account.id.0]));
+
+        assert_eq!(integrity::repair_balances(&pool, user.id).await.unwrap(), 1);
+        let (cached, live) = cached_and_live_balance(&pool, account.id, krw.id).await;
+        assert_eq!(cached, Some(Decimal::from(3_000)));
+        assert_eq!(cached, live);
+        assert_eq!(integrity::repair_balances(&pool, user.id).await.unwrap(), 0);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets", "institution_tree"))]
+    async fn it_counts_accounts_and_keeps_institutions_in_use(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_enforcer = Enforcer::new(
+            AUTH_MODEL_PATH.get().unwrap().as_str(),
+            AUTH_POLICY_PATH.get().unwrap().as_str(),
+        )
+        .await
+        .unwrap();
+        admin_enforcer.enable_auto_save(false);
+        admin_enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "institutions".to_owned(),
+                "*".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let mut admin_api = create_api(pool, Arc::new(admin_enforcer));
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        for auth_token in [&user_auth_token, &user_two_auth_token] {
+            let create_account_request = AccountCreateRequest {
+                name: "Test Account".into(),
+                institution_id: InstitutionId(SHINHAN.parse().unwrap()),
+                notes: None,
+                default_asset_id: None,
+            };
+            let _ = create_account(&create_account_request, auth_token, &mut api).await;
+        }
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/institutions/permissions",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({ "create": false, "update": false, "delete": false })
+        );
+
+        // Only the accounts of the caller are counted.
+        let account_count = async |api: &mut RouterIntoService<Body>, id: &str| {
+            let (status, body) = send_json(
+                "GET",
+                "/api/institutions?include_counts=true&max_items=100",
+                None,
+                &user_auth_token,
+                api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            body["institutions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["id"] == id)
+                .unwrap()["account_count"]
+                .clone()
+        };
+        assert_eq!(account_count(&mut api, SHINHAN).await, 1);
+        assert_eq!(account_count(&mut api, SHINHAN_BUSAN).await, 0);
+        let (_, body) =
+            send_json("GET", "/api/institutions", None, &user_auth_token, &mut api).await;
+        assert!(body["institutions"][0].get("account_count").is_none());
+
+        let uri = format!("/api/institutions/{SHINHAN}");
+        let (status, body) =
+            send_json("DELETE", &uri, None, &user_auth_token, &mut admin_api).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], 4091);
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/institutions",
+            Some(serde_json::json!({ "name": "Unused Bank" })),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let uri = format!("/api/institutions/{}", body["id"].as_str().unwrap());
+        let (status, _) = send_json("DELETE", &uri, None, &user_auth_token, &mut admin_api).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[rstest]
+    #[case("GET", "/api/not-an-endpoint", StatusCode::NOT_FOUND)]
+    #[case("GET", "/api", StatusCode::NOT_FOUND)]
+    #[case("PUT", "/api/accounts", StatusCode::METHOD_NOT_ALLOWED)]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_negotiates_the_format_of_errors(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[case] method: &str,
+        #[case] uri: &str,
+        #[case] expected_status: StatusCode,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        let mut send = async |method: &str, uri: &str, accept: &str| {
+            let request = Request::builder()
+                .method(method)
+                .header("Authorization", &user_auth_token)
+                .header("Accept", accept)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let response = ServiceExt::<Request<Body>>::ready(&mut api)
+                .await
+                .unwrap()
+                .call(request)
+                .await
+                .unwrap();
+            let status = response.status();
+            let content_type = response
+                .headers()
+                .get("Content-Type")
+                .map(|x| x.to_str().unwrap().to_owned())
+                .unwrap_or_default();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (
+                status,
+                content_type,
+                String::from_utf8(body.to_vec()).unwrap(),
+            )
+        };
+
+        let (status, content_type, body) = send(method, uri, "application/json").await;
+        assert_eq!(status, expected_status);
+        assert!(content_type.starts_with("application/json"));
+        let body = serde_json::from_str::<Value>(&body).unwrap();
+        assert_eq!(body["code"], expected_status.as_u16() * 10);
+
+        let (status, content_type, body) = send(method, uri, "text/plain").await;
+        assert_eq!(status, expected_status);
+        assert!(content_type.starts_with("text/plain"));
+        assert!(serde_json::from_str::<Value>(&body).is_err());
+        assert!(!body.is_empty());
+
+        // Errors raised by an endpoint take the same format.
+        let uri = format!("/api/accounts/{}", uuid::Uuid::new_v4());
+        let (status, content_type, body) = send("GET", &uri, "text/plain").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(content_type.starts_with("text/plain"));
+        assert_eq!(body, "Not found.");
+        let (_, content_type, _) = send("GET", &uri, "text/html, */*;q=0.8").await;
+        assert!(content_type.starts_with("application/json"));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_answers_panics_with_an_internal_server_error(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let panics = panic::panics();
+        let send = async |api: &mut RouterIntoService<Body>, request_id: Option<&str>| {
+            let mut request = Request::builder()
+                .method("GET")
+                .header("Accept", "application/json")
+                .uri("/api/test/panic");
+            if let Some(request_id) = request_id {
+                request = request.header(REQUEST_ID_HEADER, request_id);
+            }
+            let response = ServiceExt::<Request<Body>>::ready(api)
+                .await
+                .unwrap()
+                .call(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (
+                status,
+                headers,
+                serde_json::from_slice::<Value>(&body).unwrap(),
+            )
+        };
+
+        let (status, headers, body) = send(&mut api, Some("edge-1234")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(headers[ERROR_CODE_HEADER], "5000");
+        assert_eq!(headers[REQUEST_ID_HEADER], "edge-1234");
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "code": 5000,
+                "message": "Internal server error.",
+                "request_id": "edge-1234",
+            })
+        );
+        assert!(panic::panics() > panics);
+
+        // Without an id of its own, the request gets one, which the body
+        // shares, and so does one with an id that isn't safe to log.
+        for request_id in [None, Some("not safe to log")] {
+            let (status, headers, body) = send(&mut api, request_id).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+            let request_id = headers[REQUEST_ID_HEADER].to_str().unwrap();
+            assert!(uuid::Uuid::parse_str(request_id).is_ok());
+            assert_eq!(body["request_id"], request_id);
+        }
+
+        // The server keeps answering after a panic.
+        let (status, _) = send_json("GET", "/api/version", None, "", &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    fn it_refuses_server_fn_uris_that_do_not_parse() {
+        assert_eq!(
+            server_fn_uri("/api/accounts?page=2".to_owned()).unwrap(),
+            "/api/accounts?page=2"
+        );
+        let error = server_fn_uri("/api/accounts?name=a b".to_owned()).unwrap_err();
+        assert!(matches!(error, ApiError::ClientError(..)));
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[ERROR_CODE_HEADER], "4001");
+    }
+
+    #[test]
+    fn it_refuses_query_strings_without_the_rejection_in_the_message() {
+        let uri = format!(
+            "/api/accounts?max_items=%3Cscript%3E{}%3C/script%3E",
+            "x".repeat(10_000)
+        )
+        .parse()
+        .unwrap();
+        let rejection =
+            axum::extract::Query::<std::collections::HashMap<String, i64>>::try_from_uri(&uri)
+                .unwrap_err();
+        let error = ApiError::from(rejection);
+        assert!(matches!(
+            error,
+            ApiError::ClientError(ClientErrorCode::InvalidQuery, _)
+        ));
+        let response = ApiErrorResponse::from(&error);
+        assert_eq!(response.code, 4003);
+        assert_eq!(response.message, "The query string is not valid.");
+        for leaked in ["script", "Failed to deserialize", "QueryRejection", "xxx"] {
+            assert!(!response.message.contains(leaked), "{leaked}");
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_translates_errors(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        let send = async |api: &mut RouterIntoService<Body>,
+                          uri: &str,
+                          accept_language: Option<&str>,
+                          accept: &str| {
+            let mut request = Request::builder()
+                .method("GET")
+                .header("Authorization", &user_auth_token)
+                .header("Accept", accept)
+                .uri(uri);
+            if let Some(accept_language) = accept_language {
+                request = request.header("Accept-Language", accept_language);
+            }
+            let response = ServiceExt::<Request<Body>>::ready(api)
+                .await
+                .unwrap()
+                .call(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = response.status();
+            let code = response
+                .headers()
+                .get(ERROR_CODE_HEADER)
+                .map(|x| x.to_str().unwrap().to_owned())
+                .unwrap_or_default();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, code, String::from_utf8(body.to_vec()).unwrap())
+        };
+
+        let account_uri = format!("/api/accounts/{}", uuid::Uuid::new_v4());
+        for uri in ["/api/not-an-endpoint", account_uri.as_str()] {
+            let (status, code, body) =
+                send(&mut api, uri, Some("ko-KR,ko;q=0.9"), "application/json").await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(code, "4040");
+            let body = serde_json::from_str::<Value>(&body).unwrap();
+            assert_eq!(body["code"], 4040);
+            assert_eq!(body["message"], "찾을 수 없습니다.");
+
+            // Languages without a catalog fall back to English.
+            let (_, _, body) = send(&mut api, uri, Some("fr-FR"), "application/json").await;
+            let body = serde_json::from_str::<Value>(&body).unwrap();
+            assert_eq!(body["code"], 4040);
+            assert_eq!(body["message"], "Not found.");
+        }
+
+        // Plain text errors carry the code in a header.
+        let (_, code, body) =
+            send(&mut api, &account_uri, Some("fr, ko;q=0.5"), "text/plain").await;
+        assert_eq!(code, "4040");
+        assert_eq!(body, "찾을 수 없습니다.");
+
+        // Without an `Accept-Language`, the locale of the user is taken.
+        let (status, _) = send_json(
+            "PATCH",
+            "/api/me/preferences",
+            Some(serde_json::json!({ "locale": "ko-KR" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, _, body) = send(&mut api, &account_uri, None, "text/plain").await;
+        assert_eq!(body, "찾을 수 없습니다.");
+        let (_, _, body) = send(&mut api, &account_uri, Some("en"), "text/plain").await;
+        assert_eq!(body, "Not found.");
+
+        // Field errors are translated with the name of the field.
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let (status, body) = send_json(
+            "POST",
+            "/api/accounts",
+            Some(
+                serde_json::to_value(AccountCreateRequest {
+                    name: "a".repeat(101),
+                    institution_id: institution.id,
+                    notes: None,
+                    default_asset_id: None,
+                })
+                .unwrap(),
+            ),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], 4001);
+        assert_eq!(
+            body["message"],
+            "계좌 이름은(는) 최대 100자까지 쓸 수 있습니다."
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_describes_what_a_user_and_an_admin_can_do(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_api = create_api(pool, admin_enforcer().await);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        let (status, _) = send_json("GET", "/api/capabilities", None, "", &mut api).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, body) =
+            send_json("GET", "/api/capabilities", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        let capabilities = serde_json::from_value::<CapabilitiesResponse>(body).unwrap();
+        let institutions = capabilities
+            .resources
+            .iter()
+            .find(|x| x.name == "institutions")
+            .unwrap();
+        assert_eq!(institutions.read, "read");
+        assert_eq!(institutions.create, "none");
+        let page_size = PagedResource::Institutions.page_size();
+        assert_eq!(
+            institutions.page_size,
+            Some(PageSizeResponse {
+                default: page_size.default,
+                max: page_size.max,
+            })
+        );
+        assert!(capabilities.allows("GET", "/api/institutions"));
+        assert!(capabilities.allows("GET", "/api/institutions/{id}"));
+        assert!(!capabilities.allows("POST", "/api/institutions"));
+        assert!(!capabilities.allows("DELETE", "/api/institutions/{id}"));
+        assert!(capabilities.allows("POST", "/api/accounts"));
+        assert!(!capabilities.allows("GET", "/api/admin/stats"));
+        assert_eq!(
+            capabilities.rate_limit,
+            RateLimitResponse::from(RateLimitConfig::from_env())
+        );
+        assert_eq!(capabilities.limits.max_notes_bytes, MAX_NOTES_BYTES);
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/capabilities",
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let capabilities = serde_json::from_value::<CapabilitiesResponse>(body).unwrap();
+        let admin = capabilities
+            .resources
+            .iter()
+            .find(|x| x.name == "admin")
+            .unwrap();
+        assert_eq!(admin.read, "read_all");
+        assert!(capabilities.allows("GET", "/api/admin/stats"));
+    }
+
+    #[sqlx::test]
+    async fn it_lets_only_one_redirect_consume_a_csrf_state(pool: Pool<Postgres>) {
+        let state = CsrfState("csrf-state".into());
+        CsrfTokenRepository
+            .create(
+                pool.begin().await.unwrap(),
+                CsrfTokenCreate {
+                    state: state.clone(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let (first, second) = futures::join!(
+            async {
+                CsrfTokenRepository
+                    .consume(
+                        pool.begin().await.unwrap(),
+                        state.clone(),
+                        csrf::issued_after(),
+                    )
+                    .await
+            },
+            async {
+                CsrfTokenRepository
+                    .consume(
+                        pool.begin().await.unwrap(),
+                        state.clone(),
+                        csrf::issued_after(),
+                    )
+                    .await
+            },
+        );
+        let (consumed, rejected) = match (first, second) {
+            (Ok(consumed), Err(rejected)) | (Err(rejected), Ok(consumed)) => (consumed, rejected),
+            (first, second) => panic!("Expected one consumption to win: {first:?}, {second:?}"),
+        };
+        assert_eq!(consumed.state, state);
+        assert!(consumed.consumed_at.is_some());
+        assert!(matches!(rejected, RepositoryError::AlreadyConsumed));
+
+        let never_issued = CsrfTokenRepository
+            .consume(
+                pool.begin().await.unwrap(),
+                CsrfState("unknown".into()),
+                csrf::issued_after(),
+            )
+            .await;
+        assert!(matches!(never_issued, Err(RepositoryError::NotFound)));
+    }
+
+    #[sqlx::test]
+    async fn it_expires_csrf_states_and_deletes_them(pool: Pool<Postgres>) {
+        for state in ["fresh", "consumed", "expired"] {
+            CsrfTokenRepository
+                .create(
+                    pool.begin().await.unwrap(),
+                    CsrfTokenCreate {
+                        state: CsrfState(state.into()),
+                    },
+                )
+                .await
+                .unwrap();
+        }
+        CsrfTokenRepository
+            .consume(
+                pool.begin().await.unwrap(),
+                CsrfState("consumed".into()),
+                csrf::issued_after(),
+            )
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"UPDATE csrf_token SET created_at = CURRENT_TIMESTAMP - $1 * INTERVAL '1 second' WHERE state = 'expired'"#,
+        )
+        .bind(csrf::STATE_TTL.as_secs() as f64 + 1.0)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // A state past its TTL can't be consumed, however unused it is.
+        let expired = CsrfTokenRepository
+            .consume(
+                pool.begin().await.unwrap(),
+                CsrfState("expired".into()),
+                csrf::issued_after(),
+            )
+            .await;
+        assert!(matches!(expired, Err(RepositoryError::NotFound)));
+
+        // Only the expired state goes, the consumed one is kept until its
+        // TTL is up to tell a replay apart.
+        assert_eq!(csrf::delete_expired(&pool).await.unwrap(), 1);
+        let states =
+            sqlx::query_scalar::<_, String>(r#"SELECT state FROM csrf_token ORDER BY state"#)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(states, vec!["consumed".to_owned(), "fresh".to_owned()]);
+        let replayed = CsrfTokenRepository
+            .consume(
+                pool.begin().await.unwrap(),
+                CsrfState("consumed".into()),
+                csrf::issued_after(),
+            )
+            .await;
+        assert!(matches!(replayed, Err(RepositoryError::AlreadyConsumed)));
+
+        sqlx::query(
+            r#"UPDATE csrf_token SET created_at = CURRENT_TIMESTAMP - $1 * INTERVAL '1 second'"#,
+        )
+        .bind(csrf::STATE_TTL.as_secs() as f64 + 1.0)
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(csrf::delete_expired(&pool).await.unwrap(), 2);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_summarizes_the_finances_of_the_user_on_the_dashboard(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+
+        // A new user has nothing to show yet.
+        let (status, body) =
+            send_json("GET", "/api/dashboard", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["balances"], serde_json::json!([]));
+        assert_eq!(body["recent_transactions"], serde_json::json!([]));
+        assert_eq!(body["budgets"], serde_json::json!([]));
+
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let account = create_account(
+            &AccountCreateRequest {
+                name: "Checking".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let now = Utc::now().trunc_subsecs(0);
+        let mut created = vec![];
+        for (days_ago, quantity) in [(60, 1_000_000_i64), (40, -100_000), (1, -30_000)] {
+            let create_request = TransactionCreateRequest {
+                posted_at: now - TimeDelta::days(days_ago),
+                description: Some(format!("{days_ago} days ago")),
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            created.push(create_transaction(&create_request, &user_auth_token, &mut api).await);
+        }
+        for i in 0..5 {
+            let create_request = TransactionCreateRequest {
+                posted_at: now - TimeDelta::hours(i),
+                description: None,
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: (-1_000).into(),
+                notes: None,
+                category: None,
+            };
+            created.push(create_transaction(&create_request, &user_auth_token, &mut api).await);
+        }
+        let (status, _) = send_json(
+            "POST",
+            "/api/budgets",
+            Some(serde_json::json!({
+                "name": "Spending",
+                "asset_id": krw.id,
+                "amount": 100_000,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, body) =
+            send_json("GET", "/api/dashboard", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["balances"].as_array().unwrap().len(), 1);
+        assert_eq!(body["balances"][0]["symbol"], "KRW");
+        assert_eq!(body["balances"][0]["balance"], 865_000);
+        assert_eq!(body["balances"][0]["last_month_balance"], 900_000);
+
+        // The latest by when they were posted, not by when they were made.
+        let recent = body["recent_transactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["id"].as_i64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            recent,
+            created[3..].iter().map(|x| x.id.0).collect::<Vec<_>>()
+        );
+
+        assert_eq!(body["budgets"].as_array().unwrap().len(), 1);
+        assert_eq!(body["budgets"][0]["name"], "Spending");
+        assert_eq!(body["budgets"][0]["limit"], 100_000);
+
+        // Nothing of one user shows up on the dashboard of another.
+        let (status, body) = send_json(
+            "GET",
+            "/api/dashboard",
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["balances"], serde_json::json!([]));
+        assert_eq!(body["recent_transactions"], serde_json::json!([]));
+        assert_eq!(body["budgets"], serde_json::json!([]));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_lets_callers_cache_the_assets_and_institutions(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        for (uri, auth_token) in [
+            ("/api/assets", Some(&user_auth_token)),
+            ("/api/institutions", Some(&user_auth_token)),
+            ("/api/assets", None),
+            ("/api/institutions", None),
+            ("/api/transactions", Some(&user_auth_token)),
+            ("/pkg/missing.js", None),
+        ] {
+            let mut request = Request::builder()
+                .method("GET")
+                .header("Accept", "application/json")
+                .uri(uri);
+            if let Some(auth_token) = auth_token {
+                request = request.header("Authorization", auth_token);
+            }
+            let response = ServiceExt::<Request<Body>>::ready(&mut api)
+                .await
+                .unwrap()
+                .call(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let headers = response.headers();
+            let cache_control = headers.get(CACHE_CONTROL).map(|x| x.to_str().unwrap());
+            let vary = headers
+                .get_all(VARY)
+                .iter()
+                .map(|x| x.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>();
+            match (uri, auth_token) {
+                ("/api/assets" | "/api/institutions", Some(_)) => {
+                    assert_eq!(response.status(), StatusCode::OK);
+                    assert_eq!(cache_control, Some("private, max-age=60"));
+                    assert!(vary.iter().any(|x| x == "Authorization"));
+                }
+                ("/api/transactions", _) => {
+                    assert_eq!(response.status(), StatusCode::OK);
+                    assert_eq!(cache_control, None);
+                }
+                _ => {
+                    assert!(!response.status().is_success());
+                    assert_eq!(cache_control, None, "{uri} is an error");
+                    assert!(!vary.iter().any(|x| x == "Authorization"));
+                }
+            }
+        }
+    }
+
+    #[rstest]
+    #[case("/api/accounts", StatusCode::NOT_FOUND)]
+    #[case("/api/assets", StatusCode::NOT_FOUND)]
+    #[case("/api/institutions", StatusCode::NOT_FOUND)]
+    #[case("/api/users", StatusCode::NOT_FOUND)]
+    // Transactions are numbered, so a UUID is as malformed as any other id.
+    #[case("/api/transactions", StatusCode::BAD_REQUEST)]
+    #[awt]
+    #[sqlx::test]
+    async fn it_rejects_malformed_ids_in_the_path(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[case] prefix: &str,
+        #[case] nil_uuid_status: StatusCode,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+
+        for (id, expected_status) in [
+            ("not-a-uuid".to_owned(), StatusCode::BAD_REQUEST),
+            (uuid::Uuid::nil().to_string(), nil_uuid_status),
+            ("f".repeat(1_000), StatusCode::BAD_REQUEST),
+        ] {
+            let uri = format!("{prefix}/{id}");
+            let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+            assert_eq!(status, expected_status, "{uri}");
+            if expected_status == StatusCode::BAD_REQUEST {
+                assert_eq!(body["code"], 4002, "{uri}");
+                assert_eq!(body["message"], "Invalid id format.", "{uri}");
+            }
+        }
+    }
+
+    /// Starts a demo session from `peer`.
+    async fn start_demo(api: &mut RouterIntoService<Body>, peer: [u8; 4]) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method("POST")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Accept", "application/json")
+            .uri("/demo/login")
+            .extension(ConnectInfo(SocketAddr::from((peer, 443))))
+            .body(Body::empty())
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_isolates_demo_sessions_from_each_other_and_the_seed(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        // Seeding again on a restart keeps the one demo user.
+        demo::seed_demo(&pool).await.unwrap();
+        demo::seed_demo(&pool).await.unwrap();
+        let seed_user = UserRepository
+            .get_by_iss_and_sub(
+                pool.begin().await.unwrap(),
+                DEMO_ISSUER.to_owned(),
+                DEMO_SEED_SUB.to_owned(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let seed_transactions = async || {
+            sqlx::query_scalar::<_, i64>(
+                r#"
+                SELECT COUNT(*) FROM "transaction"
+                JOIN account ON account.id = "transaction".account_id
+                WHERE account.user_id = $1
+                "#,
+            )
+            .bind(seed_user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        };
+        let seeded = seed_transactions().await;
+        assert!(seeded > 0);
+
+        let (status, _) = start_demo(
+            &mut create_api(pool.clone(), enforcer.clone()),
+            [198, 51, 100, 1],
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let mut api = ApiV1::router_with_config(
+            Arc::new(pool.clone()),
+            enforcer,
+            RouterConfig {
+                demo_config: DemoConfig {
+                    enabled: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .into_service();
+        let mut tokens = vec![];
+        for peer in [[198, 51, 100, 1], [198, 51, 100, 2]] {
+            let (status, body) = start_demo(&mut api, peer).await;
+            assert_eq!(status, StatusCode::OK);
+            let login = serde_json::from_value::<RefreshResponse>(body).unwrap();
+            assert!(login.access_token.starts_with(DEMO_TOKEN_PREFIX));
+            assert!(login.refresh_token.is_none());
+            tokens.push(format!("Bearer {}", login.access_token));
+        }
+        let [first, second] = &tokens[..] else {
+            unreachable!()
+        };
+
+        let transactions = async |auth_token: &str, api: &mut RouterIntoService<Body>| {
+            let (status, body) = send_json(
+                "GET",
+                "/api/transactions?max_items=500",
+                None,
+                auth_token,
+                api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            serde_json::from_value::<TransactionGetListResponse>(body)
+                .unwrap()
+                .transactions
+        };
+        let first_accounts = get_accounts(first, &mut api).await.accounts;
+        let second_accounts = get_accounts(second, &mut api).await.accounts;
+        assert_eq!(first_accounts.len(), 3);
+        assert_eq!(second_accounts.len(), 3);
+        assert!(
+            first_accounts
+                .iter()
+                .all(|x| second_accounts.iter().all(|y| x.id != y.id))
+        );
+        let first_transactions = transactions(first, &mut api).await;
+        let second_transactions = transactions(second, &mut api).await;
+        assert_eq!(first_transactions.len() as i64, seeded);
+        assert_eq!(second_transactions.len() as i64, seeded);
+
+        // Writes succeed within the session that made them.
+        let (status, _) = send_json(
+            "POST",
+            "/api/transactions",
+            Some(
+                serde_json::to_value(TransactionCreateRequest {
+                    posted_at: Utc::now(),
+                    description: Some("Demo coffee".into()),
+                    account_id: first_accounts[0].id,
+                    asset_id: first_transactions[0].asset_id,
+                    quantity: 10.into(),
+                    notes: None,
+                    category: None,
+                })
+                .unwrap(),
+            ),
+            first,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        for transaction in &first_transactions[..2] {
+            let (status, _) = send_json(
+                "DELETE",
+                &format!("/api/transactions/{}", transaction.id.0),
+                None,
+                first,
+                &mut api,
+            )
+            .await;
+            assert!(status.is_success());
+        }
+        assert_eq!(transactions(first, &mut api).await.len() as i64, seeded - 1);
+
+        // Neither the other session nor the seed sees them.
+        assert_eq!(transactions(second, &mut api).await.len() as i64, seeded);
+        assert_eq!(seed_transactions().await, seeded);
+        let (status, _) = send_json(
+            "GET",
+            &format!("/api/transactions/{}", second_transactions[0].id.0),
+            None,
+            first,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Expired sessions are deleted along with their copies.
+        let deleted = demo::delete_expired(
+            &pool,
+            &DemoConfig {
+                enabled: true,
+                session_ttl: Duration::ZERO,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(deleted, 2);
+        let demo_users =
+            sqlx::query_scalar::<_, i64>(r#"SELECT COUNT(*) FROM "user" WHERE iss = $1"#)
+                .bind(DEMO_ISSUER)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(demo_users, 1);
+        assert_eq!(seed_transactions().await, seeded);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_caps_demo_sessions_and_their_starts_per_address(
+        #[future] enforcer: Arc<Enforcer>,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        demo::seed_demo(&pool).await.unwrap();
+        let mut api = ApiV1::router_with_config(
+            Arc::new(pool.clone()),
+            enforcer,
+            RouterConfig {
+                demo_config: DemoConfig {
+                    enabled: true,
+                    max_sessions: 3,
+                    starts_per_ip: 2,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .into_service();
+
+        let (status, _) = send_json("GET", "/demo/login", None, "", &mut api).await;
+        assert_ne!(status, StatusCode::OK);
+
+        for _ in 0..2 {
+            let (status, _) = start_demo(&mut api, [198, 51, 100, 1]).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let (status, body) = start_demo(&mut api, [198, 51, 100, 1]).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["code"], 4290);
+
+        // Other addresses start sessions until the cap is reached.
+        let (status, _) = start_demo(&mut api, [198, 51, 100, 2]).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = start_demo(&mut api, [198, 51, 100, 3]).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        let sessions = sqlx::query_scalar::<_, i64>(
+            r#"SELECT COUNT(*) FROM "user" WHERE iss = $1 AND sub <> $2"#,
+        )
+        .bind(DEMO_ISSUER)
+        .bind(DEMO_SEED_SUB)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(sessions, 3);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_fires_alert_rules_once_per_cooldown(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        let (status, _) = send_json(
+            "POST",
+            "/api/alert-rules",
+            Some(serde_json::json!({
+                "account_id": account.id,
+                "asset_id": krw.id,
+                "threshold": 100_000,
+            })),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/alert-rules",
+            Some(serde_json::json!({
+                "account_id": account.id,
+                "asset_id": krw.id,
+                "comparator": "below",
+                "threshold": 100_000,
+                "channel": "webhook",
+                "cooldown_seconds": 3600,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["threshold"], 100_000);
+        let events_uri = format!("/api/alert-rules/{}/events", body["id"].as_str().unwrap());
+
+        // The balance goes 500,000 -> 50,000 -> 150,000 -> 50,000, crossing
+        // below the threshold twice within the cooldown.
+        for quantity in [500_000_i64, -450_000, 100_000, -100_000] {
+            let create_request = TransactionCreateRequest {
+                posted_at: "2025-01-02T00:00:00Z".parse().unwrap(),
+                description: None,
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            let _ = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        }
+
+        let (status, _) = send_json("GET", &events_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json("GET", &events_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        let events = body["alert_events"].as_array().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["balance"], 50_000);
+        assert_eq!(events[0]["channel"], "webhook");
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets", "asset_prices"))]
+    async fn it_keeps_a_watchlist_with_the_latest_prices(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let usd = get_asset_by_symbol(&user_auth_token, &mut api, "USD").await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        for request in [
+            serde_json::json!({
+                "asset_id": usd.id,
+                "quote_asset_id": krw.id,
+                "target_price": 0,
+            }),
+            serde_json::json!({
+                "asset_id": usd.id,
+                "quote_asset_id": usd.id,
+            }),
+        ] {
+            let (status, _) = send_json(
+                "POST",
+                "/api/watchlist",
+                Some(request),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/watchlist",
+            Some(serde_json::json!({
+                "asset_id": usd.id,
+                "quote_asset_id": krw.id,
+                "target_price": 1500,
+                "direction": "above",
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["symbol"], "USD");
+        assert_eq!(body["quote_symbol"], "KRW");
+        assert_eq!(body["target_price"], "1500");
+        assert_eq!(body["repeating"], false);
+        let entry_uri = format!("/api/watchlist/{}", body["id"].as_str().unwrap());
+
+        // The latest of the prices of the pair, not the first.
+        let (status, body) =
+            send_json("GET", "/api/watchlist", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        let entries = body["watchlist_entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["latest_price"], "1450");
+        assert!(entries[0].get("triggered_at").is_none());
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/watchlist",
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["watchlist_entries"], serde_json::json!([]));
+        let (status, _) = send_json("GET", &entry_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // A new target arms an entry that already fired again.
+        sqlx::query(
+            r#"
+            UPDATE watchlist_entry SET triggered_at = now(), triggered_price = 1500
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let (status, body) = send_json(
+            "PATCH",
+            &entry_uri,
+            Some(serde_json::json!({ "repeating": true })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["repeating"], true);
+        assert_eq!(body["triggered_price"], "1500");
+        let (status, body) = send_json(
+            "PATCH",
+            &entry_uri,
+            Some(serde_json::json!({ "target_price": "1600.5" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["target_price"], "1600.5");
+        assert_eq!(body["latest_price"], "1450");
+        assert!(body.get("triggered_at").is_none());
+        assert!(body.get("triggered_price").is_none());
+
+        let (status, _) =
+            send_json("DELETE", &entry_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json("DELETE", &entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json("GET", &entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_keeps_journal_entries_balanced(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let mut accounts = vec![];
+        for name in ["Cash", "Checking"] {
+            let create_account_request = AccountCreateRequest {
+                name: name.into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            };
+            accounts
+                .push(create_account(&create_account_request, &user_auth_token, &mut api).await);
+        }
+        let (cash, checking) = (&accounts[0], &accounts[1]);
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let usd = get_asset_by_symbol(&user_auth_token, &mut api, "USD").await;
+        let leg = |account_id: AccountId, asset_id: AssetId, quantity: i64| {
+            serde_json::json!({
+                "account_id": account_id,
+                "asset_id": asset_id,
+                "quantity": quantity,
+            })
+        };
+
+        for (legs, message) in [
+            (
+                vec![leg(cash.id, krw.id, -1000), leg(checking.id, krw.id, 900)],
+                format!(
+                    "The legs of the entry don't balance, in asset {} they sum to -100.",
+                    krw.id.0
+                ),
+            ),
+            (
+                vec![leg(cash.id, krw.id, 0)],
+                "A journal entry needs at least two legs.".to_owned(),
+            ),
+        ] {
+            let (status, body) = send_json(
+                "POST",
+                "/api/journal-entries",
+                Some(serde_json::json!({
+                    "posted_at": "2025-01-02T00:00:00Z",
+                    "legs": legs,
+                })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["message"], message);
+        }
+
+        // Buying a dollar with cash, moving the won out of the account.
+        let entry = serde_json::json!({
+            "posted_at": "2025-01-02T00:00:00Z",
+            "description": "Exchange",
+            "legs": [
+                leg(cash.id, krw.id, -1300),
+                leg(checking.id, krw.id, 1300),
+                leg(cash.id, usd.id, 1),
+                leg(checking.id, usd.id, -1),
+            ],
+        });
+        let (status, _) = send_json(
+            "POST",
+            "/api/journal-entries",
+            Some(entry.clone()),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, body) = send_json(
+            "POST",
+            "/api/journal-entries",
+            Some(entry),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let entry_id = body["id"].as_str().unwrap().to_owned();
+        let legs = body["legs"].as_array().unwrap();
+        assert_eq!(legs.len(), 4);
+        assert!(
+            legs.iter()
+                .all(|x| x["journal_entry_id"] == entry_id.as_str()
+                    && x["description"] == "Exchange")
+        );
+        let leg_uri = format!("/api/transactions/{}", legs[0]["id"]);
+        let entry_uri = format!("/api/journal-entries/{entry_id}");
+
+        let (status, _) = send_json("GET", &entry_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, body) = send_json("GET", &entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["legs"].as_array().unwrap().len(), 4);
+
+        // A leg can't be changed or deleted on its own.
+        let (status, _) = send_json(
+            "PATCH",
+            &leg_uri,
+            Some(serde_json::json!({ "quantity": -1200 })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send_json("DELETE", &leg_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, body) = send_json(
+            "PATCH",
+            &leg_uri,
+            Some(serde_json::json!({ "notes": "At the airport" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quantity"], -1300);
+
+        let (_, body) = send_json(
+            "GET",
+            "/api/reports/trial-balance?as_of=2025-01-01T00:00:00Z",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(body["lines"], serde_json::json!([]));
+        let (status, body) = send_json(
+            "GET",
+            "/api/reports/trial-balance?as_of=2025-01-03T00:00:00Z",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["lines"].as_array().unwrap().len(), 4);
+        let totals = body["totals"].as_array().unwrap();
+        assert_eq!(totals.len(), 2);
+        let krw_total = totals
+            .iter()
+            .find(|x| x["asset_id"] == krw.id.0.to_string())
+            .unwrap();
+        assert_eq!(
+            krw_total,
+            &serde_json::json!({
+                "asset_id": krw.id,
+                "debit": 1300,
+                "credit": 1300,
+                "balanced": true,
+            })
+        );
+        let (_, body) = send_json(
+            "GET",
+            "/api/reports/trial-balance?as_of=2025-01-03T00:00:00Z",
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(body["lines"], serde_json::json!([]));
+
+        // In double-entry mode transactions only come in journal entries.
+        let create_request = serde_json::json!({
+            "posted_at": "2025-01-02T00:00:00Z",
+            "description": null,
+            "account_id": cash.id,
+            "asset_id": krw.id,
+            "quantity": 500,
+        });
+        let (status, _) = send_json(
+            "POST",
+            "/api/transactions",
+            Some(create_request.clone()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, body) = send_json(
+            "PATCH",
+            "/api/me/preferences",
+            Some(serde_json::json!({ "double_entry": true })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["double_entry"], true);
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions",
+            Some(create_request),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], 4093);
+        assert!(
+            body["message"]
+                .as_str()
+                .unwrap()
+                .contains("POST /api/journal-entries")
+        );
+
+        // Deleting the entry deletes its legs and what they did to the
+        // balances.
+        let (status, _) =
+            send_json("DELETE", &entry_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json("DELETE", &entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json("GET", &leg_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json("GET", &entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, body) = send_json(
+            "GET",
+            "/api/reports/trial-balance?as_of=2025-01-03T00:00:00Z",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(
+            body["lines"],
+            serde_json::json!([{
+                "account_id": cash.id,
+                "asset_id": krw.id,
+                "debit": 500,
+                "credit": 0,
+            }])
+        );
+        let (_, body) = send_json(
+            "GET",
+            &format!("/api/accounts/{}/balances", checking.id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let balances = body["balances"].as_array().unwrap();
+        assert_eq!(balances.len(), 2);
+        assert!(balances.iter().all(|x| x["balance"] == 0));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_transfers_between_accounts_in_one_entry(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let mut accounts = vec![];
+        for (name, auth_token) in [
+            ("Cash", &user_auth_token),
+            ("Checking", &user_auth_token),
+            ("Savings", &user_two_auth_token),
+        ] {
+            let create_account_request = AccountCreateRequest {
+                name: name.into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            };
+            accounts.push(create_account(&create_account_request, auth_token, &mut api).await);
+        }
+        let (cash, checking, savings) = (&accounts[0], &accounts[1], &accounts[2]);
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let transfer = |from: AccountId, to: AccountId, quantity: i64| {
+            serde_json::json!({
+                "from_account_id": from,
+                "to_account_id": to,
+                "asset_id": krw.id,
+                "quantity": quantity,
+                "posted_at": "2025-01-02T00:00:00Z",
+                "description": "Top up",
+            })
+        };
+
+        for (body, code) in [
+            (transfer(cash.id, cash.id, 1000), 4001),
+            (transfer(cash.id, checking.id, 0), 4006),
+            (transfer(cash.id, checking.id, -1000), 4006),
+        ] {
+            let (status, body) = send_json(
+                "POST",
+                "/api/transfers",
+                Some(body),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["code"], code);
+        }
+
+        // A transfer into an account of someone else fails as a whole, so
+        // the leg on the caller's own account isn't left behind.
+        let (status, _) = send_json(
+            "POST",
+            "/api/transfers",
+            Some(transfer(cash.id, savings.id, 1000)),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, body) =
+            send_json("GET", "/api/transactions", None, &user_auth_token, &mut api).await;
+        assert_eq!(body["transactions"], serde_json::json!([]));
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/transfers",
+            Some(transfer(cash.id, checking.id, 1000)),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let entry_id = body["id"].as_str().unwrap().to_owned();
+        let legs = body["legs"].as_array().unwrap();
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0]["account_id"], cash.id.0.to_string());
+        assert_eq!(legs[0]["quantity"], -1000);
+        assert_eq!(legs[1]["account_id"], checking.id.0.to_string());
+        assert_eq!(legs[1]["quantity"], 1000);
+        assert!(
+            legs.iter()
+                .all(|x| x["journal_entry_id"] == entry_id.as_str() && x["description"] == "Top up")
+        );
+        for (account, balance) in [(cash, -1000), (checking, 1000)] {
+            let (_, body) = send_json(
+                "GET",
+                &format!("/api/accounts/{}/balances", account.id.0),
+                None,
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(body["balances"][0]["balance"], balance);
+        }
+
+        // The transfer is read and deleted as the entry it is.
+        let entry_uri = format!("/api/journal-entries/{entry_id}");
+        let (status, body) = send_json("GET", &entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["legs"].as_array().unwrap().len(), 2);
+        let (status, _) = send_json("DELETE", &entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, body) =
+            send_json("GET", "/api/transactions", None, &user_auth_token, &mut api).await;
+        assert_eq!(body["transactions"], serde_json::json!([]));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_serves_version_two_over_version_one(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = ApiV2::mount(ApiV1::router_with_config(
+            Arc::new(pool),
+            enforcer,
+            RouterConfig {
+                deprecation_config: DeprecationConfig {
+                    deprecated_at: Some("2025-05-01T00:00:00Z".parse().unwrap()),
+                    sunset_at: Some("2025-11-01T00:00:00Z".parse().unwrap()),
+                },
+                ..Default::default()
+            },
+        ))
+        .into_service();
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Cash".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let (status, body) = send_json(
+            "POST",
+            "/api/v2/transactions",
+            Some(serde_json::json!({
+                "account_id": account.id,
+                "asset_id": krw.id,
+                "quantity": 1000,
+                "posted_at": "2025-01-02T00:00:00Z",
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["quantity"], "1000");
+        let transaction_id = body["id"].clone();
+
+        let get = async |uri: &str, api: &mut RouterIntoService<Body>| {
+            let request = Request::builder()
+                .method("GET")
+                .header("Authorization", &user_auth_token)
+                .header("Accept", "application/json")
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let response = ServiceExt::<Request<Body>>::ready(api)
+                .await
+                .unwrap()
+                .call(request)
+                .await
+                .unwrap();
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (
+                status,
+                headers,
+                serde_json::from_slice::<Value>(&body).unwrap(),
+            )
+        };
+
+        // The endpoints version 2 supersedes announce it in version 1.
+        let (status, headers, body) =
+            get(&format!("/api/transactions/{transaction_id}"), &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quantity"], 1000);
+        assert_eq!(headers[DEPRECATION_HEADER], "@1746057600");
+        assert_eq!(headers[SUNSET_HEADER], "Sat, 01 Nov 2025 00:00:00 GMT");
+        assert_eq!(
+            headers[LINK],
+            format!("</api/v2/transactions/{transaction_id}>; rel=\"successor-version\"")
+        );
+
+        let (status, headers, body) =
+            get(&format!("/api/v2/transactions/{transaction_id}"), &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quantity"], "1000");
+        assert!(headers.get(DEPRECATION_HEADER).is_none());
+        assert!(headers.get(SUNSET_HEADER).is_none());
+
+        // The rest are left alone, but still write strings in version 2.
+        let (status, headers, body) = get(
+            &format!("/api/accounts/{}/balances", account.id.0),
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["balances"][0]["balance"], 1000);
+        assert!(headers.get(DEPRECATION_HEADER).is_none());
+        assert!(headers.get(SUNSET_HEADER).is_none());
+        assert!(headers.get(LINK).is_none());
+        let (_, _, body) = get(
+            &format!("/api/v2/accounts/{}/balances", account.id.0),
+            &mut api,
+        )
+        .await;
+        assert_eq!(body["balances"][0]["balance"], "1000");
+
+        for (uri, version) in [("/api/version", "v1"), ("/api/v2/version", "v2")] {
+            let (status, headers, body) = get(uri, &mut api).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["api_version"], version);
+            assert_eq!(body["api_versions"], serde_json::json!(["v1", "v2"]));
+            assert!(headers.get(DEPRECATION_HEADER).is_none());
+        }
+
+        let (status, _, body) = get("/api/v2/nothing", &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], 4040);
+    }
+
+    #[sqlx::test]
+    async fn it_cancels_queries_past_the_deadline(pool: Pool<Postgres>) {
+        let deadline_pool = deadline::pool_options(DatabaseConfig {
+            statement_timeout: Duration::from_secs(10),
+        })
+        .max_connections(1)
+        .connect_with(pool.connect_options().as_ref().clone())
+        .await
+        .unwrap();
+        let timeouts = deadline::query_timeouts();
+
+        let started = Instant::now();
+        let result = DEADLINE
+            .scope(Instant::now() + Duration::from_millis(500), async {
+                StatsRepository
+                    .sleep(deadline::begin(&deadline_pool).await.unwrap(), 5.0)
+                    .await
+            })
+            .await;
+        assert!(
+            matches!(result, Err(RepositoryError::Timeout)),
+            "{result:?}"
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(deadline::query_timeouts() > timeouts);
+        let error = ApiError::from(ServiceError::from(result.unwrap_err()));
+        assert_eq!(error.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(ApiErrorResponse::from(&error).code, 5040);
+
+        // Postgres itself cancelled the query, rather than it running on
+        // after the client gave up.
+        let sleeping = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM pg_stat_activity
+            WHERE state = 'active' AND query LIKE 'SELECT pg_sleep%'
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(sleeping, 0);
+
+        // The connection is held to the configured timeout again outside of
+        // the request.
+        StatsRepository
+            .sleep(deadline_pool.begin().await.unwrap(), 1.0)
+            .await
+            .unwrap();
+
+        // A request with more time left than that leaves the timeout be.
+        let timeout = DEADLINE
+            .scope(Instant::now() + Duration::from_secs(60), async {
+                let mut transaction = deadline::begin(&deadline_pool).await.unwrap();
+                sqlx::query_scalar::<_, String>("SHOW statement_timeout")
+                    .fetch_one(&mut *transaction)
+                    .await
+                    .unwrap()
+            })
+            .await;
+        assert_eq!(timeout, "10s");
+    }
+
+    #[tokio::test]
+    async fn it_answers_requests_past_their_budget() {
+        let mut api = Router::new()
+            .route(
+                "/slow",
+                axum::routing::get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "slow"
+                }),
+            )
+            .route(
+                "/remaining",
+                axum::routing::get(|| async {
+                    deadline::remaining().unwrap().as_millis().to_string()
+                }),
+            )
+            .layer(from_fn_with_state(
+                Duration::from_millis(200),
+                set_request_deadline,
+            ))
+            .into_service();
+        let timeouts = deadline::query_timeouts();
+
+        let (status, body) = send_json("GET", "/slow", None, "", &mut api).await;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(body["code"], 5040);
+        assert!(deadline::query_timeouts() > timeouts);
+
+        let (status, body) = send_json("GET", "/remaining", None, "", &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.as_u64().unwrap() <= 200);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_merges_duplicate_accounts(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let toss = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let hana = get_institution_by_name("Hana Bank", &user_auth_token, &mut api).await;
+        let account_at = |name: &str, institution_id: InstitutionId| AccountCreateRequest {
+            name: name.into(),
+            institution_id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let target =
+            create_account(&account_at("Checking", toss.id), &user_auth_token, &mut api).await;
+        let source = create_account(
+            &account_at("Checking (old)", toss.id),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let elsewhere =
+            create_account(&account_at("Checking", hana.id), &user_auth_token, &mut api).await;
+        let theirs = create_account(
+            &account_at("Checking", toss.id),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        for (account_id, quantity, auth_token) in [
+            (target.id, 1_000, &user_auth_token),
+            (source.id, 250, &user_auth_token),
+            (source.id, -50, &user_auth_token),
+            (elsewhere.id, 300, &user_auth_token),
+            (theirs.id, 7, &user_two_auth_token),
+        ] {
+            let create_request = TransactionCreateRequest {
+                posted_at: Utc::now(),
+                description: "A test transaction".to_owned().into(),
+                account_id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            let _ = create_transaction(&create_request, auth_token, &mut api).await;
+        }
+        let merge_uri = format!("/api/accounts/{}/merge", target.id.0);
+
+        // Neither user can merge an account of the other.
+        let (status, _) = send_json(
+            "POST",
+            &merge_uri,
+            Some(serde_json::json!({ "source_id": theirs.id })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json(
+            "POST",
+            &merge_uri,
+            Some(serde_json::json!({ "source_id": source.id })),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json(
+            "POST",
+            &merge_uri,
+            Some(serde_json::json!({ "source_id": target.id })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Accounts at different institutions only merge when forced.
+        let (status, body) = send_json(
+            "POST",
+            &merge_uri,
+            Some(serde_json::json!({ "source_id": elsewhere.id })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], 4094);
+        let (cached, _) = cached_and_live_balance(&pool, elsewhere.id, krw.id).await;
+        assert_eq!(cached, Some(Decimal::from(300)));
+
+        let (status, body) = send_json(
+            "POST",
+            &merge_uri,
+            Some(serde_json::json!({ "source_id": source.id })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["forced"], false);
+        assert_eq!(body["moved"]["transactions"], 2);
+        let (cached, live) = cached_and_live_balance(&pool, target.id, krw.id).await;
+        assert_eq!(live, Some(Decimal::from(1_200)));
+        assert_eq!(cached, live);
+        let (status, _) = send_json(
+            "GET",
+            &format!("/api/accounts/{}", source.id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json(
+            "POST",
+            &merge_uri,
+            Some(serde_json::json!({ "source_id": elsewhere.id, "force": true })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["forced"], true);
+        assert_eq!(body["moved"]["transactions"], 1);
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/accounts/{}/balances", target.id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["balances"].as_array().unwrap().len(), 1);
+        assert_eq!(body["balances"][0]["balance"], 1_500);
+        let (cached, _) = cached_and_live_balance(&pool, elsewhere.id, krw.id).await;
+        assert_eq!(cached, None);
+
+        let merges =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM account_merge WHERE target_id = $1")
+                .bind(target.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(merges, 2);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_previews_and_confirms_account_deletions(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let toss = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let account_named = |name: &str| AccountCreateRequest {
+            name: name.into(),
+            institution_id: toss.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let checking = create_account(&account_named("Checking"), &user_auth_token, &mut api).await;
+        let savings = create_account(&account_named("Savings"), &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let mut transactions = vec![];
+        for (account_id, quantity) in [
+            (checking.id, 1_000),
+            (checking.id, -250),
+            (checking.id, -50),
+            (savings.id, 5_000),
+        ] {
+            let create_request = TransactionCreateRequest {
+                posted_at: Utc::now(),
+                description: "A test transaction".to_owned().into(),
+                account_id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            transactions
+                .push(create_transaction(&create_request, &user_auth_token, &mut api).await);
+        }
+        for (uri, body) in [
+            (
+                "/api/attachments",
+                serde_json::json!({
+                    "transaction_id": transactions[0].id,
+                    "filename": "receipt.pdf",
+                    "content_type": "application/pdf",
+                    "content": BASE64_STANDARD.encode(b"%PDF-1.7\n"),
+                }),
+            ),
+            (
+                "/api/attachments",
+                serde_json::json!({
+                    "transaction_id": transactions[3].id,
+                    "filename": "receipt.pdf",
+                    "content_type": "application/pdf",
+                    "content": BASE64_STANDARD.encode(b"%PDF-1.7\n"),
+                }),
+            ),
+            (
+                "/api/categorization-rules",
+                serde_json::json!({
+                    "field": "account",
+                    "account_id": checking.id,
+                    "category": "Checking",
+                }),
+            ),
+            (
+                "/api/alert-rules",
+                serde_json::json!({
+                    "account_id": checking.id,
+                    "asset_id": krw.id,
+                    "threshold": 100_000,
+                }),
+            ),
+        ] {
+            let (status, _) = send_json("POST", uri, Some(body), &user_auth_token, &mut api).await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+        let preview_uri = format!("/api/accounts/{}/deletion-preview", checking.id.0);
+        let delete_uri = format!("/api/accounts/{}?confirm=true", checking.id.0);
+
+        let (status, _) =
+            send_json("GET", &preview_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, preview) =
+            send_json("GET", &preview_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            preview,
+            serde_json::json!({
+                "transactions": 3,
+                "attachments": 1,
+                "categorization_rules": 1,
+                "alert_rules": 1,
+                "journal_entry_legs": 0,
+            })
+        );
+
+        // The deletion has to be confirmed.
+        for uri in [
+            format!("/api/accounts/{}", checking.id.0),
+            format!("/api/accounts/{}?confirm=false", checking.id.0),
+        ] {
+            let (status, body) = send_json("DELETE", &uri, None, &user_auth_token, &mut api).await;
+            assert_eq!(status, StatusCode::PRECONDITION_REQUIRED);
+            assert_eq!(body["code"], 4280);
+        }
+        // A confirmation that isn't one is refused without being echoed.
+        let uri = format!(
+            "/api/accounts/{}?confirm=%3Cscript%3Ealert(1)%3C/script%3E",
+            checking.id.0
+        );
+        let (status, body) = send_json("DELETE", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], 4003);
+        assert_eq!(body["message"], "The query string is not valid.");
+        let (status, _) =
+            send_json("DELETE", &delete_uri, None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = send_json("DELETE", &delete_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_json("GET", &preview_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // The deletion removed what the preview counted, and no more.
+        let remaining = sqlx::query_as::<_, (i64, i64, i64, i64)>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM "transaction"),
+                (SELECT COUNT(*) FROM attachment),
+                (SELECT COUNT(*) FROM categorization_rule),
+                (SELECT COUNT(*) FROM alert_rule)
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(remaining, (1, 1, 0, 0));
+        let deleted = sqlx::query_as::<_, (i64, i64, i64, i64)>(
+            r#"
+            SELECT transactions, attachments, categorization_rules, alert_rules
+            FROM account_deletion
+            WHERE account_id = $1
+            "#,
+        )
+        .bind(checking.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(deleted, (3, 1, 1, 1));
+        let events = sqlx::query_as::<_, (String, Value)>(
+            "SELECT event, payload FROM webhook_event ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, "account.deleted");
+        assert_eq!(events[0].1["account_id"], checking.id.0.to_string());
+        assert_eq!(events[0].1["account_name"], "Checking");
+        assert_eq!(events[0].1["transactions"], 3);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_locks_accounts_being_deleted(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), Arc::clone(&enforcer));
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let toss = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let account_named = |name: &str| AccountCreateRequest {
+            name: name.into(),
+            institution_id: toss.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let checking = create_account(&account_named("Checking"), &user_auth_token, &mut api).await;
+        let savings = create_account(&account_named("Savings"), &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let spawn_request = |method: &'static str, uri: String, body: Option<Value>| {
+            let mut api = create_api(pool.clone(), Arc::clone(&enforcer));
+            let auth_token = user_auth_token.clone();
+            tokio::spawn(async move { send_json(method, &uri, body, &auth_token, &mut api).await })
+        };
+
+        // A transaction created while the account is being deleted waits
+        // for the deletion, and then has no account to go to.
+        let mut deletion = pool.begin().await.unwrap();
+        sqlx::query("SELECT 1 FROM account WHERE id = $1 FOR UPDATE")
+            .bind(checking.id)
+            .execute(&mut *deletion)
+            .await
+            .unwrap();
+        let create = spawn_request(
+            "POST",
+            "/api/transactions".into(),
+            Some(
+                serde_json::to_value(TransactionCreateRequest {
+                    posted_at: Utc::now(),
+                    description: None,
+                    account_id: checking.id,
+                    asset_id: krw.id,
+                    quantity: 1_000.into(),
+                    notes: None,
+                    category: None,
+                })
+                .unwrap(),
+            ),
+        );
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!create.is_finished());
+        sqlx::query("DELETE FROM account WHERE id = $1")
+            .bind(checking.id)
+            .execute(&mut *deletion)
+            .await
+            .unwrap();
+        deletion.commit().await.unwrap();
+        let (status, _) = create.await.unwrap();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // A deletion waits for a transaction being created, and then
+        // deletes it along with the account.
+        let mut insert = pool.begin().await.unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO "transaction" (account_id, asset_id, posted_at, quantity)
+            VALUES ($1, $2, CURRENT_TIMESTAMP, 1000)
+            "#,
+        )
+        .bind(savings.id)
+        .bind(krw.id)
+        .execute(&mut *insert)
+        .await
+        .unwrap();
+        let delete = spawn_request(
+            "DELETE",
+            format!("/api/accounts/{}?confirm=true", savings.id.0),
+            None,
+        );
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!delete.is_finished());
+        insert.commit().await.unwrap();
+        let (status, _) = delete.await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let transactions = sqlx::query_scalar::<_, i64>(
+            "SELECT transactions FROM account_deletion WHERE account_id = $1",
+        )
+        .bind(savings.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(transactions, 1);
+        let remaining = sqlx::query_scalar::<_, i64>(r#"SELECT COUNT(*) FROM "transaction""#)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_filters_on_when_rows_were_created_and_updated(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let mut accounts = vec![];
+        for name in ["Cash", "Checking"] {
+            let create_account_request = AccountCreateRequest {
+                name: name.into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            };
+            accounts
+                .push(create_account(&create_account_request, &user_auth_token, &mut api).await);
+        }
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let mut transactions = vec![];
+        for quantity in [100, 200] {
+            let create_request = TransactionCreateRequest {
+                posted_at: "2025-01-02T00:00:00Z".parse().unwrap(),
+                description: "A test transaction".to_owned().into(),
+                account_id: accounts[0].id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            transactions
+                .push(create_transaction(&create_request, &user_auth_token, &mut api).await);
+        }
+        let at = |datetime: DateTime<Utc>| urlencoding::encode(&datetime.to_rfc3339()).into_owned();
+        let names = async |query: String, api: &mut RouterIntoService<Body>| {
+            let (status, body) = send_json(
+                "GET",
+                &format!("/api/accounts?{query}"),
+                None,
+                &user_auth_token,
+                api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{query}");
+            body["accounts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["name"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        // The bounds are exclusive, so a row isn't after its own time.
+        let (cash, checking) = (&accounts[0], &accounts[1]);
+        assert_eq!(
+            names(format!("created_after={}", at(cash.created_at)), &mut api).await,
+            ["Checking"]
+        );
+        assert_eq!(
+            names(
+                format!("created_before={}", at(checking.created_at)),
+                &mut api
+            )
+            .await,
+            ["Cash"]
+        );
+        assert_eq!(
+            names(
+                format!(
+                    "updated_after={}",
+                    at(cash.updated_at - TimeDelta::microseconds(1))
+                ),
+                &mut api
+            )
+            .await
+            .len(),
+            2
+        );
+        assert!(
+            names(format!("updated_before={}", at(cash.updated_at)), &mut api)
+                .await
+                .is_empty()
+        );
+
+        let quantities = async |query: String, api: &mut RouterIntoService<Body>| {
+            let (status, body) = send_json(
+                "GET",
+                &format!("/api/transactions?{query}"),
+                None,
+                &user_auth_token,
+                api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{query}");
+            let mut quantities = body["transactions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["quantity"].as_i64().unwrap())
+                .collect::<Vec<_>>();
+            quantities.sort();
+            quantities
+        };
+        let (first, second) = (&transactions[0], &transactions[1]);
+        assert_eq!(
+            quantities(format!("updated_after={}", at(first.updated_at)), &mut api).await,
+            [200]
+        );
+
+        // Changing a transaction moves when it was updated, but neither when
+        // it was created nor when it was posted.
+        let (status, _) = send_json(
+            "PATCH",
+            &format!("/api/transactions/{}", first.id.0),
+            Some(serde_json::json!({ "notes": "Changed" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            quantities(format!("updated_after={}", at(second.updated_at)), &mut api).await,
+            [100]
+        );
+        assert_eq!(
+            quantities(format!("created_after={}", at(first.created_at)), &mut api).await,
+            [200]
+        );
+        assert_eq!(
+            quantities(
+                format!(
+                    "posted_after={}&updated_before={}",
+                    at(first.posted_at - TimeDelta::seconds(1)),
+                    at(second.updated_at + TimeDelta::microseconds(1))
+                ),
+                &mut api
+            )
+            .await,
+            [200]
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_filters_on_text_with_percent_and_plus_signs(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut enforcer = Arc::into_inner(enforcer).unwrap();
+        enforcer.enable_auto_save(false);
+        enforcer
+            .add_policy(vec![
+                Group::User.as_policy_subject().to_owned(),
+                "assets".to_owned(),
+                "create".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let mut api = create_api(pool, Arc::new(enforcer));
+        let create_user_request = UserCreateRequest {
+            name: "A+B & 50% 가계부".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        // The names come in pairs that a filter decoded a second time, or
+        // with `+` read as a space, would mix up.
+        let names = [
+            "50% off",
+            "50%25 off",
+            "A+B & C",
+            "A B & C",
+            "가계부 100%",
+            "가계부 100",
+        ];
+        for (i, name) in names.into_iter().enumerate() {
+            let create_account_request = AccountCreateRequest {
+                name: name.into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            };
+            let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+            let (status, _) = send_json(
+                "POST",
+                "/api/assets",
+                Some(serde_json::json!({ "name": name, "symbol": format!("T{i}") })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED);
+            let create_request = TransactionCreateRequest {
+                posted_at: "2025-01-02T00:00:00Z".parse().unwrap(),
+                description: name.to_owned().into(),
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: 100.into(),
+                notes: None,
+                category: None,
+            };
+            let _ = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        }
+
+        // Filters are percent-encoded once, as any client encodes a query.
+        let listed =
+            async |resource: &str, field: &str, value: &str, api: &mut RouterIntoService<Body>| {
+                let uri = format!("/api/{resource}?{field}={}", urlencoding::encode(value));
+                let (status, body) = send_json("GET", &uri, None, &user_auth_token, api).await;
+                assert_eq!(status, StatusCode::OK, "{uri}");
+                let mut values = body[resource]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|x| x[field].as_str().unwrap().to_owned())
+                    .collect::<Vec<_>>();
+                values.sort();
+                values
+            };
+        for name in names {
+            assert_eq!(listed("accounts", "name", name, &mut api).await, [name]);
+            assert_eq!(listed("assets", "name", name, &mut api).await, [name]);
+        }
+        assert_eq!(
+            listed("users", "name", "A+B & 50% 가계부", &mut api).await,
+            ["A+B & 50% 가계부"]
+        );
+        assert!(
+            listed("users", "name", "A B & 50% 가계부", &mut api)
+                .await
+                .is_empty()
+        );
+
+        // Descriptions are searched for text they contain, and `%` in it
+        // is a literal.
+        let cases = [
+            ("0% o", vec!["50% off"]),
+            ("50%25", vec!["50%25 off"]),
+            ("A+B", vec!["A+B & C"]),
+            ("& C", vec!["A B & C", "A+B & C"]),
+            ("100%", vec!["가계부 100%"]),
+            ("%", vec!["50% off", "50%25 off", "가계부 100%"]),
+        ];
+        for (description, expected) in cases {
+            assert_eq!(
+                listed("transactions", "description", description, &mut api).await,
+                expected,
+                "{description}"
+            );
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_filters_transactions_by_a_date_range(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        for (quantity, posted_at) in [
+            (100, "2024-12-31T23:00:00Z"),
+            (200, "2025-01-01T00:00:00Z"),
+            (300, "2025-01-31T15:30:00Z"),
+        ] {
+            let create_request = TransactionCreateRequest {
+                posted_at: posted_at.parse().unwrap(),
+                description: "A test transaction".to_owned().into(),
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            let _ = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        }
+        let list = async |query: &str, api: &mut RouterIntoService<Body>| {
+            let (status, body) = send_json(
+                "GET",
+                &format!("/api/transactions?{query}"),
+                None,
+                &user_auth_token,
+                api,
+            )
+            .await;
+            if status != StatusCode::OK {
+                return Err(status);
+            }
+            let mut quantities = body["transactions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["quantity"].as_i64().unwrap())
+                .collect::<Vec<_>>();
+            quantities.sort();
+            Ok(quantities)
+        };
+
+        // A date as `to` takes in all of that day.
+        assert_eq!(
+            list("from=2025-01-01&to=2025-01-31", &mut api).await,
+            Ok(vec![200, 300])
+        );
+        assert_eq!(
+            list("to=2025-01-01T00:00:00Z", &mut api).await,
+            Ok(vec![100])
+        );
+        // Midnight in Seoul is 15:00 the day before in UTC.
+        assert_eq!(
+            list("from=2025-01-01&utc_offset=%2B09:00", &mut api).await,
+            Ok(vec![100, 200, 300])
+        );
+        assert_eq!(
+            list("to=2025-01-31&utc_offset=%2B09:00", &mut api).await,
+            Ok(vec![100, 200])
+        );
+
+        // The older bounds still work on their own, but not with a range.
+        assert_eq!(
+            list("posted_after=2024-12-31T23%3A00%3A00Z", &mut api).await,
+            Ok(vec![200, 300])
+        );
+        assert_eq!(
+            list("posted_before=2025-01-01T00%3A00%3A00Z", &mut api).await,
+            Ok(vec![100])
+        );
+        for query in [
+            "from=2025-01-01&posted_before=2025-02-01T00%3A00%3A00Z",
+            "preset=ytd&from=2025-01-01",
+            "from=yesterday",
+            "from=2025-02-01&to=2025-01-01",
+            "from=2025-01-01&utc_offset=Asia%2FSeoul",
+        ] {
+            assert_eq!(
+                list(query, &mut api).await,
+                Err(StatusCode::BAD_REQUEST),
+                "{query}"
+            );
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_records_transactions_from_quick_entries(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let theirs = create_account(&create_account_request, &user_two_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        // An entry can't point at the account of another user, nor go
+        // without a label.
+        for (label, account_id) in [("Coffee", theirs.id), ("  ", account.id)] {
+            let (status, _) = send_json(
+                "POST",
+                "/api/quick-entries",
+                Some(serde_json::json!({
+                    "label": label,
+                    "account_id": account_id,
+                    "asset_id": krw.id,
+                    "quantity": -4_500,
+                })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_ne!(status, StatusCode::CREATED);
+        }
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/quick-entries",
+            Some(serde_json::json!({
+                "label": "Coffee",
+                "account_id": account.id,
+                "asset_id": krw.id,
+                "quantity": -4_500,
+                "description": "Morning coffee",
+                "category": "Food",
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["quantity"], -4_500);
+        let quick_entry_uri = format!("/api/quick-entries/{}", body["id"].as_str().unwrap());
+        let execute_uri = format!("{quick_entry_uri}/execute");
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/quick-entries",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quick_entries"].as_array().unwrap().len(), 1);
+
+        // The entries of other users are hidden from them.
+        for (method, body) in [
+            ("GET", None),
+            ("PATCH", Some(serde_json::json!({ "label": "Tea" }))),
+            ("DELETE", None),
+        ] {
+            let (status, _) = send_json(
+                method,
+                &quick_entry_uri,
+                body,
+                &user_two_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+        }
+        let (status, _) = send_json(
+            "POST",
+            &execute_uri,
+            Some(serde_json::json!({})),
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send_json(
+            "POST",
+            &execute_uri,
+            Some(serde_json::json!({})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["account_id"], account.id.0.to_string());
+        assert_eq!(body["quantity"], -4_500);
+        assert_eq!(body["description"], "Morning coffee");
+        assert_eq!(body["category"], "Food");
+
+        let (status, body) = send_json(
+            "POST",
+            &execute_uri,
+            Some(serde_json::json!({ "posted_at": "2025-01-02T00:00:00Z" })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            body["posted_at"]
+                .as_str()
+                .unwrap()
+                .parse::<DateTime<Utc>>()
+                .unwrap(),
+            "2025-01-02T00:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        // Both went through the usual path, keeping the balance in step.
+        let (cached, live) = cached_and_live_balance(&pool, account.id, krw.id).await;
+        assert_eq!(cached, Some(Decimal::from(-9_000)));
+        assert_eq!(cached, live);
+
+        let (status, body) = send_json(
+            "PATCH",
+            &quick_entry_uri,
+            Some(serde_json::json!({ "label": "Large coffee", "quantity": -6_000 })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["label"], "Large coffee");
+        assert_eq!(body["quantity"], -6_000);
+
+        let (status, _) =
+            send_json("DELETE", &quick_entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) =
+            send_json("GET", &quick_entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_refuses_to_execute_a_quick_entry_whose_account_is_gone(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let account_named = |name: &str| AccountCreateRequest {
+            name: name.into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&account_named("Checking"), &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/quick-entries",
+            Some(serde_json::json!({
+                "label": "Rent",
+                "account_id": account.id,
+                "asset_id": krw.id,
+                "quantity": -500_000,
+                "description": "Monthly rent",
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let quick_entry_uri = format!("/api/quick-entries/{}", body["id"].as_str().unwrap());
+        let execute_uri = format!("{quick_entry_uri}/execute");
+
+        let (status, _) = send_json(
+            "DELETE",
+            &format!("/api/accounts/{}?confirm=true", account.id.0),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        // The entry outlives its account, but can't record anything until
+        // it is pointed at another.
+        let (status, body) =
+            send_json("GET", &quick_entry_uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["account_id"].is_null());
+        let (status, _) = send_json(
+            "POST",
+            &execute_uri,
+            Some(serde_json::json!({})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let recorded = sqlx::query_scalar::<_, i64>(
+            r#"SELECT COUNT(*) FROM "transaction" WHERE description = 'Monthly rent'"#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(recorded, 0);
+
+        let savings = create_account(&account_named("Savings"), &user_auth_token, &mut api).await;
+        let (status, _) = send_json(
+            "PATCH",
+            &quick_entry_uri,
+            Some(serde_json::json!({ "account_id": savings.id })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = send_json(
+            "POST",
+            &execute_uri,
+            Some(serde_json::json!({})),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["account_id"], savings.id.0.to_string());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_saves_the_feature_flags_admins_toggle(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_api = create_api(pool.clone(), admin_enforcer().await);
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let update = serde_json::json!({"features": {"alerts": false}});
+
+        let (status, _) = send_json(
+            "PATCH",
+            "/api/admin/features",
+            Some(update.clone()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = send_json(
+            "PATCH",
+            "/api/admin/features",
+            Some(serde_json::json!({"features": {"webhooks": true}})),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send_json(
+            "PATCH",
+            "/api/admin/features",
+            Some(update),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["restart_required"], true);
+        let alerts = body["features"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|x| x["name"] == "alerts")
+            .unwrap();
+        assert_eq!(alerts["enabled"], false);
+        assert_eq!(alerts["mounted"], true);
+        assert_eq!(alerts["restart_required"], true);
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/admin/features",
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["restart_required"], true);
+
+        let enabled =
+            sqlx::query_scalar::<_, bool>("SELECT enabled FROM feature_flag WHERE name = 'alerts'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(!enabled);
+
+        // A restart loads the override, and mounts the routes to match.
+        let features = super::admin_api::load_feature_flags(&pool, FeatureFlags::default())
+            .await
+            .unwrap();
+        assert!(!features.is_enabled(Feature::Alerts));
+        assert!(!features.is_mounted(Feature::Alerts));
+        assert!(!features.restart_required(Feature::Alerts));
+        assert!(features.is_enabled(Feature::ExportSchedules));
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_only_mounts_the_routes_of_enabled_features(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer.clone());
+        let _ = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let (status, _) =
+            send_json("GET", "/api/alert-rules", None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let mut gated_api = ApiV1::router_with_config(
+            Arc::new(pool),
+            enforcer,
+            RouterConfig {
+                features: FeatureFlags::default().with_overrides([(Feature::Alerts, false)]),
+                ..Default::default()
+            },
+        )
+        .into_service();
+        let (status, _) = send_json(
+            "GET",
+            "/api/alert-rules",
+            None,
+            &user_auth_token,
+            &mut gated_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send_json(
+            "GET",
+            "/api/export-schedules",
+            None,
+            &user_auth_token,
+            &mut gated_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_compares_the_permissions_of_proposed_policies(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_api = create_api(pool, admin_enforcer().await);
+        // The policy file without `p, user, transactions, delete`, and with
+        // auditors reading every account.
+        let policies = std::fs::read_to_string(AUTH_POLICY_PATH.get().unwrap())
+            .unwrap()
+            .lines()
+            .filter(|x| x.trim() != "p, user, transactions, delete")
+            .chain(["p, auditors, accounts, read_all"])
+            .collect::<Vec<_>>()
+            .join("\n");
+        let request = serde_json::json!({
+            "policies": policies,
+            "checks": [
+                {"group": "user", "resource": "transactions"},
+                {"group": "org:auditors", "resource": "accounts"},
+                {"group": "admin", "resource": "institutions"},
+            ],
+        });
+
+        let (status, _) = send_json(
+            "POST",
+            "/api/admin/policies/simulate",
+            Some(request.clone()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/admin/policies/simulate",
+            Some(request),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["changed"], 2);
+        let comparisons = body["comparisons"].as_array().unwrap();
+        assert_eq!(
+            comparisons[0]["current"],
+            serde_json::json!({"read": "read", "create": "create", "update": "update", "delete": "delete"})
+        );
+        assert_eq!(
+            comparisons[0]["proposed"],
+            serde_json::json!({"read": "read", "create": "create", "update": "update", "delete": "none"})
+        );
+        assert_eq!(comparisons[0]["differences"], serde_json::json!(["delete"]));
+        assert_eq!(comparisons[1]["current"]["read"], "none");
+        assert_eq!(comparisons[1]["proposed"]["read"], "read_all");
+        assert_eq!(comparisons[1]["differences"], serde_json::json!(["read"]));
+        assert_eq!(comparisons[2]["current"], comparisons[2]["proposed"]);
+        assert_eq!(comparisons[2]["differences"], serde_json::json!([]));
+
+        // The live policies are left as they were.
+        let (status, body) = send_json(
+            "POST",
+            "/api/admin/policies/simulate",
+            Some(serde_json::json!({
+                "policies": "",
+                "checks": [{"group": "user", "resource": "transactions"}],
+            })),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["comparisons"][0]["current"]["delete"], "delete");
+        assert_eq!(body["comparisons"][0]["proposed"]["delete"], "none");
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/admin/policies/simulate",
+            Some(serde_json::json!({
+                "policies": "p, user, accounts\n",
+                "checks": [],
+            })),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["message"],
+            "Line 1 of the policies is neither `p, subject, object, action` nor `g, member, role`."
+        );
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/admin/policies/simulate",
+            Some(serde_json::json!({
+                "policies": "p, user, accounts, read\np, user, accounts, raed\n",
+                "checks": [],
+            })),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["message"],
+            "Line 2 of the policies has an unknown action `raed`."
+        );
+
+        let (status, _) = send_json(
+            "POST",
+            "/api/admin/policies/simulate",
+            Some(serde_json::json!({
+                "policies": "# ".repeat(MAX_PROPOSAL_BYTES),
+                "checks": [],
+            })),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_summarizes_the_policies_and_flags_unknown_actions(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let (status, _) = send_json(
+            "GET",
+            "/api/admin/policies/summary",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let admin_enforcer = admin_enforcer().await;
+        assert!(validate_policies(&admin_enforcer).is_ok());
+        let mut admin_api = create_api(pool.clone(), admin_enforcer);
+        let (status, body) = send_json(
+            "GET",
+            "/api/admin/policies/summary",
+            None,
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["invalid"], serde_json::json!([]));
+        let grants = body["grants"].as_array().unwrap();
+        let user_accounts = grants
+            .iter()
+            .find(|x| x["subject"] == "user" && x["resource"] == "accounts")
+            .unwrap();
+        assert_eq!(
+            user_accounts["actions"],
+            serde_json::json!(["create", "update", "delete"])
+        );
+        assert_eq!(
+            user_accounts["description"],
+            "`user` may create their own, update their own, delete their own on `accounts`"
+        );
+        assert!(grants.iter().any(|x| x["subject"] == "admin"
+            && x["resource"] == "*"
+            && x["actions"] == serde_json::json!(["*"])));
+
+        // A typo'd action loads, but grants nothing.
+        let mut typo_enforcer = Arc::into_inner(admin_enforcer().await).unwrap();
+        typo_enforcer
+            .add_policy(vec![
+                "user".to_owned(),
+                "accounts".to_owned(),
+                "raed".to_owned(),
+            ])
+            .await
+            .unwrap();
+        let error = validate_policies(&typo_enforcer).unwrap_err();
+        assert_eq!(error.0.len(), 1);
+        assert_eq!(error.0[0].action, "raed");
+        let mut typo_api = create_api(pool, Arc::new(typo_enforcer));
+        let (status, body) = send_json(
+            "GET",
+            "/api/admin/policies/summary",
+            None,
+            &user_auth_token,
+            &mut typo_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["invalid"],
+            serde_json::json!([{
+                "subject": "user",
+                "resource": "accounts",
+                "action": "raed",
+                "message": "Unknown action `raed`.",
+            }])
+        );
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_bulk_upserts_institution_directories(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_api = create_api(pool.clone(), admin_enforcer().await);
+        // 5000 institutions, each tenth a branch of the one before it, with
+        // a malformed line every thousand.
+        let mut directory = String::new();
+        for i in 0..5_000 {
+            let row = match i {
+                i if i % 1_000 == 999 => "{\"name\": ".to_owned(),
+                i if i % 10 == 9 => serde_json::json!({
+                    "name": format!("Bank {i}"),
+                    "parent": format!("Bank {}", i - 1),
+                })
+                .to_string(),
+                i => serde_json::json!({
+                    "name": format!("Bank {i}"),
+                    "default_asset": "KRW",
+                })
+                .to_string(),
+            };
+            directory.push_str(&row);
+            directory.push('\n');
+        }
+        directory.push_str(
+            &serde_json::json!({"name": "Toss Bank", "default_asset": "JPY"}).to_string(),
+        );
+        directory.push('\n');
+        directory
+            .push_str(&serde_json::json!({"name": "Bank X", "default_asset": "XXX"}).to_string());
+        directory.push('\n');
+        directory.push_str(&serde_json::json!({"name": "Bank Y", "parent": "Bank Z"}).to_string());
+        directory.push('\n');
+        directory.push_str(&format!(
+            "{{\"name\": \"{}\"}}\n",
+            "a".repeat(MAX_ROW_BYTES)
+        ));
+        let uri = "/api/admin/institutions/bulk-upsert";
+
+        let (status, _) = send_bytes(
+            uri,
+            "application/x-ndjson",
+            directory.clone().into_bytes(),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let institutions = async || {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM institution")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+        let before = institutions().await;
+        let (status, body) = send_bytes(
+            &format!("{uri}?dry_run=true"),
+            "application/x-ndjson",
+            directory.clone().into_bytes(),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["dry_run"], true);
+        assert_eq!(body["created"], 4_995);
+        assert_eq!(body["failed"], 8);
+        assert_eq!(institutions().await, before);
+
+        let (status, body) = send_bytes(
+            uri,
+            "application/x-ndjson",
+            directory.clone().into_bytes(),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.get("dry_run").is_none());
+        assert_eq!(body["created"], 4_995);
+        assert_eq!(body["updated"], 1);
+        assert_eq!(body["unchanged"], 0);
+        assert_eq!(body["failed"], 8);
+        let errors = body["errors"].as_array().unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|x| x["line"].as_u64().unwrap())
+                .collect::<Vec<_>>(),
+            vec![1_000, 2_000, 3_000, 4_000, 5_000, 5_002, 5_003, 5_004]
+        );
+        assert_eq!(errors[5]["message"], "There is no asset `XXX`.");
+        assert_eq!(
+            errors[6]["message"],
+            "There is no institution named `Bank Z`."
+        );
+        assert_eq!(
+            errors[7]["message"],
+            format!("The row is longer than {MAX_ROW_BYTES} bytes.")
+        );
+        let (parent_name, default_asset) = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+            r#"
+            SELECT p.name, a.symbol FROM institution i
+            LEFT JOIN institution p ON p.id = i.parent_id
+            LEFT JOIN asset a ON a.id = i.default_asset_id
+            WHERE i.name = 'Bank 19'
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(parent_name.as_deref(), Some("Bank 18"));
+        assert_eq!(default_asset, None);
+
+        // Sending it again writes nothing new.
+        let (status, body) = send_bytes(
+            uri,
+            "application/x-ndjson",
+            directory.into_bytes(),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["created"], 0);
+        assert_eq!(body["updated"], 0);
+        assert_eq!(body["unchanged"], 4_996);
+        assert_eq!(body["failed"], 8);
+
+        let csv = concat!(
+            "name,parent,default_asset\r\n",
+            "\"Bank 0, Seoul\",Bank 0,USD\r\n",
+            "Bank 1,,EUR\r\n",
+            ",Bank 0,\r\n",
+            "\"Bank 2\n",
+        );
+        let (status, body) = send_bytes(
+            &format!("{uri}?format=csv"),
+            "text/csv",
+            csv.as_bytes().to_vec(),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["created"], 1);
+        assert_eq!(body["updated"], 1);
+        assert_eq!(body["failed"], 2);
+        assert_eq!(body["errors"][0]["line"], 4);
+        assert_eq!(body["errors"][1]["line"], 5);
+
+        let (status, body) = send_bytes(
+            &format!("{uri}?format=csv"),
+            "text/csv",
+            b"title,parent\nBank 0,\n".to_vec(),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Column `name` is not in the CSV header.");
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_renders_monthly_statements_matching_the_raw_data(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let toss = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let account = create_account(
+            &AccountCreateRequest {
+                name: "Checking <main>".into(),
+                institution_id: toss.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        for (posted_at, quantity, category) in [
+            ("2025-05-20T09:00:00Z", 100_000, Some("Salary")),
+            ("2025-05-31T23:59:59Z", -4_000, Some("Groceries")),
+            ("2025-06-01T00:00:00Z", -12_000, Some("Groceries")),
+            ("2025-06-15T12:30:00Z", -3_500, Some("Groceries")),
+            ("2025-06-25T08:00:00Z", 250_000, Some("Salary")),
+            ("2025-06-30T23:59:59Z", -800, None),
+            ("2025-07-01T00:00:00Z", -9_999, Some("Groceries")),
+        ] {
+            let create_request = TransactionCreateRequest {
+                posted_at: posted_at.parse::<DateTime<Utc>>().unwrap(),
+                description: "A test transaction".to_owned().into(),
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: category.map(str::to_owned),
+            };
+            let _ = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        }
+        let uri = format!(
+            "/api/accounts/{}/statement?year=2025&month=06",
+            account.id.0
+        );
+
+        let (status, headers, body) = get_document(&uri, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["content-type"], "text/html; charset=utf-8");
+        let html = String::from_utf8(body).unwrap();
+        assert!(html.contains("<h1>Checking &lt;main&gt;</h1>"));
+
+        let (opening, closing) = sqlx::query_as::<_, (Decimal, Decimal)>(
+            r#"
+            SELECT
+                COALESCE(SUM(quantity) FILTER (WHERE posted_at < '2025-06-01T00:00:00Z'), 0),
+                COALESCE(SUM(quantity) FILTER (WHERE posted_at < '2025-07-01T00:00:00Z'), 0)
+            FROM "transaction"
+            WHERE account_id = $1
+            "#,
+        )
+        .bind(account.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(html.contains(&format!(
+            "id=\"opening-KRW\">{}</td>",
+            format_quantity(opening, krw.decimals)
+        )));
+        assert!(html.contains(&format!(
+            "id=\"closing-KRW\">{}</td>",
+            format_quantity(closing, krw.decimals)
+        )));
+
+        let subtotals = sqlx::query_as::<_, (Option<String>, i64, Decimal)>(
+            r#"
+            SELECT category, COUNT(*), SUM(quantity)
+            FROM "transaction"
+            WHERE account_id = $1
+                AND posted_at >= '2025-06-01T00:00:00Z'
+                AND posted_at < '2025-07-01T00:00:00Z'
+            GROUP BY category
+            "#,
+        )
+        .bind(account.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(subtotals.len(), 3);
+        for (category, transactions, quantity) in &subtotals {
+            let category = category.as_deref().unwrap_or("Uncategorized");
+            assert!(
+                html.contains(&format!(
+                    "<tr data-category=\"{category}\"><td>{category}</td><td>{transactions}</td>\
+                     <td class=\"quantity\">{}</td></tr>",
+                    format_quantity(*quantity, krw.decimals)
+                )),
+                "{category}"
+            );
+        }
+        // The subtotals account for the whole change of the balance.
+        assert_eq!(
+            subtotals.iter().map(|(_, _, x)| x).sum::<Decimal>(),
+            closing - opening
+        );
+        assert_eq!(html.matches("<tr><td>2025-06-").count(), 4);
+        assert!(!html.contains("2025-05-31 23:59"));
+        assert!(!html.contains("2025-07-01 00:00</td>"));
+
+        let (status, _, _) = get_document(&uri, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // The same month as a date range renders the same statement.
+        let (status, _, body) = get_document(
+            &format!(
+                "/api/accounts/{}/statement?from=2025-06-01&to=2025-06-30",
+                account.id.0
+            ),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(String::from_utf8(body).unwrap(), html);
+
+        let now = Utc::now();
+        for uri in [
+            format!(
+                "/api/accounts/{}/statement?year={}&month={}",
+                account.id.0,
+                now.year(),
+                now.month()
+            ),
+            format!(
+                "/api/accounts/{}/statement?year=2025&month=13",
+                account.id.0
+            ),
+            format!("/api/accounts/{}/statement?month=06", account.id.0),
+            format!(
+                "/api/accounts/{}/statement?from=2025-06-01&to=2025-06-15",
+                account.id.0
+            ),
+            format!(
+                "/api/accounts/{}/statement?year=2025&month=06&from=2025-06-01",
+                account.id.0
+            ),
+            format!("/api/accounts/{}/statement?preset=this_month", account.id.0),
+        ] {
+            let (status, _, _) = get_document(&uri, &user_auth_token, &mut api).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        }
+
+        let (status, headers, body) =
+            get_document(&format!("{uri}&format=pdf"), &user_auth_token, &mut api).await;
+        if cfg!(feature = "pdf") {
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(headers["content-type"], "application/pdf");
+            assert!(body.starts_with(b"%PDF-"));
+        } else {
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_recategorizes_the_transactions_a_filter_matches(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let other_account =
+            create_account(&create_account_request, &user_two_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        for (account_id, description, category) in [
+            (account.id, "Coffee Bean", None),
+            (account.id, "Blue Bottle Coffee", None),
+            (account.id, "Coffee Bean", Some("Coffee")),
+            (account.id, "Rent", None),
+            (other_account.id, "Coffee Bean", None),
+        ] {
+            sqlx::query(
+                r#"
+                INSERT INTO "transaction" (posted_at, account_id, asset_id, quantity, description, category)
+                VALUES (NOW(), $1, $2, -5000, $3, $4)
+                "#,
+            )
+            .bind(account_id)
+            .bind(krw.id)
+            .bind(description)
+            .bind(category)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let categorized = |account_id: AccountId| {
+            sqlx::query_scalar::<_, i64>(
+                r#"SELECT COUNT(*) FROM "transaction" WHERE account_id = $1 AND category = 'Coffee'"#,
+            )
+            .bind(account_id)
+            .fetch_one(&pool)
+        };
+
+        let (status, _) = send_json(
+            "POST",
+            "/api/transactions/recategorize",
+            Some(serde_json::json!({ "filter": { "description": "coffee" }, "category": " " })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Without `execute` it only counts what it would change.
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions/recategorize",
+            Some(
+                serde_json::json!({ "filter": { "description": "coffee" }, "category": "Coffee" }),
+            ),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["matched"], 3);
+        assert!(body.get("recategorization").is_none());
+        assert_eq!(categorized(account.id).await.unwrap(), 1);
+
+        // A dry run changes them and rolls the change back.
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions/recategorize?dry_run=true",
+            Some(serde_json::json!({
+                "filter": { "description": "coffee" },
+                "category": "Coffee",
+                "execute": true,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["matched"], 3);
+        assert_eq!(body["changed"], 2);
+        assert_eq!(body["dry_run"], true);
+        assert!(body.get("recategorization").is_none());
+        assert_eq!(categorized(account.id).await.unwrap(), 1);
+        let recategorizations =
+            sqlx::query_scalar::<_, i64>(r#"SELECT COUNT(*) FROM recategorization"#)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(recategorizations, 0);
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions/recategorize",
+            Some(serde_json::json!({
+                "filter": { "description": "coffee" },
+                "category": "Coffee",
+                "execute": true,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["matched"], 3);
+        let recategorization = &body["recategorization"];
+        assert_eq!(recategorization["status"], "complete");
+        assert_eq!(recategorization["matched"], 3);
+        // The one that already had the category is left as it was.
+        assert_eq!(recategorization["changed"], 2);
+        assert_eq!(recategorization["filter"]["description"], "coffee");
+        assert_eq!(categorized(account.id).await.unwrap(), 3);
+        assert_eq!(categorized(other_account.id).await.unwrap(), 0);
+        let rent_category = sqlx::query_scalar::<_, Option<String>>(
+            r#"SELECT category FROM "transaction" WHERE description = 'Rent'"#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rent_category, None);
+
+        let recategorization_uri = recategorization["url"].as_str().unwrap().to_owned();
+        let (status, _) = send_json(
+            "GET",
+            &recategorization_uri,
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, body) = send_json(
+            "GET",
+            &recategorization_uri,
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], recategorization["id"]);
+        assert_eq!(body["changed"], 2);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_recategorizes_many_transactions_after_responding(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let account = create_account(
+            &AccountCreateRequest {
+                name: "Checking".into(),
+                institution_id: institution.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        let total = RECATEGORIZE_BATCH_SIZE * 2 + 500;
+        sqlx::query(
+            r#"
+            INSERT INTO "transaction" (posted_at, account_id, asset_id, quantity)
+            SELECT '2025-06-01T00:00:00Z'::TIMESTAMPTZ + g * INTERVAL '1 minute', $1, $2, g
+            FROM generate_series(1, $3) g
+            "#,
+        )
+        .bind(account.id)
+        .bind(krw.id)
+        .bind(total)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/transactions/recategorize",
+            Some(serde_json::json!({
+                "filter": { "account_id": account.id },
+                "category": "Imported",
+                "execute": true,
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["matched"], total);
+        assert_eq!(body["recategorization"]["status"], "pending");
+        let recategorization_uri = body["recategorization"]["url"].as_str().unwrap().to_owned();
+
+        let mut polls = 0;
+        let body = loop {
+            let (status, body) = send_json(
+                "GET",
+                &recategorization_uri,
+                None,
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            if status == StatusCode::OK {
+                break body;
+            }
+            assert_eq!(status, StatusCode::ACCEPTED);
+            polls += 1;
+            assert!(polls < 100, "the recategorization never finished");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        };
+        assert_eq!(body["status"], "complete");
+        assert_eq!(body["changed"], total);
+        let categorized = sqlx::query_scalar::<_, i64>(
+            r#"SELECT COUNT(*) FROM "transaction" WHERE category = 'Imported'"#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(categorized, total);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_renders_large_statements_after_responding(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let _ = create_user(&create_user_request, &user_two_auth_token, &mut api).await;
+        let toss = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let account = create_account(
+            &AccountCreateRequest {
+                name: "Checking".into(),
+                institution_id: toss.id,
+                notes: None,
+                default_asset_id: None,
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        sqlx::query(
+            r#"
+            INSERT INTO "transaction" (posted_at, account_id, asset_id, quantity)
+            SELECT '2025-06-01T00:00:00Z'::TIMESTAMPTZ + g * INTERVAL '1 minute', $1, $2, g
+            FROM generate_series(1, $3) g
+            "#,
+        )
+        .bind(account.id)
+        .bind(krw.id)
+        .bind(INLINE_TRANSACTIONS + 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let uri = format!("/api/accounts/{}/statement?year=2025&month=6", account.id.0);
+
+        let (status, body) = send_json("GET", &uri, None, &user_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["status"], "pending");
+        assert_eq!(body["format"], "html");
+        let statement_uri = body["url"].as_str().unwrap().to_owned();
+        assert_eq!(
+            statement_uri,
+            format!(
+                "/api/accounts/{}/statements/{}",
+                account.id.0,
+                body["id"].as_str().unwrap()
+            )
+        );
+
+        let (status, _, _) = get_document(&statement_uri, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let mut polls = 0;
+        let (headers, body) = loop {
+            let (status, headers, body) =
+                get_document(&statement_uri, &user_auth_token, &mut api).await;
+            if status == StatusCode::OK {
+                break (headers, body);
+            }
+            assert_eq!(status, StatusCode::ACCEPTED);
+            polls += 1;
+            assert!(polls < 100, "the statement was never rendered");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        };
+        assert_eq!(headers["content-type"], "text/html; charset=utf-8");
+        let html = String::from_utf8(body).unwrap();
+        let total = (1..=INLINE_TRANSACTIONS).sum::<i64>() + INLINE_TRANSACTIONS + 1;
+        assert!(html.contains(&format!(
+            "id=\"closing-KRW\">{}</td>",
+            format_quantity(total.into(), krw.decimals)
+        )));
+        assert_eq!(
+            html.matches("<tr><td>2025-06-").count() as i64,
+            INLINE_TRANSACTIONS + 1
+        );
+    }
+
+    /// An API whose users also administer `resources`.
+    async fn create_admin_api(pool: Pool<Postgres>, resources: &[&str]) -> RouterIntoService<Body> {
+        let mut admin_enforcer = Enforcer::new(
+            AUTH_MODEL_PATH.get().unwrap().as_str(),
+            AUTH_POLICY_PATH.get().unwrap().as_str(),
+        )
+        .await
+        .unwrap();
+        admin_enforcer.enable_auto_save(false);
+        for &resource in resources {
+            admin_enforcer
+                .add_policy(vec![
+                    Group::User.as_policy_subject().to_owned(),
+                    resource.to_owned(),
+                    "*".to_owned(),
+                ])
+                .await
+                .unwrap();
+        }
+        create_api(pool, Arc::new(admin_enforcer))
+    }
+
+    /// How many institutions and assets there are.
+    async fn shared_counts(pool: &Pool<Postgres>) -> (i64, i64) {
+        sqlx::query_as::<_, (i64, i64)>(
+            "SELECT (SELECT COUNT(*) FROM institution), (SELECT COUNT(*) FROM asset)",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_applies_an_approved_proposal_once(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_api =
+            create_admin_api(pool.clone(), &["proposals", "institutions", "assets"]).await;
+        let user = create_user(
+            &UserCreateRequest {
+                name: "Test User".into(),
+            },
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        let (institutions, _) = shared_counts(&pool).await;
+
+        let (status, body) = send_json(
+            "POST",
+            "/api/proposals",
+            Some(serde_json::json!({
+                "kind": "create_institution",
+                "payload": { "name": "Credit Union" },
+            })),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["status"], "pending");
+        assert_eq!(body["proposer_id"], serde_json::json!(user.id));
+        assert_eq!(body["payload"]["name"], "Credit Union");
+        let uri = format!("/api/proposals/{}", body["id"].as_str().unwrap());
+        let approve = serde_json::json!({ "decision": "approve", "review_note": "Welcome" });
+
+        let (status, _) = send_json(
+            "PATCH",
+            &uri,
+            Some(approve.clone()),
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(shared_counts(&pool).await.0, institutions);
+
+        let (status, body) = send_json(
+            "PATCH",
+            &uri,
+            Some(approve.clone()),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "approved");
+        assert_eq!(body["reviewer_id"], serde_json::json!(user.id));
+        assert_eq!(body["review_note"], "Welcome");
+        let institution_id = body["institution_id"].as_str().unwrap().to_owned();
+
+        let (status, body) = send_json(
+            "PATCH",
+            &uri,
+            Some(approve),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], 4095);
+        assert_eq!(shared_counts(&pool).await.0, institutions + 1);
+
+        let (status, body) = send_json(
+            "GET",
+            &format!("/api/institutions/{institution_id}"),
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["name"], "Credit Union");
+
+        let events = sqlx::query_as::<_, (String, Value)>(
+            "SELECT event, payload FROM webhook_event ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, "proposal.approved");
+        assert_eq!(events[0].1["institution_id"], institution_id.as_str());
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_changes_nothing_for_a_rejected_proposal(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool.clone(), enforcer);
+        let mut admin_api =
+            create_admin_api(pool.clone(), &["proposals", "institutions", "assets"]).await;
+        for token in [&user_auth_token, &user_two_auth_token] {
+            create_user(
+                &UserCreateRequest {
+                    name: "Test User".into(),
+                },
+                token,
+                &mut api,
+            )
+            .await;
+        }
+        let before = shared_counts(&pool).await;
+
+        let mut uris = Vec::new();
+        for proposal in [
+            serde_json::json!({
+                "kind": "create_asset",
+                "payload": { "name": "Gold", "symbol": "XAU", "decimals": 3 },
+            }),
+            serde_json::json!({
+                "kind": "create_institution",
+                "payload": { "name": "Credit Union" },
+            }),
+        ] {
+            let (status, body) = send_json(
+                "POST",
+                "/api/proposals",
+                Some(proposal),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED);
+            uris.push(format!("/api/proposals/{}", body["id"].as_str().unwrap()));
+        }
+
+        let (status, _) = send_json("GET", &uris[0], None, &user_two_auth_token, &mut api).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, body) = send_json(
+            "GET",
+            "/api/proposals",
+            None,
+            &user_two_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["proposals"], serde_json::json!([]));
+
+        for uri in &uris {
+            let (status, body) = send_json(
+                "PATCH",
+                uri,
+                Some(serde_json::json!({ "decision": "reject", "review_note": "Duplicate" })),
+                &user_auth_token,
+                &mut admin_api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["status"], "rejected");
+            assert_eq!(body["institution_id"], Value::Null);
+            assert_eq!(body["asset_id"], Value::Null);
+        }
+        let (status, _) = send_json(
+            "PATCH",
+            &uris[0],
+            Some(serde_json::json!({ "decision": "approve" })),
+            &user_auth_token,
+            &mut admin_api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(shared_counts(&pool).await, before);
+
+        let (status, body) = send_json(
+            "GET",
+            "/api/proposals?status=rejected",
+            None,
+            &user_auth_token,
+            &mut api,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["proposals"].as_array().unwrap().len(), 2);
+        assert_eq!(body["proposals"][0]["review_note"], "Duplicate");
+
+        let events = sqlx::query_scalar::<_, String>("SELECT event FROM webhook_event ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(events, ["proposal.rejected", "proposal.rejected"]);
+    }
+
+    /// Opens an event stream, leaving its body unread.
+    async fn open_stream(auth_token: &str, api: &mut RouterIntoService<Body>) -> Response {
+        let request = Request::builder()
+            .method("GET")
+            .header("Authorization", auth_token)
+            .header("Accept", "text/event-stream")
+            .uri("/api/events")
+            .body(Body::empty())
+            .unwrap();
+        ServiceExt::<Request<Body>>::ready(api)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap()
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test]
+    async fn it_limits_the_event_streams_of_a_user(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[future] user_two_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        for token in [&user_auth_token, &user_two_auth_token] {
+            create_user(
+                &UserCreateRequest {
+                    name: "Test User".into(),
+                },
+                token,
+                &mut api,
+            )
+            .await;
+        }
+
+        let mut streams = Vec::new();
+        for _ in 0..MAX_STREAMS_PER_USER {
+            let response = open_stream(&user_auth_token, &mut api).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["content-type"], "text/event-stream");
+            streams.push(response);
+        }
+        let response = open_stream(&user_auth_token, &mut api).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // The limit is of each user.
+        let response = open_stream(&user_two_auth_token, &mut api).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[rstest]
+    #[awt]
+    #[sqlx::test(fixtures("institutions", "assets"))]
+    async fn it_streams_alerts_of_rules_on_the_sse_channel(
+        #[future] enforcer: Arc<Enforcer>,
+        #[future] user_auth_token: String,
+        #[ignore] pool: Pool<Postgres>,
+    ) {
+        let mut api = create_api(pool, enforcer);
+        let create_user_request = UserCreateRequest {
+            name: "Test User".into(),
+        };
+        let _ = create_user(&create_user_request, &user_auth_token, &mut api).await;
+        let institution = get_institution_by_name("Toss Bank", &user_auth_token, &mut api).await;
+        let create_account_request = AccountCreateRequest {
+            name: "Checking".into(),
+            institution_id: institution.id,
+            notes: None,
+            default_asset_id: None,
+        };
+        let account = create_account(&create_account_request, &user_auth_token, &mut api).await;
+        let krw = get_asset_by_symbol(&user_auth_token, &mut api, "KRW").await;
+        for (channel, threshold) in [("webhook", 200_000), ("sse", 100_000)] {
+            let (status, _) = send_json(
+                "POST",
+                "/api/alert-rules",
+                Some(serde_json::json!({
+                    "account_id": account.id,
+                    "asset_id": krw.id,
+                    "comparator": "below",
+                    "threshold": threshold,
+                    "channel": channel,
+                })),
+                &user_auth_token,
+                &mut api,
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+
+        let response = open_stream(&user_auth_token, &mut api).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body();
+
+        // The balance goes 500,000 -> 50,000, crossing below both thresholds.
+        for quantity in [500_000_i64, -450_000] {
+            let create_request = TransactionCreateRequest {
+                posted_at: "2025-01-02T00:00:00Z".parse().unwrap(),
+                description: None,
+                account_id: account.id,
+                asset_id: krw.id,
+                quantity: quantity.into(),
+                notes: None,
+                category: None,
+            };
+            let _ = create_transaction(&create_request, &user_auth_token, &mut api).await;
+        }
+
+        let mut streamed = String::new();
+        while !streamed.contains("\n\n") {
+            let frame = tokio::time::timeout(Duration::from_secs(5), body.frame())
+                .await
+                .expect("No alert was streamed.")
+                .unwrap()
+                .unwrap();
+            if let Ok(data) = frame.into_data() {
+                streamed.push_str(&String::from_utf8_lossy(&data));
+            }
+        }
+        let (name, data) = streamed.trim_end().split_once('\n').unwrap();
+        assert_eq!(name, "event: alert.fired");
+        let data: Value = serde_json::from_str(data.strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(data["channel"], "sse");
+        assert_eq!(data["balance"], 50_000);
     }
 }
@@ -0,0 +1,408 @@
+//! Tells how two OpenAPI documents differ, for checking a committed one.
+//!
+//! Clients generated from a snapshot of the spec go stale as soon as the
+//! API changes. [`OpenApiDiff::between`] compares the snapshot with the
+//! spec the server builds, by endpoint and by schema, so that a check in CI
+//! can say which endpoints were added or removed and what changed in the
+//! ones that were kept, rather than only that the files differ.
+
+use std::{collections::BTreeSet, fmt};
+
+use serde_json::{Map, Value};
+
+/// The methods an OpenAPI path item may have an operation for.
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// How a value at a place in a document changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(String),
+    Removed(String),
+    Changed(String),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added(pointer) => write!(f, "+ {pointer}"),
+            Self::Removed(pointer) => write!(f, "- {pointer}"),
+            Self::Changed(pointer) => write!(f, "~ {pointer}"),
+        }
+    }
+}
+
+/// An endpoint or a schema that both documents have, and how it changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changed {
+    /// The endpoint, like `GET /api/accounts`, or the name of the schema
+    pub name: String,
+    /// Where in it the values differ, as JSON pointers within it
+    pub changes: Vec<Change>,
+}
+
+/// What changed from one document to another.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpenApiDiff {
+    /// The endpoints only the new document has, like `GET /api/accounts`
+    pub added_endpoints: Vec<String>,
+    /// The endpoints only the old document has
+    pub removed_endpoints: Vec<String>,
+    pub changed_endpoints: Vec<Changed>,
+    /// The schemas only the new document has, by name
+    pub added_schemas: Vec<String>,
+    /// The schemas only the old document has
+    pub removed_schemas: Vec<String>,
+    pub changed_schemas: Vec<Changed>,
+    /// The places outside of the endpoints and schemas that differ, such as
+    /// the info or the security schemes
+    pub other_changes: Vec<Change>,
+}
+
+impl OpenApiDiff {
+    /// What changed from the document `old` to `new`.
+    pub fn between(old: &Value, new: &Value) -> Self {
+        let mut diff = Self::default();
+
+        let endpoints = |document: &Value| {
+            let mut endpoints = vec![];
+            for (path, item) in object(document.get("paths")) {
+                for method in METHODS {
+                    if let Some(operation) = item.get(method) {
+                        endpoints.push((format!("{} {path}", method.to_uppercase()), operation));
+                    }
+                }
+            }
+            endpoints
+        };
+        let (old_endpoints, new_endpoints) = (endpoints(old), endpoints(new));
+        let (added, removed, kept) = compare(&old_endpoints, &new_endpoints);
+        diff.added_endpoints = added;
+        diff.removed_endpoints = removed;
+        diff.changed_endpoints = kept;
+
+        let schemas = |document: &Value| {
+            object(document.pointer("/components/schemas"))
+                .iter()
+                .map(|(name, schema)| (name.clone(), schema))
+                .collect::<Vec<_>>()
+        };
+        let (added, removed, kept) = compare(&schemas(old), &schemas(new));
+        diff.added_schemas = added;
+        diff.removed_schemas = removed;
+        diff.changed_schemas = kept;
+
+        // The rest of the document, with what was compared above left out.
+        let rest = |document: &Value| {
+            let mut document = document.clone();
+            if let Some(document) = document.as_object_mut() {
+                document.remove("paths");
+            }
+            if let Some(components) = document
+                .get_mut("components")
+                .and_then(Value::as_object_mut)
+            {
+                components.remove("schemas");
+            }
+            document
+        };
+        diff_values("", &rest(old), &rest(new), &mut diff.other_changes);
+        diff
+    }
+
+    /// Whether the documents are the same.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl fmt::Display for OpenApiDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "The documents are the same.");
+        }
+        write_section(
+            f,
+            "Endpoints",
+            &self.added_endpoints,
+            &self.removed_endpoints,
+            &self.changed_endpoints,
+        )?;
+        write_section(
+            f,
+            "Schemas",
+            &self.added_schemas,
+            &self.removed_schemas,
+            &self.changed_schemas,
+        )?;
+        if !self.other_changes.is_empty() {
+            writeln!(f, "Elsewhere:")?;
+            for change in &self.other_changes {
+                writeln!(f, "  {change}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the names added, removed and changed under `title`, if any.
+fn write_section(
+    f: &mut fmt::Formatter<'_>,
+    title: &str,
+    added: &[String],
+    removed: &[String],
+    changed: &[Changed],
+) -> fmt::Result {
+    if added.is_empty() && removed.is_empty() && changed.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "{title}:")?;
+    for name in added {
+        writeln!(f, "  + {name}")?;
+    }
+    for name in removed {
+        writeln!(f, "  - {name}")?;
+    }
+    for changed in changed {
+        writeln!(f, "  ~ {}", changed.name)?;
+        for change in &changed.changes {
+            writeln!(f, "      {change}")?;
+        }
+    }
+    Ok(())
+}
+
+fn object(value: Option<&Value>) -> Map<String, Value> {
+    value
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// The names only `new` has, those only `old` has, and the changes of
+/// those both have that differ, each in order.
+fn compare(
+    old: &[(String, &Value)],
+    new: &[(String, &Value)],
+) -> (Vec<String>, Vec<String>, Vec<Changed>) {
+    let old_names = old.iter().map(|(name, _)| name).collect::<BTreeSet<_>>();
+    let new_names = new.iter().map(|(name, _)| name).collect::<BTreeSet<_>>();
+    let added = new_names
+        .difference(&old_names)
+        .map(|x| x.to_string())
+        .collect();
+    let removed = old_names
+        .difference(&new_names)
+        .map(|x| x.to_string())
+        .collect();
+    let mut changed = vec![];
+    for name in old_names.intersection(&new_names) {
+        let find = |values: &[(String, &Value)]| {
+            values
+                .iter()
+                .find(|(x, _)| x == *name)
+                .map(|(_, value)| (*value).clone())
+                .unwrap_or_default()
+        };
+        let mut changes = vec![];
+        diff_values("", &find(old), &find(new), &mut changes);
+        if !changes.is_empty() {
+            changed.push(Changed {
+                name: name.to_string(),
+                changes,
+            });
+        }
+    }
+    (added, removed, changed)
+}
+
+/// Escapes a key for a JSON pointer, as RFC 6901 has it.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Records where `new` differs from `old`, both at `pointer`, down to the
+/// deepest place that was added, removed or changed.
+fn diff_values(pointer: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let keys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+            for key in keys {
+                let pointer = format!("{pointer}/{}", escape(key));
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_values(&pointer, old, new, changes),
+                    (None, Some(_)) => changes.push(Change::Added(pointer)),
+                    (Some(_), None) => changes.push(Change::Removed(pointer)),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for index in 0..old.len().max(new.len()) {
+                let pointer = format!("{pointer}/{index}");
+                match (old.get(index), new.get(index)) {
+                    (Some(old), Some(new)) => diff_values(&pointer, old, new, changes),
+                    (None, Some(_)) => changes.push(Change::Added(pointer)),
+                    (Some(_), None) => changes.push(Change::Removed(pointer)),
+                    (None, None) => {}
+                }
+            }
+        }
+        (old, new) if old != new => changes.push(Change::Changed(if pointer.is_empty() {
+            "/".to_owned()
+        } else {
+            pointer.to_owned()
+        })),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn document(paths: Value, schemas: Value) -> Value {
+        json!({
+            "openapi": "3.1.0",
+            "info": {"title": "treasury", "version": "0.1.0"},
+            "paths": paths,
+            "components": {"schemas": schemas},
+        })
+    }
+
+    fn accounts() -> Value {
+        json!({
+            "/api/accounts": {
+                "get": {
+                    "responses": {
+                        "200": {
+                            "content": {
+                                "application/json": {
+                                    "schema": {"$ref": "#/components/schemas/AccountResponse"}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn account_response(name_type: &str) -> Value {
+        json!({
+            "AccountResponse": {
+                "type": "object",
+                "required": ["id", "name"],
+                "properties": {
+                    "id": {"type": "string"},
+                    "name": {"type": name_type},
+                },
+            }
+        })
+    }
+
+    #[test]
+    fn it_finds_nothing_between_the_same_documents() {
+        let old = document(accounts(), account_response("string"));
+        let diff = OpenApiDiff::between(&old, &old.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "The documents are the same.\n");
+    }
+
+    #[test]
+    fn it_lists_an_added_endpoint() {
+        let old = document(accounts(), account_response("string"));
+        let mut paths = accounts();
+        paths["/api/accounts"]["post"] = json!({"responses": {"201": {}}});
+        paths["/api/watchlist"] = json!({"get": {"responses": {"200": {}}}});
+        let new = document(paths, account_response("string"));
+
+        let diff = OpenApiDiff::between(&old, &new);
+        assert_eq!(
+            diff.added_endpoints,
+            vec![
+                "GET /api/watchlist".to_owned(),
+                "POST /api/accounts".to_owned()
+            ]
+        );
+        assert_eq!(diff.removed_endpoints, Vec::<String>::new());
+        assert_eq!(diff.changed_endpoints, vec![]);
+        assert_eq!(
+            diff.to_string(),
+            "Endpoints:\n  + GET /api/watchlist\n  + POST /api/accounts\n"
+        );
+
+        let diff = OpenApiDiff::between(&new, &old);
+        assert_eq!(
+            diff.removed_endpoints,
+            vec![
+                "GET /api/watchlist".to_owned(),
+                "POST /api/accounts".to_owned()
+            ]
+        );
+    }
+
+    #[test]
+    fn it_points_into_a_changed_response_schema() {
+        let old = document(accounts(), account_response("string"));
+        let mut schemas = account_response("integer");
+        schemas["AccountResponse"]["properties"]["notes"] = json!({"type": "string"});
+        schemas["AccountResponse"]["required"] = json!(["id"]);
+        let mut paths = accounts();
+        paths["/api/accounts"]["get"]["responses"]["200"]["content"]["application/json"]["schema"] =
+            json!({"$ref": "#/components/schemas/AccountListResponse"});
+        let new = document(paths, schemas);
+
+        let diff = OpenApiDiff::between(&old, &new);
+        assert_eq!(
+            diff.changed_schemas,
+            vec![Changed {
+                name: "AccountResponse".to_owned(),
+                changes: vec![
+                    Change::Changed("/properties/name/type".to_owned()),
+                    Change::Added("/properties/notes".to_owned()),
+                    Change::Removed("/required/1".to_owned()),
+                ],
+            }]
+        );
+        assert_eq!(
+            diff.changed_endpoints,
+            vec![Changed {
+                name: "GET /api/accounts".to_owned(),
+                changes: vec![Change::Changed(
+                    "/responses/200/content/application~1json/schema/$ref".to_owned()
+                )],
+            }]
+        );
+        assert_eq!(diff.other_changes, vec![]);
+        assert_eq!(
+            diff.to_string(),
+            "\
+Endpoints:
+  ~ GET /api/accounts
+      ~ /responses/200/content/application~1json/schema/$ref
+Schemas:
+  ~ AccountResponse
+      ~ /properties/name/type
+      + /properties/notes
+      - /required/1
+"
+        );
+    }
+
+    #[test]
+    fn it_reports_changes_outside_of_endpoints_and_schemas() {
+        let old = document(accounts(), account_response("string"));
+        let mut new = old.clone();
+        new["info"]["version"] = json!("0.2.0");
+        let diff = OpenApiDiff::between(&old, &new);
+        assert_eq!(
+            diff.other_changes,
+            vec![Change::Changed("/info/version".to_owned())]
+        );
+        assert_eq!(diff.to_string(), "Elsewhere:\n  ~ /info/version\n");
+    }
+}
@@ -0,0 +1,94 @@
+//! Answers requests whose handler panicked instead of dropping them.
+//!
+//! Without it, a panic kills the connection and the client is left with
+//! nothing to go on. [`handle_panic`] answers with the usual 500
+//! [`ApiErrorResponse`] and the id of the request, which the log of the
+//! panic shares so that a reported error can be found, and [`panics`]
+//! counts them since the server started.
+
+use std::{
+    any::Any,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use axum::response::{IntoResponse, Response};
+use http::{HeaderValue, StatusCode};
+use serde::Serialize;
+use tracing::error;
+
+use crate::api::{
+    ApiError, ApiErrorResponse, ApiJson,
+    error::ERROR_CODE_HEADER,
+    request_id::{REQUEST_ID_HEADER, RequestId},
+};
+
+static PANICS: AtomicU64 = AtomicU64::new(0);
+
+/// The body of a request that panicked.
+#[derive(Debug, Serialize)]
+struct PanicResponse {
+    #[serde(flatten)]
+    error: ApiErrorResponse,
+    /// The id the panic was logged with
+    request_id: String,
+}
+
+/// The handlers that panicked since the server started.
+pub fn panics() -> u64 {
+    PANICS.load(Ordering::Relaxed)
+}
+
+/// What a panic was raised with, if it was a message.
+fn message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("a payload that isn't a message")
+}
+
+/// Logs and counts a panic, answering with an internal server error, for
+/// `CatchPanicLayer::custom`.
+pub fn handle_panic(payload: Box<dyn Any + Send + 'static>) -> Response {
+    PANICS.fetch_add(1, Ordering::Relaxed);
+    let request_id = RequestId::current().unwrap_or_default();
+    error!(
+        request_id = %request_id,
+        "A handler panicked with {}",
+        message(payload.as_ref())
+    );
+    let error = ApiError::ServerError;
+    let code = HeaderValue::from(ApiErrorResponse::from(&error).code);
+    let mut response = (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        ApiJson(PanicResponse {
+            error: ApiErrorResponse::from(&error),
+            request_id: request_id.to_string(),
+        }),
+    )
+        .into_response();
+    response.headers_mut().insert(ERROR_CODE_HEADER, code);
+    // A request that panicked outside of `set_request_id` still gets the
+    // id it was logged with.
+    if !response.headers().contains_key(REQUEST_ID_HEADER)
+        && let Ok(header) = HeaderValue::from_str(request_id.as_str())
+    {
+        response.headers_mut().insert(REQUEST_ID_HEADER, header);
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_logs_the_message_of_the_panic() {
+        let payload: Box<dyn Any + Send> = Box::new(format!("{} went wrong", "something"));
+        assert_eq!(message(payload.as_ref()), "something went wrong");
+        let payload: Box<dyn Any + Send> = Box::new("it went wrong");
+        assert_eq!(message(payload.as_ref()), "it went wrong");
+        let payload: Box<dyn Any + Send> = Box::new(42);
+        assert_eq!(message(payload.as_ref()), "a payload that isn't a message");
+    }
+}
@@ -0,0 +1,560 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::{passkey::PasskeyId, user::UserId},
+    schema::passkey::{
+        PasskeyCreateResponse, PasskeyDeleteResponse, PasskeyGetListResponse,
+        RegisterFinishRequest, RegisterStartResponse, StepUpFinishRequest, StepUpFinishResponse,
+        StepUpStartResponse,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{
+            authenticator::Authenticator,
+            registered_user::RegisteredUser,
+            step_up::{Elevation, webauthn},
+        },
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        model::{
+            passkey::{Passkey, PasskeyCreate, PasskeyFilter},
+            webauthn_challenge::{CeremonyState, WebauthnChallenge, WebauthnChallengeId},
+        },
+        schema::passkey::ChallengeResponse,
+        service::{
+            ServiceError, passkey_service::PasskeyServiceMethods,
+            passkey_service_factory::PasskeyServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+    pub use tracing::{debug, error};
+    pub use uuid::Uuid;
+    pub use webauthn_rs::prelude::{PublicKeyCredential, RegisterPublicKeyCredential};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathUserId {
+    id: UserId,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathPasskeyId {
+    passkey_id: PasskeyId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `passkeys` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct PasskeyApiResource;
+
+    impl ApiResource for PasskeyApiResource {
+        const NAME: &'static str = "passkeys";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn PasskeyServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            PasskeyServiceFactory::build(owner, Arc::clone(&state.connection_pool), permission_set)
+        }
+    }
+
+    pub type PasskeyApiState = ResourceContext<PasskeyApiResource>;
+
+    /// Passkeys can only be managed by the user they belong to, so the
+    /// user in the path must be the caller.
+    pub async fn path_user(state: &AppState) -> Result<RegisteredUser, ApiError> {
+        let registered_user = extract_with_state::<RegisteredUser, _>(state).await?;
+        let PathUserId { id } = extract_path().await?;
+        if id != registered_user.id() {
+            return Err(ApiError::NotFound);
+        }
+        Ok(registered_user)
+    }
+
+    pub async fn user_passkeys(api_state: &PasskeyApiState) -> Result<Vec<Passkey>, ApiError> {
+        let passkeys = api_state
+            .service
+            .get_list(0, None, PasskeyFilter::default())
+            .await?;
+        Ok(passkeys)
+    }
+
+    /// Takes the challenge presented to finish a ceremony. Challenges that
+    /// are missing, expired or another user's are all just invalid.
+    pub async fn take_challenge(
+        api_state: &PasskeyApiState,
+        challenge_id: WebauthnChallengeId,
+    ) -> Result<WebauthnChallenge, ApiError> {
+        match api_state.service.take_challenge(challenge_id).await {
+            Ok(challenge) => Ok(challenge),
+            Err(ServiceError::NotFound) => Err(ApiError::client(
+                ClientErrorCode::InvalidPasskey,
+                "Invalid challenge.",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn issue_challenge(
+        api_state: &PasskeyApiState,
+        ceremony_state: CeremonyState,
+        options: impl Serialize,
+    ) -> Result<ChallengeResponse, ApiError> {
+        let options = serde_json::to_value(options).map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?;
+        let challenge = api_state.service.issue_challenge(ceremony_state).await?;
+        Ok(ChallengeResponse {
+            challenge_id: challenge.id,
+            options,
+        })
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        // Server fn endpoints can't carry path parameters, so ids are
+        // dropped here and recovered with `extract` inside each server fn.
+        let mut path = req
+            .uri()
+            .path()
+            .split('/')
+            .map(|segment| {
+                if segment.parse::<Uuid>().is_ok() {
+                    ""
+                } else {
+                    segment
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        if let Some(query) = req.uri().query() {
+            path = format!("{path}?{query}");
+        }
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/users{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct PasskeyApi;
+
+    impl Api for PasskeyApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/passkeys"),
+                (Method::DELETE, "/passkeys/{passkey_id}"),
+                (Method::POST, "/passkeys/register/start"),
+                (Method::POST, "/passkeys/register/finish"),
+                (Method::POST, "/step-up/start"),
+                (Method::POST, "/step-up/finish"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/passkeys", axum::routing::get(server_fn_handler))
+                .route(
+                    "/passkeys/{passkey_id}",
+                    axum::routing::delete(server_fn_handler),
+                )
+                .route(
+                    "/passkeys/register/start",
+                    axum::routing::post(server_fn_handler),
+                )
+                .route(
+                    "/passkeys/register/finish",
+                    axum::routing::post(server_fn_handler),
+                )
+                .route("/step-up/start", axum::routing::post(server_fn_handler))
+                .route("/step-up/finish", axum::routing::post(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/users/{id}/passkeys",
+    tag = "Passkeys",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The passkeys registered to the user.", body = PasskeyGetListResponse),
+        (status = 404, description = "The user was not found."),
+    ),
+))]
+#[server(
+    name = PasskeyApiGetList,
+    prefix = "/api",
+    endpoint = "users/passkeys",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<PasskeyGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    path_user(&state).await?;
+    let api_state = extract_with_state::<PasskeyApiState, _>(&state).await?;
+
+    let passkeys = user_passkeys(&api_state).await?;
+    Ok(passkeys.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/users/{id}/passkeys/register/start",
+    tag = "Passkeys",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The options for `navigator.credentials.create`.", body = RegisterStartResponse),
+        (status = 401, description = "A step-up is required to add another passkey.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4011,
+            message: "step_up_required".to_string()
+        })),
+        (status = 404, description = "The user was not found."),
+    ),
+))]
+#[server(
+    name = PasskeyApiRegisterStart,
+    prefix = "/api",
+    endpoint = "users/passkeys/register/start",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn register_start() -> Result<RegisterStartResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let registered_user = path_user(&state).await?;
+    let api_state = extract_with_state::<PasskeyApiState, _>(&state).await?;
+    // Otherwise a stolen token could enroll its own passkey and step up with it.
+    extract_with_state::<Elevation, _>(&state)
+        .await?
+        .require()?;
+
+    let exclude_credentials = user_passkeys(&api_state)
+        .await?
+        .iter()
+        .map(|passkey| passkey.credential.cred_id().clone())
+        .collect::<Vec<_>>();
+    let user = &registered_user.user;
+    let (options, registration) = webauthn()
+        .start_passkey_registration(
+            user.id.0,
+            &user.email,
+            &user.name,
+            Some(exclude_credentials),
+        )
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?;
+
+    issue_challenge(
+        &api_state,
+        CeremonyState::Registration(registration),
+        options,
+    )
+    .await
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/users/{id}/passkeys/register/finish",
+    tag = "Passkeys",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = RegisterFinishRequest,
+    responses(
+        (status = 201, description = "The newly registered passkey.", body = PasskeyCreateResponse),
+        (status = 400, description = "The challenge or credential was invalid."),
+        (status = 404, description = "The user was not found."),
+    ),
+))]
+#[server(
+    name = PasskeyApiRegisterFinish,
+    prefix = "/api",
+    endpoint = "users/passkeys/register/finish",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn register_finish(
+    #[server(flatten)] finish_request: RegisterFinishRequest,
+) -> Result<PasskeyCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let registered_user = path_user(&state).await?;
+    let api_state = extract_with_state::<PasskeyApiState, _>(&state).await?;
+
+    let challenge = take_challenge(&api_state, finish_request.challenge_id).await?;
+    let CeremonyState::Registration(registration) = challenge.state.0 else {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidPasskey,
+            "Invalid challenge.",
+        ));
+    };
+    let credential = serde_json::from_value::<RegisterPublicKeyCredential>(
+        finish_request.credential,
+    )
+    .map_err(|_| ApiError::client(ClientErrorCode::InvalidPasskey, "Invalid credential."))?;
+    let credential = webauthn()
+        .finish_passkey_registration(&credential, &registration)
+        .map_err(|e| {
+            debug!("{e}");
+            ApiError::client(
+                ClientErrorCode::InvalidPasskey,
+                "Passkey registration failed.",
+            )
+        })?;
+
+    let passkey = api_state
+        .service
+        .create(PasskeyCreate {
+            user_id: registered_user.id(),
+            name: finish_request.name,
+            credential,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(PasskeyCreateResponse::status());
+    provide_context(response_opts);
+    Ok(passkey.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/users/{id}/passkeys/{passkey_id}",
+    tag = "Passkeys",
+    params(UserId, PasskeyId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The passkey was successfully removed."),
+        (status = 401, description = "A step-up is required to remove a passkey.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4011,
+            message: "step_up_required".to_string()
+        })),
+        (status = 404, description = "The passkey was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = PasskeyApiDelete,
+    prefix = "/api",
+    endpoint = "users/passkeys/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<PasskeyDeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    path_user(&state).await?;
+    let api_state = extract_with_state::<PasskeyApiState, _>(&state).await?;
+    extract_with_state::<Elevation, _>(&state)
+        .await?
+        .require()?;
+    let PathPasskeyId { passkey_id } = extract_path().await?;
+
+    api_state.service.delete(passkey_id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(PasskeyDeleteResponse::status());
+    provide_context(response_opts);
+    Ok(PasskeyDeleteResponse {})
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/users/{id}/step-up/start",
+    tag = "Passkeys",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The options for `navigator.credentials.get`.", body = StepUpStartResponse),
+        (status = 400, description = "The user has no registered passkeys."),
+        (status = 404, description = "The user was not found."),
+    ),
+))]
+#[server(
+    name = StepUpApiStart,
+    prefix = "/api",
+    endpoint = "users/step-up/start",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn step_up_start() -> Result<StepUpStartResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    path_user(&state).await?;
+    let api_state = extract_with_state::<PasskeyApiState, _>(&state).await?;
+
+    let credentials = user_passkeys(&api_state)
+        .await?
+        .into_iter()
+        .map(|passkey| passkey.credential.0)
+        .collect::<Vec<_>>();
+    if credentials.is_empty() {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidPasskey,
+            "No passkeys are registered.",
+        ));
+    }
+    let (options, authentication) = webauthn()
+        .start_passkey_authentication(&credentials)
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?;
+
+    issue_challenge(
+        &api_state,
+        CeremonyState::Authentication(authentication),
+        options,
+    )
+    .await
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/users/{id}/step-up/finish",
+    tag = "Passkeys",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = StepUpFinishRequest,
+    responses(
+        (status = 200, description = "The session is elevated until the returned time.", body = StepUpFinishResponse),
+        (status = 400, description = "The challenge or assertion was invalid."),
+        (status = 404, description = "The user was not found."),
+    ),
+))]
+#[server(
+    name = StepUpApiFinish,
+    prefix = "/api",
+    endpoint = "users/step-up/finish",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn step_up_finish(
+    #[server(flatten)] finish_request: StepUpFinishRequest,
+) -> Result<StepUpFinishResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    path_user(&state).await?;
+    let api_state = extract_with_state::<PasskeyApiState, _>(&state).await?;
+
+    let challenge = take_challenge(&api_state, finish_request.challenge_id).await?;
+    let CeremonyState::Authentication(authentication) = challenge.state.0 else {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidPasskey,
+            "Invalid challenge.",
+        ));
+    };
+    let credential = serde_json::from_value::<PublicKeyCredential>(finish_request.credential)
+        .map_err(|_| ApiError::client(ClientErrorCode::InvalidPasskey, "Invalid credential."))?;
+    let result = webauthn()
+        .finish_passkey_authentication(&credential, &authentication)
+        .map_err(|e| {
+            debug!("{e}");
+            ApiError::client(ClientErrorCode::InvalidPasskey, "Step-up failed.")
+        })?;
+
+    let grant = match api_state
+        .service
+        .step_up(api_state.authenticated_token.session(), result)
+        .await
+    {
+        Ok(grant) => grant,
+        Err(ServiceError::NotFound) => {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidPasskey,
+                "Step-up failed.",
+            ));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(StepUpFinishResponse {
+        expires_at: grant.expires_at,
+    })
+}
@@ -0,0 +1,502 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::change_proposal::ChangeProposalId,
+    schema::{
+        Pagination,
+        proposal::{
+            CreateRequest, GetListRequest, ProposalCreateResponse, ProposalGetListResponse,
+            ProposalGetResponse, ProposalReviewResponse, ReviewRequest,
+        },
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{GetUrl, Json, PatchJson},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, AppState, ClientErrorCode,
+            asset_api::{AssetApiResource, MAX_DECIMALS},
+            extract_path, extract_with_state,
+            institution_api::InstitutionApiResource,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{
+            authenticated_token::AuthenticatedToken, authenticator::Authenticator,
+            registered_user::RegisteredUser,
+        },
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        config::PagedResource,
+        model::{
+            asset::AssetId,
+            change_proposal::{ChangeProposal, ChangeProposalCreate, ChangeProposalFilter},
+            cursor_key::CursorKey,
+            institution::InstitutionId,
+        },
+        schema::{
+            asset::CreateRequest as AssetCreateRequest,
+            institution::{
+                CreateRequest as InstitutionCreateRequest,
+                UpdateRequest as InstitutionUpdateRequest,
+            },
+            notes::validate_notes,
+            proposal::{EditInstitutionRequest, ReviewDecision},
+            text::{ASSET_NAME, ASSET_SYMBOL, INSTITUTION_NAME},
+        },
+        service::{
+            proposal_service::ProposalServiceMethods,
+            proposal_service_factory::ProposalServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use serde_json::json;
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+    pub use tracing::error;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathProposalId {
+    id: ChangeProposalId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `proposals` the API resolves for a caller. Users
+    /// propose and read their own proposals, reviewing them takes
+    /// `update_all`.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::ReadAll,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::UpdateAll,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct ProposalApiResource;
+
+    impl ApiResource for ProposalApiResource {
+        const NAME: &'static str = "proposals";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn ProposalServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            ProposalServiceFactory::build(owner, Arc::clone(&state.connection_pool), permission_set)
+        }
+    }
+
+    pub type ProposalApiState = ResourceContext<ProposalApiResource>;
+
+    /// Sanitizes the fields of a proposal the way the API of the resource
+    /// it changes would.
+    pub fn validate_proposal(create_request: CreateRequest) -> Result<CreateRequest, ApiError> {
+        Ok(match create_request {
+            CreateRequest::CreateInstitution(request) => {
+                CreateRequest::CreateInstitution(InstitutionCreateRequest {
+                    name: INSTITUTION_NAME.sanitize(&request.name)?,
+                    ..request
+                })
+            }
+            CreateRequest::EditInstitution(EditInstitutionRequest {
+                institution_id,
+                changes,
+            }) => CreateRequest::EditInstitution(EditInstitutionRequest {
+                institution_id,
+                changes: InstitutionUpdateRequest {
+                    name: INSTITUTION_NAME.sanitize_option(changes.name)?,
+                    ..changes
+                },
+            }),
+            CreateRequest::CreateAsset(request) => {
+                if !(0..=MAX_DECIMALS).contains(&request.decimals) {
+                    return Err(ApiError::client(
+                        ClientErrorCode::InvalidRequest,
+                        format!("Assets have between 0 and {MAX_DECIMALS} decimals."),
+                    ));
+                }
+                CreateRequest::CreateAsset(AssetCreateRequest {
+                    name: ASSET_NAME.sanitize(&request.name)?,
+                    symbol: ASSET_SYMBOL.sanitize(&request.symbol)?,
+                    ..request
+                })
+            }
+        })
+    }
+
+    /// The permissions of the reviewer on the resource of `name`, which
+    /// the change is made with.
+    fn permission_set(
+        state: &AppState,
+        authenticated_token: &AuthenticatedToken,
+        name: &str,
+        permission_config: PermissionConfig,
+    ) -> Result<PermissionSet, ApiError> {
+        PermissionSet::new(
+            name,
+            &state.enforcer,
+            authenticated_token,
+            permission_config,
+        )
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })
+    }
+
+    /// Makes the change of an approved proposal through the service of the
+    /// resource it changes, returning the institution or asset it made.
+    pub async fn apply(
+        state: &AppState,
+        authenticated_token: &AuthenticatedToken,
+        proposal: &ChangeProposal,
+    ) -> Result<(Option<InstitutionId>, Option<AssetId>), ApiError> {
+        let request = serde_json::from_value::<CreateRequest>(json!({
+            "kind": proposal.kind,
+            "payload": proposal.payload.0,
+        }))
+        .map_err(|e| {
+            error!("Proposal {} has an invalid payload: {e}", proposal.id);
+            ApiError::ServerError
+        })?;
+        match request {
+            CreateRequest::CreateInstitution(request) => {
+                let service = InstitutionApiResource::service(
+                    state,
+                    (),
+                    permission_set(
+                        state,
+                        authenticated_token,
+                        InstitutionApiResource::NAME,
+                        InstitutionApiResource::PERMISSION_CONFIG,
+                    )?,
+                );
+                let institution = service.create(request.into()).await?;
+                Ok((Some(institution.id), None))
+            }
+            CreateRequest::EditInstitution(EditInstitutionRequest {
+                institution_id,
+                changes,
+            }) => {
+                let service = InstitutionApiResource::service(
+                    state,
+                    (),
+                    permission_set(
+                        state,
+                        authenticated_token,
+                        InstitutionApiResource::NAME,
+                        InstitutionApiResource::PERMISSION_CONFIG,
+                    )?,
+                );
+                let institution = service.update(institution_id, changes.into()).await?;
+                Ok((Some(institution.id), None))
+            }
+            CreateRequest::CreateAsset(request) => {
+                let service = AssetApiResource::service(
+                    state,
+                    (),
+                    permission_set(
+                        state,
+                        authenticated_token,
+                        AssetApiResource::NAME,
+                        AssetApiResource::PERMISSION_CONFIG,
+                    )?,
+                );
+                let asset = service.create(request.into()).await?;
+                Ok((None, Some(asset.id)))
+            }
+        }
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/proposals{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct ProposalApi;
+
+    impl Api for ProposalApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler).patch(server_fn_handler),
+                )
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[allow(unused_variables)]
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/proposals",
+    tag = "Proposals",
+    params(GetListRequest, Pagination),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "Every proposal for admins, the caller's own otherwise, oldest first.", body = ProposalGetListResponse)
+    ),
+))]
+#[server(
+    name = ProposalApiGetList,
+    prefix = "/api",
+    endpoint = "/proposals",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list(
+    #[server(flatten)]
+    #[server(default)]
+    filter: GetListRequest,
+    #[server(flatten)]
+    #[server(default)]
+    pagination: Pagination,
+) -> Result<ProposalGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ProposalApiState, _>(&state).await?;
+    let pagination = extract_with_state::<Pagination, _>(&state)
+        .await?
+        .for_resource(PagedResource::Proposals)?;
+    let cursor_key = extract_with_state::<CursorKey, _>(&state).await?;
+
+    let proposals = api_state
+        .service
+        .get_list(
+            pagination.offset(),
+            pagination.limit().into(),
+            ChangeProposalFilter {
+                proposer_id: None,
+                status: filter.status,
+            },
+        )
+        .await?;
+    let response = ProposalGetListResponse::new(proposals, &pagination, &cursor_key)?;
+    Ok(response)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/proposals/{id}",
+    tag = "Proposals",
+    params(ChangeProposalId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The proposal.", body = ProposalGetResponse),
+        (status = 404, description = "The proposal was not found, or is of another user."),
+    ),
+))]
+#[server(
+    name = ProposalApiGet,
+    prefix = "/api",
+    endpoint = "proposals/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<ProposalGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ProposalApiState, _>(&state).await?;
+    let PathProposalId { id } = extract_path().await?;
+
+    let proposal = api_state.service.get(id).await?;
+    Ok(proposal.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/proposals",
+    tag = "Proposals",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The proposal, pending review by an admin.", body = ProposalCreateResponse),
+        (status = 400, description = "The change would be refused by the API of the resource it changes."),
+        (status = 404, description = "The institution to edit was not found."),
+    ),
+))]
+#[server(
+    name = ProposalApiCreate,
+    prefix = "/api",
+    endpoint = "proposals",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<ProposalCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ProposalApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+
+    let create_request = validate_proposal(create_request)?;
+    let institution_id = match &create_request {
+        CreateRequest::EditInstitution(request) => Some(request.institution_id),
+        _ => None,
+    };
+    let payload = match &create_request {
+        CreateRequest::CreateInstitution(request) => serde_json::to_value(request),
+        CreateRequest::EditInstitution(request) => serde_json::to_value(request),
+        CreateRequest::CreateAsset(request) => serde_json::to_value(request),
+    }
+    .map_err(|e| {
+        error!("{e}");
+        ApiError::ServerError
+    })?;
+    let proposal = api_state
+        .service
+        .create(ChangeProposalCreate {
+            kind: create_request.kind(),
+            payload,
+            proposer_id: registered_user.id(),
+            institution_id,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(ProposalCreateResponse::status());
+    provide_context(response_opts);
+    Ok(proposal.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/proposals/{id}",
+    tag = "Proposals",
+    params(ChangeProposalId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = ReviewRequest,
+    responses(
+        (status = 200, description = "The reviewed proposal, linked to what approving it created or changed.", body = ProposalReviewResponse),
+        (status = 403, description = "Only admins can review proposals."),
+        (status = 404, description = "The proposal was not found."),
+        (status = 409, description = "The proposal was already reviewed."),
+    ),
+))]
+#[server(
+    name = ProposalApiReview,
+    prefix = "/api",
+    endpoint = "proposals/",
+    input = PatchJson,
+    output = PatchJson,
+    client = ApiClient,
+)]
+pub async fn review(
+    #[server(flatten)] review_request: ReviewRequest,
+) -> Result<ProposalReviewResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<ProposalApiState, _>(&state).await?;
+    let PathProposalId { id } = extract_path().await?;
+    validate_notes(review_request.review_note.as_deref())?;
+
+    // Claiming the proposal first means two admins approving it at once
+    // only make its change once.
+    let proposal = api_state
+        .service
+        .claim(
+            id,
+            review_request.decision.status(),
+            review_request.review_note,
+        )
+        .await?;
+    let (institution_id, asset_id) = match review_request.decision {
+        ReviewDecision::Reject => (None, None),
+        ReviewDecision::Approve => {
+            match apply(&state, &api_state.authenticated_token, &proposal).await {
+                Ok(applied) => applied,
+                Err(e) => {
+                    api_state.service.release(id).await?;
+                    return Err(e);
+                }
+            }
+        }
+    };
+
+    let proposal = api_state
+        .service
+        .complete(id, institution_id, asset_id)
+        .await?;
+    Ok(proposal.into())
+}
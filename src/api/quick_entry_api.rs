@@ -0,0 +1,460 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::quick_entry::QuickEntryId,
+    schema::quick_entry::{
+        CreateRequest, DeleteResponse, ExecuteRequest, QuickEntryCreateResponse,
+        QuickEntryExecuteResponse, QuickEntryGetListResponse, QuickEntryGetResponse,
+        QuickEntryUpdateResponse, UpdateRequest,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json, PatchJson},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode,
+            asset_api::asset_scale,
+            extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+            transaction_api::{TransactionApiState, ensure_single_entry},
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        categorization::sanitize_category,
+        model::{
+            quick_entry::{QuickEntryCreate, QuickEntryFilter},
+            transaction::TransactionCreate,
+        },
+        schema::text::{QUICK_ENTRY_LABEL, TRANSACTION_DESCRIPTION},
+        service::{
+            quick_entry_service::QuickEntryServiceMethods,
+            quick_entry_service_factory::QuickEntryServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use chrono::Utc;
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathQuickEntryId {
+    id: QuickEntryId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `quick_entries` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct QuickEntryApiResource;
+
+    impl ApiResource for QuickEntryApiResource {
+        const NAME: &'static str = "quick_entries";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn QuickEntryServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            QuickEntryServiceFactory::build(
+                owner,
+                Arc::clone(&state.connection_pool),
+                permission_set,
+            )
+        }
+    }
+
+    pub type QuickEntryApiState = ResourceContext<QuickEntryApiResource>;
+
+    /// Checks a label isn't blank once sanitized.
+    pub fn sanitize_label(label: &str) -> Result<String, ApiError> {
+        let label = QUICK_ENTRY_LABEL.sanitize(label.trim())?;
+        if label.is_empty() {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "The quick entry label must not be empty.",
+            ));
+        }
+        Ok(label)
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            val if val.ends_with("/execute") => "/execute".to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/quick-entries{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct QuickEntryApi;
+
+    impl Api for QuickEntryApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
+                (Method::POST, "/{id}/execute"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler)
+                        .patch(server_fn_handler)
+                        .delete(server_fn_handler),
+                )
+                .route("/{id}/execute", axum::routing::post(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/quick-entries",
+    tag = "Quick Entries",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The quick entries of the user.", body = QuickEntryGetListResponse)
+    ),
+))]
+#[server(
+    name = QuickEntryApiGetList,
+    prefix = "/api",
+    endpoint = "/quick-entries",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<QuickEntryGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<QuickEntryApiState, _>(&state).await?;
+
+    let quick_entries = api_state
+        .service
+        .get_list(0, None, QuickEntryFilter::default())
+        .await?;
+    Ok(quick_entries.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/quick-entries/{id}",
+    tag = "Quick Entries",
+    params(QuickEntryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The quick entry.", body = QuickEntryGetResponse),
+        (status = 404, description = "The quick entry was not found."),
+    ),
+))]
+#[server(
+    name = QuickEntryApiGet,
+    prefix = "/api",
+    endpoint = "quick-entries/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<QuickEntryGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<QuickEntryApiState, _>(&state).await?;
+    let PathQuickEntryId { id } = extract_path().await?;
+
+    let quick_entry = api_state.service.get(id).await?;
+    Ok(quick_entry.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/quick-entries",
+    tag = "Quick Entries",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The newly created quick entry.", body = QuickEntryCreateResponse),
+        (status = 400, description = "The label, quantity, description or category is invalid."),
+        (status = 404, description = "The account or the asset was not found."),
+    ),
+))]
+#[server(
+    name = QuickEntryApiCreate,
+    prefix = "/api",
+    endpoint = "quick-entries",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<QuickEntryCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<QuickEntryApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+
+    let label = sanitize_label(&create_request.label)?;
+    let description = TRANSACTION_DESCRIPTION.sanitize_option(create_request.description)?;
+    let category = create_request
+        .category
+        .as_deref()
+        .map(sanitize_category)
+        .transpose()?;
+    let scale = asset_scale(&state, create_request.asset_id).await?;
+
+    let quick_entry = api_state
+        .service
+        .create(QuickEntryCreate {
+            user_id: registered_user.id(),
+            label,
+            account_id: create_request.account_id,
+            asset_id: create_request.asset_id,
+            quantity: create_request.quantity.in_asset(scale)?,
+            description,
+            category,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(QuickEntryCreateResponse::status());
+    provide_context(response_opts);
+    Ok(quick_entry.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/quick-entries/{id}",
+    tag = "Quick Entries",
+    params(QuickEntryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = UpdateRequest,
+    responses(
+        (status = 200, description = "The updated quick entry.", body = QuickEntryUpdateResponse),
+        (status = 400, description = "The label, quantity, description or category is invalid."),
+        (status = 404, description = "The quick entry, the account or the asset was not found."),
+    ),
+))]
+#[server(
+    name = QuickEntryApiUpdate,
+    prefix = "/api",
+    endpoint = "quick-entries/",
+    input = PatchJson,
+    output = PatchJson,
+    client = ApiClient,
+)]
+pub async fn update(
+    #[server(flatten)] update_request: UpdateRequest,
+) -> Result<QuickEntryUpdateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<QuickEntryApiState, _>(&state).await?;
+    let PathQuickEntryId { id } = extract_path().await?;
+
+    let mut quick_entry = api_state.service.get(id).await?;
+    if let Some(label) = update_request.label {
+        quick_entry.label = sanitize_label(&label)?;
+    }
+    if let Some(account_id) = update_request.account_id {
+        quick_entry.account_id = Some(account_id);
+    }
+    // The quantity is read in the asset the entry is left in, and one that
+    // isn't given is carried over when the asset changes.
+    let asset_id = update_request.asset_id.unwrap_or(quick_entry.asset_id);
+    if let Some(quantity) = update_request.quantity {
+        quick_entry.quantity = quantity.in_asset(asset_scale(&state, asset_id).await?)?;
+    } else if asset_id != quick_entry.asset_id {
+        asset_scale(&state, asset_id).await?;
+    }
+    quick_entry.asset_id = asset_id;
+    if let Some(description) = update_request.description {
+        quick_entry.description = TRANSACTION_DESCRIPTION.sanitize_option(Some(description))?;
+    }
+    if let Some(category) = update_request.category {
+        quick_entry.category = Some(sanitize_category(&category)?);
+    }
+
+    let quick_entry = api_state.service.update(id, quick_entry).await?;
+    Ok(quick_entry.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/quick-entries/{id}",
+    tag = "Quick Entries",
+    params(QuickEntryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The quick entry was successfully deleted, leaving the transactions it created."),
+        (status = 404, description = "The quick entry was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = QuickEntryApiDelete,
+    prefix = "/api",
+    endpoint = "quick-entries/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<QuickEntryApiState, _>(&state).await?;
+    let PathQuickEntryId { id } = extract_path().await?;
+
+    api_state.service.delete(id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/quick-entries/{id}/execute",
+    tag = "Quick Entries",
+    params(QuickEntryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = ExecuteRequest,
+    responses(
+        (status = 201, description = "The transaction created from the quick entry.", body = QuickEntryExecuteResponse),
+        (status = 400, description = "The account of the entry was deleted, or the user keeps their books in double entry.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4000,
+            message: "The account of the quick entry no longer exists, point it at another account.".to_string()
+        })),
+        (status = 404, description = "The quick entry was not found, or its account no longer belongs to the user."),
+    ),
+))]
+#[server(
+    name = QuickEntryApiExecute,
+    prefix = "/api",
+    endpoint = "quick-entries/execute",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn execute(
+    #[server(flatten)]
+    #[server(default)]
+    execute_request: ExecuteRequest,
+) -> Result<QuickEntryExecuteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let quick_entry_api_state = extract_with_state::<QuickEntryApiState, _>(&state).await?;
+    let PathQuickEntryId { id } = extract_path().await?;
+
+    let quick_entry = quick_entry_api_state.service.executable(id).await?;
+    let Some(account_id) = quick_entry.account_id else {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            "The account of the quick entry no longer exists, point it at another account.",
+        ));
+    };
+    ensure_single_entry(&state).await?;
+
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let transaction = api_state
+        .service
+        .create(TransactionCreate {
+            account_id,
+            asset_id: quick_entry.asset_id,
+            description: quick_entry.description,
+            posted_at: execute_request.posted_at.unwrap_or_else(Utc::now),
+            quantity: quick_entry.quantity,
+            notes: None,
+            external_id: None,
+            category: quick_entry.category,
+            applied_rule_id: None,
+            journal_entry_id: None,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(QuickEntryExecuteResponse::status());
+    provide_context(response_opts);
+    Ok(transaction.into())
+}
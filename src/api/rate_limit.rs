@@ -0,0 +1,260 @@
+//! Limits how many API requests each client makes in a window of time.
+//!
+//! The [`rate_limit`] middleware counts the request against the window of
+//! its client and leaves the [`RateLimit`] it arrived at in the extensions
+//! of the response, where [`set_rate_limit_headers`] writes it out. A
+//! client over its limit gets an [`ApiError::RateLimited`] instead, which
+//! carries the same numbers.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http::{HeaderMap, HeaderValue, header::AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    api::{ApiError, AppState},
+    authentication::{
+        api_key::{api_key_secret, resolve_api_key},
+        authenticated_token::AuthenticatedToken,
+        authenticator::Authenticator,
+        client_address::ClientAddress,
+    },
+    config::RateLimitConfig,
+};
+
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "X-RateLimit-Limit";
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "X-RateLimit-Remaining";
+pub const RATE_LIMIT_RESET_HEADER: &str = "X-RateLimit-Reset";
+
+/// How many windows are kept before the ones that ended are dropped.
+const MAX_WINDOWS: usize = 10_000;
+
+/// Where a client stands against its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RateLimit {
+    /// How many requests the client may make in a window
+    pub limit: u32,
+    /// How many requests the client has left in the current window
+    pub remaining: u32,
+    /// How many seconds are left until the window starts over
+    pub reset: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    started_at: Instant,
+    count: u32,
+}
+
+/// Counts the requests of each client in fixed windows.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    windows: Arc<Mutex<HashMap<String, Window>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            windows: Arc::default(),
+        }
+    }
+
+    /// The limit every client is held to.
+    pub fn config(&self) -> RateLimitConfig {
+        self.config
+    }
+
+    /// Who a request counts against. Authenticated requests count against
+    /// their API key or their user, however many tokens the user has, and
+    /// others against the address of their client, so that made up
+    /// credentials don't get a window of their own.
+    pub fn key(token: Option<&AuthenticatedToken>, client_ip: Option<IpAddr>) -> String {
+        match token {
+            Some(token) => match token.api_key_scope() {
+                Some(scope) => format!("key:{}", scope.id),
+                None => format!("user:{}:{}", token.iss(), token.sub()),
+            },
+            None => client_ip.map(|ip| format!("ip:{ip}")).unwrap_or_default(),
+        }
+    }
+
+    /// Counts a request of `key` made at `now`. Fails with where the client
+    /// stands when it has no requests left in the window.
+    pub fn check(&self, key: &str, now: Instant) -> Result<RateLimit, RateLimit> {
+        let RateLimitConfig { limit, window } = self.config;
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() >= MAX_WINDOWS {
+            windows.retain(|_, w| now.duration_since(w.started_at) < window);
+        }
+        let current = windows
+            .entry(key.to_owned())
+            .and_modify(|w| {
+                if now.duration_since(w.started_at) >= window {
+                    *w = Window {
+                        started_at: now,
+                        count: 0,
+                    };
+                }
+            })
+            .or_insert(Window {
+                started_at: now,
+                count: 0,
+            });
+        let reset = window
+            .saturating_sub(now.duration_since(current.started_at))
+            .as_secs_f64()
+            .ceil() as u64;
+        if current.count >= limit {
+            return Err(RateLimit {
+                limit,
+                remaining: 0,
+                reset,
+            });
+        }
+        current.count += 1;
+        Ok(RateLimit {
+            limit,
+            remaining: limit - current.count,
+            reset,
+        })
+    }
+}
+
+/// Authenticates a request to find who it counts against, without turning
+/// it away when it isn't.
+async fn identify(state: &AppState, headers: &HeaderMap) -> Option<AuthenticatedToken> {
+    if let Some(secret) = api_key_secret(headers) {
+        return resolve_api_key(state, secret).await.ok();
+    }
+    let authorization = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    Authenticator::authenticate(authorization).await.ok()
+}
+
+/// Counts API requests against the limit of their client, turning away
+/// those over it.
+///
+/// The request is authenticated here already, and the token left in its
+/// extensions for the routes to use rather than authenticating it again.
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !request.uri().path().starts_with("/api/") {
+        return next.run(request).await;
+    }
+    let token = identify(&state, request.headers()).await;
+    let key = RateLimiter::key(
+        token.as_ref(),
+        ClientAddress::from_env().client_or_peer_ip(request.headers(), request.extensions()),
+    );
+    if let Some(token) = token {
+        request.extensions_mut().insert(token);
+    }
+    match state.rate_limiter.check(&key, Instant::now()) {
+        Ok(rate_limit) => {
+            let mut response = next.run(request).await;
+            response.extensions_mut().insert(rate_limit);
+            response
+        }
+        Err(rate_limit) => ApiError::RateLimited(rate_limit).into_response(),
+    }
+}
+
+/// Writes the [`RateLimit`] left in the extensions of a response as its
+/// `X-RateLimit-*` headers.
+pub async fn set_rate_limit_headers(mut response: Response) -> Response {
+    if let Some(rate_limit) = response.extensions().get::<RateLimit>().copied() {
+        let headers = response.headers_mut();
+        headers.insert(RATE_LIMIT_LIMIT_HEADER, HeaderValue::from(rate_limit.limit));
+        headers.insert(
+            RATE_LIMIT_REMAINING_HEADER,
+            HeaderValue::from(rate_limit.remaining),
+        );
+        headers.insert(RATE_LIMIT_RESET_HEADER, HeaderValue::from(rate_limit.reset));
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::authorization::group::Group;
+
+    fn limiter(limit: u32) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            limit,
+            window: Duration::from_secs(10),
+        })
+    }
+
+    #[test]
+    fn it_counts_down_within_a_window() {
+        let limiter = limiter(2);
+        let now = Instant::now();
+        assert_eq!(
+            limiter.check("a", now),
+            Ok(RateLimit {
+                limit: 2,
+                remaining: 1,
+                reset: 10
+            })
+        );
+        assert_eq!(
+            limiter.check("a", now + Duration::from_millis(2_500)),
+            Ok(RateLimit {
+                limit: 2,
+                remaining: 0,
+                reset: 8
+            })
+        );
+        assert_eq!(
+            limiter.check("a", now + Duration::from_secs(3)),
+            Err(RateLimit {
+                limit: 2,
+                remaining: 0,
+                reset: 7
+            })
+        );
+        assert_eq!(limiter.check("b", now).map(|x| x.remaining), Ok(1));
+    }
+
+    #[test]
+    fn it_starts_over_after_the_window() {
+        let limiter = limiter(1);
+        let now = Instant::now();
+        assert!(limiter.check("a", now).is_ok());
+        assert!(limiter.check("a", now + Duration::from_secs(9)).is_err());
+        assert_eq!(
+            limiter.check("a", now + Duration::from_secs(10)),
+            Ok(RateLimit {
+                limit: 1,
+                remaining: 0,
+                reset: 10
+            })
+        );
+    }
+
+    #[test]
+    fn it_keys_anonymous_requests_by_their_address() {
+        let ip = "203.0.113.7".parse().unwrap();
+        assert_eq!(RateLimiter::key(None, Some(ip)), "ip:203.0.113.7");
+        assert_eq!(RateLimiter::key(None, None), "");
+
+        let token = AuthenticatedToken::for_group(Group::User);
+        assert_eq!(RateLimiter::key(Some(&token), Some(ip)), "user::");
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::{
+        DateRange,
+        report::{ReportTrialBalanceResponse, TrialBalanceRequest},
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{GetUrl, Json},
+};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, extract_with_state, server_fn_uri,
+            set_user_groups, transaction_api::TransactionApiState,
+        },
+        authentication::{api_key::authenticate_api_key, authenticator::Authenticator},
+        service::transaction_service::TransactionServiceJournal,
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use chrono::{TimeDelta, Utc};
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = req.uri().to_string();
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/reports{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct ReportApi;
+
+    impl Api for ReportApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::GET, "/trial-balance")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/trial-balance", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(from_fn_with_state(state.clone(), authenticate_api_key))
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[allow(unused_variables)]
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/reports/trial-balance",
+    tag = "Reports",
+    params(TrialBalanceRequest, DateRange),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The debits and credits of each account the caller can read, from the transactions posted in the date range, or up to `as_of`.", body = ReportTrialBalanceResponse),
+        (status = 400, description = "The date range is invalid, or ends as well as `as_of`.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4001,
+            message: "The date range ends before it starts.".to_string()
+        })),
+    )
+))]
+#[server(
+    name = ReportApiTrialBalance,
+    prefix = "/api",
+    endpoint = "/reports/trial-balance",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn trial_balance(
+    #[server(flatten)]
+    #[server(default)]
+    request: TrialBalanceRequest,
+    #[server(flatten)]
+    #[server(default)]
+    range: DateRange,
+) -> Result<ReportTrialBalanceResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+
+    let now = Utc::now();
+    let posted = range.resolve(now)?;
+    if posted.to.is_some() && request.as_of.is_some() {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            "Use either the `to` of a date range or the deprecated `as_of`, not both.",
+        ));
+    }
+    // The end of a range is excluded, and the database keeps times to the
+    // microsecond, so the last time in the range is just before it.
+    let as_of = posted
+        .to
+        .map(|x| x - TimeDelta::microseconds(1))
+        .or(request.as_of)
+        .unwrap_or(now);
+    let lines = api_state.service.trial_balance(posted.from, as_of).await?;
+    Ok(ReportTrialBalanceResponse::new(posted.from, as_of, lines))
+}
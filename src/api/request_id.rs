@@ -0,0 +1,103 @@
+//! Tells the requests apart in the logs and in the errors clients report.
+//!
+//! [`set_request_id`] gives each request an id, the one of its
+//! `X-Request-Id` header if a proxy in front of the server already set a
+//! sensible one, and answers with it in the same header. The handlers of
+//! the request read it with [`RequestId::current`].
+
+use std::fmt;
+
+use axum::{extract::Request, middleware::Next, response::Response};
+use http::HeaderValue;
+use uuid::Uuid;
+
+/// The header carrying the id of a request, both ways.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// The longest id taken from a request, past which one is made up.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+tokio::task_local! {
+    /// The id of the request being handled.
+    pub static REQUEST_ID: RequestId;
+}
+
+impl RequestId {
+    /// The id of the request being handled, if in one.
+    pub fn current() -> Option<Self> {
+        REQUEST_ID.try_with(Clone::clone).ok()
+    }
+
+    /// The id a request carries, if it is short and printable enough to
+    /// be logged as it is.
+    pub fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?;
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value
+                .bytes()
+                .all(|x| x.is_ascii_alphanumeric() || b"-_.:".contains(&x));
+        valid.then(|| Self(value.to_owned()))
+    }
+
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Gives the request an id, which the response carries back.
+pub async fn set_request_id(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(RequestId::from_header)
+        .unwrap_or_default();
+    let header = HeaderValue::from_str(request_id.as_str()).ok();
+    let mut response = REQUEST_ID.scope(request_id, next.run(request)).await;
+    if let Some(header) = header {
+        response.headers_mut().insert(REQUEST_ID_HEADER, header);
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_only_keeps_ids_that_are_safe_to_log() {
+        let id = |x: &'static str| RequestId::from_header(&HeaderValue::from_static(x));
+        assert_eq!(
+            id("0b6f6a4e-2d1c-4f3a-9e7a-1c2b3d4e5f60").map(|x| x.to_string()),
+            Some("0b6f6a4e-2d1c-4f3a-9e7a-1c2b3d4e5f60".to_owned())
+        );
+        assert_eq!(
+            id("trace:1.2_3").map(|x| x.to_string()),
+            Some("trace:1.2_3".to_owned())
+        );
+        assert_eq!(id(""), None);
+        assert_eq!(id("two words"), None);
+        assert_eq!(id("a\"quote"), None);
+        assert_eq!(id("a".repeat(MAX_REQUEST_ID_LEN + 1).leak()), None);
+        assert_ne!(RequestId::new(), RequestId::new());
+    }
+}
@@ -0,0 +1,134 @@
+//! The state the handlers of a resource act with.
+//!
+//! Every API resolves the caller's token, the user their resource belongs
+//! to and their [`PermissionSet`] on it before building its service. An
+//! [`ApiResource`] says what those are for a resource, and
+//! [`ResourceContext`] extracts them in that order, so a new resource only
+//! names itself:
+//!
+//! ```
+//! use treasury::{
+//!     api::{AppState, resource_context::{ApiResource, ResourceContext}},
+//!     authentication::registered_user::RegisteredUser,
+//!     authorization::{PermissionConfig, PermissionSet, actions::*},
+//! };
+//!
+//! pub struct GoalApiResource;
+//!
+//! impl ApiResource for GoalApiResource {
+//!     const NAME: &'static str = "goals";
+//!     const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+//!         min_read_level: ReadLevel::Read,
+//!         min_create_level: CreateLevel::Create,
+//!         min_update_level: UpdateLevel::Update,
+//!         min_delete_level: DeleteLevel::Delete,
+//!     };
+//!     type Owner = RegisteredUser;
+//!     type Service = (RegisteredUser, PermissionSet);
+//!
+//!     fn service(_: &AppState, user: RegisteredUser, set: PermissionSet) -> Self::Service {
+//!         (user, set)
+//!     }
+//! }
+//!
+//! pub type GoalApiState = ResourceContext<GoalApiResource>;
+//! ```
+
+use axum::{RequestPartsExt, extract::FromRequestParts};
+use http::request::Parts;
+use tracing::error;
+
+use crate::{
+    api::{ApiError, AppState},
+    authentication::{authenticated_token::AuthenticatedToken, registered_user::RegisteredUser},
+    authorization::{PermissionConfig, PermissionSet},
+};
+
+/// A resource of the API, and how its service is built for a caller.
+pub trait ApiResource: Send + Sync + 'static {
+    /// The name of the resource in the policies
+    const NAME: &'static str;
+    /// The levels of the resource the API resolves for a caller
+    const PERMISSION_CONFIG: PermissionConfig;
+    /// Who the service acts for
+    type Owner: ResourceOwner;
+    type Service: Send;
+
+    fn service(
+        state: &AppState,
+        owner: Self::Owner,
+        permission_set: PermissionSet,
+    ) -> Self::Service;
+}
+
+/// Who a service acts for: the [`RegisteredUser`] making the request for
+/// resources users own, `Option<RegisteredUser>` for resources callers may
+/// act on before they register, and `()` for shared resources.
+pub trait ResourceOwner: Sized + Send {
+    fn extract(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> impl Future<Output = Result<Self, ApiError>> + Send;
+}
+
+impl ResourceOwner for () {
+    async fn extract(_: &mut Parts, _: &AppState) -> Result<Self, ApiError> {
+        Ok(())
+    }
+}
+
+impl ResourceOwner for RegisteredUser {
+    async fn extract(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        parts.extract_with_state::<RegisteredUser, _>(state).await
+    }
+}
+
+impl ResourceOwner for Option<RegisteredUser> {
+    async fn extract(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        parts
+            .extract_with_state::<Option<RegisteredUser>, _>(state)
+            .await
+    }
+}
+
+/// The caller of a request on `R`, with their permissions on it and its
+/// service.
+pub struct ResourceContext<R: ApiResource> {
+    pub authenticated_token: AuthenticatedToken,
+    pub permission_set: PermissionSet,
+    pub service: R::Service,
+}
+
+impl<R: ApiResource> FromRequestParts<AppState> for ResourceContext<R> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let authenticated_token = parts
+            .extract_with_state::<AuthenticatedToken, _>(state)
+            .await?;
+
+        let owner = R::Owner::extract(parts, state).await?;
+
+        let permission_set = PermissionSet::new(
+            R::NAME,
+            &state.enforcer,
+            &authenticated_token,
+            R::PERMISSION_CONFIG,
+        )
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?;
+
+        let service = R::service(state, owner, permission_set);
+
+        Ok(Self {
+            authenticated_token,
+            permission_set,
+            service,
+        })
+    }
+}
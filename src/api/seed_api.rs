@@ -0,0 +1,159 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::seed::{SeedRequest, SeedResponse},
+};
+use leptos::{server, server_fn::codec::Json};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, AppState, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::authenticator::Authenticator,
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        seed::{SeedOptions, is_development, seed},
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `seed` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::ReadAll,
+        min_create_level: CreateLevel::CreateAll,
+        min_update_level: UpdateLevel::UpdateAll,
+        min_delete_level: DeleteLevel::DeleteAll,
+    };
+
+    pub struct SeedApiResource;
+
+    impl ApiResource for SeedApiResource {
+        const NAME: &'static str = "seed";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = ();
+        type Service = ();
+
+        fn service(_: &AppState, _: (), _: PermissionSet) {}
+    }
+
+    /// The permissions of the caller on seeding. A seed file creates users
+    /// and shared data, so loading one takes the `_all` levels.
+    pub type SeedApiState = ResourceContext<SeedApiResource>;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = req.uri().to_string();
+        let path = path.trim_start_matches('/');
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/seed{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct SeedApi;
+
+    impl Api for SeedApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::POST, "/")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::post(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/seed",
+    tag = "Seed",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = SeedRequest,
+    responses(
+        (status = 200, description = "How many rows loading the seed file created.", body = SeedResponse),
+        (status = 400, description = "The seed file is invalid."),
+        (status = 403, description = "The caller may not seed the database."),
+        (status = 404, description = "The deployment is not a development one."),
+    ),
+))]
+#[server(
+    name = SeedApiSeed,
+    prefix = "/api",
+    endpoint = "seed",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] seed_request: SeedRequest,
+) -> Result<SeedResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<SeedApiState, _>(&state).await?;
+    if !is_development() {
+        return Err(ApiError::NotFound);
+    }
+    if api_state.permission_set.create_level != CreateLevel::CreateAll {
+        return Err(ApiError::Forbidden);
+    }
+
+    let report = seed(
+        &state.connection_pool,
+        seed_request.file,
+        SeedOptions {
+            seed: seed_request.seed,
+            wipe: seed_request.wipe,
+        },
+    )
+    .await?;
+    Ok(report)
+}
@@ -0,0 +1,46 @@
+//! Tells clients the time of the server.
+//!
+//! Every response carries an `X-Server-Time` header written like `Date`,
+//! so a client with a skewed clock can tell how far it is off and schedule
+//! the refresh of its token by the server's clock rather than its own.
+
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use http::HeaderValue;
+
+use crate::api::versioning::http_date;
+
+pub const SERVER_TIME_HEADER: &str = "X-Server-Time";
+
+/// The `X-Server-Time` header of a response sent at `now`.
+pub fn server_time_header(now: DateTime<Utc>) -> HeaderValue {
+    HeaderValue::from_str(&http_date(now)).expect("Invalid X-Server-Time header.")
+}
+
+/// Stamps the response with the time it was sent at.
+pub async fn set_server_time(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .insert(SERVER_TIME_HEADER, server_time_header(Utc::now()));
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn it_writes_the_time_like_a_date_header() {
+        let now = Utc.with_ymd_and_hms(2025, 5, 4, 9, 30, 5).unwrap();
+        let header = server_time_header(now);
+        assert_eq!(header, "Sun, 04 May 2025 09:30:05 GMT");
+        assert_eq!(
+            DateTime::parse_from_rfc2822(header.to_str().unwrap())
+                .unwrap()
+                .to_utc(),
+            now
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::{user::UserId, user_session::UserSessionId},
+    schema::user_session::{
+        RevokeOthersResponse, UserSessionDeleteResponse, UserSessionGetListResponse,
+        UserSessionResponse,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, extract_path, extract_with_state,
+            passkey_api::path_user,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{
+            api_key::hash_secret, authenticator::Authenticator, registered_user::RegisteredUser,
+        },
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        model::user_session::UserSessionFilter,
+        service::{
+            user_session_service::UserSessionServiceMethods,
+            user_session_service_factory::UserSessionServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::{HeaderMap, Method};
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, extract, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathUserSessionId {
+    session_id: UserSessionId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `sessions` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct SessionApiResource;
+
+    impl ApiResource for SessionApiResource {
+        const NAME: &'static str = "sessions";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn UserSessionServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            UserSessionServiceFactory::build(
+                owner,
+                Arc::clone(&state.connection_pool),
+                permission_set,
+            )
+        }
+    }
+
+    pub type SessionApiState = ResourceContext<SessionApiResource>;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        // The user and session ids are recovered with `extract` inside each
+        // server fn.
+        let path = match req.uri().path() {
+            "/" => "",
+            "/revoke-others" => "/revoke-others",
+            _ => "/",
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/users/sessions{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    /// The digest of the refresh token the request presented, which
+    /// identifies the session it was made from.
+    pub async fn current_token_hash() -> Result<Option<Vec<u8>>, ApiError> {
+        let headers = extract::<HeaderMap>().await?;
+        let refresh_token = expect_context::<AppState>()
+            .header_refresh
+            .presented_refresh_token(&headers)?;
+        Ok(refresh_token.as_deref().map(hash_secret))
+    }
+
+    pub struct SessionApi;
+
+    impl Api for SessionApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/revoke-others"),
+                (Method::DELETE, "/{session_id}"),
+            ]
+        }
+
+        // API keys can't manage sessions, so only OIDC tokens are accepted.
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::get(server_fn_handler))
+                .route("/revoke-others", axum::routing::post(server_fn_handler))
+                .route("/{session_id}", axum::routing::delete(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/users/{id}/sessions",
+    tag = "Sessions",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The devices the user is signed in on.", body = UserSessionGetListResponse),
+        (status = 404, description = "The user was not found."),
+    ),
+))]
+#[server(
+    name = SessionApiGetList,
+    prefix = "/api",
+    endpoint = "users/sessions",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<UserSessionGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    path_user(&state).await?;
+    let api_state = extract_with_state::<SessionApiState, _>(&state).await?;
+    let current_hash = current_token_hash().await?;
+
+    let sessions = api_state
+        .service
+        .get_list(0, None, UserSessionFilter::default())
+        .await?;
+    Ok(UserSessionGetListResponse {
+        sessions: sessions
+            .into_iter()
+            .map(|x| UserSessionResponse::new(x, current_hash.as_deref()))
+            .collect(),
+    })
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/users/{id}/sessions/{session_id}",
+    tag = "Sessions",
+    params(UserId, UserSessionId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The session was revoked, so its next refresh fails."),
+        (status = 404, description = "The session was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = SessionApiDelete,
+    prefix = "/api",
+    endpoint = "users/sessions/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<UserSessionDeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    path_user(&state).await?;
+    let api_state = extract_with_state::<SessionApiState, _>(&state).await?;
+    let PathUserSessionId { session_id } = extract_path().await?;
+
+    api_state.service.delete(session_id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(UserSessionDeleteResponse::status());
+    provide_context(response_opts);
+    Ok(UserSessionDeleteResponse {})
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/users/{id}/sessions/revoke-others",
+    tag = "Sessions",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The sessions other than the current one were revoked.", body = RevokeOthersResponse),
+        (status = 400, description = "The request presented no refresh token of a session."),
+        (status = 404, description = "The user was not found."),
+    ),
+))]
+#[server(
+    name = SessionApiRevokeOthers,
+    prefix = "/api",
+    endpoint = "users/sessions/revoke-others",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn revoke_others() -> Result<RevokeOthersResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    path_user(&state).await?;
+    let api_state = extract_with_state::<SessionApiState, _>(&state).await?;
+
+    let revoked = api_state
+        .service
+        .revoke_others(current_token_hash().await?)
+        .await?;
+    Ok(RevokeOthersResponse {
+        revoked: revoked.len() as u64,
+    })
+}
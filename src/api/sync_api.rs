@@ -0,0 +1,182 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::sync::{SyncRequest, SyncResponse},
+};
+use leptos::{
+    server,
+    server_fn::codec::{GetUrl, Json},
+};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, AppState, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        model::cursor_key::CursorKey,
+        schema::{open_token, sync::SyncToken},
+        service::{sync_service::SyncServiceMethods, sync_service_factory::SyncServiceFactory},
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// The most changes one sync returns.
+pub const MAX_SYNC_ITEMS: i64 = 500;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `sync` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct SyncApiResource;
+
+    impl ApiResource for SyncApiResource {
+        const NAME: &'static str = "sync";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn SyncServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            SyncServiceFactory::build(owner, Arc::clone(&state.connection_pool), permission_set)
+        }
+    }
+
+    pub type SyncApiState = ResourceContext<SyncApiResource>;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/sync{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct SyncApi;
+
+    impl Api for SyncApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::GET, "/")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::get(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/sync",
+    tag = "Sync",
+    params(SyncRequest),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The accounts and transactions changed since the token.", body = SyncResponse),
+        (status = 400, description = "The token is invalid."),
+    ),
+))]
+#[server(
+    name = SyncApiGet,
+    prefix = "/api",
+    endpoint = "/sync",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get(
+    #[server(flatten)]
+    #[server(default)]
+    sync_request: SyncRequest,
+) -> Result<SyncResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<SyncApiState, _>(&state).await?;
+    let cursor_key = extract_with_state::<CursorKey, _>(&state).await?;
+
+    let token = match sync_request.since {
+        Some(since) => open_token::<SyncToken>(&state, &since).await?,
+        None => SyncToken::default(),
+    };
+    let limit = sync_request
+        .max_items
+        .unwrap_or(MAX_SYNC_ITEMS)
+        .clamp(1, MAX_SYNC_ITEMS);
+    let changes = api_state.service.changes(token, limit).await?;
+
+    let next_since = cursor_key.encrypt_base64(token.advance(
+        &changes.accounts,
+        &changes.transactions,
+        &changes.tombstones,
+    ))?;
+    Ok(SyncResponse {
+        accounts: changes.accounts.into_iter().map(|x| x.into()).collect(),
+        transactions: changes.transactions.into_iter().map(|x| x.into()).collect(),
+        deleted: changes.tombstones.into_iter().map(|x| x.into()).collect(),
+        next_since,
+        has_more: changes.has_more,
+    })
+}
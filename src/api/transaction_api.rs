@@ -1,11 +1,19 @@
 use crate::{
     api::{ApiError, client::ApiClient},
-    model::transaction::TransactionId,
+    model::{
+        recategorization::RecategorizationId, transaction::TransactionId,
+        transaction_history::TransactionHistoryId,
+    },
     schema::{
-        Pagination,
+        DateRange, Pagination,
+        notes::NotesHtmlResponse,
         transaction::{
-            CreateRequest, DeleteResponse, GetListRequest, TransactionCreateResponse,
-            TransactionGetListResponse, TransactionGetResponse, TransactionUpdateResponse,
+            CategorizeRequest, CreateRequest, DeleteResponse, GetListRequest,
+            HistoryGetListResponse, ImportPreviewResponse, ImportRequest, ImportResponse,
+            RecategorizeRequest, TransactionCategorizeResponse, TransactionCreateResponse,
+            TransactionGetListResponse, TransactionGetResponse,
+            TransactionRecategorizationResponse, TransactionRecategorizeResponse,
+            TransactionUncategorizedResponse, TransactionUpdateResponse, UncategorizedRequest,
             UpdateRequest,
         },
     },
@@ -19,32 +27,66 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
-        api::{Api, ApiErrorResponse, AppState, extract_with_state, set_user_groups},
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode,
+            asset_api::asset_scale,
+            extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
         authentication::{
-            authenticated_token::AuthenticatedToken, authenticator::Authenticator,
+            api_key::authenticate_api_key, authenticator::Authenticator,
             registered_user::RegisteredUser,
         },
         authorization::{
             PermissionConfig, PermissionSet,
             actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
         },
-        model::cursor_key::CursorKey,
+        categorization::{RECATEGORIZE_BATCH_SIZE, compile_pattern, sanitize_category},
+        config::PagedResource,
+        import::{ImportError, ImportedRow, MAX_IMPORT_ROWS, PREVIEW_ROWS},
+        model::{
+            account::{AccountFilter, AccountId},
+            categorization_rule::{CategorizationRuleCreate, CategorizationRuleField},
+            cursor_key::CursorKey,
+            import_profile::ImportMapping,
+            recategorization::RecategorizationCreate,
+            transaction::TransactionCreate,
+        },
+        resource::{
+            GetListRepository, GetRepository, MAX_LIMIT, account_repository::AccountRepository,
+            deadline, import_profile_repository::ImportProfileRepository,
+            user_preference_repository::UserPreferenceRepository,
+        },
+        rounding::RoundingPolicy,
+        schema::{
+            DryRunRequest, Quantity,
+            notes::validate_notes,
+            text::TRANSACTION_DESCRIPTION,
+            transaction::{DEFAULT_UNCATEGORIZED, ImportPreviewRow},
+        },
+        service::ServiceError,
         service::{
-            transaction_service::TransactionServiceMethods,
+            transaction_service::{
+                TransactionServiceCategorize, TransactionServiceConvert,
+                TransactionServiceCreateMany, TransactionServiceHistory, TransactionServiceMethods,
+                TransactionServiceUncategorized,
+            },
             transaction_service_factory::TransactionServiceFactory,
         },
     };
     pub use axum::{
-        RequestPartsExt, Router,
+        Router,
         body::Body,
-        extract::{FromRequestParts, Path, Request, State},
+        extract::{Query, Request, State},
         middleware::from_fn_with_state,
         response::IntoResponse,
     };
-    pub use http::request::Parts;
+    pub use chrono::Utc;
+    pub use http::Method;
     pub use leptos::prelude::*;
     pub use leptos_axum::{
-        ResponseOptions, extract, generate_request_and_parts, handle_server_fns_with_context,
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
     };
     pub use std::sync::Arc;
     pub use tower::ServiceBuilder;
@@ -60,53 +102,137 @@ pub struct PathTransactionId {
     id: TransactionId,
 }
 
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathTransactionHistoryId {
+    id: TransactionId,
+    history_id: TransactionHistoryId,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathRecategorizationId {
+    id: RecategorizationId,
+}
+
 #[cfg(feature = "ssr")]
 mod ssr {
     use super::*;
-    pub struct TransactionApiState {
-        pub authenticated_token: AuthenticatedToken,
-        pub transaction_service: Box<dyn TransactionServiceMethods + Send>,
-    }
 
-    impl FromRequestParts<AppState> for TransactionApiState {
-        type Rejection = ApiError;
+    /// The levels of `transactions` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct TransactionApiResource;
 
-        async fn from_request_parts(
-            parts: &mut Parts,
+    impl ApiResource for TransactionApiResource {
+        const NAME: &'static str = "transactions";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn TransactionServiceMethods + Send>;
+
+        fn service(
             state: &AppState,
-        ) -> Result<Self, Self::Rejection> {
-            let authenticated_token = parts
-                .extract_with_state::<AuthenticatedToken, _>(state)
-                .await?;
-
-            let registered_user = parts.extract_with_state::<RegisteredUser, _>(state).await?;
-
-            let permission_set = PermissionSet::new(
-                "transactions",
-                &state.enforcer,
-                &authenticated_token,
-                PermissionConfig {
-                    min_read_level: ReadLevel::Read,
-                    min_create_level: CreateLevel::Create,
-                    min_update_level: UpdateLevel::Update,
-                    min_delete_level: DeleteLevel::Delete,
-                },
-            )
-            .map_err(|e| {
-                error!("{e}");
-                ApiError::ServerError
-            })?;
-            let transaction_service = TransactionServiceFactory::build(
-                registered_user,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            TransactionServiceFactory::build(
+                owner,
                 Arc::clone(&state.connection_pool),
                 permission_set,
-            );
+                state.features.clone(),
+                state.events.clone(),
+            )
+        }
+    }
 
-            Ok(Self {
-                authenticated_token,
-                transaction_service,
-            })
+    pub type TransactionApiState = ResourceContext<TransactionApiResource>;
+
+    /// Refuses to create transactions one at a time for users in
+    /// double-entry mode, whose transactions have to come in balanced
+    /// journal entries.
+    pub async fn ensure_single_entry(state: &AppState) -> Result<(), ApiError> {
+        let registered_user = extract_with_state::<RegisteredUser, _>(state).await?;
+        let user_preference = UserPreferenceRepository
+            .get_by_user_id(
+                deadline::begin(&state.connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                registered_user.id(),
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        if user_preference.is_some_and(|x| x.double_entry) {
+            return Err(ServiceError::DoubleEntry.into());
         }
+        Ok(())
+    }
+
+    /// Resolves the mapping and account of an import, from its profile or
+    /// inline.
+    pub async fn import_mapping(
+        state: &AppState,
+        import_request: &ImportRequest,
+    ) -> Result<(ImportMapping, AccountId), ApiError> {
+        let registered_user = extract_with_state::<RegisteredUser, _>(state).await?;
+        let (mapping, default_account_id) =
+            match (import_request.profile_id, &import_request.mapping) {
+                (Some(profile_id), None) => {
+                    let import_profile = ImportProfileRepository
+                        .get(
+                            deadline::begin(&state.connection_pool)
+                                .await
+                                .map_err(ServiceError::from)?,
+                            profile_id,
+                        )
+                        .await
+                        .map_err(ServiceError::from)?;
+                    if import_profile.user_id != registered_user.id() {
+                        return Err(ApiError::NotFound);
+                    }
+                    (import_profile.mapping(), import_profile.default_account_id)
+                }
+                (None, Some(mapping)) => (mapping.clone(), None),
+                _ => {
+                    return Err(ApiError::client(
+                        ClientErrorCode::InvalidRequest,
+                        "Provide either a `profile_id` or a `mapping`.",
+                    ));
+                }
+            };
+        let account_id = import_request
+            .account_id
+            .or(default_account_id)
+            .ok_or_else(|| {
+                ApiError::client(
+                    ClientErrorCode::InvalidRequest,
+                    "No account to import into.",
+                )
+            })?;
+
+        // Accounts of other users are indistinguishable from missing ones.
+        let accounts = AccountRepository
+            .get_list(
+                deadline::begin(&state.connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                0,
+                None,
+                AccountFilter {
+                    id: account_id.into(),
+                    user_id: registered_user.id().into(),
+                    account_ids: registered_user.account_scope(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        if accounts.is_empty() {
+            return Err(ApiError::NotFound);
+        }
+        Ok((mapping, account_id))
     }
 
     async fn server_fn_handler(
@@ -116,10 +242,25 @@ mod ssr {
         let path = match req.uri().to_string() {
             val if val == "/" => "".to_string(),
             val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            val if val == "/import" || val == "/import/preview" => val,
+            // A dry run is asked for in the query, which the server fn reads
+            // from the request parts.
+            val if val.starts_with("/import?") => "/import".to_string(),
+            val if val.starts_with("/uncategorized") => val,
+            val if val == "/recategorize" => val,
+            val if val.starts_with("/recategorize?") => "/recategorize".to_string(),
+            val if val.starts_with("/recategorize/") => "/recategorize/".to_string(),
+            val if val.ends_with("/categorize") => "/categorize".to_string(),
+            val if val.ends_with("/notes/html") => "/notes/html".to_string(),
+            val if val.ends_with("/history") => "/history".to_string(),
+            val if val.contains("/revert/") => "/revert/".to_string(),
             _ => "/".to_string(),
         };
         let (mut req, parts) = generate_request_and_parts(req);
-        *req.uri_mut() = format!("/api/transactions{path}").parse().unwrap();
+        *req.uri_mut() = match server_fn_uri(format!("/api/transactions{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
         handle_server_fns_with_context(
             {
                 let app_state = state.clone();
@@ -131,11 +272,37 @@ mod ssr {
             req,
         )
         .await
+        .into_response()
     }
 
     pub struct TransactionApi;
 
     impl Api for TransactionApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
+                (Method::GET, "/{id}/notes/html"),
+                (Method::GET, "/{id}/history"),
+                (Method::POST, "/{id}/revert/{history_id}"),
+                (Method::POST, "/{id}/categorize"),
+                (Method::GET, "/uncategorized"),
+                (Method::POST, "/recategorize"),
+                (Method::GET, "/recategorize/{id}"),
+                (Method::POST, "/import"),
+                (Method::POST, "/import/preview"),
+            ]
+        }
+
+        /// Version 2 writes the quantities of transactions as decimal
+        /// strings.
+        fn deprecated() -> Vec<(Method, &'static str)> {
+            vec![(Method::GET, "/"), (Method::GET, "/{id}")]
+        }
+
         fn router(state: AppState) -> Router<AppState> {
             Router::new()
                 .route(
@@ -148,11 +315,25 @@ mod ssr {
                         .patch(server_fn_handler)
                         .delete(server_fn_handler),
                 )
+                .route("/{id}/notes/html", axum::routing::get(server_fn_handler))
+                .route("/{id}/history", axum::routing::get(server_fn_handler))
+                .route(
+                    "/{id}/revert/{history_id}",
+                    axum::routing::post(server_fn_handler),
+                )
+                .route("/{id}/categorize", axum::routing::post(server_fn_handler))
+                .route("/uncategorized", axum::routing::get(server_fn_handler))
+                .route("/recategorize", axum::routing::post(server_fn_handler))
+                .route("/recategorize/{id}", axum::routing::get(server_fn_handler))
+                .route("/import", axum::routing::post(server_fn_handler))
+                .route("/import/preview", axum::routing::post(server_fn_handler))
                 .layer(
                     ServiceBuilder::new()
+                        .layer(from_fn_with_state(state.clone(), authenticate_api_key))
                         .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
                         .layer(from_fn_with_state(state.clone(), set_user_groups)),
                 )
+                .with_state(state)
         }
     }
 }
@@ -165,12 +346,16 @@ pub use ssr::*;
     get,
     path = "/api/transactions",
     tag = "Transactions",
-    params(GetListRequest, Pagination),
+    params(GetListRequest, DateRange, Pagination),
     security(
         ("OpenIDConnect" = ["groups", "email"])
     ),
     responses(
-        (status = 200, description = "The list of transactions.", body = TransactionGetListResponse)
+        (status = 200, description = "The list of transactions. The date range and the `posted_*` filters are on when a transaction happened at its institution, while `created_*` and `updated_*` are on when it was recorded and last changed here, as a sync would track. The date range includes its start, every other bound excludes the time itself. The quantity filters all apply, so `min_quantity`, `max_quantity`, `direction` and the `*_abs_quantity` bounds narrow the list together.", body = TransactionGetListResponse),
+        (status = 400, description = "The date range is invalid, or given with `posted_after` or `posted_before`.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4001,
+            message: "A date range is either a `preset` or `from` and `to`, not both.".to_string()
+        })),
     )
 ))]
 #[server(
@@ -187,20 +372,40 @@ pub async fn get_list(
     filter: GetListRequest,
     #[server(flatten)]
     #[server(default)]
+    range: DateRange,
+    #[server(flatten)]
+    #[server(default)]
     pagination: Pagination,
 ) -> Result<TransactionGetListResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
 
-    let pagination = extract_with_state::<Pagination, _>(&state).await?;
+    let pagination = extract_with_state::<Pagination, _>(&state)
+        .await?
+        .for_resource(PagedResource::Transactions)?;
     let cursor_key = extract_with_state::<CursorKey, _>(&state).await?;
 
     let offset = pagination.offset();
+    let convert_to = filter.convert_to.clone();
+    let filter = filter.into_filter(range.resolve(Utc::now())?)?;
     let transactions = api_state
-        .transaction_service
-        .get_list(offset, pagination.max_items, filter.into())
+        .service
+        .get_list(offset, pagination.limit().into(), filter)
         .await?;
-    let response = TransactionGetListResponse::new(transactions, &pagination, &cursor_key)?;
+    let rounding = RoundingPolicy::from_env();
+    let conversions = match convert_to {
+        Some(quote_symbol) => Some(
+            api_state
+                .service
+                .convert(&transactions, quote_symbol, rounding)
+                .await?,
+        ),
+        None => None,
+    };
+    let mut response = TransactionGetListResponse::new(transactions, &pagination, &cursor_key)?;
+    if let Some(conversions) = conversions {
+        response = response.with_conversions(conversions, rounding);
+    }
     Ok(response)
 }
 
@@ -228,9 +433,9 @@ pub async fn get_list(
 pub async fn get() -> Result<TransactionGetResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
-    let Path(PathTransactionId { id }) = extract().await?;
+    let PathTransactionId { id } = extract_path().await?;
 
-    let transaction = api_state.transaction_service.get(id).await?;
+    let transaction = api_state.service.get(id).await?;
     Ok(transaction.into())
 }
 
@@ -244,6 +449,10 @@ pub async fn get() -> Result<TransactionGetResponse, ApiError> {
     request_body = CreateRequest,
     responses(
         (status = 201, description = "The newly created transaction.", body = TransactionCreateResponse),
+        (status = 409, description = "The caller is in double-entry mode, so creates transactions with `POST /api/journal-entries`.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4093,
+            message: "Double-entry mode is on, create transactions as balanced journal entries with POST /api/journal-entries.".to_string()
+        })),
     ),
 ))]
 #[server(
@@ -257,11 +466,23 @@ pub async fn get() -> Result<TransactionGetResponse, ApiError> {
 pub async fn create(
     #[server(flatten)] create_request: CreateRequest,
 ) -> Result<TransactionCreateResponse, ApiError> {
+    validate_notes(create_request.notes.as_deref())?;
+    let create_request = CreateRequest {
+        description: TRANSACTION_DESCRIPTION.sanitize_option(create_request.description)?,
+        category: create_request
+            .category
+            .as_deref()
+            .map(sanitize_category)
+            .transpose()?,
+        ..create_request
+    };
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    ensure_single_entry(&state).await?;
+    let scale = asset_scale(&state, create_request.asset_id).await?;
     let transaction = api_state
-        .transaction_service
-        .create(create_request.into())
+        .service
+        .create(create_request.into_create(scale)?)
         .await?;
     let response_opts = expect_context::<ResponseOptions>();
     response_opts.set_status(TransactionCreateResponse::status());
@@ -281,24 +502,48 @@ pub async fn create(
     responses(
         (status = 200, description = "The updated transaction.", body = TransactionUpdateResponse),
         (status = 404, description = "The transaction was not found."),
+        (status = 409, description = "The transaction is a leg of a journal entry, which the update would unbalance."),
     ),
 ))]
 #[server(
     name = TransactionApiUpdate,
     prefix = "/api",
-    endpoint = "assets/",
+    endpoint = "transactions/",
     input = PatchJson,
     output = PatchJson,
     client = ApiClient,
 )]
-pub async fn update(update_request: UpdateRequest) -> Result<TransactionUpdateResponse, ApiError> {
+pub async fn update(
+    #[server(flatten)] update_request: UpdateRequest,
+) -> Result<TransactionUpdateResponse, ApiError> {
+    validate_notes(update_request.notes.as_deref())?;
+    let update_request = UpdateRequest {
+        description: TRANSACTION_DESCRIPTION.sanitize_option(update_request.description)?,
+        category: update_request
+            .category
+            .as_deref()
+            .map(sanitize_category)
+            .transpose()?,
+        ..update_request
+    };
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
-    let Path(PathTransactionId { id }) = extract().await?;
+    let PathTransactionId { id } = extract_path().await?;
 
+    // The quantity is read in the asset the transaction is left in, and one
+    // that isn't given is carried over when the asset changes.
+    let transaction = api_state.service.get(id).await?;
+    let asset_id = update_request.asset_id.unwrap_or(transaction.asset_id);
+    let scale = asset_scale(&state, asset_id).await?;
+    let update_request = UpdateRequest {
+        quantity: update_request
+            .quantity
+            .or((asset_id != transaction.asset_id).then_some(transaction.quantity.into())),
+        ..update_request
+    };
     let transaction = api_state
-        .transaction_service
-        .update(id, update_request.into())
+        .service
+        .update(id, update_request.into_update(scale)?)
         .await?;
     Ok(transaction.into())
 }
@@ -317,6 +562,7 @@ pub async fn update(update_request: UpdateRequest) -> Result<TransactionUpdateRe
             code: 4040,
             message: "Not found.".to_string()
         })),
+        (status = 409, description = "The transaction is a leg of a journal entry, which is deleted as a whole."),
     ),
 ))]
 #[server(
@@ -329,11 +575,476 @@ pub async fn update(update_request: UpdateRequest) -> Result<TransactionUpdateRe
 pub async fn delete() -> Result<DeleteResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
-    let Path(PathTransactionId { id }) = extract().await?;
+    let PathTransactionId { id } = extract_path().await?;
 
-    api_state.transaction_service.delete(id).await?;
+    api_state.service.delete(id).await?;
     let response_opts = expect_context::<ResponseOptions>();
     response_opts.set_status(DeleteResponse::status());
     provide_context(response_opts);
     Ok(DeleteResponse {})
 }
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/transactions/{id}/notes/html",
+    tag = "Transactions",
+    params(TransactionId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The transaction notes rendered as sanitized HTML.", body = NotesHtmlResponse),
+        (status = 404, description = "The transaction was not found."),
+    )
+))]
+#[server(
+    name = TransactionApiGetNotesHtml,
+    prefix = "/api",
+    endpoint = "transactions/notes/html",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_notes_html() -> Result<NotesHtmlResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathTransactionId { id } = extract_path().await?;
+
+    let transaction = api_state.service.get(id).await?;
+    Ok(NotesHtmlResponse::render(
+        transaction.notes.as_deref().unwrap_or_default(),
+    ))
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/transactions/{id}/history",
+    tag = "Transactions",
+    params(TransactionId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The latest edits of the transaction, newest first.", body = HistoryGetListResponse),
+        (status = 404, description = "The transaction was not found."),
+    )
+))]
+#[server(
+    name = TransactionApiGetHistory,
+    prefix = "/api",
+    endpoint = "transactions/history",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_history() -> Result<HistoryGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathTransactionId { id } = extract_path().await?;
+
+    let transaction_history = api_state.service.get_history(id).await?;
+    Ok(transaction_history.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/transactions/{id}/revert/{history_id}",
+    tag = "Transactions",
+    params(TransactionId, TransactionHistoryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The transaction as it was before the edit.", body = TransactionUpdateResponse),
+        (status = 404, description = "The transaction or the edit was not found."),
+    )
+))]
+#[server(
+    name = TransactionApiRevert,
+    prefix = "/api",
+    endpoint = "transactions/revert/",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn revert() -> Result<TransactionUpdateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathTransactionHistoryId { id, history_id } = extract_path().await?;
+
+    let transaction = api_state.service.revert(id, history_id).await?;
+    Ok(transaction.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/transactions/import",
+    tag = "Transactions",
+    params(DryRunRequest),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = ImportRequest,
+    responses(
+        (status = 200, description = "A dry run: the transactions that would have been created from the CSV, none of which were kept.", body = ImportResponse),
+        (status = 201, description = "The transactions created from the CSV.", body = ImportResponse),
+        (status = 400, description = "The CSV could not be mapped.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4000,
+            message: "Column `Date` is not in the CSV header.".to_string()
+        })),
+        (status = 404, description = "The profile or account was not found."),
+    ),
+))]
+#[server(
+    name = TransactionApiImport,
+    prefix = "/api",
+    endpoint = "transactions/import",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn import(
+    #[server(flatten)] import_request: ImportRequest,
+) -> Result<ImportResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    ensure_single_entry(&state).await?;
+    let Query(DryRunRequest { dry_run }) =
+        extract_with_state::<Query<DryRunRequest>, _>(&()).await?;
+    let (mapping, account_id) = import_mapping(&state, &import_request).await?;
+
+    let rows = mapping
+        .apply(&import_request.csv, Some(MAX_IMPORT_ROWS + 1))?
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(ImportError::TooManyRows.into());
+    }
+    // Every row is checked before any is created, so a bad description does
+    // not leave the import half done.
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            Ok(ImportedRow {
+                description: TRANSACTION_DESCRIPTION.sanitize_option(row.description)?,
+                ..row
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let scale = asset_scale(&state, import_request.asset_id).await?;
+    let create_models = rows
+        .into_iter()
+        .map(|row| {
+            Ok(TransactionCreate {
+                account_id,
+                asset_id: import_request.asset_id,
+                description: row.description,
+                posted_at: row.posted_at,
+                quantity: Quantity::from(row.quantity).in_asset(scale)?,
+                notes: None,
+                external_id: None,
+                category: None,
+                applied_rule_id: None,
+                journal_entry_id: None,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let transactions = api_state
+        .service
+        .create_many(create_models, dry_run)
+        .await?;
+    let response = ImportResponse::from(transactions).dry_run(dry_run);
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(response.status());
+    provide_context(response_opts);
+    Ok(response)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/transactions/import/preview",
+    tag = "Transactions",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = ImportRequest,
+    responses(
+        (status = 200, description = "The first rows of the CSV as they would be imported.", body = ImportPreviewResponse),
+        (status = 400, description = "The CSV could not be mapped."),
+        (status = 404, description = "The profile or account was not found."),
+    ),
+))]
+#[server(
+    name = TransactionApiImportPreview,
+    prefix = "/api",
+    endpoint = "transactions/import/preview",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn import_preview(
+    #[server(flatten)] import_request: ImportRequest,
+) -> Result<ImportPreviewResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    // Checks the caller could run the import, though nothing is created.
+    extract_with_state::<TransactionApiState, _>(&state).await?;
+    let (mapping, account_id) = import_mapping(&state, &import_request).await?;
+    let scale = asset_scale(&state, import_request.asset_id).await?;
+
+    let rows = mapping
+        .apply(&import_request.csv, Some(PREVIEW_ROWS))?
+        .into_iter()
+        .map(|x| ImportPreviewRow::new(x, scale))
+        .collect();
+    Ok(ImportPreviewResponse {
+        account_id,
+        asset_id: import_request.asset_id,
+        rows,
+    })
+}
+
+#[allow(unused_variables)]
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/transactions/uncategorized",
+    tag = "Transactions",
+    params(UncategorizedRequest),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The latest transactions of the caller without a category, with how many there are in all.", body = TransactionUncategorizedResponse)
+    )
+))]
+#[server(
+    name = TransactionApiGetUncategorized,
+    prefix = "/api",
+    endpoint = "transactions/uncategorized",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_uncategorized(
+    #[server(flatten)]
+    #[server(default)]
+    request: UncategorizedRequest,
+) -> Result<TransactionUncategorizedResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+
+    let limit = request
+        .limit
+        .unwrap_or(DEFAULT_UNCATEGORIZED)
+        .clamp(1, MAX_LIMIT);
+    let (transactions, count) = api_state.service.get_uncategorized(limit).await?;
+    Ok(TransactionUncategorizedResponse {
+        transactions: transactions.into_iter().map(|x| x.into()).collect(),
+        count,
+    })
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/transactions/{id}/categorize",
+    tag = "Transactions",
+    params(TransactionId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CategorizeRequest,
+    responses(
+        (status = 200, description = "The categorized transaction, with the rule created along with it if `create_rule` was set.", body = TransactionCategorizeResponse),
+        (status = 400, description = "The category is empty, or the pattern is invalid.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4000,
+            message: "The transaction has no description to make a rule of, give a `pattern`.".to_string()
+        })),
+        (status = 404, description = "The transaction was not found."),
+        (status = 422, description = "The caller already has as many categorization rules as they may, so the transaction was left as it was."),
+    ),
+))]
+#[server(
+    name = TransactionApiCategorize,
+    prefix = "/api",
+    endpoint = "transactions/categorize",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn categorize(
+    #[server(flatten)] categorize_request: CategorizeRequest,
+) -> Result<TransactionCategorizeResponse, ApiError> {
+    let category = sanitize_category(&categorize_request.category)?;
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathTransactionId { id } = extract_path().await?;
+
+    let rule = match (categorize_request.create_rule, categorize_request.pattern) {
+        (false, None) => None,
+        (false, Some(_)) => {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "A `pattern` is only used with `create_rule`.",
+            ));
+        }
+        (true, pattern) => {
+            let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+            let pattern = match pattern {
+                Some(pattern) => pattern,
+                None => {
+                    let transaction = api_state.service.get(id).await?;
+                    let description = transaction.description.ok_or_else(|| {
+                        ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                            "The transaction has no description to make a rule of, give a `pattern`.",
+                        )
+                    })?;
+                    regex::escape(&description)
+                }
+            };
+            compile_pattern(&pattern)?;
+            Some(CategorizationRuleCreate {
+                user_id: registered_user.id(),
+                field: CategorizationRuleField::Description,
+                pattern: Some(pattern),
+                min_quantity: None,
+                max_quantity: None,
+                account_id: None,
+                category: category.clone(),
+                priority: categorize_request.priority,
+            })
+        }
+    };
+
+    let (transaction, rule) = api_state.service.categorize(id, category, rule).await?;
+    Ok(TransactionCategorizeResponse {
+        transaction: transaction.into(),
+        rule: rule.map(|x| x.into()),
+    })
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/transactions/recategorize",
+    tag = "Transactions",
+    params(DryRunRequest),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = RecategorizeRequest,
+    responses(
+        (status = 200, description = "How many transactions of the caller the filter matches. With `execute`, the recategorization as well, done once it matched at most 1000 transactions. With `dry_run`, how many it would change, of which none were kept.", body = TransactionRecategorizeResponse),
+        (status = 202, description = "The filter matched more than 1000 transactions, which are recategorized after responding. Poll the `url` of the recategorization for how far it has come.", body = TransactionRecategorizeResponse),
+        (status = 400, description = "The category is empty or too long."),
+    ),
+))]
+#[server(
+    name = TransactionApiRecategorize,
+    prefix = "/api",
+    endpoint = "transactions/recategorize",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn recategorize(
+    #[server(flatten)] recategorize_request: RecategorizeRequest,
+) -> Result<TransactionRecategorizeResponse, ApiError> {
+    let category = sanitize_category(&recategorize_request.category)?;
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+    let Query(DryRunRequest { dry_run }) =
+        extract_with_state::<Query<DryRunRequest>, _>(&()).await?;
+    let filter = recategorize_request.filter;
+
+    let matched = api_state
+        .service
+        .count_recategorizable(filter.clone())
+        .await?;
+    if dry_run {
+        // Without a recategorization, as it is the record of one that ran.
+        let changed = api_state
+            .service
+            .dry_run_recategorize(filter, category)
+            .await?;
+        return Ok(TransactionRecategorizeResponse {
+            matched,
+            recategorization: None,
+            changed: Some(changed),
+            dry_run,
+        });
+    }
+    if !recategorize_request.execute {
+        return Ok(TransactionRecategorizeResponse {
+            matched,
+            recategorization: None,
+            changed: None,
+            dry_run,
+        });
+    }
+
+    let create_model = RecategorizationCreate {
+        user_id: registered_user.id(),
+        filter: serde_json::to_value(&filter).map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?,
+        category,
+        matched,
+    };
+    let job = api_state.service.recategorize(create_model, filter).await?;
+    let recategorization = if matched <= RECATEGORIZE_BATCH_SIZE {
+        job.run(&state.connection_pool).await?
+    } else {
+        let recategorization = job.recategorization.clone();
+        job.spawn(Arc::clone(&state.connection_pool));
+        recategorization
+    };
+
+    let response = TransactionRecategorizeResponse {
+        matched,
+        recategorization: Some(recategorization.into()),
+        changed: None,
+        dry_run,
+    };
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(response.status_code());
+    provide_context(response_opts);
+    Ok(response)
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/transactions/recategorize/{id}",
+    tag = "Transactions",
+    params(RecategorizationId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The recategorization once it is complete or failed.", body = TransactionRecategorizationResponse),
+        (status = 202, description = "The recategorization is still running, with how many transactions it changed so far.", body = TransactionRecategorizationResponse),
+        (status = 404, description = "The recategorization was not found."),
+    ),
+))]
+#[server(
+    name = TransactionApiGetRecategorization,
+    prefix = "/api",
+    endpoint = "transactions/recategorize/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_recategorization() -> Result<TransactionRecategorizationResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+    let PathRecategorizationId { id } = extract_path().await?;
+
+    let recategorization = api_state.service.get_recategorization(id).await?;
+    let response = TransactionRecategorizationResponse::from(recategorization);
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(response.status_code());
+    provide_context(response_opts);
+    Ok(response)
+}
@@ -0,0 +1,165 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::{journal_entry::JournalEntryCreateResponse, transfer::CreateRequest},
+};
+use leptos::{server, server_fn::codec::Json};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode, asset_api::asset_scale,
+            extract_with_state, server_fn_uri, set_user_groups,
+            transaction_api::TransactionApiState,
+        },
+        authentication::{api_key::authenticate_api_key, authenticator::Authenticator},
+        model::journal_entry::{JournalEntryCreate, JournalLeg},
+        schema::{notes::validate_notes, text::TRANSACTION_DESCRIPTION},
+        service::transaction_service::TransactionServiceJournalCreate,
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The entry a transfer is created as: a credit of the account the
+    /// asset leaves and a debit of the one it arrives in, which balance by
+    /// construction.
+    pub async fn transfer_entry(
+        state: &AppState,
+        create_request: CreateRequest,
+    ) -> Result<JournalEntryCreate, ApiError> {
+        if create_request.from_account_id == create_request.to_account_id {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "A transfer needs two different accounts.",
+            ));
+        }
+        let scale = asset_scale(state, create_request.asset_id).await?;
+        let quantity = create_request.quantity.in_asset(scale)?;
+        if quantity.is_zero() || quantity.is_sign_negative() {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidQuantity,
+                "The quantity of a transfer has to be positive.",
+            ));
+        }
+        validate_notes(create_request.notes.as_deref())?;
+        let leg = |account_id, quantity| JournalLeg {
+            account_id,
+            asset_id: create_request.asset_id,
+            quantity,
+            notes: create_request.notes.clone(),
+        };
+        Ok(JournalEntryCreate {
+            posted_at: create_request.posted_at,
+            description: TRANSACTION_DESCRIPTION.sanitize_option(create_request.description)?,
+            legs: vec![
+                leg(create_request.from_account_id, -quantity),
+                leg(create_request.to_account_id, quantity),
+            ],
+        })
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri("/api/transfers".to_string()) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct TransferApi;
+
+    impl Api for TransferApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::POST, "/")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::post(server_fn_handler))
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(from_fn_with_state(state.clone(), authenticate_api_key))
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/transfers",
+    tag = "Transfers",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The journal entry of the transfer, with the leg of the account the asset left first. Either both legs are created or neither is.", body = JournalEntryCreateResponse),
+        (status = 400, description = "The accounts are the same, or the quantity isn't positive.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4006,
+            message: "The quantity of a transfer has to be positive.".to_string()
+        })),
+        (status = 404, description = "Either account or the asset was not found."),
+    ),
+))]
+#[server(
+    name = TransferApiCreate,
+    prefix = "/api",
+    endpoint = "transfers",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<JournalEntryCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<TransactionApiState, _>(&state).await?;
+
+    let create_model = transfer_entry(&state, create_request).await?;
+    let journal_entry = api_state.service.create_journal_entry(create_model).await?;
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(JournalEntryCreateResponse::status());
+    provide_context(response_opts);
+    Ok(journal_entry.into())
+}
@@ -1,106 +1,95 @@
 use crate::{
     api::{
-        Api, ApiError, ApiErrorResponse, AppState, client::ApiClient, extract_with_state,
-        set_user_groups,
+        Api, ApiError, ApiErrorResponse, AppState,
+        client::ApiClient,
+        extract_path, extract_with_state,
+        resource_context::{ApiResource, ResourceContext},
+        server_fn_uri, set_user_groups,
     },
     authentication::{
-        authenticated_token::AuthenticatedToken, authenticator::Authenticator,
-        registered_user::RegisteredUser,
+        authenticator::Authenticator, registered_user::RegisteredUser, step_up::Elevation,
     },
     authorization::{
         PermissionConfig, PermissionSet,
         actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
     },
+    config::PagedResource,
+    integrity::{self, claim_run},
     model::{
         cursor_key::CursorKey,
+        login_event::LoginEventFilter,
         user::{UserCreate, UserId},
     },
+    resource::{GetListRepository, deadline, login_event_repository::LoginEventRepository},
     schema::{
         Pagination,
+        text::USER_NAME,
         user::{
-            CreateRequest as UserCreateRequest, GetListRequest, UpdateRequest as UserUpdateRequest,
-            UserCreateResponse, UserDeleteResponse, UserGetListResponse, UserGetResponse,
+            ActivityGetListResponse, CreateRequest as UserCreateRequest, GetListRequest,
+            IntegrityResponse, UpdateRequest as UserUpdateRequest, UserCreateResponse,
+            UserDeleteResponse, UserGetListResponse, UserGetResponse, UserResponse,
             UserUpdateResponse,
         },
     },
-    service::{user_service::UserServiceMethods, user_service_factory::UserServiceFactory},
+    service::{
+        ServiceError, user_service::UserServiceMethods, user_service_factory::UserServiceFactory,
+    },
 };
 use axum::{
     Router,
     body::Body,
-    extract::{FromRequestParts, Path, Request, State},
-    http::request::Parts,
+    extract::{Request, State},
     middleware::from_fn_with_state,
     response::IntoResponse,
 };
+use http::Method;
 use leptos::{
     prelude::{expect_context, provide_context},
     server,
     server_fn::codec::{DeleteUrl, GetUrl, Json, PatchJson},
 };
-use leptos_axum::{
-    ResponseOptions, extract, generate_request_and_parts, handle_server_fns_with_context,
-};
+use leptos_axum::{ResponseOptions, generate_request_and_parts, handle_server_fns_with_context};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tower::ServiceBuilder;
 use tower_http::auth::AsyncRequireAuthorizationLayer;
-use tracing::error;
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct PathUserId {
     id: UserId,
 }
 
-pub struct UserApiState {
-    pub authenticated_token: AuthenticatedToken,
-    pub user_service: Box<dyn UserServiceMethods + Send>,
-}
-
-impl FromRequestParts<AppState> for UserApiState {
-    type Rejection = ApiError;
-
-    async fn from_request_parts(
-        parts: &mut Parts,
-        state: &AppState,
-    ) -> Result<Self, Self::Rejection> {
-        use axum::RequestPartsExt;
+/// The levels of `users` the API resolves for a caller.
+pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+    min_read_level: ReadLevel::Read,
+    min_create_level: CreateLevel::Create,
+    min_update_level: UpdateLevel::Update,
+    min_delete_level: DeleteLevel::Delete,
+};
 
-        let authenticated_token = parts
-            .extract_with_state::<AuthenticatedToken, _>(state)
-            .await?;
+pub struct UserApiResource;
 
-        let registered_user = parts
-            .extract_with_state::<Option<RegisteredUser>, _>(state)
-            .await?;
+impl ApiResource for UserApiResource {
+    const NAME: &'static str = "users";
+    const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+    type Owner = Option<RegisteredUser>;
+    type Service = Box<dyn UserServiceMethods + Send>;
 
-        let permission_set = PermissionSet::new(
-            "users",
-            &state.enforcer,
-            &authenticated_token,
-            PermissionConfig {
-                min_read_level: ReadLevel::Read,
-                min_create_level: CreateLevel::Create,
-                min_update_level: UpdateLevel::Update,
-                min_delete_level: DeleteLevel::Delete,
-            },
-        )
-        .map_err(|e| {
-            error!("{e}");
-            ApiError::ServerError
-        })?;
+    fn service(
+        state: &AppState,
+        owner: Option<RegisteredUser>,
+        permission_set: PermissionSet,
+    ) -> Self::Service {
+        UserServiceFactory::build(owner, Arc::clone(&state.connection_pool), permission_set)
+    }
+}
 
-        let user_service = UserServiceFactory::build(
-            registered_user,
-            Arc::clone(&state.connection_pool),
-            permission_set,
-        );
+pub type UserApiState = ResourceContext<UserApiResource>;
 
-        Ok(Self {
-            authenticated_token,
-            user_service,
-        })
-    }
+/// Whether the caller sees what only admins do of users, like how they
+/// registered.
+fn is_admin(api_state: &UserApiState) -> bool {
+    api_state.permission_set.read_level == ReadLevel::ReadAll
 }
 
 #[utoipa::path(
@@ -123,7 +112,7 @@ impl FromRequestParts<AppState> for UserApiState {
     output = Json,
     client = ApiClient,
 )]
-async fn get_list(
+pub async fn get_list(
     #[server(flatten)]
     #[server(default)]
     filter: GetListRequest,
@@ -131,15 +120,24 @@ async fn get_list(
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<UserApiState, _>(&state).await?;
 
-    let pagination = extract_with_state::<Pagination, _>(&state).await?;
+    let pagination = extract_with_state::<Pagination, _>(&state)
+        .await?
+        .for_resource(PagedResource::Users)?;
     let cursor_key = extract_with_state::<CursorKey, _>(&state).await?;
 
     let offset = pagination.offset();
     let users = api_state
-        .user_service
-        .get_list(offset, pagination.max_items, filter.into())
+        .service
+        .get_list(offset, pagination.limit().into(), filter.into())
         .await?;
-    let response = UserGetListResponse::new(users, &pagination, &cursor_key)?;
+    let mut response = UserGetListResponse::new(users, &pagination, &cursor_key)?;
+    if !is_admin(&api_state) {
+        response.users = response
+            .users
+            .into_iter()
+            .map(UserResponse::redacted)
+            .collect();
+    }
 
     Ok(response)
 }
@@ -165,13 +163,16 @@ async fn get_list(
     output = Json,
     client = ApiClient,
 )]
-async fn get() -> Result<UserGetResponse, ApiError> {
+pub async fn get() -> Result<UserGetResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<UserApiState, _>(&state).await?;
-    let Path(PathUserId { id }) = extract().await?;
+    let PathUserId { id } = extract_path().await?;
 
-    let user = api_state.user_service.get(id).await?;
-    let response = user.into();
+    let user = api_state.service.get(id).await?;
+    let response = UserGetResponse::from(user);
+    if !is_admin(&api_state) {
+        return Ok(response.redacted());
+    }
     Ok(response)
 }
 
@@ -185,6 +186,10 @@ async fn get() -> Result<UserGetResponse, ApiError> {
     request_body = UserCreateRequest,
     responses(
         (status = 201, description = "The newly created user.", body = UserCreateResponse),
+        (status = 403, description = "The email of the token is not verified.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4031,
+            message: "The email address is not verified.".to_string()
+        })),
     ),
 )]
 #[server(
@@ -195,23 +200,24 @@ async fn get() -> Result<UserGetResponse, ApiError> {
     output = Json,
     client = ApiClient,
 )]
-async fn create(
+pub async fn create(
     #[server(flatten)] create_request: UserCreateRequest,
 ) -> Result<UserCreateResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<UserApiState, _>(&state).await?;
 
     let user_create = UserCreate {
-        name: create_request.name,
+        name: USER_NAME.sanitize(&create_request.name)?,
         email: api_state.authenticated_token.email().to_owned(),
         iss: api_state.authenticated_token.iss().to_owned(),
         sub: api_state.authenticated_token.sub().to_owned(),
+        email_verified: Some(api_state.authenticated_token.email_verified()),
     };
-    let user = api_state.user_service.create(user_create).await?;
+    let user = api_state.service.create(user_create).await?;
     let response_opts = expect_context::<ResponseOptions>();
     response_opts.set_status(UserCreateResponse::status());
     provide_context(response_opts);
-    Ok(user.into())
+    Ok(UserCreateResponse::from(user).redacted())
 }
 
 #[utoipa::path(
@@ -225,6 +231,10 @@ async fn create(
     request_body = UserUpdateRequest,
     responses(
         (status = 200, description = "The updated user.", body = UserUpdateResponse),
+        (status = 401, description = "A step-up is required to change the email.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4011,
+            message: "step_up_required".to_string()
+        })),
         (status = 404, description = "The user was not found."),
     ),
 )]
@@ -236,18 +246,28 @@ async fn create(
     output = PatchJson,
     client = ApiClient,
 )]
-async fn update(
+pub async fn update(
     #[server(flatten)] update_request: UserUpdateRequest,
 ) -> Result<UserUpdateResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<UserApiState, _>(&state).await?;
-    let Path(PathUserId { id }) = extract().await?;
+    let PathUserId { id } = extract_path().await?;
+    if update_request.email.is_some() {
+        extract_with_state::<Elevation, _>(&state)
+            .await?
+            .require()?;
+    }
+    let update_request = UserUpdateRequest {
+        name: USER_NAME.sanitize_option(update_request.name)?,
+        ..update_request
+    };
 
-    let user = api_state
-        .user_service
-        .update(id, update_request.into())
-        .await?;
-    Ok(user.into())
+    let user = api_state.service.update(id, update_request.into()).await?;
+    let response = UserUpdateResponse::from(user);
+    if !is_admin(&api_state) {
+        return Ok(response.redacted());
+    }
+    Ok(response)
 }
 
 #[utoipa::path(
@@ -260,6 +280,10 @@ async fn update(
     ),
     responses(
         (status = 204, description = "The user was successfully deleted."),
+        (status = 401, description = "A step-up is required to delete the user.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4011,
+            message: "step_up_required".to_string()
+        })),
         (status = 404, description = "The user was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
             code: 4040,
             message: "Not found.".to_string()
@@ -273,26 +297,121 @@ async fn update(
     input = DeleteUrl,
     client = ApiClient,
 )]
-async fn delete() -> Result<UserDeleteResponse, ApiError> {
+pub async fn delete() -> Result<UserDeleteResponse, ApiError> {
     let state = expect_context::<AppState>();
     let api_state = extract_with_state::<UserApiState, _>(&state).await?;
-    let Path(PathUserId { id }) = extract().await?;
+    let PathUserId { id } = extract_path().await?;
+    extract_with_state::<Elevation, _>(&state)
+        .await?
+        .require()?;
 
-    api_state.user_service.delete(id).await?;
+    api_state.service.delete(id).await?;
     let response_opts = expect_context::<ResponseOptions>();
     response_opts.set_status(UserDeleteResponse::status());
     provide_context(response_opts);
     Ok(UserDeleteResponse {})
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/integrity",
+    tag = "Users",
+    params(UserId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "What the consistency checks of the data of the user found.", body = IntegrityResponse),
+        (status = 404, description = "The user was not found."),
+        (status = 429, description = "The checks of the user already ran within the last minute."),
+    ),
+)]
+#[server(
+    name = UserApiIntegrity,
+    prefix = "/api",
+    endpoint = "users/integrity",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn integrity() -> Result<IntegrityResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<UserApiState, _>(&state).await?;
+    let PathUserId { id } = extract_path().await?;
+
+    // Users can only see their own user, unless they may read all of them.
+    let user = api_state.service.get(id).await?;
+    if !claim_run(user.id) {
+        return Err(ApiError::TooManyRequests);
+    }
+    let report = integrity::run(&state.connection_pool, user.id).await?;
+    Ok(report.into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/activity",
+    tag = "Users",
+    params(UserId, Pagination),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The login history of the user, newest first.", body = ActivityGetListResponse),
+        (status = 404, description = "The user was not found."),
+    ),
+)]
+#[server(
+    name = UserApiActivity,
+    prefix = "/api",
+    endpoint = "users/activity",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn activity() -> Result<ActivityGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<UserApiState, _>(&state).await?;
+    let PathUserId { id } = extract_path().await?;
+
+    // Users can only see their own history, unless they may read all users.
+    let user = api_state.service.get(id).await?;
+    let pagination = extract_with_state::<Pagination, _>(&state).await?;
+    let cursor_key = extract_with_state::<CursorKey, _>(&state).await?;
+
+    let events = LoginEventRepository
+        .get_list(
+            deadline::begin(&state.connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            pagination.offset(),
+            pagination.limit().into(),
+            LoginEventFilter {
+                user_id: Some(user.id),
+            },
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    let response = ActivityGetListResponse::new(events, &pagination, &cursor_key)?;
+    Ok(response)
+}
+
 async fn server_fn_handler(State(state): State<AppState>, req: Request<Body>) -> impl IntoResponse {
     let path = match req.uri().to_string() {
         val if val == "/" => "".to_string(),
         val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+        val if val.ends_with("/integrity") => "/integrity".to_string(),
+        val if req.uri().path().ends_with("/activity") => match val.split_once('?') {
+            Some((_, query)) => format!("/activity?{query}"),
+            None => "/activity".to_string(),
+        },
         _ => "/".to_string(),
     };
     let (mut req, parts) = generate_request_and_parts(req);
-    *req.uri_mut() = format!("/api/users{path}").parse().unwrap();
+    *req.uri_mut() = match server_fn_uri(format!("/api/users{path}")) {
+        Ok(uri) => uri,
+        Err(e) => return e.into_response(),
+    };
     handle_server_fns_with_context(
         {
             let app_state = state.clone();
@@ -304,11 +423,24 @@ async fn server_fn_handler(State(state): State<AppState>, req: Request<Body>) ->
         req,
     )
     .await
+    .into_response()
 }
 
 pub struct UserApi;
 
 impl Api for UserApi {
+    fn endpoints() -> Vec<(Method, &'static str)> {
+        vec![
+            (Method::GET, "/"),
+            (Method::POST, "/"),
+            (Method::GET, "/{id}"),
+            (Method::PATCH, "/{id}"),
+            (Method::DELETE, "/{id}"),
+            (Method::GET, "/{id}/integrity"),
+            (Method::GET, "/{id}/activity"),
+        ]
+    }
+
     fn router(state: AppState) -> Router<AppState> {
         Router::new()
             .route(
@@ -321,6 +453,8 @@ impl Api for UserApi {
                     .patch(server_fn_handler)
                     .delete(server_fn_handler),
             )
+            .route("/{id}/integrity", axum::routing::get(server_fn_handler))
+            .route("/{id}/activity", axum::routing::get(server_fn_handler))
             .layer(
                 ServiceBuilder::new()
                     .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
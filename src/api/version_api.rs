@@ -0,0 +1,106 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    schema::version::VersionResponse,
+};
+use leptos::{
+    server,
+    server_fn::codec::{GetUrl, Json},
+};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{Api, AppState, server_fn_uri},
+        schema::version::ApiVersion,
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        response::IntoResponse,
+    };
+    pub use http::{Method, request::Parts};
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{generate_request_and_parts, handle_server_fns_with_context};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = req.uri().to_string();
+        let path = path.trim_start_matches('/');
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/version{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct VersionApi;
+
+    impl Api for VersionApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![(Method::GET, "/")]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route("/", axum::routing::get(server_fn_handler))
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/version",
+    tag = "Version",
+    responses(
+        (status = 200, description = "The version of the API that served the request, and the versions being served.", body = VersionResponse),
+    ),
+))]
+#[server(
+    name = VersionApiGet,
+    prefix = "/api",
+    endpoint = "version",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<VersionResponse, ApiError> {
+    let parts = expect_context::<Parts>();
+    let api_version = parts
+        .extensions
+        .get::<ApiVersion>()
+        .copied()
+        .unwrap_or_default();
+
+    Ok(VersionResponse {
+        api_version,
+        api_versions: ApiVersion::ALL.to_vec(),
+        server_version: env!("CARGO_PKG_VERSION").to_owned(),
+    })
+}
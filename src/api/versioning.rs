@@ -0,0 +1,248 @@
+//! Moves callers from one version of the API to the next.
+//!
+//! A newer version is mounted under its own prefix, see
+//! [`ApiVersion::prefix`], and serves the endpoints it changes itself while
+//! handing the rest to the version before it with [`fall_through`]. The
+//! [`set_deprecation_headers`] middleware marks the responses of the
+//! endpoints the resource registry flags as deprecated with `Deprecation`,
+//! `Sunset` and `Link` headers pointing at their successor.
+
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use http::{HeaderValue, Method, header::LINK};
+use tower::ServiceExt;
+
+use crate::{config::DeprecationConfig, schema::version::ApiVersion};
+
+pub const DEPRECATION_HEADER: &str = "Deprecation";
+pub const SUNSET_HEADER: &str = "Sunset";
+
+/// The deprecated endpoints, and when they were deprecated.
+#[derive(Debug, Clone)]
+pub struct DeprecationPolicy {
+    /// The `(method, path)` pairs of the deprecated endpoints of the first
+    /// version, with their parameters in braces
+    pub endpoints: Arc<Vec<(Method, String)>>,
+    pub config: DeprecationConfig,
+}
+
+impl DeprecationPolicy {
+    /// Whether a request to `path` is served by a deprecated endpoint.
+    pub fn is_deprecated(&self, method: &Method, path: &str) -> bool {
+        self.endpoints.iter().any(|(deprecated_method, pattern)| {
+            deprecated_method == method && path_matches(pattern, path)
+        })
+    }
+
+    /// The headers announcing the deprecation of an endpoint at `path`,
+    /// none until [`DeprecationConfig::deprecated_at`] is set.
+    pub fn headers(&self, path: &str) -> Vec<(&'static str, HeaderValue)> {
+        let Some(deprecated_at) = self.config.deprecated_at else {
+            return vec![];
+        };
+        let mut headers = vec![(
+            DEPRECATION_HEADER,
+            HeaderValue::from_str(&format!("@{}", deprecated_at.timestamp()))
+                .expect("Invalid Deprecation header."),
+        )];
+        if let Some(sunset_at) = self.config.sunset_at {
+            headers.push((
+                SUNSET_HEADER,
+                HeaderValue::from_str(&http_date(sunset_at)).expect("Invalid Sunset header."),
+            ));
+        }
+        let successor = path.strip_prefix(ApiVersion::V1.prefix()).unwrap_or(path);
+        if let Ok(link) = HeaderValue::from_str(&format!(
+            "<{}{successor}>; rel=\"successor-version\"",
+            ApiVersion::V2.prefix()
+        )) {
+            headers.push((LINK.as_str(), link));
+        }
+        headers
+    }
+}
+
+/// Whether `path` is one `pattern` routes, where a parameter in braces
+/// matches any one segment.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.split('/');
+    let mut path = path.split('/');
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return true,
+            (Some(expected), Some(segment)) => {
+                let is_parameter = expected.starts_with('{') && expected.ends_with('}');
+                if !((is_parameter && !segment.is_empty()) || expected == segment) {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Writes `time` the way HTTP dates are written.
+pub fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Marks the responses of deprecated endpoints. Requests a newer version
+/// handed down with [`fall_through`] are left alone, as it is the
+/// successor of the endpoint that serves them.
+pub async fn set_deprecation_headers(
+    State(policy): State<DeprecationPolicy>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let version = request
+        .extensions()
+        .get::<ApiVersion>()
+        .copied()
+        .unwrap_or_default();
+    let path = request.uri().path().to_owned();
+    let deprecated = version == ApiVersion::V1 && policy.is_deprecated(request.method(), &path);
+    let mut response = next.run(request).await;
+    if deprecated {
+        let headers = response.headers_mut();
+        for (name, value) in policy.headers(&path) {
+            headers.insert(name, value);
+        }
+    }
+    response
+}
+
+/// Serves the requests of `version` that it doesn't serve itself with the
+/// `previous` version, under which the request is marked as made to
+/// `version`. `request` has the prefix of `version` stripped.
+pub async fn fall_through(previous: Router, version: ApiVersion, mut request: Request) -> Response {
+    let path = match request.uri().path() {
+        "/" => "",
+        path => path,
+    };
+    let uri = match request.uri().query() {
+        Some(query) => format!("{}{path}?{query}", ApiVersion::V1.prefix()),
+        None => format!("{}{path}", ApiVersion::V1.prefix()),
+    };
+    *request.uri_mut() = uri.parse().expect("Invalid fall through URI.");
+    request.extensions_mut().insert(version);
+    match previous.oneshot(request).await {
+        Ok(response) => response,
+        Err(e) => match e {},
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{body::Body, middleware::from_fn_with_state, routing::get};
+    use http::StatusCode;
+
+    use super::*;
+
+    fn policy(config: DeprecationConfig) -> DeprecationPolicy {
+        DeprecationPolicy {
+            endpoints: Arc::new(vec![
+                (Method::GET, "/api/transactions".to_owned()),
+                (Method::GET, "/api/transactions/{id}".to_owned()),
+            ]),
+            config,
+        }
+    }
+
+    fn deprecated() -> DeprecationConfig {
+        DeprecationConfig {
+            deprecated_at: Some("2025-05-01T00:00:00Z".parse().unwrap()),
+            sunset_at: Some("2025-11-01T00:00:00Z".parse().unwrap()),
+        }
+    }
+
+    fn router(config: DeprecationConfig) -> Router {
+        let v1 = Router::new()
+            .route(
+                "/api/transactions",
+                get(|| async { "transactions" }).post(|| async { "created" }),
+            )
+            .route("/api/transactions/{id}", get(|| async { "transaction" }))
+            .route("/api/accounts", get(|| async { "accounts" }))
+            .layer(from_fn_with_state(policy(config), set_deprecation_headers));
+        let fall_through_v1 = v1.clone();
+        Router::new()
+            .nest_service(
+                ApiVersion::V2.prefix(),
+                Router::new().fallback(move |request: Request| {
+                    fall_through(fall_through_v1.clone(), ApiVersion::V2, request)
+                }),
+            )
+            .fallback_service(v1)
+    }
+
+    async fn respond(config: DeprecationConfig, method: Method, uri: &str) -> Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        router(config).oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_marks_deprecated_endpoints() {
+        let response = respond(deprecated(), Method::GET, "/api/transactions/5?x=1").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers[DEPRECATION_HEADER], "@1746057600");
+        assert_eq!(headers[SUNSET_HEADER], "Sat, 01 Nov 2025 00:00:00 GMT");
+        assert_eq!(
+            headers[LINK],
+            "</api/v2/transactions/5>; rel=\"successor-version\""
+        );
+    }
+
+    #[tokio::test]
+    async fn it_leaves_other_endpoints_alone() {
+        for (method, uri) in [
+            (Method::POST, "/api/transactions"),
+            (Method::GET, "/api/accounts"),
+            (Method::GET, "/api/transactions/5/history"),
+        ] {
+            let response = respond(deprecated(), method, uri).await;
+            assert!(
+                response.headers().get(DEPRECATION_HEADER).is_none(),
+                "{uri}"
+            );
+            assert!(response.headers().get(SUNSET_HEADER).is_none(), "{uri}");
+            assert!(response.headers().get(LINK).is_none(), "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn it_waits_for_a_deprecation_date() {
+        let config = DeprecationConfig {
+            deprecated_at: None,
+            ..deprecated()
+        };
+        let response = respond(config, Method::GET, "/api/transactions").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(DEPRECATION_HEADER).is_none());
+        assert!(response.headers().get(SUNSET_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn it_falls_through_to_the_previous_version() {
+        let response = respond(deprecated(), Method::GET, "/api/v2/transactions/5").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(DEPRECATION_HEADER).is_none());
+
+        let response = respond(deprecated(), Method::GET, "/api/v2/accounts").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = respond(deprecated(), Method::GET, "/api/v2/budgets").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
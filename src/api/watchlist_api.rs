@@ -0,0 +1,401 @@
+use crate::{
+    api::{ApiError, client::ApiClient},
+    model::watchlist_entry::WatchlistEntryId,
+    schema::watchlist_entry::{
+        CreateRequest, DeleteResponse, UpdateRequest, WatchlistEntryCreateResponse,
+        WatchlistEntryGetListResponse, WatchlistEntryGetResponse, WatchlistEntryUpdateResponse,
+    },
+};
+use leptos::{
+    server,
+    server_fn::codec::{DeleteUrl, GetUrl, Json, PatchJson},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        api::{
+            Api, ApiErrorResponse, AppState, ClientErrorCode,
+            asset_api::asset_scale,
+            extract_path, extract_with_state,
+            resource_context::{ApiResource, ResourceContext},
+            server_fn_uri, set_user_groups,
+        },
+        authentication::{authenticator::Authenticator, registered_user::RegisteredUser},
+        authorization::{
+            PermissionConfig, PermissionSet,
+            actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        },
+        model::{
+            asset::AssetId,
+            watchlist_entry::{WatchlistEntryCreate, WatchlistEntryFilter},
+        },
+        service::{
+            watchlist_entry_service::WatchlistEntryServiceMethods,
+            watchlist_entry_service_factory::WatchlistEntryServiceFactory,
+        },
+    };
+    pub use axum::{
+        Router,
+        body::Body,
+        extract::{Request, State},
+        middleware::from_fn_with_state,
+        response::IntoResponse,
+    };
+    pub use http::Method;
+    pub use leptos::prelude::*;
+    pub use leptos_axum::{
+        ResponseOptions, generate_request_and_parts, handle_server_fns_with_context,
+    };
+    pub use rust_decimal::Decimal;
+    pub use std::sync::Arc;
+    pub use tower::ServiceBuilder;
+    pub use tower_http::auth::AsyncRequireAuthorizationLayer;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PathWatchlistEntryId {
+    id: WatchlistEntryId,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The levels of `watchlist` the API resolves for a caller.
+    pub const PERMISSION_CONFIG: PermissionConfig = PermissionConfig {
+        min_read_level: ReadLevel::Read,
+        min_create_level: CreateLevel::Create,
+        min_update_level: UpdateLevel::Update,
+        min_delete_level: DeleteLevel::Delete,
+    };
+
+    pub struct WatchlistApiResource;
+
+    impl ApiResource for WatchlistApiResource {
+        const NAME: &'static str = "watchlist";
+        const PERMISSION_CONFIG: PermissionConfig = PERMISSION_CONFIG;
+        type Owner = RegisteredUser;
+        type Service = Box<dyn WatchlistEntryServiceMethods + Send>;
+
+        fn service(
+            state: &AppState,
+            owner: RegisteredUser,
+            permission_set: PermissionSet,
+        ) -> Self::Service {
+            WatchlistEntryServiceFactory::build(
+                owner,
+                Arc::clone(&state.connection_pool),
+                permission_set,
+            )
+        }
+    }
+
+    pub type WatchlistApiState = ResourceContext<WatchlistApiResource>;
+
+    /// Checks a target price is positive, as prices are.
+    pub fn validate_target_price(target_price: Decimal) -> Result<(), ApiError> {
+        if target_price <= Decimal::ZERO {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "The `target_price` must be positive.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks both assets of a pair exist and are not the same one.
+    pub async fn validate_pair(
+        state: &AppState,
+        asset_id: AssetId,
+        quote_asset_id: AssetId,
+    ) -> Result<(), ApiError> {
+        if asset_id == quote_asset_id {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "An asset can't be priced in itself.",
+            ));
+        }
+        for id in [asset_id, quote_asset_id] {
+            asset_scale(state, id).await?;
+        }
+        Ok(())
+    }
+
+    async fn server_fn_handler(
+        State(state): State<AppState>,
+        req: Request<Body>,
+    ) -> impl IntoResponse {
+        let path = match req.uri().to_string() {
+            val if val == "/" => "".to_string(),
+            val if val.starts_with("/?") => val.trim_start_matches("/").to_string(),
+            _ => "/".to_string(),
+        };
+        let (mut req, parts) = generate_request_and_parts(req);
+        *req.uri_mut() = match server_fn_uri(format!("/api/watchlist{path}")) {
+            Ok(uri) => uri,
+            Err(e) => return e.into_response(),
+        };
+        handle_server_fns_with_context(
+            {
+                let app_state = state.clone();
+                move || {
+                    provide_context(app_state.clone());
+                    provide_context(parts.clone());
+                }
+            },
+            req,
+        )
+        .await
+        .into_response()
+    }
+
+    pub struct WatchlistApi;
+
+    impl Api for WatchlistApi {
+        fn endpoints() -> Vec<(Method, &'static str)> {
+            vec![
+                (Method::GET, "/"),
+                (Method::POST, "/"),
+                (Method::GET, "/{id}"),
+                (Method::PATCH, "/{id}"),
+                (Method::DELETE, "/{id}"),
+            ]
+        }
+
+        fn router(state: AppState) -> Router<AppState> {
+            Router::new()
+                .route(
+                    "/",
+                    axum::routing::get(server_fn_handler).post(server_fn_handler),
+                )
+                .route(
+                    "/{id}",
+                    axum::routing::get(server_fn_handler)
+                        .patch(server_fn_handler)
+                        .delete(server_fn_handler),
+                )
+                .layer(
+                    ServiceBuilder::new()
+                        .layer(AsyncRequireAuthorizationLayer::new(Authenticator))
+                        .layer(from_fn_with_state(state.clone(), set_user_groups)),
+                )
+                .with_state(state)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/watchlist",
+    tag = "Watchlist",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The watchlist of the user, with the latest price of each entry.", body = WatchlistEntryGetListResponse)
+    ),
+))]
+#[server(
+    name = WatchlistApiGetList,
+    prefix = "/api",
+    endpoint = "/watchlist",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get_list() -> Result<WatchlistEntryGetListResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<WatchlistApiState, _>(&state).await?;
+
+    let watchlist_entries = api_state
+        .service
+        .get_list(0, None, WatchlistEntryFilter::default())
+        .await?;
+    Ok(watchlist_entries.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    get,
+    path = "/api/watchlist/{id}",
+    tag = "Watchlist",
+    params(WatchlistEntryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 200, description = "The watchlist entry.", body = WatchlistEntryGetResponse),
+        (status = 404, description = "The watchlist entry was not found."),
+    ),
+))]
+#[server(
+    name = WatchlistApiGet,
+    prefix = "/api",
+    endpoint = "watchlist/",
+    input = GetUrl,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn get() -> Result<WatchlistEntryGetResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<WatchlistApiState, _>(&state).await?;
+    let PathWatchlistEntryId { id } = extract_path().await?;
+
+    let watchlist_entry = api_state.service.get(id).await?;
+    Ok(watchlist_entry.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    post,
+    path = "/api/watchlist",
+    tag = "Watchlist",
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = CreateRequest,
+    responses(
+        (status = 201, description = "The newly created watchlist entry.", body = WatchlistEntryCreateResponse),
+        (status = 400, description = "The target price or the pair of assets is invalid."),
+        (status = 404, description = "An asset was not found."),
+    ),
+))]
+#[server(
+    name = WatchlistApiCreate,
+    prefix = "/api",
+    endpoint = "watchlist",
+    input = Json,
+    output = Json,
+    client = ApiClient,
+)]
+pub async fn create(
+    #[server(flatten)] create_request: CreateRequest,
+) -> Result<WatchlistEntryCreateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<WatchlistApiState, _>(&state).await?;
+    let registered_user = extract_with_state::<RegisteredUser, _>(&state).await?;
+
+    if let Some(target_price) = create_request.target_price {
+        validate_target_price(target_price)?;
+    }
+    validate_pair(
+        &state,
+        create_request.asset_id,
+        create_request.quote_asset_id,
+    )
+    .await?;
+
+    let watchlist_entry = api_state
+        .service
+        .create(WatchlistEntryCreate {
+            user_id: registered_user.id(),
+            asset_id: create_request.asset_id,
+            quote_asset_id: create_request.quote_asset_id,
+            target_price: create_request.target_price,
+            direction: create_request.direction,
+            channel: create_request.channel,
+            repeating: create_request.repeating,
+        })
+        .await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(WatchlistEntryCreateResponse::status());
+    provide_context(response_opts);
+    Ok(watchlist_entry.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    patch,
+    path = "/api/watchlist/{id}",
+    tag = "Watchlist",
+    params(WatchlistEntryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    request_body = UpdateRequest,
+    responses(
+        (status = 200, description = "The updated watchlist entry.", body = WatchlistEntryUpdateResponse),
+        (status = 400, description = "The target price is invalid."),
+        (status = 404, description = "The watchlist entry was not found."),
+    ),
+))]
+#[server(
+    name = WatchlistApiUpdate,
+    prefix = "/api",
+    endpoint = "watchlist/",
+    input = PatchJson,
+    output = PatchJson,
+    client = ApiClient,
+)]
+pub async fn update(
+    #[server(flatten)] update_request: UpdateRequest,
+) -> Result<WatchlistEntryUpdateResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<WatchlistApiState, _>(&state).await?;
+    let PathWatchlistEntryId { id } = extract_path().await?;
+
+    let mut watchlist_entry = api_state.service.get(id).await?;
+    if let Some(target_price) = update_request.target_price {
+        validate_target_price(target_price)?;
+        watchlist_entry.target_price = Some(target_price);
+    }
+    if let Some(direction) = update_request.direction {
+        watchlist_entry.direction = direction;
+    }
+    // A new target is a new thing to be told about.
+    if update_request.target_price.is_some() || update_request.direction.is_some() {
+        watchlist_entry.triggered_at = None;
+        watchlist_entry.triggered_price = None;
+    }
+    if let Some(channel) = update_request.channel {
+        watchlist_entry.channel = channel;
+    }
+    if let Some(repeating) = update_request.repeating {
+        watchlist_entry.repeating = repeating;
+    }
+
+    let watchlist_entry = api_state.service.update(id, watchlist_entry).await?;
+    Ok(watchlist_entry.into())
+}
+
+#[cfg_attr(feature = "ssr", utoipa::path(
+    delete,
+    path = "/api/watchlist/{id}",
+    tag = "Watchlist",
+    params(WatchlistEntryId),
+    security(
+        ("OpenIDConnect" = ["groups", "email"])
+    ),
+    responses(
+        (status = 204, description = "The watchlist entry was successfully deleted."),
+        (status = 404, description = "The watchlist entry was not found.", body = ApiErrorResponse, content_type = "application/json", example = json!(ApiErrorResponse {
+            code: 4040,
+            message: "Not found.".to_string()
+        })),
+    ),
+))]
+#[server(
+    name = WatchlistApiDelete,
+    prefix = "/api",
+    endpoint = "watchlist/",
+    input = DeleteUrl,
+    client = ApiClient,
+)]
+pub async fn delete() -> Result<DeleteResponse, ApiError> {
+    let state = expect_context::<AppState>();
+    let api_state = extract_with_state::<WatchlistApiState, _>(&state).await?;
+    let PathWatchlistEntryId { id } = extract_path().await?;
+
+    api_state.service.delete(id).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.set_status(DeleteResponse::status());
+    provide_context(response_opts);
+    Ok(DeleteResponse)
+}
@@ -25,16 +25,10 @@ pub fn Accounts() -> impl IntoView {
             if auth_signal.is_none() {
                 return vec![];
             }
-            account_get_list(
-                AccountGetListRequest {
-                    name: None,
-                    institution_id: None,
-                },
-                Pagination::default(),
-            )
-            .await
-            .expect("Failed to get accounts")
-            .accounts
+            account_get_list(AccountGetListRequest::default(), Pagination::default())
+                .await
+                .expect("Failed to get accounts")
+                .accounts
         },
     );
 
@@ -44,16 +38,13 @@ pub fn Accounts() -> impl IntoView {
             if auth_signal.is_none() {
                 return HashMap::new();
             }
-            institution_get_list(
-                InstitutionGetListRequest { name: None },
-                Pagination::default(),
-            )
-            .await
-            .expect("Failed to get institutions")
-            .institutions
-            .into_iter()
-            .map(|i| (i.id, i))
-            .collect()
+            institution_get_list(InstitutionGetListRequest::default(), Pagination::default())
+                .await
+                .expect("Failed to get institutions")
+                .institutions
+                .into_iter()
+                .map(|i| (i.id, i))
+                .collect()
         },
     );
 
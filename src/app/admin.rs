@@ -0,0 +1,81 @@
+use leptos::prelude::*;
+
+use crate::{
+    api::admin_api::stats,
+    app::toast::Toasts,
+    schema::admin::{AdminStatsResponse, StatsRequest},
+};
+
+/// The system wide counts and the gauges of the server, for admins.
+#[component]
+pub fn AdminStats() -> impl IntoView {
+    let rw_exact = RwSignal::new(false);
+    let toasts = expect_context::<Toasts>();
+
+    let stats = LocalResource::new(move || {
+        let exact = rw_exact.get();
+        async move {
+            match stats(StatsRequest { exact: Some(exact) }).await {
+                Ok(response) => Some(response),
+                Err(e) => {
+                    toasts.error(&e);
+                    None
+                }
+            }
+        }
+    });
+
+    let rows = move |stats: AdminStatsResponse| {
+        let transactions = if stats.transactions_exact {
+            stats.transactions.to_string()
+        } else {
+            format!("~{}", stats.transactions)
+        };
+        [
+            ("Users", stats.users.to_string()),
+            ("Accounts", stats.accounts.to_string()),
+            ("Transactions", transactions),
+            ("Active sessions", stats.active_sessions.to_string()),
+            ("Pending extractions", stats.pending_extractions.to_string()),
+            ("Query timeouts", stats.query_timeouts.to_string()),
+            ("Panics", stats.panics.to_string()),
+            ("Throttled logins", stats.login_throttled.to_string()),
+            (
+                "Pool connections",
+                format!("{} ({} idle)", stats.pool.size, stats.pool.idle),
+            ),
+            ("Cached assets", stats.caches.assets.to_string()),
+            ("Cached institutions", stats.caches.institutions.to_string()),
+        ]
+        .into_iter()
+        .map(|(name, value)| {
+            view! {
+                <tr class="border border-ctp-surface2 odd:bg-ctp-surface0 even:bg-ctp-surface1">
+                    <td class="border border-ctp-surface2 px-2">{name}</td>
+                    <td class="border border-ctp-surface2 px-2 text-right">{value}</td>
+                </tr>
+            }
+        })
+        .collect_view()
+    };
+
+    view! {
+        <div class="container mx-auto px-4 py-8 text-ctp-text">
+            <div class="mb-4 flex flex-row gap-2">
+                <h1 class="flex-auto text-xl">"System"</h1>
+                <Show when=move || !rw_exact.get()>
+                    <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=move |_| rw_exact.set(true)>
+                        "Count exactly"
+                    </button>
+                </Show>
+            </div>
+            <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+                <table class="w-full table-auto bg-ctp-base">
+                    <tbody>
+                        {move || stats.get().flatten().map(rows)}
+                    </tbody>
+                </table>
+            </Suspense>
+        </div>
+    }
+}
@@ -0,0 +1,94 @@
+use leptos::prelude::*;
+
+use crate::{
+    api::announcement_api::get_active,
+    app::AuthToken,
+    model::announcement::{AnnouncementId, AnnouncementSeverity},
+    schema::announcement::ActiveAnnouncement,
+};
+
+/// The local storage key of the dismissed announcement ids.
+const DISMISSED_KEY: &str = "dismissed_announcements";
+
+fn class(severity: AnnouncementSeverity) -> &'static str {
+    match severity {
+        AnnouncementSeverity::Info => "border-ctp-blue",
+        AnnouncementSeverity::Warning => "border-ctp-yellow",
+        AnnouncementSeverity::Critical => "border-ctp-red",
+    }
+}
+
+fn load_dismissed() -> Vec<AnnouncementId> {
+    window()
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(DISMISSED_KEY).ok().flatten())
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+fn save_dismissed(dismissed: &[AnnouncementId]) {
+    if let Some(storage) = window().local_storage().ok().flatten()
+        && let Ok(value) = serde_json::to_string(dismissed)
+    {
+        let _ = storage.set_item(DISMISSED_KEY, &value);
+    }
+}
+
+/// Shows the active announcements the user hasn't dismissed. Dismissals are
+/// remembered per announcement in local storage, and the ids of
+/// announcements that are no longer active are dropped on load.
+#[component]
+pub fn AnnouncementBanner() -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let rw_dismissed = RwSignal::new(Vec::<AnnouncementId>::new());
+
+    let announcements = LocalResource::new(move || {
+        let auth_token = rw_auth_token.get();
+        async move {
+            if auth_token.is_none() {
+                return Vec::<ActiveAnnouncement>::new();
+            }
+            let announcements = get_active()
+                .await
+                .map(|response| response.announcements)
+                .unwrap_or_default();
+            let dismissed = load_dismissed()
+                .into_iter()
+                .filter(|id| announcements.iter().any(|x| x.id == *id))
+                .collect::<Vec<_>>();
+            save_dismissed(&dismissed);
+            rw_dismissed.set(dismissed);
+            announcements
+        }
+    });
+
+    let dismiss = move |id: AnnouncementId| {
+        rw_dismissed.update(|dismissed| dismissed.push(id));
+        rw_dismissed.with_untracked(|dismissed| save_dismissed(dismissed));
+    };
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || announcements.get().map(|announcements| {
+                let dismissed = rw_dismissed.get();
+                announcements
+                    .into_iter()
+                    .filter(|announcement| !dismissed.contains(&announcement.id))
+                    .map(|announcement| {
+                        let id = announcement.id;
+                        view! {
+                            <div role="status" class=format!("mx-1 mb-1 flex flex-row rounded-lg border-l-4 bg-ctp-surface0 px-4 py-2 text-ctp-text {}", class(announcement.severity))>
+                                <span class="flex-auto pr-4">{announcement.message}</span>
+                                <button class="cursor-pointer text-ctp-overlay1 hover:text-ctp-text" aria-label="Dismiss" on:click=move |_| dismiss(id)>
+                                    "×"
+                                </button>
+                            </div>
+                        }
+                    })
+                    .collect_view()
+            })}
+        </Suspense>
+    }
+}
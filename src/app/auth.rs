@@ -1,37 +1,63 @@
 use crate::{
-    api::ApiError,
-    app::{AuthToken, ExpiresIn},
+    api::{ApiError, ClientErrorCode},
+    app::{
+        AuthToken, ExpiresIn, TokenClock,
+        guard::{NEXT_PARAM, remember_destination, take_destination},
+        toast::Toasts,
+    },
 };
 use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use chrono::Utc;
 use leptos::{prelude::*, reactive::traits::Get, server_fn::codec::GetUrl};
 use leptos_router::{
     NavigateOptions,
-    hooks::{use_navigate, use_query},
+    hooks::{use_navigate, use_query, use_query_map},
     params::Params,
 };
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::{
+    fmt::{Debug, Formatter},
+    time::Duration,
+};
 
 pub const REFRESH_TOKEN_MAX_AGE: i64 = 86400;
 pub const REFRESH_TOKEN_INTERVAL: i64 = 3600;
+/// How long before its token expires a session is refreshed.
+pub const REFRESH_MARGIN_SECONDS: i64 = 30;
+/// How much of the user agent of a client is kept in the login history.
+#[cfg(feature = "ssr")]
+const MAX_USER_AGENT_LENGTH: usize = 512;
 
 #[cfg(feature = "ssr")]
 pub mod ssr_imports {
     pub use crate::{
-        api::AppState,
+        api::{AppState, rate_limit::RateLimiter},
         authentication::{
+            api_key::hash_secret,
             authenticated_token::{AuthenticatedToken, Claims},
             authenticator::Authenticator,
+            client_address::ClientAddress,
+            csrf,
+        },
+        demo,
+        model::{
+            csrf_token::CsrfState,
+            login_event::{LoginEventCreate, LoginEventType},
+            user::{UserCreate, UserId},
+            user_session::{UserSession, UserSessionCreate},
         },
-        model::user::UserCreate,
         resource::{
-            CreateRepository, DeleteRepository, csrf_token_repository::CsrfTokenRepository,
-            user_repository::UserRepository,
+            CreateRepository, RepositoryError, csrf_token_repository::CsrfTokenRepository,
+            deadline, login_event_repository::LoginEventRepository,
+            user_repository::UserRepository, user_session_repository::UserSessionRepository,
         },
+        schema::text::{USER_NAME, escape_output},
     };
     pub use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
     pub use http::{
-        HeaderValue,
-        header::{SET_COOKIE, X_CONTENT_TYPE_OPTIONS},
+        Extensions, HeaderMap, HeaderValue,
+        header::{CACHE_CONTROL, SET_COOKIE, USER_AGENT, X_CONTENT_TYPE_OPTIONS},
     };
     pub use leptos_axum::{ResponseOptions, extract};
     pub use oauth2::{
@@ -39,6 +65,7 @@ pub mod ssr_imports {
         Scope, TokenResponse,
     };
     pub use reqwest::redirect::Policy;
+    pub use std::time::Instant;
     pub use time::{Date, OffsetDateTime};
     pub use tracing::{debug, error, warn};
 }
@@ -83,7 +110,7 @@ pub async fn sso(code_challenge: String) -> Result<String, ApiError> {
     let token_repository = CsrfTokenRepository;
     token_repository
         .create(
-            state.connection_pool.begin().await.map_err(|e| {
+            deadline::begin(&state.connection_pool).await.map_err(|e| {
                 error!("{e}");
                 ApiError::ServerError
             })?,
@@ -114,17 +141,30 @@ pub async fn handle_auth_redirect(
     let oauth_client = app_state.oauth_client;
     let token_repository = CsrfTokenRepository;
     token_repository
-        .delete(
-            app_state.connection_pool.begin().await.map_err(|e| {
-                error!("{e}");
-                ApiError::ServerError
-            })?,
-            state,
+        .consume(
+            deadline::begin(&app_state.connection_pool)
+                .await
+                .map_err(|e| {
+                    error!("{e}");
+                    ApiError::ServerError
+                })?,
+            CsrfState(state),
+            csrf::issued_after(),
         )
         .await
-        .map_err(|e| {
-            error!("{e}");
-            ApiError::ServerError
+        .map_err(|e| match e {
+            RepositoryError::NotFound => ApiError::client(
+                ClientErrorCode::InvalidSignIn,
+                "This sign in was not started here or has expired. Please sign in again.",
+            ),
+            RepositoryError::AlreadyConsumed => ApiError::client(
+                ClientErrorCode::InvalidSignIn,
+                "This sign in was already used. Please sign in again.",
+            ),
+            e => {
+                error!("{e}");
+                ApiError::ServerError
+            }
         })?;
 
     let http_client = reqwest::ClientBuilder::new()
@@ -160,18 +200,21 @@ pub async fn handle_auth_redirect(
             ApiError::ServerError
         })?;
     if !auth_token.email_verified() {
-        return Err(ApiError::ClientError(
-            "Email address is not verified.".into(),
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidSignIn,
+            "Email address is not verified.",
         ));
     }
 
     let user_repository = UserRepository;
     let user = user_repository
         .get_by_iss_and_sub(
-            app_state.connection_pool.begin().await.map_err(|e| {
-                error!("{e}");
-                ApiError::ServerError
-            })?,
+            deadline::begin(&app_state.connection_pool)
+                .await
+                .map_err(|e| {
+                    error!("{e}");
+                    ApiError::ServerError
+                })?,
             auth_token.iss().into(),
             auth_token.sub().into(),
         )
@@ -181,31 +224,38 @@ pub async fn handle_auth_redirect(
             ApiError::ServerError
         })?;
 
-    if user.is_none() {
-        // Register a new user
-        let _ = user_repository
+    let user = match user {
+        Some(user) => user,
+        // Register a new user. A name from the provider that would not pass
+        // as a display name is dropped, so onboarding asks for one instead.
+        None => user_repository
             .create(
-                app_state.connection_pool.begin().await.map_err(|e| {
-                    error!("{e}");
-                    ApiError::ServerError
-                })?,
+                deadline::begin(&app_state.connection_pool)
+                    .await
+                    .map_err(|e| {
+                        error!("{e}");
+                        ApiError::ServerError
+                    })?,
                 UserCreate {
                     name: auth_token
                         .preferred_username()
                         .or(auth_token.name())
-                        .cloned()
-                        .unwrap_or("".to_owned()),
+                        .and_then(|name| USER_NAME.sanitize(name).ok())
+                        .unwrap_or_default(),
                     email: auth_token.email().into(),
                     sub: auth_token.sub().into(),
                     iss: auth_token.iss().into(),
+                    email_verified: Some(auth_token.email_verified()),
                 },
             )
             .await
             .map_err(|e| {
                 error!("{e}");
                 ApiError::ServerError
-            })?;
-    }
+            })?,
+    };
+    record_login_event(user.id, auth_token.iss(), LoginEventType::Login).await;
+    start_session(user.id, &refresh_token).await;
 
     let expires_in = token_response
         .expires_in()
@@ -232,20 +282,248 @@ pub async fn handle_auth_redirect(
     Ok((access_token, expires_in))
 }
 
+/// The address and user agent of the client of the request, in the form the
+/// login history and the sessions keep them.
+#[cfg(feature = "ssr")]
+async fn client_details() -> (Option<String>, Option<String>) {
+    use ssr_imports::*;
+
+    let headers = extract::<HeaderMap>().await.unwrap_or_default();
+    let ip_address = ClientAddress::from_env().recorded_ip(&headers);
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            escape_output(v)
+                .chars()
+                .take(MAX_USER_AGENT_LENGTH)
+                .collect()
+        });
+    (ip_address, user_agent)
+}
+
+/// Adds an event to the login history of a user. The history is only
+/// informative, so failing to record it does not fail the request.
+#[cfg(feature = "ssr")]
+async fn record_login_event(
+    user_id: ssr_imports::UserId,
+    issuer: &str,
+    event_type: ssr_imports::LoginEventType,
+) {
+    use ssr_imports::*;
+
+    let app_state = expect_context::<AppState>();
+    let (ip_address, user_agent) = client_details().await;
+    let login_event = LoginEventCreate {
+        user_id,
+        event_type,
+        issuer: issuer.to_owned(),
+        ip_address,
+        user_agent,
+    };
+    let result = match deadline::begin(&app_state.connection_pool).await {
+        Ok(session) => LoginEventRepository
+            .create(session, login_event)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        warn!("Failed to record a {event_type:?} event: {e}");
+    }
+}
+
+/// Adds an event to the login history of the user an id token was issued
+/// to, if they are registered, and returns who they are.
+#[cfg(feature = "ssr")]
+async fn record_token_event(
+    id_token: &str,
+    event_type: ssr_imports::LoginEventType,
+) -> Option<ssr_imports::UserId> {
+    use ssr_imports::*;
+
+    let app_state = expect_context::<AppState>();
+    let auth_token = match Authenticator::authenticate(&format!("Bearer {id_token}")).await {
+        Ok(auth_token) => auth_token,
+        Err(e) => {
+            warn!("Failed to record a {event_type:?} event: {e}");
+            return None;
+        }
+    };
+    let user = match deadline::begin(&app_state.connection_pool).await {
+        Ok(session) => UserRepository
+            .get_by_iss_and_sub(session, auth_token.iss().into(), auth_token.sub().into())
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match user {
+        Ok(Some(user)) => {
+            record_login_event(user.id, auth_token.iss(), event_type).await;
+            Some(user.id)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            warn!("Failed to record a {event_type:?} event: {e}");
+            None
+        }
+    }
+}
+
+/// Starts the refresh token chain of a new login. Sessions only tell the
+/// user where they are signed in, so failing to record one does not fail
+/// the login.
+#[cfg(feature = "ssr")]
+async fn start_session(user_id: ssr_imports::UserId, refresh_token: &str) {
+    use ssr_imports::*;
+
+    let app_state = expect_context::<AppState>();
+    let (ip_address, user_agent) = client_details().await;
+    let user_session = UserSessionCreate {
+        user_id,
+        token_hash: hash_secret(refresh_token),
+        ip_address,
+        user_agent,
+    };
+    let result = match deadline::begin(&app_state.connection_pool).await {
+        Ok(session) => UserSessionRepository
+            .create(session, user_session)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        warn!("Failed to start a session: {e}");
+    }
+}
+
+/// The session a refresh token belongs to, if it is the latest of a chain.
+#[cfg(feature = "ssr")]
+async fn find_session(refresh_token: &str) -> Result<Option<ssr_imports::UserSession>, ApiError> {
+    use ssr_imports::*;
+
+    let app_state = expect_context::<AppState>();
+    let session = deadline::begin(&app_state.connection_pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?;
+    UserSessionRepository
+        .get_by_token_hash(session, &hash_secret(refresh_token))
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })
+}
+
+/// Revokes the session of a logout. The session is revoked here as well as
+/// by the provider, so a logout is not undone by a provider that keeps
+/// honoring the token.
+#[cfg(feature = "ssr")]
+async fn end_session(user_session: ssr_imports::UserSession) {
+    use ssr_imports::*;
+
+    let app_state = expect_context::<AppState>();
+    let result = match deadline::begin(&app_state.connection_pool).await {
+        Ok(session) => UserSessionRepository
+            .revoke(session, user_session.id)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        warn!("Failed to end session {}: {e}", user_session.id);
+    }
+}
+
+/// Moves a session on to the refresh token that replaced its last one.
+/// Tokens issued before sessions were recorded start a chain of their own.
+///
+/// A session revoked while its tokens were being refreshed refuses the new
+/// ones, which are never handed out.
+#[cfg(feature = "ssr")]
+async fn continue_session(
+    user_session: Option<ssr_imports::UserSession>,
+    user_id: Option<ssr_imports::UserId>,
+    refresh_token: &str,
+) -> Result<(), ApiError> {
+    use ssr_imports::*;
+
+    let Some(user_session) = user_session else {
+        if let Some(user_id) = user_id {
+            start_session(user_id, refresh_token).await;
+        }
+        return Ok(());
+    };
+    let app_state = expect_context::<AppState>();
+    let (ip_address, user_agent) = client_details().await;
+    let session = deadline::begin(&app_state.connection_pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::ServerError
+        })?;
+    match UserSessionRepository
+        .rotate(
+            session,
+            user_session.id,
+            hash_secret(refresh_token),
+            ip_address,
+            user_agent,
+        )
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(RepositoryError::NotFound) => {
+            warn!("Refused a refresh of revoked session {}", user_session.id);
+            Err(ApiError::Forbidden)
+        }
+        Err(e) => {
+            error!("{e}");
+            Err(ApiError::ServerError)
+        }
+    }
+}
+
 fn get_code_challenge(verifier: &str) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(verifier.as_bytes());
     hasher.finalize().into()
 }
 
+/// Whether the request has a refresh token cookie. Only a refresh tells
+/// whether the token is still good, but a request without one is surely
+/// not signed in.
+#[server(
+    name = SessionCookie,
+    prefix = "/login",
+    endpoint = "/session",
+    input = GetUrl,
+)]
+pub async fn has_session() -> Result<bool, ApiError> {
+    use ssr_imports::*;
+
+    let cookie_jar = extract::<CookieJar>().await?;
+    Ok(cookie_jar
+        .get("refresh_token")
+        .is_some_and(|x| !x.value().is_empty()))
+}
+
 #[component]
 pub fn Login() -> impl IntoView {
     let auth = ServerAction::<Sso>::new();
+    let toasts = expect_context::<Toasts>();
+    let query = use_query_map();
 
-    Effect::new(move |_| {
-        if let Some(Ok(redirect)) = auth.value().get() {
-            window().location().set_href(&redirect).unwrap();
-        }
+    Effect::new(move |_| match auth.value().get() {
+        Some(Ok(redirect)) => window().location().set_href(&redirect).unwrap(),
+        Some(Err(e)) => toasts.error(&e),
+        None => {}
     });
 
     view! {
@@ -259,6 +537,7 @@ pub fn Login() -> impl IntoView {
 
             let storage = window().local_storage().expect("Failed to get local storage API").expect("No local storage");
             storage.set_item("pkce_verifier", &string_verifier).expect("Failed to save verifier");
+            remember_destination(query.with_untracked(|x| x.get(NEXT_PARAM)));
 
             let hash = get_code_challenge(&string_verifier);
             let code_challenge = BASE64_URL_SAFE_NO_PAD.encode(hash);
@@ -284,22 +563,34 @@ pub fn HandleAuth() -> impl IntoView {
 
     let rw_auth_token = expect_context::<AuthToken>().0;
     let rw_expires_in = expect_context::<ExpiresIn>().0;
-
-    Effect::new(move |_| {
-        if let Some(Ok((auth_token, expires_in))) = handle_sso_redirect.value().get() {
+    let rw_token_clock = expect_context::<TokenClock>().0;
+    let toasts = expect_context::<Toasts>();
+
+    Effect::new(move |_| match handle_sso_redirect.value().get() {
+        Some(Ok((auth_token, expires_in))) => {
+            // The login doesn't say when the token was issued, so its
+            // expiry is counted from now.
+            rw_token_clock.set(ServerClock::default());
             rw_auth_token.set(Some(auth_token));
             rw_expires_in.set(expires_in);
-            navigate("/home", NavigateOptions::default());
+            toasts.success("Logged in.");
+            navigate(&take_destination(), NavigateOptions::default());
         }
+        Some(Err(e)) => toasts.error(&e),
+        None => {}
     });
 
+    let navigate_home = use_navigate();
     Effect::new(move |_| {
         let storage = window()
             .local_storage()
             .expect("Failed to get local storage API")
             .expect("No local storage");
+        // Without a verifier no sign in was started here, or it was already
+        // finished, as when going back to the redirect.
         let Some(code_verifier) = storage.get_item("pkce_verifier").expect("No pkce_verifier")
         else {
+            navigate_home("/home", NavigateOptions::default());
             return;
         };
         storage
@@ -320,23 +611,108 @@ pub fn HandleAuth() -> impl IntoView {
     view! {}
 }
 
+/// When the current token was issued by the server's clock, and how far
+/// that clock is off from ours, so its refresh is scheduled by the server's
+/// clock even when ours is skewed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ServerClock {
+    /// When the token was issued, in seconds since the epoch by the
+    /// server's clock. Zero when it isn't known.
+    pub issued_at: i64,
+    /// How many seconds the server's clock is ahead of ours
+    pub skew: i64,
+}
+
+impl ServerClock {
+    /// The clock of a token issued at `server_time`, received at
+    /// `local_time` by our clock.
+    pub fn observe(server_time: i64, local_time: i64) -> Self {
+        if server_time == 0 {
+            return Self::default();
+        }
+        Self {
+            issued_at: server_time,
+            skew: server_time - local_time,
+        }
+    }
+
+    /// How long to wait at `local_time` before refreshing a token that
+    /// expires `expires_in` seconds after it was issued, leaving
+    /// [`REFRESH_MARGIN_SECONDS`] to spare. Without an issue time the
+    /// expiry is counted from now.
+    pub fn refresh_delay(&self, expires_in: i64, local_time: i64) -> Duration {
+        let remaining = if self.issued_at == 0 {
+            expires_in
+        } else {
+            self.issued_at + expires_in - (local_time + self.skew)
+        };
+        Duration::from_secs((remaining - REFRESH_MARGIN_SECONDS).max(0) as u64)
+    }
+}
+
+/// The tokens issued by a refresh.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RefreshResponse {
+    pub access_token: String,
+    pub expires_in: i64,
+    /// When the server issued the tokens, in seconds since the epoch, by
+    /// its own clock
+    #[serde(default)]
+    pub server_time: i64,
+    /// The rotated refresh token. Only returned to clients using the
+    /// `Refresh-Token` header, browsers get it as a cookie instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+}
+
+impl Debug for RefreshResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefreshResponse")
+            .field("access_token", &"<redacted>")
+            .field("expires_in", &self.expires_in)
+            .field("server_time", &self.server_time)
+            .field(
+                "refresh_token",
+                &self.refresh_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
 #[server(
     name = SsoRefresh,
     prefix = "/login",
     endpoint = "/refresh",
 )]
-pub async fn refresh_token() -> Result<(String, i64), ApiError> {
+pub async fn refresh_token() -> Result<RefreshResponse, ApiError> {
     use ssr_imports::*;
 
-    let cookie_jar = extract::<CookieJar>().await?;
+    let headers = extract::<HeaderMap>().await?;
+    let header_refresh_token = expect_context::<AppState>()
+        .header_refresh
+        .refresh_token(&headers)?;
+    let refresh_token = match &header_refresh_token {
+        Some(refresh_token) => oauth2::RefreshToken::new(refresh_token.clone()),
+        None => {
+            let cookie_jar = extract::<CookieJar>().await?;
+            oauth2::RefreshToken::new(
+                cookie_jar
+                    .get("refresh_token")
+                    .ok_or(ApiError::Forbidden)?
+                    .value()
+                    .to_string(),
+            )
+        }
+    };
 
-    let refresh_token = oauth2::RefreshToken::new(
-        cookie_jar
-            .get("refresh_token")
-            .ok_or(ApiError::Forbidden)?
-            .value()
-            .to_string(),
-    );
+    // A revoked chain is refused before the provider is asked for tokens.
+    let user_session = find_session(refresh_token.secret()).await?;
+    if let Some(user_session) = &user_session
+        && user_session.revoked_at.is_some()
+    {
+        warn!("Refused a refresh of revoked session {}", user_session.id);
+        return Err(ApiError::Forbidden);
+    }
 
     let oauth_client = expect_context::<AppState>().oauth_client;
     let http_client = reqwest::ClientBuilder::new()
@@ -344,15 +720,29 @@ pub async fn refresh_token() -> Result<(String, i64), ApiError> {
         .build()
         .expect("Failed to build reqwest client");
 
+    // A token the provider refuses is the client's doing, and counts
+    // against it in the login throttle.
     let token_response = oauth_client
         .exchange_refresh_token(&refresh_token)
         .request_async(&http_client)
         .await
-        .map_err(|e| {
-            error!("{e}");
-            ApiError::ServerError
+        .map_err(|e| match e {
+            oauth2::RequestTokenError::ServerResponse(e) => {
+                warn!("Refused a refresh token: {e}");
+                ApiError::Forbidden
+            }
+            e => {
+                error!("{e}");
+                ApiError::ServerError
+            }
         })?;
 
+    let user_id = record_token_event(
+        &token_response.extra_fields().id_token,
+        LoginEventType::Refresh,
+    )
+    .await;
+
     let access_token = token_response.access_token().secret().clone();
     let expires_in = token_response
         .expires_in()
@@ -362,6 +752,20 @@ pub async fn refresh_token() -> Result<(String, i64), ApiError> {
         .refresh_token()
         .expect("Missing refresh token in response.")
         .secret();
+    continue_session(user_session, user_id, refresh_token).await?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.append_header(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+
+    if header_refresh_token.is_some() {
+        response_opts.insert_header(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        return Ok(RefreshResponse {
+            access_token,
+            expires_in,
+            server_time: Utc::now().timestamp(),
+            refresh_token: Some(refresh_token.clone()),
+        });
+    }
 
     let cookie: Cookie = Cookie::build(("refresh_token", refresh_token))
         .path("/")
@@ -371,7 +775,6 @@ pub async fn refresh_token() -> Result<(String, i64), ApiError> {
         .max_age(time::Duration::seconds(REFRESH_TOKEN_MAX_AGE))
         .into();
 
-    let response_opts = expect_context::<ResponseOptions>();
     response_opts.insert_header(
         SET_COOKIE,
         HeaderValue::from_str(&cookie.to_string()).map_err(|e| {
@@ -379,9 +782,13 @@ pub async fn refresh_token() -> Result<(String, i64), ApiError> {
             ApiError::ServerError
         })?,
     );
-    response_opts.append_header(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
 
-    Ok((access_token, expires_in))
+    Ok(RefreshResponse {
+        access_token,
+        expires_in,
+        server_time: Utc::now().timestamp(),
+        refresh_token: None,
+    })
 }
 
 #[server(
@@ -393,10 +800,24 @@ pub async fn logout() -> Result<(), ApiError> {
     use ssr_imports::*;
 
     // Use the refresh token to invalidate it.
+    let headers = extract::<HeaderMap>().await?;
+    let header_refresh_token = expect_context::<AppState>()
+        .header_refresh
+        .refresh_token(&headers)?;
     let cookie_jar = extract::<CookieJar>().await?;
 
-    if let Some(rt) = cookie_jar.get("refresh_token") {
-        let refresh_token = oauth2::RefreshToken::new(rt.value().to_string());
+    let presented = match &header_refresh_token {
+        Some(refresh_token) => Some(refresh_token.clone()),
+        None => cookie_jar
+            .get("refresh_token")
+            .map(|rt| rt.value().to_string()),
+    };
+
+    if let Some(refresh_token) = presented {
+        if let Some(user_session) = find_session(&refresh_token).await.ok().flatten() {
+            end_session(user_session).await;
+        }
+        let refresh_token = oauth2::RefreshToken::new(refresh_token);
 
         let oauth_client = expect_context::<AppState>().oauth_client;
         let http_client = reqwest::ClientBuilder::new()
@@ -404,7 +825,7 @@ pub async fn logout() -> Result<(), ApiError> {
             .build()
             .expect("Failed to build reqwest client");
 
-        let _ = oauth_client
+        let token_response = oauth_client
             .exchange_refresh_token(&refresh_token)
             .request_async(&http_client)
             .await
@@ -413,6 +834,18 @@ pub async fn logout() -> Result<(), ApiError> {
                 ApiError::ServerError
             })
             .ok();
+        if let Some(token_response) = token_response {
+            record_token_event(
+                &token_response.extra_fields().id_token,
+                LoginEventType::Logout,
+            )
+            .await;
+        }
+    }
+
+    // Header clients hold their own token, there is no cookie to clear.
+    if header_refresh_token.is_some() {
+        return Ok(());
     }
 
     let response_opts = expect_context::<ResponseOptions>();
@@ -442,12 +875,15 @@ pub fn Logout() -> impl IntoView {
     let sso_logout = ServerAction::<SsoLogout>::new();
     let rw_auth_token = expect_context::<AuthToken>().0;
     let navigate = use_navigate();
+    let toasts = expect_context::<Toasts>();
 
-    Effect::new(move |_| {
-        if let Some(Ok(())) = sso_logout.value().get() {
+    Effect::new(move |_| match sso_logout.value().get() {
+        Some(Ok(())) => {
             rw_auth_token.set(None);
             navigate("/home", NavigateOptions::default());
         }
+        Some(Err(e)) => toasts.error(&e),
+        None => {}
     });
 
     view! {
@@ -458,3 +894,121 @@ pub fn Logout() -> impl IntoView {
         </button>
     }
 }
+
+/// Starts a demo session, answering with its token the way a refresh does.
+/// Demo sessions have no refresh token, a new one is started once the token
+/// expires.
+///
+/// Every session copies the seeded data, so each address may only start a
+/// few, and none are started while too many are alive.
+#[server(
+    name = DemoLogin,
+    prefix = "/demo",
+    endpoint = "/login",
+)]
+pub async fn demo_login() -> Result<RefreshResponse, ApiError> {
+    use ssr_imports::*;
+
+    let state = expect_context::<AppState>();
+    // Outside of a demo the endpoint answers as if it didn't exist.
+    if !state.demo_config.enabled {
+        return Err(ApiError::NotFound);
+    }
+    let headers = extract::<HeaderMap>().await?;
+    let extensions = extract::<Extensions>().await?;
+    let key = RateLimiter::key(
+        None,
+        ClientAddress::from_env().client_or_peer_ip(&headers, &extensions),
+    );
+    if state.demo_starts.check(&key, Instant::now()).is_err() {
+        return Err(ApiError::TooManyRequests);
+    }
+    let demo_session = demo::start_session(&state.connection_pool, &state.demo_config)
+        .await
+        .map_err(|e| {
+            error!("Failed to start a demo session: {e}");
+            ApiError::ServerError
+        })?
+        .ok_or(ApiError::TooManyRequests)?;
+
+    let response_opts = expect_context::<ResponseOptions>();
+    response_opts.insert_header(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    Ok(RefreshResponse {
+        access_token: demo_session.token,
+        expires_in: demo_session.expires_in,
+        server_time: Utc::now().timestamp(),
+        refresh_token: None,
+    })
+}
+
+/// Signs the visitor in to a new demo session.
+#[component]
+pub fn DemoStart() -> impl IntoView {
+    let demo_login = ServerAction::<DemoLogin>::new();
+    let navigate = use_navigate();
+
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let rw_expires_in = expect_context::<ExpiresIn>().0;
+    let rw_token_clock = expect_context::<TokenClock>().0;
+    let toasts = expect_context::<Toasts>();
+
+    Effect::new(move |_| match demo_login.value().get() {
+        Some(Ok(RefreshResponse {
+            access_token,
+            expires_in,
+            server_time,
+            ..
+        })) => {
+            rw_token_clock.set(ServerClock::observe(server_time, Utc::now().timestamp()));
+            rw_auth_token.set(Some(access_token));
+            rw_expires_in.set(expires_in);
+            toasts.success("Started a demo. Nothing you change is kept.");
+            navigate("/home", NavigateOptions::default());
+        }
+        Some(Err(e)) => toasts.error(&e),
+        None => {}
+    });
+
+    Effect::new(move |_| {
+        demo_login.dispatch(DemoLogin {});
+    });
+
+    view! {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_schedules_the_refresh_by_the_server_clock() {
+        // Our clock is two minutes behind the server's.
+        let server_time = 1_746_350_000;
+        let local_time = server_time - 120;
+        let clock = ServerClock::observe(server_time, local_time);
+        assert_eq!(clock.skew, 120);
+
+        assert_eq!(
+            clock.refresh_delay(300, local_time),
+            Duration::from_secs(270)
+        );
+        // A minute later by any clock, a minute less is left.
+        assert_eq!(
+            clock.refresh_delay(300, local_time + 60),
+            Duration::from_secs(210)
+        );
+        // Past the expiry the refresh is due at once.
+        assert_eq!(clock.refresh_delay(300, local_time + 600), Duration::ZERO);
+    }
+
+    #[test]
+    fn it_counts_from_now_without_an_issue_time() {
+        let clock = ServerClock::observe(0, 1_746_350_000);
+        assert_eq!(clock, ServerClock::default());
+        assert_eq!(
+            clock.refresh_delay(300, 1_746_350_000),
+            Duration::from_secs(270)
+        );
+        assert_eq!(clock.refresh_delay(10, 1_746_350_000), Duration::ZERO);
+    }
+}
@@ -0,0 +1,329 @@
+//! What the signed in token can do, for pages to hide what would be refused
+//! instead of probing for it.
+//!
+//! The document is fetched once per sign in through a `Resource`, so the
+//! server renders with the same document the browser hydrates. The server
+//! has no access token, only the browser gets one from the first refresh,
+//! so both start from the signed out document, where everything is refused,
+//! and the browser fetches the real one once it is signed in. Signing out
+//! drops it without a request.
+//!
+//! The groups of a token can change while the app is open, which shows as
+//! a 403 from a request the document said was allowed. Any 403 reported to
+//! [`ApiStatus`] fetches the document again.
+
+use leptos::prelude::*;
+
+use crate::{
+    api::{
+        capabilities_api::get,
+        client::{ApiFailure, ApiStatus, RequestState},
+    },
+    schema::capabilities::CapabilitiesResponse,
+};
+
+/// The endpoint the admin pages are built on.
+const ADMIN_STATS: (&str, &str) = ("GET", "/api/admin/stats");
+
+/// When the capabilities are fetched again.
+///
+/// Every fetch has a generation, and a 403 seen while signed in moves on to
+/// the next one. The 403s of a burst come while the fetch they started is
+/// under way, as may the fetch's own if the token lost access to the
+/// document too, so those don't start another.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshPolicy {
+    generation: u64,
+    fetching: bool,
+}
+
+impl RefreshPolicy {
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn started(&mut self) {
+        self.fetching = true;
+    }
+
+    pub fn finished(&mut self) {
+        self.fetching = false;
+    }
+
+    /// Sees a request end in `state`, returning whether the capabilities are
+    /// to be fetched again.
+    pub fn observe(&mut self, state: &RequestState, signed_in: bool) -> bool {
+        let forbidden = matches!(
+            state,
+            RequestState::Failed(ApiFailure::Client { status: 403 })
+        );
+        if !forbidden || !signed_in || self.fetching {
+            return false;
+        }
+        self.generation += 1;
+        true
+    }
+}
+
+/// The capabilities of the signed in token, provided by
+/// [`App`](crate::app::App) and read with [`use_capabilities`].
+#[derive(Debug, Clone, Copy)]
+pub struct Capabilities {
+    /// `None` while the document loads, then `Some(None)` while signed out
+    /// or if it couldn't be fetched
+    document: Signal<Option<Option<CapabilitiesResponse>>>,
+}
+
+impl Capabilities {
+    pub fn new(auth_token: RwSignal<Option<String>>, api_status: ApiStatus) -> Self {
+        // Refreshing the token replaces it, which doesn't change what it
+        // can do.
+        let signed_in = Memo::new(move |_| auth_token.with(Option::is_some));
+        let rw_policy = RwSignal::new(RefreshPolicy::default());
+        let generation = Memo::new(move |_| rw_policy.with(RefreshPolicy::generation));
+
+        let document = Resource::new(
+            move || (signed_in.get(), generation.get()),
+            move |(signed_in, _)| async move {
+                if !signed_in {
+                    return None;
+                }
+                rw_policy.try_update(RefreshPolicy::started);
+                let document = get().await.ok();
+                rw_policy.try_update(RefreshPolicy::finished);
+                document
+            },
+        );
+
+        Effect::new(move |_| {
+            let state = api_status.0.get();
+            let signed_in = signed_in.get_untracked();
+            rw_policy.update(|policy| {
+                policy.observe(&state, signed_in);
+            });
+        });
+
+        Self::from_document(Signal::derive(move || document.get()))
+    }
+
+    fn from_document(document: Signal<Option<Option<CapabilitiesResponse>>>) -> Self {
+        Self { document }
+    }
+
+    /// Whether the caller may call the endpoint with `method` and the path
+    /// template `path`, `None` until that is known. Signed out it is never
+    /// known, so a guard waits for the sign in rather than bouncing.
+    pub fn allows(&self, method: &str, path: &str) -> Option<bool> {
+        self.document.with(|document| {
+            document
+                .as_ref()
+                .and_then(Option::as_ref)
+                .map(|document| document.allows(method, path))
+        })
+    }
+
+    /// Whether the caller may call the endpoint, refused until that is
+    /// known.
+    pub fn can(&self, method: &'static str, path: &'static str) -> Signal<bool> {
+        let capabilities = *self;
+        Signal::derive(move || capabilities.allows(method, path) == Some(true))
+    }
+
+    pub fn can_create_account(&self) -> Signal<bool> {
+        self.can("POST", "/api/accounts")
+    }
+
+    pub fn can_create_institution(&self) -> Signal<bool> {
+        self.can("POST", "/api/institutions")
+    }
+
+    pub fn can_update_institution(&self) -> Signal<bool> {
+        self.can("PATCH", "/api/institutions/{id}")
+    }
+
+    pub fn can_delete_institution(&self) -> Signal<bool> {
+        self.can("DELETE", "/api/institutions/{id}")
+    }
+
+    pub fn can_update_transaction(&self) -> Signal<bool> {
+        self.can("PATCH", "/api/transactions/{id}")
+    }
+
+    pub fn can_delete_transaction(&self) -> Signal<bool> {
+        self.can("DELETE", "/api/transactions/{id}")
+    }
+
+    pub fn can_view_transaction_history(&self) -> Signal<bool> {
+        self.can("GET", "/api/transactions/{id}/history")
+    }
+
+    pub fn can_revert_transaction(&self) -> Signal<bool> {
+        self.can("POST", "/api/transactions/{id}/revert/{history_id}")
+    }
+
+    pub fn can_view_attachments(&self) -> Signal<bool> {
+        self.can("GET", "/api/attachments")
+    }
+
+    pub fn can_create_attachment(&self) -> Signal<bool> {
+        self.can("POST", "/api/attachments")
+    }
+
+    pub fn can_delete_attachment(&self) -> Signal<bool> {
+        self.can("DELETE", "/api/attachments/{id}")
+    }
+
+    pub fn can_view_admin(&self) -> Signal<bool> {
+        self.can(ADMIN_STATS.0, ADMIN_STATS.1)
+    }
+
+    /// Whether the caller may see the admin pages, `None` until that is
+    /// known, for their route to wait on.
+    pub fn admin(&self) -> Option<bool> {
+        self.allows(ADMIN_STATS.0, ADMIN_STATS.1)
+    }
+}
+
+/// The capabilities of the signed in token.
+pub fn use_capabilities() -> Capabilities {
+    expect_context::<Capabilities>()
+}
+
+#[cfg(test)]
+mod test {
+    use leptos::tachys::view::RenderHtml;
+
+    use super::*;
+    use crate::schema::capabilities::{
+        EndpointResponse, LimitsResponse, RateLimitResponse, ResourceCapabilitiesResponse,
+    };
+
+    const FORBIDDEN: RequestState = RequestState::Failed(ApiFailure::Client { status: 403 });
+
+    fn document(endpoints: &[(&str, &str)]) -> CapabilitiesResponse {
+        CapabilitiesResponse {
+            resources: vec![ResourceCapabilitiesResponse {
+                name: "institutions".to_owned(),
+                read: "read".to_owned(),
+                create: "create".to_owned(),
+                update: "none".to_owned(),
+                delete: "none".to_owned(),
+                endpoints: endpoints
+                    .iter()
+                    .map(|(method, path)| EndpointResponse {
+                        method: (*method).to_owned(),
+                        path: (*path).to_owned(),
+                    })
+                    .collect(),
+                page_size: None,
+            }],
+            rate_limit: RateLimitResponse {
+                requests: 100,
+                window_seconds: 60,
+            },
+            limits: LimitsResponse {
+                max_attachment_bytes: 0,
+                max_notes_bytes: 0,
+                max_categorization_rules: 0,
+                max_sync_items: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn it_fetches_again_once_per_burst_of_403s() {
+        let mut policy = RefreshPolicy::default();
+        assert!(policy.observe(&FORBIDDEN, true));
+        assert_eq!(policy.generation(), 1);
+
+        // The rest of the burst, and the fetch's own 403, come while it is
+        // under way.
+        policy.started();
+        assert!(!policy.observe(&FORBIDDEN, true));
+        assert!(!policy.observe(&FORBIDDEN, true));
+        policy.finished();
+        assert_eq!(policy.generation(), 1);
+
+        assert!(policy.observe(&FORBIDDEN, true));
+        assert_eq!(policy.generation(), 2);
+    }
+
+    #[test]
+    fn it_only_fetches_again_on_a_403_while_signed_in() {
+        let mut policy = RefreshPolicy::default();
+        assert!(!policy.observe(&FORBIDDEN, false));
+        for state in [
+            RequestState::Idle,
+            RequestState::Failed(ApiFailure::Client { status: 401 }),
+            RequestState::Failed(ApiFailure::Client { status: 404 }),
+            RequestState::Failed(ApiFailure::Server { status: 503 }),
+            RequestState::Retrying {
+                attempt: 1,
+                failure: ApiFailure::Network,
+            },
+        ] {
+            assert!(!policy.observe(&state, true));
+        }
+        assert_eq!(policy.generation(), 0);
+    }
+
+    #[test]
+    fn it_refuses_everything_until_the_document_is_known() {
+        let owner = Owner::new();
+        owner.with(|| {
+            for document in [None, Some(None)] {
+                provide_context(Capabilities::from_document(Signal::stored(document)));
+                let capabilities = use_capabilities();
+                assert_eq!(capabilities.allows("POST", "/api/institutions"), None);
+                assert_eq!(capabilities.admin(), None);
+                assert!(!capabilities.can_create_account().get_untracked());
+                assert!(!capabilities.can_create_institution().get_untracked());
+                assert!(!capabilities.can_view_admin().get_untracked());
+            }
+
+            let capabilities =
+                Capabilities::from_document(Signal::stored(Some(Some(document(&[
+                    ("GET", "/api/institutions"),
+                    ("POST", "/api/institutions"),
+                ])))));
+            assert!(capabilities.can_create_institution().get_untracked());
+            assert!(!capabilities.can_update_institution().get_untracked());
+            assert_eq!(capabilities.admin(), Some(false));
+        });
+    }
+
+    #[test]
+    fn it_renders_on_the_server_what_the_browser_hydrates() {
+        // The server resolves the document signed out, which is what the
+        // browser hydrates with until its first refresh, so the markup of
+        // the two agrees.
+        let render = |document| {
+            let owner = Owner::new();
+            owner.with(|| {
+                let capabilities = Capabilities::from_document(Signal::stored(document));
+                let can_create = capabilities.can_create_institution();
+                view! {
+                    <Show when=move || can_create.get() fallback=|| view! { <p>"Read only"</p> }>
+                        <button>"New institution"</button>
+                    </Show>
+                }
+                .to_html()
+            })
+        };
+        let server = render(Some(None));
+        assert!(server.contains("Read only"));
+        assert!(!server.contains("New institution"));
+        assert_eq!(render(None), server);
+
+        let signed_in = render(Some(Some(document(&[("POST", "/api/institutions")]))));
+        assert!(signed_in.contains("New institution"));
+    }
+
+    #[test]
+    fn it_matches_endpoints_by_method_and_path_template() {
+        let document = document(&[("GET", "/api/institutions"), ("POST", "/api/institutions")]);
+        assert!(document.allows("POST", "/api/institutions"));
+        assert!(!document.allows("PATCH", "/api/institutions/{id}"));
+        assert!(!document.allows("POST", "/api/accounts"));
+    }
+}
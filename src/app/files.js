@@ -0,0 +1,39 @@
+// Glue between file inputs and the base64 JSON the attachment endpoints
+// take, and between downloads that need the access token and the browser's
+// save prompt, which a plain link can't authorize.
+
+function toBase64(buffer) {
+  const bytes = new Uint8Array(buffer);
+  let binary = "";
+  for (const byte of bytes) {
+    binary += String.fromCharCode(byte);
+  }
+  return btoa(binary);
+}
+
+export async function readFile(input) {
+  const file = input.files?.[0];
+  if (!file) {
+    return null;
+  }
+  return JSON.stringify({
+    filename: file.name,
+    content_type: file.type,
+    content: toBase64(await file.arrayBuffer()),
+  });
+}
+
+export async function downloadFile(url, token, filename) {
+  const response = await fetch(url, {
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) {
+    throw new Error(`${response.status}`);
+  }
+  const href = URL.createObjectURL(await response.blob());
+  const link = document.createElement("a");
+  link.href = href;
+  link.download = filename;
+  link.click();
+  URL.revokeObjectURL(href);
+}
@@ -0,0 +1,254 @@
+//! Guards of the pages that depend on whether the visitor is signed in.
+//!
+//! Only the browser knows whether a visitor is signed in, once their refresh
+//! token has been exchanged for an access token. During SSR the guards go by
+//! whether the request has a refresh token cookie at all: without one the
+//! server redirects before sending the page, with one it renders the same
+//! neutral loading state the browser hydrates, until the first refresh
+//! resolves.
+
+use leptos::prelude::*;
+use leptos_router::{
+    NavigateOptions,
+    components::Redirect,
+    hooks::{use_location, use_query_map},
+};
+
+use crate::app::{AuthToken, SessionRefresh, auth::has_session};
+
+/// Where visitors who aren't signed in are sent to log in.
+pub const LOGIN_PATH: &str = "/home";
+/// The query parameter of [`LOGIN_PATH`] naming where to go once signed in.
+pub const NEXT_PARAM: &str = "next";
+/// Where the destination is kept over the round trip to the provider.
+const DESTINATION_KEY: &str = "login_destination";
+
+/// Whether a guard shows its page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Granted,
+    /// Not known until the first refresh resolves
+    Pending,
+    Refused,
+}
+
+impl Access {
+    /// Access to a page for the signed in. `has_session` is whether the
+    /// request has a refresh token cookie and `refreshed` whether the last
+    /// refresh succeeded, both `None` while not known.
+    pub fn for_signed_in(
+        signed_in: bool,
+        has_session: Option<bool>,
+        refreshed: Option<bool>,
+    ) -> Self {
+        match (signed_in, has_session, refreshed) {
+            (true, _, _) => Self::Granted,
+            (false, Some(false), _) | (false, _, Some(false)) => Self::Refused,
+            _ => Self::Pending,
+        }
+    }
+
+    /// Access to a page for the signed out. A sign in under way, whose
+    /// redirect carries a `code`, navigates on by itself once it is done.
+    pub fn for_signed_out(signed_in: bool, has_session: Option<bool>, signing_in: bool) -> Self {
+        match (signed_in, has_session, signing_in) {
+            (_, _, true) => Self::Granted,
+            (true, _, _) | (false, Some(true), _) => Self::Refused,
+            (false, None, _) => Self::Pending,
+            (false, Some(false), _) => Self::Granted,
+        }
+    }
+}
+
+/// The login entry that returns to `path` once signed in.
+pub fn login_path(path: &str) -> String {
+    format!("{LOGIN_PATH}?{NEXT_PARAM}={}", urlencoding::encode(path))
+}
+
+/// Where to go once signed in, only ever a path of this site.
+pub fn safe_destination(next: Option<&str>) -> Option<&str> {
+    next.filter(|x| x.starts_with('/') && !x.starts_with("//") && !x.starts_with("/\\"))
+}
+
+/// Remembers where to go once signed in, as the provider redirects back to
+/// the same page whatever the visitor was after.
+pub fn remember_destination(next: Option<String>) {
+    let Ok(Some(storage)) = window().local_storage() else {
+        return;
+    };
+    let _ = match safe_destination(next.as_deref()) {
+        Some(next) => storage.set_item(DESTINATION_KEY, next),
+        None => storage.remove_item(DESTINATION_KEY),
+    };
+}
+
+/// Takes where to go once signed in, [`LOGIN_PATH`] unless one was
+/// remembered.
+pub fn take_destination() -> String {
+    let Ok(Some(storage)) = window().local_storage() else {
+        return LOGIN_PATH.to_owned();
+    };
+    let next = storage.get_item(DESTINATION_KEY).ok().flatten();
+    let _ = storage.remove_item(DESTINATION_KEY);
+    safe_destination(next.as_deref())
+        .unwrap_or(LOGIN_PATH)
+        .to_owned()
+}
+
+/// Whether the request has a refresh token cookie, `None` if it couldn't
+/// be told. Blocking, so a server redirect goes out before the page does.
+fn use_session() -> Resource<Option<bool>> {
+    Resource::new_blocking(|| (), |_| async move { has_session().await.ok() })
+}
+
+#[component]
+fn SessionPending() -> impl IntoView {
+    view! {
+        <p class="px-4 py-8 text-ctp-subtext0" aria-busy="true" data-session="pending">
+            "Loading..."
+        </p>
+    }
+}
+
+/// Shows its children to the signed in and sends everyone else to log in,
+/// preserving where they were going.
+#[component]
+pub fn RequireAuth(children: ChildrenFn) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let rw_refreshed = expect_context::<SessionRefresh>().0;
+    let session = use_session();
+    let location = use_location();
+
+    let access = move || {
+        Access::for_signed_in(
+            rw_auth_token.with(Option::is_some),
+            session.get().flatten(),
+            rw_refreshed.get(),
+        )
+    };
+    let intended = move || {
+        let search = location.search.get_untracked();
+        let search = search.trim_start_matches('?');
+        let pathname = location.pathname.get_untracked();
+        if search.is_empty() {
+            pathname
+        } else {
+            format!("{pathname}?{search}")
+        }
+    };
+
+    view! {
+        <Suspense fallback=SessionPending>
+            {move || match access() {
+                Access::Granted => children().into_any(),
+                Access::Pending => view! { <SessionPending/> }.into_any(),
+                Access::Refused => view! {
+                    <Redirect
+                        path=login_path(&intended())
+                        options=NavigateOptions { replace: true, ..Default::default() }
+                    />
+                }
+                .into_any(),
+            }}
+        </Suspense>
+    }
+}
+
+/// Shows its children to the signed out and sends the signed in home.
+#[component]
+pub fn RequireSignedOut(children: ChildrenFn) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let session = use_session();
+    let query = use_query_map();
+
+    let access = move || {
+        Access::for_signed_out(
+            rw_auth_token.with(Option::is_some),
+            session.get().flatten(),
+            query.with(|x| x.get("code").is_some()),
+        )
+    };
+
+    view! {
+        <Suspense fallback=SessionPending>
+            {move || match access() {
+                Access::Granted => children().into_any(),
+                Access::Pending => view! { <SessionPending/> }.into_any(),
+                Access::Refused => view! {
+                    <Redirect
+                        path=LOGIN_PATH
+                        options=NavigateOptions { replace: true, ..Default::default() }
+                    />
+                }
+                .into_any(),
+            }}
+        </Suspense>
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_waits_for_the_first_refresh_of_a_session() {
+        // What the server renders is what the browser hydrates: neither has
+        // a token before the first refresh resolves.
+        assert_eq!(
+            Access::for_signed_in(false, Some(true), None),
+            Access::Pending
+        );
+        assert_eq!(Access::for_signed_in(false, None, None), Access::Pending);
+        assert_eq!(
+            Access::for_signed_in(true, Some(true), Some(true)),
+            Access::Granted
+        );
+        assert_eq!(
+            Access::for_signed_in(false, Some(true), Some(false)),
+            Access::Refused
+        );
+        assert_eq!(
+            Access::for_signed_in(false, Some(false), None),
+            Access::Refused
+        );
+        // A demo has a token but no refresh token.
+        assert_eq!(
+            Access::for_signed_in(true, Some(false), Some(false)),
+            Access::Granted
+        );
+    }
+
+    #[test]
+    fn it_lets_a_sign_in_finish_before_bouncing() {
+        assert_eq!(
+            Access::for_signed_out(false, Some(false), false),
+            Access::Granted
+        );
+        assert_eq!(
+            Access::for_signed_out(false, Some(true), false),
+            Access::Refused
+        );
+        assert_eq!(
+            Access::for_signed_out(true, Some(false), false),
+            Access::Refused
+        );
+        assert_eq!(Access::for_signed_out(false, None, false), Access::Pending);
+        assert_eq!(
+            Access::for_signed_out(true, Some(true), true),
+            Access::Granted
+        );
+    }
+
+    #[test]
+    fn it_only_returns_to_paths_of_this_site() {
+        assert_eq!(
+            login_path("/accounts/1?tab=a&b"),
+            "/home?next=%2Faccounts%2F1%3Ftab%3Da%26b"
+        );
+        assert_eq!(safe_destination(Some("/accounts")), Some("/accounts"));
+        assert_eq!(safe_destination(Some("//evil.example")), None);
+        assert_eq!(safe_destination(Some("/\\evil.example")), None);
+        assert_eq!(safe_destination(Some("https://evil.example")), None);
+        assert_eq!(safe_destination(None), None);
+    }
+}
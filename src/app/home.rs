@@ -1,100 +1,714 @@
-use std::collections::HashMap;
-
 use leptos::prelude::*;
+use leptos_router::{NavigateOptions, hooks::use_navigate};
+use reqwest::Method;
+use rust_decimal::Decimal;
 
 use crate::{
     api::{
-        account_api::get_list as account_get_list,
-        institution_api::get_list as institution_get_list,
+        ApiError, ClientErrorCode, dashboard_api::get as dashboard_get, quick_entry_api::get_list,
+        transaction_api::get_uncategorized, watchlist_api::get_list as watchlist_get_list,
+    },
+    app::{AuthToken, passkeys::request, toast::Toasts, welcome::use_onboarding_state},
+    model::{
+        quick_entry::QuickEntryId, transaction::TransactionId, watchlist_entry::WatchDirection,
     },
-    app::AuthToken,
     schema::{
-        Pagination, account::GetListRequest as AccountGetListRequest,
-        institution::GetListRequest as InstitutionGetListRequest,
+        GetList,
+        budget::BudgetStatusResponse,
+        dashboard::{BudgetSummaryResponse, DashboardResponse, NetBalanceResponse},
+        quick_entry::QuickEntryExecuteResponse,
+        transaction::{TransactionCategorizeResponse, TransactionResponse, UncategorizedRequest},
+        watchlist_entry::WatchlistEntryResponse,
     },
 };
 
+/// Which way a balance moved since last month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Up,
+    Down,
+    Flat,
+}
+
+impl Trend {
+    pub fn of(balance: &NetBalanceResponse) -> Self {
+        match balance.balance.cmp(&balance.last_month_balance) {
+            std::cmp::Ordering::Greater => Self::Up,
+            std::cmp::Ordering::Less => Self::Down,
+            std::cmp::Ordering::Equal => Self::Flat,
+        }
+    }
+
+    fn arrow(self) -> &'static str {
+        match self {
+            Self::Up => "▲",
+            Self::Down => "▼",
+            Self::Flat => "▬",
+        }
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            Self::Up => "text-ctp-green",
+            Self::Down => "text-ctp-red",
+            Self::Flat => "text-ctp-overlay1",
+        }
+    }
+}
+
+/// The change of a balance since last month as a percentage of what it
+/// was, to one decimal. `None` if it was zero, since any change from
+/// nothing is unbounded.
+pub fn change_percent(balance: &NetBalanceResponse) -> Option<Decimal> {
+    let last_month = balance.last_month_balance;
+    if last_month.is_zero() {
+        return None;
+    }
+    Some(((balance.balance - last_month) / last_month.abs() * Decimal::ONE_HUNDRED).round_dp(1))
+}
+
+/// How much of the limit of a budget is spent as a whole percentage.
+/// `None` for a budget without a positive limit.
+pub fn percent_spent(status: &BudgetStatusResponse) -> Option<Decimal> {
+    let limit = status
+        .limit
+        .filter(|x| x.is_sign_positive() && !x.is_zero())?;
+    Some((status.spent / limit * Decimal::ONE_HUNDRED).round_dp(0))
+}
+
+/// The width of the status bar of a budget, which fills up at the limit.
+pub fn bar_width(percent: Decimal) -> Decimal {
+    percent.clamp(Decimal::ZERO, Decimal::ONE_HUNDRED)
+}
+
+/// The target of a watchlist entry with the way the price has to go to
+/// reach it, like "▲ 1400". `None` for an entry that only watches.
+pub fn target_label(entry: &WatchlistEntryResponse<GetList>) -> Option<String> {
+    let arrow = match entry.direction {
+        WatchDirection::Above => "▲",
+        WatchDirection::Below => "▼",
+    };
+    entry
+        .target_price
+        .map(|target| format!("{arrow} {}", target.normalize()))
+}
+
+/// Links into onboarding while the user is still in it, and to `href`
+/// once they are through it.
 #[component]
-pub fn Home() -> impl IntoView {
-    let auth_token = expect_context::<AuthToken>().0;
+fn CallToAction(onboarded: Signal<bool>, href: &'static str, label: &'static str) -> impl IntoView {
+    view! {
+        <a
+            class="mt-2 inline-block rounded-full bg-ctp-surface1 px-4 py-2 text-ctp-text hover:bg-ctp-surface2"
+            href=move || if onboarded.get() { href } else { "/welcome" }
+        >
+            {label}
+        </a>
+    }
+}
+
+/// Stands in for the rows of a section while the dashboard loads.
+#[component]
+fn Skeleton(rows: usize) -> impl IntoView {
+    (0..rows)
+        .map(
+            |_| view! { <div class="mb-2 h-6 w-full animate-pulse rounded bg-ctp-surface1"></div> },
+        )
+        .collect_view()
+}
+
+#[component]
+fn Section(title: &'static str, children: Children) -> impl IntoView {
+    view! {
+        <section class="rounded-lg bg-ctp-base p-4">
+            <h2 class="mb-2 text-lg font-medium text-ctp-text">{title}</h2>
+            {children()}
+        </section>
+    }
+}
 
-    let accounts = Resource::new(
-        move || auth_token.get(),
-        |auth_signal| async move {
-            if auth_signal.is_none() {
-                return vec![];
+#[component]
+fn Balances(balances: Vec<NetBalanceResponse>, onboarded: Signal<bool>) -> impl IntoView {
+    if balances.is_empty() {
+        return view! {
+            <p class="text-ctp-subtext0">"Nothing to total yet."</p>
+            <CallToAction onboarded href="/accounts" label="Add an account"/>
+        }
+        .into_any();
+    }
+    balances
+        .into_iter()
+        .map(|balance| {
+            let trend = Trend::of(&balance);
+            let change = change_percent(&balance)
+                .map(|x| format!("{x}%"))
+                .unwrap_or_default();
+            view! {
+                <div class="flex flex-row gap-2 text-ctp-text">
+                    <span class="flex-auto">{balance.symbol.clone()}</span>
+                    <span class="text-right">{balance.balance.to_string()}</span>
+                    <span class=trend.class() title="Since last month">
+                        {trend.arrow()} " " {change}
+                    </span>
+                </div>
+            }
+        })
+        .collect_view()
+        .into_any()
+}
+
+#[component]
+fn RecentTransactions(
+    transactions: Vec<TransactionResponse<GetList>>,
+    onboarded: Signal<bool>,
+) -> impl IntoView {
+    if transactions.is_empty() {
+        return view! {
+            <p class="text-ctp-subtext0">"No transactions yet."</p>
+            <CallToAction onboarded href="/transactions" label="Record a transaction"/>
+        }
+        .into_any();
+    }
+    view! {
+        <ul>
+            {transactions
+                .into_iter()
+                .map(|transaction| view! {
+                    <li class="flex flex-row gap-2 text-ctp-text">
+                        <a class="flex-auto hover:underline" href=format!("/transactions/{}", transaction.id.0)>
+                            {transaction.description.clone().unwrap_or_else(|| "Untitled".to_owned())}
+                        </a>
+                        <span class="text-ctp-subtext0">{transaction.posted_at.format("%Y-%m-%d").to_string()}</span>
+                        <span class="text-right">{transaction.quantity.to_string()}</span>
+                    </li>
+                })
+                .collect_view()}
+        </ul>
+        <a class="text-ctp-blue hover:underline" href="/transactions">"All transactions"</a>
+    }
+    .into_any()
+}
+
+#[component]
+fn Budgets(budgets: Vec<BudgetSummaryResponse>, onboarded: Signal<bool>) -> impl IntoView {
+    if budgets.is_empty() {
+        return view! {
+            <p class="text-ctp-subtext0">"No budgets yet."</p>
+            <CallToAction onboarded href="/transactions" label="Review your spending"/>
+        }
+        .into_any();
+    }
+    budgets
+        .into_iter()
+        .map(|budget| {
+            let percent = percent_spent(&budget.status);
+            let bar_class = if budget.status.met {
+                "h-2 rounded bg-ctp-green"
+            } else {
+                "h-2 rounded bg-ctp-red"
+            };
+            view! {
+                <div class="mb-2 text-ctp-text">
+                    <div class="flex flex-row">
+                        <span class="flex-auto">{budget.name.clone()}</span>
+                        <span>
+                            {match percent {
+                                Some(percent) => format!("{percent}%"),
+                                None if budget.status.no_income => "No income yet".to_owned(),
+                                None => "No limit".to_owned(),
+                            }}
+                        </span>
+                    </div>
+                    <div class="h-2 w-full rounded bg-ctp-surface1">
+                        <div
+                            class=bar_class
+                            style=format!("width: {}%", bar_width(percent.unwrap_or_default()))
+                        ></div>
+                    </div>
+                </div>
+            }
+        })
+        .collect_view()
+        .into_any()
+}
+
+// Executing an entry is addressed by its id, which server fn endpoints
+// can't express, so it is called directly.
+async fn execute_quick_entry(
+    auth_token: &str,
+    id: QuickEntryId,
+) -> Result<QuickEntryExecuteResponse, ApiError> {
+    request(
+        auth_token,
+        Method::POST,
+        &format!("/api/quick-entries/{id}/execute"),
+        Some(serde_json::json!({})),
+    )
+    .await
+}
+
+/// The saved transactions of the user, each recorded again now with one
+/// click. `rw_version` is bumped after each, so the dashboard catches up.
+#[component]
+fn QuickEntries(rw_version: RwSignal<usize>) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let toasts = expect_context::<Toasts>();
+
+    let quick_entries = Resource::new(
+        move || rw_auth_token.get(),
+        move |auth_signal| async move {
+            auth_signal.as_ref()?;
+            match get_list().await {
+                Ok(response) => Some(response.quick_entries),
+                Err(e) => {
+                    toasts.error(&e);
+                    None
+                }
             }
-            account_get_list(
-                AccountGetListRequest {
-                    name: None,
-                    institution_id: None,
-                },
-                Pagination::default(),
-            )
-            .await
-            .expect("Failed to get accounts")
-            .accounts
         },
     );
 
-    let institutions_map = Resource::new(
-        move || auth_token.get(),
-        |auth_signal| async move {
-            if auth_signal.is_none() {
-                return HashMap::new();
+    let execute = move |id: QuickEntryId, label: String| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match execute_quick_entry(&auth_token, id).await {
+                Ok(_) => toasts.success(format!("Recorded {label}.")),
+                Err(e) => toasts.error(&e),
+            }
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    view! {
+        <Suspense fallback=|| view! { <Skeleton rows=2/> }>
+            {move || quick_entries.get().flatten().map(|quick_entries| {
+                if quick_entries.is_empty() {
+                    return view! {
+                        <p class="text-ctp-subtext0">"No quick entries yet."</p>
+                    }
+                    .into_any();
+                }
+                view! {
+                    <div class="flex flex-row flex-wrap gap-2">
+                        {quick_entries
+                            .into_iter()
+                            .map(|quick_entry| {
+                                let id = quick_entry.id;
+                                let label = quick_entry.label.clone();
+                                let disabled = quick_entry.account_id.is_none();
+                                view! {
+                                    <button
+                                        class="rounded-full bg-ctp-surface1 px-4 py-2 text-ctp-text hover:bg-ctp-surface2 disabled:opacity-50"
+                                        title=if disabled { "Its account no longer exists" } else { "" }
+                                        disabled=disabled
+                                        on:click=move |_| execute(id, label.clone())
+                                    >
+                                        {quick_entry.label}
+                                        " "
+                                        <span class="text-ctp-subtext0">{quick_entry.quantity.to_string()}</span>
+                                    </button>
+                                }
+                            })
+                            .collect_view()}
+                    </div>
+                }
+                .into_any()
+            })}
+        </Suspense>
+    }
+}
+
+/// The latest price of each asset the user watches, with its target and
+/// whether the price reached it.
+#[component]
+fn Watchlist() -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let toasts = expect_context::<Toasts>();
+
+    let watchlist_entries = Resource::new(
+        move || rw_auth_token.get(),
+        move |auth_signal| async move {
+            auth_signal.as_ref()?;
+            match watchlist_get_list().await {
+                Ok(response) => Some(response.watchlist_entries),
+                Err(e) => {
+                    toasts.error(&e);
+                    None
+                }
             }
-            institution_get_list(
-                InstitutionGetListRequest { name: None },
-                Pagination::default(),
-            )
-            .await
-            .expect("Failed to get institutions")
-            .institutions
-            .into_iter()
-            .map(|i| (i.id, i))
-            .collect()
         },
     );
 
     view! {
-        <Show when=move || auth_token.get().is_some() fallback=|| view! {<p class="text-ctp-text">"Log in to access Treasury."</p>}>
-            <div class="container">
-                <div class="flex justify-center">
-                    <Suspense fallback=move || view! { <p>"Loading"</p> }>
-                        <table class="bg-ctp-base table-auto">
-                            <thead>
-                                <tr>
-                                    <th class="text-ctp-yellow px-2 border border-ctp-surface2">"Institution"</th>
-                                    <th class="text-ctp-blue px-2 border border-ctp-surface2">"Account Name"</th>
-                                    <th class="text-ctp-green px-2 border border-ctp-surface2">"Account Value"</th>
-                                    <th class="text-ctp-mauve px-2 border border-ctp-surface2">"% Change (1d)"</th>
-                                </tr>
-                            </thead>
-                            <tbody>
-                                {move || {
-                                        let institutions = institutions_map.get().unwrap_or(HashMap::new());
-                                        accounts.get().unwrap_or(vec![]).iter().enumerate()
-                                            .map(|(i, a)| view! {
-                                                <tr class={
-                                                    if i % 2 == 0 {
-                                                        "bg-ctp-surface0 border border-ctp-surface2"
-                                                    } else {
-                                                        "bg-ctp-surface1 border border-ctp-surface2"
-                                                    }
-                                                }>
-                                                    <td class="text-ctp-text px-2 text-center border border-ctp-surface2">{institutions.get(&a.institution_id).map(|i| i.name.clone()).unwrap_or("".to_owned())}</td>
-                                                    <td class="text-ctp-text px-2 text-center border border-ctp-surface2">{a.name.clone()}</td>
-                                                    <td class="text-ctp-text px-4 text-right border border-ctp-surface2">"1234.56"</td>
-                                                    <td class="text-ctp-text px-10 text-right border border-ctp-surface2">"12.7"</td>
-                                                </tr>
-                                            }).collect_view()
+        <Suspense fallback=|| view! { <Skeleton rows=2/> }>
+            {move || watchlist_entries.get().flatten().map(|watchlist_entries| {
+                if watchlist_entries.is_empty() {
+                    return view! {
+                        <p class="text-ctp-subtext0">"Nothing on your watchlist yet."</p>
+                    }
+                    .into_any();
+                }
+                watchlist_entries
+                    .into_iter()
+                    .map(|entry| {
+                        let target = target_label(&entry);
+                        let reached = entry.triggered_at.is_some();
+                        view! {
+                            <div class="mb-2 flex flex-row gap-2 text-ctp-text">
+                                <span class="flex-auto">
+                                    {format!("{}/{}", entry.symbol, entry.quote_symbol)}
+                                </span>
+                                <span>
+                                    {entry
+                                        .latest_price
+                                        .map(|x| x.normalize().to_string())
+                                        .unwrap_or_else(|| "No price yet".to_owned())}
+                                </span>
+                                {target.map(|target| view! {
+                                    <span
+                                        class=if reached { "text-ctp-green" } else { "text-ctp-overlay1" }
+                                        title=if reached { "The price reached the target" } else { "" }
+                                    >
+                                        {target}
+                                    </span>
+                                })}
+                            </div>
+                        }
+                    })
+                    .collect_view()
+                    .into_any()
+            })}
+        </Suspense>
+    }
+}
+
+// Categorizing is addressed by the id of the transaction too.
+async fn categorize_transaction(
+    auth_token: &str,
+    id: TransactionId,
+    category: &str,
+    create_rule: bool,
+) -> Result<TransactionCategorizeResponse, ApiError> {
+    request(
+        auth_token,
+        Method::POST,
+        &format!("/api/transactions/{}/categorize", id.0),
+        Some(serde_json::json!({ "category": category, "create_rule": create_rule })),
+    )
+    .await
+}
+
+/// How many transactions of the user are without a category, opening onto
+/// the latest of them to categorize each with one click.
+#[component]
+fn Uncategorized(rw_version: RwSignal<usize>) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let toasts = expect_context::<Toasts>();
+    let rw_open = RwSignal::new(false);
+    let rw_category = RwSignal::new(String::new());
+    let rw_create_rule = RwSignal::new(false);
+
+    let uncategorized = Resource::new(
+        move || (rw_auth_token.get(), rw_version.get()),
+        move |(auth_signal, _)| async move {
+            auth_signal.as_ref()?;
+            match get_uncategorized(UncategorizedRequest::default()).await {
+                Ok(response) => Some(response),
+                Err(e) => {
+                    toasts.error(&e);
+                    None
+                }
+            }
+        },
+    );
+
+    let categorize = move |id: TransactionId| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        let category = rw_category.get_untracked();
+        if category.trim().is_empty() {
+            toasts.error(&ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "Enter a category first.",
+            ));
+            return;
+        }
+        let create_rule = rw_create_rule.get_untracked();
+        leptos::task::spawn_local(async move {
+            match categorize_transaction(&auth_token, id, &category, create_rule).await {
+                Ok(response) if response.rule.is_some() => toasts.success(format!(
+                    "Categorized as {category}, and so will be the ones like it."
+                )),
+                Ok(_) => toasts.success(format!("Categorized as {category}.")),
+                Err(e) => toasts.error(&e),
+            }
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    view! {
+        <Suspense fallback=|| view! { <Skeleton rows=1/> }>
+            {move || uncategorized.get().flatten().map(|uncategorized| {
+                if uncategorized.count == 0 {
+                    return view! {
+                        <p class="text-ctp-subtext0">"Every transaction has a category."</p>
+                    }
+                    .into_any();
+                }
+                view! {
+                    <div class="flex flex-row gap-2 text-ctp-text">
+                        <span class="flex-auto">
+                            {format!("{} without a category", uncategorized.count)}
+                        </span>
+                        <button
+                            class="text-ctp-blue hover:underline"
+                            on:click=move |_| rw_open.update(|x| *x = !*x)
+                        >
+                            {move || if rw_open.get() { "Hide" } else { "Review" }}
+                        </button>
+                    </div>
+                    <Show when=move || rw_open.get()>
+                        <div class="my-2 flex flex-row gap-2">
+                            <input
+                                class="flex-auto rounded bg-ctp-surface0 px-2 text-ctp-text"
+                                placeholder="Category"
+                                bind:value=rw_category
+                            />
+                            <label class="text-ctp-subtext0">
+                                <input type="checkbox" bind:checked=rw_create_rule/>
+                                " Make a rule"
+                            </label>
+                        </div>
+                        <ul>
+                            {uncategorized
+                                .transactions
+                                .clone()
+                                .into_iter()
+                                .map(|transaction| {
+                                    let id = transaction.id;
+                                    view! {
+                                        <li class="flex flex-row gap-2 text-ctp-text">
+                                            <span class="flex-auto">
+                                                {transaction.description.clone().unwrap_or_else(|| "Untitled".to_owned())}
+                                            </span>
+                                            <span class="text-right">{transaction.quantity.to_string()}</span>
+                                            <button
+                                                class="text-ctp-blue hover:underline"
+                                                on:click=move |_| categorize(id)
+                                            >
+                                                "Categorize"
+                                            </button>
+                                        </li>
                                     }
-                                }
-                            </tbody>
-                        </table>
+                                })
+                                .collect_view()}
+                        </ul>
+                    </Show>
+                }
+                .into_any()
+            })}
+        </Suspense>
+    }
+}
+
+/// The overview of the finances of the user: what they hold, what they
+/// last spent and how their budgets are doing this month.
+#[component]
+pub fn Home() -> impl IntoView {
+    let auth_token = expect_context::<AuthToken>().0;
+    let toasts = expect_context::<Toasts>();
+    let onboarding = use_onboarding_state(RwSignal::new(0));
+    let navigate = use_navigate();
+
+    // New users have nothing to see here until they are onboarded.
+    Effect::new(move |_| {
+        if onboarding
+            .get()
+            .flatten()
+            .is_some_and(|onboarding| !onboarding.complete)
+        {
+            navigate("/welcome", NavigateOptions::default());
+        }
+    });
+    let onboarded = Signal::derive(move || {
+        onboarding
+            .get()
+            .flatten()
+            .is_none_or(|onboarding| onboarding.complete)
+    });
+
+    // Bumped when a quick entry records a transaction or one is categorized.
+    let rw_version = RwSignal::new(0);
+    let dashboard = Resource::new(
+        move || (auth_token.get(), rw_version.get()),
+        move |(auth_signal, _)| async move {
+            auth_signal.as_ref()?;
+            match dashboard_get().await {
+                Ok(response) => Some(response),
+                Err(e) => {
+                    toasts.error(&e);
+                    None
+                }
+            }
+        },
+    );
+
+    let loaded = move |section: fn(DashboardResponse, Signal<bool>) -> AnyView| {
+        move || dashboard.get().flatten().map(|x| section(x, onboarded))
+    };
+
+    view! {
+        <Show when=move || auth_token.get().is_some() fallback=|| view! {<p class="text-ctp-text">"Log in to access Treasury."</p>}>
+            <div class="container mx-auto grid grid-cols-1 gap-4 px-4 py-8 md:grid-cols-2">
+                <Section title="Net balance">
+                    <Suspense fallback=|| view! { <Skeleton rows=3/> }>
+                        {loaded(|x, onboarded| view! { <Balances balances=x.balances onboarded/> }.into_any())}
                     </Suspense>
-                </div>
+                </Section>
+                <Section title="Recent transactions">
+                    <Suspense fallback=|| view! { <Skeleton rows=5/> }>
+                        {loaded(|x, onboarded| view! {
+                            <RecentTransactions transactions=x.recent_transactions onboarded/>
+                        }.into_any())}
+                    </Suspense>
+                </Section>
+                <Section title="Budgets this month">
+                    <Suspense fallback=|| view! { <Skeleton rows=2/> }>
+                        {loaded(|x, onboarded| view! { <Budgets budgets=x.budgets onboarded/> }.into_any())}
+                    </Suspense>
+                </Section>
+                <Section title="Quick entry">
+                    <QuickEntries rw_version/>
+                </Section>
+                <Section title="Watchlist">
+                    <Watchlist/>
+                </Section>
+                <Section title="To categorize">
+                    <Uncategorized rw_version/>
+                </Section>
             </div>
         </Show>
     }
 }
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::model::{asset::AssetId, budget::BudgetKind};
+
+    fn net_balance(balance: i64, last_month_balance: i64) -> NetBalanceResponse {
+        NetBalanceResponse {
+            asset_id: AssetId::default(),
+            symbol: "KRW".to_owned(),
+            balance: balance.into(),
+            last_month_balance: last_month_balance.into(),
+        }
+    }
+
+    fn budget_status(spent: i64, limit: Option<i64>) -> BudgetStatusResponse {
+        BudgetStatusResponse {
+            budget_id: Default::default(),
+            start: Utc::now(),
+            end: Utc::now(),
+            asset_id: AssetId::default(),
+            kind: BudgetKind::default(),
+            spent: spent.into(),
+            income: Decimal::ZERO,
+            limit: limit.map(Decimal::from),
+            remaining: limit.map(|x| Decimal::from(x - spent)),
+            met: limit.is_some_and(|x| spent <= x),
+            no_income: false,
+            unconverted: vec![],
+            rounding: Default::default(),
+        }
+    }
+
+    #[test]
+    fn it_follows_the_trend_of_a_balance() {
+        let rw_balance = RwSignal::new(net_balance(120, 100));
+        let trend = Memo::new(move |_| rw_balance.with(Trend::of));
+        let change = Memo::new(move |_| rw_balance.with(change_percent));
+
+        assert_eq!(trend.get_untracked(), Trend::Up);
+        assert_eq!(change.get_untracked(), Some(Decimal::from(20)));
+
+        rw_balance.set(net_balance(75, 100));
+        assert_eq!(trend.get_untracked(), Trend::Down);
+        assert_eq!(change.get_untracked(), Some(Decimal::from(-25)));
+
+        rw_balance.set(net_balance(100, 100));
+        assert_eq!(trend.get_untracked(), Trend::Flat);
+        assert_eq!(change.get_untracked(), Some(Decimal::ZERO));
+    }
+
+    #[test]
+    fn it_measures_change_against_a_negative_balance() {
+        // Paying down a debt is an increase.
+        assert_eq!(
+            change_percent(&net_balance(-50, -100)),
+            Some(Decimal::from(50))
+        );
+        assert_eq!(Trend::of(&net_balance(-50, -100)), Trend::Up);
+    }
+
+    #[test]
+    fn it_has_no_change_from_nothing() {
+        assert_eq!(change_percent(&net_balance(100, 0)), None);
+        assert_eq!(Trend::of(&net_balance(100, 0)), Trend::Up);
+    }
+
+    #[test]
+    fn it_fills_the_bar_of_a_budget_up_to_its_limit() {
+        let rw_status = RwSignal::new(budget_status(250, Some(1000)));
+        let percent = Memo::new(move |_| rw_status.with(percent_spent));
+        let width = Memo::new(move |_| bar_width(percent.get().unwrap_or_default()));
+
+        assert_eq!(percent.get_untracked(), Some(Decimal::from(25)));
+        assert_eq!(width.get_untracked(), Decimal::from(25));
+
+        rw_status.set(budget_status(1500, Some(1000)));
+        assert_eq!(percent.get_untracked(), Some(Decimal::from(150)));
+        assert_eq!(width.get_untracked(), Decimal::ONE_HUNDRED);
+
+        rw_status.set(budget_status(1500, None));
+        assert_eq!(percent.get_untracked(), None);
+        assert_eq!(width.get_untracked(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn it_has_no_percentage_of_a_zero_limit() {
+        assert_eq!(percent_spent(&budget_status(10, Some(0))), None);
+    }
+
+    #[test]
+    fn it_labels_the_target_of_a_watchlist_entry() {
+        let mut entry = WatchlistEntryResponse::<GetList> {
+            id: Default::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            asset_id: AssetId::default(),
+            quote_asset_id: AssetId::default(),
+            symbol: "USD".to_owned(),
+            quote_symbol: "KRW".to_owned(),
+            target_price: Some(Decimal::new(140000, 2)),
+            direction: WatchDirection::Above,
+            channel: Default::default(),
+            repeating: false,
+            triggered_at: None,
+            triggered_price: None,
+            latest_price: None,
+            latest_priced_at: None,
+            _phantom: Default::default(),
+        };
+        assert_eq!(target_label(&entry), Some("▲ 1400".to_owned()));
+        entry.direction = WatchDirection::Below;
+        assert_eq!(target_label(&entry), Some("▼ 1400".to_owned()));
+        entry.target_price = None;
+        assert_eq!(target_label(&entry), None);
+    }
+}
@@ -0,0 +1,379 @@
+//! Locks the app after the user has been away, for shared computers.
+//!
+//! [`IdleLock`] counts the time since the last mouse, keyboard or touch
+//! event with an [`IdleTimer`]. Once it runs past the idle lock the user
+//! chose in their preferences, the access token is dropped, which blanks
+//! the guarded pages, and a lock screen covers the rest. Unlocking takes a
+//! refresh of the session, which the refresh token cookie still allows; if
+//! the session is gone too, the user is sent to log in.
+//!
+//! The listeners and the timer only run in the browser, the server renders
+//! the lock screen as it stands without touching `window`.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use leptos::{ev, prelude::*};
+
+use crate::{
+    api::me_api::get_preferences,
+    app::{
+        AuthToken, ExpiresIn, IdleLocked, SessionRefresh, TokenClock,
+        auth::{RefreshResponse, ServerClock, SsoRefresh},
+        guard::login_path,
+    },
+    schema::me::{DEFAULT_IDLE_LOCK_MINUTES, PreferenceResponse},
+};
+
+/// How often the timer checks whether the app should lock.
+pub const IDLE_TICK: Duration = Duration::from_secs(1);
+
+/// When the app locks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleSettings {
+    /// How long the app may sit idle, never locking if `None`
+    pub timeout: Option<Duration>,
+    /// Whether time in a hidden tab counts, rather than pausing the timer
+    pub while_hidden: bool,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(DEFAULT_IDLE_LOCK_MINUTES as u64 * 60)),
+            while_hidden: true,
+        }
+    }
+}
+
+impl From<&PreferenceResponse> for IdleSettings {
+    fn from(value: &PreferenceResponse) -> Self {
+        Self {
+            timeout: (value.idle_lock_minutes > 0)
+                .then(|| Duration::from_secs(value.idle_lock_minutes as u64 * 60)),
+            while_hidden: value.idle_lock_while_hidden,
+        }
+    }
+}
+
+/// How long the user has been away, by the clock of the browser in
+/// milliseconds. Time is only ever measured between two readings, so a
+/// tab whose timers the browser throttled still locks on its next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleTimer {
+    settings: IdleSettings,
+    /// The idle time as of `last_reading`
+    idle_ms: i64,
+    last_reading: i64,
+    hidden: bool,
+    locked: bool,
+}
+
+impl IdleTimer {
+    pub fn new(settings: IdleSettings, now: i64) -> Self {
+        Self {
+            settings,
+            idle_ms: 0,
+            last_reading: now,
+            hidden: false,
+            locked: false,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Changes when the app locks, keeping the time idle so far.
+    pub fn configure(&mut self, settings: IdleSettings, now: i64) -> bool {
+        self.read(now);
+        self.settings = settings;
+        self.expire()
+    }
+
+    /// Checks the time, returning whether the app just locked.
+    pub fn tick(&mut self, now: i64) -> bool {
+        self.read(now);
+        self.expire()
+    }
+
+    /// The user did something, which restarts the timer unless the app
+    /// locked in the meantime. Only unlocking restarts a locked one.
+    pub fn activity(&mut self, now: i64) -> bool {
+        let locked = self.tick(now);
+        if !self.locked {
+            self.idle_ms = 0;
+        }
+        locked
+    }
+
+    /// The tab was hidden or shown, which is not activity in itself.
+    pub fn set_hidden(&mut self, hidden: bool, now: i64) -> bool {
+        self.read(now);
+        self.hidden = hidden;
+        self.expire()
+    }
+
+    /// Restarts the timer, unlocked.
+    pub fn reset(&mut self, now: i64) {
+        self.idle_ms = 0;
+        self.last_reading = now;
+        self.locked = false;
+    }
+
+    fn read(&mut self, now: i64) {
+        // A clock set back counts as no time at all.
+        let elapsed = (now - self.last_reading).max(0);
+        if !self.hidden || self.settings.while_hidden {
+            self.idle_ms += elapsed;
+        }
+        self.last_reading = now;
+    }
+
+    fn expire(&mut self) -> bool {
+        let expired = self
+            .settings
+            .timeout
+            .is_some_and(|x| self.idle_ms >= x.as_millis() as i64);
+        if self.locked || !expired {
+            return false;
+        }
+        self.locked = true;
+        true
+    }
+}
+
+fn now() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+/// Covers the app once the user has been idle for longer than they chose,
+/// until the session is refreshed.
+#[component]
+pub fn IdleLock() -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let rw_expires_in = expect_context::<ExpiresIn>().0;
+    let rw_token_clock = expect_context::<TokenClock>().0;
+    let rw_refreshed = expect_context::<SessionRefresh>().0;
+    let rw_locked = expect_context::<IdleLocked>().0;
+    let timer = StoredValue::new(IdleTimer::new(IdleSettings::default(), now()));
+    let unlock = ServerAction::<SsoRefresh>::new();
+
+    // Nothing to lock for the signed out, whose timer just restarts.
+    let lock = move || {
+        if rw_auth_token.with_untracked(Option::is_none) {
+            timer.update_value(|x| x.reset(now()));
+            return;
+        }
+        rw_auth_token.set(None);
+        rw_locked.set(true);
+    };
+
+    // The preferences of the user, again with every new token in case
+    // they changed.
+    Effect::new(move |_| {
+        if rw_auth_token.with(Option::is_none) {
+            return;
+        }
+        leptos::task::spawn_local(async move {
+            if let Ok(preferences) = get_preferences().await
+                && timer.try_update_value(|x| x.configure(IdleSettings::from(&preferences), now()))
+                    == Some(true)
+            {
+                lock();
+            }
+        });
+    });
+
+    Effect::new(move |_| {
+        let on_activity = move || {
+            if timer.try_update_value(|x| x.activity(now())) == Some(true) {
+                lock();
+            }
+        };
+        timer.update_value(|x| {
+            x.set_hidden(document().hidden(), now());
+        });
+        let handles = [
+            window_event_listener(ev::mousemove, move |_| on_activity()),
+            window_event_listener(ev::mousedown, move |_| on_activity()),
+            window_event_listener(ev::keydown, move |_| on_activity()),
+            window_event_listener(ev::wheel, move |_| on_activity()),
+            window_event_listener(ev::touchstart, move |_| on_activity()),
+            window_event_listener(ev::visibilitychange, move |_| {
+                let hidden = document().hidden();
+                if timer.try_update_value(|x| x.set_hidden(hidden, now())) == Some(true) {
+                    lock();
+                }
+            }),
+        ];
+        let interval = set_interval_with_handle(
+            move || {
+                if timer.try_update_value(|x| x.tick(now())) == Some(true) {
+                    lock();
+                }
+            },
+            IDLE_TICK,
+        )
+        .ok();
+        on_cleanup(move || {
+            for handle in handles {
+                handle.remove();
+            }
+            if let Some(interval) = interval {
+                interval.clear();
+            }
+        });
+    });
+
+    Effect::new(move |_| match unlock.value().get() {
+        Some(Ok(RefreshResponse {
+            access_token,
+            expires_in,
+            server_time,
+            ..
+        })) => {
+            timer.update_value(|x| x.reset(now()));
+            rw_locked.set(false);
+            rw_token_clock.set(ServerClock::observe(server_time, Utc::now().timestamp()));
+            rw_expires_in.set(expires_in);
+            rw_auth_token.set(Some(access_token));
+        }
+        Some(Err(_)) => {
+            rw_locked.set(false);
+            rw_refreshed.set(Some(false));
+            let location = window().location();
+            let path = format!(
+                "{}{}",
+                location.pathname().unwrap_or_default(),
+                location.search().unwrap_or_default()
+            );
+            let _ = location.set_href(&login_path(&path));
+        }
+        None => {}
+    });
+
+    view! {
+        <Show when=move || rw_locked.get()>
+            <div
+                class="fixed inset-0 z-50 flex flex-col items-center justify-center bg-ctp-crust text-ctp-text"
+                role="dialog"
+                aria-modal="true"
+                data-idle-lock="locked"
+            >
+                <h2 class="mb-2 text-xl font-medium">"Treasury is locked"</h2>
+                <p class="mb-4 text-ctp-subtext0">"It locked itself while you were away."</p>
+                <button
+                    class="rounded-full bg-ctp-surface0 hover:bg-ctp-surface1 px-4 py-2 font-medium transition cursor-pointer transition-colors"
+                    disabled=move || unlock.pending().get()
+                    on:click=move |_| {
+                        unlock.dispatch(SsoRefresh {});
+                    }
+                >
+                    "Unlock"
+                </button>
+            </div>
+        </Show>
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use leptos::tachys::view::RenderHtml;
+
+    use super::*;
+
+    const MINUTE: i64 = 60_000;
+
+    fn settings(minutes: u64, while_hidden: bool) -> IdleSettings {
+        IdleSettings {
+            timeout: Some(Duration::from_secs(minutes * 60)),
+            while_hidden,
+        }
+    }
+
+    #[test]
+    fn it_locks_after_the_idle_time_unless_there_is_activity() {
+        let mut timer = IdleTimer::new(settings(10, true), 0);
+        assert!(!timer.tick(9 * MINUTE));
+        assert!(!timer.activity(9 * MINUTE));
+        assert!(!timer.tick(18 * MINUTE));
+        assert!(timer.tick(19 * MINUTE));
+        assert!(timer.is_locked());
+        // It only locks once, and activity doesn't unlock it.
+        assert!(!timer.tick(20 * MINUTE));
+        assert!(!timer.activity(21 * MINUTE));
+        assert!(timer.is_locked());
+
+        timer.reset(30 * MINUTE);
+        assert!(!timer.is_locked());
+        assert!(!timer.tick(39 * MINUTE));
+        assert!(timer.tick(40 * MINUTE));
+    }
+
+    #[test]
+    fn it_locks_rather_than_restarts_on_activity_after_a_throttled_tick() {
+        let mut timer = IdleTimer::new(settings(10, true), 0);
+        assert!(timer.activity(15 * MINUTE));
+        assert!(timer.is_locked());
+    }
+
+    #[test]
+    fn it_counts_hidden_time_if_the_user_prefers() {
+        let mut timer = IdleTimer::new(settings(10, true), 0);
+        assert!(!timer.set_hidden(true, MINUTE));
+        // Coming back isn't activity, the time away counts.
+        assert!(timer.set_hidden(false, 12 * MINUTE));
+    }
+
+    #[test]
+    fn it_pauses_while_hidden_if_the_user_prefers() {
+        let mut timer = IdleTimer::new(settings(10, false), 0);
+        assert!(!timer.set_hidden(true, 5 * MINUTE));
+        assert!(!timer.tick(60 * MINUTE));
+        assert!(!timer.set_hidden(false, 120 * MINUTE));
+        assert!(!timer.tick(124 * MINUTE));
+        assert!(timer.tick(125 * MINUTE));
+    }
+
+    #[test]
+    fn it_keeps_the_idle_time_when_reconfigured() {
+        let mut timer = IdleTimer::new(IdleSettings::default(), 0);
+        assert!(!timer.tick(4 * MINUTE));
+        assert!(timer.configure(settings(3, true), 4 * MINUTE));
+
+        let mut timer = IdleTimer::new(IdleSettings::default(), 0);
+        let never = IdleSettings::from(&PreferenceResponse {
+            idle_lock_minutes: 0,
+            ..Default::default()
+        });
+        assert_eq!(never.timeout, None);
+        assert!(!timer.configure(never, 0));
+        assert!(!timer.tick(24 * 60 * MINUTE));
+    }
+
+    #[test]
+    fn it_counts_no_time_when_the_clock_goes_back() {
+        let mut timer = IdleTimer::new(settings(10, true), 20 * MINUTE);
+        assert!(!timer.tick(0));
+        assert!(!timer.tick(9 * MINUTE));
+        assert!(timer.tick(10 * MINUTE));
+    }
+
+    #[test]
+    fn it_renders_on_the_server_without_a_window() {
+        // Effects don't run on the server, so rendering the lock screen
+        // must not reach for `window` or `document` outside of them.
+        let owner = Owner::new();
+        owner.with(|| {
+            provide_context(AuthToken(RwSignal::new(None)));
+            provide_context(ExpiresIn(RwSignal::new(0)));
+            provide_context(TokenClock(RwSignal::new(ServerClock::default())));
+            provide_context(SessionRefresh(RwSignal::new(Some(true))));
+            provide_context(IdleLocked(RwSignal::new(true)));
+            let html = view! { <IdleLock/> }.to_html();
+            assert!(html.contains("data-idle-lock=\"locked\""));
+            assert!(html.contains("Unlock"));
+        });
+    }
+}
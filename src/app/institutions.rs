@@ -1,9 +1,381 @@
 use leptos::prelude::*;
+use leptos_router::components::Outlet;
+use reqwest::Method;
+use serde_json::json;
 
+use crate::{
+    api::{
+        ApiError, ClientErrorCode,
+        institution_api::{create as institution_create, get_list as institution_get_list},
+    },
+    app::{
+        AuthToken,
+        capabilities::use_capabilities,
+        passkeys::request,
+        toast::{Toasts, error_message},
+    },
+    model::institution::InstitutionId,
+    schema::{
+        GetList, Pagination,
+        institution::{
+            CreateRequest, GetListRequest, InstitutionGetListResponse, InstitutionResponse,
+            InstitutionUpdateResponse,
+        },
+        text::INSTITUTION_NAME,
+    },
+};
+
+type Institution = InstitutionResponse<GetList>;
+
+/// The dialog open over the list, if any.
+#[derive(Debug, Clone)]
+enum Dialog {
+    Create,
+    Edit(Institution),
+    Delete(Institution),
+}
+
+/// Checks the name the way the server will, so the dialog can say what is
+/// wrong before it is sent.
+fn validate_name(name: &str) -> Result<String, ApiError> {
+    let name = INSTITUTION_NAME.sanitize(name.trim())?;
+    if name.is_empty() {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            "Name the institution.",
+        ));
+    }
+    Ok(name)
+}
+
+/// The page of institutions after `cursor`, with the account counts.
+///
+/// The server fn takes the cursor decoded, which the browser can't do, so
+/// the pages after the first are fetched directly.
+pub async fn list_institutions(
+    auth_token: &str,
+    name: Option<&str>,
+    cursor: &str,
+) -> Result<InstitutionGetListResponse, ApiError> {
+    let name = name
+        .map(|x| format!("&name={}", urlencoding::encode(x)))
+        .unwrap_or_default();
+    request(
+        auth_token,
+        Method::GET,
+        &format!("/api/institutions?include_counts=true&cursor={cursor}{name}"),
+        None,
+    )
+    .await
+}
+
+// Updates and deletes are addressed by id, which server fn endpoints can't
+// express, so they are called directly.
+pub async fn update_institution(
+    auth_token: &str,
+    id: InstitutionId,
+    name: &str,
+) -> Result<InstitutionUpdateResponse, ApiError> {
+    request(
+        auth_token,
+        Method::PATCH,
+        &format!("/api/institutions/{id}"),
+        Some(json!({ "name": name })),
+    )
+    .await
+}
+
+pub async fn delete_institution(auth_token: &str, id: InstitutionId) -> Result<(), ApiError> {
+    request(
+        auth_token,
+        Method::DELETE,
+        &format!("/api/institutions/{id}"),
+        None,
+    )
+    .await
+}
+
+/// Browses the institutions with the number of the user's accounts at each.
+/// Users who may manage institutions can also create, rename and delete
+/// them; everyone else gets the list alone, which they need to pick an
+/// institution for a new account.
 #[component]
 pub fn Institutions() -> impl IntoView {
+    let auth_token = expect_context::<AuthToken>().0;
+    let rw_search = RwSignal::new(String::new());
+    let rw_name = RwSignal::<Option<String>>::new(None);
+    let rw_version = RwSignal::new(0);
+    let rw_more = RwSignal::<Vec<Institution>>::new(vec![]);
+    // The cursor after the last page shown, once more than the first is.
+    let rw_more_cursor = RwSignal::<Option<Option<String>>>::new(None);
+    let rw_dialog = RwSignal::<Option<Dialog>>::new(None);
+    let toasts = expect_context::<Toasts>();
+
+    let capabilities = use_capabilities();
+    let can_create = capabilities.can_create_institution();
+    let can_update = capabilities.can_update_institution();
+    let can_delete = capabilities.can_delete_institution();
+
+    // The first page is a `Resource` so it is rendered with the page, later
+    // pages are appended to it as they are asked for.
+    let first_page = Resource::new(
+        move || (auth_token.get(), rw_name.get(), rw_version.get()),
+        move |(auth_token, name, _)| async move {
+            if auth_token.is_none() {
+                return None;
+            }
+            let filter = GetListRequest {
+                name,
+                include_counts: Some(true),
+                ..Default::default()
+            };
+            match institution_get_list(filter, Pagination::default()).await {
+                Ok(response) => Some(response),
+                Err(e) => {
+                    toasts.error(&e);
+                    None
+                }
+            }
+        },
+    );
+    let next_cursor = move || {
+        rw_more_cursor
+            .get()
+            .unwrap_or_else(|| first_page.get().flatten().and_then(|x| x.next_cursor))
+    };
+    let reload = move || {
+        rw_more.set(vec![]);
+        rw_more_cursor.set(None);
+        rw_version.update(|v| *v += 1);
+    };
+
+    let show_more = move |_| {
+        let (Some(auth_token), Some(cursor)) = (auth_token.get_untracked(), next_cursor()) else {
+            return;
+        };
+        let name = rw_name.get_untracked();
+        leptos::task::spawn_local(async move {
+            match list_institutions(&auth_token, name.as_deref(), &cursor).await {
+                Ok(response) => {
+                    rw_more.update(|more| more.extend(response.institutions));
+                    rw_more_cursor.set(Some(response.next_cursor));
+                }
+                Err(e) => toasts.error(&e),
+            }
+        });
+    };
+
+    let search = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        let name = rw_search.get_untracked().trim().to_owned();
+        rw_name.set((!name.is_empty()).then_some(name));
+        reload();
+    };
+
+    let on_saved = Callback::new(move |_: ()| {
+        rw_dialog.set(None);
+        reload();
+    });
+
+    view! {
+        <div class="container mx-auto px-4 py-8 text-ctp-text">
+            <div class="mb-4 flex flex-row gap-2">
+                <form class="flex flex-auto flex-row" on:submit=search>
+                    <input class="flex-auto rounded-l-full bg-ctp-surface0 px-4 py-2" type="search" placeholder="Search institutions" bind:value=rw_search/>
+                    <button class="cursor-pointer rounded-r-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" type="submit">
+                        "Search"
+                    </button>
+                </form>
+                <Show when=move || can_create.get()>
+                    <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=move |_| rw_dialog.set(Some(Dialog::Create))>
+                        "New institution"
+                    </button>
+                </Show>
+            </div>
+            <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+                <table class="w-full table-auto bg-ctp-base">
+                    <thead>
+                        <tr>
+                            <th class="border border-ctp-surface2 px-2 text-left text-ctp-yellow">"Institution"</th>
+                            <th class="border border-ctp-surface2 px-2 text-right text-ctp-blue">"Accounts"</th>
+                            <th class="border border-ctp-surface2 px-2"></th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {move || {
+                            let institutions = first_page
+                                .get()
+                                .flatten()
+                                .map(|x| x.institutions)
+                                .unwrap_or_default();
+                            institutions.into_iter().chain(rw_more.get()).map(|institution| {
+                                let edit = institution.clone();
+                                let delete = institution.clone();
+                                view! {
+                                    <tr class="border border-ctp-surface2 even:bg-ctp-surface1 odd:bg-ctp-surface0">
+                                        <td class="border border-ctp-surface2 px-2">{institution.name}</td>
+                                        <td class="border border-ctp-surface2 px-2 text-right">
+                                            {institution.account_count.unwrap_or_default()}
+                                        </td>
+                                        <td class="border border-ctp-surface2 px-2 text-right">
+                                            <Show when=move || can_update.get()>
+                                                <button class="cursor-pointer px-2 hover:text-ctp-blue" on:click={
+                                                    let edit = edit.clone();
+                                                    move |_| rw_dialog.set(Some(Dialog::Edit(edit.clone())))
+                                                }>
+                                                    "Edit"
+                                                </button>
+                                            </Show>
+                                            <Show when=move || can_delete.get()>
+                                                <button class="cursor-pointer px-2 hover:text-ctp-red" on:click={
+                                                    let delete = delete.clone();
+                                                    move |_| rw_dialog.set(Some(Dialog::Delete(delete.clone())))
+                                                }>
+                                                    "Delete"
+                                                </button>
+                                            </Show>
+                                        </td>
+                                    </tr>
+                                }
+                            }).collect_view()
+                        }}
+                    </tbody>
+                </table>
+            </Suspense>
+            <Show when=move || next_cursor().is_some()>
+                <button class="mt-2 cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=show_more>
+                    "Show more"
+                </button>
+            </Show>
+            {move || rw_dialog.get().map(|dialog| match dialog {
+                Dialog::Create => view! { <NameDialog institution=None rw_dialog on_saved/> }.into_any(),
+                Dialog::Edit(institution) => {
+                    view! { <NameDialog institution=Some(institution) rw_dialog on_saved/> }.into_any()
+                }
+                Dialog::Delete(institution) => {
+                    view! { <DeleteDialog institution rw_dialog on_saved/> }.into_any()
+                }
+            })}
+        </div>
+        <Outlet/>
+    }
+}
+
+/// Creates an institution, or renames `institution` when it is given.
+#[component]
+fn NameDialog(
+    institution: Option<Institution>,
+    rw_dialog: RwSignal<Option<Dialog>>,
+    on_saved: Callback<()>,
+) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let toasts = expect_context::<Toasts>();
+    let id = institution.as_ref().map(|x| x.id);
+    let title = if id.is_some() {
+        "Rename institution"
+    } else {
+        "New institution"
+    };
+    let rw_name = RwSignal::new(institution.map(|x| x.name).unwrap_or_default());
+    let error = move || {
+        validate_name(&rw_name.get())
+            .err()
+            .map(|e| error_message(&e, toasts.1))
+    };
+
+    let save = move |_| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        let name = match validate_name(&rw_name.get_untracked()) {
+            Ok(name) => name,
+            Err(e) => return toasts.error(&e),
+        };
+        leptos::task::spawn_local(async move {
+            let result = match id {
+                Some(id) => update_institution(&auth_token, id, &name).await.map(|_| ()),
+                None => institution_create(CreateRequest {
+                    name,
+                    parent_id: None,
+                    default_asset_id: None,
+                })
+                .await
+                .map(|_| ()),
+            };
+            match result {
+                Ok(()) => {
+                    toasts.success("Institution saved.");
+                    on_saved.run(());
+                }
+                Err(e) => toasts.error(&e),
+            }
+        });
+    };
+
+    view! {
+        <div class="fixed inset-0 flex items-center justify-center bg-ctp-crust/75" role="dialog" aria-modal="true">
+            <div class="w-full max-w-md rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+                <h2 class="mb-2 font-medium">{title}</h2>
+                <input class="w-full rounded-full bg-ctp-surface1 px-4 py-2" type="text" placeholder="Name" bind:value=rw_name/>
+                <p class="mt-1 min-h-5 text-sm text-ctp-red">{error}</p>
+                <div class="mt-2 flex flex-row justify-end gap-2">
+                    <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=move |_| rw_dialog.set(None)>
+                        "Cancel"
+                    </button>
+                    <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" disabled=move || error().is_some() on:click=save>
+                        "Save"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// Confirms deleting an institution. The server refuses while accounts are
+/// still held there, which is shown in the dialog since there is nothing
+/// to retry.
+#[component]
+fn DeleteDialog(
+    institution: Institution,
+    rw_dialog: RwSignal<Option<Dialog>>,
+    on_saved: Callback<()>,
+) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let toasts = expect_context::<Toasts>();
+    let rw_error = RwSignal::<Option<String>>::new(None);
+    let id = institution.id;
+
+    let delete = move |_| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match delete_institution(&auth_token, id).await {
+                Ok(()) => {
+                    toasts.success("Institution deleted.");
+                    on_saved.run(());
+                }
+                Err(ApiError::ClientError(_, message)) => rw_error.set(Some(message)),
+                Err(e) => toasts.error(&e),
+            }
+        });
+    };
+
     view! {
-        <p>"Institutions"</p>
+        <div class="fixed inset-0 flex items-center justify-center bg-ctp-crust/75" role="dialog" aria-modal="true">
+            <div class="w-full max-w-md rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+                <h2 class="mb-2 font-medium">{format!("Delete {}?", institution.name)}</h2>
+                <p class="min-h-5 text-sm text-ctp-red">{move || rw_error.get()}</p>
+                <div class="mt-2 flex flex-row justify-end gap-2">
+                    <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=move |_| rw_dialog.set(None)>
+                        "Cancel"
+                    </button>
+                    <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 text-ctp-red hover:bg-ctp-surface2" disabled=move || rw_error.get().is_some() on:click=delete>
+                        "Delete"
+                    </button>
+                </div>
+            </div>
+        </div>
     }
 }
 
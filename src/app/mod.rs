@@ -1,27 +1,50 @@
+use chrono::Utc;
 use leptos::prelude::*;
-use leptos_meta::{MetaTags, Title, provide_meta_context};
+use leptos_meta::{HashedStylesheet, MetaTags, Title, provide_meta_context};
 use leptos_router::{
-    components::{ParentRoute, Route, Router, Routes},
+    SsrMode,
+    components::{ParentRoute, ProtectedRoute, Route, Router, Routes},
     path,
 };
 
-use crate::app::{
-    accounts::{AccountDetail, Accounts, NoAccount},
-    assets::{AssetDetail, Assets, NoAsset},
-    auth::{HandleAuth, Login, Logout, SsoRefresh},
-    home::Home,
-    institutions::{InstitutionDetail, Institutions, NoInstitution},
-    transactions::{NoTransaction, TransactionDetail, Transactions},
-    users::{NoUser, UserDetail, Users},
+use crate::{
+    api::{
+        client::{ApiStatus, RequestState},
+        messages::Language,
+    },
+    app::{
+        accounts::{AccountDetail, Accounts, NoAccount},
+        admin::AdminStats,
+        announcements::AnnouncementBanner,
+        assets::{AssetDetail, Assets, NoAsset},
+        auth::{DemoStart, HandleAuth, Login, Logout, RefreshResponse, ServerClock, SsoRefresh},
+        capabilities::Capabilities,
+        guard::{RequireAuth, RequireSignedOut},
+        home::Home,
+        idle::IdleLock,
+        institutions::{InstitutionDetail, Institutions, NoInstitution},
+        toast::{ToastHost, ToastQueue, Toasts},
+        transactions::{NoTransaction, TransactionDetail, Transactions},
+        users::{NoUser, UserDetail, Users},
+        welcome::Welcome,
+    },
 };
 
 pub mod accounts;
+pub mod admin;
+pub mod announcements;
 pub mod assets;
 pub mod auth;
+pub mod capabilities;
+pub mod guard;
 pub mod home;
+pub mod idle;
 pub mod institutions;
+pub mod passkeys;
+pub mod toast;
 pub mod transactions;
 pub mod users;
+pub mod welcome;
 
 pub fn shell(options: LeptosOptions) -> impl IntoView {
     view! {
@@ -31,8 +54,8 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
                 <meta charset="utf-8"/>
                 <meta name="viewport" content="width=device-width, initial-scale=1"/>
                 <AutoReload options=options.clone() />
-                <HydrationScripts options/>
-                <link rel="stylesheet" id="leptos" href="/pkg/treasury.css"/>
+                <HydrationScripts options=options.clone()/>
+                <HashedStylesheet options id="leptos"/>
                 <MetaTags/>
             </head>
             <body class="ctp-mocha bg-gradient-to-b from-ctp-base to-ctp-crust h-full min-h-screen">
@@ -46,17 +69,42 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
 pub struct AuthToken(pub RwSignal<Option<String>>);
 #[derive(Clone, Debug)]
 pub struct ExpiresIn(pub RwSignal<i64>);
+#[derive(Clone, Debug)]
+pub struct TokenClock(pub RwSignal<ServerClock>);
+/// Whether the last refresh of the session succeeded, `None` until the
+/// first one resolves.
+#[derive(Clone, Debug)]
+pub struct SessionRefresh(pub RwSignal<Option<bool>>);
+/// Whether the app locked itself after the user was idle. The session is
+/// not refreshed in the background while it is.
+#[derive(Clone, Debug)]
+pub struct IdleLocked(pub RwSignal<bool>);
 
 #[component]
 pub fn App() -> impl IntoView {
     provide_meta_context();
     let rw_auth_token = RwSignal::<Option<String>, _>::new(None);
     let rw_expires_in = RwSignal::<i64, _>::new(0);
+    let rw_token_clock = RwSignal::new(ServerClock::default());
+    let rw_refreshed = RwSignal::new(None);
+    let rw_locked = RwSignal::new(false);
 
     provide_context(AuthToken(rw_auth_token));
     provide_context(ExpiresIn(rw_expires_in));
+    provide_context(TokenClock(rw_token_clock));
+    provide_context(SessionRefresh(rw_refreshed));
+    provide_context(IdleLocked(rw_locked));
+    let language = Language::of_browser();
+    provide_context(language);
+    provide_context(Toasts(RwSignal::new(ToastQueue::default()), language));
+    let api_status = ApiStatus(RwSignal::new(RequestState::Idle));
+    provide_context(api_status);
+    let capabilities = Capabilities::new(rw_auth_token, api_status);
+    provide_context(capabilities);
+    let can_view_admin = capabilities.can_view_admin();
 
     let refresh_token = ServerAction::<SsoRefresh>::new();
+    let is_admin = move || capabilities.admin();
 
     Effect::new(move |handle: Option<Option<TimeoutHandle>>| {
         if let Some(prev_handle) = handle.flatten() {
@@ -67,11 +115,13 @@ pub fn App() -> impl IntoView {
         if expires_in != 0 {
             let handle = set_timeout_with_handle(
                 move || {
-                    refresh_token.dispatch(SsoRefresh {});
+                    if !rw_locked.get_untracked() {
+                        refresh_token.dispatch(SsoRefresh {});
+                    }
                 },
-                std::time::Duration::from_secs(
-                    expires_in.checked_sub(30).unwrap_or_default() as u64
-                ),
+                rw_token_clock
+                    .get()
+                    .refresh_delay(expires_in, Utc::now().timestamp()),
             )
             .unwrap();
             Some(handle)
@@ -88,11 +138,22 @@ pub fn App() -> impl IntoView {
         }
     });
 
-    Effect::new(move |_| {
-        if let Some(Ok((auth_token, expires_in))) = refresh_token.value().get() {
+    Effect::new(move |_| match refresh_token.value().get() {
+        // Only unlocking brings the token back.
+        Some(Ok(_)) if rw_locked.get_untracked() => {}
+        Some(Ok(RefreshResponse {
+            access_token,
+            expires_in,
+            server_time,
+            ..
+        })) => {
+            rw_token_clock.set(ServerClock::observe(server_time, Utc::now().timestamp()));
             rw_expires_in.set(expires_in);
-            rw_auth_token.set(Some(auth_token));
+            rw_auth_token.set(Some(access_token));
+            rw_refreshed.set(Some(true));
         }
+        Some(Err(_)) => rw_refreshed.set(Some(false)),
+        None => {}
     });
 
     view! {
@@ -108,36 +169,53 @@ pub fn App() -> impl IntoView {
                         <a class="border-x-1 border-ctp-overlay0 bg-ctp-surface0 hover:bg-ctp-surface1 px-4 py-2 font-medium transition cursor-pointer transition-colors">"Accounts"</a>
                         <a class="rounded-r-full border-l-1 border-ctp-overlay0 bg-ctp-surface0 hover:bg-ctp-surface1 px-4 py-2 font-medium transition cursor-pointer transition-colors">"Transactions"</a>
                         <div class="flex-auto"></div>
+                        <Show when=move || can_view_admin.get()>
+                            <a class="mr-4 rounded-full bg-ctp-surface0 hover:bg-ctp-surface1 px-4 py-2 font-medium transition cursor-pointer transition-colors" href="/admin">"Admin"</a>
+                        </Show>
                         <a class="rounded-l-full border-ctp-overlay0 border-r-1 bg-ctp-surface0 hover:bg-ctp-surface1 px-4 py-2 font-medium transition cursor-pointer transition-colors" href="/profile">"Profile Options"</a>
                         <Logout/>
                     </Show>
                 </nav>
+                <AnnouncementBanner/>
+                <Show when=move || matches!(api_status.0.get(), RequestState::Retrying { .. })>
+                    <div class="mx-1 mb-1 rounded-lg border-l-4 border-ctp-yellow bg-ctp-surface0 px-4 py-2 text-ctp-text">
+                        "Reconnecting…"
+                    </div>
+                </Show>
 
+                // The data heavy pages stream out of order so the shell paints
+                // before their lists resolve inside `Suspense`. The pages of
+                // the signed in are guarded, see `guard`.
                 <Routes fallback=|| "This page could not be found.">
-                    <Route path=path!("/oauth2-redirect") view=HandleAuth/>
-                    <Route path=path!("/home") view=Home/>
-                    <ParentRoute path=path!("/accounts") view=Accounts>
+                    <Route path=path!("/oauth2-redirect") view=|| view! { <RequireSignedOut><HandleAuth/></RequireSignedOut> }/>
+                    <Route path=path!("/demo") view=DemoStart/>
+                    <Route path=path!("/home") view=Home ssr=SsrMode::OutOfOrder/>
+                    <Route path=path!("/welcome") view=|| view! { <RequireAuth><Welcome/></RequireAuth> }/>
+                    <ProtectedRoute path=path!("/admin") view=AdminStats condition=is_admin redirect_path=|| "/home"/>
+                    <ParentRoute path=path!("/accounts") view=|| view! { <RequireAuth><Accounts/></RequireAuth> } ssr=SsrMode::OutOfOrder>
                         <Route path=path!(":id") view=AccountDetail/>
                         <Route path=path!("") view=NoAccount/>
                     </ParentRoute>
-                    <ParentRoute path=path!("/users") view=Users>
+                    <ParentRoute path=path!("/users") view=|| view! { <RequireAuth><Users/></RequireAuth> }>
                         <Route path=path!(":id") view=UserDetail/>
                         <Route path=path!("") view=NoUser/>
                     </ParentRoute>
-                    <ParentRoute path=path!("/assets") view=Assets>
+                    <ParentRoute path=path!("/assets") view=|| view! { <RequireAuth><Assets/></RequireAuth> }>
                         <Route path=path!(":id") view=AssetDetail/>
                         <Route path=path!("") view=NoAsset/>
                     </ParentRoute>
-                    <ParentRoute path=path!("/institutions") view=Institutions>
+                    <ParentRoute path=path!("/institutions") view=|| view! { <RequireAuth><Institutions/></RequireAuth> } ssr=SsrMode::OutOfOrder>
                         <Route path=path!(":id") view=InstitutionDetail/>
                         <Route path=path!("") view=NoInstitution/>
                     </ParentRoute>
-                    <ParentRoute path=path!("/transactions") view=Transactions>
+                    <ParentRoute path=path!("/transactions") view=|| view! { <RequireAuth><Transactions/></RequireAuth> } ssr=SsrMode::OutOfOrder>
                         <Route path=path!(":id") view=TransactionDetail/>
                         <Route path=path!("") view=NoTransaction/>
                     </ParentRoute>
                 </Routes>
+                <IdleLock/>
             </Router>
+            <ToastHost/>
         </main>
     }
 }
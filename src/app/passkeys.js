@@ -0,0 +1,62 @@
+// Glue between the WebAuthn JSON options produced by the server and
+// `navigator.credentials`, which works with ArrayBuffers.
+
+function toBuffer(value) {
+  const base64 = value.replace(/-/g, "+").replace(/_/g, "/");
+  const padded = base64 + "===".slice((base64.length + 3) % 4);
+  return Uint8Array.from(atob(padded), (c) => c.charCodeAt(0)).buffer;
+}
+
+function fromBuffer(buffer) {
+  const bytes = new Uint8Array(buffer);
+  let binary = "";
+  for (const byte of bytes) {
+    binary += String.fromCharCode(byte);
+  }
+  return btoa(binary).replace(/\+/g, "-").replace(/\//g, "_").replace(/=+$/, "");
+}
+
+export async function createPasskey(options) {
+  const { publicKey } = JSON.parse(options);
+  publicKey.challenge = toBuffer(publicKey.challenge);
+  publicKey.user.id = toBuffer(publicKey.user.id);
+  for (const credential of publicKey.excludeCredentials ?? []) {
+    credential.id = toBuffer(credential.id);
+  }
+
+  const credential = await navigator.credentials.create({ publicKey });
+  return JSON.stringify({
+    id: credential.id,
+    rawId: fromBuffer(credential.rawId),
+    type: credential.type,
+    response: {
+      attestationObject: fromBuffer(credential.response.attestationObject),
+      clientDataJSON: fromBuffer(credential.response.clientDataJSON),
+      transports: credential.response.getTransports?.() ?? [],
+    },
+    extensions: credential.getClientExtensionResults(),
+  });
+}
+
+export async function getPasskey(options) {
+  const { publicKey } = JSON.parse(options);
+  publicKey.challenge = toBuffer(publicKey.challenge);
+  for (const credential of publicKey.allowCredentials ?? []) {
+    credential.id = toBuffer(credential.id);
+  }
+
+  const credential = await navigator.credentials.get({ publicKey });
+  const { response } = credential;
+  return JSON.stringify({
+    id: credential.id,
+    rawId: fromBuffer(credential.rawId),
+    type: credential.type,
+    response: {
+      authenticatorData: fromBuffer(response.authenticatorData),
+      clientDataJSON: fromBuffer(response.clientDataJSON),
+      signature: fromBuffer(response.signature),
+      userHandle: response.userHandle ? fromBuffer(response.userHandle) : null,
+    },
+    extensions: credential.getClientExtensionResults(),
+  });
+}
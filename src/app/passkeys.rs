@@ -0,0 +1,318 @@
+use leptos::prelude::*;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+use crate::{
+    api::{
+        ApiError, ClientErrorCode,
+        client::{ApiFailure, ApiStatus, RequestState},
+        messages::Language,
+    },
+    app::{AuthToken, toast::Toasts},
+    model::{passkey::PasskeyId, user::UserId},
+    schema::passkey::{
+        ChallengeResponse, PasskeyCreateResponse, PasskeyGetListResponse, StepUpFinishResponse,
+    },
+};
+
+#[cfg(feature = "hydrate")]
+mod glue {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen(module = "/src/app/passkeys.js")]
+    extern "C" {
+        #[wasm_bindgen(catch, js_name = createPasskey)]
+        pub async fn create_passkey(options: String) -> Result<JsValue, JsValue>;
+        #[wasm_bindgen(catch, js_name = getPasskey)]
+        pub async fn get_passkey(options: String) -> Result<JsValue, JsValue>;
+    }
+}
+
+/// Runs `navigator.credentials.create` (or `.get`) with the server options.
+async fn credentials(create: bool, options: &Value) -> Result<Value, ApiError> {
+    #[cfg(feature = "hydrate")]
+    {
+        let options = options.to_string();
+        let result = if create {
+            glue::create_passkey(options).await
+        } else {
+            glue::get_passkey(options).await
+        };
+        let credential = result
+            .ok()
+            .and_then(|value| value.as_string())
+            .ok_or_else(|| {
+                ApiError::client(
+                    ClientErrorCode::InvalidPasskey,
+                    "The passkey prompt was dismissed.",
+                )
+            })?;
+        serde_json::from_str(&credential)
+            .map_err(|_| ApiError::client(ClientErrorCode::InvalidPasskey, "Invalid credential."))
+    }
+    #[cfg(not(feature = "hydrate"))]
+    {
+        let _ = (create, options);
+        Err(ApiError::client(
+            ClientErrorCode::InvalidPasskey,
+            "Passkeys require a browser.",
+        ))
+    }
+}
+
+// The passkey endpoints are addressed by user id, which server fn
+// endpoints can't express, so they are called directly. So are the user
+// endpoints, whose server fns are only built for the server.
+pub(crate) async fn request<T: DeserializeOwned>(
+    auth_token: &str,
+    method: Method,
+    path: &str,
+    body: Option<Value>,
+) -> Result<T, ApiError> {
+    request_with_status(auth_token, method, path, body)
+        .await
+        .map_err(|e| e.error)
+}
+
+/// The error of a request along with the status of its response, which
+/// the error read from the body doesn't carry.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestError {
+    /// `None` if there was no response
+    pub status: Option<u16>,
+    pub error: ApiError,
+}
+
+impl RequestError {
+    fn unanswered() -> Self {
+        Self {
+            status: None,
+            error: ApiError::ServerError,
+        }
+    }
+}
+
+/// [`request`] for callers that handle some statuses themselves, like a
+/// resource deleted elsewhere.
+pub(crate) async fn request_with_status<T: DeserializeOwned>(
+    auth_token: &str,
+    method: Method,
+    path: &str,
+    body: Option<Value>,
+) -> Result<T, RequestError> {
+    // Read up front, the request may resume outside of the owner.
+    let api_status = use_context::<ApiStatus>();
+    let origin = window()
+        .location()
+        .origin()
+        .map_err(|_| RequestError::unanswered())?;
+    let mut request = reqwest::Client::new()
+        .request(method, format!("{origin}{path}"))
+        .bearer_auth(auth_token)
+        .header("Accept", "application/json")
+        .header(
+            "Accept-Language",
+            use_context::<Language>().unwrap_or_default().tag(),
+        );
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|_| RequestError::unanswered())?;
+    let status = response.status().as_u16();
+    let success = response.status().is_success();
+    // A refusal tells the capabilities they may be out of date, as it does
+    // for requests of the `ApiClient`.
+    if let Some(api_status) = api_status {
+        let failure = ApiFailure::from_status(status, None);
+        api_status
+            .0
+            .try_set(failure.map_or(RequestState::Idle, RequestState::Failed));
+    }
+    let failed = |error| RequestError {
+        status: Some(status),
+        error,
+    };
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|_| failed(ApiError::ServerError))?;
+    let bytes: &[u8] = if bytes.is_empty() { b"null" } else { &bytes };
+    if success {
+        serde_json::from_slice::<T>(bytes).map_err(|_| failed(ApiError::ServerError))
+    } else {
+        Err(failed(
+            serde_json::from_slice::<ApiError>(bytes).unwrap_or(ApiError::ServerError),
+        ))
+    }
+}
+
+pub async fn list_passkeys(
+    auth_token: &str,
+    user_id: UserId,
+) -> Result<PasskeyGetListResponse, ApiError> {
+    request(
+        auth_token,
+        Method::GET,
+        &format!("/api/users/{user_id}/passkeys"),
+        None,
+    )
+    .await
+}
+
+pub async fn register_passkey(
+    auth_token: &str,
+    user_id: UserId,
+    name: &str,
+) -> Result<PasskeyCreateResponse, ApiError> {
+    let challenge = request::<ChallengeResponse>(
+        auth_token,
+        Method::POST,
+        &format!("/api/users/{user_id}/passkeys/register/start"),
+        Some(json!({})),
+    )
+    .await?;
+    let credential = credentials(true, &challenge.options).await?;
+    request(
+        auth_token,
+        Method::POST,
+        &format!("/api/users/{user_id}/passkeys/register/finish"),
+        Some(json!({
+            "challenge_id": challenge.challenge_id,
+            "name": name,
+            "credential": credential,
+        })),
+    )
+    .await
+}
+
+pub async fn remove_passkey(
+    auth_token: &str,
+    user_id: UserId,
+    passkey_id: PasskeyId,
+) -> Result<(), ApiError> {
+    request(
+        auth_token,
+        Method::DELETE,
+        &format!("/api/users/{user_id}/passkeys/{passkey_id}"),
+        None,
+    )
+    .await
+}
+
+pub async fn step_up(auth_token: &str, user_id: UserId) -> Result<StepUpFinishResponse, ApiError> {
+    let challenge = request::<ChallengeResponse>(
+        auth_token,
+        Method::POST,
+        &format!("/api/users/{user_id}/step-up/start"),
+        Some(json!({})),
+    )
+    .await?;
+    let credential = credentials(false, &challenge.options).await?;
+    request(
+        auth_token,
+        Method::POST,
+        &format!("/api/users/{user_id}/step-up/finish"),
+        Some(json!({
+            "challenge_id": challenge.challenge_id,
+            "credential": credential,
+        })),
+    )
+    .await
+}
+
+#[component]
+pub fn Passkeys(user_id: UserId) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let rw_version = RwSignal::new(0);
+    let rw_name = RwSignal::new(String::new());
+    let toasts = expect_context::<Toasts>();
+
+    let passkeys = LocalResource::new(move || {
+        rw_version.track();
+        let auth_token = rw_auth_token.get();
+        async move {
+            let Some(auth_token) = auth_token else {
+                return vec![];
+            };
+            list_passkeys(&auth_token, user_id)
+                .await
+                .map(|response| response.passkeys)
+                .unwrap_or_default()
+        }
+    });
+
+    let add = move |_| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        let name = rw_name.get_untracked();
+        leptos::task::spawn_local(async move {
+            let result = match register_passkey(&auth_token, user_id, &name).await {
+                Err(ApiError::StepUpRequired) => match step_up(&auth_token, user_id).await {
+                    Ok(_) => register_passkey(&auth_token, user_id, &name).await,
+                    Err(e) => Err(e),
+                },
+                result => result,
+            };
+            match result {
+                Ok(_) => toasts.success("Passkey added."),
+                Err(e) => toasts.error(&e),
+            }
+            rw_name.set(String::new());
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    let remove = move |passkey_id: PasskeyId| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            let result = match remove_passkey(&auth_token, user_id, passkey_id).await {
+                Err(ApiError::StepUpRequired) => match step_up(&auth_token, user_id).await {
+                    Ok(_) => remove_passkey(&auth_token, user_id, passkey_id).await,
+                    Err(e) => Err(e),
+                },
+                result => result,
+            };
+            match result {
+                Ok(()) => toasts.success("Passkey removed."),
+                Err(e) => toasts.error(&e),
+            }
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    view! {
+        <div class="m-2 rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+            <h2 class="mb-2 font-medium">"Passkeys"</h2>
+            <Suspense fallback=|| view! { <p>"Loading..."</p> }>
+                <ul>
+                    {move || passkeys.get().map(|passkeys| {
+                        passkeys.into_iter().map(|passkey| {
+                            let passkey_id = passkey.id;
+                            view! {
+                                <li class="flex flex-row py-1">
+                                    <span class="flex-auto">{passkey.name}</span>
+                                    <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 hover:bg-ctp-surface2" on:click=move |_| remove(passkey_id)>
+                                        "Remove"
+                                    </button>
+                                </li>
+                            }
+                        }).collect_view()
+                    })}
+                </ul>
+            </Suspense>
+            <div class="mt-2 flex flex-row">
+                <input class="flex-auto rounded-l-full bg-ctp-surface1 px-4 py-2" type="text" placeholder="Passkey name" bind:value=rw_name/>
+                <button class="cursor-pointer rounded-r-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=add>
+                    "Add passkey"
+                </button>
+            </div>
+        </div>
+    }
+}
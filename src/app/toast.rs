@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use leptos::prelude::*;
+
+use crate::api::{ApiError, ApiErrorResponse, messages::Language};
+
+/// How long a toast stays up before it dismisses itself.
+pub const TOAST_DURATION: Duration = Duration::from_secs(5);
+/// The most toasts shown at once, older ones are dropped first.
+pub const MAX_TOASTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn class(self) -> &'static str {
+        match self {
+            Self::Info => "border-ctp-blue",
+            Self::Success => "border-ctp-green",
+            Self::Warning => "border-ctp-yellow",
+            Self::Error => "border-ctp-red",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toast {
+    pub id: u64,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// The toasts currently shown, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct ToastQueue {
+    next_id: u64,
+    toasts: Vec<Toast>,
+}
+
+impl ToastQueue {
+    /// Queues a toast and returns its id, dropping the oldest toasts past
+    /// [`MAX_TOASTS`].
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.toasts.push(Toast {
+            id,
+            severity,
+            message: message.into(),
+        });
+        if self.toasts.len() > MAX_TOASTS {
+            let excess = self.toasts.len() - MAX_TOASTS;
+            self.toasts.drain(..excess);
+        }
+        id
+    }
+
+    /// Removes a toast. Toasts that were already dismissed are ignored.
+    pub fn dismiss(&mut self, id: u64) {
+        self.toasts.retain(|toast| toast.id != id);
+    }
+
+    pub fn toasts(&self) -> &[Toast] {
+        &self.toasts
+    }
+}
+
+/// The user readable message for an error from the API in `language`.
+/// The messages of client errors come from the server, which already wrote
+/// them in the language the app asked for.
+pub fn error_message(error: &ApiError, language: Language) -> String {
+    let message = match (language, error) {
+        (Language::En, ApiError::ServerError) => "Something went wrong, please try again.",
+        (Language::Ko, ApiError::ServerError) => "문제가 발생했습니다. 다시 시도해 주세요.",
+        (Language::En, ApiError::Forbidden) => "You are not allowed to do that.",
+        (Language::Ko, ApiError::Forbidden) => "그 작업을 할 권한이 없습니다.",
+        (Language::En, ApiError::StepUpRequired) => "Confirm it is you with a passkey to continue.",
+        (Language::Ko, ApiError::StepUpRequired) => "계속하려면 패스키로 본인 확인을 해 주세요.",
+        (Language::En, ApiError::TooManyRequests) => {
+            "You are doing that too often, please wait a moment."
+        }
+        (Language::Ko, ApiError::TooManyRequests) => {
+            "요청이 너무 잦습니다. 잠시 후 다시 시도해 주세요."
+        }
+        (language, ApiError::InvalidField(field_error)) => return field_error.message(language),
+        (_, error) => return error.to_string(),
+    };
+    message.into()
+}
+
+/// The toasts of the app, in the language it asks the API for, provided
+/// by [`App`](crate::app::App).
+#[derive(Debug, Clone, Copy)]
+pub struct Toasts(pub RwSignal<ToastQueue>, pub Language);
+
+impl Toasts {
+    /// Shows a toast until it is dismissed or [`TOAST_DURATION`] passes.
+    pub fn push(&self, severity: Severity, message: impl Into<String>) {
+        let queue = self.0;
+        let Some(id) = queue.try_update(|queue| queue.push(severity, message)) else {
+            return;
+        };
+        set_timeout(
+            move || {
+                queue.try_update(|queue| queue.dismiss(id));
+            },
+            TOAST_DURATION,
+        );
+    }
+
+    pub fn success(&self, message: impl Into<String>) {
+        self.push(Severity::Success, message);
+    }
+
+    pub fn error(&self, error: &ApiError) {
+        self.push(Severity::Error, error_message(error, self.1));
+    }
+
+    pub fn error_response(&self, response: ApiErrorResponse) {
+        self.error(&response.into());
+    }
+
+    pub fn dismiss(&self, id: u64) {
+        self.0.update(|queue| queue.dismiss(id));
+    }
+}
+
+/// Renders the queued toasts. Nothing is queued while rendering on the
+/// server, so the host hydrates as an empty list.
+#[component]
+pub fn ToastHost() -> impl IntoView {
+    let toasts = expect_context::<Toasts>();
+
+    view! {
+        <div class="fixed right-4 bottom-4 flex flex-col gap-2" role="status" aria-live="polite">
+            <For
+                each=move || toasts.0.with(|queue| queue.toasts().to_vec())
+                key=|toast| toast.id
+                children=move |toast: Toast| {
+                    let id = toast.id;
+                    view! {
+                        <div class=format!("flex flex-row rounded-lg border-l-4 bg-ctp-surface0 px-4 py-2 text-ctp-text shadow {}", toast.severity.class())>
+                            <span class="flex-auto pr-4">{toast.message}</span>
+                            <button class="cursor-pointer text-ctp-overlay1 hover:text-ctp-text" aria-label="Dismiss" on:click=move |_| toasts.dismiss(id)>
+                                "×"
+                            </button>
+                        </div>
+                    }
+                }
+            />
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use leptos::tachys::view::RenderHtml;
+
+    use super::*;
+    use crate::api::error::FieldError;
+
+    #[test]
+    fn it_queues_toasts_with_increasing_ids() {
+        let mut queue = ToastQueue::default();
+        let first = queue.push(Severity::Info, "first");
+        let second = queue.push(Severity::Error, "second");
+
+        assert!(first < second);
+        assert_eq!(
+            queue
+                .toasts()
+                .iter()
+                .map(|x| (x.severity, x.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(Severity::Info, "first"), (Severity::Error, "second")]
+        );
+    }
+
+    #[test]
+    fn it_dismisses_toasts_by_id() {
+        let mut queue = ToastQueue::default();
+        let first = queue.push(Severity::Info, "first");
+        let second = queue.push(Severity::Info, "second");
+
+        queue.dismiss(first);
+        queue.dismiss(first);
+        assert_eq!(
+            queue.toasts().iter().map(|x| x.id).collect::<Vec<_>>(),
+            vec![second]
+        );
+    }
+
+    #[test]
+    fn it_drops_the_oldest_toasts_past_the_limit() {
+        let mut queue = ToastQueue::default();
+        let ids = (0..MAX_TOASTS + 2)
+            .map(|i| queue.push(Severity::Info, format!("toast {i}")))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            queue.toasts().iter().map(|x| x.id).collect::<Vec<_>>(),
+            ids[2..]
+        );
+    }
+
+    #[test]
+    fn it_makes_api_errors_readable() {
+        let response = ApiErrorResponse {
+            code: 4001,
+            message: "The period must end after it starts.".into(),
+        };
+        assert_eq!(
+            error_message(&response.into(), Language::En),
+            "The period must end after it starts."
+        );
+        assert_eq!(
+            error_message(&ApiError::ServerError, Language::En),
+            "Something went wrong, please try again."
+        );
+    }
+
+    #[test]
+    fn it_localizes_api_errors() {
+        assert_eq!(
+            error_message(&ApiError::ServerError, Language::Ko),
+            "문제가 발생했습니다. 다시 시도해 주세요."
+        );
+        let error = ApiError::InvalidField(FieldError::TooLong("institution name", 100));
+        assert_eq!(
+            error_message(&error, Language::En),
+            "The institution name must be at most 100 characters."
+        );
+        assert_eq!(
+            error_message(&error, Language::Ko),
+            "기관 이름은(는) 최대 100자까지 쓸 수 있습니다."
+        );
+    }
+
+    #[test]
+    fn it_renders_messages_as_text() {
+        let owner = Owner::new();
+        let html = owner.with(|| {
+            let mut queue = ToastQueue::default();
+            queue.push(Severity::Error, "No column `<img src=x onerror=alert(1)>`.");
+            provide_context(Toasts(RwSignal::new(queue), Language::En));
+            view! { <ToastHost/> }.to_html()
+        });
+        assert!(!html.contains("<img"));
+        assert!(html.contains("&lt;img src=x onerror=alert(1)&gt;"));
+    }
+}
@@ -1,22 +1,1020 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
 use leptos::prelude::*;
+use leptos_router::{
+    NavigateOptions,
+    components::Outlet,
+    hooks::{use_navigate, use_params_map},
+};
+use reqwest::Method;
+use serde::Deserialize;
+
+use crate::{
+    api::{ApiError, ClientErrorCode},
+    app::{
+        AuthToken,
+        capabilities::use_capabilities,
+        passkeys::{RequestError, request, request_with_status},
+        toast::Toasts,
+    },
+    model::{
+        attachment::AttachmentId, transaction::TransactionId,
+        transaction_history::TransactionHistoryId,
+    },
+    schema::{
+        account::AccountGetResponse,
+        asset::AssetGetResponse,
+        attachment::{AttachmentCreateResponse, AttachmentGetListResponse, CreateRequest},
+        transaction::{
+            HistoryGetListResponse, TransactionGetResponse, TransactionUpdateResponse,
+            UpdateRequest,
+        },
+    },
+};
+
+#[cfg(feature = "hydrate")]
+mod glue {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen(module = "/src/app/files.js")]
+    extern "C" {
+        #[wasm_bindgen(catch, js_name = readFile)]
+        pub async fn read_file(input: &JsValue) -> Result<JsValue, JsValue>;
+        #[wasm_bindgen(catch, js_name = downloadFile)]
+        pub async fn download_file(
+            url: String,
+            token: String,
+            filename: String,
+        ) -> Result<JsValue, JsValue>;
+    }
+}
+
+/// The format of `posted_at` in the form, that of a `datetime-local` input.
+/// Dates are edited in UTC, as they are shown.
+const POSTED_AT_FORMAT: &str = "%Y-%m-%dT%H:%M";
 
 #[component]
 pub fn Transactions() -> impl IntoView {
     view! {
         <p>"Transactions"</p>
+        <Outlet/>
     }
 }
 
+#[component]
+pub fn NoTransaction() -> impl IntoView {
+    view! {
+        <p></p>
+    }
+}
+
+// The transaction endpoints are addressed by id, which server fn endpoints
+// can't express, so they are called directly. Those the page reads or
+// edits the transaction with keep the status, since a 404 and a 409 are
+// handled rather than shown.
+pub async fn get_transaction(
+    auth_token: &str,
+    id: TransactionId,
+) -> Result<TransactionGetResponse, RequestError> {
+    request_with_status(
+        auth_token,
+        Method::GET,
+        &format!("/api/transactions/{}", id.0),
+        None,
+    )
+    .await
+}
+
+pub async fn update_transaction(
+    auth_token: &str,
+    id: TransactionId,
+    update: &UpdateRequest,
+) -> Result<TransactionUpdateResponse, RequestError> {
+    request_with_status(
+        auth_token,
+        Method::PATCH,
+        &format!("/api/transactions/{}", id.0),
+        serde_json::to_value(update).ok(),
+    )
+    .await
+}
+
+pub async fn delete_transaction(auth_token: &str, id: TransactionId) -> Result<(), RequestError> {
+    request_with_status(
+        auth_token,
+        Method::DELETE,
+        &format!("/api/transactions/{}", id.0),
+        None,
+    )
+    .await
+}
+
+pub async fn list_history(
+    auth_token: &str,
+    id: TransactionId,
+) -> Result<HistoryGetListResponse, ApiError> {
+    request(
+        auth_token,
+        Method::GET,
+        &format!("/api/transactions/{}/history", id.0),
+        None,
+    )
+    .await
+}
+
+pub async fn revert_transaction(
+    auth_token: &str,
+    id: TransactionId,
+    history_id: TransactionHistoryId,
+) -> Result<TransactionUpdateResponse, ApiError> {
+    request(
+        auth_token,
+        Method::POST,
+        &format!("/api/transactions/{}/revert/{history_id}", id.0),
+        Some(serde_json::json!({})),
+    )
+    .await
+}
+
+pub async fn list_attachments(
+    auth_token: &str,
+    id: TransactionId,
+) -> Result<AttachmentGetListResponse, ApiError> {
+    request(
+        auth_token,
+        Method::GET,
+        &format!("/api/attachments?transaction_id={}", id.0),
+        None,
+    )
+    .await
+}
+
+pub async fn create_attachment(
+    auth_token: &str,
+    attachment: &CreateRequest,
+) -> Result<AttachmentCreateResponse, ApiError> {
+    request(
+        auth_token,
+        Method::POST,
+        "/api/attachments",
+        serde_json::to_value(attachment).ok(),
+    )
+    .await
+}
+
+pub async fn delete_attachment(auth_token: &str, id: AttachmentId) -> Result<(), ApiError> {
+    request(
+        auth_token,
+        Method::DELETE,
+        &format!("/api/attachments/{id}"),
+        None,
+    )
+    .await
+}
+
+/// The fields of a transaction the page edits, as they are in the form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Fields {
+    description: String,
+    posted_at: String,
+    category: String,
+}
+
+impl Fields {
+    fn of(transaction: &TransactionGetResponse) -> Self {
+        Self {
+            description: transaction.description.clone().unwrap_or_default(),
+            posted_at: transaction.posted_at.format(POSTED_AT_FORMAT).to_string(),
+            category: transaction.category.clone().unwrap_or_default(),
+        }
+    }
+
+    /// The update that makes `base` into the form, with only the fields
+    /// that differ, or `None` if none do. A date that isn't changed is not
+    /// sent, so the seconds the form doesn't show are kept.
+    fn changes(&self, base: &Self) -> Result<Option<UpdateRequest>, ApiError> {
+        let changed = |new: &str, old: &str| (new != old).then(|| new.trim().to_owned());
+        let posted_at = changed(&self.posted_at, &base.posted_at)
+            .map(|x| parse_posted_at(&x))
+            .transpose()?;
+        let description = changed(&self.description, &base.description);
+        let category = changed(&self.category, &base.category);
+        if posted_at.is_none() && description.is_none() && category.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(UpdateRequest {
+            asset_id: None,
+            posted_at,
+            description,
+            quantity: None,
+            notes: None,
+            category,
+        }))
+    }
+}
+
+fn parse_posted_at(value: &str) -> Result<DateTime<Utc>, ApiError> {
+    NaiveDateTime::parse_from_str(value, POSTED_AT_FORMAT)
+        .map(|x| x.and_utc())
+        .map_err(|_| {
+            ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "Enter when the transaction was posted.",
+            )
+        })
+}
+
+/// The form once the transaction changed under it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Merged {
+    fields: Fields,
+    /// The fields both the form and the server changed, differently, for
+    /// which the form keeps its own
+    conflicts: Vec<&'static str>,
+}
+
+/// Merges the edits made from `base` with the `latest` transaction: a field
+/// only one side changed takes that side's value, so the edits are kept
+/// where the server didn't touch them and the server's changes are taken
+/// where the user didn't.
+fn merge(base: &Fields, edits: &Fields, latest: &Fields) -> Merged {
+    let mut conflicts = vec![];
+    let mut field = |name, base: &str, edit: &str, latest: &str| {
+        if edit == base || edit == latest {
+            latest.to_owned()
+        } else {
+            if latest != base {
+                conflicts.push(name);
+            }
+            edit.to_owned()
+        }
+    };
+    let fields = Fields {
+        description: field(
+            "description",
+            &base.description,
+            &edits.description,
+            &latest.description,
+        ),
+        posted_at: field(
+            "posted at",
+            &base.posted_at,
+            &edits.posted_at,
+            &latest.posted_at,
+        ),
+        category: field(
+            "category",
+            &base.category,
+            &edits.category,
+            &latest.category,
+        ),
+    };
+    Merged { fields, conflicts }
+}
+
+/// The transaction with the names of its asset and account, which fall
+/// back to their ids if they can't be read.
+#[derive(Debug, Clone)]
+struct Detail {
+    transaction: TransactionGetResponse,
+    symbol: String,
+    account: String,
+}
+
+#[derive(Debug, Clone)]
+enum Loaded {
+    Found(Box<Detail>),
+    /// The transaction was deleted, on another device or in another tab
+    Gone,
+    Failed(ApiError),
+}
+
+async fn load(auth_token: &str, id: TransactionId) -> Loaded {
+    let transaction = match get_transaction(auth_token, id).await {
+        Ok(transaction) => transaction,
+        Err(RequestError {
+            status: Some(404), ..
+        }) => return Loaded::Gone,
+        Err(e) => return Loaded::Failed(e.error),
+    };
+    let symbol = request::<AssetGetResponse>(
+        auth_token,
+        Method::GET,
+        &format!("/api/assets/{}", transaction.asset_id.0),
+        None,
+    )
+    .await
+    .map_or_else(|_| transaction.asset_id.0.to_string(), |x| x.symbol);
+    let account = request::<AccountGetResponse>(
+        auth_token,
+        Method::GET,
+        &format!("/api/accounts/{}", transaction.account_id),
+        None,
+    )
+    .await
+    .map_or_else(|_| transaction.account_id.to_string(), |x| x.name);
+    Loaded::Found(Box::new(Detail {
+        transaction,
+        symbol,
+        account,
+    }))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DetailTab {
+    Attachments,
+    History,
+}
+
 #[component]
 pub fn TransactionDetail() -> impl IntoView {
+    let params = use_params_map();
+    let id = move || {
+        params
+            .read()
+            .get("id")
+            .and_then(|id| id.parse::<TransactionId>().ok())
+    };
+
     view! {
-        <p>"Transaction Detail"</p>
+        {move || match id() {
+            Some(id) => view! { <TransactionPage id/> }.into_any(),
+            None => view! { <p class="m-2 text-ctp-text">"There is no such transaction."</p> }.into_any(),
+        }}
     }
 }
 
+/// A transaction with its edit form and the tabs of what hangs off it, each
+/// of which loads when it is first opened.
+///
+/// Edits are sent as the fields that changed since the form was opened. If
+/// the server refuses them with a 409 the transaction is read again and the
+/// edits merged into it, so the user only has to look at the fields the
+/// server changed too.
 #[component]
-pub fn NoTransaction() -> impl IntoView {
+fn TransactionPage(id: TransactionId) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let toasts = expect_context::<Toasts>();
+    let navigate = use_navigate();
+    let rw_version = RwSignal::new(0);
+    // The fields the form was opened with, while it is open.
+    let rw_base = RwSignal::<Option<Fields>>::new(None);
+    let rw_description = RwSignal::new(String::new());
+    let rw_posted_at = RwSignal::new(String::new());
+    let rw_category = RwSignal::new(String::new());
+    let rw_conflicts = RwSignal::<Vec<&'static str>>::new(vec![]);
+    let rw_confirm_delete = RwSignal::new(false);
+    let rw_deleted = RwSignal::new(false);
+    let rw_tab = RwSignal::<Option<DetailTab>>::new(None);
+
+    let capabilities = use_capabilities();
+    let can_update = capabilities.can_update_transaction();
+    let can_delete = capabilities.can_delete_transaction();
+    let can_view_attachments = capabilities.can_view_attachments();
+    let can_view_history = capabilities.can_view_transaction_history();
+
+    let detail = LocalResource::new(move || {
+        rw_version.track();
+        let auth_token = rw_auth_token.get();
+        async move {
+            let auth_token = auth_token?;
+            Some(load(&auth_token, id).await)
+        }
+    });
+
+    Effect::new(move |_| {
+        if rw_deleted.get() {
+            navigate("/transactions", NavigateOptions::default());
+        }
+    });
+
+    let form = move || Fields {
+        description: rw_description.get_untracked(),
+        posted_at: rw_posted_at.get_untracked(),
+        category: rw_category.get_untracked(),
+    };
+    let set_form = move |fields: Fields| {
+        rw_description.set(fields.description);
+        rw_posted_at.set(fields.posted_at);
+        rw_category.set(fields.category);
+    };
+
+    let edit = move |fields: Fields| {
+        rw_conflicts.set(vec![]);
+        rw_base.set(Some(fields.clone()));
+        set_form(fields);
+    };
+
+    let save = move |_| {
+        let (Some(auth_token), Some(base)) =
+            (rw_auth_token.get_untracked(), rw_base.get_untracked())
+        else {
+            return;
+        };
+        let edits = form();
+        let update = match edits.changes(&base) {
+            Ok(Some(update)) => update,
+            Ok(None) => return rw_base.set(None),
+            Err(e) => return toasts.error(&e),
+        };
+        leptos::task::spawn_local(async move {
+            match update_transaction(&auth_token, id, &update).await {
+                Ok(_) => {
+                    toasts.success("Transaction saved.");
+                    rw_base.set(None);
+                    rw_conflicts.set(vec![]);
+                }
+                Err(RequestError {
+                    status: Some(404), ..
+                }) => rw_base.set(None),
+                Err(RequestError {
+                    status: Some(409),
+                    error,
+                }) => {
+                    toasts.error(&error);
+                    match get_transaction(&auth_token, id).await {
+                        Ok(latest) => {
+                            let latest = Fields::of(&latest);
+                            let merged = merge(&base, &edits, &latest);
+                            rw_base.set(Some(latest));
+                            set_form(merged.fields);
+                            rw_conflicts.set(merged.conflicts);
+                        }
+                        Err(_) => rw_base.set(None),
+                    }
+                }
+                Err(e) => return toasts.error(&e.error),
+            }
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    let delete = move |_| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match delete_transaction(&auth_token, id).await {
+                // Already deleted elsewhere is as good as deleted here.
+                Ok(())
+                | Err(RequestError {
+                    status: Some(404), ..
+                }) => {
+                    toasts.success("Transaction deleted.");
+                    rw_deleted.set(true);
+                }
+                Err(e) => {
+                    rw_confirm_delete.set(false);
+                    toasts.error(&e.error);
+                }
+            }
+        });
+    };
+
+    let tab_class = move |tab| {
+        if rw_tab.get() == Some(tab) {
+            "cursor-pointer rounded-full bg-ctp-surface2 px-4 py-1"
+        } else {
+            "cursor-pointer rounded-full bg-ctp-surface0 px-4 py-1 hover:bg-ctp-surface1"
+        }
+    };
+    let on_reverted = Callback::new(move |_: ()| rw_version.update(|v| *v += 1));
+
     view! {
-        <p></p>
+        <Suspense fallback=|| view! { <p>"Loading..."</p> }>
+            {move || detail.get().flatten().map(|loaded| match loaded {
+                Loaded::Gone => view! {
+                    <div class="m-2 rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+                        <p>"This transaction was deleted, perhaps on another device."</p>
+                        <a class="text-ctp-blue hover:underline" href="/transactions">"Back to transactions"</a>
+                    </div>
+                }.into_any(),
+                Loaded::Failed(e) => {
+                    toasts.error(&e);
+                    view! {
+                        <div class="m-2 rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+                            <p>"The transaction could not be loaded."</p>
+                            <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=move |_| rw_version.update(|v| *v += 1)>
+                                "Try again"
+                            </button>
+                        </div>
+                    }.into_any()
+                }
+                Loaded::Found(found) => {
+                    let Detail { transaction, symbol, account } = *found;
+                    let fields = Fields::of(&transaction);
+                    let quantity_class = if transaction.quantity.is_sign_negative() {
+                        "text-2xl text-ctp-red"
+                    } else {
+                        "text-2xl text-ctp-green"
+                    };
+                    view! {
+                        <div class="m-2 rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+                            <p class=quantity_class>{format!("{} {symbol}", transaction.quantity)}</p>
+                            <p class="text-sm text-ctp-subtext0">
+                                {format!("{account}, posted at {}", transaction.posted_at.format("%Y-%m-%d %H:%M UTC"))}
+                            </p>
+                            <Show
+                                when=move || rw_base.get().is_some()
+                                fallback={
+                                    let description = transaction.description.clone().unwrap_or_default();
+                                    let category = transaction.category.clone().unwrap_or_else(|| "Uncategorized".to_owned());
+                                    let fields = fields.clone();
+                                    move || view! {
+                                        <p class="mt-2">{description.clone()}</p>
+                                        <p class="text-sm text-ctp-subtext0">{category.clone()}</p>
+                                        <div class="mt-2 flex flex-row gap-2">
+                                            <Show when=move || can_update.get()>
+                                                <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click={
+                                                    let fields = fields.clone();
+                                                    move |_| edit(fields.clone())
+                                                }>
+                                                    "Edit"
+                                                </button>
+                                            </Show>
+                                            <Show when=move || can_delete.get()>
+                                                <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 text-ctp-red hover:bg-ctp-surface2" on:click=move |_| rw_confirm_delete.set(true)>
+                                                    "Delete"
+                                                </button>
+                                            </Show>
+                                        </div>
+                                    }
+                                }
+                            >
+                                <div class="mt-2 flex flex-col gap-2">
+                                    <input class="rounded-full bg-ctp-surface1 px-4 py-2" type="text" placeholder="Description" bind:value=rw_description/>
+                                    <input class="rounded-full bg-ctp-surface1 px-4 py-2" type="datetime-local" bind:value=rw_posted_at/>
+                                    <input class="rounded-full bg-ctp-surface1 px-4 py-2" type="text" placeholder="Category" bind:value=rw_category/>
+                                    <Show when=move || !rw_conflicts.get().is_empty()>
+                                        <p class="text-sm text-ctp-yellow">
+                                            {move || format!(
+                                                "The transaction changed while you were editing it. Check the {} before saving again.",
+                                                rw_conflicts.get().join(" and "),
+                                            )}
+                                        </p>
+                                    </Show>
+                                    <div class="flex flex-row justify-end gap-2">
+                                        <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=move |_| rw_base.set(None)>
+                                            "Cancel"
+                                        </button>
+                                        <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=save>
+                                            "Save"
+                                        </button>
+                                    </div>
+                                </div>
+                            </Show>
+                        </div>
+                    }.into_any()
+                }
+            })}
+        </Suspense>
+        <div class="m-2 flex flex-row gap-2 text-ctp-text">
+            <Show when=move || can_view_attachments.get()>
+                <button class=move || tab_class(DetailTab::Attachments) on:click=move |_| rw_tab.set(Some(DetailTab::Attachments))>
+                    "Attachments"
+                </button>
+            </Show>
+            <Show when=move || can_view_history.get()>
+                <button class=move || tab_class(DetailTab::History) on:click=move |_| rw_tab.set(Some(DetailTab::History))>
+                    "History"
+                </button>
+            </Show>
+        </div>
+        {move || match rw_tab.get() {
+            Some(DetailTab::Attachments) if can_view_attachments.get() => {
+                view! { <Attachments id/> }.into_any()
+            }
+            Some(DetailTab::History) if can_view_history.get() => {
+                view! { <History id on_reverted/> }.into_any()
+            }
+            _ => ().into_any(),
+        }}
+        <Show when=move || rw_confirm_delete.get()>
+            <div class="fixed inset-0 flex items-center justify-center bg-ctp-crust/75" role="dialog" aria-modal="true">
+                <div class="w-full max-w-md rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+                    <h2 class="mb-2 font-medium">"Delete this transaction?"</h2>
+                    <p class="text-sm text-ctp-subtext0">"Its attachments and history are deleted with it."</p>
+                    <div class="mt-2 flex flex-row justify-end gap-2">
+                        <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=move |_| rw_confirm_delete.set(false)>
+                            "Cancel"
+                        </button>
+                        <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 text-ctp-red hover:bg-ctp-surface2" on:click=delete>
+                            "Delete"
+                        </button>
+                    </div>
+                </div>
+            </div>
+        </Show>
+    }
+}
+
+/// A file picked to attach, read by the browser.
+#[derive(Debug, Clone, Deserialize)]
+struct PickedFile {
+    filename: String,
+    content_type: String,
+    /// The file, base64 encoded
+    content: String,
+}
+
+/// The file picked in `input`, `None` if none is.
+async fn picked_file(input: NodeRef<leptos::html::Input>) -> Result<Option<PickedFile>, ApiError> {
+    #[cfg(feature = "hydrate")]
+    {
+        let unreadable =
+            || ApiError::client(ClientErrorCode::InvalidFile, "The file could not be read.");
+        let Some(input) = input.get_untracked() else {
+            return Ok(None);
+        };
+        let file = glue::read_file(input.as_ref())
+            .await
+            .map_err(|_| unreadable())?;
+        let Some(file) = file.as_string() else {
+            return Ok(None);
+        };
+        serde_json::from_str(&file)
+            .map(Some)
+            .map_err(|_| unreadable())
+    }
+    #[cfg(not(feature = "hydrate"))]
+    {
+        let _ = input;
+        Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            "Uploads require a browser.",
+        ))
+    }
+}
+
+/// Saves the content of an attachment under its filename. The content
+/// needs the access token, which a link can't send.
+async fn download(auth_token: String, id: AttachmentId, filename: String) -> Result<(), ApiError> {
+    #[cfg(feature = "hydrate")]
+    {
+        glue::download_file(
+            format!("/api/attachments/{id}/content"),
+            auth_token,
+            filename,
+        )
+        .await
+        .map(|_| ())
+        .map_err(|_| {
+            ApiError::client(
+                ClientErrorCode::InvalidFile,
+                "The attachment could not be downloaded.",
+            )
+        })
+    }
+    #[cfg(not(feature = "hydrate"))]
+    {
+        let _ = (auth_token, id, filename);
+        Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            "Downloads require a browser.",
+        ))
+    }
+}
+
+/// How far an upload has got. The file is sent in one request, so it moves
+/// a step at a time rather than by the byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Upload {
+    Reading,
+    Sending { filename: String, size: usize },
+}
+
+impl Upload {
+    fn sending(file: &PickedFile) -> Self {
+        Self::Sending {
+            filename: file.filename.clone(),
+            size: file.content.len() / 4 * 3,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Self::Reading => "Reading the file...".to_owned(),
+            Self::Sending { filename, size } => {
+                format!("Uploading {filename} ({})...", size_label(*size))
+            }
+        }
+    }
+}
+
+fn size_label(bytes: usize) -> String {
+    match bytes {
+        0..1_024 => format!("{bytes} B"),
+        1_024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1_024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// The files attached to the transaction, which can be downloaded, and
+/// uploaded or deleted by those allowed to.
+#[component]
+fn Attachments(id: TransactionId) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let toasts = expect_context::<Toasts>();
+    let rw_version = RwSignal::new(0);
+    let rw_upload = RwSignal::<Option<Upload>>::new(None);
+    let input_ref = NodeRef::<leptos::html::Input>::new();
+
+    let capabilities = use_capabilities();
+    let can_create = capabilities.can_create_attachment();
+    let can_delete = capabilities.can_delete_attachment();
+
+    let attachments = LocalResource::new(move || {
+        rw_version.track();
+        let auth_token = rw_auth_token.get();
+        async move {
+            let Some(auth_token) = auth_token else {
+                return vec![];
+            };
+            match list_attachments(&auth_token, id).await {
+                Ok(response) => response.attachments,
+                Err(e) => {
+                    toasts.error(&e);
+                    vec![]
+                }
+            }
+        }
+    });
+
+    let upload = move |_| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        if rw_upload.get_untracked().is_some() {
+            return;
+        }
+        rw_upload.set(Some(Upload::Reading));
+        leptos::task::spawn_local(async move {
+            let file = match picked_file(input_ref).await {
+                Ok(Some(file)) => file,
+                Ok(None) => return rw_upload.set(None),
+                Err(e) => {
+                    rw_upload.set(None);
+                    return toasts.error(&e);
+                }
+            };
+            rw_upload.set(Some(Upload::sending(&file)));
+            let attachment = CreateRequest {
+                transaction_id: id,
+                filename: file.filename,
+                content_type: file.content_type,
+                content: file.content,
+            };
+            match create_attachment(&auth_token, &attachment).await {
+                Ok(_) => toasts.success("Attachment uploaded."),
+                Err(e) => toasts.error(&e),
+            }
+            rw_upload.set(None);
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    let delete = move |attachment_id: AttachmentId| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match delete_attachment(&auth_token, attachment_id).await {
+                Ok(()) => toasts.success("Attachment deleted."),
+                Err(e) => toasts.error(&e),
+            }
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    let save = move |attachment_id: AttachmentId, filename: String| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            if let Err(e) = download(auth_token, attachment_id, filename).await {
+                toasts.error(&e);
+            }
+        });
+    };
+
+    view! {
+        <div class="m-2 rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+            <h2 class="mb-2 font-medium">"Attachments"</h2>
+            <Suspense fallback=|| view! { <p>"Loading..."</p> }>
+                <ul>
+                    {move || attachments.get().map(|attachments| {
+                        attachments.into_iter().map(|attachment| {
+                            let attachment_id = attachment.id;
+                            let filename = attachment.filename.clone();
+                            view! {
+                                <li class="flex flex-row py-1">
+                                    <span class="flex-auto">
+                                        <span>{attachment.filename}</span>
+                                        <span class="block text-sm text-ctp-subtext0">
+                                            {format!("{}, {}", attachment.content_type, size_label(attachment.size.max(0) as usize))}
+                                        </span>
+                                    </span>
+                                    <button class="cursor-pointer px-2 hover:text-ctp-blue" on:click=move |_| save(attachment_id, filename.clone())>
+                                        "Download"
+                                    </button>
+                                    <Show when=move || can_delete.get()>
+                                        <button class="cursor-pointer px-2 hover:text-ctp-red" on:click=move |_| delete(attachment_id)>
+                                            "Delete"
+                                        </button>
+                                    </Show>
+                                </li>
+                            }
+                        }).collect_view()
+                    })}
+                </ul>
+            </Suspense>
+            <Show when=move || can_create.get()>
+                <div class="mt-2 flex flex-row gap-2">
+                    <input class="flex-auto" type="file" accept="application/pdf,image/png,image/jpeg,image/heic" node_ref=input_ref/>
+                    <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" disabled=move || rw_upload.get().is_some() on:click=upload>
+                        "Upload"
+                    </button>
+                </div>
+                <p class="mt-1 min-h-5 text-sm text-ctp-subtext0">{move || rw_upload.get().map(|x| x.label())}</p>
+            </Show>
+        </div>
+    }
+}
+
+/// The latest edits of the transaction, each of which can be reverted by
+/// those allowed to.
+#[component]
+fn History(id: TransactionId, on_reverted: Callback<()>) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let toasts = expect_context::<Toasts>();
+    let rw_version = RwSignal::new(0);
+    let can_revert = use_capabilities().can_revert_transaction();
+
+    let history = LocalResource::new(move || {
+        rw_version.track();
+        let auth_token = rw_auth_token.get();
+        async move {
+            let Some(auth_token) = auth_token else {
+                return vec![];
+            };
+            match list_history(&auth_token, id).await {
+                Ok(response) => response.history,
+                Err(e) => {
+                    toasts.error(&e);
+                    vec![]
+                }
+            }
+        }
+    });
+
+    let revert = move |history_id: TransactionHistoryId| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match revert_transaction(&auth_token, id, history_id).await {
+                Ok(_) => {
+                    toasts.success("Edit reverted.");
+                    on_reverted.run(());
+                }
+                Err(e) => toasts.error(&e),
+            }
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    view! {
+        <div class="m-2 rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+            <h2 class="mb-2 font-medium">"History"</h2>
+            <Suspense fallback=|| view! { <p>"Loading..."</p> }>
+                <ul>
+                    {move || history.get().map(|history| {
+                        history.into_iter().map(|entry| {
+                            let history_id = entry.id;
+                            let (before, after) = (entry.before, entry.after);
+                            let mut changes = vec![];
+                            if before.description != after.description {
+                                changes.push(format!(
+                                    "Description from \"{}\" to \"{}\"",
+                                    before.description.unwrap_or_default(),
+                                    after.description.unwrap_or_default(),
+                                ));
+                            }
+                            if before.posted_at != after.posted_at {
+                                changes.push(format!(
+                                    "Posted at from {} to {}",
+                                    before.posted_at.format("%Y-%m-%d %H:%M"),
+                                    after.posted_at.format("%Y-%m-%d %H:%M"),
+                                ));
+                            }
+                            if before.quantity != after.quantity || before.asset_id != after.asset_id {
+                                changes.push(format!("Quantity from {} to {}", before.quantity, after.quantity));
+                            }
+                            view! {
+                                <li class="flex flex-row py-1">
+                                    <span class="flex-auto">
+                                        <span>{format!("Edited at {}", entry.created_at.format("%Y-%m-%d %H:%M UTC"))}</span>
+                                        {changes.into_iter().map(|change| view! {
+                                            <span class="block text-sm text-ctp-subtext0">{change}</span>
+                                        }).collect_view()}
+                                    </span>
+                                    <Show when=move || can_revert.get()>
+                                        <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 hover:bg-ctp-surface2" on:click=move |_| revert(history_id)>
+                                            "Revert"
+                                        </button>
+                                    </Show>
+                                </li>
+                            }
+                        }).collect_view()
+                    })}
+                </ul>
+            </Suspense>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn fields(description: &str, posted_at: &str, category: &str) -> Fields {
+        Fields {
+            description: description.to_owned(),
+            posted_at: posted_at.to_owned(),
+            category: category.to_owned(),
+        }
+    }
+
+    #[test]
+    fn it_sends_only_the_fields_that_changed() {
+        let base = fields("Coffee", "2025-05-01T09:30", "Food");
+        assert!(base.changes(&base).unwrap().is_none());
+
+        let edits = fields(" Coffee beans ", "2025-05-02T10:00", "Food");
+        let update = edits.changes(&base).unwrap().unwrap();
+        assert_eq!(update.description.as_deref(), Some("Coffee beans"));
+        assert_eq!(
+            update.posted_at,
+            Some(Utc.with_ymd_and_hms(2025, 5, 2, 10, 0, 0).unwrap())
+        );
+        assert_eq!(update.category, None);
+        assert!(update.quantity.is_none() && update.asset_id.is_none());
+
+        let edits = fields("Coffee", "yesterday", "Food");
+        assert!(matches!(
+            edits.changes(&base),
+            Err(ApiError::ClientError(..))
+        ));
+    }
+
+    #[test]
+    fn it_keeps_edits_the_server_did_not_touch() {
+        let base = fields("Coffee", "2025-05-01T09:30", "Food");
+        let edits = fields("Coffee beans", "2025-05-01T09:30", "Food");
+        let latest = fields("Coffee", "2025-05-01T09:30", "Groceries");
+
+        let merged = merge(&base, &edits, &latest);
+        assert_eq!(
+            merged.fields,
+            fields("Coffee beans", "2025-05-01T09:30", "Groceries")
+        );
+        assert!(merged.conflicts.is_empty());
+        // What is left to send is only the user's edit.
+        let update = merged.fields.changes(&latest).unwrap().unwrap();
+        assert_eq!(update.description.as_deref(), Some("Coffee beans"));
+        assert_eq!(update.category, None);
+    }
+
+    #[test]
+    fn it_reports_fields_both_sides_changed() {
+        let base = fields("Coffee", "2025-05-01T09:30", "Food");
+        let edits = fields("Coffee beans", "2025-05-01T09:30", "Drinks");
+        let latest = fields("Tea", "2025-05-01T09:30", "Drinks");
+
+        let merged = merge(&base, &edits, &latest);
+        // The same change on both sides agrees, a different one is the
+        // user's to settle, with their edit kept.
+        assert_eq!(
+            merged.fields,
+            fields("Coffee beans", "2025-05-01T09:30", "Drinks")
+        );
+        assert_eq!(merged.conflicts, vec!["description"]);
+    }
+
+    #[test]
+    fn it_labels_the_steps_of_an_upload() {
+        let file = PickedFile {
+            filename: "receipt.pdf".to_owned(),
+            content_type: "application/pdf".to_owned(),
+            content: "A".repeat(4 * 1_024),
+        };
+        assert_eq!(Upload::Reading.label(), "Reading the file...");
+        assert_eq!(
+            Upload::sending(&file).label(),
+            "Uploading receipt.pdf (3.0 KB)..."
+        );
+        assert_eq!(size_label(512), "512 B");
+        assert_eq!(size_label(3 * 1_048_576 / 2), "1.5 MB");
     }
 }
@@ -1,4 +1,20 @@
 use leptos::prelude::*;
+use leptos_router::hooks::use_params_map;
+use reqwest::Method;
+
+use crate::{
+    api::ApiError,
+    app::{
+        AuthToken,
+        passkeys::{Passkeys, request},
+        toast::Toasts,
+    },
+    model::{login_event::LoginEventType, user::UserId, user_session::UserSessionId},
+    schema::{
+        user::{ActivityGetListResponse, LoginEventResponse},
+        user_session::{RevokeOthersResponse, UserSessionGetListResponse},
+    },
+};
 
 #[component]
 pub fn Users() -> impl IntoView {
@@ -7,10 +23,247 @@ pub fn Users() -> impl IntoView {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UserTab {
+    Passkeys,
+    Sessions,
+    Activity,
+}
+
 #[component]
 pub fn UserDetail() -> impl IntoView {
+    let params = use_params_map();
+    let user_id = move || {
+        params
+            .read()
+            .get("id")
+            .and_then(|id| id.parse::<UserId>().ok())
+    };
+    let rw_tab = RwSignal::new(UserTab::Passkeys);
+    let tab_class = move |tab| {
+        if rw_tab.get() == tab {
+            "cursor-pointer rounded-full bg-ctp-surface2 px-4 py-1"
+        } else {
+            "cursor-pointer rounded-full bg-ctp-surface0 px-4 py-1 hover:bg-ctp-surface1"
+        }
+    };
+
     view! {
         <p>"User Detail"</p>
+        <div class="m-2 flex flex-row gap-2 text-ctp-text">
+            <button class=move || tab_class(UserTab::Passkeys) on:click=move |_| rw_tab.set(UserTab::Passkeys)>
+                "Passkeys"
+            </button>
+            <button class=move || tab_class(UserTab::Sessions) on:click=move |_| rw_tab.set(UserTab::Sessions)>
+                "Sessions"
+            </button>
+            <button class=move || tab_class(UserTab::Activity) on:click=move |_| rw_tab.set(UserTab::Activity)>
+                "Activity"
+            </button>
+        </div>
+        {move || user_id().map(|user_id| match rw_tab.get() {
+            UserTab::Passkeys => view! { <Passkeys user_id/> }.into_any(),
+            UserTab::Sessions => view! { <Sessions user_id/> }.into_any(),
+            UserTab::Activity => view! { <Activity user_id/> }.into_any(),
+        })}
+    }
+}
+
+pub async fn list_sessions(
+    auth_token: &str,
+    user_id: UserId,
+) -> Result<UserSessionGetListResponse, ApiError> {
+    request(
+        auth_token,
+        Method::GET,
+        &format!("/api/users/{user_id}/sessions"),
+        None,
+    )
+    .await
+}
+
+pub async fn revoke_session(
+    auth_token: &str,
+    user_id: UserId,
+    session_id: UserSessionId,
+) -> Result<(), ApiError> {
+    request(
+        auth_token,
+        Method::DELETE,
+        &format!("/api/users/{user_id}/sessions/{session_id}"),
+        None,
+    )
+    .await
+}
+
+pub async fn revoke_other_sessions(
+    auth_token: &str,
+    user_id: UserId,
+) -> Result<RevokeOthersResponse, ApiError> {
+    request(
+        auth_token,
+        Method::POST,
+        &format!("/api/users/{user_id}/sessions/revoke-others"),
+        Some(serde_json::json!({})),
+    )
+    .await
+}
+
+/// The devices the user is signed in on, each of which can be signed out.
+#[component]
+fn Sessions(user_id: UserId) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let rw_version = RwSignal::new(0);
+    let toasts = expect_context::<Toasts>();
+
+    let sessions = LocalResource::new(move || {
+        rw_version.track();
+        let auth_token = rw_auth_token.get();
+        async move {
+            let Some(auth_token) = auth_token else {
+                return vec![];
+            };
+            list_sessions(&auth_token, user_id)
+                .await
+                .map(|response| response.sessions)
+                .unwrap_or_default()
+        }
+    });
+
+    let revoke = move |session_id: UserSessionId| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match revoke_session(&auth_token, user_id, session_id).await {
+                Ok(()) => toasts.success("Session revoked."),
+                Err(e) => toasts.error(&e),
+            }
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    let revoke_others = move |_| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match revoke_other_sessions(&auth_token, user_id).await {
+                Ok(response) => toasts.success(format!("Revoked {} sessions.", response.revoked)),
+                Err(e) => toasts.error(&e),
+            }
+            rw_version.update(|v| *v += 1);
+        });
+    };
+
+    view! {
+        <div class="m-2 rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+            <h2 class="mb-2 font-medium">"Sessions"</h2>
+            <Suspense fallback=|| view! { <p>"Loading..."</p> }>
+                <ul>
+                    {move || sessions.get().map(|sessions| {
+                        sessions.into_iter().map(|session| {
+                            let session_id = session.id;
+                            let current = session.current;
+                            let from = session.ip_address.map(|ip| format!(" from {ip}")).unwrap_or_default();
+                            view! {
+                                <li class="flex flex-row py-1">
+                                    <span class="flex-auto">
+                                        <span>
+                                            {format!("Signed in{from} at {}", session.created_at.format("%Y-%m-%d %H:%M UTC"))}
+                                            {current.then_some(" (this device)")}
+                                        </span>
+                                        <span class="block text-sm text-ctp-subtext0">
+                                            {format!("Last active at {}", session.last_refreshed_at.format("%Y-%m-%d %H:%M UTC"))}
+                                        </span>
+                                        <span class="block text-sm text-ctp-subtext0">
+                                            {session.user_agent.unwrap_or_default()}
+                                        </span>
+                                    </span>
+                                    <Show when=move || !current>
+                                        <button class="cursor-pointer rounded-full bg-ctp-surface1 px-4 hover:bg-ctp-surface2" on:click=move |_| revoke(session_id)>
+                                            "Revoke"
+                                        </button>
+                                    </Show>
+                                </li>
+                            }
+                        }).collect_view()
+                    })}
+                </ul>
+            </Suspense>
+            <button class="mt-2 cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=revoke_others>
+                "Revoke all other sessions"
+            </button>
+        </div>
+    }
+}
+
+pub async fn list_activity(
+    auth_token: &str,
+    user_id: UserId,
+    cursor: Option<&str>,
+) -> Result<ActivityGetListResponse, ApiError> {
+    let query = cursor.map(|x| format!("?cursor={x}")).unwrap_or_default();
+    request(
+        auth_token,
+        Method::GET,
+        &format!("/api/users/{user_id}/activity{query}"),
+        None,
+    )
+    .await
+}
+
+/// The login history of the user, a page at a time.
+#[component]
+fn Activity(user_id: UserId) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let rw_events = RwSignal::<Vec<LoginEventResponse>>::new(vec![]);
+    let rw_next_cursor = RwSignal::<Option<String>>::new(None);
+    let toasts = expect_context::<Toasts>();
+
+    let load = move |cursor: Option<String>| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match list_activity(&auth_token, user_id, cursor.as_deref()).await {
+                Ok(response) => {
+                    rw_events.update(|events| events.extend(response.events));
+                    rw_next_cursor.set(response.next_cursor);
+                }
+                Err(e) => toasts.error(&e),
+            }
+        });
+    };
+    load(None);
+
+    view! {
+        <div class="m-2 rounded-lg bg-ctp-surface0 p-4 text-ctp-text">
+            <h2 class="mb-2 font-medium">"Activity"</h2>
+            <ul>
+                {move || rw_events.get().into_iter().map(|event| {
+                    let event_type = match event.event_type {
+                        LoginEventType::Login => "Logged in",
+                        LoginEventType::Refresh => "Refreshed the session",
+                        LoginEventType::Logout => "Logged out",
+                    };
+                    let from = event.ip_address.map(|ip| format!(" from {ip}")).unwrap_or_default();
+                    view! {
+                        <li class="py-1">
+                            <span>{format!("{event_type}{from} at {}", event.created_at.format("%Y-%m-%d %H:%M UTC"))}</span>
+                            <span class="block text-sm text-ctp-subtext0">
+                                {event.user_agent.unwrap_or_default()}
+                            </span>
+                        </li>
+                    }
+                }).collect_view()}
+            </ul>
+            <Show when=move || rw_next_cursor.get().is_some()>
+                <button class="mt-2 cursor-pointer rounded-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" on:click=move |_| load(rw_next_cursor.get_untracked())>
+                    "Show more"
+                </button>
+            </Show>
+        </div>
     }
 }
 
@@ -0,0 +1,354 @@
+use leptos::prelude::*;
+use leptos_router::{NavigateOptions, hooks::use_navigate};
+use reqwest::Method;
+use serde_json::json;
+
+use crate::{
+    api::{
+        ApiError, ClientErrorCode,
+        account_api::create as account_create,
+        asset_api::get_list as asset_get_list,
+        institution_api::get_list as institution_get_list,
+        me_api::{onboarding_state, update_preferences},
+        proposal_api::create as proposal_create,
+    },
+    app::{AuthToken, passkeys::request, toast::Toasts},
+    model::{asset::AssetId, institution::InstitutionId, user::UserId},
+    schema::{
+        Pagination,
+        account::CreateRequest as AccountCreateRequest,
+        asset::GetListRequest as AssetGetListRequest,
+        institution::{
+            CreateRequest as InstitutionCreateRequest, GetListRequest as InstitutionGetListRequest,
+        },
+        me::{OnboardingStateResponse, OnboardingStep, PreferenceUpdateRequest},
+        proposal::CreateRequest as ProposalCreateRequest,
+        user::{UserCreateResponse, UserUpdateResponse},
+    },
+};
+
+/// The onboarding state of the user, once they are logged in.
+pub fn use_onboarding_state(
+    rw_version: RwSignal<i32>,
+) -> LocalResource<Option<OnboardingStateResponse>> {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    LocalResource::new(move || {
+        rw_version.track();
+        let auth_token = rw_auth_token.get();
+        async move {
+            auth_token?;
+            onboarding_state().await.ok()
+        }
+    })
+}
+
+/// Walks a new user through choosing a name, a default currency and
+/// creating their first account. Each step is saved as it is taken and the
+/// wizard resumes from the state the server derives, so a refresh picks up
+/// where it left off.
+#[component]
+pub fn Welcome() -> impl IntoView {
+    let rw_version = RwSignal::new(0);
+    let onboarding = use_onboarding_state(rw_version);
+    let navigate = use_navigate();
+
+    Effect::new(move |_| {
+        if onboarding
+            .get()
+            .flatten()
+            .is_some_and(|onboarding| onboarding.complete)
+        {
+            navigate("/home", NavigateOptions::default());
+        }
+    });
+
+    view! {
+        <div class="container mx-auto max-w-lg px-4 py-8 text-ctp-text">
+            <h1 class="mb-4 text-2xl font-bold">"Welcome to Treasury"</h1>
+            <Suspense fallback=|| view! { <p>"Loading..."</p> }>
+                {move || onboarding.get().flatten().map(|onboarding| {
+                    let progress = match onboarding.step {
+                        OnboardingStep::Register | OnboardingStep::Name => 1,
+                        OnboardingStep::Currency => 2,
+                        OnboardingStep::Account | OnboardingStep::Done => 3,
+                    };
+                    view! {
+                        <p class="mb-2 text-ctp-subtext0">{format!("Step {progress} of 3")}</p>
+                        {match onboarding.step {
+                            OnboardingStep::Register | OnboardingStep::Name => view! {
+                                <NameStep
+                                    user_id=onboarding.user_id
+                                    name=onboarding.name.unwrap_or_default()
+                                    rw_version
+                                />
+                            }.into_any(),
+                            OnboardingStep::Currency => view! { <CurrencyStep rw_version/> }.into_any(),
+                            OnboardingStep::Account => view! { <AccountStep rw_version/> }.into_any(),
+                            OnboardingStep::Done => view! { <p>"You are all set."</p> }.into_any(),
+                        }}
+                    }
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+/// Registers the user with the name, or renames them if they already are.
+#[component]
+fn NameStep(user_id: Option<UserId>, name: String, rw_version: RwSignal<i32>) -> impl IntoView {
+    let rw_auth_token = expect_context::<AuthToken>().0;
+    let rw_name = RwSignal::new(name);
+    let toasts = expect_context::<Toasts>();
+
+    let save = move |_| {
+        let Some(auth_token) = rw_auth_token.get_untracked() else {
+            return;
+        };
+        let body = Some(json!({ "name": rw_name.get_untracked().trim() }));
+        leptos::task::spawn_local(async move {
+            let result = match user_id {
+                Some(user_id) => request::<UserUpdateResponse>(
+                    &auth_token,
+                    Method::PATCH,
+                    &format!("/api/users/{user_id}"),
+                    body,
+                )
+                .await
+                .map(|_| ()),
+                None => {
+                    request::<UserCreateResponse>(&auth_token, Method::POST, "/api/users", body)
+                        .await
+                        .map(|_| ())
+                }
+            };
+            match result {
+                Ok(()) => rw_version.update(|v| *v += 1),
+                Err(e) => toasts.error(&e),
+            }
+        });
+    };
+
+    view! {
+        <div class="rounded-lg bg-ctp-surface0 p-4">
+            <h2 class="mb-2 font-medium">"What should we call you?"</h2>
+            <div class="flex flex-row">
+                <input class="flex-auto rounded-l-full bg-ctp-surface1 px-4 py-2" type="text" placeholder="Display name" bind:value=rw_name/>
+                <button class="cursor-pointer rounded-r-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" disabled=move || rw_name.get().trim().is_empty() on:click=save>
+                    "Continue"
+                </button>
+            </div>
+        </div>
+    }
+}
+
+/// Chooses the default asset of the user from the seeded assets.
+#[component]
+fn CurrencyStep(rw_version: RwSignal<i32>) -> impl IntoView {
+    let rw_asset_id = RwSignal::<Option<AssetId>>::new(None);
+    let toasts = expect_context::<Toasts>();
+
+    let assets = LocalResource::new(|| async {
+        asset_get_list(AssetGetListRequest::default(), Pagination::default())
+            .await
+            .map(|response| response.assets)
+            .unwrap_or_default()
+    });
+
+    let save = move |_| {
+        let Some(default_asset_id) = rw_asset_id.get_untracked() else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            let result = update_preferences(PreferenceUpdateRequest {
+                default_asset_id: Some(default_asset_id),
+                ..Default::default()
+            })
+            .await;
+            match result {
+                Ok(_) => rw_version.update(|v| *v += 1),
+                Err(e) => toasts.error(&e),
+            }
+        });
+    };
+
+    view! {
+        <div class="rounded-lg bg-ctp-surface0 p-4">
+            <h2 class="mb-2 font-medium">"Which currency do you keep your books in?"</h2>
+            <div class="flex flex-row">
+                <select class="flex-auto rounded-l-full bg-ctp-surface1 px-4 py-2" on:change=move |ev| {
+                    rw_asset_id.set(event_target_value(&ev).parse::<AssetId>().ok());
+                }>
+                    <option value="">"Choose a currency"</option>
+                    <Suspense fallback=|| ()>
+                        {move || assets.get().map(|assets| {
+                            assets.into_iter().map(|asset| view! {
+                                <option value=asset.id.to_string()>
+                                    {format!("{} ({})", asset.name, asset.symbol)}
+                                </option>
+                            }).collect_view()
+                        })}
+                    </Suspense>
+                </select>
+                <button class="cursor-pointer rounded-r-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" disabled=move || rw_asset_id.get().is_none() on:click=save>
+                    "Continue"
+                </button>
+            </div>
+        </div>
+    }
+}
+
+/// Creates the first account of the user at one of the institutions. The
+/// currency of the account starts as the default of the institution.
+#[component]
+fn AccountStep(rw_version: RwSignal<i32>) -> impl IntoView {
+    let rw_institution_id = RwSignal::<Option<InstitutionId>>::new(None);
+    let rw_asset_id = RwSignal::<Option<AssetId>>::new(None);
+    let rw_name = RwSignal::new(String::new());
+    let toasts = expect_context::<Toasts>();
+
+    let institutions = LocalResource::new(|| async {
+        institution_get_list(InstitutionGetListRequest::default(), Pagination::default())
+            .await
+            .map(|response| response.institutions)
+            .unwrap_or_default()
+    });
+    let assets = LocalResource::new(|| async {
+        asset_get_list(AssetGetListRequest::default(), Pagination::default())
+            .await
+            .map(|response| response.assets)
+            .unwrap_or_default()
+    });
+
+    let choose_institution = move |institution_id: Option<InstitutionId>| {
+        rw_institution_id.set(institution_id);
+        let default_asset_id = institutions.get_untracked().and_then(|institutions| {
+            institutions
+                .into_iter()
+                .find(|institution| Some(institution.id) == institution_id)
+                .and_then(|institution| institution.default_asset_id)
+        });
+        rw_asset_id.set(default_asset_id);
+    };
+
+    let save = move |_| {
+        let Some(institution_id) = rw_institution_id.get_untracked() else {
+            return;
+        };
+        let name = rw_name.get_untracked().trim().to_owned();
+        if name.is_empty() {
+            toasts.error(&ApiError::client(
+                ClientErrorCode::InvalidRequest,
+                "Name the account.",
+            ));
+            return;
+        }
+        leptos::task::spawn_local(async move {
+            let result = account_create(AccountCreateRequest {
+                name,
+                institution_id,
+                notes: None,
+                default_asset_id: rw_asset_id.get_untracked(),
+            })
+            .await;
+            match result {
+                Ok(_) => {
+                    toasts.success("Account created.");
+                    rw_version.update(|v| *v += 1);
+                }
+                Err(e) => toasts.error(&e),
+            }
+        });
+    };
+
+    view! {
+        <div class="rounded-lg bg-ctp-surface0 p-4">
+            <h2 class="mb-2 font-medium">"Add your first account"</h2>
+            <select class="mb-2 w-full rounded-full bg-ctp-surface1 px-4 py-2" on:change=move |ev| {
+                choose_institution(event_target_value(&ev).parse::<InstitutionId>().ok());
+            }>
+                <option value="">"Choose an institution"</option>
+                <Suspense fallback=|| ()>
+                    {move || institutions.get().map(|institutions| {
+                        institutions.into_iter().map(|institution| view! {
+                            <option value=institution.id.to_string()>{institution.name}</option>
+                        }).collect_view()
+                    })}
+                </Suspense>
+            </select>
+            <RequestInstitution/>
+            <select class="mb-2 w-full rounded-full bg-ctp-surface1 px-4 py-2" on:change=move |ev| {
+                rw_asset_id.set(event_target_value(&ev).parse::<AssetId>().ok());
+            }>
+                <option value="" selected=move || rw_asset_id.get().is_none()>"Choose a currency"</option>
+                <Suspense fallback=|| ()>
+                    {move || assets.get().map(|assets| {
+                        assets.into_iter().map(|asset| view! {
+                            <option value=asset.id.to_string() selected=move || rw_asset_id.get() == Some(asset.id)>
+                                {format!("{} ({})", asset.name, asset.symbol)}
+                            </option>
+                        }).collect_view()
+                    })}
+                </Suspense>
+            </select>
+            <div class="flex flex-row">
+                <input class="flex-auto rounded-l-full bg-ctp-surface1 px-4 py-2" type="text" placeholder="Account name" bind:value=rw_name/>
+                <button class="cursor-pointer rounded-r-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" disabled=move || rw_institution_id.get().is_none() on:click=save>
+                    "Create account"
+                </button>
+            </div>
+        </div>
+    }
+}
+
+/// Asks an admin to add an institution that isn't listed yet. It is only
+/// listed once an admin approves the request.
+#[component]
+fn RequestInstitution() -> impl IntoView {
+    let rw_open = RwSignal::new(false);
+    let rw_name = RwSignal::new(String::new());
+    let toasts = expect_context::<Toasts>();
+
+    let request = move |_| {
+        let name = rw_name.get_untracked().trim().to_owned();
+        if name.is_empty() {
+            return;
+        }
+        leptos::task::spawn_local(async move {
+            let result = proposal_create(ProposalCreateRequest::CreateInstitution(
+                InstitutionCreateRequest {
+                    name,
+                    parent_id: None,
+                    default_asset_id: None,
+                },
+            ))
+            .await;
+            match result {
+                Ok(_) => {
+                    toasts
+                        .success("Requested. The institution is listed once an admin approves it.");
+                    rw_name.set(String::new());
+                    rw_open.set(false);
+                }
+                Err(e) => toasts.error(&e),
+            }
+        });
+    };
+
+    view! {
+        <Show
+            when=move || rw_open.get()
+            fallback=move || view! {
+                <button class="mb-2 cursor-pointer text-sm text-ctp-subtext0 underline hover:text-ctp-text" on:click=move |_| rw_open.set(true)>
+                    "Request a new institution"
+                </button>
+            }
+        >
+            <div class="mb-2 flex flex-row">
+                <input class="flex-auto rounded-l-full bg-ctp-surface1 px-4 py-2" type="text" placeholder="Institution name" bind:value=rw_name/>
+                <button class="cursor-pointer rounded-r-full bg-ctp-surface1 px-4 py-2 hover:bg-ctp-surface2" disabled=move || rw_name.get().trim().is_empty() on:click=request>
+                    "Request"
+                </button>
+            </div>
+        </Show>
+    }
+}
@@ -0,0 +1,139 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use http::{HeaderMap, StatusCode, header::AUTHORIZATION};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use tracing::{debug, error};
+
+use crate::{
+    api::AppState,
+    authentication::authenticated_token::AuthenticatedToken,
+    model::{
+        account::AccountId,
+        api_key::{ApiKey, ApiKeyFilter, ApiKeyId},
+    },
+    resource::{
+        GetListRepository, GetRepository, api_key_repository::ApiKeyRepository, deadline,
+        user_repository::UserRepository,
+    },
+};
+
+/// The prefix telling an API key apart from an OIDC token.
+pub const API_KEY_PREFIX: &str = "trk_";
+
+/// What a request authenticated with an API key may touch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyScope {
+    /// The key the request was made with
+    pub id: ApiKeyId,
+    /// The accounts the key is limited to, or all of the user's accounts
+    /// if unset
+    pub account_ids: Option<Vec<AccountId>>,
+    /// Whether the key may only read
+    pub read_only: bool,
+}
+
+impl From<&ApiKey> for ApiKeyScope {
+    fn from(value: &ApiKey) -> Self {
+        Self {
+            id: value.id,
+            account_ids: value.account_ids.clone(),
+            read_only: value.read_only,
+        }
+    }
+}
+
+/// Generates a new secret, returning it along with the digest to store.
+pub fn generate_secret() -> (String, Vec<u8>) {
+    let mut rng = rand::rng();
+    let secret_bytes: [u8; 32] = rng.random();
+    let secret = format!(
+        "{API_KEY_PREFIX}{}",
+        BASE64_URL_SAFE_NO_PAD.encode(secret_bytes)
+    );
+    let key_hash = hash_secret(&secret);
+    (secret, key_hash)
+}
+
+pub fn hash_secret(secret: &str) -> Vec<u8> {
+    Sha256::digest(secret.as_bytes()).to_vec()
+}
+
+/// The secret of the API key a request bears, if it bears one rather than
+/// an OIDC token.
+pub fn api_key_secret(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|s| s.starts_with(API_KEY_PREFIX))
+}
+
+/// Finds the owner of the API key `secret`, standing in for them with a
+/// token. Fails with the status to refuse the request with.
+pub async fn resolve_api_key(
+    state: &AppState,
+    secret: &str,
+) -> Result<AuthenticatedToken, StatusCode> {
+    let session = deadline::begin(&state.connection_pool).await.map_err(|e| {
+        error!("{e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let Some(api_key) = ApiKeyRepository
+        .get_list(
+            session,
+            0,
+            1.into(),
+            ApiKeyFilter {
+                key_hash: hash_secret(secret).into(),
+                ..Default::default()
+            },
+        )
+        .await
+        .ok()
+        .and_then(|mut keys| keys.pop())
+    else {
+        debug!("Unknown API key");
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let session = deadline::begin(&state.connection_pool).await.map_err(|e| {
+        error!("{e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let Ok(user) = UserRepository.get(session, api_key.user_id).await else {
+        debug!("API key owner no longer exists");
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    Ok(AuthenticatedToken::for_api_key(&user, &api_key))
+}
+
+/// Authenticates requests bearing an API key as the user who owns it.
+///
+/// Requests with any other bearer token are passed on untouched for the
+/// `Authenticator` to validate, as are those authenticated already.
+pub async fn authenticate_api_key(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if request.extensions().get::<AuthenticatedToken>().is_some() {
+        return next.run(request).await;
+    }
+    let Some(secret) = api_key_secret(request.headers()).map(|s| s.to_owned()) else {
+        return next.run(request).await;
+    };
+
+    match resolve_api_key(&state, &secret).await {
+        Ok(token) => {
+            request.extensions_mut().insert(token);
+            next.run(request).await
+        }
+        Err(status) => status.into_response(),
+    }
+}
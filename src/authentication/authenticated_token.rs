@@ -1,13 +1,22 @@
 use axum::extract::FromRequestParts;
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
 use http::request::Parts;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 
-use crate::{api::ApiError, service::ServiceError};
+use crate::{
+    api::ApiError,
+    authentication::api_key::ApiKeyScope,
+    authorization::group::Group,
+    config::GroupFilterConfig,
+    model::{api_key::ApiKey, user::User},
+    service::ServiceError,
+};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Claims {
     #[serde(default)]
-    groups: Vec<String>,
+    groups: Vec<Group>,
     email: String,
     email_verified: bool,
     sub: String,
@@ -22,11 +31,54 @@ pub struct Claims {
 pub struct AuthenticatedToken {
     /// The claims on the authenticated token
     claims: Claims,
+    /// The limits of the API key the request was made with, if any
+    api_key_scope: Option<ApiKeyScope>,
 }
 
 impl AuthenticatedToken {
     pub fn new(claims: Claims) -> Self {
-        Self { claims }
+        Self {
+            claims,
+            api_key_scope: None,
+        }
+    }
+
+    /// A token standing in for the owner of an API key. It carries no
+    /// groups, so it is granted the same ones as the owner's own token.
+    pub fn for_api_key(user: &User, api_key: &ApiKey) -> Self {
+        Self {
+            claims: Claims {
+                groups: vec![],
+                email: user.email.clone(),
+                email_verified: true,
+                sub: user.sub.clone(),
+                iss: user.iss.clone(),
+                iat: api_key.created_at.timestamp(),
+                exp: i64::MAX,
+                name: Some(user.name.clone()),
+                preferred_username: None,
+            },
+            api_key_scope: Some(api_key.into()),
+        }
+    }
+
+    /// A token claiming nothing but `group`, to find what the policies
+    /// grant the group on its own.
+    pub fn for_group(group: Group) -> Self {
+        Self {
+            claims: Claims {
+                groups: vec![group.normalize()],
+                email: String::new(),
+                email_verified: false,
+                sub: String::new(),
+                iss: String::new(),
+                iat: 0,
+                exp: 0,
+                name: None,
+                preferred_username: None,
+            },
+            api_key_scope: None,
+        }
     }
 
     pub fn sub(&self) -> &str {
@@ -45,7 +97,7 @@ impl AuthenticatedToken {
         self.claims.email_verified
     }
 
-    pub fn groups(&self) -> &[String] {
+    pub fn groups(&self) -> &[Group] {
         &self.claims.groups
     }
 
@@ -65,17 +117,47 @@ impl AuthenticatedToken {
         self.claims.preferred_username.as_ref()
     }
 
-    pub fn add_group(&mut self, group: String) {
+    pub fn api_key_scope(&self) -> Option<&ApiKeyScope> {
+        self.api_key_scope.as_ref()
+    }
+
+    /// A fingerprint identifying the session this token belongs to. A token
+    /// minted by a refresh carries a new `iat` and so a new fingerprint.
+    pub fn session(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.iss().as_bytes());
+        hasher.update([0]);
+        hasher.update(self.sub().as_bytes());
+        hasher.update([0]);
+        hasher.update(self.iat().to_be_bytes());
+        BASE64_URL_SAFE_NO_PAD.encode(hasher.finalize())
+    }
+
+    pub fn add_group(&mut self, group: Group) {
         self.claims.groups.push(group)
     }
 
+    /// Drops the custom groups the filter doesn't keep. The built in groups
+    /// are always kept, whatever namespace they are claimed in.
+    pub fn filter_groups(&mut self, filter: &GroupFilterConfig) {
+        if filter.keeps_all() {
+            return;
+        }
+        self.claims
+            .groups
+            .retain(|group| match group.clone().normalize() {
+                Group::Custom(subject) => filter.keeps(group.as_policy_subject(), &subject),
+                _ => true,
+            });
+    }
+
     pub fn normalize_groups(&mut self) {
         self.claims.groups = self
             .claims
             .groups
-            .iter()
-            .flat_map(|g| g.split(":").last().map(|x| x.to_owned()))
-            .collect::<Vec<String>>();
+            .drain(..)
+            .map(Group::normalize)
+            .collect::<Vec<Group>>();
     }
 }
 
@@ -1,23 +1,41 @@
-use std::{env::var, sync::OnceLock};
+use std::{
+    env::var,
+    sync::{LazyLock, Mutex, OnceLock},
+};
 
-use crate::authentication::{
-    AuthenticationError,
-    authenticated_token::{AuthenticatedToken, Claims},
-    well_known::WellKnown,
+use crate::{
+    authentication::{
+        AuthenticationError,
+        authenticated_token::{AuthenticatedToken, Claims},
+        well_known::WellKnown,
+    },
+    config::{DemoConfig, TokenValidationConfig},
+    demo::{self, DEMO_TOKEN_PREFIX},
 };
 use axum::{
     body::Body,
-    http::{Request, Response, StatusCode},
+    http::{Request, Response, StatusCode, header::WWW_AUTHENTICATE},
 };
-use cached::proc_macro::cached;
+use cached::{Cached, TimedCache, proc_macro::cached};
+use chrono::Utc;
 use futures_util::future::BoxFuture;
-use jsonwebtoken::{DecodingKey, Validation, decode, decode_header, jwk::JwkSet};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode, decode_header,
+    errors::ErrorKind,
+    jwk::{Jwk, JwkSet},
+};
 use tower_http::auth::AsyncAuthorizeRequest;
 use tracing::{debug, error};
 
 pub static AUTH_WELL_KNOWN_URI: OnceLock<String> = OnceLock::new();
-static AUTH_ISSUER: OnceLock<String> = OnceLock::new();
-static AUTH_AUDIENCE: OnceLock<String> = OnceLock::new();
+pub static AUTH_ISSUER: OnceLock<String> = OnceLock::new();
+pub static AUTH_AUDIENCE: OnceLock<String> = OnceLock::new();
+
+/// How long a key id that is missing even from a freshly fetched key set
+/// is remembered, so that tokens naming it do not refetch the key set.
+pub const MISSING_KEY_TTL_SECONDS: u64 = 60;
+
+static KEY_ROTATION: LazyLock<KeyRotation> = LazyLock::new(KeyRotation::default);
 
 #[derive(Debug, Clone, Copy)]
 pub struct Authenticator;
@@ -46,6 +64,113 @@ async fn get_jwk_set(well_known: WellKnown) -> Result<JwkSet, AuthenticationErro
     Ok(jwks)
 }
 
+/// Looks up the keys tokens are signed with across a rotation of the keys
+/// of the provider.
+///
+/// The key set is cached for five minutes, so a token signed with a new key
+/// would otherwise be rejected until the cache expires. Instead, a token
+/// naming a key the cached set does not have refreshes the set once. Only
+/// one refresh runs at a time, and the ids of keys that are still missing
+/// afterwards are remembered for [`MISSING_KEY_TTL_SECONDS`] so that
+/// invalid tokens cannot keep the provider busy.
+pub struct KeyRotation {
+    refresh_lock: tokio::sync::Mutex<()>,
+    missing_kids: Mutex<TimedCache<String, ()>>,
+}
+
+impl Default for KeyRotation {
+    fn default() -> Self {
+        Self::new(MISSING_KEY_TTL_SECONDS)
+    }
+}
+
+impl KeyRotation {
+    pub fn new(missing_key_ttl_seconds: u64) -> Self {
+        Self {
+            refresh_lock: tokio::sync::Mutex::new(()),
+            missing_kids: Mutex::new(TimedCache::with_lifespan(missing_key_ttl_seconds)),
+        }
+    }
+
+    fn is_missing(&self, kid: &str) -> bool {
+        self.missing_kids
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .cache_get(kid)
+            .is_some()
+    }
+
+    /// Finds the key with `kid` in the set `cached` returns, falling back
+    /// to the set `refresh` fetches when it is not there.
+    pub async fn find_key<C, CachedFut, R, RefreshFut>(
+        &self,
+        kid: &str,
+        cached: C,
+        refresh: R,
+    ) -> Result<Jwk, AuthenticationError>
+    where
+        C: Fn() -> CachedFut,
+        CachedFut: Future<Output = Result<JwkSet, AuthenticationError>>,
+        R: FnOnce() -> RefreshFut,
+        RefreshFut: Future<Output = Result<JwkSet, AuthenticationError>>,
+    {
+        if let Some(jwk) = cached().await?.find(kid) {
+            return Ok(jwk.clone());
+        }
+        if self.is_missing(kid) {
+            return Err(AuthenticationError::MissingKey);
+        }
+
+        let _guard = self.refresh_lock.lock().await;
+        // Another request may have refreshed the set while this one waited.
+        if let Some(jwk) = cached().await?.find(kid) {
+            return Ok(jwk.clone());
+        }
+        if self.is_missing(kid) {
+            return Err(AuthenticationError::MissingKey);
+        }
+
+        debug!("Key {kid:?} is not in the cached jwk set, refreshing.");
+        match refresh().await?.find(kid) {
+            Some(jwk) => Ok(jwk.clone()),
+            None => {
+                self.missing_kids
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .cache_set(kid.to_owned(), ());
+                Err(AuthenticationError::MissingKey)
+            }
+        }
+    }
+}
+
+/// The `WWW-Authenticate` challenge for a failed authentication, with the
+/// reason it failed.
+fn challenge(error: Option<&AuthenticationError>) -> String {
+    match error {
+        None => "Bearer".to_owned(),
+        Some(
+            e @ (AuthenticationError::MissingBearer
+            | AuthenticationError::MissingToken
+            | AuthenticationError::MissingHeader),
+        ) => format!(r#"Bearer error="invalid_request", error_description="{e}""#),
+        Some(AuthenticationError::InvalidToken(e))
+            if matches!(e.kind(), ErrorKind::ExpiredSignature) =>
+        {
+            r#"Bearer error="invalid_token", error_description="The token has expired.""#.to_owned()
+        }
+        Some(e) => format!(r#"Bearer error="invalid_token", error_description="{e}""#),
+    }
+}
+
+fn unauthorized(error: Option<&AuthenticationError>) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header(WWW_AUTHENTICATE, challenge(error))
+        .body(Body::default())
+        .unwrap()
+}
+
 impl Authenticator {
     pub async fn authenticate(
         authorization_header: &str,
@@ -57,36 +182,77 @@ impl Authenticator {
         }
 
         let token = tokens.next().ok_or(AuthenticationError::MissingToken)?;
+        if let Some(demo_token) = token.strip_prefix(DEMO_TOKEN_PREFIX) {
+            return demo::authenticate(demo_token);
+        }
+        // A demo has no identity provider to validate any other token with.
+        if DemoConfig::from_env().enabled {
+            return Err(AuthenticationError::InvalidToken(
+                ErrorKind::InvalidToken.into(),
+            ));
+        }
         let header = decode_header(token)?;
         let kid = header.kid.ok_or(AuthenticationError::MissingKeyId)?;
 
-        let well_known = get_well_known().await?;
-        let jwk_set = get_jwk_set(well_known).await?;
-        let jwk = jwk_set.find(&kid).ok_or(AuthenticationError::MissingKey)?;
-        let decoding_key = DecodingKey::from_jwk(jwk)?;
+        let jwk = KEY_ROTATION
+            .find_key(
+                &kid,
+                || async { get_jwk_set(get_well_known().await?).await },
+                || async { get_jwk_set_prime_cache(get_well_known_prime_cache().await?).await },
+            )
+            .await?;
+        let decoding_key = DecodingKey::from_jwk(&jwk)?;
 
-        let mut validation = Validation::new(header.alg);
         let issuer = AUTH_ISSUER.get_or_init(|| {
             var("AUTH_ISSUER").expect("Failed to read `AUTH_ISSUER` environment variable.")
         });
         let audience = AUTH_AUDIENCE.get_or_init(|| {
             var("AUTH_AUDIENCE").expect("Failed to read `AUTH_AUDIENCE` environment variable.")
         });
-        validation.set_issuer(&[issuer]);
-        validation.set_audience(&[audience]);
-        validation.set_required_spec_claims(&[
-            "iss",
-            "exp",
-            "aud",
-            "email",
-            "email_verified",
-            "sub",
-        ]);
-        let claims = decode::<Claims>(token, &decoding_key, &validation)?.claims;
-        Ok(AuthenticatedToken::new(claims))
+        let validation = validation(
+            header.alg,
+            issuer,
+            audience,
+            TokenValidationConfig::from_env(),
+        );
+        decode_token(token, &decoding_key, &validation)
     }
 }
 
+/// How the tokens of the identity provider are checked, allowing for its
+/// clock being up to the configured leeway off from ours.
+fn validation(
+    alg: Algorithm,
+    issuer: &str,
+    audience: &str,
+    config: TokenValidationConfig,
+) -> Validation {
+    let mut validation = Validation::new(alg);
+    validation.set_issuer(&[issuer]);
+    validation.set_audience(&[audience]);
+    validation.set_required_spec_claims(&["iss", "exp", "aud", "email", "email_verified", "sub"]);
+    validation.leeway = config.leeway.as_secs();
+    validation.validate_nbf = true;
+    validation
+}
+
+/// Decodes a token, refusing one issued further in the future than the
+/// leeway of `validation`, which `jsonwebtoken` doesn't check itself.
+fn decode_token(
+    token: &str,
+    decoding_key: &DecodingKey,
+    validation: &Validation,
+) -> Result<AuthenticatedToken, AuthenticationError> {
+    let claims = decode::<Claims>(token, decoding_key, validation)?.claims;
+    let token = AuthenticatedToken::new(claims);
+    if token.iat() > Utc::now().timestamp() + validation.leeway as i64 {
+        return Err(AuthenticationError::InvalidToken(
+            ErrorKind::ImmatureSignature.into(),
+        ));
+    }
+    Ok(token)
+}
+
 impl<B: Send + 'static> AsyncAuthorizeRequest<B> for Authenticator {
     type RequestBody = B;
     type ResponseBody = Body;
@@ -94,6 +260,11 @@ impl<B: Send + 'static> AsyncAuthorizeRequest<B> for Authenticator {
 
     fn authorize(&mut self, mut request: Request<B>) -> Self::Future {
         Box::pin(async move {
+            // Already authenticated, with an API key or to count the request
+            // against the rate limit of its user.
+            if request.extensions().get::<AuthenticatedToken>().is_some() {
+                return Ok(request);
+            }
             let Some(authorization_header) = request
                 .headers()
                 .get("Authorization")
@@ -101,10 +272,7 @@ impl<B: Send + 'static> AsyncAuthorizeRequest<B> for Authenticator {
                 .map(|s| s.to_owned())
             else {
                 debug!("No Authentication Header");
-                return Err(Response::builder()
-                    .status(StatusCode::UNAUTHORIZED)
-                    .body(Body::default())
-                    .unwrap());
+                return Err(unauthorized(None));
             };
             match Self::authenticate(&authorization_header).await {
                 Ok(user) => {
@@ -126,22 +294,186 @@ impl<B: Send + 'static> AsyncAuthorizeRequest<B> for Authenticator {
                             .body(Body::default())
                             .unwrap())
                     }
-                    AuthenticationError::InvalidToken(e) => {
-                        debug!("{:?}", e.kind());
-                        Err(Response::builder()
-                            .status(StatusCode::UNAUTHORIZED)
-                            .body(Body::default())
-                            .unwrap())
+                    AuthenticationError::InvalidToken(ref error) => {
+                        debug!("{:?}", error.kind());
+                        Err(unauthorized(Some(&e)))
                     }
                     e => {
                         debug!("{e}");
-                        Err(Response::builder()
-                            .status(StatusCode::UNAUTHORIZED)
-                            .body(Body::default())
-                            .unwrap())
+                        Err(unauthorized(Some(&e)))
                     }
                 },
             }
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    const KEY_AFTER_ROTATION: &[u8] = b"treasury-rotation-test-key-after!";
+
+    fn jwk_set(document: &str) -> JwkSet {
+        serde_json::from_str(document).unwrap()
+    }
+
+    fn before_rotation() -> JwkSet {
+        jwk_set(include_str!("fixtures/jwks_before_rotation.json"))
+    }
+
+    fn after_rotation() -> JwkSet {
+        jwk_set(include_str!("fixtures/jwks_after_rotation.json"))
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct TestClaims {
+        sub: String,
+        exp: i64,
+    }
+
+    #[tokio::test]
+    async fn it_refreshes_the_key_set_once_for_a_rotated_key() {
+        let key_rotation = KeyRotation::default();
+        let refreshes = AtomicUsize::new(0);
+        let claims = TestClaims {
+            sub: "rotated".into(),
+            exp: i64::from(u32::MAX),
+        };
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("key-after".into());
+        let token = encode(
+            &header,
+            &claims,
+            &EncodingKey::from_secret(KEY_AFTER_ROTATION),
+        )
+        .unwrap();
+
+        let jwk = key_rotation
+            .find_key(
+                "key-after",
+                || async { Ok(before_rotation()) },
+                || async {
+                    refreshes.fetch_add(1, Ordering::SeqCst);
+                    Ok(after_rotation())
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.validate_aud = false;
+        let decoded =
+            decode::<TestClaims>(&token, &DecodingKey::from_jwk(&jwk).unwrap(), &validation)
+                .unwrap();
+        assert_eq!(decoded.claims.sub, "rotated");
+    }
+
+    #[tokio::test]
+    async fn it_uses_the_cached_key_set_when_it_has_the_key() {
+        let key_rotation = KeyRotation::default();
+        let refreshes = AtomicUsize::new(0);
+        let jwk = key_rotation
+            .find_key(
+                "key-before",
+                || async { Ok(before_rotation()) },
+                || async {
+                    refreshes.fetch_add(1, Ordering::SeqCst);
+                    Ok(after_rotation())
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(jwk.common.key_id.as_deref(), Some("key-before"));
+        assert_eq!(refreshes.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn it_remembers_keys_that_are_missing_after_a_refresh() {
+        let key_rotation = KeyRotation::default();
+        let refreshes = AtomicUsize::new(0);
+        for _ in 0..3 {
+            let result = key_rotation
+                .find_key(
+                    "key-unknown",
+                    || async { Ok(before_rotation()) },
+                    || async {
+                        refreshes.fetch_add(1, Ordering::SeqCst);
+                        Ok(after_rotation())
+                    },
+                )
+                .await;
+            assert!(matches!(result, Err(AuthenticationError::MissingKey)));
+        }
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn it_describes_why_authentication_failed() {
+        assert_eq!(challenge(None), "Bearer");
+        assert_eq!(
+            challenge(Some(&AuthenticationError::MissingKey)),
+            r#"Bearer error="invalid_token", error_description="Missing key with kid in JWKSet""#
+        );
+        assert!(
+            challenge(Some(&AuthenticationError::MissingBearer))
+                .starts_with(r#"Bearer error="invalid_request""#)
+        );
+    }
+
+    fn signed(iat_offset: i64) -> String {
+        let now = Utc::now().timestamp();
+        encode(
+            &Header::new(Algorithm::HS256),
+            &serde_json::json!({
+                "iss": "https://issuer.example.com",
+                "aud": "treasury",
+                "sub": "skewed",
+                "email": "skewed@example.com",
+                "email_verified": true,
+                "iat": now + iat_offset,
+                "nbf": now + iat_offset,
+                "exp": now + iat_offset + 3600,
+            }),
+            &EncodingKey::from_secret(KEY_AFTER_ROTATION),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_accepts_tokens_issued_in_the_future_within_the_leeway() {
+        let lenient = validation(
+            Algorithm::HS256,
+            "https://issuer.example.com",
+            "treasury",
+            TokenValidationConfig::default(),
+        );
+        let decoding_key = DecodingKey::from_secret(KEY_AFTER_ROTATION);
+
+        let token = decode_token(&signed(20), &decoding_key, &lenient).unwrap();
+        assert_eq!(token.sub(), "skewed");
+
+        let Err(AuthenticationError::InvalidToken(error)) =
+            decode_token(&signed(120), &decoding_key, &lenient)
+        else {
+            panic!("A token issued two minutes ahead was accepted.");
+        };
+        assert!(matches!(error.kind(), ErrorKind::ImmatureSignature));
+
+        let strict = validation(
+            Algorithm::HS256,
+            "https://issuer.example.com",
+            "treasury",
+            TokenValidationConfig {
+                leeway: std::time::Duration::ZERO,
+            },
+        );
+        assert!(decode_token(&signed(20), &decoding_key, &strict).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    env::var,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::OnceLock,
+};
+
+use axum::extract::{ConnectInfo, connect_info::MockConnectInfo};
+use http::{Extensions, HeaderMap};
+use sha2::{Digest, Sha256};
+
+/// The header a reverse proxy records the address of the client in.
+pub const FORWARDED_FOR_HEADER: &str = "X-Forwarded-For";
+
+static CLIENT_ADDRESS: OnceLock<ClientAddress> = OnceLock::new();
+
+/// How much of the address of a client is kept.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IpMode {
+    /// The network of the address, a /24 for IPv4 and a /48 for IPv6
+    #[default]
+    Truncate,
+    /// A salted hash of the address, which tells addresses apart without
+    /// revealing them
+    Hash,
+    /// Nothing
+    None,
+}
+
+impl IpMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "truncate" => Some(Self::Truncate),
+            "hash" => Some(Self::Hash),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+/// Where requests come from, as far as the deployment lets the server
+/// know, and how that is recorded.
+#[derive(Debug, Clone, Default)]
+pub struct ClientAddress {
+    /// Whether a trusted proxy in front of the server sets the
+    /// `X-Forwarded-For` header. Without one the header is whatever the
+    /// client sent, so it is ignored.
+    pub trust_forwarded_for: bool,
+    pub ip_mode: IpMode,
+    /// Mixed into hashed addresses so they cannot be recovered by hashing
+    /// every address
+    pub salt: String,
+}
+
+impl ClientAddress {
+    /// Reads `TRUST_FORWARDED_FOR`, `LOGIN_EVENT_IP_MODE` (`truncate`,
+    /// `hash` or `none`) and `LOGIN_EVENT_IP_SALT`.
+    pub fn from_env() -> &'static Self {
+        CLIENT_ADDRESS.get_or_init(|| Self {
+            trust_forwarded_for: var("TRUST_FORWARDED_FOR").is_ok_and(|v| v == "true"),
+            ip_mode: var("LOGIN_EVENT_IP_MODE")
+                .ok()
+                .and_then(|v| IpMode::parse(&v))
+                .unwrap_or_default(),
+            salt: var("LOGIN_EVENT_IP_SALT").unwrap_or_default(),
+        })
+    }
+
+    /// The address of the client, if a trusted proxy forwarded it.
+    ///
+    /// Each proxy appends the address it received the request from, so the
+    /// last entry is the one the trusted proxy saw while the ones before it
+    /// came from the client.
+    pub fn client_ip(&self, headers: &HeaderMap) -> Option<IpAddr> {
+        if !self.trust_forwarded_for {
+            return None;
+        }
+        headers
+            .get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .next_back()
+            .and_then(|ip| ip.trim().parse().ok())
+    }
+
+    /// The address of the client if a trusted proxy forwarded it, or else
+    /// that of the peer the request came in from. Without a trusted proxy
+    /// the peer is the client itself.
+    pub fn client_or_peer_ip(
+        &self,
+        headers: &HeaderMap,
+        extensions: &Extensions,
+    ) -> Option<IpAddr> {
+        self.client_ip(headers).or_else(|| peer_ip(extensions))
+    }
+
+    /// The address in the form it is stored in.
+    pub fn anonymize(&self, ip: IpAddr) -> Option<String> {
+        match self.ip_mode {
+            IpMode::Truncate => Some(match ip {
+                IpAddr::V4(ip) => {
+                    let [a, b, c, _] = ip.octets();
+                    Ipv4Addr::new(a, b, c, 0).to_string()
+                }
+                IpAddr::V6(ip) => {
+                    let [a, b, c, ..] = ip.segments();
+                    Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0).to_string()
+                }
+            }),
+            IpMode::Hash => {
+                let mut hasher = Sha256::new();
+                hasher.update(self.salt.as_bytes());
+                hasher.update(ip.to_string().as_bytes());
+                Some(
+                    hasher.finalize()[..8]
+                        .iter()
+                        .map(|b| format!("{b:02x}"))
+                        .collect(),
+                )
+            }
+            IpMode::None => None,
+        }
+    }
+
+    /// The stored form of the address of the client of a request.
+    pub fn recorded_ip(&self, headers: &HeaderMap) -> Option<String> {
+        self.client_ip(headers).and_then(|ip| self.anonymize(ip))
+    }
+}
+
+/// The address of the peer a request came in from, which is known when the
+/// router is served with connect info.
+pub fn peer_ip(extensions: &Extensions) -> Option<IpAddr> {
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip())
+        .or_else(|| {
+            extensions
+                .get::<MockConnectInfo<SocketAddr>>()
+                .map(|MockConnectInfo(address)| address.ip())
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http::HeaderValue;
+    use rstest::rstest;
+
+    fn headers(forwarded_for: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in forwarded_for {
+            headers.append(FORWARDED_FOR_HEADER, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn trusted(ip_mode: IpMode) -> ClientAddress {
+        ClientAddress {
+            trust_forwarded_for: true,
+            ip_mode,
+            salt: "salt".into(),
+        }
+    }
+
+    #[rstest]
+    #[case(&["203.0.113.7"], Some("203.0.113.7"))]
+    #[case(&["198.51.100.1, 203.0.113.7"], Some("203.0.113.7"))]
+    #[case(&["198.51.100.1", "203.0.113.7"], Some("203.0.113.7"))]
+    #[case(&[" 2001:db8::1 "], Some("2001:db8::1"))]
+    #[case(&["203.0.113.7, unknown"], None)]
+    #[case(&[], None)]
+    fn it_takes_the_address_the_trusted_proxy_saw(
+        #[case] forwarded_for: &[&str],
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(
+            trusted(IpMode::Truncate).client_ip(&headers(forwarded_for)),
+            expected.map(|ip| ip.parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn it_ignores_the_header_without_a_trusted_proxy() {
+        let client_address = ClientAddress {
+            trust_forwarded_for: false,
+            ..trusted(IpMode::Truncate)
+        };
+        assert_eq!(client_address.client_ip(&headers(&["203.0.113.7"])), None);
+        assert_eq!(client_address.recorded_ip(&headers(&["203.0.113.7"])), None);
+    }
+
+    #[test]
+    fn it_falls_back_to_the_peer_without_a_forwarded_address() {
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([198, 51, 100, 1], 443))));
+        let peer = Some("198.51.100.1".parse().unwrap());
+        let untrusted = ClientAddress::default();
+        assert_eq!(
+            untrusted.client_or_peer_ip(&headers(&["203.0.113.7"]), &extensions),
+            peer
+        );
+        assert_eq!(
+            trusted(IpMode::Truncate).client_or_peer_ip(&headers(&["203.0.113.7"]), &extensions),
+            Some("203.0.113.7".parse().unwrap())
+        );
+        assert_eq!(
+            trusted(IpMode::Truncate).client_or_peer_ip(&headers(&[]), &extensions),
+            peer
+        );
+        assert_eq!(
+            untrusted.client_or_peer_ip(&headers(&[]), &Extensions::new()),
+            None
+        );
+    }
+
+    #[rstest]
+    #[case("203.0.113.7", "203.0.113.0")]
+    #[case("2001:db8:1234:5678::1", "2001:db8:1234::")]
+    fn it_truncates_addresses(#[case] ip: &str, #[case] expected: &str) {
+        assert_eq!(
+            trusted(IpMode::Truncate).anonymize(ip.parse().unwrap()),
+            Some(expected.to_owned())
+        );
+    }
+
+    #[test]
+    fn it_hashes_addresses_with_the_salt() {
+        let ip = "203.0.113.7".parse().unwrap();
+        let hashed = trusted(IpMode::Hash).anonymize(ip).unwrap();
+        assert_eq!(hashed.len(), 16);
+        assert_eq!(trusted(IpMode::Hash).anonymize(ip), Some(hashed.clone()));
+
+        let resalted = ClientAddress {
+            salt: "pepper".into(),
+            ..trusted(IpMode::Hash)
+        };
+        assert_ne!(resalted.anonymize(ip), Some(hashed));
+        assert_eq!(trusted(IpMode::None).anonymize(ip), None);
+    }
+}
@@ -0,0 +1,54 @@
+//! The lifetime of the `state` of a sign in, from when `/login/sso` issues
+//! it to when the redirect back consumes it.
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::PgPool;
+use tracing::{error, info, instrument};
+
+use crate::{
+    resource::{csrf_token_repository::CsrfTokenRepository, deadline},
+    service::ServiceError,
+};
+
+/// How long a state can be consumed for after it is issued. A consumed
+/// state is kept as long, so using it twice is told apart from never
+/// having issued it.
+pub const STATE_TTL: Duration = Duration::from_secs(600);
+/// How often the states older than [`STATE_TTL`] are deleted.
+pub const REAPER_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The earliest a state still in its TTL was issued.
+pub fn issued_after() -> DateTime<Utc> {
+    TimeDelta::from_std(STATE_TTL)
+        .ok()
+        .and_then(|state_ttl| Utc::now().checked_sub_signed(state_ttl))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Deletes the states older than [`STATE_TTL`], returning how many there
+/// were.
+#[instrument(skip_all)]
+pub async fn delete_expired(connection_pool: &PgPool) -> Result<u64, ServiceError> {
+    let deleted = CsrfTokenRepository
+        .delete_issued_before(deadline::begin(&connection_pool).await?, issued_after())
+        .await?;
+    if deleted > 0 {
+        info!("Deleted {deleted} expired sign in states");
+    }
+    Ok(deleted)
+}
+
+/// Deletes expired states every [`REAPER_INTERVAL`].
+pub fn spawn_reaper(connection_pool: Arc<PgPool>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REAPER_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = delete_expired(&connection_pool).await {
+                error!("Failed to delete the expired sign in states: {e}");
+            }
+        }
+    });
+}
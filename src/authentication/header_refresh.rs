@@ -0,0 +1,79 @@
+use std::{env::var, sync::OnceLock};
+
+use axum_extra::extract::cookie::CookieJar;
+use http::HeaderMap;
+use tracing::warn;
+
+use crate::api::ApiError;
+
+/// The header non-browser clients send their refresh token in.
+pub const REFRESH_TOKEN_HEADER: &str = "Refresh-Token";
+/// The header identifying a non-browser client.
+pub const CLIENT_ID_HEADER: &str = "Client-Id";
+
+static HEADER_REFRESH: OnceLock<HeaderRefresh> = OnceLock::new();
+
+/// Whether refresh tokens may be presented in a header instead of the
+/// `refresh_token` cookie, and by which clients.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderRefresh {
+    pub enabled: bool,
+    pub clients: Vec<String>,
+}
+
+impl HeaderRefresh {
+    /// Reads `ALLOW_HEADER_REFRESH` and the comma separated
+    /// `HEADER_REFRESH_CLIENTS` allowlist.
+    pub fn from_env() -> &'static Self {
+        HEADER_REFRESH.get_or_init(|| Self {
+            enabled: var("ALLOW_HEADER_REFRESH").is_ok_and(|v| v == "true"),
+            clients: var("HEADER_REFRESH_CLIENTS")
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|c| !c.is_empty())
+                        .map(str::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Returns the refresh token presented in the headers, if any.
+    ///
+    /// Requests without the header fall back to the cookie flow. A header
+    /// is rejected unless the flow is enabled and the client is allowed.
+    pub fn refresh_token(&self, headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+        let Some(refresh_token) = headers.get(REFRESH_TOKEN_HEADER) else {
+            return Ok(None);
+        };
+        if !self.enabled {
+            warn!("Rejected header refresh: the header flow is disabled.");
+            return Err(ApiError::Forbidden);
+        }
+        let client_id = headers
+            .get(CLIENT_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or(ApiError::Forbidden)?;
+        if !self.clients.iter().any(|c| c == client_id) {
+            warn!("Rejected header refresh: client {client_id:?} is not allowed.");
+            return Err(ApiError::Forbidden);
+        }
+        let refresh_token = refresh_token.to_str().map_err(|_| ApiError::Forbidden)?;
+        if refresh_token.is_empty() {
+            return Err(ApiError::Forbidden);
+        }
+        Ok(Some(refresh_token.to_owned()))
+    }
+
+    /// Returns the refresh token presented in the headers, or else in the
+    /// `refresh_token` cookie.
+    pub fn presented_refresh_token(&self, headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+        if let Some(refresh_token) = self.refresh_token(headers)? {
+            return Ok(Some(refresh_token));
+        }
+        Ok(CookieJar::from_headers(headers)
+            .get("refresh_token")
+            .map(|cookie| cookie.value().to_owned()))
+    }
+}
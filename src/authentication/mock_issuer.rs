@@ -0,0 +1,239 @@
+use std::env::var;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use chrono::{TimeDelta, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use rsa::{
+    RsaPrivateKey,
+    pkcs1::{EncodeRsaPrivateKey, LineEnding},
+    rand_core::OsRng,
+    traits::PublicKeyParts,
+};
+use serde_json::{Map, Value, json};
+use tokio::sync::OnceCell;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
+
+use crate::authentication::authenticator::{AUTH_AUDIENCE, AUTH_ISSUER, AUTH_WELL_KNOWN_URI};
+
+/// Set to `mock` to authenticate tests with a [`MockIssuer`] rather than a
+/// live Dex.
+pub const TEST_OIDC_VAR: &str = "TEST_OIDC";
+
+/// The audience tokens are minted for, unless the authenticator already
+/// read another from `AUTH_AUDIENCE`.
+pub const MOCK_AUDIENCE: &str = "treasury-test";
+
+const MOCK_KEY_ID: &str = "treasury-test-key";
+
+static MOCK_ISSUER: OnceCell<MockIssuer> = OnceCell::const_new();
+
+/// The claims of a token minted by a [`MockIssuer`].
+#[derive(Debug, Clone, Default)]
+pub struct MockClaims {
+    pub sub: String,
+    pub email: String,
+    pub groups: Vec<String>,
+    pub name: Option<String>,
+    /// Any other claims, which override the ones above
+    pub extra: Map<String, Value>,
+}
+
+impl MockClaims {
+    pub fn new(sub: &str, email: &str) -> Self {
+        Self {
+            sub: sub.to_owned(),
+            email: email.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+/// An identity provider for tests, which serves a discovery document and
+/// key set from a local server and signs tokens for any subject.
+pub struct MockIssuer {
+    server: MockServer,
+    encoding_key: EncodingKey,
+    issuer: String,
+    audience: String,
+}
+
+impl MockIssuer {
+    /// Whether the tests authenticate with a mock issuer.
+    pub fn enabled() -> bool {
+        var(TEST_OIDC_VAR).is_ok_and(|x| x == "mock")
+    }
+
+    /// The issuer shared by the tests of the process, which the
+    /// authenticator is pointed at when it is started.
+    pub async fn shared() -> &'static Self {
+        MOCK_ISSUER
+            .get_or_init(|| async {
+                let mut issuer = Self::start().await;
+                issuer.install();
+                issuer
+            })
+            .await
+    }
+
+    /// Starts an issuer of its own, which the authenticator doesn't trust.
+    pub async fn start() -> Self {
+        let private_key =
+            RsaPrivateKey::new(&mut OsRng, 2048).expect("Failed to generate the signing key");
+        let encoding_key = EncodingKey::from_rsa_pem(
+            private_key
+                .to_pkcs1_pem(LineEnding::LF)
+                .expect("Failed to encode the signing key")
+                .as_bytes(),
+        )
+        .expect("Failed to read the signing key");
+
+        let server = MockServer::start().await;
+        let uri = server.uri();
+        let issuer = uri.clone();
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-configuration"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issuer": issuer,
+                "authorization_endpoint": format!("{uri}/auth"),
+                "token_endpoint": format!("{uri}/token"),
+                "jwks_uri": format!("{uri}/keys"),
+                "userinfo_endpoint": format!("{uri}/userinfo"),
+                "device_authorization_endpoint": format!("{uri}/device/code"),
+                "introspection_endpoint": format!("{uri}/token/introspect"),
+                "grant_types_supported": ["authorization_code", "refresh_token"],
+                "response_types_supported": ["code"],
+                "subject_types_supported": ["public"],
+                "id_token_signing_alg_values_supported": ["RS256"],
+                "code_challenge_methods_supported": ["S256"],
+                "scopes_supported": ["openid", "email", "groups", "profile"],
+                "token_endpoint_auth_methods_supported": ["client_secret_basic"],
+                "claims_supported": ["iss", "sub", "aud", "exp", "iat", "email", "email_verified", "groups", "name"],
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/keys"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "keys": [{
+                    "kty": "RSA",
+                    "use": "sig",
+                    "alg": "RS256",
+                    "kid": MOCK_KEY_ID,
+                    "n": BASE64_URL_SAFE_NO_PAD.encode(private_key.n().to_bytes_be()),
+                    "e": BASE64_URL_SAFE_NO_PAD.encode(private_key.e().to_bytes_be()),
+                }],
+            })))
+            .mount(&server)
+            .await;
+
+        Self {
+            server,
+            encoding_key,
+            issuer,
+            audience: MOCK_AUDIENCE.to_owned(),
+        }
+    }
+
+    /// Points the authenticator at the issuer. Tokens are minted for the
+    /// issuer and audience the authenticator checks, when it read them
+    /// from the environment before.
+    fn install(&mut self) {
+        let well_known_uri = format!("{}/.well-known/openid-configuration", self.server.uri());
+        let _ = AUTH_WELL_KNOWN_URI.set(well_known_uri.clone());
+        assert_eq!(
+            AUTH_WELL_KNOWN_URI.get(),
+            Some(&well_known_uri),
+            "The authenticator already fetches keys from another provider."
+        );
+        self.issuer = AUTH_ISSUER.get_or_init(|| self.issuer.clone()).clone();
+        self.audience = AUTH_AUDIENCE.get_or_init(|| self.audience.clone()).clone();
+    }
+
+    /// Signs an id token with the claims, valid for an hour, and returns it
+    /// as an `Authorization` header value.
+    pub fn token(&self, claims: &MockClaims) -> String {
+        let now = Utc::now();
+        let mut payload = json!({
+            "iss": self.issuer,
+            "aud": self.audience,
+            "iat": now.timestamp(),
+            "exp": (now + TimeDelta::hours(1)).timestamp(),
+            "sub": claims.sub,
+            "email": claims.email,
+            "email_verified": true,
+            "groups": claims.groups,
+            "name": claims.name,
+        });
+        if let Value::Object(payload) = &mut payload {
+            payload.extend(claims.extra.clone());
+        }
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(MOCK_KEY_ID.to_owned());
+        let token =
+            encode(&header, &payload, &self.encoding_key).expect("Failed to sign the token");
+        format!("Bearer {token}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jsonwebtoken::{DecodingKey, Validation, decode, jwk::JwkSet};
+
+    use super::*;
+    use crate::authentication::{
+        authenticated_token::{AuthenticatedToken, Claims},
+        well_known::WellKnown,
+    };
+
+    #[tokio::test]
+    async fn it_signs_tokens_with_the_keys_it_serves() {
+        let issuer = MockIssuer::start().await;
+        let mut claims = MockClaims::new("mock-user", "mock@example.com");
+        claims.groups = vec!["admin".into()];
+        claims
+            .extra
+            .insert("preferred_username".into(), "mock".into());
+        let authorization = issuer.token(&claims);
+        let token = authorization.trim_start_matches("Bearer ");
+
+        let well_known = reqwest::get(format!(
+            "{}/.well-known/openid-configuration",
+            issuer.server.uri()
+        ))
+        .await
+        .unwrap()
+        .json::<WellKnown>()
+        .await
+        .unwrap();
+        let jwk_set = reqwest::get(well_known.jwks_uri)
+            .await
+            .unwrap()
+            .json::<JwkSet>()
+            .await
+            .unwrap();
+        let decoding_key = DecodingKey::from_jwk(jwk_set.find(MOCK_KEY_ID).unwrap()).unwrap();
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(&[well_known.issuer]);
+        validation.set_audience(&[MOCK_AUDIENCE]);
+
+        let decoded = decode::<Claims>(token, &decoding_key, &validation).unwrap();
+        let authenticated_token = AuthenticatedToken::new(decoded.claims);
+        assert_eq!(authenticated_token.sub(), "mock-user");
+        assert_eq!(authenticated_token.email(), "mock@example.com");
+        assert_eq!(authenticated_token.groups(), &["admin".into()]);
+
+        claims.extra.insert("exp".into(), 0.into());
+        let expired = issuer.token(&claims);
+        assert!(
+            decode::<Claims>(
+                expired.trim_start_matches("Bearer "),
+                &decoding_key,
+                &validation
+            )
+            .is_err()
+        );
+    }
+}
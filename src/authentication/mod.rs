@@ -1,6 +1,13 @@
+pub mod api_key;
 pub mod authenticated_token;
 pub mod authenticator;
+pub mod client_address;
+pub mod csrf;
+pub mod header_refresh;
+#[cfg(test)]
+pub mod mock_issuer;
 pub mod registered_user;
+pub mod step_up;
 pub mod well_known;
 
 use thiserror::Error;
@@ -5,24 +5,96 @@ use tracing::error;
 use crate::{
     api::{ApiError, AppState},
     authentication::authenticated_token::AuthenticatedToken,
-    model::user::{User, UserFilter, UserId},
-    resource::{GetListRepository, user_repository::UserRepository},
+    model::{
+        account::AccountId,
+        user::{User, UserFilter, UserId},
+    },
+    resource::{GetListRepository, deadline, user_repository::UserRepository},
     service::ServiceError,
 };
 
 #[derive(Debug, Clone)]
 pub struct RegisteredUser {
     pub user: User,
+    /// The accounts the request is limited to when made with a scoped
+    /// API key
+    account_scope: Option<Vec<AccountId>>,
 }
 
 impl RegisteredUser {
     pub fn new(user: User) -> Self {
-        Self { user }
+        Self {
+            user,
+            account_scope: None,
+        }
+    }
+
+    pub fn with_account_scope(mut self, account_scope: Option<Vec<AccountId>>) -> Self {
+        self.account_scope = account_scope;
+        self
     }
 
     pub fn id(&self) -> UserId {
         self.user.id
     }
+
+    pub fn account_scope(&self) -> Option<Vec<AccountId>> {
+        self.account_scope.clone()
+    }
+
+    pub fn is_account_scoped(&self) -> bool {
+        self.account_scope.is_some()
+    }
+}
+
+/// The user the middleware found for the request, which the extractors
+/// after it read instead of looking the user up again.
+#[derive(Debug, Clone)]
+pub struct CachedUser(pub Option<RegisteredUser>);
+
+/// Finds the registered user of the token of the request.
+async fn find_registered_user(
+    parts: &Parts,
+    state: &AppState,
+) -> Result<Option<RegisteredUser>, ApiError> {
+    if let Some(CachedUser(Some(registered_user))) = parts.extensions.get::<CachedUser>() {
+        return Ok(Some(registered_user.clone()));
+    }
+
+    let authenticated_token = parts
+        .extensions
+        .get::<AuthenticatedToken>()
+        .cloned()
+        .ok_or(ApiError::Service(ServiceError::Unauthorized))?;
+
+    let user_repository = UserRepository {};
+    let registered_user = user_repository
+        .get_list(
+            deadline::begin(&state.connection_pool).await.map_err(|e| {
+                error!("{e}");
+                ApiError::ServerError
+            })?,
+            0,
+            1.into(),
+            UserFilter {
+                iss: authenticated_token.iss().to_owned().into(),
+                sub: authenticated_token.sub().to_owned().into(),
+                ..Default::default()
+            },
+        )
+        .await
+        .ok()
+        .unwrap_or(vec![])
+        .pop()
+        .map(|user| {
+            RegisteredUser::new(user).with_account_scope(
+                authenticated_token
+                    .api_key_scope()
+                    .and_then(|scope| scope.account_ids.clone()),
+            )
+        });
+
+    Ok(registered_user)
 }
 
 impl FromRequestParts<AppState> for RegisteredUser {
@@ -32,35 +104,9 @@ impl FromRequestParts<AppState> for RegisteredUser {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        let authenticated_token = parts
-            .extensions
-            .get::<AuthenticatedToken>()
-            .cloned()
-            .ok_or(ApiError::Service(ServiceError::Unauthorized))?;
-
-        let user_repository = UserRepository {};
-        let user = user_repository
-            .get_list(
-                state.connection_pool.begin().await.map_err(|e| {
-                    error!("{e}");
-                    ApiError::ServerError
-                })?,
-                0,
-                1.into(),
-                UserFilter {
-                    iss: authenticated_token.iss().to_owned().into(),
-                    sub: authenticated_token.sub().to_owned().into(),
-                    ..Default::default()
-                },
-            )
-            .await
-            .ok()
-            .unwrap_or(vec![])
-            .pop()
-            .ok_or(ApiError::Service(ServiceError::Unauthorized))?;
-
-        let registered_user = RegisteredUser::new(user);
-        Ok(registered_user)
+        find_registered_user(parts, state)
+            .await?
+            .ok_or(ApiError::Service(ServiceError::Unauthorized))
     }
 }
 
@@ -71,33 +117,6 @@ impl OptionalFromRequestParts<AppState> for RegisteredUser {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Option<Self>, Self::Rejection> {
-        let authenticated_token = parts
-            .extensions
-            .get::<AuthenticatedToken>()
-            .cloned()
-            .ok_or(ApiError::Service(ServiceError::Unauthorized))?;
-
-        let user_repository = UserRepository {};
-        let registered_user = user_repository
-            .get_list(
-                state.connection_pool.begin().await.map_err(|e| {
-                    error!("{e}");
-                    ApiError::ServerError
-                })?,
-                0,
-                1.into(),
-                UserFilter {
-                    iss: authenticated_token.iss().to_owned().into(),
-                    sub: authenticated_token.sub().to_owned().into(),
-                    ..Default::default()
-                },
-            )
-            .await
-            .ok()
-            .unwrap_or(vec![])
-            .pop()
-            .map(RegisteredUser::new);
-
-        Ok(registered_user)
+        find_registered_user(parts, state).await
     }
 }
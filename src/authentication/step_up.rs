@@ -0,0 +1,93 @@
+use std::{env::var, sync::OnceLock};
+
+use axum::{RequestPartsExt, extract::FromRequestParts};
+use http::request::Parts;
+use webauthn_rs::{Webauthn, WebauthnBuilder, prelude::Url};
+
+use crate::{
+    api::{ApiError, AppState, passkey_api::PasskeyApiState},
+    authentication::{authenticated_token::AuthenticatedToken, registered_user::RegisteredUser},
+    service::{ServiceError, passkey_service::PasskeyServiceElevation},
+};
+
+/// How long a passkey ceremony may take before its challenge is rejected.
+pub const WEBAUTHN_CHALLENGE_TTL: i64 = 300;
+/// How long a successful step-up keeps the session elevated.
+pub const STEP_UP_GRANT_TTL: i64 = 300;
+
+static WEBAUTHN: OnceLock<Webauthn> = OnceLock::new();
+static WEBAUTHN_RP_ID: OnceLock<String> = OnceLock::new();
+static WEBAUTHN_RP_ORIGIN: OnceLock<String> = OnceLock::new();
+
+pub fn webauthn() -> &'static Webauthn {
+    WEBAUTHN.get_or_init(|| {
+        let rp_id = WEBAUTHN_RP_ID.get_or_init(|| {
+            var("WEBAUTHN_RP_ID").expect("Failed to read `WEBAUTHN_RP_ID` environment variable.")
+        });
+        let rp_origin = WEBAUTHN_RP_ORIGIN.get_or_init(|| {
+            var("WEBAUTHN_RP_ORIGIN")
+                .expect("Failed to read `WEBAUTHN_RP_ORIGIN` environment variable.")
+        });
+        let rp_origin = Url::parse(rp_origin).expect("Invalid webauthn relying party origin.");
+        WebauthnBuilder::new(rp_id, &rp_origin)
+            .expect("Invalid webauthn configuration.")
+            .rp_name("Treasury")
+            .build()
+            .expect("Invalid webauthn configuration.")
+    })
+}
+
+/// Whether the current session may perform sensitive operations.
+///
+/// Users without a registered passkey cannot step up, so elevation is only
+/// demanded once at least one passkey exists.
+#[derive(Debug, Clone, Copy)]
+pub struct Elevation {
+    pub required: bool,
+    pub elevated: bool,
+}
+
+impl Elevation {
+    pub fn require(&self) -> Result<(), ApiError> {
+        if self.required && !self.elevated {
+            return Err(ApiError::StepUpRequired);
+        }
+        Ok(())
+    }
+}
+
+impl FromRequestParts<AppState> for Elevation {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let authenticated_token = parts
+            .extensions
+            .get::<AuthenticatedToken>()
+            .cloned()
+            .ok_or(ApiError::Service(ServiceError::Unauthorized))?;
+
+        // Callers yet to register have no passkeys to step up with.
+        if parts
+            .extract_with_state::<Option<RegisteredUser>, _>(state)
+            .await?
+            .is_none()
+        {
+            return Ok(Self {
+                required: false,
+                elevated: false,
+            });
+        }
+
+        let api_state = parts
+            .extract_with_state::<PasskeyApiState, _>(state)
+            .await?;
+        let elevation = api_state
+            .service
+            .elevation(authenticated_token.session())
+            .await?;
+        Ok(elevation)
+    }
+}
@@ -1,4 +1,6 @@
-use std::marker::PhantomData;
+use std::{fmt::Display, marker::PhantomData, str::FromStr};
+
+use thiserror::Error;
 
 pub struct NoPermission;
 pub struct Read;
@@ -10,7 +12,43 @@ pub struct UpdateAll;
 pub struct Delete;
 pub struct DeleteAll;
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+/// An action that is none of the levels it was read as.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Unknown action `{0}`.")]
+pub struct UnknownAction(pub String);
+
+/// Names the levels of an action as they are written in the policies,
+/// with `as_str` and a `FromStr` that fails on any other name.
+macro_rules! action_level {
+    ($level:ident { $($variant:ident => $name:literal),+ $(,)? }) => {
+        impl $level {
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)+
+                }
+            }
+        }
+
+        impl FromStr for $level {
+            type Err = UnknownAction;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($name => Ok(Self::$variant),)+
+                    _ => Err(UnknownAction(s.to_owned())),
+                }
+            }
+        }
+
+        impl Display for $level {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum ReadLevel {
     ReadAll,
     Read,
@@ -26,47 +64,13 @@ impl ReadLevel {
     }
 }
 
-impl From<ReadLevel> for &str {
-    fn from(value: ReadLevel) -> Self {
-        match value {
-            ReadLevel::ReadAll => "read_all",
-            ReadLevel::Read => "read",
-            ReadLevel::NoPermission => "none",
-        }
-    }
-}
-
-impl From<&str> for ReadLevel {
-    fn from(value: &str) -> Self {
-        match value {
-            "read" => ReadLevel::Read,
-            "read_all" => ReadLevel::ReadAll,
-            _ => ReadLevel::default(),
-        }
-    }
-}
-
-impl From<String> for ReadLevel {
-    fn from(value: String) -> Self {
-        match value {
-            val if val == *"read" => ReadLevel::Read,
-            val if val == *"read_all" => ReadLevel::ReadAll,
-            _ => ReadLevel::default(),
-        }
-    }
-}
-
-impl From<&String> for ReadLevel {
-    fn from(value: &String) -> Self {
-        match value {
-            val if val == "read" => ReadLevel::Read,
-            val if val == "read_all" => ReadLevel::ReadAll,
-            _ => ReadLevel::default(),
-        }
-    }
-}
+action_level!(ReadLevel {
+    ReadAll => "read_all",
+    Read => "read",
+    NoPermission => "none",
+});
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum CreateLevel {
     CreateAll,
     Create,
@@ -82,47 +86,13 @@ impl CreateLevel {
     }
 }
 
-impl From<CreateLevel> for &str {
-    fn from(value: CreateLevel) -> Self {
-        match value {
-            CreateLevel::CreateAll => "create_all",
-            CreateLevel::Create => "create",
-            CreateLevel::NoPermission => "none",
-        }
-    }
-}
-
-impl From<&str> for CreateLevel {
-    fn from(value: &str) -> Self {
-        match value {
-            "create_all" => CreateLevel::CreateAll,
-            "create" => CreateLevel::Create,
-            _ => CreateLevel::default(),
-        }
-    }
-}
-
-impl From<String> for CreateLevel {
-    fn from(value: String) -> Self {
-        match value {
-            val if val == *"create_all" => CreateLevel::CreateAll,
-            val if val == *"create" => CreateLevel::Create,
-            _ => CreateLevel::default(),
-        }
-    }
-}
-
-impl From<&String> for CreateLevel {
-    fn from(value: &String) -> Self {
-        match value {
-            val if val == "create_all" => CreateLevel::CreateAll,
-            val if val == "create" => CreateLevel::Create,
-            _ => CreateLevel::default(),
-        }
-    }
-}
+action_level!(CreateLevel {
+    CreateAll => "create_all",
+    Create => "create",
+    NoPermission => "none",
+});
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum UpdateLevel {
     UpdateAll,
     Update,
@@ -138,47 +108,13 @@ impl UpdateLevel {
     }
 }
 
-impl From<UpdateLevel> for &str {
-    fn from(value: UpdateLevel) -> Self {
-        match value {
-            UpdateLevel::UpdateAll => "update_all",
-            UpdateLevel::Update => "update",
-            UpdateLevel::NoPermission => "none",
-        }
-    }
-}
-
-impl From<&str> for UpdateLevel {
-    fn from(value: &str) -> Self {
-        match value {
-            "update" => UpdateLevel::Update,
-            "update_all" => UpdateLevel::UpdateAll,
-            _ => UpdateLevel::default(),
-        }
-    }
-}
+action_level!(UpdateLevel {
+    UpdateAll => "update_all",
+    Update => "update",
+    NoPermission => "none",
+});
 
-impl From<String> for UpdateLevel {
-    fn from(value: String) -> Self {
-        match value {
-            val if val == *"update" => UpdateLevel::Update,
-            val if val == *"update_all" => UpdateLevel::UpdateAll,
-            _ => UpdateLevel::default(),
-        }
-    }
-}
-
-impl From<&String> for UpdateLevel {
-    fn from(value: &String) -> Self {
-        match value {
-            val if val == "update" => UpdateLevel::Update,
-            val if val == "update_all" => UpdateLevel::UpdateAll,
-            _ => UpdateLevel::default(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum DeleteLevel {
     DeleteAll,
     Delete,
@@ -194,43 +130,78 @@ impl DeleteLevel {
     }
 }
 
-impl From<DeleteLevel> for &str {
-    fn from(value: DeleteLevel) -> Self {
-        match value {
-            DeleteLevel::DeleteAll => "delete_all",
-            DeleteLevel::Delete => "delete",
-            DeleteLevel::NoPermission => "none",
+action_level!(DeleteLevel {
+    DeleteAll => "delete_all",
+    Delete => "delete",
+    NoPermission => "none",
+});
+
+/// The action of a policy, which grants one level of an action, or every
+/// action at every level with `*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PolicyAction {
+    Any,
+    Read(ReadLevel),
+    Create(CreateLevel),
+    Update(UpdateLevel),
+    Delete(DeleteLevel),
+}
+
+impl PolicyAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Any => "*",
+            Self::Read(level) => level.as_str(),
+            Self::Create(level) => level.as_str(),
+            Self::Update(level) => level.as_str(),
+            Self::Delete(level) => level.as_str(),
         }
     }
-}
 
-impl From<&str> for DeleteLevel {
-    fn from(value: &str) -> Self {
-        match value {
-            "delete" => DeleteLevel::Delete,
-            "delete_all" => DeleteLevel::DeleteAll,
-            _ => DeleteLevel::default(),
+    /// What the action lets a subject do, in words.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Any => "do anything",
+            Self::Read(ReadLevel::ReadAll) => "read everyone's",
+            Self::Read(_) => "read their own",
+            Self::Create(CreateLevel::CreateAll) => "create for anyone",
+            Self::Create(_) => "create their own",
+            Self::Update(UpdateLevel::UpdateAll) => "update everyone's",
+            Self::Update(_) => "update their own",
+            Self::Delete(DeleteLevel::DeleteAll) => "delete everyone's",
+            Self::Delete(_) => "delete their own",
         }
     }
 }
 
-impl From<String> for DeleteLevel {
-    fn from(value: String) -> Self {
-        match value {
-            val if val == *"delete" => DeleteLevel::Delete,
-            val if val == *"delete_all" => DeleteLevel::DeleteAll,
-            _ => DeleteLevel::default(),
+impl FromStr for PolicyAction {
+    type Err = UnknownAction;
+
+    /// Reads the action of a policy. `none` is the level of no permission,
+    /// which a policy can't grant, so it is as unknown as a typo.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "*" {
+            return Ok(Self::Any);
+        }
+        let action = s
+            .parse()
+            .map(Self::Read)
+            .or_else(|_| s.parse().map(Self::Create))
+            .or_else(|_| s.parse().map(Self::Update))
+            .or_else(|_| s.parse().map(Self::Delete))?;
+        match action {
+            Self::Read(ReadLevel::NoPermission)
+            | Self::Create(CreateLevel::NoPermission)
+            | Self::Update(UpdateLevel::NoPermission)
+            | Self::Delete(DeleteLevel::NoPermission) => Err(UnknownAction(s.to_owned())),
+            action => Ok(action),
         }
     }
 }
 
-impl From<&String> for DeleteLevel {
-    fn from(value: &String) -> Self {
-        match value {
-            val if val == "delete" => DeleteLevel::Delete,
-            val if val == "delete_all" => DeleteLevel::DeleteAll,
-            _ => DeleteLevel::default(),
-        }
+impl Display for PolicyAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
@@ -245,3 +216,56 @@ pub struct ActionSet<
     update: PhantomData<Update>,
     delete: PhantomData<Delete>,
 }
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn it_round_trips_the_names_of_every_level() {
+        for level in ReadLevel::levels() {
+            assert_eq!(level.as_str().parse(), Ok(level));
+        }
+        for level in CreateLevel::levels() {
+            assert_eq!(level.as_str().parse(), Ok(level));
+        }
+        for level in UpdateLevel::levels() {
+            assert_eq!(level.as_str().parse(), Ok(level));
+        }
+        for level in DeleteLevel::levels() {
+            assert_eq!(level.as_str().parse(), Ok(level));
+        }
+    }
+
+    #[rstest]
+    #[case("*", Ok(PolicyAction::Any))]
+    #[case("read", Ok(PolicyAction::Read(ReadLevel::Read)))]
+    #[case("read_all", Ok(PolicyAction::Read(ReadLevel::ReadAll)))]
+    #[case("create_all", Ok(PolicyAction::Create(CreateLevel::CreateAll)))]
+    #[case("update", Ok(PolicyAction::Update(UpdateLevel::Update)))]
+    #[case("delete_all", Ok(PolicyAction::Delete(DeleteLevel::DeleteAll)))]
+    #[case("raed", Err(UnknownAction("raed".to_owned())))]
+    #[case("Read", Err(UnknownAction("Read".to_owned())))]
+    #[case("none", Err(UnknownAction("none".to_owned())))]
+    #[case("", Err(UnknownAction("".to_owned())))]
+    fn it_parses_the_actions_of_policies(
+        #[case] action: &str,
+        #[case] expected: Result<PolicyAction, UnknownAction>,
+    ) {
+        assert_eq!(action.parse::<PolicyAction>(), expected);
+        if let Ok(parsed) = expected {
+            assert_eq!(parsed.as_str(), action);
+        }
+    }
+
+    #[test]
+    fn it_fails_on_unknown_levels_rather_than_denying() {
+        assert_eq!(
+            "raed".parse::<ReadLevel>(),
+            Err(UnknownAction("raed".to_owned()))
+        );
+        assert!("create".parse::<ReadLevel>().is_err());
+    }
+}
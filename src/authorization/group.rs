@@ -0,0 +1,77 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A group a token belongs to, used as the subject of the casbin policies.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Group {
+    User,
+    UnregisteredUser,
+    Admin,
+    /// A group from the identity provider without a built in meaning
+    Custom(String),
+}
+
+impl Group {
+    /// The subject the group is referred to by in the policy file.
+    pub fn as_policy_subject(&self) -> &str {
+        match self {
+            Self::User => "user",
+            Self::UnregisteredUser => "unregistered_user",
+            Self::Admin => "admin",
+            Self::Custom(name) => name,
+        }
+    }
+
+    /// Strips any namespace from a claimed group, so `org:admin` becomes
+    /// [`Group::Admin`].
+    pub fn normalize(self) -> Self {
+        match self {
+            Self::Custom(name) if name.contains(':') => {
+                name.rsplit(':').next().unwrap_or_default().into()
+            }
+            group => group,
+        }
+    }
+}
+
+impl From<&str> for Group {
+    fn from(value: &str) -> Self {
+        match value {
+            "user" => Self::User,
+            "unregistered_user" => Self::UnregisteredUser,
+            "admin" => Self::Admin,
+            name => Self::Custom(name.to_owned()),
+        }
+    }
+}
+
+impl From<String> for Group {
+    fn from(value: String) -> Self {
+        value.as_str().into()
+    }
+}
+
+impl Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_policy_subject())
+    }
+}
+
+impl Serialize for Group {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_policy_subject())
+    }
+}
+
+impl<'de> Deserialize<'de> for Group {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(String::deserialize(deserializer)?.into())
+    }
+}
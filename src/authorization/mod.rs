@@ -1,23 +1,32 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
-use casbin::{CoreApi, Enforcer};
+use casbin::{CoreApi, Enforcer, MgmtApi};
 use thiserror::Error;
 use tracing::debug;
 
 use crate::{
     authentication::authenticated_token::AuthenticatedToken,
-    authorization::actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+    authorization::{
+        actions::{CreateLevel, DeleteLevel, ReadLevel, UpdateLevel},
+        group::Group,
+        summary::InvalidPolicies,
+    },
 };
 
 pub mod actions;
+pub mod group;
 pub mod policy;
 pub mod resources;
 pub mod roles;
+pub mod simulation;
+pub mod summary;
 
 #[derive(Debug, Error)]
 pub enum AuthorizationError {
     #[error("{0}")]
     Policy(#[from] casbin::Error),
+    #[error(transparent)]
+    InvalidPolicies(#[from] InvalidPolicies),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -45,10 +54,22 @@ impl PermissionSet {
         resource_name: &str,
         enforcer: &Arc<Enforcer>,
         token: &AuthenticatedToken,
-        config: PermissionConfig,
+        mut config: PermissionConfig,
     ) -> Result<Self, AuthorizationError> {
-        let groups = token.groups();
-        debug!("User Groups: {groups:?}");
+        if let Some(scope) = token.api_key_scope() {
+            // A key acts on its owner's own resources only.
+            config.min_read_level = config.min_read_level.max(ReadLevel::Read);
+            config.min_create_level = config.min_create_level.max(CreateLevel::Create);
+            config.min_update_level = config.min_update_level.max(UpdateLevel::Update);
+            config.min_delete_level = config.min_delete_level.max(DeleteLevel::Delete);
+            if scope.read_only {
+                config.min_create_level = CreateLevel::NoPermission;
+                config.min_update_level = UpdateLevel::NoPermission;
+                config.min_delete_level = DeleteLevel::NoPermission;
+            }
+        }
+        debug!("User Groups: {:?}", token.groups());
+        let groups = policy_groups(enforcer, token.groups());
         let mut read_level = ReadLevel::default();
         let mut create_level = CreateLevel::default();
         let mut update_level = UpdateLevel::default();
@@ -58,8 +79,8 @@ impl PermissionSet {
             .into_iter()
             .filter(|&x| config.min_read_level <= x)
         {
-            let level_str: &str = level.into();
-            for group in groups.iter() {
+            let level_str = level.as_str();
+            for &group in groups.iter() {
                 if enforcer.enforce((group, resource_name, level_str))? {
                     read_level = level;
                     break 'outer;
@@ -70,8 +91,8 @@ impl PermissionSet {
             .into_iter()
             .filter(|&x| config.min_create_level <= x)
         {
-            let level_str: &str = level.into();
-            for group in groups.iter() {
+            let level_str = level.as_str();
+            for &group in groups.iter() {
                 if enforcer.enforce((group, resource_name, level_str))? {
                     create_level = level;
                     break 'outer;
@@ -83,8 +104,8 @@ impl PermissionSet {
             .into_iter()
             .filter(|&x| config.min_update_level <= x)
         {
-            let level_str: &str = level.into();
-            for group in groups.iter() {
+            let level_str = level.as_str();
+            for &group in groups.iter() {
                 if enforcer.enforce((group, resource_name, level_str))? {
                     update_level = level;
                     break 'outer;
@@ -96,8 +117,8 @@ impl PermissionSet {
             .into_iter()
             .filter(|&x| config.min_delete_level <= x)
         {
-            let level_str: &str = level.into();
-            for group in groups.iter() {
+            let level_str = level.as_str();
+            for &group in groups.iter() {
                 if enforcer.enforce((group, resource_name, level_str))? {
                     delete_level = level;
                     break 'outer;
@@ -113,3 +134,27 @@ impl PermissionSet {
         })
     }
 }
+
+/// The distinct subjects of the groups which the policies can grant
+/// anything to, in the order they were claimed. A group that is neither
+/// the subject of a policy nor a member of a role never matches, so
+/// tokens with many unrelated groups are only checked against the few
+/// that count.
+fn policy_groups<'a>(enforcer: &Enforcer, groups: &'a [Group]) -> Vec<&'a str> {
+    let subjects = enforcer
+        .get_all_subjects()
+        .into_iter()
+        .chain(
+            enforcer
+                .get_grouping_policy()
+                .into_iter()
+                .filter_map(|x| x.into_iter().next()),
+        )
+        .collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    groups
+        .iter()
+        .map(Group::as_policy_subject)
+        .filter(|x| subjects.contains(*x) && seen.insert(*x))
+        .collect()
+}
@@ -3,3 +3,16 @@ pub struct Institution;
 pub struct Account;
 pub struct Asset;
 pub struct Transaction;
+pub struct Budget;
+pub struct AlertRule;
+pub struct CategorizationRule;
+pub struct ImportProfile;
+pub struct QuickEntry;
+pub struct WatchlistEntry;
+pub struct ExportSchedule;
+pub struct Passkey;
+pub struct ApiKey;
+pub struct Announcement;
+pub struct UserSession;
+pub struct Proposal;
+pub struct SyncChange;
@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+
+use casbin::{CoreApi, DefaultModel, Enforcer, MemoryAdapter, MgmtApi};
+
+use crate::authorization::{AuthorizationError, actions::PolicyAction};
+
+/// The largest proposal read, in bytes.
+pub const MAX_PROPOSAL_BYTES: usize = 64 * 1024;
+
+/// The most rules a proposal can have.
+pub const MAX_PROPOSAL_RULES: usize = 1000;
+
+/// A line of a policy file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PolicyRule {
+    /// `p, subject, object, action`
+    Policy(Vec<String>),
+    /// `g, member, role`
+    Grouping(Vec<String>),
+}
+
+/// Reads rules in the CSV format of the policy file, skipping blank lines
+/// and `#` comments. Repeated rules are read once, and policies with an
+/// action that is not a known level are refused.
+pub fn parse_policies(policies: &str) -> Result<Vec<PolicyRule>, String> {
+    if policies.len() > MAX_PROPOSAL_BYTES {
+        return Err(format!(
+            "The policies can be at most {MAX_PROPOSAL_BYTES} bytes."
+        ));
+    }
+    let mut rules = Vec::<PolicyRule>::new();
+    let mut seen = HashSet::new();
+    for (i, line) in policies.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(',').map(|x| x.trim().to_owned());
+        let kind = fields.next().unwrap_or_default();
+        let fields = fields.collect::<Vec<_>>();
+        if fields.iter().any(String::is_empty) {
+            return Err(format!(
+                "Line {} of the policies has an empty field.",
+                i + 1
+            ));
+        }
+        let rule = match (kind.as_str(), fields.len()) {
+            ("p", 3) if fields[2].parse::<PolicyAction>().is_err() => {
+                return Err(format!(
+                    "Line {} of the policies has an unknown action `{}`.",
+                    i + 1,
+                    fields[2]
+                ));
+            }
+            ("p", 3) => PolicyRule::Policy(fields),
+            ("g", 2) => PolicyRule::Grouping(fields),
+            _ => {
+                return Err(format!(
+                    "Line {} of the policies is neither `p, subject, object, action` nor `g, member, role`.",
+                    i + 1
+                ));
+            }
+        };
+        if !seen.insert(rule.clone()) {
+            continue;
+        }
+        if rules.len() == MAX_PROPOSAL_RULES {
+            return Err(format!(
+                "The policies can have at most {MAX_PROPOSAL_RULES} rules."
+            ));
+        }
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+/// An enforcer of its own under the model at `model_path`, holding only
+/// `rules`. Nothing it is given is saved.
+pub async fn scratch_enforcer(
+    model_path: &str,
+    rules: Vec<PolicyRule>,
+) -> Result<Enforcer, AuthorizationError> {
+    let model = DefaultModel::from_file(model_path).await?;
+    let mut enforcer = Enforcer::new(model, MemoryAdapter::default()).await?;
+    let (policies, groupings): (Vec<_>, Vec<_>) = rules
+        .into_iter()
+        .partition(|x| matches!(x, PolicyRule::Policy(_)));
+    let fields = |rules: Vec<PolicyRule>| {
+        rules
+            .into_iter()
+            .map(|x| match x {
+                PolicyRule::Policy(fields) | PolicyRule::Grouping(fields) => fields,
+            })
+            .collect::<Vec<_>>()
+    };
+    if !policies.is_empty() {
+        enforcer.add_policies(fields(policies)).await?;
+    }
+    if !groupings.is_empty() {
+        enforcer.add_grouping_policies(fields(groupings)).await?;
+    }
+    Ok(enforcer)
+}
@@ -0,0 +1,242 @@
+//! What the policies of an enforcer grant, for people to read.
+//!
+//! Casbin compares actions as plain strings, so a policy with a typo'd
+//! action such as `raed` loads fine and quietly grants nothing.
+//! [`validate_policies`] refuses such policies when the server starts, and
+//! [`PolicySummary`] lists them apart from the grants it describes.
+
+use std::collections::BTreeMap;
+
+use casbin::{Enforcer, MgmtApi};
+use thiserror::Error;
+
+use crate::authorization::actions::{PolicyAction, UnknownAction};
+
+/// A policy whose action is none of the known ones.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("`p, {subject}, {resource}, {action}`: {error}")]
+pub struct InvalidPolicy {
+    pub subject: String,
+    pub resource: String,
+    pub action: String,
+    pub error: UnknownAction,
+}
+
+/// The policies with unknown actions, which are listed when the enforcer
+/// is refused.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("The policies have unknown actions: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+pub struct InvalidPolicies(pub Vec<InvalidPolicy>);
+
+/// What a subject may do to a resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyGrant {
+    pub subject: String,
+    /// The resource, or `*` for every one
+    pub resource: String,
+    /// The actions granted, strongest of each first
+    pub actions: Vec<PolicyAction>,
+}
+
+impl PolicyGrant {
+    /// The grant in a sentence, like "`user` may read their own, create
+    /// their own on `accounts`".
+    pub fn describe(&self) -> String {
+        let actions = self
+            .actions
+            .iter()
+            .map(|x| x.describe())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let resource = match self.resource.as_str() {
+            "*" => "every resource".to_owned(),
+            resource => format!("`{resource}`"),
+        };
+        format!("`{}` may {actions} on {resource}", self.subject)
+    }
+}
+
+/// The policies of an enforcer, by subject and resource.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicySummary {
+    /// The grants, by subject then resource
+    pub grants: Vec<PolicyGrant>,
+    /// The members of each role, by role
+    pub roles: BTreeMap<String, Vec<String>>,
+    /// The policies that grant nothing, because their action is unknown
+    pub invalid: Vec<InvalidPolicy>,
+}
+
+impl PolicySummary {
+    pub fn of(enforcer: &Enforcer) -> Self {
+        let mut grants = BTreeMap::<(String, String), Vec<PolicyAction>>::new();
+        let mut invalid = vec![];
+        for rule in enforcer.get_policy() {
+            let [subject, resource, action] = <[String; 3]>::try_from(rule).unwrap_or_default();
+            match action.parse::<PolicyAction>() {
+                Ok(parsed) => grants.entry((subject, resource)).or_default().push(parsed),
+                Err(error) => invalid.push(InvalidPolicy {
+                    subject,
+                    resource,
+                    action,
+                    error,
+                }),
+            }
+        }
+        let mut roles = BTreeMap::<String, Vec<String>>::new();
+        for rule in enforcer.get_grouping_policy() {
+            let [member, role] = <[String; 2]>::try_from(rule).unwrap_or_default();
+            roles.entry(role).or_default().push(member);
+        }
+        for members in roles.values_mut() {
+            members.sort();
+            members.dedup();
+        }
+        Self {
+            grants: grants
+                .into_iter()
+                .map(|((subject, resource), mut actions)| {
+                    actions.sort();
+                    actions.dedup();
+                    PolicyGrant {
+                        subject,
+                        resource,
+                        actions,
+                    }
+                })
+                .collect(),
+            roles,
+            invalid,
+        }
+    }
+}
+
+/// Refuses the policies of `enforcer` if any has an action that is not
+/// one of the known levels, and would never match.
+pub fn validate_policies(enforcer: &Enforcer) -> Result<(), InvalidPolicies> {
+    let invalid = PolicySummary::of(enforcer).invalid;
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(InvalidPolicies(invalid))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use casbin::{CoreApi, DefaultModel, MemoryAdapter};
+
+    use super::*;
+    use crate::authorization::actions::{CreateLevel, ReadLevel};
+
+    const MODEL: &str = r#"
+[request_definition]
+r = sub, obj, act
+
+[policy_definition]
+p = sub, obj, act
+
+[role_definition]
+g = _, _
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = g(r.sub, p.sub) && (r.obj == p.obj || p.obj == "*") && (r.act == p.act || p.act == "*")
+"#;
+
+    async fn enforcer(policies: &[[&str; 3]], groupings: &[[&str; 2]]) -> Enforcer {
+        let model = DefaultModel::from_str(MODEL).await.unwrap();
+        let mut enforcer = Enforcer::new(model, MemoryAdapter::default())
+            .await
+            .unwrap();
+        for policy in policies {
+            enforcer
+                .add_policy(policy.iter().map(|x| x.to_string()).collect())
+                .await
+                .unwrap();
+        }
+        for grouping in groupings {
+            enforcer
+                .add_grouping_policy(grouping.iter().map(|x| x.to_string()).collect())
+                .await
+                .unwrap();
+        }
+        enforcer
+    }
+
+    #[tokio::test]
+    async fn it_summarizes_the_grants_of_each_subject() {
+        let enforcer = enforcer(
+            &[
+                ["user", "accounts", "create"],
+                ["user", "accounts", "read_all"],
+                ["user", "*", "read"],
+                ["admin", "*", "*"],
+            ],
+            &[["alice", "admin"], ["bob", "admin"]],
+        )
+        .await;
+        let summary = PolicySummary::of(&enforcer);
+        assert_eq!(summary.invalid, vec![]);
+        assert_eq!(
+            summary.grants,
+            vec![
+                PolicyGrant {
+                    subject: "admin".to_owned(),
+                    resource: "*".to_owned(),
+                    actions: vec![PolicyAction::Any],
+                },
+                PolicyGrant {
+                    subject: "user".to_owned(),
+                    resource: "*".to_owned(),
+                    actions: vec![PolicyAction::Read(ReadLevel::Read)],
+                },
+                PolicyGrant {
+                    subject: "user".to_owned(),
+                    resource: "accounts".to_owned(),
+                    actions: vec![
+                        PolicyAction::Read(ReadLevel::ReadAll),
+                        PolicyAction::Create(CreateLevel::Create),
+                    ],
+                },
+            ]
+        );
+        assert_eq!(
+            summary.grants[2].describe(),
+            "`user` may read everyone's, create their own on `accounts`"
+        );
+        assert_eq!(
+            summary.grants[0].describe(),
+            "`admin` may do anything on every resource"
+        );
+        assert_eq!(
+            summary.roles["admin"],
+            vec!["alice".to_owned(), "bob".to_owned()]
+        );
+        assert_eq!(validate_policies(&enforcer), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn it_flags_policies_with_unknown_actions() {
+        let enforcer = enforcer(
+            &[["user", "accounts", "raed"], ["user", "accounts", "create"]],
+            &[],
+        )
+        .await;
+        let invalid = InvalidPolicy {
+            subject: "user".to_owned(),
+            resource: "accounts".to_owned(),
+            action: "raed".to_owned(),
+            error: UnknownAction("raed".to_owned()),
+        };
+        assert_eq!(PolicySummary::of(&enforcer).invalid, vec![invalid.clone()]);
+        let error = validate_policies(&enforcer).unwrap_err();
+        assert_eq!(error, InvalidPolicies(vec![invalid]));
+        assert_eq!(
+            error.to_string(),
+            "The policies have unknown actions: `p, user, accounts, raed`: Unknown action `raed`."
+        );
+    }
+}
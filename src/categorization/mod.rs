@@ -0,0 +1,522 @@
+//! Categorizes transactions by the rules of their user.
+//!
+//! A [`CategorizationRule`] matches transactions on their description, their
+//! quantity or their account, and sets a category. A [`Categorizer`] holds
+//! the rules of a user in the order they are tried, and the first one that
+//! matches a transaction wins. New transactions without a category are
+//! categorized as they are created, while a [`CategorizationJob`] runs the
+//! rules over the transactions a user has left uncategorized. A
+//! [`RecategorizationJob`] sets one category on every transaction of a user
+//! a filter matches instead.
+//!
+//! Description patterns are regular expressions. The `regex` crate matches
+//! in time linear in the description, so patterns are bounded in length and
+//! compiled size rather than in how they are written.
+
+use std::sync::Arc;
+
+use regex::{Regex, RegexBuilder};
+use rust_decimal::Decimal;
+use sqlx::{Acquire, PgPool};
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    api::{ApiError, ClientErrorCode},
+    model::{
+        account::AccountId,
+        categorization_rule::{
+            CategorizationRule, CategorizationRuleField, CategorizationRuleFilter,
+        },
+        recategorization::Recategorization,
+        transaction::TransactionId,
+        user::UserId,
+    },
+    resource::{
+        GetListRepository, MAX_LIMIT, categorization_rule_repository::CategorizationRuleRepository,
+        deadline, recategorization_repository::RecategorizationRepository,
+        transaction_repository::TransactionRepository,
+    },
+    schema::{text::CATEGORY, transaction::GetListRequest},
+    service::ServiceError,
+};
+
+/// The most rules a user may have, so that every rule is tried.
+pub const MAX_RULES: usize = MAX_LIMIT as usize;
+/// The longest description pattern, in bytes.
+pub const MAX_PATTERN_LEN: usize = 256;
+/// The most memory a compiled pattern may take.
+const PATTERN_SIZE_LIMIT: usize = 1 << 16;
+/// How deeply groups and repetitions may nest in a pattern.
+const PATTERN_NEST_LIMIT: u32 = 16;
+
+/// How many uncategorized transactions a job loads at a time.
+const BATCH_SIZE: i64 = 500;
+
+/// How many transactions a recategorization changes at a time. A filter
+/// matching more is recategorized after responding.
+pub const RECATEGORIZE_BATCH_SIZE: i64 = 1_000;
+
+#[derive(Debug, Error)]
+pub enum CategorizationError {
+    #[error("The pattern must be at most {MAX_PATTERN_LEN} bytes.")]
+    PatternTooLong,
+    #[error("The pattern is invalid: {0}")]
+    InvalidPattern(String),
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<CategorizationError> for ApiError {
+    fn from(value: CategorizationError) -> Self {
+        match value {
+            CategorizationError::Service(e) => Self::Service(e),
+            e => Self::client(ClientErrorCode::InvalidRequest, e.to_string()),
+        }
+    }
+}
+
+/// Trims a category and checks it is not empty or too long.
+pub fn sanitize_category(category: &str) -> Result<String, ApiError> {
+    let category = CATEGORY.sanitize(category.trim())?;
+    if category.is_empty() {
+        return Err(ApiError::client(
+            ClientErrorCode::InvalidRequest,
+            "The category must not be empty.",
+        ));
+    }
+    Ok(category)
+}
+
+/// Compiles a description pattern, which matches case insensitively anywhere
+/// in the description.
+pub fn compile_pattern(pattern: &str) -> Result<Regex, CategorizationError> {
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(CategorizationError::PatternTooLong);
+    }
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .dfa_size_limit(PATTERN_SIZE_LIMIT)
+        .nest_limit(PATTERN_NEST_LIMIT)
+        .build()
+        .map_err(|e| CategorizationError::InvalidPattern(e.to_string()))
+}
+
+/// The rules of a user, ready to match transactions.
+#[derive(Debug, Clone)]
+pub struct Categorizer {
+    rules: Vec<(CategorizationRule, Option<Regex>)>,
+}
+
+impl Categorizer {
+    /// Takes the rules in the order they are tried. A stored pattern that no
+    /// longer compiles leaves its rule out rather than failing every
+    /// transaction.
+    pub fn new(rules: Vec<CategorizationRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .filter_map(|rule| match (rule.field, rule.pattern.as_deref()) {
+                (CategorizationRuleField::Description, Some(pattern)) => {
+                    match compile_pattern(pattern) {
+                        Ok(regex) => Some((rule, Some(regex))),
+                        Err(e) => {
+                            warn!("Skipping categorization rule {}: {e}", rule.id);
+                            None
+                        }
+                    }
+                }
+                (CategorizationRuleField::Description, None) => None,
+                _ => Some((rule, None)),
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The first rule that matches a transaction.
+    pub fn categorize(
+        &self,
+        account_id: AccountId,
+        description: Option<&str>,
+        quantity: Decimal,
+    ) -> Option<&CategorizationRule> {
+        self.rules
+            .iter()
+            .find(|(rule, regex)| match rule.field {
+                CategorizationRuleField::Description => regex
+                    .as_ref()
+                    .zip(description)
+                    .is_some_and(|(regex, description)| regex.is_match(description)),
+                CategorizationRuleField::QuantityRange => {
+                    rule.min_quantity.is_none_or(|min| quantity >= min)
+                        && rule.max_quantity.is_none_or(|max| quantity <= max)
+                }
+                CategorizationRuleField::Account => rule.account_id == Some(account_id),
+            })
+            .map(|(rule, _)| rule)
+    }
+}
+
+/// Runs the rules of a user over their transactions without a category.
+///
+/// There is no job queue in the tree yet, so the job is run by the request
+/// that starts it.
+#[derive(Debug, Clone)]
+pub struct CategorizationJob {
+    pub user_id: UserId,
+}
+
+impl CategorizationJob {
+    /// Categorizes what the rules match and returns how many transactions
+    /// changed.
+    #[instrument(name = "CategorizationJob::run", skip_all, fields(user_id = ?self.user_id))]
+    pub async fn run(&self, connection_pool: &PgPool) -> Result<u64, CategorizationError> {
+        let rules = CategorizationRuleRepository
+            .get_list(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                0,
+                None,
+                CategorizationRuleFilter {
+                    user_id: Some(self.user_id),
+                },
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        let categorizer = Categorizer::new(rules);
+        if categorizer.is_empty() {
+            return Ok(0);
+        }
+
+        let mut changed = 0;
+        let mut after = TransactionId(0);
+        loop {
+            let transactions = TransactionRepository
+                .get_uncategorized(
+                    deadline::begin(&connection_pool)
+                        .await
+                        .map_err(ServiceError::from)?,
+                    self.user_id,
+                    after,
+                    BATCH_SIZE,
+                )
+                .await
+                .map_err(ServiceError::from)?;
+            let Some(last) = transactions.last() else {
+                break;
+            };
+            after = last.id;
+            let categories = transactions
+                .iter()
+                .filter_map(|transaction| {
+                    categorizer
+                        .categorize(
+                            transaction.account_id,
+                            transaction.description.as_deref(),
+                            transaction.quantity,
+                        )
+                        .map(|rule| (transaction.id, rule.category.clone(), rule.id))
+                })
+                .collect::<Vec<_>>();
+            if !categories.is_empty() {
+                changed += TransactionRepository
+                    .categorize(
+                        deadline::begin(&connection_pool)
+                            .await
+                            .map_err(ServiceError::from)?,
+                        categories,
+                    )
+                    .await
+                    .map_err(ServiceError::from)?;
+            }
+        }
+        info!("Categorized {changed} transactions");
+        Ok(changed)
+    }
+}
+
+/// Counts the transactions of `user_id` the filter matches that a
+/// recategorization would change, by changing them batch by batch in a
+/// database transaction that is rolled back.
+#[instrument(skip_all)]
+pub async fn dry_run_recategorization(
+    connection_pool: &PgPool,
+    user_id: UserId,
+    account_ids: Option<Vec<AccountId>>,
+    filter: GetListRequest,
+    category: String,
+) -> Result<i64, ServiceError> {
+    let mut trans = deadline::begin(&connection_pool).await?;
+    let mut changed = 0;
+    loop {
+        let batch = TransactionRepository
+            .recategorize_with_user_id(
+                trans.begin().await?,
+                user_id,
+                account_ids.clone(),
+                filter.clone().into(),
+                category.clone(),
+                RECATEGORIZE_BATCH_SIZE,
+            )
+            .await?;
+        changed += batch as i64;
+        if batch < RECATEGORIZE_BATCH_SIZE as u64 {
+            break;
+        }
+    }
+    trans.rollback().await?;
+    Ok(changed)
+}
+
+/// Sets the category of a [`Recategorization`] on every transaction of its
+/// user the filter matches, [`RECATEGORIZE_BATCH_SIZE`] at a time, and
+/// records how many changed after each batch.
+///
+/// Like a [`CategorizationJob`] it has no job queue to run in: a small
+/// match is recategorized by the request, a large one by a task spawned
+/// after responding.
+#[derive(Debug, Clone)]
+pub struct RecategorizationJob {
+    pub recategorization: Recategorization,
+    pub filter: GetListRequest,
+    /// The accounts a scoped API key limits the caller to
+    pub account_ids: Option<Vec<AccountId>>,
+}
+
+impl RecategorizationJob {
+    /// Recategorizes the transactions and records the outcome. A failed
+    /// batch leaves those before it changed, and is recorded on the
+    /// recategorization as well as returned.
+    #[instrument(
+        name = "RecategorizationJob::run",
+        skip_all,
+        fields(id = ?self.recategorization.id)
+    )]
+    pub async fn run(self, connection_pool: &PgPool) -> Result<Recategorization, ApiError> {
+        let id = self.recategorization.id;
+        let mut changed = 0;
+        let outcome = loop {
+            let batch = match self.batch(connection_pool).await {
+                Ok(batch) => batch,
+                Err(e) => break Err(e),
+            };
+            changed += batch as i64;
+            if batch < RECATEGORIZE_BATCH_SIZE as u64 {
+                break Ok(());
+            }
+            if let Err(e) = self.record_progress(connection_pool, changed).await {
+                break Err(e);
+            }
+        };
+        let recategorization = RecategorizationRepository
+            .finish(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                id,
+                changed,
+                outcome.as_ref().err().map(|e| e.to_string()),
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        outcome?;
+        info!("Recategorized {changed} transactions");
+        Ok(recategorization)
+    }
+
+    async fn batch(&self, connection_pool: &PgPool) -> Result<u64, ServiceError> {
+        let changed = TransactionRepository
+            .recategorize_with_user_id(
+                deadline::begin(&connection_pool).await?,
+                self.recategorization.user_id,
+                self.account_ids.clone(),
+                self.filter.clone().into(),
+                self.recategorization.category.clone(),
+                RECATEGORIZE_BATCH_SIZE,
+            )
+            .await?;
+        Ok(changed)
+    }
+
+    async fn record_progress(
+        &self,
+        connection_pool: &PgPool,
+        changed: i64,
+    ) -> Result<(), ServiceError> {
+        RecategorizationRepository
+            .record_progress(
+                deadline::begin(&connection_pool).await?,
+                self.recategorization.id,
+                changed,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Runs the job after the request that started it has been responded
+    /// to.
+    pub fn spawn(self, connection_pool: Arc<PgPool>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let id = self.recategorization.id;
+            if let Err(e) = self.run(&connection_pool).await {
+                error!("Recategorization {id} failed: {e}");
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::categorization_rule::CategorizationRuleId;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    const CHECKING: AccountId = AccountId(Uuid::from_u128(1));
+    const SAVINGS: AccountId = AccountId(Uuid::from_u128(2));
+
+    fn rule(field: CategorizationRuleField, category: &str) -> CategorizationRule {
+        CategorizationRule {
+            id: CategorizationRuleId(Uuid::new_v4()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            user_id: UserId(Uuid::new_v4()),
+            field,
+            pattern: None,
+            min_quantity: None,
+            max_quantity: None,
+            account_id: None,
+            category: category.into(),
+            priority: 0,
+        }
+    }
+
+    fn description(pattern: &str, category: &str) -> CategorizationRule {
+        CategorizationRule {
+            pattern: Some(pattern.into()),
+            ..rule(CategorizationRuleField::Description, category)
+        }
+    }
+
+    fn quantity_range(
+        min_quantity: Option<i64>,
+        max_quantity: Option<i64>,
+        category: &str,
+    ) -> CategorizationRule {
+        CategorizationRule {
+            min_quantity: min_quantity.map(Decimal::from),
+            max_quantity: max_quantity.map(Decimal::from),
+            ..rule(CategorizationRuleField::QuantityRange, category)
+        }
+    }
+
+    fn category<'a>(
+        categorizer: &'a Categorizer,
+        account_id: AccountId,
+        description: Option<&str>,
+        quantity: i64,
+    ) -> Option<&'a str> {
+        categorizer
+            .categorize(account_id, description, quantity.into())
+            .map(|rule| rule.category.as_str())
+    }
+
+    #[test]
+    fn it_uses_the_first_rule_that_matches() {
+        let categorizer = Categorizer::new(vec![
+            description("coffee", "Coffee"),
+            description("cafe|coffee", "Eating out"),
+            quantity_range(None, Some(0), "Spending"),
+        ]);
+
+        assert_eq!(
+            category(&categorizer, CHECKING, Some("Coffee Bean"), -5),
+            Some("Coffee")
+        );
+        assert_eq!(
+            category(&categorizer, CHECKING, Some("Blue Bottle Cafe"), -5),
+            Some("Eating out")
+        );
+        assert_eq!(
+            category(&categorizer, CHECKING, Some("Rent"), -500),
+            Some("Spending")
+        );
+        assert_eq!(category(&categorizer, CHECKING, None, 500), None);
+    }
+
+    #[test]
+    fn it_matches_descriptions_anywhere_ignoring_case() {
+        let categorizer = Categorizer::new(vec![description("^uber", "Transport")]);
+
+        assert_eq!(
+            category(&categorizer, CHECKING, Some("UBER TRIP"), -10),
+            Some("Transport")
+        );
+        assert_eq!(
+            category(&categorizer, CHECKING, Some("Payment to Uber"), -10),
+            None
+        );
+        assert_eq!(category(&categorizer, CHECKING, None, -10), None);
+    }
+
+    #[test]
+    fn it_matches_quantity_bounds_inclusively() {
+        let categorizer = Categorizer::new(vec![quantity_range(Some(-100), Some(-10), "Small")]);
+
+        assert_eq!(category(&categorizer, CHECKING, None, -100), Some("Small"));
+        assert_eq!(category(&categorizer, CHECKING, None, -10), Some("Small"));
+        assert_eq!(category(&categorizer, CHECKING, None, -101), None);
+        assert_eq!(category(&categorizer, CHECKING, None, -9), None);
+    }
+
+    #[test]
+    fn it_matches_accounts() {
+        let categorizer = Categorizer::new(vec![CategorizationRule {
+            account_id: Some(SAVINGS),
+            ..rule(CategorizationRuleField::Account, "Savings")
+        }]);
+
+        assert_eq!(category(&categorizer, SAVINGS, None, 1), Some("Savings"));
+        assert_eq!(category(&categorizer, CHECKING, None, 1), None);
+    }
+
+    #[test]
+    fn it_bounds_patterns() {
+        assert!(compile_pattern("coffee|cafe").is_ok());
+        assert!(matches!(
+            compile_pattern(&"a".repeat(MAX_PATTERN_LEN + 1)),
+            Err(CategorizationError::PatternTooLong)
+        ));
+        assert!(matches!(
+            compile_pattern("(unclosed"),
+            Err(CategorizationError::InvalidPattern(_))
+        ));
+        assert!(matches!(
+            compile_pattern(&format!("{}a{}", "(".repeat(20), ")".repeat(20))),
+            Err(CategorizationError::InvalidPattern(_))
+        ));
+        assert!(matches!(
+            compile_pattern(r"\w{1000}\w{1000}"),
+            Err(CategorizationError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn it_skips_rules_whose_patterns_do_not_compile() {
+        let categorizer = Categorizer::new(vec![
+            description("(unclosed", "Broken"),
+            description("rent", "Housing"),
+        ]);
+
+        assert_eq!(
+            category(&categorizer, CHECKING, Some("Rent"), -500),
+            Some("Housing")
+        );
+    }
+}
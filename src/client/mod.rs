@@ -0,0 +1,639 @@
+//! A typed client for the REST API, for Rust services that call Treasury.
+//!
+//! Enable the `client` feature to build it without the server or the app.
+
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::{Serialize, de::DeserializeOwned};
+use thiserror::Error;
+
+pub use crate::{api::ApiErrorResponse, model, schema};
+use crate::{
+    model::{
+        account::AccountId, api_key::ApiKeyId, asset::AssetId, budget::BudgetId,
+        categorization_rule::CategorizationRuleId, import_profile::ImportProfileId,
+        institution::InstitutionId, transaction::TransactionId, user::UserId,
+        user_session::UserSessionId,
+    },
+    schema::{
+        account, api_key, asset, budget, categorization_rule, import_profile, institution,
+        transaction, user, user_session,
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("{0}")]
+    Http(#[from] reqwest::Error),
+    #[error("{status}: {}", .response.message)]
+    Api {
+        status: StatusCode,
+        response: ApiErrorResponse,
+    },
+}
+
+/// The page to request from a list endpoint.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Page {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+/// A list response that pages with `next_cursor`.
+pub trait Paged: DeserializeOwned {
+    type Item;
+
+    fn into_page(self) -> (Vec<Self::Item>, Option<String>);
+}
+
+macro_rules! paged {
+    ($response:ty, $field:ident, $item:ty) => {
+        impl Paged for $response {
+            type Item = $item;
+
+            fn into_page(self) -> (Vec<Self::Item>, Option<String>) {
+                (self.$field, self.next_cursor)
+            }
+        }
+    };
+}
+
+paged!(
+    account::GetListResponse,
+    accounts,
+    account::AccountResponse<schema::GetList>
+);
+paged!(
+    asset::GetListResponse,
+    assets,
+    asset::AssetResponse<schema::GetList>
+);
+paged!(
+    institution::GetListResponse,
+    institutions,
+    institution::InstitutionResponse<schema::GetList>
+);
+paged!(
+    transaction::GetListResponse,
+    transactions,
+    transaction::TransactionResponse<schema::GetList>
+);
+paged!(
+    user::GetListResponse,
+    users,
+    user::UserResponse<schema::GetList>
+);
+
+/// Calls the API with a bearer token, which may be an access token or an
+/// API key.
+#[derive(Debug, Clone)]
+pub struct TreasuryClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl TreasuryClient {
+    pub fn new(base_url: impl Into<String>, token: impl Into<String>) -> Self {
+        Self::with_http_client(reqwest::Client::new(), base_url, token)
+    }
+
+    pub fn with_http_client(
+        http: reqwest::Client,
+        base_url: impl Into<String>,
+        token: impl Into<String>,
+    ) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            token: token.into(),
+        }
+    }
+
+    fn builder(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{path}", self.base_url))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/json")
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ClientError> {
+        let response = request.send().await?;
+        let status = response.status();
+        let bytes = response.bytes().await?;
+        if !status.is_success() {
+            let response =
+                serde_json::from_slice::<ApiErrorResponse>(&bytes).unwrap_or(ApiErrorResponse {
+                    code: status.as_u16() as usize * 10,
+                    message: String::from_utf8_lossy(&bytes).into_owned(),
+                });
+            return Err(ClientError::Api { status, response });
+        }
+        // Deletes answer with an empty body.
+        let bytes: &[u8] = if bytes.is_empty() { b"null" } else { &bytes };
+        serde_json::from_slice(bytes).map_err(|e| ClientError::Api {
+            status,
+            response: ApiErrorResponse {
+                code: 5000,
+                message: e.to_string(),
+            },
+        })
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &impl Serialize,
+    ) -> Result<T, ClientError> {
+        self.send(self.builder(Method::GET, path).query(query))
+            .await
+    }
+
+    async fn json<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<T, ClientError> {
+        self.send(self.builder(method, path).json(body)).await
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), ClientError> {
+        self.send::<serde_json::Value>(self.builder(Method::DELETE, path))
+            .await
+            .map(|_| ())
+    }
+
+    async fn list<R: Paged>(
+        &self,
+        path: &str,
+        query: &impl Serialize,
+        page: &Page,
+    ) -> Result<R, ClientError> {
+        self.send(self.builder(Method::GET, path).query(query).query(page))
+            .await
+    }
+
+    /// Follows `next_cursor` from the first page until a page comes back
+    /// empty, keeping the page size of `max_items`.
+    async fn list_all<R: Paged>(
+        &self,
+        path: &str,
+        query: &impl Serialize,
+        max_items: Option<i64>,
+    ) -> Result<Vec<R::Item>, ClientError> {
+        let mut items = vec![];
+        let mut page = Page {
+            max_items,
+            cursor: None,
+        };
+        loop {
+            let (page_items, next_cursor) = self.list::<R>(path, query, &page).await?.into_page();
+            if page_items.is_empty() {
+                return Ok(items);
+            }
+            items.extend(page_items);
+            let Some(next_cursor) = next_cursor else {
+                return Ok(items);
+            };
+            // The cursor carries the page size.
+            page = Page {
+                max_items: None,
+                cursor: Some(next_cursor),
+            };
+        }
+    }
+
+    pub async fn list_accounts(
+        &self,
+        request: &account::GetListRequest,
+        page: &Page,
+    ) -> Result<account::AccountGetListResponse, ClientError> {
+        self.list("/api/accounts", request, page).await
+    }
+
+    pub async fn list_all_accounts(
+        &self,
+        request: &account::GetListRequest,
+        max_items: Option<i64>,
+    ) -> Result<Vec<account::AccountResponse<schema::GetList>>, ClientError> {
+        self.list_all::<account::GetListResponse>("/api/accounts", request, max_items)
+            .await
+    }
+
+    pub async fn get_account(
+        &self,
+        id: AccountId,
+    ) -> Result<account::AccountGetResponse, ClientError> {
+        self.get(&format!("/api/accounts/{id}"), &()).await
+    }
+
+    pub async fn create_account(
+        &self,
+        request: &account::CreateRequest,
+    ) -> Result<account::AccountCreateResponse, ClientError> {
+        self.json(Method::POST, "/api/accounts", request).await
+    }
+
+    pub async fn update_account(
+        &self,
+        id: AccountId,
+        request: &account::UpdateRequest,
+    ) -> Result<account::AccountUpdateResponse, ClientError> {
+        self.json(Method::PATCH, &format!("/api/accounts/{id}"), request)
+            .await
+    }
+
+    pub async fn preview_account_deletion(
+        &self,
+        id: AccountId,
+    ) -> Result<account::DeletionPreviewResponse, ClientError> {
+        self.get(&format!("/api/accounts/{id}/deletion-preview"), &())
+            .await
+    }
+
+    /// Deletes the account along with what
+    /// [`preview_account_deletion`](Self::preview_account_deletion) counts.
+    pub async fn delete_account(&self, id: AccountId) -> Result<(), ClientError> {
+        self.send::<serde_json::Value>(
+            self.builder(Method::DELETE, &format!("/api/accounts/{id}"))
+                .query(&account::DeleteRequest { confirm: true }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn sync_account(&self, id: AccountId) -> Result<account::SyncResponse, ClientError> {
+        self.json(
+            Method::POST,
+            &format!("/api/accounts/{id}/sync"),
+            &serde_json::json!({}),
+        )
+        .await
+    }
+
+    pub async fn list_assets(
+        &self,
+        request: &asset::GetListRequest,
+        page: &Page,
+    ) -> Result<asset::AssetGetListResponse, ClientError> {
+        self.list("/api/assets", request, page).await
+    }
+
+    pub async fn list_all_assets(
+        &self,
+        request: &asset::GetListRequest,
+        max_items: Option<i64>,
+    ) -> Result<Vec<asset::AssetResponse<schema::GetList>>, ClientError> {
+        self.list_all::<asset::GetListResponse>("/api/assets", request, max_items)
+            .await
+    }
+
+    pub async fn get_asset(&self, id: AssetId) -> Result<asset::AssetGetResponse, ClientError> {
+        self.get(&format!("/api/assets/{id}"), &()).await
+    }
+
+    pub async fn create_asset(
+        &self,
+        request: &asset::CreateRequest,
+    ) -> Result<asset::AssetCreateResponse, ClientError> {
+        self.json(Method::POST, "/api/assets", request).await
+    }
+
+    pub async fn update_asset(
+        &self,
+        id: AssetId,
+        request: &asset::UpdateRequest,
+    ) -> Result<asset::AssetUpdateResponse, ClientError> {
+        self.json(Method::PATCH, &format!("/api/assets/{id}"), request)
+            .await
+    }
+
+    pub async fn delete_asset(&self, id: AssetId) -> Result<(), ClientError> {
+        self.delete(&format!("/api/assets/{id}")).await
+    }
+
+    pub async fn list_budgets(&self) -> Result<budget::BudgetGetListResponse, ClientError> {
+        self.get("/api/budgets", &()).await
+    }
+
+    pub async fn get_budget(&self, id: BudgetId) -> Result<budget::BudgetGetResponse, ClientError> {
+        self.get(&format!("/api/budgets/{id}"), &()).await
+    }
+
+    pub async fn create_budget(
+        &self,
+        request: &budget::CreateRequest,
+    ) -> Result<budget::BudgetCreateResponse, ClientError> {
+        self.json(Method::POST, "/api/budgets", request).await
+    }
+
+    pub async fn delete_budget(&self, id: BudgetId) -> Result<(), ClientError> {
+        self.delete(&format!("/api/budgets/{id}")).await
+    }
+
+    pub async fn get_budget_status(
+        &self,
+        id: BudgetId,
+        request: &budget::StatusRequest,
+    ) -> Result<budget::BudgetStatusResponse, ClientError> {
+        self.get(&format!("/api/budgets/{id}/status"), request)
+            .await
+    }
+
+    pub async fn list_categorization_rules(
+        &self,
+    ) -> Result<categorization_rule::CategorizationRuleGetListResponse, ClientError> {
+        self.get("/api/categorization-rules", &()).await
+    }
+
+    pub async fn get_categorization_rule(
+        &self,
+        id: CategorizationRuleId,
+    ) -> Result<categorization_rule::CategorizationRuleGetResponse, ClientError> {
+        self.get(&format!("/api/categorization-rules/{id}"), &())
+            .await
+    }
+
+    pub async fn create_categorization_rule(
+        &self,
+        request: &categorization_rule::CreateRequest,
+    ) -> Result<categorization_rule::CategorizationRuleCreateResponse, ClientError> {
+        self.json(Method::POST, "/api/categorization-rules", request)
+            .await
+    }
+
+    pub async fn update_categorization_rule(
+        &self,
+        id: CategorizationRuleId,
+        request: &categorization_rule::UpdateRequest,
+    ) -> Result<categorization_rule::CategorizationRuleUpdateResponse, ClientError> {
+        self.json(
+            Method::PATCH,
+            &format!("/api/categorization-rules/{id}"),
+            request,
+        )
+        .await
+    }
+
+    pub async fn delete_categorization_rule(
+        &self,
+        id: CategorizationRuleId,
+    ) -> Result<(), ClientError> {
+        self.delete(&format!("/api/categorization-rules/{id}"))
+            .await
+    }
+
+    /// Runs the rules over the transactions the user has left uncategorized.
+    pub async fn apply_categorization_rules(
+        &self,
+    ) -> Result<categorization_rule::ApplyResponse, ClientError> {
+        self.json(
+            Method::POST,
+            "/api/categorization-rules/apply",
+            &serde_json::json!({}),
+        )
+        .await
+    }
+
+    pub async fn list_import_profiles(
+        &self,
+    ) -> Result<import_profile::ImportProfileGetListResponse, ClientError> {
+        self.get("/api/import-profiles", &()).await
+    }
+
+    pub async fn get_import_profile(
+        &self,
+        id: ImportProfileId,
+    ) -> Result<import_profile::ImportProfileGetResponse, ClientError> {
+        self.get(&format!("/api/import-profiles/{id}"), &()).await
+    }
+
+    pub async fn create_import_profile(
+        &self,
+        request: &import_profile::CreateRequest,
+    ) -> Result<import_profile::ImportProfileCreateResponse, ClientError> {
+        self.json(Method::POST, "/api/import-profiles", request)
+            .await
+    }
+
+    pub async fn update_import_profile(
+        &self,
+        id: ImportProfileId,
+        request: &import_profile::UpdateRequest,
+    ) -> Result<import_profile::ImportProfileUpdateResponse, ClientError> {
+        self.json(
+            Method::PATCH,
+            &format!("/api/import-profiles/{id}"),
+            request,
+        )
+        .await
+    }
+
+    pub async fn delete_import_profile(&self, id: ImportProfileId) -> Result<(), ClientError> {
+        self.delete(&format!("/api/import-profiles/{id}")).await
+    }
+
+    pub async fn list_institutions(
+        &self,
+        request: &institution::GetListRequest,
+        page: &Page,
+    ) -> Result<institution::InstitutionGetListResponse, ClientError> {
+        self.list("/api/institutions", request, page).await
+    }
+
+    pub async fn list_all_institutions(
+        &self,
+        request: &institution::GetListRequest,
+        max_items: Option<i64>,
+    ) -> Result<Vec<institution::InstitutionResponse<schema::GetList>>, ClientError> {
+        self.list_all::<institution::GetListResponse>("/api/institutions", request, max_items)
+            .await
+    }
+
+    pub async fn get_institution(
+        &self,
+        id: InstitutionId,
+        request: &institution::GetRequest,
+    ) -> Result<institution::InstitutionGetResponse, ClientError> {
+        self.get(&format!("/api/institutions/{id}"), request).await
+    }
+
+    pub async fn get_institution_rollup(
+        &self,
+        id: InstitutionId,
+    ) -> Result<institution::RollupResponse, ClientError> {
+        self.get(&format!("/api/institutions/{id}/rollup"), &())
+            .await
+    }
+
+    pub async fn create_institution(
+        &self,
+        request: &institution::CreateRequest,
+    ) -> Result<institution::InstitutionCreateResponse, ClientError> {
+        self.json(Method::POST, "/api/institutions", request).await
+    }
+
+    pub async fn update_institution(
+        &self,
+        id: InstitutionId,
+        request: &institution::UpdateRequest,
+    ) -> Result<institution::InstitutionUpdateResponse, ClientError> {
+        self.json(Method::PATCH, &format!("/api/institutions/{id}"), request)
+            .await
+    }
+
+    pub async fn delete_institution(&self, id: InstitutionId) -> Result<(), ClientError> {
+        self.delete(&format!("/api/institutions/{id}")).await
+    }
+
+    pub async fn list_transactions(
+        &self,
+        request: &transaction::GetListRequest,
+        page: &Page,
+    ) -> Result<transaction::TransactionGetListResponse, ClientError> {
+        self.list("/api/transactions", request, page).await
+    }
+
+    pub async fn list_all_transactions(
+        &self,
+        request: &transaction::GetListRequest,
+        max_items: Option<i64>,
+    ) -> Result<Vec<transaction::TransactionResponse<schema::GetList>>, ClientError> {
+        self.list_all::<transaction::GetListResponse>("/api/transactions", request, max_items)
+            .await
+    }
+
+    pub async fn get_transaction(
+        &self,
+        id: TransactionId,
+    ) -> Result<transaction::TransactionGetResponse, ClientError> {
+        self.get(&format!("/api/transactions/{}", id.0), &()).await
+    }
+
+    pub async fn create_transaction(
+        &self,
+        request: &transaction::CreateRequest,
+    ) -> Result<transaction::TransactionCreateResponse, ClientError> {
+        self.json(Method::POST, "/api/transactions", request).await
+    }
+
+    pub async fn update_transaction(
+        &self,
+        id: TransactionId,
+        request: &transaction::UpdateRequest,
+    ) -> Result<transaction::TransactionUpdateResponse, ClientError> {
+        self.json(
+            Method::PATCH,
+            &format!("/api/transactions/{}", id.0),
+            request,
+        )
+        .await
+    }
+
+    pub async fn delete_transaction(&self, id: TransactionId) -> Result<(), ClientError> {
+        self.delete(&format!("/api/transactions/{}", id.0)).await
+    }
+
+    pub async fn import_transactions(
+        &self,
+        request: &transaction::ImportRequest,
+    ) -> Result<transaction::ImportResponse, ClientError> {
+        self.json(Method::POST, "/api/transactions/import", request)
+            .await
+    }
+
+    pub async fn preview_import(
+        &self,
+        request: &transaction::ImportRequest,
+    ) -> Result<transaction::ImportPreviewResponse, ClientError> {
+        self.json(Method::POST, "/api/transactions/import/preview", request)
+            .await
+    }
+
+    pub async fn list_users(
+        &self,
+        request: &user::GetListRequest,
+        page: &Page,
+    ) -> Result<user::UserGetListResponse, ClientError> {
+        self.list("/api/users", request, page).await
+    }
+
+    pub async fn list_all_users(
+        &self,
+        request: &user::GetListRequest,
+        max_items: Option<i64>,
+    ) -> Result<Vec<user::UserResponse<schema::GetList>>, ClientError> {
+        self.list_all::<user::GetListResponse>("/api/users", request, max_items)
+            .await
+    }
+
+    pub async fn get_user(&self, id: UserId) -> Result<user::UserGetResponse, ClientError> {
+        self.get(&format!("/api/users/{id}"), &()).await
+    }
+
+    pub async fn create_user(
+        &self,
+        request: &user::CreateRequest,
+    ) -> Result<user::UserCreateResponse, ClientError> {
+        self.json(Method::POST, "/api/users", request).await
+    }
+
+    pub async fn update_user(
+        &self,
+        id: UserId,
+        request: &user::UpdateRequest,
+    ) -> Result<user::UserUpdateResponse, ClientError> {
+        self.json(Method::PATCH, &format!("/api/users/{id}"), request)
+            .await
+    }
+
+    pub async fn delete_user(&self, id: UserId) -> Result<(), ClientError> {
+        self.delete(&format!("/api/users/{id}")).await
+    }
+
+    pub async fn list_api_keys(
+        &self,
+        user_id: UserId,
+    ) -> Result<api_key::ApiKeyGetListResponse, ClientError> {
+        self.get(&format!("/api/users/{user_id}/api-keys"), &())
+            .await
+    }
+
+    pub async fn create_api_key(
+        &self,
+        user_id: UserId,
+        request: &api_key::CreateRequest,
+    ) -> Result<api_key::ApiKeyCreateResponse, ClientError> {
+        self.json(
+            Method::POST,
+            &format!("/api/users/{user_id}/api-keys"),
+            request,
+        )
+        .await
+    }
+
+    pub async fn delete_api_key(&self, user_id: UserId, id: ApiKeyId) -> Result<(), ClientError> {
+        self.delete(&format!("/api/users/{user_id}/api-keys/{id}"))
+            .await
+    }
+
+    pub async fn list_sessions(
+        &self,
+        user_id: UserId,
+    ) -> Result<user_session::UserSessionGetListResponse, ClientError> {
+        self.get(&format!("/api/users/{user_id}/sessions"), &())
+            .await
+    }
+
+    /// Revokes a session, so the next refresh from its device fails.
+    pub async fn revoke_session(
+        &self,
+        user_id: UserId,
+        id: UserSessionId,
+    ) -> Result<(), ClientError> {
+        self.delete(&format!("/api/users/{user_id}/sessions/{id}"))
+            .await
+    }
+}
@@ -0,0 +1,1074 @@
+//! Settings read from the environment the first time they are used.
+
+use std::{
+    env::var,
+    str::FromStr,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::resource::MAX_LIMIT;
+
+/// The resources that are listed a page at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PagedResource {
+    Accounts,
+    Announcements,
+    Assets,
+    Institutions,
+    Proposals,
+    Transactions,
+    Users,
+}
+
+impl PagedResource {
+    pub const ALL: [Self; 7] = [
+        Self::Accounts,
+        Self::Announcements,
+        Self::Assets,
+        Self::Institutions,
+        Self::Proposals,
+        Self::Transactions,
+        Self::Users,
+    ];
+
+    /// The name of the resource in the `PAGINATION_<NAME>_DEFAULT` and
+    /// `PAGINATION_<NAME>_MAX` environment variables.
+    pub fn env_name(self) -> &'static str {
+        match self {
+            Self::Accounts => "ACCOUNTS",
+            Self::Announcements => "ANNOUNCEMENTS",
+            Self::Assets => "ASSETS",
+            Self::Institutions => "INSTITUTIONS",
+            Self::Proposals => "PROPOSALS",
+            Self::Transactions => "TRANSACTIONS",
+            Self::Users => "USERS",
+        }
+    }
+
+    /// The path of the list endpoint of the resource.
+    pub fn path(self) -> &'static str {
+        match self {
+            Self::Accounts => "/api/accounts",
+            Self::Announcements => "/api/announcements",
+            Self::Assets => "/api/assets",
+            Self::Institutions => "/api/institutions",
+            Self::Proposals => "/api/proposals",
+            Self::Transactions => "/api/transactions",
+            Self::Users => "/api/users",
+        }
+    }
+
+    /// The page size used when the environment does not configure one.
+    /// Transactions are small rows listed in bulk, so they allow more.
+    pub fn default_page_size(self) -> PageSize {
+        match self {
+            Self::Transactions => PageSize {
+                default: MAX_LIMIT,
+                max: 500,
+            },
+            _ => PageSize::default(),
+        }
+    }
+
+    /// The configured page size of the resource.
+    pub fn page_size(self) -> PageSize {
+        static PAGE_SIZES: OnceLock<Vec<PageSize>> = OnceLock::new();
+        let page_sizes = PAGE_SIZES.get_or_init(|| {
+            PagedResource::ALL
+                .iter()
+                .map(|resource| {
+                    PageSize::from_vars(resource.default_page_size(), |name| {
+                        var(format!("PAGINATION_{}_{name}", resource.env_name())).ok()
+                    })
+                })
+                .collect()
+        });
+        page_sizes[self as usize]
+    }
+}
+
+/// How many items a page of a resource holds when a request does not say,
+/// and at most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageSize {
+    pub default: i64,
+    pub max: i64,
+}
+
+impl Default for PageSize {
+    fn default() -> Self {
+        Self {
+            default: MAX_LIMIT,
+            max: MAX_LIMIT,
+        }
+    }
+}
+
+impl PageSize {
+    /// Reads the `DEFAULT` and `MAX` settings through `lookup`, falling back
+    /// to `fallback` for settings that are unset or not positive numbers.
+    /// The default is capped at the maximum.
+    pub fn from_vars(fallback: Self, lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let read = |name| {
+            lookup(name)
+                .and_then(|x| x.parse::<i64>().ok())
+                .filter(|x| *x > 0)
+        };
+        let max = read("MAX").unwrap_or(fallback.max);
+        let default = read("DEFAULT").unwrap_or(fallback.default).min(max);
+        Self { default, max }
+    }
+
+    /// The number of items to return for the requested `max_items`.
+    pub fn clamp(&self, max_items: Option<i64>) -> i64 {
+        max_items
+            .map(|x| x.clamp(1, self.max))
+            .unwrap_or(self.default)
+    }
+}
+
+/// Who the API docs at `/docs` and `/private/api.json` are served to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DocsMode {
+    /// Anyone
+    Open,
+    /// Signed in callers who may read every resource
+    Admin,
+    /// No one. The routes answer as if they didn't exist.
+    #[default]
+    Disabled,
+}
+
+impl DocsMode {
+    /// Reads `ENABLE_DOCS`, which is `true`, `admin` or `false`. Debug
+    /// builds serve the docs to anyone when it is unset, release builds
+    /// don't serve them.
+    pub fn from_env() -> Self {
+        static DOCS_MODE: OnceLock<DocsMode> = OnceLock::new();
+        *DOCS_MODE
+            .get_or_init(|| Self::parse(var("ENABLE_DOCS").ok().as_deref(), cfg!(debug_assertions)))
+    }
+
+    /// The mode `value` configures, falling back to the default of the
+    /// build for unset or unknown values.
+    pub fn parse(value: Option<&str>, debug_build: bool) -> Self {
+        match value {
+            Some("true") => Self::Open,
+            Some("admin") => Self::Admin,
+            Some("false") => Self::Disabled,
+            _ if debug_build => Self::Open,
+            _ => Self::Disabled,
+        }
+    }
+}
+
+/// How many API requests a client may make in each window of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    pub limit: u32,
+    pub window: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            limit: 600,
+            window: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Reads `RATE_LIMIT_REQUESTS` and `RATE_LIMIT_WINDOW_SECONDS`.
+    pub fn from_env() -> Self {
+        static RATE_LIMIT: OnceLock<RateLimitConfig> = OnceLock::new();
+        *RATE_LIMIT.get_or_init(|| Self::from_vars(|name| var(format!("RATE_LIMIT_{name}")).ok()))
+    }
+
+    /// Reads the `REQUESTS` and `WINDOW_SECONDS` settings through `lookup`,
+    /// falling back to the default for settings that are unset or not
+    /// positive numbers.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let fallback = Self::default();
+        let read = |name| {
+            lookup(name)
+                .and_then(|x| x.parse::<u32>().ok())
+                .filter(|x| *x > 0)
+        };
+        Self {
+            limit: read("REQUESTS").unwrap_or(fallback.limit),
+            window: read("WINDOW_SECONDS")
+                .map(|x| Duration::from_secs(x.into()))
+                .unwrap_or(fallback.window),
+        }
+    }
+}
+
+/// How hard the sign in and refresh endpoints are throttled, see
+/// [`crate::api::login_throttle`].
+///
+/// The defaults leave room for what a browser does: a sign in is two
+/// requests, and every tab refreshes once when it opens and then once an
+/// hour, each time with a new refresh token. A household behind one
+/// address would have to open thirty tabs in a minute to be limited, and a
+/// refresh token is only ever retried a few times on a flaky network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginThrottleConfig {
+    /// The requests each address may make in a window
+    pub per_ip: u32,
+    /// The refreshes each refresh token may be sent to in a window
+    pub per_refresh_token: u32,
+    pub window: Duration,
+    /// The refusals in a row an address or refresh token is allowed before
+    /// it has to wait
+    pub failures: u32,
+    /// How long the first wait is, doubling with every refusal after it
+    pub backoff: Duration,
+    /// The longest wait
+    pub max_backoff: Duration,
+    /// Whether the address of the client is taken from `X-Forwarded-For`,
+    /// see [`crate::authentication::client_address::ClientAddress`].
+    /// Without it the address of the peer the request came in from is
+    /// throttled instead.
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        Self {
+            per_ip: 30,
+            per_refresh_token: 10,
+            window: Duration::from_secs(60),
+            failures: 5,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(15 * 60),
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+impl LoginThrottleConfig {
+    /// Reads `LOGIN_THROTTLE_PER_IP`, `LOGIN_THROTTLE_PER_REFRESH_TOKEN`,
+    /// `LOGIN_THROTTLE_WINDOW_SECONDS`, `LOGIN_THROTTLE_FAILURES`,
+    /// `LOGIN_THROTTLE_BACKOFF_SECONDS`, `LOGIN_THROTTLE_MAX_BACKOFF_SECONDS`
+    /// and `TRUST_FORWARDED_FOR`.
+    pub fn from_env() -> Self {
+        static LOGIN_THROTTLE: OnceLock<LoginThrottleConfig> = OnceLock::new();
+        *LOGIN_THROTTLE.get_or_init(|| Self {
+            trust_forwarded_for: var("TRUST_FORWARDED_FOR").is_ok_and(|v| v == "true"),
+            ..Self::from_vars(|name| var(format!("LOGIN_THROTTLE_{name}")).ok())
+        })
+    }
+
+    /// Reads the settings without their `LOGIN_THROTTLE_` prefix through
+    /// `lookup`, falling back to the default for settings that are unset or
+    /// not positive numbers.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let fallback = Self::default();
+        let read = |name| {
+            lookup(name)
+                .and_then(|x| x.parse::<u32>().ok())
+                .filter(|x| *x > 0)
+        };
+        let seconds = |name| read(name).map(|x| Duration::from_secs(x.into()));
+        Self {
+            per_ip: read("PER_IP").unwrap_or(fallback.per_ip),
+            per_refresh_token: read("PER_REFRESH_TOKEN").unwrap_or(fallback.per_refresh_token),
+            window: seconds("WINDOW_SECONDS").unwrap_or(fallback.window),
+            failures: read("FAILURES").unwrap_or(fallback.failures),
+            backoff: seconds("BACKOFF_SECONDS").unwrap_or(fallback.backoff),
+            max_backoff: seconds("MAX_BACKOFF_SECONDS").unwrap_or(fallback.max_backoff),
+            trust_forwarded_for: fallback.trust_forwarded_for,
+        }
+    }
+}
+
+/// How long callers may keep the responses of the rarely changing lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// How long the assets and institutions lists stay fresh. Zero keeps
+    /// them from being cached.
+    pub list_max_age: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            list_max_age: Duration::from_secs(60),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Reads `CACHE_LIST_MAX_AGE_SECONDS`.
+    pub fn from_env() -> Self {
+        static CACHE: OnceLock<CacheConfig> = OnceLock::new();
+        *CACHE.get_or_init(|| Self::from_vars(|name| var(format!("CACHE_{name}")).ok()))
+    }
+
+    /// Reads the `LIST_MAX_AGE_SECONDS` setting through `lookup`, falling
+    /// back to the default when it is unset or not a number.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let fallback = Self::default();
+        Self {
+            list_max_age: lookup("LIST_MAX_AGE_SECONDS")
+                .and_then(|x| x.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(fallback.list_max_age),
+        }
+    }
+}
+
+/// How long each phase of startup may take, and how long the timings of
+/// the phases are reported after boot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartupConfig {
+    /// How long any one phase may take before startup gives up on it
+    pub phase_timeout: Duration,
+    /// How long after boot `/readyz` reports the timings of the phases
+    pub report_window: Duration,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            phase_timeout: Duration::from_secs(120),
+            report_window: Duration::from_secs(300),
+        }
+    }
+}
+
+impl StartupConfig {
+    /// Reads `STARTUP_PHASE_TIMEOUT_SECONDS` and
+    /// `STARTUP_REPORT_WINDOW_SECONDS`.
+    pub fn from_env() -> Self {
+        static STARTUP: OnceLock<StartupConfig> = OnceLock::new();
+        *STARTUP.get_or_init(|| Self::from_vars(|name| var(format!("STARTUP_{name}")).ok()))
+    }
+
+    /// Reads the `PHASE_TIMEOUT_SECONDS` and `REPORT_WINDOW_SECONDS` settings
+    /// through `lookup`. The timeout falls back to the default when it is
+    /// unset or not a positive number, the window when it is unset or not a
+    /// number.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let fallback = Self::default();
+        Self {
+            phase_timeout: lookup("PHASE_TIMEOUT_SECONDS")
+                .and_then(|x| x.parse::<u64>().ok())
+                .filter(|x| *x > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(fallback.phase_timeout),
+            report_window: lookup("REPORT_WINDOW_SECONDS")
+                .and_then(|x| x.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(fallback.report_window),
+        }
+    }
+}
+
+/// How the connections to the database are held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatabaseConfig {
+    /// The longest a statement may run, outside of a request or within one
+    /// with more time left
+    pub statement_timeout: Duration,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            statement_timeout: Duration::from_secs(25),
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Reads `DATABASE_STATEMENT_TIMEOUT_SECONDS`.
+    pub fn from_env() -> Self {
+        static DATABASE: OnceLock<DatabaseConfig> = OnceLock::new();
+        *DATABASE.get_or_init(|| Self::from_vars(|name| var(format!("DATABASE_{name}")).ok()))
+    }
+
+    /// Reads the `STATEMENT_TIMEOUT_SECONDS` setting through `lookup`,
+    /// falling back to the default when it is unset or not a positive
+    /// number.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let fallback = Self::default();
+        Self {
+            statement_timeout: lookup("STATEMENT_TIMEOUT_SECONDS")
+                .and_then(|x| x.parse::<u64>().ok())
+                .filter(|x| *x > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(fallback.statement_timeout),
+        }
+    }
+}
+
+/// When the endpoints flagged as deprecated stopped being recommended, and
+/// when they go away.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeprecationConfig {
+    /// Flagged endpoints only announce their deprecation once this is set
+    pub deprecated_at: Option<DateTime<Utc>>,
+    /// When the flagged endpoints stop being served
+    pub sunset_at: Option<DateTime<Utc>>,
+}
+
+impl DeprecationConfig {
+    /// Reads `API_DEPRECATION_DATE` and `API_SUNSET_DATE`.
+    pub fn from_env() -> Self {
+        static DEPRECATION: OnceLock<DeprecationConfig> = OnceLock::new();
+        *DEPRECATION.get_or_init(|| Self::from_vars(|name| var(format!("API_{name}")).ok()))
+    }
+
+    /// Reads the `DEPRECATION_DATE` and `SUNSET_DATE` settings through
+    /// `lookup`, each either an RFC 3339 time or a date taken as midnight
+    /// UTC. Settings that are unset or neither are left unset.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let read = |name| {
+            lookup(name).and_then(|x| {
+                DateTime::parse_from_rfc3339(&x)
+                    .map(|x| x.to_utc())
+                    .or_else(|_| {
+                        NaiveDate::parse_from_str(&x, "%Y-%m-%d")
+                            .map(|x| x.and_hms_opt(0, 0, 0).unwrap().and_utc())
+                    })
+                    .ok()
+            })
+        };
+        Self {
+            deprecated_at: read("DEPRECATION_DATE"),
+            sunset_at: read("SUNSET_DATE"),
+        }
+    }
+}
+
+/// How strictly the times of a token are checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenValidationConfig {
+    /// How far the clock of the identity provider may be off from ours, so
+    /// a token issued a few seconds in our future or past its expiry is
+    /// still accepted
+    pub leeway: Duration,
+}
+
+impl Default for TokenValidationConfig {
+    fn default() -> Self {
+        Self {
+            leeway: Duration::from_secs(30),
+        }
+    }
+}
+
+impl TokenValidationConfig {
+    /// Reads `AUTH_LEEWAY_SECONDS`.
+    pub fn from_env() -> Self {
+        static TOKEN_VALIDATION: OnceLock<TokenValidationConfig> = OnceLock::new();
+        *TOKEN_VALIDATION.get_or_init(|| Self::from_vars(|name| var(format!("AUTH_{name}")).ok()))
+    }
+
+    /// Reads the `LEEWAY_SECONDS` setting through `lookup`, falling back to
+    /// the default when it is unset or not a number.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let fallback = Self::default();
+        Self {
+            leeway: lookup("LEEWAY_SECONDS")
+                .and_then(|x| x.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(fallback.leeway),
+        }
+    }
+}
+
+/// Which of the groups claimed by a token are kept, so tokens carrying
+/// hundreds of directory groups are only checked against the ones the
+/// policies name. Leaving both lists empty keeps every group.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupFilterConfig {
+    /// The subjects kept as they are written in the policy file, after any
+    /// namespace is stripped
+    pub allowed: Vec<String>,
+    /// The prefixes of the claimed or stripped names that are kept
+    pub prefixes: Vec<String>,
+}
+
+impl GroupFilterConfig {
+    /// Reads `AUTH_ALLOWED_GROUPS` and `AUTH_ALLOWED_GROUP_PREFIXES`.
+    pub fn from_env() -> &'static Self {
+        static GROUP_FILTER: OnceLock<GroupFilterConfig> = OnceLock::new();
+        GROUP_FILTER.get_or_init(|| Self::from_vars(|name| var(format!("AUTH_{name}")).ok()))
+    }
+
+    /// Reads the `ALLOWED_GROUPS` and `ALLOWED_GROUP_PREFIXES` settings
+    /// through `lookup`, each a comma separated list. Blank entries are
+    /// skipped.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let read = |name| {
+            lookup(name)
+                .map(|x| {
+                    x.split(',')
+                        .map(str::trim)
+                        .filter(|x| !x.is_empty())
+                        .map(str::to_owned)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+        Self {
+            allowed: read("ALLOWED_GROUPS"),
+            prefixes: read("ALLOWED_GROUP_PREFIXES"),
+        }
+    }
+
+    /// Whether every group is kept.
+    pub fn keeps_all(&self) -> bool {
+        self.allowed.is_empty() && self.prefixes.is_empty()
+    }
+
+    /// Whether a group claimed as `claimed`, and referred to by `subject`
+    /// once its namespace is stripped, is kept.
+    pub fn keeps(&self, claimed: &str, subject: &str) -> bool {
+        self.keeps_all()
+            || self.allowed.iter().any(|x| x == subject)
+            || self
+                .prefixes
+                .iter()
+                .any(|x| claimed.starts_with(x.as_str()) || subject.starts_with(x.as_str()))
+    }
+}
+
+/// The optional subsystems, each of which can be turned off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// Alert rules, and checking them as balances change
+    Alerts,
+    /// Exchange rate backfills, and the daily ingestion of rates
+    ExchangeRates,
+    /// Export schedules, and running them when they are due
+    ExportSchedules,
+    /// Reading the details of receipts as they are attached
+    ReceiptExtraction,
+}
+
+impl Feature {
+    pub const COUNT: usize = 4;
+
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::Alerts,
+        Self::ExchangeRates,
+        Self::ExportSchedules,
+        Self::ReceiptExtraction,
+    ];
+
+    /// The name of the feature in the API and the `feature_flag` table.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Alerts => "alerts",
+            Self::ExchangeRates => "exchange_rates",
+            Self::ExportSchedules => "export_schedules",
+            Self::ReceiptExtraction => "receipt_extraction",
+        }
+    }
+
+    /// The name of the feature in the `FEATURE_<NAME>` environment
+    /// variables.
+    pub fn env_name(self) -> &'static str {
+        match self {
+            Self::Alerts => "ALERTS",
+            Self::ExchangeRates => "EXCHANGE_RATES",
+            Self::ExportSchedules => "EXPORT_SCHEDULES",
+            Self::ReceiptExtraction => "RECEIPT_EXTRACTION",
+        }
+    }
+
+    /// Whether the feature has routes of its own, which are only mounted
+    /// when the router is built.
+    pub fn mounts_routes(self) -> bool {
+        match self {
+            Self::Alerts | Self::ExchangeRates | Self::ExportSchedules => true,
+            Self::ReceiptExtraction => false,
+        }
+    }
+}
+
+impl FromStr for Feature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|feature| feature.name() == s)
+            .ok_or_else(|| format!("There is no feature `{s}`."))
+    }
+}
+
+/// Which optional subsystems are enabled. Every feature is enabled unless
+/// configured otherwise.
+///
+/// The flags are shared by the clones of a `FeatureFlags`, so a toggle is
+/// seen by every service right away. Routes are mounted from the flags as
+/// they were when the router was built, so toggling a feature with routes
+/// only adds or removes them on the next restart.
+#[derive(Debug, Clone)]
+pub struct FeatureFlags {
+    enabled: Arc<[AtomicBool; Feature::COUNT]>,
+    mounted: [bool; Feature::COUNT],
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self::new([true; Feature::COUNT])
+    }
+}
+
+impl FeatureFlags {
+    fn new(enabled: [bool; Feature::COUNT]) -> Self {
+        Self {
+            enabled: Arc::new(enabled.map(AtomicBool::new)),
+            mounted: enabled,
+        }
+    }
+
+    /// Reads `FEATURE_<NAME>` for each feature.
+    pub fn from_env() -> Self {
+        static FEATURES: OnceLock<[bool; Feature::COUNT]> = OnceLock::new();
+        Self::new(
+            *FEATURES.get_or_init(|| {
+                Self::from_vars(|name| var(format!("FEATURE_{name}")).ok()).snapshot()
+            }),
+        )
+    }
+
+    /// Reads the setting of each feature through `lookup`, by its
+    /// [`Feature::env_name`]. A feature is disabled by `false` and enabled
+    /// otherwise.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        Self::new(
+            Feature::ALL.map(|feature| lookup(feature.env_name()).is_none_or(|x| x != "false")),
+        )
+    }
+
+    /// The flags with `overrides` applied, as they are before any routes
+    /// are mounted from them.
+    pub fn with_overrides(self, overrides: impl IntoIterator<Item = (Feature, bool)>) -> Self {
+        let mut enabled = self.snapshot();
+        for (feature, value) in overrides {
+            enabled[feature as usize] = value;
+        }
+        Self::new(enabled)
+    }
+
+    fn snapshot(&self) -> [bool; Feature::COUNT] {
+        Feature::ALL.map(|feature| self.is_enabled(feature))
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        self.enabled[feature as usize].load(Ordering::Relaxed)
+    }
+
+    /// Whether the routes of the feature were mounted, which is only ever
+    /// the case for features with routes.
+    pub fn is_mounted(&self, feature: Feature) -> bool {
+        feature.mounts_routes() && self.mounted[feature as usize]
+    }
+
+    /// Turns a feature on or off for every holder of the flags.
+    pub fn set(&self, feature: Feature, enabled: bool) {
+        self.enabled[feature as usize].store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether the routes of the feature disagree with its flag until the
+    /// server restarts.
+    pub fn restart_required(&self, feature: Feature) -> bool {
+        feature.mounts_routes() && self.is_enabled(feature) != self.mounted[feature as usize]
+    }
+}
+
+/// The settings of a real identity provider, which a demo must not be
+/// configured alongside.
+pub const OIDC_SETTINGS: [&str; 8] = [
+    "AUTH_WELL_KNOWN_URI",
+    "AUTH_ISSUER",
+    "AUTH_AUDIENCE",
+    "DEX_STATIC_CLIENT_ID",
+    "DEX_STATIC_CLIENT_SECRET",
+    "DEX_AUTH_URL",
+    "DEX_TOKEN_URL",
+    "DEX_REDIRECT_URL",
+];
+
+/// Whether the deployment is a public demo, how long its sessions last and
+/// how many may be started.
+///
+/// Every session is a copy of the seeded data, so the sessions alive at
+/// once are capped and each address may only start a few an hour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DemoConfig {
+    pub enabled: bool,
+    /// How long a demo token is valid, after which the data of its session
+    /// is deleted
+    pub session_ttl: Duration,
+    /// The most sessions that are alive at once
+    pub max_sessions: u32,
+    /// The sessions each address may start in an hour
+    pub starts_per_ip: u32,
+}
+
+impl Default for DemoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            session_ttl: Duration::from_secs(3600),
+            max_sessions: 100,
+            starts_per_ip: 5,
+        }
+    }
+}
+
+impl DemoConfig {
+    /// Reads `DEMO_MODE`, `DEMO_SESSION_TTL_SECONDS`, `DEMO_MAX_SESSIONS`
+    /// and `DEMO_STARTS_PER_IP`.
+    pub fn from_env() -> Self {
+        static DEMO: OnceLock<DemoConfig> = OnceLock::new();
+        *DEMO.get_or_init(|| Self::from_vars(|name| var(format!("DEMO_{name}")).ok()))
+    }
+
+    /// Reads the `MODE`, `SESSION_TTL_SECONDS`, `MAX_SESSIONS` and
+    /// `STARTS_PER_IP` settings through `lookup`. The mode is only enabled
+    /// by `true`, and the others fall back to the default when they are
+    /// unset or not positive numbers.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let fallback = Self::default();
+        let read = |name| {
+            lookup(name)
+                .and_then(|x| x.parse::<u32>().ok())
+                .filter(|x| *x > 0)
+        };
+        Self {
+            enabled: lookup("MODE").is_some_and(|x| x == "true"),
+            session_ttl: lookup("SESSION_TTL_SECONDS")
+                .and_then(|x| x.parse::<u64>().ok())
+                .filter(|x| *x > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(fallback.session_ttl),
+            max_sessions: read("MAX_SESSIONS").unwrap_or(fallback.max_sessions),
+            starts_per_ip: read("STARTS_PER_IP").unwrap_or(fallback.starts_per_ip),
+        }
+    }
+
+    /// Refuses a demo configured alongside a real identity provider, whose
+    /// users could otherwise sign in to a deployment anyone may write to.
+    /// `lookup` reads the environment.
+    pub fn validate(&self, lookup: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let configured = OIDC_SETTINGS
+            .into_iter()
+            .filter(|name| lookup(name).is_some())
+            .collect::<Vec<_>>();
+        if configured.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "`DEMO_MODE=true` can't be used with an identity provider, unset {}.",
+                configured.join(", ")
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vars<'a>(pairs: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |name| {
+            pairs
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| (*value).to_owned())
+        }
+    }
+
+    #[test]
+    fn it_reads_the_page_size_from_the_environment() {
+        let page_size = PageSize::from_vars(
+            PageSize::default(),
+            vars(&[("DEFAULT", "20"), ("MAX", "50")]),
+        );
+        assert_eq!(
+            page_size,
+            PageSize {
+                default: 20,
+                max: 50
+            }
+        );
+        assert_eq!(page_size.clamp(None), 20);
+        assert_eq!(page_size.clamp(Some(50)), 50);
+        assert_eq!(page_size.clamp(Some(51)), 50);
+        assert_eq!(page_size.clamp(Some(0)), 1);
+    }
+
+    #[test]
+    fn it_falls_back_for_invalid_settings() {
+        let fallback = PagedResource::Transactions.default_page_size();
+        let page_size = PageSize::from_vars(fallback, vars(&[("DEFAULT", "-1"), ("MAX", "x")]));
+        assert_eq!(page_size, fallback);
+    }
+
+    #[test]
+    fn it_caps_the_default_at_the_max() {
+        let page_size = PageSize::from_vars(PageSize::default(), vars(&[("MAX", "10")]));
+        assert_eq!(
+            page_size,
+            PageSize {
+                default: 10,
+                max: 10
+            }
+        );
+    }
+
+    #[test]
+    fn it_reads_the_docs_mode() {
+        assert_eq!(DocsMode::parse(Some("true"), false), DocsMode::Open);
+        assert_eq!(DocsMode::parse(Some("admin"), false), DocsMode::Admin);
+        assert_eq!(DocsMode::parse(Some("false"), true), DocsMode::Disabled);
+    }
+
+    #[test]
+    fn it_reads_the_rate_limit_from_the_environment() {
+        assert_eq!(
+            RateLimitConfig::from_vars(vars(&[("REQUESTS", "10"), ("WINDOW_SECONDS", "5")])),
+            RateLimitConfig {
+                limit: 10,
+                window: Duration::from_secs(5)
+            }
+        );
+        assert_eq!(
+            RateLimitConfig::from_vars(vars(&[("REQUESTS", "0"), ("WINDOW_SECONDS", "x")])),
+            RateLimitConfig::default()
+        );
+    }
+
+    #[test]
+    fn it_reads_the_login_throttle_from_the_environment() {
+        assert_eq!(
+            LoginThrottleConfig::from_vars(vars(&[
+                ("PER_IP", "5"),
+                ("FAILURES", "3"),
+                ("MAX_BACKOFF_SECONDS", "60"),
+                ("BACKOFF_SECONDS", "0"),
+            ])),
+            LoginThrottleConfig {
+                per_ip: 5,
+                failures: 3,
+                max_backoff: Duration::from_secs(60),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn it_serves_docs_by_default_only_in_debug_builds() {
+        assert_eq!(DocsMode::parse(None, true), DocsMode::Open);
+        assert_eq!(DocsMode::parse(None, false), DocsMode::Disabled);
+        assert_eq!(DocsMode::parse(Some("yes"), false), DocsMode::Disabled);
+    }
+
+    #[test]
+    fn it_reads_how_long_lists_are_cached_from_the_environment() {
+        assert_eq!(
+            CacheConfig::from_vars(vars(&[("LIST_MAX_AGE_SECONDS", "300")])),
+            CacheConfig {
+                list_max_age: Duration::from_secs(300)
+            }
+        );
+        assert_eq!(
+            CacheConfig::from_vars(vars(&[("LIST_MAX_AGE_SECONDS", "0")])).list_max_age,
+            Duration::ZERO
+        );
+        assert_eq!(
+            CacheConfig::from_vars(vars(&[("LIST_MAX_AGE_SECONDS", "-1")])),
+            CacheConfig::default()
+        );
+    }
+
+    #[test]
+    fn it_reads_the_startup_timeouts_from_the_environment() {
+        assert_eq!(
+            StartupConfig::from_vars(vars(&[
+                ("PHASE_TIMEOUT_SECONDS", "45"),
+                ("REPORT_WINDOW_SECONDS", "0")
+            ])),
+            StartupConfig {
+                phase_timeout: Duration::from_secs(45),
+                report_window: Duration::ZERO
+            }
+        );
+        assert_eq!(
+            StartupConfig::from_vars(vars(&[("PHASE_TIMEOUT_SECONDS", "0")])),
+            StartupConfig::default()
+        );
+    }
+
+    #[test]
+    fn it_reads_the_statement_timeout_from_the_environment() {
+        assert_eq!(
+            DatabaseConfig::from_vars(vars(&[("STATEMENT_TIMEOUT_SECONDS", "10")])),
+            DatabaseConfig {
+                statement_timeout: Duration::from_secs(10)
+            }
+        );
+        assert_eq!(
+            DatabaseConfig::from_vars(vars(&[("STATEMENT_TIMEOUT_SECONDS", "0")])),
+            DatabaseConfig::default()
+        );
+    }
+
+    #[test]
+    fn it_reads_the_deprecation_dates_from_the_environment() {
+        assert_eq!(
+            DeprecationConfig::from_vars(vars(&[
+                ("DEPRECATION_DATE", "2025-05-01"),
+                ("SUNSET_DATE", "2025-11-01T12:00:00+09:00")
+            ])),
+            DeprecationConfig {
+                deprecated_at: Some("2025-05-01T00:00:00Z".parse().unwrap()),
+                sunset_at: Some("2025-11-01T03:00:00Z".parse().unwrap()),
+            }
+        );
+        assert_eq!(
+            DeprecationConfig::from_vars(vars(&[("DEPRECATION_DATE", "soon")])),
+            DeprecationConfig::default()
+        );
+    }
+
+    #[test]
+    fn it_reads_the_demo_mode_from_the_environment() {
+        assert_eq!(
+            DemoConfig::from_vars(vars(&[
+                ("MODE", "true"),
+                ("SESSION_TTL_SECONDS", "600"),
+                ("MAX_SESSIONS", "20"),
+                ("STARTS_PER_IP", "2"),
+            ])),
+            DemoConfig {
+                enabled: true,
+                session_ttl: Duration::from_secs(600),
+                max_sessions: 20,
+                starts_per_ip: 2,
+            }
+        );
+        assert_eq!(
+            DemoConfig::from_vars(vars(&[
+                ("MODE", "yes"),
+                ("SESSION_TTL_SECONDS", "0"),
+                ("MAX_SESSIONS", "0"),
+                ("STARTS_PER_IP", "many"),
+            ])),
+            DemoConfig::default()
+        );
+    }
+
+    #[test]
+    fn it_refuses_a_demo_alongside_an_identity_provider() {
+        let demo = DemoConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(demo.validate(vars(&[])), Ok(()));
+        let error = demo
+            .validate(vars(&[
+                ("AUTH_ISSUER", "https://dex"),
+                ("DEX_AUTH_URL", "x"),
+            ]))
+            .unwrap_err();
+        assert!(error.contains("AUTH_ISSUER, DEX_AUTH_URL"), "{error}");
+        assert_eq!(
+            DemoConfig::default().validate(vars(&[("AUTH_ISSUER", "https://dex")])),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn it_reads_the_group_filter_from_the_environment() {
+        let group_filter = GroupFilterConfig::from_vars(vars(&[
+            ("ALLOWED_GROUPS", "auditors, bookkeepers,,"),
+            ("ALLOWED_GROUP_PREFIXES", "treasury-"),
+        ]));
+        assert_eq!(group_filter.allowed, vec!["auditors", "bookkeepers"]);
+        assert_eq!(group_filter.prefixes, vec!["treasury-"]);
+        assert!(group_filter.keeps("org:auditors", "auditors"));
+        assert!(group_filter.keeps("treasury-ops", "treasury-ops"));
+        assert!(group_filter.keeps("org:treasury-ops", "treasury-ops"));
+        assert!(!group_filter.keeps("org:engineering", "engineering"));
+
+        let group_filter = GroupFilterConfig::from_vars(vars(&[]));
+        assert!(group_filter.keeps_all());
+        assert!(group_filter.keeps("org:engineering", "engineering"));
+    }
+
+    #[test]
+    fn it_reads_the_token_leeway_from_the_environment() {
+        assert_eq!(
+            TokenValidationConfig::from_vars(vars(&[("LEEWAY_SECONDS", "5")])).leeway,
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            TokenValidationConfig::from_vars(vars(&[("LEEWAY_SECONDS", "0")])).leeway,
+            Duration::ZERO
+        );
+        assert_eq!(
+            TokenValidationConfig::from_vars(vars(&[("LEEWAY_SECONDS", "soon")])),
+            TokenValidationConfig::default()
+        );
+    }
+
+    #[test]
+    fn it_reads_the_feature_flags_from_the_environment() {
+        let features = FeatureFlags::from_vars(vars(&[
+            ("ALERTS", "false"),
+            ("EXPORT_SCHEDULES", "true"),
+            ("RECEIPT_EXTRACTION", "no"),
+        ]));
+        assert!(!features.is_enabled(Feature::Alerts));
+        assert!(features.is_enabled(Feature::ExchangeRates));
+        assert!(features.is_enabled(Feature::ExportSchedules));
+        assert!(features.is_enabled(Feature::ReceiptExtraction));
+        assert!(!features.is_mounted(Feature::Alerts));
+        assert!(!features.is_mounted(Feature::ReceiptExtraction));
+
+        let features =
+            features.with_overrides([(Feature::Alerts, true), (Feature::ExchangeRates, false)]);
+        assert!(features.is_enabled(Feature::Alerts));
+        assert!(features.is_mounted(Feature::Alerts));
+        assert!(!features.is_enabled(Feature::ExchangeRates));
+        assert!(!features.is_mounted(Feature::ExchangeRates));
+        assert_eq!("exchange_rates".parse(), Ok(Feature::ExchangeRates));
+        assert!("webhooks".parse::<Feature>().is_err());
+    }
+
+    #[test]
+    fn it_shares_toggles_but_only_remounts_on_restart() {
+        let features = FeatureFlags::default();
+        let shared = features.clone();
+
+        shared.set(Feature::Alerts, false);
+        shared.set(Feature::ReceiptExtraction, false);
+        assert!(!features.is_enabled(Feature::Alerts));
+        assert!(features.is_mounted(Feature::Alerts));
+        assert!(features.restart_required(Feature::Alerts));
+        assert!(!features.is_enabled(Feature::ReceiptExtraction));
+        assert!(!features.restart_required(Feature::ReceiptExtraction));
+
+        shared.set(Feature::Alerts, true);
+        assert!(!features.restart_required(Feature::Alerts));
+    }
+}
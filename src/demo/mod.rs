@@ -0,0 +1,225 @@
+//! A public demo, where anyone can try the app on canned data that nothing
+//! persists to.
+//!
+//! With `DEMO_MODE=true` the demo user of [`DEMO_SEED_FILE`] is seeded on
+//! startup and `POST /demo/login` hands out demo tokens, as long as fewer
+//! than the most sessions of the [`DemoConfig`] are alive and the address
+//! asking hasn't started too many in the last [`START_WINDOW`]. Each login starts a
+//! session with its own copy of the accounts and transactions of the demo
+//! user, owned by a user only the token of the session resolves to. Requests
+//! go through the same services as those of any other user, so writes
+//! succeed but only ever change the copy of their session, never the seeded
+//! rows or the copy of another session. Copies are deleted once their
+//! session expires.
+
+use std::{
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use rand::Rng;
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    authentication::{
+        AuthenticationError,
+        authenticated_token::{AuthenticatedToken, Claims},
+    },
+    config::DemoConfig,
+    model::user::{User, UserCreate},
+    resource::{
+        account_balance_repository::AccountBalanceRepository, deadline,
+        demo_repository::DemoRepository, user_repository::UserRepository,
+    },
+    seed::{SeedError, SeedOptions, seed},
+    service::ServiceError,
+};
+
+/// The seed file of the demo, with the one user sessions copy.
+pub const DEMO_SEED_FILE: &str = include_str!("seed.json");
+/// The issuer of the demo users.
+pub const DEMO_ISSUER: &str = "treasury-demo";
+/// The subject of the seeded demo user, which no token is issued for.
+pub const DEMO_SEED_SUB: &str = "demo";
+/// The prefix of the subject of the user of each demo session.
+pub const DEMO_SESSION_SUB_PREFIX: &str = "session:";
+/// The prefix telling a demo token apart from an OIDC token.
+pub const DEMO_TOKEN_PREFIX: &str = "trd_";
+/// How often the data of expired sessions is deleted.
+pub const REAPER_INTERVAL: Duration = Duration::from_secs(60);
+/// The window the sessions started from an address are counted in.
+pub const START_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Signs the demo tokens. The key only lives as long as the process, so a
+/// restart ends every session.
+static SIGNING_KEY: LazyLock<[u8; 32]> = LazyLock::new(|| rand::rng().random());
+
+/// The claims of a demo token, read back as the [`Claims`] of an OIDC token.
+#[derive(Debug, Serialize)]
+struct DemoClaims<'a> {
+    email: &'a str,
+    email_verified: bool,
+    sub: &'a str,
+    iss: &'a str,
+    iat: i64,
+    exp: i64,
+    name: &'a str,
+}
+
+/// A demo session started by a login.
+#[derive(Debug, Clone)]
+pub struct DemoSession {
+    /// The user owning the copy of the session
+    pub user: User,
+    /// The bearer token of the session
+    pub token: String,
+    /// How many seconds the token is valid for
+    pub expires_in: i64,
+}
+
+/// Seeds the demo user, unless a previous start already did.
+#[instrument(skip_all)]
+pub async fn seed_demo(connection_pool: &PgPool) -> Result<(), SeedError> {
+    let session = deadline::begin(&connection_pool)
+        .await
+        .map_err(ServiceError::from)?;
+    let existing = UserRepository
+        .get_by_iss_and_sub(session, DEMO_ISSUER.to_owned(), DEMO_SEED_SUB.to_owned())
+        .await
+        .map_err(ServiceError::from)?;
+    if existing.is_some() {
+        info!("The demo user is already seeded");
+        return Ok(());
+    }
+    let file = serde_json::from_str(DEMO_SEED_FILE)
+        .map_err(|e| SeedError::Invalid(format!("The demo seed file: {e}")))?;
+    seed(connection_pool, file, SeedOptions::default()).await?;
+    Ok(())
+}
+
+/// Starts a demo session on a fresh copy of the data of the demo user, or
+/// none when as many sessions as the config allows are alive.
+#[instrument(skip_all)]
+pub async fn start_session(
+    connection_pool: &PgPool,
+    config: &DemoConfig,
+) -> Result<Option<DemoSession>, ServiceError> {
+    // Sessions outlive the TTL until the reaper gets to them, but no longer
+    // count.
+    let created_after = TimeDelta::from_std(config.session_ttl)
+        .ok()
+        .and_then(|session_ttl| Utc::now().checked_sub_signed(session_ttl))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let seed_user = UserRepository
+        .get_by_iss_and_sub(
+            deadline::begin(&connection_pool).await?,
+            DEMO_ISSUER.to_owned(),
+            DEMO_SEED_SUB.to_owned(),
+        )
+        .await?
+        .ok_or(ServiceError::NotFound)?;
+    let Some(user) = DemoRepository
+        .create_session(
+            deadline::begin(&connection_pool).await?,
+            seed_user.id,
+            UserCreate {
+                name: seed_user.name,
+                email: seed_user.email,
+                sub: format!("{DEMO_SESSION_SUB_PREFIX}{}", Uuid::new_v4()),
+                iss: DEMO_ISSUER.to_owned(),
+                email_verified: None,
+            },
+            DEMO_SESSION_SUB_PREFIX,
+            config.max_sessions,
+            created_after,
+        )
+        .await?
+    else {
+        warn!("Refused a demo session, {} are alive", config.max_sessions);
+        return Ok(None);
+    };
+    // The copied transactions skip the service, so their balances are
+    // cached at once.
+    AccountBalanceRepository
+        .rebuild(deadline::begin(&connection_pool).await?, Some(user.id))
+        .await?;
+
+    let iat = Utc::now().timestamp();
+    let expires_in = config.session_ttl.as_secs() as i64;
+    let claims = DemoClaims {
+        email: &user.email,
+        email_verified: true,
+        sub: &user.sub,
+        iss: &user.iss,
+        iat,
+        exp: iat + expires_in,
+        name: &user.name,
+    };
+    let token = encode(
+        &Header::new(Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(&*SIGNING_KEY),
+    )
+    .expect("Failed to sign a demo token.");
+    info!("Started demo session {}", user.sub);
+    Ok(Some(DemoSession {
+        token: format!("{DEMO_TOKEN_PREFIX}{token}"),
+        user,
+        expires_in,
+    }))
+}
+
+/// Validates a demo token, without its [`DEMO_TOKEN_PREFIX`].
+pub fn authenticate(token: &str) -> Result<AuthenticatedToken, AuthenticationError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_aud = false;
+    validation.set_issuer(&[DEMO_ISSUER]);
+    validation.set_required_spec_claims(&["iss", "exp", "sub"]);
+    let claims = decode::<Claims>(token, &DecodingKey::from_secret(&*SIGNING_KEY), &validation)?;
+    Ok(AuthenticatedToken::new(claims.claims))
+}
+
+/// Deletes the data of the sessions older than the session TTL, returning
+/// how many sessions it belonged to.
+#[instrument(skip_all)]
+pub async fn delete_expired(
+    connection_pool: &PgPool,
+    config: &DemoConfig,
+) -> Result<u64, ServiceError> {
+    let Some(created_before) = TimeDelta::from_std(config.session_ttl)
+        .ok()
+        .and_then(|session_ttl| Utc::now().checked_sub_signed(session_ttl))
+    else {
+        return Ok(0);
+    };
+    let deleted = DemoRepository
+        .delete_sessions(
+            deadline::begin(&connection_pool).await?,
+            DEMO_ISSUER,
+            DEMO_SESSION_SUB_PREFIX,
+            created_before,
+        )
+        .await?;
+    if deleted > 0 {
+        info!("Deleted {deleted} expired demo sessions");
+    }
+    Ok(deleted)
+}
+
+/// Deletes the data of expired sessions every [`REAPER_INTERVAL`].
+pub fn spawn_reaper(connection_pool: Arc<PgPool>, config: DemoConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REAPER_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = delete_expired(&connection_pool, &config).await {
+                error!("Failed to delete the expired demo sessions: {e}");
+            }
+        }
+    });
+}
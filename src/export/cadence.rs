@@ -0,0 +1,211 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Months, NaiveTime, SubsecRound, TimeDelta, Timelike, Utc};
+
+use crate::export::ExportError;
+
+/// How far ahead to look for the next run before giving up on a cadence
+/// that can never match, such as the 31st of February.
+const SEARCH_DAYS: i64 = 4 * 366;
+
+/// When a scheduled export runs, in a subset of the cron syntax.
+///
+/// A cadence is either `@hourly`, `@daily`, `@weekly` or `@monthly`, or
+/// the five fields `minute hour day-of-month month day-of-week`. Each field
+/// is `*`, a number, a range `a-b`, either of those with a `/step`, or a
+/// comma separated list of them. Days of the week count from Sunday as 0.
+/// As in cron, a day matches either of the day fields when both are
+/// restricted. Times are in UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cadence {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl Cadence {
+    /// The first time after `after` the cadence matches, to the minute.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut t = after.trunc_subsecs(0).with_second(0)? + TimeDelta::minutes(1);
+        let limit = after + TimeDelta::days(SEARCH_DAYS);
+        while t <= limit {
+            let midnight = t.date_naive().and_time(NaiveTime::MIN).and_utc();
+            if !matches(self.months, t.month()) {
+                t = midnight.with_day(1)?.checked_add_months(Months::new(1))?;
+            } else if !self.matches_day(t) {
+                t = midnight + TimeDelta::days(1);
+            } else if !matches(self.hours, t.hour()) {
+                t = t.with_minute(0)? + TimeDelta::hours(1);
+            } else if !matches(self.minutes, t.minute()) {
+                t += TimeDelta::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    /// Whether a run is due at `now` when the last one was at `since`.
+    pub fn is_due(&self, since: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.next_after(since).is_some_and(|next| next <= now)
+    }
+
+    fn matches_day(&self, t: DateTime<Utc>) -> bool {
+        let day_of_month = matches(self.days_of_month, t.day());
+        let day_of_week = matches(self.days_of_week, t.weekday().num_days_from_sunday());
+        if self.any_day_of_month || self.any_day_of_week {
+            day_of_month && day_of_week
+        } else {
+            day_of_month || day_of_week
+        }
+    }
+}
+
+fn matches(field: u64, value: u32) -> bool {
+    field & (1 << value) != 0
+}
+
+/// Parses one field into a bit set of the values it matches.
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut values = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|x| *x > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+                None => {
+                    let value = range.parse().ok()?;
+                    (value, if step > 1 { max } else { value })
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(step as usize) {
+            values |= 1 << value;
+        }
+    }
+    Some(values)
+}
+
+impl FromStr for Cadence {
+    type Err = ExportError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let expression = match value.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            expression => expression,
+        };
+        let invalid = || ExportError::InvalidCadence(value.to_owned());
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(invalid());
+        };
+        let mut days_of_week_values = parse_field(days_of_week, 0, 7).ok_or_else(invalid)?;
+        // Both 0 and 7 are Sunday.
+        if matches(days_of_week_values, 7) {
+            days_of_week_values |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minutes, 0, 59).ok_or_else(invalid)?,
+            hours: parse_field(hours, 0, 23).ok_or_else(invalid)?,
+            days_of_month: parse_field(days_of_month, 1, 31).ok_or_else(invalid)?,
+            months: parse_field(months, 1, 12).ok_or_else(invalid)?,
+            days_of_week: days_of_week_values,
+            any_day_of_month: days_of_month == "*",
+            any_day_of_week: days_of_week == "*",
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    #[test]
+    fn it_runs_daily_at_midnight() {
+        let cadence = "@daily".parse::<Cadence>().unwrap();
+        assert_eq!(
+            cadence.next_after(at("2025-04-18T09:15:30Z")),
+            Some(at("2025-04-19T00:00:00Z"))
+        );
+        assert_eq!(
+            cadence.next_after(at("2025-04-19T00:00:00Z")),
+            Some(at("2025-04-20T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn it_runs_at_the_listed_minutes_and_hours() {
+        let cadence = "15,45 2-4 * * *".parse::<Cadence>().unwrap();
+        assert_eq!(
+            cadence.next_after(at("2025-04-18T02:15:00Z")),
+            Some(at("2025-04-18T02:45:00Z"))
+        );
+        assert_eq!(
+            cadence.next_after(at("2025-04-18T04:50:00Z")),
+            Some(at("2025-04-19T02:15:00Z"))
+        );
+    }
+
+    #[test]
+    fn it_runs_on_either_restricted_day() {
+        // The 1st of the month or any Friday.
+        let cadence = "0 3 1 * 5".parse::<Cadence>().unwrap();
+        assert_eq!(
+            cadence.next_after(at("2025-04-18T03:00:00Z")),
+            Some(at("2025-04-25T03:00:00Z"))
+        );
+        assert_eq!(
+            cadence.next_after(at("2025-04-25T03:00:00Z")),
+            Some(at("2025-05-01T03:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn it_is_due_once_the_next_run_has_passed() {
+        let cadence = "*/30 * * * *".parse::<Cadence>().unwrap();
+        let since = at("2025-04-18T09:00:00Z");
+        assert!(!cadence.is_due(since, at("2025-04-18T09:29:59Z")));
+        assert!(cadence.is_due(since, at("2025-04-18T09:30:00Z")));
+    }
+
+    #[test]
+    fn it_rejects_invalid_cadences() {
+        for cadence in [
+            "",
+            "@nightly",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(cadence.parse::<Cadence>().is_err(), "{cadence}");
+        }
+    }
+
+    #[test]
+    fn it_never_runs_on_an_impossible_day() {
+        let cadence = "0 0 31 2 *".parse::<Cadence>().unwrap();
+        assert_eq!(cadence.next_after(at("2025-04-18T00:00:00Z")), None);
+    }
+}
@@ -0,0 +1,260 @@
+use std::{io::SeekFrom, ops::Range, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use http::{HeaderMap, HeaderValue, header::AUTHORIZATION};
+use object_store::{
+    ClientOptions, ObjectStore, PutPayload, aws::AmazonS3Builder, http::HttpBuilder, path::Path,
+};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+use crate::{export::ExportError, model::export_schedule::DestinationConfig};
+
+/// Somewhere exports are uploaded to, and downloaded back from.
+#[async_trait]
+pub trait StorageDestination: Send + Sync {
+    async fn upload(&self, name: &str, content: Vec<u8>) -> Result<(), ExportError>;
+
+    /// The size of an upload in bytes.
+    async fn size(&self, name: &str) -> Result<u64, ExportError>;
+
+    /// Reads the bytes of `range` of an upload, which must be within its
+    /// size.
+    async fn read_range(&self, name: &str, range: Range<u64>) -> Result<Vec<u8>, ExportError>;
+}
+
+/// A destination backed by an [`ObjectStore`], which covers both S3 and
+/// WebDAV as well as the in-memory store of the tests.
+pub struct ObjectStoreDestination {
+    store: Arc<dyn ObjectStore>,
+    prefix: Option<String>,
+}
+
+impl ObjectStoreDestination {
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: Option<String>) -> Self {
+        Self { store, prefix }
+    }
+
+    /// Builds the destination a configuration describes, if it has one.
+    pub fn from_config(config: &DestinationConfig) -> Result<Option<Self>, ExportError> {
+        let invalid = |e: object_store::Error| ExportError::InvalidDestination(e.to_string());
+        let destination = match config {
+            DestinationConfig::S3 {
+                bucket,
+                region,
+                endpoint,
+                prefix,
+                access_key_id,
+                secret_access_key,
+            } => {
+                let (Some(access_key_id), Some(secret_access_key)) =
+                    (access_key_id, secret_access_key)
+                else {
+                    return Err(ExportError::InvalidDestination(
+                        "An S3 destination needs an access key.".into(),
+                    ));
+                };
+                let mut builder = AmazonS3Builder::new()
+                    .with_bucket_name(bucket)
+                    .with_region(region)
+                    .with_access_key_id(access_key_id)
+                    .with_secret_access_key(secret_access_key);
+                if let Some(endpoint) = endpoint {
+                    builder = builder
+                        .with_endpoint(endpoint)
+                        .with_allow_http(endpoint.starts_with("http://"));
+                }
+                Self::new(Arc::new(builder.build().map_err(invalid)?), prefix.clone())
+            }
+            DestinationConfig::Webdav {
+                url,
+                username,
+                password,
+            } => {
+                let mut options = ClientOptions::new();
+                if let Some(username) = username {
+                    let credentials = BASE64_STANDARD.encode(format!(
+                        "{username}:{}",
+                        password.as_deref().unwrap_or_default()
+                    ));
+                    let mut value = HeaderValue::from_str(&format!("Basic {credentials}"))
+                        .map_err(|e| ExportError::InvalidDestination(e.to_string()))?;
+                    value.set_sensitive(true);
+                    let mut headers = HeaderMap::new();
+                    headers.insert(AUTHORIZATION, value);
+                    options = options.with_default_headers(headers);
+                }
+                let store = HttpBuilder::new()
+                    .with_url(url)
+                    .with_client_options(options)
+                    .build()
+                    .map_err(invalid)?;
+                Self::new(Arc::new(store), None)
+            }
+            DestinationConfig::None => return Ok(None),
+        };
+        Ok(Some(destination))
+    }
+
+    fn location(&self, name: &str) -> Path {
+        match &self.prefix {
+            Some(prefix) => Path::from(format!("{}/{name}", prefix.trim_end_matches('/'))),
+            None => Path::from(name),
+        }
+    }
+}
+
+/// The error of reading an object, which is missing rather than failed if
+/// it isn't there.
+fn download_error(e: object_store::Error) -> ExportError {
+    match e {
+        object_store::Error::NotFound { .. } => ExportError::NotFound,
+        e => ExportError::Download(e.to_string()),
+    }
+}
+
+#[async_trait]
+impl StorageDestination for ObjectStoreDestination {
+    async fn upload(&self, name: &str, content: Vec<u8>) -> Result<(), ExportError> {
+        self.store
+            .put(&self.location(name), PutPayload::from(content))
+            .await
+            .map_err(|e| ExportError::Upload(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn size(&self, name: &str) -> Result<u64, ExportError> {
+        let meta = self
+            .store
+            .head(&self.location(name))
+            .await
+            .map_err(download_error)?;
+        Ok(meta.size)
+    }
+
+    async fn read_range(&self, name: &str, range: Range<u64>) -> Result<Vec<u8>, ExportError> {
+        if range.is_empty() {
+            return Ok(vec![]);
+        }
+        let content = self
+            .store
+            .get_range(&self.location(name), range)
+            .await
+            .map_err(download_error)?;
+        Ok(content.to_vec())
+    }
+}
+
+/// A destination in a directory the server can write to, such as a volume
+/// mounted into its container.
+pub struct FileSystemDestination {
+    root: PathBuf,
+}
+
+impl FileSystemDestination {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The path of an upload, which is only ever directly under the root.
+    fn path(&self, name: &str) -> Result<PathBuf, ExportError> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(ExportError::InvalidDestination(format!(
+                "`{name}` is not a valid file name."
+            )));
+        }
+        Ok(self.root.join(name))
+    }
+}
+
+/// The error of reading a file, which is missing rather than failed if it
+/// isn't there.
+fn read_error(e: std::io::Error) -> ExportError {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => ExportError::NotFound,
+        _ => ExportError::Download(e.to_string()),
+    }
+}
+
+#[async_trait]
+impl StorageDestination for FileSystemDestination {
+    async fn upload(&self, name: &str, content: Vec<u8>) -> Result<(), ExportError> {
+        let path = self.path(name)?;
+        let upload = async {
+            fs::create_dir_all(&self.root).await?;
+            // Written aside and renamed, so a download never sees half an
+            // upload.
+            let partial = self.root.join(format!(".{name}.partial"));
+            fs::write(&partial, content).await?;
+            fs::rename(&partial, &path).await
+        };
+        upload.await.map_err(|e| ExportError::Upload(e.to_string()))
+    }
+
+    async fn size(&self, name: &str) -> Result<u64, ExportError> {
+        let meta = fs::metadata(self.path(name)?).await.map_err(read_error)?;
+        Ok(meta.len())
+    }
+
+    async fn read_range(&self, name: &str, range: Range<u64>) -> Result<Vec<u8>, ExportError> {
+        let mut file = fs::File::open(self.path(name)?).await.map_err(read_error)?;
+        file.seek(SeekFrom::Start(range.start))
+            .await
+            .map_err(read_error)?;
+        let mut content = vec![0; (range.end - range.start) as usize];
+        file.read_exact(&mut content).await.map_err(read_error)?;
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use object_store::memory::InMemory;
+    use uuid::Uuid;
+
+    use super::*;
+
+    async fn assert_reads_back_in_ranges(destination: &dyn StorageDestination) {
+        let blob = (0..1_000).map(|x| (x % 251) as u8).collect::<Vec<_>>();
+        destination
+            .upload("export.json", blob.clone())
+            .await
+            .unwrap();
+        assert_eq!(destination.size("export.json").await.unwrap(), 1_000);
+        let mut reassembled = vec![];
+        for range in [0..400, 400..800, 800..1_000] {
+            reassembled.extend(destination.read_range("export.json", range).await.unwrap());
+        }
+        assert_eq!(reassembled, blob);
+        assert!(matches!(
+            destination.size("missing.json").await,
+            Err(ExportError::NotFound)
+        ));
+        assert!(matches!(
+            destination.read_range("missing.json", 0..1).await,
+            Err(ExportError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_reads_objects_back_in_ranges() {
+        let destination =
+            ObjectStoreDestination::new(Arc::new(InMemory::new()), Some("treasury/".into()));
+        assert_reads_back_in_ranges(&destination).await;
+    }
+
+    #[tokio::test]
+    async fn it_reads_files_back_in_ranges() {
+        let root = std::env::temp_dir().join(format!("treasury-{}", Uuid::new_v4()));
+        let destination = FileSystemDestination::new(&root);
+        assert_reads_back_in_ranges(&destination).await;
+        assert!(matches!(
+            destination.upload("../escape.json", vec![]).await,
+            Err(ExportError::InvalidDestination(_))
+        ));
+        fs::remove_dir_all(root).await.unwrap();
+    }
+}
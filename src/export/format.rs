@@ -0,0 +1,381 @@
+//! How the values of CSV exports are written for the locale of the reader.
+//!
+//! Spreadsheets read CSV with the conventions of the locale they run in.
+//! Excel in Germany splits fields on `;`, reads `,` as the decimal separator
+//! and expects the day first, while Excel in Korea reads the conventions of
+//! the US with the year first. The [`CsvOptions`] of an export come from the
+//! [`ExportOptionsRequest`] of the request, then from the locale the user
+//! prefers, and otherwise are locale neutral.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::{
+    export::ExportError,
+    schema::{export_schedule::ExportOptionsRequest, text::escape_output},
+};
+
+/// The byte order mark Excel needs to read a CSV as UTF-8.
+pub const BOM: &str = "\u{feff}";
+
+/// The locales exports can be formatted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    EnUs,
+    KoKr,
+    DeDe,
+}
+
+impl Locale {
+    pub const ALL: [Self; 3] = [Self::EnUs, Self::KoKr, Self::DeDe];
+
+    /// The BCP 47 tag of the locale.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::EnUs => "en-US",
+            Self::KoKr => "ko-KR",
+            Self::DeDe => "de-DE",
+        }
+    }
+
+    fn decimal_separator(self) -> char {
+        match self {
+            Self::EnUs | Self::KoKr => '.',
+            Self::DeDe => ',',
+        }
+    }
+
+    fn delimiter(self) -> Delimiter {
+        match self {
+            Self::EnUs | Self::KoKr => Delimiter::Comma,
+            Self::DeDe => Delimiter::Semicolon,
+        }
+    }
+
+    fn date_format(self) -> DateFormat {
+        match self {
+            Self::EnUs => DateFormat::MonthDayYear,
+            Self::KoKr => DateFormat::YearMonthDay,
+            Self::DeDe => DateFormat::DayMonthYear,
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.tag() == s)
+            .ok_or_else(|| {
+                ExportError::InvalidOption(format!(
+                    "`{s}` is not a supported locale, use one of {}.",
+                    Self::ALL.map(Locale::tag).join(", ")
+                ))
+            })
+    }
+}
+
+/// What separates the fields of a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    Comma,
+    Semicolon,
+    Tab,
+}
+
+impl Delimiter {
+    pub fn as_char(self) -> char {
+        match self {
+            Self::Comma => ',',
+            Self::Semicolon => ';',
+            Self::Tab => '\t',
+        }
+    }
+}
+
+impl FromStr for Delimiter {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "comma" | "," => Ok(Self::Comma),
+            "semicolon" | ";" => Ok(Self::Semicolon),
+            "tab" | "\t" => Ok(Self::Tab),
+            _ => Err(ExportError::InvalidOption(format!(
+                "`{s}` is not a supported delimiter, use one of comma, semicolon, tab."
+            ))),
+        }
+    }
+}
+
+/// How dates are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateFormat {
+    /// The full timestamp in UTC, like `2025-04-29T09:30:00+00:00`
+    Rfc3339,
+    /// `YYYY-MM-DD`
+    YearMonthDay,
+    /// `DD.MM.YYYY`
+    DayMonthYear,
+    /// `MM/DD/YYYY`
+    MonthDayYear,
+}
+
+impl DateFormat {
+    pub const ALL: [Self; 4] = [
+        Self::Rfc3339,
+        Self::YearMonthDay,
+        Self::DayMonthYear,
+        Self::MonthDayYear,
+    ];
+
+    /// The name of the format in the `date_format` option.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rfc3339 => "rfc3339",
+            Self::YearMonthDay => "YYYY-MM-DD",
+            Self::DayMonthYear => "DD.MM.YYYY",
+            Self::MonthDayYear => "MM/DD/YYYY",
+        }
+    }
+
+    pub fn format(self, date: DateTime<Utc>) -> String {
+        match self {
+            Self::Rfc3339 => date.to_rfc3339(),
+            Self::YearMonthDay => date.format("%Y-%m-%d").to_string(),
+            Self::DayMonthYear => date.format("%d.%m.%Y").to_string(),
+            Self::MonthDayYear => date.format("%m/%d/%Y").to_string(),
+        }
+    }
+}
+
+impl FromStr for DateFormat {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| {
+                ExportError::InvalidOption(format!(
+                    "`{s}` is not a supported date format, use one of {}.",
+                    Self::ALL.map(DateFormat::name).join(", ")
+                ))
+            })
+    }
+}
+
+/// How the values of a CSV export are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub decimal_separator: char,
+    pub delimiter: Delimiter,
+    pub date_format: DateFormat,
+    /// Whether the export starts with a [`BOM`]
+    pub bom: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            delimiter: Delimiter::Comma,
+            date_format: DateFormat::Rfc3339,
+            bom: false,
+        }
+    }
+}
+
+impl From<Locale> for CsvOptions {
+    fn from(value: Locale) -> Self {
+        Self {
+            decimal_separator: value.decimal_separator(),
+            delimiter: value.delimiter(),
+            date_format: value.date_format(),
+            bom: false,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// The options `request` asks for, with the ones it leaves out taken
+    /// from the locale it names, or else from `preferred_locale`.
+    pub fn resolve(
+        request: &ExportOptionsRequest,
+        preferred_locale: Option<&str>,
+    ) -> Result<Self, ExportError> {
+        let locale = match request.locale.as_deref() {
+            Some(locale) => Some(Locale::from_str(locale)?),
+            // A preference is validated when it is set.
+            None => preferred_locale.and_then(|x| Locale::from_str(x).ok()),
+        };
+        let fallback = locale.map(Self::from).unwrap_or_default();
+        Ok(Self {
+            decimal_separator: fallback.decimal_separator,
+            delimiter: match request.delimiter.as_deref() {
+                Some(delimiter) => Delimiter::from_str(delimiter)?,
+                None => fallback.delimiter,
+            },
+            date_format: match request.date_format.as_deref() {
+                Some(date_format) => DateFormat::from_str(date_format)?,
+                None => fallback.date_format,
+            },
+            bom: request.bom.unwrap_or(fallback.bom),
+        })
+    }
+
+    pub fn quantity(&self, quantity: Decimal) -> String {
+        let quantity = quantity.to_string();
+        if self.decimal_separator == '.' {
+            quantity
+        } else {
+            quantity.replace('.', &self.decimal_separator.to_string())
+        }
+    }
+
+    pub fn date(&self, date: DateTime<Utc>) -> String {
+        self.date_format.format(date)
+    }
+
+    /// Quotes a field when it needs to be, after escaping any control
+    /// characters it was stored with.
+    pub fn field(&self, value: &str) -> String {
+        let value = escape_output(value);
+        if value.contains([self.delimiter.as_char(), '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.into_owned()
+        }
+    }
+
+    /// Writes a row of fields, each quoted as needed.
+    pub fn row<S: AsRef<str>>(&self, csv: &mut String, fields: &[S]) {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                csv.push(self.delimiter.as_char());
+            }
+            csv.push_str(&self.field(field.as_ref()));
+        }
+        csv.push('\n');
+    }
+
+    /// Starts a document with its header row.
+    pub fn start(&self, header: &[&str]) -> String {
+        let mut csv = String::new();
+        if self.bom {
+            csv.push_str(BOM);
+        }
+        self.row(&mut csv, header);
+        csv
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn request(
+        locale: Option<&str>,
+        delimiter: Option<&str>,
+        date_format: Option<&str>,
+    ) -> ExportOptionsRequest {
+        ExportOptionsRequest {
+            locale: locale.map(str::to_owned),
+            delimiter: delimiter.map(str::to_owned),
+            date_format: date_format.map(str::to_owned),
+            bom: None,
+        }
+    }
+
+    #[test]
+    fn it_takes_the_options_left_out_from_the_locale() {
+        assert_eq!(
+            CsvOptions::resolve(&request(Some("de-DE"), None, None), None).unwrap(),
+            CsvOptions {
+                decimal_separator: ',',
+                delimiter: Delimiter::Semicolon,
+                date_format: DateFormat::DayMonthYear,
+                bom: false,
+            }
+        );
+        assert_eq!(
+            CsvOptions::resolve(&request(None, Some("tab"), None), Some("ko-KR")).unwrap(),
+            CsvOptions {
+                decimal_separator: '.',
+                delimiter: Delimiter::Tab,
+                date_format: DateFormat::YearMonthDay,
+                bom: false,
+            }
+        );
+        assert_eq!(
+            CsvOptions::resolve(&request(None, None, None), None).unwrap(),
+            CsvOptions::default()
+        );
+    }
+
+    #[test]
+    fn it_rejects_options_outside_the_allowlists() {
+        assert_eq!(
+            CsvOptions::resolve(&request(Some("fr-FR"), None, None), None)
+                .unwrap_err()
+                .to_string(),
+            "`fr-FR` is not a supported locale, use one of en-US, ko-KR, de-DE."
+        );
+        assert!(CsvOptions::resolve(&request(None, Some("|"), None), None).is_err());
+        assert!(CsvOptions::resolve(&request(None, None, Some("%Y")), None).is_err());
+    }
+
+    #[test]
+    fn it_writes_rows_for_the_locale() {
+        let posted_at = Utc.with_ymd_and_hms(2025, 4, 29, 9, 30, 0).unwrap();
+        let quantity = Decimal::new(-125050, 2);
+        for (locale, expected) in [
+            (
+                Locale::KoKr,
+                "\u{feff}posted_at,quantity,description\n2025-04-29,-1250.50,\"Rent, March\"\n",
+            ),
+            (
+                Locale::DeDe,
+                "\u{feff}posted_at;quantity;description\n29.04.2025;-1250,50;Rent, March\n",
+            ),
+        ] {
+            let options = CsvOptions {
+                bom: true,
+                ..locale.into()
+            };
+            let mut csv = options.start(&["posted_at", "quantity", "description"]);
+            options.row(
+                &mut csv,
+                &[
+                    options.date(posted_at),
+                    options.quantity(quantity),
+                    "Rent, March".to_owned(),
+                ],
+            );
+            assert_eq!(csv.as_bytes(), expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn it_quotes_decimal_commas_under_a_comma_delimiter() {
+        let options = CsvOptions {
+            delimiter: Delimiter::Comma,
+            ..Locale::DeDe.into()
+        };
+        let mut csv = String::new();
+        options.row(&mut csv, &[options.quantity(Decimal::new(15, 1))]);
+        assert_eq!(csv, "\"1,5\"\n");
+    }
+}
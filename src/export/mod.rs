@@ -0,0 +1,428 @@
+//! Scheduled exports of the data of a user to storage they control.
+//!
+//! An [`ExportSchedule`] names the format, the [`Cadence`] and a sealed
+//! [`DestinationConfig`]. The scheduler started by [`spawn_scheduler`]
+//! wakes every minute and runs an [`ExportJob`] for each schedule that is
+//! due, which serializes the takeout of the user and uploads it to the
+//! [`StorageDestination`]. A failed upload is recorded on the schedule
+//! rather than retried, the next run is simply the next one due. CSV exports
+//! are written with the [`CsvOptions`] of the run, which default to the
+//! locale the user prefers.
+
+use std::{env::var, str::FromStr, sync::Arc, sync::OnceLock, time::Duration};
+
+use aes_gcm_siv::{Aes256GcmSiv, KeyInit, Nonce, aead::Aead};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::{error, info, warn};
+
+use crate::{
+    api::{ApiError, ClientErrorCode},
+    config::{Feature, FeatureFlags},
+    model::{
+        account::AccountFilter,
+        export_schedule::{
+            DestinationConfig, ExportFormat, ExportRun, ExportRunStatus, ExportSchedule,
+            ExportScheduleFilter,
+        },
+        transaction::TransactionFilter,
+        user::UserId,
+    },
+    resource::{
+        GetListRepository, MAX_LIMIT, account_repository::AccountRepository, deadline,
+        export_schedule_repository::ExportScheduleRepository,
+        transaction_repository::TransactionRepository,
+    },
+    schema::{
+        GetList, ResolvedDateRange, account::AccountResponse, text::escape_output,
+        transaction::TransactionResponse,
+    },
+    service::ServiceError,
+};
+
+pub mod cadence;
+pub mod destination;
+pub mod format;
+
+pub use cadence::Cadence;
+pub use destination::{FileSystemDestination, ObjectStoreDestination, StorageDestination};
+pub use format::CsvOptions;
+
+/// How often the scheduler looks for due exports.
+pub const SCHEDULER_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Error)]
+pub enum ExportError {
+    #[error("`{0}` is not a valid cadence.")]
+    InvalidCadence(String),
+    #[error("{0}")]
+    InvalidOption(String),
+    #[error("The destination is invalid: {0}")]
+    InvalidDestination(String),
+    #[error("The schedule has no destination.")]
+    NoDestination,
+    #[error("The upload failed: {0}")]
+    Upload(String),
+    #[error("The export is not at the destination.")]
+    NotFound,
+    #[error("The download failed: {0}")]
+    Download(String),
+    #[error("The data key is missing or invalid.")]
+    DataKey,
+    #[error("The destination configuration could not be sealed or opened.")]
+    Seal,
+    #[error("The export could not be serialized.")]
+    Serialize,
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<ExportError> for ApiError {
+    fn from(value: ExportError) -> Self {
+        match value {
+            ExportError::Service(e) => Self::Service(e),
+            ExportError::NotFound => Self::NotFound,
+            e @ (ExportError::DataKey
+            | ExportError::Seal
+            | ExportError::Serialize
+            | ExportError::Upload(_)
+            | ExportError::Download(_)) => {
+                error!("{e}");
+                Self::ServerError
+            }
+            e => Self::client(ClientErrorCode::InvalidRequest, e.to_string()),
+        }
+    }
+}
+
+static DATA_KEY: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+
+/// The key destination configurations are sealed with, the base64 of 32
+/// bytes in `DATA_KEY`. Tests without one get a key for the process.
+fn data_key() -> Result<&'static [u8], ExportError> {
+    DATA_KEY
+        .get_or_init(|| match var("DATA_KEY") {
+            Ok(key) => BASE64_STANDARD
+                .decode(key)
+                .ok()
+                .filter(|key| key.len() == 32),
+            #[cfg(test)]
+            Err(_) => Some(rand::random::<[u8; 32]>().to_vec()),
+            #[cfg(not(test))]
+            Err(_) => None,
+        })
+        .as_deref()
+        .ok_or(ExportError::DataKey)
+}
+
+/// Seals a destination configuration with the data key, as the nonce
+/// followed by the ciphertext.
+pub fn seal(config: &DestinationConfig) -> Result<Vec<u8>, ExportError> {
+    let key = Aes256GcmSiv::new_from_slice(data_key()?).map_err(|_| ExportError::DataKey)?;
+    let nonce_bytes = rand::random::<[u8; 12]>();
+    let plaintext = serde_json::to_vec(config).map_err(|_| ExportError::Seal)?;
+    let ciphertext = key
+        .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_slice())
+        .map_err(|_| ExportError::Seal)?;
+    Ok([nonce_bytes.as_slice(), &ciphertext].concat())
+}
+
+/// Opens a destination configuration sealed by [`seal`].
+pub fn open(sealed: &[u8]) -> Result<DestinationConfig, ExportError> {
+    let key = Aes256GcmSiv::new_from_slice(data_key()?).map_err(|_| ExportError::DataKey)?;
+    let (nonce_bytes, ciphertext) = sealed.split_at_checked(12).ok_or(ExportError::Seal)?;
+    let plaintext = key
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| ExportError::Seal)?;
+    serde_json::from_slice(&plaintext).map_err(|_| ExportError::Seal)
+}
+
+#[derive(Debug, Serialize)]
+struct Takeout {
+    accounts: Vec<AccountResponse<GetList>>,
+    transactions: Vec<TransactionResponse<GetList>>,
+}
+
+/// Serializes all the accounts of a user, and their transactions posted in
+/// `posted`.
+///
+/// The JSON format holds both in the shape the API returns them in, while
+/// the CSV format has one row per transaction, written with `csv_options`.
+pub async fn takeout(
+    connection_pool: &PgPool,
+    user_id: UserId,
+    format: ExportFormat,
+    csv_options: CsvOptions,
+    posted: ResolvedDateRange,
+) -> Result<Vec<u8>, ExportError> {
+    let mut accounts = vec![];
+    loop {
+        let page = AccountRepository
+            .get_list(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                accounts.len() as i64,
+                Some(MAX_LIMIT),
+                AccountFilter {
+                    user_id: Some(user_id),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        let done = (page.len() as i64) < MAX_LIMIT;
+        accounts.extend(page.into_iter().map(AccountResponse::<GetList>::from));
+        if done {
+            break;
+        }
+    }
+    let mut transactions = vec![];
+    loop {
+        let page = TransactionRepository
+            .get_list_with_user_id(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                transactions.len() as i64,
+                Some(MAX_LIMIT),
+                user_id,
+                None,
+                TransactionFilter {
+                    posted_from: posted.from,
+                    posted_before: posted.to,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        let done = (page.len() as i64) < MAX_LIMIT;
+        transactions.extend(page.into_iter().map(TransactionResponse::<GetList>::from));
+        if done {
+            break;
+        }
+    }
+
+    match format {
+        ExportFormat::Json => serde_json::to_vec(&Takeout {
+            accounts,
+            transactions,
+        })
+        .map_err(|_| ExportError::Serialize),
+        ExportFormat::Csv => {
+            let mut csv = csv_options.start(&[
+                "id",
+                "posted_at",
+                "account_id",
+                "asset_id",
+                "quantity",
+                "description",
+                "notes",
+            ]);
+            for transaction in transactions {
+                csv_options.row(
+                    &mut csv,
+                    &[
+                        transaction.id.0.to_string(),
+                        csv_options.date(transaction.posted_at),
+                        transaction.account_id.0.to_string(),
+                        transaction.asset_id.0.to_string(),
+                        csv_options.quantity(transaction.quantity),
+                        transaction.description.unwrap_or_default(),
+                        transaction.notes.unwrap_or_default(),
+                    ],
+                );
+            }
+            Ok(csv.into_bytes())
+        }
+    }
+}
+
+/// The CSV options a user gets when they ask for none, from the locale they
+/// prefer.
+pub async fn preferred_csv_options(
+    connection_pool: &PgPool,
+    user_id: UserId,
+) -> Result<CsvOptions, ExportError> {
+    let user_preference = UserPreferenceRepository
+        .get_by_user_id(
+            deadline::begin(&connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            user_id,
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    CsvOptions::resolve(
+        &ExportOptionsRequest::default(),
+        user_preference.and_then(|x| x.locale).as_deref(),
+    )
+}
+
+/// Exports the data of a user to the destination of one schedule.
+pub struct ExportJob {
+    pub export_schedule: ExportSchedule,
+    /// How a CSV export is written, the preferred options of the user if
+    /// none
+    pub csv_options: Option<CsvOptions>,
+    /// When the transactions exported were posted, all of them if open
+    pub posted: ResolvedDateRange,
+}
+
+impl ExportJob {
+    /// The name the export is uploaded under.
+    pub fn file_name(&self, ran_at: DateTime<Utc>) -> String {
+        format!(
+            "treasury-export-{}.{}",
+            ran_at.format("%Y%m%dT%H%M%SZ"),
+            self.export_schedule.format.extension()
+        )
+    }
+
+    /// Produces the export, uploads it and records the outcome. Failures of
+    /// the export itself are recorded on the schedule rather than returned.
+    pub async fn run(
+        self,
+        connection_pool: &PgPool,
+        destination: &dyn StorageDestination,
+        ran_at: DateTime<Utc>,
+    ) -> Result<ExportSchedule, ApiError> {
+        let name = self.file_name(ran_at);
+        let csv_options = match self.csv_options {
+            Some(csv_options) => Ok(csv_options),
+            None => preferred_csv_options(connection_pool, self.export_schedule.user_id).await,
+        };
+        let result = match csv_options {
+            Ok(csv_options) => {
+                takeout(
+                    connection_pool,
+                    self.export_schedule.user_id,
+                    self.export_schedule.format,
+                    csv_options,
+                    self.posted,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        let result = match result {
+            Ok(content) => destination.upload(&name, content).await,
+            Err(e) => Err(e),
+        };
+        let run = match result {
+            Ok(()) => {
+                info!("Exported schedule {} as `{name}`.", self.export_schedule.id);
+                ExportRun {
+                    ran_at,
+                    status: ExportRunStatus::Succeeded,
+                    error: None,
+                }
+            }
+            Err(e) => {
+                warn!("Export of schedule {} failed: {e}", self.export_schedule.id);
+                ExportRun {
+                    ran_at,
+                    status: ExportRunStatus::Failed,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        let export_schedule = ExportScheduleRepository
+            .record_run(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                self.export_schedule.id,
+                run,
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        Ok(export_schedule)
+    }
+}
+
+/// Runs the export of every schedule with a destination that is due at
+/// `now`, returning how many ran.
+pub async fn run_due(connection_pool: &PgPool, now: DateTime<Utc>) -> Result<usize, ApiError> {
+    let mut export_schedules = vec![];
+    loop {
+        let page = ExportScheduleRepository
+            .get_list(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                export_schedules.len() as i64,
+                Some(MAX_LIMIT),
+                ExportScheduleFilter {
+                    with_destination: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        let done = (page.len() as i64) < MAX_LIMIT;
+        export_schedules.extend(page);
+        if done {
+            break;
+        }
+    }
+
+    let mut ran = 0;
+    for export_schedule in export_schedules {
+        let Ok(cadence) = Cadence::from_str(&export_schedule.cadence) else {
+            warn!(
+                "Skipping schedule {} with the invalid cadence {:?}.",
+                export_schedule.id, export_schedule.cadence
+            );
+            continue;
+        };
+        let since = export_schedule
+            .last_run_at
+            .unwrap_or(export_schedule.created_at);
+        if !cadence.is_due(since, now) {
+            continue;
+        }
+        let destination = match open(&export_schedule.destination_config)
+            .and_then(|config| ObjectStoreDestination::from_config(&config))
+        {
+            Ok(Some(destination)) => destination,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!(
+                    "Skipping schedule {} without a usable destination: {e}",
+                    export_schedule.id
+                );
+                continue;
+            }
+        };
+        ExportJob {
+            export_schedule,
+            csv_options: None,
+            posted: ResolvedDateRange::default(),
+        }
+        .run(connection_pool, &destination, now)
+        .await?;
+        ran += 1;
+    }
+    Ok(ran)
+}
+
+/// Starts looking for due exports every [`SCHEDULER_INTERVAL`], while
+/// export schedules are enabled.
+pub fn spawn_scheduler(connection_pool: Arc<PgPool>, features: FeatureFlags) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !features.is_enabled(Feature::ExportSchedules) {
+                continue;
+            }
+            if let Err(e) = run_due(&connection_pool, Utc::now()).await {
+                error!("Failed to run the due exports: {e}");
+            }
+        }
+    });
+}
@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+
+use crate::extraction::{ExtractionError, ReceiptCandidates, ReceiptExtractor, parse_receipt_text};
+
+pub const NAME: &str = "fake";
+
+/// Treats PDF attachments as a header line followed by already recognized
+/// text, so tests can drive the parsing without OCR.
+pub struct FakeExtractor;
+
+#[async_trait]
+impl ReceiptExtractor for FakeExtractor {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn extract(
+        &self,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<ReceiptCandidates, ExtractionError> {
+        if content_type != "application/pdf" {
+            return Err(ExtractionError::UnsupportedContentType(
+                content_type.to_owned(),
+            ));
+        }
+        let text =
+            std::str::from_utf8(content).map_err(|e| ExtractionError::Extractor(e.to_string()))?;
+        let (_, text) = text.split_once('\n').unwrap_or_default();
+        Ok(parse_receipt_text(text))
+    }
+}
@@ -0,0 +1,181 @@
+//! The extraction of receipt details from attachments.
+//!
+//! A [`ReceiptExtractor`] reads the total, date and merchant off an uploaded
+//! file, and an [`ExtractionJob`] records what it found so clients can offer
+//! the candidates when editing the transaction. Extractors failing never
+//! fails the upload, the extraction is only marked as failed.
+
+use std::{env::var, sync::OnceLock, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use sqlx::PgPool;
+use thiserror::Error;
+use tokio::time::timeout;
+use tracing::warn;
+
+use crate::{
+    api::ApiError,
+    model::attachment::{
+        Attachment, AttachmentExtraction, AttachmentExtractionCreate, ExtractionStatus,
+    },
+    resource::{attachment_repository::AttachmentRepository, deadline},
+    service::ServiceError,
+};
+
+#[cfg(test)]
+pub mod fake;
+pub mod noop;
+#[cfg(feature = "ocr")]
+pub mod tesseract;
+
+/// How long an extractor may take before the extraction is marked failed.
+pub const EXTRACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Error)]
+pub enum ExtractionError {
+    #[error("`{0}` files are not supported.")]
+    UnsupportedContentType(String),
+    #[error("The extractor failed: {0}")]
+    Extractor(String),
+    #[error("The extractor took too long.")]
+    Timeout,
+}
+
+/// The details read off a receipt. Any of them may be missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReceiptCandidates {
+    /// The total, in the smallest unit printed on the receipt
+    pub quantity: Option<i64>,
+    pub posted_at: Option<DateTime<Utc>>,
+    pub merchant: Option<String>,
+}
+
+#[async_trait]
+pub trait ReceiptExtractor: Send + Sync {
+    /// The name recorded with each extraction.
+    fn name(&self) -> &'static str;
+
+    async fn extract(
+        &self,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<ReceiptCandidates, ExtractionError>;
+}
+
+static EXTRACTOR: OnceLock<String> = OnceLock::new();
+
+/// The extractor named by `RECEIPT_EXTRACTOR`, defaulting to the no-op one.
+pub fn extractor() -> Box<dyn ReceiptExtractor> {
+    let name = EXTRACTOR.get_or_init(|| var("RECEIPT_EXTRACTOR").unwrap_or_default());
+    match name.as_str() {
+        #[cfg(feature = "ocr")]
+        tesseract::NAME => Box::new(tesseract::TesseractExtractor::from_env()),
+        "" | noop::NAME => Box::new(noop::NoopExtractor),
+        name => {
+            warn!("Unknown receipt extractor `{name}`, extracting nothing.");
+            Box::new(noop::NoopExtractor)
+        }
+    }
+}
+
+/// Reads receipt details out of OCR text.
+///
+/// The merchant is taken to be the first line, the date the first
+/// `YYYY-MM-DD` like token and the total the last number on the first line
+/// mentioning a total.
+pub fn parse_receipt_text(text: &str) -> ReceiptCandidates {
+    let mut lines = text.lines().map(str::trim).filter(|x| !x.is_empty());
+    let merchant = lines.next().map(str::to_owned);
+    let posted_at = text
+        .split_whitespace()
+        .find_map(|token| {
+            let token = token.replace(['.', '/'], "-");
+            NaiveDate::parse_from_str(&token, "%Y-%m-%d").ok()
+        })
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc());
+    let quantity = text
+        .lines()
+        .find(|line| {
+            let line = line.to_lowercase();
+            line.contains("total") || line.contains("합계")
+        })
+        .and_then(|line| {
+            line.split_whitespace().rev().find_map(|token| {
+                let digits = token
+                    .trim_matches(|c: char| !c.is_ascii_digit())
+                    .replace([',', '.'], "");
+                if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                digits.parse::<i64>().ok()
+            })
+        });
+    ReceiptCandidates {
+        quantity,
+        posted_at,
+        merchant,
+    }
+}
+
+/// Extracts the receipt details of one attachment.
+pub struct ExtractionJob {
+    pub attachment: Attachment,
+    pub content: Vec<u8>,
+}
+
+impl ExtractionJob {
+    /// Runs the extractor and records the outcome. Extractor errors are
+    /// recorded on the extraction rather than returned.
+    pub async fn run(
+        self,
+        connection_pool: &PgPool,
+        extractor: &dyn ReceiptExtractor,
+    ) -> Result<AttachmentExtraction, ApiError> {
+        let result = match timeout(
+            EXTRACTION_TIMEOUT,
+            extractor.extract(&self.attachment.content_type, &self.content),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(ExtractionError::Timeout),
+        };
+        let create_model = match result {
+            Ok(candidates) => AttachmentExtractionCreate {
+                attachment_id: self.attachment.id,
+                extractor: extractor.name().to_owned(),
+                status: ExtractionStatus::Complete,
+                quantity: candidates.quantity,
+                posted_at: candidates.posted_at,
+                merchant: candidates.merchant,
+                error: None,
+            },
+            Err(e) => {
+                warn!(
+                    "Extraction of attachment {} failed: {e}",
+                    self.attachment.id
+                );
+                AttachmentExtractionCreate {
+                    attachment_id: self.attachment.id,
+                    extractor: extractor.name().to_owned(),
+                    status: ExtractionStatus::Failed,
+                    quantity: None,
+                    posted_at: None,
+                    merchant: None,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        let extraction = AttachmentRepository
+            .upsert_extraction(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                create_model,
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        Ok(extraction)
+    }
+}
@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+
+use crate::extraction::{ExtractionError, ReceiptCandidates, ReceiptExtractor};
+
+pub const NAME: &str = "noop";
+
+/// Extracts nothing, for deployments without OCR.
+pub struct NoopExtractor;
+
+#[async_trait]
+impl ReceiptExtractor for NoopExtractor {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn extract(
+        &self,
+        _content_type: &str,
+        _content: &[u8],
+    ) -> Result<ReceiptCandidates, ExtractionError> {
+        Ok(ReceiptCandidates::default())
+    }
+}
@@ -0,0 +1,71 @@
+use std::{env::var, process::Stdio};
+
+use async_trait::async_trait;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::extraction::{ExtractionError, ReceiptCandidates, ReceiptExtractor, parse_receipt_text};
+
+pub const NAME: &str = "tesseract";
+
+/// The image types tesseract reads.
+const CONTENT_TYPES: [&str; 3] = ["image/png", "image/jpeg", "image/tiff"];
+
+/// Runs the `tesseract` binary over image attachments.
+pub struct TesseractExtractor {
+    /// The path of the binary, from `TESSERACT_PATH`
+    pub program: String,
+    /// The languages to recognize, from `TESSERACT_LANGUAGES`
+    pub languages: String,
+}
+
+impl TesseractExtractor {
+    pub fn from_env() -> Self {
+        Self {
+            program: var("TESSERACT_PATH").unwrap_or_else(|_| "tesseract".into()),
+            languages: var("TESSERACT_LANGUAGES").unwrap_or_else(|_| "eng+kor".into()),
+        }
+    }
+}
+
+#[async_trait]
+impl ReceiptExtractor for TesseractExtractor {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn extract(
+        &self,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<ReceiptCandidates, ExtractionError> {
+        if !CONTENT_TYPES.contains(&content_type) {
+            return Err(ExtractionError::UnsupportedContentType(
+                content_type.to_owned(),
+            ));
+        }
+        let mut child = Command::new(&self.program)
+            .args(["stdin", "stdout", "-l", &self.languages])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ExtractionError::Extractor(e.to_string()))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(content)
+                .await
+                .map_err(|e| ExtractionError::Extractor(e.to_string()))?;
+        }
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| ExtractionError::Extractor(e.to_string()))?;
+        if !output.status.success() {
+            return Err(ExtractionError::Extractor(
+                String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            ));
+        }
+        Ok(parse_receipt_text(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
@@ -0,0 +1,697 @@
+//! Daily fiat exchange rates pulled from a public HTTP source.
+//!
+//! The [`FxSource`] is configured by `FX_SOURCE_URL`, a URL template with
+//! `{date}`, `{base}` and `{api_key}` placeholders, along with `FX_API_KEY`
+//! and `FX_BASE_SYMBOL`. For a date it answers with the price of one unit of
+//! the base asset in other currencies, as `{"rates": {"KRW": 1400.5}}`.
+//! [`ingest`] writes one `asset_price` row per currency that names an
+//! existing asset, so running it again for a date updates the rates rather
+//! than adding more. The scheduler started by [`spawn_ingestion`] ingests
+//! the current date every [`INGESTION_INTERVAL`], and [`backfill`] catches
+//! up on a range of dates.
+//!
+//! A rate that changes the latest price of a pair fires the watchlist
+//! entries whose target it reaches, and those on the event stream channel
+//! are published to the streams of their users. Backfilling dates older
+//! than the latest price leaves it as it is, so old rates don't set off
+//! entries.
+
+use std::{collections::HashMap, env::var, sync::Arc, time::Duration};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rand::Rng;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    api::{
+        ApiError, ClientErrorCode,
+        event_hub::{Event, EventHub, WATCHLIST_FIRED},
+    },
+    config::{Feature, FeatureFlags},
+    model::{alert_rule::AlertChannel, watchlist_entry::WatchlistEntry},
+    resource::{
+        asset_price_repository::{AssetPriceRepository, AssetPriceUpsert},
+        deadline,
+        watchlist_entry_repository::WatchlistEntryRepository,
+    },
+    schema::watchlist_entry::WatchlistEntryGetResponse,
+    service::ServiceError,
+};
+
+/// How long a request to the source may take.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long connecting to the source may take.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How many times a request is tried before the date is given up on.
+pub const MAX_ATTEMPTS: u32 = 3;
+/// The delay before the first retry, doubled for each one after it.
+pub const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// How often the scheduler ingests the rates of the current date.
+pub const INGESTION_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// The most dates one backfill covers.
+pub const MAX_BACKFILL_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Error)]
+pub enum FxError {
+    #[error("Exchange rate ingestion is not configured.")]
+    NotConfigured,
+    #[error("The exchange rate source failed: {0}")]
+    Source(String),
+    #[error("No asset has the base symbol `{0}`.")]
+    UnknownBase(String),
+    #[error("The backfill must start before it ends and cover at most {MAX_BACKFILL_DAYS} days.")]
+    InvalidRange,
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<FxError> for ApiError {
+    fn from(value: FxError) -> Self {
+        match value {
+            FxError::Service(e) => Self::Service(e),
+            FxError::Source(e) => {
+                error!("{e}");
+                Self::ServerError
+            }
+            e => Self::client(ClientErrorCode::InvalidRequest, e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RatesResponse {
+    rates: HashMap<String, f64>,
+}
+
+/// Where the rates come from.
+#[derive(Debug, Clone)]
+pub struct FxSource {
+    url_template: String,
+    api_key: Option<String>,
+    base_symbol: String,
+    retry_base_delay: Duration,
+    http: reqwest::Client,
+}
+
+impl FxSource {
+    pub fn new(url_template: String, api_key: Option<String>, base_symbol: String) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .expect("Failed to build reqwest client");
+        Self {
+            url_template,
+            api_key,
+            base_symbol,
+            retry_base_delay: RETRY_BASE_DELAY,
+            http,
+        }
+    }
+
+    /// Reads `FX_SOURCE_URL`, `FX_API_KEY` and `FX_BASE_SYMBOL`, which
+    /// defaults to `USD`. Without a URL there is no source.
+    pub fn from_env() -> Result<Self, FxError> {
+        let url_template = var("FX_SOURCE_URL").map_err(|_| FxError::NotConfigured)?;
+        Ok(Self::new(
+            url_template,
+            var("FX_API_KEY").ok(),
+            var("FX_BASE_SYMBOL").unwrap_or_else(|_| "USD".to_owned()),
+        ))
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn with_retry_base_delay(self, retry_base_delay: Duration) -> Self {
+        Self {
+            retry_base_delay,
+            ..self
+        }
+    }
+
+    pub fn base_symbol(&self) -> &str {
+        &self.base_symbol
+    }
+
+    fn url(&self, date: NaiveDate) -> String {
+        self.url_template
+            .replace("{date}", &date.format("%Y-%m-%d").to_string())
+            .replace("{base}", &self.base_symbol)
+            .replace("{api_key}", self.api_key.as_deref().unwrap_or_default())
+    }
+
+    /// Fetches the rates of a date by symbol.
+    ///
+    /// Failures to connect, timeouts and responses that say to try again
+    /// are retried up to [`MAX_ATTEMPTS`] times, after a growing delay with
+    /// jitter so that many servers do not retry in step.
+    #[instrument(skip(self))]
+    pub async fn fetch(&self, date: NaiveDate) -> Result<HashMap<String, f64>, FxError> {
+        let url = self.url(date);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let error = match self.http.get(&url).send().await {
+                Ok(response) if response.status().is_success() => {
+                    return response
+                        .json::<RatesResponse>()
+                        .await
+                        .map(|x| x.rates)
+                        .map_err(|e| {
+                            FxError::Source(format!("Invalid response: {}", e.without_url()))
+                        });
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let error = FxError::Source(format!("The source responded with {status}."));
+                    if !(status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS) {
+                        return Err(error);
+                    }
+                    error
+                }
+                // The URL is left out of the errors, it may hold the API key.
+                Err(e) if e.is_connect() || e.is_timeout() => {
+                    FxError::Source(e.without_url().to_string())
+                }
+                Err(e) => return Err(FxError::Source(e.without_url().to_string())),
+            };
+            if attempt >= MAX_ATTEMPTS {
+                return Err(error);
+            }
+            let delay = self.retry_base_delay * 2u32.pow(attempt - 1);
+            let jitter = rand::rng().random_range(0..=self.retry_base_delay.as_millis() as u64);
+            warn!("Retrying the rates of {date}: {error}");
+            tokio::time::sleep(delay + Duration::from_millis(jitter)).await;
+        }
+    }
+}
+
+/// What ingesting the rates of a date did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayReport {
+    pub date: NaiveDate,
+    /// How many rates were written
+    pub written: usize,
+    /// The symbols of the rates that name no asset
+    pub skipped: Vec<String>,
+    /// How many watchlist entries the new rates fired
+    pub triggered: usize,
+}
+
+/// When the rates of a date apply.
+fn priced_at(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// Writes the rates of a date, replacing those already written for it.
+#[instrument(skip(connection_pool, events, source))]
+pub async fn ingest(
+    connection_pool: &PgPool,
+    events: &EventHub,
+    source: &FxSource,
+    date: NaiveDate,
+) -> Result<DayReport, FxError> {
+    let rates = source.fetch(date).await?;
+    let mut symbols = rates.keys().cloned().collect::<Vec<_>>();
+    symbols.push(source.base_symbol.clone());
+    let asset_ids = AssetPriceRepository
+        .asset_ids_by_symbol(
+            deadline::begin(&connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            &symbols,
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    let base_asset_id = *asset_ids
+        .get(&source.base_symbol)
+        .ok_or_else(|| FxError::UnknownBase(source.base_symbol.clone()))?;
+
+    let mut prices = vec![];
+    let mut skipped = vec![];
+    for (symbol, rate) in rates {
+        if symbol == source.base_symbol {
+            continue;
+        }
+        match asset_ids.get(&symbol) {
+            Some(quote_asset_id) if rate.is_finite() && rate > 0.0 => {
+                prices.push(AssetPriceUpsert {
+                    asset_id: base_asset_id,
+                    quote_asset_id: *quote_asset_id,
+                    priced_at: priced_at(date),
+                    rate,
+                });
+            }
+            Some(_) => warn!("Skipping the invalid rate {rate} of `{symbol}` on {date}."),
+            None => {
+                warn!("Skipping the rate of `{symbol}` on {date}, no asset has the symbol.");
+                skipped.push(symbol);
+            }
+        }
+    }
+    skipped.sort();
+    let pairs = prices
+        .iter()
+        .map(|x| (x.asset_id, x.quote_asset_id))
+        .collect::<Vec<_>>();
+    let before = AssetPriceRepository
+        .latest_rates(
+            deadline::begin(&connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            &pairs,
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    let written = AssetPriceRepository
+        .upsert(
+            deadline::begin(&connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            &prices,
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    info!("Wrote {written} rates of {date}.");
+    let after = AssetPriceRepository
+        .latest_rates(
+            deadline::begin(&connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            &pairs,
+        )
+        .await
+        .map_err(ServiceError::from)?;
+
+    let mut triggered = 0;
+    for (pair, rate) in after {
+        let previous = before.get(&pair).copied();
+        if previous == Some(rate) {
+            continue;
+        }
+        let entries = WatchlistEntryRepository
+            .evaluate(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                pair.0,
+                pair.1,
+                previous,
+                rate,
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        triggered += entries.len();
+        dispatch_price_alerts(events, &entries);
+    }
+    Ok(DayReport {
+        date,
+        written,
+        skipped,
+        triggered,
+    })
+}
+
+/// Sends out the notifications of the watchlist entries that fired on the
+/// channels they chose. Like the alerts of alert rules, those on the event
+/// stream are published to the streams their users have open, while the
+/// others are only logged until their channels have senders.
+fn dispatch_price_alerts(events: &EventHub, watchlist_entries: &[WatchlistEntry]) {
+    for watchlist_entry in watchlist_entries {
+        info!(
+            watchlist_entry_id = %watchlist_entry.id,
+            user_id = %watchlist_entry.user_id,
+            channel = ?watchlist_entry.channel,
+            price = ?watchlist_entry.triggered_price,
+            "Watchlist entry fired"
+        );
+        if watchlist_entry.channel != AlertChannel::Sse {
+            continue;
+        }
+        let response = WatchlistEntryGetResponse::from(watchlist_entry.clone());
+        match Event::new(WATCHLIST_FIRED, &response) {
+            Ok(event) => events.publish(watchlist_entry.user_id, event),
+            Err(e) => error!(
+                watchlist_entry_id = %watchlist_entry.id,
+                "Failed to serialize watchlist entry: {e}"
+            ),
+        }
+    }
+}
+
+/// Ingests the rates of every date from `from` to `to`, both inclusive.
+///
+/// A date that fails does not stop the others, its error is returned in
+/// its place and running the backfill again retries it.
+pub async fn backfill(
+    connection_pool: &PgPool,
+    events: &EventHub,
+    source: &FxSource,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<(NaiveDate, Result<DayReport, FxError>)>, FxError> {
+    let days = (to - from).num_days() + 1;
+    if days < 1 || days > MAX_BACKFILL_DAYS {
+        return Err(FxError::InvalidRange);
+    }
+    let mut results = vec![];
+    for date in from.iter_days().take(days as usize) {
+        let result = ingest(connection_pool, events, source, date).await;
+        if let Err(e) = &result {
+            warn!("Failed to backfill the rates of {date}: {e}");
+        }
+        results.push((date, result));
+    }
+    Ok(results)
+}
+
+/// Starts ingesting the rates of the current date every
+/// [`INGESTION_INTERVAL`], if a source is configured and exchange rates are
+/// enabled. The watchlist entries the rates fire are published to `events`.
+pub fn spawn_ingestion(connection_pool: Arc<PgPool>, events: EventHub, features: FeatureFlags) {
+    let source = match FxSource::from_env() {
+        Ok(source) => source,
+        Err(e) => {
+            info!("{e}");
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(INGESTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !features.is_enabled(Feature::ExchangeRates) {
+                continue;
+            }
+            let date = Utc::now().date_naive();
+            if let Err(e) = ingest(&connection_pool, &events, &source, date).await {
+                error!("Failed to ingest the rates of {date}: {e}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::user::UserId;
+    use sqlx::{Pool, Postgres};
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    fn source(server: &MockServer) -> FxSource {
+        FxSource::new(
+            format!("{}/{{date}}?base={{base}}&key={{api_key}}", server.uri()),
+            Some("key".into()),
+            "USD".into(),
+        )
+        .with_retry_base_delay(Duration::from_millis(1))
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    async fn rate(pool: &Pool<Postgres>, quote_symbol: &str, date: NaiveDate) -> Vec<f64> {
+        sqlx::query_scalar::<_, f64>(
+            r#"
+            SELECT asset_price.rate::float8
+            FROM asset_price
+            JOIN asset base ON base.id = asset_price.asset_id
+            JOIN asset quote ON quote.id = asset_price.quote_asset_id
+            WHERE base.symbol = 'USD' AND quote.symbol = $1 AND priced_at = $2
+            "#,
+        )
+        .bind(quote_symbol)
+        .bind(priced_at(date))
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_retries_failed_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/2025-01-02"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/2025-01-02"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"rates": {"KRW": 1450.0}})),
+            )
+            .mount(&server)
+            .await;
+
+        let rates = source(&server).fetch(date("2025-01-02")).await.unwrap();
+        assert_eq!(rates, HashMap::from([("KRW".to_owned(), 1450.0)]));
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_on_client_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = source(&server).fetch(date("2025-01-02")).await;
+        assert!(matches!(result, Err(FxError::Source(_))));
+    }
+
+    #[sqlx::test(fixtures(path = "../api/fixtures", scripts("assets")))]
+    async fn it_backfills_and_updates_rates(pool: Pool<Postgres>) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/2025-01-01"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"rates": {"KRW": 1400.5, "JPY": 157.0, "XAU": 0.0004}}),
+            ))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/2025-01-01"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"rates": {"KRW": 1410.0}})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/2025-01-02"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let source = source(&server);
+        let events = EventHub::default();
+        let results = backfill(
+            &pool,
+            &events,
+            &source,
+            date("2025-01-01"),
+            date("2025-01-02"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].1.as_ref().unwrap(),
+            &DayReport {
+                date: date("2025-01-01"),
+                written: 2,
+                skipped: vec!["XAU".into()],
+                triggered: 0,
+            }
+        );
+        assert!(matches!(results[1].1, Err(FxError::Source(_))));
+        assert_eq!(rate(&pool, "KRW", date("2025-01-01")).await, vec![1400.5]);
+        assert!(rate(&pool, "KRW", date("2025-01-02")).await.is_empty());
+
+        // Running the date again updates its rates.
+        ingest(&pool, &events, &source, date("2025-01-01"))
+            .await
+            .unwrap();
+        assert_eq!(rate(&pool, "KRW", date("2025-01-01")).await, vec![1410.0]);
+        assert_eq!(rate(&pool, "JPY", date("2025-01-01")).await, vec![157.0]);
+
+        assert!(matches!(
+            backfill(
+                &pool,
+                &events,
+                &source,
+                date("2025-01-02"),
+                date("2025-01-01")
+            )
+            .await,
+            Err(FxError::InvalidRange)
+        ));
+    }
+
+    #[sqlx::test(fixtures(path = "../api/fixtures", scripts("assets")))]
+    async fn it_fires_watchlist_entries_when_rates_reach_their_target(pool: Pool<Postgres>) {
+        let server = MockServer::start().await;
+        for (day, krw) in [
+            ("2024-12-31", 1500.0),
+            ("2025-01-01", 1390.0),
+            ("2025-01-02", 1400.0),
+            ("2025-01-03", 1340.0),
+            ("2025-01-04", 1410.0),
+            ("2025-01-05", 1300.0),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(format!("/{day}")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({"rates": {"KRW": krw}})),
+                )
+                .mount(&server)
+                .await;
+        }
+        let user_id = sqlx::query_scalar::<_, uuid::Uuid>(
+            r#"
+            INSERT INTO "user" (name, email, iss, sub)
+            VALUES ('watcher', 'watcher@example.com', 'iss', 'watcher')
+            RETURNING id
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO watchlist_entry (
+                user_id, asset_id, quote_asset_id, target_price, direction, repeating
+            )
+            SELECT $1, base.id, quote.id, e.target_price, e.direction::watch_direction, e.repeating
+            FROM (
+                VALUES (1400, 'above', FALSE), (1350, 'below', TRUE)
+            ) AS e (target_price, direction, repeating)
+            CROSS JOIN asset base
+            CROSS JOIN asset quote
+            WHERE base.symbol = 'USD' AND quote.symbol = 'KRW'
+            "#,
+        )
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let source = source(&server);
+        let events = EventHub::default();
+        let mut triggered = vec![];
+        for day in [
+            "2025-01-01",
+            "2025-01-02",
+            "2025-01-03",
+            "2025-01-04",
+            "2024-12-31",
+            "2025-01-05",
+        ] {
+            triggered.push(
+                ingest(&pool, &events, &source, date(day))
+                    .await
+                    .unwrap()
+                    .triggered,
+            );
+        }
+        // Neither target is reached by the first price, then the one-shot
+        // entry fires on reaching its target exactly, and only once, while
+        // the repeating one fires on each crossing. A backfilled date
+        // older than the latest price fires nothing.
+        assert_eq!(triggered, vec![0, 1, 1, 0, 0, 1]);
+
+        let fired = sqlx::query_as::<_, (String, Option<f64>)>(
+            r#"
+            SELECT direction::text, triggered_price::float8
+            FROM watchlist_entry
+            ORDER BY direction
+            "#,
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            fired,
+            vec![
+                ("above".to_owned(), Some(1400.0)),
+                ("below".to_owned(), Some(1300.0)),
+            ]
+        );
+    }
+
+    #[sqlx::test(fixtures(path = "../api/fixtures", scripts("assets")))]
+    async fn it_streams_watchlist_entries_on_the_sse_channel(pool: Pool<Postgres>) {
+        let server = MockServer::start().await;
+        for (day, krw) in [("2025-01-01", 1390.0), ("2025-01-02", 1410.0)] {
+            Mock::given(method("GET"))
+                .and(path(format!("/{day}")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({"rates": {"KRW": krw}})),
+                )
+                .mount(&server)
+                .await;
+        }
+        let user_id = sqlx::query_scalar::<_, uuid::Uuid>(
+            r#"
+            INSERT INTO "user" (name, email, iss, sub)
+            VALUES ('watcher', 'watcher@example.com', 'iss', 'watcher')
+            RETURNING id
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO watchlist_entry (
+                user_id, asset_id, quote_asset_id, target_price, direction, channel
+            )
+            SELECT $1, base.id, quote.id, 1400, 'above'::watch_direction, e.channel::alert_channel
+            FROM (VALUES ('email'), ('sse')) AS e (channel)
+            CROSS JOIN asset base
+            CROSS JOIN asset quote
+            WHERE base.symbol = 'USD' AND quote.symbol = 'KRW'
+            "#,
+        )
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let source = source(&server);
+        let events = EventHub::default();
+        let mut subscription = events.subscribe(UserId(user_id)).unwrap();
+        for day in ["2025-01-01", "2025-01-02"] {
+            ingest(&pool, &events, &source, date(day)).await.unwrap();
+        }
+
+        // Both entries fire, but only the one on the event stream is sent.
+        let event = tokio::time::timeout(Duration::from_secs(1), subscription.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.name, WATCHLIST_FIRED);
+        let data = serde_json::from_str::<serde_json::Value>(&event.data).unwrap();
+        assert_eq!(data["channel"], "sse");
+        assert_eq!(data["symbol"], "USD");
+        assert_eq!(data["quote_symbol"], "KRW");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), subscription.next())
+                .await
+                .is_err()
+        );
+    }
+}
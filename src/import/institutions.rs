@@ -0,0 +1,364 @@
+//! Bulk upserts of institution directories.
+//!
+//! A directory is read as NDJSON or CSV from a stream, one row at a time,
+//! and written [`BATCH_ROWS`] rows per statement, so memory stays flat
+//! however long the directory is. Institutions are keyed on their name and
+//! rows that match their institution are left untouched, so a directory
+//! that was only partly written can be sent again. A dry run writes every
+//! batch in one database transaction that is rolled back once the directory
+//! is read, so it reports what would have been written.
+
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use sqlx::{Acquire, PgPool, PgTransaction};
+
+use crate::{
+    api::{ApiError, ClientErrorCode},
+    import::parse_csv,
+    model::institution::{InstitutionUpsert, UpsertOutcome},
+    resource::{
+        asset_price_repository::AssetPriceRepository, deadline,
+        institution_repository::InstitutionRepository,
+    },
+    schema::{
+        admin::{BulkUpsertResponse, DirectoryFormat, DirectoryRowError},
+        text::INSTITUTION_NAME,
+    },
+    service::ServiceError,
+};
+
+/// How many rows are written per statement.
+pub const BATCH_ROWS: usize = 500;
+/// The longest row read, in bytes.
+pub const MAX_ROW_BYTES: usize = 4 * 1024;
+/// The most row errors reported, past which they are only counted.
+pub const MAX_REPORTED_ERRORS: usize = 1_000;
+
+/// A row of a directory as it is sent.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct DirectoryRow {
+    name: String,
+    /// The name of the parent institution
+    #[serde(default)]
+    parent: Option<String>,
+    /// The symbol of the asset new accounts at the institution default to
+    #[serde(default)]
+    default_asset: Option<String>,
+}
+
+/// The columns of a CSV directory, by their position in its header.
+#[derive(Debug, Clone, Copy)]
+struct CsvColumns {
+    name: usize,
+    parent: Option<usize>,
+    default_asset: Option<usize>,
+}
+
+/// Reads a directory pushed to it in chunks of any size, writing its rows
+/// as each batch fills.
+pub struct DirectoryUpsert<'a> {
+    connection_pool: &'a PgPool,
+    /// The database transaction of a dry run, which every batch is written
+    /// in
+    dry_run: Option<PgTransaction<'static>>,
+    format: DirectoryFormat,
+    columns: Option<CsvColumns>,
+    /// The bytes of the row being read, up to [`MAX_ROW_BYTES`]
+    row: Vec<u8>,
+    row_too_long: bool,
+    /// Whether the row being read is inside a quoted CSV field
+    in_quotes: bool,
+    /// The line the row being read starts on
+    row_line: usize,
+    line: usize,
+    batch: Vec<(usize, DirectoryRow)>,
+    report: BulkUpsertResponse,
+}
+
+impl<'a> DirectoryUpsert<'a> {
+    pub async fn new(
+        connection_pool: &'a PgPool,
+        format: DirectoryFormat,
+        dry_run: bool,
+    ) -> Result<Self, ApiError> {
+        let dry_run = if dry_run {
+            Some(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+            )
+        } else {
+            None
+        };
+        Ok(Self {
+            connection_pool,
+            dry_run,
+            format,
+            columns: None,
+            row: vec![],
+            row_too_long: false,
+            in_quotes: false,
+            row_line: 1,
+            line: 1,
+            batch: Vec::with_capacity(BATCH_ROWS),
+            report: BulkUpsertResponse::default(),
+        })
+    }
+
+    /// Reads the rows completed by `chunk`, writing a batch whenever one
+    /// fills.
+    pub async fn push(&mut self, chunk: &[u8]) -> Result<(), ApiError> {
+        for &byte in chunk {
+            if byte == b'"' && self.format == DirectoryFormat::Csv {
+                self.in_quotes = !self.in_quotes;
+            }
+            if byte == b'\n' {
+                self.line += 1;
+                if !self.in_quotes {
+                    self.end_row().await?;
+                    continue;
+                }
+            }
+            if self.row.len() < MAX_ROW_BYTES {
+                self.row.push(byte);
+            } else {
+                self.row_too_long = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the last row and writes what is left of the directory.
+    pub async fn finish(mut self) -> Result<BulkUpsertResponse, ApiError> {
+        if self.in_quotes {
+            self.error(self.row_line, "The row has an unterminated quote.".into());
+            self.row.clear();
+        }
+        self.end_row().await?;
+        if self.format == DirectoryFormat::Csv && self.columns.is_none() {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidFile,
+                "The CSV has no header row.",
+            ));
+        }
+        self.write_batch().await?;
+        if let Some(trans) = self.dry_run.take() {
+            trans.rollback().await.map_err(ServiceError::from)?;
+            self.report.dry_run = true;
+        }
+        Ok(self.report)
+    }
+
+    async fn end_row(&mut self) -> Result<(), ApiError> {
+        let line = self.row_line;
+        self.row_line = self.line;
+        let mut row = std::mem::take(&mut self.row);
+        if row.last() == Some(&b'\r') {
+            row.pop();
+        }
+        if std::mem::take(&mut self.row_too_long) {
+            self.error(
+                line,
+                format!("The row is longer than {MAX_ROW_BYTES} bytes."),
+            );
+            return Ok(());
+        }
+        if row.iter().all(u8::is_ascii_whitespace) {
+            return Ok(());
+        }
+        let parsed = match self.format {
+            DirectoryFormat::Ndjson => {
+                serde_json::from_slice::<DirectoryRow>(&row).map_err(|e| e.to_string())
+            }
+            DirectoryFormat::Csv => match self.csv_row(&row)? {
+                Some(parsed) => parsed,
+                None => return Ok(()),
+            },
+        };
+        let row = match parsed.and_then(validate) {
+            Ok(row) => row,
+            Err(message) => {
+                self.error(line, message);
+                return Ok(());
+            }
+        };
+        // A statement can't upsert the same institution twice.
+        if self.batch.len() == BATCH_ROWS || self.batch.iter().any(|(_, x)| x.name == row.name) {
+            self.write_batch().await?;
+        }
+        self.batch.push((line, row));
+        Ok(())
+    }
+
+    /// Reads a CSV record, or its header when it is the first one.
+    fn csv_row(&mut self, row: &[u8]) -> Result<Option<Result<DirectoryRow, String>>, ApiError> {
+        let fields = match std::str::from_utf8(row) {
+            Ok(row) => parse_csv(row)
+                .map(|mut records| records.pop().map(|(_, fields)| fields).unwrap_or_default())
+                .map_err(|e| e.to_string()),
+            Err(_) => Err("The row is not valid UTF-8.".to_owned()),
+        };
+        let Some(columns) = self.columns else {
+            let header = fields.map_err(|e| ApiError::client(ClientErrorCode::InvalidFile, e))?;
+            let column = |name: &str| header.iter().position(|x| x.trim() == name);
+            self.columns = Some(CsvColumns {
+                name: column("name").ok_or_else(|| {
+                    ApiError::client(
+                        ClientErrorCode::InvalidFile,
+                        "Column `name` is not in the CSV header.",
+                    )
+                })?,
+                parent: column("parent"),
+                default_asset: column("default_asset"),
+            });
+            return Ok(None);
+        };
+        Ok(Some(fields.and_then(|fields| {
+            let field = |column: Option<usize>| column.and_then(|i| fields.get(i)).cloned();
+            Ok(DirectoryRow {
+                name: field(Some(columns.name))
+                    .ok_or_else(|| "The row has no `name`.".to_owned())?,
+                parent: field(columns.parent),
+                default_asset: field(columns.default_asset),
+            })
+        })))
+    }
+
+    /// Writes the batch, reporting the rows naming an asset or a parent
+    /// that doesn't exist.
+    async fn write_batch(&mut self) -> Result<(), ApiError> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let batch = std::mem::take(&mut self.batch);
+
+        let symbols = batch
+            .iter()
+            .filter_map(|(_, x)| x.default_asset.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let asset_ids = AssetPriceRepository
+            .asset_ids_by_symbol(self.begin().await?, &symbols)
+            .await
+            .map_err(ServiceError::from)?;
+        let names = batch
+            .iter()
+            .map(|(_, x)| x.name.as_str())
+            .collect::<HashSet<_>>();
+        let parents = batch
+            .iter()
+            .filter_map(|(_, x)| x.parent.clone())
+            .filter(|x| !names.contains(x.as_str()))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let existing_parents = InstitutionRepository
+            .existing_names(self.begin().await?, &parents)
+            .await
+            .map_err(ServiceError::from)?;
+
+        let mut upserts = Vec::with_capacity(batch.len());
+        let mut errors = vec![];
+        for (line, row) in &batch {
+            let default_asset_id = match &row.default_asset {
+                Some(symbol) => match asset_ids.get(symbol) {
+                    Some(asset_id) => Some(*asset_id),
+                    None => {
+                        errors.push((*line, format!("There is no asset `{symbol}`.")));
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let missing_parent = row.parent.as_ref().filter(|parent| {
+                !names.contains(parent.as_str()) && !existing_parents.contains(*parent)
+            });
+            if let Some(parent) = missing_parent {
+                errors.push((*line, format!("There is no institution named `{parent}`.")));
+                continue;
+            }
+            upserts.push(InstitutionUpsert {
+                name: row.name.clone(),
+                parent: row.parent.clone(),
+                default_asset_id,
+            });
+        }
+        // A row of the batch naming a parent that failed can't be written.
+        let written = upserts
+            .iter()
+            .map(|x| x.name.clone())
+            .collect::<HashSet<_>>();
+        let (upserts, orphans): (Vec<_>, Vec<_>) = upserts.into_iter().partition(|x| {
+            x.parent
+                .as_ref()
+                .is_none_or(|parent| written.contains(parent) || existing_parents.contains(parent))
+        });
+        for orphan in orphans {
+            let line = batch
+                .iter()
+                .find(|(_, x)| x.name == orphan.name)
+                .map(|(line, _)| *line)
+                .unwrap_or_default();
+            let parent = orphan.parent.unwrap_or_default();
+            errors.push((line, format!("There is no institution named `{parent}`.")));
+        }
+        errors.sort();
+        for (line, message) in errors {
+            self.error(line, message);
+        }
+
+        let outcomes = InstitutionRepository
+            .upsert(self.begin().await?, &upserts)
+            .await
+            .map_err(ServiceError::from)?;
+        for outcome in outcomes {
+            match outcome {
+                UpsertOutcome::Created => self.report.created += 1,
+                UpsertOutcome::Updated => self.report.updated += 1,
+                UpsertOutcome::Unchanged => self.report.unchanged += 1,
+            }
+        }
+        Ok(())
+    }
+
+    /// A database transaction to write a batch in, a savepoint of the dry
+    /// run's during one.
+    async fn begin(&mut self) -> Result<PgTransaction<'_>, ServiceError> {
+        Ok(match &mut self.dry_run {
+            Some(trans) => trans.begin().await?,
+            None => deadline::begin(&self.connection_pool).await?,
+        })
+    }
+
+    fn error(&mut self, line: usize, message: String) {
+        self.report.failed += 1;
+        if self.report.errors.len() < MAX_REPORTED_ERRORS {
+            self.report.errors.push(DirectoryRowError { line, message });
+        }
+    }
+}
+
+/// Sanitizes the name of a row, and reads its empty fields as missing.
+fn validate(row: DirectoryRow) -> Result<DirectoryRow, String> {
+    let name = INSTITUTION_NAME
+        .sanitize(row.name.trim())
+        .map_err(|e| e.to_string())?;
+    if name.is_empty() {
+        return Err("The row has no `name`.".into());
+    }
+    let present = |x: Option<String>| x.map(|x| x.trim().to_owned()).filter(|x| !x.is_empty());
+    let parent = present(row.parent)
+        .map(|x| INSTITUTION_NAME.sanitize(&x))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    if parent.as_ref() == Some(&name) {
+        return Err("An institution can't be its own parent.".into());
+    }
+    Ok(DirectoryRow {
+        name,
+        parent,
+        default_asset: present(row.default_asset),
+    })
+}
@@ -0,0 +1,264 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use thiserror::Error;
+
+use crate::{
+    api::{ApiError, ClientErrorCode},
+    model::import_profile::{ImportMapping, SignConvention},
+};
+
+pub mod institutions;
+
+/// How many rows a preview runs the mapping against.
+pub const PREVIEW_ROWS: usize = 5;
+/// The most rows a single import may create.
+pub const MAX_IMPORT_ROWS: usize = 1_000;
+/// The most decimal places an amount may have and still fit in an `i64`.
+pub const MAX_DECIMAL_PLACES: u8 = 18;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ImportError {
+    #[error("The CSV has no header row.")]
+    MissingHeader,
+    #[error("Column `{0}` is not in the CSV header.")]
+    MissingColumn(String),
+    #[error("Line {0} has an unterminated quote.")]
+    UnterminatedQuote(usize),
+    #[error("Line {0} is missing a mapped field.")]
+    MissingField(usize),
+    #[error("Line {line}: `{value}` does not match the date format.")]
+    InvalidDate { line: usize, value: String },
+    #[error("Line {line}: `{value}` is not a valid amount.")]
+    InvalidAmount { line: usize, value: String },
+    #[error("`{0}` is not a valid decimal separator.")]
+    InvalidDecimalSeparator(String),
+    #[error("The date format must not be empty.")]
+    EmptyDateFormat,
+    #[error("An amount may have at most {MAX_DECIMAL_PLACES} decimal places.")]
+    TooManyDecimalPlaces,
+    #[error("An import may create at most {MAX_IMPORT_ROWS} transactions.")]
+    TooManyRows,
+}
+
+impl ImportError {
+    /// The line of the CSV the error is on, if it is about a single row.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Self::UnterminatedQuote(line) | Self::MissingField(line) => Some(*line),
+            Self::InvalidDate { line, .. } | Self::InvalidAmount { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+}
+
+impl From<ImportError> for ApiError {
+    fn from(value: ImportError) -> Self {
+        Self::client(ClientErrorCode::InvalidFile, value.to_string())
+    }
+}
+
+/// A CSV row with the mapping applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedRow {
+    /// The line of the CSV the row starts on
+    pub line: usize,
+    pub posted_at: DateTime<Utc>,
+    pub description: Option<String>,
+    pub quantity: i64,
+}
+
+/// Splits CSV text into records, each with the line it starts on.
+///
+/// Fields may be quoted, with `""` standing for a literal quote inside
+/// them. Blank lines are skipped.
+pub fn parse_csv(csv: &str) -> Result<Vec<(usize, Vec<String>)>, ImportError> {
+    let csv = csv.strip_prefix('\u{feff}').unwrap_or(csv);
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = csv.chars().peekable();
+
+    let mut end_record = |record: &mut Vec<String>, start: usize| {
+        let record = std::mem::take(record);
+        if record.iter().any(|field| !field.is_empty()) {
+            records.push((start, record));
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                end_record(&mut record, start);
+                line += 1;
+                start = line;
+            }
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(ImportError::UnterminatedQuote(start));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        end_record(&mut record, start);
+    }
+    Ok(records)
+}
+
+impl ImportMapping {
+    /// Checks the mapping could be applied to a CSV at all.
+    pub fn validate(&self) -> Result<(), ImportError> {
+        self.separators()?;
+        if self.date_format.trim().is_empty() {
+            return Err(ImportError::EmptyDateFormat);
+        }
+        if self.decimal_places > MAX_DECIMAL_PLACES {
+            return Err(ImportError::TooManyDecimalPlaces);
+        }
+        Ok(())
+    }
+
+    /// Applies the mapping to the rows after the header, up to `limit` of
+    /// them.
+    ///
+    /// The outer error is for problems with the CSV or the mapping as a
+    /// whole, while each row carries its own.
+    pub fn apply(
+        &self,
+        csv: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<Result<ImportedRow, ImportError>>, ImportError> {
+        self.validate()?;
+        let mut records = parse_csv(csv)?.into_iter();
+        let (_, header) = records.next().ok_or(ImportError::MissingHeader)?;
+        let column = |name: &String| {
+            header
+                .iter()
+                .position(|x| x.trim() == name)
+                .ok_or_else(|| ImportError::MissingColumn(name.clone()))
+        };
+        let posted_at = column(&self.column_mapping.posted_at)?;
+        let amount = column(&self.column_mapping.amount)?;
+        let description = self
+            .column_mapping
+            .description
+            .as_ref()
+            .map(column)
+            .transpose()?;
+
+        Ok(records
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(line, record)| {
+                let field = |index: usize| {
+                    record
+                        .get(index)
+                        .map(|x| x.trim())
+                        .ok_or(ImportError::MissingField(line))
+                };
+                Ok(ImportedRow {
+                    line,
+                    posted_at: self.parse_date(line, field(posted_at)?)?,
+                    description: description
+                        .map(field)
+                        .transpose()?
+                        .filter(|x| !x.is_empty())
+                        .map(str::to_owned),
+                    quantity: self.parse_amount(line, field(amount)?)?,
+                })
+            })
+            .collect())
+    }
+
+    /// The decimal and thousands separators.
+    fn separators(&self) -> Result<(char, char), ImportError> {
+        match self.decimal_separator.as_str() {
+            "." => Ok(('.', ',')),
+            "," => Ok((',', '.')),
+            separator => Err(ImportError::InvalidDecimalSeparator(separator.to_owned())),
+        }
+    }
+
+    fn parse_date(&self, line: usize, value: &str) -> Result<DateTime<Utc>, ImportError> {
+        NaiveDateTime::parse_from_str(value, &self.date_format)
+            .map(|x| x.and_utc())
+            .or_else(|_| {
+                NaiveDate::parse_from_str(value, &self.date_format)
+                    .map(|x| x.and_time(NaiveTime::default()).and_utc())
+            })
+            .map_err(|_| ImportError::InvalidDate {
+                line,
+                value: value.to_owned(),
+            })
+    }
+
+    /// Parses an amount into minor units, e.g. `-1.234,50` with two decimal
+    /// places into `-123450`.
+    fn parse_amount(&self, line: usize, value: &str) -> Result<i64, ImportError> {
+        let invalid = || ImportError::InvalidAmount {
+            line,
+            value: value.to_owned(),
+        };
+        let (decimal, group) = self.separators()?;
+        let cleaned = value
+            .chars()
+            .filter(|&c| !c.is_whitespace() && c != group)
+            .collect::<String>();
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, cleaned.strip_prefix('+').unwrap_or(&cleaned)),
+        };
+        let (whole, fraction) = digits.split_once(decimal).unwrap_or((digits, ""));
+        let places = usize::from(self.decimal_places);
+        if (whole.is_empty() && fraction.is_empty())
+            || !whole.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+            || fraction.chars().skip(places).any(|c| c != '0')
+        {
+            return Err(invalid());
+        }
+
+        let fraction = format!("{:0<places$}", &fraction[..fraction.len().min(places)]);
+        let scale = 10_i64
+            .checked_pow(self.decimal_places.into())
+            .ok_or_else(invalid)?;
+        let whole = match whole {
+            "" => 0,
+            whole => whole.parse::<i64>().map_err(|_| invalid())?,
+        };
+        let fraction = match fraction.as_str() {
+            "" => 0,
+            fraction => fraction.parse::<i64>().map_err(|_| invalid())?,
+        };
+        let quantity = whole
+            .checked_mul(scale)
+            .and_then(|x| x.checked_add(fraction))
+            .ok_or_else(invalid)?;
+        let quantity = if negative { -quantity } else { quantity };
+        Ok(match self.sign_convention {
+            SignConvention::AsIs => quantity,
+            SignConvention::Inverted => -quantity,
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::integration::{
+    IntegrationError, ProviderConnector, ProviderSession, RemoteAccount, RemotePage,
+    RemoteTransaction,
+};
+
+/// The connector name of [`FakeConnector`].
+pub const NAME: &str = "fake";
+
+/// A provider whose accounts and transactions come from the connection
+/// config.
+///
+/// Every fetch replays all the transactions of an account, like a provider
+/// whose cursors overlap, so syncs always exercise deduplication.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FakeConnector {
+    /// The password `authenticate` expects, if any
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub accounts: Vec<RemoteAccount>,
+    /// The transactions of each remote account, by remote account id
+    #[serde(default)]
+    pub transactions: HashMap<String, Vec<RemoteTransaction>>,
+}
+
+impl FakeConnector {
+    pub fn from_config(config: &Value) -> Result<Self, IntegrationError> {
+        serde_json::from_value(config.clone())
+            .map_err(|e| IntegrationError::InvalidConfig(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl ProviderConnector for FakeConnector {
+    async fn authenticate(&self, config: &Value) -> Result<ProviderSession, IntegrationError> {
+        match &self.password {
+            Some(password) if config["credentials"] != *password => {
+                Err(IntegrationError::Authentication)
+            }
+            _ => Ok(ProviderSession("fake".into())),
+        }
+    }
+
+    async fn list_accounts(
+        &self,
+        _session: &ProviderSession,
+    ) -> Result<Vec<RemoteAccount>, IntegrationError> {
+        Ok(self.accounts.clone())
+    }
+
+    async fn fetch_transactions(
+        &self,
+        _session: &ProviderSession,
+        remote_account_id: &str,
+        _cursor: Option<&str>,
+    ) -> Result<RemotePage, IntegrationError> {
+        let transactions = self
+            .transactions
+            .get(remote_account_id)
+            .cloned()
+            .unwrap_or_default();
+        Ok(RemotePage {
+            cursor: Some(transactions.len().to_string()),
+            transactions,
+        })
+    }
+}
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::integration::{
+    IntegrationError, ProviderConnector, ProviderSession, RemoteAccount, RemotePage,
+};
+
+/// The connector name of [`ManualConnector`].
+pub const NAME: &str = "manual";
+
+/// The connector of institutions whose transactions are entered by hand. It
+/// sees no accounts and fetches nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ManualConnector;
+
+#[async_trait]
+impl ProviderConnector for ManualConnector {
+    async fn authenticate(&self, _config: &Value) -> Result<ProviderSession, IntegrationError> {
+        Ok(ProviderSession(String::new()))
+    }
+
+    async fn list_accounts(
+        &self,
+        _session: &ProviderSession,
+    ) -> Result<Vec<RemoteAccount>, IntegrationError> {
+        Ok(vec![])
+    }
+
+    async fn fetch_transactions(
+        &self,
+        _session: &ProviderSession,
+        _remote_account_id: &str,
+        _cursor: Option<&str>,
+    ) -> Result<RemotePage, IntegrationError> {
+        Ok(RemotePage::default())
+    }
+}
@@ -0,0 +1,288 @@
+//! The extension point for syncing accounts from institution providers.
+//!
+//! A [`ProviderConnector`] speaks to one provider. The
+//! [`ProviderConnection`] of a user at an institution names the connector
+//! and holds its configuration, and a [`SyncJob`] drives the connector for
+//! one account, skipping transactions it has already imported.
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::error;
+
+use crate::{
+    api::{ApiError, ClientErrorCode},
+    model::{
+        account::Account,
+        asset::{AssetFilter, AssetId},
+        provider_connection::ProviderConnection,
+        transaction::{Transaction, TransactionCreate},
+    },
+    resource::{
+        GetListRepository, UpdateRepository, asset_repository::AssetRepository, deadline,
+        provider_connection_repository::ProviderConnectionRepository,
+        transaction_repository::TransactionRepository,
+    },
+    schema::Quantity,
+    service::{ServiceError, transaction_service::TransactionServiceMethods},
+};
+
+#[cfg(test)]
+pub mod fake;
+pub mod manual;
+
+#[derive(Debug, Clone, Error)]
+pub enum IntegrationError {
+    #[error("There is no `{0}` connector.")]
+    UnknownConnector(String),
+    #[error("The connector configuration is invalid: {0}")]
+    InvalidConfig(String),
+    #[error("The provider rejected the credentials.")]
+    Authentication,
+    #[error("No remote account is named `{0}`.")]
+    NoRemoteAccount(String),
+    #[error("The provider failed: {0}")]
+    Provider(String),
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<IntegrationError> for ApiError {
+    fn from(value: IntegrationError) -> Self {
+        match value {
+            IntegrationError::Service(e) => Self::Service(e),
+            IntegrationError::Provider(e) => {
+                error!("{e}");
+                Self::ServerError
+            }
+            e => Self::client(ClientErrorCode::InvalidRequest, e.to_string()),
+        }
+    }
+}
+
+/// A signed in session with a provider.
+#[derive(Debug, Clone)]
+pub struct ProviderSession(pub String);
+
+/// An account as the provider knows it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RemoteAccount {
+    pub id: String,
+    pub name: String,
+}
+
+/// A transaction as the provider reports it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RemoteTransaction {
+    /// The id of the transaction at the provider, stable across fetches
+    pub external_id: String,
+    pub posted_at: DateTime<Utc>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// In the smallest unit of the asset if an integer, or in whole units
+    /// if a decimal string
+    pub quantity: Quantity,
+    /// The symbol of the asset the quantity is in
+    pub asset_symbol: String,
+}
+
+/// The transactions fetched since a cursor.
+#[derive(Debug, Clone, Default)]
+pub struct RemotePage {
+    pub transactions: Vec<RemoteTransaction>,
+    /// Where the next fetch should resume, if the provider has cursors
+    pub cursor: Option<String>,
+}
+
+#[async_trait]
+pub trait ProviderConnector: Send + Sync {
+    /// Signs in to the provider with the configuration of the connection.
+    async fn authenticate(&self, config: &Value) -> Result<ProviderSession, IntegrationError>;
+
+    /// Lists the accounts the session can see.
+    async fn list_accounts(
+        &self,
+        session: &ProviderSession,
+    ) -> Result<Vec<RemoteAccount>, IntegrationError>;
+
+    /// Fetches the transactions of a remote account posted since `cursor`.
+    ///
+    /// Providers may return transactions that were already fetched, so
+    /// callers must deduplicate them by external id.
+    async fn fetch_transactions(
+        &self,
+        session: &ProviderSession,
+        remote_account_id: &str,
+        cursor: Option<&str>,
+    ) -> Result<RemotePage, IntegrationError>;
+}
+
+/// Looks up the connector with `name`, set up with the connection config.
+pub fn connector(
+    name: &str,
+    config: &Value,
+) -> Result<Box<dyn ProviderConnector>, IntegrationError> {
+    match name {
+        manual::NAME => Ok(Box::new(manual::ManualConnector)),
+        #[cfg(test)]
+        fake::NAME => Ok(Box::new(fake::FakeConnector::from_config(config)?)),
+        name => {
+            let _ = config;
+            Err(IntegrationError::UnknownConnector(name.to_owned()))
+        }
+    }
+}
+
+/// What a sync did to an account.
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    /// The transactions that were new to the account
+    pub created: Vec<Transaction>,
+    /// How many fetched transactions the account already had
+    pub duplicates: usize,
+    /// How many fetched transactions were in assets that aren't tracked
+    pub unknown_assets: usize,
+}
+
+/// Syncs one account through its provider connection.
+///
+/// There is no job queue in the tree yet, so jobs are run by the request
+/// that starts them.
+#[derive(Debug, Clone)]
+pub struct SyncJob {
+    pub connection: ProviderConnection,
+    pub account: Account,
+}
+
+impl SyncJob {
+    /// Runs the sync, creating transactions through `transaction_service`
+    /// so they are subject to the permissions of the caller.
+    pub async fn run(
+        self,
+        connection_pool: &PgPool,
+        transaction_service: Box<dyn TransactionServiceMethods + Send>,
+    ) -> Result<SyncReport, IntegrationError> {
+        let Self {
+            mut connection,
+            account,
+        } = self;
+        let connector = connector(&connection.connector, &connection.config)?;
+        let session = connector.authenticate(&connection.config).await?;
+        let remote_account = connector
+            .list_accounts(&session)
+            .await?
+            .into_iter()
+            .find(|x| x.name == account.name)
+            .ok_or_else(|| IntegrationError::NoRemoteAccount(account.name.clone()))?;
+        let cursor = connection.cursors.get(&remote_account.id).cloned();
+        let page = connector
+            .fetch_transactions(&session, &remote_account.id, cursor.as_deref())
+            .await?;
+
+        let mut report = SyncReport::default();
+        let mut seen = HashSet::new();
+        let mut fetched = vec![];
+        for transaction in page.transactions {
+            if seen.insert(transaction.external_id.clone()) {
+                fetched.push(transaction);
+            } else {
+                report.duplicates += 1;
+            }
+        }
+        let existing = TransactionRepository
+            .get_external_ids(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                account.id,
+                fetched.iter().map(|x| x.external_id.clone()).collect(),
+            )
+            .await
+            .map_err(ServiceError::from)?
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let mut assets = Vec::<(String, Option<(AssetId, u32)>)>::new();
+        for transaction in fetched {
+            if existing.contains(&transaction.external_id) {
+                report.duplicates += 1;
+                continue;
+            }
+            let asset = match assets
+                .iter()
+                .find(|(symbol, _)| *symbol == transaction.asset_symbol)
+            {
+                Some((_, asset)) => *asset,
+                None => {
+                    let asset = AssetRepository
+                        .get_list(
+                            deadline::begin(&connection_pool)
+                                .await
+                                .map_err(ServiceError::from)?,
+                            0,
+                            Some(1),
+                            AssetFilter {
+                                symbol: transaction.asset_symbol.clone().into(),
+                                ..Default::default()
+                            },
+                        )
+                        .await
+                        .map_err(ServiceError::from)?
+                        .first()
+                        .map(|x| (x.id, x.scale()));
+                    assets.push((transaction.asset_symbol.clone(), asset));
+                    asset
+                }
+            };
+            let Some((asset_id, scale)) = asset else {
+                report.unknown_assets += 1;
+                continue;
+            };
+            let quantity = transaction.quantity.in_asset(scale).map_err(|_| {
+                IntegrationError::Provider(format!(
+                    "`{}` has a quantity that doesn't fit {}.",
+                    transaction.external_id, transaction.asset_symbol
+                ))
+            })?;
+            let transaction = transaction_service
+                .create(TransactionCreate {
+                    account_id: account.id,
+                    asset_id,
+                    description: transaction.description,
+                    posted_at: transaction.posted_at,
+                    quantity,
+                    notes: None,
+                    external_id: Some(transaction.external_id),
+                    category: None,
+                    applied_rule_id: None,
+                    journal_entry_id: None,
+                })
+                .await?;
+            report.created.push(transaction);
+        }
+
+        // Rows in untracked assets are fetched again next time, once the
+        // asset may have been added.
+        if report.unknown_assets == 0
+            && let Some(cursor) = page.cursor
+        {
+            connection.cursors.insert(remote_account.id, cursor);
+        }
+        connection.last_synced_at = Some(Utc::now());
+        ProviderConnectionRepository
+            .update(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                connection,
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        Ok(report)
+    }
+}
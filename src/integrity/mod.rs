@@ -0,0 +1,248 @@
+//! Consistency checks over the data of a user.
+//!
+//! Each [`IntegrityCheck`] is one query for the ids of the rows of a user
+//! that break an invariant the rest of the code relies on, with the id of
+//! the user bound as `$1`. [`run`] runs every check in [`CHECKS`] and
+//! reports how many rows each found along with a sample of their ids, so a
+//! new check only needs a new entry there. The queries scan all the data of
+//! the user, which is why [`claim_run`] limits how often they run.
+
+use std::{
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use cached::{Cached, TimedCache};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, query_as};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    model::user::UserId,
+    resource::{InstrumentQuery, account_balance_repository::AccountBalanceRepository, deadline},
+    service::ServiceError,
+};
+
+/// How many of the ids a check finds are included in its result.
+pub const SAMPLE_SIZE: i64 = 10;
+
+/// How long after a run of the checks of a user they can run again.
+pub const RUN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A query for the ids of the rows of a user that are inconsistent.
+#[derive(Debug, Clone, Copy)]
+pub struct IntegrityCheck {
+    pub name: &'static str,
+    pub description: &'static str,
+    query: &'static str,
+}
+
+pub const CHECKS: &[IntegrityCheck] = &[
+    IntegrityCheck {
+        name: "transactions_of_deleted_accounts",
+        description: "Transactions that still exist after their account was deleted.",
+        query: r#"
+            SELECT DISTINCT "transaction".id::text AS id
+            FROM "transaction"
+            JOIN sync_tombstone
+                ON sync_tombstone.resource = 'account'
+                AND sync_tombstone.account_id = "transaction".account_id
+            WHERE sync_tombstone.user_id = $1
+            AND NOT EXISTS (
+                SELECT 1 FROM account WHERE account.id = "transaction".account_id
+            )
+            "#,
+    },
+    IntegrityCheck {
+        name: "tombstones_of_existing_rows",
+        description: "Deletions recorded for delta sync of accounts or transactions that still exist.",
+        query: r#"
+            SELECT sync_tombstone.resource_id AS id
+            FROM sync_tombstone
+            WHERE sync_tombstone.user_id = $1
+            AND (
+                (sync_tombstone.resource = 'account' AND EXISTS (
+                    SELECT 1 FROM account
+                    WHERE account.id::text = sync_tombstone.resource_id
+                ))
+                OR (sync_tombstone.resource = 'transaction' AND EXISTS (
+                    SELECT 1 FROM "transaction"
+                    WHERE "transaction".id::text = sync_tombstone.resource_id
+                ))
+            )
+            "#,
+    },
+    IntegrityCheck {
+        name: "history_out_of_sync",
+        description: "Transactions that differ from what their latest recorded edit left them as.",
+        query: r#"
+            SELECT "transaction".id::text AS id
+            FROM "transaction"
+            JOIN account ON account.id = "transaction".account_id
+            JOIN LATERAL (
+                SELECT * FROM transaction_history
+                WHERE transaction_history.transaction_id = "transaction".id
+                ORDER BY transaction_history.id DESC
+                LIMIT 1
+            ) AS latest ON true
+            WHERE account.user_id = $1
+            AND (
+                latest.after_posted_at,
+                latest.after_asset_id,
+                latest.after_description,
+                latest.after_quantity
+            ) IS DISTINCT FROM (
+                "transaction".posted_at,
+                "transaction".asset_id,
+                "transaction".description,
+                "transaction".quantity
+            )
+            "#,
+    },
+    IntegrityCheck {
+        name: "quantity_precision",
+        description: "Transactions with more decimals than their asset has.",
+        query: r#"
+            SELECT "transaction".id::text AS id
+            FROM "transaction"
+            JOIN account ON account.id = "transaction".account_id
+            JOIN asset ON asset.id = "transaction".asset_id
+            WHERE account.user_id = $1
+            AND "transaction".quantity <> ROUND("transaction".quantity, asset.decimals)
+            "#,
+    },
+    IntegrityCheck {
+        name: "balance_overflow",
+        description: "Accounts with a balance in an asset that is too large to represent.",
+        query: r#"
+            SELECT DISTINCT balance.account_id::text AS id
+            FROM (
+                SELECT "transaction".account_id, "transaction".asset_id, SUM("transaction".quantity) AS quantity
+                FROM "transaction"
+                JOIN account ON account.id = "transaction".account_id
+                WHERE account.user_id = $1
+                GROUP BY "transaction".account_id, "transaction".asset_id
+            ) AS balance
+            JOIN asset ON asset.id = balance.asset_id
+            WHERE ABS(balance.quantity * 10::NUMERIC ^ asset.decimals) > 79228162514264337593543950335
+            "#,
+    },
+    IntegrityCheck {
+        name: "cached_balance_drift",
+        description: "Accounts with a cached balance that differs from the sum of their transactions.",
+        query: r#"
+            SELECT DISTINCT COALESCE(live.account_id, cached.account_id)::text AS id
+            FROM (
+                SELECT "transaction".account_id, "transaction".asset_id, SUM("transaction".quantity) AS balance
+                FROM "transaction"
+                JOIN account ON account.id = "transaction".account_id
+                WHERE account.user_id = $1
+                GROUP BY "transaction".account_id, "transaction".asset_id
+            ) AS live
+            FULL OUTER JOIN (
+                SELECT account_balance.*
+                FROM account_balance
+                JOIN account ON account.id = account_balance.account_id
+                WHERE account.user_id = $1
+            ) AS cached
+                ON cached.account_id = live.account_id
+                AND cached.asset_id = live.asset_id
+            WHERE COALESCE(live.balance, 0) <> COALESCE(cached.balance, 0)
+            "#,
+    },
+];
+
+/// What one check found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// How many rows are inconsistent
+    pub count: i64,
+    /// The ids of up to [`SAMPLE_SIZE`] of them
+    pub sample_ids: Vec<String>,
+}
+
+/// What the checks of a user found.
+#[derive(Debug, Clone)]
+pub struct IntegrityReport {
+    pub user_id: UserId,
+    pub checked_at: DateTime<Utc>,
+    pub checks: Vec<CheckResult>,
+}
+
+impl IntegrityCheck {
+    #[instrument(name = "IntegrityCheck::run", skip_all, fields(name = self.name))]
+    async fn run(
+        &self,
+        connection_pool: &PgPool,
+        user_id: UserId,
+    ) -> Result<CheckResult, ServiceError> {
+        let rows = query_as::<_, (i64, String)>(&format!(
+            r#"
+            SELECT COUNT(*) OVER () AS count, found.id
+            FROM ({}) AS found
+            ORDER BY found.id
+            LIMIT $2
+            "#,
+            self.query
+        ))
+        .bind(user_id)
+        .bind(SAMPLE_SIZE)
+        .fetch_all(connection_pool)
+        .in_query_span()
+        .await?;
+        Ok(CheckResult {
+            name: self.name,
+            description: self.description,
+            count: rows.first().map(|(count, _)| *count).unwrap_or_default(),
+            sample_ids: rows.into_iter().map(|(_, id)| id).collect(),
+        })
+    }
+}
+
+/// Runs all of [`CHECKS`] over the data of a user.
+#[instrument(skip(connection_pool))]
+pub async fn run(
+    connection_pool: &PgPool,
+    user_id: UserId,
+) -> Result<IntegrityReport, ServiceError> {
+    let mut checks = vec![];
+    for check in CHECKS {
+        checks.push(check.run(connection_pool, user_id).await?);
+    }
+    Ok(IntegrityReport {
+        user_id,
+        checked_at: Utc::now(),
+        checks,
+    })
+}
+
+/// Sets the cached balances of a user to the sums of their transactions,
+/// which repairs what `cached_balance_drift` finds. Returns how many of the
+/// balances had drifted.
+#[instrument(skip(connection_pool))]
+pub async fn repair_balances(
+    connection_pool: &PgPool,
+    user_id: UserId,
+) -> Result<i64, ServiceError> {
+    let repaired = AccountBalanceRepository
+        .rebuild(deadline::begin(&connection_pool).await?, Some(user_id))
+        .await?;
+    Ok(repaired)
+}
+
+static LAST_RUNS: LazyLock<Mutex<TimedCache<Uuid, ()>>> =
+    LazyLock::new(|| Mutex::new(TimedCache::with_lifespan(RUN_INTERVAL.as_secs())));
+
+/// Claims a run of the checks of a user, which fails when they already ran
+/// within the last [`RUN_INTERVAL`].
+pub fn claim_run(user_id: UserId) -> bool {
+    let mut last_runs = LAST_RUNS.lock().unwrap_or_else(|e| e.into_inner());
+    if last_runs.cache_get(&user_id.0).is_some() {
+        return false;
+    }
+    last_runs.cache_set(user_id.0, ());
+    true
+}
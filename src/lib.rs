@@ -6,18 +6,50 @@ pub mod api;
 pub mod authentication;
 #[cfg(feature = "ssr")]
 pub mod authorization;
+#[cfg(feature = "ssr")]
+pub mod categorization;
+#[cfg(any(feature = "client", test))]
+pub mod client;
+#[cfg(feature = "ssr")]
+pub mod config;
+#[cfg(feature = "ssr")]
+pub mod demo;
+#[cfg(feature = "ssr")]
+pub mod export;
+#[cfg(feature = "ssr")]
+pub mod extraction;
+#[cfg(feature = "fx")]
+pub mod fx;
+#[cfg(feature = "ssr")]
+pub mod import;
+#[cfg(feature = "ssr")]
+pub mod integration;
+#[cfg(feature = "ssr")]
+pub mod integrity;
 pub mod model;
 #[cfg(feature = "ssr")]
 pub mod resource;
+pub mod rounding;
 pub mod schema;
 #[cfg(feature = "ssr")]
+pub mod seed;
+#[cfg(feature = "ssr")]
 pub mod service;
+#[cfg(feature = "ssr")]
+pub mod startup;
+#[cfg(feature = "ssr")]
+pub mod statement;
+#[cfg(feature = "ssr")]
+pub mod telemetry;
+#[cfg(feature = "ssr")]
+pub mod upload;
 
 #[cfg(feature = "ssr")]
 pub static AUTH_MODEL_PATH: OnceLock<String> = OnceLock::new();
 #[cfg(feature = "ssr")]
 pub static AUTH_POLICY_PATH: OnceLock<String> = OnceLock::new();
 
+#[cfg(any(feature = "ssr", feature = "hydrate"))]
 pub mod app;
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
@@ -4,17 +4,127 @@ async fn main() {
     use axum::serve;
     use casbin::{CoreApi, Enforcer};
     use sqlx::postgres::PgPoolOptions;
-    use std::{env::var, sync::Arc};
+    use std::{env::var, net::SocketAddr, sync::Arc};
     use tokio::net::TcpListener;
     use tracing::info;
-    use tracing_subscriber::{EnvFilter, FmtSubscriber};
-    use treasury::{AUTH_MODEL_PATH, AUTH_POLICY_PATH, api::ApiV1};
-
-    let subscriber = FmtSubscriber::builder()
-        .with_env_filter(EnvFilter::from_default_env())
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)
-        .expect("Failed to initialize tracing subscriber.");
+    use treasury::{
+        AUTH_MODEL_PATH, AUTH_POLICY_PATH,
+        api::{
+            ApiV1, ApiV2, admin_api::load_feature_flags, docs_api::DocsApi, event_hub::EventHub,
+            openapi_diff::OpenApiDiff,
+        },
+        authentication::csrf,
+        authorization::{AuthorizationError, summary::validate_policies},
+        config::{DatabaseConfig, DemoConfig, FeatureFlags, StartupConfig},
+        demo, export, integrity,
+        resource::{account_balance_repository::AccountBalanceRepository, deadline},
+        schema::version::ApiVersion,
+        seed,
+        startup::{Startup, StartupError},
+        telemetry,
+    };
+
+    /// Exits with the error of a phase that failed.
+    fn or_exit<T>(result: Result<T, StartupError>) -> T {
+        result.unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        })
+    }
+
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    // The spec is built from the code alone, so these run before anything
+    // reads the environment, as they do when clients are generated in CI.
+    if args.first().is_some_and(|x| x == "openapi-dump") {
+        // `treasury openapi-dump [--pretty]` prints the spec of version 1.
+        let openapi = DocsApi::openapi_for(ApiVersion::V1);
+        let json = if args.get(1).is_some_and(|x| x == "--pretty") {
+            openapi.to_pretty_json()
+        } else {
+            openapi.to_json()
+        };
+        println!("{}", json.expect("Failed to serialize the spec"));
+        return;
+    }
+    if args.first().is_some_and(|x| x == "openapi-diff") {
+        // `treasury openapi-diff <file>` tells how the spec differs from the
+        // one in `<file>`, failing if it does.
+        let Some(path) = args.get(1) else {
+            eprintln!("Usage: treasury openapi-diff <file>");
+            std::process::exit(2);
+        };
+        let committed = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|x| serde_json::from_str(&x).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to read the spec in `{path}`: {e}");
+                std::process::exit(2);
+            });
+        let current = serde_json::to_value(DocsApi::openapi_for(ApiVersion::V1))
+            .expect("Failed to serialize the spec");
+        let diff = OpenApiDiff::between(&committed, &current);
+        print!("{diff}");
+        if !diff.is_empty() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let _telemetry = telemetry::init();
+    let demo_config = DemoConfig::from_env();
+    if let Err(e) = demo_config.validate(|name| var(name).ok()) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+    let startup = Startup::new(StartupConfig::from_env());
+    let database_url = var("DATABASE_URL").expect("Failed to read `DATABASE_URL` env variable");
+    // The commands run without the statement timeout of the server, a
+    // rebuild of every balance can take a while.
+    let connect = |options: PgPoolOptions| {
+        startup.phase(
+            "database",
+            options.max_connections(5).connect(&database_url),
+        )
+    };
+
+    if args.first().is_some_and(|x| x == "seed") {
+        let pool = or_exit(connect(PgPoolOptions::new()).await);
+        match seed::run(&pool, &args[1..]).await {
+            Ok(report) => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if args.first().is_some_and(|x| x == "rebuild-balances") {
+        let pool = or_exit(connect(PgPoolOptions::new()).await);
+        // `treasury rebuild-balances [<user id>]` repairs the cached balances
+        // of one user, or of every account.
+        let repaired = match args.get(1).map(|x| x.parse()) {
+            Some(Ok(user_id)) => integrity::repair_balances(&pool, user_id)
+                .await
+                .map_err(|e| e.to_string()),
+            Some(Err(_)) => Err("Usage: treasury rebuild-balances [<user id>]".to_owned()),
+            None => match pool.begin().await {
+                Ok(session) => AccountBalanceRepository
+                    .rebuild(session, None)
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+        };
+        match repaired {
+            Ok(repaired) => println!("Repaired {repaired} balances"),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     let model_path: &'static str = AUTH_MODEL_PATH.get_or_init(|| {
         var("AUTH_MODEL_PATH").expect("Failed to read `AUTH_MODEL_PATH` env variable")
     });
@@ -23,32 +133,66 @@ async fn main() {
         var("AUTH_POLICY_PATH").expect("Failed to read `AUTH_POLICY_PATH` env variable")
     });
 
-    let enforcer = Arc::new(
-        Enforcer::new(model_path, policies_path)
-            .await
-            .expect("Failed to load authorization policy"),
+    // The pool and the policy don't depend on each other, and both can take
+    // a while on a cold start.
+    let (pool, enforcer) = tokio::join!(
+        connect(deadline::pool_options(DatabaseConfig::from_env())),
+        // A policy with a typo'd action would never match, refuse it rather
+        // than quietly deny what it was meant to grant.
+        startup.phase("enforcer", async {
+            let enforcer = Enforcer::new(model_path, policies_path).await?;
+            validate_policies(&enforcer)?;
+            Ok::<_, AuthorizationError>(enforcer)
+        }),
     );
+    let pool = Arc::new(or_exit(pool));
+    let enforcer = Arc::new(or_exit(enforcer));
 
-    let database_url = var("DATABASE_URL").expect("Failed to read `DATABASE_URL` env variable");
-    let pool = Arc::new(
-        PgPoolOptions::new()
-            .max_connections(5)
-            .connect(&database_url)
-            .await
-            .expect("Failed to connect to database."),
+    if demo_config.enabled {
+        or_exit(startup.phase("demo_seed", demo::seed_demo(&pool)).await);
+        demo::spawn_reaper(pool.clone(), demo_config);
+        info!("Serving a demo, sign in at `/demo`");
+    }
+
+    // The overrides saved by admins win over the environment, and the
+    // routes are mounted from the flags as they are now.
+    let features = or_exit(
+        startup
+            .phase(
+                "features",
+                load_feature_flags(&pool, FeatureFlags::from_env()),
+            )
+            .await,
     );
 
-    info!("Connected to database");
+    csrf::spawn_reaper(pool.clone());
+    export::spawn_scheduler(pool.clone(), features.clone());
+    // The services and the background tasks publish to the same streams.
+    let events = EventHub::default();
+    #[cfg(feature = "fx")]
+    treasury::fx::spawn_ingestion(pool.clone(), events.clone(), features.clone());
 
-    let listener = TcpListener::bind("0.0.0.0:8080")
-        .await
-        .expect("Failed to create listener.");
+    let listener = or_exit(
+        startup
+            .phase("listener", TcpListener::bind("0.0.0.0:8080"))
+            .await,
+    );
+    let report = startup.finish();
 
     info!("Listening for traffic at `0.0.0.0:8080`");
 
-    serve(listener, ApiV1::router(pool, enforcer))
-        .await
-        .expect("Failed to serve app");
+    // The address of the peer is what anonymous requests are limited by
+    // when there is no trusted proxy to forward that of the client.
+    serve(
+        listener,
+        ApiV2::mount(ApiV1::router_with_features(
+            pool, enforcer, features, events,
+        ))
+        .merge(report.router())
+        .into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .expect("Failed to serve app");
 }
 
 #[cfg(not(feature = "ssr"))]
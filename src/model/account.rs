@@ -4,7 +4,10 @@ use uuid::Uuid;
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
-    pub use crate::model::{Filter, institution::InstitutionId, user::UserId};
+    pub use crate::model::{
+        Condition, Filter, ListSort, Predicate, asset::AssetId, institution::InstitutionId,
+        user::UserId,
+    };
     pub use chrono::{DateTime, Utc};
     pub use sqlx::{FromRow, Type};
     pub use utoipa::{IntoParams, ToSchema};
@@ -42,6 +45,10 @@ mod ssr {
         pub institution_id: InstitutionId,
         /// The name of the account
         pub name: String,
+        /// Free-form markdown notes about the account
+        pub notes: Option<String>,
+        /// The asset the account shows its values in
+        pub default_asset_id: Option<AssetId>,
     }
 
     #[derive(Debug, Clone)]
@@ -49,11 +56,73 @@ mod ssr {
         pub name: String,
         pub institution_id: InstitutionId,
         pub user_id: UserId,
+        pub notes: Option<String>,
+        /// Falls back to the default of the institution when not given
+        pub default_asset_id: Option<AssetId>,
     }
 
     #[derive(Debug, Clone)]
     pub struct AccountUpdate {
         pub name: String,
+        pub notes: Option<String>,
+        pub default_asset_id: Option<AssetId>,
+    }
+
+    /// The merge of a duplicate account into another, recording what the
+    /// deleted source was and how many of its rows were moved.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct AccountMerge {
+        pub id: Uuid,
+        pub merged_at: DateTime<Utc>,
+        pub user_id: UserId,
+        /// The account that was kept
+        pub target_id: AccountId,
+        /// The account that was merged into the target and deleted
+        pub source_id: AccountId,
+        pub source_name: String,
+        pub source_institution_id: InstitutionId,
+        pub source_notes: Option<String>,
+        pub source_created_at: DateTime<Utc>,
+        /// Whether the accounts were at different institutions
+        pub forced: bool,
+        pub transactions: i64,
+        pub attachments: i64,
+        pub categorization_rules: i64,
+        pub alert_rules: i64,
+        pub import_profiles: i64,
+        pub budgets: i64,
+        pub api_keys: i64,
+    }
+
+    /// How many rows go with an account when it is deleted.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromRow)]
+    pub struct AccountDeletionCounts {
+        pub transactions: i64,
+        /// The attachments of the transactions
+        pub attachments: i64,
+        pub categorization_rules: i64,
+        pub alert_rules: i64,
+        /// The transactions that are legs of journal entries, which keep an
+        /// account from being deleted
+        pub journal_entry_legs: i64,
+    }
+
+    /// The deletion of an account, recording what it was and how many of
+    /// its rows were deleted with it.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct AccountDeletion {
+        pub id: Uuid,
+        pub deleted_at: DateTime<Utc>,
+        pub user_id: UserId,
+        pub account_id: AccountId,
+        pub account_name: String,
+        pub institution_id: InstitutionId,
+        pub notes: Option<String>,
+        pub account_created_at: DateTime<Utc>,
+        pub transactions: i64,
+        pub attachments: i64,
+        pub categorization_rules: i64,
+        pub alert_rules: i64,
     }
 
     #[derive(Debug, Clone, Default)]
@@ -62,50 +131,40 @@ mod ssr {
         pub name: Option<String>,
         pub institution_id: Option<InstitutionId>,
         pub user_id: Option<UserId>,
+        /// Limits the accounts to those of a scoped API key
+        pub account_ids: Option<Vec<AccountId>>,
+        pub created_after: Option<DateTime<Utc>>,
+        pub created_before: Option<DateTime<Utc>>,
+        pub updated_after: Option<DateTime<Utc>>,
+        pub updated_before: Option<DateTime<Utc>>,
+        /// The order the rows are listed in
+        pub sort: ListSort,
     }
 
     impl Filter for AccountFilter {
-        fn push(self, query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>) {
-            if self.id.is_none()
-                && self.name.is_none()
-                && self.institution_id.is_none()
-                && self.user_id.is_none()
-            {
-                return;
-            }
-            query.push(r#"WHERE "#);
-
-            let has_id = self.id.is_some();
-            if let Some(id) = self.id {
-                query.push(r#"id = "#);
-                query.push_bind(id);
-            }
-
-            let has_name = self.name.is_some();
-            if let Some(name) = self.name {
-                if has_id {
-                    query.push(r#" AND "#);
-                }
-                query.push(r#"name = "#);
-                query.push_bind(name);
-            }
-
-            let has_institution_id = self.institution_id.is_some();
-            if let Some(insitution_id) = self.institution_id {
-                if has_id || has_name {
-                    query.push(r#" AND "#);
-                }
-                query.push(r#"institution_id = "#);
-                query.push_bind(insitution_id);
-            }
-
-            if let Some(user_id) = self.user_id {
-                if has_id || has_name || has_institution_id {
-                    query.push(r#" AND "#);
-                }
-                query.push(r#"user_id = "#);
-                query.push_bind(user_id);
-            }
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.id, |id| Condition::eq("id", id))
+                .and_some(self.name, |name| Condition::eq("name", name))
+                .and_some(self.institution_id, |institution_id| {
+                    Condition::eq("institution_id", institution_id)
+                })
+                .and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+                .and_some(self.account_ids, |account_ids| {
+                    Condition::any("id", account_ids)
+                })
+                .and_some(self.created_after, |created_after| {
+                    Condition::gt("created_at", created_after)
+                })
+                .and_some(self.created_before, |created_before| {
+                    Condition::lt("created_at", created_before)
+                })
+                .and_some(self.updated_after, |updated_after| {
+                    Condition::gt("updated_at", updated_after)
+                })
+                .and_some(self.updated_before, |updated_before| {
+                    Condition::lt("updated_at", updated_before)
+                })
         }
     }
 }
@@ -0,0 +1,30 @@
+use crate::model::{account::AccountId, asset::AssetId, transaction::TransactionId};
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+
+/// The balance of an account in an asset as of the last transaction applied
+/// to it.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct AccountBalance {
+    /// The account the balance is of
+    pub account_id: AccountId,
+    /// The asset the balance is in
+    pub asset_id: AssetId,
+    /// The sum of the quantities of the transactions
+    pub balance: Decimal,
+    /// The last transaction applied to the balance
+    pub as_of_transaction_id: Option<TransactionId>,
+}
+
+/// The balance of all the accounts of a user in an asset, now and at an
+/// earlier point.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct NetBalance {
+    pub asset_id: AssetId,
+    pub symbol: String,
+    /// The sum of the quantities of all the transactions
+    pub balance: Decimal,
+    /// The sum of the quantities of the transactions posted before the
+    /// earlier point
+    pub previous_balance: Decimal,
+}
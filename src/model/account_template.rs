@@ -0,0 +1,64 @@
+/// An account a template opens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemplateAccount {
+    /// The name of the account
+    pub name: &'static str,
+    /// What the account is used for, such as `checking` or `credit`
+    pub kind: &'static str,
+}
+
+/// Accounts commonly opened together at an institution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountTemplate {
+    /// The name the template is chosen by
+    pub name: &'static str,
+    /// A description of the template for people choosing one
+    pub description: &'static str,
+    /// The accounts the template opens, in order
+    pub accounts: &'static [TemplateAccount],
+}
+
+/// The templates accounts can be created from.
+pub const ACCOUNT_TEMPLATES: &[AccountTemplate] = &[
+    AccountTemplate {
+        name: "everyday",
+        description: "A checking account, a savings account and a credit card.",
+        accounts: &[
+            TemplateAccount {
+                name: "Checking",
+                kind: "checking",
+            },
+            TemplateAccount {
+                name: "Savings",
+                kind: "savings",
+            },
+            TemplateAccount {
+                name: "Credit Card",
+                kind: "credit",
+            },
+        ],
+    },
+    AccountTemplate {
+        name: "investing",
+        description: "A brokerage account and a retirement account.",
+        accounts: &[
+            TemplateAccount {
+                name: "Brokerage",
+                kind: "brokerage",
+            },
+            TemplateAccount {
+                name: "Retirement",
+                kind: "retirement",
+            },
+        ],
+    },
+];
+
+impl AccountTemplate {
+    /// The template named `name`, if there is one.
+    pub fn find(name: &str) -> Option<&'static Self> {
+        ACCOUNT_TEMPLATES
+            .iter()
+            .find(|template| template.name == name)
+    }
+}
@@ -0,0 +1,155 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{
+        Condition, Filter, Predicate, account::AccountId, asset::AssetId, user::UserId,
+    };
+    pub use chrono::{DateTime, Utc};
+    pub use rust_decimal::Decimal;
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct AlertRuleId(pub Uuid);
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct AlertEventId(pub i64);
+
+/// Which way the balance has to cross the threshold for a rule to fire.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "alert_comparator", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertComparator {
+    /// The balance drops below the threshold
+    #[default]
+    Below,
+    /// The balance rises above the threshold
+    Above,
+}
+
+/// Where a rule sends its alerts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "alert_channel", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertChannel {
+    /// An email to the address of the user
+    #[default]
+    Email,
+    /// A request to the webhook of the user
+    Webhook,
+    /// An event on the event stream of the user
+    Sse,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct AlertRule {
+        /// The id of the rule
+        pub id: AlertRuleId,
+        /// When the rule was created
+        pub created_at: DateTime<Utc>,
+        /// When the rule was updated
+        pub updated_at: DateTime<Utc>,
+        /// The user the rule alerts
+        pub user_id: UserId,
+        /// The account whose balance the rule watches
+        pub account_id: AccountId,
+        /// The asset of the balance
+        pub asset_id: AssetId,
+        pub comparator: AlertComparator,
+        /// The balance the rule fires on crossing, in whole units of the
+        /// asset
+        pub threshold: Decimal,
+        pub channel: AlertChannel,
+        /// How many seconds the rule stays quiet after firing
+        pub cooldown_seconds: i64,
+        /// When the rule last fired
+        pub last_fired_at: Option<DateTime<Utc>>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct AlertRuleCreate {
+        pub user_id: UserId,
+        pub account_id: AccountId,
+        pub asset_id: AssetId,
+        pub comparator: AlertComparator,
+        pub threshold: Decimal,
+        pub channel: AlertChannel,
+        pub cooldown_seconds: i64,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct AlertRuleFilter {
+        pub user_id: Option<UserId>,
+    }
+
+    impl Filter for AlertRuleFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new().and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+        }
+    }
+
+    /// A time an alert rule fired.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct AlertEvent {
+        pub id: AlertEventId,
+        /// When the rule fired
+        pub created_at: DateTime<Utc>,
+        pub alert_rule_id: AlertRuleId,
+        /// The balance that crossed the threshold
+        pub balance: Decimal,
+        /// The channel the alert went out on
+        pub channel: AlertChannel,
+    }
+
+    /// An alert that just fired, with the user its rule alerts.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct FiredAlert {
+        #[sqlx(flatten)]
+        pub alert_event: AlertEvent,
+        pub user_id: UserId,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct AlertEventFilter {
+        pub alert_rule_id: Option<AlertRuleId>,
+    }
+
+    impl Filter for AlertEventFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new().and_some(self.alert_rule_id, |alert_rule_id| {
+                Condition::eq("alert_rule_id", alert_rule_id)
+            })
+        }
+    }
+}
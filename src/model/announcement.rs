@@ -0,0 +1,92 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{Condition, Filter, Predicate, user::UserId};
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct AnnouncementId(pub Uuid);
+
+/// How prominently an announcement is shown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "announcement_severity", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnouncementSeverity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct Announcement {
+        /// The id of the announcement
+        pub id: AnnouncementId,
+        /// When the announcement was created
+        pub created_at: DateTime<Utc>,
+        /// When the announcement was updated
+        pub updated_at: DateTime<Utc>,
+        /// The text shown to users
+        pub message: String,
+        pub severity: AnnouncementSeverity,
+        /// When the announcement starts being shown
+        pub starts_at: DateTime<Utc>,
+        /// When the announcement stops being shown
+        pub ends_at: DateTime<Utc>,
+        /// The admin who created the announcement
+        pub created_by: Option<UserId>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct AnnouncementCreate {
+        pub message: String,
+        pub severity: AnnouncementSeverity,
+        pub starts_at: DateTime<Utc>,
+        pub ends_at: DateTime<Utc>,
+        pub created_by: Option<UserId>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct AnnouncementFilter {
+        /// Only the announcements shown at this time, which starts inclusive
+        /// and ends exclusive
+        pub active_at: Option<DateTime<Utc>>,
+    }
+
+    impl Filter for AnnouncementFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.active_at, |active_at| {
+                    Condition::lte("starts_at", active_at)
+                })
+                .and_some(self.active_at, |active_at| {
+                    Condition::gt("ends_at", active_at)
+                })
+        }
+    }
+}
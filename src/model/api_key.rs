@@ -0,0 +1,76 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{Condition, Filter, Predicate, account::AccountId, user::UserId};
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("api_key_id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct ApiKeyId(pub Uuid);
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct ApiKey {
+        /// The id of the API key
+        pub id: ApiKeyId,
+        /// When the API key was created
+        pub created_at: DateTime<Utc>,
+        /// When the API key was updated
+        pub updated_at: DateTime<Utc>,
+        /// The user on whose behalf the key acts
+        pub user_id: UserId,
+        /// The display name of the API key
+        pub name: String,
+        /// The SHA-256 digest of the secret, which is never stored
+        pub key_hash: Vec<u8>,
+        /// Whether the key may only read
+        pub read_only: bool,
+        /// The accounts the key is limited to, or all of the user's accounts
+        /// if unset
+        pub account_ids: Option<Vec<AccountId>>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct ApiKeyCreate {
+        pub user_id: UserId,
+        pub name: String,
+        pub key_hash: Vec<u8>,
+        pub read_only: bool,
+        pub account_ids: Option<Vec<AccountId>>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct ApiKeyFilter {
+        pub user_id: Option<UserId>,
+        pub key_hash: Option<Vec<u8>>,
+    }
+
+    impl Filter for ApiKeyFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+                .and_some(self.key_hash, |key_hash| {
+                    Condition::eq("key_hash", key_hash)
+                })
+        }
+    }
+}
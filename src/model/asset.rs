@@ -4,7 +4,7 @@ use uuid::Uuid;
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
-    pub use crate::model::Filter;
+    pub use crate::model::{Condition, Filter, ListSort, Predicate};
     pub use chrono::{DateTime, Utc};
     pub use sqlx::{FromRow, Type};
     pub use utoipa::{IntoParams, ToSchema};
@@ -13,12 +13,17 @@ mod ssr_imports {
 #[cfg(feature = "ssr")]
 use ssr_imports::*;
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize)]
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, FromStr, From, Serialize, Deserialize,
+)]
 #[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
 #[cfg_attr(feature = "ssr", into_params(names("id")))]
 #[cfg_attr(feature = "ssr", sqlx(transparent))]
 pub struct AssetId(pub Uuid);
 
+/// The most decimals the quantities of an asset may have.
+pub const MAX_DECIMALS: i16 = 18;
+
 #[cfg(feature = "ssr")]
 pub use ssr::*;
 
@@ -33,12 +38,22 @@ mod ssr {
         pub updated_at: DateTime<Utc>,
         pub name: String,
         pub symbol: String,
+        /// How many decimals quantities of the asset have
+        pub decimals: i16,
+    }
+
+    impl Asset {
+        /// The decimals of the asset as a scale of its quantities.
+        pub fn scale(&self) -> u32 {
+            self.decimals.unsigned_abs().into()
+        }
     }
 
     #[derive(Debug, Clone)]
     pub struct AssetCreate {
         pub name: String,
         pub symbol: String,
+        pub decimals: i16,
     }
 
     #[derive(Debug, Clone, Default)]
@@ -51,29 +66,15 @@ mod ssr {
     pub struct AssetFilter {
         pub name: Option<String>,
         pub symbol: Option<String>,
+        /// The order the rows are listed in
+        pub sort: ListSort,
     }
 
     impl Filter for AssetFilter {
-        fn push(self, query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>) {
-            if self.name.is_none() && self.symbol.is_none() {
-                return;
-            }
-            query.push(r#"WHERE "#);
-            let mut has_prev_filter = false;
-
-            if let Some(name) = self.name {
-                has_prev_filter |= true;
-                query.push(r#"name = "#);
-                query.push_bind(name);
-            }
-
-            if let Some(symbol) = self.symbol {
-                if has_prev_filter {
-                    query.push(r#" AND "#);
-                }
-                query.push(r#"symbol = "#);
-                query.push_bind(symbol);
-            }
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.name, |name| Condition::eq("name", name))
+                .and_some(self.symbol, |symbol| Condition::eq("symbol", symbol))
         }
     }
 }
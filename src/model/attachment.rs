@@ -0,0 +1,121 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{Condition, Filter, Predicate, transaction::TransactionId};
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct AttachmentId(pub Uuid);
+
+/// How far the extraction of an attachment has come.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "extraction_status", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionStatus {
+    #[default]
+    Pending,
+    Complete,
+    /// The extractor errored, the attachment itself is kept
+    Failed,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The columns of an attachment without its content.
+    pub const ATTACHMENT_COLUMNS: &str =
+        "id, created_at, updated_at, transaction_id, filename, content_type, size";
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct Attachment {
+        /// The id of the attachment
+        pub id: AttachmentId,
+        /// When the attachment was created
+        pub created_at: DateTime<Utc>,
+        /// When the attachment was updated
+        pub updated_at: DateTime<Utc>,
+        /// The transaction the attachment belongs to
+        pub transaction_id: TransactionId,
+        /// The sanitized name of the uploaded file, only for display. The
+        /// content is kept under the id rather than the name.
+        pub filename: String,
+        /// The content type sniffed from the file
+        pub content_type: String,
+        /// The size of the content in bytes
+        pub size: i64,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct AttachmentCreate {
+        pub transaction_id: TransactionId,
+        pub filename: String,
+        pub content_type: String,
+        pub content: Vec<u8>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct AttachmentFilter {
+        pub transaction_id: Option<TransactionId>,
+    }
+
+    impl Filter for AttachmentFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new().and_some(self.transaction_id, |transaction_id| {
+                Condition::eq("transaction_id", transaction_id)
+            })
+        }
+    }
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct AttachmentExtraction {
+        pub id: Uuid,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+        /// The attachment the candidates were read from
+        pub attachment_id: AttachmentId,
+        /// The name of the extractor that ran
+        pub extractor: String,
+        pub status: ExtractionStatus,
+        /// The total of the receipt, in the smallest unit printed on it
+        pub quantity: Option<i64>,
+        /// The date printed on the receipt
+        pub posted_at: Option<DateTime<Utc>>,
+        /// The merchant printed on the receipt
+        pub merchant: Option<String>,
+        /// Why the extraction failed
+        pub error: Option<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct AttachmentExtractionCreate {
+        pub attachment_id: AttachmentId,
+        pub extractor: String,
+        pub status: ExtractionStatus,
+        pub quantity: Option<i64>,
+        pub posted_at: Option<DateTime<Utc>>,
+        pub merchant: Option<String>,
+        pub error: Option<String>,
+    }
+}
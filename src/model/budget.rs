@@ -0,0 +1,122 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        model::{Condition, Filter, Predicate, account::AccountId, asset::AssetId, user::UserId},
+        rounding::RoundingPolicy,
+    };
+    pub use chrono::{DateTime, Utc};
+    pub use rust_decimal::Decimal;
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct BudgetId(pub Uuid);
+
+/// How the spending limit of a budget is set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "budget_kind", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    /// A fixed amount of the budget asset
+    #[default]
+    Fixed,
+    /// A percentage of the income over the same period
+    PercentOfIncome,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct Budget {
+        /// The id of the budget
+        pub id: BudgetId,
+        /// When the budget was created
+        pub created_at: DateTime<Utc>,
+        /// When the budget was updated
+        pub updated_at: DateTime<Utc>,
+        /// The user to whom the budget belongs
+        pub user_id: UserId,
+        /// The name of the budget
+        pub name: String,
+        /// The asset the limit is in and spending is converted into
+        pub asset_id: AssetId,
+        pub kind: BudgetKind,
+        /// The limit of a fixed budget
+        pub amount: Option<Decimal>,
+        /// The percentage of income a percent of income budget allows
+        pub percent: Option<i16>,
+        /// The accounts whose spending counts, or all of them if none
+        pub account_ids: Option<Vec<AccountId>>,
+    }
+
+    impl Budget {
+        /// The spending limit given the income of the period. A percent of
+        /// income budget has none while there is no income, and is rounded
+        /// by `rounding` at the precision of the income.
+        pub fn limit(&self, income: Decimal, rounding: RoundingPolicy) -> Option<Decimal> {
+            match self.kind {
+                BudgetKind::Fixed => self.amount,
+                BudgetKind::PercentOfIncome if income > Decimal::ZERO => {
+                    self.percent.and_then(|percent| {
+                        rounding.percent_of(income, percent.into(), income.scale())
+                    })
+                }
+                BudgetKind::PercentOfIncome => None,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct BudgetCreate {
+        pub user_id: UserId,
+        pub name: String,
+        pub asset_id: AssetId,
+        pub kind: BudgetKind,
+        pub amount: Option<Decimal>,
+        pub percent: Option<i16>,
+        pub account_ids: Option<Vec<AccountId>>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct BudgetFilter {
+        pub user_id: Option<UserId>,
+    }
+
+    impl Filter for BudgetFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new().and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+        }
+    }
+
+    /// The spending and income of a period. Amounts that could be converted
+    /// into the budget asset have no `asset_id`, while the rest are in the
+    /// asset given.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct BudgetTotal {
+        pub asset_id: Option<AssetId>,
+        pub spent: Decimal,
+        pub income: Decimal,
+    }
+}
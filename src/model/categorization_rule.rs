@@ -0,0 +1,97 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{Condition, Filter, Predicate, account::AccountId, user::UserId};
+    pub use chrono::{DateTime, Utc};
+    pub use rust_decimal::Decimal;
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct CategorizationRuleId(pub Uuid);
+
+/// What a categorization rule matches transactions on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "categorization_rule_field", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CategorizationRuleField {
+    /// The description matches the `pattern`
+    #[default]
+    Description,
+    /// The quantity is within `min_quantity` and `max_quantity`
+    QuantityRange,
+    /// The transaction is in the `account_id` account
+    Account,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct CategorizationRule {
+        /// The id of the rule
+        pub id: CategorizationRuleId,
+        /// When the rule was created
+        pub created_at: DateTime<Utc>,
+        /// When the rule was updated
+        pub updated_at: DateTime<Utc>,
+        /// The user whose transactions the rule categorizes
+        pub user_id: UserId,
+        pub field: CategorizationRuleField,
+        /// The regular expression a `description` rule searches for
+        pub pattern: Option<String>,
+        /// The inclusive bounds of a `quantity_range` rule, in whole units
+        /// of the asset of the transaction
+        pub min_quantity: Option<Decimal>,
+        pub max_quantity: Option<Decimal>,
+        /// The account of an `account` rule
+        pub account_id: Option<AccountId>,
+        /// The category the rule sets
+        pub category: String,
+        /// Rules with a higher priority are tried first
+        pub priority: i32,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct CategorizationRuleCreate {
+        pub user_id: UserId,
+        pub field: CategorizationRuleField,
+        pub pattern: Option<String>,
+        pub min_quantity: Option<Decimal>,
+        pub max_quantity: Option<Decimal>,
+        pub account_id: Option<AccountId>,
+        pub category: String,
+        pub priority: i32,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct CategorizationRuleFilter {
+        pub user_id: Option<UserId>,
+    }
+
+    impl Filter for CategorizationRuleFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new().and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+        }
+    }
+}
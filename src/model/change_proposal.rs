@@ -0,0 +1,108 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{
+        Condition, Filter, Predicate, asset::AssetId, institution::InstitutionId, user::UserId,
+    };
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type, types::Json};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct ChangeProposalId(pub Uuid);
+
+/// The change to the shared institutions and assets a proposal makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "change_proposal_kind", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeProposalKind {
+    CreateInstitution,
+    EditInstitution,
+    CreateAsset,
+}
+
+/// Where a proposal is in its review.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "change_proposal_status", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeProposalStatus {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct ChangeProposal {
+        pub id: ChangeProposalId,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+        pub kind: ChangeProposalKind,
+        /// The request the change is made with once approved
+        pub payload: Json<serde_json::Value>,
+        /// The user who proposed the change
+        pub proposer_id: UserId,
+        pub status: ChangeProposalStatus,
+        /// The admin who approved or rejected the proposal
+        pub reviewer_id: Option<UserId>,
+        /// Why the proposal was approved or rejected
+        pub review_note: Option<String>,
+        pub reviewed_at: Option<DateTime<Utc>>,
+        /// The institution the proposal edits, or was created by approving it
+        pub institution_id: Option<InstitutionId>,
+        /// The asset created by approving the proposal
+        pub asset_id: Option<AssetId>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct ChangeProposalCreate {
+        pub kind: ChangeProposalKind,
+        pub payload: serde_json::Value,
+        pub proposer_id: UserId,
+        /// The institution an edit is of
+        pub institution_id: Option<InstitutionId>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct ChangeProposalFilter {
+        pub proposer_id: Option<UserId>,
+        pub status: Option<ChangeProposalStatus>,
+    }
+
+    impl Filter for ChangeProposalFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.proposer_id, |proposer_id| {
+                    Condition::eq("proposer_id", proposer_id)
+                })
+                .and_some(self.status, |status| Condition::eq("status", status))
+        }
+    }
+}
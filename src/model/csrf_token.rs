@@ -1,17 +1,40 @@
+use chrono::{DateTime, Utc};
 use derive_more::{Display, From, FromStr};
 use oauth2::CsrfToken as OauthCsrfToken;
 use serde::{Deserialize, Serialize};
-use sqlx::prelude::FromRow;
+use sqlx::{Type, prelude::FromRow};
 
-#[derive(Debug, Default, Clone, FromRow, FromStr, From, Serialize, Deserialize, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, From, Serialize, Deserialize, Type)]
+#[sqlx(transparent)]
+pub struct CsrfTokenId(pub i64);
+
+/// The `state` an authorization request is sent with and the redirect
+/// back from the provider returns.
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Display, FromStr, From, Serialize, Deserialize, Type,
+)]
+#[sqlx(transparent)]
+pub struct CsrfState(pub String);
+
+/// A state issued for a login, which the redirect back consumes once.
+#[derive(Debug, Clone, FromRow)]
 pub struct CsrfToken {
-    pub token: String,
+    pub id: CsrfTokenId,
+    pub state: CsrfState,
+    pub created_at: DateTime<Utc>,
+    /// When the redirect back consumed the state
+    pub consumed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CsrfTokenCreate {
+    pub state: CsrfState,
 }
 
-impl From<OauthCsrfToken> for CsrfToken {
+impl From<OauthCsrfToken> for CsrfTokenCreate {
     fn from(value: OauthCsrfToken) -> Self {
         Self {
-            token: value.into_secret(),
+            state: CsrfState(value.into_secret()),
         }
     }
 }
@@ -15,14 +15,15 @@ use serde::{Deserialize, Serialize};
 use sqlx::{Acquire, FromRow, Type};
 use thiserror::Error;
 use tracing::{debug, error};
-use zerocopy::{FromBytes, IntoBytes, SizeError};
+use zerocopy::{FromBytes, Immutable, IntoBytes, SizeError};
 use zerocopy_derive::{FromBytes, Immutable, IntoBytes};
 
 use crate::{
     api::{ApiError, AppState},
-    model::Filter,
-    resource::{CreateRepository, GetListRepository, cursor_key_repository::CursorKeyRepository},
-    schema::Cursor,
+    model::{Condition, Filter, Predicate},
+    resource::{
+        CreateRepository, GetListRepository, cursor_key_repository::CursorKeyRepository, deadline,
+    },
 };
 
 #[derive(
@@ -55,7 +56,7 @@ pub struct CursorKey {
     pub key_data: Vec<u8>,
 }
 
-#[derive(Debug, Error, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Error, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum EncryptionError {
     #[error("Invalid length.")]
     InvalidLength,
@@ -63,6 +64,8 @@ pub enum EncryptionError {
     Aes,
     #[error("Invalid size")]
     Size,
+    #[error("Wrong key.")]
+    WrongKey,
 }
 
 impl From<InvalidLength> for EncryptionError {
@@ -83,35 +86,77 @@ impl<S, D> From<SizeError<S, D>> for EncryptionError {
     }
 }
 
+/// The length of the key id and the nonce before the ciphertext.
+const HEADER_LEN: usize = 16;
+
+/// Reads the id of the key a packed cursor was encrypted with.
+pub fn cursor_key_id(packed_bytes: &[u8]) -> Result<CursorKeyId, EncryptionError> {
+    let id_bytes = packed_bytes
+        .get(0..4)
+        .ok_or(EncryptionError::InvalidLength)?;
+    Ok(CursorKeyId::read_from_bytes(id_bytes)?)
+}
+
 impl CursorKey {
-    fn encrypt(&self, cursor: Cursor) -> Result<Vec<u8>, EncryptionError> {
+    /// A key that has not been stored, as used outside of the server.
+    pub fn new(id: CursorKeyId, key_data: Vec<u8>) -> Self {
+        let now = Utc::now();
+        Self {
+            id,
+            created_at: now,
+            updated_at: now,
+            expires_at: None,
+            key_data,
+        }
+    }
+
+    fn encrypt<T: IntoBytes + Immutable>(&self, value: T) -> Result<Vec<u8>, EncryptionError> {
         let mut rng = rand::rng();
         let nonce_bytes: [u8; 12] = rng.random();
         let nonce = Nonce::from_slice(&nonce_bytes);
-        let cursor_bytes = cursor.as_bytes();
+        let cursor_bytes = value.as_bytes();
         let key = Aes256GcmSiv::new_from_slice(&self.key_data)?;
         let cursor_encrypted_bytes = key.encrypt(nonce, cursor_bytes)?;
-        let mut encrypted_bytes = vec![0; 16 + cursor_encrypted_bytes.len()];
+        let mut encrypted_bytes = vec![0; HEADER_LEN + cursor_encrypted_bytes.len()];
         encrypted_bytes[0..4].copy_from_slice(self.id.as_bytes());
-        encrypted_bytes[4..16].copy_from_slice(&nonce_bytes);
-        encrypted_bytes[16..].copy_from_slice(&cursor_encrypted_bytes);
+        encrypted_bytes[4..HEADER_LEN].copy_from_slice(&nonce_bytes);
+        encrypted_bytes[HEADER_LEN..].copy_from_slice(&cursor_encrypted_bytes);
 
         Ok(encrypted_bytes)
     }
 
-    pub fn encrypt_base64(&self, cursor: Cursor) -> Result<String, EncryptionError> {
-        let encrypted_bytes = self.encrypt(cursor)?;
+    /// Encrypts an opaque token handed to clients. Cursors are laid out by
+    /// [`schema::cursor`](crate::schema::cursor) first.
+    pub fn encrypt_base64<T: IntoBytes + Immutable>(
+        &self,
+        value: T,
+    ) -> Result<String, EncryptionError> {
+        let encrypted_bytes = self.encrypt(value)?;
         let engine = GeneralPurpose::new(&URL_SAFE, general_purpose::NO_PAD);
         let encoded_string = engine.encode(encrypted_bytes);
         Ok(encoded_string)
     }
 
-    pub fn decrypt(&self, packed_bytes: &[u8]) -> Result<Cursor, EncryptionError> {
-        let nonce = Nonce::from_slice(&packed_bytes[4..16]);
+    /// Decrypts a packed token. Any input that was not produced by
+    /// [`CursorKey::encrypt_base64`] with this key is an error.
+    pub fn decrypt<T: FromBytes>(&self, packed_bytes: &[u8]) -> Result<T, EncryptionError> {
+        let decrypted_bytes = self.decrypt_bytes(packed_bytes)?;
+        let value = T::read_from_bytes(&decrypted_bytes)?;
+        Ok(value)
+    }
+
+    /// Decrypts a packed token into its plaintext, for layouts whose
+    /// length varies.
+    pub fn decrypt_bytes(&self, packed_bytes: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        if packed_bytes.len() < HEADER_LEN {
+            return Err(EncryptionError::InvalidLength);
+        }
+        if cursor_key_id(packed_bytes)? != self.id {
+            return Err(EncryptionError::WrongKey);
+        }
+        let nonce = Nonce::from_slice(&packed_bytes[4..HEADER_LEN]);
         let key = Aes256GcmSiv::new_from_slice(&self.key_data)?;
-        let decrypted_bytes = key.decrypt(nonce, &packed_bytes[16..])?;
-        let cursor = Cursor::read_from_bytes(&decrypted_bytes)?;
-        Ok(cursor)
+        Ok(key.decrypt(nonce, &packed_bytes[HEADER_LEN..])?)
     }
 }
 
@@ -124,15 +169,10 @@ pub struct CursorKeyFilter {
 }
 
 impl Filter for CursorKeyFilter {
-    fn push(self, query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>) {
-        if self.expires_at.is_none() {
-            return;
-        }
-        query.push(r#"WHERE "#);
-        if let Some(expires_at) = self.expires_at {
-            query.push(r#"expires_at IS NULL OR expires_at > "#);
-            query.push_bind(expires_at);
-        }
+    fn predicate(self) -> Predicate {
+        Predicate::new().and_some(self.expires_at, |expires_at| {
+            Condition::is_null("expires_at").or(Condition::gt("expires_at", expires_at))
+        })
     }
 }
 
@@ -144,7 +184,7 @@ impl Filter for CursorKeyFilter {
 )]
 async fn get_cursor_key(state: &AppState) -> Result<CursorKey, ApiError> {
     debug!("Refreshing cursor key.");
-    let mut connection = state.connection_pool.begin().await.map_err(|e| {
+    let mut connection = deadline::begin(&state.connection_pool).await.map_err(|e| {
         error!("{e}");
         ApiError::ServerError
     })?;
@@ -200,3 +240,65 @@ impl FromRequestParts<AppState> for CursorKey {
         Ok(cursor_key)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::schema::cursor::{Cursor, decode, encode};
+
+    fn cursor_key(id: i32) -> CursorKey {
+        CursorKey::new(CursorKeyId(id), rand::random::<[u8; 32]>().to_vec())
+    }
+
+    proptest! {
+        #[test]
+        fn it_round_trips_cursors(id: i32, offset: i64, max_items: i64) {
+            let cursor_key = cursor_key(id);
+            let packed_bytes = cursor_key.encrypt(encode(Cursor { offset, max_items })).unwrap();
+            prop_assert_eq!(cursor_key_id(&packed_bytes).unwrap(), cursor_key.id);
+            let cursor = decode(&cursor_key.decrypt_bytes(&packed_bytes).unwrap(), 1).unwrap();
+            prop_assert_eq!((cursor.offset, cursor.max_items), (offset, max_items));
+        }
+
+        #[test]
+        fn it_rejects_mutated_cursors(
+            offset: i64,
+            max_items: i64,
+            index: prop::sample::Index,
+            flip in 1..=u8::MAX,
+        ) {
+            let cursor_key = cursor_key(1);
+            let mut packed_bytes = cursor_key.encrypt(encode(Cursor { offset, max_items })).unwrap();
+            let index = index.index(packed_bytes.len());
+            packed_bytes[index] ^= flip;
+            prop_assert!(cursor_key.decrypt_bytes(&packed_bytes).is_err());
+        }
+
+        #[test]
+        fn it_rejects_truncated_cursors(offset: i64, max_items: i64, len: prop::sample::Index) {
+            let cursor_key = cursor_key(1);
+            let packed_bytes = cursor_key.encrypt(encode(Cursor { offset, max_items })).unwrap();
+            let len = len.index(packed_bytes.len());
+            prop_assert!(cursor_key.decrypt_bytes(&packed_bytes[..len]).is_err());
+        }
+
+        #[test]
+        fn it_rejects_cursors_of_other_keys(offset: i64, max_items: i64) {
+            let packed_bytes = cursor_key(1).encrypt(encode(Cursor { offset, max_items })).unwrap();
+            prop_assert!(matches!(
+                cursor_key(2).decrypt_bytes(&packed_bytes),
+                Err(EncryptionError::WrongKey)
+            ));
+            prop_assert!(cursor_key(1).decrypt_bytes(&packed_bytes).is_err());
+        }
+
+        #[test]
+        fn it_never_panics_on_arbitrary_bytes(
+            packed_bytes in prop::collection::vec(any::<u8>(), 0..96),
+        ) {
+            let _ = cursor_key(1).decrypt_bytes(&packed_bytes);
+        }
+    }
+}
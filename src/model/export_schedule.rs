@@ -0,0 +1,250 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{Condition, Filter, Predicate, user::UserId};
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct ExportScheduleId(pub Uuid);
+
+/// The file format of an export.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "export_format", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// The accounts and transactions of the user as one JSON document
+    #[default]
+    Json,
+    /// The transactions of the user, one per row
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv",
+        }
+    }
+}
+
+/// Where exports are uploaded to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "export_destination_kind", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportDestinationKind {
+    S3,
+    Webdav,
+    /// Not uploaded anywhere, which pauses the schedule
+    #[default]
+    None,
+}
+
+/// How the last run of a schedule went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "export_run_status", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportRunStatus {
+    Succeeded,
+    Failed,
+}
+
+/// The configuration of an export destination.
+///
+/// The credentials are write only: they are sealed with the data key when
+/// stored and stripped by [`DestinationConfig::redacted`] before the
+/// configuration is returned.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DestinationConfig {
+    S3 {
+        bucket: String,
+        region: String,
+        /// The endpoint of an S3 compatible store, if not AWS
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        endpoint: Option<String>,
+        /// The key prefix the exports are uploaded under
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        prefix: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "ssr", schema(write_only))]
+        access_key_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "ssr", schema(write_only))]
+        secret_access_key: Option<String>,
+    },
+    Webdav {
+        /// The collection the exports are uploaded into
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        username: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "ssr", schema(write_only))]
+        password: Option<String>,
+    },
+    #[default]
+    None,
+}
+
+impl DestinationConfig {
+    pub fn kind(&self) -> ExportDestinationKind {
+        match self {
+            Self::S3 { .. } => ExportDestinationKind::S3,
+            Self::Webdav { .. } => ExportDestinationKind::Webdav,
+            Self::None => ExportDestinationKind::None,
+        }
+    }
+
+    /// The configuration without its credentials.
+    pub fn redacted(mut self) -> Self {
+        match &mut self {
+            Self::S3 {
+                access_key_id,
+                secret_access_key,
+                ..
+            } => {
+                *access_key_id = None;
+                *secret_access_key = None;
+            }
+            Self::Webdav { password, .. } => *password = None,
+            Self::None => {}
+        }
+        self
+    }
+
+    /// Fills the credentials left out of an update from the stored
+    /// configuration, as long as the destination is of the same kind.
+    pub fn keep_credentials_of(mut self, stored: &Self) -> Self {
+        match (&mut self, stored) {
+            (
+                Self::S3 {
+                    access_key_id,
+                    secret_access_key,
+                    ..
+                },
+                Self::S3 {
+                    access_key_id: stored_access_key_id,
+                    secret_access_key: stored_secret_access_key,
+                    ..
+                },
+            ) => {
+                if access_key_id.is_none() {
+                    access_key_id.clone_from(stored_access_key_id);
+                }
+                if secret_access_key.is_none() {
+                    secret_access_key.clone_from(stored_secret_access_key);
+                }
+            }
+            (
+                Self::Webdav { password, .. },
+                Self::Webdav {
+                    password: stored_password,
+                    ..
+                },
+            ) if password.is_none() => password.clone_from(stored_password),
+            _ => {}
+        }
+        self
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct ExportSchedule {
+        /// The id of the export schedule
+        pub id: ExportScheduleId,
+        /// When the export schedule was created
+        pub created_at: DateTime<Utc>,
+        /// When the export schedule was updated
+        pub updated_at: DateTime<Utc>,
+        /// The user whose data is exported
+        pub user_id: UserId,
+        pub format: ExportFormat,
+        pub destination_kind: ExportDestinationKind,
+        /// The [`DestinationConfig`], sealed with the data key
+        pub destination_config: Vec<u8>,
+        /// When the export runs, see [`crate::export::cadence::Cadence`]
+        pub cadence: String,
+        /// When the export last ran
+        pub last_run_at: Option<DateTime<Utc>>,
+        pub last_status: Option<ExportRunStatus>,
+        /// Why the last run failed, if it did
+        pub last_error: Option<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct ExportScheduleCreate {
+        pub user_id: UserId,
+        pub format: ExportFormat,
+        pub destination_kind: ExportDestinationKind,
+        pub destination_config: Vec<u8>,
+        pub cadence: String,
+    }
+
+    /// The outcome of running the export of a schedule.
+    #[derive(Debug, Clone)]
+    pub struct ExportRun {
+        pub ran_at: DateTime<Utc>,
+        pub status: ExportRunStatus,
+        pub error: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct ExportScheduleFilter {
+        pub user_id: Option<UserId>,
+        /// Only the schedules uploading somewhere
+        pub with_destination: bool,
+    }
+
+    impl Filter for ExportScheduleFilter {
+        fn predicate(self) -> Predicate {
+            let predicate = Predicate::new()
+                .and_some(self.user_id, |user_id| Condition::eq("user_id", user_id));
+            if self.with_destination {
+                predicate.and(Condition::sql(r#"destination_kind <> 'none'"#))
+            } else {
+                predicate
+            }
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+
+/// A feature an admin turned on or off, overriding the environment.
+#[derive(Debug, Clone, FromRow)]
+pub struct FeatureFlagOverride {
+    /// The [`crate::config::Feature::name`] of the feature
+    pub name: String,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,148 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{Condition, Filter, Predicate, account::AccountId, user::UserId};
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type, types::Json};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct ImportProfileId(pub Uuid);
+
+/// The CSV header names holding each transaction field.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ColumnMapping {
+    /// The column holding the posting date
+    pub posted_at: String,
+    /// The column holding the signed amount
+    pub amount: String,
+    /// The column holding the description, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// How the sign of an amount in the CSV relates to the transaction quantity.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "import_sign_convention", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SignConvention {
+    /// Deposits are positive and withdrawals negative
+    #[default]
+    AsIs,
+    /// Withdrawals are positive, as on most card statements
+    Inverted,
+}
+
+/// Everything needed to turn the rows of a CSV export into transactions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ImportMapping {
+    pub column_mapping: ColumnMapping,
+    /// A `strftime` style format for the posting date, with or without a time
+    pub date_format: String,
+    /// Either `.` or `,`. The other is taken to group thousands.
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: String,
+    /// How many minor units make up the amount, e.g. 2 for cents
+    #[serde(default)]
+    pub decimal_places: u8,
+    #[serde(default)]
+    pub sign_convention: SignConvention,
+}
+
+fn default_decimal_separator() -> String {
+    ".".into()
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct ImportProfile {
+        /// The id of the import profile
+        pub id: ImportProfileId,
+        /// When the import profile was created
+        pub created_at: DateTime<Utc>,
+        /// When the import profile was updated
+        pub updated_at: DateTime<Utc>,
+        /// The user to whom the import profile belongs
+        pub user_id: UserId,
+        /// The name of the import profile
+        pub name: String,
+        /// The CSV columns holding each transaction field
+        pub column_mapping: Json<ColumnMapping>,
+        /// The format of the posting date
+        pub date_format: String,
+        /// The decimal separator of amounts
+        pub decimal_separator: String,
+        /// How many minor units make up an amount
+        pub decimal_places: i16,
+        /// How the sign of an amount maps to the quantity
+        pub sign_convention: SignConvention,
+        /// The account imports go to unless another is given
+        pub default_account_id: Option<AccountId>,
+    }
+
+    impl ImportProfile {
+        pub fn mapping(&self) -> ImportMapping {
+            ImportMapping {
+                column_mapping: self.column_mapping.0.clone(),
+                date_format: self.date_format.clone(),
+                decimal_separator: self.decimal_separator.clone(),
+                decimal_places: u8::try_from(self.decimal_places).unwrap_or_default(),
+                sign_convention: self.sign_convention,
+            }
+        }
+
+        pub fn set_mapping(&mut self, mapping: ImportMapping) {
+            self.column_mapping = Json(mapping.column_mapping);
+            self.date_format = mapping.date_format;
+            self.decimal_separator = mapping.decimal_separator;
+            self.decimal_places = mapping.decimal_places.into();
+            self.sign_convention = mapping.sign_convention;
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct ImportProfileCreate {
+        pub user_id: UserId,
+        pub name: String,
+        pub mapping: ImportMapping,
+        pub default_account_id: Option<AccountId>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct ImportProfileFilter {
+        pub user_id: Option<UserId>,
+        pub name: Option<String>,
+    }
+
+    impl Filter for ImportProfileFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+                .and_some(self.name, |name| Condition::eq("name", name))
+        }
+    }
+}
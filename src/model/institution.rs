@@ -4,8 +4,9 @@ use uuid::Uuid;
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
-    pub use crate::model::Filter;
+    pub use crate::model::{Condition, Filter, ListSort, Predicate, asset::AssetId};
     pub use chrono::{DateTime, Utc};
+    pub use rust_decimal::Decimal;
     pub use sqlx::{Type, prelude::FromRow};
     pub use utoipa::{IntoParams, ToSchema};
 }
@@ -38,37 +39,90 @@ mod ssr {
         pub updated_at: DateTime<Utc>,
         /// The institution name
         pub name: String,
+        /// The institution this one is a part of, such as the bank of a branch
+        pub parent_id: Option<InstitutionId>,
+        /// The asset new accounts at the institution default to
+        pub default_asset_id: Option<AssetId>,
     }
 
     #[derive(Debug, Clone)]
     pub struct InstitutionCreate {
         /// The institution name
         pub name: String,
+        /// The parent institution
+        pub parent_id: Option<InstitutionId>,
+        /// The asset new accounts at the institution default to
+        pub default_asset_id: Option<AssetId>,
     }
 
     #[derive(Debug, Clone)]
     pub struct InstitutionUpdate {
         /// The new institution name
         pub name: Option<String>,
+        /// The new parent institution
+        pub parent_id: Option<InstitutionId>,
+        /// The new asset new accounts at the institution default to
+        pub default_asset_id: Option<AssetId>,
+    }
+
+    /// A row of an institution directory, keyed on the name of the
+    /// institution.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct InstitutionUpsert {
+        pub name: String,
+        /// The name of the parent institution, which must already exist or
+        /// be upserted alongside
+        pub parent: Option<String>,
+        pub default_asset_id: Option<AssetId>,
+    }
+
+    /// What upserting a row of a directory did to its institution.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum UpsertOutcome {
+        Created,
+        Updated,
+        Unchanged,
     }
 
     #[derive(Debug, Clone, Default)]
     pub struct InstitutionFilter {
         /// The institution name to filter on
         pub name: Option<String>,
+        /// The parent institution to filter on
+        pub parent_id: Option<InstitutionId>,
+        /// The order the rows are listed in
+        pub sort: ListSort,
     }
 
     impl Filter for InstitutionFilter {
-        fn push(self, query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>) {
-            if self.name.is_none() {
-                return;
-            }
-
-            query.push(r#"WHERE "#);
-            if let Some(name) = self.name {
-                query.push(r#"name = "#);
-                query.push_bind(name);
-            }
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.name, |name| Condition::eq("name", name))
+                .and_some(self.parent_id, |parent_id| {
+                    Condition::eq("parent_id", parent_id)
+                })
         }
     }
+
+    /// The totals of the caller's visible accounts across an institution
+    /// and all of its descendants.
+    #[derive(Debug, Clone)]
+    pub struct InstitutionRollup {
+        /// The number of institutions in the subtree, including the root
+        pub institution_count: i64,
+        /// The number of accounts held at institutions in the subtree
+        pub account_count: i64,
+        /// The balance of each asset held across those accounts
+        pub balances: Vec<InstitutionBalance>,
+    }
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct InstitutionBalance {
+        /// The asset of the balance
+        pub asset_id: AssetId,
+        /// The sum of the transaction quantities in the asset
+        pub quantity: Decimal,
+        /// The number of transactions making up the balance
+        pub transaction_count: i64,
+    }
 }
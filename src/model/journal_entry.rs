@@ -0,0 +1,128 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{account::AccountId, asset::AssetId, transaction::Transaction};
+    pub use chrono::{DateTime, Utc};
+    pub use rust_decimal::Decimal;
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct JournalEntryId(pub Uuid);
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// An entry of double-entry bookkeeping. Its legs are the transactions
+    /// linked to it, which sum to zero in each asset.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct JournalEntry {
+        pub id: JournalEntryId,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+        /// When the entry, and so each of its legs, was posted
+        pub posted_at: DateTime<Utc>,
+        pub description: Option<String>,
+    }
+
+    /// An entry together with its legs.
+    #[derive(Debug, Clone)]
+    pub struct JournalEntryWithLegs {
+        pub journal_entry: JournalEntry,
+        pub legs: Vec<Transaction>,
+    }
+
+    /// One side of an entry, moving `quantity` of an asset in or out of an
+    /// account.
+    #[derive(Debug, Clone)]
+    pub struct JournalLeg {
+        pub account_id: AccountId,
+        pub asset_id: AssetId,
+        /// The quantity in whole units of the asset, at its decimals
+        pub quantity: Decimal,
+        pub notes: Option<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct JournalEntryCreate {
+        pub posted_at: DateTime<Utc>,
+        pub description: Option<String>,
+        pub legs: Vec<JournalLeg>,
+    }
+
+    impl JournalEntryCreate {
+        /// The assets whose legs don't sum to zero, with what they sum to.
+        pub fn imbalances(&self) -> Vec<(AssetId, Decimal)> {
+            let mut sums: Vec<(AssetId, Decimal)> = vec![];
+            for leg in &self.legs {
+                match sums
+                    .iter_mut()
+                    .find(|(asset_id, _)| *asset_id == leg.asset_id)
+                {
+                    Some((_, sum)) => *sum += leg.quantity,
+                    None => sums.push((leg.asset_id, leg.quantity)),
+                }
+            }
+            sums.retain(|(_, sum)| !sum.is_zero());
+            sums
+        }
+    }
+
+    /// The balance of an account in an asset up to a point in time.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct TrialBalanceLine {
+        pub account_id: AccountId,
+        pub asset_id: AssetId,
+        pub balance: Decimal,
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod test {
+    use super::*;
+
+    fn leg(account_id: AccountId, asset_id: AssetId, quantity: i64) -> JournalLeg {
+        JournalLeg {
+            account_id,
+            asset_id,
+            quantity: Decimal::from(quantity),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn it_finds_the_assets_whose_legs_do_not_balance() {
+        let (cash, bank) = (AccountId(Uuid::new_v4()), AccountId(Uuid::new_v4()));
+        let (krw, usd) = (AssetId(Uuid::new_v4()), AssetId(Uuid::new_v4()));
+        let mut entry = JournalEntryCreate {
+            posted_at: Utc::now(),
+            description: None,
+            legs: vec![
+                leg(cash, krw, -1300),
+                leg(bank, krw, 1300),
+                leg(cash, usd, 1),
+                leg(bank, usd, -1),
+            ],
+        };
+        assert_eq!(entry.imbalances(), vec![]);
+
+        entry.legs.push(leg(bank, usd, 5));
+        assert_eq!(entry.imbalances(), vec![(usd, Decimal::from(5))]);
+    }
+}
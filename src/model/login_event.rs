@@ -0,0 +1,81 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{Condition, Filter, Predicate, user::UserId};
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct LoginEventId(pub i64);
+
+/// What the user did to the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "login_event_type", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginEventType {
+    /// Signed in through the identity provider
+    Login,
+    /// Refreshed the tokens of a session
+    Refresh,
+    /// Signed out
+    Logout,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct LoginEvent {
+        pub id: LoginEventId,
+        /// When the event happened
+        pub created_at: DateTime<Utc>,
+        pub user_id: UserId,
+        pub event_type: LoginEventType,
+        /// The issuer of the token of the session
+        pub issuer: String,
+        /// The address of the client, truncated or hashed, see
+        /// [`crate::authentication::client_address`]
+        pub ip_address: Option<String>,
+        pub user_agent: Option<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct LoginEventCreate {
+        pub user_id: UserId,
+        pub event_type: LoginEventType,
+        pub issuer: String,
+        pub ip_address: Option<String>,
+        pub user_agent: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct LoginEventFilter {
+        pub user_id: Option<UserId>,
+    }
+
+    impl Filter for LoginEventFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new().and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+        }
+    }
+}
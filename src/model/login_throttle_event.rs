@@ -0,0 +1,71 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct LoginThrottleEventId(pub i64);
+
+/// What the sign in and refresh endpoints count requests against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "login_throttle_key", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleKey {
+    /// The address of the client
+    Ip,
+    /// The refresh token sent to `/login/refresh`
+    RefreshToken,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// An address or refresh token that was made to wait.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct LoginThrottleEvent {
+        pub id: LoginThrottleEventId,
+        /// When it started to wait
+        pub created_at: DateTime<Utc>,
+        /// The path of the endpoint that made it wait
+        pub endpoint: String,
+        pub key_kind: ThrottleKey,
+        /// The address, truncated or hashed, see
+        /// [`crate::authentication::client_address`], or the fingerprint
+        /// of the refresh token. `None` for an address kept in no form.
+        pub key: Option<String>,
+        /// The refusals in a row before it, zero if it went over its limit
+        pub failures: i32,
+        /// Until when it waits
+        pub locked_until: DateTime<Utc>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct LoginThrottleEventCreate {
+        pub endpoint: String,
+        pub key_kind: ThrottleKey,
+        pub key: Option<String>,
+        pub failures: i32,
+        pub locked_until: DateTime<Utc>,
+    }
+}
@@ -1,18 +1,112 @@
 pub mod account;
+#[cfg(feature = "ssr")]
+pub mod account_balance;
+#[cfg(feature = "ssr")]
+pub mod account_template;
+pub mod alert_rule;
+pub mod announcement;
+pub mod api_key;
 pub mod asset;
+pub mod attachment;
+pub mod budget;
+pub mod categorization_rule;
+pub mod change_proposal;
 #[cfg(feature = "ssr")]
 pub mod csrf_token;
 #[cfg(feature = "ssr")]
 pub mod cursor_key;
+pub mod export_schedule;
+#[cfg(feature = "ssr")]
+pub mod feature_flag;
+pub mod import_profile;
 pub mod institution;
+pub mod journal_entry;
+pub mod login_event;
+pub mod login_throttle_event;
+pub mod passkey;
+#[cfg(feature = "ssr")]
+pub mod predicate;
+#[cfg(feature = "ssr")]
+pub mod provider_connection;
+pub mod quick_entry;
+pub mod recategorization;
+pub mod statement;
+#[cfg(feature = "ssr")]
+pub mod step_up_grant;
+pub mod sync;
 pub mod transaction;
+pub mod transaction_history;
 pub mod user;
+#[cfg(feature = "ssr")]
+pub mod user_preference;
+pub mod user_session;
+pub mod watchlist_entry;
+pub mod webauthn_challenge;
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The order a list is returned in. Rows that tie on it are ordered by
+/// their id, so walking a list page by page returns every row once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ListSort {
+    /// Oldest first
+    #[default]
+    CreatedAt,
+    /// By name, then oldest first
+    Name,
+}
+
+impl ListSort {
+    pub const ALL: [Self; 2] = [Self::CreatedAt, Self::Name];
+
+    /// The name of the sort in a query.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::Name => "name",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("There is no sort `{0}`.")]
+pub struct UnknownListSort(String);
+
+impl FromStr for ListSort {
+    type Err = UnknownListSort;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|sort| sort.name() == s)
+            .ok_or_else(|| UnknownListSort(s.to_owned()))
+    }
+}
 
 #[cfg(feature = "ssr")]
 mod ssr {
-    use sqlx::{Postgres, QueryBuilder};
+    use super::ListSort;
+    pub use super::predicate::{Condition, Predicate};
+
     pub trait Filter {
-        fn push(self, query: &mut QueryBuilder<'_, Postgres>);
+        /// The conditions a row has to meet to be listed.
+        fn predicate(self) -> Predicate;
+    }
+
+    impl ListSort {
+        /// The `ORDER BY` of the sort, without the `id` tie-breaker
+        /// [`crate::resource::list_query`] appends.
+        pub fn order_by(self) -> &'static str {
+            match self {
+                Self::CreatedAt => "created_at",
+                Self::Name => "name, created_at",
+            }
+        }
     }
 }
 
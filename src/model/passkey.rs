@@ -0,0 +1,74 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{Condition, Filter, Predicate, user::UserId};
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type, types::Json};
+    pub use utoipa::{IntoParams, ToSchema};
+    pub use webauthn_rs::prelude::Passkey as WebauthnPasskey;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("passkey_id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct PasskeyId(pub Uuid);
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct Passkey {
+        /// The id of the passkey
+        pub id: PasskeyId,
+        /// When the passkey was registered
+        pub created_at: DateTime<Utc>,
+        /// When the passkey was updated
+        pub updated_at: DateTime<Utc>,
+        /// The user to whom the passkey belongs
+        pub user_id: UserId,
+        /// The display name of the passkey
+        pub name: String,
+        /// The raw WebAuthn credential id
+        pub credential_id: Vec<u8>,
+        /// The stored credential, including its signature counter
+        pub credential: Json<WebauthnPasskey>,
+        /// When the passkey was last used for a step-up
+        pub last_used_at: Option<DateTime<Utc>>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct PasskeyCreate {
+        pub user_id: UserId,
+        pub name: String,
+        pub credential: WebauthnPasskey,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct PasskeyFilter {
+        pub user_id: Option<UserId>,
+        pub credential_id: Option<Vec<u8>>,
+    }
+
+    impl Filter for PasskeyFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+                .and_some(self.credential_id, |credential_id| {
+                    Condition::eq("credential_id", credential_id)
+                })
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use sqlx::{Encode, Postgres, QueryBuilder, Type};
+
+/// Pushes a value of a condition as a bind parameter.
+type Bind = Box<dyn FnOnce(&mut QueryBuilder<'_, Postgres>) + Send>;
+
+fn bind<T>(value: T) -> Bind
+where
+    T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Send + 'static,
+{
+    Box::new(move |query: &mut QueryBuilder<'_, Postgres>| {
+        query.push_bind(value);
+    })
+}
+
+/// `value` with the wildcards of `LIKE`, and the backslash that escapes
+/// them, escaped.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('%', r"\%")
+        .replace('_', r"\_")
+}
+
+/// How a column is compared with a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    ILike,
+}
+
+impl Comparison {
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Eq => " = ",
+            Self::Lt => " < ",
+            Self::Lte => " <= ",
+            Self::Gt => " > ",
+            Self::Gte => " >= ",
+            Self::ILike => " ILIKE ",
+        }
+    }
+}
+
+/// A condition a row has to meet. Columns and SQL are static, so only the
+/// bound values come from a request.
+pub enum Condition {
+    /// `column <comparison> value`
+    Compare {
+        column: &'static str,
+        comparison: Comparison,
+        value: Bind,
+    },
+    /// `column = ANY(values)`
+    Any { column: &'static str, values: Bind },
+    /// `column IN (subquery value)`, the subquery ending where its value
+    /// is bound
+    InSubquery {
+        column: &'static str,
+        subquery: &'static str,
+        value: Bind,
+    },
+    /// `column IS NULL`
+    IsNull(&'static str),
+    /// SQL without any values
+    Sql(&'static str),
+    /// Either of two conditions
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    fn compare<T>(column: &'static str, comparison: Comparison, value: T) -> Self
+    where
+        T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Send + 'static,
+    {
+        Self::Compare {
+            column,
+            comparison,
+            value: bind(value),
+        }
+    }
+
+    pub fn eq<T>(column: &'static str, value: T) -> Self
+    where
+        T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Send + 'static,
+    {
+        Self::compare(column, Comparison::Eq, value)
+    }
+
+    pub fn lt<T>(column: &'static str, value: T) -> Self
+    where
+        T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Send + 'static,
+    {
+        Self::compare(column, Comparison::Lt, value)
+    }
+
+    pub fn lte<T>(column: &'static str, value: T) -> Self
+    where
+        T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Send + 'static,
+    {
+        Self::compare(column, Comparison::Lte, value)
+    }
+
+    pub fn gt<T>(column: &'static str, value: T) -> Self
+    where
+        T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Send + 'static,
+    {
+        Self::compare(column, Comparison::Gt, value)
+    }
+
+    pub fn gte<T>(column: &'static str, value: T) -> Self
+    where
+        T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Send + 'static,
+    {
+        Self::compare(column, Comparison::Gte, value)
+    }
+
+    /// Matches `column` case insensitively against a `LIKE` pattern.
+    pub fn ilike(column: &'static str, pattern: String) -> Self {
+        Self::compare(column, Comparison::ILike, pattern)
+    }
+
+    /// Matches `column` case insensitively against any text containing
+    /// `value`. The wildcards of `LIKE` in `value` are escaped, so `50%`
+    /// only matches text with a literal `50%` in it.
+    pub fn contains(column: &'static str, value: &str) -> Self {
+        Self::ilike(column, format!("%{}%", escape_like(value)))
+    }
+
+    pub fn any<T>(column: &'static str, values: Vec<T>) -> Self
+    where
+        Vec<T>: for<'q> Encode<'q, Postgres> + Type<Postgres> + Send + 'static,
+    {
+        Self::Any {
+            column,
+            values: bind(values),
+        }
+    }
+
+    pub fn in_subquery<T>(column: &'static str, subquery: &'static str, value: T) -> Self
+    where
+        T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Send + 'static,
+    {
+        Self::InSubquery {
+            column,
+            subquery,
+            value: bind(value),
+        }
+    }
+
+    pub fn is_null(column: &'static str) -> Self {
+        Self::IsNull(column)
+    }
+
+    pub fn sql(sql: &'static str) -> Self {
+        Self::Sql(sql)
+    }
+
+    pub fn or(self, other: Condition) -> Self {
+        Self::Or(Box::new(self), Box::new(other))
+    }
+
+    fn push(self, query: &mut QueryBuilder<'_, Postgres>) {
+        match self {
+            Self::Compare {
+                column,
+                comparison,
+                value,
+            } => {
+                query.push(column);
+                query.push(comparison.sql());
+                value(query);
+            }
+            Self::Any { column, values } => {
+                query.push(column);
+                query.push(r#" = ANY("#);
+                values(query);
+                query.push(r#")"#);
+            }
+            Self::InSubquery {
+                column,
+                subquery,
+                value,
+            } => {
+                query.push(column);
+                query.push(r#" IN ("#);
+                query.push(subquery);
+                value(query);
+                query.push(r#")"#);
+            }
+            Self::IsNull(column) => {
+                query.push(column);
+                query.push(r#" IS NULL"#);
+            }
+            Self::Sql(sql) => {
+                query.push(sql);
+            }
+            Self::Or(left, right) => {
+                query.push(r#"("#);
+                left.push(query);
+                query.push(r#" OR "#);
+                right.push(query);
+                query.push(r#")"#);
+            }
+        }
+    }
+}
+
+/// The conditions of a filter, all of which a row has to meet.
+#[derive(Default)]
+pub struct Predicate(Vec<Condition>);
+
+impl Predicate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn and(mut self, condition: Condition) -> Self {
+        self.0.push(condition);
+        self
+    }
+
+    /// Adds the condition on `value` if there is one.
+    pub fn and_some<T>(self, value: Option<T>, condition: impl FnOnce(T) -> Condition) -> Self {
+        match value {
+            Some(value) => self.and(condition(value)),
+            None => self,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Pushes the `WHERE` clause of the conditions, if there are any.
+    pub fn push(self, query: &mut QueryBuilder<'_, Postgres>) {
+        for (i, condition) in self.0.into_iter().enumerate() {
+            query.push(if i == 0 { r#" WHERE "# } else { r#" AND "# });
+            condition.push(query);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn sql(predicate: Predicate) -> String {
+        let mut query = QueryBuilder::<Postgres>::new(r#"SELECT * FROM "transaction""#);
+        predicate.push(&mut query);
+        query.sql().to_owned()
+    }
+
+    #[test]
+    fn it_pushes_nothing_without_conditions() {
+        assert_eq!(sql(Predicate::new()), r#"SELECT * FROM "transaction""#);
+    }
+
+    #[test]
+    fn it_joins_conditions_with_and() {
+        let predicate = Predicate::new()
+            .and(Condition::contains("description", "coffee"))
+            .and_some(None::<i64>, |id| Condition::eq("id", id))
+            .and(Condition::gte("quantity", 1_i64))
+            .and(Condition::in_subquery(
+                "account_id",
+                "SELECT id FROM account WHERE user_id = ",
+                Uuid::nil(),
+            ))
+            .and(Condition::any("account_id", vec![Uuid::nil()]))
+            .and(Condition::is_null("expires_at").or(Condition::gt("expires_at", 0_i64)));
+        assert_eq!(
+            sql(predicate),
+            r#"SELECT * FROM "transaction" WHERE description ILIKE $1 AND quantity >= $2 AND account_id IN (SELECT id FROM account WHERE user_id = $3) AND account_id = ANY($4) AND (expires_at IS NULL OR expires_at > $5)"#
+        );
+    }
+
+    #[test]
+    fn it_binds_filter_values_instead_of_pushing_them() {
+        let injection = "'; DROP TABLE \"transaction\"; --";
+        let sql = sql(Predicate::new().and(Condition::contains("description", injection)));
+        assert_eq!(
+            sql,
+            r#"SELECT * FROM "transaction" WHERE description ILIKE $1"#
+        );
+        assert!(!sql.contains("DROP"));
+    }
+
+    #[test]
+    fn it_escapes_wildcards_in_text_to_contain() {
+        assert_eq!(escape_like("coffee"), "coffee");
+        assert_eq!(escape_like("50% off"), r"50\% off");
+        assert_eq!(escape_like("snake_case"), r"snake\_case");
+        assert_eq!(escape_like(r"C:\%"), r"C:\\\%");
+    }
+}
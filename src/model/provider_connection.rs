@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, Type, types::Json};
+use uuid::Uuid;
+
+use crate::model::{Condition, Filter, Predicate, institution::InstitutionId, user::UserId};
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize, Type,
+)]
+#[sqlx(transparent)]
+pub struct ProviderConnectionId(pub Uuid);
+
+/// Ties the accounts a user holds at an institution to the connector that
+/// syncs them.
+#[derive(Debug, Clone, FromRow)]
+pub struct ProviderConnection {
+    /// The id of the provider connection
+    pub id: ProviderConnectionId,
+    /// When the provider connection was created
+    pub created_at: DateTime<Utc>,
+    /// When the provider connection was updated
+    pub updated_at: DateTime<Utc>,
+    /// The user whose accounts are synced
+    pub user_id: UserId,
+    /// The institution the accounts are held at
+    pub institution_id: InstitutionId,
+    /// The name of the connector, e.g. `manual`
+    pub connector: String,
+    /// The connector specific configuration, such as credentials
+    pub config: Json<Value>,
+    /// The sync cursor of each remote account, by remote account id
+    pub cursors: Json<HashMap<String, String>>,
+    /// When an account was last synced through the connection
+    pub last_synced_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderConnectionCreate {
+    pub user_id: UserId,
+    pub institution_id: InstitutionId,
+    pub connector: String,
+    pub config: Value,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProviderConnectionFilter {
+    pub user_id: Option<UserId>,
+    pub institution_id: Option<InstitutionId>,
+}
+
+impl Filter for ProviderConnectionFilter {
+    fn predicate(self) -> Predicate {
+        Predicate::new()
+            .and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+            .and_some(self.institution_id, |institution_id| {
+                Condition::eq("institution_id", institution_id)
+            })
+    }
+}
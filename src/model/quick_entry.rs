@@ -0,0 +1,77 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{
+        Condition, Filter, Predicate, account::AccountId, asset::AssetId, user::UserId,
+    };
+    pub use chrono::{DateTime, Utc};
+    pub use rust_decimal::Decimal;
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct QuickEntryId(pub Uuid);
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct QuickEntry {
+        /// The id of the entry
+        pub id: QuickEntryId,
+        /// When the entry was created
+        pub created_at: DateTime<Utc>,
+        /// When the entry was updated
+        pub updated_at: DateTime<Utc>,
+        /// The user the entry belongs to
+        pub user_id: UserId,
+        /// What the entry is shown as
+        pub label: String,
+        /// The account the transactions are created in, none once it was
+        /// deleted
+        pub account_id: Option<AccountId>,
+        pub asset_id: AssetId,
+        /// The quantity of the transactions, in whole units of the asset
+        pub quantity: Decimal,
+        pub description: Option<String>,
+        pub category: Option<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct QuickEntryCreate {
+        pub user_id: UserId,
+        pub label: String,
+        pub account_id: AccountId,
+        pub asset_id: AssetId,
+        pub quantity: Decimal,
+        pub description: Option<String>,
+        pub category: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct QuickEntryFilter {
+        pub user_id: Option<UserId>,
+    }
+
+    impl Filter for QuickEntryFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new().and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+        }
+    }
+}
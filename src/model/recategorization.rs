@@ -0,0 +1,74 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::user::UserId;
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type, types::Json};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct RecategorizationId(pub Uuid);
+
+/// How far a recategorization has come.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "recategorization_status", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RecategorizationStatus {
+    #[default]
+    Pending,
+    Complete,
+    Failed,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// A bulk recategorization of the transactions of a user a filter
+    /// matches, kept as the record of the operation.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct Recategorization {
+        pub id: RecategorizationId,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+        pub user_id: UserId,
+        /// The filter of transactions it was made with
+        pub filter: Json<serde_json::Value>,
+        /// The category it sets
+        pub category: String,
+        /// How many transactions the filter matched when it started
+        pub matched: i64,
+        /// How many transactions it changed so far
+        pub changed: i64,
+        pub status: RecategorizationStatus,
+        /// Why it failed
+        pub error: Option<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct RecategorizationCreate {
+        pub user_id: UserId,
+        pub filter: serde_json::Value,
+        pub category: String,
+        pub matched: i64,
+    }
+}
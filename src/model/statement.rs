@@ -0,0 +1,139 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{account::AccountId, asset::AssetId, transaction::Transaction};
+    pub use chrono::{DateTime, Utc};
+    pub use rust_decimal::Decimal;
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("statement_id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct StatementId(pub Uuid);
+
+/// What a statement is rendered as.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "statement_format", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementFormat {
+    #[default]
+    Html,
+    /// Only rendered by servers built with the `pdf` feature
+    Pdf,
+}
+
+impl StatementFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Html => "text/html; charset=utf-8",
+            Self::Pdf => "application/pdf",
+        }
+    }
+}
+
+/// How far the rendering of a statement has come.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "statement_status", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementStatus {
+    #[default]
+    Pending,
+    Complete,
+    Failed,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The columns of a statement without its content.
+    pub const STATEMENT_COLUMNS: &str =
+        "id, created_at, updated_at, account_id, year, month, format, status, error";
+
+    /// A statement rendered after responding, as it was too large to
+    /// render while doing so.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct AccountStatement {
+        pub id: StatementId,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+        /// The account the statement is of
+        pub account_id: AccountId,
+        pub year: i32,
+        /// The month of the year, from 1
+        pub month: i32,
+        pub format: StatementFormat,
+        pub status: StatementStatus,
+        /// Why the rendering failed
+        pub error: Option<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct AccountStatementCreate {
+        pub account_id: AccountId,
+        pub year: i32,
+        pub month: i32,
+        pub format: StatementFormat,
+    }
+
+    /// The balance of an account in an asset at the start and the end of a
+    /// month.
+    #[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+    pub struct StatementBalance {
+        pub asset_id: AssetId,
+        pub symbol: String,
+        pub decimals: i16,
+        /// The sum of the quantities posted before the month
+        pub opening_balance: Decimal,
+        /// The sum of the quantities posted before the end of the month
+        pub closing_balance: Decimal,
+    }
+
+    /// The transactions of a month in an asset and a category.
+    #[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+    pub struct CategorySubtotal {
+        pub asset_id: AssetId,
+        /// The category, or `None` for the uncategorized transactions
+        pub category: Option<String>,
+        pub transactions: i64,
+        pub quantity: Decimal,
+    }
+
+    /// The rows a statement of a month is made of, read in one snapshot.
+    #[derive(Debug, Clone, Default)]
+    pub struct StatementRows {
+        pub balances: Vec<StatementBalance>,
+        /// The transactions posted in the month, in the order they were
+        pub transactions: Vec<Transaction>,
+        pub subtotals: Vec<CategorySubtotal>,
+    }
+}
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+
+use crate::model::user::UserId;
+
+/// A short-lived elevation minted by a successful passkey step-up and
+/// bound to the session fingerprint of the token that completed it.
+#[derive(Debug, Clone, FromRow)]
+pub struct StepUpGrant {
+    pub session: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub user_id: UserId,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepUpGrantCreate {
+    pub session: String,
+    pub expires_at: DateTime<Utc>,
+    pub user_id: UserId,
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{
+        account::{Account, AccountId},
+        transaction::Transaction,
+        user::UserId,
+    };
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// The kinds of resource a delta sync reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "sync_resource", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncResource {
+    Account,
+    Transaction,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// A record of a deleted account or transaction, written by a trigger
+    /// on the delete.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct SyncTombstone {
+        /// Increases with each deletion, and is the watermark for them
+        pub id: i64,
+        pub deleted_at: DateTime<Utc>,
+        pub user_id: UserId,
+        /// The deleted account, or the account of the deleted transaction
+        pub account_id: AccountId,
+        pub resource: SyncResource,
+        /// The id of the deleted resource, as text
+        pub resource_id: String,
+    }
+
+    /// A page of the changes of a delta sync, filled with accounts first.
+    #[derive(Debug, Clone)]
+    pub struct SyncChanges {
+        pub accounts: Vec<Account>,
+        pub transactions: Vec<Transaction>,
+        pub tombstones: Vec<SyncTombstone>,
+        /// Whether there are more changes after the page
+        pub has_more: bool,
+    }
+}
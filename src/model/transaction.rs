@@ -3,8 +3,12 @@ use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
-    pub use crate::model::{Filter, account::AccountId, asset::AssetId};
+    pub use crate::model::{
+        Condition, Filter, Predicate, account::AccountId, asset::AssetId,
+        categorization_rule::CategorizationRuleId, journal_entry::JournalEntryId,
+    };
     pub use chrono::{DateTime, Utc};
+    pub use rust_decimal::Decimal;
     pub use sqlx::{Type, prelude::FromRow};
     pub use utoipa::{IntoParams, ToSchema};
 }
@@ -18,6 +22,17 @@ use ssr_imports::*;
 #[cfg_attr(feature = "ssr", sqlx(transparent))]
 pub struct TransactionId(pub i64);
 
+/// Which way a transaction moves value, by the sign of its quantity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionDirection {
+    /// Value leaving the account, a negative quantity
+    Debit,
+    /// Value entering the account, a positive quantity
+    Credit,
+}
+
 #[cfg(feature = "ssr")]
 pub use ssr::*;
 
@@ -34,7 +49,16 @@ mod ssr {
         pub account_id: AccountId,
         pub asset_id: AssetId,
         pub description: Option<String>,
-        pub quantity: i64,
+        /// The quantity in whole units of the asset, at its decimals
+        pub quantity: Decimal,
+        pub notes: Option<String>,
+        /// The id of the transaction at the provider it was synced from
+        pub external_id: Option<String>,
+        pub category: Option<String>,
+        /// The rule that set the category, if one did
+        pub applied_rule_id: Option<CategorizationRuleId>,
+        /// The journal entry the transaction is a leg of, if any
+        pub journal_entry_id: Option<JournalEntryId>,
     }
 
     impl Transaction {
@@ -54,16 +78,42 @@ mod ssr {
             if let Some(quantity) = update_model.quantity {
                 self.quantity = quantity;
             }
+
+            // Empty notes clear the ones there were.
+            if let Some(notes) = update_model.notes {
+                self.notes = Some(notes).filter(|notes| !notes.trim().is_empty());
+            }
+
+            // A category set by hand is no longer the doing of a rule.
+            if let Some(category) = update_model.category {
+                self.category.replace(category);
+                self.applied_rule_id = None;
+            }
         }
     }
 
+    /// A transaction's quantity expressed in another asset, using the
+    /// latest price at or before `posted_at`.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct TransactionConversion {
+        pub transaction_id: TransactionId,
+        pub converted_quantity: Option<Decimal>,
+        pub rate_used: Option<String>,
+    }
+
     #[derive(Debug, Clone)]
     pub struct TransactionCreate {
         pub account_id: AccountId,
         pub asset_id: AssetId,
         pub description: Option<String>,
         pub posted_at: DateTime<Utc>,
-        pub quantity: i64,
+        pub quantity: Decimal,
+        pub notes: Option<String>,
+        pub external_id: Option<String>,
+        /// The category, which the rules of the user set if it is absent
+        pub category: Option<String>,
+        pub applied_rule_id: Option<CategorizationRuleId>,
+        pub journal_entry_id: Option<JournalEntryId>,
     }
 
     #[derive(Debug, Clone, Default)]
@@ -71,117 +121,102 @@ mod ssr {
         pub asset_id: Option<AssetId>,
         pub description: Option<String>,
         pub posted_at: Option<DateTime<Utc>>,
-        pub quantity: Option<i64>,
+        pub quantity: Option<Decimal>,
+        pub notes: Option<String>,
+        pub category: Option<String>,
     }
 
+    impl TransactionUpdate {
+        /// Whether the update moves value between assets or over time, which
+        /// the legs of a journal entry can't do without unbalancing it.
+        pub fn changes_amount(&self, transaction: &Transaction) -> bool {
+            self.asset_id.is_some_and(|x| x != transaction.asset_id)
+                || self.quantity.is_some_and(|x| x != transaction.quantity)
+                || self.posted_at.is_some_and(|x| x != transaction.posted_at)
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
     pub struct TransactionFilter {
         pub account_id: Option<AccountId>,
         pub asset_id: Option<AssetId>,
         pub description: Option<String>,
-        pub quantity: Option<i64>,
-        pub max_quantity: Option<i64>,
-        pub min_quantity: Option<i64>,
+        pub quantity: Option<Decimal>,
+        pub max_quantity: Option<Decimal>,
+        pub min_quantity: Option<Decimal>,
+        /// Only debits or only credits. Transactions of no quantity are
+        /// neither.
+        pub direction: Option<TransactionDirection>,
+        /// The bounds of the quantity regardless of its sign, which hold
+        /// along with those on the signed quantity
+        pub max_abs_quantity: Option<Decimal>,
+        pub min_abs_quantity: Option<Decimal>,
         pub posted_at: Option<DateTime<Utc>>,
         pub posted_before: Option<DateTime<Utc>>,
         pub posted_after: Option<DateTime<Utc>>,
+        /// Posted at or after, the start of a date range
+        pub posted_from: Option<DateTime<Utc>>,
+        pub created_after: Option<DateTime<Utc>>,
+        pub created_before: Option<DateTime<Utc>>,
+        pub updated_after: Option<DateTime<Utc>>,
+        pub updated_before: Option<DateTime<Utc>>,
     }
 
     impl Filter for TransactionFilter {
-        fn push(self, query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>) {
-            if self.description.is_none()
-                && self.asset_id.is_none()
-                && self.account_id.is_none()
-                && self.quantity.is_none()
-                && self.max_quantity.is_none()
-                && self.min_quantity.is_none()
-                && self.posted_at.is_none()
-                && self.posted_before.is_none()
-                && self.posted_after.is_none()
-            {
-                return;
-            }
-
-            query.push(r#"WHERE "#);
-
-            let mut has_prev_filter = false;
-
-            if let Some(description) = self.description {
-                has_prev_filter |= true;
-                query.push(r#"description ILIKE %"#);
-                query.push_bind(description);
-                query.push(r#"%"#);
-            }
-
-            if let Some(asset_id) = self.asset_id {
-                if has_prev_filter {
-                    query.push(r#" AND "#);
-                }
-                has_prev_filter |= true;
-                query.push(r#"asset_id = "#);
-                query.push_bind(asset_id);
-            }
-
-            if let Some(account_id) = self.account_id {
-                if has_prev_filter {
-                    query.push(r#" AND "#);
-                }
-                has_prev_filter |= true;
-                query.push(r#"account_id = "#);
-                query.push_bind(account_id);
-            }
-
-            if let Some(quantity) = self.quantity {
-                if has_prev_filter {
-                    query.push(r#" AND "#);
-                }
-                has_prev_filter |= true;
-                query.push(r#"quantity = "#);
-                query.push_bind(quantity);
-            }
-
-            if let Some(max_quantity) = self.max_quantity {
-                if has_prev_filter {
-                    query.push(r#" AND "#);
-                }
-                has_prev_filter |= true;
-                query.push(r#"quantity <= "#);
-                query.push_bind(max_quantity);
-            }
-
-            if let Some(min_quantity) = self.min_quantity {
-                if has_prev_filter {
-                    query.push(r#" AND "#);
-                }
-                has_prev_filter |= true;
-                query.push(r#"quantity >= "#);
-                query.push_bind(min_quantity);
-            }
-
-            if let Some(posted_at) = self.posted_at {
-                if has_prev_filter {
-                    query.push(r#" AND "#);
-                }
-                has_prev_filter |= true;
-                query.push(r#"posted_at = "#);
-                query.push_bind(posted_at);
-            }
-
-            if let Some(posted_before) = self.posted_before {
-                if has_prev_filter {
-                    query.push(r#" AND "#);
-                }
-                has_prev_filter |= true;
-                query.push(r#"posted_at < "#);
-                query.push_bind(posted_before);
-            }
-
-            if let Some(posted_after) = self.posted_after {
-                if has_prev_filter {
-                    query.push(r#" AND "#);
-                }
-                query.push(r#"posted_at > "#);
-                query.push_bind(posted_after);
-            }
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.description, |description| {
+                    Condition::contains("description", &description)
+                })
+                .and_some(self.asset_id, |asset_id| {
+                    Condition::eq("asset_id", asset_id)
+                })
+                .and_some(self.account_id, |account_id| {
+                    Condition::eq("account_id", account_id)
+                })
+                .and_some(self.quantity, |quantity| {
+                    Condition::eq("quantity", quantity)
+                })
+                .and_some(self.max_quantity, |max_quantity| {
+                    Condition::lte("quantity", max_quantity)
+                })
+                .and_some(self.min_quantity, |min_quantity| {
+                    Condition::gte("quantity", min_quantity)
+                })
+                .and_some(self.direction, |direction| match direction {
+                    TransactionDirection::Debit => Condition::sql("quantity < 0"),
+                    TransactionDirection::Credit => Condition::sql("quantity > 0"),
+                })
+                .and_some(self.max_abs_quantity, |max_abs_quantity| {
+                    Condition::lte("ABS(quantity)", max_abs_quantity)
+                })
+                .and_some(self.min_abs_quantity, |min_abs_quantity| {
+                    Condition::gte("ABS(quantity)", min_abs_quantity)
+                })
+                .and_some(self.posted_at, |posted_at| {
+                    Condition::eq("posted_at", posted_at)
+                })
+                .and_some(self.posted_before, |posted_before| {
+                    Condition::lt("posted_at", posted_before)
+                })
+                .and_some(self.posted_after, |posted_after| {
+                    Condition::gt("posted_at", posted_after)
+                })
+                .and_some(self.posted_from, |posted_from| {
+                    Condition::gte("posted_at", posted_from)
+                })
+                .and_some(self.created_after, |created_after| {
+                    Condition::gt("created_at", created_after)
+                })
+                .and_some(self.created_before, |created_before| {
+                    Condition::lt("created_at", created_before)
+                })
+                .and_some(self.updated_after, |updated_after| {
+                    Condition::gt("updated_at", updated_after)
+                })
+                .and_some(self.updated_before, |updated_before| {
+                    Condition::lt("updated_at", updated_before)
+                })
         }
     }
 }
@@ -0,0 +1,110 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{
+        Condition, Filter, Predicate,
+        asset::AssetId,
+        transaction::{TransactionId, TransactionUpdate},
+    };
+    pub use chrono::{DateTime, Utc};
+    pub use rust_decimal::Decimal;
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("history_id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct TransactionHistoryId(pub i64);
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The fields of a transaction an edit can be reverted on.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct TransactionSnapshot {
+        pub posted_at: DateTime<Utc>,
+        pub asset_id: AssetId,
+        pub description: Option<String>,
+        pub quantity: Decimal,
+    }
+
+    /// An update back to the snapshot. Updates cannot clear a description,
+    /// so one the snapshot lacks is left as it is.
+    impl From<TransactionSnapshot> for TransactionUpdate {
+        fn from(value: TransactionSnapshot) -> Self {
+            Self {
+                asset_id: Some(value.asset_id),
+                description: value.description,
+                posted_at: Some(value.posted_at),
+                quantity: Some(value.quantity),
+                notes: None,
+                category: None,
+            }
+        }
+    }
+
+    /// An edit of a transaction, written by a trigger on the update.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct TransactionHistory {
+        pub id: TransactionHistoryId,
+        /// When the transaction was edited
+        pub created_at: DateTime<Utc>,
+        pub transaction_id: TransactionId,
+        pub before_posted_at: DateTime<Utc>,
+        pub before_asset_id: AssetId,
+        pub before_description: Option<String>,
+        pub before_quantity: Decimal,
+        pub after_posted_at: DateTime<Utc>,
+        pub after_asset_id: AssetId,
+        pub after_description: Option<String>,
+        pub after_quantity: Decimal,
+    }
+
+    impl TransactionHistory {
+        /// The transaction as it was before the edit.
+        pub fn before(&self) -> TransactionSnapshot {
+            TransactionSnapshot {
+                posted_at: self.before_posted_at,
+                asset_id: self.before_asset_id,
+                description: self.before_description.clone(),
+                quantity: self.before_quantity,
+            }
+        }
+
+        /// The transaction as the edit left it.
+        pub fn after(&self) -> TransactionSnapshot {
+            TransactionSnapshot {
+                posted_at: self.after_posted_at,
+                asset_id: self.after_asset_id,
+                description: self.after_description.clone(),
+                quantity: self.after_quantity,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct TransactionHistoryFilter {
+        pub transaction_id: Option<TransactionId>,
+    }
+
+    impl Filter for TransactionHistoryFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new().and_some(self.transaction_id, |transaction_id| {
+                Condition::eq("transaction_id", transaction_id)
+            })
+        }
+    }
+}
@@ -4,7 +4,7 @@ use uuid::Uuid;
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
-    pub use crate::model::Filter;
+    pub use crate::model::{Condition, Filter, ListSort, Predicate};
     pub use chrono::{DateTime, Utc};
     pub use sqlx::{FromRow, Type};
     pub use utoipa::{IntoParams, ToSchema};
@@ -14,7 +14,7 @@ mod ssr_imports {
 use ssr_imports::*;
 
 #[derive(
-    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, Hash, FromStr, From, Serialize, Deserialize,
 )]
 #[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
 #[cfg_attr(feature = "ssr", into_params(names("id")))]
@@ -44,6 +44,10 @@ mod ssr {
         pub sub: String,
         /// The OAuth `iss` claim
         pub iss: String,
+        /// Whether the email of the token the user registered with was
+        /// verified, unknown for users registered before it was recorded or
+        /// created without a token
+        pub email_verified_at_registration: Option<bool>,
     }
 
     #[derive(Debug, Clone)]
@@ -56,6 +60,9 @@ mod ssr {
         pub sub: String,
         /// The OAuth `iss` claim
         pub iss: String,
+        /// Whether the token registering the user has a verified email, or
+        /// `None` when it is created without one
+        pub email_verified: Option<bool>,
     }
 
     #[derive(Debug, Clone)]
@@ -78,61 +85,18 @@ mod ssr {
         pub sub: Option<String>,
         /// The OAuth `iss` claim
         pub iss: Option<String>,
+        /// The order the rows are listed in
+        pub sort: ListSort,
     }
 
     impl Filter for UserFilter {
-        fn push(self, query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>) {
-            if self.id.is_none()
-                && self.name.is_none()
-                && self.email.is_none()
-                && self.sub.is_none()
-                && self.iss.is_none()
-            {
-                return;
-            }
-
-            query.push(r#"WHERE "#);
-
-            let has_id = self.id.is_some();
-            if let Some(id) = self.id {
-                query.push(r#"id = "#);
-                query.push_bind(id);
-            }
-
-            let has_name = self.name.is_some();
-            if let Some(name) = self.name {
-                if has_id {
-                    query.push(r#" AND "#);
-                }
-                query.push(r#"name = "#);
-                query.push_bind(name);
-            }
-
-            let has_email = self.email.is_some();
-            if let Some(email) = self.email {
-                if has_id || has_name {
-                    query.push(r#" AND "#);
-                }
-                query.push(r#"email = "#);
-                query.push_bind(email);
-            }
-
-            let has_sub = self.sub.is_some();
-            if let Some(sub) = self.sub {
-                if has_id || has_name || has_email {
-                    query.push(r#" AND "#);
-                }
-                query.push(r#"sub = "#);
-                query.push_bind(sub);
-            }
-
-            if let Some(iss) = self.iss {
-                if has_id || has_name || has_email || has_sub {
-                    query.push(r#" AND "#);
-                }
-                query.push(r#"iss = "#);
-                query.push_bind(iss);
-            }
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and_some(self.id, |id| Condition::eq("id", id))
+                .and_some(self.name, |name| Condition::eq("name", name))
+                .and_some(self.email, |email| Condition::eq("email", email))
+                .and_some(self.sub, |sub| Condition::eq("sub", sub))
+                .and_some(self.iss, |iss| Condition::eq("iss", iss))
         }
     }
 }
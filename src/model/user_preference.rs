@@ -0,0 +1,40 @@
+use crate::model::{asset::AssetId, user::UserId};
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+/// The settings a user chooses for themselves. Users without one have the
+/// defaults.
+#[derive(Debug, Clone, FromRow)]
+pub struct UserPreference {
+    /// The user the settings are of
+    pub user_id: UserId,
+    /// When the settings were first chosen
+    pub created_at: DateTime<Utc>,
+    /// When the settings were updated
+    pub updated_at: DateTime<Utc>,
+    /// The asset, usually the currency, the user keeps their books in
+    pub default_asset_id: Option<AssetId>,
+    /// The locale exports are formatted for, like `ko-KR`
+    pub locale: Option<String>,
+    /// Whether transactions are only created as balanced journal entries
+    pub double_entry: bool,
+    /// How many idle minutes the app locks after, the default if `None`
+    /// and never if 0
+    pub idle_lock_minutes: Option<i32>,
+    /// Whether time in a hidden tab counts towards locking the app
+    pub idle_lock_while_hidden: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UserPreferenceUpdate {
+    /// The new default asset
+    pub default_asset_id: Option<AssetId>,
+    /// The new locale
+    pub locale: Option<String>,
+    /// Whether to turn double-entry mode on or off
+    pub double_entry: Option<bool>,
+    /// The new idle minutes
+    pub idle_lock_minutes: Option<i32>,
+    /// Whether hidden tabs should count towards locking
+    pub idle_lock_while_hidden: Option<bool>,
+}
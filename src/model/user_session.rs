@@ -0,0 +1,73 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{Condition, Filter, Predicate, user::UserId};
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("session_id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct UserSessionId(pub Uuid);
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// A refresh token chain, from the login on a device through every
+    /// refresh of its tokens.
+    #[derive(Debug, Clone, FromRow)]
+    pub struct UserSession {
+        pub id: UserSessionId,
+        /// When the user logged in
+        pub created_at: DateTime<Utc>,
+        /// When the tokens of the session were last refreshed
+        pub last_refreshed_at: DateTime<Utc>,
+        pub user_id: UserId,
+        /// The SHA-256 digest of the latest refresh token, which is never
+        /// stored
+        pub token_hash: Vec<u8>,
+        /// The address of the client at the last refresh, truncated or
+        /// hashed, see [`crate::authentication::client_address`]
+        pub ip_address: Option<String>,
+        pub user_agent: Option<String>,
+        /// When the session was revoked, after which it can't be refreshed
+        pub revoked_at: Option<DateTime<Utc>>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct UserSessionCreate {
+        pub user_id: UserId,
+        pub token_hash: Vec<u8>,
+        pub ip_address: Option<String>,
+        pub user_agent: Option<String>,
+    }
+
+    /// Filters the sessions that have not been revoked.
+    #[derive(Debug, Clone, Default)]
+    pub struct UserSessionFilter {
+        pub user_id: Option<UserId>,
+    }
+
+    impl Filter for UserSessionFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new()
+                .and(Condition::is_null("revoked_at"))
+                .and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use derive_more::{Display, From, FromStr};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{
+        Condition, Filter, Predicate, alert_rule::AlertChannel, asset::AssetId, user::UserId,
+    };
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type};
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Type))]
+#[cfg_attr(feature = "ssr", into_params(names("id")))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct WatchlistEntryId(pub Uuid);
+
+/// Which way the price has to go to reach the target of an entry.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "watch_direction", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchDirection {
+    /// The price rises to the target or past it
+    #[default]
+    Above,
+    /// The price falls to the target or past it
+    Below,
+}
+
+impl WatchDirection {
+    /// Whether the price going from `before` to `after` reached `target`.
+    /// A price that was already there doesn't reach it again, while the
+    /// first price of a pair reaches it if it is there at all.
+    pub fn crossed(self, target: Decimal, before: Option<Decimal>, after: Decimal) -> bool {
+        match self {
+            Self::Above => after >= target && before.is_none_or(|x| x < target),
+            Self::Below => after <= target && before.is_none_or(|x| x > target),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct WatchlistEntry {
+        /// The id of the entry
+        pub id: WatchlistEntryId,
+        /// When the entry was created
+        pub created_at: DateTime<Utc>,
+        /// When the entry was updated
+        pub updated_at: DateTime<Utc>,
+        /// The user watching the asset
+        pub user_id: UserId,
+        /// The asset being watched
+        pub asset_id: AssetId,
+        /// The asset the price is in
+        pub quote_asset_id: AssetId,
+        /// The price to be notified at, if any
+        pub target_price: Option<Decimal>,
+        pub direction: WatchDirection,
+        pub channel: AlertChannel,
+        /// Whether the entry fires on every crossing rather than once
+        pub repeating: bool,
+        /// When the entry last fired
+        pub triggered_at: Option<DateTime<Utc>>,
+        /// The price the entry last fired at
+        pub triggered_price: Option<Decimal>,
+        /// The symbol of the asset
+        pub symbol: String,
+        /// The symbol of the quote asset
+        pub quote_symbol: String,
+        /// The latest price of the asset in the quote asset
+        pub latest_price: Option<Decimal>,
+        /// When the latest price applies
+        pub latest_priced_at: Option<DateTime<Utc>>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct WatchlistEntryCreate {
+        pub user_id: UserId,
+        pub asset_id: AssetId,
+        pub quote_asset_id: AssetId,
+        pub target_price: Option<Decimal>,
+        pub direction: WatchDirection,
+        pub channel: AlertChannel,
+        pub repeating: bool,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct WatchlistEntryFilter {
+        pub user_id: Option<UserId>,
+    }
+
+    impl Filter for WatchlistEntryFilter {
+        fn predicate(self) -> Predicate {
+            Predicate::new().and_some(self.user_id, |user_id| Condition::eq("user_id", user_id))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(WatchDirection::Above, Some(90), 99, false)]
+    #[case(WatchDirection::Above, Some(90), 100, true)]
+    #[case(WatchDirection::Above, Some(90), 110, true)]
+    #[case(WatchDirection::Above, Some(100), 110, false)]
+    #[case(WatchDirection::Above, Some(110), 100, false)]
+    #[case(WatchDirection::Above, None, 100, true)]
+    #[case(WatchDirection::Above, None, 90, false)]
+    #[case(WatchDirection::Below, Some(110), 101, false)]
+    #[case(WatchDirection::Below, Some(110), 100, true)]
+    #[case(WatchDirection::Below, Some(110), 90, true)]
+    #[case(WatchDirection::Below, Some(100), 90, false)]
+    #[case(WatchDirection::Below, Some(90), 100, false)]
+    #[case(WatchDirection::Below, None, 100, true)]
+    #[case(WatchDirection::Below, None, 110, false)]
+    fn it_fires_when_the_price_reaches_the_target(
+        #[case] direction: WatchDirection,
+        #[case] before: Option<i64>,
+        #[case] after: i64,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(
+            direction.crossed(Decimal::from(100), before.map(Decimal::from), after.into()),
+            expected
+        );
+    }
+}
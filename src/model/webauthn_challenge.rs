@@ -0,0 +1,59 @@
+use derive_more::{Display, From, FromStr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::user::UserId;
+    pub use chrono::{DateTime, Utc};
+    pub use sqlx::{FromRow, Type, types::Json};
+    pub use utoipa::ToSchema;
+    pub use webauthn_rs::prelude::{PasskeyAuthentication, PasskeyRegistration};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromStr, From, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, Type))]
+#[cfg_attr(feature = "ssr", sqlx(transparent))]
+pub struct WebauthnChallengeId(pub Uuid);
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The server side state of an in-flight WebAuthn ceremony.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(tag = "ceremony", content = "state", rename_all = "snake_case")]
+    pub enum CeremonyState {
+        Registration(PasskeyRegistration),
+        Authentication(PasskeyAuthentication),
+    }
+
+    #[derive(Debug, Clone, FromRow)]
+    pub struct WebauthnChallenge {
+        /// The id of the challenge
+        pub id: WebauthnChallengeId,
+        /// When the challenge was issued
+        pub created_at: DateTime<Utc>,
+        /// When the challenge can no longer be completed
+        pub expires_at: DateTime<Utc>,
+        /// The user the challenge was issued to
+        pub user_id: UserId,
+        /// The ceremony state
+        pub state: Json<CeremonyState>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct WebauthnChallengeCreate {
+        pub user_id: UserId,
+        pub expires_at: DateTime<Utc>,
+        pub state: CeremonyState,
+    }
+}
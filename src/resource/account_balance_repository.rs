@@ -0,0 +1,237 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgTransaction, query_as, query_scalar};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        account::AccountId,
+        account_balance::{AccountBalance, NetBalance},
+        asset::AssetId,
+        transaction::TransactionId,
+        user::UserId,
+    },
+    resource::{InstrumentQuery, RepositoryError, record_rows},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct AccountBalanceRepository;
+
+impl AccountBalanceRepository {
+    /// The balances of an account in each asset it has transactions in.
+    ///
+    /// The cached balances are read when the account has any, otherwise
+    /// they are summed from its transactions.
+    #[instrument(
+        name = "AccountBalanceRepository::get_list_for_account",
+        skip_all,
+        fields(account_id = ?account_id, rows = tracing::field::Empty)
+    )]
+    pub async fn get_list_for_account(
+        &self,
+        mut session: PgTransaction<'_>,
+        account_id: AccountId,
+    ) -> Result<Vec<AccountBalance>, RepositoryError> {
+        let balances = query_as::<_, AccountBalance>(
+            r#"
+            SELECT * FROM account_balance
+            WHERE account_id = $1
+            ORDER BY asset_id
+            "#,
+        )
+        .bind(account_id)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        if !balances.is_empty() {
+            return Ok(record_rows(balances));
+        }
+
+        let balances = query_as::<_, AccountBalance>(
+            r#"
+            SELECT
+                account_id,
+                asset_id,
+                SUM(quantity) AS balance,
+                MAX(id) AS as_of_transaction_id
+            FROM "transaction"
+            WHERE account_id = $1
+            GROUP BY account_id, asset_id
+            ORDER BY asset_id
+            "#,
+        )
+        .bind(account_id)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(record_rows(balances))
+    }
+
+    /// The balances of all the accounts of a user, or of `account_ids` if
+    /// given, in each asset they have transactions in, along with what they
+    /// were before `previous_at`.
+    #[instrument(
+        name = "AccountBalanceRepository::get_net_for_user",
+        skip_all,
+        fields(user_id = ?user_id, rows = tracing::field::Empty)
+    )]
+    pub async fn get_net_for_user(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
+        previous_at: DateTime<Utc>,
+    ) -> Result<Vec<NetBalance>, RepositoryError> {
+        let balances = query_as::<_, NetBalance>(
+            r#"
+            SELECT
+                t.asset_id,
+                asset.symbol,
+                SUM(t.quantity) AS balance,
+                COALESCE(SUM(t.quantity) FILTER (WHERE t.posted_at < $2), 0) AS previous_balance
+            FROM "transaction" t
+            JOIN account ON account.id = t.account_id
+            JOIN asset ON asset.id = t.asset_id
+            WHERE account.user_id = $1
+            AND ($3::UUID[] IS NULL OR account.id = ANY($3))
+            GROUP BY t.asset_id, asset.symbol
+            ORDER BY asset.symbol
+            "#,
+        )
+        .bind(user_id)
+        .bind(previous_at)
+        .bind(account_ids)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(record_rows(balances))
+    }
+
+    /// Adds `delta` to the balance of an account in an asset after
+    /// `transaction_id` changed it.
+    ///
+    /// The cached balance is locked while it is adjusted, so concurrent
+    /// changes to it apply one after the other. A balance that isn't cached
+    /// yet is summed from the transactions the session sees, which include
+    /// the change, and a concurrent change that cached it first waits on
+    /// the insert and is then added to it.
+    #[instrument(
+        name = "AccountBalanceRepository::adjust",
+        skip_all,
+        fields(account_id = ?account_id, asset_id = ?asset_id, transaction_id = ?transaction_id)
+    )]
+    pub async fn adjust(
+        &self,
+        mut session: PgTransaction<'_>,
+        account_id: AccountId,
+        asset_id: AssetId,
+        delta: Decimal,
+        transaction_id: TransactionId,
+    ) -> Result<AccountBalance, RepositoryError> {
+        let created = query_as::<_, AccountBalance>(
+            r#"
+            INSERT INTO account_balance (account_id, asset_id, balance, as_of_transaction_id)
+            SELECT $1, $2, COALESCE(SUM(quantity), 0), $3
+            FROM "transaction"
+            WHERE account_id = $1 AND asset_id = $2
+            ON CONFLICT (account_id, asset_id) DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(account_id)
+        .bind(asset_id)
+        .bind(transaction_id)
+        .fetch_optional(&mut *session)
+        .in_query_span()
+        .await?;
+        if let Some(created) = created {
+            session.commit().await?;
+            return Ok(created);
+        }
+
+        let balance = query_scalar::<_, Decimal>(
+            r#"
+            SELECT balance FROM account_balance
+            WHERE account_id = $1 AND asset_id = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(account_id)
+        .bind(asset_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+
+        let adjusted = query_as::<_, AccountBalance>(
+            r#"
+            UPDATE account_balance
+            SET balance = $3, as_of_transaction_id = $4
+            WHERE account_id = $1 AND asset_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(account_id)
+        .bind(asset_id)
+        .bind(balance + delta)
+        .bind(transaction_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(adjusted)
+    }
+
+    /// Sets the cached balances of the accounts of a user, or of every
+    /// account without one, to the sums of their transactions. Returns how
+    /// many balances had drifted from their sums.
+    #[instrument(name = "AccountBalanceRepository::rebuild", skip_all, fields(user_id = ?user_id))]
+    pub async fn rebuild(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: Option<UserId>,
+    ) -> Result<i64, RepositoryError> {
+        let repaired = query_scalar::<_, i64>(
+            r#"
+            WITH live AS (
+                SELECT
+                    "transaction".account_id,
+                    "transaction".asset_id,
+                    SUM("transaction".quantity) AS balance,
+                    MAX("transaction".id) AS as_of_transaction_id
+                FROM "transaction"
+                JOIN account ON account.id = "transaction".account_id
+                WHERE $1::UUID IS NULL OR account.user_id = $1
+                GROUP BY "transaction".account_id, "transaction".asset_id
+            ),
+            removed AS (
+                DELETE FROM account_balance
+                USING account
+                WHERE account.id = account_balance.account_id
+                AND ($1::UUID IS NULL OR account.user_id = $1)
+                AND account_balance.balance <> 0
+                AND NOT EXISTS (
+                    SELECT 1 FROM live
+                    WHERE live.account_id = account_balance.account_id
+                    AND live.asset_id = account_balance.asset_id
+                )
+                RETURNING 1
+            ),
+            upserted AS (
+                INSERT INTO account_balance (account_id, asset_id, balance, as_of_transaction_id)
+                SELECT * FROM live
+                ON CONFLICT (account_id, asset_id) DO UPDATE
+                SET balance = EXCLUDED.balance, as_of_transaction_id = EXCLUDED.as_of_transaction_id
+                WHERE account_balance.balance <> EXCLUDED.balance
+                RETURNING 1
+            )
+            SELECT (SELECT COUNT(*) FROM removed) + (SELECT COUNT(*) FROM upserted)
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(repaired)
+    }
+}
@@ -1,13 +1,18 @@
-use sqlx::{PgTransaction, QueryBuilder, query_as};
+use sqlx::{PgTransaction, query, query_as};
+use tracing::instrument;
 
 use crate::{
     model::{
         Filter,
-        account::{Account, AccountCreate, AccountFilter, AccountId},
+        account::{
+            Account, AccountCreate, AccountDeletion, AccountDeletionCounts, AccountFilter,
+            AccountId, AccountMerge,
+        },
+        asset::AssetId,
     },
     resource::{
-        CreateRepository, DeleteRepository, GetListRepository, GetRepository, MAX_LIMIT,
-        RepositoryError, UpdateRepository,
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
     },
 };
 
@@ -15,6 +20,7 @@ use crate::{
 pub struct AccountRepository;
 
 impl GetRepository<AccountId, Account> for AccountRepository {
+    #[instrument(name = "AccountRepository::get", skip_all, fields(id = ?id))]
     async fn get(
         &self,
         mut session: PgTransaction<'_>,
@@ -23,18 +29,26 @@ impl GetRepository<AccountId, Account> for AccountRepository {
         let account = query_as!(
             Account,
             r#"
-            SELECT * FROM account
+            SELECT id, created_at, updated_at, user_id, institution_id, name, notes,
+            default_asset_id AS "default_asset_id: AssetId"
+            FROM account
             WHERE id = $1
-        "#,
+            "#,
             id.0
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         Ok(account)
     }
 }
 
 impl GetListRepository<Account, AccountFilter> for AccountRepository {
+    #[instrument(
+        name = "AccountRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
     async fn get_list(
         &self,
         mut session: PgTransaction<'_>,
@@ -42,31 +56,29 @@ impl GetListRepository<Account, AccountFilter> for AccountRepository {
         limit: Option<i64>,
         filter: AccountFilter,
     ) -> Result<Vec<Account>, RepositoryError> {
-        let offset = offset.max(0);
-        let limit = limit.map(|x| x.clamp(1, MAX_LIMIT)).unwrap_or(MAX_LIMIT);
-
-        let mut query = QueryBuilder::new(
+        let order_by = filter.sort.order_by();
+        let mut query = list_query(
             r#"
             SELECT * FROM account
             "#,
+            filter.predicate(),
+            Some(order_by),
+            offset,
+            limit,
         );
 
-        filter.push(&mut query);
-        query.push(r#" OFFSET "#);
-        query.push_bind(offset);
-        query.push(r#" LIMIT "#);
-        query.push_bind(limit);
-
         let accounts = query
             .build_query_as::<Account>()
             .fetch_all(&mut *session)
+            .in_query_span()
             .await?;
 
-        Ok(accounts)
+        Ok(record_rows(accounts))
     }
 }
 
 impl CreateRepository<AccountCreate, Account> for AccountRepository {
+    #[instrument(name = "AccountRepository::create", skip_all)]
     async fn create(
         &self,
         mut session: PgTransaction<'_>,
@@ -75,15 +87,19 @@ impl CreateRepository<AccountCreate, Account> for AccountRepository {
         let new_account = query_as!(
             Account,
             r#"
-            INSERT INTO account (name, institution_id, user_id)
-            VALUES ($1, $2, $3)
-            RETURNING *
+            INSERT INTO account (name, institution_id, user_id, notes, default_asset_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, created_at, updated_at, user_id, institution_id, name, notes,
+            default_asset_id AS "default_asset_id: AssetId"
             "#,
             create_model.name,
             create_model.institution_id.0,
             create_model.user_id.0,
+            create_model.notes,
+            create_model.default_asset_id as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(new_account)
@@ -91,6 +107,7 @@ impl CreateRepository<AccountCreate, Account> for AccountRepository {
 }
 
 impl UpdateRepository<Account> for AccountRepository {
+    #[instrument(name = "AccountRepository::update", skip_all, fields(id = ?model.id))]
     async fn update(
         &self,
         mut session: PgTransaction<'_>,
@@ -100,16 +117,20 @@ impl UpdateRepository<Account> for AccountRepository {
             Account,
             r#"
             UPDATE account
-            SET name = $2, institution_id = $3, user_id = $4
+            SET name = $2, institution_id = $3, user_id = $4, notes = $5, default_asset_id = $6
             WHERE id = $1
-            RETURNING *
+            RETURNING id, created_at, updated_at, user_id, institution_id, name, notes,
+            default_asset_id AS "default_asset_id: AssetId"
             "#,
             model.id.0,
             model.name,
             model.institution_id.0,
             model.user_id.0,
+            model.notes,
+            model.default_asset_id as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(updated_account)
@@ -117,6 +138,7 @@ impl UpdateRepository<Account> for AccountRepository {
 }
 
 impl DeleteRepository<AccountId, Account> for AccountRepository {
+    #[instrument(name = "AccountRepository::delete", skip_all, fields(id = ?id))]
     async fn delete(
         &self,
         mut session: PgTransaction<'_>,
@@ -127,13 +149,323 @@ impl DeleteRepository<AccountId, Account> for AccountRepository {
             r#"
             DELETE FROM account
             WHERE id = $1
-            RETURNING *
+            RETURNING id, created_at, updated_at, user_id, institution_id, name, notes,
+            default_asset_id AS "default_asset_id: AssetId"
             "#,
             id.0
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(deleted_account)
     }
 }
+
+impl AccountRepository {
+    /// Moves the transactions, rules, profile defaults and key and budget
+    /// scopes of `source` onto `target`, recomputes the cached balances of
+    /// `target` and deletes `source`, recording the merge.
+    ///
+    /// A moved transaction keeps its external id unless `target` already
+    /// has a transaction with it, in which case the id is cleared so that
+    /// the sums of both accounts carry over.
+    #[instrument(
+        name = "AccountRepository::merge",
+        skip_all,
+        fields(target_id = ?target_id, source_id = ?source.id)
+    )]
+    pub async fn merge(
+        &self,
+        mut session: PgTransaction<'_>,
+        target_id: AccountId,
+        source: Account,
+        forced: bool,
+    ) -> Result<AccountMerge, RepositoryError> {
+        query(
+            r#"
+            UPDATE "transaction" s
+            SET external_id = NULL
+            WHERE s.account_id = $2
+            AND s.external_id IS NOT NULL
+            AND EXISTS (
+                SELECT 1 FROM "transaction" t
+                WHERE t.account_id = $1 AND t.external_id = s.external_id
+            )
+            "#,
+        )
+        .bind(target_id)
+        .bind(source.id)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?;
+
+        let (transactions, attachments) = query_as::<_, (i64, i64)>(
+            r#"
+            WITH moved AS (
+                UPDATE "transaction"
+                SET account_id = $1
+                WHERE account_id = $2
+                RETURNING id
+            )
+            SELECT
+                (SELECT COUNT(*) FROM moved),
+                (SELECT COUNT(*) FROM attachment WHERE transaction_id IN (SELECT id FROM moved))
+            "#,
+        )
+        .bind(target_id)
+        .bind(source.id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+
+        let mut moved = Vec::with_capacity(5);
+        for statement in [
+            "UPDATE categorization_rule SET account_id = $1 WHERE account_id = $2",
+            "UPDATE alert_rule SET account_id = $1 WHERE account_id = $2",
+            "UPDATE import_profile SET default_account_id = $1 WHERE default_account_id = $2",
+            r#"
+            UPDATE budget
+            SET account_ids = ARRAY(SELECT DISTINCT UNNEST(array_replace(account_ids, $2, $1)))
+            WHERE $2 = ANY(account_ids)
+            "#,
+            r#"
+            UPDATE api_key
+            SET account_ids = ARRAY(SELECT DISTINCT UNNEST(array_replace(account_ids, $2, $1)))
+            WHERE $2 = ANY(account_ids)
+            "#,
+        ] {
+            let result = query(statement)
+                .bind(target_id)
+                .bind(source.id)
+                .execute(&mut *session)
+                .in_query_span()
+                .await?;
+            moved.push(result.rows_affected() as i64);
+        }
+        // The quick entries follow the account too, though they aren't
+        // counted in the summary.
+        query("UPDATE quick_entry SET account_id = $1 WHERE account_id = $2")
+            .bind(target_id)
+            .bind(source.id)
+            .execute(&mut *session)
+            .in_query_span()
+            .await?;
+
+        query(
+            r#"
+            INSERT INTO account_balance (account_id, asset_id, balance, as_of_transaction_id)
+            SELECT account_id, asset_id, SUM(quantity), MAX(id)
+            FROM "transaction"
+            WHERE account_id = $1
+            GROUP BY account_id, asset_id
+            ON CONFLICT (account_id, asset_id) DO UPDATE
+            SET balance = EXCLUDED.balance, as_of_transaction_id = EXCLUDED.as_of_transaction_id
+            "#,
+        )
+        .bind(target_id)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?;
+
+        query(
+            r#"
+            DELETE FROM account
+            WHERE id = $1
+            "#,
+        )
+        .bind(source.id)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?;
+
+        let account_merge = query_as::<_, AccountMerge>(
+            r#"
+            INSERT INTO account_merge (
+                user_id, target_id, source_id, source_name, source_institution_id,
+                source_notes, source_created_at, forced, transactions, attachments,
+                categorization_rules, alert_rules, import_profiles, budgets, api_keys
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            RETURNING *
+            "#,
+        )
+        .bind(source.user_id)
+        .bind(target_id)
+        .bind(source.id)
+        .bind(source.name)
+        .bind(source.institution_id)
+        .bind(source.notes)
+        .bind(source.created_at)
+        .bind(forced)
+        .bind(transactions)
+        .bind(attachments)
+        .bind(moved[0])
+        .bind(moved[1])
+        .bind(moved[2])
+        .bind(moved[3])
+        .bind(moved[4])
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(account_merge)
+    }
+
+    /// Fetches the account of `id`, locking it until the end of the
+    /// transaction. Transactions can't be created on the account while it
+    /// is locked, as their key on it conflicts with the lock.
+    #[instrument(name = "AccountRepository::lock", skip_all, fields(id = ?id))]
+    pub async fn lock(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AccountId,
+    ) -> Result<Account, RepositoryError> {
+        let account = query_as!(
+            Account,
+            r#"
+            SELECT id, created_at, updated_at, user_id, institution_id, name, notes,
+            default_asset_id AS "default_asset_id: AssetId"
+            FROM account
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            id.0
+        )
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(account)
+    }
+
+    /// Counts the rows deleted with the account of `id`.
+    #[instrument(name = "AccountRepository::deletion_counts", skip_all, fields(id = ?id))]
+    pub async fn deletion_counts(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AccountId,
+    ) -> Result<AccountDeletionCounts, RepositoryError> {
+        let counts = query_as::<_, AccountDeletionCounts>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM "transaction" WHERE account_id = $1) AS transactions,
+                (
+                    SELECT COUNT(*) FROM attachment
+                    WHERE transaction_id IN (SELECT id FROM "transaction" WHERE account_id = $1)
+                ) AS attachments,
+                (SELECT COUNT(*) FROM categorization_rule WHERE account_id = $1) AS categorization_rules,
+                (SELECT COUNT(*) FROM alert_rule WHERE account_id = $1) AS alert_rules,
+                (
+                    SELECT COUNT(*) FROM "transaction"
+                    WHERE account_id = $1 AND journal_entry_id IS NOT NULL
+                ) AS journal_entry_legs
+            "#,
+        )
+        .bind(id.0)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(counts)
+    }
+
+    /// Deletes `account` with its transactions, their attachments and its
+    /// rules, recording the deletion and an `account.deleted` webhook
+    /// event. Each delete must remove as many rows as `counts` has for it,
+    /// or nothing is deleted.
+    ///
+    /// The account should be [locked](Self::lock) since it was counted.
+    #[instrument(
+        name = "AccountRepository::delete_with_dependents",
+        skip_all,
+        fields(id = ?account.id)
+    )]
+    pub async fn delete_with_dependents(
+        &self,
+        mut session: PgTransaction<'_>,
+        account: Account,
+        counts: AccountDeletionCounts,
+    ) -> Result<AccountDeletion, RepositoryError> {
+        for (table, expected, statement) in [
+            (
+                "attachment",
+                counts.attachments,
+                r#"
+                DELETE FROM attachment
+                WHERE transaction_id IN (SELECT id FROM "transaction" WHERE account_id = $1)
+                "#,
+            ),
+            (
+                "transaction",
+                counts.transactions,
+                r#"DELETE FROM "transaction" WHERE account_id = $1"#,
+            ),
+            (
+                "categorization_rule",
+                counts.categorization_rules,
+                "DELETE FROM categorization_rule WHERE account_id = $1",
+            ),
+            (
+                "alert_rule",
+                counts.alert_rules,
+                "DELETE FROM alert_rule WHERE account_id = $1",
+            ),
+            ("account", 1, "DELETE FROM account WHERE id = $1"),
+        ] {
+            let affected = query(statement)
+                .bind(account.id)
+                .execute(&mut *session)
+                .in_query_span()
+                .await?
+                .rows_affected();
+            if affected != expected as u64 {
+                return Err(RepositoryError::RowCount {
+                    table,
+                    expected,
+                    affected,
+                });
+            }
+        }
+
+        let account_deletion = query_as::<_, AccountDeletion>(
+            r#"
+            INSERT INTO account_deletion (
+                user_id, account_id, account_name, institution_id, notes,
+                account_created_at, transactions, attachments, categorization_rules,
+                alert_rules
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING *
+            "#,
+        )
+        .bind(account.user_id)
+        .bind(account.id)
+        .bind(account.name)
+        .bind(account.institution_id)
+        .bind(account.notes)
+        .bind(account.created_at)
+        .bind(counts.transactions)
+        .bind(counts.attachments)
+        .bind(counts.categorization_rules)
+        .bind(counts.alert_rules)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+
+        query(
+            r#"
+            INSERT INTO webhook_event (user_id, event, payload)
+            SELECT user_id, 'account.deleted', to_jsonb(account_deletion)
+            FROM account_deletion
+            WHERE id = $1
+            "#,
+        )
+        .bind(account_deletion.id)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(account_deletion)
+    }
+}
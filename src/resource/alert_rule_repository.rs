@@ -0,0 +1,253 @@
+use rust_decimal::Decimal;
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        account::AccountId,
+        alert_rule::{
+            AlertEvent, AlertEventFilter, AlertRule, AlertRuleCreate, AlertRuleFilter, AlertRuleId,
+            FiredAlert,
+        },
+        asset::AssetId,
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct AlertRuleRepository;
+
+impl GetRepository<AlertRuleId, AlertRule> for AlertRuleRepository {
+    #[instrument(name = "AlertRuleRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AlertRuleId,
+    ) -> Result<AlertRule, RepositoryError> {
+        let alert_rule = query_as::<_, AlertRule>(
+            r#"
+            SELECT * FROM alert_rule
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(alert_rule)
+    }
+}
+
+impl GetListRepository<AlertRule, AlertRuleFilter> for AlertRuleRepository {
+    #[instrument(
+        name = "AlertRuleRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: AlertRuleFilter,
+    ) -> Result<Vec<AlertRule>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM alert_rule
+            "#,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let alert_rules = query
+            .build_query_as::<AlertRule>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(alert_rules))
+    }
+}
+
+impl CreateRepository<AlertRuleCreate, AlertRule> for AlertRuleRepository {
+    #[instrument(name = "AlertRuleRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: AlertRuleCreate,
+    ) -> Result<AlertRule, RepositoryError> {
+        let new_alert_rule = query_as::<_, AlertRule>(
+            r#"
+            INSERT INTO alert_rule (
+                user_id, account_id, asset_id, comparator, threshold, channel, cooldown_seconds
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.account_id)
+        .bind(create_model.asset_id)
+        .bind(create_model.comparator)
+        .bind(create_model.threshold)
+        .bind(create_model.channel)
+        .bind(create_model.cooldown_seconds)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_alert_rule)
+    }
+}
+
+impl UpdateRepository<AlertRule> for AlertRuleRepository {
+    #[instrument(name = "AlertRuleRepository::update", skip_all, fields(id = ?model.id))]
+    async fn update(
+        &self,
+        mut session: PgTransaction<'_>,
+        model: AlertRule,
+    ) -> Result<AlertRule, RepositoryError> {
+        let updated_alert_rule = query_as::<_, AlertRule>(
+            r#"
+            UPDATE alert_rule
+            SET
+                comparator = $2,
+                threshold = $3,
+                channel = $4,
+                cooldown_seconds = $5
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(model.id)
+        .bind(model.comparator)
+        .bind(model.threshold)
+        .bind(model.channel)
+        .bind(model.cooldown_seconds)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(updated_alert_rule)
+    }
+}
+
+impl DeleteRepository<AlertRuleId, AlertRule> for AlertRuleRepository {
+    #[instrument(name = "AlertRuleRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AlertRuleId,
+    ) -> Result<AlertRule, RepositoryError> {
+        let deleted_alert_rule = query_as::<_, AlertRule>(
+            r#"
+            DELETE FROM alert_rule
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_alert_rule)
+    }
+}
+
+impl AlertRuleRepository {
+    /// Fires the rules of the account and asset whose threshold the balance
+    /// crossed going from `before` to `after`, other than those still
+    /// cooling down from their last firing. Each rule that fires records an
+    /// event and starts its cooldown over, and is returned with its user.
+    ///
+    /// Only the rules of the one balance are looked at, through their
+    /// index, so the cost doesn't grow with the transactions of the account.
+    #[instrument(
+        name = "AlertRuleRepository::evaluate",
+        skip_all,
+        fields(account_id = ?account_id, asset_id = ?asset_id, rows = tracing::field::Empty)
+    )]
+    pub async fn evaluate(
+        &self,
+        mut session: PgTransaction<'_>,
+        account_id: AccountId,
+        asset_id: AssetId,
+        before: Decimal,
+        after: Decimal,
+    ) -> Result<Vec<FiredAlert>, RepositoryError> {
+        let fired_alerts = query_as::<_, FiredAlert>(
+            r#"
+            WITH fired AS (
+                UPDATE alert_rule
+                SET last_fired_at = now()
+                WHERE account_id = $1
+                AND asset_id = $2
+                AND CASE comparator
+                    WHEN 'below' THEN $3 >= threshold AND $4 < threshold
+                    WHEN 'above' THEN $3 <= threshold AND $4 > threshold
+                END
+                AND (
+                    last_fired_at IS NULL
+                    OR last_fired_at + cooldown_seconds * INTERVAL '1 second' <= now()
+                )
+                RETURNING id, user_id, channel
+            ), inserted AS (
+                INSERT INTO alert_event (alert_rule_id, balance, channel)
+                SELECT id, $4, channel FROM fired
+                RETURNING *
+            )
+            SELECT inserted.*, fired.user_id
+            FROM inserted
+            JOIN fired ON fired.id = inserted.alert_rule_id
+            "#,
+        )
+        .bind(account_id)
+        .bind(asset_id)
+        .bind(before)
+        .bind(after)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(record_rows(fired_alerts))
+    }
+
+    /// The times the rules fired, newest first.
+    #[instrument(
+        name = "AlertRuleRepository::get_events",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    pub async fn get_events(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: AlertEventFilter,
+    ) -> Result<Vec<AlertEvent>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM alert_event
+            "#,
+            filter.predicate(),
+            Some(r#"id DESC"#),
+            offset,
+            limit,
+        );
+
+        let alert_events = query
+            .build_query_as::<AlertEvent>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(alert_events))
+    }
+}
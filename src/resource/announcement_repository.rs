@@ -0,0 +1,152 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        announcement::{Announcement, AnnouncementCreate, AnnouncementFilter, AnnouncementId},
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnnouncementRepository;
+
+impl GetRepository<AnnouncementId, Announcement> for AnnouncementRepository {
+    #[instrument(name = "AnnouncementRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AnnouncementId,
+    ) -> Result<Announcement, RepositoryError> {
+        let announcement = query_as::<_, Announcement>(
+            r#"
+            SELECT * FROM announcement
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(announcement)
+    }
+}
+
+impl GetListRepository<Announcement, AnnouncementFilter> for AnnouncementRepository {
+    #[instrument(
+        name = "AnnouncementRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: AnnouncementFilter,
+    ) -> Result<Vec<Announcement>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM announcement
+            "#,
+            filter.predicate(),
+            Some(r#"starts_at DESC, id"#),
+            offset,
+            limit,
+        );
+
+        let announcements = query
+            .build_query_as::<Announcement>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(announcements))
+    }
+}
+
+impl CreateRepository<AnnouncementCreate, Announcement> for AnnouncementRepository {
+    #[instrument(name = "AnnouncementRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: AnnouncementCreate,
+    ) -> Result<Announcement, RepositoryError> {
+        let new_announcement = query_as::<_, Announcement>(
+            r#"
+            INSERT INTO announcement (message, severity, starts_at, ends_at, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.message)
+        .bind(create_model.severity)
+        .bind(create_model.starts_at)
+        .bind(create_model.ends_at)
+        .bind(create_model.created_by)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_announcement)
+    }
+}
+
+impl UpdateRepository<Announcement> for AnnouncementRepository {
+    #[instrument(name = "AnnouncementRepository::update", skip_all, fields(id = ?model.id))]
+    async fn update(
+        &self,
+        mut session: PgTransaction<'_>,
+        model: Announcement,
+    ) -> Result<Announcement, RepositoryError> {
+        let updated_announcement = query_as::<_, Announcement>(
+            r#"
+            UPDATE announcement
+            SET
+                message = $2,
+                severity = $3,
+                starts_at = $4,
+                ends_at = $5
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(model.id)
+        .bind(model.message)
+        .bind(model.severity)
+        .bind(model.starts_at)
+        .bind(model.ends_at)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(updated_announcement)
+    }
+}
+
+impl DeleteRepository<AnnouncementId, Announcement> for AnnouncementRepository {
+    #[instrument(name = "AnnouncementRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AnnouncementId,
+    ) -> Result<Announcement, RepositoryError> {
+        let deleted_announcement = query_as::<_, Announcement>(
+            r#"
+            DELETE FROM announcement
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_announcement)
+    }
+}
@@ -0,0 +1,120 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        api_key::{ApiKey, ApiKeyCreate, ApiKeyFilter, ApiKeyId},
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ApiKeyRepository;
+
+impl GetRepository<ApiKeyId, ApiKey> for ApiKeyRepository {
+    #[instrument(name = "ApiKeyRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ApiKeyId,
+    ) -> Result<ApiKey, RepositoryError> {
+        let api_key = query_as::<_, ApiKey>(
+            r#"
+            SELECT * FROM api_key
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(api_key)
+    }
+}
+
+impl GetListRepository<ApiKey, ApiKeyFilter> for ApiKeyRepository {
+    #[instrument(
+        name = "ApiKeyRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: ApiKeyFilter,
+    ) -> Result<Vec<ApiKey>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM api_key
+            "#,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let api_keys = query
+            .build_query_as::<ApiKey>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(api_keys))
+    }
+}
+
+impl CreateRepository<ApiKeyCreate, ApiKey> for ApiKeyRepository {
+    #[instrument(name = "ApiKeyRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: ApiKeyCreate,
+    ) -> Result<ApiKey, RepositoryError> {
+        let new_api_key = query_as::<_, ApiKey>(
+            r#"
+            INSERT INTO api_key (user_id, name, key_hash, read_only, account_ids)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.name)
+        .bind(create_model.key_hash)
+        .bind(create_model.read_only)
+        .bind(create_model.account_ids)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_api_key)
+    }
+}
+
+impl DeleteRepository<ApiKeyId, ApiKey> for ApiKeyRepository {
+    #[instrument(name = "ApiKeyRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ApiKeyId,
+    ) -> Result<ApiKey, RepositoryError> {
+        let deleted_api_key = query_as::<_, ApiKey>(
+            r#"
+            DELETE FROM api_key
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_api_key)
+    }
+}
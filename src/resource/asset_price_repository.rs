@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgTransaction, QueryBuilder, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::asset::AssetId,
+    resource::{InstrumentQuery, RepositoryError},
+};
+
+/// The price of one unit of an asset in a quote asset at a time.
+#[derive(Debug, Clone)]
+pub struct AssetPriceUpsert {
+    pub asset_id: AssetId,
+    pub quote_asset_id: AssetId,
+    pub priced_at: DateTime<Utc>,
+    pub rate: f64,
+}
+
+/// Writes the prices the valuations read. Prices are keyed by their asset,
+/// quote asset and time, so writing one again replaces its rate.
+#[derive(Debug, Clone, Copy)]
+pub struct AssetPriceRepository;
+
+impl AssetPriceRepository {
+    /// The ids of the assets with the symbols, by symbol.
+    #[instrument(name = "AssetPriceRepository::asset_ids_by_symbol", skip_all)]
+    pub async fn asset_ids_by_symbol(
+        &self,
+        mut session: PgTransaction<'_>,
+        symbols: &[String],
+    ) -> Result<HashMap<String, AssetId>, RepositoryError> {
+        let assets = query_as::<_, (String, AssetId)>(
+            r#"
+            SELECT symbol, id FROM asset
+            WHERE symbol = ANY($1)
+            "#,
+        )
+        .bind(symbols)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(assets.into_iter().collect())
+    }
+
+    /// Writes the prices, returning how many were written.
+    #[instrument(name = "AssetPriceRepository::upsert", skip_all, fields(prices = prices.len()))]
+    pub async fn upsert(
+        &self,
+        mut session: PgTransaction<'_>,
+        prices: &[AssetPriceUpsert],
+    ) -> Result<usize, RepositoryError> {
+        if prices.is_empty() {
+            return Ok(0);
+        }
+        let mut query = QueryBuilder::new(
+            r#"
+            INSERT INTO asset_price (asset_id, quote_asset_id, priced_at, rate)
+            "#,
+        );
+        query.push_values(prices, |mut row, price| {
+            row.push_bind(price.asset_id)
+                .push_bind(price.quote_asset_id)
+                .push_bind(price.priced_at)
+                .push_bind(price.rate)
+                .push_unseparated("::numeric");
+        });
+        query.push(
+            r#"
+            ON CONFLICT (asset_id, quote_asset_id, priced_at)
+            DO UPDATE SET rate = EXCLUDED.rate
+            "#,
+        );
+        let result = query.build().execute(&mut *session).in_query_span().await?;
+        session.commit().await?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    /// The latest rate of each of the pairs of an asset and its quote
+    /// asset that has one, by pair.
+    #[instrument(name = "AssetPriceRepository::latest_rates", skip_all, fields(pairs = pairs.len()))]
+    pub async fn latest_rates(
+        &self,
+        mut session: PgTransaction<'_>,
+        pairs: &[(AssetId, AssetId)],
+    ) -> Result<HashMap<(AssetId, AssetId), Decimal>, RepositoryError> {
+        let (asset_ids, quote_asset_ids): (Vec<_>, Vec<_>) = pairs.iter().copied().unzip();
+        let rates = query_as::<_, (AssetId, AssetId, Decimal)>(
+            r#"
+            SELECT DISTINCT ON (ap.asset_id, ap.quote_asset_id)
+                ap.asset_id, ap.quote_asset_id, ap.rate
+            FROM asset_price ap
+            JOIN unnest($1::UUID[], $2::UUID[]) AS pair (asset_id, quote_asset_id)
+                ON pair.asset_id = ap.asset_id AND pair.quote_asset_id = ap.quote_asset_id
+            ORDER BY ap.asset_id, ap.quote_asset_id, ap.priced_at DESC
+            "#,
+        )
+        .bind(asset_ids)
+        .bind(quote_asset_ids)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(rates
+            .into_iter()
+            .map(|(asset_id, quote_asset_id, rate)| ((asset_id, quote_asset_id), rate))
+            .collect())
+    }
+}
@@ -1,4 +1,5 @@
-use sqlx::{PgTransaction, QueryBuilder, query_as};
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
 
 use crate::{
     model::{
@@ -6,8 +7,8 @@ use crate::{
         asset::{Asset, AssetCreate, AssetFilter, AssetId},
     },
     resource::{
-        CreateRepository, DeleteRepository, GetListRepository, GetRepository, MAX_LIMIT,
-        RepositoryError, UpdateRepository,
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
     },
 };
 
@@ -15,6 +16,7 @@ use crate::{
 pub struct AssetRepository;
 
 impl GetRepository<AssetId, Asset> for AssetRepository {
+    #[instrument(name = "AssetRepository::get", skip_all, fields(id = ?id))]
     async fn get(
         &self,
         mut session: PgTransaction<'_>,
@@ -29,12 +31,18 @@ impl GetRepository<AssetId, Asset> for AssetRepository {
             id.0
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         Ok(asset)
     }
 }
 
 impl GetListRepository<Asset, AssetFilter> for AssetRepository {
+    #[instrument(
+        name = "AssetRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
     async fn get_list(
         &self,
         mut session: PgTransaction<'_>,
@@ -42,30 +50,28 @@ impl GetListRepository<Asset, AssetFilter> for AssetRepository {
         limit: Option<i64>,
         filter: AssetFilter,
     ) -> Result<Vec<Asset>, RepositoryError> {
-        let offset = offset.max(0);
-        let limit = limit.map(|x| x.clamp(1, MAX_LIMIT)).unwrap_or(MAX_LIMIT);
-
-        let mut query = QueryBuilder::new(
+        let order_by = filter.sort.order_by();
+        let mut query = list_query(
             r#"
             SELECT * FROM asset
             "#,
+            filter.predicate(),
+            Some(order_by),
+            offset,
+            limit,
         );
 
-        filter.push(&mut query);
-        query.push(r#" OFFSET "#);
-        query.push_bind(offset);
-        query.push(r#" LIMIT "#);
-        query.push_bind(limit);
-
         let assets = query
             .build_query_as::<Asset>()
             .fetch_all(&mut *session)
+            .in_query_span()
             .await?;
-        Ok(assets)
+        Ok(record_rows(assets))
     }
 }
 
 impl CreateRepository<AssetCreate, Asset> for AssetRepository {
+    #[instrument(name = "AssetRepository::create", skip_all)]
     async fn create(
         &self,
         mut session: PgTransaction<'_>,
@@ -74,14 +80,16 @@ impl CreateRepository<AssetCreate, Asset> for AssetRepository {
         let new_asset = query_as!(
             Asset,
             r#"
-                INSERT INTO asset (name, symbol)
-                VALUES ($1, $2)
+                INSERT INTO asset (name, symbol, decimals)
+                VALUES ($1, $2, $3)
                 RETURNING *
             "#,
             create_model.name,
-            create_model.symbol
+            create_model.symbol,
+            create_model.decimals
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(new_asset)
@@ -89,6 +97,7 @@ impl CreateRepository<AssetCreate, Asset> for AssetRepository {
 }
 
 impl UpdateRepository<Asset> for AssetRepository {
+    #[instrument(name = "AssetRepository::update", skip_all, fields(id = ?model.id))]
     async fn update(
         &self,
         mut session: PgTransaction<'_>,
@@ -107,6 +116,7 @@ impl UpdateRepository<Asset> for AssetRepository {
             model.symbol
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(updated_asset)
@@ -114,6 +124,7 @@ impl UpdateRepository<Asset> for AssetRepository {
 }
 
 impl DeleteRepository<AssetId, Asset> for AssetRepository {
+    #[instrument(name = "AssetRepository::delete", skip_all, fields(id = ?id))]
     async fn delete(
         &self,
         mut session: PgTransaction<'_>,
@@ -129,6 +140,7 @@ impl DeleteRepository<AssetId, Asset> for AssetRepository {
             id.0
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(deleted_asset)
@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+use sqlx::{PgTransaction, query_as, query_scalar};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        attachment::{
+            ATTACHMENT_COLUMNS, Attachment, AttachmentCreate, AttachmentExtraction,
+            AttachmentExtractionCreate, AttachmentFilter, AttachmentId,
+        },
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct AttachmentRepository;
+
+impl AttachmentRepository {
+    #[instrument(name = "AttachmentRepository::get_content", skip_all, fields(id = ?id))]
+    pub async fn get_content(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AttachmentId,
+    ) -> Result<Vec<u8>, RepositoryError> {
+        let content = query_scalar::<_, Vec<u8>>(
+            r#"
+            SELECT content FROM attachment
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(content)
+    }
+
+    /// Reads the bytes of `range` of the content of an attachment, which
+    /// are never more than [`MAX_ATTACHMENT_BYTES`](crate::api::attachment_api::MAX_ATTACHMENT_BYTES).
+    #[instrument(
+        name = "AttachmentRepository::get_content_range",
+        skip_all,
+        fields(id = ?id, range = ?range)
+    )]
+    pub async fn get_content_range(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AttachmentId,
+        range: Range<u64>,
+    ) -> Result<Vec<u8>, RepositoryError> {
+        let content = query_scalar::<_, Vec<u8>>(
+            r#"
+            SELECT substring(content FROM $2 FOR $3) FROM attachment
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(range.start as i32 + 1)
+        .bind((range.end - range.start) as i32)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(content)
+    }
+
+    #[instrument(name = "AttachmentRepository::get_extraction", skip_all, fields(id = ?id))]
+    pub async fn get_extraction(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AttachmentId,
+    ) -> Result<AttachmentExtraction, RepositoryError> {
+        let extraction = query_as::<_, AttachmentExtraction>(
+            r#"
+            SELECT * FROM attachment_extraction
+            WHERE attachment_id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(extraction)
+    }
+
+    /// Records the outcome of an extraction, replacing that of an earlier
+    /// run.
+    #[instrument(name = "AttachmentRepository::upsert_extraction", skip_all)]
+    pub async fn upsert_extraction(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: AttachmentExtractionCreate,
+    ) -> Result<AttachmentExtraction, RepositoryError> {
+        let extraction = query_as::<_, AttachmentExtraction>(
+            r#"
+            INSERT INTO attachment_extraction (
+                attachment_id,
+                extractor,
+                status,
+                quantity,
+                posted_at,
+                merchant,
+                error
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (attachment_id) DO UPDATE
+            SET
+                extractor = EXCLUDED.extractor,
+                status = EXCLUDED.status,
+                quantity = EXCLUDED.quantity,
+                posted_at = EXCLUDED.posted_at,
+                merchant = EXCLUDED.merchant,
+                error = EXCLUDED.error
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.attachment_id)
+        .bind(create_model.extractor)
+        .bind(create_model.status)
+        .bind(create_model.quantity)
+        .bind(create_model.posted_at)
+        .bind(create_model.merchant)
+        .bind(create_model.error)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(extraction)
+    }
+}
+
+impl GetRepository<AttachmentId, Attachment> for AttachmentRepository {
+    #[instrument(name = "AttachmentRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AttachmentId,
+    ) -> Result<Attachment, RepositoryError> {
+        let attachment = query_as::<_, Attachment>(&format!(
+            r#"
+            SELECT {ATTACHMENT_COLUMNS} FROM attachment
+            WHERE id = $1
+            "#
+        ))
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(attachment)
+    }
+}
+
+impl GetListRepository<Attachment, AttachmentFilter> for AttachmentRepository {
+    #[instrument(
+        name = "AttachmentRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: AttachmentFilter,
+    ) -> Result<Vec<Attachment>, RepositoryError> {
+        let mut query = list_query(
+            format!(r#"SELECT {ATTACHMENT_COLUMNS} FROM attachment"#),
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let attachments = query
+            .build_query_as::<Attachment>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(attachments))
+    }
+}
+
+impl CreateRepository<AttachmentCreate, Attachment> for AttachmentRepository {
+    #[instrument(name = "AttachmentRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: AttachmentCreate,
+    ) -> Result<Attachment, RepositoryError> {
+        let new_attachment = query_as::<_, Attachment>(&format!(
+            r#"
+            INSERT INTO attachment (
+                transaction_id,
+                filename,
+                content_type,
+                size,
+                content
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {ATTACHMENT_COLUMNS}
+            "#
+        ))
+        .bind(create_model.transaction_id)
+        .bind(create_model.filename)
+        .bind(create_model.content_type)
+        .bind(create_model.content.len() as i64)
+        .bind(create_model.content)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_attachment)
+    }
+}
+
+impl DeleteRepository<AttachmentId, Attachment> for AttachmentRepository {
+    #[instrument(name = "AttachmentRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: AttachmentId,
+    ) -> Result<Attachment, RepositoryError> {
+        let deleted_attachment = query_as::<_, Attachment>(&format!(
+            r#"
+            DELETE FROM attachment
+            WHERE id = $1
+            RETURNING {ATTACHMENT_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_attachment)
+    }
+}
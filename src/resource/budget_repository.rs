@@ -0,0 +1,196 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        budget::{Budget, BudgetCreate, BudgetFilter, BudgetId, BudgetTotal},
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, list_query, record_rows,
+    },
+    rounding::RoundingPolicy,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetRepository;
+
+impl GetRepository<BudgetId, Budget> for BudgetRepository {
+    #[instrument(name = "BudgetRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: BudgetId,
+    ) -> Result<Budget, RepositoryError> {
+        let budget = query_as::<_, Budget>(
+            r#"
+            SELECT * FROM budget
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(budget)
+    }
+}
+
+impl GetListRepository<Budget, BudgetFilter> for BudgetRepository {
+    #[instrument(
+        name = "BudgetRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: BudgetFilter,
+    ) -> Result<Vec<Budget>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM budget
+            "#,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let budgets = query
+            .build_query_as::<Budget>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(budgets))
+    }
+}
+
+impl CreateRepository<BudgetCreate, Budget> for BudgetRepository {
+    #[instrument(name = "BudgetRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: BudgetCreate,
+    ) -> Result<Budget, RepositoryError> {
+        let new_budget = query_as::<_, Budget>(
+            r#"
+            INSERT INTO budget (user_id, name, asset_id, kind, amount, percent, account_ids)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.name)
+        .bind(create_model.asset_id)
+        .bind(create_model.kind)
+        .bind(create_model.amount)
+        .bind(create_model.percent)
+        .bind(create_model.account_ids)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_budget)
+    }
+}
+
+impl DeleteRepository<BudgetId, Budget> for BudgetRepository {
+    #[instrument(name = "BudgetRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: BudgetId,
+    ) -> Result<Budget, RepositoryError> {
+        let deleted_budget = query_as::<_, Budget>(
+            r#"
+            DELETE FROM budget
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_budget)
+    }
+}
+
+impl BudgetRepository {
+    /// Totals the spending of the budget accounts and the income of all the
+    /// accounts of the user posted in `[start, end)`.
+    ///
+    /// Each transaction converts at the latest price into the budget asset
+    /// at or before it was posted, rounded to the decimals of the budget
+    /// asset by `rounding` before it is totalled. Those without one are
+    /// totalled by asset instead.
+    #[instrument(name = "BudgetRepository::get_totals", skip_all)]
+    pub async fn get_totals(
+        &self,
+        mut session: PgTransaction<'_>,
+        budget: &Budget,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        rounding: RoundingPolicy,
+    ) -> Result<Vec<BudgetTotal>, RepositoryError> {
+        let totals = query_as::<_, BudgetTotal>(
+            r#"
+            WITH converted AS (
+                SELECT
+                    t.asset_id,
+                    t.quantity,
+                    ($5::UUID[] IS NULL OR t.account_id = ANY($5)) AS tracked,
+                    round_amount(t.quantity * r.rate, q.decimals, $6) AS converted_quantity
+                FROM "transaction" t
+                JOIN account a ON a.id = t.account_id
+                JOIN asset q ON q.id = $2
+                LEFT JOIN LATERAL (
+                    SELECT ap.rate
+                    FROM asset_price ap
+                    WHERE ap.asset_id = t.asset_id
+                    AND ap.quote_asset_id = $2
+                    AND ap.priced_at <= t.posted_at
+                    ORDER BY ap.priced_at DESC
+                    LIMIT 1
+                ) p ON TRUE
+                CROSS JOIN LATERAL (
+                    SELECT CASE WHEN t.asset_id = $2 THEN 1::NUMERIC ELSE p.rate END AS rate
+                ) r
+                WHERE a.user_id = $1
+                AND t.posted_at >= $3
+                AND t.posted_at < $4
+            )
+            SELECT
+                CASE WHEN converted_quantity IS NULL THEN asset_id END AS asset_id,
+                COALESCE(
+                    SUM(-COALESCE(converted_quantity, quantity)) FILTER (WHERE tracked AND quantity < 0),
+                    0
+                ) AS spent,
+                COALESCE(
+                    SUM(COALESCE(converted_quantity, quantity)) FILTER (WHERE quantity > 0),
+                    0
+                ) AS income
+            FROM converted
+            GROUP BY 1
+            ORDER BY 1 NULLS FIRST
+            "#,
+        )
+        .bind(budget.user_id)
+        .bind(budget.asset_id)
+        .bind(start)
+        .bind(end)
+        .bind(budget.account_ids.clone())
+        .bind(rounding.name())
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(totals)
+    }
+}
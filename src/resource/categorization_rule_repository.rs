@@ -0,0 +1,201 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        account::AccountId,
+        categorization_rule::{
+            CategorizationRule, CategorizationRuleCreate, CategorizationRuleFilter,
+            CategorizationRuleId,
+        },
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct CategorizationRuleRepository;
+
+impl GetRepository<CategorizationRuleId, CategorizationRule> for CategorizationRuleRepository {
+    #[instrument(name = "CategorizationRuleRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: CategorizationRuleId,
+    ) -> Result<CategorizationRule, RepositoryError> {
+        let categorization_rule = query_as::<_, CategorizationRule>(
+            r#"
+            SELECT * FROM categorization_rule
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(categorization_rule)
+    }
+}
+
+impl GetListRepository<CategorizationRule, CategorizationRuleFilter>
+    for CategorizationRuleRepository
+{
+    /// The rules in the order they are tried.
+    #[instrument(
+        name = "CategorizationRuleRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: CategorizationRuleFilter,
+    ) -> Result<Vec<CategorizationRule>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM categorization_rule
+            "#,
+            filter.predicate(),
+            Some(r#"priority DESC, created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let categorization_rules = query
+            .build_query_as::<CategorizationRule>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(categorization_rules))
+    }
+}
+
+impl CreateRepository<CategorizationRuleCreate, CategorizationRule>
+    for CategorizationRuleRepository
+{
+    #[instrument(name = "CategorizationRuleRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: CategorizationRuleCreate,
+    ) -> Result<CategorizationRule, RepositoryError> {
+        let new_categorization_rule = query_as::<_, CategorizationRule>(
+            r#"
+            INSERT INTO categorization_rule (
+                user_id, field, pattern, min_quantity, max_quantity, account_id, category, priority
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.field)
+        .bind(create_model.pattern)
+        .bind(create_model.min_quantity)
+        .bind(create_model.max_quantity)
+        .bind(create_model.account_id)
+        .bind(create_model.category)
+        .bind(create_model.priority)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_categorization_rule)
+    }
+}
+
+impl UpdateRepository<CategorizationRule> for CategorizationRuleRepository {
+    #[instrument(name = "CategorizationRuleRepository::update", skip_all, fields(id = ?model.id))]
+    async fn update(
+        &self,
+        mut session: PgTransaction<'_>,
+        model: CategorizationRule,
+    ) -> Result<CategorizationRule, RepositoryError> {
+        let updated_categorization_rule = query_as::<_, CategorizationRule>(
+            r#"
+            UPDATE categorization_rule
+            SET
+                field = $2,
+                pattern = $3,
+                min_quantity = $4,
+                max_quantity = $5,
+                account_id = $6,
+                category = $7,
+                priority = $8
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(model.id)
+        .bind(model.field)
+        .bind(model.pattern)
+        .bind(model.min_quantity)
+        .bind(model.max_quantity)
+        .bind(model.account_id)
+        .bind(model.category)
+        .bind(model.priority)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(updated_categorization_rule)
+    }
+}
+
+impl DeleteRepository<CategorizationRuleId, CategorizationRule> for CategorizationRuleRepository {
+    #[instrument(name = "CategorizationRuleRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: CategorizationRuleId,
+    ) -> Result<CategorizationRule, RepositoryError> {
+        let deleted_categorization_rule = query_as::<_, CategorizationRule>(
+            r#"
+            DELETE FROM categorization_rule
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_categorization_rule)
+    }
+}
+
+impl CategorizationRuleRepository {
+    /// The rules of the user the account belongs to, in the order they are
+    /// tried.
+    #[instrument(
+        name = "CategorizationRuleRepository::get_list_for_account",
+        skip_all,
+        fields(account_id = ?account_id, rows = tracing::field::Empty)
+    )]
+    pub async fn get_list_for_account(
+        &self,
+        mut session: PgTransaction<'_>,
+        account_id: AccountId,
+    ) -> Result<Vec<CategorizationRule>, RepositoryError> {
+        let categorization_rules = query_as::<_, CategorizationRule>(
+            r#"
+            SELECT r.* FROM categorization_rule r
+            JOIN account a ON a.user_id = r.user_id
+            WHERE a.id = $1
+            ORDER BY r.priority DESC, r.created_at, r.id
+            "#,
+        )
+        .bind(account_id)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(record_rows(categorization_rules))
+    }
+}
@@ -0,0 +1,227 @@
+use sqlx::{PgTransaction, query, query_as, query_scalar};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        asset::AssetId,
+        change_proposal::{
+            ChangeProposal, ChangeProposalCreate, ChangeProposalFilter, ChangeProposalId,
+            ChangeProposalStatus,
+        },
+        institution::InstitutionId,
+        user::UserId,
+    },
+    resource::{
+        CreateRepository, GetListRepository, GetRepository, InstrumentQuery, RepositoryError,
+        list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChangeProposalRepository;
+
+impl GetRepository<ChangeProposalId, ChangeProposal> for ChangeProposalRepository {
+    #[instrument(name = "ChangeProposalRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ChangeProposalId,
+    ) -> Result<ChangeProposal, RepositoryError> {
+        let change_proposal = query_as::<_, ChangeProposal>(
+            r#"
+            SELECT * FROM change_proposal
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(change_proposal)
+    }
+}
+
+impl GetListRepository<ChangeProposal, ChangeProposalFilter> for ChangeProposalRepository {
+    #[instrument(
+        name = "ChangeProposalRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: ChangeProposalFilter,
+    ) -> Result<Vec<ChangeProposal>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM change_proposal
+            "#,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let change_proposals = query
+            .build_query_as::<ChangeProposal>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(change_proposals))
+    }
+}
+
+impl CreateRepository<ChangeProposalCreate, ChangeProposal> for ChangeProposalRepository {
+    #[instrument(name = "ChangeProposalRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: ChangeProposalCreate,
+    ) -> Result<ChangeProposal, RepositoryError> {
+        let new_change_proposal = query_as::<_, ChangeProposal>(
+            r#"
+            INSERT INTO change_proposal (kind, payload, proposer_id, institution_id)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.kind)
+        .bind(create_model.payload)
+        .bind(create_model.proposer_id)
+        .bind(create_model.institution_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_change_proposal)
+    }
+}
+
+impl ChangeProposalRepository {
+    /// Decides a pending proposal, locking its row so only one review of it
+    /// goes through. A proposal that was already decided is
+    /// [`RepositoryError::AlreadyConsumed`].
+    #[instrument(
+        name = "ChangeProposalRepository::claim",
+        skip_all,
+        fields(id = ?id, status = ?status)
+    )]
+    pub async fn claim(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ChangeProposalId,
+        status: ChangeProposalStatus,
+        reviewer_id: UserId,
+        review_note: Option<String>,
+    ) -> Result<ChangeProposal, RepositoryError> {
+        let current_status = query_scalar::<_, ChangeProposalStatus>(
+            r#"
+            SELECT status FROM change_proposal
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        if current_status != ChangeProposalStatus::Pending {
+            return Err(RepositoryError::AlreadyConsumed);
+        }
+
+        let change_proposal = query_as::<_, ChangeProposal>(
+            r#"
+            UPDATE change_proposal
+            SET
+                status = $2,
+                reviewer_id = $3,
+                review_note = $4,
+                reviewed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(status)
+        .bind(reviewer_id)
+        .bind(review_note)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(change_proposal)
+    }
+
+    /// Returns a claimed proposal to review, as its change couldn't be made.
+    #[instrument(name = "ChangeProposalRepository::release", skip_all, fields(id = ?id))]
+    pub async fn release(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ChangeProposalId,
+    ) -> Result<(), RepositoryError> {
+        query(
+            r#"
+            UPDATE change_proposal
+            SET
+                status = 'pending',
+                reviewer_id = NULL,
+                review_note = NULL,
+                reviewed_at = NULL
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(())
+    }
+
+    /// Links a decided proposal to what approving it created or changed, and
+    /// notifies the proposer of the decision.
+    #[instrument(name = "ChangeProposalRepository::complete", skip_all, fields(id = ?id))]
+    pub async fn complete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ChangeProposalId,
+        institution_id: Option<InstitutionId>,
+        asset_id: Option<AssetId>,
+    ) -> Result<ChangeProposal, RepositoryError> {
+        let change_proposal = query_as::<_, ChangeProposal>(
+            r#"
+            UPDATE change_proposal
+            SET
+                institution_id = COALESCE($2, institution_id),
+                asset_id = COALESCE($3, asset_id)
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(institution_id)
+        .bind(asset_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+
+        query(
+            r#"
+            INSERT INTO webhook_event (user_id, event, payload)
+            SELECT proposer_id, 'proposal.' || status::TEXT, to_jsonb(change_proposal)
+            FROM change_proposal
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(change_proposal)
+    }
+}
@@ -1,73 +1,127 @@
-use sqlx::{PgTransaction, query_as};
+use chrono::{DateTime, Utc};
+use sqlx::{PgTransaction, query, query_as, query_scalar};
+use tracing::instrument;
 
 use crate::{
-    model::csrf_token::CsrfToken,
-    resource::{CreateRepository, DeleteRepository, GetRepository, RepositoryError},
+    model::csrf_token::{CsrfState, CsrfToken, CsrfTokenCreate},
+    resource::{CreateRepository, GetRepository, InstrumentQuery, RepositoryError},
 };
 
 #[derive(Debug, Clone, Copy)]
 pub struct CsrfTokenRepository;
 
-impl GetRepository<String, CsrfToken> for CsrfTokenRepository {
-    async fn get(
+impl CsrfTokenRepository {
+    /// Marks the token of `state` consumed and returns it.
+    ///
+    /// The row is locked while it is marked, so of concurrent consumers of
+    /// the same state only one succeeds and the others get
+    /// [`RepositoryError::AlreadyConsumed`]. A state that was never issued,
+    /// or was issued before `issued_after`, is [`RepositoryError::NotFound`].
+    #[instrument(name = "CsrfTokenRepository::consume", skip_all)]
+    pub async fn consume(
         &self,
         mut session: PgTransaction<'_>,
-        id: String,
+        state: CsrfState,
+        issued_after: DateTime<Utc>,
     ) -> Result<CsrfToken, RepositoryError> {
-        let csrf_token = query_as!(
-            CsrfToken,
+        let consumed = query_as::<_, CsrfToken>(
             r#"
-                SELECT * FROM csrf_token
-                where token = $1
+            UPDATE csrf_token
+            SET consumed_at = CURRENT_TIMESTAMP
+            WHERE state = $1 AND consumed_at IS NULL AND created_at > $2
+            RETURNING *
             "#,
-            id
         )
-        .fetch_one(&mut *session)
+        .bind(&state)
+        .bind(issued_after)
+        .fetch_optional(&mut *session)
+        .in_query_span()
         .await?;
-        Ok(csrf_token)
+        if let Some(consumed) = consumed {
+            session.commit().await?;
+            return Ok(consumed);
+        }
+
+        let consumed = query_scalar::<_, bool>(
+            r#"
+            SELECT consumed_at IS NOT NULL FROM csrf_token WHERE state = $1
+            "#,
+        )
+        .bind(&state)
+        .fetch_optional(&mut *session)
+        .in_query_span()
+        .await?;
+        if consumed == Some(true) {
+            Err(RepositoryError::AlreadyConsumed)
+        } else {
+            Err(RepositoryError::NotFound)
+        }
+    }
+
+    /// Deletes the tokens issued before `issued_before`, consumed or not,
+    /// returning how many there were.
+    #[instrument(name = "CsrfTokenRepository::delete_issued_before", skip_all)]
+    pub async fn delete_issued_before(
+        &self,
+        mut session: PgTransaction<'_>,
+        issued_before: DateTime<Utc>,
+    ) -> Result<u64, RepositoryError> {
+        let deleted = query(
+            r#"
+            DELETE FROM csrf_token
+            WHERE created_at <= $1
+            "#,
+        )
+        .bind(issued_before)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?
+        .rows_affected();
+        session.commit().await?;
+        Ok(deleted)
     }
 }
 
-impl CreateRepository<CsrfToken, CsrfToken> for CsrfTokenRepository {
-    async fn create(
+impl GetRepository<CsrfState, CsrfToken> for CsrfTokenRepository {
+    #[instrument(name = "CsrfTokenRepository::get", skip_all)]
+    async fn get(
         &self,
         mut session: PgTransaction<'_>,
-        create_model: CsrfToken,
+        id: CsrfState,
     ) -> Result<CsrfToken, RepositoryError> {
-        let new_token = query_as!(
-            CsrfToken,
+        let csrf_token = query_as::<_, CsrfToken>(
             r#"
-                INSERT INTO csrf_token (token)
-                VALUES ($1)
-                RETURNING *
+            SELECT * FROM csrf_token
+            WHERE state = $1
             "#,
-            create_model.token
         )
+        .bind(id)
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
-        session.commit().await?;
-        Ok(new_token)
+        Ok(csrf_token)
     }
 }
 
-impl DeleteRepository<String, CsrfToken> for CsrfTokenRepository {
-    async fn delete(
+impl CreateRepository<CsrfTokenCreate, CsrfToken> for CsrfTokenRepository {
+    #[instrument(name = "CsrfTokenRepository::create", skip_all)]
+    async fn create(
         &self,
         mut session: PgTransaction<'_>,
-        id: String,
+        create_model: CsrfTokenCreate,
     ) -> Result<CsrfToken, RepositoryError> {
-        let deleted_token = query_as!(
-            CsrfToken,
+        let new_token = query_as::<_, CsrfToken>(
             r#"
-                DELETE FROM csrf_token
-                WHERE token = $1
-                RETURNING *
+            INSERT INTO csrf_token (state)
+            VALUES ($1)
+            RETURNING *
             "#,
-            id
         )
+        .bind(create_model.state)
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
-        Ok(deleted_token)
+        Ok(new_token)
     }
 }
@@ -1,19 +1,22 @@
-use sqlx::{PgTransaction, QueryBuilder, query_as};
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
 
 use crate::{
     model::{
         Filter,
         cursor_key::{CursorKey, CursorKeyCreate, CursorKeyFilter, CursorKeyId},
     },
-    resource::{CreateRepository, GetListRepository, GetRepository, RepositoryError},
+    resource::{
+        CreateRepository, GetListRepository, GetRepository, InstrumentQuery, RepositoryError,
+        list_query, record_rows,
+    },
 };
 
-const MAX_LIMIT: i64 = 100;
-
 #[derive(Debug, Clone)]
 pub struct CursorKeyRepository;
 
 impl GetRepository<CursorKeyId, CursorKey> for CursorKeyRepository {
+    #[instrument(name = "CursorKeyRepository::get", skip_all, fields(id = ?id))]
     async fn get(
         &self,
         mut session: PgTransaction<'_>,
@@ -28,12 +31,18 @@ impl GetRepository<CursorKeyId, CursorKey> for CursorKeyRepository {
             id.0,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         Ok(cursor_key)
     }
 }
 
 impl GetListRepository<CursorKey, CursorKeyFilter> for CursorKeyRepository {
+    #[instrument(
+        name = "CursorKeyRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
     async fn get_list(
         &self,
         mut session: PgTransaction<'_>,
@@ -41,31 +50,28 @@ impl GetListRepository<CursorKey, CursorKeyFilter> for CursorKeyRepository {
         limit: Option<i64>,
         filter: CursorKeyFilter,
     ) -> Result<Vec<CursorKey>, RepositoryError> {
-        let offset = offset.max(0);
-        let limit = limit.map(|x| x.clamp(1, MAX_LIMIT)).unwrap_or(MAX_LIMIT);
-        let mut query = QueryBuilder::new(
+        let mut query = list_query(
             r#"
             SELECT * FROM cursor_key
         "#,
+            filter.predicate(),
+            None,
+            offset,
+            limit,
         );
 
-        filter.push(&mut query);
-
-        query.push(r#" OFFSET "#);
-        query.push_bind(offset);
-        query.push(r#" LIMIT "#);
-        query.push_bind(limit);
-
         let cursor_keys = query
             .build_query_as::<CursorKey>()
             .fetch_all(&mut *session)
+            .in_query_span()
             .await?;
 
-        Ok(cursor_keys)
+        Ok(record_rows(cursor_keys))
     }
 }
 
 impl CreateRepository<CursorKeyCreate, CursorKey> for CursorKeyRepository {
+    #[instrument(name = "CursorKeyRepository::create", skip_all)]
     async fn create(
         &self,
         mut session: PgTransaction<'_>,
@@ -81,6 +87,7 @@ impl CreateRepository<CursorKeyCreate, CursorKey> for CursorKeyRepository {
             create_model.expires_at,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(new_cursor_key)
@@ -0,0 +1,101 @@
+//! Keeps a slow query from outliving the request that made it.
+//!
+//! The API handles each request under a [`DEADLINE`] a little short of the
+//! HTTP timeout. Every connection of a pool built with [`pool_options`]
+//! holds its statements to [`DatabaseConfig::statement_timeout`], and a
+//! transaction begun with [`begin`] once less than that is left until the
+//! deadline is held to what is left instead, so Postgres cancels a query
+//! that would run past it rather than keep running it for a client that
+//! has gone.
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use sqlx::{PgConnection, PgPool, PgTransaction, postgres::PgPoolOptions, query};
+
+use crate::config::DatabaseConfig;
+
+/// The SQLSTATE of a statement Postgres cancelled, on its timeout or on
+/// request.
+const QUERY_CANCELED: &str = "57014";
+
+tokio::task_local! {
+    /// When the request being handled must be answered by.
+    pub static DEADLINE: Instant;
+}
+
+/// The queries that ran out of time since the server started.
+static QUERY_TIMEOUTS: AtomicU64 = AtomicU64::new(0);
+
+/// What is left until the deadline of the request being handled, if in
+/// one.
+pub fn remaining() -> Option<Duration> {
+    DEADLINE
+        .try_with(|deadline| deadline.saturating_duration_since(Instant::now()))
+        .ok()
+}
+
+/// The timeout of the statements run now. Postgres takes a zero timeout to
+/// mean none, so a spent budget still leaves a millisecond.
+pub fn statement_timeout(config: DatabaseConfig) -> Duration {
+    remaining()
+        .map_or(config.statement_timeout, |remaining| {
+            remaining.min(config.statement_timeout)
+        })
+        .max(Duration::from_millis(1))
+}
+
+/// Counts a query that ran out of time.
+pub fn record_timeout() {
+    QUERY_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The queries that ran out of time since the server started.
+pub fn query_timeouts() -> u64 {
+    QUERY_TIMEOUTS.load(Ordering::Relaxed)
+}
+
+/// Whether Postgres cancelled the statement.
+pub fn is_query_canceled(error: &sqlx::Error) -> bool {
+    matches!(error, sqlx::Error::Database(e) if e.code().as_deref() == Some(QUERY_CANCELED))
+}
+
+/// Sets the `statement_timeout` of the session, or only of the transaction
+/// it is in if `local`.
+async fn set_statement_timeout(
+    connection: &mut PgConnection,
+    timeout: Duration,
+    local: bool,
+) -> Result<(), sqlx::Error> {
+    query("SELECT set_config('statement_timeout', $1, $2)")
+        .bind(format!("{}ms", timeout.as_millis()))
+        .bind(local)
+        .execute(connection)
+        .await?;
+    Ok(())
+}
+
+/// The options of a pool whose connections hold their statements to the
+/// timeout of `config`, set once as each one connects.
+pub fn pool_options(config: DatabaseConfig) -> PgPoolOptions {
+    PgPoolOptions::new().after_connect(move |connection, _| {
+        Box::pin(
+            async move { set_statement_timeout(connection, config.statement_timeout, false).await },
+        )
+    })
+}
+
+/// Begins a transaction on `pool`. Once the request being handled has less
+/// time left than the timeout of the connections, the statements of the
+/// transaction are held to what is left, which lapses as it ends.
+pub async fn begin(pool: &PgPool) -> Result<PgTransaction<'static>, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let config = DatabaseConfig::from_env();
+    let timeout = statement_timeout(config);
+    if timeout < config.statement_timeout {
+        set_statement_timeout(&mut transaction, timeout, true).await?;
+    }
+    Ok(transaction)
+}
@@ -0,0 +1,178 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgTransaction, query, query_as, query_scalar};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        account::AccountId,
+        user::{User, UserCreate, UserId},
+    },
+    resource::{InstrumentQuery, RepositoryError},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct DemoRepository;
+
+impl DemoRepository {
+    /// Creates the user of a demo session along with a copy of the accounts
+    /// and transactions of the seeded demo user, all at once. Creates
+    /// nothing when there are `max_sessions` of `sub_prefix` created after
+    /// `created_after` already.
+    #[instrument(
+        name = "DemoRepository::create_session",
+        skip_all,
+        fields(seed_user_id = ?seed_user_id)
+    )]
+    pub async fn create_session(
+        &self,
+        mut session: PgTransaction<'_>,
+        seed_user_id: UserId,
+        create_model: UserCreate,
+        sub_prefix: &str,
+        max_sessions: u32,
+        created_after: DateTime<Utc>,
+    ) -> Result<Option<User>, RepositoryError> {
+        // Logins wait on each other here, so they can't all pass the cap
+        // at once.
+        query(r#"SELECT pg_advisory_xact_lock(hashtext('demo_session'))"#)
+            .execute(&mut *session)
+            .in_query_span()
+            .await?;
+        let sessions = query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM "user"
+            WHERE iss = $1 AND sub LIKE $2 || '%' AND created_at > $3
+            "#,
+        )
+        .bind(&create_model.iss)
+        .bind(sub_prefix)
+        .bind(created_after)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        if sessions >= i64::from(max_sessions) {
+            return Ok(None);
+        }
+
+        let user = query_as::<_, User>(
+            r#"
+            INSERT INTO "user" (name, email, sub, iss)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.name)
+        .bind(create_model.email)
+        .bind(create_model.sub)
+        .bind(create_model.iss)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+
+        let seed_account_ids = query_scalar::<_, AccountId>(
+            r#"
+            SELECT id FROM account
+            WHERE user_id = $1
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(seed_user_id)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        for seed_account_id in seed_account_ids {
+            let account_id = query_scalar::<_, AccountId>(
+                r#"
+                INSERT INTO account (user_id, institution_id, name, notes, default_asset_id)
+                SELECT $1, institution_id, name, notes, default_asset_id
+                FROM account
+                WHERE id = $2
+                RETURNING id
+                "#,
+            )
+            .bind(user.id)
+            .bind(seed_account_id)
+            .fetch_one(&mut *session)
+            .in_query_span()
+            .await?;
+            query(
+                r#"
+                INSERT INTO "transaction" (
+                    account_id, asset_id, posted_at, description, quantity, notes, external_id,
+                    category
+                )
+                SELECT $1, asset_id, posted_at, description, quantity, notes, external_id, category
+                FROM "transaction"
+                WHERE account_id = $2
+                ORDER BY id
+                "#,
+            )
+            .bind(account_id)
+            .bind(seed_account_id)
+            .execute(&mut *session)
+            .in_query_span()
+            .await?;
+        }
+
+        session.commit().await?;
+        Ok(Some(user))
+    }
+
+    /// Deletes the users of the demo sessions created before
+    /// `created_before`, along with everything they own. Returns how many
+    /// sessions were deleted.
+    #[instrument(name = "DemoRepository::delete_sessions", skip_all)]
+    pub async fn delete_sessions(
+        &self,
+        mut session: PgTransaction<'_>,
+        iss: &str,
+        sub_prefix: &str,
+        created_before: DateTime<Utc>,
+    ) -> Result<u64, RepositoryError> {
+        let user_ids = query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM "user"
+            WHERE iss = $1 AND sub LIKE $2 || '%' AND created_at < $3
+            "#,
+        )
+        .bind(iss)
+        .bind(sub_prefix)
+        .bind(created_before)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        if user_ids.is_empty() {
+            return Ok(0);
+        }
+
+        // Accounts and transactions don't cascade from their user, the
+        // rest of what a user owns does.
+        query(
+            r#"
+            DELETE FROM "transaction"
+            USING account
+            WHERE account.id = "transaction".account_id
+            AND account.user_id = ANY($1)
+            "#,
+        )
+        .bind(&user_ids)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?;
+        query("DELETE FROM account WHERE user_id = ANY($1)")
+            .bind(&user_ids)
+            .execute(&mut *session)
+            .in_query_span()
+            .await?;
+        let deleted = query(r#"DELETE FROM "user" WHERE id = ANY($1)"#)
+            .bind(&user_ids)
+            .execute(&mut *session)
+            .in_query_span()
+            .await?
+            .rows_affected();
+
+        session.commit().await?;
+        Ok(deleted)
+    }
+}
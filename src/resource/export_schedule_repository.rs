@@ -0,0 +1,185 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        export_schedule::{
+            ExportRun, ExportSchedule, ExportScheduleCreate, ExportScheduleFilter, ExportScheduleId,
+        },
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ExportScheduleRepository;
+
+impl GetRepository<ExportScheduleId, ExportSchedule> for ExportScheduleRepository {
+    #[instrument(name = "ExportScheduleRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ExportScheduleId,
+    ) -> Result<ExportSchedule, RepositoryError> {
+        let export_schedule = query_as::<_, ExportSchedule>(
+            r#"
+            SELECT * FROM export_schedule
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(export_schedule)
+    }
+}
+
+impl GetListRepository<ExportSchedule, ExportScheduleFilter> for ExportScheduleRepository {
+    #[instrument(
+        name = "ExportScheduleRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: ExportScheduleFilter,
+    ) -> Result<Vec<ExportSchedule>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM export_schedule
+            "#,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let export_schedules = query
+            .build_query_as::<ExportSchedule>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(export_schedules))
+    }
+}
+
+impl CreateRepository<ExportScheduleCreate, ExportSchedule> for ExportScheduleRepository {
+    #[instrument(name = "ExportScheduleRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: ExportScheduleCreate,
+    ) -> Result<ExportSchedule, RepositoryError> {
+        let new_export_schedule = query_as::<_, ExportSchedule>(
+            r#"
+            INSERT INTO export_schedule (
+                user_id, format, destination_kind, destination_config, cadence
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.format)
+        .bind(create_model.destination_kind)
+        .bind(create_model.destination_config)
+        .bind(create_model.cadence)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_export_schedule)
+    }
+}
+
+impl UpdateRepository<ExportSchedule> for ExportScheduleRepository {
+    #[instrument(name = "ExportScheduleRepository::update", skip_all, fields(id = ?model.id))]
+    async fn update(
+        &self,
+        mut session: PgTransaction<'_>,
+        model: ExportSchedule,
+    ) -> Result<ExportSchedule, RepositoryError> {
+        let updated_export_schedule = query_as::<_, ExportSchedule>(
+            r#"
+            UPDATE export_schedule
+            SET
+                format = $2,
+                destination_kind = $3,
+                destination_config = $4,
+                cadence = $5
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(model.id)
+        .bind(model.format)
+        .bind(model.destination_kind)
+        .bind(model.destination_config)
+        .bind(model.cadence)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(updated_export_schedule)
+    }
+}
+
+impl DeleteRepository<ExportScheduleId, ExportSchedule> for ExportScheduleRepository {
+    #[instrument(name = "ExportScheduleRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ExportScheduleId,
+    ) -> Result<ExportSchedule, RepositoryError> {
+        let deleted_export_schedule = query_as::<_, ExportSchedule>(
+            r#"
+            DELETE FROM export_schedule
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_export_schedule)
+    }
+}
+
+impl ExportScheduleRepository {
+    /// Records the outcome of a run of the export.
+    #[instrument(name = "ExportScheduleRepository::record_run", skip_all, fields(id = ?id))]
+    pub async fn record_run(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ExportScheduleId,
+        run: ExportRun,
+    ) -> Result<ExportSchedule, RepositoryError> {
+        let export_schedule = query_as::<_, ExportSchedule>(
+            r#"
+            UPDATE export_schedule
+            SET last_run_at = $2, last_status = $3, last_error = $4
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(run.ran_at)
+        .bind(run.status)
+        .bind(run.error)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(export_schedule)
+    }
+}
@@ -0,0 +1,60 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::feature_flag::FeatureFlagOverride,
+    resource::{InstrumentQuery, RepositoryError, record_rows},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct FeatureFlagRepository;
+
+impl FeatureFlagRepository {
+    /// Every override, including those of features that no longer exist.
+    #[instrument(
+        name = "FeatureFlagRepository::get_all",
+        skip_all,
+        fields(rows = tracing::field::Empty)
+    )]
+    pub async fn get_all(
+        &self,
+        mut session: PgTransaction<'_>,
+    ) -> Result<Vec<FeatureFlagOverride>, RepositoryError> {
+        let overrides = query_as::<_, FeatureFlagOverride>(
+            r#"
+            SELECT * FROM feature_flag
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(record_rows(overrides))
+    }
+
+    /// Overrides a feature, replacing any earlier override of it.
+    #[instrument(name = "FeatureFlagRepository::set", skip_all, fields(name = name))]
+    pub async fn set(
+        &self,
+        mut session: PgTransaction<'_>,
+        name: &str,
+        enabled: bool,
+    ) -> Result<FeatureFlagOverride, RepositoryError> {
+        let flag = query_as::<_, FeatureFlagOverride>(
+            r#"
+            INSERT INTO feature_flag (name, enabled)
+            VALUES ($1, $2)
+            ON CONFLICT (name) DO UPDATE
+            SET enabled = EXCLUDED.enabled, updated_at = CURRENT_TIMESTAMP
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(enabled)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(flag)
+    }
+}
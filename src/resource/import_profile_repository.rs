@@ -0,0 +1,173 @@
+use sqlx::{PgTransaction, query_as, types::Json};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        import_profile::{
+            ImportProfile, ImportProfileCreate, ImportProfileFilter, ImportProfileId,
+        },
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImportProfileRepository;
+
+impl GetRepository<ImportProfileId, ImportProfile> for ImportProfileRepository {
+    #[instrument(name = "ImportProfileRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ImportProfileId,
+    ) -> Result<ImportProfile, RepositoryError> {
+        let import_profile = query_as::<_, ImportProfile>(
+            r#"
+            SELECT * FROM import_profile
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(import_profile)
+    }
+}
+
+impl GetListRepository<ImportProfile, ImportProfileFilter> for ImportProfileRepository {
+    #[instrument(
+        name = "ImportProfileRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: ImportProfileFilter,
+    ) -> Result<Vec<ImportProfile>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM import_profile
+            "#,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let import_profiles = query
+            .build_query_as::<ImportProfile>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(import_profiles))
+    }
+}
+
+impl CreateRepository<ImportProfileCreate, ImportProfile> for ImportProfileRepository {
+    #[instrument(name = "ImportProfileRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: ImportProfileCreate,
+    ) -> Result<ImportProfile, RepositoryError> {
+        let mapping = create_model.mapping;
+        let new_import_profile = query_as::<_, ImportProfile>(
+            r#"
+            INSERT INTO import_profile (
+                user_id,
+                name,
+                column_mapping,
+                date_format,
+                decimal_separator,
+                decimal_places,
+                sign_convention,
+                default_account_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.name)
+        .bind(Json(mapping.column_mapping))
+        .bind(mapping.date_format)
+        .bind(mapping.decimal_separator)
+        .bind(i16::from(mapping.decimal_places))
+        .bind(mapping.sign_convention)
+        .bind(create_model.default_account_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_import_profile)
+    }
+}
+
+impl UpdateRepository<ImportProfile> for ImportProfileRepository {
+    #[instrument(name = "ImportProfileRepository::update", skip_all, fields(id = ?model.id))]
+    async fn update(
+        &self,
+        mut session: PgTransaction<'_>,
+        model: ImportProfile,
+    ) -> Result<ImportProfile, RepositoryError> {
+        let updated_import_profile = query_as::<_, ImportProfile>(
+            r#"
+            UPDATE import_profile
+            SET
+                name = $2,
+                column_mapping = $3,
+                date_format = $4,
+                decimal_separator = $5,
+                decimal_places = $6,
+                sign_convention = $7,
+                default_account_id = $8
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(model.id)
+        .bind(model.name)
+        .bind(model.column_mapping)
+        .bind(model.date_format)
+        .bind(model.decimal_separator)
+        .bind(model.decimal_places)
+        .bind(model.sign_convention)
+        .bind(model.default_account_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(updated_import_profile)
+    }
+}
+
+impl DeleteRepository<ImportProfileId, ImportProfile> for ImportProfileRepository {
+    #[instrument(name = "ImportProfileRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ImportProfileId,
+    ) -> Result<ImportProfile, RepositoryError> {
+        let deleted_import_profile = query_as::<_, ImportProfile>(
+            r#"
+            DELETE FROM import_profile
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_import_profile)
+    }
+}
@@ -1,20 +1,307 @@
-use sqlx::{PgTransaction, QueryBuilder, query_as};
+use std::collections::{HashMap, HashSet};
+
+use sqlx::{PgTransaction, QueryBuilder, query_as, query_scalar};
+use tracing::instrument;
 
 use crate::{
     model::{
         Filter,
-        institution::{Institution, InstitutionCreate, InstitutionFilter, InstitutionId},
+        account::AccountId,
+        asset::AssetId,
+        institution::{
+            Institution, InstitutionBalance, InstitutionCreate, InstitutionFilter, InstitutionId,
+            InstitutionRollup, InstitutionUpsert, UpsertOutcome,
+        },
+        user::UserId,
     },
     resource::{
-        CreateRepository, DeleteRepository, GetListRepository, GetRepository, MAX_LIMIT,
-        RepositoryError, UpdateRepository,
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
     },
 };
 
 #[derive(Debug, Clone, Copy)]
 pub struct InstitutionRepository;
 
+impl InstitutionRepository {
+    /// Which of the names are taken by an institution.
+    #[instrument(name = "InstitutionRepository::existing_names", skip_all)]
+    pub async fn existing_names(
+        &self,
+        mut session: PgTransaction<'_>,
+        names: &[String],
+    ) -> Result<HashSet<String>, RepositoryError> {
+        let existing = query_scalar::<_, String>(
+            r#"
+            SELECT name FROM institution
+            WHERE name = ANY($1)
+            "#,
+        )
+        .bind(names)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(existing.into_iter().collect())
+    }
+
+    /// Creates or updates the institution of each row in one statement,
+    /// then points them at their parents in another, so a parent can be
+    /// upserted alongside its children. Rows that match their institution
+    /// are left untouched. The names of the rows must be distinct.
+    #[instrument(
+        name = "InstitutionRepository::upsert",
+        skip_all,
+        fields(rows = rows.len())
+    )]
+    pub async fn upsert(
+        &self,
+        mut session: PgTransaction<'_>,
+        rows: &[InstitutionUpsert],
+    ) -> Result<Vec<UpsertOutcome>, RepositoryError> {
+        if rows.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut query = QueryBuilder::new(
+            r#"
+            INSERT INTO institution (name, default_asset_id)
+            "#,
+        );
+        query.push_values(rows, |mut row, upsert| {
+            row.push_bind(&upsert.name)
+                .push_bind(upsert.default_asset_id);
+        });
+        query.push(
+            r#"
+            ON CONFLICT (name) DO UPDATE
+            SET default_asset_id = EXCLUDED.default_asset_id
+            WHERE institution.default_asset_id IS DISTINCT FROM EXCLUDED.default_asset_id
+            RETURNING name, xmax = 0
+            "#,
+        );
+        let written = query
+            .build_query_as::<(String, bool)>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let mut query = QueryBuilder::new(
+            r#"
+            UPDATE institution AS i
+            SET parent_id = p.id
+            FROM (
+            "#,
+        );
+        query.push_values(rows, |mut row, upsert| {
+            row.push_bind(&upsert.name)
+                .push_bind(&upsert.parent)
+                .push_unseparated("::VARCHAR");
+        });
+        query.push(
+            r#"
+            ) AS x (name, parent)
+            LEFT JOIN institution AS p ON p.name = x.parent
+            WHERE i.name = x.name
+            AND i.parent_id IS DISTINCT FROM p.id
+            RETURNING i.name
+            "#,
+        );
+        let reparented = query
+            .build_query_scalar::<String>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        session.commit().await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| match written.get(&row.name) {
+                Some(true) => UpsertOutcome::Created,
+                Some(false) => UpsertOutcome::Updated,
+                None if reparented.contains(&row.name) => UpsertOutcome::Updated,
+                None => UpsertOutcome::Unchanged,
+            })
+            .collect())
+    }
+
+    /// Totals the accounts held at `id` and every institution below it.
+    ///
+    /// Only accounts owned by `user_id` are counted when it is given.
+    #[instrument(
+        name = "InstitutionRepository::get_rollup",
+        skip_all,
+        fields(id = ?id, user_id = ?user_id)
+    )]
+    pub async fn get_rollup(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: InstitutionId,
+        user_id: Option<UserId>,
+        account_ids: Option<Vec<AccountId>>,
+    ) -> Result<InstitutionRollup, RepositoryError> {
+        // `UNION` rather than `UNION ALL` so a cycle can't recurse forever.
+        const SUBTREE: &str = r#"
+            WITH RECURSIVE subtree AS (
+                SELECT id FROM institution WHERE id = $1
+                UNION
+                SELECT i.id FROM institution i
+                JOIN subtree s ON i.parent_id = s.id
+            ),
+            visible_account AS (
+                SELECT a.id FROM account a
+                JOIN subtree s ON a.institution_id = s.id
+                WHERE ($2::UUID IS NULL OR a.user_id = $2)
+                AND ($3::UUID[] IS NULL OR a.id = ANY($3))
+            )
+        "#;
+
+        let institution_count =
+            query_scalar::<_, i64>(&format!(r#"{SUBTREE} SELECT COUNT(*) FROM subtree"#))
+                .bind(id)
+                .bind(user_id)
+                .bind(&account_ids)
+                .fetch_one(&mut *session)
+                .in_query_span()
+                .await?;
+        if institution_count == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+
+        let account_count = query_scalar::<_, i64>(&format!(
+            r#"{SUBTREE} SELECT COUNT(*) FROM visible_account"#
+        ))
+        .bind(id)
+        .bind(user_id)
+        .bind(&account_ids)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+
+        let balances = query_as::<_, InstitutionBalance>(&format!(
+            r#"{SUBTREE}
+            SELECT
+                t.asset_id,
+                SUM(t.quantity) AS quantity,
+                COUNT(*) AS transaction_count
+            FROM "transaction" t
+            JOIN visible_account a ON t.account_id = a.id
+            GROUP BY t.asset_id
+            ORDER BY t.asset_id
+            "#
+        ))
+        .bind(id)
+        .bind(user_id)
+        .bind(&account_ids)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+
+        Ok(InstitutionRollup {
+            institution_count,
+            account_count,
+            balances,
+        })
+    }
+
+    /// Fetches the institution of `id`, locking it until the end of the
+    /// transaction. A change of the hierarchy that has to stay consistent
+    /// locks the institutions it looks at, so concurrent changes of them
+    /// wait for it.
+    #[instrument(name = "InstitutionRepository::lock", skip_all, fields(id = ?id))]
+    pub async fn lock(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: InstitutionId,
+    ) -> Result<Institution, RepositoryError> {
+        let institution = query_as!(
+            Institution,
+            r#"
+            SELECT id, created_at, updated_at, name,
+            parent_id AS "parent_id: InstitutionId",
+            default_asset_id AS "default_asset_id: AssetId"
+            FROM institution
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            id.0
+        )
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(institution)
+    }
+
+    /// How many levels the hierarchy below `id` has, counting `id` itself.
+    /// Levels past `max_height` aren't counted, so the walk ends even on a
+    /// cycle.
+    #[instrument(name = "InstitutionRepository::get_height", skip_all, fields(id = ?id))]
+    pub async fn get_height(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: InstitutionId,
+        max_height: i32,
+    ) -> Result<i32, RepositoryError> {
+        let height = query_scalar!(
+            r#"
+            WITH RECURSIVE subtree AS (
+                SELECT id, 1 AS level FROM institution WHERE id = $1
+                UNION ALL
+                SELECT i.id, s.level + 1 FROM institution i
+                JOIN subtree s ON i.parent_id = s.id
+                WHERE s.level < $2
+            )
+            SELECT COALESCE(MAX(level), 0) AS "height!" FROM subtree
+            "#,
+            id.0,
+            max_height
+        )
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(height)
+    }
+
+    /// Counts the accounts held directly at each of `ids`. Institutions
+    /// without accounts are left out.
+    ///
+    /// Only accounts owned by `user_id` are counted when it is given.
+    #[instrument(
+        name = "InstitutionRepository::get_account_counts",
+        skip_all,
+        fields(user_id = ?user_id)
+    )]
+    pub async fn get_account_counts(
+        &self,
+        mut session: PgTransaction<'_>,
+        ids: &[InstitutionId],
+        user_id: Option<UserId>,
+        account_ids: Option<Vec<AccountId>>,
+    ) -> Result<HashMap<InstitutionId, i64>, RepositoryError> {
+        let counts = query_as::<_, (InstitutionId, i64)>(
+            r#"
+            SELECT institution_id, COUNT(*) FROM account
+            WHERE institution_id = ANY($1)
+            AND ($2::UUID IS NULL OR user_id = $2)
+            AND ($3::UUID[] IS NULL OR id = ANY($3))
+            GROUP BY institution_id
+            "#,
+        )
+        .bind(ids)
+        .bind(user_id)
+        .bind(&account_ids)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(counts.into_iter().collect())
+    }
+}
+
 impl GetRepository<InstitutionId, Institution> for InstitutionRepository {
+    #[instrument(name = "InstitutionRepository::get", skip_all, fields(id = ?id))]
     async fn get(
         &self,
         mut session: PgTransaction<'_>,
@@ -23,18 +310,27 @@ impl GetRepository<InstitutionId, Institution> for InstitutionRepository {
         let institution = query_as!(
             Institution,
             r#"
-            SELECT * FROM institution
+            SELECT id, created_at, updated_at, name,
+            parent_id AS "parent_id: InstitutionId",
+            default_asset_id AS "default_asset_id: AssetId"
+            FROM institution
             WHERE id = $1
             "#,
-            id.0,
+            id.0
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         Ok(institution)
     }
 }
 
 impl GetListRepository<Institution, InstitutionFilter> for InstitutionRepository {
+    #[instrument(
+        name = "InstitutionRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
     async fn get_list(
         &self,
         mut session: PgTransaction<'_>,
@@ -42,30 +338,28 @@ impl GetListRepository<Institution, InstitutionFilter> for InstitutionRepository
         limit: Option<i64>,
         filter: InstitutionFilter,
     ) -> Result<Vec<Institution>, RepositoryError> {
-        let offset = offset.max(0);
-        let limit = limit.map(|x| x.clamp(1, MAX_LIMIT)).unwrap_or(MAX_LIMIT);
-        let mut query = QueryBuilder::new(
+        let order_by = filter.sort.order_by();
+        let mut query = list_query(
             r#"
             SELECT * from institution
             "#,
+            filter.predicate(),
+            Some(order_by),
+            offset,
+            limit,
         );
 
-        filter.push(&mut query);
-
-        query.push(r#" OFFSET "#);
-        query.push_bind(offset);
-        query.push(r#" LIMIT "#);
-        query.push_bind(limit);
-
         let institutions = query
             .build_query_as::<Institution>()
             .fetch_all(&mut *session)
+            .in_query_span()
             .await?;
-        Ok(institutions)
+        Ok(record_rows(institutions))
     }
 }
 
 impl CreateRepository<InstitutionCreate, Institution> for InstitutionRepository {
+    #[instrument(name = "InstitutionRepository::create", skip_all)]
     async fn create(
         &self,
         mut session: PgTransaction<'_>,
@@ -74,13 +368,18 @@ impl CreateRepository<InstitutionCreate, Institution> for InstitutionRepository
         let new_institution = query_as!(
             Institution,
             r#"
-            INSERT INTO institution (name)
-            VALUES ($1)
-            RETURNING *
+            INSERT INTO institution (name, parent_id, default_asset_id)
+            VALUES ($1, $2, $3)
+            RETURNING id, created_at, updated_at, name,
+            parent_id AS "parent_id: InstitutionId",
+            default_asset_id AS "default_asset_id: AssetId"
             "#,
-            create_model.name
+            create_model.name,
+            create_model.parent_id as _,
+            create_model.default_asset_id as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(new_institution)
@@ -88,6 +387,7 @@ impl CreateRepository<InstitutionCreate, Institution> for InstitutionRepository
 }
 
 impl UpdateRepository<Institution> for InstitutionRepository {
+    #[instrument(name = "InstitutionRepository::update", skip_all, fields(id = ?model.id))]
     async fn update(
         &self,
         mut session: PgTransaction<'_>,
@@ -97,14 +397,19 @@ impl UpdateRepository<Institution> for InstitutionRepository {
             Institution,
             r#"
             UPDATE institution
-            SET name = $2
+            SET name = $2, parent_id = $3, default_asset_id = $4
             WHERE id = $1
-            RETURNING *
+            RETURNING id, created_at, updated_at, name,
+            parent_id AS "parent_id: InstitutionId",
+            default_asset_id AS "default_asset_id: AssetId"
             "#,
             model.id.0,
             model.name,
+            model.parent_id as _,
+            model.default_asset_id as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(updated_institution)
@@ -112,6 +417,7 @@ impl UpdateRepository<Institution> for InstitutionRepository {
 }
 
 impl DeleteRepository<InstitutionId, Institution> for InstitutionRepository {
+    #[instrument(name = "InstitutionRepository::delete", skip_all, fields(id = ?id))]
     async fn delete(
         &self,
         mut session: PgTransaction<'_>,
@@ -122,11 +428,14 @@ impl DeleteRepository<InstitutionId, Institution> for InstitutionRepository {
             r#"
             DELETE FROM institution
             WHERE id = $1
-            RETURNING *
+            RETURNING id, created_at, updated_at, name,
+            parent_id AS "parent_id: InstitutionId",
+            default_asset_id AS "default_asset_id: AssetId"
             "#,
             id.0
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(deleted_institution)
@@ -0,0 +1,82 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::journal_entry::{JournalEntry, JournalEntryCreate, JournalEntryId},
+    resource::{
+        CreateRepository, DeleteRepository, GetRepository, InstrumentQuery, RepositoryError,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct JournalEntryRepository;
+
+impl GetRepository<JournalEntryId, JournalEntry> for JournalEntryRepository {
+    #[instrument(name = "JournalEntryRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: JournalEntryId,
+    ) -> Result<JournalEntry, RepositoryError> {
+        let journal_entry = query_as::<_, JournalEntry>(
+            r#"
+            SELECT * FROM journal_entry
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(journal_entry)
+    }
+}
+
+impl CreateRepository<JournalEntryCreate, JournalEntry> for JournalEntryRepository {
+    /// Creates the entry alone, its legs are created as transactions.
+    #[instrument(name = "JournalEntryRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: JournalEntryCreate,
+    ) -> Result<JournalEntry, RepositoryError> {
+        let journal_entry = query_as::<_, JournalEntry>(
+            r#"
+            INSERT INTO journal_entry (posted_at, description)
+            VALUES ($1, $2)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.posted_at)
+        .bind(create_model.description)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(journal_entry)
+    }
+}
+
+impl DeleteRepository<JournalEntryId, JournalEntry> for JournalEntryRepository {
+    /// Deletes the entry, and with it its legs.
+    #[instrument(name = "JournalEntryRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: JournalEntryId,
+    ) -> Result<JournalEntry, RepositoryError> {
+        let journal_entry = query_as::<_, JournalEntry>(
+            r#"
+            DELETE FROM journal_entry
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(journal_entry)
+    }
+}
@@ -0,0 +1,78 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        login_event::{LoginEvent, LoginEventCreate, LoginEventFilter},
+    },
+    resource::{
+        CreateRepository, GetListRepository, InstrumentQuery, RepositoryError, list_query,
+        record_rows,
+    },
+};
+
+/// The login history of the users, newest first. Events are only ever
+/// added, and go away with their user.
+#[derive(Debug, Clone, Copy)]
+pub struct LoginEventRepository;
+
+impl GetListRepository<LoginEvent, LoginEventFilter> for LoginEventRepository {
+    #[instrument(
+        name = "LoginEventRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: LoginEventFilter,
+    ) -> Result<Vec<LoginEvent>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM login_event
+            "#,
+            filter.predicate(),
+            Some(r#"id DESC"#),
+            offset,
+            limit,
+        );
+
+        let login_events = query
+            .build_query_as::<LoginEvent>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(login_events))
+    }
+}
+
+impl CreateRepository<LoginEventCreate, LoginEvent> for LoginEventRepository {
+    #[instrument(name = "LoginEventRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: LoginEventCreate,
+    ) -> Result<LoginEvent, RepositoryError> {
+        let login_event = query_as::<_, LoginEvent>(
+            r#"
+            INSERT INTO login_event (user_id, event_type, issuer, ip_address, user_agent)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.event_type)
+        .bind(create_model.issuer)
+        .bind(create_model.ip_address)
+        .bind(create_model.user_agent)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(login_event)
+    }
+}
@@ -0,0 +1,41 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::login_throttle_event::{LoginThrottleEvent, LoginThrottleEventCreate},
+    resource::{CreateRepository, InstrumentQuery, RepositoryError},
+};
+
+/// The record of the lockouts of the sign in and refresh endpoints, which
+/// is only ever added to.
+#[derive(Debug, Clone, Copy)]
+pub struct LoginThrottleEventRepository;
+
+impl CreateRepository<LoginThrottleEventCreate, LoginThrottleEvent>
+    for LoginThrottleEventRepository
+{
+    #[instrument(name = "LoginThrottleEventRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: LoginThrottleEventCreate,
+    ) -> Result<LoginThrottleEvent, RepositoryError> {
+        let login_throttle_event = query_as::<_, LoginThrottleEvent>(
+            r#"
+            INSERT INTO login_throttle_event (endpoint, key_kind, key, failures, locked_until)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.endpoint)
+        .bind(create_model.key_kind)
+        .bind(create_model.key)
+        .bind(create_model.failures)
+        .bind(create_model.locked_until)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(login_throttle_event)
+    }
+}
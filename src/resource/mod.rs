@@ -1,20 +1,66 @@
+pub mod account_balance_repository;
 pub mod account_repository;
+pub mod alert_rule_repository;
+pub mod announcement_repository;
+pub mod api_key_repository;
+pub mod asset_price_repository;
 pub mod asset_repository;
+pub mod attachment_repository;
+pub mod budget_repository;
+pub mod categorization_rule_repository;
+pub mod change_proposal_repository;
 pub mod csrf_token_repository;
 pub mod cursor_key_repository;
+pub mod deadline;
+pub mod demo_repository;
+pub mod export_schedule_repository;
+pub mod feature_flag_repository;
+pub mod import_profile_repository;
 pub mod institution_repository;
+pub mod journal_entry_repository;
+pub mod login_event_repository;
+pub mod login_throttle_event_repository;
+pub mod passkey_repository;
+pub mod provider_connection_repository;
+pub mod quick_entry_repository;
+pub mod recategorization_repository;
+pub mod statement_repository;
+pub mod stats_repository;
+pub mod step_up_grant_repository;
+pub mod sync_repository;
+pub mod transaction_history_repository;
 pub mod transaction_repository;
+pub mod user_preference_repository;
 pub mod user_repository;
+pub mod user_session_repository;
+pub mod watchlist_entry_repository;
+pub mod webauthn_challenge_repository;
 
 use derive_more::Display;
-use sqlx::PgTransaction;
+use sqlx::{PgTransaction, Postgres, QueryBuilder};
 use thiserror::Error;
+use tracing::{Instrument, Span, info_span, instrument::Instrumented};
 
+use crate::model::Predicate;
+
+/// The number of rows a `get_list` returns when it is not given a limit.
 pub const MAX_LIMIT: i64 = 100;
 
 #[derive(Error, Debug, Display, Clone)]
 pub enum RepositoryError {
     NotFound,
+    /// The row was already used up, like a consumed CSRF state
+    AlreadyConsumed,
+    /// Postgres cancelled the query, which ran out of time
+    Timeout,
+    /// A statement touched a different number of rows than was counted
+    /// for it, so the rows changed under the transaction
+    #[display("{table}: {affected} rows affected, {expected} expected")]
+    RowCount {
+        table: &'static str,
+        expected: i64,
+        affected: u64,
+    },
     Sqlx(String),
 }
 
@@ -22,11 +68,77 @@ impl From<sqlx::Error> for RepositoryError {
     fn from(value: sqlx::Error) -> Self {
         match value {
             sqlx::Error::RowNotFound => Self::NotFound,
+            e if deadline::is_query_canceled(&e) => {
+                deadline::record_timeout();
+                Self::Timeout
+            }
             e => Self::Sqlx(format!("{e}")),
         }
     }
 }
 
+/// Runs a query in a `db.query` span, so its timing shows up as a child of
+/// the repository call it was made from.
+pub trait InstrumentQuery: Future + Sized {
+    fn in_query_span(self) -> Instrumented<Self> {
+        self.instrument(info_span!(
+            "db.query",
+            db.system = "postgresql",
+            otel.kind = "client"
+        ))
+    }
+}
+
+impl<F: Future> InstrumentQuery for F {}
+
+/// Records how many rows a `get_list` returned on its span.
+pub fn record_rows<Model>(rows: Vec<Model>) -> Vec<Model> {
+    Span::current().record("rows", rows.len());
+    rows
+}
+
+/// The order of a `get_list` page when its repository gives none.
+pub const DEFAULT_ORDER: &str = "created_at";
+
+/// Builds the query of a `get_list` page: the rows of `select` meeting
+/// `predicate`, in the order of `order_by`, or [`DEFAULT_ORDER`] if there
+/// is none.
+///
+/// Every page is ordered deterministically: rows that tie on `order_by`
+/// are ordered by `id`, so consecutive pages neither repeat nor skip rows.
+pub fn list_query(
+    select: impl Into<String>,
+    predicate: Predicate,
+    order_by: Option<&str>,
+    offset: i64,
+    limit: Option<i64>,
+) -> QueryBuilder<'static, Postgres> {
+    let offset = offset.max(0);
+    let limit = limit.unwrap_or(MAX_LIMIT).max(1);
+    let mut query = QueryBuilder::new(select);
+    predicate.push(&mut query);
+    query.push(r#" ORDER BY "#);
+    query.push(with_tie_breaker(order_by.unwrap_or(DEFAULT_ORDER)));
+    query.push(r#" OFFSET "#);
+    query.push_bind(offset);
+    query.push(r#" LIMIT "#);
+    query.push_bind(limit);
+    query
+}
+
+/// Appends `id` to `order_by`, unless it already ends on it.
+fn with_tie_breaker(order_by: &str) -> String {
+    let last = order_by
+        .rsplit(',')
+        .next()
+        .and_then(|x| x.split_whitespace().next());
+    if last == Some("id") {
+        order_by.to_owned()
+    } else {
+        format!("{order_by}, id")
+    }
+}
+
 pub trait GetRepository<Id, Model> {
     fn get(
         &self,
@@ -36,6 +148,8 @@ pub trait GetRepository<Id, Model> {
 }
 
 pub trait GetListRepository<Model, Filter> {
+    /// Lists a page of rows. The `limit` is used as given, callers bound it
+    /// by the page size of the resource, see [`crate::config::PageSize`].
     fn get_list(
         &self,
         session: PgTransaction,
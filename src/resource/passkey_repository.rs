@@ -0,0 +1,147 @@
+use sqlx::{PgTransaction, query_as, types::Json};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        passkey::{Passkey, PasskeyCreate, PasskeyFilter, PasskeyId},
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct PasskeyRepository;
+
+impl GetRepository<PasskeyId, Passkey> for PasskeyRepository {
+    #[instrument(name = "PasskeyRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: PasskeyId,
+    ) -> Result<Passkey, RepositoryError> {
+        let passkey = query_as::<_, Passkey>(
+            r#"
+            SELECT * FROM passkey
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(passkey)
+    }
+}
+
+impl GetListRepository<Passkey, PasskeyFilter> for PasskeyRepository {
+    #[instrument(
+        name = "PasskeyRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: PasskeyFilter,
+    ) -> Result<Vec<Passkey>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM passkey
+            "#,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let passkeys = query
+            .build_query_as::<Passkey>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(passkeys))
+    }
+}
+
+impl CreateRepository<PasskeyCreate, Passkey> for PasskeyRepository {
+    #[instrument(name = "PasskeyRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: PasskeyCreate,
+    ) -> Result<Passkey, RepositoryError> {
+        let credential_id = create_model.credential.cred_id().as_ref().to_vec();
+        let new_passkey = query_as::<_, Passkey>(
+            r#"
+            INSERT INTO passkey (user_id, name, credential_id, credential)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.name)
+        .bind(credential_id)
+        .bind(Json(create_model.credential))
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_passkey)
+    }
+}
+
+impl UpdateRepository<Passkey> for PasskeyRepository {
+    #[instrument(name = "PasskeyRepository::update", skip_all, fields(id = ?model.id))]
+    async fn update(
+        &self,
+        mut session: PgTransaction<'_>,
+        model: Passkey,
+    ) -> Result<Passkey, RepositoryError> {
+        let updated_passkey = query_as::<_, Passkey>(
+            r#"
+            UPDATE passkey
+            SET name = $2, credential = $3, last_used_at = $4
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(model.id)
+        .bind(model.name)
+        .bind(model.credential)
+        .bind(model.last_used_at)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(updated_passkey)
+    }
+}
+
+impl DeleteRepository<PasskeyId, Passkey> for PasskeyRepository {
+    #[instrument(name = "PasskeyRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: PasskeyId,
+    ) -> Result<Passkey, RepositoryError> {
+        let deleted_passkey = query_as::<_, Passkey>(
+            r#"
+            DELETE FROM passkey
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_passkey)
+    }
+}
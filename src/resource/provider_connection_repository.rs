@@ -0,0 +1,158 @@
+use sqlx::{PgTransaction, query_as, types::Json};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        provider_connection::{
+            ProviderConnection, ProviderConnectionCreate, ProviderConnectionFilter,
+            ProviderConnectionId,
+        },
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProviderConnectionRepository;
+
+impl GetRepository<ProviderConnectionId, ProviderConnection> for ProviderConnectionRepository {
+    #[instrument(name = "ProviderConnectionRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ProviderConnectionId,
+    ) -> Result<ProviderConnection, RepositoryError> {
+        let provider_connection = query_as::<_, ProviderConnection>(
+            r#"
+            SELECT * FROM provider_connection
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(provider_connection)
+    }
+}
+
+impl GetListRepository<ProviderConnection, ProviderConnectionFilter>
+    for ProviderConnectionRepository
+{
+    #[instrument(
+        name = "ProviderConnectionRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: ProviderConnectionFilter,
+    ) -> Result<Vec<ProviderConnection>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM provider_connection
+            "#,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let provider_connections = query
+            .build_query_as::<ProviderConnection>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(provider_connections))
+    }
+}
+
+impl CreateRepository<ProviderConnectionCreate, ProviderConnection>
+    for ProviderConnectionRepository
+{
+    #[instrument(name = "ProviderConnectionRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: ProviderConnectionCreate,
+    ) -> Result<ProviderConnection, RepositoryError> {
+        let new_provider_connection = query_as::<_, ProviderConnection>(
+            r#"
+            INSERT INTO provider_connection (user_id, institution_id, connector, config)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.institution_id)
+        .bind(create_model.connector)
+        .bind(Json(create_model.config))
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_provider_connection)
+    }
+}
+
+impl UpdateRepository<ProviderConnection> for ProviderConnectionRepository {
+    #[instrument(name = "ProviderConnectionRepository::update", skip_all, fields(id = ?model.id))]
+    async fn update(
+        &self,
+        mut session: PgTransaction<'_>,
+        model: ProviderConnection,
+    ) -> Result<ProviderConnection, RepositoryError> {
+        let updated_provider_connection = query_as::<_, ProviderConnection>(
+            r#"
+            UPDATE provider_connection
+            SET
+                connector = $2,
+                config = $3,
+                cursors = $4,
+                last_synced_at = $5
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(model.id)
+        .bind(model.connector)
+        .bind(model.config)
+        .bind(model.cursors)
+        .bind(model.last_synced_at)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(updated_provider_connection)
+    }
+}
+
+impl DeleteRepository<ProviderConnectionId, ProviderConnection> for ProviderConnectionRepository {
+    #[instrument(name = "ProviderConnectionRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: ProviderConnectionId,
+    ) -> Result<ProviderConnection, RepositoryError> {
+        let deleted_provider_connection = query_as::<_, ProviderConnection>(
+            r#"
+            DELETE FROM provider_connection
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_provider_connection)
+    }
+}
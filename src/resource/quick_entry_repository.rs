@@ -0,0 +1,160 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        quick_entry::{QuickEntry, QuickEntryCreate, QuickEntryFilter, QuickEntryId},
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct QuickEntryRepository;
+
+impl GetRepository<QuickEntryId, QuickEntry> for QuickEntryRepository {
+    #[instrument(name = "QuickEntryRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: QuickEntryId,
+    ) -> Result<QuickEntry, RepositoryError> {
+        let quick_entry = query_as::<_, QuickEntry>(
+            r#"
+            SELECT * FROM quick_entry
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(quick_entry)
+    }
+}
+
+impl GetListRepository<QuickEntry, QuickEntryFilter> for QuickEntryRepository {
+    #[instrument(
+        name = "QuickEntryRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: QuickEntryFilter,
+    ) -> Result<Vec<QuickEntry>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM quick_entry
+            "#,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let quick_entries = query
+            .build_query_as::<QuickEntry>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(quick_entries))
+    }
+}
+
+impl CreateRepository<QuickEntryCreate, QuickEntry> for QuickEntryRepository {
+    #[instrument(name = "QuickEntryRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: QuickEntryCreate,
+    ) -> Result<QuickEntry, RepositoryError> {
+        let new_quick_entry = query_as::<_, QuickEntry>(
+            r#"
+            INSERT INTO quick_entry (
+                user_id, label, account_id, asset_id, quantity, description, category
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.label)
+        .bind(create_model.account_id)
+        .bind(create_model.asset_id)
+        .bind(create_model.quantity)
+        .bind(create_model.description)
+        .bind(create_model.category)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_quick_entry)
+    }
+}
+
+impl UpdateRepository<QuickEntry> for QuickEntryRepository {
+    #[instrument(name = "QuickEntryRepository::update", skip_all, fields(id = ?model.id))]
+    async fn update(
+        &self,
+        mut session: PgTransaction<'_>,
+        model: QuickEntry,
+    ) -> Result<QuickEntry, RepositoryError> {
+        let updated_quick_entry = query_as::<_, QuickEntry>(
+            r#"
+            UPDATE quick_entry
+            SET
+                label = $2,
+                account_id = $3,
+                asset_id = $4,
+                quantity = $5,
+                description = $6,
+                category = $7
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(model.id)
+        .bind(model.label)
+        .bind(model.account_id)
+        .bind(model.asset_id)
+        .bind(model.quantity)
+        .bind(model.description)
+        .bind(model.category)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(updated_quick_entry)
+    }
+}
+
+impl DeleteRepository<QuickEntryId, QuickEntry> for QuickEntryRepository {
+    #[instrument(name = "QuickEntryRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: QuickEntryId,
+    ) -> Result<QuickEntry, RepositoryError> {
+        let deleted_quick_entry = query_as::<_, QuickEntry>(
+            r#"
+            DELETE FROM quick_entry
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_quick_entry)
+    }
+}
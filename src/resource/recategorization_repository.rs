@@ -0,0 +1,120 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        recategorization::{Recategorization, RecategorizationCreate, RecategorizationId},
+        user::UserId,
+    },
+    resource::{InstrumentQuery, RepositoryError},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RecategorizationRepository;
+
+impl RecategorizationRepository {
+    #[instrument(name = "RecategorizationRepository::create", skip_all)]
+    pub async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: RecategorizationCreate,
+    ) -> Result<Recategorization, RepositoryError> {
+        let recategorization = query_as::<_, Recategorization>(
+            r#"
+            INSERT INTO recategorization (user_id, filter, category, matched)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.filter)
+        .bind(create_model.category)
+        .bind(create_model.matched)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(recategorization)
+    }
+
+    /// The recategorization of a user, which is not found for other users.
+    #[instrument(name = "RecategorizationRepository::get", skip_all, fields(id = ?id))]
+    pub async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        id: RecategorizationId,
+    ) -> Result<Recategorization, RepositoryError> {
+        let recategorization = query_as::<_, Recategorization>(
+            r#"
+            SELECT * FROM recategorization
+            WHERE id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(recategorization)
+    }
+
+    /// Records how many transactions a running recategorization changed.
+    #[instrument(
+        name = "RecategorizationRepository::record_progress",
+        skip_all,
+        fields(id = ?id, changed = changed)
+    )]
+    pub async fn record_progress(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: RecategorizationId,
+        changed: i64,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query(
+            r#"
+            UPDATE recategorization
+            SET changed = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(changed)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(())
+    }
+
+    /// Records the outcome of a recategorization, how many transactions it
+    /// changed and why it stopped if it failed.
+    #[instrument(name = "RecategorizationRepository::finish", skip_all, fields(id = ?id))]
+    pub async fn finish(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: RecategorizationId,
+        changed: i64,
+        error: Option<String>,
+    ) -> Result<Recategorization, RepositoryError> {
+        let recategorization = query_as::<_, Recategorization>(
+            r#"
+            UPDATE recategorization
+            SET
+                changed = $2,
+                error = $3,
+                status = CASE WHEN $3::TEXT IS NULL THEN 'complete' ELSE 'failed' END::recategorization_status
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(changed)
+        .bind(error)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(recategorization)
+    }
+}
@@ -0,0 +1,228 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgTransaction, query, query_as, query_scalar};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        account::AccountId,
+        statement::{
+            AccountStatement, AccountStatementCreate, CategorySubtotal, STATEMENT_COLUMNS,
+            StatementBalance, StatementId, StatementRows, StatementStatus,
+        },
+        transaction::Transaction,
+    },
+    resource::{InstrumentQuery, RepositoryError},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct StatementRepository;
+
+impl StatementRepository {
+    /// How many transactions of an account were posted from `start` until
+    /// `end`.
+    #[instrument(
+        name = "StatementRepository::count_transactions",
+        skip_all,
+        fields(account_id = ?account_id)
+    )]
+    pub async fn count_transactions(
+        &self,
+        mut session: PgTransaction<'_>,
+        account_id: AccountId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<i64, RepositoryError> {
+        let count = query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM "transaction"
+            WHERE account_id = $1 AND posted_at >= $2 AND posted_at < $3
+            "#,
+        )
+        .bind(account_id)
+        .bind(start)
+        .bind(end)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(count)
+    }
+
+    /// The balances, transactions and category subtotals of an account from
+    /// `start` until `end`.
+    ///
+    /// They are read in one repeatable read snapshot, so transactions
+    /// written meanwhile are either in all of them or in none.
+    #[instrument(
+        name = "StatementRepository::read_month",
+        skip_all,
+        fields(account_id = ?account_id)
+    )]
+    pub async fn read_month(
+        &self,
+        mut session: PgTransaction<'_>,
+        account_id: AccountId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<StatementRows, RepositoryError> {
+        query(r#"SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY"#)
+            .execute(&mut *session)
+            .in_query_span()
+            .await?;
+        let balances = query_as::<_, StatementBalance>(
+            r#"
+            SELECT
+                asset.id AS asset_id,
+                asset.symbol,
+                asset.decimals,
+                COALESCE(SUM(t.quantity) FILTER (WHERE t.posted_at < $2), 0) AS opening_balance,
+                SUM(t.quantity) AS closing_balance
+            FROM "transaction" AS t
+            JOIN asset ON asset.id = t.asset_id
+            WHERE t.account_id = $1 AND t.posted_at < $3
+            GROUP BY asset.id, asset.symbol, asset.decimals
+            ORDER BY asset.symbol, asset.id
+            "#,
+        )
+        .bind(account_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        let transactions = query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM "transaction"
+            WHERE account_id = $1 AND posted_at >= $2 AND posted_at < $3
+            ORDER BY posted_at, id
+            "#,
+        )
+        .bind(account_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        let subtotals = query_as::<_, CategorySubtotal>(
+            r#"
+            SELECT
+                asset_id,
+                category,
+                COUNT(*) AS transactions,
+                SUM(quantity) AS quantity
+            FROM "transaction"
+            WHERE account_id = $1 AND posted_at >= $2 AND posted_at < $3
+            GROUP BY asset_id, category
+            ORDER BY asset_id, category NULLS LAST
+            "#,
+        )
+        .bind(account_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(StatementRows {
+            balances,
+            transactions,
+            subtotals,
+        })
+    }
+
+    #[instrument(name = "StatementRepository::create", skip_all)]
+    pub async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: AccountStatementCreate,
+    ) -> Result<AccountStatement, RepositoryError> {
+        let statement = query_as::<_, AccountStatement>(&format!(
+            r#"
+            INSERT INTO account_statement (account_id, year, month, format)
+            VALUES ($1, $2, $3, $4)
+            RETURNING {STATEMENT_COLUMNS}
+            "#
+        ))
+        .bind(create_model.account_id)
+        .bind(create_model.year)
+        .bind(create_model.month)
+        .bind(create_model.format)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(statement)
+    }
+
+    /// The statement of an account, which is not found for other accounts.
+    #[instrument(name = "StatementRepository::get", skip_all, fields(id = ?id))]
+    pub async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        account_id: AccountId,
+        id: StatementId,
+    ) -> Result<AccountStatement, RepositoryError> {
+        let statement = query_as::<_, AccountStatement>(&format!(
+            r#"
+            SELECT {STATEMENT_COLUMNS} FROM account_statement
+            WHERE id = $1 AND account_id = $2
+            "#
+        ))
+        .bind(id)
+        .bind(account_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(statement)
+    }
+
+    #[instrument(name = "StatementRepository::get_content", skip_all, fields(id = ?id))]
+    pub async fn get_content(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: StatementId,
+    ) -> Result<Vec<u8>, RepositoryError> {
+        let content = query_scalar::<_, Vec<u8>>(
+            r#"
+            SELECT content FROM account_statement
+            WHERE id = $1 AND content IS NOT NULL
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(content)
+    }
+
+    /// Records the outcome of rendering a statement, its content if it was
+    /// rendered and otherwise why it wasn't.
+    #[instrument(name = "StatementRepository::finish", skip_all, fields(id = ?id))]
+    pub async fn finish(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: StatementId,
+        outcome: Result<Vec<u8>, String>,
+    ) -> Result<AccountStatement, RepositoryError> {
+        let (status, content, error) = match outcome {
+            Ok(content) => (StatementStatus::Complete, Some(content), None),
+            Err(error) => (StatementStatus::Failed, None, Some(error)),
+        };
+        let statement = query_as::<_, AccountStatement>(&format!(
+            r#"
+            UPDATE account_statement
+            SET status = $2, content = $3, error = $4
+            WHERE id = $1
+            RETURNING {STATEMENT_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .bind(status)
+        .bind(content)
+        .bind(error)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(statement)
+    }
+}
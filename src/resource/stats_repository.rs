@@ -0,0 +1,83 @@
+use sqlx::{PgTransaction, query_scalar};
+use tracing::instrument;
+
+use crate::resource::{InstrumentQuery, RepositoryError};
+
+/// The rows counted across every user for the admin stats.
+#[derive(Debug, Clone, Copy)]
+pub enum Count {
+    Users,
+    Accounts,
+    Transactions,
+    /// The sessions that have not been revoked
+    ActiveSessions,
+    /// The receipt extractions waiting to run
+    PendingExtractions,
+}
+
+impl Count {
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Users => r#"SELECT COUNT(*) FROM "user""#,
+            Self::Accounts => r#"SELECT COUNT(*) FROM account"#,
+            Self::Transactions => r#"SELECT COUNT(*) FROM "transaction""#,
+            Self::ActiveSessions => r#"SELECT COUNT(*) FROM user_session WHERE revoked_at IS NULL"#,
+            Self::PendingExtractions => {
+                r#"SELECT COUNT(*) FROM attachment_extraction WHERE status = 'pending'"#
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StatsRepository;
+
+impl StatsRepository {
+    #[instrument(name = "StatsRepository::count", skip_all, fields(count = ?count))]
+    pub async fn count(
+        &self,
+        mut session: PgTransaction<'_>,
+        count: Count,
+    ) -> Result<i64, RepositoryError> {
+        let count = query_scalar::<_, i64>(count.sql())
+            .fetch_one(&mut *session)
+            .in_query_span()
+            .await?;
+        Ok(count)
+    }
+
+    /// Sleeps in Postgres for `seconds`, standing in for a slow query.
+    #[cfg(test)]
+    pub async fn sleep(
+        &self,
+        mut session: PgTransaction<'_>,
+        seconds: f64,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query("SELECT pg_sleep($1)")
+            .bind(seconds)
+            .execute(&mut *session)
+            .in_query_span()
+            .await?;
+        Ok(())
+    }
+
+    /// The planner's estimate of the number of transactions, as of the last
+    /// time the table was analyzed. `None` if it never was.
+    #[instrument(name = "StatsRepository::estimate_transactions", skip_all)]
+    pub async fn estimate_transactions(
+        &self,
+        mut session: PgTransaction<'_>,
+    ) -> Result<Option<i64>, RepositoryError> {
+        let estimate = query_scalar::<_, i64>(
+            r#"
+            SELECT reltuples::BIGINT
+            FROM pg_class
+            WHERE oid = '"transaction"'::regclass
+            "#,
+        )
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok((estimate >= 0).then_some(estimate))
+    }
+}
@@ -0,0 +1,58 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::step_up_grant::{StepUpGrant, StepUpGrantCreate},
+    resource::{CreateRepository, GetRepository, InstrumentQuery, RepositoryError},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct StepUpGrantRepository;
+
+impl GetRepository<String, StepUpGrant> for StepUpGrantRepository {
+    #[instrument(name = "StepUpGrantRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: String,
+    ) -> Result<StepUpGrant, RepositoryError> {
+        let grant = query_as::<_, StepUpGrant>(
+            r#"
+            SELECT * FROM step_up_grant
+            WHERE session = $1 AND expires_at > CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(grant)
+    }
+}
+
+impl CreateRepository<StepUpGrantCreate, StepUpGrant> for StepUpGrantRepository {
+    #[instrument(name = "StepUpGrantRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: StepUpGrantCreate,
+    ) -> Result<StepUpGrant, RepositoryError> {
+        let grant = query_as::<_, StepUpGrant>(
+            r#"
+            INSERT INTO step_up_grant (session, expires_at, user_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (session) DO UPDATE
+            SET expires_at = EXCLUDED.expires_at, user_id = EXCLUDED.user_id
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.session)
+        .bind(create_model.expires_at)
+        .bind(create_model.user_id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(grant)
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgTransaction, QueryBuilder};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        account::{Account, AccountId},
+        sync::SyncTombstone,
+        transaction::{Transaction, TransactionId},
+        user::UserId,
+    },
+    resource::{InstrumentQuery, RepositoryError, record_rows},
+};
+
+/// Reads what changed for a user after a watermark, for delta syncs.
+///
+/// Changes are ordered by `(updated_at, id)`, or by `id` for deletions, so
+/// that the last row of a page is the watermark for the next one.
+#[derive(Debug, Clone, Copy)]
+pub struct SyncRepository;
+
+impl SyncRepository {
+    #[instrument(
+        name = "SyncRepository::get_changed_accounts",
+        skip_all,
+        fields(user_id = ?user_id, limit = limit, rows = tracing::field::Empty)
+    )]
+    pub async fn get_changed_accounts(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
+        after: (DateTime<Utc>, AccountId),
+        limit: i64,
+    ) -> Result<Vec<Account>, RepositoryError> {
+        let mut query = QueryBuilder::new(
+            r#"
+            SELECT * FROM account
+            WHERE user_id =
+            "#,
+        );
+        query.push_bind(user_id);
+        query.push(r#" AND (updated_at, id) > ("#);
+        query.push_bind(after.0);
+        query.push(r#", "#);
+        query.push_bind(after.1);
+        query.push(r#")"#);
+        if let Some(account_ids) = account_ids {
+            query.push(r#" AND id = ANY("#);
+            query.push_bind(account_ids.into_iter().map(|x| x.0).collect::<Vec<_>>());
+            query.push(r#")"#);
+        }
+        query.push(r#" ORDER BY updated_at, id LIMIT "#);
+        query.push_bind(limit);
+
+        let accounts = query
+            .build_query_as::<Account>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+        Ok(record_rows(accounts))
+    }
+
+    #[instrument(
+        name = "SyncRepository::get_changed_transactions",
+        skip_all,
+        fields(user_id = ?user_id, limit = limit, rows = tracing::field::Empty)
+    )]
+    pub async fn get_changed_transactions(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
+        after: (DateTime<Utc>, TransactionId),
+        limit: i64,
+    ) -> Result<Vec<Transaction>, RepositoryError> {
+        let mut query = QueryBuilder::new(
+            r#"
+            SELECT t.* FROM "transaction" t
+            JOIN account a ON a.id = t.account_id
+            WHERE a.user_id =
+            "#,
+        );
+        query.push_bind(user_id);
+        query.push(r#" AND (t.updated_at, t.id) > ("#);
+        query.push_bind(after.0);
+        query.push(r#", "#);
+        query.push_bind(after.1.0);
+        query.push(r#")"#);
+        if let Some(account_ids) = account_ids {
+            query.push(r#" AND t.account_id = ANY("#);
+            query.push_bind(account_ids.into_iter().map(|x| x.0).collect::<Vec<_>>());
+            query.push(r#")"#);
+        }
+        query.push(r#" ORDER BY t.updated_at, t.id LIMIT "#);
+        query.push_bind(limit);
+
+        let transactions = query
+            .build_query_as::<Transaction>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+        Ok(record_rows(transactions))
+    }
+
+    #[instrument(
+        name = "SyncRepository::get_tombstones",
+        skip_all,
+        fields(user_id = ?user_id, limit = limit, rows = tracing::field::Empty)
+    )]
+    pub async fn get_tombstones(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<SyncTombstone>, RepositoryError> {
+        let mut query = QueryBuilder::new(
+            r#"
+            SELECT * FROM sync_tombstone
+            WHERE user_id =
+            "#,
+        );
+        query.push_bind(user_id);
+        query.push(r#" AND id > "#);
+        query.push_bind(after);
+        if let Some(account_ids) = account_ids {
+            query.push(r#" AND account_id = ANY("#);
+            query.push_bind(account_ids.into_iter().map(|x| x.0).collect::<Vec<_>>());
+            query.push(r#")"#);
+        }
+        query.push(r#" ORDER BY id LIMIT "#);
+        query.push_bind(limit);
+
+        let tombstones = query
+            .build_query_as::<SyncTombstone>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+        Ok(record_rows(tombstones))
+    }
+}
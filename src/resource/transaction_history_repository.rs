@@ -0,0 +1,73 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        transaction_history::{TransactionHistory, TransactionHistoryFilter, TransactionHistoryId},
+    },
+    resource::{
+        GetListRepository, GetRepository, InstrumentQuery, RepositoryError, list_query, record_rows,
+    },
+};
+
+/// Reads the history the `record_transaction_history` trigger writes, newest
+/// first. It is never written to directly.
+#[derive(Debug, Clone, Copy)]
+pub struct TransactionHistoryRepository;
+
+impl GetRepository<TransactionHistoryId, TransactionHistory> for TransactionHistoryRepository {
+    #[instrument(name = "TransactionHistoryRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: TransactionHistoryId,
+    ) -> Result<TransactionHistory, RepositoryError> {
+        let transaction_history = query_as::<_, TransactionHistory>(
+            r#"
+            SELECT * FROM transaction_history
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(transaction_history)
+    }
+}
+
+impl GetListRepository<TransactionHistory, TransactionHistoryFilter>
+    for TransactionHistoryRepository
+{
+    #[instrument(
+        name = "TransactionHistoryRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: TransactionHistoryFilter,
+    ) -> Result<Vec<TransactionHistory>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM transaction_history
+            "#,
+            filter.predicate(),
+            Some(r#"id DESC"#),
+            offset,
+            limit,
+        );
+
+        let transaction_history = query
+            .build_query_as::<TransactionHistory>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(transaction_history))
+    }
+}
@@ -1,21 +1,49 @@
-use sqlx::{PgTransaction, QueryBuilder, query_as};
+use chrono::{DateTime, Utc};
+use sqlx::{PgTransaction, QueryBuilder, query_as, query_scalar};
+use tracing::instrument;
 
 use crate::{
     model::{
-        Filter,
-        transaction::{Transaction, TransactionCreate, TransactionFilter, TransactionId},
+        Condition, Filter, Predicate,
+        account::AccountId,
+        categorization_rule::CategorizationRuleId,
+        journal_entry::{JournalEntryId, TrialBalanceLine},
+        transaction::{
+            Transaction, TransactionConversion, TransactionCreate, TransactionFilter, TransactionId,
+        },
         user::UserId,
     },
     resource::{
-        CreateRepository, DeleteRepository, GetListRepository, GetRepository, MAX_LIMIT,
-        RepositoryError, UpdateRepository,
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
     },
+    rounding::RoundingPolicy,
 };
 
 #[derive(Debug, Clone, Copy)]
 pub struct TransactionRepository;
 
+/// The conditions of `filter` on the transactions of a user, limited to
+/// `account_ids` if given.
+fn user_predicate(
+    filter: TransactionFilter,
+    user_id: UserId,
+    account_ids: Option<Vec<AccountId>>,
+) -> Predicate {
+    filter
+        .predicate()
+        .and(Condition::in_subquery(
+            "account_id",
+            r#"SELECT id FROM account WHERE user_id = "#,
+            user_id,
+        ))
+        .and_some(account_ids, |account_ids| {
+            Condition::any("account_id", account_ids)
+        })
+}
+
 impl GetRepository<TransactionId, Transaction> for TransactionRepository {
+    #[instrument(name = "TransactionRepository::get", skip_all, fields(id = ?id))]
     async fn get(
         &self,
         mut session: PgTransaction<'_>,
@@ -24,18 +52,28 @@ impl GetRepository<TransactionId, Transaction> for TransactionRepository {
         let transaction = query_as!(
             Transaction,
             r#"
-                SELECT * from "transaction"
-                WHERE id = $1
+            SELECT id, created_at, updated_at, posted_at, account_id, asset_id, description, quantity,
+            notes, external_id, category,
+            applied_rule_id AS "applied_rule_id: CategorizationRuleId",
+            journal_entry_id AS "journal_entry_id: JournalEntryId"
+            FROM "transaction"
+            WHERE id = $1
             "#,
             id.0
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         Ok(transaction)
     }
 }
 
 impl GetListRepository<Transaction, TransactionFilter> for TransactionRepository {
+    #[instrument(
+        name = "TransactionRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
     async fn get_list(
         &self,
         mut session: PgTransaction<'_>,
@@ -43,29 +81,27 @@ impl GetListRepository<Transaction, TransactionFilter> for TransactionRepository
         limit: Option<i64>,
         filter: TransactionFilter,
     ) -> Result<Vec<Transaction>, RepositoryError> {
-        let offset = offset.max(0);
-        let limit = limit.map(|x| x.clamp(1, MAX_LIMIT)).unwrap_or(MAX_LIMIT);
-        let mut query = QueryBuilder::new(
+        let mut query = list_query(
             r#"
             SELECT * FROM "transaction"
             "#,
+            filter.predicate(),
+            None,
+            offset,
+            limit,
         );
 
-        filter.push(&mut query);
-        query.push(r#" OFFSET "#);
-        query.push_bind(offset);
-        query.push(r#" LIMIT "#);
-        query.push_bind(limit);
-
         let transactions = query
             .build_query_as::<Transaction>()
             .fetch_all(&mut *session)
+            .in_query_span()
             .await?;
-        Ok(transactions)
+        Ok(record_rows(transactions))
     }
 }
 
 impl CreateRepository<TransactionCreate, Transaction> for TransactionRepository {
+    #[instrument(name = "TransactionRepository::create", skip_all)]
     async fn create(
         &self,
         mut session: PgTransaction<'_>,
@@ -74,17 +110,26 @@ impl CreateRepository<TransactionCreate, Transaction> for TransactionRepository
         let new_transaction = query_as!(
             Transaction,
             r#"
-            INSERT INTO "transaction" (account_id, asset_id, description, posted_at, quantity)
-            VALUES ($1, $2, $3, $4, $5)
-            RETURNING *
+            INSERT INTO "transaction" (account_id, asset_id, description, posted_at, quantity, notes, external_id, category, applied_rule_id, journal_entry_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, created_at, updated_at, posted_at, account_id, asset_id, description, quantity,
+            notes, external_id, category,
+            applied_rule_id AS "applied_rule_id: CategorizationRuleId",
+            journal_entry_id AS "journal_entry_id: JournalEntryId"
             "#,
             create_model.account_id.0,
             create_model.asset_id.0,
             create_model.description,
             create_model.posted_at,
-            create_model.quantity
+            create_model.quantity,
+            create_model.notes,
+            create_model.external_id,
+            create_model.category,
+            create_model.applied_rule_id as _,
+            create_model.journal_entry_id as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(new_transaction)
@@ -92,6 +137,7 @@ impl CreateRepository<TransactionCreate, Transaction> for TransactionRepository
 }
 
 impl UpdateRepository<Transaction> for TransactionRepository {
+    #[instrument(name = "TransactionRepository::update", skip_all, fields(id = ?model.id))]
     async fn update(
         &self,
         mut session: PgTransaction<'_>,
@@ -101,18 +147,25 @@ impl UpdateRepository<Transaction> for TransactionRepository {
             Transaction,
             r#"
             UPDATE "transaction"
-            SET account_id = $2, asset_id = $3, description = $4, posted_at = $5, quantity = $6
+            SET account_id = $2, asset_id = $3, description = $4, posted_at = $5, quantity = $6, notes = $7, category = $8, applied_rule_id = $9
             WHERE id = $1
-            RETURNING *
-        "#,
+            RETURNING id, created_at, updated_at, posted_at, account_id, asset_id, description, quantity,
+            notes, external_id, category,
+            applied_rule_id AS "applied_rule_id: CategorizationRuleId",
+            journal_entry_id AS "journal_entry_id: JournalEntryId"
+            "#,
             model.id.0,
             model.account_id.0,
             model.asset_id.0,
             model.description,
             model.posted_at,
             model.quantity,
+            model.notes,
+            model.category,
+            model.applied_rule_id as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(updated_transaction)
@@ -120,6 +173,7 @@ impl UpdateRepository<Transaction> for TransactionRepository {
 }
 
 impl DeleteRepository<TransactionId, Transaction> for TransactionRepository {
+    #[instrument(name = "TransactionRepository::delete", skip_all, fields(id = ?id))]
     async fn delete(
         &self,
         mut session: PgTransaction<'_>,
@@ -128,13 +182,17 @@ impl DeleteRepository<TransactionId, Transaction> for TransactionRepository {
         let deleted_transaction = query_as!(
             Transaction,
             r#"
-                DELETE FROM "transaction"
-                WHERE id = $1
-                RETURNING *
+            DELETE FROM "transaction"
+            WHERE id = $1
+            RETURNING id, created_at, updated_at, posted_at, account_id, asset_id, description, quantity,
+            notes, external_id, category,
+            applied_rule_id AS "applied_rule_id: CategorizationRuleId",
+            journal_entry_id AS "journal_entry_id: JournalEntryId"
             "#,
             id.0
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(deleted_transaction)
@@ -142,212 +200,575 @@ impl DeleteRepository<TransactionId, Transaction> for TransactionRepository {
 }
 
 impl TransactionRepository {
+    #[instrument(
+        name = "TransactionRepository::get_with_user_id",
+        skip_all,
+        fields(transaction_id = ?transaction_id, user_id = ?user_id)
+    )]
     pub async fn get_with_user_id(
         &self,
         mut session: PgTransaction<'_>,
         transaction_id: TransactionId,
         user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
     ) -> Result<Transaction, RepositoryError> {
         let transaction = query_as!(
             Transaction,
             r#"
-            SELECT t.*
+            SELECT t.id, t.created_at, t.updated_at, t.posted_at, t.account_id, t.asset_id, t.description,
+            t.quantity, t.notes, t.external_id, t.category,
+            t.applied_rule_id AS "applied_rule_id: CategorizationRuleId",
+            t.journal_entry_id AS "journal_entry_id: JournalEntryId"
             FROM "transaction" t
             JOIN account a ON t.account_id = a.id
             WHERE t.id = $1
             AND a.user_id = $2
-        "#,
+            AND ($3::UUID[] IS NULL OR a.id = ANY($3))
+            "#,
             transaction_id.0,
-            user_id.0
+            user_id.0,
+            account_ids as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         Ok(transaction)
     }
 
+    #[instrument(
+        name = "TransactionRepository::get_list_with_user_id",
+        skip_all,
+        fields(offset = offset, limit = ?limit, user_id = ?user_id, rows = tracing::field::Empty)
+    )]
     pub async fn get_list_with_user_id(
         &self,
         mut session: PgTransaction<'_>,
         offset: i64,
         limit: Option<i64>,
         user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
         filter: TransactionFilter,
     ) -> Result<Vec<Transaction>, RepositoryError> {
-        let offset = offset.max(0);
-        let limit = limit.map(|x| x.clamp(1, MAX_LIMIT)).unwrap_or(MAX_LIMIT);
-        let mut query = QueryBuilder::new(
-            r#"
-            SELECT t.*
-            FROM "transaction" t
-            WHERE t.account_id IN (
-                SELECT id
-                FROM account
-                WHERE user_id ="#,
+        let mut query = list_query(
+            r#"SELECT * FROM "transaction""#,
+            user_predicate(filter, user_id, account_ids),
+            None,
+            offset,
+            limit,
         );
-        query.push_bind(user_id);
-        query.push(r#")"#);
-
-        if let Some(description) = filter.description {
-            query.push(r#" AND "#);
-            query.push(r#"t.description ILIKE "#);
-            query.push_bind(format!("%{description}%"));
-        }
-
-        if let Some(asset_id) = filter.asset_id {
-            query.push(r#" AND "#);
-            query.push(r#"t.asset_id = "#);
-            query.push_bind(asset_id);
-        }
-
-        if let Some(account_id) = filter.account_id {
-            query.push(r#" AND "#);
-            query.push(r#"t.account_id = "#);
-            query.push_bind(account_id);
-        }
-
-        if let Some(quantity) = filter.quantity {
-            query.push(r#" AND "#);
-            query.push(r#"t.quantity = "#);
-            query.push_bind(quantity);
-        }
-
-        if let Some(max_quantity) = filter.max_quantity {
-            query.push(r#" AND "#);
-            query.push(r#"t.quantity <= "#);
-            query.push_bind(max_quantity);
-        }
-
-        if let Some(min_quantity) = filter.min_quantity {
-            query.push(r#" AND "#);
-            query.push(r#"t.quantity >= "#);
-            query.push_bind(min_quantity);
-        }
-
-        if let Some(posted_at) = filter.posted_at {
-            query.push(r#" AND "#);
-            query.push(r#"t.posted_at = "#);
-            query.push_bind(posted_at);
-        }
-
-        if let Some(posted_before) = filter.posted_before {
-            query.push(r#" AND "#);
-            query.push(r#"t.posted_at < "#);
-            query.push_bind(posted_before);
-        }
-
-        if let Some(posted_after) = filter.posted_after {
-            query.push(r#" AND "#);
-            query.push(r#"t.posted_at > "#);
-            query.push_bind(posted_after);
-        }
-
-        query.push(r#" OFFSET "#);
-        query.push_bind(offset);
-        query.push(r#" LIMIT "#);
-        query.push_bind(limit);
 
         let transactions = query
             .build_query_as::<Transaction>()
             .fetch_all(&mut *session)
+            .in_query_span()
             .await?;
-        Ok(transactions)
+        Ok(record_rows(transactions))
+    }
+
+    /// The latest `limit` transactions of a user by when they were posted,
+    /// limited to `account_ids` if given.
+    #[instrument(
+        name = "TransactionRepository::get_recent_with_user_id",
+        skip_all,
+        fields(limit = limit, user_id = ?user_id, rows = tracing::field::Empty)
+    )]
+    pub async fn get_recent_with_user_id(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
+        limit: i64,
+    ) -> Result<Vec<Transaction>, RepositoryError> {
+        let transactions = query_as::<_, Transaction>(
+            r#"
+            SELECT t.* FROM "transaction" t
+            JOIN account ON account.id = t.account_id
+            WHERE account.user_id = $1
+            AND ($3::UUID[] IS NULL OR account.id = ANY($3))
+            ORDER BY t.posted_at DESC, t.id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .bind(account_ids)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(record_rows(transactions))
     }
 
+    #[instrument(
+        name = "TransactionRepository::create_with_user_id",
+        skip_all,
+        fields(user_id = ?user_id)
+    )]
     pub async fn create_with_user_id(
         &self,
         mut session: PgTransaction<'_>,
         create_model: TransactionCreate,
         user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
     ) -> Result<Transaction, RepositoryError> {
+        // The account is key share locked, so the insert waits out an
+        // account being deleted, which then isn't found, rather than
+        // failing on its foreign key.
         let transaction = query_as!(
             Transaction,
             r#"
-            INSERT INTO "transaction" (account_id, asset_id, description, posted_at, quantity)
-            SELECT $1, $2, $3, $4, $5
+            INSERT INTO "transaction" (account_id, asset_id, description, posted_at, quantity, notes, external_id, category, applied_rule_id, journal_entry_id)
+            SELECT $1, $2, $3, $4, $5, $7, $9, $10, $11, $12
             WHERE EXISTS (
                 SELECT 1
                 FROM account
                 WHERE id = $1
                 AND user_id = $6
+                AND ($8::UUID[] IS NULL OR id = ANY($8))
+                FOR KEY SHARE
             )
-            RETURNING *
-        "#,
+            RETURNING id, created_at, updated_at, posted_at, account_id, asset_id, description, quantity,
+            notes, external_id, category,
+            applied_rule_id AS "applied_rule_id: CategorizationRuleId",
+            journal_entry_id AS "journal_entry_id: JournalEntryId"
+            "#,
             create_model.account_id.0,
             create_model.asset_id.0,
             create_model.description,
             create_model.posted_at,
             create_model.quantity,
-            user_id.0
+            user_id.0,
+            create_model.notes,
+            account_ids as _,
+            create_model.external_id,
+            create_model.category,
+            create_model.applied_rule_id as _,
+            create_model.journal_entry_id as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
 
         Ok(transaction)
     }
 
+    /// Returns which of `external_ids` the account already has a
+    /// transaction for.
+    #[instrument(
+        name = "TransactionRepository::get_external_ids",
+        skip_all,
+        fields(account_id = ?account_id)
+    )]
+    pub async fn get_external_ids(
+        &self,
+        mut session: PgTransaction<'_>,
+        account_id: AccountId,
+        external_ids: Vec<String>,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let external_ids = query_scalar::<_, String>(
+            r#"
+            SELECT external_id FROM "transaction"
+            WHERE account_id = $1
+            AND external_id = ANY($2)
+            "#,
+        )
+        .bind(account_id.0)
+        .bind(external_ids)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(external_ids)
+    }
+
+    /// The transactions of the user without a category after `after`, in
+    /// the order of their ids.
+    #[instrument(
+        name = "TransactionRepository::get_uncategorized",
+        skip_all,
+        fields(user_id = ?user_id, after = ?after, rows = tracing::field::Empty)
+    )]
+    pub async fn get_uncategorized(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        after: TransactionId,
+        limit: i64,
+    ) -> Result<Vec<Transaction>, RepositoryError> {
+        let transactions = query_as::<_, Transaction>(
+            r#"
+            SELECT t.*
+            FROM "transaction" t
+            JOIN account a ON t.account_id = a.id
+            WHERE a.user_id = $1
+            AND t.category IS NULL
+            AND t.id > $2
+            ORDER BY t.id
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id.0)
+        .bind(after.0)
+        .bind(limit.max(1))
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(record_rows(transactions))
+    }
+
+    /// The latest `limit` transactions of a user without a category by when
+    /// they were posted, with how many there are in all.
+    #[instrument(
+        name = "TransactionRepository::get_recent_uncategorized_with_user_id",
+        skip_all,
+        fields(limit = limit, user_id = ?user_id, rows = tracing::field::Empty)
+    )]
+    pub async fn get_recent_uncategorized_with_user_id(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
+        limit: i64,
+    ) -> Result<(Vec<Transaction>, i64), RepositoryError> {
+        let transactions = query_as::<_, Transaction>(
+            r#"
+            SELECT t.* FROM "transaction" t
+            JOIN account a ON a.id = t.account_id
+            WHERE a.user_id = $1
+            AND ($2::UUID[] IS NULL OR a.id = ANY($2))
+            AND t.category IS NULL
+            ORDER BY t.posted_at DESC, t.id DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id.0)
+        .bind(account_ids.clone())
+        .bind(limit.max(1))
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        let count = query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM "transaction" t
+            JOIN account a ON a.id = t.account_id
+            WHERE a.user_id = $1
+            AND ($2::UUID[] IS NULL OR a.id = ANY($2))
+            AND t.category IS NULL
+            "#,
+        )
+        .bind(user_id.0)
+        .bind(account_ids)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok((record_rows(transactions), count))
+    }
+
+    /// Sets the categories the rules found for transactions, skipping those
+    /// that were categorized in the meantime. Returns how many changed.
+    #[instrument(name = "TransactionRepository::categorize", skip_all)]
+    pub async fn categorize(
+        &self,
+        mut session: PgTransaction<'_>,
+        categories: Vec<(TransactionId, String, CategorizationRuleId)>,
+    ) -> Result<u64, RepositoryError> {
+        let mut ids = Vec::with_capacity(categories.len());
+        let mut names = Vec::with_capacity(categories.len());
+        let mut rule_ids = Vec::with_capacity(categories.len());
+        for (id, category, rule_id) in categories {
+            ids.push(id.0);
+            names.push(category);
+            rule_ids.push(rule_id.0);
+        }
+        let result = sqlx::query(
+            r#"
+            UPDATE "transaction" t
+            SET category = c.category, applied_rule_id = c.applied_rule_id
+            FROM UNNEST($1::BIGINT[], $2::TEXT[], $3::UUID[]) AS c(id, category, applied_rule_id)
+            WHERE t.id = c.id
+            AND t.category IS NULL
+            "#,
+        )
+        .bind(ids)
+        .bind(names)
+        .bind(rule_ids)
+        .execute(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    /// How many transactions of a user `filter` matches.
+    #[instrument(
+        name = "TransactionRepository::count_with_user_id",
+        skip_all,
+        fields(user_id = ?user_id)
+    )]
+    pub async fn count_with_user_id(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
+        filter: TransactionFilter,
+    ) -> Result<i64, RepositoryError> {
+        let mut query = QueryBuilder::new(r#"SELECT COUNT(*) FROM "transaction""#);
+        user_predicate(filter, user_id, account_ids).push(&mut query);
+        let count = query
+            .build_query_scalar::<i64>()
+            .fetch_one(&mut *session)
+            .in_query_span()
+            .await?;
+        Ok(count)
+    }
+
+    /// Sets the category of the first `limit` transactions of a user that
+    /// `filter` matches and that have another category, in the order of
+    /// their ids. The category is set by hand, so the rule that set the one
+    /// before is cleared. Returns how many transactions changed, which is
+    /// less than `limit` once none are left.
+    #[instrument(
+        name = "TransactionRepository::recategorize_with_user_id",
+        skip_all,
+        fields(user_id = ?user_id, limit = limit)
+    )]
+    pub async fn recategorize_with_user_id(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
+        filter: TransactionFilter,
+        category: String,
+        limit: i64,
+    ) -> Result<u64, RepositoryError> {
+        let mut query =
+            QueryBuilder::new(r#"UPDATE "transaction" SET applied_rule_id = NULL, category = "#);
+        query.push_bind(category.clone());
+        query.push(r#" WHERE id IN (SELECT id FROM "transaction""#);
+        user_predicate(filter, user_id, account_ids).push(&mut query);
+        query.push(r#" AND category IS DISTINCT FROM "#);
+        query.push_bind(category);
+        query.push(r#" ORDER BY id LIMIT "#);
+        query.push_bind(limit.max(1));
+        query.push(r#")"#);
+        let result = query.build().execute(&mut *session).in_query_span().await?;
+        session.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    #[instrument(
+        name = "TransactionRepository::update_with_user_id",
+        skip_all,
+        fields(id = ?model.id, user_id = ?user_id)
+    )]
     pub async fn update_with_user_id(
         &self,
         mut session: PgTransaction<'_>,
         model: Transaction,
         user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
     ) -> Result<Transaction, RepositoryError> {
         let transaction = query_as!(
             Transaction,
             r#"
-                UPDATE "transaction"
-                SET
-                    asset_id = $1,
-                    description = $2,
-                    posted_at = $3,
-                    quantity = $4
-                WHERE
-                    id = $5
-                    AND account_id IN (
-                        SELECT id
-                        FROM account
-                        WHERE
-                            user_id = $6
-                    )
-                RETURNING *
-        "#,
+            UPDATE "transaction"
+            SET
+                asset_id = $1,
+                description = $2,
+                posted_at = $3,
+                quantity = $4,
+                notes = $7,
+                category = $9,
+                applied_rule_id = $10
+            WHERE
+                id = $5
+                AND account_id IN (
+                    SELECT id
+                    FROM account
+                    WHERE
+                        user_id = $6
+                        AND ($8::UUID[] IS NULL OR id = ANY($8))
+                )
+            RETURNING id, created_at, updated_at, posted_at, account_id, asset_id, description, quantity,
+            notes, external_id, category,
+            applied_rule_id AS "applied_rule_id: CategorizationRuleId",
+            journal_entry_id AS "journal_entry_id: JournalEntryId"
+            "#,
             model.asset_id.0,
             model.description,
             model.posted_at,
             model.quantity,
             model.id.0,
-            user_id.0
+            user_id.0,
+            model.notes,
+            account_ids as _,
+            model.category,
+            model.applied_rule_id as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(transaction)
     }
 
+    #[instrument(
+        name = "TransactionRepository::delete_with_user_id",
+        skip_all,
+        fields(id = ?id, user_id = ?user_id)
+    )]
     pub async fn delete_with_user_id(
         &self,
         mut session: PgTransaction<'_>,
         id: TransactionId,
         user_id: UserId,
+        account_ids: Option<Vec<AccountId>>,
     ) -> Result<Transaction, RepositoryError> {
         let deleted_transaction = query_as!(
             Transaction,
             r#"
-                DELETE FROM "transaction"
-                WHERE id = $1
-                AND account_id IN (
-                    SELECT id
-                    FROM account
-                    WHERE user_id = $2
-                )
-                RETURNING *
+            DELETE FROM "transaction"
+            WHERE id = $1
+            AND account_id IN (
+                SELECT id
+                FROM account
+                WHERE user_id = $2
+                AND ($3::UUID[] IS NULL OR id = ANY($3))
+            )
+            RETURNING id, created_at, updated_at, posted_at, account_id, asset_id, description, quantity,
+            notes, external_id, category,
+            applied_rule_id AS "applied_rule_id: CategorizationRuleId",
+            journal_entry_id AS "journal_entry_id: JournalEntryId"
             "#,
             id.0,
             user_id.0,
+            account_ids as _,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(deleted_transaction)
     }
+
+    /// The legs of a journal entry, limited to the accounts of `user_id`
+    /// when given.
+    #[instrument(
+        name = "TransactionRepository::get_by_journal_entry",
+        skip_all,
+        fields(journal_entry_id = ?journal_entry_id, user_id = ?user_id, rows = tracing::field::Empty)
+    )]
+    pub async fn get_by_journal_entry(
+        &self,
+        mut session: PgTransaction<'_>,
+        journal_entry_id: JournalEntryId,
+        user_id: Option<UserId>,
+        account_ids: Option<Vec<AccountId>>,
+    ) -> Result<Vec<Transaction>, RepositoryError> {
+        let transactions = query_as::<_, Transaction>(
+            r#"
+            SELECT t.*
+            FROM "transaction" t
+            JOIN account a ON t.account_id = a.id
+            WHERE t.journal_entry_id = $1
+            AND ($2::UUID IS NULL OR a.user_id = $2)
+            AND ($3::UUID[] IS NULL OR a.id = ANY($3))
+            ORDER BY t.id
+            "#,
+        )
+        .bind(journal_entry_id)
+        .bind(user_id)
+        .bind(account_ids)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(record_rows(transactions))
+    }
+
+    /// The balance of each account in each asset from the transactions
+    /// posted up to `as_of`, and from `from` when given, leaving out those
+    /// that come to zero. Limited to the accounts of `user_id` when given.
+    #[instrument(
+        name = "TransactionRepository::trial_balance",
+        skip_all,
+        fields(user_id = ?user_id, from = ?from, as_of = ?as_of, rows = tracing::field::Empty)
+    )]
+    pub async fn trial_balance(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: Option<UserId>,
+        account_ids: Option<Vec<AccountId>>,
+        from: Option<DateTime<Utc>>,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<TrialBalanceLine>, RepositoryError> {
+        let lines = query_as::<_, TrialBalanceLine>(
+            r#"
+            SELECT t.account_id, t.asset_id, SUM(t.quantity) AS balance
+            FROM "transaction" t
+            JOIN account a ON t.account_id = a.id
+            WHERE t.posted_at <= $1
+            AND ($2::UUID IS NULL OR a.user_id = $2)
+            AND ($3::UUID[] IS NULL OR a.id = ANY($3))
+            AND ($4::TIMESTAMPTZ IS NULL OR t.posted_at >= $4)
+            GROUP BY t.account_id, t.asset_id
+            HAVING SUM(t.quantity) <> 0
+            ORDER BY t.asset_id, t.account_id
+            "#,
+        )
+        .bind(as_of)
+        .bind(user_id)
+        .bind(account_ids)
+        .bind(from)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(record_rows(lines))
+    }
+
+    /// Converts the given transactions into the asset with `quote_symbol`.
+    ///
+    /// Transactions already in the quote asset convert at a rate of 1, and
+    /// those without a price at or before `posted_at` convert to nulls.
+    /// Converted quantities are rounded to the decimals of the quote asset
+    /// by `rounding`.
+    #[instrument(name = "TransactionRepository::get_conversions", skip_all)]
+    pub async fn get_conversions(
+        &self,
+        mut session: PgTransaction<'_>,
+        transaction_ids: Vec<TransactionId>,
+        quote_symbol: String,
+        rounding: RoundingPolicy,
+    ) -> Result<Vec<TransactionConversion>, RepositoryError> {
+        let transaction_ids = transaction_ids.into_iter().map(|x| x.0).collect::<Vec<_>>();
+        let conversions = sqlx::query_as::<_, TransactionConversion>(
+            r#"
+            SELECT
+                t.id AS transaction_id,
+                round_amount(t.quantity * r.rate, q.decimals, $3) AS converted_quantity,
+                r.rate::TEXT AS rate_used
+            FROM "transaction" t
+            JOIN asset a ON a.id = t.asset_id
+            LEFT JOIN asset q ON q.symbol = $2
+            LEFT JOIN LATERAL (
+                SELECT ap.rate
+                FROM asset_price ap
+                WHERE ap.asset_id = t.asset_id
+                AND ap.quote_asset_id = q.id
+                AND ap.priced_at <= t.posted_at
+                ORDER BY ap.priced_at DESC
+                LIMIT 1
+            ) p ON TRUE
+            CROSS JOIN LATERAL (
+                SELECT CASE WHEN a.symbol = $2 THEN 1::NUMERIC ELSE p.rate END AS rate
+            ) r
+            WHERE t.id = ANY($1)
+            "#,
+        )
+        .bind(transaction_ids)
+        .bind(quote_symbol)
+        .bind(rounding.name())
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(conversions)
+    }
 }
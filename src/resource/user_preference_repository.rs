@@ -0,0 +1,74 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        user::UserId,
+        user_preference::{UserPreference, UserPreferenceUpdate},
+    },
+    resource::{InstrumentQuery, RepositoryError},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct UserPreferenceRepository;
+
+impl UserPreferenceRepository {
+    /// Gets the preferences of a user, if they ever chose any.
+    #[instrument(name = "UserPreferenceRepository::get_by_user_id", skip_all, fields(user_id = ?user_id))]
+    pub async fn get_by_user_id(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+    ) -> Result<Option<UserPreference>, RepositoryError> {
+        let user_preference = query_as::<_, UserPreference>(
+            r#"
+            SELECT * FROM user_preference
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(user_preference)
+    }
+
+    /// Sets the preferences of a user the update has, creating them if the
+    /// user had none.
+    #[instrument(name = "UserPreferenceRepository::upsert", skip_all, fields(user_id = ?user_id))]
+    pub async fn upsert(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        update: UserPreferenceUpdate,
+    ) -> Result<UserPreference, RepositoryError> {
+        let user_preference = query_as::<_, UserPreference>(
+            r#"
+            INSERT INTO user_preference (
+                user_id, default_asset_id, locale, double_entry, idle_lock_minutes,
+                idle_lock_while_hidden
+            )
+            VALUES ($1, $2, $3, COALESCE($4, FALSE), $5, COALESCE($6, TRUE))
+            ON CONFLICT (user_id) DO UPDATE
+            SET
+                default_asset_id = COALESCE(EXCLUDED.default_asset_id, user_preference.default_asset_id),
+                locale = COALESCE(EXCLUDED.locale, user_preference.locale),
+                double_entry = COALESCE($4, user_preference.double_entry),
+                idle_lock_minutes = COALESCE($5, user_preference.idle_lock_minutes),
+                idle_lock_while_hidden = COALESCE($6, user_preference.idle_lock_while_hidden)
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(update.default_asset_id)
+        .bind(update.locale)
+        .bind(update.double_entry)
+        .bind(update.idle_lock_minutes)
+        .bind(update.idle_lock_while_hidden)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(user_preference)
+    }
+}
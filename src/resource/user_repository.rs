@@ -1,16 +1,18 @@
-use sqlx::{PgTransaction, QueryBuilder, query_as};
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
 
 use crate::model::Filter;
 use crate::model::user::{User, UserCreate, UserFilter, UserId};
 use crate::resource::{
-    CreateRepository, DeleteRepository, GetListRepository, GetRepository, MAX_LIMIT,
-    RepositoryError, UpdateRepository,
+    CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+    RepositoryError, UpdateRepository, list_query, record_rows,
 };
 
 #[derive(Debug, Clone, Copy)]
 pub struct UserRepository;
 
 impl UserRepository {
+    #[instrument(name = "UserRepository::get_by_iss_and_sub", skip_all)]
     pub async fn get_by_iss_and_sub(
         &self,
         mut session: PgTransaction<'_>,
@@ -27,12 +29,14 @@ impl UserRepository {
             sub
         )
         .fetch_optional(&mut *session)
+        .in_query_span()
         .await?;
         Ok(user)
     }
 }
 
 impl GetRepository<UserId, User> for UserRepository {
+    #[instrument(name = "UserRepository::get", skip_all, fields(id = ?id))]
     async fn get(
         &self,
         mut session: PgTransaction<'_>,
@@ -47,12 +51,18 @@ impl GetRepository<UserId, User> for UserRepository {
             id.0,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         Ok(user)
     }
 }
 
 impl GetListRepository<User, UserFilter> for UserRepository {
+    #[instrument(
+        name = "UserRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
     async fn get_list(
         &self,
         mut session: PgTransaction<'_>,
@@ -60,31 +70,29 @@ impl GetListRepository<User, UserFilter> for UserRepository {
         limit: Option<i64>,
         filter: UserFilter,
     ) -> Result<Vec<User>, RepositoryError> {
-        let offset = offset.max(0);
-        let limit = limit.map(|x| x.clamp(1, MAX_LIMIT)).unwrap_or(MAX_LIMIT);
-        let mut query = QueryBuilder::new(
+        let order_by = filter.sort.order_by();
+        let mut query = list_query(
             r#"
             SELECT * FROM "user"
             "#,
+            filter.predicate(),
+            Some(order_by),
+            offset,
+            limit,
         );
 
-        filter.push(&mut query);
-
-        query.push(r#" OFFSET "#);
-        query.push_bind(offset);
-        query.push(r#" LIMIT "#);
-        query.push_bind(limit);
-
         let users = query
             .build_query_as::<User>()
             .fetch_all(&mut *session)
+            .in_query_span()
             .await?;
 
-        Ok(users)
+        Ok(record_rows(users))
     }
 }
 
 impl CreateRepository<UserCreate, User> for UserRepository {
+    #[instrument(name = "UserRepository::create", skip_all)]
     async fn create(
         &self,
         mut session: PgTransaction<'_>,
@@ -93,16 +101,18 @@ impl CreateRepository<UserCreate, User> for UserRepository {
         let new_user = query_as!(
             User,
             r#"
-                INSERT INTO "user" (name, email, iss, sub) 
-                VALUES ($1, $2, $3, $4) 
+                INSERT INTO "user" (name, email, iss, sub, email_verified_at_registration)
+                VALUES ($1, $2, $3, $4, $5)
                 RETURNING *
             "#,
             create_model.name,
             create_model.email,
             create_model.iss,
             create_model.sub,
+            create_model.email_verified,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(new_user)
@@ -110,6 +120,7 @@ impl CreateRepository<UserCreate, User> for UserRepository {
 }
 
 impl UpdateRepository<User> for UserRepository {
+    #[instrument(name = "UserRepository::update", skip_all, fields(id = ?model.id))]
     async fn update(
         &self,
         mut session: PgTransaction<'_>,
@@ -128,6 +139,7 @@ impl UpdateRepository<User> for UserRepository {
             model.email,
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(updated_user)
@@ -135,6 +147,7 @@ impl UpdateRepository<User> for UserRepository {
 }
 
 impl DeleteRepository<UserId, User> for UserRepository {
+    #[instrument(name = "UserRepository::delete", skip_all, fields(id = ?id))]
     async fn delete(
         &self,
         mut session: PgTransaction<'_>,
@@ -150,6 +163,7 @@ impl DeleteRepository<UserId, User> for UserRepository {
             id.0
         )
         .fetch_one(&mut *session)
+        .in_query_span()
         .await?;
         session.commit().await?;
         Ok(deleted_user)
@@ -0,0 +1,210 @@
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        user::UserId,
+        user_session::{UserSession, UserSessionCreate, UserSessionFilter, UserSessionId},
+    },
+    resource::{
+        CreateRepository, GetListRepository, GetRepository, InstrumentQuery, RepositoryError,
+        list_query, record_rows,
+    },
+};
+
+/// The refresh token chains of the users. Revoked sessions are kept, so a
+/// refresh token of a revoked chain is recognized and refused.
+#[derive(Debug, Clone, Copy)]
+pub struct UserSessionRepository;
+
+impl GetRepository<UserSessionId, UserSession> for UserSessionRepository {
+    #[instrument(name = "UserSessionRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: UserSessionId,
+    ) -> Result<UserSession, RepositoryError> {
+        let user_session = query_as::<_, UserSession>(
+            r#"
+            SELECT * FROM user_session
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(user_session)
+    }
+}
+
+impl GetListRepository<UserSession, UserSessionFilter> for UserSessionRepository {
+    /// The sessions that have not been revoked, most recently refreshed
+    /// first.
+    #[instrument(
+        name = "UserSessionRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: UserSessionFilter,
+    ) -> Result<Vec<UserSession>, RepositoryError> {
+        let mut query = list_query(
+            r#"
+            SELECT * FROM user_session
+            "#,
+            filter.predicate(),
+            Some(r#"last_refreshed_at DESC, id"#),
+            offset,
+            limit,
+        );
+
+        let user_sessions = query
+            .build_query_as::<UserSession>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(user_sessions))
+    }
+}
+
+impl CreateRepository<UserSessionCreate, UserSession> for UserSessionRepository {
+    #[instrument(name = "UserSessionRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: UserSessionCreate,
+    ) -> Result<UserSession, RepositoryError> {
+        let user_session = query_as::<_, UserSession>(
+            r#"
+            INSERT INTO user_session (user_id, token_hash, ip_address, user_agent)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.token_hash)
+        .bind(create_model.ip_address)
+        .bind(create_model.user_agent)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(user_session)
+    }
+}
+
+impl UserSessionRepository {
+    /// The session whose latest refresh token has the digest, revoked or
+    /// not.
+    #[instrument(name = "UserSessionRepository::get_by_token_hash", skip_all)]
+    pub async fn get_by_token_hash(
+        &self,
+        mut session: PgTransaction<'_>,
+        token_hash: &[u8],
+    ) -> Result<Option<UserSession>, RepositoryError> {
+        let user_session = query_as::<_, UserSession>(
+            r#"
+            SELECT * FROM user_session
+            WHERE token_hash = $1
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(user_session)
+    }
+
+    /// Moves a session on to the refresh token that replaced its last one.
+    #[instrument(name = "UserSessionRepository::rotate", skip_all, fields(id = ?id))]
+    pub async fn rotate(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: UserSessionId,
+        token_hash: Vec<u8>,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    ) -> Result<UserSession, RepositoryError> {
+        let user_session = query_as::<_, UserSession>(
+            r#"
+            UPDATE user_session
+            SET
+                token_hash = $2,
+                ip_address = $3,
+                user_agent = $4,
+                last_refreshed_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND revoked_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(token_hash)
+        .bind(ip_address)
+        .bind(user_agent)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(user_session)
+    }
+
+    /// Revokes a session, which is a no-op if it already was.
+    #[instrument(name = "UserSessionRepository::revoke", skip_all, fields(id = ?id))]
+    pub async fn revoke(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: UserSessionId,
+    ) -> Result<UserSession, RepositoryError> {
+        let user_session = query_as::<_, UserSession>(
+            r#"
+            UPDATE user_session
+            SET revoked_at = COALESCE(revoked_at, CURRENT_TIMESTAMP)
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(user_session)
+    }
+
+    /// Revokes the sessions of a user other than the one kept, returning
+    /// those it revoked.
+    #[instrument(
+        name = "UserSessionRepository::revoke_others",
+        skip_all,
+        fields(user_id = ?user_id, keep = ?keep, rows = tracing::field::Empty)
+    )]
+    pub async fn revoke_others(
+        &self,
+        mut session: PgTransaction<'_>,
+        user_id: UserId,
+        keep: UserSessionId,
+    ) -> Result<Vec<UserSession>, RepositoryError> {
+        let user_sessions = query_as::<_, UserSession>(
+            r#"
+            UPDATE user_session
+            SET revoked_at = CURRENT_TIMESTAMP
+            WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(keep)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(record_rows(user_sessions))
+    }
+}
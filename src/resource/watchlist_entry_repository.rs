@@ -0,0 +1,264 @@
+use rust_decimal::Decimal;
+use sqlx::{PgTransaction, query_as};
+use tracing::instrument;
+
+use crate::{
+    model::{
+        Filter,
+        asset::AssetId,
+        watchlist_entry::{
+            WatchDirection, WatchlistEntry, WatchlistEntryCreate, WatchlistEntryFilter,
+            WatchlistEntryId,
+        },
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, InstrumentQuery,
+        RepositoryError, UpdateRepository, list_query, record_rows,
+    },
+};
+
+/// The entries of `watchlist_entry` with the symbols and the latest price
+/// of their pair, under the same name so that filters and orders apply as
+/// they would to the table. Mutations select from a CTE of the name over
+/// their `RETURNING`.
+const SELECT_WITH_LATEST_PRICE: &str = r#"
+    SELECT * FROM (
+        SELECT
+            watchlist_entry.*,
+            asset.symbol,
+            quote_asset.symbol AS quote_symbol,
+            price.latest_price,
+            price.latest_priced_at
+        FROM watchlist_entry
+        JOIN asset ON asset.id = watchlist_entry.asset_id
+        JOIN asset quote_asset ON quote_asset.id = watchlist_entry.quote_asset_id
+        LEFT JOIN LATERAL (
+            SELECT ap.rate AS latest_price, ap.priced_at AS latest_priced_at
+            FROM asset_price ap
+            WHERE ap.asset_id = watchlist_entry.asset_id
+            AND ap.quote_asset_id = watchlist_entry.quote_asset_id
+            ORDER BY ap.priced_at DESC
+            LIMIT 1
+        ) price ON TRUE
+    ) watchlist_entry
+"#;
+
+#[derive(Debug, Clone, Copy)]
+pub struct WatchlistEntryRepository;
+
+impl GetRepository<WatchlistEntryId, WatchlistEntry> for WatchlistEntryRepository {
+    #[instrument(name = "WatchlistEntryRepository::get", skip_all, fields(id = ?id))]
+    async fn get(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: WatchlistEntryId,
+    ) -> Result<WatchlistEntry, RepositoryError> {
+        let watchlist_entry = query_as::<_, WatchlistEntry>(&format!(
+            r#"
+            {SELECT_WITH_LATEST_PRICE}
+            WHERE id = $1
+            "#
+        ))
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        Ok(watchlist_entry)
+    }
+}
+
+impl GetListRepository<WatchlistEntry, WatchlistEntryFilter> for WatchlistEntryRepository {
+    #[instrument(
+        name = "WatchlistEntryRepository::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit, rows = tracing::field::Empty)
+    )]
+    async fn get_list(
+        &self,
+        mut session: PgTransaction<'_>,
+        offset: i64,
+        limit: Option<i64>,
+        filter: WatchlistEntryFilter,
+    ) -> Result<Vec<WatchlistEntry>, RepositoryError> {
+        let mut query = list_query(
+            SELECT_WITH_LATEST_PRICE,
+            filter.predicate(),
+            Some(r#"created_at, id"#),
+            offset,
+            limit,
+        );
+
+        let watchlist_entries = query
+            .build_query_as::<WatchlistEntry>()
+            .fetch_all(&mut *session)
+            .in_query_span()
+            .await?;
+
+        Ok(record_rows(watchlist_entries))
+    }
+}
+
+impl CreateRepository<WatchlistEntryCreate, WatchlistEntry> for WatchlistEntryRepository {
+    #[instrument(name = "WatchlistEntryRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: WatchlistEntryCreate,
+    ) -> Result<WatchlistEntry, RepositoryError> {
+        let new_watchlist_entry = query_as::<_, WatchlistEntry>(&format!(
+            r#"
+            WITH watchlist_entry AS (
+                INSERT INTO watchlist_entry (
+                    user_id, asset_id, quote_asset_id, target_price, direction, channel, repeating
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING *
+            )
+            {SELECT_WITH_LATEST_PRICE}
+            "#
+        ))
+        .bind(create_model.user_id)
+        .bind(create_model.asset_id)
+        .bind(create_model.quote_asset_id)
+        .bind(create_model.target_price)
+        .bind(create_model.direction)
+        .bind(create_model.channel)
+        .bind(create_model.repeating)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(new_watchlist_entry)
+    }
+}
+
+impl UpdateRepository<WatchlistEntry> for WatchlistEntryRepository {
+    #[instrument(name = "WatchlistEntryRepository::update", skip_all, fields(id = ?model.id))]
+    async fn update(
+        &self,
+        mut session: PgTransaction<'_>,
+        model: WatchlistEntry,
+    ) -> Result<WatchlistEntry, RepositoryError> {
+        let updated_watchlist_entry = query_as::<_, WatchlistEntry>(&format!(
+            r#"
+            WITH watchlist_entry AS (
+                UPDATE watchlist_entry
+                SET
+                    target_price = $2,
+                    direction = $3,
+                    channel = $4,
+                    repeating = $5,
+                    triggered_at = $6,
+                    triggered_price = $7
+                WHERE id = $1
+                RETURNING *
+            )
+            {SELECT_WITH_LATEST_PRICE}
+            "#
+        ))
+        .bind(model.id)
+        .bind(model.target_price)
+        .bind(model.direction)
+        .bind(model.channel)
+        .bind(model.repeating)
+        .bind(model.triggered_at)
+        .bind(model.triggered_price)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(updated_watchlist_entry)
+    }
+}
+
+impl DeleteRepository<WatchlistEntryId, WatchlistEntry> for WatchlistEntryRepository {
+    #[instrument(name = "WatchlistEntryRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: WatchlistEntryId,
+    ) -> Result<WatchlistEntry, RepositoryError> {
+        let deleted_watchlist_entry = query_as::<_, WatchlistEntry>(&format!(
+            r#"
+            WITH watchlist_entry AS (
+                DELETE FROM watchlist_entry
+                WHERE id = $1
+                RETURNING *
+            )
+            {SELECT_WITH_LATEST_PRICE}
+            "#
+        ))
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(deleted_watchlist_entry)
+    }
+}
+
+impl WatchlistEntryRepository {
+    /// Fires the armed entries of the pair whose target the latest price
+    /// reached going from `before` to `after`, marking them triggered so
+    /// that one-shot entries stay quiet until their target changes.
+    ///
+    /// The entries are locked while they are looked at, so two ingestions
+    /// of the same pair can't both fire one.
+    #[instrument(
+        name = "WatchlistEntryRepository::evaluate",
+        skip_all,
+        fields(asset_id = ?asset_id, quote_asset_id = ?quote_asset_id, rows = tracing::field::Empty)
+    )]
+    pub async fn evaluate(
+        &self,
+        mut session: PgTransaction<'_>,
+        asset_id: AssetId,
+        quote_asset_id: AssetId,
+        before: Option<Decimal>,
+        after: Decimal,
+    ) -> Result<Vec<WatchlistEntry>, RepositoryError> {
+        let armed = query_as::<_, (WatchlistEntryId, Decimal, WatchDirection)>(
+            r#"
+            SELECT id, target_price, direction FROM watchlist_entry
+            WHERE asset_id = $1
+            AND quote_asset_id = $2
+            AND target_price IS NOT NULL
+            AND (repeating OR triggered_at IS NULL)
+            FOR UPDATE
+            "#,
+        )
+        .bind(asset_id)
+        .bind(quote_asset_id)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        let crossed = armed
+            .into_iter()
+            .filter(|(_, target_price, direction)| direction.crossed(*target_price, before, after))
+            .map(|(id, _, _)| id)
+            .collect::<Vec<_>>();
+        if crossed.is_empty() {
+            session.commit().await?;
+            return Ok(vec![]);
+        }
+
+        let triggered = query_as::<_, WatchlistEntry>(&format!(
+            r#"
+            WITH watchlist_entry AS (
+                UPDATE watchlist_entry
+                SET triggered_at = now(), triggered_price = $2
+                WHERE id = ANY($1)
+                RETURNING *
+            )
+            {SELECT_WITH_LATEST_PRICE}
+            "#
+        ))
+        .bind(crossed)
+        .bind(after)
+        .fetch_all(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(record_rows(triggered))
+    }
+}
@@ -0,0 +1,81 @@
+use sqlx::{PgTransaction, query, query_as, types::Json};
+use tracing::instrument;
+
+use crate::{
+    model::webauthn_challenge::{WebauthnChallenge, WebauthnChallengeCreate, WebauthnChallengeId},
+    resource::{CreateRepository, DeleteRepository, InstrumentQuery, RepositoryError},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct WebauthnChallengeRepository;
+
+impl WebauthnChallengeRepository {
+    /// Deletes the challenges that can no longer be completed, returning
+    /// how many there were.
+    #[instrument(name = "WebauthnChallengeRepository::delete_expired", skip_all)]
+    pub async fn delete_expired(
+        &self,
+        mut session: PgTransaction<'_>,
+    ) -> Result<u64, RepositoryError> {
+        let deleted = query(
+            r#"
+            DELETE FROM webauthn_challenge
+            WHERE expires_at < CURRENT_TIMESTAMP
+            "#,
+        )
+        .execute(&mut *session)
+        .in_query_span()
+        .await?
+        .rows_affected();
+        session.commit().await?;
+        Ok(deleted)
+    }
+}
+
+impl CreateRepository<WebauthnChallengeCreate, WebauthnChallenge> for WebauthnChallengeRepository {
+    #[instrument(name = "WebauthnChallengeRepository::create", skip_all)]
+    async fn create(
+        &self,
+        mut session: PgTransaction<'_>,
+        create_model: WebauthnChallengeCreate,
+    ) -> Result<WebauthnChallenge, RepositoryError> {
+        let challenge = query_as::<_, WebauthnChallenge>(
+            r#"
+            INSERT INTO webauthn_challenge (user_id, expires_at, state)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(create_model.user_id)
+        .bind(create_model.expires_at)
+        .bind(Json(create_model.state))
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(challenge)
+    }
+}
+
+impl DeleteRepository<WebauthnChallengeId, WebauthnChallenge> for WebauthnChallengeRepository {
+    #[instrument(name = "WebauthnChallengeRepository::delete", skip_all, fields(id = ?id))]
+    async fn delete(
+        &self,
+        mut session: PgTransaction<'_>,
+        id: WebauthnChallengeId,
+    ) -> Result<WebauthnChallenge, RepositoryError> {
+        let challenge = query_as::<_, WebauthnChallenge>(
+            r#"
+            DELETE FROM webauthn_challenge
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *session)
+        .in_query_span()
+        .await?;
+        session.commit().await?;
+        Ok(challenge)
+    }
+}
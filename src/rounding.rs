@@ -0,0 +1,228 @@
+//! How amounts are rounded to the decimals of their asset.
+//!
+//! Amounts are rounded one at a time, each converted transaction or
+//! percentage of an amount, and a total is the sum of the rounded amounts
+//! rather than the rounded sum, so it always equals the sum of its parts.
+//! The policy only decides which way a half goes. Queries round with the
+//! `round_amount` function of the database, which rounds the same way.
+
+use std::str::FromStr;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Which way amounts exactly halfway between two are rounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingPolicy {
+    /// To the even neighbour, so halves round up as often as down and the
+    /// totals of many amounts don't drift
+    #[default]
+    Bankers,
+    /// Away from zero, as `ROUND` in Postgres does, so `-2.5` rounds to `-3`
+    HalfUp,
+}
+
+impl RoundingPolicy {
+    pub const ALL: [Self; 2] = [Self::Bankers, Self::HalfUp];
+
+    /// The name of the policy in the `ROUNDING_POLICY` setting and in
+    /// queries.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bankers => "bankers",
+            Self::HalfUp => "half_up",
+        }
+    }
+
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            Self::Bankers => RoundingStrategy::MidpointNearestEven,
+            Self::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+        }
+    }
+
+    /// Rounds `value` to `decimals` places, padding it with zeros to have
+    /// exactly that many.
+    pub fn round(self, value: Decimal, decimals: u32) -> Decimal {
+        let mut rounded = value.round_dp_with_strategy(decimals, self.strategy());
+        rounded.rescale(decimals);
+        rounded
+    }
+
+    /// `percent` percent of `value`, rounded to `decimals` places, or `None`
+    /// if it doesn't fit in a decimal.
+    pub fn percent_of(self, value: Decimal, percent: Decimal, decimals: u32) -> Option<Decimal> {
+        value
+            .checked_mul(percent)
+            .and_then(|x| x.checked_div(Decimal::ONE_HUNDRED))
+            .map(|x| self.round(x, decimals))
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl RoundingPolicy {
+    /// Reads `ROUNDING_POLICY`, which is `bankers` or `half_up`.
+    pub fn from_env() -> Self {
+        static ROUNDING_POLICY: std::sync::OnceLock<RoundingPolicy> = std::sync::OnceLock::new();
+        *ROUNDING_POLICY
+            .get_or_init(|| Self::parse(std::env::var("ROUNDING_POLICY").ok().as_deref()))
+    }
+
+    /// The policy `value` names, falling back to banker's rounding for
+    /// unset or unknown values.
+    pub fn parse(value: Option<&str>) -> Self {
+        value.and_then(|x| x.parse().ok()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("There is no rounding policy `{0}`.")]
+pub struct UnknownRoundingPolicy(String);
+
+impl FromStr for RoundingPolicy {
+    type Err = UnknownRoundingPolicy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == s)
+            .ok_or_else(|| UnknownRoundingPolicy(s.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[rstest]
+    #[case("2.5", 0, "2", "3")]
+    #[case("3.5", 0, "4", "4")]
+    #[case("-2.5", 0, "-2", "-3")]
+    #[case("-3.5", 0, "-4", "-4")]
+    #[case("0.5", 0, "0", "1")]
+    #[case("-0.5", 0, "0", "-1")]
+    #[case("1.005", 2, "1.00", "1.01")]
+    #[case("1.015", 2, "1.02", "1.02")]
+    #[case("-1.005", 2, "-1.00", "-1.01")]
+    // Just either side of a half isn't a half.
+    #[case("2.4999999999", 0, "2", "2")]
+    #[case("2.5000000001", 0, "3", "3")]
+    #[case("-2.5000000001", 0, "-3", "-3")]
+    // Values already at the decimals are padded, not changed.
+    #[case("7", 2, "7.00", "7.00")]
+    #[case("-0.1", 3, "-0.100", "-0.100")]
+    fn it_rounds_halves_by_the_policy(
+        #[case] value: &str,
+        #[case] decimals: u32,
+        #[case] bankers: &str,
+        #[case] half_up: &str,
+    ) {
+        let value = decimal(value);
+        let rounded = RoundingPolicy::Bankers.round(value, decimals);
+        assert_eq!(rounded, decimal(bankers));
+        assert_eq!(rounded.scale(), decimals);
+        let rounded = RoundingPolicy::HalfUp.round(value, decimals);
+        assert_eq!(rounded, decimal(half_up));
+        assert_eq!(rounded.scale(), decimals);
+    }
+
+    #[test]
+    fn it_takes_percentages_at_the_decimals_of_the_asset() {
+        // 12.5% of 1,004 KRW is 125.5 KRW.
+        let income = Decimal::from(1_004);
+        let percent = decimal("12.5");
+        assert_eq!(
+            RoundingPolicy::Bankers.percent_of(income, percent, 0),
+            Some(Decimal::from(126))
+        );
+        assert_eq!(
+            RoundingPolicy::Bankers.percent_of(Decimal::from(1_012), percent, 0),
+            Some(Decimal::from(126))
+        );
+        assert_eq!(
+            RoundingPolicy::HalfUp.percent_of(Decimal::from(1_012), percent, 0),
+            Some(Decimal::from(127))
+        );
+        assert_eq!(
+            RoundingPolicy::Bankers.percent_of(Decimal::MAX, Decimal::from(100), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn it_reads_the_policy_by_name() {
+        for policy in RoundingPolicy::ALL {
+            assert_eq!(policy.name().parse::<RoundingPolicy>().unwrap(), policy);
+            assert_eq!(
+                serde_json::to_value(policy).unwrap(),
+                serde_json::json!(policy.name())
+            );
+        }
+        assert_eq!(RoundingPolicy::parse(None), RoundingPolicy::Bankers);
+        assert_eq!(RoundingPolicy::parse(Some("up")), RoundingPolicy::Bankers);
+        assert_eq!(
+            RoundingPolicy::parse(Some("half_up")),
+            RoundingPolicy::HalfUp
+        );
+    }
+
+    fn policy() -> impl Strategy<Value = RoundingPolicy> {
+        prop_oneof![Just(RoundingPolicy::Bankers), Just(RoundingPolicy::HalfUp)]
+    }
+
+    proptest! {
+        #[test]
+        fn it_totals_groups_to_the_sum_of_their_subtotals(
+            policy in policy(),
+            decimals in 0u32..4,
+            rate in (1i64..10_000_000, 0u32..6).prop_map(|(x, scale)| Decimal::new(x, scale)),
+            transactions in prop::collection::vec(
+                (0usize..5, (-1_000_000_000i64..1_000_000_000, 0u32..4)),
+                0..50,
+            ),
+        ) {
+            // Each transaction converts and rounds on its own, as a report
+            // does, then the groups and the report total them.
+            let converted = transactions
+                .iter()
+                .map(|(group, (quantity, scale))| {
+                    (*group, policy.round(Decimal::new(*quantity, *scale) * rate, decimals))
+                })
+                .collect::<Vec<_>>();
+            let mut subtotals = [Decimal::ZERO; 5];
+            for (group, amount) in &converted {
+                subtotals[*group] += amount;
+            }
+            let total = converted.iter().map(|(_, amount)| amount).sum::<Decimal>();
+
+            prop_assert_eq!(subtotals.iter().sum::<Decimal>(), total);
+            for amount in subtotals.iter().chain([&total]) {
+                prop_assert_eq!(policy.round(*amount, decimals), *amount);
+            }
+        }
+
+        #[test]
+        fn it_rounds_to_a_neighbour_within_half_a_unit(
+            policy in policy(),
+            decimals in 0u32..6,
+            value in (any::<i64>(), 0u32..12).prop_map(|(x, scale)| Decimal::new(x, scale)),
+        ) {
+            let rounded = policy.round(value, decimals);
+            let unit = Decimal::new(1, decimals);
+            prop_assert!((rounded - value).abs() * Decimal::TWO <= unit);
+            prop_assert_eq!(rounded.scale(), decimals);
+            // Rounding is symmetric about zero under either policy.
+            prop_assert_eq!(policy.round(-value, decimals), -rounded);
+        }
+    }
+}
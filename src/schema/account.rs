@@ -1,20 +1,34 @@
 use crate::{
-    model::{account::AccountId, institution::InstitutionId, user::UserId},
+    model::{
+        ListSort,
+        account::AccountId,
+        asset::AssetId,
+        institution::InstitutionId,
+        statement::{StatementFormat, StatementId, StatementStatus},
+        transaction::TransactionId,
+        user::UserId,
+    },
     schema::{
         CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
-        serialize_datetime,
+        deserialize_datetime_option, deserialize_quantity, serialize_datetime,
+        serialize_datetime_option, serialize_quantity, transaction::TransactionResponse,
     },
 };
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
+use uuid::Uuid;
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
+        integration::SyncReport,
         model::{
-            account::{Account, AccountFilter, AccountUpdate},
+            account::{Account, AccountDeletionCounts, AccountFilter, AccountMerge, AccountUpdate},
+            account_balance::AccountBalance,
             cursor_key::{CursorKey, EncryptionError},
+            statement::AccountStatement,
         },
         schema::Pagination,
     };
@@ -50,6 +64,16 @@ pub struct AccountResponse<T> {
     /// The institution id of which the account belongs
     pub institution_id: InstitutionId,
     pub user_id: UserId,
+    /// The account notes, in markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// The asset the account shows its values in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_asset_id: Option<AssetId>,
+    /// The asset the account shows its values in, falling back to the
+    /// default of its institution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_default_asset_id: Option<AssetId>,
     #[serde(skip)]
     pub _phantom: PhantomData<T>,
 }
@@ -62,6 +86,8 @@ impl PartialEq<AccountResponse<CreateResponse>> for AccountResponse<GetList> {
             && self.name == other.name
             && self.institution_id == other.institution_id
             && self.user_id == other.user_id
+            && self.notes == other.notes
+            && self.default_asset_id == other.default_asset_id
     }
 }
 
@@ -72,13 +98,21 @@ pub struct CreateRequest {
     pub name: String,
     /// The institution id of which the account belongs
     pub institution_id: InstitutionId,
+    /// The account notes, in markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// The asset the account shows its values in, the default of the
+    /// institution when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_asset_id: Option<AssetId>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams, Params))]
 #[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
 pub struct GetListRequest {
-    /// The name to filter on
+    /// The exact name to filter on, percent-encoded once like any other
+    /// query parameter, so `50%25%20off` is `50% off`
     #[cfg_attr(feature = "ssr", param(value_type = String, required = false))]
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
@@ -86,6 +120,42 @@ pub struct GetListRequest {
     #[cfg_attr(feature = "ssr", param(value_type = Uuid, required = false))]
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub institution_id: Option<InstitutionId>,
+    /// Only accounts created strictly after this time
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only accounts created strictly before this time
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only accounts last changed strictly after this time
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Only accounts last changed strictly before this time
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub updated_before: Option<DateTime<Utc>>,
+    /// The order to list in, by `created_at` unless given
+    #[cfg_attr(feature = "ssr", param(inline, required = false))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ListSort>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -103,12 +173,173 @@ pub struct GetListResponse {
 #[cfg_attr(feature = "ssr", derive(ToSchema))]
 pub struct UpdateRequest {
     pub name: String,
+    /// The new account notes, in markdown, which an empty string clears
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// The new asset the account shows its values in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_asset_id: Option<AssetId>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct FromTemplateRequest {
+    /// The institution to open the accounts at
+    pub institution_id: InstitutionId,
+    /// The name of the template, see `GET /api/account-templates`
+    pub template: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct FromTemplateResponse {
+    /// The accounts created, in the order of the template
+    pub accounts: Vec<AccountResponse<CreateResponse>>,
+}
+
+/// The query of a deletion of an account.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct DeleteRequest {
+    /// Delete the account along with the rows its deletion preview counts
+    #[serde(default)]
+    pub confirm: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[cfg_attr(feature = "ssr", derive(ToSchema))]
 pub struct DeleteResponse;
 
+/// How many rows of each kind are deleted with the account.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DeletionPreviewResponse {
+    pub transactions: i64,
+    /// The attachments of the transactions
+    pub attachments: i64,
+    pub categorization_rules: i64,
+    pub alert_rules: i64,
+    /// The transactions that are legs of journal entries. The account
+    /// can't be deleted until those entries are.
+    pub journal_entry_legs: i64,
+}
+
+/// The query of a statement of an account, which is of a `year` and
+/// `month`, or of a date range of a whole month in UTC.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct StatementRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    /// The month of the year, from 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub month: Option<u32>,
+    /// The format to render the statement as. `pdf` is only rendered by
+    /// servers built with PDF statements.
+    #[serde(default)]
+    pub format: StatementFormat,
+}
+
+/// A statement rendered after responding, and how far it has come.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct StatementResponse {
+    pub id: StatementId,
+    pub account_id: AccountId,
+    pub year: i32,
+    pub month: i32,
+    pub format: StatementFormat,
+    pub status: StatementStatus,
+    /// Where to poll for the statement, which is downloaded from there
+    /// once it is complete
+    pub url: String,
+    /// Why the statement could not be rendered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SyncResponse {
+    /// The transactions the provider had that the account didn't
+    pub created: Vec<TransactionResponse<CreateResponse>>,
+    /// How many fetched transactions the account already had
+    pub duplicates: usize,
+    /// How many fetched transactions were in assets that aren't tracked
+    pub unknown_assets: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct AccountBalanceResponse {
+    /// The asset of the balance
+    pub asset_id: AssetId,
+    /// The sum of the transaction quantities in the asset
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub balance: Decimal,
+    /// The last transaction applied to the balance
+    pub as_of_transaction_id: Option<TransactionId>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct BalancesResponse {
+    /// The balance of the account in each asset it has transactions in
+    pub balances: Vec<AccountBalanceResponse>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct MergeRequest {
+    /// The duplicate account to merge into this one, which is deleted
+    pub source_id: AccountId,
+    /// Merge the accounts even if they are at different institutions
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// How many rows of each kind were moved off the source account.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct MergeCountsResponse {
+    pub transactions: i64,
+    /// The attachments of the moved transactions
+    pub attachments: i64,
+    pub categorization_rules: i64,
+    pub alert_rules: i64,
+    /// The import profiles that defaulted to the source account
+    pub import_profiles: i64,
+    /// The budgets that tracked the source account
+    pub budgets: i64,
+    /// The API keys scoped to the source account
+    pub api_keys: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct MergeResponse {
+    /// The id of the record of the merge
+    pub id: Uuid,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub merged_at: DateTime<Utc>,
+    /// The account that was kept
+    pub target_id: AccountId,
+    /// The account that was deleted
+    pub source_id: AccountId,
+    /// Whether the accounts were at different institutions
+    pub forced: bool,
+    pub moved: MergeCountsResponse,
+}
+
 pub type AccountGetResponse = AccountResponse<GetResponse>;
 pub type AccountGetListResponse = GetListResponse;
 pub type AccountCreateResponse = AccountResponse<CreateResponse>;
@@ -133,11 +364,23 @@ mod ssr {
                 name: value.name,
                 institution_id: value.institution_id,
                 user_id: value.user_id,
+                notes: value.notes,
+                default_asset_id: value.default_asset_id,
+                effective_default_asset_id: value.default_asset_id,
                 _phantom: PhantomData,
             }
         }
     }
 
+    impl AccountResponse<GetResponse> {
+        /// Falls back to the default of the institution of the account when
+        /// the account has none.
+        pub fn with_institution_default(mut self, default_asset_id: Option<AssetId>) -> Self {
+            self.effective_default_asset_id = self.default_asset_id.or(default_asset_id);
+            self
+        }
+    }
+
     impl IntoResponse for AccountResponse<CreateResponse> {
         fn into_response(self) -> Response {
             (StatusCode::CREATED, Json(self)).into_response()
@@ -156,11 +399,136 @@ mod ssr {
         }
     }
 
+    impl From<SyncReport> for SyncResponse {
+        fn from(value: SyncReport) -> Self {
+            Self {
+                created: value.created.into_iter().map(|x| x.into()).collect(),
+                duplicates: value.duplicates,
+                unknown_assets: value.unknown_assets,
+            }
+        }
+    }
+
+    impl From<AccountBalance> for AccountBalanceResponse {
+        fn from(value: AccountBalance) -> Self {
+            Self {
+                asset_id: value.asset_id,
+                balance: value.balance,
+                as_of_transaction_id: value.as_of_transaction_id,
+            }
+        }
+    }
+
+    impl FromTemplateResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl IntoResponse for FromTemplateResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for BalancesResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<AccountMerge> for MergeResponse {
+        fn from(value: AccountMerge) -> Self {
+            Self {
+                id: value.id,
+                merged_at: value.merged_at,
+                target_id: value.target_id,
+                source_id: value.source_id,
+                forced: value.forced,
+                moved: MergeCountsResponse {
+                    transactions: value.transactions,
+                    attachments: value.attachments,
+                    categorization_rules: value.categorization_rules,
+                    alert_rules: value.alert_rules,
+                    import_profiles: value.import_profiles,
+                    budgets: value.budgets,
+                    api_keys: value.api_keys,
+                },
+            }
+        }
+    }
+
+    impl From<AccountDeletionCounts> for DeletionPreviewResponse {
+        fn from(value: AccountDeletionCounts) -> Self {
+            Self {
+                transactions: value.transactions,
+                attachments: value.attachments,
+                categorization_rules: value.categorization_rules,
+                alert_rules: value.alert_rules,
+                journal_entry_legs: value.journal_entry_legs,
+            }
+        }
+    }
+
+    impl IntoResponse for DeletionPreviewResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<AccountStatement> for StatementResponse {
+        fn from(value: AccountStatement) -> Self {
+            Self {
+                id: value.id,
+                account_id: value.account_id,
+                year: value.year,
+                month: value.month,
+                format: value.format,
+                status: value.status,
+                url: format!("/api/accounts/{}/statements/{}", value.account_id, value.id),
+                error: value.error,
+            }
+        }
+    }
+
+    impl StatementResponse {
+        /// A pending statement is accepted, to be polled until it isn't.
+        pub fn status_code(&self) -> StatusCode {
+            match self.status {
+                StatementStatus::Pending => StatusCode::ACCEPTED,
+                StatementStatus::Complete | StatementStatus::Failed => StatusCode::OK,
+            }
+        }
+    }
+
+    impl IntoResponse for StatementResponse {
+        fn into_response(self) -> Response {
+            (self.status_code(), Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for MergeResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for SyncResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
     impl From<GetListRequest> for AccountFilter {
         fn from(value: GetListRequest) -> Self {
             Self {
                 name: value.name,
                 institution_id: value.institution_id,
+                created_after: value.created_after,
+                created_before: value.created_before,
+                updated_after: value.updated_after,
+                updated_before: value.updated_before,
+                sort: value.sort.unwrap_or_default(),
                 ..Default::default()
             }
         }
@@ -191,7 +559,11 @@ mod ssr {
 
     impl From<UpdateRequest> for AccountUpdate {
         fn from(value: UpdateRequest) -> Self {
-            Self { name: value.name }
+            Self {
+                name: value.name,
+                notes: value.notes,
+                default_asset_id: value.default_asset_id,
+            }
         }
     }
 
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::account_template::{AccountTemplate, TemplateAccount};
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// An account a template opens.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct TemplateAccountResponse {
+    /// The name of the account
+    pub name: String,
+    /// What the account is used for, such as `checking` or `credit`
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct AccountTemplateResponse {
+    /// The name the template is chosen by
+    pub name: String,
+    pub description: String,
+    /// The accounts the template opens, in order
+    pub accounts: Vec<TemplateAccountResponse>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// Every template accounts can be created from
+    pub templates: Vec<AccountTemplateResponse>,
+}
+
+pub type AccountTemplateGetListResponse = GetListResponse;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl From<&TemplateAccount> for TemplateAccountResponse {
+        fn from(value: &TemplateAccount) -> Self {
+            Self {
+                name: value.name.to_owned(),
+                kind: value.kind.to_owned(),
+            }
+        }
+    }
+
+    impl From<&AccountTemplate> for AccountTemplateResponse {
+        fn from(value: &AccountTemplate) -> Self {
+            Self {
+                name: value.name.to_owned(),
+                description: value.description.to_owned(),
+                accounts: value.accounts.iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
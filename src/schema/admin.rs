@@ -0,0 +1,364 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        authorization::{PermissionSet, summary::PolicySummary},
+        config::{Feature, FeatureFlags},
+    };
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct StatsRequest {
+    /// Counts the transactions exactly instead of using the planner
+    /// estimate, which scans the whole table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact: Option<bool>,
+}
+
+/// The connections of the database pool of this server.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PoolStatsResponse {
+    /// The open connections, idle or in use
+    pub size: u32,
+    pub idle: usize,
+}
+
+/// The entries held by the read caches of this server.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CacheStatsResponse {
+    pub assets: usize,
+    pub institutions: usize,
+}
+
+/// The event streams of this server.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct EventStatsResponse {
+    /// The streams open now
+    pub active_streams: usize,
+    /// The events dropped from the queues of clients that fell behind since
+    /// the server started
+    pub dropped_events: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct StatsResponse {
+    pub users: i64,
+    pub accounts: i64,
+    /// The number of transactions, estimated unless `exact` was asked for
+    /// or the table has not been analyzed yet
+    pub transactions: i64,
+    /// Whether `transactions` is an exact count
+    pub transactions_exact: bool,
+    /// The sessions that have not been revoked
+    pub active_sessions: i64,
+    /// The receipt extractions waiting to run
+    pub pending_extractions: i64,
+    /// The queries cancelled for running past the deadline of their request
+    /// since the server started
+    #[serde(default)]
+    pub query_timeouts: u64,
+    /// The handlers that panicked since the server started
+    #[serde(default)]
+    pub panics: u64,
+    /// The requests to sign in or refresh turned away by the login
+    /// throttle since the server started
+    #[serde(default)]
+    pub login_throttled: u64,
+    pub pool: PoolStatsResponse,
+    pub caches: CacheStatsResponse,
+    #[serde(default)]
+    pub events: EventStatsResponse,
+}
+
+pub type AdminStatsResponse = StatsResponse;
+
+/// The state of an optional subsystem.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct FeatureResponse {
+    pub name: String,
+    pub enabled: bool,
+    /// Whether the routes of the feature are served, which only changes
+    /// when the server restarts
+    pub mounted: bool,
+    /// Whether the feature was toggled since its routes were mounted, and
+    /// only takes full effect on the next restart
+    pub restart_required: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct FeaturesResponse {
+    pub features: Vec<FeatureResponse>,
+    /// Whether any of the features only takes full effect on the next
+    /// restart
+    pub restart_required: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UpdateFeaturesRequest {
+    /// Whether to enable each feature, by name. The features left out keep
+    /// their setting.
+    pub features: BTreeMap<String, bool>,
+}
+
+pub type AdminFeaturesResponse = FeaturesResponse;
+pub type AdminUpdateFeaturesResponse = FeaturesResponse;
+
+/// The most group and resource pairs a simulation compares.
+pub const MAX_SIMULATION_CHECKS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PolicyCheckRequest {
+    /// The group, as claimed by a token
+    pub group: String,
+    pub resource: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SimulatePoliciesRequest {
+    /// The whole proposed policy set, in the CSV format of the policy file
+    pub policies: String,
+    /// The groups and resources to compare the permissions of
+    pub checks: Vec<PolicyCheckRequest>,
+}
+
+/// The levels of each action the policies grant, as named in them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PermissionLevelsResponse {
+    pub read: String,
+    pub create: String,
+    pub update: String,
+    pub delete: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PolicyComparisonResponse {
+    pub group: String,
+    pub resource: String,
+    /// The levels under the policies the server enforces
+    pub current: PermissionLevelsResponse,
+    /// The levels under the proposed policies
+    pub proposed: PermissionLevelsResponse,
+    /// The actions whose levels differ
+    pub differences: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PolicySimulationResponse {
+    /// The comparisons, in the order of the checks
+    pub comparisons: Vec<PolicyComparisonResponse>,
+    /// The number of comparisons with any difference
+    pub changed: usize,
+}
+
+pub type AdminSimulatePoliciesResponse = PolicySimulationResponse;
+
+/// What a subject of the policies may do to a resource.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PolicyGrantResponse {
+    /// The group or role the policies name
+    pub subject: String,
+    /// The resource, or `*` for every one
+    pub resource: String,
+    /// The actions granted, as named in the policies
+    pub actions: Vec<String>,
+    /// The grant in a sentence
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PolicyRoleResponse {
+    pub role: String,
+    pub members: Vec<String>,
+}
+
+/// A policy that grants nothing, because its action is unknown.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct InvalidPolicyResponse {
+    pub subject: String,
+    pub resource: String,
+    pub action: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PolicySummaryResponse {
+    /// The grants, by subject then resource
+    pub grants: Vec<PolicyGrantResponse>,
+    /// The members of each role, by role
+    pub roles: Vec<PolicyRoleResponse>,
+    /// The policies with actions that are not known levels
+    pub invalid: Vec<InvalidPolicyResponse>,
+}
+
+pub type AdminPolicySummaryResponse = PolicySummaryResponse;
+
+/// The format of an institution directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum DirectoryFormat {
+    /// An object with a `name`, and optionally a `parent` and a
+    /// `default_asset`, on each line
+    #[default]
+    Ndjson,
+    /// A header naming the `name`, `parent` and `default_asset` columns,
+    /// then a record on each line
+    Csv,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct BulkUpsertRequest {
+    #[serde(default)]
+    pub format: DirectoryFormat,
+    /// Check and write the directory in a database transaction that is
+    /// rolled back, reporting what it would have written
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// A row of a directory that was not written.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DirectoryRowError {
+    /// The line the row starts on
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct BulkUpsertResponse {
+    pub created: u64,
+    pub updated: u64,
+    /// The rows that matched their institution already
+    pub unchanged: u64,
+    /// The rows that were not written
+    pub failed: u64,
+    /// Why rows were not written, for the first of them
+    pub errors: Vec<DirectoryRowError>,
+    /// Whether the upsert was a dry run that wrote nothing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+pub type AdminBulkUpsertInstitutionsResponse = BulkUpsertResponse;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl From<&FeatureFlags> for FeaturesResponse {
+        fn from(value: &FeatureFlags) -> Self {
+            let features = Feature::ALL
+                .into_iter()
+                .map(|feature| FeatureResponse {
+                    name: feature.name().to_owned(),
+                    enabled: value.is_enabled(feature),
+                    mounted: value.is_mounted(feature),
+                    restart_required: value.restart_required(feature),
+                })
+                .collect::<Vec<_>>();
+            Self {
+                restart_required: features.iter().any(|x| x.restart_required),
+                features,
+            }
+        }
+    }
+
+    impl From<PermissionSet> for PermissionLevelsResponse {
+        fn from(value: PermissionSet) -> Self {
+            Self {
+                read: value.read_level.as_str().to_owned(),
+                create: value.create_level.as_str().to_owned(),
+                update: value.update_level.as_str().to_owned(),
+                delete: value.delete_level.as_str().to_owned(),
+            }
+        }
+    }
+
+    impl From<PolicySummary> for PolicySummaryResponse {
+        fn from(value: PolicySummary) -> Self {
+            Self {
+                grants: value
+                    .grants
+                    .into_iter()
+                    .map(|x| PolicyGrantResponse {
+                        description: x.describe(),
+                        actions: x.actions.iter().map(|x| x.as_str().to_owned()).collect(),
+                        subject: x.subject,
+                        resource: x.resource,
+                    })
+                    .collect(),
+                roles: value
+                    .roles
+                    .into_iter()
+                    .map(|(role, members)| PolicyRoleResponse { role, members })
+                    .collect(),
+                invalid: value
+                    .invalid
+                    .into_iter()
+                    .map(|x| InvalidPolicyResponse {
+                        message: x.error.to_string(),
+                        subject: x.subject,
+                        resource: x.resource,
+                        action: x.action,
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl PolicyComparisonResponse {
+        pub fn new(
+            check: PolicyCheckRequest,
+            current: PermissionSet,
+            proposed: PermissionSet,
+        ) -> Self {
+            let current = PermissionLevelsResponse::from(current);
+            let proposed = PermissionLevelsResponse::from(proposed);
+            let differences = [
+                ("read", current.read != proposed.read),
+                ("create", current.create != proposed.create),
+                ("update", current.update != proposed.update),
+                ("delete", current.delete != proposed.delete),
+            ]
+            .into_iter()
+            .filter(|(_, differs)| *differs)
+            .map(|(action, _)| action.to_owned())
+            .collect();
+            Self {
+                group: check.group,
+                resource: check.resource,
+                current,
+                proposed,
+                differences,
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{
+    model::{
+        account::AccountId,
+        alert_rule::{AlertChannel, AlertComparator, AlertEventId, AlertRuleId},
+        asset::AssetId,
+    },
+    schema::{
+        CreateResponse, GetList, GetResponse, Quantity, UpdateResponse, deserialize_datetime,
+        deserialize_datetime_option, deserialize_quantity, serialize_datetime,
+        serialize_datetime_option, serialize_quantity,
+    },
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::alert_rule::{AlertEvent, AlertRule};
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct AlertRuleResponse<T> {
+    pub id: AlertRuleId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    /// The account whose balance the rule watches
+    pub account_id: AccountId,
+    /// The asset of the balance
+    pub asset_id: AssetId,
+    pub comparator: AlertComparator,
+    /// The balance the rule fires on crossing
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub threshold: Decimal,
+    pub channel: AlertChannel,
+    /// How many seconds the rule stays quiet after firing
+    pub cooldown_seconds: i64,
+    /// When the rule last fired
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub last_fired_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The alert rules of the user
+    pub alert_rules: Vec<AlertRuleResponse<GetList>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    pub account_id: AccountId,
+    pub asset_id: AssetId,
+    #[serde(default)]
+    pub comparator: AlertComparator,
+    /// The balance to fire on crossing, in the asset
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub threshold: Quantity,
+    #[serde(default)]
+    pub channel: AlertChannel,
+    /// How many seconds the rule stays quiet after firing, none by default
+    #[serde(default)]
+    pub cooldown_seconds: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UpdateRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comparator: Option<AlertComparator>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub threshold: Option<Quantity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<AlertChannel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_seconds: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DeleteResponse;
+
+/// A time an alert rule fired.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct AlertEventResponse {
+    pub id: AlertEventId,
+    /// When the rule fired
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    pub alert_rule_id: AlertRuleId,
+    /// The balance that crossed the threshold
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub balance: Decimal,
+    /// The channel the alert went out on
+    pub channel: AlertChannel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetEventsResponse {
+    /// The latest firings of the rule, newest first
+    pub alert_events: Vec<AlertEventResponse>,
+}
+
+pub type AlertRuleGetResponse = AlertRuleResponse<GetResponse>;
+pub type AlertRuleGetListResponse = GetListResponse;
+pub type AlertRuleCreateResponse = AlertRuleResponse<CreateResponse>;
+pub type AlertRuleUpdateResponse = AlertRuleResponse<UpdateResponse>;
+pub type AlertRuleGetEventsResponse = GetEventsResponse;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl AlertRuleResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<AlertRule> for AlertRuleResponse<T> {
+        fn from(value: AlertRule) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                account_id: value.account_id,
+                asset_id: value.asset_id,
+                comparator: value.comparator,
+                threshold: value.threshold,
+                channel: value.channel,
+                cooldown_seconds: value.cooldown_seconds,
+                last_fired_at: value.last_fired_at,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for AlertRuleResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for AlertRuleResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for AlertRuleResponse<UpdateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<Vec<AlertRule>> for GetListResponse {
+        fn from(value: Vec<AlertRule>) -> Self {
+            Self {
+                alert_rules: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for DeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    impl From<AlertEvent> for AlertEventResponse {
+        fn from(value: AlertEvent) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                alert_rule_id: value.alert_rule_id,
+                balance: value.balance,
+                channel: value.channel,
+            }
+        }
+    }
+
+    impl From<Vec<AlertEvent>> for GetEventsResponse {
+        fn from(value: Vec<AlertEvent>) -> Self {
+            Self {
+                alert_events: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetEventsResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    model::{
+        announcement::{AnnouncementId, AnnouncementSeverity},
+        user::UserId,
+    },
+    schema::{
+        CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
+        deserialize_datetime_option, serialize_datetime, serialize_datetime_option,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        model::{
+            announcement::Announcement,
+            cursor_key::{CursorKey, EncryptionError},
+        },
+        schema::Pagination,
+    };
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct AnnouncementResponse<T> {
+    pub id: AnnouncementId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    pub message: String,
+    pub severity: AnnouncementSeverity,
+    /// When the announcement starts being shown
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub starts_at: DateTime<Utc>,
+    /// When the announcement stops being shown
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub ends_at: DateTime<Utc>,
+    /// The admin who created the announcement
+    pub created_by: Option<UserId>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+/// An announcement as shown to users.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ActiveAnnouncement {
+    pub id: AnnouncementId,
+    pub message: String,
+    pub severity: AnnouncementSeverity,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ActiveResponse {
+    pub announcements: Vec<ActiveAnnouncement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    pub announcements: Vec<AnnouncementResponse<GetList>>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    pub message: String,
+    #[serde(default)]
+    pub severity: AnnouncementSeverity,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub starts_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UpdateRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<AnnouncementSeverity>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub starts_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeleteResponse;
+
+pub type AnnouncementGetResponse = AnnouncementResponse<GetResponse>;
+pub type AnnouncementGetListResponse = GetListResponse;
+pub type AnnouncementCreateResponse = AnnouncementResponse<CreateResponse>;
+pub type AnnouncementUpdateResponse = AnnouncementResponse<UpdateResponse>;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl AnnouncementResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<Announcement> for AnnouncementResponse<T> {
+        fn from(value: Announcement) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                message: value.message,
+                severity: value.severity,
+                starts_at: value.starts_at,
+                ends_at: value.ends_at,
+                created_by: value.created_by,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for AnnouncementResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for AnnouncementResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for AnnouncementResponse<UpdateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<Announcement> for ActiveAnnouncement {
+        fn from(value: Announcement) -> Self {
+            Self {
+                id: value.id,
+                message: value.message,
+                severity: value.severity,
+                ends_at: value.ends_at,
+            }
+        }
+    }
+
+    impl From<Vec<Announcement>> for ActiveResponse {
+        fn from(value: Vec<Announcement>) -> Self {
+            Self {
+                announcements: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for ActiveResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl GetListResponse {
+        pub fn new(
+            announcements: Vec<Announcement>,
+            pagination: &Pagination,
+            cursor_key: &CursorKey,
+        ) -> Result<Self, EncryptionError> {
+            let announcements = announcements
+                .into_iter()
+                .map(|x| x.into())
+                .collect::<Vec<_>>();
+            let next_cursor = pagination.next_cursor(&announcements, cursor_key)?;
+            let prev_cursor = pagination.prev_cursor(cursor_key)?;
+            Ok(Self {
+                announcements,
+                next_cursor,
+                prev_cursor,
+            })
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    impl IntoResponse for DeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+}
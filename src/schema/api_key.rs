@@ -0,0 +1,154 @@
+use crate::{
+    model::{account::AccountId, api_key::ApiKeyId},
+    schema::{CreateResponse, GetList, deserialize_datetime, serialize_datetime},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::api_key::ApiKey;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ApiKeyResponse<T> {
+    /// The API key id
+    pub id: ApiKeyId,
+    /// When the API key was created
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    /// When the API key was updated
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    /// The API key name
+    pub name: String,
+    /// Whether the key may only read
+    pub read_only: bool,
+    /// The accounts the key is limited to, or all of the user's accounts
+    /// if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_ids: Option<Vec<AccountId>>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The API keys issued by the user
+    pub api_keys: Vec<ApiKeyResponse<GetList>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    /// The display name of the new API key
+    pub name: String,
+    /// Whether the key may only read, which is the default
+    #[serde(default = "read_only_default")]
+    pub read_only: bool,
+    /// The accounts to limit the key to, which must belong to the user
+    #[serde(default)]
+    pub account_ids: Option<Vec<AccountId>>,
+}
+
+fn read_only_default() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ApiKeyCreateResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse<CreateResponse>,
+    /// The secret to authenticate with. It is only ever shown once.
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ApiKeyDeleteResponse {}
+
+pub type ApiKeyGetListResponse = GetListResponse;
+pub type ApiKeyCreateRequest = CreateRequest;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl ApiKeyCreateResponse {
+        pub fn new(api_key: ApiKey, secret: String) -> Self {
+            Self {
+                api_key: api_key.into(),
+                secret,
+            }
+        }
+
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<ApiKey> for ApiKeyResponse<T> {
+        fn from(value: ApiKey) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                name: value.name,
+                read_only: value.read_only,
+                account_ids: value.account_ids,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for ApiKeyCreateResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl From<Vec<ApiKey>> for GetListResponse {
+        fn from(value: Vec<ApiKey>) -> Self {
+            Self {
+                api_keys: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl ApiKeyDeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    impl IntoResponse for ApiKeyDeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+}
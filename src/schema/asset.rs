@@ -1,5 +1,5 @@
 use crate::{
-    model::asset::AssetId,
+    model::{ListSort, asset::AssetId},
     schema::{
         CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
         deserialize_optional_url_encoded, serialize_datetime,
@@ -50,6 +50,9 @@ pub struct AssetResponse<T> {
     pub name: String,
     /// The asset symbol
     pub symbol: String,
+    /// How many decimals quantities of the asset have
+    #[serde(default)]
+    pub decimals: i16,
     #[serde(skip)]
     pub _phantom: PhantomData<T>,
 }
@@ -72,6 +75,7 @@ mod ssr {
                 updated_at: value.updated_at,
                 name: value.name,
                 symbol: value.symbol,
+                decimals: value.decimals,
                 _phantom: PhantomData,
             }
         }
@@ -100,6 +104,7 @@ mod ssr {
             Self {
                 name: value.name,
                 symbol: value.symbol,
+                decimals: value.decimals,
             }
         }
     }
@@ -109,6 +114,7 @@ mod ssr {
             Self {
                 name: value.name,
                 symbol: value.symbol,
+                sort: value.sort.unwrap_or_default(),
             }
         }
     }
@@ -145,6 +151,12 @@ mod ssr {
         }
     }
 
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+
     impl IntoResponse for DeleteResponse {
         fn into_response(self) -> Response {
             StatusCode::NO_CONTENT.into_response()
@@ -157,25 +169,35 @@ mod ssr {
 pub struct CreateRequest {
     pub name: String,
     pub symbol: String,
+    /// How many decimals quantities of the asset have, which cannot change
+    /// once the asset is created
+    #[serde(default)]
+    pub decimals: i16,
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
 #[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
 pub struct GetListRequest {
+    /// The exact name to filter on, percent-encoded once like any other
+    /// query parameter, so `50%25%20off` is `50% off`
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_optional_url_encoded"
     )]
     pub name: Option<String>,
-
+    /// The exact symbol to filter on, percent-encoded once
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_optional_url_encoded"
     )]
     pub symbol: Option<String>,
+    /// The order to list in, by `created_at` unless given
+    #[cfg_attr(feature = "ssr", param(inline, required = false))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ListSort>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,208 @@
+use crate::{
+    model::{
+        attachment::{AttachmentId, ExtractionStatus},
+        transaction::TransactionId,
+    },
+    schema::{
+        CreateResponse, GetList, GetResponse, deserialize_datetime, deserialize_datetime_option,
+        deserialize_quantity_option, serialize_datetime, serialize_datetime_option,
+        serialize_quantity_option,
+    },
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::attachment::{Attachment, AttachmentExtraction};
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct AttachmentResponse<T> {
+    pub id: AttachmentId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    /// The transaction the attachment belongs to
+    pub transaction_id: TransactionId,
+    /// The sanitized name of the uploaded file
+    pub filename: String,
+    /// The content type sniffed from the file
+    pub content_type: String,
+    /// The size of the file in bytes
+    pub size: i64,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct GetListRequest {
+    /// The transaction to list the attachments of
+    pub transaction_id: TransactionId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The attachments of the transaction
+    pub attachments: Vec<AttachmentResponse<GetList>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    pub transaction_id: TransactionId,
+    /// The name of the file, kept sanitized for display
+    pub filename: String,
+    /// One of `application/pdf`, `image/png`, `image/jpeg` or `image/heic`,
+    /// which the content must sniff as too
+    pub content_type: String,
+    /// The file, base64 encoded
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DeleteResponse;
+
+/// The receipt details read off an attachment, to prefill its transaction.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ExtractionResponse {
+    pub attachment_id: AttachmentId,
+    /// The extractor that ran
+    pub extractor: String,
+    pub status: ExtractionStatus,
+    /// The total of the receipt, in the smallest unit printed on it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_quantity_option",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub quantity: Option<Decimal>,
+    /// The date printed on the receipt
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub posted_at: Option<DateTime<Utc>>,
+    /// The merchant printed on the receipt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merchant: Option<String>,
+    /// Why the extraction failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub type AttachmentGetResponse = AttachmentResponse<GetResponse>;
+pub type AttachmentGetListResponse = GetListResponse;
+pub type AttachmentCreateResponse = AttachmentResponse<CreateResponse>;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl AttachmentResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<Attachment> for AttachmentResponse<T> {
+        fn from(value: Attachment) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                transaction_id: value.transaction_id,
+                filename: value.filename,
+                content_type: value.content_type,
+                size: value.size,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for AttachmentResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for AttachmentResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<Vec<Attachment>> for GetListResponse {
+        fn from(value: Vec<Attachment>) -> Self {
+            Self {
+                attachments: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<AttachmentExtraction> for ExtractionResponse {
+        fn from(value: AttachmentExtraction) -> Self {
+            Self {
+                attachment_id: value.attachment_id,
+                extractor: value.extractor,
+                status: value.status,
+                quantity: value.quantity.map(Decimal::from),
+                posted_at: value.posted_at,
+                merchant: value.merchant,
+                error: value.error,
+            }
+        }
+    }
+
+    impl IntoResponse for ExtractionResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for DeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{
+    model::{
+        account::AccountId,
+        asset::AssetId,
+        budget::{BudgetId, BudgetKind},
+    },
+    rounding::RoundingPolicy,
+    schema::{
+        CreateResponse, GetList, GetResponse, Quantity, deserialize_datetime,
+        deserialize_datetime_option, deserialize_quantity, deserialize_quantity_option,
+        serialize_datetime, serialize_datetime_option, serialize_quantity,
+        serialize_quantity_option,
+    },
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::budget::{Budget, BudgetTotal};
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct BudgetResponse<T> {
+    pub id: BudgetId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    pub name: String,
+    /// The asset the limit is in and spending is converted into
+    pub asset_id: AssetId,
+    pub kind: BudgetKind,
+    /// The limit of a `fixed` budget
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_quantity_option",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub amount: Option<Decimal>,
+    /// The percentage of income a `percent_of_income` budget allows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<i16>,
+    /// The accounts whose spending counts, or all of them if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_ids: Option<Vec<AccountId>>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The budgets of the user
+    pub budgets: Vec<BudgetResponse<GetList>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    pub name: String,
+    pub asset_id: AssetId,
+    #[serde(default)]
+    pub kind: BudgetKind,
+    /// The limit, required for `fixed` budgets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub amount: Option<Quantity>,
+    /// The percentage of income from 0 to 100, required for
+    /// `percent_of_income` budgets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<i16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_ids: Option<Vec<AccountId>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DeleteResponse;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct StatusRequest {
+    /// The start of the period, defaulting to the start of this month
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub start: Option<DateTime<Utc>>,
+    /// The exclusive end of the period, defaulting to a month after `start`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// Spending and income that had no price into the budget asset.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UnconvertedAmount {
+    pub asset_id: AssetId,
+    /// The spending of the budget accounts, in the asset
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub spent: Decimal,
+    /// The income of all accounts, in the asset
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub income: Decimal,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct StatusResponse {
+    pub budget_id: BudgetId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub start: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub end: DateTime<Utc>,
+    pub asset_id: AssetId,
+    pub kind: BudgetKind,
+    /// The spending of the budget accounts, in the budget asset
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub spent: Decimal,
+    /// The income of all accounts, in the budget asset
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub income: Decimal,
+    /// The most that may be spent, absent for a `percent_of_income` budget
+    /// without income
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_quantity_option",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub limit: Option<Decimal>,
+    /// What is left of the limit, negative once it is exceeded
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_quantity_option",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub remaining: Option<Decimal>,
+    /// Whether spending is within the limit. A budget without a limit is
+    /// never met.
+    pub met: bool,
+    /// Set when a `percent_of_income` budget has no income to take a
+    /// percentage of
+    pub no_income: bool,
+    /// The amounts left out of `spent` and `income` for want of a price
+    pub unconverted: Vec<UnconvertedAmount>,
+    /// How each converted transaction and the limit of a
+    /// `percent_of_income` budget were rounded to the decimals of the
+    /// budget asset, before `spent` and `income` totalled them
+    #[serde(default)]
+    pub rounding: RoundingPolicy,
+}
+
+pub type BudgetGetResponse = BudgetResponse<GetResponse>;
+pub type BudgetGetListResponse = GetListResponse;
+pub type BudgetCreateResponse = BudgetResponse<CreateResponse>;
+pub type BudgetStatusResponse = StatusResponse;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl BudgetResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<Budget> for BudgetResponse<T> {
+        fn from(value: Budget) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                name: value.name,
+                asset_id: value.asset_id,
+                kind: value.kind,
+                amount: value.amount,
+                percent: value.percent,
+                account_ids: value.account_ids,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for BudgetResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for BudgetResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<Vec<Budget>> for GetListResponse {
+        fn from(value: Vec<Budget>) -> Self {
+            Self {
+                budgets: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for DeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    impl StatusResponse {
+        pub fn new(
+            budget: &Budget,
+            start: DateTime<Utc>,
+            end: DateTime<Utc>,
+            totals: Vec<BudgetTotal>,
+            rounding: RoundingPolicy,
+        ) -> Self {
+            let mut spent = Decimal::ZERO;
+            let mut income = Decimal::ZERO;
+            let mut unconverted = vec![];
+            for total in totals {
+                match total.asset_id {
+                    None => {
+                        spent = total.spent;
+                        income = total.income;
+                    }
+                    Some(asset_id) if !total.spent.is_zero() || !total.income.is_zero() => {
+                        unconverted.push(UnconvertedAmount {
+                            asset_id,
+                            spent: total.spent,
+                            income: total.income,
+                        });
+                    }
+                    Some(_) => {}
+                }
+            }
+            let limit = budget.limit(income, rounding);
+            Self {
+                budget_id: budget.id,
+                start,
+                end,
+                asset_id: budget.asset_id,
+                kind: budget.kind,
+                spent,
+                income,
+                limit,
+                remaining: limit.map(|x| x.saturating_sub(spent)),
+                met: limit.is_some_and(|x| spent <= x),
+                no_income: budget.kind == BudgetKind::PercentOfIncome && income <= Decimal::ZERO,
+                unconverted,
+                rounding,
+            }
+        }
+    }
+
+    impl IntoResponse for StatusResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        authorization::PermissionSet,
+        config::{PageSize, RateLimitConfig},
+    };
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// An endpoint the caller may call.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct EndpointResponse {
+    /// The HTTP method of the endpoint
+    pub method: String,
+    /// The path of the endpoint, with its parameters in braces
+    pub path: String,
+}
+
+/// How many items a page of a resource holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PageSizeResponse {
+    /// The items a page holds when the request does not say
+    pub default: i64,
+    /// The most items a page holds
+    pub max: i64,
+}
+
+/// What the caller may do with one resource.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ResourceCapabilitiesResponse {
+    /// The name of the resource in the authorization policy
+    pub name: String,
+    /// `read_all`, `read` or `none`
+    pub read: String,
+    /// `create_all`, `create` or `none`
+    pub create: String,
+    /// `update_all`, `update` or `none`
+    pub update: String,
+    /// `delete_all`, `delete` or `none`
+    pub delete: String,
+    /// The endpoints of the resource the levels allow
+    pub endpoints: Vec<EndpointResponse>,
+    /// The page size of the list of the resource, if it is listed a page at
+    /// a time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<PageSizeResponse>,
+}
+
+/// How many requests the caller may make in a window of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct RateLimitResponse {
+    pub requests: u32,
+    pub window_seconds: u64,
+}
+
+/// The limits on the size of what the caller sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct LimitsResponse {
+    /// The largest attachment that can be uploaded
+    pub max_attachment_bytes: usize,
+    /// The longest notes of an account or transaction
+    pub max_notes_bytes: usize,
+    /// The most categorization rules a user can have
+    pub max_categorization_rules: usize,
+    /// The most changes a page of delta sync holds
+    pub max_sync_items: i64,
+}
+
+/// What the calling token can do and where.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CapabilitiesResponse {
+    pub resources: Vec<ResourceCapabilitiesResponse>,
+    pub rate_limit: RateLimitResponse,
+    pub limits: LimitsResponse,
+}
+
+impl CapabilitiesResponse {
+    /// Whether the caller may call the endpoint with `method` and the path
+    /// template `path`.
+    pub fn allows(&self, method: &str, path: &str) -> bool {
+        self.resources
+            .iter()
+            .flat_map(|resource| resource.endpoints.iter())
+            .any(|endpoint| endpoint.method == method && endpoint.path == path)
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl ResourceCapabilitiesResponse {
+        pub fn new(
+            name: &str,
+            permission_set: PermissionSet,
+            endpoints: Vec<EndpointResponse>,
+            page_size: Option<PageSize>,
+        ) -> Self {
+            Self {
+                name: name.to_owned(),
+                read: permission_set.read_level.as_str().to_owned(),
+                create: permission_set.create_level.as_str().to_owned(),
+                update: permission_set.update_level.as_str().to_owned(),
+                delete: permission_set.delete_level.as_str().to_owned(),
+                endpoints,
+                page_size: page_size.map(|x| PageSizeResponse {
+                    default: x.default,
+                    max: x.max,
+                }),
+            }
+        }
+    }
+
+    impl From<RateLimitConfig> for RateLimitResponse {
+        fn from(value: RateLimitConfig) -> Self {
+            Self {
+                requests: value.limit,
+                window_seconds: value.window.as_secs(),
+            }
+        }
+    }
+
+    impl IntoResponse for CapabilitiesResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{
+    model::{
+        account::AccountId,
+        categorization_rule::{CategorizationRuleField, CategorizationRuleId},
+    },
+    schema::{
+        CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
+        deserialize_quantity_option, serialize_datetime,
+    },
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::categorization_rule::CategorizationRule;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CategorizationRuleResponse<T> {
+    pub id: CategorizationRuleId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    pub field: CategorizationRuleField,
+    /// The regular expression a `description` rule searches for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// The smallest quantity a `quantity_range` rule matches, in whole units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub min_quantity: Option<Decimal>,
+    /// The largest quantity a `quantity_range` rule matches, in whole units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub max_quantity: Option<Decimal>,
+    /// The account an `account` rule matches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<AccountId>,
+    pub category: String,
+    pub priority: i32,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The rules of the user, in the order they are tried
+    pub categorization_rules: Vec<CategorizationRuleResponse<GetList>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    #[serde(default)]
+    pub field: CategorizationRuleField,
+    /// A regular expression, required for `description` rules. It matches
+    /// anywhere in the description, ignoring case.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// The inclusive bounds of a `quantity_range` rule in whole units, at
+    /// least one of which is required. Rules span assets, so an integer is
+    /// taken as whole units rather than the smallest unit of an asset.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub min_quantity: Option<Decimal>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub max_quantity: Option<Decimal>,
+    /// The account, required for `account` rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<AccountId>,
+    pub category: String,
+    /// Rules with a higher priority are tried first, and rules of the same
+    /// priority in the order they were created
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// Changes a rule. Giving another `field` clears what the rule matched on,
+/// so the request needs what the new field matches on.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UpdateRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<CategorizationRuleField>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub min_quantity: Option<Decimal>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub max_quantity: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<AccountId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DeleteResponse;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ApplyResponse {
+    /// How many uncategorized transactions the rules categorized
+    pub changed: u64,
+}
+
+pub type CategorizationRuleGetResponse = CategorizationRuleResponse<GetResponse>;
+pub type CategorizationRuleGetListResponse = GetListResponse;
+pub type CategorizationRuleCreateResponse = CategorizationRuleResponse<CreateResponse>;
+pub type CategorizationRuleUpdateResponse = CategorizationRuleResponse<UpdateResponse>;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl CategorizationRuleResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<CategorizationRule> for CategorizationRuleResponse<T> {
+        fn from(value: CategorizationRule) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                field: value.field,
+                pattern: value.pattern,
+                min_quantity: value.min_quantity,
+                max_quantity: value.max_quantity,
+                account_id: value.account_id,
+                category: value.category,
+                priority: value.priority,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for CategorizationRuleResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for CategorizationRuleResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for CategorizationRuleResponse<UpdateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<Vec<CategorizationRule>> for GetListResponse {
+        fn from(value: Vec<CategorizationRule>) -> Self {
+            Self {
+                categorization_rules: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for DeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    impl IntoResponse for ApplyResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
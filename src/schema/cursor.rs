@@ -0,0 +1,207 @@
+//! The plaintext layout of pagination cursors, by version.
+//!
+//! A cursor is encrypted with a [`CursorKey`] before it is handed out, and
+//! clients hold on to it for as long as they page, across releases. Its
+//! layout starts with a one-byte version tag so that a release can change
+//! what a cursor holds while the cursors of the release before still open:
+//! [`decode`] reads every layout it knows into the current [`Cursor`],
+//! filling in defaults for what an older one lacks. A version it doesn't
+//! know, such as one from a newer release that was rolled back, is an
+//! invalid cursor like any other.
+//!
+//! | Version | Layout |
+//! |---------|--------|
+//! | 1 | `offset`, as a little-endian `i64`, with no tag |
+//! | 2 | the tag, then `offset`, `max_items`, as little-endian `i64`s |
+//!
+//! Version 1 cursors were minted before there was a tag, and are told
+//! apart by their length, which no tagged layout may share. They predate
+//! cursors carrying their page size, so they walk the default page size of
+//! the resource being listed, which the caller supplies.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    api::{ApiError, ClientErrorCode},
+    model::cursor_key::{CursorKey, EncryptionError},
+};
+
+/// The version of the cursors this release mints.
+pub const CURSOR_VERSION: u8 = 2;
+
+/// The length of a version 1 cursor, which has no tag.
+const V1_LEN: usize = 8;
+
+/// The length of a version 2 cursor.
+const V2_LEN: usize = 17;
+
+/// Where a listing is up to, as decoded from any version of cursor.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Cursor {
+    pub offset: i64,
+    /// The page size the cursor was issued for
+    pub max_items: i64,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CursorError {
+    #[error("Unknown cursor version {0}.")]
+    UnknownVersion(u8),
+    #[error("A version {version} cursor can't be {len} bytes.")]
+    InvalidLength { version: u8, len: usize },
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+}
+
+impl From<CursorError> for ApiError {
+    fn from(_: CursorError) -> Self {
+        Self::client(ClientErrorCode::InvalidCursor, "Invalid cursor.")
+    }
+}
+
+/// Lays `cursor` out as the current version.
+pub fn encode(cursor: Cursor) -> [u8; V2_LEN] {
+    let mut bytes = [0; V2_LEN];
+    bytes[0] = CURSOR_VERSION;
+    bytes[1..9].copy_from_slice(&cursor.offset.to_le_bytes());
+    bytes[9..].copy_from_slice(&cursor.max_items.to_le_bytes());
+    bytes
+}
+
+/// Reads a cursor of any version this release knows, giving one that
+/// doesn't carry its page size `default_max_items`.
+pub fn decode(bytes: &[u8], default_max_items: i64) -> Result<Cursor, CursorError> {
+    if bytes.len() == V1_LEN {
+        return Ok(Cursor {
+            offset: read_i64(bytes),
+            max_items: default_max_items,
+        });
+    }
+    let (&version, rest) = bytes
+        .split_first()
+        .ok_or(CursorError::InvalidLength { version: 0, len: 0 })?;
+    match version {
+        2 if bytes.len() == V2_LEN => Ok(Cursor {
+            offset: read_i64(&rest[..8]),
+            max_items: read_i64(&rest[8..]),
+        }),
+        2 => Err(CursorError::InvalidLength {
+            version,
+            len: bytes.len(),
+        }),
+        version => Err(CursorError::UnknownVersion(version)),
+    }
+}
+
+fn read_i64(bytes: &[u8]) -> i64 {
+    let mut le_bytes = [0; 8];
+    le_bytes.copy_from_slice(bytes);
+    i64::from_le_bytes(le_bytes)
+}
+
+/// Encrypts `cursor` into the opaque token handed to clients.
+pub fn seal(cursor_key: &CursorKey, cursor: Cursor) -> Result<String, EncryptionError> {
+    cursor_key.encrypt_base64(encode(cursor))
+}
+
+/// Decrypts and reads the packed bytes of a cursor, see [`decode`].
+pub fn open(
+    cursor_key: &CursorKey,
+    packed_bytes: &[u8],
+    default_max_items: i64,
+) -> Result<Cursor, CursorError> {
+    decode(&cursor_key.decrypt_bytes(packed_bytes)?, default_max_items)
+}
+
+#[cfg(test)]
+mod test {
+    use base64::{
+        Engine,
+        alphabet::URL_SAFE,
+        engine::{GeneralPurpose, general_purpose},
+    };
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::model::cursor_key::CursorKeyId;
+
+    /// A version 1 cursor at offset 100, minted as the offset alone before
+    /// cursors had a version, and the key of `cursor_v1.sql` it was
+    /// encrypted with.
+    const V1_CURSOR: &str = "QUIPAAABAgMEBQYHCAkKCwTg7DV-O4W2IrVa_xD9he-SfNVt11G_aA";
+
+    fn v1_cursor_key() -> CursorKey {
+        CursorKey::new(CursorKeyId(1_000_001), (0..32).collect())
+    }
+
+    fn v1(offset: i64) -> Vec<u8> {
+        offset.to_le_bytes().to_vec()
+    }
+
+    proptest! {
+        #[test]
+        fn it_round_trips_the_current_version(offset: i64, max_items: i64) {
+            let cursor = Cursor { offset, max_items };
+            let bytes = encode(cursor);
+            prop_assert_eq!(bytes[0], CURSOR_VERSION);
+            prop_assert_eq!(decode(&bytes, 1), Ok(cursor));
+        }
+
+        #[test]
+        fn it_upgrades_version_1_cursors(offset: i64, default_max_items: i64) {
+            let cursor = Cursor { offset, max_items: default_max_items };
+            prop_assert_eq!(decode(&v1(offset), default_max_items), Ok(cursor));
+            prop_assert_eq!(decode(&encode(cursor), 1), Ok(cursor));
+        }
+
+        #[test]
+        fn it_seals_and_opens_cursors(offset: i64, max_items: i64) {
+            let cursor_key = CursorKey::new(CursorKeyId(1), rand::random::<[u8; 32]>().to_vec());
+            let cursor = Cursor { offset, max_items };
+            let sealed = seal(&cursor_key, cursor).unwrap();
+            let engine = GeneralPurpose::new(&URL_SAFE, general_purpose::NO_PAD);
+            let packed_bytes = engine.decode(sealed).unwrap();
+            prop_assert_eq!(open(&cursor_key, &packed_bytes, 1), Ok(cursor));
+        }
+
+        #[test]
+        fn it_rejects_versions_it_does_not_know(
+            version in (CURSOR_VERSION + 1)..=u8::MAX,
+            rest in prop::collection::vec(any::<u8>(), 16),
+        ) {
+            let bytes = [&[version][..], &rest].concat();
+            prop_assert_eq!(decode(&bytes, 1), Err(CursorError::UnknownVersion(version)));
+            prop_assert!(matches!(
+                ApiError::from(CursorError::UnknownVersion(version)),
+                ApiError::ClientError(ClientErrorCode::InvalidCursor, message) if message == "Invalid cursor."
+            ));
+        }
+
+        #[test]
+        fn it_rejects_layouts_of_the_wrong_length(
+            bytes in prop::collection::vec(any::<u8>(), 0..48),
+        ) {
+            prop_assume!(bytes.len() != V1_LEN && bytes.len() != V2_LEN);
+            prop_assert!(decode(&bytes, 1).is_err());
+        }
+    }
+
+    #[test]
+    fn it_opens_the_cursors_of_the_release_before_versions() {
+        let engine = GeneralPurpose::new(&URL_SAFE, general_purpose::NO_PAD);
+        let packed_bytes = engine.decode(V1_CURSOR).unwrap();
+        assert_eq!(
+            open(&v1_cursor_key(), &packed_bytes, 25),
+            Ok(Cursor {
+                offset: 100,
+                max_items: 25
+            })
+        );
+        assert_eq!(
+            decode(&[0], 25),
+            Err(CursorError::UnknownVersion(0)),
+            "version 0 was never minted"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    model::asset::AssetId,
+    schema::{
+        GetList, budget::StatusResponse, deserialize_quantity, serialize_quantity,
+        transaction::TransactionResponse,
+    },
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::account_balance::NetBalance;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// The balance of all the accounts of the user in an asset.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct NetBalanceResponse {
+    pub asset_id: AssetId,
+    pub symbol: String,
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub balance: Decimal,
+    /// The balance a month ago
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub last_month_balance: Decimal,
+}
+
+/// How a budget of the user is doing this month.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct BudgetSummaryResponse {
+    pub name: String,
+    #[serde(flatten)]
+    pub status: StatusResponse,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DashboardResponse {
+    /// The balances of the user in each asset they hold
+    pub balances: Vec<NetBalanceResponse>,
+    /// The latest transactions of the user by when they were posted
+    pub recent_transactions: Vec<TransactionResponse<GetList>>,
+    pub budgets: Vec<BudgetSummaryResponse>,
+}
+
+pub type DashboardGetResponse = DashboardResponse;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl From<NetBalance> for NetBalanceResponse {
+        fn from(value: NetBalance) -> Self {
+            Self {
+                asset_id: value.asset_id,
+                symbol: value.symbol,
+                balance: value.balance,
+                last_month_balance: value.previous_balance,
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::schema::{deserialize_date, serialize_date};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct BackfillRequest {
+    /// The first date to ingest the rates of
+    #[serde(
+        serialize_with = "serialize_date",
+        deserialize_with = "deserialize_date"
+    )]
+    pub from: NaiveDate,
+    /// The last date to ingest the rates of
+    #[serde(
+        serialize_with = "serialize_date",
+        deserialize_with = "deserialize_date"
+    )]
+    pub to: NaiveDate,
+}
+
+/// What ingesting the rates of one date did.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct BackfillDayResponse {
+    #[serde(
+        serialize_with = "serialize_date",
+        deserialize_with = "deserialize_date"
+    )]
+    pub date: NaiveDate,
+    /// How many rates were written
+    pub written: usize,
+    /// The symbols of the rates that name no asset
+    pub skipped: Vec<String>,
+    /// How many watchlist entries the new rates fired
+    #[serde(default)]
+    pub triggered: usize,
+    /// Why the date failed, if it did. Running the backfill again retries it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct BackfillResponse {
+    /// The dates of the range in order
+    pub days: Vec<BackfillDayResponse>,
+}
+
+#[cfg(feature = "fx")]
+mod fx {
+    use super::*;
+    use crate::fx::{DayReport, FxError};
+
+    impl From<Vec<(NaiveDate, Result<DayReport, FxError>)>> for BackfillResponse {
+        fn from(value: Vec<(NaiveDate, Result<DayReport, FxError>)>) -> Self {
+            Self {
+                days: value
+                    .into_iter()
+                    .map(|(date, result)| match result {
+                        Ok(report) => BackfillDayResponse {
+                            date,
+                            written: report.written,
+                            skipped: report.skipped,
+                            triggered: report.triggered,
+                            error: None,
+                        },
+                        Err(e) => BackfillDayResponse {
+                            date,
+                            written: 0,
+                            skipped: vec![],
+                            triggered: 0,
+                            error: Some(e.to_string()),
+                        },
+                    })
+                    .collect(),
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    model::export_schedule::{DestinationConfig, ExportFormat, ExportRunStatus, ExportScheduleId},
+    schema::{
+        CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
+        deserialize_datetime_option, serialize_datetime, serialize_datetime_option,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::export_schedule::ExportSchedule;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+fn default_cadence() -> String {
+    "@daily".to_owned()
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ExportScheduleResponse<T> {
+    pub id: ExportScheduleId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    pub format: ExportFormat,
+    /// The destination, without its credentials
+    pub destination: DestinationConfig,
+    /// When the export runs, as `@daily` or five cron fields in UTC
+    pub cadence: String,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub last_run_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_status: Option<ExportRunStatus>,
+    /// Why the last run failed, if it did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The export schedules of the user
+    pub export_schedules: Vec<ExportScheduleResponse<GetList>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub destination: DestinationConfig,
+    /// When the export runs, defaulting to `@daily`
+    #[serde(default = "default_cadence")]
+    pub cadence: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UpdateRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ExportFormat>,
+    /// The new destination. Credentials left out are kept from the current
+    /// destination when it is of the same kind.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<DestinationConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cadence: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DeleteResponse;
+
+/// How a CSV export is written. Options left out come from the locale,
+/// which defaults to the one the user prefers.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct ExportOptionsRequest {
+    /// One of `en-US`, `ko-KR` and `de-DE`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// One of `comma`, `semicolon` and `tab`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delimiter: Option<String>,
+    /// One of `rfc3339`, `YYYY-MM-DD`, `DD.MM.YYYY` and `MM/DD/YYYY`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// Starts the export with a UTF-8 byte order mark, for Excel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bom: Option<bool>,
+}
+
+pub type ExportScheduleGetResponse = ExportScheduleResponse<GetResponse>;
+pub type ExportScheduleGetListResponse = GetListResponse;
+pub type ExportScheduleCreateResponse = ExportScheduleResponse<CreateResponse>;
+pub type ExportScheduleUpdateResponse = ExportScheduleResponse<UpdateResponse>;
+pub type ExportScheduleRunResponse = ExportScheduleResponse<GetResponse>;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl ExportScheduleResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> ExportScheduleResponse<T> {
+        /// The response for a schedule, given its opened destination.
+        pub fn new(value: ExportSchedule, destination: DestinationConfig) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                format: value.format,
+                destination: destination.redacted(),
+                cadence: value.cadence,
+                last_run_at: value.last_run_at,
+                last_status: value.last_status,
+                last_error: value.last_error,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for ExportScheduleResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for ExportScheduleResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for ExportScheduleResponse<UpdateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for DeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{
+    model::{
+        account::AccountId,
+        import_profile::{ColumnMapping, ImportMapping, ImportProfileId, SignConvention},
+    },
+    schema::{
+        CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
+        serialize_datetime,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::import_profile::ImportProfile;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ImportProfileResponse<T> {
+    pub id: ImportProfileId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    /// The import profile name
+    pub name: String,
+    #[serde(flatten)]
+    pub mapping: ImportMapping,
+    /// The account imports go to unless another is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_account_id: Option<AccountId>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The import profiles of the user
+    pub import_profiles: Vec<ImportProfileResponse<GetList>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    pub name: String,
+    #[serde(flatten)]
+    pub mapping: ImportMapping,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_account_id: Option<AccountId>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UpdateRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column_mapping: Option<ColumnMapping>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimal_separator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimal_places: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign_convention: Option<SignConvention>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_account_id: Option<AccountId>,
+}
+
+impl UpdateRequest {
+    /// The mapping with the requested changes applied.
+    pub fn apply(&self, mut mapping: ImportMapping) -> ImportMapping {
+        if let Some(column_mapping) = &self.column_mapping {
+            mapping.column_mapping = column_mapping.clone();
+        }
+        if let Some(date_format) = &self.date_format {
+            mapping.date_format = date_format.clone();
+        }
+        if let Some(decimal_separator) = &self.decimal_separator {
+            mapping.decimal_separator = decimal_separator.clone();
+        }
+        if let Some(decimal_places) = self.decimal_places {
+            mapping.decimal_places = decimal_places;
+        }
+        if let Some(sign_convention) = self.sign_convention {
+            mapping.sign_convention = sign_convention;
+        }
+        mapping
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DeleteResponse;
+
+pub type ImportProfileGetResponse = ImportProfileResponse<GetResponse>;
+pub type ImportProfileGetListResponse = GetListResponse;
+pub type ImportProfileCreateResponse = ImportProfileResponse<CreateResponse>;
+pub type ImportProfileUpdateResponse = ImportProfileResponse<UpdateResponse>;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl ImportProfileResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<ImportProfile> for ImportProfileResponse<T> {
+        fn from(value: ImportProfile) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                mapping: value.mapping(),
+                name: value.name,
+                default_account_id: value.default_account_id,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for ImportProfileResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for ImportProfileResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for ImportProfileResponse<UpdateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<Vec<ImportProfile>> for GetListResponse {
+        fn from(value: Vec<ImportProfile>) -> Self {
+            Self {
+                import_profiles: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for DeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+}
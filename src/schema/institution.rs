@@ -1,20 +1,29 @@
 use crate::{
-    model::institution::InstitutionId,
+    model::{ListSort, asset::AssetId, institution::InstitutionId},
     schema::{
         CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
-        deserialize_optional_url_encoded, serialize_datetime,
+        deserialize_optional_url_encoded, deserialize_quantity, serialize_datetime,
+        serialize_quantity,
     },
 };
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
+        authorization::{
+            PermissionSet,
+            actions::{CreateLevel, DeleteLevel, UpdateLevel},
+        },
         model::{
             cursor_key::{CursorKey, EncryptionError},
-            institution::{Institution, InstitutionCreate, InstitutionFilter, InstitutionUpdate},
+            institution::{
+                Institution, InstitutionBalance, InstitutionCreate, InstitutionFilter,
+                InstitutionRollup, InstitutionUpdate,
+            },
         },
         schema::Pagination,
     };
@@ -23,6 +32,7 @@ mod ssr_imports {
         response::{IntoResponse, Response},
     };
     pub use http::StatusCode;
+    pub use std::collections::HashMap;
     pub use utoipa::{IntoParams, ToSchema};
 }
 
@@ -44,6 +54,20 @@ pub struct InstitutionResponse<T> {
     )]
     pub updated_at: DateTime<Utc>,
     pub name: String,
+    /// The institution this one is a part of
+    #[serde(default)]
+    pub parent_id: Option<InstitutionId>,
+    /// The asset new accounts at the institution default to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_asset_id: Option<AssetId>,
+    /// The direct children of the institution, present with `expand=children`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ssr", schema(no_recursion))]
+    pub children: Option<Vec<InstitutionResponse<GetList>>>,
+    /// The number of the caller's accounts at the institution, present with
+    /// `include_counts=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_count: Option<i64>,
 
     #[serde(skip)]
     pub _phantom: PhantomData<T>,
@@ -53,19 +77,42 @@ pub struct InstitutionResponse<T> {
 #[cfg_attr(feature = "ssr", derive(ToSchema))]
 pub struct CreateRequest {
     pub name: String,
+    /// The institution the new one is a part of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<InstitutionId>,
+    /// The asset new accounts at the institution default to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_asset_id: Option<AssetId>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct GetRequest {
+    /// Related data to include, only `children` is supported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expand: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
 #[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
 pub struct GetListRequest {
-    /// The name to filter on
+    /// The exact name to filter on, percent-encoded once like any other
+    /// query parameter, so `50%25%20off` is `50% off`
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_optional_url_encoded"
     )]
     pub name: Option<String>,
+    /// Whether to count the caller's accounts at each institution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_counts: Option<bool>,
+    /// The order to list in, by `created_at` unless given
+    #[cfg_attr(feature = "ssr", param(inline, required = false))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ListSort>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -85,6 +132,51 @@ pub struct UpdateRequest {
     /// The new institution name
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// The new parent institution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<InstitutionId>,
+    /// The new asset new accounts at the institution default to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_asset_id: Option<AssetId>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct BalanceResponse {
+    /// The asset of the balance
+    pub asset_id: AssetId,
+    /// The sum of the transaction quantities in the asset
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub quantity: Decimal,
+    /// The number of transactions making up the balance
+    pub transaction_count: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct RollupResponse {
+    /// The institution at the root of the rollup
+    pub institution_id: InstitutionId,
+    /// The number of institutions in the subtree, including the root
+    pub institution_count: i64,
+    /// The number of the caller's accounts in the subtree
+    pub account_count: i64,
+    /// The balance of each asset held across those accounts
+    pub balances: Vec<BalanceResponse>,
+}
+
+/// What the caller may do with institutions, so a client can hide what it
+/// would be refused.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PermissionsResponse {
+    pub create: bool,
+    pub update: bool,
+    pub delete: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -99,6 +191,12 @@ pub type InstitutionUpdateResponse = InstitutionResponse<UpdateResponse>;
 mod ssr {
     use super::*;
 
+    impl InstitutionResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
     impl<T> From<Institution> for InstitutionResponse<T> {
         fn from(value: Institution) -> Self {
             Self {
@@ -106,11 +204,30 @@ mod ssr {
                 created_at: value.created_at,
                 updated_at: value.updated_at,
                 name: value.name,
+                parent_id: value.parent_id,
+                default_asset_id: value.default_asset_id,
+                children: None,
+                account_count: None,
                 _phantom: PhantomData,
             }
         }
     }
 
+    impl InstitutionResponse<GetResponse> {
+        pub fn with_children(mut self, children: Vec<Institution>) -> Self {
+            self.children = Some(children.into_iter().map(|x| x.into()).collect());
+            self
+        }
+    }
+
+    impl GetRequest {
+        pub fn expand_children(&self) -> bool {
+            self.expand
+                .as_deref()
+                .is_some_and(|expand| expand.split(',').any(|x| x.trim() == "children"))
+        }
+    }
+
     impl IntoResponse for InstitutionResponse<CreateResponse> {
         fn into_response(self) -> Response {
             (StatusCode::CREATED, Json(self)).into_response()
@@ -131,13 +248,21 @@ mod ssr {
 
     impl From<CreateRequest> for InstitutionCreate {
         fn from(value: CreateRequest) -> Self {
-            Self { name: value.name }
+            Self {
+                name: value.name,
+                parent_id: value.parent_id,
+                default_asset_id: value.default_asset_id,
+            }
         }
     }
 
     impl From<GetListRequest> for InstitutionFilter {
         fn from(value: GetListRequest) -> Self {
-            Self { name: value.name }
+            Self {
+                name: value.name,
+                sort: value.sort.unwrap_or_default(),
+                ..Default::default()
+            }
         }
     }
 
@@ -162,6 +287,22 @@ mod ssr {
         }
     }
 
+    impl GetListResponse {
+        /// Fills in the account count of each institution, zero if it is
+        /// missing from `account_counts`.
+        pub fn with_account_counts(mut self, account_counts: HashMap<InstitutionId, i64>) -> Self {
+            for institution in &mut self.institutions {
+                institution.account_count = Some(
+                    account_counts
+                        .get(&institution.id)
+                        .copied()
+                        .unwrap_or_default(),
+                );
+            }
+            self
+        }
+    }
+
     impl IntoResponse for GetListResponse {
         fn into_response(self) -> Response {
             (StatusCode::OK, Json(self)).into_response()
@@ -170,7 +311,60 @@ mod ssr {
 
     impl From<UpdateRequest> for InstitutionUpdate {
         fn from(value: UpdateRequest) -> Self {
-            Self { name: value.name }
+            Self {
+                name: value.name,
+                parent_id: value.parent_id,
+                default_asset_id: value.default_asset_id,
+            }
+        }
+    }
+
+    impl RollupResponse {
+        pub fn new(institution_id: InstitutionId, rollup: InstitutionRollup) -> Self {
+            Self {
+                institution_id,
+                institution_count: rollup.institution_count,
+                account_count: rollup.account_count,
+                balances: rollup.balances.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl From<InstitutionBalance> for BalanceResponse {
+        fn from(value: InstitutionBalance) -> Self {
+            Self {
+                asset_id: value.asset_id,
+                quantity: value.quantity,
+                transaction_count: value.transaction_count,
+            }
+        }
+    }
+
+    impl IntoResponse for RollupResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<PermissionSet> for PermissionsResponse {
+        fn from(value: PermissionSet) -> Self {
+            Self {
+                create: value.create_level != CreateLevel::NoPermission,
+                update: value.update_level != UpdateLevel::NoPermission,
+                delete: value.delete_level != DeleteLevel::NoPermission,
+            }
+        }
+    }
+
+    impl IntoResponse for PermissionsResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
         }
     }
 
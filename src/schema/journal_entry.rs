@@ -0,0 +1,137 @@
+use crate::{
+    model::{account::AccountId, asset::AssetId, journal_entry::JournalEntryId},
+    schema::{
+        CreateResponse, GetList, GetResponse, Quantity, deserialize_datetime, serialize_datetime,
+        transaction::TransactionResponse,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::journal_entry::JournalEntryWithLegs;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// The most legs an entry can have.
+pub const MAX_LEGS: usize = 100;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct JournalEntryResponse<T> {
+    pub id: JournalEntryId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub posted_at: DateTime<Utc>,
+    pub description: Option<String>,
+    /// The transactions of the entry, which sum to zero in each asset
+    pub legs: Vec<TransactionResponse<GetList>>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct LegRequest {
+    pub account_id: AccountId,
+    pub asset_id: AssetId,
+    /// Positive for a debit of the account, negative for a credit
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub quantity: Quantity,
+    /// The notes of the leg, in markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub posted_at: DateTime<Utc>,
+    /// The description of the entry, which each of its legs is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// At least two legs, summing to zero in each asset
+    pub legs: Vec<LegRequest>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeleteResponse;
+
+pub type JournalEntryGetResponse = JournalEntryResponse<GetResponse>;
+pub type JournalEntryCreateResponse = JournalEntryResponse<CreateResponse>;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl JournalEntryResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<JournalEntryWithLegs> for JournalEntryResponse<T> {
+        fn from(value: JournalEntryWithLegs) -> Self {
+            let journal_entry = value.journal_entry;
+            Self {
+                id: journal_entry.id,
+                created_at: journal_entry.created_at,
+                updated_at: journal_entry.updated_at,
+                posted_at: journal_entry.posted_at,
+                description: journal_entry.description,
+                legs: value.legs.into_iter().map(|x| x.into()).collect(),
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for JournalEntryResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for JournalEntryResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for DeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+}
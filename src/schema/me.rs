@@ -0,0 +1,170 @@
+use crate::model::{asset::AssetId, user::UserId};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::user_preference::{UserPreference, UserPreferenceUpdate};
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// How many idle minutes the app locks after for users who chose nothing.
+pub const DEFAULT_IDLE_LOCK_MINUTES: i32 = 10;
+/// The most idle minutes a user may choose, a day.
+pub const MAX_IDLE_LOCK_MINUTES: i32 = 24 * 60;
+
+fn default_idle_lock_minutes() -> i32 {
+    DEFAULT_IDLE_LOCK_MINUTES
+}
+
+fn default_idle_lock_while_hidden() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PreferenceResponse {
+    /// The asset, usually the currency, the user keeps their books in
+    pub default_asset_id: Option<AssetId>,
+    /// The locale exports are formatted for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Whether transactions are only created as balanced journal entries
+    #[serde(default)]
+    pub double_entry: bool,
+    /// How many minutes without a mouse or keyboard event the app locks
+    /// after, never if 0
+    #[serde(default = "default_idle_lock_minutes")]
+    pub idle_lock_minutes: i32,
+    /// Whether time in a hidden tab counts towards locking the app, rather
+    /// than pausing it
+    #[serde(default = "default_idle_lock_while_hidden")]
+    pub idle_lock_while_hidden: bool,
+}
+
+impl Default for PreferenceResponse {
+    fn default() -> Self {
+        Self {
+            default_asset_id: None,
+            locale: None,
+            double_entry: false,
+            idle_lock_minutes: DEFAULT_IDLE_LOCK_MINUTES,
+            idle_lock_while_hidden: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PreferenceUpdateRequest {
+    /// The new default asset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_asset_id: Option<AssetId>,
+    /// The new locale exports are formatted for, one of `en-US`, `ko-KR`
+    /// and `de-DE`. Errors are written in its language when the request
+    /// has no `Accept-Language`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Whether to only create transactions as balanced journal entries with
+    /// `POST /api/journal-entries`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub double_entry: Option<bool>,
+    /// How many idle minutes to lock the app after, up to a day, or 0 to
+    /// never lock it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_lock_minutes: Option<i32>,
+    /// Whether time in a hidden tab counts towards locking the app
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_lock_while_hidden: Option<bool>,
+}
+
+/// The next thing a new user has to do before they can use Treasury.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    /// Create the user with `POST /api/users`
+    Register,
+    /// Choose a display name
+    Name,
+    /// Choose the default asset
+    Currency,
+    /// Create the first account
+    Account,
+    /// Nothing, onboarding is complete
+    Done,
+}
+
+impl OnboardingStep {
+    /// The first step of the onboarding that is not done yet.
+    pub fn next(
+        registered: bool,
+        name: Option<&str>,
+        default_asset_id: Option<AssetId>,
+        has_account: bool,
+    ) -> Self {
+        if !registered {
+            Self::Register
+        } else if name.is_none_or(|name| name.trim().is_empty()) {
+            Self::Name
+        } else if default_asset_id.is_none() {
+            Self::Currency
+        } else if !has_account {
+            Self::Account
+        } else {
+            Self::Done
+        }
+    }
+}
+
+/// How far a user is through onboarding, derived from their data.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct OnboardingStateResponse {
+    /// The next step to take
+    pub step: OnboardingStep,
+    /// Whether every step is done
+    pub complete: bool,
+    /// The user id, unless the user is not registered yet
+    pub user_id: Option<UserId>,
+    /// The current display name of the user
+    pub name: Option<String>,
+    /// The default asset of the user
+    pub default_asset_id: Option<AssetId>,
+    /// Whether the user has created an account
+    pub has_account: bool,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl From<Option<UserPreference>> for PreferenceResponse {
+        fn from(value: Option<UserPreference>) -> Self {
+            let Some(value) = value else {
+                return Self::default();
+            };
+            Self {
+                default_asset_id: value.default_asset_id,
+                locale: value.locale,
+                double_entry: value.double_entry,
+                idle_lock_minutes: value.idle_lock_minutes.unwrap_or(DEFAULT_IDLE_LOCK_MINUTES),
+                idle_lock_while_hidden: value.idle_lock_while_hidden,
+            }
+        }
+    }
+
+    impl From<PreferenceUpdateRequest> for UserPreferenceUpdate {
+        fn from(value: PreferenceUpdateRequest) -> Self {
+            Self {
+                default_asset_id: value.default_asset_id,
+                locale: value.locale,
+                double_entry: value.double_entry,
+                idle_lock_minutes: value.idle_lock_minutes,
+                idle_lock_while_hidden: value.idle_lock_while_hidden,
+            }
+        }
+    }
+}
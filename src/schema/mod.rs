@@ -1,12 +1,21 @@
-use chrono::{DateTime, Utc};
+use crate::api::{ApiError, ClientErrorCode};
+use chrono::{DateTime, Datelike, Days, FixedOffset, Months, NaiveDate, NaiveTime, Offset, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use thiserror::Error;
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
-        api::{ApiError, AppState},
-        model::cursor_key::{CursorKey, CursorKeyId, EncryptionError},
-        resource::{GetRepository, RepositoryError, cursor_key_repository::CursorKeyRepository},
+        api::AppState,
+        config::{PageSize, PagedResource},
+        model::cursor_key::{CursorKey, CursorKeyId, EncryptionError, cursor_key_id},
+        resource::{
+            GetRepository, MAX_LIMIT, RepositoryError, cursor_key_repository::CursorKeyRepository,
+            deadline,
+        },
+        schema::cursor::{self, Cursor},
     };
     pub use axum::{
         RequestPartsExt,
@@ -23,17 +32,45 @@ mod ssr_imports {
     pub use tracing::{debug, error};
     pub use utoipa::{IntoParams, ToSchema};
     pub use zerocopy::FromBytes;
-    pub use zerocopy_derive::{FromBytes, Immutable, IntoBytes};
 }
 
 #[cfg(feature = "ssr")]
 pub use ssr_imports::*;
 
 pub mod account;
+pub mod account_template;
+pub mod admin;
+pub mod alert_rule;
+pub mod announcement;
+pub mod api_key;
 pub mod asset;
+pub mod attachment;
+pub mod budget;
+pub mod capabilities;
+pub mod categorization_rule;
+#[cfg(feature = "ssr")]
+pub mod cursor;
+pub mod dashboard;
+pub mod exchange_rate;
+pub mod export_schedule;
+pub mod import_profile;
 pub mod institution;
+pub mod journal_entry;
+pub mod me;
+pub mod notes;
+pub mod passkey;
+pub mod proposal;
+pub mod quick_entry;
+pub mod report;
+pub mod seed;
+pub mod sync;
+pub mod text;
 pub mod transaction;
+pub mod transfer;
 pub mod user;
+pub mod user_session;
+pub mod version;
+pub mod watchlist_entry;
 
 #[cfg(feature = "ssr")]
 #[derive(Debug, Default, Clone, Deserialize, Serialize, IntoParams, ToSchema, Copy)]
@@ -48,6 +85,10 @@ pub struct Pagination {
     #[schema(value_type = String, required = false)]
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cursor: Option<Cursor>,
+    /// The page size of the resource being listed, see
+    /// [`Pagination::for_resource`]
+    #[serde(skip)]
+    pub page_size: PageSize,
 }
 
 #[cfg(not(feature = "ssr"))]
@@ -66,6 +107,84 @@ mod ssr {
             self.cursor.map(|x| x.offset).unwrap_or(0)
         }
 
+        /// The page size, taken from the cursor when there is one so that
+        /// paging in either direction keeps the size the walk started with.
+        pub fn limit(&self) -> i64 {
+            self.page_size
+                .clamp(self.cursor.map(|x| x.max_items).or(self.max_items))
+        }
+
+        /// Decodes a cursor from a query into the id of the key it was
+        /// encrypted with and its packed bytes.
+        pub fn decode_cursor(cursor: &str) -> Result<(CursorKeyId, Vec<u8>), ApiError> {
+            let engine = GeneralPurpose::new(&URL_SAFE, general_purpose::NO_PAD);
+            let cursor_bytes = engine.decode(cursor).map_err(|_| invalid_cursor())?;
+            let cursor_key_id = cursor_key_id(&cursor_bytes).map_err(|_| invalid_cursor())?;
+            Ok((cursor_key_id, cursor_bytes))
+        }
+
+        /// Decrypts the packed bytes of a cursor with the key they name. A
+        /// cursor that doesn't carry its page size is given
+        /// `UNSIZED_CURSOR` until [`Pagination::with_page_size`] knows the
+        /// default page size of the resource.
+        pub fn open_cursor(
+            cursor_key: &CursorKey,
+            cursor_bytes: &[u8],
+        ) -> Result<Cursor, ApiError> {
+            Ok(cursor::open(cursor_key, cursor_bytes, UNSIZED_CURSOR)?)
+        }
+
+        /// Builds the pagination from the raw `max_items` of a query and
+        /// its decrypted cursor.
+        pub fn parse(max_items: Option<&str>, cursor: Option<Cursor>) -> Result<Self, ApiError> {
+            let max_items = max_items
+                .map(|x| {
+                    x.parse::<i64>().map_err(|_| {
+                        ApiError::client(
+                            ClientErrorCode::InvalidQuery,
+                            "Could not parse max items.",
+                        )
+                    })
+                })
+                .transpose()?;
+
+            Ok(Self {
+                max_items,
+                cursor,
+                page_size: PageSize::default(),
+            })
+        }
+
+        /// Bounds the pagination by the configured page size of the
+        /// resource being listed.
+        pub fn for_resource(self, resource: PagedResource) -> Result<Self, ApiError> {
+            self.with_page_size(resource.page_size())
+        }
+
+        pub fn with_page_size(self, page_size: PageSize) -> Result<Self, ApiError> {
+            let cursor = self.cursor.map(|cursor| match cursor.max_items {
+                UNSIZED_CURSOR => Cursor {
+                    max_items: page_size.default,
+                    ..cursor
+                },
+                _ => cursor,
+            });
+            if let (Some(max_items), Some(cursor)) = (self.max_items, cursor)
+                && page_size.clamp(Some(max_items)) != cursor.max_items
+            {
+                return Err(ApiError::client(
+                    ClientErrorCode::InvalidCursor,
+                    "Max items does not match the page size of the cursor.",
+                ));
+            }
+
+            Ok(Self {
+                cursor,
+                page_size,
+                ..self
+            })
+        }
+
         pub fn next_cursor<T>(
             &self,
             results: &[T],
@@ -75,9 +194,13 @@ mod ssr {
                 None
             } else {
                 let next_offset = self.offset() + results.len() as i64;
-                Some(cursor_key.encrypt_base64(Cursor {
-                    offset: next_offset,
-                })?)
+                Some(cursor::seal(
+                    cursor_key,
+                    Cursor {
+                        offset: next_offset,
+                        max_items: self.limit(),
+                    },
+                )?)
             };
 
             Ok(next_cursor)
@@ -90,18 +213,37 @@ mod ssr {
             let prev_cursor = if self.offset() == 0 {
                 None
             } else {
-                let prev_offset = self
-                    .offset()
-                    .saturating_sub(self.max_items.unwrap_or(100))
-                    .max(0);
-                Some(cursor_key.encrypt_base64(Cursor {
-                    offset: prev_offset,
-                })?)
+                let prev_offset = self.offset().saturating_sub(self.limit()).max(0);
+                Some(cursor::seal(
+                    cursor_key,
+                    Cursor {
+                        offset: prev_offset,
+                        max_items: self.limit(),
+                    },
+                )?)
             };
             Ok(prev_cursor)
         }
     }
 
+    /// Opens an opaque token other than a cursor, such as a sync token,
+    /// encrypted with one of the cursor keys.
+    pub async fn open_token<T: FromBytes>(state: &AppState, token: &str) -> Result<T, ApiError> {
+        let (cursor_key_id, token_bytes) = Pagination::decode_cursor(token)?;
+        let cursor_key = get_cursor_key(state, cursor_key_id).await?;
+        cursor_key
+            .decrypt(&token_bytes)
+            .map_err(|_| invalid_cursor())
+    }
+
+    /// The page size of an opened cursor that doesn't carry one, which no
+    /// cursor is minted with since page sizes are at least 1.
+    const UNSIZED_CURSOR: i64 = 0;
+
+    fn invalid_cursor() -> ApiError {
+        ApiError::client(ClientErrorCode::InvalidCursor, "Invalid cursor.")
+    }
+
     // We need to make sure the cursor is opaque so that clients don't
     // rely on the implementation details.
     impl FromRequestParts<AppState> for Pagination {
@@ -114,51 +256,20 @@ mod ssr {
             let query_params = parts
                 .extract::<Query<HashMap<String, String>>>()
                 .await
-                .map(|Query(params)| params)
-                .map_err(|err| ApiError::ClientError(format!("{err:?}")))?;
-
-            let max_items =
-                if let Some(max_items) = query_params.get("max_items") {
-                    Some(max_items.parse::<i64>().map_err(|_| {
-                        ApiError::ClientError("Could not parse max items.".to_owned())
-                    })?)
-                } else {
-                    None
-                };
-            let cursor = if let Some(c) = query_params.get("cursor") {
-                let engine = GeneralPurpose::new(&URL_SAFE, general_purpose::NO_PAD);
-                let cursor_bytes = engine
-                    .decode(c)
-                    .map_err(|_| ApiError::ClientError("Invalid cursor.".to_owned()))?;
-                if cursor_bytes.len() < 16 {
-                    return Err(ApiError::ClientError("Invalid cursor.".to_owned()));
-                }
+                .map(|Query(params)| params)?;
 
-                let cursor_key_id_bytes = &cursor_bytes[0..4];
-                let cursor_key_id = CursorKeyId::read_from_bytes(cursor_key_id_bytes)
-                    .map_err(|_| ApiError::ClientError("Invalid cursor.".to_owned()))?;
-                let cursor_key = get_cursor_key(state, cursor_key_id).await?;
-                let cursor = cursor_key
-                    .decrypt(&cursor_bytes)
-                    .map_err(|_| ApiError::ClientError("Invalid cursor.".to_owned()))?;
-
-                Some(cursor)
-            } else {
-                None
+            let cursor = match query_params.get("cursor") {
+                Some(c) => {
+                    let (cursor_key_id, cursor_bytes) = Self::decode_cursor(c)?;
+                    let cursor_key = get_cursor_key(state, cursor_key_id).await?;
+                    Some(Self::open_cursor(&cursor_key, &cursor_bytes)?)
+                }
+                None => None,
             };
-
-            Ok(Self { max_items, cursor })
+            Self::parse(query_params.get("max_items").map(String::as_str), cursor)
         }
     }
 
-    #[cfg(feature = "ssr")]
-    #[derive(
-        Debug, Default, Deserialize, Clone, Copy, IntoBytes, Immutable, FromBytes, Serialize,
-    )]
-    pub struct Cursor {
-        pub offset: i64,
-    }
-
     #[cached(
         key = "String",
         convert = r##"{format!("{}", cursor_key_id)}"##,
@@ -171,7 +282,7 @@ mod ssr {
     ) -> Result<CursorKey, ApiError> {
         debug!("Refreshing cursor key");
         let cursor_key_repository = CursorKeyRepository {};
-        let transaction = state.connection_pool.begin().await.map_err(|e| {
+        let transaction = deadline::begin(&state.connection_pool).await.map_err(|e| {
             error!("{e}");
             ApiError::ServerError
         })?;
@@ -179,8 +290,10 @@ mod ssr {
             .get(transaction, cursor_key_id)
             .await
             .map_err(|e| match e {
-                RepositoryError::NotFound => ApiError::ClientError("Invalid cursor.".to_owned()),
-                RepositoryError::Sqlx(e) => {
+                RepositoryError::NotFound => {
+                    ApiError::client(ClientErrorCode::InvalidCursor, "Invalid cursor.")
+                }
+                e => {
                     error!("{e}");
                     ApiError::ServerError
                 }
@@ -189,32 +302,114 @@ mod ssr {
     }
 }
 
+/// How the text filters of a query string are read. The query extractor
+/// has already percent-decoded each value once, which is the whole of the
+/// encoding a client applies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilterDecoding {
+    /// Values are compared as the extractor decoded them, so a filter that
+    /// was encoded once, `%`, `+` and `&` included, matches exactly
+    #[default]
+    Raw,
+    /// Values that still read as percent-encoded after the extractor are
+    /// decoded again, for clients that encode filters twice. Deprecated,
+    /// since a filter that only looks encoded, like `50%41`, is read as
+    /// `50A`.
+    Compatible,
+}
+
+impl FilterDecoding {
+    /// The mode `value` names, falling back to raw for unset or unknown
+    /// values.
+    pub fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some("compatible") => Self::Compatible,
+            _ => Self::Raw,
+        }
+    }
+
+    /// Reads `value`, as the query extractor decoded it, as a filter.
+    /// Decoding it again never fails: a value that isn't UTF-8 once decoded
+    /// is kept as it is.
+    pub fn decode(self, value: String) -> String {
+        match self {
+            Self::Compatible if is_percent_encoded(&value) => urlencoding::decode(&value)
+                .map(Cow::into_owned)
+                .unwrap_or(value),
+            _ => value,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl FilterDecoding {
+    /// Reads `FILTER_DECODING`, which is `raw` or `compatible`.
+    pub fn from_env() -> Self {
+        static FILTER_DECODING: std::sync::OnceLock<FilterDecoding> = std::sync::OnceLock::new();
+        *FILTER_DECODING
+            .get_or_init(|| Self::parse(std::env::var("FILTER_DECODING").ok().as_deref()))
+    }
+}
+
+/// Whether `value` reads as the output of percent-encoding: at least one
+/// escape, and otherwise only the characters encoders leave as they are.
+/// A value with a space, a stray `%` or anything outside ASCII was
+/// decoded already.
+fn is_percent_encoded(value: &str) -> bool {
+    let mut bytes = value.bytes();
+    let mut escapes = 0;
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'%' => {
+                let escape = bytes.next().zip(bytes.next());
+                if !escape.is_some_and(|(x, y)| x.is_ascii_hexdigit() && y.is_ascii_hexdigit()) {
+                    return false;
+                }
+                escapes += 1;
+            }
+            x if x.is_ascii_alphanumeric() || b"-._~!'()*".contains(&x) => {}
+            _ => return false,
+        }
+    }
+    escapes > 0
+}
+
+/// Reads a filter of a query string in the configured [`FilterDecoding`].
+fn decode_filter(value: String) -> String {
+    #[cfg(feature = "ssr")]
+    let decoding = FilterDecoding::from_env();
+    #[cfg(not(feature = "ssr"))]
+    let decoding = FilterDecoding::default();
+    decoding.decode(value)
+}
+
 pub fn deserialize_url_encoded<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let encoded_str = String::deserialize(deserializer)?;
-
-    urlencoding::decode(&encoded_str)
-        .map_err(serde::de::Error::custom)
-        .map(|cow| cow.into_owned())
+    String::deserialize(deserializer).map(decode_filter)
 }
 
 pub fn deserialize_optional_url_encoded<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let opt = Option::<String>::deserialize(deserializer)?;
+    Ok(Option::<String>::deserialize(deserializer)?.map(decode_filter))
+}
 
-    match opt {
-        Some(encoded) => {
-            let decoded = urlencoding::decode(&encoded)
-                .map_err(serde::de::Error::custom)?
-                .into_owned();
-            Ok(Some(decoded))
-        }
-        None => Ok(None),
-    }
+pub fn serialize_date<S>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&date.format("%Y-%m-%d").to_string())
+}
+
+pub fn deserialize_date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let string = String::deserialize(deserializer)?;
+    NaiveDate::parse_from_str(&string, "%Y-%m-%d").map_err(serde::de::Error::custom)
 }
 
 pub fn serialize_datetime<S>(datetime: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
@@ -257,9 +452,7 @@ where
 {
     let opt = Option::<String>::deserialize(deserializer)?;
     if let Some(encoded) = opt {
-        let decoded = urlencoding::decode(&encoded)
-            .map_err(serde::de::Error::custom)?
-            .into_owned();
+        let decoded = decode_filter(encoded);
         let datetime = DateTime::parse_from_rfc3339(&decoded)
             .map_err(serde::de::Error::custom)?
             .to_utc();
@@ -269,9 +462,485 @@ where
     }
 }
 
+/// A period named relative to today, in the timezone of the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub enum DatePreset {
+    /// From the first of this month to the first of the next
+    #[serde(rename = "this_month")]
+    ThisMonth,
+    /// From the first of the last month to the first of this one
+    #[serde(rename = "last_month")]
+    LastMonth,
+    /// From the first of January to the end of today
+    #[serde(rename = "ytd")]
+    YearToDate,
+    /// The 90 days up to the end of today, today included
+    #[serde(rename = "last_90_days")]
+    Last90Days,
+}
+
+/// A range of time to filter or report on, given either as explicit bounds
+/// or as a preset, which the server resolves when it answers.
+///
+/// Bounds are dates, like `2025-03-01`, or RFC 3339 times. A date starts at
+/// midnight in `utc_offset`, and a date as `to` includes the whole day,
+/// while a time as `to` is excluded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct DateRange {
+    /// The start of the range, included
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_optional_url_encoded"
+    )]
+    pub from: Option<String>,
+    /// The end of the range
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_optional_url_encoded"
+    )]
+    pub to: Option<String>,
+    /// A period relative to today, instead of `from` and `to`: one of
+    /// `this_month`, `last_month`, `ytd` and `last_90_days`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ssr", param(inline))]
+    pub preset: Option<DatePreset>,
+    /// The offset from UTC of the timezone of the user, like `+09:00`,
+    /// which dates and presets are read in. UTC by default.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_optional_url_encoded"
+    )]
+    pub utc_offset: Option<String>,
+}
+
+/// A [`DateRange`] resolved into times, from `from` included until `to`
+/// excluded. Either end is open when it is `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResolvedDateRange {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl ResolvedDateRange {
+    pub fn is_open(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DateRangeError {
+    #[error("A date range is either a `preset` or `from` and `to`, not both.")]
+    PresetWithBounds,
+    #[error("`{0}` is neither a date like `2025-03-01` nor an RFC 3339 time.")]
+    InvalidBound(&'static str),
+    #[error("`utc_offset` is not an offset from UTC like `+09:00`.")]
+    InvalidOffset,
+    #[error("The date range ends before it starts.")]
+    Reversed,
+}
+
+impl From<DateRangeError> for ApiError {
+    fn from(value: DateRangeError) -> Self {
+        Self::client(ClientErrorCode::InvalidRequest, value.to_string())
+    }
+}
+
+/// Reads an offset like `+09:00`, `-0530` or `Z`. A `+` that was decoded
+/// from a query into a space is read as one.
+fn parse_utc_offset(offset: &str) -> Option<FixedOffset> {
+    let offset = offset.trim();
+    if offset.eq_ignore_ascii_case("z") || offset.eq_ignore_ascii_case("utc") {
+        return FixedOffset::east_opt(0);
+    }
+    let (sign, rest) = match offset.split_at_checked(1)? {
+        ("-", rest) => (-1, rest),
+        ("+", rest) => (1, rest),
+        _ => (1, offset),
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "00"),
+    };
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours = hours.parse::<u8>().ok()?;
+    let minutes = minutes.parse::<u8>().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (i32::from(hours) * 3600 + i32::from(minutes) * 60))
+}
+
+impl DateRange {
+    /// The range of a preset.
+    pub fn preset(preset: DatePreset) -> Self {
+        Self {
+            preset: Some(preset),
+            ..Default::default()
+        }
+    }
+
+    /// Whether no bound nor preset was asked for.
+    pub fn is_empty(&self) -> bool {
+        self.from.is_none() && self.to.is_none() && self.preset.is_none()
+    }
+
+    /// Resolves the range at `now`, reading dates and presets in the
+    /// offset of the user.
+    pub fn resolve(&self, now: DateTime<Utc>) -> Result<ResolvedDateRange, DateRangeError> {
+        let offset = match self.utc_offset.as_deref() {
+            Some(offset) => parse_utc_offset(offset).ok_or(DateRangeError::InvalidOffset)?,
+            None => Utc.fix(),
+        };
+        let midnight = |date: NaiveDate| {
+            date.and_time(NaiveTime::MIN)
+                .and_local_timezone(offset)
+                .single()
+                .map(|x| x.to_utc())
+        };
+        let range = match self.preset {
+            Some(_) if self.from.is_some() || self.to.is_some() => {
+                return Err(DateRangeError::PresetWithBounds);
+            }
+            Some(preset) => {
+                let today = now.with_timezone(&offset).date_naive();
+                let first_of_month = today.with_day(1).unwrap_or(today);
+                let (from, to) = match preset {
+                    DatePreset::ThisMonth => (
+                        first_of_month,
+                        first_of_month.checked_add_months(Months::new(1)),
+                    ),
+                    DatePreset::LastMonth => (
+                        first_of_month
+                            .checked_sub_months(Months::new(1))
+                            .unwrap_or(first_of_month),
+                        Some(first_of_month),
+                    ),
+                    DatePreset::YearToDate => (
+                        today.with_ordinal(1).unwrap_or(today),
+                        today.checked_add_days(Days::new(1)),
+                    ),
+                    DatePreset::Last90Days => (
+                        today.checked_sub_days(Days::new(89)).unwrap_or(today),
+                        today.checked_add_days(Days::new(1)),
+                    ),
+                };
+                ResolvedDateRange {
+                    from: midnight(from),
+                    to: to.and_then(midnight),
+                }
+            }
+            None => {
+                let bound = |name: &'static str, bound: &str, end: bool| {
+                    if let Ok(datetime) = DateTime::parse_from_rfc3339(bound) {
+                        return Ok(datetime.to_utc());
+                    }
+                    NaiveDate::parse_from_str(bound, "%Y-%m-%d")
+                        .ok()
+                        .and_then(|date| {
+                            if end {
+                                date.checked_add_days(Days::new(1))
+                            } else {
+                                Some(date)
+                            }
+                        })
+                        .and_then(midnight)
+                        .ok_or(DateRangeError::InvalidBound(name))
+                };
+                ResolvedDateRange {
+                    from: self
+                        .from
+                        .as_deref()
+                        .map(|x| bound("from", x, false))
+                        .transpose()?,
+                    to: self
+                        .to
+                        .as_deref()
+                        .map(|x| bound("to", x, true))
+                        .transpose()?,
+                }
+            }
+        };
+        if let (Some(from), Some(to)) = (range.from, range.to)
+            && to < from
+        {
+            return Err(DateRangeError::Reversed);
+        }
+        Ok(range)
+    }
+}
+
+/// How quantities are written in responses.
+///
+/// Quantities are decimals held at the precision of their asset. By default
+/// they are written the way clients from before that read them, as integers
+/// of the smallest unit of the asset. A client may ask for decimal strings
+/// of whole units instead with `?numbers=string` or an
+/// `Accept: application/json; numbers=string` profile, which also keeps
+/// JavaScript clients from losing precision past 2^53. Quantities too large
+/// for an `i64` are written as decimal strings either way.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFormat {
+    #[default]
+    Number,
+    String,
+}
+
+#[cfg(feature = "ssr")]
+tokio::task_local! {
+    /// The number format of the request being handled.
+    pub static NUMBER_FORMAT: NumberFormat;
+}
+
+impl NumberFormat {
+    /// The parameter selecting the string format, in both the query and the
+    /// `Accept` header.
+    pub const STRING_PARAMETER: &str = "numbers=string";
+
+    /// The format of the request being handled, or numbers outside of one.
+    pub fn current() -> Self {
+        #[cfg(feature = "ssr")]
+        {
+            NUMBER_FORMAT.try_with(|format| *format).unwrap_or_default()
+        }
+        #[cfg(not(feature = "ssr"))]
+        {
+            Self::default()
+        }
+    }
+
+    /// Reads the format asked for by the query or the `Accept` header.
+    #[cfg(feature = "ssr")]
+    pub fn from_request(headers: &http::HeaderMap, query: Option<&str>) -> Self {
+        let in_query = query
+            .unwrap_or_default()
+            .split('&')
+            .any(|pair| pair == Self::STRING_PARAMETER);
+        let in_accept = headers
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .flat_map(|media_range| media_range.split(';').skip(1))
+            .any(|parameter| {
+                parameter
+                    .trim()
+                    .eq_ignore_ascii_case(Self::STRING_PARAMETER)
+            });
+        if in_query || in_accept {
+            Self::String
+        } else {
+            Self::Number
+        }
+    }
+}
+
+/// A quantity as a client sends it.
+///
+/// Integers are in the smallest unit of the asset, as clients from before
+/// decimal quantities send them, so `150` of an asset with two decimals is
+/// `1.50`. Strings are decimals in whole units of the asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    Units(i64),
+    Decimal(Decimal),
+}
+
+impl Quantity {
+    /// The quantity in an asset with `decimals` decimals, held at exactly
+    /// that precision.
+    pub fn in_asset(self, decimals: u32) -> Result<Decimal, ApiError> {
+        let mut quantity = match self {
+            Self::Units(units) => Decimal::try_new(units, decimals).map_err(|e| {
+                ApiError::client(
+                    ClientErrorCode::InvalidQuantity,
+                    format!("Invalid quantity: {e}"),
+                )
+            })?,
+            Self::Decimal(quantity) => quantity,
+        };
+        if quantity.normalize().scale() > decimals {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidQuantity,
+                format!("The quantity has more decimals than the {decimals} of its asset."),
+            ));
+        }
+        quantity.rescale(decimals);
+        if quantity.scale() != decimals {
+            return Err(ApiError::client(
+                ClientErrorCode::InvalidQuantity,
+                "The quantity is too large for the decimals of its asset.",
+            ));
+        }
+        Ok(quantity)
+    }
+}
+
+impl From<i64> for Quantity {
+    fn from(value: i64) -> Self {
+        Self::Units(value)
+    }
+}
+
+impl From<Decimal> for Quantity {
+    fn from(value: Decimal) -> Self {
+        Self::Decimal(value)
+    }
+}
+
+impl Serialize for Quantity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Units(units) => serializer.serialize_i64(*units),
+            Self::Decimal(quantity) => serializer.serialize_str(&quantity.to_string()),
+        }
+    }
+}
+
+struct QuantityVisitor;
+
+impl serde::de::Visitor<'_> for QuantityVisitor {
+    type Value = Quantity;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an integer or a decimal string")
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Quantity::Units(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        i64::try_from(value).map(Quantity::Units).map_err(E::custom)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        value.parse().map(Quantity::Decimal).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Quantity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(QuantityVisitor)
+    }
+}
+
+pub fn serialize_quantity<S>(quantity: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    // Quantities are held at the precision of their asset, so the mantissa
+    // is the quantity in its smallest unit.
+    match (NumberFormat::current(), i64::try_from(quantity.mantissa())) {
+        (NumberFormat::Number, Ok(units)) => serializer.serialize_i64(units),
+        _ => serializer.serialize_str(&quantity.to_string()),
+    }
+}
+
+pub fn serialize_quantity_option<S>(
+    quantity: &Option<Decimal>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if let Some(quantity) = quantity {
+        serialize_quantity(quantity, serializer)
+    } else {
+        serializer.serialize_none()
+    }
+}
+
+/// Reads a quantity of a response. Without the asset at hand an integer is
+/// taken as whole units, so clients of assets with decimals ask for strings.
+pub fn deserialize_quantity<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Quantity::deserialize(deserializer)? {
+        Quantity::Units(units) => Ok(units.into()),
+        Quantity::Decimal(quantity) => Ok(quantity),
+    }
+}
+
+pub fn deserialize_quantity_option<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct ResponseQuantity(#[serde(deserialize_with = "deserialize_quantity")] Decimal);
+
+    Ok(Option::<ResponseQuantity>::deserialize(deserializer)?
+        .map(|ResponseQuantity(quantity)| quantity))
+}
+
+/// Documents a quantity as either an integer of the smallest unit of the
+/// asset or a decimal string.
+#[cfg(feature = "ssr")]
+pub fn quantity_schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+    use utoipa::openapi::schema::{
+        KnownFormat, ObjectBuilder, OneOfBuilder, Schema, SchemaFormat, Type,
+    };
+
+    Schema::OneOf(
+        OneOfBuilder::new()
+            .item(
+                ObjectBuilder::new()
+                    .schema_type(Type::Integer)
+                    .format(Some(SchemaFormat::KnownFormat(KnownFormat::Int64)))
+                    .description(Some("In the smallest unit of the asset.")),
+            )
+            .item(
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .pattern(Some("^-?[0-9]+(\\.[0-9]+)?$"))
+                    .description(Some("In whole units of the asset.")),
+            )
+            .description(Some(
+                "Clients may send either. Responses are strings when `numbers=string` is requested, otherwise integers.",
+            ))
+            .build(),
+    )
+    .into()
+}
+
 #[cfg(feature = "ssr")]
 pub use ssr::*;
 
+/// Asks for a bulk change to be checked and made in a database transaction
+/// that is rolled back, reporting what it would have changed.
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct DryRunRequest {
+    /// Report what the change would do without keeping any of it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(feature = "ssr", derive(ToSchema))]
 pub struct GetResponse;
@@ -284,3 +953,314 @@ pub struct CreateResponse;
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(feature = "ssr", derive(ToSchema))]
 pub struct UpdateResponse;
+
+#[cfg(all(test, feature = "ssr"))]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn cursor_key() -> CursorKey {
+        CursorKey::new(CursorKeyId(1), rand::random::<[u8; 32]>().to_vec())
+    }
+
+    fn extract(
+        cursor_key: &CursorKey,
+        max_items: Option<&str>,
+        cursor: &str,
+    ) -> Result<Pagination, ApiError> {
+        let (_, cursor_bytes) = Pagination::decode_cursor(cursor)?;
+        let cursor = Pagination::open_cursor(cursor_key, &cursor_bytes)?;
+        Pagination::parse(max_items, Some(cursor))?.with_page_size(PageSize::default())
+    }
+
+    proptest! {
+        #[test]
+        fn it_extracts_the_cursors_it_issues(offset in 0..i64::MAX, max_items in 1..=MAX_LIMIT) {
+            let cursor_key = cursor_key();
+            let cursor = cursor::seal(&cursor_key, Cursor { offset, max_items }).unwrap();
+            let max_items_param = max_items.to_string();
+            let pagination = extract(&cursor_key, Some(&max_items_param), &cursor).unwrap();
+            prop_assert_eq!(pagination.offset(), offset);
+            prop_assert_eq!(pagination.limit(), max_items);
+        }
+
+        #[test]
+        fn it_rejects_mutated_cursors_as_client_errors(
+            offset in 0..i64::MAX,
+            max_items in 1..=MAX_LIMIT,
+            index: prop::sample::Index,
+            replacement in "[A-Za-z0-9_=+/.-]",
+        ) {
+            let cursor_key = cursor_key();
+            let cursor = cursor::seal(&cursor_key, Cursor { offset, max_items }).unwrap();
+            let index = index.index(cursor.len());
+            let mutated = format!("{}{replacement}{}", &cursor[..index], &cursor[index + 1..]);
+            prop_assume!(mutated != cursor);
+            let result = extract(&cursor_key, None, &mutated);
+            prop_assert!(matches!(result, Err(ApiError::ClientError(..))), "{result:?}");
+        }
+
+        #[test]
+        fn it_rejects_arbitrary_cursors_as_client_errors(cursor in ".{0,96}") {
+            let result = extract(&cursor_key(), None, &cursor);
+            prop_assert!(matches!(result, Err(ApiError::ClientError(..))), "{result:?}");
+        }
+
+        #[test]
+        fn it_rejects_max_items_that_do_not_match_the_cursor(
+            max_items in 1..=MAX_LIMIT,
+            other in 1..=MAX_LIMIT,
+        ) {
+            prop_assume!(max_items != other);
+            let result = Pagination::parse(
+                Some(&other.to_string()),
+                Some(Cursor { offset: 0, max_items }),
+            )
+            .and_then(|pagination| pagination.with_page_size(PageSize::default()));
+            prop_assert!(matches!(result, Err(ApiError::ClientError(..))), "{result:?}");
+        }
+
+        #[test]
+        fn it_clamps_max_items_to_the_page_size(
+            max_items in proptest::option::of(-1000..1000i64),
+            default in 1..=MAX_LIMIT,
+            max in MAX_LIMIT..=500,
+        ) {
+            let page_size = PageSize { default, max };
+            let pagination = Pagination::parse(max_items.map(|x| x.to_string()).as_deref(), None)
+                .and_then(|pagination| pagination.with_page_size(page_size))
+                .unwrap();
+            let limit = pagination.limit();
+            prop_assert!((1..=max).contains(&limit));
+            prop_assert_eq!(limit, max_items.map_or(default, |x| x.clamp(1, max)));
+        }
+
+        #[test]
+        fn it_walks_offset_only_cursors_at_the_default_page_size(
+            offset in 0..i64::MAX,
+            default in 1..=MAX_LIMIT,
+        ) {
+            let cursor_key = cursor_key();
+            let cursor = cursor_key.encrypt_base64(offset).unwrap();
+            let (_, cursor_bytes) = Pagination::decode_cursor(&cursor).unwrap();
+            let cursor = Pagination::open_cursor(&cursor_key, &cursor_bytes).unwrap();
+            let pagination = Pagination::parse(Some(&default.to_string()), Some(cursor))
+                .and_then(|pagination| {
+                    pagination.with_page_size(PageSize { default, max: MAX_LIMIT })
+                })
+                .unwrap();
+            prop_assert_eq!(pagination.offset(), offset);
+            prop_assert_eq!(pagination.limit(), default);
+        }
+
+        #[test]
+        fn it_reads_integers_and_strings_of_a_quantity_alike(
+            units: i64,
+            decimals in 0..=18u32,
+        ) {
+            let from_units = Quantity::Units(units).in_asset(decimals).unwrap();
+            prop_assert_eq!(from_units.mantissa(), i128::from(units));
+            prop_assert_eq!(from_units.scale(), decimals);
+            let from_string = serde_json::from_value::<Quantity>(
+                serde_json::Value::String(from_units.to_string()),
+            )
+            .unwrap()
+            .in_asset(decimals)
+            .unwrap();
+            prop_assert_eq!(from_string.mantissa(), i128::from(units));
+            prop_assert_eq!(from_string.scale(), decimals);
+        }
+
+        #[test]
+        fn it_rejects_quantities_finer_than_their_asset(
+            units in 1..i64::MAX,
+            decimals in 0..18u32,
+        ) {
+            prop_assume!(units % 10 != 0);
+            let quantity = Decimal::new(units, decimals + 1);
+            let result = Quantity::Decimal(quantity).in_asset(decimals);
+            prop_assert!(matches!(result, Err(ApiError::ClientError(..))), "{result:?}");
+        }
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    fn range(from: &str, to: &str) -> ResolvedDateRange {
+        ResolvedDateRange {
+            from: Some(at(from)),
+            to: Some(at(to)),
+        }
+    }
+
+    #[test]
+    fn it_takes_filters_as_the_query_extractor_decoded_them() {
+        for value in ["50% off", "50%25", "A+B & C", "%EA%B0%80", "가계부 100%"] {
+            assert_eq!(FilterDecoding::Raw.decode(value.to_owned()), value);
+        }
+    }
+
+    #[test]
+    fn it_decodes_filters_encoded_twice_when_compatible() {
+        let decode = |value: &str| FilterDecoding::Compatible.decode(value.to_owned());
+        assert_eq!(decode("50%25%20off"), "50% off");
+        assert_eq!(decode("A%2BB%20%26%20C"), "A+B & C");
+        assert_eq!(decode("%EA%B0%80%EA%B3%84%EB%B6%80"), "가계부");
+        // Values with anything an encoder would have escaped were decoded
+        // already.
+        for value in ["50% off", "100%", "50%2", "A+B", "가계부 %25", "plain"] {
+            assert_eq!(decode(value), value);
+        }
+        // Escapes that don't decode to UTF-8 are kept rather than refused.
+        assert_eq!(decode("%FF"), "%FF");
+    }
+
+    #[test]
+    fn it_reads_the_filter_decoding_by_name() {
+        assert_eq!(FilterDecoding::parse(None), FilterDecoding::Raw);
+        assert_eq!(FilterDecoding::parse(Some("raw")), FilterDecoding::Raw);
+        assert_eq!(
+            FilterDecoding::parse(Some("compatible")),
+            FilterDecoding::Compatible
+        );
+        assert_eq!(FilterDecoding::parse(Some("twice")), FilterDecoding::Raw);
+    }
+
+    #[test]
+    fn it_resolves_presets_across_the_turn_of_the_year() {
+        let now = at("2025-01-15T10:00:00Z");
+        let resolve = |preset| DateRange::preset(preset).resolve(now).unwrap();
+        assert_eq!(
+            resolve(DatePreset::ThisMonth),
+            range("2025-01-01T00:00:00Z", "2025-02-01T00:00:00Z")
+        );
+        assert_eq!(
+            resolve(DatePreset::LastMonth),
+            range("2024-12-01T00:00:00Z", "2025-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            resolve(DatePreset::YearToDate),
+            range("2025-01-01T00:00:00Z", "2025-01-16T00:00:00Z")
+        );
+        assert_eq!(
+            resolve(DatePreset::Last90Days),
+            range("2024-10-18T00:00:00Z", "2025-01-16T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn it_resolves_presets_in_the_timezone_of_the_user() {
+        // New Year's Day in Seoul, still New Year's Eve in UTC.
+        let now = at("2024-12-31T20:00:00Z");
+        let seoul = |preset| DateRange {
+            utc_offset: Some("+09:00".to_owned()),
+            ..DateRange::preset(preset)
+        };
+        assert_eq!(
+            seoul(DatePreset::YearToDate).resolve(now).unwrap(),
+            range("2024-12-31T15:00:00Z", "2025-01-01T15:00:00Z")
+        );
+        assert_eq!(
+            seoul(DatePreset::LastMonth).resolve(now).unwrap(),
+            range("2024-11-30T15:00:00Z", "2024-12-31T15:00:00Z")
+        );
+        assert_eq!(
+            DateRange::preset(DatePreset::YearToDate)
+                .resolve(now)
+                .unwrap(),
+            range("2024-01-01T00:00:00Z", "2025-01-01T00:00:00Z")
+        );
+        // A `+` decoded from a query into a space.
+        let decoded = DateRange {
+            utc_offset: Some(" 09:00".to_owned()),
+            ..DateRange::preset(DatePreset::YearToDate)
+        };
+        assert_eq!(
+            decoded.resolve(now),
+            seoul(DatePreset::YearToDate).resolve(now)
+        );
+    }
+
+    #[test]
+    fn it_resolves_explicit_bounds() {
+        let now = at("2025-06-01T00:00:00Z");
+        let dates = DateRange {
+            from: Some("2025-03-01".to_owned()),
+            to: Some("2025-03-31".to_owned()),
+            utc_offset: Some("-0500".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            dates.resolve(now).unwrap(),
+            range("2025-03-01T05:00:00Z", "2025-04-01T05:00:00Z")
+        );
+        let times = DateRange {
+            from: Some("2025-03-01T12:00:00+09:00".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            times.resolve(now).unwrap(),
+            ResolvedDateRange {
+                from: Some(at("2025-03-01T03:00:00Z")),
+                to: None,
+            }
+        );
+        assert!(DateRange::default().resolve(now).unwrap().is_open());
+    }
+
+    #[test]
+    fn it_rejects_ranges_it_can_not_resolve() {
+        let now = at("2025-06-01T00:00:00Z");
+        let cases = [
+            (
+                DateRange {
+                    from: Some("2025-01-01".to_owned()),
+                    ..DateRange::preset(DatePreset::ThisMonth)
+                },
+                DateRangeError::PresetWithBounds,
+            ),
+            (
+                DateRange {
+                    to: Some("March".to_owned()),
+                    ..Default::default()
+                },
+                DateRangeError::InvalidBound("to"),
+            ),
+            (
+                DateRange {
+                    utc_offset: Some("+25:00".to_owned()),
+                    ..DateRange::preset(DatePreset::ThisMonth)
+                },
+                DateRangeError::InvalidOffset,
+            ),
+            (
+                DateRange {
+                    from: Some("2025-03-02".to_owned()),
+                    to: Some("2025-03-01".to_owned()),
+                    ..Default::default()
+                },
+                DateRangeError::Reversed,
+            ),
+        ];
+        for (date_range, error) in cases {
+            assert_eq!(date_range.resolve(now), Err(error.clone()));
+            assert!(matches!(ApiError::from(error), ApiError::ClientError(..)));
+        }
+    }
+
+    #[test]
+    fn it_reads_presets_by_their_documented_names() {
+        for (name, preset) in [
+            ("this_month", DatePreset::ThisMonth),
+            ("last_month", DatePreset::LastMonth),
+            ("ytd", DatePreset::YearToDate),
+            ("last_90_days", DatePreset::Last90Days),
+        ] {
+            let date_range =
+                serde_json::from_value::<DateRange>(serde_json::json!({ "preset": name })).unwrap();
+            assert_eq!(date_range, DateRange::preset(preset));
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::{ApiError, error::FieldError};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use ammonia::clean;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use pulldown_cmark::{Options, Parser, html::push_html};
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// The largest notes accepted on an account or transaction, in bytes.
+pub const MAX_NOTES_BYTES: usize = 10 * 1024;
+
+/// Rejects notes longer than [`MAX_NOTES_BYTES`].
+pub fn validate_notes(notes: Option<&str>) -> Result<(), ApiError> {
+    match notes {
+        Some(notes) if notes.len() > MAX_NOTES_BYTES => Err(ApiError::InvalidField(
+            FieldError::TooLarge("notes", MAX_NOTES_BYTES),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct NotesHtmlResponse {
+    /// The notes rendered from markdown to sanitized HTML
+    pub html: String,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl NotesHtmlResponse {
+        /// Renders markdown and strips anything unsafe from the resulting
+        /// HTML, including raw HTML embedded in the markdown.
+        pub fn render(markdown: &str) -> Self {
+            let parser = Parser::new_ext(
+                markdown,
+                Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS,
+            );
+            let mut html = String::new();
+            push_html(&mut html, parser);
+            Self { html: clean(&html) }
+        }
+    }
+
+    impl IntoResponse for NotesHtmlResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
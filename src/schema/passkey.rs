@@ -0,0 +1,170 @@
+use crate::{
+    model::{passkey::PasskeyId, webauthn_challenge::WebauthnChallengeId},
+    schema::{
+        CreateResponse, GetList, deserialize_datetime, deserialize_datetime_option,
+        serialize_datetime, serialize_datetime_option,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::passkey::Passkey;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PasskeyResponse<T> {
+    /// The passkey id
+    pub id: PasskeyId,
+    /// When the passkey was registered
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    /// When the passkey was updated
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    /// The passkey name
+    pub name: String,
+    /// When the passkey was last used for a step-up
+    #[serde(
+        default,
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The passkeys registered to the user
+    pub passkeys: Vec<PasskeyResponse<GetList>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct PasskeyDeleteResponse {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ChallengeResponse {
+    /// The id to send back when finishing the ceremony
+    pub challenge_id: WebauthnChallengeId,
+    /// The options to pass to `navigator.credentials`
+    #[cfg_attr(feature = "ssr", schema(value_type = Object))]
+    pub options: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct RegisterFinishRequest {
+    /// The challenge returned by the start of the ceremony
+    pub challenge_id: WebauthnChallengeId,
+    /// The display name of the new passkey
+    pub name: String,
+    /// The credential returned by `navigator.credentials.create`
+    #[cfg_attr(feature = "ssr", schema(value_type = Object))]
+    pub credential: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct StepUpFinishRequest {
+    /// The challenge returned by the start of the ceremony
+    pub challenge_id: WebauthnChallengeId,
+    /// The assertion returned by `navigator.credentials.get`
+    #[cfg_attr(feature = "ssr", schema(value_type = Object))]
+    pub credential: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct StepUpFinishResponse {
+    /// When the elevation granted to the session ends
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub expires_at: DateTime<Utc>,
+}
+
+pub type PasskeyGetListResponse = GetListResponse;
+pub type PasskeyCreateResponse = PasskeyResponse<CreateResponse>;
+pub type RegisterStartResponse = ChallengeResponse;
+pub type StepUpStartResponse = ChallengeResponse;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl PasskeyResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<Passkey> for PasskeyResponse<T> {
+        fn from(value: Passkey) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                name: value.name,
+                last_used_at: value.last_used_at,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for PasskeyResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl From<Vec<Passkey>> for GetListResponse {
+        fn from(value: Vec<Passkey>) -> Self {
+            Self {
+                passkeys: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl PasskeyDeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    impl IntoResponse for PasskeyDeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    model::{
+        asset::AssetId,
+        change_proposal::{ChangeProposalId, ChangeProposalKind, ChangeProposalStatus},
+        institution::InstitutionId,
+        user::UserId,
+    },
+    schema::{
+        CreateResponse, GetList, GetResponse, UpdateResponse, asset, deserialize_datetime,
+        deserialize_datetime_option, institution, serialize_datetime, serialize_datetime_option,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::{
+        model::{
+            change_proposal::ChangeProposal,
+            cursor_key::{CursorKey, EncryptionError},
+        },
+        schema::Pagination,
+    };
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ProposalResponse<T> {
+    pub id: ChangeProposalId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    pub kind: ChangeProposalKind,
+    /// The request the change is made with once approved
+    #[cfg_attr(feature = "ssr", schema(value_type = Object))]
+    pub payload: Value,
+    /// The user who proposed the change
+    pub proposer_id: UserId,
+    pub status: ChangeProposalStatus,
+    /// The admin who approved or rejected the proposal
+    pub reviewer_id: Option<UserId>,
+    /// Why the proposal was approved or rejected
+    pub review_note: Option<String>,
+    #[serde(
+        default,
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub reviewed_at: Option<DateTime<Utc>>,
+    /// The institution the proposal edits, or was created by approving it
+    pub institution_id: Option<InstitutionId>,
+    /// The asset created by approving the proposal
+    pub asset_id: Option<AssetId>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+/// An edit of an institution, with the fields to change.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct EditInstitutionRequest {
+    pub institution_id: InstitutionId,
+    #[serde(flatten)]
+    pub changes: institution::UpdateRequest,
+}
+
+/// The change to propose, with the request an admin would make it with.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+#[serde(tag = "kind", content = "payload", rename_all = "snake_case")]
+pub enum CreateRequest {
+    CreateInstitution(institution::CreateRequest),
+    EditInstitution(EditInstitutionRequest),
+    CreateAsset(asset::CreateRequest),
+}
+
+impl CreateRequest {
+    pub fn kind(&self) -> ChangeProposalKind {
+        match self {
+            Self::CreateInstitution(_) => ChangeProposalKind::CreateInstitution,
+            Self::EditInstitution(_) => ChangeProposalKind::EditInstitution,
+            Self::CreateAsset(_) => ChangeProposalKind::CreateAsset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct GetListRequest {
+    /// Only the proposals in this status
+    #[cfg_attr(feature = "ssr", param(inline, required = false))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ChangeProposalStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    pub proposals: Vec<ProposalResponse<GetList>>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewDecision {
+    Approve,
+    Reject,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ReviewRequest {
+    pub decision: ReviewDecision,
+    /// Why the proposal is approved or rejected, shown to the proposer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review_note: Option<String>,
+}
+
+impl ReviewDecision {
+    pub fn status(self) -> ChangeProposalStatus {
+        match self {
+            Self::Approve => ChangeProposalStatus::Approved,
+            Self::Reject => ChangeProposalStatus::Rejected,
+        }
+    }
+}
+
+pub type ProposalGetResponse = ProposalResponse<GetResponse>;
+pub type ProposalGetListResponse = GetListResponse;
+pub type ProposalCreateResponse = ProposalResponse<CreateResponse>;
+pub type ProposalReviewResponse = ProposalResponse<UpdateResponse>;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl ProposalResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<ChangeProposal> for ProposalResponse<T> {
+        fn from(value: ChangeProposal) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                kind: value.kind,
+                payload: value.payload.0,
+                proposer_id: value.proposer_id,
+                status: value.status,
+                reviewer_id: value.reviewer_id,
+                review_note: value.review_note,
+                reviewed_at: value.reviewed_at,
+                institution_id: value.institution_id,
+                asset_id: value.asset_id,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for ProposalResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for ProposalResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for ProposalResponse<UpdateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl GetListResponse {
+        pub fn new(
+            proposals: Vec<ChangeProposal>,
+            pagination: &Pagination,
+            cursor_key: &CursorKey,
+        ) -> Result<Self, EncryptionError> {
+            let proposals = proposals.into_iter().map(|x| x.into()).collect::<Vec<_>>();
+            let next_cursor = pagination.next_cursor(&proposals, cursor_key)?;
+            let prev_cursor = pagination.prev_cursor(cursor_key)?;
+            Ok(Self {
+                proposals,
+                next_cursor,
+                prev_cursor,
+            })
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    model::{account::AccountId, asset::AssetId},
+    schema::{
+        deserialize_datetime, deserialize_datetime_option, deserialize_quantity,
+        serialize_datetime, serialize_datetime_option, serialize_quantity,
+    },
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::journal_entry::TrialBalanceLine;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::{IntoParams, ToSchema};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct TrialBalanceRequest {
+    /// The time to total the transactions up to, now by default. Deprecated
+    /// for the `to` of a date range.
+    #[cfg_attr(feature = "ssr", param(deprecated), schema(deprecated))]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+/// The balance of an account in an asset, on the side of the ledger it
+/// falls on.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct TrialBalanceLineResponse {
+    pub account_id: AccountId,
+    pub asset_id: AssetId,
+    /// The balance when it is positive, zero otherwise
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub debit: Decimal,
+    /// The negated balance when it is negative, zero otherwise
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub credit: Decimal,
+}
+
+/// The debits and credits of all the accounts in an asset, which are equal
+/// when every transaction in it is a leg of a journal entry.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct TrialBalanceTotalResponse {
+    pub asset_id: AssetId,
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub debit: Decimal,
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub credit: Decimal,
+    /// Whether the debits equal the credits
+    pub balanced: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct TrialBalanceResponse {
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub as_of: DateTime<Utc>,
+    /// The time the transactions were totaled from, when a date range
+    /// started one, otherwise the start of the ledger
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub from: Option<DateTime<Utc>>,
+    /// The accounts with a balance, by asset
+    pub lines: Vec<TrialBalanceLineResponse>,
+    /// The totals of each asset
+    pub totals: Vec<TrialBalanceTotalResponse>,
+}
+
+pub type ReportTrialBalanceResponse = TrialBalanceResponse;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl From<TrialBalanceLine> for TrialBalanceLineResponse {
+        fn from(value: TrialBalanceLine) -> Self {
+            let zero = Decimal::new(0, value.balance.scale());
+            let (debit, credit) = if value.balance.is_sign_negative() {
+                (zero, -value.balance)
+            } else {
+                (value.balance, zero)
+            };
+            Self {
+                account_id: value.account_id,
+                asset_id: value.asset_id,
+                debit,
+                credit,
+            }
+        }
+    }
+
+    impl TrialBalanceResponse {
+        /// The trial balance of the lines, which come ordered by asset.
+        pub fn new(
+            from: Option<DateTime<Utc>>,
+            as_of: DateTime<Utc>,
+            lines: Vec<TrialBalanceLine>,
+        ) -> Self {
+            let lines = lines
+                .into_iter()
+                .map(TrialBalanceLineResponse::from)
+                .collect::<Vec<_>>();
+            let mut totals: Vec<TrialBalanceTotalResponse> = vec![];
+            for line in &lines {
+                match totals.last_mut() {
+                    Some(total) if total.asset_id == line.asset_id => {
+                        total.debit += line.debit;
+                        total.credit += line.credit;
+                    }
+                    _ => totals.push(TrialBalanceTotalResponse {
+                        asset_id: line.asset_id,
+                        debit: line.debit,
+                        credit: line.credit,
+                        balanced: false,
+                    }),
+                }
+            }
+            for total in &mut totals {
+                total.balanced = total.debit == total.credit;
+            }
+            Self {
+                as_of,
+                from,
+                lines,
+                totals,
+            }
+        }
+    }
+
+    impl IntoResponse for TrialBalanceResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
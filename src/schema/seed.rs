@@ -0,0 +1,119 @@
+use crate::schema::{deserialize_date, serialize_date};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// The data of a local environment, as a seed file describes it.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SeedFile {
+    /// Parents are listed before their children
+    #[serde(default)]
+    pub institutions: Vec<SeedInstitution>,
+    #[serde(default)]
+    pub assets: Vec<SeedAsset>,
+    #[serde(default)]
+    pub users: Vec<SeedUser>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SeedInstitution {
+    pub name: String,
+    /// The name of the parent institution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SeedAsset {
+    pub name: String,
+    pub symbol: String,
+    #[serde(default)]
+    pub decimals: i16,
+}
+
+/// A user, identified the way the identity provider does.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SeedUser {
+    pub name: String,
+    pub email: String,
+    pub iss: String,
+    pub sub: String,
+    #[serde(default)]
+    pub accounts: Vec<SeedAccount>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SeedAccount {
+    pub name: String,
+    /// The name of the institution of the account
+    pub institution: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<SeedTransactions>,
+}
+
+/// How to generate the random transactions of an account.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SeedTransactions {
+    pub count: usize,
+    /// The symbol of the asset of the transactions
+    pub asset: String,
+    /// The first date a transaction may be posted on
+    #[serde(
+        serialize_with = "serialize_date",
+        deserialize_with = "deserialize_date"
+    )]
+    pub from: NaiveDate,
+    /// The last date a transaction may be posted on
+    #[serde(
+        serialize_with = "serialize_date",
+        deserialize_with = "deserialize_date"
+    )]
+    pub to: NaiveDate,
+    /// The smallest quantity, in whole units of the asset
+    pub min: Decimal,
+    /// The largest quantity, in whole units of the asset
+    pub max: Decimal,
+    /// The descriptions to pick from. Transactions have none without any.
+    #[serde(default)]
+    pub descriptions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SeedRequest {
+    pub file: SeedFile,
+    /// Seeds the generator, so the same file and seed load the same data
+    #[serde(default)]
+    pub seed: u64,
+    /// Deletes every user and all of their data first
+    #[serde(default)]
+    pub wipe: bool,
+}
+
+/// How many rows loading a seed file created. Institutions, assets and
+/// users that already existed are reused and not counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SeedResponse {
+    pub institutions: usize,
+    pub assets: usize,
+    pub users: usize,
+    pub accounts: usize,
+    pub transactions: usize,
+}
@@ -0,0 +1,148 @@
+use crate::{
+    model::sync::SyncResource,
+    schema::{
+        GetList, account::AccountResponse, deserialize_datetime, serialize_datetime,
+        transaction::TransactionResponse,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::{
+        account::{Account, AccountId},
+        sync::SyncTombstone,
+        transaction::{Transaction, TransactionId},
+    };
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::{IntoParams, ToSchema};
+    pub use uuid::Uuid;
+    pub use zerocopy_derive::{FromBytes, Immutable, IntoBytes};
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct SyncRequest {
+    /// The `next_since` of the previous sync. Without one, everything is
+    /// returned as changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// The most changes to return, at most 500
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<i64>,
+}
+
+/// An account or transaction that was deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DeletedResponse {
+    pub resource: SyncResource,
+    /// The id of the deleted account or transaction
+    pub id: String,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SyncResponse {
+    /// The accounts created or updated since the token
+    pub accounts: Vec<AccountResponse<GetList>>,
+    /// The transactions created or updated since the token
+    pub transactions: Vec<TransactionResponse<GetList>>,
+    /// The accounts and transactions deleted since the token. Deleting an
+    /// account is reported for the account alone.
+    pub deleted: Vec<DeletedResponse>,
+    /// The token to pass as `since` on the next sync
+    pub next_since: String,
+    /// Whether there are more changes to fetch with `next_since` now
+    pub has_more: bool,
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    /// The watermarks of a delta sync, handed to clients encrypted as
+    /// `next_since`. The zeroed token is before every change.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoBytes, FromBytes, Immutable)]
+    #[repr(C)]
+    pub struct SyncToken {
+        /// The `updated_at` of the last account, in microseconds
+        pub account_updated_at: i64,
+        pub account_id: [u8; 16],
+        /// The `updated_at` of the last transaction, in microseconds
+        pub transaction_updated_at: i64,
+        pub transaction_id: i64,
+        /// The id of the last tombstone
+        pub tombstone_id: i64,
+    }
+
+    impl SyncToken {
+        pub fn account_watermark(&self) -> (DateTime<Utc>, AccountId) {
+            (
+                DateTime::from_timestamp_micros(self.account_updated_at).unwrap_or_default(),
+                AccountId(Uuid::from_bytes(self.account_id)),
+            )
+        }
+
+        pub fn transaction_watermark(&self) -> (DateTime<Utc>, TransactionId) {
+            (
+                DateTime::from_timestamp_micros(self.transaction_updated_at).unwrap_or_default(),
+                TransactionId(self.transaction_id),
+            )
+        }
+
+        /// Moves the watermarks past the last of each kind of change.
+        pub fn advance(
+            mut self,
+            accounts: &[Account],
+            transactions: &[Transaction],
+            tombstones: &[SyncTombstone],
+        ) -> Self {
+            if let Some(account) = accounts.last() {
+                self.account_updated_at = account.updated_at.timestamp_micros();
+                self.account_id = account.id.0.into_bytes();
+            }
+            if let Some(transaction) = transactions.last() {
+                self.transaction_updated_at = transaction.updated_at.timestamp_micros();
+                self.transaction_id = transaction.id.0;
+            }
+            if let Some(tombstone) = tombstones.last() {
+                self.tombstone_id = tombstone.id;
+            }
+            self
+        }
+    }
+
+    impl From<SyncTombstone> for DeletedResponse {
+        fn from(value: SyncTombstone) -> Self {
+            Self {
+                resource: value.resource,
+                id: value.resource_id,
+                deleted_at: value.deleted_at,
+            }
+        }
+    }
+
+    impl IntoResponse for SyncResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
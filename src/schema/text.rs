@@ -0,0 +1,219 @@
+use std::borrow::Cow;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::api::{ApiError, error::FieldError};
+
+/// A field of free text and how long it may be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextField {
+    /// What the field is called in errors
+    pub name: &'static str,
+    /// The most characters as a reader counts them, in grapheme clusters
+    pub max_graphemes: usize,
+    /// The most code points, which is what the column is sized in
+    pub max_chars: usize,
+}
+
+pub const USER_NAME: TextField = TextField {
+    name: "user name",
+    max_graphemes: 100,
+    max_chars: 254,
+};
+
+pub const ACCOUNT_NAME: TextField = TextField {
+    name: "account name",
+    max_graphemes: 100,
+    max_chars: 254,
+};
+
+pub const INSTITUTION_NAME: TextField = TextField {
+    name: "institution name",
+    max_graphemes: 100,
+    max_chars: 254,
+};
+
+pub const ASSET_NAME: TextField = TextField {
+    name: "asset name",
+    max_graphemes: 100,
+    max_chars: 254,
+};
+
+pub const ASSET_SYMBOL: TextField = TextField {
+    name: "asset symbol",
+    max_graphemes: 8,
+    max_chars: 8,
+};
+
+pub const TRANSACTION_DESCRIPTION: TextField = TextField {
+    name: "transaction description",
+    max_graphemes: 500,
+    max_chars: 2000,
+};
+
+pub const CATEGORY: TextField = TextField {
+    name: "category",
+    max_graphemes: 100,
+    max_chars: 254,
+};
+
+pub const QUICK_ENTRY_LABEL: TextField = TextField {
+    name: "quick entry label",
+    max_graphemes: 50,
+    max_chars: 254,
+};
+
+/// The codepoints that embed or override the direction of the text after
+/// them, which can make a name read as something it is not.
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+fn is_disallowed_control(c: char) -> bool {
+    c.is_control() && !c.is_whitespace()
+}
+
+impl TextField {
+    /// Strips bidi controls and normalizes the text to NFC, rejecting
+    /// control characters other than whitespace and text that is too long.
+    pub fn sanitize(&self, value: &str) -> Result<String, ApiError> {
+        if value.chars().any(is_disallowed_control) {
+            return Err(ApiError::InvalidField(FieldError::ControlCharacters(
+                self.name,
+            )));
+        }
+        let value = value
+            .chars()
+            .filter(|c| !is_bidi_control(*c))
+            .nfc()
+            .collect::<String>();
+        if value.graphemes(true).count() > self.max_graphemes
+            || value.chars().count() > self.max_chars
+        {
+            return Err(ApiError::InvalidField(FieldError::TooLong(
+                self.name,
+                self.max_graphemes,
+            )));
+        }
+        Ok(value)
+    }
+
+    pub fn sanitize_option(&self, value: Option<String>) -> Result<Option<String>, ApiError> {
+        value.map(|value| self.sanitize(&value)).transpose()
+    }
+}
+
+/// Makes text safe to write out, for text that was stored before it was
+/// sanitized: bidi controls are removed and other control characters that
+/// are not whitespace are replaced with U+FFFD.
+pub fn escape_output(value: &str) -> Cow<'_, str> {
+    if !value
+        .chars()
+        .any(|c| is_bidi_control(c) || is_disallowed_control(c))
+    {
+        return Cow::Borrowed(value);
+    }
+    value
+        .chars()
+        .filter(|c| !is_bidi_control(*c))
+        .map(|c| {
+            if is_disallowed_control(c) {
+                char::REPLACEMENT_CHARACTER
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    const FIELDS: [TextField; 7] = [
+        USER_NAME,
+        ACCOUNT_NAME,
+        INSTITUTION_NAME,
+        ASSET_NAME,
+        ASSET_SYMBOL,
+        TRANSACTION_DESCRIPTION,
+        CATEGORY,
+    ];
+
+    #[rstest]
+    #[case("A\0B")]
+    #[case("Bell\u{7}")]
+    #[case("\u{1b}[31mred")]
+    #[case("Del\u{7f}")]
+    #[case("C1\u{9b}")]
+    fn it_rejects_control_characters(#[case] value: &str) {
+        for field in FIELDS {
+            assert!(field.sanitize(value).is_err(), "{} {value:?}", field.name);
+        }
+    }
+
+    #[test]
+    fn it_strips_bidi_controls() {
+        for field in FIELDS {
+            assert_eq!(
+                field.sanitize("ab\u{202E}cd\u{2066}").unwrap(),
+                "abcd",
+                "{}",
+                field.name
+            );
+        }
+    }
+
+    #[test]
+    fn it_keeps_whitespace() {
+        for field in FIELDS {
+            assert_eq!(field.sanitize("a\tb").unwrap(), "a\tb", "{}", field.name);
+        }
+    }
+
+    #[test]
+    fn it_normalizes_to_nfc() {
+        for field in FIELDS {
+            assert_eq!(
+                field.sanitize("Cafe\u{301}").unwrap(),
+                "Caf\u{e9}",
+                "{}",
+                field.name
+            );
+        }
+    }
+
+    #[test]
+    fn it_counts_graphemes() {
+        for field in FIELDS {
+            // A family emoji is one grapheme of several code points.
+            let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+            let fits = field.max_chars / family.chars().count();
+            let value = family.repeat(fits.min(field.max_graphemes));
+            assert!(field.sanitize(&value).is_ok(), "{}", field.name);
+        }
+        assert!(ASSET_SYMBOL.sanitize("KRW").is_ok());
+        assert!(ASSET_SYMBOL.sanitize("\u{1F468}\u{200D}\u{1F469}").is_ok());
+    }
+
+    #[test]
+    fn it_rejects_long_text() {
+        for field in FIELDS {
+            let graphemes = "a".repeat(field.max_graphemes + 1);
+            assert!(field.sanitize(&graphemes).is_err(), "{}", field.name);
+            let emoji = "\u{1F600}".repeat(100_000);
+            assert!(field.sanitize(&emoji).is_err(), "{}", field.name);
+            // Few graphemes, but too many code points for the column.
+            let combining = format!("a{}", "\u{301}".repeat(field.max_chars + 1));
+            assert!(field.sanitize(&combining).is_err(), "{}", field.name);
+        }
+    }
+
+    #[test]
+    fn it_escapes_output() {
+        assert!(matches!(escape_output("Coffee"), Cow::Borrowed("Coffee")));
+        assert_eq!(escape_output("a\0b\u{202E}c\nd"), "a\u{FFFD}bc\nd");
+    }
+}
@@ -1,25 +1,45 @@
 use crate::{
-    model::{account::AccountId, asset::AssetId, transaction::TransactionId},
+    model::{
+        account::AccountId,
+        asset::AssetId,
+        categorization_rule::CategorizationRuleId,
+        import_profile::{ImportMapping, ImportProfileId},
+        journal_entry::JournalEntryId,
+        recategorization::{RecategorizationId, RecategorizationStatus},
+        transaction::{TransactionDirection, TransactionId},
+        transaction_history::TransactionHistoryId,
+    },
+    rounding::RoundingPolicy,
     schema::{
-        CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
-        deserialize_datetime_option, deserialize_optional_url_encoded, serialize_datetime,
-        serialize_datetime_option,
+        CreateResponse, GetList, GetResponse, Quantity, UpdateResponse,
+        categorization_rule::CategorizationRuleResponse, deserialize_datetime,
+        deserialize_datetime_option, deserialize_optional_url_encoded, deserialize_quantity,
+        deserialize_quantity_option, serialize_datetime, serialize_datetime_option,
+        serialize_quantity, serialize_quantity_option,
     },
 };
 #[cfg(test)]
 use chrono::SubsecRound;
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
+        api::{ApiError, ClientErrorCode},
+        import::{ImportError, ImportedRow},
         model::{
             cursor_key::{CursorKey, EncryptionError},
-            transaction::{Transaction, TransactionCreate, TransactionFilter, TransactionUpdate},
+            recategorization::Recategorization,
+            transaction::{
+                Transaction, TransactionConversion, TransactionCreate, TransactionFilter,
+                TransactionUpdate,
+            },
+            transaction_history::{TransactionHistory, TransactionSnapshot},
         },
-        schema::Pagination,
+        schema::{Pagination, ResolvedDateRange},
     };
     pub use axum::{
         Json,
@@ -54,7 +74,38 @@ pub struct TransactionResponse<T> {
     pub description: Option<String>,
     pub account_id: AccountId,
     pub asset_id: AssetId,
-    pub quantity: i64,
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub quantity: Decimal,
+    /// The transaction notes, in markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// The quantity in the `convert_to` asset, if a rate was available
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_quantity_option",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub converted_quantity: Option<Decimal>,
+    /// The rate used for `converted_quantity`, as a decimal string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_used: Option<String>,
+    /// The id of the transaction at the provider it was synced from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// The categorization rule that set the category, if one did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_rule_id: Option<CategorizationRuleId>,
+    /// The journal entry the transaction is a leg of, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub journal_entry_id: Option<JournalEntryId>,
 
     #[serde(skip)]
     pub _phantom: PhantomData<T>,
@@ -71,7 +122,14 @@ pub struct CreateRequest {
     pub description: Option<String>,
     pub account_id: AccountId,
     pub asset_id: AssetId,
-    pub quantity: i64,
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub quantity: Quantity,
+    /// The transaction notes, in markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// The category, which the categorization rules set if it is absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
 }
 
 #[cfg(test)]
@@ -81,7 +139,9 @@ impl<T> PartialEq<TransactionResponse<T>> for CreateRequest {
             && self.posted_at.round_subsecs(3) == other.posted_at.round_subsecs(3)
             && self.account_id == other.account_id
             && self.asset_id == other.asset_id
-            && self.quantity == other.quantity
+            && self.quantity.in_asset(other.quantity.scale()).ok() == Some(other.quantity)
+            && self.notes == other.notes
+            && (self.category.is_none() || self.category == other.category)
     }
 }
 
@@ -89,6 +149,8 @@ impl<T> PartialEq<TransactionResponse<T>> for CreateRequest {
 #[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
 #[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
 pub struct GetListRequest {
+    /// When the transaction happened at its institution, which is unrelated
+    /// to when it was recorded or last changed here
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -96,26 +158,50 @@ pub struct GetListRequest {
         deserialize_with = "deserialize_datetime_option"
     )]
     pub posted_at: Option<DateTime<Utc>>,
+    /// Only transactions posted strictly before this time. Deprecated for
+    /// the `to` of a date range.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         serialize_with = "serialize_datetime_option",
         deserialize_with = "deserialize_datetime_option"
     )]
+    #[cfg_attr(feature = "ssr", param(deprecated), schema(deprecated))]
     pub posted_before: Option<DateTime<Utc>>,
+    /// Only transactions posted strictly after this time. Deprecated for
+    /// the `from` of a date range.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         serialize_with = "serialize_datetime_option",
         deserialize_with = "deserialize_datetime_option"
     )]
+    #[cfg_attr(feature = "ssr", param(deprecated), schema(deprecated))]
     pub posted_after: Option<DateTime<Utc>>,
+    /// A quantity in whole units of the asset, like the rest of the
+    /// quantity filters
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub quantity: Option<i64>,
+    pub quantity: Option<Decimal>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub max_quantity: Option<i64>,
+    pub max_quantity: Option<Decimal>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub min_quantity: Option<i64>,
+    pub min_quantity: Option<Decimal>,
+    /// Only debits, which have a negative quantity, or only credits, which
+    /// have a positive one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<TransactionDirection>,
+    /// The largest quantity regardless of its sign. It applies along with
+    /// `min_quantity` and `max_quantity`, so a transaction has to be within
+    /// all of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_abs_quantity: Option<Decimal>,
+    /// The smallest quantity regardless of its sign, so `direction=debit`
+    /// with `min_abs_quantity=50000` is every debit of 50,000 or more
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_abs_quantity: Option<Decimal>,
+    /// Text the description contains, in any case, percent-encoded once
+    /// like any other query parameter. `%` and `_` match themselves, so
+    /// `50%25%20off` finds descriptions with `50% off` in them.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -126,12 +212,54 @@ pub struct GetListRequest {
     pub asset_id: Option<AssetId>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub account_id: Option<AccountId>,
+    /// The symbol of the asset to convert quantities into for display
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub convert_to: Option<String>,
+    /// Only transactions created strictly after this time
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only transactions created strictly before this time
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only transactions last changed strictly after this time
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Only transactions last changed strictly before this time
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub updated_before: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 #[cfg_attr(feature = "ssr", derive(ToSchema))]
 pub struct GetListResponse {
     pub transactions: Vec<TransactionResponse<GetList>>,
+    /// How many transactions had no rate into the `convert_to` asset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing_rates: Option<i64>,
+    /// How the converted quantities were rounded to the decimals of the
+    /// `convert_to` asset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<RoundingPolicy>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub next_cursor: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -153,16 +281,247 @@ pub struct UpdateRequest {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub quantity: Option<i64>,
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub quantity: Option<Quantity>,
+    /// The new transaction notes, in markdown, which an empty string clears
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// The new category, which is then no longer the doing of a rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DeleteResponse;
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ImportRequest {
+    /// The stored import profile to map the CSV with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_id: Option<ImportProfileId>,
+    /// A one-off mapping to use instead of a stored profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<ImportMapping>,
+    /// The account to import into, if not the default of the profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<AccountId>,
+    pub asset_id: AssetId,
+    /// The CSV text, starting with its header row
+    pub csv: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ImportResponse {
+    /// The transactions created from the rows of the CSV. Those of a dry run
+    /// were rolled back, so their ids don't exist.
+    pub transactions: Vec<TransactionResponse<CreateResponse>>,
+    /// Whether the import was a dry run that created nothing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ImportPreviewRow {
+    /// The line of the CSV the row starts on
+    pub line: usize,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub posted_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_quantity_option",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub quantity: Option<Decimal>,
+    /// Why the row could not be mapped, if it couldn't
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ImportPreviewResponse {
+    /// The account the transactions would be created in
+    pub account_id: AccountId,
+    pub asset_id: AssetId,
+    /// The first rows of the CSV with the mapping applied
+    pub rows: Vec<ImportPreviewRow>,
+}
+
+/// The fields of a transaction an edit can be reverted on.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct SnapshotResponse {
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub posted_at: DateTime<Utc>,
+    pub asset_id: AssetId,
+    pub description: Option<String>,
+    #[serde(
+        serialize_with = "serialize_quantity",
+        deserialize_with = "deserialize_quantity"
+    )]
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct HistoryResponse {
+    pub id: TransactionHistoryId,
+    /// When the transaction was edited
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    pub transaction_id: TransactionId,
+    /// The transaction before the edit, which a revert restores
+    pub before: SnapshotResponse,
+    /// The transaction as the edit left it
+    pub after: SnapshotResponse,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct HistoryGetListResponse {
+    /// The latest edits of the transaction, newest first
+    pub history: Vec<HistoryResponse>,
+}
+
+/// How many uncategorized transactions are listed unless a `limit` is
+/// given.
+pub const DEFAULT_UNCATEGORIZED: i64 = 20;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
+#[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
+pub struct UncategorizedRequest {
+    /// How many of the latest uncategorized transactions to list, at most
+    /// 100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UncategorizedResponse {
+    /// The latest transactions without a category by when they were posted
+    pub transactions: Vec<TransactionResponse<GetList>>,
+    /// How many transactions are without a category in all
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CategorizeRequest {
+    pub category: String,
+    /// Whether to also create a description rule setting the category
+    #[serde(default)]
+    pub create_rule: bool,
+    /// The pattern of the rule, which otherwise matches the description of
+    /// the transaction as it is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// The priority of the rule
+    #[serde(default)]
+    pub priority: i32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CategorizeResponse {
+    pub transaction: TransactionResponse<UpdateResponse>,
+    /// The rule created along with the category, if one was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<CategorizationRuleResponse<CreateResponse>>,
+}
+
+/// Sets one category on every transaction of the caller a filter matches.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct RecategorizeRequest {
+    /// The transactions to recategorize, filtered as they are listed
+    #[serde(default)]
+    pub filter: GetListRequest,
+    pub category: String,
+    /// Whether to recategorize the transactions, rather than only count
+    /// them
+    #[serde(default)]
+    pub execute: bool,
+}
+
+/// A recategorization that was executed, and how far it has come.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct RecategorizationResponse {
+    pub id: RecategorizationId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    /// The filter the transactions were recategorized by
+    #[cfg_attr(feature = "ssr", schema(value_type = Object))]
+    pub filter: serde_json::Value,
+    pub category: String,
+    /// How many transactions the filter matched when it started
+    pub matched: i64,
+    /// How many transactions changed so far. Those that already had the
+    /// category are left as they are.
+    pub changed: i64,
+    pub status: RecategorizationStatus,
+    /// Where to poll for the recategorization
+    pub url: String,
+    /// Why the recategorization failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct RecategorizeResponse {
+    /// How many transactions of the caller the filter matches
+    pub matched: i64,
+    /// The recategorization, unless only the count was asked for or it was
+    /// a dry run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recategorization: Option<RecategorizationResponse>,
+    /// How many transactions a dry run would have changed. Those that
+    /// already have the category are left as they are.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed: Option<i64>,
+    /// Whether the recategorization was a dry run that changed nothing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
 pub type TransactionGetResponse = TransactionResponse<GetResponse>;
 pub type TransactionGetListResponse = GetListResponse;
 pub type TransactionCreateResponse = TransactionResponse<CreateResponse>;
 pub type TransactionUpdateResponse = TransactionResponse<UpdateResponse>;
+pub type TransactionUncategorizedResponse = UncategorizedResponse;
+pub type TransactionCategorizeResponse = CategorizeResponse;
+pub type TransactionRecategorizeResponse = RecategorizeResponse;
+pub type TransactionRecategorizationResponse = RecategorizationResponse;
 
 #[cfg(feature = "ssr")]
 mod ssr {
@@ -185,11 +544,66 @@ mod ssr {
                 account_id: value.account_id,
                 asset_id: value.asset_id,
                 quantity: value.quantity,
+                notes: value.notes,
+                external_id: value.external_id,
+                category: value.category,
+                applied_rule_id: value.applied_rule_id,
+                journal_entry_id: value.journal_entry_id,
+                converted_quantity: None,
+                rate_used: None,
                 _phantom: PhantomData,
             }
         }
     }
 
+    impl From<Recategorization> for RecategorizationResponse {
+        fn from(value: Recategorization) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                filter: value.filter.0,
+                category: value.category,
+                matched: value.matched,
+                changed: value.changed,
+                status: value.status,
+                url: format!("/api/transactions/recategorize/{}", value.id),
+                error: value.error,
+            }
+        }
+    }
+
+    impl RecategorizationResponse {
+        /// A pending recategorization is accepted, to be polled until it
+        /// isn't.
+        pub fn status_code(&self) -> StatusCode {
+            match self.status {
+                RecategorizationStatus::Pending => StatusCode::ACCEPTED,
+                RecategorizationStatus::Complete | RecategorizationStatus::Failed => StatusCode::OK,
+            }
+        }
+    }
+
+    impl IntoResponse for RecategorizationResponse {
+        fn into_response(self) -> Response {
+            (self.status_code(), Json(self)).into_response()
+        }
+    }
+
+    impl RecategorizeResponse {
+        pub fn status_code(&self) -> StatusCode {
+            self.recategorization
+                .as_ref()
+                .map_or(StatusCode::OK, RecategorizationResponse::status_code)
+        }
+    }
+
+    impl IntoResponse for RecategorizeResponse {
+        fn into_response(self) -> Response {
+            (self.status_code(), Json(self)).into_response()
+        }
+    }
+
     impl IntoResponse for TransactionResponse<CreateResponse> {
         fn into_response(self) -> Response {
             (StatusCode::CREATED, Json(self)).into_response()
@@ -208,15 +622,99 @@ mod ssr {
         }
     }
 
-    impl From<CreateRequest> for TransactionCreate {
-        fn from(value: CreateRequest) -> Self {
+    impl ImportResponse {
+        /// Created, unless the import was a dry run.
+        pub fn status(&self) -> StatusCode {
+            if self.dry_run {
+                StatusCode::OK
+            } else {
+                StatusCode::CREATED
+            }
+        }
+
+        pub fn dry_run(self, dry_run: bool) -> Self {
+            Self { dry_run, ..self }
+        }
+    }
+
+    impl From<Vec<Transaction>> for ImportResponse {
+        fn from(value: Vec<Transaction>) -> Self {
             Self {
-                posted_at: value.posted_at,
-                description: value.description,
-                account_id: value.account_id,
-                asset_id: value.asset_id,
-                quantity: value.quantity,
+                transactions: value.into_iter().map(|x| x.into()).collect(),
+                dry_run: false,
+            }
+        }
+    }
+
+    impl IntoResponse for ImportResponse {
+        fn into_response(self) -> Response {
+            (self.status(), Json(self)).into_response()
+        }
+    }
+
+    impl ImportPreviewRow {
+        /// A row of the preview, with the quantity in the `scale` of the
+        /// asset imported into.
+        pub fn new(value: Result<ImportedRow, ImportError>, scale: u32) -> Self {
+            match value {
+                Ok(row) => Self {
+                    line: row.line,
+                    posted_at: row.posted_at.into(),
+                    description: row.description,
+                    quantity: Quantity::from(row.quantity).in_asset(scale).ok(),
+                    error: None,
+                },
+                Err(e) => Self {
+                    line: e.line().unwrap_or_default(),
+                    posted_at: None,
+                    description: None,
+                    quantity: None,
+                    error: e.to_string().into(),
+                },
+            }
+        }
+    }
+
+    impl IntoResponse for ImportPreviewResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl CreateRequest {
+        /// The transaction to create, with the quantity read in the `scale`
+        /// of its asset.
+        pub fn into_create(self, scale: u32) -> Result<TransactionCreate, ApiError> {
+            Ok(TransactionCreate {
+                posted_at: self.posted_at,
+                description: self.description,
+                account_id: self.account_id,
+                asset_id: self.asset_id,
+                quantity: self.quantity.in_asset(scale)?,
+                notes: self.notes,
+                external_id: None,
+                category: self.category,
+                applied_rule_id: None,
+                journal_entry_id: None,
+            })
+        }
+    }
+
+    impl GetListRequest {
+        /// The filter of the request, with the transactions posted in
+        /// `posted`. The date range replaces `posted_after` and
+        /// `posted_before`, which can't be given with it.
+        pub fn into_filter(self, posted: ResolvedDateRange) -> Result<TransactionFilter, ApiError> {
+            if !posted.is_open() && (self.posted_after.is_some() || self.posted_before.is_some()) {
+                return Err(ApiError::client(
+                    ClientErrorCode::InvalidRequest,
+                    "Use either `from`, `to` and `preset`, or the deprecated `posted_after` and `posted_before`, not both.",
+                ));
             }
+            let mut filter = TransactionFilter::from(self);
+            filter.posted_from = posted.from;
+            filter.posted_before = filter.posted_before.or(posted.to);
+            Ok(filter)
         }
     }
 
@@ -226,9 +724,17 @@ mod ssr {
                 posted_at: value.posted_at,
                 posted_before: value.posted_before,
                 posted_after: value.posted_after,
+                posted_from: None,
+                created_after: value.created_after,
+                created_before: value.created_before,
+                updated_after: value.updated_after,
+                updated_before: value.updated_before,
                 quantity: value.quantity,
                 min_quantity: value.min_quantity,
                 max_quantity: value.max_quantity,
+                direction: value.direction,
+                min_abs_quantity: value.min_abs_quantity,
+                max_abs_quantity: value.max_abs_quantity,
                 description: value.description,
                 account_id: value.account_id,
                 asset_id: value.asset_id,
@@ -250,10 +756,35 @@ mod ssr {
             let prev_cursor = pagination.prev_cursor(cursor_key)?;
             Ok(Self {
                 transactions,
+                missing_rates: None,
+                rounding: None,
                 next_cursor,
                 prev_cursor,
             })
         }
+
+        /// Fills in the converted quantities, rounded by `rounding`, and
+        /// counts the rows without a rate.
+        pub fn with_conversions(
+            mut self,
+            conversions: Vec<TransactionConversion>,
+            rounding: RoundingPolicy,
+        ) -> Self {
+            let mut missing_rates = 0;
+            for transaction in &mut self.transactions {
+                let conversion = conversions
+                    .iter()
+                    .find(|x| x.transaction_id == transaction.id);
+                transaction.converted_quantity = conversion.and_then(|x| x.converted_quantity);
+                transaction.rate_used = conversion.and_then(|x| x.rate_used.clone());
+                if transaction.rate_used.is_none() {
+                    missing_rates += 1;
+                }
+            }
+            self.missing_rates = Some(missing_rates);
+            self.rounding = Some(rounding);
+            self
+        }
     }
 
     impl IntoResponse for GetListResponse {
@@ -262,17 +793,61 @@ mod ssr {
         }
     }
 
-    impl From<UpdateRequest> for TransactionUpdate {
-        fn from(value: UpdateRequest) -> Self {
+    impl UpdateRequest {
+        /// The update to make, with the quantity read in the `scale` of the
+        /// asset the transaction is left in.
+        pub fn into_update(self, scale: u32) -> Result<TransactionUpdate, ApiError> {
+            Ok(TransactionUpdate {
+                asset_id: self.asset_id,
+                posted_at: self.posted_at,
+                description: self.description,
+                quantity: self
+                    .quantity
+                    .map(|quantity| quantity.in_asset(scale))
+                    .transpose()?,
+                notes: self.notes,
+                category: self.category,
+            })
+        }
+    }
+
+    impl From<TransactionSnapshot> for SnapshotResponse {
+        fn from(value: TransactionSnapshot) -> Self {
             Self {
-                asset_id: value.asset_id,
                 posted_at: value.posted_at,
+                asset_id: value.asset_id,
                 description: value.description,
                 quantity: value.quantity,
             }
         }
     }
 
+    impl From<TransactionHistory> for HistoryResponse {
+        fn from(value: TransactionHistory) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                transaction_id: value.transaction_id,
+                before: value.before().into(),
+                after: value.after().into(),
+            }
+        }
+    }
+
+    impl From<Vec<TransactionHistory>> for HistoryGetListResponse {
+        fn from(value: Vec<TransactionHistory>) -> Self {
+            Self {
+                history: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for HistoryGetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
     impl DeleteResponse {
         pub fn status() -> StatusCode {
             StatusCode::NO_CONTENT
@@ -0,0 +1,35 @@
+use crate::{
+    model::{account::AccountId, asset::AssetId},
+    schema::{Quantity, deserialize_datetime, serialize_datetime},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use utoipa::ToSchema;
+
+/// A move of an asset from one account to another, which is created as a
+/// journal entry of two legs.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    /// The account the asset leaves, whose leg is a credit
+    pub from_account_id: AccountId,
+    /// The account the asset arrives in, whose leg is a debit
+    pub to_account_id: AccountId,
+    pub asset_id: AssetId,
+    /// How much of the asset moves, which is positive
+    #[cfg_attr(feature = "ssr", schema(schema_with = crate::schema::quantity_schema))]
+    pub quantity: Quantity,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub posted_at: DateTime<Utc>,
+    /// The description of the transfer, which both of its legs are given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The notes of both legs, in markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
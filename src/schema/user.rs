@@ -1,5 +1,9 @@
 use crate::{
-    model::user::UserId,
+    model::{
+        ListSort,
+        login_event::{LoginEventId, LoginEventType},
+        user::UserId,
+    },
     schema::{
         CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
         deserialize_optional_url_encoded, serialize_datetime,
@@ -12,8 +16,10 @@ use std::marker::PhantomData;
 #[cfg(feature = "ssr")]
 mod ssr_imports {
     pub use crate::{
+        integrity::{CheckResult, IntegrityReport},
         model::{
             cursor_key::{CursorKey, EncryptionError},
+            login_event::LoginEvent,
             user::{User, UserFilter, UserUpdate},
         },
         schema::Pagination,
@@ -50,6 +56,11 @@ pub struct UserResponse<T> {
     pub name: String,
     /// The user email
     pub email: String,
+    /// Whether the email of the token the user registered with was
+    /// verified, shown to admins. Unknown for users registered before it was
+    /// recorded or created without a token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_verified_at_registration: Option<bool>,
 
     #[serde(skip)]
     pub _phantom: PhantomData<T>,
@@ -66,20 +77,26 @@ pub struct CreateRequest {
 #[cfg_attr(feature = "ssr", derive(ToSchema, IntoParams))]
 #[cfg_attr(feature = "ssr", into_params(parameter_in = Query))]
 pub struct GetListRequest {
-    /// The name to filter on
+    /// The exact name to filter on, percent-encoded once like any other
+    /// query parameter, so `50%25%20off` is `50% off`
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_optional_url_encoded"
     )]
     pub name: Option<String>,
-    /// The email to filter on
+    /// The exact email to filter on, percent-encoded once, so
+    /// `a%2Bb%40example.com` is `a+b@example.com`
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_optional_url_encoded"
     )]
     pub email: Option<String>,
+    /// The order to list in, by `created_at` unless given
+    #[cfg_attr(feature = "ssr", param(inline, required = false))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ListSort>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -88,6 +105,9 @@ pub struct UpdateRequest {
     /// The new user name
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// The new user email. Changing it requires a recent step-up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +125,66 @@ pub struct GetListResponse {
     pub prev_cursor: Option<String>,
 }
 
+/// What one consistency check found.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct IntegrityCheckResponse {
+    /// The name of the check
+    pub name: String,
+    /// What the check looks for
+    pub description: String,
+    /// How many inconsistent rows the check found
+    pub count: i64,
+    /// The ids of some of the inconsistent rows
+    pub sample_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct IntegrityResponse {
+    pub user_id: UserId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub checked_at: DateTime<Utc>,
+    /// The result of each check, whether or not it found anything
+    pub checks: Vec<IntegrityCheckResponse>,
+}
+
+/// An event in the login history of a user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct LoginEventResponse {
+    pub id: LoginEventId,
+    /// When the event happened
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    pub event_type: LoginEventType,
+    /// The issuer of the token of the session
+    pub issuer: String,
+    /// The network or a hash of the address of the client, when the server
+    /// is behind a trusted proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct ActivityGetListResponse {
+    /// The login history of the user, newest first
+    pub events: Vec<LoginEventResponse>,
+    /// The cursor to get the next set of events
+    pub next_cursor: Option<String>,
+    /// The cursor to get the previous set of events
+    pub prev_cursor: Option<String>,
+}
+
 pub type UserGetResponse = UserResponse<GetResponse>;
 pub type UserGetListResponse = GetListResponse;
 pub type UserCreateResponse = UserResponse<CreateResponse>;
@@ -128,11 +208,22 @@ mod ssr {
                 updated_at: value.updated_at,
                 name: value.name,
                 email: value.email,
+                email_verified_at_registration: value.email_verified_at_registration,
                 _phantom: PhantomData,
             }
         }
     }
 
+    impl<T> UserResponse<T> {
+        /// The response without what only admins see.
+        pub fn redacted(self) -> Self {
+            Self {
+                email_verified_at_registration: None,
+                ..self
+            }
+        }
+    }
+
     impl IntoResponse for UserResponse<CreateResponse> {
         fn into_response(self) -> Response {
             (StatusCode::CREATED, Json(self)).into_response()
@@ -156,6 +247,7 @@ mod ssr {
             Self {
                 name: value.name,
                 email: value.email,
+                sort: value.sort.unwrap_or_default(),
                 ..Default::default()
             }
         }
@@ -165,7 +257,28 @@ mod ssr {
         fn from(value: UpdateRequest) -> Self {
             Self {
                 name: value.name,
-                email: None,
+                email: value.email,
+            }
+        }
+    }
+
+    impl From<CheckResult> for IntegrityCheckResponse {
+        fn from(value: CheckResult) -> Self {
+            Self {
+                name: value.name.into(),
+                description: value.description.into(),
+                count: value.count,
+                sample_ids: value.sample_ids,
+            }
+        }
+    }
+
+    impl From<IntegrityReport> for IntegrityResponse {
+        fn from(value: IntegrityReport) -> Self {
+            Self {
+                user_id: value.user_id,
+                checked_at: value.checked_at,
+                checks: value.checks.into_iter().map(|x| x.into()).collect(),
             }
         }
     }
@@ -199,6 +312,42 @@ mod ssr {
         }
     }
 
+    impl From<LoginEvent> for LoginEventResponse {
+        fn from(value: LoginEvent) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                event_type: value.event_type,
+                issuer: value.issuer,
+                ip_address: value.ip_address,
+                user_agent: value.user_agent,
+            }
+        }
+    }
+
+    impl ActivityGetListResponse {
+        pub fn new(
+            events: Vec<LoginEvent>,
+            pagination: &Pagination,
+            cursor_key: &CursorKey,
+        ) -> Result<Self, EncryptionError> {
+            let events = events.into_iter().map(|x| x.into()).collect::<Vec<_>>();
+            let next_cursor = pagination.next_cursor(&events, cursor_key)?;
+            let prev_cursor = pagination.prev_cursor(cursor_key)?;
+            Ok(Self {
+                events,
+                next_cursor,
+                prev_cursor,
+            })
+        }
+    }
+
+    impl IntoResponse for ActivityGetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
     impl IntoResponse for GetListResponse {
         fn into_response(self) -> Response {
             (StatusCode::OK, Json(self)).into_response()
@@ -0,0 +1,111 @@
+use crate::{
+    model::user_session::UserSessionId,
+    schema::{deserialize_datetime, serialize_datetime},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::user_session::UserSession;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UserSessionResponse {
+    pub id: UserSessionId,
+    /// When the user logged in on the device
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    /// When the tokens of the session were last refreshed
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub last_refreshed_at: DateTime<Utc>,
+    /// The network or a hash of the address of the client at the last
+    /// refresh, when the server is behind a trusted proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Whether this is the session of the request
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The sessions of the user that have not been revoked, most recently
+    /// refreshed first
+    pub sessions: Vec<UserSessionResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UserSessionDeleteResponse {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct RevokeOthersResponse {
+    /// How many sessions were revoked
+    pub revoked: u64,
+}
+
+pub type UserSessionGetListResponse = GetListResponse;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl UserSessionResponse {
+        /// Marks the session the presented refresh token belongs to as the
+        /// current one.
+        pub fn new(value: UserSession, current_hash: Option<&[u8]>) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                last_refreshed_at: value.last_refreshed_at,
+                ip_address: value.ip_address,
+                user_agent: value.user_agent,
+                current: current_hash == Some(value.token_hash.as_slice()),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl UserSessionDeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    impl IntoResponse for UserSessionDeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+
+    impl IntoResponse for RevokeOthersResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+/// A version of the API. Each is mounted under its own prefix, except the
+/// first, which is mounted at `/api` itself.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+    #[default]
+    V1,
+    /// Writes quantities as decimal strings of whole units
+    V2,
+}
+
+impl ApiVersion {
+    pub const ALL: [Self; 2] = [Self::V1, Self::V2];
+
+    /// Where the endpoints of the version are mounted.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::V1 => "/api",
+            Self::V2 => "/api/v2",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct VersionResponse {
+    /// The version of the API the request was served by
+    pub api_version: ApiVersion,
+    /// Every version of the API being served
+    pub api_versions: Vec<ApiVersion>,
+    /// The version of the server
+    pub server_version: String,
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl IntoResponse for VersionResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    model::{
+        alert_rule::AlertChannel,
+        asset::AssetId,
+        watchlist_entry::{WatchDirection, WatchlistEntryId},
+    },
+    schema::{
+        CreateResponse, GetList, GetResponse, UpdateResponse, deserialize_datetime,
+        deserialize_datetime_option, deserialize_quantity_option, serialize_datetime,
+        serialize_datetime_option,
+    },
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[cfg(feature = "ssr")]
+mod ssr_imports {
+    pub use crate::model::watchlist_entry::WatchlistEntry;
+    pub use axum::{
+        Json,
+        response::{IntoResponse, Response},
+    };
+    pub use http::StatusCode;
+    pub use utoipa::ToSchema;
+}
+
+#[cfg(feature = "ssr")]
+use ssr_imports::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct WatchlistEntryResponse<T> {
+    pub id: WatchlistEntryId,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub updated_at: DateTime<Utc>,
+    /// The asset being watched
+    pub asset_id: AssetId,
+    /// The asset the price is in
+    pub quote_asset_id: AssetId,
+    pub symbol: String,
+    pub quote_symbol: String,
+    /// The price to be notified at, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub target_price: Option<Decimal>,
+    pub direction: WatchDirection,
+    pub channel: AlertChannel,
+    /// Whether the entry fires on every crossing rather than once
+    pub repeating: bool,
+    /// When the entry last fired. A one-shot entry stays quiet after it
+    /// until its target or direction changes.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub triggered_at: Option<DateTime<Utc>>,
+    /// The price the entry last fired at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub triggered_price: Option<Decimal>,
+    /// The latest price of the asset in the quote asset, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub latest_price: Option<Decimal>,
+    /// When the latest price applies
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime_option"
+    )]
+    pub latest_priced_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub _phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct GetListResponse {
+    /// The watchlist of the user, oldest entry first
+    pub watchlist_entries: Vec<WatchlistEntryResponse<GetList>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct CreateRequest {
+    pub asset_id: AssetId,
+    pub quote_asset_id: AssetId,
+    /// The price to be notified at in the quote asset, leaving the asset
+    /// only watched without one. Prices span assets, so an integer is taken
+    /// as whole units rather than the smallest unit of the quote asset.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub target_price: Option<Decimal>,
+    #[serde(default)]
+    pub direction: WatchDirection,
+    #[serde(default)]
+    pub channel: AlertChannel,
+    /// Whether to fire on every crossing rather than once
+    #[serde(default)]
+    pub repeating: bool,
+}
+
+/// Changes an entry. Giving a target or a direction arms a one-shot entry
+/// that already fired again.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct UpdateRequest {
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_quantity_option"
+    )]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub target_price: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<WatchDirection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<AlertChannel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeating: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(ToSchema))]
+pub struct DeleteResponse;
+
+pub type WatchlistEntryGetResponse = WatchlistEntryResponse<GetResponse>;
+pub type WatchlistEntryGetListResponse = GetListResponse;
+pub type WatchlistEntryCreateResponse = WatchlistEntryResponse<CreateResponse>;
+pub type WatchlistEntryUpdateResponse = WatchlistEntryResponse<UpdateResponse>;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use super::*;
+
+    impl WatchlistEntryResponse<CreateResponse> {
+        pub fn status() -> StatusCode {
+            StatusCode::CREATED
+        }
+    }
+
+    impl<T> From<WatchlistEntry> for WatchlistEntryResponse<T> {
+        fn from(value: WatchlistEntry) -> Self {
+            Self {
+                id: value.id,
+                created_at: value.created_at,
+                updated_at: value.updated_at,
+                asset_id: value.asset_id,
+                quote_asset_id: value.quote_asset_id,
+                symbol: value.symbol,
+                quote_symbol: value.quote_symbol,
+                target_price: value.target_price,
+                direction: value.direction,
+                channel: value.channel,
+                repeating: value.repeating,
+                triggered_at: value.triggered_at,
+                triggered_price: value.triggered_price,
+                latest_price: value.latest_price,
+                latest_priced_at: value.latest_priced_at,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl IntoResponse for WatchlistEntryResponse<CreateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::CREATED, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for WatchlistEntryResponse<GetResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for WatchlistEntryResponse<UpdateResponse> {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl From<Vec<WatchlistEntry>> for GetListResponse {
+        fn from(value: Vec<WatchlistEntry>) -> Self {
+            Self {
+                watchlist_entries: value.into_iter().map(|x| x.into()).collect(),
+            }
+        }
+    }
+
+    impl IntoResponse for GetListResponse {
+        fn into_response(self) -> Response {
+            (StatusCode::OK, Json(self)).into_response()
+        }
+    }
+
+    impl IntoResponse for DeleteResponse {
+        fn into_response(self) -> Response {
+            StatusCode::NO_CONTENT.into_response()
+        }
+    }
+
+    impl DeleteResponse {
+        pub fn status() -> StatusCode {
+            StatusCode::NO_CONTENT
+        }
+    }
+}
@@ -0,0 +1,559 @@
+//! Loads a seed file into the database for local development.
+//!
+//! A [`SeedFile`] names the institutions, assets and users of a realistic
+//! environment, the accounts of each user and how many transactions to
+//! generate for them. Everything is inserted through the repositories.
+//! Institutions, assets and users that already exist are reused so a file
+//! can be loaded again, while accounts and their transactions are always
+//! created. The transactions come from a [`StdRng`] seeded with
+//! [`SeedOptions::seed`], so the same file and seed load the same data.
+//!
+//! `treasury seed <file> [--wipe] [--seed <n>]` loads a file from the
+//! command line, and `POST /api/seed` does so for development deployments.
+
+use std::{env::var, path::PathBuf};
+
+use chrono::{DateTime, NaiveTime, TimeDelta, Utc};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::{info, instrument};
+
+use crate::{
+    api::{ApiError, ClientErrorCode},
+    model::{
+        account::AccountCreate,
+        asset::{AssetCreate, AssetFilter, MAX_DECIMALS},
+        institution::{InstitutionCreate, InstitutionFilter, InstitutionId},
+        transaction::TransactionCreate,
+        user::UserCreate,
+    },
+    resource::{
+        CreateRepository, GetListRepository, account_balance_repository::AccountBalanceRepository,
+        account_repository::AccountRepository, asset_repository::AssetRepository,
+        institution_repository::InstitutionRepository,
+        transaction_repository::TransactionRepository, user_repository::UserRepository,
+    },
+    schema::{
+        Quantity,
+        seed::{SeedFile, SeedResponse, SeedTransactions},
+    },
+    service::ServiceError,
+};
+
+/// The seed file loaded for local development.
+pub const SAMPLE_SEED_FILE: &str = "local/seed.json";
+
+#[derive(Debug, Error)]
+pub enum SeedError {
+    #[error("Usage: treasury seed <file> [--wipe] [--seed <n>]")]
+    Usage,
+    #[error("The seed file is invalid: {0}")]
+    Invalid(String),
+    #[error("Seeding is only allowed when `ENVIRONMENT=development`.")]
+    NotDevelopment,
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<SeedError> for ApiError {
+    fn from(value: SeedError) -> Self {
+        match value {
+            SeedError::Service(e) => Self::Service(e),
+            SeedError::NotDevelopment => Self::NotFound,
+            e => Self::client(ClientErrorCode::InvalidRequest, e.to_string()),
+        }
+    }
+}
+
+/// How a seed file is loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SeedOptions {
+    /// Seeds the generator of the transactions
+    pub seed: u64,
+    /// Deletes every user and all of their data first
+    pub wipe: bool,
+}
+
+impl SeedOptions {
+    /// Reads the arguments after `seed` on the command line into the path
+    /// of the seed file and the options.
+    pub fn from_args(args: &[String]) -> Result<(PathBuf, Self), SeedError> {
+        let mut path = None;
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--wipe" => options.wipe = true,
+                "--seed" => {
+                    options.seed = args
+                        .next()
+                        .and_then(|x| x.parse().ok())
+                        .ok_or(SeedError::Usage)?;
+                }
+                flag if flag.starts_with("--") => return Err(SeedError::Usage),
+                file if path.is_none() => path = Some(PathBuf::from(file)),
+                _ => return Err(SeedError::Usage),
+            }
+        }
+        Ok((path.ok_or(SeedError::Usage)?, options))
+    }
+}
+
+/// Loads the seed file named by the arguments after `seed` on the command
+/// line.
+pub async fn run(connection_pool: &PgPool, args: &[String]) -> Result<SeedResponse, SeedError> {
+    let (path, options) = SeedOptions::from_args(args)?;
+    let file = std::fs::read_to_string(&path)
+        .map_err(|e| SeedError::Invalid(format!("{}: {e}", path.display())))?;
+    let file = serde_json::from_str(&file)
+        .map_err(|e| SeedError::Invalid(format!("{}: {e}", path.display())))?;
+    seed(connection_pool, file, options).await
+}
+
+/// Whether the deployment is a local development one, the only kind that
+/// may be wiped or seeded over the API.
+pub fn is_development() -> bool {
+    var("ENVIRONMENT").is_ok_and(|v| v == "development")
+}
+
+/// A transaction drawn by [`generate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedTransaction {
+    pub posted_at: DateTime<Utc>,
+    pub quantity: Decimal,
+    pub description: Option<String>,
+}
+
+/// Draws the transactions of an account in an asset with `decimals`
+/// decimals, in the order they were posted. Quantities are uniform between
+/// the bounds and held at the precision of the asset, dates are uniform over
+/// the days of the range.
+pub fn generate(
+    transactions: &SeedTransactions,
+    decimals: u32,
+    rng: &mut impl Rng,
+) -> Result<Vec<GeneratedTransaction>, SeedError> {
+    let invalid = |reason: &str| {
+        SeedError::Invalid(format!(
+            "The transactions in {} {reason}.",
+            transactions.asset
+        ))
+    };
+    if transactions.from > transactions.to {
+        return Err(invalid("end before they start"));
+    }
+    if transactions.min > transactions.max {
+        return Err(invalid("have a minimum above their maximum"));
+    }
+    let smallest_unit = |bound: Decimal| {
+        Quantity::Decimal(bound)
+            .in_asset(decimals)
+            .ok()
+            .and_then(|bound| i64::try_from(bound.mantissa()).ok())
+    };
+    let (Some(min), Some(max)) = (
+        smallest_unit(transactions.min),
+        smallest_unit(transactions.max),
+    ) else {
+        return Err(invalid(
+            "have bounds that don't fit the decimals of the asset",
+        ));
+    };
+
+    let start = transactions.from.and_time(NaiveTime::MIN).and_utc();
+    let seconds = (transactions.to - transactions.from).num_seconds() + 24 * 60 * 60;
+    let mut generated = (0..transactions.count)
+        .map(|_| GeneratedTransaction {
+            posted_at: start + TimeDelta::seconds(rng.random_range(0..seconds)),
+            quantity: Decimal::new(rng.random_range(min..=max), decimals),
+            description: (!transactions.descriptions.is_empty()).then(|| {
+                transactions.descriptions[rng.random_range(0..transactions.descriptions.len())]
+                    .clone()
+            }),
+        })
+        .collect::<Vec<_>>();
+    generated.sort_by_key(|x| x.posted_at);
+    Ok(generated)
+}
+
+/// Deletes every user and every row that references one, keeping the
+/// institutions, assets and rates users share.
+#[instrument(skip_all)]
+pub async fn wipe(connection_pool: &PgPool) -> Result<(), SeedError> {
+    // Everything a user owns references the user, down to the transactions
+    // through their accounts.
+    sqlx::query(r#"TRUNCATE "user" CASCADE"#)
+        .execute(connection_pool)
+        .await
+        .map_err(ServiceError::from)?;
+    info!("Wiped the user data");
+    Ok(())
+}
+
+/// Loads `file`, wiping the user data first if asked to.
+#[instrument(skip_all, fields(seed = options.seed, wipe = options.wipe))]
+pub async fn seed(
+    connection_pool: &PgPool,
+    file: SeedFile,
+    options: SeedOptions,
+) -> Result<SeedResponse, SeedError> {
+    if options.wipe {
+        if !is_development() {
+            return Err(SeedError::NotDevelopment);
+        }
+        wipe(connection_pool).await?;
+    }
+
+    let begin = async || {
+        connection_pool
+            .begin()
+            .await
+            .map_err(|e| SeedError::Service(e.into()))
+    };
+    let mut report = SeedResponse::default();
+    let mut rng = StdRng::seed_from_u64(options.seed);
+
+    let mut institutions = Vec::<(String, InstitutionId)>::new();
+    let institution_id = |institutions: &[(String, InstitutionId)], name: &str| {
+        institutions
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, id)| *id)
+            .ok_or_else(|| SeedError::Invalid(format!("No institution is named `{name}`.")))
+    };
+    for institution in file.institutions {
+        let parent_id = institution
+            .parent
+            .as_deref()
+            .map(|parent| institution_id(&institutions, parent))
+            .transpose()?;
+        let existing = InstitutionRepository
+            .get_list(
+                begin().await?,
+                0,
+                Some(1),
+                InstitutionFilter {
+                    name: Some(institution.name.clone()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        let id = match existing.first() {
+            Some(existing) => existing.id,
+            None => {
+                report.institutions += 1;
+                InstitutionRepository
+                    .create(
+                        begin().await?,
+                        InstitutionCreate {
+                            name: institution.name.clone(),
+                            parent_id,
+                            default_asset_id: None,
+                        },
+                    )
+                    .await
+                    .map_err(ServiceError::from)?
+                    .id
+            }
+        };
+        institutions.push((institution.name, id));
+    }
+
+    for asset in file.assets {
+        if !(0..=MAX_DECIMALS).contains(&asset.decimals) {
+            return Err(SeedError::Invalid(format!(
+                "{} has more than {MAX_DECIMALS} decimals.",
+                asset.symbol
+            )));
+        }
+        let existing = AssetRepository
+            .get_list(
+                begin().await?,
+                0,
+                Some(1),
+                AssetFilter {
+                    symbol: Some(asset.symbol.clone()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        if existing.is_empty() {
+            report.assets += 1;
+            AssetRepository
+                .create(
+                    begin().await?,
+                    AssetCreate {
+                        name: asset.name,
+                        symbol: asset.symbol,
+                        decimals: asset.decimals,
+                    },
+                )
+                .await
+                .map_err(ServiceError::from)?;
+        }
+    }
+
+    for user in file.users {
+        let existing = UserRepository
+            .get_by_iss_and_sub(begin().await?, user.iss.clone(), user.sub.clone())
+            .await
+            .map_err(ServiceError::from)?;
+        let user_id = match existing {
+            Some(existing) => existing.id,
+            None => {
+                report.users += 1;
+                UserRepository
+                    .create(
+                        begin().await?,
+                        UserCreate {
+                            name: user.name,
+                            email: user.email,
+                            sub: user.sub,
+                            iss: user.iss,
+                            email_verified: None,
+                        },
+                    )
+                    .await
+                    .map_err(ServiceError::from)?
+                    .id
+            }
+        };
+
+        for account in user.accounts {
+            let created = AccountRepository
+                .create(
+                    begin().await?,
+                    AccountCreate {
+                        name: account.name,
+                        institution_id: institution_id(&institutions, &account.institution)?,
+                        user_id,
+                        notes: account.notes,
+                        default_asset_id: None,
+                    },
+                )
+                .await
+                .map_err(ServiceError::from)?;
+            report.accounts += 1;
+
+            let Some(transactions) = account.transactions else {
+                continue;
+            };
+            let asset = AssetRepository
+                .get_list(
+                    begin().await?,
+                    0,
+                    Some(1),
+                    AssetFilter {
+                        symbol: Some(transactions.asset.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(ServiceError::from)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    SeedError::Invalid(format!("No asset has the symbol `{}`.", transactions.asset))
+                })?;
+            for transaction in generate(&transactions, asset.scale(), &mut rng)? {
+                TransactionRepository
+                    .create(
+                        begin().await?,
+                        TransactionCreate {
+                            account_id: created.id,
+                            asset_id: asset.id,
+                            description: transaction.description,
+                            posted_at: transaction.posted_at,
+                            quantity: transaction.quantity,
+                            notes: None,
+                            external_id: None,
+                            category: None,
+                            applied_rule_id: None,
+                            journal_entry_id: None,
+                        },
+                    )
+                    .await
+                    .map_err(ServiceError::from)?;
+                report.transactions += 1;
+            }
+        }
+    }
+
+    // The transactions skip the service, so their balances are cached at
+    // once.
+    AccountBalanceRepository
+        .rebuild(begin().await?, None)
+        .await
+        .map_err(ServiceError::from)?;
+
+    info!(
+        "Seeded {} institutions, {} assets, {} users, {} accounts and {} transactions",
+        report.institutions, report.assets, report.users, report.accounts, report.transactions
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+    use proptest::prelude::*;
+    use sqlx::{Pool, Postgres};
+
+    fn transactions(min: &str, max: &str) -> SeedTransactions {
+        SeedTransactions {
+            count: 50,
+            asset: "USD".into(),
+            from: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+            min: min.parse().unwrap(),
+            max: max.parse().unwrap(),
+            descriptions: vec!["Coffee".into(), "Lunch".into()],
+        }
+    }
+
+    fn sample_file() -> SeedFile {
+        serde_json::from_str(include_str!("../../local/seed.json")).unwrap()
+    }
+
+    #[test]
+    fn it_generates_the_same_transactions_for_a_seed() {
+        let transactions = transactions("-10.00", "10.00");
+        let generate_with = |seed| generate(&transactions, 2, &mut StdRng::seed_from_u64(seed));
+        assert_eq!(generate_with(7).unwrap(), generate_with(7).unwrap());
+        assert_ne!(generate_with(7).unwrap(), generate_with(8).unwrap());
+    }
+
+    #[test]
+    fn it_rejects_invalid_transactions() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for (transactions, decimals) in [
+            (transactions("10", "-10"), 0),
+            (transactions("-0.001", "1"), 2),
+            (
+                SeedTransactions {
+                    to: NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+                    ..transactions("-1", "1")
+                },
+                0,
+            ),
+        ] {
+            let result = generate(&transactions, decimals, &mut rng);
+            assert!(matches!(result, Err(SeedError::Invalid(_))), "{result:?}");
+        }
+    }
+
+    #[test]
+    fn it_reads_the_command_line() {
+        let args = |args: &[&str]| {
+            SeedOptions::from_args(&args.iter().map(|x| (*x).to_owned()).collect::<Vec<_>>())
+        };
+        let (path, options) = args(&["local/seed.json", "--wipe", "--seed", "42"]).unwrap();
+        assert_eq!(path, PathBuf::from("local/seed.json"));
+        assert_eq!(
+            options,
+            SeedOptions {
+                seed: 42,
+                wipe: true
+            }
+        );
+        assert_eq!(
+            args(&["local/seed.json"]).unwrap().1,
+            SeedOptions::default()
+        );
+        for invalid in [
+            &[][..],
+            &["--seed", "x", "a.json"],
+            &["a.json", "b.json"],
+            &["--force"],
+        ] {
+            assert!(
+                matches!(args(invalid), Err(SeedError::Usage)),
+                "{invalid:?}"
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn it_generates_transactions_within_the_bounds(
+            seed: u64,
+            decimals in 0..=8u32,
+            min in -1_000_000..0i64,
+            max in 0..1_000_000i64,
+        ) {
+            let mut transactions = transactions("0", "0");
+            transactions.min = Decimal::new(min, decimals);
+            transactions.max = Decimal::new(max, decimals);
+            let generated =
+                generate(&transactions, decimals, &mut StdRng::seed_from_u64(seed)).unwrap();
+            prop_assert_eq!(generated.len(), transactions.count);
+            let start = transactions.from.and_time(NaiveTime::MIN).and_utc();
+            let end = (transactions.to + TimeDelta::days(1)).and_time(NaiveTime::MIN).and_utc();
+            for (i, transaction) in generated.iter().enumerate() {
+                prop_assert!((transactions.min..=transactions.max).contains(&transaction.quantity));
+                prop_assert_eq!(transaction.quantity.scale(), decimals);
+                prop_assert!(start <= transaction.posted_at && transaction.posted_at < end);
+                prop_assert!(i == 0 || generated[i - 1].posted_at <= transaction.posted_at);
+                prop_assert!(transaction.description.is_some());
+            }
+        }
+    }
+
+    #[sqlx::test]
+    async fn it_loads_the_sample_seed_file(pool: Pool<Postgres>) {
+        let options = SeedOptions {
+            seed: 42,
+            wipe: false,
+        };
+        let report = seed(&pool, sample_file(), options).await.unwrap();
+        assert_eq!(
+            report,
+            SeedResponse {
+                institutions: 4,
+                assets: 3,
+                users: 2,
+                accounts: 4,
+                transactions: 80,
+            }
+        );
+        let parent = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT parent.name FROM institution
+            JOIN institution parent ON parent.id = institution.parent_id
+            WHERE institution.name = 'Shinhan Bank Seoul'
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(parent, "Shinhan Bank");
+
+        // Loading it again reuses the shared rows and the users.
+        let report = seed(&pool, sample_file(), options).await.unwrap();
+        assert_eq!(
+            (report.institutions, report.assets, report.users),
+            (0, 0, 0)
+        );
+        assert_eq!((report.accounts, report.transactions), (4, 80));
+
+        wipe(&pool).await.unwrap();
+        let count = |table: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>(&format!(r#"SELECT COUNT(*) FROM "{table}""#))
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!(count("user").await, 0);
+        assert_eq!(count("transaction").await, 0);
+        assert_eq!(count("asset").await, 3);
+        assert_eq!(count("institution").await, 4);
+    }
+}
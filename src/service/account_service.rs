@@ -1,7 +1,9 @@
-use std::{marker::PhantomData, sync::Arc};
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
 
 use async_trait::async_trait;
-use sqlx::{Acquire, PgPool};
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
 
 use crate::{
     authentication::registered_user::RegisteredUser,
@@ -13,10 +15,20 @@ use crate::{
         policy::Policy,
         resources::Account as AccountResource,
     },
-    model::account::{Account, AccountCreate, AccountFilter, AccountId, AccountUpdate},
+    model::{
+        account::{
+            Account, AccountCreate, AccountDeletionCounts, AccountFilter, AccountId, AccountMerge,
+            AccountUpdate,
+        },
+        account_balance::NetBalance,
+        institution::{InstitutionId, InstitutionRollup},
+        statement::{AccountStatement, StatementId, StatementStatus},
+    },
     resource::{
-        CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
-        account_repository::AccountRepository,
+        CreateRepository, GetListRepository, GetRepository, RepositoryError, UpdateRepository,
+        account_balance_repository::AccountBalanceRepository,
+        account_repository::AccountRepository, deadline,
+        institution_repository::InstitutionRepository, statement_repository::StatementRepository,
     },
     service::{
         ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
@@ -24,15 +36,97 @@ use crate::{
     },
 };
 
+#[async_trait]
+pub trait AccountServiceRollup {
+    /// Totals the accounts the caller can read across an institution and
+    /// its descendants.
+    async fn rollup(
+        &self,
+        institution_id: InstitutionId,
+    ) -> Result<InstitutionRollup, ServiceError>;
+
+    /// Counts the accounts the caller can read held directly at each of the
+    /// institutions.
+    async fn account_counts(
+        &self,
+        institution_ids: Vec<InstitutionId>,
+    ) -> Result<HashMap<InstitutionId, i64>, ServiceError>;
+}
+
+#[async_trait]
+pub trait AccountServiceBalances {
+    /// Nets the balances of the caller's own accounts in each asset, now
+    /// and before `previous_at`. Accounts of other users the caller may
+    /// read are left out.
+    async fn net_balances(
+        &self,
+        previous_at: DateTime<Utc>,
+    ) -> Result<Vec<NetBalance>, ServiceError>;
+}
+
+#[async_trait]
+pub trait AccountServiceStatement {
+    /// A statement of an account the caller can read, with its document
+    /// once it is rendered. Statements of other accounts are
+    /// indistinguishable from missing ones.
+    async fn get_statement(
+        &self,
+        id: AccountId,
+        statement_id: StatementId,
+    ) -> Result<(AccountStatement, Option<Vec<u8>>), ServiceError>;
+}
+
+#[async_trait]
+pub trait AccountServiceCreateMany {
+    /// Creates all of the accounts or none of them. None are created when
+    /// any has the name of an account already at its institution.
+    async fn create_many(
+        &self,
+        create_models: Vec<AccountCreate>,
+    ) -> Result<Vec<Account>, ServiceError>;
+}
+
+#[async_trait]
+pub trait AccountServiceMerge {
+    /// Merges the source account into the account of `id`, deleting the
+    /// source. Both accounts must belong to the same user, and be at the
+    /// same institution unless `force` is set.
+    async fn merge(
+        &self,
+        id: AccountId,
+        source_id: AccountId,
+        force: bool,
+    ) -> Result<AccountMerge, ServiceError>;
+}
+
+#[async_trait]
+pub trait AccountServiceDeletion {
+    /// Counts the rows deleting the account of `id` would delete with it.
+    async fn deletion_preview(&self, id: AccountId) -> Result<AccountDeletionCounts, ServiceError>;
+}
+
 #[async_trait]
 pub trait AccountServiceMethods:
     ServiceCrud<AccountId, Account, AccountFilter, AccountCreate, AccountUpdate>
+    + AccountServiceRollup
+    + AccountServiceBalances
+    + AccountServiceStatement
+    + AccountServiceCreateMany
+    + AccountServiceMerge
+    + AccountServiceDeletion
 {
 }
 
 #[async_trait]
-impl<T: ServiceCrud<AccountId, Account, AccountFilter, AccountCreate, AccountUpdate>>
-    AccountServiceMethods for T
+impl<
+    T: ServiceCrud<AccountId, Account, AccountFilter, AccountCreate, AccountUpdate>
+        + AccountServiceRollup
+        + AccountServiceBalances
+        + AccountServiceStatement
+        + AccountServiceCreateMany
+        + AccountServiceMerge
+        + AccountServiceDeletion,
+> AccountServiceMethods for T
 {
 }
 
@@ -56,6 +150,370 @@ impl<Policy> AccountService<Policy> {
             policy: PhantomData,
         }
     }
+
+    /// Creates the account with the default asset of its institution when
+    /// it isn't given one.
+    async fn create_with_institution_default(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        mut create_model: AccountCreate,
+    ) -> Result<Account, ServiceError> {
+        if create_model.default_asset_id.is_none() {
+            create_model.default_asset_id = match InstitutionRepository
+                .get(transaction.begin().await?, create_model.institution_id)
+                .await
+            {
+                Ok(institution) => institution.default_asset_id,
+                // The insert reports the missing institution.
+                Err(RepositoryError::NotFound) => None,
+                Err(e) => return Err(e.into()),
+            };
+        }
+        let account = self
+            .account_repository
+            .create(transaction.begin().await?, create_model)
+            .await?;
+        Ok(account)
+    }
+
+    async fn create_one(&self, create_model: AccountCreate) -> Result<Account, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let account = self
+            .create_with_institution_default(&mut transaction, create_model)
+            .await?;
+        transaction.commit().await?;
+        Ok(account)
+    }
+
+    async fn create_all(
+        &self,
+        create_models: Vec<AccountCreate>,
+    ) -> Result<Vec<Account>, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let mut existing = vec![];
+        for create_model in &create_models {
+            let accounts = self
+                .account_repository
+                .get_list(
+                    transaction.begin().await?,
+                    0,
+                    1.into(),
+                    AccountFilter {
+                        name: create_model.name.clone().into(),
+                        institution_id: create_model.institution_id.into(),
+                        user_id: create_model.user_id.into(),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            if !accounts.is_empty() {
+                existing.push(create_model.name.clone());
+            }
+        }
+        if !existing.is_empty() {
+            return Err(ServiceError::AccountsExist(existing));
+        }
+
+        let mut accounts = Vec::with_capacity(create_models.len());
+        for create_model in create_models {
+            accounts.push(
+                self.create_with_institution_default(&mut transaction, create_model)
+                    .await?,
+            );
+        }
+        transaction.commit().await?;
+        Ok(accounts)
+    }
+
+    async fn caller_net_balances(
+        &self,
+        previous_at: DateTime<Utc>,
+    ) -> Result<Vec<NetBalance>, ServiceError> {
+        let balances = AccountBalanceRepository
+            .get_net_for_user(
+                deadline::begin(&self.connection_pool).await?,
+                self.registered_user.id(),
+                self.registered_user.account_scope(),
+                previous_at,
+            )
+            .await?;
+        Ok(balances)
+    }
+
+    async fn account_statement(
+        &self,
+        id: AccountId,
+        statement_id: StatementId,
+        unscoped: bool,
+    ) -> Result<(AccountStatement, Option<Vec<u8>>), ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let account = self.caller_account(&mut transaction, id, unscoped).await?;
+        let statement = StatementRepository
+            .get(transaction.begin().await?, account.id, statement_id)
+            .await?;
+        let content = match statement.status {
+            StatementStatus::Complete => Some(
+                StatementRepository
+                    .get_content(transaction.begin().await?, statement.id)
+                    .await?,
+            ),
+            _ => None,
+        };
+        transaction.commit().await?;
+        Ok((statement, content))
+    }
+
+    /// Fetches an account, of the caller and in the scope of their key
+    /// unless `unscoped`.
+    async fn caller_account(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: AccountId,
+        unscoped: bool,
+    ) -> Result<Account, ServiceError> {
+        let filter = match unscoped {
+            true => AccountFilter {
+                id: id.into(),
+                ..Default::default()
+            },
+            false => AccountFilter {
+                id: id.into(),
+                user_id: self.registered_user.id().into(),
+                account_ids: self.registered_user.account_scope(),
+                ..Default::default()
+            },
+        };
+        let account = self
+            .account_repository
+            .get_list(transaction.begin().await?, 0, 1.into(), filter)
+            .await?
+            .pop()
+            .ok_or(ServiceError::NotFound)?;
+        Ok(account)
+    }
+
+    async fn merge_accounts(
+        &self,
+        id: AccountId,
+        source_id: AccountId,
+        force: bool,
+        unscoped: bool,
+    ) -> Result<AccountMerge, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let target = self.caller_account(&mut transaction, id, unscoped).await?;
+        let source = self
+            .caller_account(&mut transaction, source_id, unscoped)
+            .await?;
+        // Another user's account is as good as missing.
+        if source.user_id != target.user_id {
+            return Err(ServiceError::NotFound);
+        }
+        let forced = source.institution_id != target.institution_id;
+        if forced && !force {
+            return Err(ServiceError::DifferentInstitutions);
+        }
+        let account_merge = self
+            .account_repository
+            .merge(transaction.begin().await?, target.id, source, forced)
+            .await?;
+        transaction.commit().await?;
+        Ok(account_merge)
+    }
+
+    async fn count_deletion(
+        &self,
+        id: AccountId,
+        unscoped: bool,
+    ) -> Result<AccountDeletionCounts, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let account = self.caller_account(&mut transaction, id, unscoped).await?;
+        let counts = self
+            .account_repository
+            .deletion_counts(transaction.begin().await?, account.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(counts)
+    }
+
+    /// Deletes an account with its transactions, their attachments and its
+    /// rules. The account is locked before they are counted, so none are
+    /// added to it until it is gone.
+    async fn delete_account(&self, id: AccountId, unscoped: bool) -> Result<Account, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let account = self.caller_account(&mut transaction, id, unscoped).await?;
+        let account = self
+            .account_repository
+            .lock(transaction.begin().await?, account.id)
+            .await?;
+        let counts = self
+            .account_repository
+            .deletion_counts(transaction.begin().await?, account.id)
+            .await?;
+        if counts.journal_entry_legs > 0 {
+            return Err(ServiceError::AccountInJournalEntries);
+        }
+        self.account_repository
+            .delete_with_dependents(transaction.begin().await?, account.clone(), counts)
+            .await?;
+        transaction.commit().await?;
+        Ok(account)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceRollup
+    for AccountService<
+        Policy<AccountResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "AccountService::rollup",
+        skip_all,
+        fields(institution_id = ?_institution_id)
+    )]
+    async fn rollup(
+        &self,
+        _institution_id: InstitutionId,
+    ) -> Result<InstitutionRollup, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "AccountService::account_counts", skip_all)]
+    async fn account_counts(
+        &self,
+        _institution_ids: Vec<InstitutionId>,
+    ) -> Result<HashMap<InstitutionId, i64>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceRollup
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "AccountService::rollup",
+        skip_all,
+        fields(institution_id = ?institution_id)
+    )]
+    async fn rollup(
+        &self,
+        institution_id: InstitutionId,
+    ) -> Result<InstitutionRollup, ServiceError> {
+        let rollup = InstitutionRepository
+            .get_rollup(
+                deadline::begin(&self.connection_pool).await?,
+                institution_id,
+                self.registered_user.id().into(),
+                self.registered_user.account_scope(),
+            )
+            .await?;
+        Ok(rollup)
+    }
+
+    #[instrument(name = "AccountService::account_counts", skip_all)]
+    async fn account_counts(
+        &self,
+        institution_ids: Vec<InstitutionId>,
+    ) -> Result<HashMap<InstitutionId, i64>, ServiceError> {
+        let account_counts = InstitutionRepository
+            .get_account_counts(
+                deadline::begin(&self.connection_pool).await?,
+                &institution_ids,
+                self.registered_user.id().into(),
+                self.registered_user.account_scope(),
+            )
+            .await?;
+        Ok(account_counts)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceRollup
+    for AccountService<Policy<AccountResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "AccountService::rollup",
+        skip_all,
+        fields(institution_id = ?institution_id)
+    )]
+    async fn rollup(
+        &self,
+        institution_id: InstitutionId,
+    ) -> Result<InstitutionRollup, ServiceError> {
+        let rollup = InstitutionRepository
+            .get_rollup(
+                deadline::begin(&self.connection_pool).await?,
+                institution_id,
+                None,
+                None,
+            )
+            .await?;
+        Ok(rollup)
+    }
+
+    #[instrument(name = "AccountService::account_counts", skip_all)]
+    async fn account_counts(
+        &self,
+        institution_ids: Vec<InstitutionId>,
+    ) -> Result<HashMap<InstitutionId, i64>, ServiceError> {
+        let account_counts = InstitutionRepository
+            .get_account_counts(
+                deadline::begin(&self.connection_pool).await?,
+                &institution_ids,
+                None,
+                None,
+            )
+            .await?;
+        Ok(account_counts)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceBalances
+    for AccountService<
+        Policy<AccountResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AccountService::net_balances", skip_all)]
+    async fn net_balances(
+        &self,
+        _previous_at: DateTime<Utc>,
+    ) -> Result<Vec<NetBalance>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceBalances
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AccountService::net_balances", skip_all)]
+    async fn net_balances(
+        &self,
+        previous_at: DateTime<Utc>,
+    ) -> Result<Vec<NetBalance>, ServiceError> {
+        self.caller_net_balances(previous_at).await
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceBalances
+    for AccountService<Policy<AccountResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AccountService::net_balances", skip_all)]
+    async fn net_balances(
+        &self,
+        previous_at: DateTime<Utc>,
+    ) -> Result<Vec<NetBalance>, ServiceError> {
+        self.caller_net_balances(previous_at).await
+    }
 }
 
 #[async_trait]
@@ -65,6 +523,7 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<AccountResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "AccountService::get", skip_all, fields(id = ?_id))]
     async fn get(&self, _id: AccountId) -> Result<Account, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -77,6 +536,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<AccountResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(
+        name = "AccountService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
     async fn get_list(
         &self,
         _offset: i64,
@@ -92,16 +556,18 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGet<AccountId, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AccountService::get", skip_all, fields(id = ?id))]
     async fn get(&self, id: AccountId) -> Result<Account, ServiceError> {
         let account = self
             .account_repository
             .get_list(
-                self.connection_pool.begin().await?,
+                deadline::begin(&self.connection_pool).await?,
                 0,
                 1.into(),
                 AccountFilter {
                     id: id.into(),
                     user_id: self.registered_user.id().into(),
+                    account_ids: self.registered_user.account_scope(),
                     ..Default::default()
                 },
             )
@@ -116,6 +582,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGetList<AccountFilter, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(
+        name = "AccountService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
     async fn get_list(
         &self,
         offset: i64,
@@ -123,9 +594,15 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         mut filter: AccountFilter,
     ) -> Result<Vec<Account>, ServiceError> {
         filter.user_id = self.registered_user.id().into();
+        filter.account_ids = self.registered_user.account_scope();
         let accounts = self
             .account_repository
-            .get_list(self.connection_pool.begin().await?, offset, limit, filter)
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
             .await?;
         Ok(accounts)
     }
@@ -136,10 +613,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGet<AccountId, Account>
     for AccountService<Policy<AccountResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AccountService::get", skip_all, fields(id = ?id))]
     async fn get(&self, id: AccountId) -> Result<Account, ServiceError> {
         let account = self
             .account_repository
-            .get(self.connection_pool.begin().await?, id)
+            .get(deadline::begin(&self.connection_pool).await?, id)
             .await?;
         Ok(account)
     }
@@ -150,6 +628,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGetList<AccountFilter, Account>
     for AccountService<Policy<AccountResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
 {
+    #[instrument(
+        name = "AccountService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
     async fn get_list(
         &self,
         offset: i64,
@@ -158,7 +641,12 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ) -> Result<Vec<Account>, ServiceError> {
         let accounts = self
             .account_repository
-            .get_list(self.connection_pool.begin().await?, offset, limit, filter)
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
             .await?;
         Ok(accounts)
     }
@@ -169,6 +657,7 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceCreate<AccountCreate, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, NoPermission, Update, Delete>, Role>>
 {
+    #[instrument(name = "AccountService::create", skip_all)]
     async fn create(&self, _create_model: AccountCreate) -> Result<Account, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -179,15 +668,15 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceCreate<AccountCreate, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AccountService::create", skip_all)]
     async fn create(&self, create_model: AccountCreate) -> Result<Account, ServiceError> {
-        if self.registered_user.id() != create_model.user_id {
+        // A new account would fall outside the scope of the key.
+        if self.registered_user.id() != create_model.user_id
+            || self.registered_user.is_account_scoped()
+        {
             return Err(ServiceError::Unauthorized);
         }
-        let account = self
-            .account_repository
-            .create(self.connection_pool.begin().await?, create_model)
-            .await?;
-        Ok(account)
+        self.create_one(create_model).await
     }
 }
 
@@ -196,12 +685,59 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceCreate<AccountCreate, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, CreateAll, Update, Delete>, Role>>
 {
+    #[instrument(name = "AccountService::create", skip_all)]
     async fn create(&self, create_model: AccountCreate) -> Result<Account, ServiceError> {
-        let account = self
-            .account_repository
-            .create(self.connection_pool.begin().await?, create_model)
-            .await?;
-        Ok(account)
+        self.create_one(create_model).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceCreateMany
+    for AccountService<Policy<AccountResource, ActionSet<Read, NoPermission, Update, Delete>, Role>>
+{
+    #[instrument(name = "AccountService::create_many", skip_all)]
+    async fn create_many(
+        &self,
+        _create_models: Vec<AccountCreate>,
+    ) -> Result<Vec<Account>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceCreateMany
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AccountService::create_many", skip_all)]
+    async fn create_many(
+        &self,
+        create_models: Vec<AccountCreate>,
+    ) -> Result<Vec<Account>, ServiceError> {
+        // A new account would fall outside the scope of the key.
+        if create_models
+            .iter()
+            .any(|x| self.registered_user.id() != x.user_id)
+            || self.registered_user.is_account_scoped()
+        {
+            return Err(ServiceError::Unauthorized);
+        }
+        self.create_all(create_models).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceCreateMany
+    for AccountService<Policy<AccountResource, ActionSet<Read, CreateAll, Update, Delete>, Role>>
+{
+    #[instrument(name = "AccountService::create_many", skip_all)]
+    async fn create_many(
+        &self,
+        create_models: Vec<AccountCreate>,
+    ) -> Result<Vec<Account>, ServiceError> {
+        self.create_all(create_models).await
     }
 }
 
@@ -210,6 +746,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceUpdate<AccountId, AccountUpdate, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, Create, NoPermission, Delete>, Role>>
 {
+    #[instrument(name = "AccountService::update", skip_all, fields(id = ?_id))]
     async fn update(
         &self,
         _id: AccountId,
@@ -224,12 +761,13 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceUpdate<AccountId, AccountUpdate, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AccountService::update", skip_all, fields(id = ?id))]
     async fn update(
         &self,
         id: AccountId,
         update_model: AccountUpdate,
     ) -> Result<Account, ServiceError> {
-        let mut transaction = self.connection_pool.begin().await?;
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
         let mut account = self
             .account_repository
             .get_list(
@@ -239,6 +777,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
                 AccountFilter {
                     id: id.into(),
                     user_id: self.registered_user.id().into(),
+                    account_ids: self.registered_user.account_scope(),
                     ..Default::default()
                 },
             )
@@ -247,6 +786,13 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
             .ok_or(ServiceError::NotFound)?;
 
         account.name = update_model.name;
+        // Empty notes clear the ones there were.
+        if let Some(notes) = update_model.notes {
+            account.notes = Some(notes).filter(|notes| !notes.trim().is_empty());
+        }
+        if let Some(default_asset_id) = update_model.default_asset_id {
+            account.default_asset_id = Some(default_asset_id);
+        }
 
         let account = self
             .account_repository
@@ -262,17 +808,25 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceUpdate<AccountId, AccountUpdate, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>>
 {
+    #[instrument(name = "AccountService::update", skip_all, fields(id = ?id))]
     async fn update(
         &self,
         id: AccountId,
         update_model: AccountUpdate,
     ) -> Result<Account, ServiceError> {
-        let mut transaction = self.connection_pool.begin().await?;
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
         let mut account = self
             .account_repository
             .get(transaction.begin().await?, id)
             .await?;
         account.name = update_model.name;
+        // Empty notes clear the ones there were.
+        if let Some(notes) = update_model.notes {
+            account.notes = Some(notes).filter(|notes| !notes.trim().is_empty());
+        }
+        if let Some(default_asset_id) = update_model.default_asset_id {
+            account.default_asset_id = Some(default_asset_id);
+        }
         let account = self
             .account_repository
             .update(transaction.begin().await?, account)
@@ -287,6 +841,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
     ServiceDelete<AccountId, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, NoPermission>, Role>>
 {
+    #[instrument(name = "AccountService::delete", skip_all, fields(id = ?_id))]
     async fn delete(&self, _id: AccountId) -> Result<Account, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -297,29 +852,9 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
     ServiceDelete<AccountId, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AccountService::delete", skip_all, fields(id = ?id))]
     async fn delete(&self, id: AccountId) -> Result<Account, ServiceError> {
-        let mut transaction = self.connection_pool.begin().await?;
-        let _ = self
-            .account_repository
-            .get_list(
-                transaction.begin().await?,
-                0,
-                1.into(),
-                AccountFilter {
-                    id: id.into(),
-                    user_id: self.registered_user.id().into(),
-                    ..Default::default()
-                },
-            )
-            .await?
-            .pop()
-            .ok_or(ServiceError::NotFound)?;
-        let account = self
-            .account_repository
-            .delete(transaction.begin().await?, id)
-            .await?;
-        transaction.commit().await?;
-        Ok(account)
+        self.delete_account(id, false).await
     }
 }
 
@@ -328,11 +863,151 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
     ServiceDelete<AccountId, Account>
     for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, DeleteAll>, Role>>
 {
+    #[instrument(name = "AccountService::delete", skip_all, fields(id = ?id))]
     async fn delete(&self, id: AccountId) -> Result<Account, ServiceError> {
-        let account = self
-            .account_repository
-            .delete(self.connection_pool.begin().await?, id)
-            .await?;
-        Ok(account)
+        self.delete_account(id, true).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceMerge
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, NoPermission, Delete>, Role>>
+{
+    #[instrument(
+        name = "AccountService::merge",
+        skip_all,
+        fields(id = ?_id, source_id = ?_source_id)
+    )]
+    async fn merge(
+        &self,
+        _id: AccountId,
+        _source_id: AccountId,
+        _force: bool,
+    ) -> Result<AccountMerge, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceMerge
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "AccountService::merge",
+        skip_all,
+        fields(id = ?id, source_id = ?source_id)
+    )]
+    async fn merge(
+        &self,
+        id: AccountId,
+        source_id: AccountId,
+        force: bool,
+    ) -> Result<AccountMerge, ServiceError> {
+        self.merge_accounts(id, source_id, force, false).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceMerge
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>>
+{
+    #[instrument(
+        name = "AccountService::merge",
+        skip_all,
+        fields(id = ?id, source_id = ?source_id)
+    )]
+    async fn merge(
+        &self,
+        id: AccountId,
+        source_id: AccountId,
+        force: bool,
+    ) -> Result<AccountMerge, ServiceError> {
+        self.merge_accounts(id, source_id, force, true).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    AccountServiceDeletion
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, NoPermission>, Role>>
+{
+    #[instrument(name = "AccountService::deletion_preview", skip_all, fields(id = ?_id))]
+    async fn deletion_preview(
+        &self,
+        _id: AccountId,
+    ) -> Result<AccountDeletionCounts, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    AccountServiceDeletion
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AccountService::deletion_preview", skip_all, fields(id = ?id))]
+    async fn deletion_preview(&self, id: AccountId) -> Result<AccountDeletionCounts, ServiceError> {
+        self.count_deletion(id, false).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    AccountServiceDeletion
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, DeleteAll>, Role>>
+{
+    #[instrument(name = "AccountService::deletion_preview", skip_all, fields(id = ?id))]
+    async fn deletion_preview(&self, id: AccountId) -> Result<AccountDeletionCounts, ServiceError> {
+        self.count_deletion(id, true).await
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceStatement
+    for AccountService<
+        Policy<AccountResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AccountService::get_statement", skip_all, fields(id = ?_id))]
+    async fn get_statement(
+        &self,
+        _id: AccountId,
+        _statement_id: StatementId,
+    ) -> Result<(AccountStatement, Option<Vec<u8>>), ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceStatement
+    for AccountService<Policy<AccountResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AccountService::get_statement", skip_all, fields(id = ?id))]
+    async fn get_statement(
+        &self,
+        id: AccountId,
+        statement_id: StatementId,
+    ) -> Result<(AccountStatement, Option<Vec<u8>>), ServiceError> {
+        self.account_statement(id, statement_id, false).await
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AccountServiceStatement
+    for AccountService<Policy<AccountResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AccountService::get_statement", skip_all, fields(id = ?id))]
+    async fn get_statement(
+        &self,
+        id: AccountId,
+        statement_id: StatementId,
+    ) -> Result<(AccountStatement, Option<Vec<u8>>), ServiceError> {
+        self.account_statement(id, statement_id, true).await
     }
 }
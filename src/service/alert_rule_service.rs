@@ -0,0 +1,321 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Create, Delete, NoPermission, Read, Update},
+        policy::Policy,
+        resources::AlertRule as AlertRuleResource,
+    },
+    model::alert_rule::{
+        AlertEvent, AlertEventFilter, AlertRule, AlertRuleCreate, AlertRuleFilter, AlertRuleId,
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
+        alert_rule_repository::AlertRuleRepository, deadline,
+    },
+    service::{
+        ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
+        ServiceUpdate, check_accounts_owned,
+    },
+};
+
+#[async_trait]
+pub trait AlertRuleServiceEvents {
+    /// The latest firings of the alert rule of `id`, newest first.
+    async fn events(&self, id: AlertRuleId) -> Result<Vec<AlertEvent>, ServiceError>;
+}
+
+/// Alert rules are updated by the rule with its changes applied, only its
+/// comparator, threshold, channel and cooldown of which are taken.
+#[async_trait]
+pub trait AlertRuleServiceMethods:
+    ServiceCrud<AlertRuleId, AlertRule, AlertRuleFilter, AlertRuleCreate, AlertRule>
+    + AlertRuleServiceEvents
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceCrud<AlertRuleId, AlertRule, AlertRuleFilter, AlertRuleCreate, AlertRule>
+        + AlertRuleServiceEvents,
+> AlertRuleServiceMethods for T
+{
+}
+
+pub struct AlertRuleService<Policy> {
+    connection_pool: Arc<PgPool>,
+    alert_rule_repository: AlertRuleRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> AlertRuleService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        alert_rule_repository: AlertRuleRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            alert_rule_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the alert rules of the caller. The rules of other
+    /// users are as good as missing.
+    async fn caller_alert_rule(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: AlertRuleId,
+    ) -> Result<AlertRule, ServiceError> {
+        let alert_rule = self
+            .alert_rule_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if alert_rule.user_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(alert_rule)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<AlertRuleId, AlertRule>
+    for AlertRuleService<
+        Policy<AlertRuleResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AlertRuleService::get", skip_all, fields(id = ?_id))]
+    async fn get(&self, _id: AlertRuleId) -> Result<AlertRule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<AlertRuleFilter, AlertRule>
+    for AlertRuleService<
+        Policy<AlertRuleResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "AlertRuleService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: AlertRuleFilter,
+    ) -> Result<Vec<AlertRule>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AlertRuleServiceEvents
+    for AlertRuleService<
+        Policy<AlertRuleResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AlertRuleService::events", skip_all, fields(id = ?_id))]
+    async fn events(&self, _id: AlertRuleId) -> Result<Vec<AlertEvent>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<AlertRuleId, AlertRule>
+    for AlertRuleService<Policy<AlertRuleResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AlertRuleService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: AlertRuleId) -> Result<AlertRule, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let alert_rule = self.caller_alert_rule(&mut transaction, id).await?;
+        transaction.commit().await?;
+        Ok(alert_rule)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<AlertRuleFilter, AlertRule>
+    for AlertRuleService<Policy<AlertRuleResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "AlertRuleService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: AlertRuleFilter,
+    ) -> Result<Vec<AlertRule>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let alert_rules = self
+            .alert_rule_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(alert_rules)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    AlertRuleServiceEvents
+    for AlertRuleService<Policy<AlertRuleResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AlertRuleService::events", skip_all, fields(id = ?id))]
+    async fn events(&self, id: AlertRuleId) -> Result<Vec<AlertEvent>, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let alert_rule = self.caller_alert_rule(&mut transaction, id).await?;
+        let alert_events = self
+            .alert_rule_repository
+            .get_events(
+                transaction.begin().await?,
+                0,
+                None,
+                AlertEventFilter {
+                    alert_rule_id: alert_rule.id.into(),
+                },
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(alert_events)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<AlertRuleCreate, AlertRule>
+    for AlertRuleService<
+        Policy<AlertRuleResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AlertRuleService::create", skip_all)]
+    async fn create(&self, _create_model: AlertRuleCreate) -> Result<AlertRule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<AlertRuleCreate, AlertRule>
+    for AlertRuleService<Policy<AlertRuleResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AlertRuleService::create", skip_all)]
+    async fn create(&self, create_model: AlertRuleCreate) -> Result<AlertRule, ServiceError> {
+        if self.registered_user.id() != create_model.user_id {
+            return Err(ServiceError::Unauthorized);
+        }
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        check_accounts_owned(
+            &mut transaction,
+            create_model.user_id,
+            &[create_model.account_id],
+        )
+        .await?;
+        let alert_rule = self
+            .alert_rule_repository
+            .create(transaction.begin().await?, create_model)
+            .await?;
+        transaction.commit().await?;
+        Ok(alert_rule)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<AlertRuleId, AlertRule, AlertRule>
+    for AlertRuleService<
+        Policy<AlertRuleResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AlertRuleService::update", skip_all, fields(id = ?_id))]
+    async fn update(
+        &self,
+        _id: AlertRuleId,
+        _update_model: AlertRule,
+    ) -> Result<AlertRule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<AlertRuleId, AlertRule, AlertRule>
+    for AlertRuleService<Policy<AlertRuleResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AlertRuleService::update", skip_all, fields(id = ?id))]
+    async fn update(
+        &self,
+        id: AlertRuleId,
+        update_model: AlertRule,
+    ) -> Result<AlertRule, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let alert_rule = self.caller_alert_rule(&mut transaction, id).await?;
+        // The rule keeps watching the balance it was created for.
+        let alert_rule = AlertRule {
+            comparator: update_model.comparator,
+            threshold: update_model.threshold,
+            channel: update_model.channel,
+            cooldown_seconds: update_model.cooldown_seconds,
+            ..alert_rule
+        };
+        let alert_rule = self
+            .alert_rule_repository
+            .update(transaction.begin().await?, alert_rule)
+            .await?;
+        transaction.commit().await?;
+        Ok(alert_rule)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<AlertRuleId, AlertRule>
+    for AlertRuleService<
+        Policy<AlertRuleResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "AlertRuleService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: AlertRuleId) -> Result<AlertRule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<AlertRuleId, AlertRule>
+    for AlertRuleService<Policy<AlertRuleResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "AlertRuleService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: AlertRuleId) -> Result<AlertRule, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let alert_rule = self.caller_alert_rule(&mut transaction, id).await?;
+        let alert_rule = self
+            .alert_rule_repository
+            .delete(transaction.begin().await?, alert_rule.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(alert_rule)
+    }
+}
@@ -0,0 +1,297 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, PgPool};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, CreateAll, DeleteAll, NoPermission, ReadAll, UpdateAll},
+        policy::Policy,
+        resources::Announcement as AnnouncementResource,
+    },
+    model::announcement::{Announcement, AnnouncementCreate, AnnouncementFilter, AnnouncementId},
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
+        announcement_repository::AnnouncementRepository, deadline,
+    },
+    service::{
+        ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
+        ServiceUpdate,
+    },
+};
+
+#[async_trait]
+pub trait AnnouncementServiceActive {
+    /// The announcements shown at `at`, which every caller may see.
+    async fn active(&self, at: DateTime<Utc>) -> Result<Vec<Announcement>, ServiceError>;
+}
+
+/// Announcements are shared by every user, so managing them takes the
+/// `_all` levels. They are updated by the announcement with its changes
+/// applied, only its message, severity and window of which are taken.
+#[async_trait]
+pub trait AnnouncementServiceMethods:
+    ServiceCrud<AnnouncementId, Announcement, AnnouncementFilter, AnnouncementCreate, Announcement>
+    + AnnouncementServiceActive
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceCrud<
+            AnnouncementId,
+            Announcement,
+            AnnouncementFilter,
+            AnnouncementCreate,
+            Announcement,
+        > + AnnouncementServiceActive,
+> AnnouncementServiceMethods for T
+{
+}
+
+pub struct AnnouncementService<Policy> {
+    connection_pool: Arc<PgPool>,
+    announcement_repository: AnnouncementRepository,
+    registered_user: Option<RegisteredUser>,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> AnnouncementService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        announcement_repository: AnnouncementRepository,
+        registered_user: Option<RegisteredUser>,
+    ) -> Self {
+        Self {
+            connection_pool,
+            announcement_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<Policy: Send + Sync> AnnouncementServiceActive for AnnouncementService<Policy> {
+    #[instrument(name = "AnnouncementService::active", skip_all)]
+    async fn active(&self, at: DateTime<Utc>) -> Result<Vec<Announcement>, ServiceError> {
+        let announcements = self
+            .announcement_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                0,
+                None,
+                AnnouncementFilter {
+                    active_at: Some(at),
+                },
+            )
+            .await?;
+        Ok(announcements)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<AnnouncementId, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AnnouncementService::get", skip_all, fields(id = ?_id))]
+    async fn get(&self, _id: AnnouncementId) -> Result<Announcement, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<AnnouncementFilter, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "AnnouncementService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: AnnouncementFilter,
+    ) -> Result<Vec<Announcement>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<AnnouncementId, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<ReadAll, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AnnouncementService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: AnnouncementId) -> Result<Announcement, ServiceError> {
+        let announcement = self
+            .announcement_repository
+            .get(deadline::begin(&self.connection_pool).await?, id)
+            .await?;
+        Ok(announcement)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<AnnouncementFilter, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<ReadAll, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "AnnouncementService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        filter: AnnouncementFilter,
+    ) -> Result<Vec<Announcement>, ServiceError> {
+        let announcements = self
+            .announcement_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(announcements)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<AnnouncementCreate, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AnnouncementService::create", skip_all)]
+    async fn create(
+        &self,
+        _create_model: AnnouncementCreate,
+    ) -> Result<Announcement, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<AnnouncementCreate, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<Read, CreateAll, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AnnouncementService::create", skip_all)]
+    async fn create(&self, create_model: AnnouncementCreate) -> Result<Announcement, ServiceError> {
+        // Announcements are signed by whoever posts them, when they are a
+        // registered user.
+        let create_model = AnnouncementCreate {
+            created_by: self.registered_user.as_ref().map(|x| x.id()),
+            ..create_model
+        };
+        let announcement = self
+            .announcement_repository
+            .create(deadline::begin(&self.connection_pool).await?, create_model)
+            .await?;
+        Ok(announcement)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<AnnouncementId, Announcement, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AnnouncementService::update", skip_all, fields(id = ?_id))]
+    async fn update(
+        &self,
+        _id: AnnouncementId,
+        _update_model: Announcement,
+    ) -> Result<Announcement, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<AnnouncementId, Announcement, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>,
+    >
+{
+    #[instrument(name = "AnnouncementService::update", skip_all, fields(id = ?id))]
+    async fn update(
+        &self,
+        id: AnnouncementId,
+        update_model: Announcement,
+    ) -> Result<Announcement, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let announcement = self
+            .announcement_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        let announcement = Announcement {
+            message: update_model.message,
+            severity: update_model.severity,
+            starts_at: update_model.starts_at,
+            ends_at: update_model.ends_at,
+            ..announcement
+        };
+        let announcement = self
+            .announcement_repository
+            .update(transaction.begin().await?, announcement)
+            .await?;
+        transaction.commit().await?;
+        Ok(announcement)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<AnnouncementId, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "AnnouncementService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: AnnouncementId) -> Result<Announcement, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<AnnouncementId, Announcement>
+    for AnnouncementService<
+        Policy<AnnouncementResource, ActionSet<Read, Create, Update, DeleteAll>, Role>,
+    >
+{
+    #[instrument(name = "AnnouncementService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: AnnouncementId) -> Result<Announcement, ServiceError> {
+        let announcement = self
+            .announcement_repository
+            .delete(deadline::begin(&self.connection_pool).await?, id)
+            .await?;
+        Ok(announcement)
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use sqlx::PgPool;
+
+use crate::authentication::registered_user::RegisteredUser;
+use crate::authorization::PermissionSet;
+use crate::authorization::actions::{
+    ActionSet, CreateAll, CreateLevel, DeleteAll, DeleteLevel, NoPermission, ReadAll, ReadLevel,
+    UpdateAll, UpdateLevel,
+};
+use crate::authorization::policy::Policy;
+use crate::authorization::resources::Announcement as AnnouncementResource;
+use crate::authorization::roles::Any;
+use crate::resource::announcement_repository::AnnouncementRepository;
+use crate::service::announcement_service::{AnnouncementService, AnnouncementServiceMethods};
+
+macro_rules! build_service {
+    ($permission_set:expr, $pool:expr, $user:expr;
+     $([ $read:ident, $create:ident, $update:ident, $delete:ident ]),* $(,)*) => {
+        match $permission_set {
+            $(
+                PermissionSet {
+                    read_level,
+                    create_level,
+                    update_level,
+                    delete_level
+                } if read_level == ReadLevel::$read &&
+                    create_level == CreateLevel::$create &&
+                    update_level == UpdateLevel::$update &&
+                    delete_level == DeleteLevel::$delete => {
+                    Box::new(AnnouncementService::<Policy<
+                        AnnouncementResource,
+                        ActionSet<
+                            $read,
+                            $create,
+                            $update,
+                            $delete
+                        >,
+                        Any
+                    >>::new($pool, AnnouncementRepository {}, $user))
+                },
+            )*
+            _ => {Box::new(AnnouncementService::<Policy<AnnouncementResource, ActionSet, Any>>::new($pool, AnnouncementRepository {}, $user))}
+        }
+    };
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AnnouncementServiceFactory;
+
+impl AnnouncementServiceFactory {
+    pub fn build(
+        user: Option<RegisteredUser>,
+        connection_pool: Arc<PgPool>,
+        permission_set: PermissionSet,
+    ) -> Box<dyn AnnouncementServiceMethods + Send> {
+        build_service!(permission_set, connection_pool, user;
+            [NoPermission, NoPermission, NoPermission, DeleteAll],
+            [NoPermission, NoPermission, UpdateAll, NoPermission],
+            [NoPermission, NoPermission, UpdateAll, DeleteAll],
+            [NoPermission, CreateAll, NoPermission, NoPermission],
+            [NoPermission, CreateAll, NoPermission, DeleteAll],
+            [NoPermission, CreateAll, UpdateAll, NoPermission],
+            [NoPermission, CreateAll, UpdateAll, DeleteAll],
+            [ReadAll, NoPermission, NoPermission, NoPermission],
+            [ReadAll, NoPermission, NoPermission, DeleteAll],
+            [ReadAll, NoPermission, UpdateAll, NoPermission],
+            [ReadAll, NoPermission, UpdateAll, DeleteAll],
+            [ReadAll, CreateAll, NoPermission, NoPermission],
+            [ReadAll, CreateAll, NoPermission, DeleteAll],
+            [ReadAll, CreateAll, UpdateAll, NoPermission],
+            [ReadAll, CreateAll, UpdateAll, DeleteAll],
+        )
+    }
+}
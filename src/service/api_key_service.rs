@@ -0,0 +1,190 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Create, Delete, NoPermission, Read},
+        policy::Policy,
+        resources::ApiKey as ApiKeyResource,
+    },
+    model::api_key::{ApiKey, ApiKeyCreate, ApiKeyFilter, ApiKeyId},
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository,
+        api_key_repository::ApiKeyRepository, deadline,
+    },
+    service::{ServiceCreate, ServiceDelete, ServiceError, ServiceGetList, check_accounts_owned},
+};
+
+/// API keys are issued and revoked, never changed.
+#[async_trait]
+pub trait ApiKeyServiceMethods:
+    ServiceGetList<ApiKeyFilter, ApiKey>
+    + ServiceCreate<ApiKeyCreate, ApiKey>
+    + ServiceDelete<ApiKeyId, ApiKey>
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceGetList<ApiKeyFilter, ApiKey>
+        + ServiceCreate<ApiKeyCreate, ApiKey>
+        + ServiceDelete<ApiKeyId, ApiKey>,
+> ApiKeyServiceMethods for T
+{
+}
+
+pub struct ApiKeyService<Policy> {
+    connection_pool: Arc<PgPool>,
+    api_key_repository: ApiKeyRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> ApiKeyService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        api_key_repository: ApiKeyRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            api_key_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the API keys of the caller. The keys of other users
+    /// are as good as missing.
+    async fn caller_api_key(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: ApiKeyId,
+    ) -> Result<ApiKey, ServiceError> {
+        let api_key = self
+            .api_key_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if api_key.user_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(api_key)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<ApiKeyFilter, ApiKey>
+    for ApiKeyService<Policy<ApiKeyResource, ActionSet<NoPermission, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "ApiKeyService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: ApiKeyFilter,
+    ) -> Result<Vec<ApiKey>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<ApiKeyFilter, ApiKey>
+    for ApiKeyService<Policy<ApiKeyResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "ApiKeyService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: ApiKeyFilter,
+    ) -> Result<Vec<ApiKey>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let api_keys = self
+            .api_key_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(api_keys)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<ApiKeyCreate, ApiKey>
+    for ApiKeyService<Policy<ApiKeyResource, ActionSet<Read, NoPermission, Update, Delete>, Role>>
+{
+    #[instrument(name = "ApiKeyService::create", skip_all)]
+    async fn create(&self, _create_model: ApiKeyCreate) -> Result<ApiKey, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<ApiKeyCreate, ApiKey>
+    for ApiKeyService<Policy<ApiKeyResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "ApiKeyService::create", skip_all)]
+    async fn create(&self, create_model: ApiKeyCreate) -> Result<ApiKey, ServiceError> {
+        if self.registered_user.id() != create_model.user_id {
+            return Err(ServiceError::Unauthorized);
+        }
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        if let Some(account_ids) = &create_model.account_ids {
+            check_accounts_owned(&mut transaction, create_model.user_id, account_ids).await?;
+        }
+        let api_key = self
+            .api_key_repository
+            .create(transaction.begin().await?, create_model)
+            .await?;
+        transaction.commit().await?;
+        Ok(api_key)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<ApiKeyId, ApiKey>
+    for ApiKeyService<Policy<ApiKeyResource, ActionSet<Read, Create, Update, NoPermission>, Role>>
+{
+    #[instrument(name = "ApiKeyService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: ApiKeyId) -> Result<ApiKey, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<ApiKeyId, ApiKey>
+    for ApiKeyService<Policy<ApiKeyResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "ApiKeyService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: ApiKeyId) -> Result<ApiKey, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let api_key = self.caller_api_key(&mut transaction, id).await?;
+        let api_key = self
+            .api_key_repository
+            .delete(transaction.begin().await?, api_key.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(api_key)
+    }
+}
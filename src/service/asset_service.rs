@@ -2,21 +2,25 @@ use std::{marker::PhantomData, sync::Arc};
 
 use async_trait::async_trait;
 use sqlx::{Acquire, PgPool};
+use tracing::instrument;
 
 use crate::{
     authorization::{
-        actions::{ActionSet, Create, Delete, NoPermission, Read, Update},
+        actions::{ActionSet, Create, Delete, NoPermission, Read, ReadLevel, Update},
         policy::Policy,
         resources::Asset as AssetResource,
     },
-    model::asset::{Asset, AssetCreate, AssetFilter, AssetId, AssetUpdate},
+    model::{
+        ListSort,
+        asset::{Asset, AssetCreate, AssetFilter, AssetId, AssetUpdate},
+    },
     resource::{
         CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
-        asset_repository::AssetRepository,
+        asset_repository::AssetRepository, deadline,
     },
     service::{
         ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
-        ServiceUpdate,
+        ServiceUpdate, cache::ReadCache,
     },
 };
 
@@ -35,6 +39,8 @@ impl<T: ServiceCrud<AssetId, Asset, AssetFilter, AssetCreate, AssetUpdate>> Asse
 pub struct AssetService<Policy> {
     connection_pool: Arc<PgPool>,
     asset_repository: AssetRepository,
+    cache: ReadCache<AssetId, Asset>,
+    read_level: ReadLevel,
     policy: PhantomData<Policy>,
 }
 
@@ -43,9 +49,18 @@ impl<Policy> AssetService<Policy> {
         Self {
             connection_pool,
             asset_repository,
+            cache: ReadCache::new(0),
+            read_level: ReadLevel::NoPermission,
             policy: PhantomData,
         }
     }
+
+    /// Serves reads through `cache`, keyed by the read level of the caller.
+    pub fn with_cache(mut self, cache: ReadCache<AssetId, Asset>, read_level: ReadLevel) -> Self {
+        self.cache = cache;
+        self.read_level = read_level;
+        self
+    }
 }
 
 #[async_trait]
@@ -53,6 +68,7 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGet<AssetId, Asset>
     for AssetService<Policy<AssetResource, ActionSet<NoPermission, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AssetService::get", skip_all, fields(id = ?_id))]
     async fn get(&self, _id: AssetId) -> Result<Asset, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -62,6 +78,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGetList<AssetFilter, Asset>
     for AssetService<Policy<AssetResource, ActionSet<NoPermission, Create, Update, Delete>, Role>>
 {
+    #[instrument(
+        name = "AssetService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
     async fn get_list(
         &self,
         _offset: i64,
@@ -77,12 +98,17 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGet<AssetId, Asset>
     for AssetService<Policy<AssetResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AssetService::get", skip_all, fields(id = ?id))]
     async fn get(&self, id: AssetId) -> Result<Asset, ServiceError> {
-        let asset = self
-            .asset_repository
-            .get(self.connection_pool.begin().await?, id)
-            .await?;
-        Ok(asset)
+        self.cache
+            .item(self.read_level, id, async {
+                let asset = self
+                    .asset_repository
+                    .get(deadline::begin(&self.connection_pool).await?, id)
+                    .await?;
+                Ok(asset)
+            })
+            .await
     }
 }
 
@@ -91,17 +117,38 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGetList<AssetFilter, Asset>
     for AssetService<Policy<AssetResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(
+        name = "AssetService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
     async fn get_list(
         &self,
         offset: i64,
         limit: Option<i64>,
         filter: AssetFilter,
     ) -> Result<Vec<Asset>, ServiceError> {
-        let assets = self
-            .asset_repository
-            .get_list(self.connection_pool.begin().await?, offset, limit, filter)
-            .await?;
-        Ok(assets)
+        let cacheable = offset == 0
+            && filter.name.is_none()
+            && filter.symbol.is_none()
+            && filter.sort == ListSort::default();
+        let load = async {
+            let assets = self
+                .asset_repository
+                .get_list(
+                    deadline::begin(&self.connection_pool).await?,
+                    offset,
+                    limit,
+                    filter,
+                )
+                .await?;
+            Ok(assets)
+        };
+        if cacheable {
+            self.cache.first_page(self.read_level, limit, load).await
+        } else {
+            load.await
+        }
     }
 }
 
@@ -110,6 +157,7 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceCreate<AssetCreate, Asset>
     for AssetService<Policy<AssetResource, ActionSet<Read, NoPermission, Update, Delete>, Role>>
 {
+    #[instrument(name = "AssetService::create", skip_all)]
     async fn create(&self, _create_model: AssetCreate) -> Result<Asset, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -120,11 +168,13 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceCreate<AssetCreate, Asset>
     for AssetService<Policy<AssetResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AssetService::create", skip_all)]
     async fn create(&self, create_model: AssetCreate) -> Result<Asset, ServiceError> {
         let asset = self
             .asset_repository
-            .create(self.connection_pool.begin().await?, create_model)
+            .create(deadline::begin(&self.connection_pool).await?, create_model)
             .await?;
+        self.cache.invalidate();
         Ok(asset)
     }
 }
@@ -134,6 +184,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceUpdate<AssetId, AssetUpdate, Asset>
     for AssetService<Policy<AssetResource, ActionSet<Read, Create, NoPermission, Delete>, Role>>
 {
+    #[instrument(name = "AssetService::update", skip_all, fields(id = ?_id))]
     async fn update(
         &self,
         _id: AssetId,
@@ -148,8 +199,9 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceUpdate<AssetId, AssetUpdate, Asset>
     for AssetService<Policy<AssetResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AssetService::update", skip_all, fields(id = ?id))]
     async fn update(&self, id: AssetId, update_model: AssetUpdate) -> Result<Asset, ServiceError> {
-        let mut transaction = self.connection_pool.begin().await?;
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
         let mut asset = self
             .asset_repository
             .get(transaction.begin().await?, id)
@@ -165,6 +217,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
             .update(transaction.begin().await?, asset)
             .await?;
         transaction.commit().await?;
+        self.cache.invalidate();
         Ok(asset)
     }
 }
@@ -174,6 +227,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
     ServiceDelete<AssetId, Asset>
     for AssetService<Policy<AssetResource, ActionSet<Read, Create, Update, NoPermission>, Role>>
 {
+    #[instrument(name = "AssetService::delete", skip_all, fields(id = ?_id))]
     async fn delete(&self, _id: AssetId) -> Result<Asset, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -184,11 +238,13 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
     ServiceDelete<AssetId, Asset>
     for AssetService<Policy<AssetResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "AssetService::delete", skip_all, fields(id = ?id))]
     async fn delete(&self, id: AssetId) -> Result<Asset, ServiceError> {
         let asset = self
             .asset_repository
-            .delete(self.connection_pool.begin().await?, id)
+            .delete(deadline::begin(&self.connection_pool).await?, id)
             .await?;
+        self.cache.invalidate();
         Ok(asset)
     }
 }
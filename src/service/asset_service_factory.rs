@@ -13,11 +13,13 @@ use crate::authorization::{
     resources::Asset as AssetResource,
     roles::Any,
 };
+use crate::model::asset::{Asset, AssetId};
 use crate::resource::asset_repository::AssetRepository;
 use crate::service::asset_service::{AssetService, AssetServiceMethods};
+use crate::service::cache::ReadCache;
 
 macro_rules! build_service {
-    ($permission_set:expr, $pool:expr;
+    ($permission_set:expr, $pool:expr, $cache:expr;
      $([ $read:ident, $create:ident, $update:ident, $delete:ident ]),* $(,)*) => {
         match $permission_set {
             $(
@@ -39,7 +41,7 @@ macro_rules! build_service {
                             $delete
                         >,
                         Any
-                    >>::new($pool, AssetRepository {}))
+                    >>::new($pool, AssetRepository {}).with_cache($cache, read_level))
                 },
             )*
             _ => {Box::new(AssetService::<Policy<AssetResource, ActionSet, Any>>::new($pool, AssetRepository {}))}
@@ -54,9 +56,10 @@ impl AssetServiceFactory {
     pub fn build(
         connection_pool: Arc<PgPool>,
         permission_set: PermissionSet,
+        cache: ReadCache<AssetId, Asset>,
     ) -> Box<dyn AssetServiceMethods + Send> {
         build_service!(
-            permission_set, connection_pool;
+            permission_set, connection_pool, cache;
             [NoPermission, NoPermission, NoPermission, Delete],
             [NoPermission, NoPermission, Update, NoPermission],
             [NoPermission, NoPermission, Update, Delete],
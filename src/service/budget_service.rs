@@ -0,0 +1,277 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Create, Delete, NoPermission, Read},
+        policy::Policy,
+        resources::Budget as BudgetResource,
+    },
+    model::budget::{Budget, BudgetCreate, BudgetFilter, BudgetId, BudgetTotal},
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository,
+        budget_repository::BudgetRepository, deadline,
+    },
+    rounding::RoundingPolicy,
+    service::{
+        ServiceCreate, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
+        check_accounts_owned,
+    },
+};
+
+#[async_trait]
+pub trait BudgetServiceStatus {
+    /// The budget of `id` with the spending against it and the income of
+    /// the user posted in `[start, end)`.
+    async fn status(
+        &self,
+        id: BudgetId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        rounding: RoundingPolicy,
+    ) -> Result<(Budget, Vec<BudgetTotal>), ServiceError>;
+}
+
+#[async_trait]
+pub trait BudgetServiceMethods:
+    ServiceGet<BudgetId, Budget>
+    + ServiceGetList<BudgetFilter, Budget>
+    + ServiceCreate<BudgetCreate, Budget>
+    + ServiceDelete<BudgetId, Budget>
+    + BudgetServiceStatus
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceGet<BudgetId, Budget>
+        + ServiceGetList<BudgetFilter, Budget>
+        + ServiceCreate<BudgetCreate, Budget>
+        + ServiceDelete<BudgetId, Budget>
+        + BudgetServiceStatus,
+> BudgetServiceMethods for T
+{
+}
+
+pub struct BudgetService<Policy> {
+    connection_pool: Arc<PgPool>,
+    budget_repository: BudgetRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> BudgetService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        budget_repository: BudgetRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            budget_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the budgets of the caller. The budgets of other users
+    /// are as good as missing.
+    async fn caller_budget(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: BudgetId,
+    ) -> Result<Budget, ServiceError> {
+        let budget = self
+            .budget_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if budget.user_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(budget)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<BudgetId, Budget>
+    for BudgetService<Policy<BudgetResource, ActionSet<NoPermission, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "BudgetService::get", skip_all, fields(id = ?_id))]
+    async fn get(&self, _id: BudgetId) -> Result<Budget, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<BudgetFilter, Budget>
+    for BudgetService<Policy<BudgetResource, ActionSet<NoPermission, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "BudgetService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: BudgetFilter,
+    ) -> Result<Vec<Budget>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    BudgetServiceStatus
+    for BudgetService<Policy<BudgetResource, ActionSet<NoPermission, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "BudgetService::status", skip_all, fields(id = ?_id))]
+    async fn status(
+        &self,
+        _id: BudgetId,
+        _start: DateTime<Utc>,
+        _end: DateTime<Utc>,
+        _rounding: RoundingPolicy,
+    ) -> Result<(Budget, Vec<BudgetTotal>), ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<BudgetId, Budget>
+    for BudgetService<Policy<BudgetResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "BudgetService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: BudgetId) -> Result<Budget, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let budget = self.caller_budget(&mut transaction, id).await?;
+        transaction.commit().await?;
+        Ok(budget)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<BudgetFilter, Budget>
+    for BudgetService<Policy<BudgetResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "BudgetService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: BudgetFilter,
+    ) -> Result<Vec<Budget>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let budgets = self
+            .budget_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(budgets)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    BudgetServiceStatus
+    for BudgetService<Policy<BudgetResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "BudgetService::status", skip_all, fields(id = ?id))]
+    async fn status(
+        &self,
+        id: BudgetId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        rounding: RoundingPolicy,
+    ) -> Result<(Budget, Vec<BudgetTotal>), ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let budget = self.caller_budget(&mut transaction, id).await?;
+        let totals = self
+            .budget_repository
+            .get_totals(transaction.begin().await?, &budget, start, end, rounding)
+            .await?;
+        transaction.commit().await?;
+        Ok((budget, totals))
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<BudgetCreate, Budget>
+    for BudgetService<Policy<BudgetResource, ActionSet<Read, NoPermission, Update, Delete>, Role>>
+{
+    #[instrument(name = "BudgetService::create", skip_all)]
+    async fn create(&self, _create_model: BudgetCreate) -> Result<Budget, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<BudgetCreate, Budget>
+    for BudgetService<Policy<BudgetResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "BudgetService::create", skip_all)]
+    async fn create(&self, create_model: BudgetCreate) -> Result<Budget, ServiceError> {
+        if self.registered_user.id() != create_model.user_id {
+            return Err(ServiceError::Unauthorized);
+        }
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        if let Some(account_ids) = &create_model.account_ids {
+            check_accounts_owned(&mut transaction, create_model.user_id, account_ids).await?;
+        }
+        let budget = self
+            .budget_repository
+            .create(transaction.begin().await?, create_model)
+            .await?;
+        transaction.commit().await?;
+        Ok(budget)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<BudgetId, Budget>
+    for BudgetService<Policy<BudgetResource, ActionSet<Read, Create, Update, NoPermission>, Role>>
+{
+    #[instrument(name = "BudgetService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: BudgetId) -> Result<Budget, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<BudgetId, Budget>
+    for BudgetService<Policy<BudgetResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "BudgetService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: BudgetId) -> Result<Budget, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let budget = self.caller_budget(&mut transaction, id).await?;
+        let budget = self
+            .budget_repository
+            .delete(transaction.begin().await?, budget.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(budget)
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use sqlx::PgPool;
+
+use crate::authentication::registered_user::RegisteredUser;
+use crate::authorization::PermissionSet;
+use crate::authorization::actions::{
+    ActionSet, Create, CreateLevel, Delete, DeleteLevel, NoPermission, Read, ReadLevel, UpdateLevel,
+};
+use crate::authorization::policy::Policy;
+use crate::authorization::resources::Budget as BudgetResource;
+use crate::authorization::roles::Any;
+use crate::resource::budget_repository::BudgetRepository;
+use crate::service::budget_service::{BudgetService, BudgetServiceMethods};
+
+macro_rules! build_service {
+    ($permission_set:expr, $pool:expr, $user:expr;
+     $([ $read:ident, $create:ident, $update:ident, $delete:ident ]),* $(,)*) => {
+        match $permission_set {
+            $(
+                PermissionSet {
+                    read_level,
+                    create_level,
+                    update_level,
+                    delete_level
+                } if read_level == ReadLevel::$read &&
+                    create_level == CreateLevel::$create &&
+                    update_level == UpdateLevel::$update &&
+                    delete_level == DeleteLevel::$delete => {
+                    Box::new(BudgetService::<Policy<
+                        BudgetResource,
+                        ActionSet<
+                            $read,
+                            $create,
+                            $update,
+                            $delete
+                        >,
+                        Any
+                    >>::new($pool, BudgetRepository {}, $user))
+                },
+            )*
+            _ => {Box::new(BudgetService::<Policy<BudgetResource, ActionSet, Any>>::new($pool, BudgetRepository {}, $user))}
+        }
+    };
+}
+
+/// Budgets are never updated, so the service is only built for the read,
+/// create and delete levels.
+#[derive(Clone, Copy, Debug)]
+pub struct BudgetServiceFactory;
+
+impl BudgetServiceFactory {
+    pub fn build(
+        user: RegisteredUser,
+        connection_pool: Arc<PgPool>,
+        permission_set: PermissionSet,
+    ) -> Box<dyn BudgetServiceMethods + Send> {
+        build_service!(permission_set, connection_pool, user;
+            [NoPermission, NoPermission, NoPermission, Delete],
+            [NoPermission, Create, NoPermission, NoPermission],
+            [NoPermission, Create, NoPermission, Delete],
+            [Read, NoPermission, NoPermission, NoPermission],
+            [Read, NoPermission, NoPermission, Delete],
+            [Read, Create, NoPermission, NoPermission],
+            [Read, Create, NoPermission, Delete],
+        )
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    env::var,
+    hash::Hash,
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use cached::{Cached, TimedCache};
+
+use crate::{
+    authorization::actions::ReadLevel,
+    model::{
+        asset::{Asset, AssetId},
+        institution::{Institution, InstitutionId},
+    },
+    service::ServiceError,
+};
+
+/// How long cached reads are served when `SERVICE_CACHE_TTL_SECONDS` is
+/// unset.
+pub const DEFAULT_CACHE_TTL_SECONDS: u64 = 60;
+
+static CACHE_TTL_SECONDS: OnceLock<u64> = OnceLock::new();
+
+/// The lifetime of cached reads, configured by `SERVICE_CACHE_TTL_SECONDS`.
+/// Zero disables caching.
+pub fn cache_ttl_seconds() -> u64 {
+    *CACHE_TTL_SECONDS.get_or_init(|| {
+        var("SERVICE_CACHE_TTL_SECONDS")
+            .ok()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_TTL_SECONDS)
+    })
+}
+
+struct Entries<Id, Model> {
+    first_pages: TimedCache<(ReadLevel, Option<i64>), Vec<Model>>,
+    items: TimedCache<(ReadLevel, Id), Model>,
+}
+
+/// A read-through cache of rows that rarely change, shared by every request.
+///
+/// Only by id lookups and the unfiltered first page are cached. Entries are
+/// keyed by the read level they were loaded with so a caller is never served
+/// rows read under a broader permission, and any write clears the cache.
+pub struct ReadCache<Id, Model> {
+    ttl_seconds: u64,
+    entries: Arc<Mutex<Entries<Id, Model>>>,
+    loads: Arc<AtomicUsize>,
+}
+
+impl<Id, Model> Clone for ReadCache<Id, Model> {
+    fn clone(&self) -> Self {
+        Self {
+            ttl_seconds: self.ttl_seconds,
+            entries: Arc::clone(&self.entries),
+            loads: Arc::clone(&self.loads),
+        }
+    }
+}
+
+impl<Id: Hash + Eq + Clone, Model: Clone> Default for ReadCache<Id, Model> {
+    fn default() -> Self {
+        Self::new(cache_ttl_seconds())
+    }
+}
+
+impl<Id: Hash + Eq + Clone, Model: Clone> ReadCache<Id, Model> {
+    pub fn new(ttl_seconds: u64) -> Self {
+        Self {
+            ttl_seconds,
+            entries: Arc::new(Mutex::new(Entries {
+                first_pages: TimedCache::with_lifespan(ttl_seconds),
+                items: TimedCache::with_lifespan(ttl_seconds),
+            })),
+            loads: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// How many reads were passed through to `load`.
+    pub fn loads(&self) -> usize {
+        self.loads.load(Ordering::Relaxed)
+    }
+
+    /// Returns the cached row, or awaits `load` and caches its result.
+    pub async fn item(
+        &self,
+        read_level: ReadLevel,
+        id: Id,
+        load: impl Future<Output = Result<Model, ServiceError>>,
+    ) -> Result<Model, ServiceError> {
+        let key = (read_level, id);
+        if let Some(model) = self.lock().items.cache_get(&key) {
+            return Ok(model.clone());
+        }
+        self.loads.fetch_add(1, Ordering::Relaxed);
+        let model = load.await?;
+        if self.ttl_seconds > 0 {
+            self.lock().items.cache_set(key, model.clone());
+        }
+        Ok(model)
+    }
+
+    /// Returns the cached first page of `limit` rows, or awaits `load` and
+    /// caches its result.
+    pub async fn first_page(
+        &self,
+        read_level: ReadLevel,
+        limit: Option<i64>,
+        load: impl Future<Output = Result<Vec<Model>, ServiceError>>,
+    ) -> Result<Vec<Model>, ServiceError> {
+        let key = (read_level, limit);
+        if let Some(models) = self.lock().first_pages.cache_get(&key) {
+            return Ok(models.clone());
+        }
+        self.loads.fetch_add(1, Ordering::Relaxed);
+        let models = load.await?;
+        if self.ttl_seconds > 0 {
+            self.lock().first_pages.cache_set(key, models.clone());
+        }
+        Ok(models)
+    }
+
+    /// How many pages and rows are cached, including expired ones not yet
+    /// evicted.
+    pub fn len(&self) -> usize {
+        let entries = self.lock();
+        entries.first_pages.cache_size() + entries.items.cache_size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops every entry, for after a write.
+    pub fn invalidate(&self) {
+        let mut entries = self.lock();
+        entries.first_pages.cache_clear();
+        entries.items.cache_clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries<Id, Model>> {
+        // A panic while holding the lock leaves nothing half written, so the
+        // entries are still usable.
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The read caches of the services, shared through the app state.
+#[derive(Clone, Default)]
+pub struct ServiceCaches {
+    pub assets: ReadCache<AssetId, Asset>,
+    pub institutions: ReadCache<InstitutionId, Institution>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    async fn counted(loads: &AtomicUsize, value: i64) -> Result<i64, ServiceError> {
+        loads.fetch_add(1, Ordering::Relaxed);
+        Ok(value)
+    }
+
+    #[tokio::test]
+    async fn it_serves_repeated_reads_from_the_cache() {
+        let cache = ReadCache::<i64, i64>::new(60);
+        let loads = AtomicUsize::new(0);
+
+        assert_eq!(
+            cache
+                .item(ReadLevel::Read, 1, counted(&loads, 10))
+                .await
+                .unwrap(),
+            10
+        );
+        assert_eq!(
+            cache
+                .item(ReadLevel::Read, 1, counted(&loads, 20))
+                .await
+                .unwrap(),
+            10
+        );
+        assert_eq!(loads.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.loads(), 1);
+    }
+
+    #[tokio::test]
+    async fn it_keys_entries_by_read_level() {
+        let cache = ReadCache::<i64, i64>::new(60);
+        let loads = AtomicUsize::new(0);
+
+        let _ = cache
+            .first_page(ReadLevel::Read, None, async { Ok(vec![1]) })
+            .await;
+        let rows = cache
+            .first_page(ReadLevel::ReadAll, None, async {
+                loads.fetch_add(1, Ordering::Relaxed);
+                Ok(vec![1, 2])
+            })
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![1, 2]);
+        assert_eq!(loads.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn it_loads_again_after_an_invalidation() {
+        let cache = ReadCache::<i64, i64>::new(60);
+        let loads = AtomicUsize::new(0);
+
+        let _ = cache.item(ReadLevel::Read, 1, counted(&loads, 10)).await;
+        cache.invalidate();
+        assert_eq!(
+            cache
+                .item(ReadLevel::Read, 1, counted(&loads, 20))
+                .await
+                .unwrap(),
+            20
+        );
+        assert_eq!(loads.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_cache_when_disabled() {
+        let cache = ReadCache::<i64, i64>::new(0);
+        let loads = AtomicUsize::new(0);
+
+        let _ = cache.item(ReadLevel::Read, 1, counted(&loads, 10)).await;
+        let _ = cache.item(ReadLevel::Read, 1, counted(&loads, 10)).await;
+        assert_eq!(loads.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_cache_failed_loads() {
+        let cache = ReadCache::<i64, i64>::new(60);
+
+        let result = cache
+            .item(ReadLevel::Read, 1, async { Err(ServiceError::NotFound) })
+            .await;
+        assert!(matches!(result, Err(ServiceError::NotFound)));
+        assert_eq!(
+            cache
+                .item(ReadLevel::Read, 1, async { Ok(10) })
+                .await
+                .unwrap(),
+            10
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Create, Delete, NoPermission, Read, Update},
+        policy::Policy,
+        resources::CategorizationRule as CategorizationRuleResource,
+    },
+    categorization::MAX_RULES,
+    model::categorization_rule::{
+        CategorizationRule, CategorizationRuleCreate, CategorizationRuleFilter,
+        CategorizationRuleId,
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
+        categorization_rule_repository::CategorizationRuleRepository, deadline,
+    },
+    service::{
+        ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
+        ServiceUpdate, check_accounts_owned,
+    },
+};
+
+/// Categorization rules are updated by the rule with its changes applied,
+/// all but the owner of which are taken.
+#[async_trait]
+pub trait CategorizationRuleServiceMethods:
+    ServiceCrud<
+        CategorizationRuleId,
+        CategorizationRule,
+        CategorizationRuleFilter,
+        CategorizationRuleCreate,
+        CategorizationRule,
+    >
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceCrud<
+            CategorizationRuleId,
+            CategorizationRule,
+            CategorizationRuleFilter,
+            CategorizationRuleCreate,
+            CategorizationRule,
+        >,
+> CategorizationRuleServiceMethods for T
+{
+}
+
+pub struct CategorizationRuleService<Policy> {
+    connection_pool: Arc<PgPool>,
+    categorization_rule_repository: CategorizationRuleRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> CategorizationRuleService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        categorization_rule_repository: CategorizationRuleRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            categorization_rule_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the categorization rules of the caller. The rules of
+    /// other users are as good as missing.
+    async fn caller_categorization_rule(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: CategorizationRuleId,
+    ) -> Result<CategorizationRule, ServiceError> {
+        let categorization_rule = self
+            .categorization_rule_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if categorization_rule.user_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(categorization_rule)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<CategorizationRuleId, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "CategorizationRuleService::get", skip_all, fields(id = ?_id))]
+    async fn get(&self, _id: CategorizationRuleId) -> Result<CategorizationRule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<CategorizationRuleFilter, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "CategorizationRuleService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: CategorizationRuleFilter,
+    ) -> Result<Vec<CategorizationRule>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<CategorizationRuleId, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "CategorizationRuleService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: CategorizationRuleId) -> Result<CategorizationRule, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let categorization_rule = self
+            .caller_categorization_rule(&mut transaction, id)
+            .await?;
+        transaction.commit().await?;
+        Ok(categorization_rule)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<CategorizationRuleFilter, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "CategorizationRuleService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: CategorizationRuleFilter,
+    ) -> Result<Vec<CategorizationRule>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let categorization_rules = self
+            .categorization_rule_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(categorization_rules)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<CategorizationRuleCreate, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "CategorizationRuleService::create", skip_all)]
+    async fn create(
+        &self,
+        _create_model: CategorizationRuleCreate,
+    ) -> Result<CategorizationRule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<CategorizationRuleCreate, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "CategorizationRuleService::create", skip_all)]
+    async fn create(
+        &self,
+        create_model: CategorizationRuleCreate,
+    ) -> Result<CategorizationRule, ServiceError> {
+        if self.registered_user.id() != create_model.user_id {
+            return Err(ServiceError::Unauthorized);
+        }
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        if let Some(account_id) = create_model.account_id {
+            check_accounts_owned(&mut transaction, create_model.user_id, &[account_id]).await?;
+        }
+        let existing = self
+            .categorization_rule_repository
+            .get_list(
+                transaction.begin().await?,
+                0,
+                None,
+                CategorizationRuleFilter {
+                    user_id: create_model.user_id.into(),
+                },
+            )
+            .await?;
+        if existing.len() >= MAX_RULES {
+            return Err(ServiceError::CategorizationRuleLimit);
+        }
+        let categorization_rule = self
+            .categorization_rule_repository
+            .create(transaction.begin().await?, create_model)
+            .await?;
+        transaction.commit().await?;
+        Ok(categorization_rule)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<CategorizationRuleId, CategorizationRule, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "CategorizationRuleService::update", skip_all, fields(id = ?_id))]
+    async fn update(
+        &self,
+        _id: CategorizationRuleId,
+        _update_model: CategorizationRule,
+    ) -> Result<CategorizationRule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<CategorizationRuleId, CategorizationRule, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "CategorizationRuleService::update", skip_all, fields(id = ?id))]
+    async fn update(
+        &self,
+        id: CategorizationRuleId,
+        update_model: CategorizationRule,
+    ) -> Result<CategorizationRule, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let categorization_rule = self
+            .caller_categorization_rule(&mut transaction, id)
+            .await?;
+        if let Some(account_id) = update_model.account_id {
+            check_accounts_owned(&mut transaction, categorization_rule.user_id, &[account_id])
+                .await?;
+        }
+        let categorization_rule = CategorizationRule {
+            field: update_model.field,
+            pattern: update_model.pattern,
+            min_quantity: update_model.min_quantity,
+            max_quantity: update_model.max_quantity,
+            account_id: update_model.account_id,
+            category: update_model.category,
+            priority: update_model.priority,
+            ..categorization_rule
+        };
+        let categorization_rule = self
+            .categorization_rule_repository
+            .update(transaction.begin().await?, categorization_rule)
+            .await?;
+        transaction.commit().await?;
+        Ok(categorization_rule)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<CategorizationRuleId, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "CategorizationRuleService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: CategorizationRuleId) -> Result<CategorizationRule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<CategorizationRuleId, CategorizationRule>
+    for CategorizationRuleService<
+        Policy<CategorizationRuleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "CategorizationRuleService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: CategorizationRuleId) -> Result<CategorizationRule, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let categorization_rule = self
+            .caller_categorization_rule(&mut transaction, id)
+            .await?;
+        let categorization_rule = self
+            .categorization_rule_repository
+            .delete(transaction.begin().await?, categorization_rule.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(categorization_rule)
+    }
+}
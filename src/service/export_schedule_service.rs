@@ -0,0 +1,295 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Create, Delete, NoPermission, Read, Update},
+        policy::Policy,
+        resources::ExportSchedule as ExportScheduleResource,
+    },
+    model::export_schedule::{
+        ExportSchedule, ExportScheduleCreate, ExportScheduleFilter, ExportScheduleId,
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
+        deadline, export_schedule_repository::ExportScheduleRepository,
+    },
+    service::{
+        ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
+        ServiceUpdate,
+    },
+};
+
+/// Export schedules are updated by the schedule with its changes applied,
+/// only its format, destination and cadence of which are taken.
+#[async_trait]
+pub trait ExportScheduleServiceMethods:
+    ServiceCrud<
+        ExportScheduleId,
+        ExportSchedule,
+        ExportScheduleFilter,
+        ExportScheduleCreate,
+        ExportSchedule,
+    >
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceCrud<
+            ExportScheduleId,
+            ExportSchedule,
+            ExportScheduleFilter,
+            ExportScheduleCreate,
+            ExportSchedule,
+        >,
+> ExportScheduleServiceMethods for T
+{
+}
+
+pub struct ExportScheduleService<Policy> {
+    connection_pool: Arc<PgPool>,
+    export_schedule_repository: ExportScheduleRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> ExportScheduleService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        export_schedule_repository: ExportScheduleRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            export_schedule_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the export schedules of the caller. The schedules
+    /// of other users are as good as missing.
+    async fn caller_export_schedule(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: ExportScheduleId,
+    ) -> Result<ExportSchedule, ServiceError> {
+        let export_schedule = self
+            .export_schedule_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if export_schedule.user_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(export_schedule)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<ExportScheduleId, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ExportScheduleService::get", skip_all, fields(id = ?_id))]
+    async fn get(&self, _id: ExportScheduleId) -> Result<ExportSchedule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<ExportScheduleFilter, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "ExportScheduleService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: ExportScheduleFilter,
+    ) -> Result<Vec<ExportSchedule>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<ExportScheduleId, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ExportScheduleService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: ExportScheduleId) -> Result<ExportSchedule, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let export_schedule = self.caller_export_schedule(&mut transaction, id).await?;
+        transaction.commit().await?;
+        Ok(export_schedule)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<ExportScheduleFilter, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "ExportScheduleService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: ExportScheduleFilter,
+    ) -> Result<Vec<ExportSchedule>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let export_schedules = self
+            .export_schedule_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(export_schedules)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<ExportScheduleCreate, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ExportScheduleService::create", skip_all)]
+    async fn create(
+        &self,
+        _create_model: ExportScheduleCreate,
+    ) -> Result<ExportSchedule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<ExportScheduleCreate, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ExportScheduleService::create", skip_all)]
+    async fn create(
+        &self,
+        create_model: ExportScheduleCreate,
+    ) -> Result<ExportSchedule, ServiceError> {
+        if self.registered_user.id() != create_model.user_id {
+            return Err(ServiceError::Unauthorized);
+        }
+        let export_schedule = self
+            .export_schedule_repository
+            .create(deadline::begin(&self.connection_pool).await?, create_model)
+            .await?;
+        Ok(export_schedule)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<ExportScheduleId, ExportSchedule, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ExportScheduleService::update", skip_all, fields(id = ?_id))]
+    async fn update(
+        &self,
+        _id: ExportScheduleId,
+        _update_model: ExportSchedule,
+    ) -> Result<ExportSchedule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<ExportScheduleId, ExportSchedule, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ExportScheduleService::update", skip_all, fields(id = ?id))]
+    async fn update(
+        &self,
+        id: ExportScheduleId,
+        update_model: ExportSchedule,
+    ) -> Result<ExportSchedule, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let export_schedule = self.caller_export_schedule(&mut transaction, id).await?;
+        // The runs are only ever recorded by the export job.
+        let export_schedule = ExportSchedule {
+            format: update_model.format,
+            destination_kind: update_model.destination_kind,
+            destination_config: update_model.destination_config,
+            cadence: update_model.cadence,
+            ..export_schedule
+        };
+        let export_schedule = self
+            .export_schedule_repository
+            .update(transaction.begin().await?, export_schedule)
+            .await?;
+        transaction.commit().await?;
+        Ok(export_schedule)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<ExportScheduleId, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "ExportScheduleService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: ExportScheduleId) -> Result<ExportSchedule, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<ExportScheduleId, ExportSchedule>
+    for ExportScheduleService<
+        Policy<ExportScheduleResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ExportScheduleService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: ExportScheduleId) -> Result<ExportSchedule, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let export_schedule = self.caller_export_schedule(&mut transaction, id).await?;
+        let export_schedule = self
+            .export_schedule_repository
+            .delete(transaction.begin().await?, export_schedule.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(export_schedule)
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use sqlx::PgPool;
+
+use crate::authentication::registered_user::RegisteredUser;
+use crate::authorization::PermissionSet;
+use crate::authorization::actions::{
+    ActionSet, Create, CreateLevel, Delete, DeleteLevel, NoPermission, Read, ReadLevel, Update,
+    UpdateLevel,
+};
+use crate::authorization::policy::Policy;
+use crate::authorization::resources::ExportSchedule as ExportScheduleResource;
+use crate::authorization::roles::Any;
+use crate::resource::export_schedule_repository::ExportScheduleRepository;
+use crate::service::export_schedule_service::{
+    ExportScheduleService, ExportScheduleServiceMethods,
+};
+
+macro_rules! build_service {
+    ($permission_set:expr, $pool:expr, $user:expr;
+     $([ $read:ident, $create:ident, $update:ident, $delete:ident ]),* $(,)*) => {
+        match $permission_set {
+            $(
+                PermissionSet {
+                    read_level,
+                    create_level,
+                    update_level,
+                    delete_level
+                } if read_level == ReadLevel::$read &&
+                    create_level == CreateLevel::$create &&
+                    update_level == UpdateLevel::$update &&
+                    delete_level == DeleteLevel::$delete => {
+                    Box::new(ExportScheduleService::<Policy<
+                        ExportScheduleResource,
+                        ActionSet<
+                            $read,
+                            $create,
+                            $update,
+                            $delete
+                        >,
+                        Any
+                    >>::new($pool, ExportScheduleRepository {}, $user))
+                },
+            )*
+            _ => {Box::new(ExportScheduleService::<Policy<ExportScheduleResource, ActionSet, Any>>::new($pool, ExportScheduleRepository {}, $user))}
+        }
+    };
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ExportScheduleServiceFactory;
+
+impl ExportScheduleServiceFactory {
+    pub fn build(
+        user: RegisteredUser,
+        connection_pool: Arc<PgPool>,
+        permission_set: PermissionSet,
+    ) -> Box<dyn ExportScheduleServiceMethods + Send> {
+        build_service!(permission_set, connection_pool, user;
+            [NoPermission, NoPermission, NoPermission, Delete],
+            [NoPermission, NoPermission, Update, NoPermission],
+            [NoPermission, NoPermission, Update, Delete],
+            [NoPermission, Create, NoPermission, NoPermission],
+            [NoPermission, Create, NoPermission, Delete],
+            [NoPermission, Create, Update, NoPermission],
+            [NoPermission, Create, Update, Delete],
+            [Read, NoPermission, NoPermission, NoPermission],
+            [Read, NoPermission, NoPermission, Delete],
+            [Read, NoPermission, Update, NoPermission],
+            [Read, NoPermission, Update, Delete],
+            [Read, Create, NoPermission, NoPermission],
+            [Read, Create, NoPermission, Delete],
+            [Read, Create, Update, NoPermission],
+            [Read, Create, Update, Delete],
+        )
+    }
+}
@@ -0,0 +1,311 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Create, Delete, NoPermission, Read, Update},
+        policy::Policy,
+        resources::ImportProfile as ImportProfileResource,
+    },
+    model::import_profile::{
+        ImportProfile, ImportProfileCreate, ImportProfileFilter, ImportProfileId,
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
+        deadline, import_profile_repository::ImportProfileRepository,
+    },
+    service::{
+        ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
+        ServiceUpdate, check_accounts_owned,
+    },
+};
+
+/// Import profiles are updated by the profile with its changes applied,
+/// all but the owner of which are taken.
+#[async_trait]
+pub trait ImportProfileServiceMethods:
+    ServiceCrud<ImportProfileId, ImportProfile, ImportProfileFilter, ImportProfileCreate, ImportProfile>
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceCrud<
+            ImportProfileId,
+            ImportProfile,
+            ImportProfileFilter,
+            ImportProfileCreate,
+            ImportProfile,
+        >,
+> ImportProfileServiceMethods for T
+{
+}
+
+pub struct ImportProfileService<Policy> {
+    connection_pool: Arc<PgPool>,
+    import_profile_repository: ImportProfileRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> ImportProfileService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        import_profile_repository: ImportProfileRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            import_profile_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the import profiles of the caller. The profiles of
+    /// other users are as good as missing.
+    async fn caller_import_profile(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: ImportProfileId,
+    ) -> Result<ImportProfile, ServiceError> {
+        let import_profile = self
+            .import_profile_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if import_profile.user_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(import_profile)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<ImportProfileId, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ImportProfileService::get", skip_all, fields(id = ?_id))]
+    async fn get(&self, _id: ImportProfileId) -> Result<ImportProfile, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<ImportProfileFilter, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "ImportProfileService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: ImportProfileFilter,
+    ) -> Result<Vec<ImportProfile>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<ImportProfileId, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ImportProfileService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: ImportProfileId) -> Result<ImportProfile, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let import_profile = self.caller_import_profile(&mut transaction, id).await?;
+        transaction.commit().await?;
+        Ok(import_profile)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<ImportProfileFilter, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "ImportProfileService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: ImportProfileFilter,
+    ) -> Result<Vec<ImportProfile>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let import_profiles = self
+            .import_profile_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(import_profiles)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<ImportProfileCreate, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ImportProfileService::create", skip_all)]
+    async fn create(
+        &self,
+        _create_model: ImportProfileCreate,
+    ) -> Result<ImportProfile, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<ImportProfileCreate, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ImportProfileService::create", skip_all)]
+    async fn create(
+        &self,
+        create_model: ImportProfileCreate,
+    ) -> Result<ImportProfile, ServiceError> {
+        if self.registered_user.id() != create_model.user_id {
+            return Err(ServiceError::Unauthorized);
+        }
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        if let Some(default_account_id) = create_model.default_account_id {
+            check_accounts_owned(
+                &mut transaction,
+                create_model.user_id,
+                &[default_account_id],
+            )
+            .await?;
+        }
+        let import_profile = self
+            .import_profile_repository
+            .create(transaction.begin().await?, create_model)
+            .await?;
+        transaction.commit().await?;
+        Ok(import_profile)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<ImportProfileId, ImportProfile, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ImportProfileService::update", skip_all, fields(id = ?_id))]
+    async fn update(
+        &self,
+        _id: ImportProfileId,
+        _update_model: ImportProfile,
+    ) -> Result<ImportProfile, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<ImportProfileId, ImportProfile, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ImportProfileService::update", skip_all, fields(id = ?id))]
+    async fn update(
+        &self,
+        id: ImportProfileId,
+        update_model: ImportProfile,
+    ) -> Result<ImportProfile, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let import_profile = self.caller_import_profile(&mut transaction, id).await?;
+        if let Some(default_account_id) = update_model.default_account_id
+            && update_model.default_account_id != import_profile.default_account_id
+        {
+            check_accounts_owned(
+                &mut transaction,
+                import_profile.user_id,
+                &[default_account_id],
+            )
+            .await?;
+        }
+        let import_profile = ImportProfile {
+            name: update_model.name,
+            column_mapping: update_model.column_mapping,
+            date_format: update_model.date_format,
+            decimal_separator: update_model.decimal_separator,
+            decimal_places: update_model.decimal_places,
+            sign_convention: update_model.sign_convention,
+            default_account_id: update_model.default_account_id,
+            ..import_profile
+        };
+        let import_profile = self
+            .import_profile_repository
+            .update(transaction.begin().await?, import_profile)
+            .await?;
+        transaction.commit().await?;
+        Ok(import_profile)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<ImportProfileId, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "ImportProfileService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: ImportProfileId) -> Result<ImportProfile, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<ImportProfileId, ImportProfile>
+    for ImportProfileService<
+        Policy<ImportProfileResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ImportProfileService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: ImportProfileId) -> Result<ImportProfile, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let import_profile = self.caller_import_profile(&mut transaction, id).await?;
+        let import_profile = self
+            .import_profile_repository
+            .delete(transaction.begin().await?, import_profile.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(import_profile)
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use sqlx::PgPool;
+
+use crate::authentication::registered_user::RegisteredUser;
+use crate::authorization::PermissionSet;
+use crate::authorization::actions::{
+    ActionSet, Create, CreateLevel, Delete, DeleteLevel, NoPermission, Read, ReadLevel, Update,
+    UpdateLevel,
+};
+use crate::authorization::policy::Policy;
+use crate::authorization::resources::ImportProfile as ImportProfileResource;
+use crate::authorization::roles::Any;
+use crate::resource::import_profile_repository::ImportProfileRepository;
+use crate::service::import_profile_service::{ImportProfileService, ImportProfileServiceMethods};
+
+macro_rules! build_service {
+    ($permission_set:expr, $pool:expr, $user:expr;
+     $([ $read:ident, $create:ident, $update:ident, $delete:ident ]),* $(,)*) => {
+        match $permission_set {
+            $(
+                PermissionSet {
+                    read_level,
+                    create_level,
+                    update_level,
+                    delete_level
+                } if read_level == ReadLevel::$read &&
+                    create_level == CreateLevel::$create &&
+                    update_level == UpdateLevel::$update &&
+                    delete_level == DeleteLevel::$delete => {
+                    Box::new(ImportProfileService::<Policy<
+                        ImportProfileResource,
+                        ActionSet<
+                            $read,
+                            $create,
+                            $update,
+                            $delete
+                        >,
+                        Any
+                    >>::new($pool, ImportProfileRepository {}, $user))
+                },
+            )*
+            _ => {Box::new(ImportProfileService::<Policy<ImportProfileResource, ActionSet, Any>>::new($pool, ImportProfileRepository {}, $user))}
+        }
+    };
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ImportProfileServiceFactory;
+
+impl ImportProfileServiceFactory {
+    pub fn build(
+        user: RegisteredUser,
+        connection_pool: Arc<PgPool>,
+        permission_set: PermissionSet,
+    ) -> Box<dyn ImportProfileServiceMethods + Send> {
+        build_service!(permission_set, connection_pool, user;
+            [NoPermission, NoPermission, NoPermission, Delete],
+            [NoPermission, NoPermission, Update, NoPermission],
+            [NoPermission, NoPermission, Update, Delete],
+            [NoPermission, Create, NoPermission, NoPermission],
+            [NoPermission, Create, NoPermission, Delete],
+            [NoPermission, Create, Update, NoPermission],
+            [NoPermission, Create, Update, Delete],
+            [Read, NoPermission, NoPermission, NoPermission],
+            [Read, NoPermission, NoPermission, Delete],
+            [Read, NoPermission, Update, NoPermission],
+            [Read, NoPermission, Update, Delete],
+            [Read, Create, NoPermission, NoPermission],
+            [Read, Create, NoPermission, Delete],
+            [Read, Create, Update, NoPermission],
+            [Read, Create, Update, Delete],
+        )
+    }
+}
@@ -1,27 +1,34 @@
 use std::{marker::PhantomData, sync::Arc};
 
 use async_trait::async_trait;
-use sqlx::{Acquire, PgPool};
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
 
 use crate::{
     authorization::{
-        actions::{ActionSet, Create, Delete, NoPermission, Read, Update},
+        actions::{ActionSet, Create, Delete, NoPermission, Read, ReadLevel, Update},
         policy::Policy,
         resources::Institution as InstitutionResource,
     },
-    model::institution::{
-        Institution, InstitutionCreate, InstitutionFilter, InstitutionId, InstitutionUpdate,
+    model::{
+        ListSort,
+        institution::{
+            Institution, InstitutionCreate, InstitutionFilter, InstitutionId, InstitutionUpdate,
+        },
     },
     resource::{
         CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
-        institution_repository::InstitutionRepository,
+        deadline, institution_repository::InstitutionRepository,
     },
     service::{
         ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
-        ServiceUpdate,
+        ServiceUpdate, cache::ReadCache,
     },
 };
 
+/// How many levels an institution hierarchy may have, counting the root.
+pub const MAX_INSTITUTION_DEPTH: usize = 5;
+
 pub trait InstitutionServiceMethods:
     ServiceCrud<InstitutionId, Institution, InstitutionFilter, InstitutionCreate, InstitutionUpdate>
 {
@@ -42,6 +49,8 @@ impl<
 pub struct InstitutionService<Policy> {
     connection_pool: Arc<PgPool>,
     institution_repository: InstitutionRepository,
+    cache: ReadCache<InstitutionId, Institution>,
+    read_level: ReadLevel,
     policy: PhantomData<Policy>,
 }
 
@@ -53,9 +62,64 @@ impl<Policy> InstitutionService<Policy> {
         Self {
             connection_pool,
             institution_repository,
+            cache: ReadCache::new(0),
+            read_level: ReadLevel::NoPermission,
             policy: PhantomData,
         }
     }
+
+    /// Serves reads through `cache`, keyed by the read level of the caller.
+    pub fn with_cache(
+        mut self,
+        cache: ReadCache<InstitutionId, Institution>,
+        read_level: ReadLevel,
+    ) -> Self {
+        self.cache = cache;
+        self.read_level = read_level;
+        self
+    }
+
+    /// Walks the ancestry of `parent_id` to check that placing `id`, with
+    /// the institutions below it, under it neither creates a cycle nor
+    /// exceeds [`MAX_INSTITUTION_DEPTH`]. The ancestors are locked until
+    /// `transaction` ends, as is `id` by the caller, so concurrent moves
+    /// can't together make a cycle or a hierarchy too deep.
+    #[instrument(
+        name = "InstitutionService::check_parent",
+        skip_all,
+        fields(id = ?id, parent_id = ?parent_id)
+    )]
+    async fn check_parent(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: Option<InstitutionId>,
+        parent_id: InstitutionId,
+    ) -> Result<(), ServiceError> {
+        let mut depth = match id {
+            Some(id) => {
+                self.institution_repository
+                    .get_height(transaction.begin().await?, id, MAX_INSTITUTION_DEPTH as i32)
+                    .await? as usize
+            }
+            None => 1,
+        };
+        let mut ancestor_id = Some(parent_id);
+        while let Some(current_id) = ancestor_id {
+            if id == Some(current_id) {
+                return Err(ServiceError::InstitutionCycle);
+            }
+            depth += 1;
+            if depth > MAX_INSTITUTION_DEPTH {
+                return Err(ServiceError::InstitutionTooDeep);
+            }
+            ancestor_id = self
+                .institution_repository
+                .lock(transaction.begin().await?, current_id)
+                .await?
+                .parent_id;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -65,6 +129,7 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<InstitutionResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "InstitutionService::get", skip_all, fields(id = ?_id))]
     async fn get(&self, _id: InstitutionId) -> Result<Institution, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -77,6 +142,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<InstitutionResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(
+        name = "InstitutionService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
     async fn get_list(
         &self,
         _offset: i64,
@@ -94,12 +164,17 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<InstitutionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "InstitutionService::get", skip_all, fields(id = ?id))]
     async fn get(&self, id: InstitutionId) -> Result<Institution, ServiceError> {
-        let institution = self
-            .institution_repository
-            .get(self.connection_pool.begin().await?, id)
-            .await?;
-        Ok(institution)
+        self.cache
+            .item(self.read_level, id, async {
+                let institution = self
+                    .institution_repository
+                    .get(deadline::begin(&self.connection_pool).await?, id)
+                    .await?;
+                Ok(institution)
+            })
+            .await
     }
 }
 
@@ -110,17 +185,38 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<InstitutionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(
+        name = "InstitutionService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
     async fn get_list(
         &self,
         offset: i64,
         limit: Option<i64>,
         filter: InstitutionFilter,
     ) -> Result<Vec<Institution>, ServiceError> {
-        let institutions = self
-            .institution_repository
-            .get_list(self.connection_pool.begin().await?, offset, limit, filter)
-            .await?;
-        Ok(institutions)
+        let cacheable = offset == 0
+            && filter.name.is_none()
+            && filter.parent_id.is_none()
+            && filter.sort == ListSort::default();
+        let load = async {
+            let institutions = self
+                .institution_repository
+                .get_list(
+                    deadline::begin(&self.connection_pool).await?,
+                    offset,
+                    limit,
+                    filter,
+                )
+                .await?;
+            Ok(institutions)
+        };
+        if cacheable {
+            self.cache.first_page(self.read_level, limit, load).await
+        } else {
+            load.await
+        }
     }
 }
 
@@ -131,6 +227,7 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<InstitutionResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "InstitutionService::create", skip_all)]
     async fn create(&self, _create_model: InstitutionCreate) -> Result<Institution, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -143,11 +240,18 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<InstitutionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "InstitutionService::create", skip_all)]
     async fn create(&self, create_model: InstitutionCreate) -> Result<Institution, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        if let Some(parent_id) = create_model.parent_id {
+            self.check_parent(&mut transaction, None, parent_id).await?;
+        }
         let institution = self
             .institution_repository
-            .create(self.connection_pool.begin().await?, create_model)
+            .create(transaction.begin().await?, create_model)
             .await?;
+        transaction.commit().await?;
+        self.cache.invalidate();
         Ok(institution)
     }
 }
@@ -159,6 +263,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<InstitutionResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
     >
 {
+    #[instrument(name = "InstitutionService::update", skip_all, fields(id = ?_id))]
     async fn update(
         &self,
         _id: InstitutionId,
@@ -175,24 +280,34 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<InstitutionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "InstitutionService::update", skip_all, fields(id = ?id))]
     async fn update(
         &self,
         id: InstitutionId,
         update_model: InstitutionUpdate,
     ) -> Result<Institution, ServiceError> {
-        let mut transaction = self.connection_pool.begin().await?;
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
         let mut institution = self
             .institution_repository
-            .get(transaction.begin().await?, id)
+            .lock(transaction.begin().await?, id)
             .await?;
         if let Some(name) = update_model.name {
             institution.name = name;
         }
+        if let Some(parent_id) = update_model.parent_id {
+            self.check_parent(&mut transaction, Some(id), parent_id)
+                .await?;
+            institution.parent_id = Some(parent_id);
+        }
+        if let Some(default_asset_id) = update_model.default_asset_id {
+            institution.default_asset_id = Some(default_asset_id);
+        }
         let institution = self
             .institution_repository
             .update(transaction.begin().await?, institution)
             .await?;
         transaction.commit().await?;
+        self.cache.invalidate();
         Ok(institution)
     }
 }
@@ -204,6 +319,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
         Policy<InstitutionResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
     >
 {
+    #[instrument(name = "InstitutionService::delete", skip_all, fields(id = ?_id))]
     async fn delete(&self, _id: InstitutionId) -> Result<Institution, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -216,11 +332,25 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
         Policy<InstitutionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "InstitutionService::delete", skip_all, fields(id = ?id))]
     async fn delete(&self, id: InstitutionId) -> Result<Institution, ServiceError> {
+        let account_counts = self
+            .institution_repository
+            .get_account_counts(
+                deadline::begin(&self.connection_pool).await?,
+                &[id],
+                None,
+                None,
+            )
+            .await?;
+        if account_counts.contains_key(&id) {
+            return Err(ServiceError::InstitutionInUse);
+        }
         let institution = self
             .institution_repository
-            .delete(self.connection_pool.begin().await?, id)
+            .delete(deadline::begin(&self.connection_pool).await?, id)
             .await?;
+        self.cache.invalidate();
         Ok(institution)
     }
 }
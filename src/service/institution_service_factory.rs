@@ -12,11 +12,13 @@ use crate::authorization::resources::Institution as InstitutionResource;
 use crate::authorization::roles::Any;
 
 use crate::authorization::PermissionSet;
+use crate::model::institution::{Institution, InstitutionId};
 use crate::resource::institution_repository::InstitutionRepository;
+use crate::service::cache::ReadCache;
 use crate::service::institution_service::{InstitutionService, InstitutionServiceMethods};
 
 macro_rules! build_service {
-    ($permission_set:expr, $pool:expr;
+    ($permission_set:expr, $pool:expr, $cache:expr;
      $([ $read:ident, $create:ident, $update:ident, $delete:ident ]),* $(,)*) => {
         match $permission_set {
             $(
@@ -38,7 +40,7 @@ macro_rules! build_service {
                             $delete
                         >,
                         Any
-                    >>::new($pool, InstitutionRepository {}))
+                    >>::new($pool, InstitutionRepository {}).with_cache($cache, read_level))
                 },
             )*
             _ => {Box::new(InstitutionService::<Policy<InstitutionResource, ActionSet, Any>>::new($pool, InstitutionRepository {}))}
@@ -53,9 +55,10 @@ impl InstitutionServiceFactory {
     pub fn build(
         connection_pool: Arc<PgPool>,
         permission_set: PermissionSet,
+        cache: ReadCache<InstitutionId, Institution>,
     ) -> Box<dyn InstitutionServiceMethods + Send> {
         build_service!(
-            permission_set, connection_pool;
+            permission_set, connection_pool, cache;
             [NoPermission, NoPermission, NoPermission, Delete],
             [NoPermission, NoPermission, Update, NoPermission],
             [NoPermission, NoPermission, Update, Delete],
@@ -1,25 +1,102 @@
 pub mod account_service;
 pub mod account_service_factory;
+pub mod alert_rule_service;
+pub mod alert_rule_service_factory;
+pub mod announcement_service;
+pub mod announcement_service_factory;
+pub mod api_key_service;
+pub mod api_key_service_factory;
 pub mod asset_service;
 pub mod asset_service_factory;
+pub mod budget_service;
+pub mod budget_service_factory;
+pub mod cache;
+pub mod categorization_rule_service;
+pub mod categorization_rule_service_factory;
+pub mod export_schedule_service;
+pub mod export_schedule_service_factory;
+pub mod import_profile_service;
+pub mod import_profile_service_factory;
 pub mod institution_service;
 pub mod institution_service_factory;
+pub mod passkey_service;
+pub mod passkey_service_factory;
+pub mod proposal_service;
+pub mod proposal_service_factory;
+pub mod quick_entry_service;
+pub mod quick_entry_service_factory;
+pub mod sync_service;
+pub mod sync_service_factory;
 pub mod transaction_service;
 pub mod transaction_service_factory;
 pub mod user_service;
 pub mod user_service_factory;
+pub mod user_session_service;
+pub mod user_session_service_factory;
+pub mod watchlist_entry_service;
+pub mod watchlist_entry_service_factory;
 
 use async_trait::async_trait;
+use sqlx::{Acquire, PgTransaction};
 use thiserror::Error;
 
-use crate::resource::RepositoryError;
+use crate::{
+    model::{
+        account::{AccountFilter, AccountId},
+        user::UserId,
+    },
+    resource::{
+        GetListRepository, RepositoryError, account_repository::AccountRepository, deadline,
+    },
+};
 
 #[derive(Debug, Error, Clone)]
 pub enum ServiceError {
+    /// The account has legs of journal entries, which would no longer
+    /// balance without it.
+    #[error("The account has legs of journal entries.")]
+    AccountInJournalEntries,
+    /// Accounts of the same names are already at the institution.
+    #[error("Accounts named {0:?} already exist.")]
+    AccountsExist(Vec<String>),
     #[error("User is already registered.")]
     AlreadyRegistered,
+    /// The user has as many categorization rules as they may.
+    #[error("The user has too many categorization rules.")]
+    CategorizationRuleLimit,
+    /// The accounts to merge are at different institutions, and the merge
+    /// wasn't forced.
+    #[error("The accounts are at different institutions.")]
+    DifferentInstitutions,
+    /// The user keeps their books in double-entry mode, so transactions are
+    /// only created as the legs of journal entries.
+    #[error("Transactions are created as journal entries in double-entry mode.")]
+    DoubleEntry,
+    /// The token registering a user doesn't have a verified email.
+    #[error("The email address is not verified.")]
+    EmailNotVerified,
+    #[error("The institution hierarchy would contain a cycle.")]
+    InstitutionCycle,
+    #[error("The institution hierarchy would be too deep.")]
+    InstitutionTooDeep,
+    #[error("The institution still has accounts.")]
+    InstitutionInUse,
+    /// The transaction is a leg of a journal entry, which would no longer
+    /// balance.
+    #[error("The transaction is a leg of a journal entry.")]
+    JournalEntryLeg,
+    /// Sessions other than the current one were to be revoked, but the
+    /// request presented no refresh token of a session of the caller.
+    #[error("No current session.")]
+    NoCurrentSession,
     #[error("Item not found.")]
     NotFound,
+    /// The change proposal was already approved or rejected.
+    #[error("The proposal was already reviewed.")]
+    ProposalReviewed,
+    /// A query ran past the deadline of the request.
+    #[error("The query timed out.")]
+    Timeout,
     #[error("Unhandled repository error: {0}")]
     UnhandledRepositoryError(RepositoryError),
     #[error("Unhandled sqlx error: {0}")]
@@ -32,6 +109,7 @@ impl From<RepositoryError> for ServiceError {
     fn from(value: RepositoryError) -> Self {
         match value {
             RepositoryError::NotFound => Self::NotFound,
+            RepositoryError::Timeout => Self::Timeout,
             e => Self::UnhandledRepositoryError(e),
         }
     }
@@ -39,6 +117,10 @@ impl From<RepositoryError> for ServiceError {
 
 impl From<sqlx::Error> for ServiceError {
     fn from(value: sqlx::Error) -> Self {
+        if deadline::is_query_canceled(&value) {
+            deadline::record_timeout();
+            return Self::Timeout;
+        }
         Self::UnhandledSqlxError(format!("{value}"))
     }
 }
@@ -98,3 +180,42 @@ impl<
 > ServiceCrud<Id, Model, Filter, CreateModel, UpdateModel> for T
 {
 }
+
+/// Checks every account of `account_ids` is one of the user's. The accounts
+/// of other users are as good as missing.
+pub async fn check_accounts_owned(
+    transaction: &mut PgTransaction<'_>,
+    user_id: UserId,
+    account_ids: &[AccountId],
+) -> Result<(), ServiceError> {
+    let owned = AccountRepository
+        .get_list(
+            transaction.begin().await?,
+            0,
+            None,
+            AccountFilter {
+                user_id: user_id.into(),
+                account_ids: account_ids.to_vec().into(),
+                ..Default::default()
+            },
+        )
+        .await?;
+    if owned.len() != account_ids.len() {
+        return Err(ServiceError::NotFound);
+    }
+    Ok(())
+}
+
+/// Ends the database transaction a request ran in, committing it, or for a
+/// dry run rolling it back so that nothing it wrote is kept.
+pub async fn commit_unless_dry_run(
+    trans: PgTransaction<'_>,
+    dry_run: bool,
+) -> Result<(), ServiceError> {
+    if dry_run {
+        trans.rollback().await?;
+    } else {
+        trans.commit().await?;
+    }
+    Ok(())
+}
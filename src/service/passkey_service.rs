@@ -0,0 +1,427 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+use webauthn_rs::prelude::AuthenticationResult;
+
+use crate::{
+    authentication::{
+        registered_user::RegisteredUser,
+        step_up::{Elevation, STEP_UP_GRANT_TTL, WEBAUTHN_CHALLENGE_TTL},
+    },
+    authorization::{
+        actions::{ActionSet, Create, Delete, NoPermission, Read, Update},
+        policy::Policy,
+        resources::Passkey as PasskeyResource,
+    },
+    model::{
+        passkey::{Passkey, PasskeyCreate, PasskeyFilter, PasskeyId},
+        step_up_grant::{StepUpGrant, StepUpGrantCreate},
+        webauthn_challenge::{
+            CeremonyState, WebauthnChallenge, WebauthnChallengeCreate, WebauthnChallengeId,
+        },
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, RepositoryError,
+        UpdateRepository, deadline, passkey_repository::PasskeyRepository,
+        step_up_grant_repository::StepUpGrantRepository,
+        webauthn_challenge_repository::WebauthnChallengeRepository,
+    },
+    service::{ServiceCreate, ServiceDelete, ServiceError, ServiceGetList},
+};
+
+#[async_trait]
+pub trait PasskeyServiceChallenges {
+    /// Issues a challenge for the ceremony of `state`, which may be taken
+    /// until [`WEBAUTHN_CHALLENGE_TTL`] has passed.
+    async fn issue_challenge(
+        &self,
+        state: CeremonyState,
+    ) -> Result<WebauthnChallenge, ServiceError>;
+    /// Takes the challenge of `id`, which is single use: it is removed as
+    /// soon as it is presented, whether or not the ceremony then succeeds.
+    /// The challenges of other users and expired ones are as good as
+    /// missing, and the expired ones are swept as well.
+    async fn take_challenge(
+        &self,
+        id: WebauthnChallengeId,
+    ) -> Result<WebauthnChallenge, ServiceError>;
+}
+
+#[async_trait]
+pub trait PasskeyServiceElevation {
+    /// Whether the session may perform sensitive operations.
+    async fn elevation(&self, session: String) -> Result<Elevation, ServiceError>;
+}
+
+#[async_trait]
+pub trait PasskeyServiceStepUp {
+    /// Records the use of the passkey `result` was asserted with and
+    /// elevates the session for [`STEP_UP_GRANT_TTL`].
+    async fn step_up(
+        &self,
+        session: String,
+        result: AuthenticationResult,
+    ) -> Result<StepUpGrant, ServiceError>;
+}
+
+#[async_trait]
+pub trait PasskeyServiceMethods:
+    ServiceGetList<PasskeyFilter, Passkey>
+    + ServiceCreate<PasskeyCreate, Passkey>
+    + ServiceDelete<PasskeyId, Passkey>
+    + PasskeyServiceChallenges
+    + PasskeyServiceElevation
+    + PasskeyServiceStepUp
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceGetList<PasskeyFilter, Passkey>
+        + ServiceCreate<PasskeyCreate, Passkey>
+        + ServiceDelete<PasskeyId, Passkey>
+        + PasskeyServiceChallenges
+        + PasskeyServiceElevation
+        + PasskeyServiceStepUp,
+> PasskeyServiceMethods for T
+{
+}
+
+pub struct PasskeyService<Policy> {
+    connection_pool: Arc<PgPool>,
+    passkey_repository: PasskeyRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> PasskeyService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        passkey_repository: PasskeyRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            passkey_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the passkeys of the caller. The passkeys of other
+    /// users are as good as missing.
+    async fn caller_passkey(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: PasskeyId,
+    ) -> Result<Passkey, ServiceError> {
+        let passkey = self
+            .passkey_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if passkey.user_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(passkey)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<PasskeyFilter, Passkey>
+    for PasskeyService<
+        Policy<PasskeyResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "PasskeyService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: PasskeyFilter,
+    ) -> Result<Vec<Passkey>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    PasskeyServiceChallenges
+    for PasskeyService<
+        Policy<PasskeyResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "PasskeyService::issue_challenge", skip_all)]
+    async fn issue_challenge(
+        &self,
+        _state: CeremonyState,
+    ) -> Result<WebauthnChallenge, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "PasskeyService::take_challenge", skip_all, fields(id = ?_id))]
+    async fn take_challenge(
+        &self,
+        _id: WebauthnChallengeId,
+    ) -> Result<WebauthnChallenge, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    PasskeyServiceElevation
+    for PasskeyService<
+        Policy<PasskeyResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "PasskeyService::elevation", skip_all)]
+    async fn elevation(&self, _session: String) -> Result<Elevation, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<PasskeyFilter, Passkey>
+    for PasskeyService<Policy<PasskeyResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "PasskeyService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: PasskeyFilter,
+    ) -> Result<Vec<Passkey>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let passkeys = self
+            .passkey_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(passkeys)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    PasskeyServiceChallenges
+    for PasskeyService<Policy<PasskeyResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "PasskeyService::issue_challenge", skip_all)]
+    async fn issue_challenge(
+        &self,
+        state: CeremonyState,
+    ) -> Result<WebauthnChallenge, ServiceError> {
+        let challenge = WebauthnChallengeRepository
+            .create(
+                deadline::begin(&self.connection_pool).await?,
+                WebauthnChallengeCreate {
+                    user_id: self.registered_user.id(),
+                    expires_at: Utc::now() + Duration::seconds(WEBAUTHN_CHALLENGE_TTL),
+                    state,
+                },
+            )
+            .await?;
+        Ok(challenge)
+    }
+
+    #[instrument(name = "PasskeyService::take_challenge", skip_all, fields(id = ?id))]
+    async fn take_challenge(
+        &self,
+        id: WebauthnChallengeId,
+    ) -> Result<WebauthnChallenge, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let challenge = WebauthnChallengeRepository
+            .delete(transaction.begin().await?, id)
+            .await?;
+        // Expired challenges are never completed, so they are swept as any
+        // one is consumed rather than by a separate task.
+        WebauthnChallengeRepository
+            .delete_expired(transaction.begin().await?)
+            .await?;
+        transaction.commit().await?;
+        if challenge.user_id != self.registered_user.id() || challenge.expires_at < Utc::now() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(challenge)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    PasskeyServiceElevation
+    for PasskeyService<Policy<PasskeyResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "PasskeyService::elevation", skip_all)]
+    async fn elevation(&self, session: String) -> Result<Elevation, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let passkeys = self
+            .passkey_repository
+            .get_list(
+                transaction.begin().await?,
+                0,
+                1.into(),
+                PasskeyFilter {
+                    user_id: self.registered_user.id().into(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        if passkeys.is_empty() {
+            return Ok(Elevation {
+                required: false,
+                elevated: false,
+            });
+        }
+        let elevated = match StepUpGrantRepository
+            .get(transaction.begin().await?, session)
+            .await
+        {
+            Ok(grant) => grant.user_id == self.registered_user.id(),
+            Err(RepositoryError::NotFound) => false,
+            Err(e) => return Err(e.into()),
+        };
+        transaction.commit().await?;
+        Ok(Elevation {
+            required: true,
+            elevated,
+        })
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<PasskeyCreate, Passkey>
+    for PasskeyService<Policy<PasskeyResource, ActionSet<Read, NoPermission, Update, Delete>, Role>>
+{
+    #[instrument(name = "PasskeyService::create", skip_all)]
+    async fn create(&self, _create_model: PasskeyCreate) -> Result<Passkey, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<PasskeyCreate, Passkey>
+    for PasskeyService<Policy<PasskeyResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "PasskeyService::create", skip_all)]
+    async fn create(&self, create_model: PasskeyCreate) -> Result<Passkey, ServiceError> {
+        if self.registered_user.id() != create_model.user_id {
+            return Err(ServiceError::Unauthorized);
+        }
+        let passkey = self
+            .passkey_repository
+            .create(deadline::begin(&self.connection_pool).await?, create_model)
+            .await?;
+        Ok(passkey)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    PasskeyServiceStepUp
+    for PasskeyService<Policy<PasskeyResource, ActionSet<Read, Create, NoPermission, Delete>, Role>>
+{
+    #[instrument(name = "PasskeyService::step_up", skip_all)]
+    async fn step_up(
+        &self,
+        _session: String,
+        _result: AuthenticationResult,
+    ) -> Result<StepUpGrant, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    PasskeyServiceStepUp
+    for PasskeyService<Policy<PasskeyResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "PasskeyService::step_up", skip_all)]
+    async fn step_up(
+        &self,
+        session: String,
+        result: AuthenticationResult,
+    ) -> Result<StepUpGrant, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let mut passkey = self
+            .passkey_repository
+            .get_list(
+                transaction.begin().await?,
+                0,
+                1.into(),
+                PasskeyFilter {
+                    user_id: self.registered_user.id().into(),
+                    credential_id: result.cred_id().as_ref().to_vec().into(),
+                },
+            )
+            .await?
+            .pop()
+            .ok_or(ServiceError::NotFound)?;
+        // Keeps the signature counter of the passkey, so a clone of it is
+        // caught the next time either is used.
+        passkey.credential.update_credential(&result);
+        passkey.last_used_at = Some(Utc::now());
+        self.passkey_repository
+            .update(transaction.begin().await?, passkey)
+            .await?;
+        let grant = StepUpGrantRepository
+            .create(
+                transaction.begin().await?,
+                StepUpGrantCreate {
+                    session,
+                    expires_at: Utc::now() + Duration::seconds(STEP_UP_GRANT_TTL),
+                    user_id: self.registered_user.id(),
+                },
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(grant)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<PasskeyId, Passkey>
+    for PasskeyService<Policy<PasskeyResource, ActionSet<Read, Create, Update, NoPermission>, Role>>
+{
+    #[instrument(name = "PasskeyService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: PasskeyId) -> Result<Passkey, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<PasskeyId, Passkey>
+    for PasskeyService<Policy<PasskeyResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "PasskeyService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: PasskeyId) -> Result<Passkey, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let passkey = self.caller_passkey(&mut transaction, id).await?;
+        let passkey = self
+            .passkey_repository
+            .delete(transaction.begin().await?, passkey.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(passkey)
+    }
+}
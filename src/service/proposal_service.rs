@@ -0,0 +1,413 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Create, CreateAll, NoPermission, Read, ReadAll, Update, UpdateAll},
+        policy::Policy,
+        resources::Proposal as ProposalResource,
+    },
+    model::{
+        asset::AssetId,
+        change_proposal::{
+            ChangeProposal, ChangeProposalCreate, ChangeProposalFilter, ChangeProposalId,
+            ChangeProposalStatus,
+        },
+        institution::InstitutionId,
+    },
+    resource::{
+        CreateRepository, GetListRepository, GetRepository, RepositoryError,
+        change_proposal_repository::ChangeProposalRepository, deadline,
+        institution_repository::InstitutionRepository,
+    },
+    service::{ServiceCreate, ServiceError, ServiceGet, ServiceGetList},
+};
+
+/// Reviewing a proposal takes `update_all`. Its change is made by the
+/// reviewer through the service of the resource it changes, between
+/// claiming and completing the proposal.
+#[async_trait]
+pub trait ProposalServiceReview {
+    /// Decides a pending proposal, so only one review of it goes through.
+    /// A proposal that was already decided is
+    /// [`ServiceError::ProposalReviewed`].
+    async fn claim(
+        &self,
+        id: ChangeProposalId,
+        status: ChangeProposalStatus,
+        review_note: Option<String>,
+    ) -> Result<ChangeProposal, ServiceError>;
+    /// Returns a claimed proposal to review, as its change couldn't be made.
+    async fn release(&self, id: ChangeProposalId) -> Result<(), ServiceError>;
+    /// Links a decided proposal to what approving it created or changed,
+    /// and notifies the proposer of the decision.
+    async fn complete(
+        &self,
+        id: ChangeProposalId,
+        institution_id: Option<InstitutionId>,
+        asset_id: Option<AssetId>,
+    ) -> Result<ChangeProposal, ServiceError>;
+}
+
+/// Users propose changes and read their own proposals, admins read and
+/// review all of them.
+#[async_trait]
+pub trait ProposalServiceMethods:
+    ServiceGet<ChangeProposalId, ChangeProposal>
+    + ServiceGetList<ChangeProposalFilter, ChangeProposal>
+    + ServiceCreate<ChangeProposalCreate, ChangeProposal>
+    + ProposalServiceReview
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceGet<ChangeProposalId, ChangeProposal>
+        + ServiceGetList<ChangeProposalFilter, ChangeProposal>
+        + ServiceCreate<ChangeProposalCreate, ChangeProposal>
+        + ProposalServiceReview,
+> ProposalServiceMethods for T
+{
+}
+
+pub struct ProposalService<Policy> {
+    connection_pool: Arc<PgPool>,
+    change_proposal_repository: ChangeProposalRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> ProposalService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        change_proposal_repository: ChangeProposalRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            change_proposal_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Records a proposal of the caller, who can't propose on behalf of
+    /// others. The institution an edit is of must exist.
+    async fn propose(
+        &self,
+        create_model: ChangeProposalCreate,
+    ) -> Result<ChangeProposal, ServiceError> {
+        if create_model.proposer_id != self.registered_user.id() {
+            return Err(ServiceError::Unauthorized);
+        }
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        if let Some(institution_id) = create_model.institution_id {
+            InstitutionRepository
+                .get(transaction.begin().await?, institution_id)
+                .await?;
+        }
+        let proposal = self
+            .change_proposal_repository
+            .create(transaction.begin().await?, create_model)
+            .await?;
+        transaction.commit().await?;
+        Ok(proposal)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<ChangeProposalId, ChangeProposal>
+    for ProposalService<
+        Policy<ProposalResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ProposalService::get", skip_all, fields(id = ?_id))]
+    async fn get(&self, _id: ChangeProposalId) -> Result<ChangeProposal, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<ChangeProposalFilter, ChangeProposal>
+    for ProposalService<
+        Policy<ProposalResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "ProposalService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: ChangeProposalFilter,
+    ) -> Result<Vec<ChangeProposal>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<ChangeProposalId, ChangeProposal>
+    for ProposalService<Policy<ProposalResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "ProposalService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: ChangeProposalId) -> Result<ChangeProposal, ServiceError> {
+        let proposal = self
+            .change_proposal_repository
+            .get(deadline::begin(&self.connection_pool).await?, id)
+            .await?;
+        // The proposals of other users are as good as missing.
+        if proposal.proposer_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(proposal)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<ChangeProposalFilter, ChangeProposal>
+    for ProposalService<Policy<ProposalResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "ProposalService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: ChangeProposalFilter,
+    ) -> Result<Vec<ChangeProposal>, ServiceError> {
+        filter.proposer_id = self.registered_user.id().into();
+        let proposals = self
+            .change_proposal_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(proposals)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<ChangeProposalId, ChangeProposal>
+    for ProposalService<Policy<ProposalResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "ProposalService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: ChangeProposalId) -> Result<ChangeProposal, ServiceError> {
+        let proposal = self
+            .change_proposal_repository
+            .get(deadline::begin(&self.connection_pool).await?, id)
+            .await?;
+        Ok(proposal)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<ChangeProposalFilter, ChangeProposal>
+    for ProposalService<Policy<ProposalResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "ProposalService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        filter: ChangeProposalFilter,
+    ) -> Result<Vec<ChangeProposal>, ServiceError> {
+        let proposals = self
+            .change_proposal_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(proposals)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<ChangeProposalCreate, ChangeProposal>
+    for ProposalService<
+        Policy<ProposalResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ProposalService::create", skip_all)]
+    async fn create(
+        &self,
+        _create_model: ChangeProposalCreate,
+    ) -> Result<ChangeProposal, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<ChangeProposalCreate, ChangeProposal>
+    for ProposalService<Policy<ProposalResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "ProposalService::create", skip_all)]
+    async fn create(
+        &self,
+        create_model: ChangeProposalCreate,
+    ) -> Result<ChangeProposal, ServiceError> {
+        self.propose(create_model).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<ChangeProposalCreate, ChangeProposal>
+    for ProposalService<Policy<ProposalResource, ActionSet<Read, CreateAll, Update, Delete>, Role>>
+{
+    #[instrument(name = "ProposalService::create", skip_all)]
+    async fn create(
+        &self,
+        create_model: ChangeProposalCreate,
+    ) -> Result<ChangeProposal, ServiceError> {
+        self.propose(create_model).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ProposalServiceReview
+    for ProposalService<
+        Policy<ProposalResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "ProposalService::claim", skip_all, fields(id = ?_id))]
+    async fn claim(
+        &self,
+        _id: ChangeProposalId,
+        _status: ChangeProposalStatus,
+        _review_note: Option<String>,
+    ) -> Result<ChangeProposal, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "ProposalService::release", skip_all, fields(id = ?_id))]
+    async fn release(&self, _id: ChangeProposalId) -> Result<(), ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "ProposalService::complete", skip_all, fields(id = ?_id))]
+    async fn complete(
+        &self,
+        _id: ChangeProposalId,
+        _institution_id: Option<InstitutionId>,
+        _asset_id: Option<AssetId>,
+    ) -> Result<ChangeProposal, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ProposalServiceReview
+    for ProposalService<Policy<ProposalResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "ProposalService::claim", skip_all, fields(id = ?_id))]
+    async fn claim(
+        &self,
+        _id: ChangeProposalId,
+        _status: ChangeProposalStatus,
+        _review_note: Option<String>,
+    ) -> Result<ChangeProposal, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "ProposalService::release", skip_all, fields(id = ?_id))]
+    async fn release(&self, _id: ChangeProposalId) -> Result<(), ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "ProposalService::complete", skip_all, fields(id = ?_id))]
+    async fn complete(
+        &self,
+        _id: ChangeProposalId,
+        _institution_id: Option<InstitutionId>,
+        _asset_id: Option<AssetId>,
+    ) -> Result<ChangeProposal, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ProposalServiceReview
+    for ProposalService<Policy<ProposalResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>>
+{
+    #[instrument(name = "ProposalService::claim", skip_all, fields(id = ?id))]
+    async fn claim(
+        &self,
+        id: ChangeProposalId,
+        status: ChangeProposalStatus,
+        review_note: Option<String>,
+    ) -> Result<ChangeProposal, ServiceError> {
+        let proposal = self
+            .change_proposal_repository
+            .claim(
+                deadline::begin(&self.connection_pool).await?,
+                id,
+                status,
+                self.registered_user.id(),
+                review_note,
+            )
+            .await
+            .map_err(|e| match e {
+                RepositoryError::AlreadyConsumed => ServiceError::ProposalReviewed,
+                e => e.into(),
+            })?;
+        Ok(proposal)
+    }
+
+    #[instrument(name = "ProposalService::release", skip_all, fields(id = ?id))]
+    async fn release(&self, id: ChangeProposalId) -> Result<(), ServiceError> {
+        self.change_proposal_repository
+            .release(deadline::begin(&self.connection_pool).await?, id)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ProposalService::complete", skip_all, fields(id = ?id))]
+    async fn complete(
+        &self,
+        id: ChangeProposalId,
+        institution_id: Option<InstitutionId>,
+        asset_id: Option<AssetId>,
+    ) -> Result<ChangeProposal, ServiceError> {
+        let proposal = self
+            .change_proposal_repository
+            .complete(
+                deadline::begin(&self.connection_pool).await?,
+                id,
+                institution_id,
+                asset_id,
+            )
+            .await?;
+        Ok(proposal)
+    }
+}
@@ -0,0 +1,320 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Create, Delete, NoPermission, Read, Update},
+        policy::Policy,
+        resources::QuickEntry as QuickEntryResource,
+    },
+    model::quick_entry::{QuickEntry, QuickEntryCreate, QuickEntryFilter, QuickEntryId},
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
+        deadline, quick_entry_repository::QuickEntryRepository,
+    },
+    service::{
+        ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
+        ServiceUpdate, check_accounts_owned,
+    },
+};
+
+#[async_trait]
+pub trait QuickEntryServiceExecutable {
+    /// The quick entry of `id` to be executed, with its account checked to
+    /// still be the caller's. An entry whose account was deleted is returned
+    /// as is.
+    async fn executable(&self, id: QuickEntryId) -> Result<QuickEntry, ServiceError>;
+}
+
+/// Quick entries are updated by the entry with its changes applied, all but
+/// the owner of which are taken.
+#[async_trait]
+pub trait QuickEntryServiceMethods:
+    ServiceCrud<QuickEntryId, QuickEntry, QuickEntryFilter, QuickEntryCreate, QuickEntry>
+    + QuickEntryServiceExecutable
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceCrud<QuickEntryId, QuickEntry, QuickEntryFilter, QuickEntryCreate, QuickEntry>
+        + QuickEntryServiceExecutable,
+> QuickEntryServiceMethods for T
+{
+}
+
+pub struct QuickEntryService<Policy> {
+    connection_pool: Arc<PgPool>,
+    quick_entry_repository: QuickEntryRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> QuickEntryService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        quick_entry_repository: QuickEntryRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            quick_entry_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the quick entries of the caller. The entries of other
+    /// users are as good as missing.
+    async fn caller_quick_entry(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: QuickEntryId,
+    ) -> Result<QuickEntry, ServiceError> {
+        let quick_entry = self
+            .quick_entry_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if quick_entry.user_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(quick_entry)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<QuickEntryId, QuickEntry>
+    for QuickEntryService<
+        Policy<QuickEntryResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "QuickEntryService::get", skip_all, fields(id = ?_id))]
+    async fn get(&self, _id: QuickEntryId) -> Result<QuickEntry, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<QuickEntryFilter, QuickEntry>
+    for QuickEntryService<
+        Policy<QuickEntryResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "QuickEntryService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: QuickEntryFilter,
+    ) -> Result<Vec<QuickEntry>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<QuickEntryId, QuickEntry>
+    for QuickEntryService<Policy<QuickEntryResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "QuickEntryService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: QuickEntryId) -> Result<QuickEntry, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let quick_entry = self.caller_quick_entry(&mut transaction, id).await?;
+        transaction.commit().await?;
+        Ok(quick_entry)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<QuickEntryFilter, QuickEntry>
+    for QuickEntryService<Policy<QuickEntryResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(
+        name = "QuickEntryService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: QuickEntryFilter,
+    ) -> Result<Vec<QuickEntry>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let quick_entries = self
+            .quick_entry_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(quick_entries)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    QuickEntryServiceExecutable
+    for QuickEntryService<
+        Policy<QuickEntryResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "QuickEntryService::executable", skip_all, fields(id = ?_id))]
+    async fn executable(&self, _id: QuickEntryId) -> Result<QuickEntry, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    QuickEntryServiceExecutable
+    for QuickEntryService<Policy<QuickEntryResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "QuickEntryService::executable", skip_all, fields(id = ?id))]
+    async fn executable(&self, id: QuickEntryId) -> Result<QuickEntry, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        // The account may have changed hands since the entry was saved.
+        let quick_entry = self.caller_quick_entry(&mut transaction, id).await?;
+        if let Some(account_id) = quick_entry.account_id {
+            check_accounts_owned(&mut transaction, quick_entry.user_id, &[account_id]).await?;
+        }
+        transaction.commit().await?;
+        Ok(quick_entry)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<QuickEntryCreate, QuickEntry>
+    for QuickEntryService<
+        Policy<QuickEntryResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "QuickEntryService::create", skip_all)]
+    async fn create(&self, _create_model: QuickEntryCreate) -> Result<QuickEntry, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<QuickEntryCreate, QuickEntry>
+    for QuickEntryService<Policy<QuickEntryResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "QuickEntryService::create", skip_all)]
+    async fn create(&self, create_model: QuickEntryCreate) -> Result<QuickEntry, ServiceError> {
+        if self.registered_user.id() != create_model.user_id {
+            return Err(ServiceError::Unauthorized);
+        }
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        check_accounts_owned(
+            &mut transaction,
+            create_model.user_id,
+            &[create_model.account_id],
+        )
+        .await?;
+        let quick_entry = self
+            .quick_entry_repository
+            .create(transaction.begin().await?, create_model)
+            .await?;
+        transaction.commit().await?;
+        Ok(quick_entry)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<QuickEntryId, QuickEntry, QuickEntry>
+    for QuickEntryService<
+        Policy<QuickEntryResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "QuickEntryService::update", skip_all, fields(id = ?_id))]
+    async fn update(
+        &self,
+        _id: QuickEntryId,
+        _update_model: QuickEntry,
+    ) -> Result<QuickEntry, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<QuickEntryId, QuickEntry, QuickEntry>
+    for QuickEntryService<Policy<QuickEntryResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "QuickEntryService::update", skip_all, fields(id = ?id))]
+    async fn update(
+        &self,
+        id: QuickEntryId,
+        update_model: QuickEntry,
+    ) -> Result<QuickEntry, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let quick_entry = self.caller_quick_entry(&mut transaction, id).await?;
+        if let Some(account_id) = update_model.account_id
+            && update_model.account_id != quick_entry.account_id
+        {
+            check_accounts_owned(&mut transaction, quick_entry.user_id, &[account_id]).await?;
+        }
+        let quick_entry = QuickEntry {
+            label: update_model.label,
+            account_id: update_model.account_id,
+            asset_id: update_model.asset_id,
+            quantity: update_model.quantity,
+            description: update_model.description,
+            category: update_model.category,
+            ..quick_entry
+        };
+        let quick_entry = self
+            .quick_entry_repository
+            .update(transaction.begin().await?, quick_entry)
+            .await?;
+        transaction.commit().await?;
+        Ok(quick_entry)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<QuickEntryId, QuickEntry>
+    for QuickEntryService<
+        Policy<QuickEntryResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "QuickEntryService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: QuickEntryId) -> Result<QuickEntry, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<QuickEntryId, QuickEntry>
+    for QuickEntryService<Policy<QuickEntryResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "QuickEntryService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: QuickEntryId) -> Result<QuickEntry, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let quick_entry = self.caller_quick_entry(&mut transaction, id).await?;
+        let quick_entry = self
+            .quick_entry_repository
+            .delete(transaction.begin().await?, quick_entry.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(quick_entry)
+    }
+}
@@ -0,0 +1,136 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, NoPermission, Read},
+        policy::Policy,
+        resources::SyncChange as SyncChangeResource,
+    },
+    model::sync::SyncChanges,
+    resource::{deadline, sync_repository::SyncRepository},
+    schema::sync::SyncToken,
+    service::ServiceError,
+};
+
+/// Delta syncs only ever read the changes of the caller, in the accounts
+/// their API key is scoped to if it is.
+#[async_trait]
+pub trait SyncServiceMethods {
+    /// The changes after the watermarks of `since`, at most `limit` of
+    /// them.
+    async fn changes(&self, since: SyncToken, limit: i64) -> Result<SyncChanges, ServiceError>;
+}
+
+pub struct SyncService<Policy> {
+    connection_pool: Arc<PgPool>,
+    sync_repository: SyncRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> SyncService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        sync_repository: SyncRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            sync_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    SyncServiceMethods
+    for SyncService<
+        Policy<SyncChangeResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "SyncService::changes", skip_all, fields(limit = _limit))]
+    async fn changes(&self, _since: SyncToken, _limit: i64) -> Result<SyncChanges, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    SyncServiceMethods
+    for SyncService<Policy<SyncChangeResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "SyncService::changes", skip_all, fields(limit = limit))]
+    async fn changes(&self, since: SyncToken, limit: i64) -> Result<SyncChanges, ServiceError> {
+        let user_id = self.registered_user.id();
+        let account_ids = self.registered_user.account_scope();
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+
+        // Each kind of change is fetched with one extra row to tell whether
+        // there are more, filling the page with accounts first.
+        let mut accounts = self
+            .sync_repository
+            .get_changed_accounts(
+                transaction.begin().await?,
+                user_id,
+                account_ids.clone(),
+                since.account_watermark(),
+                limit + 1,
+            )
+            .await?;
+        let mut has_more = accounts.len() as i64 > limit;
+        accounts.truncate(limit as usize);
+        let mut remaining = limit - accounts.len() as i64;
+
+        let mut transactions = vec![];
+        if remaining > 0 {
+            transactions = self
+                .sync_repository
+                .get_changed_transactions(
+                    transaction.begin().await?,
+                    user_id,
+                    account_ids.clone(),
+                    since.transaction_watermark(),
+                    remaining + 1,
+                )
+                .await?;
+            has_more |= transactions.len() as i64 > remaining;
+            transactions.truncate(remaining as usize);
+            remaining -= transactions.len() as i64;
+        } else {
+            has_more = true;
+        }
+
+        let mut tombstones = vec![];
+        if remaining > 0 {
+            tombstones = self
+                .sync_repository
+                .get_tombstones(
+                    transaction.begin().await?,
+                    user_id,
+                    account_ids,
+                    since.tombstone_id,
+                    remaining + 1,
+                )
+                .await?;
+            has_more |= tombstones.len() as i64 > remaining;
+            tombstones.truncate(remaining as usize);
+        } else {
+            has_more = true;
+        }
+        transaction.commit().await?;
+
+        Ok(SyncChanges {
+            accounts,
+            transactions,
+            tombstones,
+            has_more,
+        })
+    }
+}
@@ -1,9 +1,13 @@
 use std::{marker::PhantomData, sync::Arc};
 
 use async_trait::async_trait;
-use sqlx::{Acquire, PgPool};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::{error, info, instrument};
 
 use crate::{
+    api::event_hub::{ALERT_FIRED, Event, EventHub},
     authentication::registered_user::RegisteredUser,
     authorization::{
         actions::{
@@ -13,22 +17,214 @@ use crate::{
         policy::Policy,
         resources::Transaction as TransactionResource,
     },
-    model::transaction::{
-        Transaction, TransactionCreate, TransactionFilter, TransactionId, TransactionUpdate,
+    categorization::{Categorizer, MAX_RULES, RecategorizationJob, dry_run_recategorization},
+    config::{Feature, FeatureFlags},
+    model::{
+        account::AccountId,
+        alert_rule::{AlertChannel, FiredAlert},
+        attachment::{Attachment, AttachmentCreate, AttachmentId},
+        categorization_rule::{
+            CategorizationRule, CategorizationRuleCreate, CategorizationRuleFilter,
+        },
+        journal_entry::{
+            JournalEntryCreate, JournalEntryId, JournalEntryWithLegs, TrialBalanceLine,
+        },
+        recategorization::{Recategorization, RecategorizationCreate, RecategorizationId},
+        transaction::{
+            Transaction, TransactionConversion, TransactionCreate, TransactionFilter,
+            TransactionId, TransactionUpdate,
+        },
+        transaction_history::{TransactionHistory, TransactionHistoryFilter, TransactionHistoryId},
+        user::UserId,
     },
     resource::{
         CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
+        account_balance_repository::AccountBalanceRepository,
+        alert_rule_repository::AlertRuleRepository, attachment_repository::AttachmentRepository,
+        categorization_rule_repository::CategorizationRuleRepository, deadline,
+        journal_entry_repository::JournalEntryRepository,
+        recategorization_repository::RecategorizationRepository,
+        transaction_history_repository::TransactionHistoryRepository,
         transaction_repository::TransactionRepository,
     },
+    rounding::RoundingPolicy,
+    schema::{alert_rule::AlertEventResponse, transaction::GetListRequest},
     service::{
         ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
-        ServiceUpdate,
+        ServiceUpdate, commit_unless_dry_run,
     },
 };
 
+#[async_trait]
+pub trait TransactionServiceConvert {
+    /// Converts transactions the caller has already been allowed to read
+    /// into the asset with `quote_symbol`, without altering them. Each is
+    /// rounded by `rounding`.
+    async fn convert(
+        &self,
+        transactions: &[Transaction],
+        quote_symbol: String,
+        rounding: RoundingPolicy,
+    ) -> Result<Vec<TransactionConversion>, ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceHistory {
+    /// The latest edits of a transaction the caller can read, newest first.
+    async fn get_history(&self, id: TransactionId)
+    -> Result<Vec<TransactionHistory>, ServiceError>;
+
+    /// Restores a transaction to how it was before one of its edits. The
+    /// restore is an update like any other, so it is recorded in the
+    /// history in turn.
+    async fn revert(
+        &self,
+        id: TransactionId,
+        history_id: TransactionHistoryId,
+    ) -> Result<Transaction, ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceJournal {
+    /// A journal entry with its legs the caller can read. Entries without
+    /// any are indistinguishable from missing ones.
+    async fn get_journal_entry(
+        &self,
+        id: JournalEntryId,
+    ) -> Result<JournalEntryWithLegs, ServiceError>;
+
+    /// The balance of each account the caller can read in each asset, from
+    /// the transactions posted from `from`, if given, up to `as_of`.
+    async fn trial_balance(
+        &self,
+        from: Option<DateTime<Utc>>,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<TrialBalanceLine>, ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceJournalCreate {
+    /// Creates the entry and a transaction for each of its legs, or nothing
+    /// at all. The legs are expected to balance already.
+    async fn create_journal_entry(
+        &self,
+        create_model: JournalEntryCreate,
+    ) -> Result<JournalEntryWithLegs, ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceCreateMany {
+    /// Creates every transaction or none of them. A dry run creates them
+    /// and adjusts balances as it would otherwise, then rolls all of it back
+    /// without sending the alerts that fired.
+    async fn create_many(
+        &self,
+        create_models: Vec<TransactionCreate>,
+        dry_run: bool,
+    ) -> Result<Vec<Transaction>, ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceJournalDelete {
+    /// Deletes the entry together with all of its legs, which the caller has
+    /// to be able to delete each of.
+    async fn delete_journal_entry(
+        &self,
+        id: JournalEntryId,
+    ) -> Result<JournalEntryWithLegs, ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceUncategorized {
+    /// The latest `limit` transactions of the caller without a category,
+    /// with how many there are in all. The queue is the caller's own
+    /// whatever else they can read.
+    async fn get_uncategorized(&self, limit: i64) -> Result<(Vec<Transaction>, i64), ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceRecent {
+    /// The latest `limit` transactions of the caller by when they were
+    /// posted. They are the caller's own whatever else they can read.
+    async fn get_recent(&self, limit: i64) -> Result<Vec<Transaction>, ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceCategorize {
+    /// Sets the category of a transaction by hand, creating `rule` along
+    /// with it, or does neither.
+    async fn categorize(
+        &self,
+        id: TransactionId,
+        category: String,
+        rule: Option<CategorizationRuleCreate>,
+    ) -> Result<(Transaction, Option<CategorizationRule>), ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceRecategorize {
+    /// How many transactions of the caller the filter matches. Only the
+    /// caller's own transactions are recategorized, whatever else they may
+    /// update.
+    async fn count_recategorizable(&self, filter: GetListRequest) -> Result<i64, ServiceError>;
+
+    /// How many of the transactions the filter matches setting `category`
+    /// would change, without changing any.
+    async fn dry_run_recategorize(
+        &self,
+        filter: GetListRequest,
+        category: String,
+    ) -> Result<i64, ServiceError>;
+
+    /// Records a recategorization of the transactions the filter matches,
+    /// returning the job that carries it out. Recategorizations of other
+    /// users are [`ServiceError::Unauthorized`].
+    async fn recategorize(
+        &self,
+        create_model: RecategorizationCreate,
+        filter: GetListRequest,
+    ) -> Result<RecategorizationJob, ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceRecategorization {
+    /// A recategorization of the caller. Those of other users are
+    /// indistinguishable from missing ones.
+    async fn get_recategorization(
+        &self,
+        id: RecategorizationId,
+    ) -> Result<Recategorization, ServiceError>;
+}
+
+#[async_trait]
+pub trait TransactionServiceAttachment {
+    /// Attaches a file to a transaction the caller can update.
+    async fn create_attachment(
+        &self,
+        create_model: AttachmentCreate,
+    ) -> Result<Attachment, ServiceError>;
+
+    /// Deletes an attachment of a transaction the caller can update.
+    /// Attachments of other transactions are indistinguishable from missing
+    /// ones.
+    async fn delete_attachment(&self, id: AttachmentId) -> Result<Attachment, ServiceError>;
+}
+
 #[async_trait]
 pub trait TransactionServiceMethods:
     ServiceCrud<TransactionId, Transaction, TransactionFilter, TransactionCreate, TransactionUpdate>
+    + TransactionServiceConvert
+    + TransactionServiceHistory
+    + TransactionServiceJournal
+    + TransactionServiceJournalCreate
+    + TransactionServiceCreateMany
+    + TransactionServiceJournalDelete
+    + TransactionServiceUncategorized
+    + TransactionServiceRecent
+    + TransactionServiceCategorize
+    + TransactionServiceRecategorize
+    + TransactionServiceRecategorization
+    + TransactionServiceAttachment
 {
 }
 
@@ -40,7 +236,18 @@ impl<
             TransactionFilter,
             TransactionCreate,
             TransactionUpdate,
-        >,
+        > + TransactionServiceConvert
+        + TransactionServiceHistory
+        + TransactionServiceJournal
+        + TransactionServiceJournalCreate
+        + TransactionServiceCreateMany
+        + TransactionServiceJournalDelete
+        + TransactionServiceUncategorized
+        + TransactionServiceRecent
+        + TransactionServiceCategorize
+        + TransactionServiceRecategorize
+        + TransactionServiceRecategorization
+        + TransactionServiceAttachment,
 > TransactionServiceMethods for T
 {
 }
@@ -49,6 +256,8 @@ pub struct TransactionService<Policy> {
     connection_pool: Arc<PgPool>,
     transaction_repository: TransactionRepository,
     registered_user: RegisteredUser,
+    features: FeatureFlags,
+    events: EventHub,
     policy: PhantomData<Policy>,
 }
 
@@ -57,14 +266,574 @@ impl<Policy> TransactionService<Policy> {
         connection_pool: Arc<PgPool>,
         transaction_repository: TransactionRepository,
         registered_user: RegisteredUser,
+        features: FeatureFlags,
+        events: EventHub,
     ) -> Self {
         Self {
             connection_pool,
             transaction_repository,
             registered_user,
+            features,
+            events,
             policy: PhantomData,
         }
     }
+
+    /// Gives a new transaction without a category the category of the first
+    /// rule of the account's user that matches it.
+    async fn categorize(
+        &self,
+        mut create_model: TransactionCreate,
+    ) -> Result<TransactionCreate, ServiceError> {
+        if create_model.category.is_some() {
+            return Ok(create_model);
+        }
+        let rules = CategorizationRuleRepository
+            .get_list_for_account(
+                deadline::begin(&self.connection_pool).await?,
+                create_model.account_id,
+            )
+            .await?;
+        if let Some(rule) = Categorizer::new(rules).categorize(
+            create_model.account_id,
+            create_model.description.as_deref(),
+            create_model.quantity,
+        ) {
+            create_model.category = Some(rule.category.clone());
+            create_model.applied_rule_id = Some(rule.id);
+        }
+        Ok(create_model)
+    }
+
+    /// Sets the category of a transaction on the accounts of `user_id`, or
+    /// of any transaction without one, and creates `rule` in the same
+    /// database transaction.
+    async fn set_category(
+        &self,
+        id: TransactionId,
+        category: String,
+        rule: Option<CategorizationRuleCreate>,
+        user_id: Option<UserId>,
+    ) -> Result<(Transaction, Option<CategorizationRule>), ServiceError> {
+        let mut trans = deadline::begin(&self.connection_pool).await?;
+        let mut transaction = match user_id {
+            Some(user_id) => {
+                self.transaction_repository
+                    .get_with_user_id(
+                        trans.begin().await?,
+                        id,
+                        user_id,
+                        self.registered_user.account_scope(),
+                    )
+                    .await?
+            }
+            None => {
+                self.transaction_repository
+                    .get(trans.begin().await?, id)
+                    .await?
+            }
+        };
+        transaction.update(TransactionUpdate {
+            category: Some(category),
+            ..Default::default()
+        });
+        let transaction = match user_id {
+            Some(user_id) => {
+                self.transaction_repository
+                    .update_with_user_id(
+                        trans.begin().await?,
+                        transaction,
+                        user_id,
+                        self.registered_user.account_scope(),
+                    )
+                    .await?
+            }
+            None => {
+                self.transaction_repository
+                    .update(trans.begin().await?, transaction)
+                    .await?
+            }
+        };
+        let categorization_rule = match rule {
+            Some(rule) => {
+                let existing = CategorizationRuleRepository
+                    .get_list(
+                        trans.begin().await?,
+                        0,
+                        None,
+                        CategorizationRuleFilter {
+                            user_id: rule.user_id.into(),
+                        },
+                    )
+                    .await?;
+                // Dropping the database transaction undoes the category.
+                if existing.len() >= MAX_RULES {
+                    return Err(ServiceError::CategorizationRuleLimit);
+                }
+                Some(
+                    CategorizationRuleRepository
+                        .create(trans.begin().await?, rule)
+                        .await?,
+                )
+            }
+            None => None,
+        };
+        trans.commit().await?;
+        Ok((transaction, categorization_rule))
+    }
+
+    async fn count_caller_transactions(&self, filter: GetListRequest) -> Result<i64, ServiceError> {
+        let matched = self
+            .transaction_repository
+            .count_with_user_id(
+                deadline::begin(&self.connection_pool).await?,
+                self.registered_user.id(),
+                self.registered_user.account_scope(),
+                filter.into(),
+            )
+            .await?;
+        Ok(matched)
+    }
+
+    async fn dry_run_caller_recategorization(
+        &self,
+        filter: GetListRequest,
+        category: String,
+    ) -> Result<i64, ServiceError> {
+        dry_run_recategorization(
+            &self.connection_pool,
+            self.registered_user.id(),
+            self.registered_user.account_scope(),
+            filter,
+            category,
+        )
+        .await
+    }
+
+    async fn start_caller_recategorization(
+        &self,
+        create_model: RecategorizationCreate,
+        filter: GetListRequest,
+    ) -> Result<RecategorizationJob, ServiceError> {
+        if create_model.user_id != self.registered_user.id() {
+            return Err(ServiceError::Unauthorized);
+        }
+        let recategorization = RecategorizationRepository
+            .create(deadline::begin(&self.connection_pool).await?, create_model)
+            .await?;
+        Ok(RecategorizationJob {
+            recategorization,
+            filter,
+            account_ids: self.registered_user.account_scope(),
+        })
+    }
+
+    async fn caller_recategorization(
+        &self,
+        id: RecategorizationId,
+    ) -> Result<Recategorization, ServiceError> {
+        let recategorization = RecategorizationRepository
+            .get(
+                deadline::begin(&self.connection_pool).await?,
+                self.registered_user.id(),
+                id,
+            )
+            .await?;
+        Ok(recategorization)
+    }
+
+    /// Gets a transaction on the accounts of `user_id`, or on any account
+    /// without one.
+    async fn find_transaction(
+        &self,
+        session: PgTransaction<'_>,
+        id: TransactionId,
+        user_id: Option<UserId>,
+    ) -> Result<Transaction, ServiceError> {
+        let transaction = match user_id {
+            Some(user_id) => {
+                self.transaction_repository
+                    .get_with_user_id(session, id, user_id, self.registered_user.account_scope())
+                    .await?
+            }
+            None => self.transaction_repository.get(session, id).await?,
+        };
+        Ok(transaction)
+    }
+
+    /// Attaches a file to a transaction on the accounts of `user_id`, or on
+    /// any account without one.
+    async fn insert_attachment(
+        &self,
+        create_model: AttachmentCreate,
+        user_id: Option<UserId>,
+    ) -> Result<Attachment, ServiceError> {
+        let mut trans = deadline::begin(&self.connection_pool).await?;
+        self.find_transaction(trans.begin().await?, create_model.transaction_id, user_id)
+            .await?;
+        let attachment = AttachmentRepository
+            .create(trans.begin().await?, create_model)
+            .await?;
+        trans.commit().await?;
+        Ok(attachment)
+    }
+
+    /// Deletes an attachment of a transaction on the accounts of `user_id`,
+    /// or on any account without one.
+    async fn remove_attachment(
+        &self,
+        id: AttachmentId,
+        user_id: Option<UserId>,
+    ) -> Result<Attachment, ServiceError> {
+        let mut trans = deadline::begin(&self.connection_pool).await?;
+        let attachment = AttachmentRepository.get(trans.begin().await?, id).await?;
+        self.find_transaction(trans.begin().await?, attachment.transaction_id, user_id)
+            .await?;
+        let attachment = AttachmentRepository
+            .delete(trans.begin().await?, id)
+            .await?;
+        trans.commit().await?;
+        Ok(attachment)
+    }
+
+    /// Gets an entry with its legs on the accounts of `user_id`, or with
+    /// all of them without one.
+    async fn find_journal_entry(
+        &self,
+        id: JournalEntryId,
+        user_id: Option<UserId>,
+        account_ids: Option<Vec<AccountId>>,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        let legs = self
+            .transaction_repository
+            .get_by_journal_entry(
+                deadline::begin(&self.connection_pool).await?,
+                id,
+                user_id,
+                account_ids,
+            )
+            .await?;
+        if user_id.is_some() && legs.is_empty() {
+            return Err(ServiceError::NotFound);
+        }
+        let journal_entry = JournalEntryRepository
+            .get(deadline::begin(&self.connection_pool).await?, id)
+            .await?;
+        Ok(JournalEntryWithLegs {
+            journal_entry,
+            legs,
+        })
+    }
+
+    /// Creates an entry with its legs on the accounts of `user_id`, or on
+    /// any accounts without one.
+    async fn insert_journal_entry(
+        &self,
+        create_model: JournalEntryCreate,
+        user_id: Option<UserId>,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        let mut trans = deadline::begin(&self.connection_pool).await?;
+        let journal_entry = JournalEntryRepository
+            .create(trans.begin().await?, create_model.clone())
+            .await?;
+        let mut legs = Vec::with_capacity(create_model.legs.len());
+        let mut alert_events = vec![];
+        for leg in create_model.legs {
+            let leg = self
+                .categorize(TransactionCreate {
+                    account_id: leg.account_id,
+                    asset_id: leg.asset_id,
+                    description: journal_entry.description.clone(),
+                    posted_at: journal_entry.posted_at,
+                    quantity: leg.quantity,
+                    notes: leg.notes,
+                    external_id: None,
+                    category: None,
+                    applied_rule_id: None,
+                    journal_entry_id: Some(journal_entry.id),
+                })
+                .await?;
+            let transaction = match user_id {
+                Some(user_id) => {
+                    self.transaction_repository
+                        .create_with_user_id(
+                            trans.begin().await?,
+                            leg,
+                            user_id,
+                            self.registered_user.account_scope(),
+                        )
+                        .await?
+                }
+                None => {
+                    self.transaction_repository
+                        .create(trans.begin().await?, leg)
+                        .await?
+                }
+            };
+            alert_events.extend(
+                adjust_balances(&mut trans, &self.features, None, Some(&transaction)).await?,
+            );
+            legs.push(transaction);
+        }
+        trans.commit().await?;
+        dispatch_alerts(&self.events, &alert_events);
+        Ok(JournalEntryWithLegs {
+            journal_entry,
+            legs,
+        })
+    }
+
+    /// Creates transactions on the accounts of `user_id`, or on any
+    /// accounts without one, in a single database transaction.
+    async fn insert_all(
+        &self,
+        create_models: Vec<TransactionCreate>,
+        user_id: Option<UserId>,
+        dry_run: bool,
+    ) -> Result<Vec<Transaction>, ServiceError> {
+        let mut trans = deadline::begin(&self.connection_pool).await?;
+        let mut transactions = Vec::with_capacity(create_models.len());
+        let mut alert_events = vec![];
+        for create_model in create_models {
+            let create_model = self.categorize(create_model).await?;
+            let transaction = match user_id {
+                Some(user_id) => {
+                    self.transaction_repository
+                        .create_with_user_id(
+                            trans.begin().await?,
+                            create_model,
+                            user_id,
+                            self.registered_user.account_scope(),
+                        )
+                        .await?
+                }
+                None => {
+                    self.transaction_repository
+                        .create(trans.begin().await?, create_model)
+                        .await?
+                }
+            };
+            alert_events.extend(
+                adjust_balances(&mut trans, &self.features, None, Some(&transaction)).await?,
+            );
+            transactions.push(transaction);
+        }
+        commit_unless_dry_run(trans, dry_run).await?;
+        if !dry_run {
+            dispatch_alerts(&self.events, &alert_events);
+        }
+        Ok(transactions)
+    }
+
+    /// Deletes an entry and its legs when all of them are on the accounts
+    /// of `user_id`, or regardless without one.
+    async fn remove_journal_entry(
+        &self,
+        id: JournalEntryId,
+        user_id: Option<UserId>,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        let mut trans = deadline::begin(&self.connection_pool).await?;
+        let legs = self
+            .transaction_repository
+            .get_by_journal_entry(trans.begin().await?, id, None, None)
+            .await?;
+        if let Some(user_id) = user_id {
+            let own_legs = self
+                .transaction_repository
+                .get_by_journal_entry(
+                    trans.begin().await?,
+                    id,
+                    Some(user_id),
+                    self.registered_user.account_scope(),
+                )
+                .await?;
+            if own_legs.is_empty() {
+                return Err(ServiceError::NotFound);
+            }
+            // Deleting part of an entry would unbalance it.
+            if own_legs.len() < legs.len() {
+                return Err(ServiceError::Unauthorized);
+            }
+        }
+        // The legs go with the entry.
+        let journal_entry = JournalEntryRepository
+            .delete(trans.begin().await?, id)
+            .await?;
+        let mut alert_events = vec![];
+        for leg in &legs {
+            alert_events
+                .extend(adjust_balances(&mut trans, &self.features, Some(leg), None).await?);
+        }
+        trans.commit().await?;
+        dispatch_alerts(&self.events, &alert_events);
+        Ok(JournalEntryWithLegs {
+            journal_entry,
+            legs,
+        })
+    }
+}
+
+/// Applies a transaction going from `before` to `after` to the cached
+/// balances, within the database transaction that changed it. The balances
+/// are adjusted in a fixed order so concurrent changes lock them in the same
+/// order. The alert rules of each balance are checked as it changes, while
+/// alerts are enabled, and the alerts that fired are returned to be sent
+/// once the database transaction commits.
+async fn adjust_balances(
+    trans: &mut PgTransaction<'_>,
+    features: &FeatureFlags,
+    before: Option<&Transaction>,
+    after: Option<&Transaction>,
+) -> Result<Vec<FiredAlert>, ServiceError> {
+    let Some(transaction_id) = after.or(before).map(|x| x.id) else {
+        return Ok(vec![]);
+    };
+    let mut deltas = vec![];
+    if let Some(before) = before {
+        deltas.push((before.account_id, before.asset_id, -before.quantity));
+    }
+    if let Some(after) = after {
+        match deltas.iter_mut().find(|(account_id, asset_id, _)| {
+            (*account_id, *asset_id) == (after.account_id, after.asset_id)
+        }) {
+            Some((_, _, delta)) => *delta += after.quantity,
+            None => deltas.push((after.account_id, after.asset_id, after.quantity)),
+        }
+    }
+    deltas.sort_by_key(|(account_id, asset_id, _)| (account_id.0, asset_id.0));
+
+    let mut fired = vec![];
+    for (account_id, asset_id, delta) in deltas {
+        if delta == Decimal::ZERO {
+            continue;
+        }
+        let adjusted = AccountBalanceRepository
+            .adjust(
+                trans.begin().await?,
+                account_id,
+                asset_id,
+                delta,
+                transaction_id,
+            )
+            .await?;
+        if !features.is_enabled(Feature::Alerts) {
+            continue;
+        }
+        let fired_alerts = AlertRuleRepository
+            .evaluate(
+                trans.begin().await?,
+                account_id,
+                asset_id,
+                adjusted.balance - delta,
+                adjusted.balance,
+            )
+            .await?;
+        fired.extend(fired_alerts);
+    }
+    Ok(fired)
+}
+
+/// Sends out alerts on the channels of their rules. Alerts on the event
+/// stream are published to the streams their users have open; the other
+/// channels have no sender yet, so their alerts are only logged, while their
+/// events record that they fired.
+fn dispatch_alerts(events: &EventHub, fired_alerts: &[FiredAlert]) {
+    for FiredAlert {
+        alert_event,
+        user_id,
+    } in fired_alerts
+    {
+        info!(
+            alert_rule_id = %alert_event.alert_rule_id,
+            channel = ?alert_event.channel,
+            balance = %alert_event.balance,
+            "Alert rule fired"
+        );
+        if alert_event.channel != AlertChannel::Sse {
+            continue;
+        }
+        match Event::new(ALERT_FIRED, &AlertEventResponse::from(alert_event.clone())) {
+            Ok(event) => events.publish(*user_id, event),
+            Err(e) => error!(
+                alert_rule_id = %alert_event.alert_rule_id,
+                "Failed to serialize alert event: {e}"
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl<P: Send + Sync> TransactionServiceConvert for TransactionService<P> {
+    #[instrument(name = "TransactionService::convert", skip_all)]
+    async fn convert(
+        &self,
+        transactions: &[Transaction],
+        quote_symbol: String,
+        rounding: RoundingPolicy,
+    ) -> Result<Vec<TransactionConversion>, ServiceError> {
+        if transactions.is_empty() {
+            return Ok(vec![]);
+        }
+        let conversions = self
+            .transaction_repository
+            .get_conversions(
+                deadline::begin(&self.connection_pool).await?,
+                transactions.iter().map(|x| x.id).collect(),
+                quote_symbol,
+                rounding,
+            )
+            .await?;
+        Ok(conversions)
+    }
+}
+
+#[async_trait]
+impl<P: Send + Sync> TransactionServiceHistory for TransactionService<P>
+where
+    Self: ServiceGet<TransactionId, Transaction>
+        + ServiceUpdate<TransactionId, TransactionUpdate, Transaction>,
+{
+    #[instrument(name = "TransactionService::get_history", skip_all, fields(id = ?id))]
+    async fn get_history(
+        &self,
+        id: TransactionId,
+    ) -> Result<Vec<TransactionHistory>, ServiceError> {
+        // Only the history of transactions the caller can read.
+        self.get(id).await?;
+        let transaction_history = TransactionHistoryRepository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                0,
+                None,
+                TransactionHistoryFilter {
+                    transaction_id: Some(id),
+                },
+            )
+            .await?;
+        Ok(transaction_history)
+    }
+
+    #[instrument(
+        name = "TransactionService::revert",
+        skip_all,
+        fields(id = ?id, history_id = ?history_id)
+    )]
+    async fn revert(
+        &self,
+        id: TransactionId,
+        history_id: TransactionHistoryId,
+    ) -> Result<Transaction, ServiceError> {
+        self.get(id).await?;
+        let transaction_history = TransactionHistoryRepository
+            .get(deadline::begin(&self.connection_pool).await?, history_id)
+            .await?;
+        // The history of other transactions is indistinguishable from
+        // missing history.
+        if transaction_history.transaction_id != id {
+            return Err(ServiceError::NotFound);
+        }
+        self.update(id, transaction_history.before().into()).await
+    }
 }
 
 #[async_trait]
@@ -74,6 +843,7 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<TransactionResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::get", skip_all, fields(id = ?_id))]
     async fn get(&self, _id: TransactionId) -> Result<Transaction, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -86,13 +856,15 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::get", skip_all, fields(id = ?id))]
     async fn get(&self, id: TransactionId) -> Result<Transaction, ServiceError> {
         let transaction = self
             .transaction_repository
             .get_with_user_id(
-                self.connection_pool.begin().await?,
+                deadline::begin(&self.connection_pool).await?,
                 id,
                 self.registered_user.id(),
+                self.registered_user.account_scope(),
             )
             .await?;
         Ok(transaction)
@@ -106,10 +878,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<TransactionResource, ActionSet<ReadAll, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::get", skip_all, fields(id = ?id))]
     async fn get(&self, id: TransactionId) -> Result<Transaction, ServiceError> {
         let transaction = self
             .transaction_repository
-            .get(self.connection_pool.begin().await?, id)
+            .get(deadline::begin(&self.connection_pool).await?, id)
             .await?;
         Ok(transaction)
     }
@@ -122,6 +895,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<TransactionResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(
+        name = "TransactionService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
     async fn get_list(
         &self,
         _offset: i64,
@@ -139,6 +917,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(
+        name = "TransactionService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
     async fn get_list(
         &self,
         offset: i64,
@@ -148,10 +931,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         let transactions = self
             .transaction_repository
             .get_list_with_user_id(
-                self.connection_pool.begin().await?,
+                deadline::begin(&self.connection_pool).await?,
                 offset,
                 limit,
                 self.registered_user.id(),
+                self.registered_user.account_scope(),
                 filter,
             )
             .await?;
@@ -166,6 +950,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
         Policy<TransactionResource, ActionSet<ReadAll, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(
+        name = "TransactionService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
     async fn get_list(
         &self,
         offset: i64,
@@ -174,7 +963,12 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ) -> Result<Vec<Transaction>, ServiceError> {
         let transactions = self
             .transaction_repository
-            .get_list(self.connection_pool.begin().await?, offset, limit, filter)
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
             .await?;
         Ok(transactions)
     }
@@ -187,6 +981,7 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<TransactionResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::create", skip_all)]
     async fn create(&self, _create_model: TransactionCreate) -> Result<Transaction, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -199,15 +994,23 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::create", skip_all)]
     async fn create(&self, create_model: TransactionCreate) -> Result<Transaction, ServiceError> {
+        let create_model = self.categorize(create_model).await?;
+        let mut trans = deadline::begin(&self.connection_pool).await?;
         let transaction = self
             .transaction_repository
             .create_with_user_id(
-                self.connection_pool.begin().await?,
+                trans.begin().await?,
                 create_model,
                 self.registered_user.id(),
+                self.registered_user.account_scope(),
             )
             .await?;
+        let alert_events =
+            adjust_balances(&mut trans, &self.features, None, Some(&transaction)).await?;
+        trans.commit().await?;
+        dispatch_alerts(&self.events, &alert_events);
         Ok(transaction)
     }
 }
@@ -219,11 +1022,18 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<TransactionResource, ActionSet<Read, CreateAll, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::create", skip_all)]
     async fn create(&self, create_model: TransactionCreate) -> Result<Transaction, ServiceError> {
+        let create_model = self.categorize(create_model).await?;
+        let mut trans = deadline::begin(&self.connection_pool).await?;
         let transaction = self
             .transaction_repository
-            .create(self.connection_pool.begin().await?, create_model)
+            .create(trans.begin().await?, create_model)
             .await?;
+        let alert_events =
+            adjust_balances(&mut trans, &self.features, None, Some(&transaction)).await?;
+        trans.commit().await?;
+        dispatch_alerts(&self.events, &alert_events);
         Ok(transaction)
     }
 }
@@ -235,6 +1045,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<TransactionResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::update", skip_all, fields(id = ?_id))]
     async fn update(
         &self,
         _id: TransactionId,
@@ -251,25 +1062,48 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::update", skip_all, fields(id = ?id))]
     async fn update(
         &self,
         id: TransactionId,
         update_model: TransactionUpdate,
     ) -> Result<Transaction, ServiceError> {
-        let mut trans = self.connection_pool.begin().await?;
+        let mut trans = deadline::begin(&self.connection_pool).await?;
 
         let mut transaction = self
             .transaction_repository
-            .get_with_user_id(trans.begin().await?, id, self.registered_user.id())
+            .get_with_user_id(
+                trans.begin().await?,
+                id,
+                self.registered_user.id(),
+                self.registered_user.account_scope(),
+            )
             .await?;
 
+        if transaction.journal_entry_id.is_some() && update_model.changes_amount(&transaction) {
+            return Err(ServiceError::JournalEntryLeg);
+        }
+        let before = transaction.clone();
         transaction.update(update_model);
 
         let transaction = self
             .transaction_repository
-            .update_with_user_id(trans.begin().await?, transaction, self.registered_user.id())
+            .update_with_user_id(
+                trans.begin().await?,
+                transaction,
+                self.registered_user.id(),
+                self.registered_user.account_scope(),
+            )
             .await?;
+        let alert_events = adjust_balances(
+            &mut trans,
+            &self.features,
+            Some(&before),
+            Some(&transaction),
+        )
+        .await?;
         trans.commit().await?;
+        dispatch_alerts(&self.events, &alert_events);
         Ok(transaction)
     }
 }
@@ -281,25 +1115,38 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
         Policy<TransactionResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::update", skip_all, fields(id = ?id))]
     async fn update(
         &self,
         id: TransactionId,
         update_model: TransactionUpdate,
     ) -> Result<Transaction, ServiceError> {
-        let mut trans = self.connection_pool.begin().await?;
+        let mut trans = deadline::begin(&self.connection_pool).await?;
 
         let mut transaction = self
             .transaction_repository
             .get(trans.begin().await?, id)
             .await?;
 
+        if transaction.journal_entry_id.is_some() && update_model.changes_amount(&transaction) {
+            return Err(ServiceError::JournalEntryLeg);
+        }
+        let before = transaction.clone();
         transaction.update(update_model);
 
         let transaction = self
             .transaction_repository
             .update(trans.begin().await?, transaction)
             .await?;
+        let alert_events = adjust_balances(
+            &mut trans,
+            &self.features,
+            Some(&before),
+            Some(&transaction),
+        )
+        .await?;
         trans.commit().await?;
+        dispatch_alerts(&self.events, &alert_events);
         Ok(transaction)
     }
 }
@@ -311,6 +1158,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
         Policy<TransactionResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::delete", skip_all, fields(id = ?_id))]
     async fn delete(&self, _id: TransactionId) -> Result<Transaction, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -323,15 +1171,26 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
         Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::delete", skip_all, fields(id = ?id))]
     async fn delete(&self, id: TransactionId) -> Result<Transaction, ServiceError> {
+        let mut trans = deadline::begin(&self.connection_pool).await?;
         let transaction = self
             .transaction_repository
             .delete_with_user_id(
-                self.connection_pool.begin().await?,
+                trans.begin().await?,
                 id,
                 self.registered_user.id(),
+                self.registered_user.account_scope(),
             )
             .await?;
+        // Dropping the database transaction undoes the delete.
+        if transaction.journal_entry_id.is_some() {
+            return Err(ServiceError::JournalEntryLeg);
+        }
+        let alert_events =
+            adjust_balances(&mut trans, &self.features, Some(&transaction), None).await?;
+        trans.commit().await?;
+        dispatch_alerts(&self.events, &alert_events);
         Ok(transaction)
     }
 }
@@ -343,11 +1202,651 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
         Policy<TransactionResource, ActionSet<Read, Create, Update, DeleteAll>, Role>,
     >
 {
+    #[instrument(name = "TransactionService::delete", skip_all, fields(id = ?id))]
     async fn delete(&self, id: TransactionId) -> Result<Transaction, ServiceError> {
+        let mut trans = deadline::begin(&self.connection_pool).await?;
         let transaction = self
             .transaction_repository
-            .delete(self.connection_pool.begin().await?, id)
+            .delete(trans.begin().await?, id)
             .await?;
+        // Dropping the database transaction undoes the delete.
+        if transaction.journal_entry_id.is_some() {
+            return Err(ServiceError::JournalEntryLeg);
+        }
+        let alert_events =
+            adjust_balances(&mut trans, &self.features, Some(&transaction), None).await?;
+        trans.commit().await?;
+        dispatch_alerts(&self.events, &alert_events);
         Ok(transaction)
     }
 }
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceJournal
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_journal_entry", skip_all, fields(id = ?_id))]
+    async fn get_journal_entry(
+        &self,
+        _id: JournalEntryId,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "TransactionService::trial_balance", skip_all, fields(as_of = ?_as_of))]
+    async fn trial_balance(
+        &self,
+        _from: Option<DateTime<Utc>>,
+        _as_of: DateTime<Utc>,
+    ) -> Result<Vec<TrialBalanceLine>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceJournal
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_journal_entry", skip_all, fields(id = ?id))]
+    async fn get_journal_entry(
+        &self,
+        id: JournalEntryId,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        self.find_journal_entry(
+            id,
+            Some(self.registered_user.id()),
+            self.registered_user.account_scope(),
+        )
+        .await
+    }
+
+    #[instrument(name = "TransactionService::trial_balance", skip_all, fields(from = ?from, as_of = ?as_of))]
+    async fn trial_balance(
+        &self,
+        from: Option<DateTime<Utc>>,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<TrialBalanceLine>, ServiceError> {
+        let lines = self
+            .transaction_repository
+            .trial_balance(
+                deadline::begin(&self.connection_pool).await?,
+                Some(self.registered_user.id()),
+                self.registered_user.account_scope(),
+                from,
+                as_of,
+            )
+            .await?;
+        Ok(lines)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceJournal
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<ReadAll, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_journal_entry", skip_all, fields(id = ?id))]
+    async fn get_journal_entry(
+        &self,
+        id: JournalEntryId,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        self.find_journal_entry(id, None, None).await
+    }
+
+    #[instrument(name = "TransactionService::trial_balance", skip_all, fields(from = ?from, as_of = ?as_of))]
+    async fn trial_balance(
+        &self,
+        from: Option<DateTime<Utc>>,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<TrialBalanceLine>, ServiceError> {
+        let lines = self
+            .transaction_repository
+            .trial_balance(
+                deadline::begin(&self.connection_pool).await?,
+                None,
+                None,
+                from,
+                as_of,
+            )
+            .await?;
+        Ok(lines)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceJournalCreate
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::create_journal_entry", skip_all)]
+    async fn create_journal_entry(
+        &self,
+        _create_model: JournalEntryCreate,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceJournalCreate
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::create_journal_entry", skip_all)]
+    async fn create_journal_entry(
+        &self,
+        create_model: JournalEntryCreate,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        self.insert_journal_entry(create_model, Some(self.registered_user.id()))
+            .await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceJournalCreate
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, CreateAll, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::create_journal_entry", skip_all)]
+    async fn create_journal_entry(
+        &self,
+        create_model: JournalEntryCreate,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        self.insert_journal_entry(create_model, None).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceCreateMany
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::create_many", skip_all)]
+    async fn create_many(
+        &self,
+        _create_models: Vec<TransactionCreate>,
+        _dry_run: bool,
+    ) -> Result<Vec<Transaction>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceCreateMany
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::create_many", skip_all, fields(dry_run = dry_run))]
+    async fn create_many(
+        &self,
+        create_models: Vec<TransactionCreate>,
+        dry_run: bool,
+    ) -> Result<Vec<Transaction>, ServiceError> {
+        self.insert_all(create_models, Some(self.registered_user.id()), dry_run)
+            .await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceCreateMany
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, CreateAll, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::create_many", skip_all, fields(dry_run = dry_run))]
+    async fn create_many(
+        &self,
+        create_models: Vec<TransactionCreate>,
+        dry_run: bool,
+    ) -> Result<Vec<Transaction>, ServiceError> {
+        self.insert_all(create_models, None, dry_run).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    TransactionServiceJournalDelete
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::delete_journal_entry", skip_all, fields(id = ?_id))]
+    async fn delete_journal_entry(
+        &self,
+        _id: JournalEntryId,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    TransactionServiceJournalDelete
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::delete_journal_entry", skip_all, fields(id = ?id))]
+    async fn delete_journal_entry(
+        &self,
+        id: JournalEntryId,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        self.remove_journal_entry(id, Some(self.registered_user.id()))
+            .await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    TransactionServiceJournalDelete
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, DeleteAll>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::delete_journal_entry", skip_all, fields(id = ?id))]
+    async fn delete_journal_entry(
+        &self,
+        id: JournalEntryId,
+    ) -> Result<JournalEntryWithLegs, ServiceError> {
+        self.remove_journal_entry(id, None).await
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceUncategorized
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_uncategorized", skip_all, fields(limit = _limit))]
+    async fn get_uncategorized(
+        &self,
+        _limit: i64,
+    ) -> Result<(Vec<Transaction>, i64), ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceUncategorized
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_uncategorized", skip_all, fields(limit = limit))]
+    async fn get_uncategorized(&self, limit: i64) -> Result<(Vec<Transaction>, i64), ServiceError> {
+        let uncategorized = self
+            .transaction_repository
+            .get_recent_uncategorized_with_user_id(
+                deadline::begin(&self.connection_pool).await?,
+                self.registered_user.id(),
+                self.registered_user.account_scope(),
+                limit,
+            )
+            .await?;
+        Ok(uncategorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceUncategorized
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<ReadAll, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_uncategorized", skip_all, fields(limit = limit))]
+    async fn get_uncategorized(&self, limit: i64) -> Result<(Vec<Transaction>, i64), ServiceError> {
+        let uncategorized = self
+            .transaction_repository
+            .get_recent_uncategorized_with_user_id(
+                deadline::begin(&self.connection_pool).await?,
+                self.registered_user.id(),
+                self.registered_user.account_scope(),
+                limit,
+            )
+            .await?;
+        Ok(uncategorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceRecent
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_recent", skip_all, fields(limit = _limit))]
+    async fn get_recent(&self, _limit: i64) -> Result<Vec<Transaction>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceRecent
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_recent", skip_all, fields(limit = limit))]
+    async fn get_recent(&self, limit: i64) -> Result<Vec<Transaction>, ServiceError> {
+        let transactions = self
+            .transaction_repository
+            .get_recent_with_user_id(
+                deadline::begin(&self.connection_pool).await?,
+                self.registered_user.id(),
+                self.registered_user.account_scope(),
+                limit,
+            )
+            .await?;
+        Ok(transactions)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceRecent
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<ReadAll, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_recent", skip_all, fields(limit = limit))]
+    async fn get_recent(&self, limit: i64) -> Result<Vec<Transaction>, ServiceError> {
+        let transactions = self
+            .transaction_repository
+            .get_recent_with_user_id(
+                deadline::begin(&self.connection_pool).await?,
+                self.registered_user.id(),
+                self.registered_user.account_scope(),
+                limit,
+            )
+            .await?;
+        Ok(transactions)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceCategorize
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::categorize", skip_all, fields(id = ?_id))]
+    async fn categorize(
+        &self,
+        _id: TransactionId,
+        _category: String,
+        _rule: Option<CategorizationRuleCreate>,
+    ) -> Result<(Transaction, Option<CategorizationRule>), ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceCategorize
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::categorize", skip_all, fields(id = ?id))]
+    async fn categorize(
+        &self,
+        id: TransactionId,
+        category: String,
+        rule: Option<CategorizationRuleCreate>,
+    ) -> Result<(Transaction, Option<CategorizationRule>), ServiceError> {
+        self.set_category(id, category, rule, Some(self.registered_user.id()))
+            .await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceCategorize
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::categorize", skip_all, fields(id = ?id))]
+    async fn categorize(
+        &self,
+        id: TransactionId,
+        category: String,
+        rule: Option<CategorizationRuleCreate>,
+    ) -> Result<(Transaction, Option<CategorizationRule>), ServiceError> {
+        self.set_category(id, category, rule, None).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceRecategorize
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::count_recategorizable", skip_all)]
+    async fn count_recategorizable(&self, _filter: GetListRequest) -> Result<i64, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "TransactionService::dry_run_recategorize", skip_all)]
+    async fn dry_run_recategorize(
+        &self,
+        _filter: GetListRequest,
+        _category: String,
+    ) -> Result<i64, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "TransactionService::recategorize", skip_all)]
+    async fn recategorize(
+        &self,
+        _create_model: RecategorizationCreate,
+        _filter: GetListRequest,
+    ) -> Result<RecategorizationJob, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceRecategorize
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::count_recategorizable", skip_all)]
+    async fn count_recategorizable(&self, filter: GetListRequest) -> Result<i64, ServiceError> {
+        self.count_caller_transactions(filter).await
+    }
+
+    #[instrument(name = "TransactionService::dry_run_recategorize", skip_all)]
+    async fn dry_run_recategorize(
+        &self,
+        filter: GetListRequest,
+        category: String,
+    ) -> Result<i64, ServiceError> {
+        self.dry_run_caller_recategorization(filter, category).await
+    }
+
+    #[instrument(name = "TransactionService::recategorize", skip_all)]
+    async fn recategorize(
+        &self,
+        create_model: RecategorizationCreate,
+        filter: GetListRequest,
+    ) -> Result<RecategorizationJob, ServiceError> {
+        self.start_caller_recategorization(create_model, filter)
+            .await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceRecategorize
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::count_recategorizable", skip_all)]
+    async fn count_recategorizable(&self, filter: GetListRequest) -> Result<i64, ServiceError> {
+        self.count_caller_transactions(filter).await
+    }
+
+    #[instrument(name = "TransactionService::dry_run_recategorize", skip_all)]
+    async fn dry_run_recategorize(
+        &self,
+        filter: GetListRequest,
+        category: String,
+    ) -> Result<i64, ServiceError> {
+        self.dry_run_caller_recategorization(filter, category).await
+    }
+
+    #[instrument(name = "TransactionService::recategorize", skip_all)]
+    async fn recategorize(
+        &self,
+        create_model: RecategorizationCreate,
+        filter: GetListRequest,
+    ) -> Result<RecategorizationJob, ServiceError> {
+        self.start_caller_recategorization(create_model, filter)
+            .await
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceRecategorization
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_recategorization", skip_all, fields(id = ?_id))]
+    async fn get_recategorization(
+        &self,
+        _id: RecategorizationId,
+    ) -> Result<Recategorization, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceRecategorization
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_recategorization", skip_all, fields(id = ?id))]
+    async fn get_recategorization(
+        &self,
+        id: RecategorizationId,
+    ) -> Result<Recategorization, ServiceError> {
+        self.caller_recategorization(id).await
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceRecategorization
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<ReadAll, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::get_recategorization", skip_all, fields(id = ?id))]
+    async fn get_recategorization(
+        &self,
+        id: RecategorizationId,
+    ) -> Result<Recategorization, ServiceError> {
+        self.caller_recategorization(id).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceAttachment
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::create_attachment", skip_all)]
+    async fn create_attachment(
+        &self,
+        _create_model: AttachmentCreate,
+    ) -> Result<Attachment, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+
+    #[instrument(name = "TransactionService::delete_attachment", skip_all, fields(id = ?_id))]
+    async fn delete_attachment(&self, _id: AttachmentId) -> Result<Attachment, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceAttachment
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::create_attachment", skip_all)]
+    async fn create_attachment(
+        &self,
+        create_model: AttachmentCreate,
+    ) -> Result<Attachment, ServiceError> {
+        self.insert_attachment(create_model, Some(self.registered_user.id()))
+            .await
+    }
+
+    #[instrument(name = "TransactionService::delete_attachment", skip_all, fields(id = ?id))]
+    async fn delete_attachment(&self, id: AttachmentId) -> Result<Attachment, ServiceError> {
+        self.remove_attachment(id, Some(self.registered_user.id()))
+            .await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    TransactionServiceAttachment
+    for TransactionService<
+        Policy<TransactionResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>,
+    >
+{
+    #[instrument(name = "TransactionService::create_attachment", skip_all)]
+    async fn create_attachment(
+        &self,
+        create_model: AttachmentCreate,
+    ) -> Result<Attachment, ServiceError> {
+        self.insert_attachment(create_model, None).await
+    }
+
+    #[instrument(name = "TransactionService::delete_attachment", skip_all, fields(id = ?id))]
+    async fn delete_attachment(&self, id: AttachmentId) -> Result<Attachment, ServiceError> {
+        self.remove_attachment(id, None).await
+    }
+}
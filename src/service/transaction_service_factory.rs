@@ -3,6 +3,7 @@ use std::sync::Arc;
 use sqlx::PgPool;
 
 use crate::{
+    api::event_hub::EventHub,
     authentication::registered_user::RegisteredUser,
     authorization::{
         PermissionSet,
@@ -14,12 +15,13 @@ use crate::{
         resources::Transaction as TransactionResource,
         roles::Any,
     },
+    config::FeatureFlags,
     resource::transaction_repository::TransactionRepository,
     service::transaction_service::{TransactionService, TransactionServiceMethods},
 };
 
 macro_rules! build_service {
-    ($permission_set:expr, $pool:expr, $user:expr;
+    ($permission_set:expr, $pool:expr, $user:expr, $features:expr, $events:expr;
      $([ $read:ident, $create:ident, $update:ident, $delete:ident ]),* $(,)*) => {
         match $permission_set {
             $(
@@ -41,10 +43,10 @@ macro_rules! build_service {
                             $delete
                         >,
                         Any
-                    >>::new($pool, TransactionRepository {}, $user))
+                    >>::new($pool, TransactionRepository {}, $user, $features, $events))
                 },
             )*
-            _ => {Box::new(TransactionService::<Policy<TransactionResource, ActionSet, Any>>::new($pool, TransactionRepository {}, $user))}
+            _ => {Box::new(TransactionService::<Policy<TransactionResource, ActionSet, Any>>::new($pool, TransactionRepository {}, $user, $features, $events))}
         }
     };
 }
@@ -57,8 +59,10 @@ impl TransactionServiceFactory {
         user: RegisteredUser,
         connection_pool: Arc<PgPool>,
         permission_set: PermissionSet,
+        features: FeatureFlags,
+        events: EventHub,
     ) -> Box<dyn TransactionServiceMethods + Send> {
-        build_service!(permission_set, connection_pool, user;
+        build_service!(permission_set, connection_pool, user, features, events;
             [NoPermission, NoPermission, NoPermission, Delete],
             [NoPermission, NoPermission, NoPermission, DeleteAll],
             [NoPermission, NoPermission, Update, NoPermission],
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use sqlx::{Acquire, PgPool};
 use std::{marker::PhantomData, sync::Arc};
+use tracing::instrument;
 
 use crate::{
     authentication::registered_user::RegisteredUser,
@@ -11,10 +12,14 @@ use crate::{
         policy::Policy,
         resources::User as UserResource,
     },
-    model::user::{User, UserCreate, UserFilter, UserId, UserUpdate},
+    model::{
+        user::{User, UserCreate, UserFilter, UserId, UserUpdate},
+        user_preference::{UserPreference, UserPreferenceUpdate},
+    },
     resource::{
         CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
-        user_repository::UserRepository,
+        asset_repository::AssetRepository, deadline,
+        user_preference_repository::UserPreferenceRepository, user_repository::UserRepository,
     },
     service::{
         ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
@@ -22,14 +27,38 @@ use crate::{
     },
 };
 
+#[async_trait]
+pub trait UserServicePreferences {
+    /// The preferences of the caller, if they ever chose any.
+    async fn preferences(&self) -> Result<Option<UserPreference>, ServiceError>;
+}
+
+#[async_trait]
+pub trait UserServiceUpdatePreferences {
+    /// Sets the preferences of the caller the update has. The default asset
+    /// not existing is [`ServiceError::NotFound`].
+    async fn update_preferences(
+        &self,
+        update: UserPreferenceUpdate,
+    ) -> Result<UserPreference, ServiceError>;
+}
+
 #[async_trait]
 pub trait UserServiceMethods:
     ServiceCrud<UserId, User, UserFilter, UserCreate, UserUpdate>
+    + UserServicePreferences
+    + UserServiceUpdatePreferences
 {
 }
 
 #[async_trait]
-impl<T: ServiceCrud<UserId, User, UserFilter, UserCreate, UserUpdate>> UserServiceMethods for T {}
+impl<
+    T: ServiceCrud<UserId, User, UserFilter, UserCreate, UserUpdate>
+        + UserServicePreferences
+        + UserServiceUpdatePreferences,
+> UserServiceMethods for T
+{
+}
 
 #[derive(Debug, Clone)]
 pub struct UserService<Policy> {
@@ -58,6 +87,35 @@ impl<Policy> UserService<Policy> {
             .clone()
             .ok_or(ServiceError::Unauthorized)
     }
+
+    /// Users only ever have their own preferences, whatever the levels.
+    async fn caller_preferences(&self) -> Result<Option<UserPreference>, ServiceError> {
+        let user_preference = UserPreferenceRepository
+            .get_by_user_id(
+                deadline::begin(&self.connection_pool).await?,
+                self.registered_user()?.id(),
+            )
+            .await?;
+        Ok(user_preference)
+    }
+
+    async fn update_caller_preferences(
+        &self,
+        update: UserPreferenceUpdate,
+    ) -> Result<UserPreference, ServiceError> {
+        let user_id = self.registered_user()?.id();
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        if let Some(default_asset_id) = update.default_asset_id {
+            AssetRepository
+                .get(transaction.begin().await?, default_asset_id)
+                .await?;
+        }
+        let user_preference = UserPreferenceRepository
+            .upsert(transaction.begin().await?, user_id, update)
+            .await?;
+        transaction.commit().await?;
+        Ok(user_preference)
+    }
 }
 
 #[async_trait]
@@ -65,6 +123,7 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGet<UserId, User>
     for UserService<Policy<UserResource, ActionSet<NoPermission, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "UserService::get", skip_all, fields(id = ?_id))]
     async fn get(&self, _id: UserId) -> Result<User, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -75,6 +134,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGetList<UserFilter, User>
     for UserService<Policy<UserResource, ActionSet<NoPermission, Create, Update, Delete>, Role>>
 {
+    #[instrument(
+        name = "UserService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
     async fn get_list(
         &self,
         _offset: i64,
@@ -90,6 +154,7 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGet<UserId, User>
     for UserService<Policy<UserResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "UserService::get", skip_all, fields(id = ?id))]
     async fn get(&self, id: UserId) -> Result<User, ServiceError> {
         let user = self.registered_user()?.user;
         if id != user.id {
@@ -106,6 +171,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGetList<UserFilter, User>
     for UserService<Policy<UserResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(
+        name = "UserService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?_limit)
+    )]
     async fn get_list(
         &self,
         offset: i64,
@@ -127,10 +197,11 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGet<UserId, User>
     for UserService<Policy<UserResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "UserService::get", skip_all, fields(id = ?id))]
     async fn get(&self, id: UserId) -> Result<User, ServiceError> {
         let user = self
             .user_repository
-            .get(self.connection_pool.begin().await?, id)
+            .get(deadline::begin(&self.connection_pool).await?, id)
             .await?;
         Ok(user)
     }
@@ -141,6 +212,7 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ServiceGetList<UserFilter, User>
     for UserService<Policy<UserResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "UserService::get_list", skip_all, fields(offset = offset, limit = ?limit))]
     async fn get_list(
         &self,
         offset: i64,
@@ -149,7 +221,12 @@ impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send +
     ) -> Result<Vec<User>, ServiceError> {
         let users = self
             .user_repository
-            .get_list(self.connection_pool.begin().await?, offset, limit, filter)
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
             .await?;
         Ok(users)
     }
@@ -160,6 +237,7 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceCreate<UserCreate, User>
     for UserService<Policy<UserResource, ActionSet<Read, NoPermission, Update, Delete>, Role>>
 {
+    #[instrument(name = "UserService::create", skip_all)]
     async fn create(&self, _create_model: UserCreate) -> Result<User, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -170,14 +248,20 @@ impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceCreate<UserCreate, User>
     for UserService<Policy<UserResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "UserService::create", skip_all)]
     async fn create(&self, create_model: UserCreate) -> Result<User, ServiceError> {
         if self.registered_user.is_some() {
             // User is already registered, don't allow re-registration
             return Err(ServiceError::AlreadyRegistered);
         }
+        // Only verified tokens are granted a group to register with, which
+        // is checked again here in case a policy ever grants one to others.
+        if create_model.email_verified != Some(true) {
+            return Err(ServiceError::EmailNotVerified);
+        }
         let user = self
             .user_repository
-            .create(self.connection_pool.begin().await?, create_model)
+            .create(deadline::begin(&self.connection_pool).await?, create_model)
             .await?;
         Ok(user)
     }
@@ -188,6 +272,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceUpdate<UserId, UserUpdate, User>
     for UserService<Policy<UserResource, ActionSet<Read, Create, NoPermission, Delete>, Role>>
 {
+    #[instrument(name = "UserService::update", skip_all, fields(id = ?_id))]
     async fn update(&self, _id: UserId, _update_model: UserUpdate) -> Result<User, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -198,6 +283,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceUpdate<UserId, UserUpdate, User>
     for UserService<Policy<UserResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "UserService::update", skip_all, fields(id = ?id))]
     async fn update(&self, id: UserId, update_model: UserUpdate) -> Result<User, ServiceError> {
         let mut user = self.registered_user()?.user;
         if id != user.id {
@@ -212,7 +298,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
 
         let user = self
             .user_repository
-            .update(self.connection_pool.begin().await?, user)
+            .update(deadline::begin(&self.connection_pool).await?, user)
             .await?;
         Ok(user)
     }
@@ -223,8 +309,9 @@ impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + S
     ServiceUpdate<UserId, UserUpdate, User>
     for UserService<Policy<UserResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>>
 {
+    #[instrument(name = "UserService::update", skip_all, fields(id = ?id))]
     async fn update(&self, id: UserId, update_model: UserUpdate) -> Result<User, ServiceError> {
-        let mut transaction = self.connection_pool.begin().await?;
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
         let mut user = self
             .user_repository
             .get(transaction.begin().await?, id)
@@ -249,6 +336,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
     ServiceDelete<UserId, User>
     for UserService<Policy<UserResource, ActionSet<Read, Create, Update, NoPermission>, Role>>
 {
+    #[instrument(name = "UserService::delete", skip_all, fields(id = ?_id))]
     async fn delete(&self, _id: UserId) -> Result<User, ServiceError> {
         Err(ServiceError::Unauthorized)
     }
@@ -259,6 +347,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
     ServiceDelete<UserId, User>
     for UserService<Policy<UserResource, ActionSet<Read, Create, Update, Delete>, Role>>
 {
+    #[instrument(name = "UserService::delete", skip_all, fields(id = ?id))]
     async fn delete(&self, id: UserId) -> Result<User, ServiceError> {
         let user = self.registered_user()?.user;
         if id != user.id {
@@ -266,7 +355,7 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
         }
         let user = self
             .user_repository
-            .delete(self.connection_pool.begin().await?, id)
+            .delete(deadline::begin(&self.connection_pool).await?, id)
             .await?;
         Ok(user)
     }
@@ -277,11 +366,87 @@ impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + S
     ServiceDelete<UserId, User>
     for UserService<Policy<UserResource, ActionSet<Read, Create, Update, DeleteAll>, Role>>
 {
+    #[instrument(name = "UserService::delete", skip_all, fields(id = ?id))]
     async fn delete(&self, id: UserId) -> Result<User, ServiceError> {
         let user = self
             .user_repository
-            .delete(self.connection_pool.begin().await?, id)
+            .delete(deadline::begin(&self.connection_pool).await?, id)
             .await?;
         Ok(user)
     }
 }
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    UserServicePreferences
+    for UserService<Policy<UserResource, ActionSet<NoPermission, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "UserService::preferences", skip_all)]
+    async fn preferences(&self) -> Result<Option<UserPreference>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    UserServicePreferences
+    for UserService<Policy<UserResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "UserService::preferences", skip_all)]
+    async fn preferences(&self) -> Result<Option<UserPreference>, ServiceError> {
+        self.caller_preferences().await
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    UserServicePreferences
+    for UserService<Policy<UserResource, ActionSet<ReadAll, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "UserService::preferences", skip_all)]
+    async fn preferences(&self) -> Result<Option<UserPreference>, ServiceError> {
+        self.caller_preferences().await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    UserServiceUpdatePreferences
+    for UserService<Policy<UserResource, ActionSet<Read, Create, NoPermission, Delete>, Role>>
+{
+    #[instrument(name = "UserService::update_preferences", skip_all)]
+    async fn update_preferences(
+        &self,
+        _update: UserPreferenceUpdate,
+    ) -> Result<UserPreference, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    UserServiceUpdatePreferences
+    for UserService<Policy<UserResource, ActionSet<Read, Create, Update, Delete>, Role>>
+{
+    #[instrument(name = "UserService::update_preferences", skip_all)]
+    async fn update_preferences(
+        &self,
+        update: UserPreferenceUpdate,
+    ) -> Result<UserPreference, ServiceError> {
+        self.update_caller_preferences(update).await
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    UserServiceUpdatePreferences
+    for UserService<Policy<UserResource, ActionSet<Read, Create, UpdateAll, Delete>, Role>>
+{
+    #[instrument(name = "UserService::update_preferences", skip_all)]
+    async fn update_preferences(
+        &self,
+        update: UserPreferenceUpdate,
+    ) -> Result<UserPreference, ServiceError> {
+        self.update_caller_preferences(update).await
+    }
+}
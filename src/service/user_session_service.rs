@@ -0,0 +1,233 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Delete, NoPermission, Read},
+        policy::Policy,
+        resources::UserSession as UserSessionResource,
+    },
+    model::user_session::{UserSession, UserSessionFilter, UserSessionId},
+    resource::{
+        GetListRepository, GetRepository, deadline, user_session_repository::UserSessionRepository,
+    },
+    service::{ServiceDelete, ServiceError, ServiceGetList},
+};
+
+#[async_trait]
+pub trait UserSessionServiceRevokeOthers {
+    /// Revokes the sessions of the caller other than the one the refresh
+    /// token of `token_hash` belongs to, returning those it revoked.
+    async fn revoke_others(
+        &self,
+        token_hash: Option<Vec<u8>>,
+    ) -> Result<Vec<UserSession>, ServiceError>;
+}
+
+/// Sessions are listed and revoked, never changed. Deleting a session
+/// revokes it, so its next refresh fails.
+#[async_trait]
+pub trait UserSessionServiceMethods:
+    ServiceGetList<UserSessionFilter, UserSession>
+    + ServiceDelete<UserSessionId, UserSession>
+    + UserSessionServiceRevokeOthers
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceGetList<UserSessionFilter, UserSession>
+        + ServiceDelete<UserSessionId, UserSession>
+        + UserSessionServiceRevokeOthers,
+> UserSessionServiceMethods for T
+{
+}
+
+pub struct UserSessionService<Policy> {
+    connection_pool: Arc<PgPool>,
+    user_session_repository: UserSessionRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> UserSessionService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        user_session_repository: UserSessionRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            user_session_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the sessions of the caller. The sessions of other
+    /// users and revoked ones are as good as missing.
+    async fn caller_user_session(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: UserSessionId,
+    ) -> Result<UserSession, ServiceError> {
+        let user_session = self
+            .user_session_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if user_session.user_id != self.registered_user.id() || user_session.revoked_at.is_some() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(user_session)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<UserSessionFilter, UserSession>
+    for UserSessionService<
+        Policy<UserSessionResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "UserSessionService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: UserSessionFilter,
+    ) -> Result<Vec<UserSession>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<UserSessionFilter, UserSession>
+    for UserSessionService<
+        Policy<UserSessionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "UserSessionService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: UserSessionFilter,
+    ) -> Result<Vec<UserSession>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let user_sessions = self
+            .user_session_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(user_sessions)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<UserSessionId, UserSession>
+    for UserSessionService<
+        Policy<UserSessionResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "UserSessionService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: UserSessionId) -> Result<UserSession, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<UserSessionId, UserSession>
+    for UserSessionService<
+        Policy<UserSessionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "UserSessionService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: UserSessionId) -> Result<UserSession, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let user_session = self.caller_user_session(&mut transaction, id).await?;
+        // There is no denylist of access tokens yet, so the access tokens
+        // issued to the session stay valid until they expire.
+        let user_session = self
+            .user_session_repository
+            .revoke(transaction.begin().await?, user_session.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(user_session)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    UserSessionServiceRevokeOthers
+    for UserSessionService<
+        Policy<UserSessionResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "UserSessionService::revoke_others", skip_all)]
+    async fn revoke_others(
+        &self,
+        _token_hash: Option<Vec<u8>>,
+    ) -> Result<Vec<UserSession>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    UserSessionServiceRevokeOthers
+    for UserSessionService<
+        Policy<UserSessionResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "UserSessionService::revoke_others", skip_all)]
+    async fn revoke_others(
+        &self,
+        token_hash: Option<Vec<u8>>,
+    ) -> Result<Vec<UserSession>, ServiceError> {
+        let Some(token_hash) = token_hash else {
+            return Err(ServiceError::NoCurrentSession);
+        };
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        // The current session is the one the refresh token belongs to,
+        // without which there is no session to keep.
+        let Some(current) = self
+            .user_session_repository
+            .get_by_token_hash(transaction.begin().await?, &token_hash)
+            .await?
+            .filter(|current| {
+                current.user_id == self.registered_user.id() && current.revoked_at.is_none()
+            })
+        else {
+            return Err(ServiceError::NoCurrentSession);
+        };
+        let user_sessions = self
+            .user_session_repository
+            .revoke_others(
+                transaction.begin().await?,
+                self.registered_user.id(),
+                current.id,
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(user_sessions)
+    }
+}
@@ -0,0 +1,297 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::{Acquire, PgPool, PgTransaction};
+use tracing::instrument;
+
+use crate::{
+    authentication::registered_user::RegisteredUser,
+    authorization::{
+        actions::{ActionSet, Create, Delete, NoPermission, Read, Update},
+        policy::Policy,
+        resources::WatchlistEntry as WatchlistEntryResource,
+    },
+    model::watchlist_entry::{
+        WatchlistEntry, WatchlistEntryCreate, WatchlistEntryFilter, WatchlistEntryId,
+    },
+    resource::{
+        CreateRepository, DeleteRepository, GetListRepository, GetRepository, UpdateRepository,
+        deadline, watchlist_entry_repository::WatchlistEntryRepository,
+    },
+    service::{
+        ServiceCreate, ServiceCrud, ServiceDelete, ServiceError, ServiceGet, ServiceGetList,
+        ServiceUpdate,
+    },
+};
+
+/// Watchlist entries are updated by the entry with its changes applied, only
+/// its target, channel, repetition and trigger of which are taken.
+#[async_trait]
+pub trait WatchlistEntryServiceMethods:
+    ServiceCrud<
+        WatchlistEntryId,
+        WatchlistEntry,
+        WatchlistEntryFilter,
+        WatchlistEntryCreate,
+        WatchlistEntry,
+    >
+{
+}
+
+#[async_trait]
+impl<
+    T: ServiceCrud<
+            WatchlistEntryId,
+            WatchlistEntry,
+            WatchlistEntryFilter,
+            WatchlistEntryCreate,
+            WatchlistEntry,
+        >,
+> WatchlistEntryServiceMethods for T
+{
+}
+
+pub struct WatchlistEntryService<Policy> {
+    connection_pool: Arc<PgPool>,
+    watchlist_entry_repository: WatchlistEntryRepository,
+    registered_user: RegisteredUser,
+    policy: PhantomData<Policy>,
+}
+
+impl<Policy> WatchlistEntryService<Policy> {
+    pub fn new(
+        connection_pool: Arc<PgPool>,
+        watchlist_entry_repository: WatchlistEntryRepository,
+        registered_user: RegisteredUser,
+    ) -> Self {
+        Self {
+            connection_pool,
+            watchlist_entry_repository,
+            registered_user,
+            policy: PhantomData,
+        }
+    }
+
+    /// Fetches one of the watchlist entries of the caller. The entries of
+    /// other users are as good as missing.
+    async fn caller_watchlist_entry(
+        &self,
+        transaction: &mut PgTransaction<'_>,
+        id: WatchlistEntryId,
+    ) -> Result<WatchlistEntry, ServiceError> {
+        let watchlist_entry = self
+            .watchlist_entry_repository
+            .get(transaction.begin().await?, id)
+            .await?;
+        if watchlist_entry.user_id != self.registered_user.id() {
+            return Err(ServiceError::NotFound);
+        }
+        Ok(watchlist_entry)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<WatchlistEntryId, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "WatchlistEntryService::get", skip_all, fields(id = ?_id))]
+    async fn get(&self, _id: WatchlistEntryId) -> Result<WatchlistEntry, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<WatchlistEntryFilter, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<NoPermission, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "WatchlistEntryService::get_list",
+        skip_all,
+        fields(offset = _offset, limit = ?_limit)
+    )]
+    async fn get_list(
+        &self,
+        _offset: i64,
+        _limit: Option<i64>,
+        _filter: WatchlistEntryFilter,
+    ) -> Result<Vec<WatchlistEntry>, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGet<WatchlistEntryId, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "WatchlistEntryService::get", skip_all, fields(id = ?id))]
+    async fn get(&self, id: WatchlistEntryId) -> Result<WatchlistEntry, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let watchlist_entry = self.caller_watchlist_entry(&mut transaction, id).await?;
+        transaction.commit().await?;
+        Ok(watchlist_entry)
+    }
+}
+
+#[async_trait]
+impl<Create: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceGetList<WatchlistEntryFilter, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(
+        name = "WatchlistEntryService::get_list",
+        skip_all,
+        fields(offset = offset, limit = ?limit)
+    )]
+    async fn get_list(
+        &self,
+        offset: i64,
+        limit: Option<i64>,
+        mut filter: WatchlistEntryFilter,
+    ) -> Result<Vec<WatchlistEntry>, ServiceError> {
+        filter.user_id = self.registered_user.id().into();
+        let watchlist_entries = self
+            .watchlist_entry_repository
+            .get_list(
+                deadline::begin(&self.connection_pool).await?,
+                offset,
+                limit,
+                filter,
+            )
+            .await?;
+        Ok(watchlist_entries)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<WatchlistEntryCreate, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<Read, NoPermission, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "WatchlistEntryService::create", skip_all)]
+    async fn create(
+        &self,
+        _create_model: WatchlistEntryCreate,
+    ) -> Result<WatchlistEntry, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Update: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceCreate<WatchlistEntryCreate, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "WatchlistEntryService::create", skip_all)]
+    async fn create(
+        &self,
+        create_model: WatchlistEntryCreate,
+    ) -> Result<WatchlistEntry, ServiceError> {
+        if self.registered_user.id() != create_model.user_id {
+            return Err(ServiceError::Unauthorized);
+        }
+        let watchlist_entry = self
+            .watchlist_entry_repository
+            .create(deadline::begin(&self.connection_pool).await?, create_model)
+            .await?;
+        Ok(watchlist_entry)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<WatchlistEntryId, WatchlistEntry, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<Read, Create, NoPermission, Delete>, Role>,
+    >
+{
+    #[instrument(name = "WatchlistEntryService::update", skip_all, fields(id = ?_id))]
+    async fn update(
+        &self,
+        _id: WatchlistEntryId,
+        _update_model: WatchlistEntry,
+    ) -> Result<WatchlistEntry, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Delete: Send + Sync, Role: Send + Sync>
+    ServiceUpdate<WatchlistEntryId, WatchlistEntry, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "WatchlistEntryService::update", skip_all, fields(id = ?id))]
+    async fn update(
+        &self,
+        id: WatchlistEntryId,
+        update_model: WatchlistEntry,
+    ) -> Result<WatchlistEntry, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let watchlist_entry = self.caller_watchlist_entry(&mut transaction, id).await?;
+        // The entry keeps watching the pair it was created for.
+        let watchlist_entry = WatchlistEntry {
+            target_price: update_model.target_price,
+            direction: update_model.direction,
+            channel: update_model.channel,
+            repeating: update_model.repeating,
+            triggered_at: update_model.triggered_at,
+            triggered_price: update_model.triggered_price,
+            ..watchlist_entry
+        };
+        let watchlist_entry = self
+            .watchlist_entry_repository
+            .update(transaction.begin().await?, watchlist_entry)
+            .await?;
+        transaction.commit().await?;
+        Ok(watchlist_entry)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<WatchlistEntryId, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<Read, Create, Update, NoPermission>, Role>,
+    >
+{
+    #[instrument(name = "WatchlistEntryService::delete", skip_all, fields(id = ?_id))]
+    async fn delete(&self, _id: WatchlistEntryId) -> Result<WatchlistEntry, ServiceError> {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<Read: Send + Sync, Create: Send + Sync, Update: Send + Sync, Role: Send + Sync>
+    ServiceDelete<WatchlistEntryId, WatchlistEntry>
+    for WatchlistEntryService<
+        Policy<WatchlistEntryResource, ActionSet<Read, Create, Update, Delete>, Role>,
+    >
+{
+    #[instrument(name = "WatchlistEntryService::delete", skip_all, fields(id = ?id))]
+    async fn delete(&self, id: WatchlistEntryId) -> Result<WatchlistEntry, ServiceError> {
+        let mut transaction = deadline::begin(&self.connection_pool).await?;
+        let watchlist_entry = self.caller_watchlist_entry(&mut transaction, id).await?;
+        let watchlist_entry = self
+            .watchlist_entry_repository
+            .delete(transaction.begin().await?, watchlist_entry.id)
+            .await?;
+        transaction.commit().await?;
+        Ok(watchlist_entry)
+    }
+}
@@ -0,0 +1,305 @@
+//! The phases the server goes through before it takes traffic.
+//!
+//! Each phase runs in its own `startup` span, logs how long it took and
+//! fails with a [`StartupError`] naming it once it runs past the
+//! [`StartupConfig::phase_timeout`]. Phases that don't depend on each other,
+//! like connecting to the database and loading the authorization policy, run
+//! at the same time through [`Startup::phase`] taking `&self`. For the
+//! [`StartupConfig::report_window`] after boot, `GET /readyz` reports the
+//! timings, so a slow cold start can be told apart from a stuck one.
+
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{Json, Router, extract::State, routing::get};
+use serde::Serialize;
+use thiserror::Error;
+use tokio::time::{Instant, timeout};
+use tracing::{Instrument, error, info, info_span};
+
+use crate::config::StartupConfig;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum StartupError {
+    #[error("Startup phase `{phase}` did not finish within {}s.", .timeout.as_secs_f64())]
+    TimedOut {
+        phase: &'static str,
+        timeout: Duration,
+    },
+    #[error("Startup phase `{phase}` failed: {message}")]
+    Failed {
+        phase: &'static str,
+        message: String,
+    },
+}
+
+impl StartupError {
+    /// The phase that failed.
+    pub fn phase(&self) -> &'static str {
+        match self {
+            Self::TimedOut { phase, .. } | Self::Failed { phase, .. } => phase,
+        }
+    }
+}
+
+/// How long a phase of startup took.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    pub elapsed_ms: u128,
+}
+
+/// Runs the phases of startup, keeping their timings.
+#[derive(Debug)]
+pub struct Startup {
+    config: StartupConfig,
+    started_at: Instant,
+    phases: Mutex<Vec<PhaseTiming>>,
+}
+
+impl Startup {
+    pub fn new(config: StartupConfig) -> Self {
+        Self {
+            config,
+            started_at: Instant::now(),
+            phases: Mutex::default(),
+        }
+    }
+
+    /// Runs `phase`, giving up on it once it takes longer than the phase
+    /// timeout. A phase that fails or times out is named in the error.
+    pub async fn phase<T, E: Display>(
+        &self,
+        name: &'static str,
+        phase: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, StartupError> {
+        let span = info_span!("startup", phase = name);
+        let phase_timeout = self.config.phase_timeout;
+        async move {
+            let started_at = Instant::now();
+            let result = match timeout(phase_timeout, phase).await {
+                Ok(Ok(value)) => Ok(value),
+                Ok(Err(e)) => Err(StartupError::Failed {
+                    phase: name,
+                    message: e.to_string(),
+                }),
+                Err(_) => Err(StartupError::TimedOut {
+                    phase: name,
+                    timeout: phase_timeout,
+                }),
+            };
+            let elapsed = started_at.elapsed();
+            match &result {
+                Ok(_) => info!(elapsed_ms = elapsed.as_millis(), "Finished `{name}`"),
+                Err(e) => error!(elapsed_ms = elapsed.as_millis(), "{e}"),
+            }
+            self.phases
+                .lock()
+                .expect("Startup phases lock poisoned")
+                .push(PhaseTiming {
+                    phase: name,
+                    elapsed_ms: elapsed.as_millis(),
+                });
+            result
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Ends startup, logging how long it took overall.
+    pub fn finish(self) -> StartupReport {
+        let total = self.started_at.elapsed();
+        info!(elapsed_ms = total.as_millis(), "Started up");
+        StartupReport {
+            booted_at: Instant::now(),
+            report_window: self.config.report_window,
+            timings: StartupTimings {
+                total_ms: total.as_millis(),
+                phases: self
+                    .phases
+                    .into_inner()
+                    .expect("Startup phases lock poisoned"),
+            },
+        }
+    }
+}
+
+/// How long startup took, phase by phase in the order they finished.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StartupTimings {
+    pub total_ms: u128,
+    pub phases: Vec<PhaseTiming>,
+}
+
+/// The timings of a finished startup, reported for a while after boot.
+#[derive(Debug, Clone)]
+pub struct StartupReport {
+    booted_at: Instant,
+    report_window: Duration,
+    timings: StartupTimings,
+}
+
+impl StartupReport {
+    /// The timings, while still within the report window.
+    pub fn timings(&self) -> Option<&StartupTimings> {
+        (self.booted_at.elapsed() < self.report_window).then_some(&self.timings)
+    }
+
+    /// Serves `GET /readyz`.
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/readyz", get(readyz))
+            .with_state(Arc::new(self))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadyResponse {
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startup: Option<StartupTimings>,
+}
+
+async fn readyz(State(report): State<Arc<StartupReport>>) -> Json<ReadyResponse> {
+    Json(ReadyResponse {
+        status: "ready",
+        startup: report.timings().cloned(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use axum::body::Body;
+    use http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use serde_json::{Value, json};
+    use tokio::time::sleep;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn startup(phase_timeout: Duration, report_window: Duration) -> Startup {
+        Startup::new(StartupConfig {
+            phase_timeout,
+            report_window,
+        })
+    }
+
+    async fn slow_phase(delay: Duration) -> Result<&'static str, String> {
+        sleep(delay).await;
+        Ok("done")
+    }
+
+    async fn get_readyz(report: StartupReport) -> Value {
+        let request = Request::builder()
+            .uri("/readyz")
+            .body(Body::empty())
+            .unwrap();
+        let response = report.router().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_names_the_phase_that_timed_out() {
+        let startup = startup(Duration::from_secs(5), Duration::from_secs(300));
+        let (database, enforcer) = tokio::join!(
+            startup.phase("database", slow_phase(Duration::from_secs(1))),
+            startup.phase("enforcer", slow_phase(Duration::from_secs(30))),
+        );
+
+        assert_eq!(database, Ok("done"));
+        let e = enforcer.unwrap_err();
+        assert_eq!(
+            e,
+            StartupError::TimedOut {
+                phase: "enforcer",
+                timeout: Duration::from_secs(5)
+            }
+        );
+        assert_eq!(e.phase(), "enforcer");
+        assert_eq!(
+            e.to_string(),
+            "Startup phase `enforcer` did not finish within 5s."
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_names_the_phase_that_failed() {
+        let startup = startup(Duration::from_secs(5), Duration::from_secs(300));
+        let e = startup
+            .phase("listener", async { Err::<(), _>("address in use") })
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            e,
+            StartupError::Failed {
+                phase: "listener",
+                message: "address in use".to_owned()
+            }
+        );
+        assert_eq!(
+            e.to_string(),
+            "Startup phase `listener` failed: address in use"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_runs_independent_phases_at_the_same_time() {
+        let startup = startup(Duration::from_secs(5), Duration::from_secs(300));
+        let started_at = Instant::now();
+        let (database, enforcer) = tokio::join!(
+            startup.phase("database", slow_phase(Duration::from_secs(3))),
+            startup.phase("enforcer", slow_phase(Duration::from_secs(4))),
+        );
+
+        assert_eq!((database, enforcer), (Ok("done"), Ok("done")));
+        assert_eq!(started_at.elapsed(), Duration::from_secs(4));
+        let report = startup.finish();
+        assert_eq!(
+            report.timings(),
+            Some(&StartupTimings {
+                total_ms: 4000,
+                phases: vec![
+                    PhaseTiming {
+                        phase: "database",
+                        elapsed_ms: 3000
+                    },
+                    PhaseTiming {
+                        phase: "enforcer",
+                        elapsed_ms: 4000
+                    },
+                ]
+            })
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_reports_the_timings_only_for_a_while_after_boot() {
+        let startup = startup(Duration::from_secs(5), Duration::from_secs(300));
+        startup
+            .phase("database", slow_phase(Duration::from_secs(2)))
+            .await
+            .unwrap();
+        let report = startup.finish();
+
+        assert_eq!(
+            get_readyz(report.clone()).await,
+            json!({
+                "status": "ready",
+                "startup": {
+                    "total_ms": 2000,
+                    "phases": [{ "phase": "database", "elapsed_ms": 2000 }]
+                }
+            })
+        );
+
+        sleep(Duration::from_secs(300)).await;
+        assert_eq!(get_readyz(report).await, json!({ "status": "ready" }));
+    }
+}
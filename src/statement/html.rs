@@ -0,0 +1,94 @@
+//! Statements as standalone HTML documents, to print or send on.
+
+use crate::{
+    schema::text::escape_output,
+    statement::{Statement, format_quantity},
+};
+
+/// How the uncategorized transactions are labelled.
+pub const UNCATEGORIZED: &str = "Uncategorized";
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+    table{border-collapse:collapse;margin-bottom:1.5em}\
+    th,td{border-bottom:1px solid #ccc;padding:.25em .75em;text-align:left}\
+    td.quantity{text-align:right;font-variant-numeric:tabular-nums}";
+
+/// Escapes text for HTML, after [`escape_output`].
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in escape_output(value).chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders a statement. The balance cells have an `id` naming their asset
+/// and the subtotal rows a `data-category` within the section of their
+/// asset, so the figures can be read back off the document.
+pub fn render(statement: &Statement) -> String {
+    let name = escape(&statement.account.name);
+    let month = statement.month.label();
+    let mut html = String::new();
+    html.push_str(&format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{name}, {month}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>{name}</h1>\n<p>Statement for {month}, from {} until {} UTC.</p>\n",
+        statement.month.start().format("%Y-%m-%d"),
+        statement.month.end().format("%Y-%m-%d"),
+    ));
+    if statement.assets.is_empty() {
+        html.push_str("<p>The account had no transactions by the end of the month.</p>\n");
+    }
+    for asset in &statement.assets {
+        let balance = &asset.balance;
+        let symbol = escape(&balance.symbol);
+        let decimals = balance.decimals;
+        html.push_str(&format!(
+            "<section id=\"asset-{symbol}\">\n<h2>{symbol}</h2>\n<table>\n\
+             <tr><th>Opening balance</th><td class=\"quantity\" id=\"opening-{symbol}\">{}</td></tr>\n\
+             <tr><th>Closing balance</th><td class=\"quantity\" id=\"closing-{symbol}\">{}</td></tr>\n\
+             </table>\n",
+            format_quantity(balance.opening_balance, decimals),
+            format_quantity(balance.closing_balance, decimals),
+        ));
+
+        html.push_str(
+            "<table>\n<thead><tr><th>Posted</th><th>Description</th><th>Category</th>\
+             <th>Quantity</th></tr></thead>\n<tbody>\n",
+        );
+        for transaction in &asset.transactions {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"quantity\">{}</td></tr>\n",
+                transaction.posted_at.format("%Y-%m-%d %H:%M"),
+                escape(transaction.description.as_deref().unwrap_or_default()),
+                escape(transaction.category.as_deref().unwrap_or_default()),
+                format_quantity(transaction.quantity, decimals),
+            ));
+        }
+        html.push_str("</tbody>\n</table>\n");
+
+        html.push_str(
+            "<table>\n<thead><tr><th>Category</th><th>Transactions</th><th>Subtotal</th>\
+             </tr></thead>\n<tbody>\n",
+        );
+        for subtotal in &asset.subtotals {
+            let category = escape(subtotal.category.as_deref().unwrap_or(UNCATEGORIZED));
+            html.push_str(&format!(
+                "<tr data-category=\"{category}\"><td>{category}</td><td>{}</td>\
+                 <td class=\"quantity\">{}</td></tr>\n",
+                subtotal.transactions,
+                format_quantity(subtotal.quantity, decimals),
+            ));
+        }
+        html.push_str("</tbody>\n</table>\n</section>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
@@ -0,0 +1,325 @@
+//! Monthly statements of accounts.
+//!
+//! A [`Statement`] covers a [`StatementMonth`] of an account: the balance
+//! it opened and closed at in each asset, the transactions posted in the
+//! month and their subtotals by category. Only months that have ended are
+//! stated, and a statement is read in one snapshot, so its balances always
+//! add up to its transactions. Statements are rendered as HTML, or as PDF
+//! by servers built with the `pdf` feature.
+//!
+//! A month of more than [`INLINE_TRANSACTIONS`] transactions is rendered by
+//! a [`StatementJob`] after responding, which stores the document on an
+//! [`AccountStatement`] for the client to poll and download.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+use crate::{
+    api::{ApiError, ClientErrorCode},
+    model::{
+        account::Account,
+        statement::{
+            AccountStatement, AccountStatementCreate, CategorySubtotal, StatementBalance,
+            StatementFormat,
+        },
+        transaction::Transaction,
+    },
+    resource::{deadline, statement_repository::StatementRepository},
+    rounding::RoundingPolicy,
+    schema::ResolvedDateRange,
+    service::ServiceError,
+};
+
+pub mod html;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+
+/// The most transactions a month may have to be rendered while
+/// responding.
+pub const INLINE_TRANSACTIONS: i64 = 1_000;
+
+#[derive(Debug, Clone, Error)]
+pub enum StatementError {
+    #[error("The year and month must name a month, from 1 to 12.")]
+    InvalidMonth,
+    #[error(
+        "A statement is of either a `year` and `month`, or a date range of a whole month in UTC."
+    )]
+    NoMonth,
+    #[error("Statements are only made for months that have ended.")]
+    OpenPeriod,
+    #[error("PDF statements are not enabled on this server.")]
+    PdfDisabled,
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<StatementError> for ApiError {
+    fn from(value: StatementError) -> Self {
+        match value {
+            StatementError::Service(e) => Self::Service(e),
+            e => Self::client(ClientErrorCode::InvalidRequest, e.to_string()),
+        }
+    }
+}
+
+/// A calendar month, in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatementMonth {
+    first_day: NaiveDate,
+    next_first_day: NaiveDate,
+}
+
+impl StatementMonth {
+    pub fn new(year: i32, month: u32) -> Result<Self, StatementError> {
+        let first_day =
+            NaiveDate::from_ymd_opt(year, month, 1).ok_or(StatementError::InvalidMonth)?;
+        let next_first_day = first_day
+            .checked_add_months(Months::new(1))
+            .ok_or(StatementError::InvalidMonth)?;
+        Ok(Self {
+            first_day,
+            next_first_day,
+        })
+    }
+
+    /// The month a date range covers, which must start on the first of a
+    /// month in UTC and end on the first of the next.
+    pub fn of_range(range: ResolvedDateRange) -> Result<Self, StatementError> {
+        let (Some(from), Some(to)) = (range.from, range.to) else {
+            return Err(StatementError::NoMonth);
+        };
+        let month = Self::new(from.year(), from.month())?;
+        if month.start() != from || month.end() != to {
+            return Err(StatementError::NoMonth);
+        }
+        Ok(month)
+    }
+
+    /// The month a queued statement is of.
+    pub fn of(statement: &AccountStatement) -> Result<Self, StatementError> {
+        let month = u32::try_from(statement.month).map_err(|_| StatementError::InvalidMonth)?;
+        Self::new(statement.year, month)
+    }
+
+    pub fn year(&self) -> i32 {
+        self.first_day.year()
+    }
+
+    /// The month of the year, from 1.
+    pub fn month(&self) -> u32 {
+        self.first_day.month()
+    }
+
+    /// When the month starts.
+    pub fn start(&self) -> DateTime<Utc> {
+        self.first_day.and_time(NaiveTime::MIN).and_utc()
+    }
+
+    /// When the next month starts, which the month runs until.
+    pub fn end(&self) -> DateTime<Utc> {
+        self.next_first_day.and_time(NaiveTime::MIN).and_utc()
+    }
+
+    /// The month as `YYYY-MM`.
+    pub fn label(&self) -> String {
+        self.first_day.format("%Y-%m").to_string()
+    }
+
+    /// Refuses months that haven't ended by `now`, whose transactions may
+    /// still change.
+    pub fn ensure_closed(&self, now: DateTime<Utc>) -> Result<(), StatementError> {
+        if self.end() > now {
+            return Err(StatementError::OpenPeriod);
+        }
+        Ok(())
+    }
+}
+
+/// A month of an account in one asset.
+#[derive(Debug, Clone)]
+pub struct AssetStatement {
+    pub balance: StatementBalance,
+    /// The transactions posted in the month, in the order they were
+    pub transactions: Vec<Transaction>,
+    /// The subtotals by category, the uncategorized transactions last
+    pub subtotals: Vec<CategorySubtotal>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub account: Account,
+    pub month: StatementMonth,
+    /// The assets the account had transactions in by the end of the month
+    pub assets: Vec<AssetStatement>,
+}
+
+impl Statement {
+    pub async fn read(
+        connection_pool: &PgPool,
+        account: Account,
+        month: StatementMonth,
+    ) -> Result<Self, StatementError> {
+        let rows = StatementRepository
+            .read_month(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                account.id,
+                month.start(),
+                month.end(),
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        let assets = rows
+            .balances
+            .into_iter()
+            .map(|balance| AssetStatement {
+                transactions: rows
+                    .transactions
+                    .iter()
+                    .filter(|x| x.asset_id == balance.asset_id)
+                    .cloned()
+                    .collect(),
+                subtotals: rows
+                    .subtotals
+                    .iter()
+                    .filter(|x| x.asset_id == balance.asset_id)
+                    .cloned()
+                    .collect(),
+                balance,
+            })
+            .collect();
+        Ok(Self {
+            account,
+            month,
+            assets,
+        })
+    }
+
+    pub fn render(&self, format: StatementFormat) -> Result<Vec<u8>, StatementError> {
+        match format {
+            StatementFormat::Html => Ok(html::render(self).into_bytes()),
+            #[cfg(feature = "pdf")]
+            StatementFormat::Pdf => Ok(pdf::render(self)),
+            #[cfg(not(feature = "pdf"))]
+            StatementFormat::Pdf => Err(StatementError::PdfDisabled),
+        }
+    }
+}
+
+/// Writes a quantity at the decimals of its asset, rounded by the
+/// configured policy if it has more.
+pub fn format_quantity(quantity: Decimal, decimals: i16) -> String {
+    RoundingPolicy::from_env()
+        .round(quantity, decimals.unsigned_abs().into())
+        .to_string()
+}
+
+/// A statement rendered while responding, or the one a job renders after.
+#[derive(Debug)]
+pub enum Rendering {
+    Inline(Vec<u8>),
+    Queued(AccountStatement),
+}
+
+/// Renders the statement of `month` of an account while responding, or
+/// queues a [`StatementJob`] for it when the month is too large to.
+pub async fn render_or_queue(
+    connection_pool: &Arc<PgPool>,
+    account: Account,
+    month: StatementMonth,
+    format: StatementFormat,
+) -> Result<Rendering, StatementError> {
+    month.ensure_closed(Utc::now())?;
+    if cfg!(not(feature = "pdf")) && format == StatementFormat::Pdf {
+        return Err(StatementError::PdfDisabled);
+    }
+    let transactions = StatementRepository
+        .count_transactions(
+            deadline::begin(&connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            account.id,
+            month.start(),
+            month.end(),
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    if transactions <= INLINE_TRANSACTIONS {
+        let statement = Statement::read(connection_pool, account, month).await?;
+        return Ok(Rendering::Inline(statement.render(format)?));
+    }
+
+    let statement = StatementRepository
+        .create(
+            deadline::begin(&connection_pool)
+                .await
+                .map_err(ServiceError::from)?,
+            AccountStatementCreate {
+                account_id: account.id,
+                year: month.year(),
+                month: month.month() as i32,
+                format,
+            },
+        )
+        .await
+        .map_err(ServiceError::from)?;
+    StatementJob {
+        statement: statement.clone(),
+        account,
+        month,
+    }
+    .spawn(Arc::clone(connection_pool));
+    Ok(Rendering::Queued(statement))
+}
+
+/// Renders a queued statement and stores the document.
+pub struct StatementJob {
+    pub statement: AccountStatement,
+    pub account: Account,
+    pub month: StatementMonth,
+}
+
+impl StatementJob {
+    /// Renders the statement and records the outcome. Rendering errors are
+    /// recorded on the statement rather than returned.
+    pub async fn run(self, connection_pool: &PgPool) -> Result<AccountStatement, ApiError> {
+        let id = self.statement.id;
+        let outcome = match Statement::read(connection_pool, self.account, self.month).await {
+            Ok(statement) => statement.render(self.statement.format),
+            Err(e) => Err(e),
+        }
+        .map_err(|e| {
+            warn!("Rendering of statement {id} failed: {e}");
+            e.to_string()
+        });
+        let statement = StatementRepository
+            .finish(
+                deadline::begin(&connection_pool)
+                    .await
+                    .map_err(ServiceError::from)?,
+                id,
+                outcome,
+            )
+            .await
+            .map_err(ServiceError::from)?;
+        Ok(statement)
+    }
+
+    /// Runs the job after the request that queued it has been responded to.
+    pub fn spawn(self, connection_pool: Arc<PgPool>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let id = self.statement.id;
+            if let Err(e) = self.run(&connection_pool).await {
+                error!("Statement {id} could not be recorded: {e}");
+            }
+        })
+    }
+}
@@ -0,0 +1,240 @@
+//! Statements as PDF documents.
+//!
+//! The statement is set as lines of 8 point Courier on A4 pages, written
+//! out directly rather than through a layout engine. Courier is one of the
+//! fonts every reader has, but it only covers Latin-1, so other characters
+//! are written as `?`. Send the HTML statement for text in other scripts.
+
+use crate::statement::{Statement, format_quantity, html::UNCATEGORIZED};
+
+const FONT_SIZE: u32 = 8;
+const LEADING: u32 = 11;
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 48;
+const LINES_PER_PAGE: usize = 68;
+
+/// The widths of the columns of the transactions, in characters.
+const POSTED: usize = 16;
+const DESCRIPTION: usize = 38;
+const CATEGORY: usize = 20;
+const QUANTITY: usize = 18;
+
+/// Pads or cuts `value` to `width` characters.
+fn cell(value: &str, width: usize) -> String {
+    format!("{value:width$.width$}")
+}
+
+/// The lines a statement is set as.
+pub fn lines(statement: &Statement) -> Vec<String> {
+    let mut lines = vec![
+        statement.account.name.clone(),
+        format!(
+            "Statement for {}, from {} until {} UTC.",
+            statement.month.label(),
+            statement.month.start().format("%Y-%m-%d"),
+            statement.month.end().format("%Y-%m-%d"),
+        ),
+    ];
+    if statement.assets.is_empty() {
+        lines.push(String::new());
+        lines.push("The account had no transactions by the end of the month.".to_owned());
+    }
+    for asset in &statement.assets {
+        let balance = &asset.balance;
+        let decimals = balance.decimals;
+        lines.extend([
+            String::new(),
+            balance.symbol.clone(),
+            format!(
+                "{}{:>QUANTITY$}",
+                cell("Opening balance", POSTED + DESCRIPTION + CATEGORY),
+                format_quantity(balance.opening_balance, decimals),
+            ),
+            format!(
+                "{}{:>QUANTITY$}",
+                cell("Closing balance", POSTED + DESCRIPTION + CATEGORY),
+                format_quantity(balance.closing_balance, decimals),
+            ),
+            String::new(),
+            format!(
+                "{}{}{}{:>QUANTITY$}",
+                cell("Posted", POSTED),
+                cell("Description", DESCRIPTION),
+                cell("Category", CATEGORY),
+                "Quantity",
+            ),
+        ]);
+        lines.extend(asset.transactions.iter().map(|transaction| {
+            format!(
+                "{}{}{}{:>QUANTITY$}",
+                cell(
+                    &transaction.posted_at.format("%Y-%m-%d %H:%M").to_string(),
+                    POSTED
+                ),
+                cell(
+                    transaction.description.as_deref().unwrap_or_default(),
+                    DESCRIPTION
+                ),
+                cell(
+                    transaction.category.as_deref().unwrap_or_default(),
+                    CATEGORY
+                ),
+                format_quantity(transaction.quantity, decimals),
+            )
+        }));
+        lines.extend([
+            String::new(),
+            format!(
+                "{}{:>POSTED$}{:>QUANTITY$}",
+                cell("Category", DESCRIPTION + CATEGORY),
+                "Transactions",
+                "Subtotal",
+            ),
+        ]);
+        lines.extend(asset.subtotals.iter().map(|subtotal| {
+            format!(
+                "{}{:>POSTED$}{:>QUANTITY$}",
+                cell(
+                    subtotal.category.as_deref().unwrap_or(UNCATEGORIZED),
+                    DESCRIPTION + CATEGORY
+                ),
+                subtotal.transactions,
+                format_quantity(subtotal.quantity, decimals),
+            )
+        }));
+    }
+    lines
+}
+
+pub fn render(statement: &Statement) -> Vec<u8> {
+    write_document(&lines(statement))
+}
+
+/// Writes `line` as a PDF string in the Latin-1 of the font.
+fn push_string(content: &mut String, line: &str) {
+    content.push('(');
+    for c in line.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                content.push('\\');
+                content.push(c);
+            }
+            ' '..='~' => content.push(c),
+            '\u{A0}'..='\u{FF}' => {
+                content.push_str(&format!("\\{:03o}", u32::from(c)));
+            }
+            _ => content.push('?'),
+        }
+    }
+    content.push(')');
+}
+
+/// Writes the lines as a document, a page for each [`LINES_PER_PAGE`].
+fn write_document(lines: &[String]) -> Vec<u8> {
+    let pages = lines.chunks(LINES_PER_PAGE).collect::<Vec<_>>();
+    let pages = if pages.is_empty() {
+        vec![&[][..]]
+    } else {
+        pages
+    };
+    // The catalog, the page tree and the font come first, then each page
+    // followed by its content.
+    let page_ids = (0..pages.len()).map(|i| 4 + 2 * i).collect::<Vec<_>>();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{id} 0 R"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len(),
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"
+            .to_owned(),
+    ];
+    for (page, id) in pages.iter().zip(&page_ids) {
+        let mut content = format!(
+            "BT\n/F1 {FONT_SIZE} Tf\n{LEADING} TL\n{MARGIN} {} Td\n",
+            PAGE_HEIGHT - MARGIN,
+        );
+        for line in page.iter() {
+            push_string(&mut content, line);
+            content.push_str(" Tj T*\n");
+        }
+        content.push_str("ET");
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            id + 1,
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{content}\nendstream",
+            content.len(),
+        ));
+    }
+
+    let mut document = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(document.len());
+        document.push_str(&format!("{} 0 obj\n{object}\nendobj\n", i + 1));
+    }
+    let xref = document.len();
+    document.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        document.push_str(&format!("{offset:010} 00000 n \n"));
+    }
+    document.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1,
+    ));
+    document.into_bytes()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_writes_a_page_for_each_lines_per_page() {
+        let lines = (0..LINES_PER_PAGE + 1)
+            .map(|i| format!("Line {i} (café, 카페)"))
+            .collect::<Vec<_>>();
+        let document = String::from_utf8(write_document(&lines)).unwrap();
+
+        assert!(document.starts_with("%PDF-1.4\n"));
+        assert!(document.ends_with("%%EOF\n"));
+        assert!(document.contains("/Count 2"));
+        assert!(document.contains(r"(Line 0 \(caf\351, ??\)) Tj T*"));
+
+        // Each entry of the cross-reference table points at its object.
+        let xref = document.find("\nxref\n").unwrap() + 1;
+        let startxref = document
+            .split("startxref\n")
+            .nth(1)
+            .and_then(|x| x.lines().next())
+            .unwrap();
+        assert_eq!(startxref.parse::<usize>().unwrap(), xref);
+        for (i, entry) in document[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|x| x.ends_with(" n "))
+            .enumerate()
+        {
+            let offset = entry[..10].parse::<usize>().unwrap();
+            assert!(document[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+        }
+    }
+
+    #[test]
+    fn it_writes_an_empty_document_as_one_page() {
+        let document = String::from_utf8(write_document(&[])).unwrap();
+        assert!(document.contains("/Count 1"));
+    }
+}
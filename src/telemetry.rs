@@ -0,0 +1,133 @@
+use http::Request;
+use tracing::{Span, info_span};
+use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+
+#[cfg(feature = "otel")]
+mod otel_imports {
+    pub use http::HeaderMap;
+    pub use opentelemetry::{
+        global,
+        propagation::Extractor,
+        trace::{TraceContextExt, TracerProvider},
+    };
+    pub use opentelemetry_otlp::SpanExporter;
+    pub use opentelemetry_sdk::{
+        Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider,
+    };
+    pub use std::env::var;
+    pub use tracing_opentelemetry::OpenTelemetrySpanExt;
+}
+
+#[cfg(feature = "otel")]
+use otel_imports::*;
+
+/// The env variable turning on the OTLP span export.
+pub const OTLP_ENDPOINT_VAR: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Keeps the span exporter alive, flushing it when dropped.
+#[derive(Default)]
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("Failed to flush the span exporter: {e}");
+        }
+    }
+}
+
+/// Installs the global subscriber, logging as filtered by `RUST_LOG`.
+///
+/// With the `otel` feature and `OTEL_EXPORTER_OTLP_ENDPOINT` set, spans
+/// are also exported over OTLP and incoming `traceparent` headers are
+/// honoured. Otherwise only the logs are kept.
+pub fn init() -> Telemetry {
+    let registry = tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(fmt::layer());
+
+    #[cfg(feature = "otel")]
+    if let Some(provider) = otlp_provider() {
+        let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+        registry
+            .with(tracing_opentelemetry::layer().with_tracer(tracer))
+            .init();
+        return Telemetry {
+            provider: Some(provider),
+        };
+    }
+
+    registry.init();
+    Telemetry::default()
+}
+
+#[cfg(feature = "otel")]
+fn otlp_provider() -> Option<SdkTracerProvider> {
+    var(OTLP_ENDPOINT_VAR).ok()?;
+    // The exporter reads the endpoint and the rest of its configuration from
+    // the standard `OTEL_EXPORTER_OTLP_*` variables.
+    let exporter = match SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Failed to build the span exporter, spans will not be exported: {e}");
+            return None;
+        }
+    };
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    Some(
+        SdkTracerProvider::builder()
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(env!("CARGO_PKG_NAME"))
+                    .build(),
+            )
+            .with_batch_exporter(exporter)
+            .build(),
+    )
+}
+
+#[cfg(feature = "otel")]
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+#[cfg(feature = "otel")]
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// The root span of a request, for `TraceLayer::make_span_with`.
+///
+/// When spans are exported, a request carrying a `traceparent` header
+/// continues the trace of the caller.
+pub fn make_request_span<B>(request: &Request<B>) -> Span {
+    let span = info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+        otel.kind = "server",
+    );
+
+    #[cfg(feature = "otel")]
+    {
+        let context = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        if context.span().span_context().is_valid() {
+            span.set_parent(context);
+        }
+    }
+
+    span
+}
@@ -0,0 +1,366 @@
+//! Checks uploaded files before they are kept, trusting neither the name nor
+//! the content type the client gives.
+//!
+//! The content type of a file is sniffed from its leading bytes and must be
+//! one of [`ALLOWED_TYPES`], agreeing with the type the client declared. A
+//! file that sniffs as something else, like a GIF with HTML after it sent as
+//! a PNG, is rejected. The name of a file is only kept for display: it is cut
+//! down to its last path component, stripped of control characters and
+//! shortened, while the content is stored under the generated id of its row.
+//!
+//! Images wider or higher than [`MAX_IMAGE_DIMENSION`] and PDFs with more
+//! than [`MAX_PDF_PAGES`] pages are rejected, as far as their headers tell
+//! without decoding them. HEIC images aren't measured.
+
+use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::api::{ApiError, ClientErrorCode};
+
+/// The longest name kept for a file, in bytes.
+pub const MAX_FILENAME_BYTES: usize = 254;
+
+/// The most pixels an image may have on either side.
+pub const MAX_IMAGE_DIMENSION: u32 = 16_384;
+
+/// The most pages a PDF may have.
+pub const MAX_PDF_PAGES: usize = 100;
+
+/// A content type files may be uploaded as.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AllowedType {
+    /// The type the content is kept and served as
+    pub content_type: &'static str,
+    /// The type the leading bytes of the content sniff as
+    sniffed: &'static str,
+    /// The types a client may declare the content as
+    declared: &'static [&'static str],
+    /// The extension of a file that had no name left once sanitized
+    extension: &'static str,
+}
+
+pub const ALLOWED_TYPES: [AllowedType; 4] = [
+    AllowedType {
+        content_type: "application/pdf",
+        sniffed: "application/pdf",
+        declared: &["application/pdf"],
+        extension: "pdf",
+    },
+    AllowedType {
+        content_type: "image/png",
+        sniffed: "image/png",
+        declared: &["image/png"],
+        extension: "png",
+    },
+    AllowedType {
+        content_type: "image/jpeg",
+        sniffed: "image/jpeg",
+        declared: &["image/jpeg", "image/jpg"],
+        extension: "jpg",
+    },
+    AllowedType {
+        content_type: "image/heic",
+        sniffed: "image/heif",
+        declared: &["image/heic", "image/heif"],
+        extension: "heic",
+    },
+];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum UploadError {
+    #[error("Attachments must be PDF, PNG, JPEG or HEIC files, not `{0}`.")]
+    UnsupportedContentType(String),
+    #[error("The content is `{sniffed}`, not the declared `{declared}`.")]
+    ContentTypeMismatch { declared: String, sniffed: String },
+    #[error("Images may be at most {MAX_IMAGE_DIMENSION} pixels wide and high.")]
+    ImageTooLarge,
+    #[error("PDFs may have at most {MAX_PDF_PAGES} pages.")]
+    TooManyPages,
+}
+
+impl From<UploadError> for ApiError {
+    fn from(value: UploadError) -> Self {
+        Self::client(ClientErrorCode::InvalidFile, value.to_string())
+    }
+}
+
+/// An uploaded file that passed the checks.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Upload {
+    /// The sanitized name, for display
+    pub filename: String,
+    pub content_type: &'static str,
+}
+
+/// Checks the content of an upload against its declared content type and
+/// sanitizes its name.
+pub fn inspect(filename: &str, declared: &str, content: &[u8]) -> Result<Upload, UploadError> {
+    let allowed = sniff_content_type(declared, content)?;
+    check_dimensions(allowed, content)?;
+    Ok(Upload {
+        filename: sanitize_filename(filename, allowed.extension),
+        content_type: allowed.content_type,
+    })
+}
+
+/// The allowed type `declared` is, if the content sniffs as it too.
+pub fn sniff_content_type(
+    declared: &str,
+    content: &[u8],
+) -> Result<&'static AllowedType, UploadError> {
+    // Parameters like `; charset=binary` don't change what the file is.
+    let declared = declared
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let allowed = ALLOWED_TYPES
+        .iter()
+        .find(|x| x.declared.contains(&declared.as_str()))
+        .ok_or_else(|| UploadError::UnsupportedContentType(declared.clone()))?;
+    let sniffed = infer::get(content).map_or("unknown", |x| x.mime_type());
+    if sniffed != allowed.sniffed {
+        return Err(UploadError::ContentTypeMismatch {
+            declared,
+            sniffed: sniffed.to_owned(),
+        });
+    }
+    Ok(allowed)
+}
+
+/// Rejects images and PDFs too large to handle, where their headers tell
+/// their size without decoding them.
+fn check_dimensions(allowed: &AllowedType, content: &[u8]) -> Result<(), UploadError> {
+    let dimensions = match allowed.content_type {
+        "image/png" => png_dimensions(content),
+        "image/jpeg" => jpeg_dimensions(content),
+        "application/pdf" => {
+            return if pdf_pages(content) > MAX_PDF_PAGES {
+                Err(UploadError::TooManyPages)
+            } else {
+                Ok(())
+            };
+        }
+        _ => None,
+    };
+    match dimensions {
+        Some((width, height)) if width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION => {
+            Err(UploadError::ImageTooLarge)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The width and height in the `IHDR` chunk, which follows the signature.
+fn png_dimensions(content: &[u8]) -> Option<(u32, u32)> {
+    let ihdr = content.get(12..24)?;
+    if &ihdr[..4] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(ihdr[4..8].try_into().ok()?);
+    let height = u32::from_be_bytes(ihdr[8..12].try_into().ok()?);
+    Some((width, height))
+}
+
+/// The width and height in the first start of frame segment, walking the
+/// segments before it by their lengths.
+fn jpeg_dimensions(content: &[u8]) -> Option<(u32, u32)> {
+    let mut at = 2;
+    loop {
+        if *content.get(at)? != 0xFF {
+            return None;
+        }
+        let marker = *content.get(at + 1)?;
+        match marker {
+            // Fill bytes before a marker
+            0xFF => at += 1,
+            // Markers without a segment
+            0x01 | 0xD0..=0xD8 => at += 2,
+            // The scan starts or the image ends before any frame
+            0xD9 | 0xDA => return None,
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let frame = content.get(at + 5..at + 9)?;
+                let height = u16::from_be_bytes([frame[0], frame[1]]);
+                let width = u16::from_be_bytes([frame[2], frame[3]]);
+                return Some((width.into(), height.into()));
+            }
+            _ => {
+                let length = content.get(at + 2..at + 4)?;
+                at += 2 + usize::from(u16::from_be_bytes([length[0], length[1]]));
+            }
+        }
+    }
+}
+
+/// The page objects of a PDF. Pages inside compressed object streams
+/// aren't seen, so it counts no more than a PDF has.
+fn pdf_pages(content: &[u8]) -> usize {
+    [&b"/Type /Page"[..], b"/Type/Page"]
+        .iter()
+        .map(|needle| {
+            content
+                .windows(needle.len() + 1)
+                .filter(|x| x.starts_with(needle) && x[needle.len()] != b's')
+                .count()
+        })
+        .sum()
+}
+
+/// Reduces a client supplied name to one that is safe to show and to save
+/// a download as. A name with nothing left is named after its content
+/// type's `extension`.
+pub fn sanitize_filename(filename: &str, extension: &str) -> String {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name = name
+        .nfc()
+        // Bidirectional overrides can make `fdp.exe` show as `exe.pdf`.
+        .filter(|c| {
+            !c.is_control() && !matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+        })
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let name = name
+        .trim()
+        .trim_start_matches('.')
+        .trim_end_matches(['.', ' ']);
+    if name.is_empty() {
+        return format!("attachment.{extension}");
+    }
+    if name.len() <= MAX_FILENAME_BYTES {
+        return name.to_owned();
+    }
+    // Shorten the stem, keeping the extension if it is a short one.
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && extension.len() <= 16 => {
+            (stem, &name[stem.len()..])
+        }
+        _ => (name, ""),
+    };
+    let mut end = MAX_FILENAME_BYTES - extension.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{extension}", &stem[..end])
+}
+
+/// A `Content-Disposition` that downloads a file as `filename`. Clients
+/// that understand RFC 5987 take the UTF-8 name, others an ASCII one with
+/// the rest replaced.
+pub fn content_disposition(filename: &str) -> String {
+    let ascii = filename
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' | ';' => '_',
+            c if c.is_ascii_graphic() || c == ' ' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    format!(
+        "attachment; filename=\"{ascii}\"; filename*=UTF-8''{}",
+        urlencoding::encode(filename)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\x01\0\0\0\x01\0\x08\x02\0\0\0";
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut content = PNG.to_vec();
+        content[16..20].copy_from_slice(&width.to_be_bytes());
+        content[20..24].copy_from_slice(&height.to_be_bytes());
+        content
+    }
+
+    #[test]
+    fn it_rejects_content_that_is_not_what_it_is_declared_as() {
+        assert_eq!(
+            sniff_content_type("image/PNG; charset=binary", PNG).map(|x| x.content_type),
+            Ok("image/png")
+        );
+        assert_eq!(
+            sniff_content_type("image/png", b"GIF89a<script>alert(1)</script>"),
+            Err(UploadError::ContentTypeMismatch {
+                declared: "image/png".into(),
+                sniffed: "image/gif".into(),
+            })
+        );
+        assert_eq!(
+            sniff_content_type("text/html", b"<html></html>"),
+            Err(UploadError::UnsupportedContentType("text/html".into()))
+        );
+        assert!(matches!(
+            sniff_content_type("application/pdf", b"plain text"),
+            Err(UploadError::ContentTypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn it_measures_images_and_pdfs_from_their_headers() {
+        assert_eq!(png_dimensions(&png(640, 480)), Some((640, 480)));
+        assert_eq!(
+            inspect("a.png", "image/png", &png(MAX_IMAGE_DIMENSION + 1, 1)),
+            Err(UploadError::ImageTooLarge)
+        );
+
+        let jpeg = b"\xFF\xD8\xFF\xE0\0\x04JF\xFF\xC0\0\x11\x08\x01\xE0\x02\x80\x03";
+        assert_eq!(jpeg_dimensions(jpeg), Some((640, 480)));
+        assert_eq!(jpeg_dimensions(&jpeg[..12]), None);
+
+        let pages = "1 0 obj << /Type /Pages /Count 2 >> 2 0 obj << /Type /Page >> 3 0 obj << /Type/Page >>";
+        assert_eq!(pdf_pages(pages.as_bytes()), 2);
+        let pdf = format!(
+            "%PDF-1.7\n{}",
+            "<< /Type /Page >>".repeat(MAX_PDF_PAGES + 1)
+        );
+        assert_eq!(
+            inspect("a.pdf", "application/pdf", pdf.as_bytes()),
+            Err(UploadError::TooManyPages)
+        );
+    }
+
+    #[test]
+    fn it_sanitizes_filenames() {
+        assert_eq!(sanitize_filename("../../etc/passwd", "pdf"), "passwd");
+        assert_eq!(
+            sanitize_filename("C:\\Users\\a\\receipt.pdf", "pdf"),
+            "receipt.pdf"
+        );
+        assert_eq!(sanitize_filename("..", "pdf"), "attachment.pdf");
+        assert_eq!(sanitize_filename(".htaccess", "png"), "htaccess");
+        assert_eq!(sanitize_filename("a\0b\r\n.png", "png"), "ab.png");
+        assert_eq!(
+            sanitize_filename("receipt\u{202E}fdp.exe", "pdf"),
+            "receiptfdp.exe"
+        );
+        assert_eq!(sanitize_filename("\"a\"<b>.pdf", "pdf"), "_a__b_.pdf");
+        // Decomposed Hangul is composed.
+        assert_eq!(
+            sanitize_filename("\u{1112}\u{1161}\u{11AB}.pdf", "pdf"),
+            "한.pdf"
+        );
+
+        let long = format!("{}.pdf", "영".repeat(100));
+        let sanitized = sanitize_filename(&long, "pdf");
+        assert!(sanitized.len() <= MAX_FILENAME_BYTES);
+        assert!(sanitized.ends_with("영.pdf"));
+    }
+
+    #[test]
+    fn it_encodes_non_ascii_names_for_downloads() {
+        assert_eq!(
+            content_disposition("영수증.pdf"),
+            "attachment; filename=\"___.pdf\"; filename*=UTF-8''%EC%98%81%EC%88%98%EC%A6%9D.pdf"
+        );
+        assert_eq!(
+            content_disposition("a;b\\c.png"),
+            "attachment; filename=\"a_b_c.png\"; filename*=UTF-8''a%3Bb%5Cc.png"
+        );
+    }
+}